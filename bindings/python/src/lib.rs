@@ -0,0 +1,50 @@
+// Copyright 2026 Cortex Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! In-process Python bindings for the Cortex map and query engine.
+//!
+//! `cortex_client` talks to a running daemon; `cortex_py` links the engine
+//! itself, so notebooks and batch jobs can load cached maps, filter nodes,
+//! pathfind and run WQL without one. Mapping and rendering still need the
+//! daemon.
+//!
+//! ```python
+//! import cortex_py
+//!
+//! site = cortex_py.SiteMap.cached("shop.com")
+//! cheap = site.filter(page_type=4, features={48: {"lt": 300}}, sort_by=(48, "asc"))
+//! path = site.pathfind(0, cheap[0].index, avoid_flags=["auth_required"])
+//! rows = cortex_py.wql("SELECT name, price FROM Product WHERE price < 200")
+//! ```
+
+use pyo3::prelude::*;
+
+mod path;
+mod query;
+mod sitemap;
+mod wql;
+
+pyo3::create_exception!(
+    cortex_py,
+    CortexError,
+    pyo3::exceptions::PyException,
+    "A map could not be loaded, or a WQL query could not be run."
+);
+
+/// Raise an engine error as a `CortexError`.
+pub(crate) fn cortex_error(e: anyhow::Error) -> PyErr {
+    CortexError::new_err(format!("{e:#}"))
+}
+
+#[pymodule]
+fn cortex_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    m.add("CortexError", m.py().get_type::<CortexError>())?;
+    m.add_class::<sitemap::PySiteMap>()?;
+    m.add_class::<query::PyNodeQuery>()?;
+    m.add_class::<query::PyNodeMatch>()?;
+    m.add_class::<path::PyPath>()?;
+    m.add_function(wrap_pyfunction!(sitemap::cached_domains, m)?)?;
+    m.add_function(wrap_pyfunction!(wql::wql, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,210 @@
+// Copyright 2026 Cortex Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! `Path`, and the pathfinding behind `SiteMap.pathfind`.
+
+use crate::cortex_error;
+use cortex_runtime::map::types::{
+    PageType, Path, PathConstraints, PathMinimize, SiteMap, FEATURE_DIM,
+};
+use cortex_runtime::navigation::cost_model::ActionCosts;
+use cortex_runtime::navigation::pathfinder::{self, Goal};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+/// A path through a site's graph.
+#[pyclass(
+    name = "Path",
+    module = "cortex_py",
+    frozen,
+    skip_from_py_object,
+    get_all
+)]
+#[derive(Debug, Clone)]
+pub struct PyPath {
+    /// Node indexes along the path, the start first.
+    pub nodes: Vec<u32>,
+    pub total_weight: f32,
+    pub hops: u32,
+    /// `(node, (category, action))` for each action to take on the way.
+    pub required_actions: Vec<(u32, (u8, u8))>,
+    /// For `goals`: the order the goals are visited in.
+    pub visit_order: Vec<u32>,
+    /// For `goal`: the node that met it.
+    pub target: Option<u32>,
+}
+
+impl From<Path> for PyPath {
+    fn from(path: Path) -> Self {
+        Self {
+            required_actions: path
+                .required_actions
+                .iter()
+                .map(|a| (a.at_node, (a.opcode.category, a.opcode.action)))
+                .collect(),
+            nodes: path.nodes,
+            total_weight: path.total_weight,
+            hops: path.hops,
+            visit_order: Vec::new(),
+            target: None,
+        }
+    }
+}
+
+#[pymethods]
+impl PyPath {
+    fn __repr__(&self) -> String {
+        format!(
+            "Path(hops={}, nodes={:?}, weight={:.1})",
+            self.hops, self.nodes, self.total_weight
+        )
+    }
+
+    fn __len__(&self) -> usize {
+        self.nodes.len()
+    }
+}
+
+/// A node given to `pathfind` as an index or a URL.
+#[derive(FromPyObject)]
+pub(crate) enum NodeArg {
+    Index(u32),
+    Url(String),
+}
+
+impl NodeArg {
+    pub(crate) fn resolve(&self, map: &SiteMap) -> PyResult<u32> {
+        match self {
+            NodeArg::Index(i) if (*i as usize) < map.nodes.len() => Ok(*i),
+            NodeArg::Index(i) => Err(PyValueError::new_err(format!(
+                "node {i} is out of range (the map has {} nodes)",
+                map.nodes.len()
+            ))),
+            NodeArg::Url(url) => map
+                .find_url(url)
+                .ok_or_else(|| PyValueError::new_err(format!("no node with URL '{url}'"))),
+        }
+    }
+}
+
+/// A `goal`: a page type code or a feature vector.
+#[derive(FromPyObject)]
+pub(crate) enum GoalArg {
+    PageType(u8),
+    Features(Vec<f32>),
+}
+
+impl GoalArg {
+    fn to_goal(&self) -> PyResult<Goal> {
+        Ok(match self {
+            GoalArg::PageType(code) => Goal::PageType(PageType::from_u8(*code)),
+            GoalArg::Features(values) => {
+                if values.len() > FEATURE_DIM {
+                    return Err(PyValueError::new_err(format!(
+                        "goal vector has {} values; at most {FEATURE_DIM}",
+                        values.len()
+                    )));
+                }
+                let mut target = [0.0f32; FEATURE_DIM];
+                target[..values.len()].copy_from_slice(values);
+                Goal::Features(Box::new(target))
+            }
+        })
+    }
+}
+
+/// The keywords of `SiteMap.pathfind` that constrain a path.
+pub(crate) struct ConstraintArgs {
+    pub avoid_flags: Vec<String>,
+    pub avoid_nodes: Vec<u32>,
+    pub avoid_page_types: Vec<u8>,
+    pub max_hops: Option<u32>,
+    pub max_risk: Option<u8>,
+    pub minimize: String,
+    pub learned_costs: bool,
+}
+
+impl ConstraintArgs {
+    fn to_constraints(&self, map: &SiteMap) -> PyResult<PathConstraints> {
+        let mut constraints = PathConstraints {
+            minimize: match self.minimize.as_str() {
+                "hops" => PathMinimize::Hops,
+                "weight" => PathMinimize::Weight,
+                "state_changes" => PathMinimize::StateChanges,
+                other => {
+                    return Err(PyValueError::new_err(format!(
+                        "minimize must be 'hops', 'weight' or 'state_changes', not '{other}'"
+                    )))
+                }
+            },
+            avoid_nodes: self.avoid_nodes.clone(),
+            avoid_page_types: self
+                .avoid_page_types
+                .iter()
+                .map(|&code| PageType::from_u8(code))
+                .collect(),
+            max_hops: self.max_hops,
+            max_risk: self.max_risk,
+            ..PathConstraints::default()
+        };
+        for flag in &self.avoid_flags {
+            match flag.as_str() {
+                "auth_required" => constraints.avoid_auth = true,
+                "state_changes" => constraints.avoid_state_changes = true,
+                other => {
+                    return Err(PyValueError::new_err(format!(
+                        "unknown flag to avoid '{other}' (use auth_required or state_changes)"
+                    )))
+                }
+            }
+        }
+        if self.learned_costs {
+            constraints.learned_costs = ActionCosts::load_default()
+                .map_err(cortex_error)?
+                .get(&map.header.domain);
+        }
+        Ok(constraints)
+    }
+}
+
+/// Find a path from `from_node` to `to_node`, a route through every node in
+/// `goals` (ending at `to_node` if given), or a path to the nearest node
+/// meeting `goal`, as the daemon's `pathfind` does.
+pub(crate) fn pathfind(
+    map: &SiteMap,
+    from_node: &NodeArg,
+    to_node: Option<&NodeArg>,
+    goals: Option<&[NodeArg]>,
+    goal: Option<&GoalArg>,
+    args: &ConstraintArgs,
+) -> PyResult<Option<PyPath>> {
+    let from = from_node.resolve(map)?;
+    let to = to_node.map(|n| n.resolve(map)).transpose()?;
+    let constraints = args.to_constraints(map)?;
+
+    if let Some(goals) = goals {
+        let goals = goals
+            .iter()
+            .map(|g| g.resolve(map))
+            .collect::<PyResult<Vec<_>>>()?;
+        return Ok(
+            pathfinder::find_route(map, from, &goals, to, &constraints).map(|route| PyPath {
+                visit_order: route.order,
+                ..route.path.into()
+            }),
+        );
+    }
+    if let Some(goal) = goal {
+        let goal = goal.to_goal()?;
+        return Ok(
+            pathfinder::find_goal(map, from, &goal, &constraints).map(|(target, path)| PyPath {
+                target: Some(target),
+                ..path.into()
+            }),
+        );
+    }
+    let Some(to) = to else {
+        return Err(PyValueError::new_err("give to_node, goals or goal"));
+    };
+    Ok(pathfinder::find_path(map, from, to, &constraints).map(PyPath::from))
+}
@@ -0,0 +1,251 @@
+// Copyright 2026 Cortex Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! `NodeQuery` and `NodeMatch`: node filters and their results.
+
+use cortex_runtime::map::types::{
+    FeatureRange, NodeFlags, NodeMatch, NodeQuery, PageType, FEATURE_DIM,
+};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::collections::HashMap;
+
+/// Matches returned when a query sets no limit, as in the daemon.
+const DEFAULT_LIMIT: usize = 100;
+
+/// A node filter, built from the same keywords as `SiteMap.filter`.
+#[pyclass(name = "NodeQuery", module = "cortex_py", frozen, skip_from_py_object)]
+#[derive(Debug, Clone)]
+pub struct PyNodeQuery {
+    pub(crate) query: NodeQuery,
+}
+
+#[pymethods]
+impl PyNodeQuery {
+    /// `page_type` is a code or list of codes; `features` maps a dimension
+    /// to bounds (`{48: {"lt": 300}}`, with `gt`/`gte` and `lt`/`lte` both
+    /// inclusive); `flags` names node flags (`rendered`, `has_price`,
+    /// `has_form`, `has_media`) a match must have (`True`) or lack
+    /// (`False`); `sort_by` is `(dimension, "asc" | "desc")`; `text` is
+    /// words to find in page titles and descriptions, ranking matches by
+    /// relevance unless `sort_by` is given.
+    #[new]
+    #[pyo3(signature = (*, page_type=None, features=None, flags=None, sort_by=None, limit=None, text=None))]
+    pub(crate) fn new(
+        page_type: Option<Bound<'_, PyAny>>,
+        features: Option<HashMap<usize, HashMap<String, f32>>>,
+        flags: Option<HashMap<String, bool>>,
+        sort_by: Option<(usize, String)>,
+        limit: Option<usize>,
+        text: Option<String>,
+    ) -> PyResult<Self> {
+        let page_types = match page_type {
+            None => None,
+            Some(v) => match v.extract::<u8>() {
+                Ok(code) => Some(vec![code]),
+                Err(_) => Some(v.extract::<Vec<u8>>()?),
+            },
+        }
+        .filter(|codes| !codes.is_empty())
+        .map(|codes| codes.into_iter().map(PageType::from_u8).collect());
+
+        let mut feature_ranges = Vec::new();
+        for (dimension, bounds) in features.unwrap_or_default() {
+            check_dimension(dimension)?;
+            let mut range = FeatureRange {
+                dimension,
+                min: None,
+                max: None,
+            };
+            for (op, value) in bounds {
+                match op.as_str() {
+                    "gt" | "gte" => range.min = Some(value),
+                    "lt" | "lte" => range.max = Some(value),
+                    _ => {
+                        return Err(PyValueError::new_err(format!(
+                            "unknown bound '{op}' for feature {dimension} (use gt, gte, lt or lte)"
+                        )))
+                    }
+                }
+            }
+            feature_ranges.push(range);
+        }
+        feature_ranges.sort_by_key(|r| r.dimension);
+
+        let (mut require, mut exclude) = (0u8, 0u8);
+        for (name, wanted) in flags.unwrap_or_default() {
+            let bit = match name.as_str() {
+                "rendered" => NodeFlags::RENDERED,
+                "has_price" => NodeFlags::HAS_PRICE,
+                "has_form" => NodeFlags::HAS_FORM,
+                "has_media" => NodeFlags::HAS_MEDIA,
+                _ => {
+                    return Err(PyValueError::new_err(format!(
+                        "unknown flag '{name}' (use rendered, has_price, has_form or has_media)"
+                    )))
+                }
+            };
+            if wanted {
+                require |= bit;
+            } else {
+                exclude |= bit;
+            }
+        }
+
+        let (sort_by_feature, sort_ascending) = match sort_by {
+            None => (None, true),
+            Some((dimension, direction)) => {
+                check_dimension(dimension)?;
+                let ascending = match direction.as_str() {
+                    "asc" => true,
+                    "desc" => false,
+                    _ => {
+                        return Err(PyValueError::new_err(format!(
+                            "sort direction must be 'asc' or 'desc', not '{direction}'"
+                        )))
+                    }
+                };
+                (Some(dimension), ascending)
+            }
+        };
+
+        Ok(Self {
+            query: NodeQuery {
+                page_types,
+                feature_ranges,
+                require_flags: (require != 0).then_some(NodeFlags(require)),
+                exclude_flags: (exclude != 0).then_some(NodeFlags(exclude)),
+                sort_by_feature,
+                sort_ascending,
+                limit: limit.unwrap_or(DEFAULT_LIMIT),
+                text: text.filter(|t| !t.trim().is_empty()),
+            },
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!("NodeQuery({:?})", self.query)
+    }
+}
+
+fn check_dimension(dimension: usize) -> PyResult<()> {
+    if dimension < FEATURE_DIM {
+        Ok(())
+    } else {
+        Err(PyValueError::new_err(format!(
+            "feature dimension {dimension} is out of range (0..{FEATURE_DIM})"
+        )))
+    }
+}
+
+/// A node that matched a query.
+#[pyclass(
+    name = "NodeMatch",
+    module = "cortex_py",
+    frozen,
+    skip_from_py_object,
+    get_all
+)]
+#[derive(Debug, Clone)]
+pub struct PyNodeMatch {
+    pub index: u32,
+    pub url: String,
+    /// Page type code.
+    pub page_type: u8,
+    pub confidence: f32,
+    /// Non-zero features, by dimension.
+    pub features: HashMap<usize, f32>,
+    /// Cosine similarity, for `nearest`.
+    pub similarity: Option<f32>,
+    /// Relevance to the query's `text`.
+    pub text_score: Option<f32>,
+}
+
+impl From<NodeMatch> for PyNodeMatch {
+    fn from(m: NodeMatch) -> Self {
+        Self {
+            index: m.index,
+            url: m.url,
+            page_type: m.page_type as u8,
+            confidence: m.confidence,
+            features: m.features.into_iter().collect(),
+            similarity: m.similarity,
+            text_score: m.text_score,
+        }
+    }
+}
+
+#[pymethods]
+impl PyNodeMatch {
+    fn __repr__(&self) -> String {
+        let similarity = self
+            .similarity
+            .map(|s| format!(", similarity={s:.3}"))
+            .unwrap_or_default();
+        let text_score = self
+            .text_score
+            .map(|s| format!(", text_score={s:.3}"))
+            .unwrap_or_default();
+        format!(
+            "NodeMatch(index={}, url={:?}, page_type={}, confidence={:.2}{similarity}{text_score})",
+            self.index, self.url, self.page_type, self.confidence
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::types::PyDict;
+
+    #[test]
+    fn test_node_query_from_keywords() {
+        Python::attach(|py| {
+            let features = HashMap::from([(48, HashMap::from([("lt".to_string(), 300.0)]))]);
+            let flags = HashMap::from([
+                ("has_price".to_string(), true),
+                ("rendered".to_string(), false),
+            ]);
+            let q = PyNodeQuery::new(
+                Some(4u8.into_pyobject(py).unwrap().into_any()),
+                Some(features),
+                Some(flags),
+                Some((48, "asc".to_string())),
+                Some(10),
+                Some("wireless headphones".to_string()),
+            )
+            .unwrap()
+            .query;
+            assert_eq!(q.page_types, Some(vec![PageType::from_u8(4)]));
+            assert_eq!(q.feature_ranges.len(), 1);
+            assert_eq!(q.feature_ranges[0].max, Some(300.0));
+            assert_eq!(q.require_flags, Some(NodeFlags(NodeFlags::HAS_PRICE)));
+            assert_eq!(q.exclude_flags, Some(NodeFlags(NodeFlags::RENDERED)));
+            assert_eq!(
+                (q.sort_by_feature, q.sort_ascending, q.limit),
+                (Some(48), true, 10)
+            );
+            assert_eq!(q.text.as_deref(), Some("wireless headphones"));
+
+            let q = PyNodeQuery::new(None, None, None, None, None, None)
+                .unwrap()
+                .query;
+            assert_eq!(q.limit, DEFAULT_LIMIT);
+            assert!(q.page_types.is_none());
+
+            let bad = HashMap::from([("colour".to_string(), true)]);
+            assert!(PyNodeQuery::new(None, None, Some(bad), None, None, None).is_err());
+            assert!(PyNodeQuery::new(
+                None,
+                None,
+                None,
+                Some((FEATURE_DIM, "asc".into())),
+                None,
+                None
+            )
+            .is_err());
+            let not_codes = PyDict::new(py).into_any();
+            assert!(PyNodeQuery::new(Some(not_codes), None, None, None, None, None).is_err());
+        });
+    }
+}
@@ -0,0 +1,444 @@
+// Copyright 2026 Cortex Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! `SiteMap`: a loaded map and the queries on it.
+
+use crate::cortex_error;
+use crate::path::{self, ConstraintArgs, GoalArg, NodeArg, PyPath};
+use crate::query::{PyNodeMatch, PyNodeQuery};
+use anyhow::Context;
+use cortex_runtime::intelligence::cache::MapCache;
+use cortex_runtime::map::types::{SiteMap, FEATURE_DIM};
+use pyo3::exceptions::{PyIndexError, PyValueError};
+use pyo3::prelude::*;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// A site's map, held in this process.
+#[pyclass(name = "SiteMap", module = "cortex_py", frozen, skip_from_py_object)]
+#[derive(Clone)]
+pub struct PySiteMap {
+    pub(crate) map: Arc<SiteMap>,
+}
+
+impl PySiteMap {
+    fn node(&self, index: u32) -> PyResult<usize> {
+        let i = index as usize;
+        if i < self.map.nodes.len() {
+            Ok(i)
+        } else {
+            Err(PyIndexError::new_err(format!(
+                "node {index} is out of range (the map has {} nodes)",
+                self.map.nodes.len()
+            )))
+        }
+    }
+}
+
+#[pymethods]
+impl PySiteMap {
+    /// Load a map from a `.ctx` file.
+    #[staticmethod]
+    fn load(py: Python<'_>, path: PathBuf) -> PyResult<Self> {
+        let map = py
+            .detach(|| {
+                let data = std::fs::read(&path)
+                    .with_context(|| format!("failed to read map: {}", path.display()))?;
+                SiteMap::deserialize(&data)
+            })
+            .map_err(cortex_error)?;
+        Ok(Self { map: Arc::new(map) })
+    }
+
+    /// Load `domain`'s map from the map cache (`~/.cortex/maps`), as the
+    /// daemon would.
+    #[staticmethod]
+    fn cached(py: Python<'_>, domain: String) -> PyResult<Self> {
+        let map = py
+            .detach(|| MapCache::default_cache()?.load_map(&domain))
+            .map_err(cortex_error)?;
+        match map {
+            Some(map) => Ok(Self { map: Arc::new(map) }),
+            None => Err(crate::CortexError::new_err(format!(
+                "no cached map for {domain} (map it with `cortex map {domain}`)"
+            ))),
+        }
+    }
+
+    /// Write the map to a `.ctx` file.
+    fn save(&self, py: Python<'_>, path: PathBuf) -> PyResult<()> {
+        let map = Arc::clone(&self.map);
+        py.detach(|| {
+            std::fs::write(&path, map.serialize())
+                .with_context(|| format!("failed to write map: {}", path.display()))
+        })
+        .map_err(cortex_error)
+    }
+
+    #[getter]
+    fn domain(&self) -> &str {
+        &self.map.header.domain
+    }
+
+    #[getter]
+    fn node_count(&self) -> usize {
+        self.map.nodes.len()
+    }
+
+    #[getter]
+    fn edge_count(&self) -> usize {
+        self.map.edges.len()
+    }
+
+    /// When the map was made, in Unix seconds.
+    #[getter]
+    fn mapped_at(&self) -> u64 {
+        self.map.header.mapped_at
+    }
+
+    fn __len__(&self) -> usize {
+        self.map.nodes.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "SiteMap(domain={:?}, nodes={}, edges={})",
+            self.map.header.domain,
+            self.map.nodes.len(),
+            self.map.edges.len()
+        )
+    }
+
+    /// URL of node `index`.
+    fn url(&self, index: u32) -> PyResult<&str> {
+        Ok(&self.map.urls[self.node(index)?])
+    }
+
+    /// Index of the node with `url` (ignoring a fragment and trailing
+    /// slash), or `None`.
+    fn find_url(&self, url: &str) -> Option<u32> {
+        self.map.find_url(url)
+    }
+
+    /// Page type code of node `index`.
+    fn page_type(&self, index: u32) -> PyResult<u8> {
+        Ok(self.map.nodes[self.node(index)?].page_type as u8)
+    }
+
+    /// The 128-dimension feature vector of node `index`.
+    fn features(&self, index: u32) -> PyResult<Vec<f32>> {
+        Ok(self.map.features[self.node(index)?].to_vec())
+    }
+
+    /// Nodes matching `query`, or a query built from the keywords (see
+    /// `NodeQuery`).
+    #[pyo3(signature = (query=None, *, page_type=None, features=None, flags=None, sort_by=None, limit=None, text=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn filter(
+        &self,
+        py: Python<'_>,
+        query: Option<PyRef<'_, PyNodeQuery>>,
+        page_type: Option<Bound<'_, PyAny>>,
+        features: Option<HashMap<usize, HashMap<String, f32>>>,
+        flags: Option<HashMap<String, bool>>,
+        sort_by: Option<(usize, String)>,
+        limit: Option<usize>,
+        text: Option<String>,
+    ) -> PyResult<Vec<PyNodeMatch>> {
+        let query = match query {
+            Some(query) => {
+                if page_type.is_some()
+                    || features.is_some()
+                    || flags.is_some()
+                    || sort_by.is_some()
+                    || limit.is_some()
+                    || text.is_some()
+                {
+                    return Err(PyValueError::new_err(
+                        "give a NodeQuery or filter keywords, not both",
+                    ));
+                }
+                query.query.clone()
+            }
+            None => PyNodeQuery::new(page_type, features, flags, sort_by, limit, text)?.query,
+        };
+        let map = Arc::clone(&self.map);
+        let matches = py.detach(|| map.filter(&query));
+        Ok(matches.into_iter().map(PyNodeMatch::from).collect())
+    }
+
+    /// The `k` nodes most similar to `goal_vector` by cosine similarity,
+    /// best first. Missing trailing dimensions count as zero.
+    #[pyo3(signature = (goal_vector, k=10))]
+    fn nearest(
+        &self,
+        py: Python<'_>,
+        goal_vector: Vec<f32>,
+        k: usize,
+    ) -> PyResult<Vec<PyNodeMatch>> {
+        if goal_vector.len() > FEATURE_DIM {
+            return Err(PyValueError::new_err(format!(
+                "goal vector has {} values; at most {FEATURE_DIM}",
+                goal_vector.len()
+            )));
+        }
+        let mut target = [0.0f32; FEATURE_DIM];
+        target[..goal_vector.len()].copy_from_slice(&goal_vector);
+        let map = Arc::clone(&self.map);
+        let matches = py.detach(|| map.nearest(&target, k));
+        Ok(matches.into_iter().map(PyNodeMatch::from).collect())
+    }
+
+    /// A path from `from_node` to `to_node` (indexes or URLs), a route
+    /// through every node in `goals`, or a path to the nearest node meeting
+    /// `goal` (a page type code or feature vector). Takes the constraints
+    /// of the daemon's `pathfind`; `learned_costs` weighs edges by the
+    /// outcomes in `~/.cortex/action_costs.json`. Returns `None` when no
+    /// path exists.
+    #[pyo3(signature = (
+        from_node,
+        to_node=None,
+        *,
+        goals=None,
+        goal=None,
+        avoid_flags=Vec::new(),
+        avoid_nodes=Vec::new(),
+        avoid_page_types=Vec::new(),
+        max_hops=None,
+        max_risk=None,
+        minimize="hops".to_string(),
+        learned_costs=true
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn pathfind(
+        &self,
+        from_node: NodeArg,
+        to_node: Option<NodeArg>,
+        goals: Option<Vec<NodeArg>>,
+        goal: Option<GoalArg>,
+        avoid_flags: Vec<String>,
+        avoid_nodes: Vec<u32>,
+        avoid_page_types: Vec<u8>,
+        max_hops: Option<u32>,
+        max_risk: Option<u8>,
+        minimize: String,
+        learned_costs: bool,
+    ) -> PyResult<Option<PyPath>> {
+        let args = ConstraintArgs {
+            avoid_flags,
+            avoid_nodes,
+            avoid_page_types,
+            max_hops,
+            max_risk,
+            minimize,
+            learned_costs,
+        };
+        path::pathfind(
+            &self.map,
+            &from_node,
+            to_node.as_ref(),
+            goals.as_deref(),
+            goal.as_ref(),
+            &args,
+        )
+    }
+}
+
+/// Domains with a map in the map cache, sorted.
+#[pyfunction]
+pub fn cached_domains(py: Python<'_>) -> PyResult<Vec<String>> {
+    let stats = py
+        .detach(|| MapCache::default_cache().map(|cache| cache.stats()))
+        .map_err(cortex_error)?;
+    let mut domains: Vec<String> = stats
+        .maps
+        .into_iter()
+        .filter(|entry| !entry.expired)
+        .map(|entry| entry.domain)
+        .collect();
+    domains.sort();
+    domains.dedup();
+    Ok(domains)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use cortex_runtime::map::types::{EdgeFlags, EdgeType, PageType};
+    use cortex_runtime::map::SiteMapBuilder;
+
+    /// Home → listing → two products → cart, with prices on dimension 48.
+    pub(crate) fn shop() -> PySiteMap {
+        let mut b = SiteMapBuilder::new("shop.com");
+        let mut page = |url: &str, page_type: PageType, price: f32| {
+            let mut features = [0.0f32; FEATURE_DIM];
+            features[48] = price;
+            b.add_node(url, page_type, features, 230)
+        };
+        let home = page("https://shop.com/", PageType::Home, 0.0);
+        let list = page("https://shop.com/c/tv", PageType::ProductListing, 0.0);
+        let cheap = page("https://shop.com/p/1", PageType::ProductDetail, 199.0);
+        let dear = page("https://shop.com/p/2", PageType::ProductDetail, 899.0);
+        let cart = page("https://shop.com/cart", PageType::Cart, 0.0);
+        for (from, to) in [
+            (home, list),
+            (list, cheap),
+            (list, dear),
+            (cheap, cart),
+            (dear, cart),
+        ] {
+            b.add_edge(from, to, EdgeType::Navigation, 1, EdgeFlags::default());
+        }
+        PySiteMap {
+            map: Arc::new(b.build()),
+        }
+    }
+
+    #[test]
+    fn test_filter_nearest_and_lookup() {
+        let site = shop();
+        Python::attach(|py| {
+            let cheap = site
+                .filter(
+                    py,
+                    None,
+                    Some(4u8.into_pyobject(py).unwrap().into_any()),
+                    Some(HashMap::from([(
+                        48,
+                        HashMap::from([("lt".to_string(), 300.0)]),
+                    )])),
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+                .unwrap();
+            assert_eq!(cheap.len(), 1);
+            assert_eq!(cheap[0].url, "https://shop.com/p/1");
+            assert_eq!(cheap[0].features.get(&48), Some(&199.0));
+
+            let query = Bound::new(
+                py,
+                PyNodeQuery::new(
+                    None,
+                    None,
+                    None,
+                    Some((48, "desc".to_string())),
+                    Some(1),
+                    None,
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            let dearest = site
+                .filter(py, Some(query.borrow()), None, None, None, None, None, None)
+                .unwrap();
+            assert_eq!(dearest[0].index, 3);
+            assert!(site
+                .filter(
+                    py,
+                    Some(query.borrow()),
+                    None,
+                    None,
+                    None,
+                    None,
+                    Some(5),
+                    None
+                )
+                .is_err());
+
+            let mut goal = vec![0.0; 49];
+            goal[48] = 1.0;
+            let near = site.nearest(py, goal, 2).unwrap();
+            assert_eq!(near.len(), 2);
+            assert!(near[0].similarity.is_some());
+        });
+
+        assert_eq!(site.find_url("https://shop.com/cart/"), Some(4));
+        assert_eq!(site.page_type(4).unwrap(), PageType::Cart as u8);
+        assert_eq!(site.features(2).unwrap()[48], 199.0);
+        assert!(site.url(9).is_err());
+    }
+
+    #[test]
+    fn test_pathfind_modes() {
+        let site = shop();
+        let args = |minimize: &str| ConstraintArgs {
+            avoid_flags: Vec::new(),
+            avoid_nodes: Vec::new(),
+            avoid_page_types: Vec::new(),
+            max_hops: None,
+            max_risk: None,
+            minimize: minimize.to_string(),
+            learned_costs: false,
+        };
+        Python::attach(|_| {
+            let path = path::pathfind(
+                &site.map,
+                &NodeArg::Index(0),
+                Some(&NodeArg::Url("https://shop.com/cart".into())),
+                None,
+                None,
+                &args("hops"),
+            )
+            .unwrap()
+            .unwrap();
+            assert_eq!(path.hops, 3);
+            assert_eq!(path.nodes.first(), Some(&0));
+            assert_eq!(path.nodes.last(), Some(&4));
+
+            let route = path::pathfind(
+                &site.map,
+                &NodeArg::Index(0),
+                None,
+                Some(&[NodeArg::Index(2), NodeArg::Index(3)]),
+                None,
+                &args("hops"),
+            )
+            .unwrap();
+            // Products only lead to the cart, so no route visits both.
+            assert!(route.is_none());
+
+            let to_cart = path::pathfind(
+                &site.map,
+                &NodeArg::Index(1),
+                None,
+                None,
+                Some(&GoalArg::PageType(PageType::Cart as u8)),
+                &args("hops"),
+            )
+            .unwrap()
+            .unwrap();
+            assert_eq!(to_cart.target, Some(4));
+
+            let no_target = path::pathfind(
+                &site.map,
+                &NodeArg::Index(0),
+                None,
+                None,
+                None,
+                &args("hops"),
+            );
+            assert!(no_target.is_err());
+            assert!(path::pathfind(
+                &site.map,
+                &NodeArg::Index(0),
+                Some(&NodeArg::Index(4)),
+                None,
+                None,
+                &args("fastest")
+            )
+            .is_err());
+            assert!(path::pathfind(
+                &site.map,
+                &NodeArg::Index(7),
+                Some(&NodeArg::Index(4)),
+                None,
+                None,
+                &args("hops")
+            )
+            .is_err());
+        });
+    }
+}
@@ -0,0 +1,100 @@
+// Copyright 2026 Cortex Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! `wql()`: WQL over maps in this process.
+
+use crate::cortex_error;
+use crate::sitemap::PySiteMap;
+use cortex_runtime::intelligence::cache::MapCache;
+use cortex_runtime::map::types::SiteMap;
+use cortex_runtime::project::Projects;
+use cortex_runtime::wql::executor::{self, Row, Value};
+use cortex_runtime::wql::{parser, planner};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::HashMap;
+
+/// Run a WQL query and return its rows as dicts of `domain`, `url`,
+/// `node_id` and the selected fields.
+///
+/// The query runs over `maps` if given, else over every map in the map
+/// cache. A query without `ACROSS` searches all of them, or only the
+/// domains of `project`.
+#[pyfunction]
+#[pyo3(signature = (query, maps=None, project=None))]
+pub fn wql<'py>(
+    py: Python<'py>,
+    query: &str,
+    maps: Option<Vec<PyRef<'py, PySiteMap>>>,
+    project: Option<&str>,
+) -> PyResult<Vec<Bound<'py, PyDict>>> {
+    let given: Option<Vec<std::sync::Arc<SiteMap>>> =
+        maps.map(|maps| maps.iter().map(|m| std::sync::Arc::clone(&m.map)).collect());
+    let rows = py
+        .detach(|| -> anyhow::Result<Vec<Row>> {
+            let search_path = match project {
+                Some(name) => Projects::load_default()?
+                    .resolve(Some(name))?
+                    .map_or_else(Vec::new, |p| p.domains.clone()),
+                None => Vec::new(),
+            };
+            let plan = planner::plan_in(&parser::parse(query)?, &search_path)?;
+            let maps: HashMap<String, SiteMap> = match given {
+                Some(maps) => maps
+                    .iter()
+                    .map(|m| (m.header.domain.clone(), SiteMap::clone(m)))
+                    .collect(),
+                None => MapCache::default_cache()?.load_all_maps()?,
+            };
+            executor::execute(&plan, &maps)
+        })
+        .map_err(cortex_error)?;
+    rows.into_iter().map(|row| row_dict(py, row)).collect()
+}
+
+fn row_dict(py: Python<'_>, row: Row) -> PyResult<Bound<'_, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("domain", row.domain)?;
+    dict.set_item("url", row.url)?;
+    dict.set_item("node_id", row.node_id)?;
+    let mut fields: Vec<(String, Value)> = row.fields.into_iter().collect();
+    fields.sort_by(|a, b| a.0.cmp(&b.0));
+    for (name, value) in fields {
+        match value {
+            Value::Float(v) => dict.set_item(name, v)?,
+            Value::Integer(v) => dict.set_item(name, v)?,
+            Value::String(v) => dict.set_item(name, v)?,
+            Value::Bool(v) => dict.set_item(name, v)?,
+            Value::Null => dict.set_item(name, py.None())?,
+        }
+    }
+    Ok(dict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sitemap::tests::shop;
+
+    #[test]
+    fn test_wql_over_given_maps() {
+        Python::attach(|py| {
+            let site = Bound::new(py, shop()).unwrap();
+            let rows = wql(
+                py,
+                "SELECT url, price FROM Product ACROSS shop.com ORDER BY price DESC",
+                Some(vec![site.borrow()]),
+                None,
+            )
+            .unwrap();
+            assert_eq!(rows.len(), 2);
+            let field = |name: &str| rows[0].get_item(name).unwrap().unwrap();
+            assert_eq!(field("domain").extract::<String>().unwrap(), "shop.com");
+            assert_eq!(field("node_id").extract::<u32>().unwrap(), 3);
+            assert_eq!(field("price").extract::<f64>().unwrap(), 899.0);
+
+            let err = wql(py, "SELEKT nothing", Some(vec![site.borrow()]), None).unwrap_err();
+            assert!(err.is_instance_of::<crate::CortexError>(py));
+        });
+    }
+}
@@ -599,6 +599,7 @@ def act(
         success=r.get("success", False),
         new_url=r.get("new_url"),
         features=r.get("features", {}),
+        latency_ms=r.get("latency_ms", 0),
     )
 
 
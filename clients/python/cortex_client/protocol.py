@@ -32,10 +32,19 @@ def query_request(
     features: dict[int, dict[str, float]] | None = None,
     flags: dict[str, bool] | None = None,
     sort_by: tuple[int, str] | None = None,
-    limit: int = 100,
+    limit: int | None = None,
+    diversify: bool = False,
+    saved: str | None = None,
+    refine: bool = False,
+    refine_budget_ms: int | None = None,
+    include_lineage: bool = False,
 ) -> dict[str, Any]:
     """Build a QUERY request."""
-    params: dict[str, Any] = {"domain": domain, "limit": limit}
+    params: dict[str, Any] = {"domain": domain}
+    if saved is not None:
+        params["saved"] = saved
+    if limit is not None or saved is None:
+        params["limit"] = 100 if limit is None else limit
     if page_type is not None:
         params["page_type"] = page_type if isinstance(page_type, list) else [page_type]
     if features:
@@ -44,26 +53,50 @@ def query_request(
         params["flags"] = flags
     if sort_by:
         params["sort_by"] = {"dimension": sort_by[0], "direction": sort_by[1]}
+    if diversify:
+        params["diversify"] = True
+    if refine:
+        params["refine"] = True
+        if refine_budget_ms is not None:
+            params["refine_budget_ms"] = refine_budget_ms
+    if include_lineage:
+        params["include_lineage"] = True
     return params
 
 
 def pathfind_request(
     domain: str,
-    from_node: int,
-    to_node: int,
+    from_node: int | str,
+    to_node: int | str | None = None,
     *,
+    goals: list[int | str] | None = None,
+    goal: int | list[float] | None = None,
     avoid_flags: list[str] | None = None,
+    avoid_nodes: list[int] | None = None,
+    avoid_page_types: list[int] | None = None,
+    max_hops: int | None = None,
+    max_risk: int | None = None,
     minimize: str = "hops",
 ) -> dict[str, Any]:
     """Build a PATHFIND request."""
-    params: dict[str, Any] = {
-        "domain": domain,
-        "from": from_node,
-        "to": to_node,
-        "minimize": minimize,
-    }
+    params: dict[str, Any] = {"domain": domain, "minimize": minimize}
+    params["from_url" if isinstance(from_node, str) else "from"] = from_node
+    if to_node is not None:
+        params["to_url" if isinstance(to_node, str) else "to"] = to_node
+    if goals:
+        params["goals"] = goals
+    if goal is not None:
+        params["goal"] = goal
     if avoid_flags:
         params["avoid_flags"] = avoid_flags
+    if avoid_nodes:
+        params["avoid_nodes"] = avoid_nodes
+    if avoid_page_types:
+        params["avoid_page_types"] = avoid_page_types
+    if max_hops is not None:
+        params["max_hops"] = max_hops
+    if max_risk is not None:
+        params["max_risk"] = max_risk
     return params
 
 
@@ -41,6 +41,10 @@ class NodeMatch:
     confidence: float
     features: dict[int, float] = field(default_factory=dict)
     similarity: float | None = None
+    group_size: int = 1
+    template: str | None = None
+    trust: float | None = None
+    lineage: dict[str, dict[str, Any]] | None = None
 
     def __repr__(self) -> str:
         url_short = self.url[:50] + "..." if len(self.url) > 50 else self.url
@@ -69,6 +73,8 @@ class Path:
     total_weight: float
     hops: int
     required_actions: list[PathAction]
+    visit_order: list[int] = field(default_factory=list)
+    target: int | None = None
 
     def __repr__(self) -> str:
         return (
@@ -122,6 +128,7 @@ class ActResult:
     success: bool
     new_url: str | None = None
     features: dict[int, float] = field(default_factory=dict)
+    latency_ms: int = 0
 
     def __repr__(self) -> str:
         url = f", new_url={self.new_url!r}" if self.new_url else ""
@@ -186,7 +193,12 @@ class SiteMap:
         features: dict[int, dict[str, float]] | None = None,
         flags: dict[str, bool] | None = None,
         sort_by: tuple[int, str] | None = None,
-        limit: int = 100,
+        limit: int | None = None,
+        diversify: bool = False,
+        saved: str | None = None,
+        refine: bool = False,
+        refine_budget_ms: int | None = None,
+        include_lineage: bool = False,
     ) -> list[NodeMatch]:
         """Filter nodes by type, features, and flags.
 
@@ -195,7 +207,18 @@ class SiteMap:
             features: Feature dimension filters. E.g. ``{48: {"lt": 300}}`` for price < $300.
             flags: Flag filters. E.g. ``{"rendered": True}``.
             sort_by: Sort by a feature dimension. E.g. ``(48, "asc")`` for price ascending.
-            limit: Maximum results to return.
+            limit: Maximum results to return (default 100, or the saved
+                query's limit).
+            diversify: Collapse near-identical results (same URL template,
+                similar features) into one representative each, with
+                ``group_size`` set; ``limit`` then counts groups.
+            saved: Run the query saved in the registry under this name
+                (``cortex query --save``) instead of the filters above.
+            refine: Re-fetch stale or low-confidence result pages before
+                answering, within ``refine_budget_ms`` (server default 3000).
+            include_lineage: Attach each match's ``trust`` score and the
+                ``lineage`` of its values (layer, selector or JSON-LD path,
+                confidence, fetch time).
 
         Returns:
             List of matching nodes. Empty list if no matches (never None).
@@ -216,6 +239,11 @@ class SiteMap:
             flags=flags,
             sort_by=sort_by,
             limit=limit,
+            diversify=diversify,
+            saved=saved,
+            refine=refine,
+            refine_budget_ms=refine_budget_ms,
+            include_lineage=include_lineage,
         )
         resp = self._conn.send("query", params)
         return _parse_node_matches(resp)
@@ -253,18 +281,34 @@ class SiteMap:
 
     def pathfind(
         self,
-        from_node: int,
-        to_node: int,
+        from_node: int | str,
+        to_node: int | str | None = None,
         *,
+        goals: list[int | str] | None = None,
+        goal: int | list[float] | None = None,
         avoid_flags: list[str] | None = None,
+        avoid_nodes: list[int] | None = None,
+        avoid_page_types: list[int] | None = None,
+        max_hops: int | None = None,
+        max_risk: int | None = None,
         minimize: str = "hops",
     ) -> Path | None:
-        """Find shortest path between two nodes.
+        """Find shortest path between two nodes, or a route visiting several.
 
         Args:
-            from_node: Source node index.
-            to_node: Target node index.
+            from_node: Source node index or URL.
+            to_node: Target node index or URL (the final stop when ``goals``
+                is set).
+            goals: Nodes (indexes or URLs) to visit in any order; the route
+                order is returned in ``Path.visit_order``.
+            goal: Instead of ``to_node``, a page type code (nearest reachable
+                page of that type) or a feature vector (most similar
+                reachable page). The chosen node is ``Path.target``.
             avoid_flags: Flags to avoid (e.g. ``["auth_required"]``).
+            avoid_nodes: Node indexes the path must not pass through.
+            avoid_page_types: Page type codes the path must not pass through.
+            max_hops: Maximum number of hops.
+            max_risk: Maximum step risk (0 safe, 1 cautious, 2 destructive).
             minimize: What to minimize — ``"hops"`` or ``"weight"``.
 
         Returns:
@@ -285,7 +329,13 @@ class SiteMap:
             self.domain,
             from_node,
             to_node,
+            goals=goals,
+            goal=goal,
             avoid_flags=avoid_flags,
+            avoid_nodes=avoid_nodes,
+            avoid_page_types=avoid_page_types,
+            max_hops=max_hops,
+            max_risk=max_risk,
             minimize=minimize,
         )
         resp = self._conn.send("pathfind", params)
@@ -309,6 +359,8 @@ class SiteMap:
             total_weight=result.get("total_weight", 0.0),
             hops=result.get("hops", 0),
             required_actions=actions,
+            visit_order=result.get("visit_order", []),
+            target=result.get("target"),
         )
 
     def refresh(
@@ -390,6 +442,7 @@ class SiteMap:
             success=result.get("success", False),
             new_url=result.get("new_url"),
             features=result.get("features", {}),
+            latency_ms=result.get("latency_ms", 0),
         )
 
     def watch(
@@ -445,6 +498,10 @@ def _parse_node_matches(resp: dict[str, Any]) -> list[NodeMatch]:
             confidence=m.get("confidence", 0.0),
             features=m.get("features", {}),
             similarity=m.get("similarity"),
+            group_size=m.get("group_size", 1),
+            template=m.get("template"),
+            trust=m.get("trust"),
+            lineage=m.get("lineage"),
         )
         for m in matches
     ]
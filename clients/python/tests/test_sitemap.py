@@ -202,6 +202,13 @@ class TestProtocol:
         req = query_request("example.com", sort_by=(48, "desc"))
         assert req["sort_by"] == {"dimension": 48, "direction": "desc"}
 
+    def test_query_request_saved(self) -> None:
+        req = query_request("example.com", saved="cheap-in-stock")
+        assert req["saved"] == "cheap-in-stock"
+        assert "limit" not in req
+        req = query_request("example.com", saved="cheap-in-stock", limit=5)
+        assert req["limit"] == 5
+
     def test_pathfind_request(self) -> None:
         req = pathfind_request("example.com", 0, 10, avoid_flags=["auth_required"])
         assert req["from"] == 0
@@ -209,6 +216,26 @@ class TestProtocol:
         assert req["avoid_flags"] == ["auth_required"]
         assert req["minimize"] == "hops"
 
+    def test_pathfind_request_multi_goal(self) -> None:
+        req = pathfind_request(
+            "example.com", 0, goals=[4, 9], avoid_page_types=[8], max_hops=6, max_risk=1
+        )
+        assert "to" not in req
+        assert req["goals"] == [4, 9]
+        assert req["avoid_page_types"] == [8]
+        assert req["max_hops"] == 6
+        assert req["max_risk"] == 1
+
+    def test_pathfind_request_urls_and_goal(self) -> None:
+        req = pathfind_request("example.com", "https://example.com/p/1", goal=0x09)
+        assert req["from_url"] == "https://example.com/p/1"
+        assert "from" not in req
+        assert req["goal"] == 0x09
+
+        req = pathfind_request("example.com", 0, "https://example.com/cart")
+        assert req["from"] == 0
+        assert req["to_url"] == "https://example.com/cart"
+
     def test_refresh_request(self) -> None:
         req = refresh_request("example.com", nodes=[1, 2, 3], stale_threshold=3600.0)
         assert req["nodes"] == [1, 2, 3]
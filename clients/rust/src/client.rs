@@ -0,0 +1,424 @@
+// Copyright 2026 Cortex Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A connection to the daemon and the typed protocol methods.
+
+use crate::error::{DaemonError, Error, Result};
+use crate::types::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::io::{
+    AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, ReadHalf, WriteHalf,
+};
+
+/// Socket the daemon listens on unless `CORTEX_SOCKET` says otherwise.
+pub const DEFAULT_SOCKET_PATH: &str = "/tmp/cortex.sock";
+
+/// How long a call waits for its answer unless changed with
+/// [`Client::with_timeout`].
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Protocol version this client speaks.
+pub const PROTOCOL_VERSION: u16 = 1;
+
+/// A byte stream the protocol can run over.
+pub trait Transport: AsyncRead + AsyncWrite + Send + Unpin + 'static {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin + 'static> Transport for T {}
+
+/// Request ids must be unique across the daemon, not just the connection,
+/// so they carry the process id and a process-wide counter.
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// One connection to the daemon. Calls are sent one at a time; open more
+/// clients to run requests in parallel.
+pub struct Client {
+    reader: BufReader<ReadHalf<Box<dyn Transport>>>,
+    writer: WriteHalf<Box<dyn Transport>>,
+    api_key: Option<String>,
+    timeout: Duration,
+}
+
+#[derive(Serialize)]
+struct Request<'a, P> {
+    id: &'a str,
+    method: &'a str,
+    params: P,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    result: Option<Value>,
+    #[serde(default)]
+    error: Option<DaemonError>,
+}
+
+impl Client {
+    /// Connect to the local daemon at `CORTEX_SOCKET`, else
+    /// [`DEFAULT_SOCKET_PATH`], presenting the API key in `CORTEX_API_KEY`
+    /// if set.
+    #[cfg(unix)]
+    pub async fn connect() -> Result<Self> {
+        let path = std::env::var("CORTEX_SOCKET").unwrap_or_else(|_| DEFAULT_SOCKET_PATH.into());
+        let client = Self::connect_unix(&path).await?;
+        Ok(match std::env::var("CORTEX_API_KEY") {
+            Ok(key) if !key.is_empty() => client.with_api_key(key),
+            _ => client,
+        })
+    }
+
+    /// Connect to the daemon's Unix socket at `path`.
+    #[cfg(unix)]
+    pub async fn connect_unix(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let stream = tokio::net::UnixStream::connect(path)
+            .await
+            .map_err(|source| Error::Connect {
+                addr: path.display().to_string(),
+                source,
+            })?;
+        Ok(Self::new(stream))
+    }
+
+    /// Connect over TCP to `addr`. The daemon itself only listens on its
+    /// Unix socket; this reaches one forwarded to a port, e.g. with
+    /// `ssh -L` or `socat`.
+    pub async fn connect_tcp(
+        addr: impl tokio::net::ToSocketAddrs + std::fmt::Display,
+    ) -> Result<Self> {
+        let stream = tokio::net::TcpStream::connect(&addr)
+            .await
+            .map_err(|source| Error::Connect {
+                addr: addr.to_string(),
+                source,
+            })?;
+        stream.set_nodelay(true)?;
+        Ok(Self::new(stream))
+    }
+
+    /// Speak the protocol over an already open `stream`.
+    pub fn new(stream: impl Transport) -> Self {
+        let (reader, writer) = tokio::io::split(Box::new(stream) as Box<dyn Transport>);
+        Self {
+            reader: BufReader::new(reader),
+            writer,
+            api_key: None,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Present `key` with every request, for a daemon that requires keys.
+    pub fn with_api_key(mut self, key: impl Into<String>) -> Self {
+        self.api_key = Some(key.into());
+        self
+    }
+
+    /// Give up on a call after `timeout`. Maps of large sites can take
+    /// minutes; raise it for them.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Send `method` with `params` and decode its result. The typed
+    /// methods below wrap this; use it directly for methods they do not
+    /// cover. Progress lines are skipped.
+    pub async fn call<P: Serialize, R: DeserializeOwned>(
+        &mut self,
+        method: &str,
+        params: &P,
+    ) -> Result<R> {
+        let id = format!(
+            "rs-{}-{}",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        );
+        let mut params = serde_json::to_value(params)?;
+        if let (Some(key), Value::Object(map)) = (&self.api_key, &mut params) {
+            map.insert("api_key".into(), Value::String(key.clone()));
+        }
+        let mut line = serde_json::to_vec(&Request {
+            id: &id,
+            method,
+            params,
+        })?;
+        line.push(b'\n');
+
+        let timeout = self.timeout;
+        match tokio::time::timeout(timeout, self.exchange(&id, method, &line)).await {
+            Ok(result) => Ok(serde_json::from_value(result?)?),
+            Err(_) => Err(Error::Timeout {
+                method: method.to_string(),
+                after: timeout,
+            }),
+        }
+    }
+
+    async fn exchange(&mut self, id: &str, method: &str, line: &[u8]) -> Result<Value> {
+        self.writer.write_all(line).await?;
+        self.writer.flush().await?;
+        let mut buf = String::new();
+        loop {
+            buf.clear();
+            if self.reader.read_line(&mut buf).await? == 0 {
+                return Err(Error::Closed {
+                    method: method.to_string(),
+                });
+            }
+            if buf.trim().is_empty() {
+                continue;
+            }
+            let response: Response = serde_json::from_str(&buf)?;
+            // Requests that fail to parse are answered without their id.
+            if response.id.as_deref().is_some_and(|r| r != id) {
+                continue;
+            }
+            if let Some(error) = response.error {
+                return Err(Error::Daemon(error));
+            }
+            if let Some(result) = response.result {
+                return Ok(result);
+            }
+            // A `progress` line; the answer follows.
+        }
+    }
+
+    /// Check the daemon speaks this client's protocol, asking for up to
+    /// `pipeline` requests in flight on the connection.
+    pub async fn handshake(&mut self, pipeline: Option<u32>) -> Result<HandshakeResult> {
+        let mut params = serde_json::json!({
+            "client_version": env!("CARGO_PKG_VERSION"),
+            "protocol_version": PROTOCOL_VERSION,
+        });
+        if let Some(pipeline) = pipeline {
+            params["pipeline"] = pipeline.into();
+        }
+        self.call("handshake", &params).await
+    }
+
+    /// Map a domain, or load its cached map.
+    pub async fn map(&mut self, params: &MapParams) -> Result<MapResult> {
+        self.call("map", params).await
+    }
+
+    /// Find nodes of a mapped domain.
+    pub async fn query(&mut self, params: &QueryParams) -> Result<QueryResult> {
+        self.call("query", params).await
+    }
+
+    /// Find the pages of a mapped domain nearest in meaning to a goal.
+    pub async fn query_semantic(&mut self, params: &SemanticQueryParams) -> Result<QueryResult> {
+        self.call("query", params).await
+    }
+
+    /// Find a path through a mapped domain.
+    pub async fn pathfind(&mut self, params: &PathfindParams) -> Result<PathResult> {
+        self.call("pathfind", params).await
+    }
+
+    /// Render one live page and encode it.
+    pub async fn perceive(&mut self, params: &PerceiveParams) -> Result<PerceiveResult> {
+        self.call("perceive", params).await
+    }
+
+    /// Authenticate with a domain and open a session.
+    pub async fn auth(&mut self, params: &AuthParams) -> Result<AuthResult> {
+        self.call("auth", params).await
+    }
+
+    /// Run a WQL query over the daemon's maps, resolving `FROM` against
+    /// `project` if given.
+    pub async fn wql(&mut self, query: &str, project: Option<&str>) -> Result<WqlResult> {
+        let mut params = serde_json::json!({ "query": query });
+        if let Some(project) = project {
+            params["project"] = project.into();
+        }
+        self.call("wql", &params).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, BufReader, DuplexStream};
+
+    /// A client and the daemon end of its connection.
+    fn pair() -> (Client, BufReader<DuplexStream>) {
+        let (client, daemon) = tokio::io::duplex(64 * 1024);
+        (Client::new(client), BufReader::new(daemon))
+    }
+
+    async fn read_request(daemon: &mut BufReader<DuplexStream>) -> Value {
+        let mut line = String::new();
+        daemon.read_line(&mut line).await.unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    async fn reply(daemon: &mut BufReader<DuplexStream>, line: Value) {
+        let mut bytes = serde_json::to_vec(&line).unwrap();
+        bytes.push(b'\n');
+        daemon.get_mut().write_all(&bytes).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_call_sends_params_and_skips_progress() {
+        let (client, mut daemon) = pair();
+        let mut client = client.with_api_key("ck_test");
+        let daemon = tokio::spawn(async move {
+            let req = read_request(&mut daemon).await;
+            assert_eq!(req["method"], "map");
+            assert_eq!(
+                req["params"],
+                serde_json::json!({"domain": "shop.com", "max_nodes": 500, "api_key": "ck_test"})
+            );
+            let id = req["id"].clone();
+            reply(
+                &mut daemon,
+                serde_json::json!({"id": "other", "result": {}}),
+            )
+            .await;
+            reply(
+                &mut daemon,
+                serde_json::json!({"id": id, "progress": {"phase": "sampling"}}),
+            )
+            .await;
+            reply(
+                &mut daemon,
+                serde_json::json!({"id": id, "result": {
+                    "domain": "shop.com", "node_count": 42, "edge_count": 80,
+                    "cached": false, "compliance": {
+                        "robots_respected": true, "robots_found": true,
+                        "robots_skipped": [{"url": "https://shop.com/cart", "rule": "/cart"}],
+                        "crawl_delay_secs": null, "crawl_delay_honored": true,
+                        "terms": null, "compliant": true, "issues": []
+                    }
+                }}),
+            )
+            .await;
+        });
+
+        let params = MapParams {
+            max_nodes: Some(500),
+            ..MapParams::new("shop.com")
+        };
+        let result = client.map(&params).await.unwrap();
+        daemon.await.unwrap();
+        assert_eq!(result.node_count, 42);
+        assert!(!result.partial);
+        let compliance = result.compliance.unwrap();
+        assert!(compliance.compliant);
+        assert_eq!(compliance.robots_skipped[0].rule, "/cart");
+    }
+
+    #[tokio::test]
+    async fn test_daemon_error_and_closed_connection() {
+        let (mut client, mut daemon) = pair();
+        let daemon = tokio::spawn(async move {
+            let req = read_request(&mut daemon).await;
+            reply(
+                &mut daemon,
+                serde_json::json!({"id": req["id"], "error": {
+                    "code": "E_RATE_LIMITED", "message": "slow down",
+                    "category": "throttled", "retryable": true, "retry_after_ms": 250
+                }}),
+            )
+            .await;
+            read_request(&mut daemon).await;
+        });
+
+        let err = client
+            .query(&QueryParams::new("shop.com"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Some("E_RATE_LIMITED"));
+        assert!(err.retryable());
+        assert_eq!(err.retry_after(), Some(Duration::from_millis(250)));
+
+        let err = client
+            .wql("SELECT url FROM Product", None)
+            .await
+            .unwrap_err();
+        daemon.await.unwrap();
+        assert!(matches!(err, Error::Closed { ref method } if method == "wql"));
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        let (client, _daemon) = pair();
+        let mut client = client.with_timeout(Duration::from_millis(20));
+        let err = client.handshake(None).await.unwrap_err();
+        assert!(matches!(err, Error::Timeout { .. }));
+    }
+
+    #[test]
+    fn test_pathfind_params_use_url_keys() {
+        let params = PathfindParams {
+            avoid_flags: vec!["auth_required".into()],
+            learned_costs: false,
+            ..PathfindParams::new("shop.com", 0, "https://shop.com/checkout")
+        };
+        assert_eq!(
+            serde_json::to_value(&params).unwrap(),
+            serde_json::json!({
+                "domain": "shop.com", "from": 0, "to_url": "https://shop.com/checkout",
+                "avoid_flags": ["auth_required"], "minimize": "hops", "learned_costs": false
+            })
+        );
+
+        let params = PathfindParams {
+            goal: Some(Goal::PageType(0x0B)),
+            ..PathfindParams::starting_at("shop.com", "https://shop.com/")
+        };
+        assert_eq!(
+            serde_json::to_value(&params).unwrap(),
+            serde_json::json!({
+                "domain": "shop.com", "from_url": "https://shop.com/", "goal": 11,
+                "minimize": "hops"
+            })
+        );
+    }
+
+    #[test]
+    fn test_auth_and_query_params() {
+        let auth = AuthParams::Bearer {
+            domain: "api.shop.com".into(),
+            token: "t".into(),
+        };
+        assert_eq!(
+            serde_json::to_value(&auth).unwrap(),
+            serde_json::json!({"auth_type": "bearer", "domain": "api.shop.com", "token": "t"})
+        );
+
+        let mut query = QueryParams::new("shop.com");
+        query.page_type = vec![4];
+        query.features.insert(48, FeatureRange::at_most(300.0));
+        query.sort_by = Some(SortBy {
+            dimension: 48,
+            direction: Direction::Asc,
+        });
+        assert_eq!(
+            serde_json::to_value(&query).unwrap(),
+            serde_json::json!({
+                "domain": "shop.com", "page_type": [4], "features": {"48": {"lt": 300.0}},
+                "sort_by": {"dimension": 48, "direction": "asc"}
+            })
+        );
+
+        let semantic = SemanticQueryParams {
+            limit: Some(5),
+            ..SemanticQueryParams::new("shop.com", "return an order")
+        };
+        assert_eq!(
+            serde_json::to_value(&semantic).unwrap(),
+            serde_json::json!({
+                "domain": "shop.com", "mode": "semantic", "goal": "return an order", "limit": 5
+            })
+        );
+    }
+}
@@ -0,0 +1,84 @@
+// Copyright 2026 Cortex Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Errors from talking to the daemon.
+
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Result of a client call.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Why a call failed.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The daemon could not be reached.
+    #[error("cannot connect to Cortex at {addr}: {source} (start it with `cortex start`)")]
+    Connect {
+        addr: String,
+        #[source]
+        source: std::io::Error,
+    },
+    /// Reading or writing the connection failed.
+    #[error("connection to Cortex failed: {0}")]
+    Io(#[from] std::io::Error),
+    /// The daemon closed the connection before answering.
+    #[error("Cortex closed the connection before answering {method}")]
+    Closed { method: String },
+    /// No answer within the client's timeout.
+    #[error("{method} timed out after {}ms", .after.as_millis())]
+    Timeout { method: String, after: Duration },
+    /// A line from the daemon, or its result, was not what the method
+    /// returns.
+    #[error("invalid response from Cortex: {0}")]
+    InvalidResponse(#[from] serde_json::Error),
+    /// The daemon answered with an error.
+    #[error(transparent)]
+    Daemon(#[from] DaemonError),
+}
+
+impl Error {
+    /// Whether sending the same request again may succeed. Timeouts and
+    /// lost connections may; daemon errors say so themselves.
+    pub fn retryable(&self) -> bool {
+        match self {
+            Error::Connect { .. } | Error::Closed { .. } | Error::Timeout { .. } => true,
+            Error::Daemon(e) => e.retryable,
+            Error::Io(_) | Error::InvalidResponse(_) => false,
+        }
+    }
+
+    /// How long to wait before retrying, when the daemon suggests it.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::Daemon(e) => e.retry_after_ms.map(Duration::from_millis),
+            _ => None,
+        }
+    }
+
+    /// The daemon's error code (`E_NOT_FOUND`, …), if it answered with one.
+    pub fn code(&self) -> Option<&str> {
+        match self {
+            Error::Daemon(e) => Some(&e.code),
+            _ => None,
+        }
+    }
+}
+
+/// An `error` object from the daemon. See the API reference's "Errors"
+/// section for the codes and categories.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, thiserror::Error)]
+#[error("{code}: {message}")]
+pub struct DaemonError {
+    /// Machine-readable code, e.g. `E_RATE_LIMITED`. New codes may be added.
+    pub code: String,
+    pub message: String,
+    /// Group of codes, e.g. `throttled`; branch on this rather than `code`.
+    #[serde(default)]
+    pub category: String,
+    #[serde(default)]
+    pub retryable: bool,
+    /// Suggested wait before retrying; present only when `retryable`.
+    #[serde(default)]
+    pub retry_after_ms: Option<u64>,
+}
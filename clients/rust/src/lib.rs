@@ -0,0 +1,35 @@
+// Copyright 2026 Cortex Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Typed async client for the Cortex runtime's socket protocol.
+//!
+//! [`Client`] sends newline-delimited JSON requests to the daemon over its
+//! Unix socket (or a TCP port forwarded to it) and decodes the answers into
+//! the types in [`types`]. Daemon errors keep their code, category and retry
+//! hint in [`DaemonError`].
+//!
+//! ```no_run
+//! use cortex_client::{Client, FeatureRange, MapParams, QueryParams};
+//!
+//! # async fn run() -> cortex_client::Result<()> {
+//! let mut client = Client::connect().await?;
+//! let site = client.map(&MapParams::new("shop.com")).await?;
+//! println!("{} pages", site.node_count);
+//!
+//! let mut query = QueryParams::new("shop.com");
+//! query.page_type = vec![4];
+//! query.features.insert(48, FeatureRange::at_most(300.0));
+//! for m in client.query(&query).await?.matches {
+//!     println!("{}", m.url);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod client;
+pub mod error;
+pub mod types;
+
+pub use client::{Client, Transport, DEFAULT_SOCKET_PATH, DEFAULT_TIMEOUT, PROTOCOL_VERSION};
+pub use error::{DaemonError, Error, Result};
+pub use types::*;
@@ -0,0 +1,635 @@
+// Copyright 2026 Cortex Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Parameters and results of the protocol methods.
+//!
+//! Parameter structs serialize to the `params` of a request, leaving out
+//! what is unset so the daemon applies its defaults; build them with `new`
+//! and struct update syntax. Result structs ignore fields they do not know,
+//! so a newer daemon does not break an older client.
+
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+// ─── handshake ───────────────────────────────────────────────────────────────
+
+/// Result of `handshake`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct HandshakeResult {
+    pub server_version: String,
+    pub protocol_version: u16,
+    pub compatible: bool,
+    /// Permission of the API key the connection presented, if any.
+    #[serde(default)]
+    pub permission: Option<String>,
+    /// Requests the connection may have in flight at once.
+    #[serde(default = "one")]
+    pub pipeline: u32,
+}
+
+fn one() -> u32 {
+    1
+}
+
+// ─── map ─────────────────────────────────────────────────────────────────────
+
+/// Parameters of `map`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MapParams {
+    pub domain: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_nodes: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_render: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_time_ms: Option<u64>,
+    /// Unset follows the domain's project, else `true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub respect_robots: Option<bool>,
+    /// Continue an interrupted map from its checkpoint.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub resume: bool,
+    /// Remap a map scoring below this (0–1) with fallbacks, and fail with
+    /// `E_LOW_QUALITY` if none reaches it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_quality: Option<f32>,
+}
+
+impl MapParams {
+    pub fn new(domain: impl Into<String>) -> Self {
+        Self {
+            domain: domain.into(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Result of `map`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MapResult {
+    /// Missing from fallback maps.
+    #[serde(default)]
+    pub domain: Option<String>,
+    pub node_count: usize,
+    pub edge_count: usize,
+    #[serde(default)]
+    pub cached: bool,
+    /// Where the daemon cached the map, if it did.
+    #[serde(default)]
+    pub map_path: Option<String>,
+    /// Built from the pages fetched before the map was cancelled.
+    #[serde(default)]
+    pub partial: bool,
+    /// Sampling stopped early; map again with `resume` to continue.
+    #[serde(default)]
+    pub resumable: bool,
+    /// A homepage-and-sitemap map built after the mapper timed out.
+    #[serde(default)]
+    pub timeout_fallback: bool,
+    /// A homepage-and-sitemap map built after the mapper failed.
+    #[serde(default)]
+    pub error_fallback: bool,
+    /// How the map treated robots.txt and the terms of service; fallback
+    /// maps have none.
+    #[serde(default)]
+    pub compliance: Option<ComplianceReport>,
+    /// How good the map is; fallback maps have none.
+    #[serde(default)]
+    pub quality: Option<QualityReport>,
+    /// Remaps tried because the map scored below `min_quality`:
+    /// `resume`, `render_more`.
+    #[serde(default)]
+    pub fallbacks: Vec<String>,
+}
+
+/// A map's record of robots.txt and terms of service.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ComplianceReport {
+    pub robots_respected: bool,
+    pub robots_found: bool,
+    #[serde(default)]
+    pub robots_skipped: Vec<RobotsSkip>,
+    #[serde(default)]
+    pub crawl_delay_secs: Option<f32>,
+    pub crawl_delay_honored: bool,
+    #[serde(default)]
+    pub terms: Option<TermsCheck>,
+    pub compliant: bool,
+    #[serde(default)]
+    pub issues: Vec<String>,
+}
+
+/// A map's quality score and the checks behind it.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct QualityReport {
+    /// From 0 to 1.
+    pub score: f32,
+    /// Share of pages reachable from the home page.
+    pub connectivity: f32,
+    /// Share of pages with a known type.
+    pub typed: f32,
+    #[serde(default)]
+    pub dominant_type: Option<String>,
+    #[serde(default)]
+    pub dominant_share: f32,
+    #[serde(default)]
+    pub pages_fetched: usize,
+    /// Share of fetched pages with content features.
+    pub feature_fill: f32,
+    pub duplicate_ratio: f32,
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+/// A URL robots.txt kept the mapper from fetching.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct RobotsSkip {
+    pub url: String,
+    pub rule: String,
+}
+
+/// What a terms-of-service page says about automated access.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TermsCheck {
+    pub url: String,
+    #[serde(default)]
+    pub mentions: Vec<String>,
+}
+
+// ─── query ───────────────────────────────────────────────────────────────────
+
+/// Parameters of `query`: a filter on a mapped domain.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct QueryParams {
+    pub domain: String,
+    /// Page type codes to match (`4` = product detail, …).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub page_type: Vec<u8>,
+    /// Ranges on feature dimensions, by dimension.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub features: BTreeMap<usize, FeatureRange>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flags: Option<QueryFlags>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_by: Option<SortBy>,
+    /// Words to find in page titles and descriptions; matches are ranked
+    /// by relevance.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Unset is 100, or the saved query's limit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// Group near-duplicate matches, one representative per group.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub diversify: bool,
+    /// Name of a saved query to run instead of the filters above.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub saved: Option<String>,
+    /// Re-fetch stale or low-confidence matches before answering.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub refine: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refine_budget_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refine_max_pages: Option<usize>,
+    /// Attach each match's lineage and trust score.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub include_lineage: bool,
+}
+
+impl QueryParams {
+    pub fn new(domain: impl Into<String>) -> Self {
+        Self {
+            domain: domain.into(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Parameters of a semantic `query`: the pages nearest in meaning to a
+/// free-text goal. Needs a daemon with a sentence model.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SemanticQueryParams {
+    pub domain: String,
+    /// What the agent is looking for, e.g. "where do I return an order".
+    pub goal: String,
+    /// Unset is 10.
+    pub limit: Option<usize>,
+    /// Attach each match's lineage and trust score.
+    pub include_lineage: bool,
+}
+
+impl SemanticQueryParams {
+    pub fn new(domain: impl Into<String>, goal: impl Into<String>) -> Self {
+        Self {
+            domain: domain.into(),
+            goal: goal.into(),
+            ..Self::default()
+        }
+    }
+}
+
+// Semantic queries are QUERY with `mode: "semantic"`.
+impl Serialize for SemanticQueryParams {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("domain", &self.domain)?;
+        map.serialize_entry("mode", "semantic")?;
+        map.serialize_entry("goal", &self.goal)?;
+        if let Some(limit) = self.limit {
+            map.serialize_entry("limit", &limit)?;
+        }
+        if self.include_lineage {
+            map.serialize_entry("include_lineage", &true)?;
+        }
+        map.end()
+    }
+}
+
+/// Bounds on one feature dimension. Lower and upper bounds are inclusive
+/// whichever of `gt`/`gte` or `lt`/`lte` is used.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct FeatureRange {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gt: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lt: Option<f32>,
+}
+
+impl FeatureRange {
+    /// Values from `min` up.
+    pub fn at_least(min: f32) -> Self {
+        Self {
+            gt: Some(min),
+            lt: None,
+        }
+    }
+
+    /// Values up to `max`.
+    pub fn at_most(max: f32) -> Self {
+        Self {
+            gt: None,
+            lt: Some(max),
+        }
+    }
+
+    /// Values from `min` to `max`.
+    pub fn between(min: f32, max: f32) -> Self {
+        Self {
+            gt: Some(min),
+            lt: Some(max),
+        }
+    }
+}
+
+/// Node flags a match must have.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct QueryFlags {
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub rendered: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub has_price: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub has_form: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub has_media: bool,
+}
+
+/// Order of matches by one feature dimension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SortBy {
+    pub dimension: usize,
+    pub direction: Direction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Asc,
+    Desc,
+}
+
+/// Result of `query`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct QueryResult {
+    pub matches: Vec<NodeMatch>,
+    /// What `refine` re-fetched, when asked for.
+    #[serde(default)]
+    pub freshness: Option<Value>,
+}
+
+/// A node that matched a query.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct NodeMatch {
+    pub index: u32,
+    pub url: String,
+    pub page_type: u8,
+    pub confidence: f32,
+    /// Non-zero features, by dimension. Empty for diversified matches.
+    #[serde(default)]
+    pub features: BTreeMap<usize, f32>,
+    #[serde(default)]
+    pub similarity: Option<f32>,
+    /// BM25 relevance of the page's title and description to the query's
+    /// `text`.
+    #[serde(default)]
+    pub text_score: Option<f32>,
+    /// For semantic queries: the page's title and description.
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// URL template of a diversified match's group.
+    #[serde(default)]
+    pub template: Option<String>,
+    #[serde(default)]
+    pub group_size: Option<usize>,
+    /// Node indexes of a diversified match's group.
+    #[serde(default)]
+    pub members: Vec<u32>,
+    /// With `include_lineage`: the node's trust score, if it has lineage.
+    #[serde(default)]
+    pub trust: Option<f32>,
+    /// With `include_lineage`: where each traced value came from.
+    #[serde(default)]
+    pub lineage: Option<Value>,
+    /// With `include_lineage`: values the acquisition layers disagreed on.
+    #[serde(default)]
+    pub conflicts: Option<Value>,
+}
+
+// ─── pathfind ────────────────────────────────────────────────────────────────
+
+/// A node given by index or by URL.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum NodeRef {
+    Index(u32),
+    Url(String),
+}
+
+impl From<u32> for NodeRef {
+    fn from(index: u32) -> Self {
+        NodeRef::Index(index)
+    }
+}
+
+impl From<&str> for NodeRef {
+    fn from(url: &str) -> Self {
+        NodeRef::Url(url.to_string())
+    }
+}
+
+impl From<String> for NodeRef {
+    fn from(url: String) -> Self {
+        NodeRef::Url(url)
+    }
+}
+
+/// A semantic pathfinding goal.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Goal {
+    /// The nearest page of this page type code.
+    PageType(u8),
+    /// The page most similar to this feature vector.
+    Features(Vec<f32>),
+}
+
+/// What a path minimizes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Minimize {
+    #[default]
+    Hops,
+    Weight,
+    StateChanges,
+}
+
+/// Parameters of `pathfind`: a path from `from` to `to`, a route through
+/// every node in `goals`, or a path to the nearest node meeting `goal`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathfindParams {
+    pub domain: String,
+    pub from: NodeRef,
+    pub to: Option<NodeRef>,
+    pub goals: Vec<NodeRef>,
+    pub goal: Option<Goal>,
+    /// `auth_required` and/or `state_changes`.
+    pub avoid_flags: Vec<String>,
+    pub avoid_nodes: Vec<u32>,
+    pub avoid_page_types: Vec<u8>,
+    pub max_hops: Option<u32>,
+    pub max_risk: Option<u8>,
+    pub minimize: Minimize,
+    /// Use action costs learned from ACT outcomes (the default).
+    pub learned_costs: bool,
+}
+
+impl PathfindParams {
+    /// A path on `domain` from `from` to `to`.
+    pub fn new(
+        domain: impl Into<String>,
+        from: impl Into<NodeRef>,
+        to: impl Into<NodeRef>,
+    ) -> Self {
+        Self {
+            to: Some(to.into()),
+            ..Self::starting_at(domain, from)
+        }
+    }
+
+    /// Pathfinding on `domain` from `from`; set `to`, `goals` or `goal`.
+    pub fn starting_at(domain: impl Into<String>, from: impl Into<NodeRef>) -> Self {
+        Self {
+            domain: domain.into(),
+            from: from.into(),
+            to: None,
+            goals: Vec::new(),
+            goal: None,
+            avoid_flags: Vec::new(),
+            avoid_nodes: Vec::new(),
+            avoid_page_types: Vec::new(),
+            max_hops: None,
+            max_risk: None,
+            minimize: Minimize::default(),
+            learned_costs: true,
+        }
+    }
+}
+
+// The daemon takes a node index and a URL under different keys (`from`,
+// `from_url`), so `NodeRef` fields are written by hand.
+impl Serialize for PathfindParams {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        fn node<M: SerializeMap>(
+            map: &mut M,
+            key: &'static str,
+            url_key: &'static str,
+            node: &NodeRef,
+        ) -> Result<(), M::Error> {
+            match node {
+                NodeRef::Index(index) => map.serialize_entry(key, index),
+                NodeRef::Url(url) => map.serialize_entry(url_key, url),
+            }
+        }
+
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("domain", &self.domain)?;
+        node(&mut map, "from", "from_url", &self.from)?;
+        if let Some(to) = &self.to {
+            node(&mut map, "to", "to_url", to)?;
+        }
+        if !self.goals.is_empty() {
+            map.serialize_entry("goals", &self.goals)?;
+        }
+        if let Some(goal) = &self.goal {
+            map.serialize_entry("goal", goal)?;
+        }
+        if !self.avoid_flags.is_empty() {
+            map.serialize_entry("avoid_flags", &self.avoid_flags)?;
+        }
+        if !self.avoid_nodes.is_empty() {
+            map.serialize_entry("avoid_nodes", &self.avoid_nodes)?;
+        }
+        if !self.avoid_page_types.is_empty() {
+            map.serialize_entry("avoid_page_types", &self.avoid_page_types)?;
+        }
+        if let Some(max_hops) = self.max_hops {
+            map.serialize_entry("max_hops", &max_hops)?;
+        }
+        if let Some(max_risk) = self.max_risk {
+            map.serialize_entry("max_risk", &max_risk)?;
+        }
+        map.serialize_entry("minimize", &self.minimize)?;
+        if !self.learned_costs {
+            map.serialize_entry("learned_costs", &false)?;
+        }
+        map.end()
+    }
+}
+
+/// Result of `pathfind`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PathResult {
+    /// Node indexes along the path, `from` first.
+    pub nodes: Vec<u32>,
+    pub total_weight: f32,
+    pub hops: u32,
+    #[serde(default)]
+    pub required_actions: Vec<RequiredAction>,
+    /// For `goals`: the order the goals are visited in.
+    #[serde(default)]
+    pub visit_order: Option<Vec<u32>>,
+    /// For `goal`: the node that met it.
+    #[serde(default)]
+    pub target: Option<u32>,
+    #[serde(default)]
+    pub target_url: Option<String>,
+}
+
+/// An action to take at a node to move along the path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct RequiredAction {
+    pub at_node: u32,
+    /// `[category, action]`.
+    pub opcode: [u8; 2],
+}
+
+// ─── perceive ────────────────────────────────────────────────────────────────
+
+/// Parameters of `perceive`: render one live page.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PerceiveParams {
+    pub url: String,
+    /// Unset includes the page's text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_content: Option<bool>,
+    /// Also return a base64 PNG screenshot.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub screenshot: bool,
+}
+
+impl PerceiveParams {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Result of `perceive`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PerceiveResult {
+    pub url: String,
+    pub final_url: String,
+    pub page_type: u8,
+    pub confidence: f32,
+    /// Non-zero features, by dimension.
+    #[serde(default)]
+    pub features: BTreeMap<usize, f32>,
+    #[serde(default)]
+    pub content: Option<String>,
+    pub load_time_ms: u64,
+    /// Base64 PNG, when asked for.
+    #[serde(default)]
+    pub screenshot: Option<String>,
+}
+
+// ─── auth ────────────────────────────────────────────────────────────────────
+
+/// Parameters of `auth`: credentials for a domain, by kind.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "auth_type", rename_all = "snake_case")]
+pub enum AuthParams {
+    /// Send `key` in header `header_name` (default `X-Api-Key`).
+    ApiKey {
+        domain: String,
+        key: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        header_name: Option<String>,
+    },
+    /// Send `Authorization: Bearer <token>`.
+    Bearer { domain: String, token: String },
+    /// Log in through the site's login form.
+    Password {
+        domain: String,
+        username: String,
+        password: String,
+    },
+}
+
+/// Result of `auth`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AuthResult {
+    /// Pass as `session_id` to later requests.
+    pub session_id: String,
+    pub domain: String,
+    pub auth_type: String,
+    /// Other domains of the domain's project the session also serves.
+    #[serde(default)]
+    pub shared_with: Vec<String>,
+}
+
+// ─── wql ─────────────────────────────────────────────────────────────────────
+
+/// Result of `wql`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct WqlResult {
+    pub query: String,
+    pub total: usize,
+    pub rows: Vec<WqlRow>,
+}
+
+/// One row of a WQL result: a node and the fields selected from it.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct WqlRow {
+    pub domain: String,
+    pub url: String,
+    pub node_id: u32,
+    #[serde(default)]
+    pub fields: BTreeMap<String, Value>,
+}
@@ -25,6 +25,7 @@ export { SiteMapClient } from "./sitemap";
 
 export type {
   NodeMatch,
+  FieldLineage,
   Path,
   PathAction,
   RefreshResult,
@@ -31,6 +31,21 @@ export interface NodeMatch {
   confidence: number;
   features: Record<number, number>;
   similarity?: number;
+  /** With `diversify`: results this one stands for, and their URL template. */
+  groupSize?: number;
+  template?: string;
+  /** With `includeLineage`: trust score and where each value came from. */
+  trust?: number;
+  lineage?: Record<string, FieldLineage>;
+}
+
+export interface FieldLineage {
+  layer: "url" | "structured_data" | "pattern" | "actions" | "browser";
+  /** Selector or JSON-LD path the value was read from. */
+  path?: string;
+  confidence: number;
+  /** Unix seconds when the page was fetched. */
+  fetched_at: number;
 }
 
 export interface PathAction {
@@ -43,6 +58,27 @@ export interface Path {
   totalWeight: number;
   hops: number;
   requiredActions: PathAction[];
+  /** Goals in visiting order, for multi-goal routes. */
+  visitOrder: number[];
+  /** The node chosen for a `goal` search. */
+  target?: number;
+}
+
+export interface PathfindOptions {
+  /** Nodes to visit in any order; `toNode` (if any) becomes the final stop. */
+  goals?: (number | string)[];
+  /**
+   * Instead of `toNode`: a page type code (nearest reachable page of that
+   * type) or a feature vector (most similar reachable page).
+   */
+  goal?: number | number[];
+  avoidFlags?: string[];
+  avoidNodes?: number[];
+  avoidPageTypes?: number[];
+  maxHops?: number;
+  /** 0 safe, 1 cautious, 2 destructive. */
+  maxRisk?: number;
+  minimize?: string;
 }
 
 export interface RefreshResult {
@@ -54,6 +90,8 @@ export interface ActResult {
   success: boolean;
   newUrl?: string;
   features: Record<number, number>;
+  /** Execution time; recorded to weight future pathfinding. */
+  latencyMs: number;
 }
 
 export interface WatchDelta {
@@ -68,6 +106,16 @@ export interface NodeQuery {
   flags?: Record<string, boolean>;
   sortBy?: { dimension: number; direction: string };
   limit?: number;
+  /** Collapse near-identical results; `limit` then counts groups. */
+  diversify?: boolean;
+  /** Run the query saved in the registry under this name instead. */
+  saved?: string;
+  /** Re-fetch stale or low-confidence result pages before answering. */
+  refine?: boolean;
+  /** Time budget for `refine` (server default 3000 ms). */
+  refineBudgetMs?: number;
+  /** Attach each match's trust score and value lineage. */
+  includeLineage?: boolean;
 }
 
 // ---------------------------------------------------------------------------
@@ -78,10 +126,12 @@ function buildQueryParams(
   domain: string,
   query: NodeQuery = {}
 ): Record<string, unknown> {
-  const params: Record<string, unknown> = {
-    domain,
-    limit: query.limit ?? 100,
-  };
+  const params: Record<string, unknown> = { domain };
+  if (query.saved !== undefined) params.saved = query.saved;
+  // A saved query carries its own limit unless one is given.
+  if (query.limit !== undefined || query.saved === undefined) {
+    params.limit = query.limit ?? 100;
+  }
   if (query.pageType !== undefined) {
     params.page_type = Array.isArray(query.pageType)
       ? query.pageType
@@ -95,6 +145,14 @@ function buildQueryParams(
       direction: query.sortBy.direction,
     };
   }
+  if (query.diversify) params.diversify = true;
+  if (query.refine) {
+    params.refine = true;
+    if (query.refineBudgetMs !== undefined) {
+      params.refine_budget_ms = query.refineBudgetMs;
+    }
+  }
+  if (query.includeLineage) params.include_lineage = true;
   return params;
 }
 
@@ -111,6 +169,10 @@ function parseNodeMatches(resp: CortexResponse): NodeMatch[] {
     confidence: (m.confidence as number) ?? 0,
     features: (m.features as Record<number, number>) ?? {},
     similarity: m.similarity as number | undefined,
+    groupSize: m.group_size as number | undefined,
+    template: m.template as string | undefined,
+    trust: (m.trust as number | null) ?? undefined,
+    lineage: (m.lineage as Record<string, FieldLineage> | null) ?? undefined,
   }));
 }
 
@@ -180,22 +242,29 @@ export class SiteMapClient {
   }
 
   /**
-   * Find shortest path between two nodes.
+   * Find shortest path between two nodes, or a route visiting `options.goals`.
+   * Nodes may be given as indexes or URLs.
    *
    * @returns Path object, or null if no path exists.
    */
   async pathfind(
-    fromNode: number,
-    toNode: number,
-    options: { avoidFlags?: string[]; minimize?: string } = {}
+    fromNode: number | string,
+    toNode: number | string | null,
+    options: PathfindOptions = {}
   ): Promise<Path | null> {
     const params: Record<string, unknown> = {
       domain: this.domain,
-      from: fromNode,
-      to: toNode,
       minimize: options.minimize ?? "hops",
     };
+    params[typeof fromNode === "string" ? "from_url" : "from"] = fromNode;
+    if (toNode !== null) params[typeof toNode === "string" ? "to_url" : "to"] = toNode;
+    if (options.goals) params.goals = options.goals;
+    if (options.goal !== undefined) params.goal = options.goal;
     if (options.avoidFlags) params.avoid_flags = options.avoidFlags;
+    if (options.avoidNodes) params.avoid_nodes = options.avoidNodes;
+    if (options.avoidPageTypes) params.avoid_page_types = options.avoidPageTypes;
+    if (options.maxHops !== undefined) params.max_hops = options.maxHops;
+    if (options.maxRisk !== undefined) params.max_risk = options.maxRisk;
 
     const resp = await this.conn.send("pathfind", params);
 
@@ -220,6 +289,8 @@ export class SiteMapClient {
       totalWeight: (result.total_weight as number) ?? 0,
       hops: (result.hops as number) ?? 0,
       requiredActions: actions,
+      visitOrder: (result.visit_order as number[]) ?? [],
+      target: result.target as number | undefined,
     };
   }
 
@@ -279,6 +350,7 @@ export class SiteMapClient {
       success: (result.success as boolean) ?? false,
       newUrl: result.new_url as string | undefined,
       features: (result.features as Record<number, number>) ?? {},
+      latencyMs: (result.latency_ms as number) ?? 0,
     };
   }
 }
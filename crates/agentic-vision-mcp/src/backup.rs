@@ -0,0 +1,361 @@
+//! Consistent backups of vision stores, and restoring them.
+//!
+//! Copying a `.avis` file while the server writes it can catch it half
+//! written. A backup instead serializes each loaded store under its session
+//! lock, so it holds one consistent state, unsaved changes included. Stores
+//! no session has loaded are copied from disk, which is safe because saves
+//! replace files atomically. Every store is read back before it is kept.
+//!
+//! A backup is a directory named by its UTC time (`20261015T152327.551Z`)
+//! holding one `{name}.avis` per store and a `manifest.json`. It is built
+//! under a `.partial` name and renamed when complete, so a directory without
+//! that suffix is always whole. Backups beyond the retention count are
+//! deleted, oldest first.
+//!
+//! Erasing a tenant shreds its store from every backup and drops it from
+//! their manifests. Restore also skips any store whose user was erased
+//! after the backup was taken, as recorded in the target's erasure audit.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use agentic_vision::{sha256_hex, AvisReader};
+
+use crate::migrate::staging_path;
+use crate::session::manager::{erase_files, ERASURE_AUDIT_FILE};
+use crate::session::VisionSessionManager;
+use crate::types::{McpError, McpResult};
+
+/// Name of the manifest in a backup directory.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Suffix of a backup directory still being written.
+const PARTIAL_SUFFIX: &str = ".partial";
+
+/// Where and how often to back up.
+#[derive(Debug, Clone)]
+pub struct BackupConfig {
+    pub dir: PathBuf,
+    /// Time between periodic backups; `None` backs up only on request.
+    pub interval: Option<Duration>,
+    /// Backups kept; older ones are deleted.
+    pub keep: usize,
+}
+
+/// A store to back up.
+pub enum BackupSource {
+    /// A store a session has loaded, serialized under its lock.
+    Session {
+        name: String,
+        session: Arc<Mutex<VisionSessionManager>>,
+    },
+    /// A store on disk that no session has loaded.
+    File { name: String, path: PathBuf },
+}
+
+/// What a backup holds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    /// Name of the backup directory.
+    pub name: String,
+    /// RFC 3339 time of the backup.
+    pub created_at: String,
+    pub stores: Vec<BackedUpStore>,
+}
+
+/// One store in a backup.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackedUpStore {
+    /// Store name: the user ID of a tenant, or the stem of the vision file.
+    pub name: String,
+    /// File in the backup directory.
+    pub file: String,
+    pub captures: usize,
+    pub bytes: u64,
+    /// Hex SHA-256 of the file, checked on restore.
+    pub sha256: String,
+}
+
+/// Back up `sources` into a new directory under `config.dir`, then delete
+/// the backups beyond `config.keep`.
+pub async fn backup(
+    sources: Vec<BackupSource>,
+    config: &BackupConfig,
+) -> McpResult<BackupManifest> {
+    let now = chrono::Utc::now();
+    let name = now.format("%Y%m%dT%H%M%S%.3fZ").to_string();
+    let partial = config.dir.join(format!("{name}{PARTIAL_SUFFIX}"));
+    std::fs::create_dir_all(&partial)?;
+
+    let result = write_stores(sources, &partial).await;
+    let stores = match result {
+        Ok(stores) => stores,
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&partial);
+            return Err(e);
+        }
+    };
+    let manifest = BackupManifest {
+        name: name.clone(),
+        created_at: now.to_rfc3339(),
+        stores,
+    };
+    write_synced(
+        &partial.join(MANIFEST_FILE),
+        &serde_json::to_vec_pretty(&manifest)?,
+    )?;
+    std::fs::rename(&partial, config.dir.join(&name))?;
+
+    let pruned = prune(&config.dir, config.keep)?;
+    tracing::info!(
+        "Backed up {} store(s) to {}; {} old backup(s) deleted",
+        manifest.stores.len(),
+        config.dir.join(&name).display(),
+        pruned.len()
+    );
+    Ok(manifest)
+}
+
+async fn write_stores(sources: Vec<BackupSource>, dir: &Path) -> McpResult<Vec<BackedUpStore>> {
+    let mut stores = Vec::new();
+    for source in sources {
+        let (name, bytes) = match source {
+            BackupSource::Session { name, session } => {
+                let session = session.lock().await;
+                // Erased while the backup was being prepared.
+                if session.is_erased() {
+                    continue;
+                }
+                (name, session.snapshot_bytes()?)
+            }
+            BackupSource::File { name, path } => match std::fs::read(&path) {
+                Ok(bytes) => (name, bytes),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            },
+        };
+        let store = AvisReader::read_from(&mut bytes.as_slice()).map_err(|e| {
+            McpError::VisionError(format!("Backup of '{name}' failed verification: {e}"))
+        })?;
+        let file = format!("{name}.avis");
+        write_synced(&dir.join(&file), &bytes)?;
+        stores.push(BackedUpStore {
+            name,
+            file,
+            captures: store.count(),
+            bytes: bytes.len() as u64,
+            sha256: sha256_hex(&bytes),
+        });
+    }
+    Ok(stores)
+}
+
+fn write_synced(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut file = std::fs::File::create(path)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+/// Complete backups under `dir`, oldest first.
+pub fn list_backups(dir: &Path) -> McpResult<Vec<PathBuf>> {
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            !path.to_string_lossy().ends_with(PARTIAL_SUFFIX) && path.join(MANIFEST_FILE).is_file()
+        })
+        .collect();
+    backups.sort();
+    Ok(backups)
+}
+
+/// Delete all but the newest `keep` backups under `dir`, and any partial
+/// one left by an interrupted run. Returns the directories deleted.
+pub fn prune(dir: &Path, keep: usize) -> McpResult<Vec<PathBuf>> {
+    let backups = list_backups(dir)?;
+    let mut deleted: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_dir() && path.to_string_lossy().ends_with(PARTIAL_SUFFIX))
+        .collect();
+    deleted.extend(
+        backups
+            .iter()
+            .take(backups.len().saturating_sub(keep))
+            .cloned(),
+    );
+    for path in &deleted {
+        std::fs::remove_dir_all(path)?;
+    }
+    Ok(deleted)
+}
+
+/// Shred the store `name` from every backup under `dir`, partial ones
+/// included, and drop it from their manifests. Returns the names of the
+/// backups it was removed from.
+pub fn purge_store(dir: &Path, name: &str) -> McpResult<Vec<String>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut purged = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let (files, _) = erase_files(&path.join(format!("{name}.avis")))?;
+        let manifest_path = path.join(MANIFEST_FILE);
+        let mut listed = false;
+        if manifest_path.is_file() {
+            let mut manifest: BackupManifest =
+                serde_json::from_slice(&std::fs::read(&manifest_path)?)?;
+            let before = manifest.stores.len();
+            manifest.stores.retain(|s| s.name != name);
+            listed = manifest.stores.len() < before;
+            if listed {
+                let staging = staging_path(&manifest_path);
+                write_synced(&staging, &serde_json::to_vec_pretty(&manifest)?)?;
+                std::fs::rename(&staging, &manifest_path)?;
+            }
+        }
+        if listed || !files.is_empty() {
+            purged.push(entry.file_name().to_string_lossy().into_owned());
+        }
+    }
+    purged.sort();
+    Ok(purged)
+}
+
+/// An entry of a data directory's erasure audit.
+#[derive(Deserialize)]
+struct ErasureEntry {
+    user_id: String,
+    erased_at: String,
+}
+
+/// Users erased from `data_dir` at or after `since` (RFC 3339).
+fn erased_since(data_dir: &Path, since: &str) -> McpResult<HashSet<String>> {
+    let audit = match std::fs::read_to_string(data_dir.join(ERASURE_AUDIT_FILE)) {
+        Ok(audit) => audit,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashSet::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let since = chrono::DateTime::parse_from_rfc3339(since)
+        .map_err(|e| McpError::VisionError(format!("Bad backup time '{since}': {e}")))?;
+    Ok(audit
+        .lines()
+        .filter_map(|line| serde_json::from_str::<ErasureEntry>(line).ok())
+        .filter(|entry| {
+            chrono::DateTime::parse_from_rfc3339(&entry.erased_at)
+                .is_ok_and(|erased_at| erased_at >= since)
+        })
+        .map(|entry| entry.user_id)
+        .collect())
+}
+
+/// The backup at `path`, or the newest one under it.
+pub fn find_backup(path: &Path) -> McpResult<(PathBuf, BackupManifest)> {
+    let dir = if path.join(MANIFEST_FILE).is_file() {
+        path.to_path_buf()
+    } else {
+        list_backups(path)?.pop().ok_or_else(|| {
+            McpError::InvalidParams(format!("No complete backup in {}", path.display()))
+        })?
+    };
+    let manifest = serde_json::from_slice(&std::fs::read(dir.join(MANIFEST_FILE))?)?;
+    Ok((dir, manifest))
+}
+
+/// Restore stores from the backup at `backup` (or the newest under it).
+///
+/// A `target` ending in `.avis` receives the one store named by `store`, or
+/// the backup's only store; any other `target` is a directory that receives
+/// `{name}.avis` for each store, as a multi-tenant data directory. Every
+/// file is checked against the manifest before anything is written, and
+/// existing files are only replaced with `force`.
+///
+/// Stores of users erased from the target's data directory since the
+/// backup was taken are never restored; they are returned as skipped.
+pub fn restore(
+    backup: &Path,
+    target: &Path,
+    store: Option<&str>,
+    force: bool,
+) -> McpResult<(BackupManifest, Vec<BackedUpStore>, Vec<String>)> {
+    let (dir, manifest) = find_backup(backup)?;
+    let single = target.extension().is_some_and(|ext| ext == "avis");
+    let data_dir = if single {
+        target.parent().unwrap_or(Path::new("."))
+    } else {
+        target
+    };
+    let erased = erased_since(data_dir, &manifest.created_at)?;
+
+    let (selected, skipped): (Vec<&BackedUpStore>, Vec<&BackedUpStore>) = manifest
+        .stores
+        .iter()
+        .filter(|s| store.is_none_or(|name| s.name == name))
+        .partition(|s| !erased.contains(&s.name));
+    let skipped: Vec<String> = skipped.into_iter().map(|s| s.name.clone()).collect();
+    if selected.is_empty() {
+        return Err(McpError::InvalidParams(match store {
+            Some(name) if !skipped.is_empty() => format!(
+                "'{name}' was erased after backup {} was taken; it is not restored",
+                manifest.name
+            ),
+            Some(name) => format!("Backup {} has no store '{name}'", manifest.name),
+            None => format!("Backup {} holds no stores to restore", manifest.name),
+        }));
+    }
+
+    if single && selected.len() > 1 {
+        return Err(McpError::InvalidParams(format!(
+            "Backup {} holds {} stores; pick one with --store or restore to a directory",
+            manifest.name,
+            selected.len()
+        )));
+    }
+
+    let mut planned = Vec::new();
+    for entry in &selected {
+        let bytes = std::fs::read(dir.join(&entry.file))?;
+        if sha256_hex(&bytes) != entry.sha256 {
+            return Err(McpError::VisionError(format!(
+                "{} in backup {} does not match its checksum",
+                entry.file, manifest.name
+            )));
+        }
+        AvisReader::read_from(&mut bytes.as_slice())?;
+        let dest = if single {
+            target.to_path_buf()
+        } else {
+            target.join(&entry.file)
+        };
+        if dest.exists() && !force {
+            return Err(McpError::InvalidParams(format!(
+                "{} already exists; pass --force to replace it",
+                dest.display()
+            )));
+        }
+        planned.push((dest, bytes));
+    }
+
+    if !single {
+        std::fs::create_dir_all(target)?;
+    }
+    for (dest, bytes) in planned {
+        let staging = staging_path(&dest);
+        write_synced(&staging, &bytes)?;
+        std::fs::rename(&staging, &dest)?;
+    }
+    let restored = selected.into_iter().cloned().collect();
+    Ok((manifest, restored, skipped))
+}
@@ -1,6 +1,12 @@
 //! Configuration loading and resolution.
 
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::time::Duration;
+
+use agentic_vision::PipelineConfig;
+
+use crate::types::{McpError, McpResult};
 
 /// Resolve the vision file path.
 pub fn resolve_vision_path(explicit: Option<&str>) -> String {
@@ -20,6 +26,225 @@ pub fn resolve_vision_path(explicit: Option<&str>) -> String {
     resolve_default_vision_path()
 }
 
+/// When captures get a generated caption (feature `caption`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptionMode {
+    /// Caption every capture as it is stored.
+    Eager,
+    /// Caption captures the first time a query returns them.
+    Lazy,
+}
+
+impl CaptionMode {
+    /// Read `AVIS_CAPTION_MODE` (`eager` or `lazy`, default `eager`).
+    pub fn from_env() -> Self {
+        match std::env::var("AVIS_CAPTION_MODE").as_deref() {
+            Ok("lazy") => Self::Lazy,
+            _ => Self::Eager,
+        }
+    }
+}
+
+/// Resolve the caption model directory: `AVIS_CAPTION_MODEL`, or the
+/// engine's default (`~/.agentic-vision/models/blip-caption`).
+pub fn resolve_caption_model() -> Option<String> {
+    std::env::var("AVIS_CAPTION_MODEL").ok()
+}
+
+/// Resolve the UI detection model directory: `AVIS_UI_DETECT_MODEL`, or
+/// the detector's default (`~/.agentic-vision/models/ui-detect`).
+pub fn resolve_ui_detect_model() -> Option<String> {
+    std::env::var("AVIS_UI_DETECT_MODEL").ok()
+}
+
+/// Backends `vision_ocr` can use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OcrBackend {
+    /// The `tesseract` command.
+    Tesseract,
+    /// In-process CRNN model (feature `ocr`).
+    Onnx,
+    /// An HTTP OCR service (feature `ocr-remote`).
+    Remote,
+}
+
+impl OcrBackend {
+    /// Parse a backend name: `tesseract`, `onnx` or `remote`.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "tesseract" => Some(Self::Tesseract),
+            "onnx" => Some(Self::Onnx),
+            "remote" => Some(Self::Remote),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Tesseract => "tesseract",
+            Self::Onnx => "onnx",
+            Self::Remote => "remote",
+        }
+    }
+}
+
+/// OCR settings.
+#[derive(Debug, Clone)]
+pub struct OcrConfig {
+    /// Backend used when a call does not name one.
+    pub backend: OcrBackend,
+    /// The `tesseract` program.
+    pub tesseract: String,
+    /// Model directory of the `onnx` backend, or its default
+    /// (`~/.agentic-vision/models/ocr`).
+    pub model: Option<String>,
+    /// Endpoint of the `remote` backend.
+    pub remote_url: Option<String>,
+    /// Bearer token of the `remote` backend.
+    pub remote_token: Option<String>,
+}
+
+impl Default for OcrConfig {
+    fn default() -> Self {
+        Self {
+            backend: OcrBackend::Tesseract,
+            tesseract: "tesseract".to_string(),
+            model: None,
+            remote_url: None,
+            remote_token: None,
+        }
+    }
+}
+
+impl OcrConfig {
+    /// Read `AVIS_OCR_BACKEND` (`tesseract`, `onnx` or `remote`, default
+    /// `tesseract`), `AVIS_OCR_TESSERACT`, `AVIS_OCR_MODEL`, `AVIS_OCR_URL`
+    /// and `AVIS_OCR_TOKEN`.
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Ok(name) = std::env::var("AVIS_OCR_BACKEND") {
+            match OcrBackend::parse(&name) {
+                Some(backend) => config.backend = backend,
+                None => tracing::warn!("Ignoring AVIS_OCR_BACKEND={name}: unknown backend"),
+            }
+        }
+        if let Ok(command) = std::env::var("AVIS_OCR_TESSERACT") {
+            config.tesseract = command;
+        }
+        config.model = std::env::var("AVIS_OCR_MODEL").ok();
+        config.remote_url = std::env::var("AVIS_OCR_URL").ok();
+        config.remote_token = std::env::var("AVIS_OCR_TOKEN").ok();
+        config
+    }
+}
+
+/// Resolve the capture pipelines: the JSON file named by
+/// `AVIS_CAPTURE_PIPELINES`, or none. A file that cannot be read or parsed
+/// is an error rather than a silently unprocessed capture.
+pub fn resolve_capture_pipelines() -> McpResult<PipelineConfig> {
+    let Ok(path) = std::env::var("AVIS_CAPTURE_PIPELINES") else {
+        return Ok(PipelineConfig::default());
+    };
+    let json = std::fs::read_to_string(&path).map_err(|e| {
+        McpError::Io(std::io::Error::other(format!(
+            "Failed to read capture pipelines {path}: {e}"
+        )))
+    })?;
+    let config = PipelineConfig::from_json(&json)?;
+    tracing::info!(
+        "Loaded {} capture pipeline(s) from {path}",
+        config.pipelines.len()
+    );
+    Ok(config)
+}
+
+/// Resolve the number of tool results cached: `AVIS_RESULT_CACHE`, or
+/// the cache's default. 0 disables caching.
+pub fn resolve_result_cache_size() -> usize {
+    std::env::var("AVIS_RESULT_CACHE")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(crate::protocol::cache::DEFAULT_CAPACITY)
+}
+
+/// Resolve how long results of calls with an idempotency key are
+/// remembered: `AVIS_IDEMPOTENCY_TTL` in seconds, or one day.
+pub fn resolve_idempotency_ttl() -> Duration {
+    let secs = std::env::var("AVIS_IDEMPOTENCY_TTL")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(crate::protocol::idempotency::DEFAULT_TTL_SECS);
+    Duration::from_secs(secs)
+}
+
+/// Resolve the resource response budget in bytes: `AVIS_RESOURCE_BUDGET`,
+/// or the resources' default (4 MiB).
+pub fn resolve_resource_budget() -> usize {
+    std::env::var("AVIS_RESOURCE_BUDGET")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(crate::resources::page::DEFAULT_BUDGET_BYTES)
+}
+
+/// Time limit of a tool call unless configured otherwise.
+const DEFAULT_TOOL_TIMEOUT_SECS: u64 = 120;
+
+/// Time limits for tool calls, per tool. `None` means no limit.
+#[derive(Debug, Clone)]
+pub struct ToolTimeouts {
+    default: Option<Duration>,
+    per_tool: HashMap<String, Option<Duration>>,
+}
+
+impl Default for ToolTimeouts {
+    fn default() -> Self {
+        Self {
+            default: Some(Duration::from_secs(DEFAULT_TOOL_TIMEOUT_SECS)),
+            per_tool: HashMap::new(),
+        }
+    }
+}
+
+impl ToolTimeouts {
+    /// Read `AVIS_TOOL_TIMEOUT` (seconds for every tool, default 120) and
+    /// per-tool overrides such as `AVIS_TOOL_TIMEOUT_VISION_CAPTURE=300`.
+    /// 0 means no limit.
+    pub fn from_env() -> Self {
+        let mut timeouts = Self::default();
+        for (key, value) in std::env::vars() {
+            let Some(tool) = key.strip_prefix("AVIS_TOOL_TIMEOUT") else {
+                continue;
+            };
+            let Ok(secs) = value.trim().parse::<u64>() else {
+                tracing::warn!("Ignoring {key}={value}: expected whole seconds");
+                continue;
+            };
+            let limit = (secs > 0).then(|| Duration::from_secs(secs));
+            match tool.strip_prefix('_') {
+                Some(tool) => timeouts.set(&tool.to_lowercase(), limit),
+                None if tool.is_empty() => timeouts.default = limit,
+                None => {}
+            }
+        }
+        timeouts
+    }
+
+    /// Set the limit of tools without their own.
+    pub fn set_default(&mut self, limit: Option<Duration>) {
+        self.default = limit;
+    }
+
+    /// Set the limit of one tool.
+    pub fn set(&mut self, tool: &str, limit: Option<Duration>) {
+        self.per_tool.insert(tool.to_string(), limit);
+    }
+
+    /// The limit of a tool.
+    pub fn get(&self, tool: &str) -> Option<Duration> {
+        self.per_tool.get(tool).copied().unwrap_or(self.default)
+    }
+}
+
 fn resolve_default_vision_path() -> String {
     let home = std::env::var("HOME")
         .or_else(|_| std::env::var("USERPROFILE"))
@@ -1,14 +1,22 @@
 //! AgenticVision MCP Server — universal LLM access to persistent visual memory.
 
+pub mod backup;
 pub mod config;
+pub mod migrate;
+pub mod ocr;
 pub mod prompts;
 pub mod protocol;
+pub mod recover;
+pub mod reindex;
 pub mod repl;
 pub mod resources;
 pub mod session;
+#[cfg(feature = "sse")]
+pub mod sync;
 pub mod tools;
 pub mod transport;
 pub mod types;
+pub mod watch;
 
 pub use config::resolve_vision_path;
 pub use protocol::ProtocolHandler;
@@ -31,6 +31,12 @@ struct Cli {
     #[arg(long, default_value = "info")]
     log_level: String,
 
+    /// Log format: text, or json with one object per line and structured
+    /// fields (tenant, correlation ID, tool, latency). Also reads from
+    /// AVIS_LOG_FORMAT.
+    #[arg(long, value_parser = ["text", "json"])]
+    log_format: Option<String>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -50,6 +56,11 @@ enum Commands {
         /// Log level (trace, debug, info, warn, error).
         #[arg(long)]
         log_level: Option<String>,
+
+        /// Ingest new images saved in this directory or the folders under
+        /// it, labeled after their folder. Repeat for several directories.
+        #[arg(long)]
+        watch_dir: Vec<String>,
     },
 
     /// Start MCP server over HTTP.
@@ -84,11 +95,150 @@ enum Commands {
         /// Each user gets {data-dir}/{user-id}.avis.
         #[arg(long)]
         data_dir: Option<String>,
+
+        /// Directory for consistent backups of the served stores; also
+        /// enables POST /admin/backup.
+        #[arg(long)]
+        backup_dir: Option<String>,
+
+        /// Seconds between scheduled backups (0 backs up only on request).
+        #[arg(long, default_value_t = 3600)]
+        backup_interval: u64,
+
+        /// Backups kept; older ones are deleted.
+        #[arg(long, default_value_t = 7)]
+        backup_keep: usize,
+
+        /// Ingest new images saved in this directory or the folders under
+        /// it, labeled after their folder (single-user mode). Repeat for
+        /// several directories.
+        #[arg(long)]
+        watch_dir: Vec<String>,
+    },
+
+    /// Sync the vision file with a remote `serve-http` server.
+    ///
+    /// Captures missing on either side are copied across; identical images
+    /// are never stored twice.
+    #[cfg(feature = "sse")]
+    Sync {
+        /// Base URL of the remote server (e.g. http://desktop:3100).
+        remote: String,
+
+        /// Bearer token of the remote server.
+        /// Also reads from AGENTIC_TOKEN env var.
+        #[arg(long)]
+        token: Option<String>,
+
+        /// User ID of the remote store (multi-tenant servers).
+        #[arg(long)]
+        user: Option<String>,
     },
 
     /// Validate a .avis vision file.
     Validate,
 
+    /// Upgrade a .avis file written by an older release.
+    ///
+    /// Computes missing layout fingerprints, normalizes labels and repairs
+    /// counters; captions are generated lazily later. The result is read
+    /// back and checked against the input before it is written.
+    Migrate {
+        /// Vision file to upgrade.
+        input: String,
+
+        /// Where to write the upgraded file (may be the input itself).
+        output: String,
+
+        /// Report what would change without writing anything.
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Salvage the readable captures of a damaged .avis file.
+    ///
+    /// The damaged file is left untouched; what can be read is written to a
+    /// new file, with a report of what was lost.
+    Recover {
+        /// Damaged vision file.
+        input: String,
+
+        /// New file to write the recovered store to.
+        output: String,
+    },
+
+    /// Report duplicate captures, and optionally merge them.
+    ///
+    /// Exact duplicates share thumbnail bytes; near duplicates have close
+    /// perceptual hashes and, when embedded, nearly identical embeddings.
+    /// Merging keeps the oldest capture of each group, with the labels and
+    /// memory links of the others.
+    DedupReport {
+        /// Only report identical images.
+        #[arg(long)]
+        exact_only: bool,
+
+        /// Embedding cosine similarity of near duplicates.
+        #[arg(long, default_value_t = agentic_vision::dedup::DEFAULT_SIMILARITY)]
+        min_similarity: f32,
+
+        /// Most bits the perceptual hashes of near duplicates differ in.
+        #[arg(long, default_value_t = agentic_vision::dedup::DEFAULT_HASH_DISTANCE)]
+        max_hash_distance: u32,
+
+        /// Merge each group into its oldest capture and remove the rest.
+        #[arg(long)]
+        merge: bool,
+    },
+
+    /// Calibrate similarity scores against labeled captures.
+    ///
+    /// Captures sharing a label count as the same screen and captures
+    /// sharing none as different. The fitted mapping turns scores into
+    /// the `confidence` reported by vision_similar and vision_compare.
+    Calibrate {
+        /// Most capture pairs to score; larger stores are sampled.
+        #[arg(long, default_value_t = agentic_vision::calibration::DEFAULT_MAX_PAIRS)]
+        max_pairs: usize,
+
+        /// Report the fit without storing it.
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Re-embed every capture with the CLIP model given by --model.
+    ///
+    /// Run after switching models. Progress is checkpointed in the vision
+    /// file; interrupt with Ctrl-C and run again to resume.
+    Reindex {
+        /// Captures re-embedded between checkpoints.
+        #[arg(long, default_value_t = agentic_vision_mcp::reindex::DEFAULT_BATCH)]
+        batch_size: usize,
+    },
+
+    /// Restore vision files from a backup made by `serve-http --backup-dir`.
+    ///
+    /// BACKUP is one backup directory, or the backup directory itself to
+    /// take the newest. A TARGET ending in .avis receives one store; any
+    /// other TARGET is a directory that receives every store, as a
+    /// multi-tenant data directory. Users erased since the backup was taken
+    /// are skipped. Stop the server writing TARGET first.
+    Restore {
+        /// Backup to restore, or the directory holding backups.
+        backup: String,
+
+        /// Vision file or data directory to restore into.
+        target: String,
+
+        /// Restore only this store (a user ID, or the vision file's name).
+        #[arg(long)]
+        store: Option<String>,
+
+        /// Replace files that already exist.
+        #[arg(long)]
+        force: bool,
+    },
+
     /// Print server capabilities as JSON.
     Info,
 
@@ -113,20 +263,30 @@ async fn main() -> anyhow::Result<()> {
     let filter = tracing_subscriber::EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(&cli.log_level));
 
-    tracing_subscriber::fmt()
+    let log_format = cli
+        .log_format
+        .clone()
+        .or_else(|| std::env::var("AVIS_LOG_FORMAT").ok());
+    let logger = tracing_subscriber::fmt()
         .with_env_filter(filter)
-        .with_writer(std::io::stderr)
-        .init();
+        .with_writer(std::io::stderr);
+    if log_format.as_deref() == Some("json") {
+        logger.json().init();
+    } else {
+        logger.init();
+    }
 
     match cli.command.unwrap_or(Commands::Serve {
         vision: None,
         model: None,
         log_level: None,
+        watch_dir: Vec::new(),
     }) {
         Commands::Serve {
             vision,
             model,
             log_level: _,
+            watch_dir,
         } => {
             let effective_vision = vision.or(cli.vision);
             let effective_model = model.or(cli.model);
@@ -134,6 +294,7 @@ async fn main() -> anyhow::Result<()> {
             let session = VisionSessionManager::open(&vision_path, effective_model.as_deref())?;
             let session = Arc::new(Mutex::new(session));
             let handler = ProtocolHandler::new(session);
+            let _watching = start_watching(&handler, watch_dir)?;
             let transport = StdioTransport::new(handler);
             transport.run().await?;
         }
@@ -147,6 +308,10 @@ async fn main() -> anyhow::Result<()> {
             token,
             multi_tenant,
             data_dir,
+            backup_dir,
+            backup_interval,
+            backup_keep,
+            watch_dir,
         } => {
             use agentic_vision_mcp::session::tenant::VisionTenantRegistry;
             use agentic_vision_mcp::transport::sse::{ServerMode, SseTransport};
@@ -154,6 +319,11 @@ async fn main() -> anyhow::Result<()> {
             // Resolve token: CLI flag > env var
             let effective_token = token.or_else(|| std::env::var("AGENTIC_TOKEN").ok());
 
+            if multi_tenant && !watch_dir.is_empty() {
+                eprintln!("Error: --watch-dir needs a single vision file, not --multi-tenant");
+                std::process::exit(1);
+            }
+            let mut watching = None;
             let server_mode = if multi_tenant {
                 let dir = data_dir.unwrap_or_else(|| {
                     eprintln!("Error: --data-dir is required when using --multi-tenant");
@@ -177,6 +347,7 @@ async fn main() -> anyhow::Result<()> {
                 let session = VisionSessionManager::open(&vision_path, effective_model.as_deref())?;
                 let session = Arc::new(Mutex::new(session));
                 let handler = ProtocolHandler::new(session);
+                watching = start_watching(&handler, watch_dir)?;
                 ServerMode::Single(Arc::new(handler))
             };
 
@@ -184,8 +355,39 @@ async fn main() -> anyhow::Result<()> {
                 tracing::info!("Auth: bearer token required");
             }
 
-            let transport = SseTransport::with_config(effective_token, server_mode);
+            let mut transport = SseTransport::with_config(effective_token, server_mode);
+            if let Some(dir) = backup_dir {
+                tracing::info!("Backups: {dir} (keeping {backup_keep})");
+                transport = transport.with_backup(agentic_vision_mcp::backup::BackupConfig {
+                    dir: std::path::PathBuf::from(dir),
+                    interval: (backup_interval > 0)
+                        .then(|| std::time::Duration::from_secs(backup_interval)),
+                    keep: backup_keep.max(1),
+                });
+            }
             transport.run(&addr).await?;
+            drop(watching);
+        }
+
+        #[cfg(feature = "sse")]
+        Commands::Sync {
+            remote,
+            token,
+            user,
+        } => {
+            use agentic_vision_mcp::sync::SyncClient;
+
+            let effective_token = token.or_else(|| std::env::var("AGENTIC_TOKEN").ok());
+            let vision_path = resolve_vision_path(cli.vision.as_deref());
+            let session = VisionSessionManager::open(&vision_path, cli.model.as_deref())?;
+            let session = Arc::new(Mutex::new(session));
+            let report = SyncClient::new(&remote, effective_token, user)
+                .sync(&session)
+                .await?;
+            println!(
+                "Synced {vision_path} with {remote}: pulled {}, pushed {} capture(s)",
+                report.pulled, report.pushed
+            );
         }
 
         Commands::Validate => {
@@ -205,6 +407,224 @@ async fn main() -> anyhow::Result<()> {
             }
         }
 
+        Commands::Migrate {
+            input,
+            output,
+            dry_run,
+        } => {
+            let report = agentic_vision_mcp::migrate::migrate_file(
+                std::path::Path::new(&input),
+                std::path::Path::new(&output),
+                dry_run,
+            )?;
+            if dry_run {
+                println!("Dry run of {input} (nothing written):");
+            } else {
+                println!("Migrated {input} to {output} (verified):");
+            }
+            println!("  Captures: {}", report.captures);
+            println!("  Fingerprints computed: {}", report.fingerprints_computed);
+            if report.fingerprints_failed > 0 {
+                println!(
+                    "  Fingerprints unavailable (undecodable thumbnails): {}",
+                    report.fingerprints_failed
+                );
+            }
+            println!("  Labels normalized: {}", report.labels_normalized);
+            println!(
+                "  Counters repaired: {}",
+                if report.counters_repaired {
+                    "yes"
+                } else {
+                    "no"
+                }
+            );
+            println!(
+                "  Captions pending (generated lazily): {}",
+                report.captions_pending
+            );
+            if !report.changed() {
+                println!("  Already up to date");
+            }
+        }
+
+        Commands::Recover { input, output } => {
+            let report = agentic_vision_mcp::recover::recover_file(
+                std::path::Path::new(&input),
+                std::path::Path::new(&output),
+            )?;
+            println!("Recovered {input} to {output}:");
+            if !report.header_valid {
+                println!("  Header: damaged (defaults used)");
+            }
+            println!("  Captures recovered: {}", report.captures_recovered);
+            if let Some(lost) = report.captures_lost() {
+                println!("  Captures lost: {lost}");
+            }
+            if report.captures_damaged > 0 {
+                println!("  Captures unreadable: {}", report.captures_damaged);
+            }
+            if report.truncated {
+                println!(
+                    "  Truncated: {} payload bytes missing",
+                    report.bytes_missing
+                );
+            }
+            println!(
+                "  Snapshots, aliases and sessions: {}",
+                if report.metadata_recovered {
+                    "recovered"
+                } else {
+                    "lost"
+                }
+            );
+        }
+
+        Commands::DedupReport {
+            exact_only,
+            min_similarity,
+            max_hash_distance,
+            merge,
+        } => {
+            let vision_path = resolve_vision_path(cli.vision.as_deref());
+            let mut session = VisionSessionManager::open(&vision_path, None)?;
+            let options = agentic_vision::DedupOptions {
+                near: !exact_only,
+                min_similarity,
+                max_hash_distance,
+            };
+            let (report, merged) = session.dedup(&options, merge)?;
+            println!("Duplicates in {vision_path}:");
+            println!("  Captures scanned: {}", report.scanned);
+            println!("  Exact duplicates: {}", report.exact_duplicates);
+            if !exact_only {
+                println!("  Near duplicates: {}", report.near_duplicates);
+            }
+            println!("  Reclaimable: {} bytes", report.reclaimable_bytes);
+            for group in &report.groups {
+                let ids: Vec<String> = group
+                    .duplicates
+                    .iter()
+                    .map(|d| {
+                        if d.exact {
+                            d.id.to_string()
+                        } else {
+                            format!("{} (~{} bits)", d.id, d.hash_distance)
+                        }
+                    })
+                    .collect();
+                println!("  Keep {}: {}", group.keep, ids.join(", "));
+            }
+            if merge {
+                println!("  Merged: {merged} capture(s) removed");
+            }
+        }
+
+        Commands::Calibrate { max_pairs, dry_run } => {
+            let vision_path = resolve_vision_path(cli.vision.as_deref());
+            let mut session = VisionSessionManager::open(&vision_path, None)?;
+            let calibration = session.calibrate(max_pairs, !dry_run)?;
+            println!("Calibration of {vision_path}:");
+            println!(
+                "  Pairs: {} ({} same, {} different)",
+                calibration.pairs,
+                calibration.same_pairs,
+                calibration.pairs - calibration.same_pairs
+            );
+            match calibration.threshold {
+                Some(threshold) => println!("  Even odds at similarity {threshold:.3}"),
+                None => println!("  Even odds at no similarity"),
+            }
+            println!("  Accuracy: {:.1}%", calibration.accuracy * 100.0);
+            println!("  Log loss: {:.4}", calibration.log_loss);
+            for score in [0.5, 0.7, 0.8, 0.9, 0.95] {
+                println!(
+                    "  Similarity {score:.2}: {:.1}% same",
+                    calibration.probability(score) * 100.0
+                );
+            }
+            if dry_run {
+                println!("  Dry run: calibration not stored");
+            }
+        }
+
+        Commands::Reindex { batch_size } => {
+            use agentic_vision_mcp::reindex;
+            use tokio_util::sync::CancellationToken;
+
+            let vision_path = resolve_vision_path(cli.vision.as_deref());
+            let session = VisionSessionManager::open(&vision_path, cli.model.as_deref())?;
+            let session = Arc::new(Mutex::new(session));
+            let (checkpoint, resumed) = session.lock().await.reindex_start()?;
+            if resumed {
+                eprintln!(
+                    "Resuming re-index of {vision_path}: {}/{} captures done",
+                    checkpoint.done + checkpoint.failed,
+                    checkpoint.total
+                );
+            }
+
+            let cancel = CancellationToken::new();
+            let on_interrupt = cancel.clone();
+            tokio::spawn(async move {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    on_interrupt.cancel();
+                }
+            });
+
+            let started = std::time::Instant::now();
+            let finished = reindex::run(&session, batch_size.max(1), &cancel, |checkpoint| {
+                eprint!(
+                    "\rRe-embedded {}/{} captures ({:.1}%)",
+                    checkpoint.done + checkpoint.failed,
+                    checkpoint.total,
+                    checkpoint.fraction() * 100.0
+                );
+            })
+            .await?;
+            eprintln!();
+            match finished {
+                Some(done) => {
+                    println!(
+                        "Re-indexed {vision_path} in {:.1}s:",
+                        started.elapsed().as_secs_f64()
+                    );
+                    println!("  Captures re-embedded: {}", done.done);
+                    if done.failed > 0 {
+                        println!("  Captures kept with old embeddings: {}", done.failed);
+                    }
+                }
+                None => {
+                    println!("Interrupted; progress saved. Run again to resume.");
+                    std::process::exit(130);
+                }
+            }
+        }
+
+        Commands::Restore {
+            backup,
+            target,
+            store,
+            force,
+        } => {
+            let (manifest, restored, skipped) = agentic_vision_mcp::backup::restore(
+                std::path::Path::new(&backup),
+                std::path::Path::new(&target),
+                store.as_deref(),
+                force,
+            )?;
+            println!(
+                "Restored backup {} ({}) to {target}:",
+                manifest.name, manifest.created_at
+            );
+            for store in restored {
+                println!("  {}: {} capture(s)", store.name, store.captures);
+            }
+            for name in skipped {
+                println!("  {name}: skipped, erased since the backup");
+            }
+        }
+
         Commands::Info => {
             let capabilities = agentic_vision_mcp::types::InitializeResult::default_result();
             let tools = ToolRegistry::list_tools();
@@ -235,3 +655,20 @@ async fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Start ingesting images from `dirs`, if any. Watching stops when the
+/// returned guard is dropped.
+fn start_watching(
+    handler: &ProtocolHandler,
+    dirs: Vec<String>,
+) -> anyhow::Result<Option<tokio_util::sync::DropGuard>> {
+    if dirs.is_empty() {
+        return Ok(None);
+    }
+    use agentic_vision_mcp::watch::{self, WatchConfig};
+
+    let config = WatchConfig::new(dirs.into_iter().map(std::path::PathBuf::from).collect());
+    let cancel = tokio_util::sync::CancellationToken::new();
+    watch::spawn(handler, &config, cancel.clone())?;
+    Ok(Some(cancel.drop_guard()))
+}
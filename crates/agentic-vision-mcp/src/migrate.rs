@@ -0,0 +1,91 @@
+//! Upgrade of `.avis` files written by older releases.
+//!
+//! The migrated store is written next to the output, read back and checked
+//! against the input (same captures, same images, same snapshots, sessions
+//! and aliases) before it replaces the output, so a failed migration never
+//! leaves a damaged file behind.
+
+use std::path::{Path, PathBuf};
+
+use agentic_vision::{AvisReader, AvisWriter, MigrationReport, VisualMemoryStore};
+
+use crate::types::{McpError, McpResult};
+
+/// Migrate the store at `input` and write it to `output`, which may be
+/// `input` itself but must not be another existing file. With `dry_run`,
+/// only report what would change.
+pub fn migrate_file(input: &Path, output: &Path, dry_run: bool) -> McpResult<MigrationReport> {
+    let original = AvisReader::read_from_file(input)?;
+    let mut store = original.clone();
+    let report = store.migrate();
+    if dry_run {
+        return Ok(report);
+    }
+
+    if output.exists() && !same_file(input, output) {
+        return Err(McpError::InvalidParams(format!(
+            "{} already exists; remove it or migrate in place",
+            output.display()
+        )));
+    }
+
+    let staging = staging_path(output);
+    AvisWriter::write_to_file(&store, &staging)?;
+    let verified = AvisReader::read_from_file(&staging)
+        .map_err(McpError::from)
+        .and_then(|written| verify(&original, &written));
+    if let Err(e) = verified {
+        let _ = std::fs::remove_file(&staging);
+        return Err(e);
+    }
+    std::fs::rename(&staging, output)?;
+    Ok(report)
+}
+
+/// Check that `migrated` holds everything `original` did.
+fn verify(original: &VisualMemoryStore, migrated: &VisualMemoryStore) -> McpResult<()> {
+    let mismatch = |what: &str, before: usize, after: usize| {
+        McpError::VisionError(format!(
+            "Migration verification failed: {what} {before} before, {after} after"
+        ))
+    };
+    let checks = [
+        ("captures", original.count(), migrated.count()),
+        (
+            "snapshots",
+            original.snapshots.len(),
+            migrated.snapshots.len(),
+        ),
+        ("sessions", original.sessions.len(), migrated.sessions.len()),
+        (
+            "label aliases",
+            original.label_aliases.len(),
+            migrated.label_aliases.len(),
+        ),
+    ];
+    for (what, before, after) in checks {
+        if before != after {
+            return Err(mismatch(what, before, after));
+        }
+    }
+    if original.manifest() != migrated.manifest() {
+        return Err(McpError::VisionError(
+            "Migration verification failed: capture IDs or images differ".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Where a file is written before it replaces `output`.
+pub(crate) fn staging_path(output: &Path) -> PathBuf {
+    let mut name = output.file_name().unwrap_or_default().to_os_string();
+    name.push(".partial");
+    output.with_file_name(name)
+}
@@ -0,0 +1,183 @@
+//! OCR engines for `vision_ocr`, created on first use of each backend.
+//!
+//! Recognition is slow and blocking (a child process, a model run, or an
+//! HTTP round trip), so engines are shared handles that a tool locks and
+//! runs on a blocking thread, without holding the session.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use agentic_vision::{OcrEngine, TesseractOcr};
+
+use crate::config::{OcrBackend, OcrConfig};
+use crate::types::{McpError, McpResult};
+
+/// An engine that can be run from a blocking thread.
+pub type SharedOcrEngine = Arc<std::sync::Mutex<Box<dyn OcrEngine>>>;
+
+/// The OCR engines of a session.
+pub struct OcrEngines {
+    config: OcrConfig,
+    engines: HashMap<OcrBackend, SharedOcrEngine>,
+}
+
+impl OcrEngines {
+    pub fn new(config: OcrConfig) -> Self {
+        Self {
+            config,
+            engines: HashMap::new(),
+        }
+    }
+
+    /// Backend used when a call does not name one.
+    pub fn default_backend(&self) -> OcrBackend {
+        self.config.backend
+    }
+
+    /// The engine of `backend`, created if this is its first use.
+    pub fn get(&mut self, backend: OcrBackend) -> McpResult<SharedOcrEngine> {
+        if let Some(engine) = self.engines.get(&backend) {
+            return Ok(engine.clone());
+        }
+        let engine: Box<dyn OcrEngine> = match backend {
+            OcrBackend::Tesseract => Box::new(TesseractOcr::new(&self.config.tesseract)),
+            OcrBackend::Onnx => self.onnx()?,
+            OcrBackend::Remote => self.remote()?,
+        };
+        let engine = Arc::new(std::sync::Mutex::new(engine));
+        self.engines.insert(backend, engine.clone());
+        Ok(engine)
+    }
+
+    #[cfg(feature = "ocr")]
+    fn onnx(&self) -> McpResult<Box<dyn OcrEngine>> {
+        Ok(Box::new(agentic_vision::OnnxOcr::new(
+            self.config.model.as_deref(),
+        )?))
+    }
+
+    #[cfg(not(feature = "ocr"))]
+    fn onnx(&self) -> McpResult<Box<dyn OcrEngine>> {
+        Err(McpError::InvalidParams(
+            "The onnx OCR backend needs a build with --features ocr".to_string(),
+        ))
+    }
+
+    #[cfg(feature = "ocr-remote")]
+    fn remote(&self) -> McpResult<Box<dyn OcrEngine>> {
+        let url = self.config.remote_url.clone().ok_or_else(|| {
+            McpError::InvalidParams(
+                "The remote OCR backend needs an endpoint in AVIS_OCR_URL".to_string(),
+            )
+        })?;
+        Ok(Box::new(RemoteOcr::new(
+            url,
+            self.config.remote_token.clone(),
+        )))
+    }
+
+    #[cfg(not(feature = "ocr-remote"))]
+    fn remote(&self) -> McpResult<Box<dyn OcrEngine>> {
+        Err(McpError::InvalidParams(
+            "The remote OCR backend needs a build with --features ocr-remote".to_string(),
+        ))
+    }
+}
+
+#[cfg(feature = "ocr-remote")]
+pub use remote::RemoteOcr;
+
+#[cfg(feature = "ocr-remote")]
+mod remote {
+    use std::time::Duration;
+
+    use base64::Engine;
+    use image::DynamicImage;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use agentic_vision::{OcrEngine, OcrLine, OcrResult, VisionError, VisionResult};
+
+    /// Longest wait for the service.
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+    #[derive(Deserialize)]
+    struct RemoteResponse {
+        #[serde(default)]
+        text: String,
+        #[serde(default)]
+        lines: Vec<OcrLine>,
+        #[serde(default)]
+        language: Option<String>,
+    }
+
+    /// OCR by an HTTP service.
+    ///
+    /// The service receives `POST {"image": <base64 PNG>, "mime":
+    /// "image/png", "language": "eng"}` and answers `{"text": ..., "lines":
+    /// [{"text", "bbox": {"x", "y", "w", "h"}, "confidence", "words"}],
+    /// "language"}`, where words are like lines without `words`; all but
+    /// `text` may be omitted. Lines are put in reading order here. Must be
+    /// run on a blocking thread inside a Tokio runtime.
+    pub struct RemoteOcr {
+        http: reqwest::Client,
+        url: String,
+        token: Option<String>,
+    }
+
+    impl RemoteOcr {
+        pub fn new(url: String, token: Option<String>) -> Self {
+            let http = reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default();
+            Self { http, url, token }
+        }
+
+        async fn request(&self, png: Vec<u8>, language: &str) -> Result<RemoteResponse, String> {
+            let body = json!({
+                "image": base64::engine::general_purpose::STANDARD.encode(png),
+                "mime": "image/png",
+                "language": language,
+            });
+            let mut request = self.http.post(&self.url).json(&body);
+            if let Some(token) = &self.token {
+                request = request.bearer_auth(token);
+            }
+            let response = request.send().await.map_err(|e| e.to_string())?;
+            let status = response.status();
+            if !status.is_success() {
+                let text = response.text().await.unwrap_or_default();
+                return Err(format!("{status}: {}", text.trim()));
+            }
+            response.json().await.map_err(|e| e.to_string())
+        }
+    }
+
+    impl OcrEngine for RemoteOcr {
+        fn name(&self) -> &'static str {
+            "remote"
+        }
+
+        fn recognize(&mut self, img: &DynamicImage, language: &str) -> VisionResult<OcrResult> {
+            let mut png = std::io::Cursor::new(Vec::new());
+            img.write_to(&mut png, image::ImageFormat::Png)?;
+            let handle = tokio::runtime::Handle::try_current()
+                .map_err(|e| VisionError::Ocr(format!("Remote OCR needs a runtime: {e}")))?;
+            let response = handle
+                .block_on(self.request(png.into_inner(), language))
+                .map_err(|e| VisionError::Ocr(format!("Remote OCR at {} failed: {e}", self.url)))?;
+
+            let mut result = if response.lines.is_empty() {
+                OcrResult {
+                    text: response.text,
+                    ..OcrResult::default()
+                }
+            } else {
+                OcrResult::from_lines(response.lines)
+            };
+            result.language = response.language;
+            Ok(result)
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! LRU cache of read-only tool results.
+//!
+//! Agents often repeat a similarity search or query with the same arguments.
+//! Results are keyed by tool, a hash of the arguments, and the store
+//! generation, so any write to the store makes every cached result stale.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::types::ToolCallResult;
+
+/// Tools whose results depend only on their arguments and the store.
+pub const CACHEABLE_TOOLS: [&str; 2] = ["vision_similar", "vision_query"];
+
+/// Results kept unless configured otherwise.
+pub const DEFAULT_CAPACITY: usize = 64;
+
+/// Hit and miss counts of a `ResultCache`.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub invalidations: u64,
+    pub entries: usize,
+    pub capacity: usize,
+}
+
+/// Least-recently-used cache of tool results for one store.
+pub struct ResultCache {
+    capacity: usize,
+    generation: u64,
+    /// Result and last use, by tool and argument hash.
+    entries: HashMap<(String, u64), (ToolCallResult, u64)>,
+    clock: u64,
+    stats: CacheStats,
+}
+
+impl ResultCache {
+    /// Create a cache of at most `capacity` results; 0 disables caching.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            generation: 0,
+            entries: HashMap::new(),
+            clock: 0,
+            stats: CacheStats {
+                capacity,
+                ..CacheStats::default()
+            },
+        }
+    }
+
+    /// Whether results of `tool` are cached.
+    pub fn caches(&self, tool: &str) -> bool {
+        self.capacity > 0 && CACHEABLE_TOOLS.contains(&tool)
+    }
+
+    /// The cached result of `tool` with `args` at store `generation`.
+    pub fn get(&mut self, tool: &str, args: &Value, generation: u64) -> Option<ToolCallResult> {
+        self.sync_generation(generation);
+        self.clock += 1;
+        match self.entries.get_mut(&(tool.to_string(), hash_args(args))) {
+            Some((result, last_used)) => {
+                *last_used = self.clock;
+                self.stats.hits += 1;
+                Some(result.clone())
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Cache the result of `tool` with `args`, computed at store
+    /// `generation`. Evicts the least recently used result when full.
+    pub fn insert(&mut self, tool: &str, args: &Value, generation: u64, result: ToolCallResult) {
+        self.sync_generation(generation);
+        if self.capacity == 0 || result.is_error == Some(true) {
+            return;
+        }
+        let key = (tool.to_string(), hash_args(args));
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            if let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| key.clone())
+            {
+                self.entries.remove(&oldest);
+                self.stats.evictions += 1;
+            }
+        }
+        self.clock += 1;
+        self.entries.insert(key, (result, self.clock));
+    }
+
+    /// Current counts.
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.entries.len(),
+            ..self.stats
+        }
+    }
+
+    /// Drop every result if the store has changed since they were cached.
+    fn sync_generation(&mut self, generation: u64) {
+        if generation != self.generation {
+            if !self.entries.is_empty() {
+                self.stats.invalidations += 1;
+            }
+            self.entries.clear();
+            self.generation = generation;
+        }
+    }
+}
+
+/// Hash of tool arguments. `serde_json` maps are ordered, so equal
+/// arguments hash equally whatever order the client sent their keys in.
+pub(crate) fn hash_args(args: &Value) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    args.to_string().hash(&mut hasher);
+    hasher.finish()
+}
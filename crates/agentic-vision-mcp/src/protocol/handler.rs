@@ -1,23 +1,77 @@
 //! Main request dispatcher — receives JSON-RPC messages, routes to handlers.
 
+use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::Instant;
+use tokio::sync::{broadcast, Mutex};
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
 
-use serde_json::Value;
+use serde_json::{json, Value};
 
+use crate::config::{resolve_resource_budget, resolve_result_cache_size, ToolTimeouts};
 use crate::prompts::PromptRegistry;
 use crate::resources::ResourceRegistry;
 use crate::session::VisionSessionManager;
-use crate::tools::ToolRegistry;
+use crate::tools::{CallContext, ToolRegistry};
 use crate::types::*;
 
+use super::cache::{CacheStats, ResultCache};
+use super::idempotency::{take_key, Claim, IDEMPOTENT_TOOLS};
 use super::negotiation::NegotiatedCapabilities;
 use super::validator::validate_request;
 
 /// The main protocol handler that dispatches incoming JSON-RPC messages.
+///
+/// Clones share state, so a transport can handle requests concurrently and
+/// still cancel them.
+#[derive(Clone)]
 pub struct ProtocolHandler {
     session: Arc<Mutex<VisionSessionManager>>,
     capabilities: Arc<Mutex<NegotiatedCapabilities>>,
+    timeouts: ToolTimeouts,
+    /// Cancellation tokens of the requests being handled, by JSON request id.
+    in_flight: Arc<Mutex<HashMap<String, CancellationToken>>>,
+    cache: Arc<Mutex<ResultCache>>,
+    /// Byte budget of a resource response.
+    resource_budget: usize,
+    /// Notifications for the client, sent by transports that can push them.
+    notifications: broadcast::Sender<Value>,
+}
+
+/// Notifications buffered for a slow client before the oldest are dropped.
+const NOTIFICATION_BUFFER: usize = 256;
+
+/// An idempotency key claimed for a call. Dropped without `finish`, as when
+/// the client goes away mid-call, it releases the key so a retry can run.
+struct Reservation {
+    session: Arc<Mutex<VisionSessionManager>>,
+    tool: String,
+    key: Option<String>,
+}
+
+impl Reservation {
+    /// Remember the result of the call, or release the key if it failed.
+    async fn finish(mut self, result: Option<&ToolCallResult>) {
+        if let Some(key) = self.key.take() {
+            self.session
+                .lock()
+                .await
+                .idempotency()
+                .finish(&self.tool, &key, result);
+        }
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        let Some(key) = self.key.take() else { return };
+        let session = Arc::clone(&self.session);
+        let tool = std::mem::take(&mut self.tool);
+        tokio::spawn(async move {
+            session.lock().await.idempotency().finish(&tool, &key, None);
+        });
+    }
 }
 
 impl ProtocolHandler {
@@ -25,12 +79,81 @@ impl ProtocolHandler {
         Self {
             session,
             capabilities: Arc::new(Mutex::new(NegotiatedCapabilities::default())),
+            timeouts: ToolTimeouts::from_env(),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            cache: Arc::new(Mutex::new(ResultCache::new(resolve_result_cache_size()))),
+            resource_budget: resolve_resource_budget(),
+            notifications: broadcast::channel(NOTIFICATION_BUFFER).0,
         }
     }
 
+    /// Replace the resource response budget read from the environment.
+    pub fn with_resource_budget(mut self, bytes: usize) -> Self {
+        self.resource_budget = bytes;
+        self
+    }
+
+    /// Replace the result cache sized from the environment with one of
+    /// `capacity` results.
+    pub fn with_cache_capacity(mut self, capacity: usize) -> Self {
+        self.cache = Arc::new(Mutex::new(ResultCache::new(capacity)));
+        self
+    }
+
+    /// Hit and miss counts of the tool result cache.
+    pub async fn cache_stats(&self) -> CacheStats {
+        self.cache.lock().await.stats()
+    }
+
+    /// Replace the tool time limits read from the environment.
+    pub fn with_tool_timeouts(mut self, timeouts: ToolTimeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Send a notification to the client of every transport subscribed.
+    /// It is dropped if none is.
+    pub fn notify(&self, method: &str, params: Value) {
+        let notification = json!({
+            "jsonrpc": JSONRPC_VERSION,
+            "method": method,
+            "params": params,
+        });
+        let _ = self.notifications.send(notification);
+    }
+
+    /// Notifications sent from now on, for a transport to deliver.
+    pub fn subscribe_notifications(&self) -> broadcast::Receiver<Value> {
+        self.notifications.subscribe()
+    }
+
+    /// The session this handler serves.
+    pub fn session(&self) -> &Arc<Mutex<VisionSessionManager>> {
+        &self.session
+    }
+
     pub async fn handle_message(&self, msg: JsonRpcMessage) -> Option<Value> {
+        self.handle_correlated(msg, None).await
+    }
+
+    /// Handle a message that carries a correlation ID from the transport,
+    /// such as an HTTP `X-Request-ID`. Tool results echo it in `_meta`.
+    pub async fn handle_correlated(
+        &self,
+        msg: JsonRpcMessage,
+        correlation_id: Option<&str>,
+    ) -> Option<Value> {
         match msg {
-            JsonRpcMessage::Request(req) => Some(self.handle_request(req).await),
+            JsonRpcMessage::Request(req) => {
+                let span = tracing::info_span!(
+                    "request",
+                    request_id = %req.id,
+                    method = %req.method
+                );
+                self.handle_request(req, correlation_id)
+                    .instrument(span)
+                    .await
+            }
             JsonRpcMessage::Notification(notif) => {
                 self.handle_notification(notif).await;
                 None
@@ -42,27 +165,61 @@ impl ProtocolHandler {
         }
     }
 
-    async fn handle_request(&self, request: JsonRpcRequest) -> Value {
+    /// Handle a request. Returns `None` if the client cancelled it: MCP
+    /// clients expect no response to a cancelled request.
+    async fn handle_request(
+        &self,
+        request: JsonRpcRequest,
+        correlation_id: Option<&str>,
+    ) -> Option<Value> {
         if let Err(e) = validate_request(&request) {
-            return serde_json::to_value(e.to_json_rpc_error(request.id)).unwrap_or_default();
+            return Some(serde_json::to_value(e.to_json_rpc_error(request.id)).unwrap_or_default());
         }
 
         let id = request.id.clone();
-        let result = self.dispatch_request(&request).await;
+        let key = serde_json::to_string(&id).unwrap_or_default();
+        let cancel = CancellationToken::new();
+        self.in_flight
+            .lock()
+            .await
+            .insert(key.clone(), cancel.clone());
+
+        let result = tokio::select! {
+            result = self.dispatch_request(&request, &cancel, correlation_id) => result,
+            _ = cancel.cancelled() => Err(McpError::RequestCancelled),
+        };
+        self.in_flight.lock().await.remove(&key);
 
-        match result {
+        if cancel.is_cancelled() {
+            tracing::info!("Request cancelled");
+            return None;
+        }
+        Some(match result {
             Ok(value) => serde_json::to_value(JsonRpcResponse::new(id, value)).unwrap_or_default(),
             Err(e) => serde_json::to_value(e.to_json_rpc_error(id)).unwrap_or_default(),
-        }
+        })
     }
 
-    async fn dispatch_request(&self, request: &JsonRpcRequest) -> McpResult<Value> {
+    async fn dispatch_request(
+        &self,
+        request: &JsonRpcRequest,
+        cancel: &CancellationToken,
+        correlation_id: Option<&str>,
+    ) -> McpResult<Value> {
         match request.method.as_str() {
             "initialize" => self.handle_initialize(request.params.clone()).await,
             "shutdown" => self.handle_shutdown().await,
 
             "tools/list" => self.handle_tools_list().await,
-            "tools/call" => self.handle_tools_call(request.params.clone()).await,
+            "tools/call" => {
+                let mut result = self
+                    .handle_tools_call(&request.id, request.params.clone(), cancel)
+                    .await?;
+                if let (Some(id), Some(result)) = (correlation_id, result.as_object_mut()) {
+                    result.insert("_meta".to_string(), json!({ "correlationId": id }));
+                }
+                Ok(result)
+            }
 
             "resources/list" => self.handle_resources_list().await,
             "resources/templates/list" => self.handle_resource_templates_list().await,
@@ -88,7 +245,7 @@ impl ProtocolHandler {
                 }
             }
             "notifications/cancelled" | "$/cancelRequest" => {
-                tracing::info!("Received cancellation notification");
+                self.handle_cancel(notification.params).await;
             }
             _ => {
                 tracing::debug!("Unknown notification: {}", notification.method);
@@ -96,6 +253,27 @@ impl ProtocolHandler {
         }
     }
 
+    async fn handle_cancel(&self, params: Option<Value>) {
+        let Some(params) =
+            params.and_then(|p| serde_json::from_value::<CancelRequestParams>(p).ok())
+        else {
+            tracing::warn!("Cancellation notification without a requestId");
+            return;
+        };
+
+        let key = params.request_id.to_string();
+        match self.in_flight.lock().await.get(&key) {
+            Some(token) => {
+                tracing::info!(
+                    "Cancelling request {key}: {}",
+                    params.reason.as_deref().unwrap_or("no reason given")
+                );
+                token.cancel();
+            }
+            None => tracing::debug!("Cancellation for unknown or finished request {key}"),
+        }
+    }
+
     async fn handle_initialize(&self, params: Option<Value>) -> McpResult<Value> {
         let init_params: InitializeParams = params
             .map(serde_json::from_value)
@@ -124,19 +302,150 @@ impl ProtocolHandler {
         serde_json::to_value(result).map_err(|e| McpError::InternalError(e.to_string()))
     }
 
-    async fn handle_tools_call(&self, params: Option<Value>) -> McpResult<Value> {
+    async fn handle_tools_call(
+        &self,
+        request_id: &RequestId,
+        params: Option<Value>,
+        cancel: &CancellationToken,
+    ) -> McpResult<Value> {
+        let started = Instant::now();
         let call_params: ToolCallParams = params
             .map(serde_json::from_value)
             .transpose()
             .map_err(|e| McpError::InvalidParams(e.to_string()))?
             .ok_or_else(|| McpError::InvalidParams("Tool call params required".to_string()))?;
 
-        let result =
-            ToolRegistry::call(&call_params.name, call_params.arguments, &self.session).await?;
+        // The time limit cancels a child token from another task, so a tool
+        // busy in synchronous work sees it at its next check, as it would a
+        // cancellation by the client.
+        let name = call_params.name;
+        let mut args = call_params
+            .arguments
+            .unwrap_or_else(|| Value::Object(serde_json::Map::new()));
+
+        // A retried call with the key of one already done gets its result.
+        let key = take_key(&mut args)?;
+        let mut reservation = None;
+        if let Some(key) = key.filter(|_| IDEMPOTENT_TOOLS.contains(&name.as_str())) {
+            let claim = self
+                .session
+                .lock()
+                .await
+                .idempotency()
+                .claim(&name, &key, &args)?;
+            match claim {
+                Claim::Replay(result) => {
+                    tracing::info!(tool = %name, idempotency_key = %key, "Tool call replayed");
+                    return serde_json::to_value(result)
+                        .map_err(|e| McpError::InternalError(e.to_string()));
+                }
+                Claim::Run => {
+                    reservation = Some(Reservation {
+                        session: Arc::clone(&self.session),
+                        tool: name.clone(),
+                        key: Some(key),
+                    })
+                }
+            }
+        }
+
+        let context = CallContext {
+            request_id: Some(request_id.to_string()),
+            client: self
+                .capabilities
+                .lock()
+                .await
+                .client_info
+                .as_ref()
+                .map(|c| format!("{}/{}", c.name, c.version)),
+        };
+
+        let limit = self.timeouts.get(&name);
+        let token = cancel.child_token();
+        let timer = limit.map(|limit| {
+            let token = token.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(limit).await;
+                token.cancel();
+            })
+        });
+
+        let result = tokio::select! {
+            result = self.call_tool(&name, args, &token, &context) => result,
+            _ = token.cancelled() => Err(McpError::RequestCancelled),
+        };
+        if let Some(timer) = timer {
+            timer.abort();
+        }
+
+        let result = match (result, limit) {
+            (Err(McpError::RequestCancelled), Some(limit)) if !cancel.is_cancelled() => {
+                Err(McpError::ToolTimeout {
+                    tool: name.clone(),
+                    ms: limit.as_millis() as u64,
+                })
+            }
+            (result, _) => result,
+        };
+
+        let latency_ms = started.elapsed().as_millis() as u64;
+        // A call that timed out may be waiting for the session; never wait
+        // for it just to log.
+        let store_bytes = self.session.try_lock().ok().map(|s| s.store_bytes());
+        match &result {
+            Ok(result) => tracing::info!(
+                tool = %name,
+                latency_ms,
+                store_bytes,
+                is_error = result.is_error.unwrap_or(false),
+                "Tool call finished"
+            ),
+            Err(e) => tracing::warn!(
+                tool = %name,
+                latency_ms,
+                store_bytes,
+                code = e.code(),
+                error = %e,
+                "Tool call failed"
+            ),
+        }
+        if let Some(reservation) = reservation {
+            reservation.finish(result.as_ref().ok()).await;
+        }
+        let result = result?;
 
         serde_json::to_value(result).map_err(|e| McpError::InternalError(e.to_string()))
     }
 
+    /// Call a tool, answering from the result cache when possible.
+    async fn call_tool(
+        &self,
+        name: &str,
+        args: Value,
+        cancel: &CancellationToken,
+        context: &CallContext,
+    ) -> McpResult<ToolCallResult> {
+        if !self.cache.lock().await.caches(name) {
+            return ToolRegistry::call(name, Some(args), &self.session, cancel, context).await;
+        }
+
+        let generation = self.session.lock().await.generation();
+        if let Some(hit) = self.cache.lock().await.get(name, &args, generation) {
+            return Ok(hit);
+        }
+        let result =
+            ToolRegistry::call(name, Some(args.clone()), &self.session, cancel, context).await?;
+        // Only cache a result if the store did not change during the call,
+        // by another request or by the tool itself (lazy captions).
+        if self.session.lock().await.generation() == generation {
+            self.cache
+                .lock()
+                .await
+                .insert(name, &args, generation, result.clone());
+        }
+        Ok(result)
+    }
+
     async fn handle_resources_list(&self) -> McpResult<Value> {
         let result = ResourceListResult {
             resources: ResourceRegistry::list_resources(),
@@ -160,7 +469,8 @@ impl ProtocolHandler {
             .map_err(|e| McpError::InvalidParams(e.to_string()))?
             .ok_or_else(|| McpError::InvalidParams("Resource read params required".to_string()))?;
 
-        let result = ResourceRegistry::read(&read_params.uri, &self.session).await?;
+        let result =
+            ResourceRegistry::read(&read_params.uri, &self.session, self.resource_budget).await?;
 
         serde_json::to_value(result).map_err(|e| McpError::InternalError(e.to_string()))
     }
@@ -0,0 +1,170 @@
+//! Replay protection for tool calls that change the store.
+//!
+//! A client that retries a call after a dropped connection cannot tell
+//! whether the first attempt went through. With an `idempotency_key`, the
+//! result of the first call is remembered for a while, and a retry with the
+//! same key gets it back instead of running again. Keys belong to a store,
+//! so tenants never see each other's.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::types::{McpError, McpResult, ToolCallResult};
+
+use super::cache::hash_args;
+
+/// Tools that change the store, and so honour an idempotency key.
+pub const IDEMPOTENT_TOOLS: [&str; 10] = [
+    "vision_capture",
+    "vision_compare_to_live",
+    "vision_link",
+    "vision_snapshot",
+    "vision_labels",
+    "vision_ocr",
+    "vision_reindex",
+    "vision_dedup_report",
+    "session_start",
+    "session_end",
+];
+
+/// Argument carrying the key.
+pub const KEY_ARG: &str = "idempotency_key";
+
+/// Longest key accepted.
+pub const MAX_KEY_LEN: usize = 255;
+
+/// How long results are remembered unless configured otherwise.
+pub const DEFAULT_TTL_SECS: u64 = 24 * 60 * 60;
+
+/// Most results remembered; the oldest go first beyond this.
+const MAX_ENTRIES: usize = 4096;
+
+enum State {
+    Running,
+    Done(ToolCallResult),
+}
+
+struct Entry {
+    /// Hash of the arguments the key was first used with.
+    args: u64,
+    state: State,
+    at: Instant,
+}
+
+/// What to do with a call that carries a key.
+pub enum Claim {
+    /// First use of the key: run the call, then `finish` it.
+    Run,
+    /// The key was used before: answer with its result.
+    Replay(ToolCallResult),
+}
+
+/// Results of keyed calls to one store, by tool and key.
+pub struct IdempotencyStore {
+    ttl: Duration,
+    entries: HashMap<(String, String), Entry>,
+}
+
+impl IdempotencyStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Claim `key` for a call of `tool` with `args` (without the key).
+    ///
+    /// Fails if the key was used with other arguments, or if a call with it
+    /// is still running.
+    pub fn claim(&mut self, tool: &str, key: &str, args: &Value) -> McpResult<Claim> {
+        self.expire();
+        let args = hash_args(args);
+        let id = (tool.to_string(), key.to_string());
+        match self.entries.get(&id) {
+            Some(entry) if entry.args != args => Err(McpError::IdempotencyConflict(format!(
+                "key '{key}' was already used with different arguments"
+            ))),
+            Some(Entry {
+                state: State::Running,
+                ..
+            }) => Err(McpError::IdempotencyConflict(format!(
+                "a call with key '{key}' is still running; retry later"
+            ))),
+            Some(Entry {
+                state: State::Done(result),
+                ..
+            }) => Ok(Claim::Replay(result.clone())),
+            None => {
+                self.entries.insert(
+                    id,
+                    Entry {
+                        args,
+                        state: State::Running,
+                        at: Instant::now(),
+                    },
+                );
+                Ok(Claim::Run)
+            }
+        }
+    }
+
+    /// Record the result of a claimed call, or with `None` release the key
+    /// of a call that failed, so that a retry runs it again.
+    pub fn finish(&mut self, tool: &str, key: &str, result: Option<&ToolCallResult>) {
+        let id = (tool.to_string(), key.to_string());
+        match result {
+            Some(result) => {
+                if let Some(entry) = self.entries.get_mut(&id) {
+                    entry.state = State::Done(result.clone());
+                    entry.at = Instant::now();
+                }
+            }
+            None => {
+                self.entries.remove(&id);
+            }
+        }
+    }
+
+    /// Keys remembered.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn expire(&mut self) {
+        let ttl = self.ttl;
+        self.entries
+            .retain(|_, e| matches!(e.state, State::Running) || e.at.elapsed() < ttl);
+        while self.entries.len() >= MAX_ENTRIES {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .filter(|(_, e)| matches!(e.state, State::Done(_)))
+                .min_by_key(|(_, e)| e.at)
+                .map(|(id, _)| id.clone())
+            else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+    }
+}
+
+/// Remove the idempotency key from tool arguments, returning it.
+pub fn take_key(args: &mut Value) -> McpResult<Option<String>> {
+    let Some(key) = args.as_object_mut().and_then(|a| a.remove(KEY_ARG)) else {
+        return Ok(None);
+    };
+    match key {
+        Value::String(key) if !key.is_empty() && key.len() <= MAX_KEY_LEN => Ok(Some(key)),
+        _ => Err(McpError::InvalidParams(format!(
+            "{KEY_ARG} must be a non-empty string of at most {MAX_KEY_LEN} bytes"
+        ))),
+    }
+}
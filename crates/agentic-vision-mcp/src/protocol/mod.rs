@@ -1,7 +1,10 @@
 //! MCP protocol handling — JSON-RPC dispatch.
 
+pub mod cache;
 pub mod handler;
+pub mod idempotency;
 pub mod negotiation;
 pub mod validator;
 
+pub use cache::{CacheStats, ResultCache};
 pub use handler::ProtocolHandler;
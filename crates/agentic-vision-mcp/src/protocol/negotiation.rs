@@ -1,26 +1,41 @@
 //! MCP capability negotiation during initialization.
 
 use crate::types::{
-    ClientCapabilities, InitializeParams, InitializeResult, McpResult, MCP_VERSION,
+    ClientCapabilities, Implementation, InitializeParams, InitializeResult, McpResult, MCP_VERSION,
+    SUPPORTED_VERSIONS,
 };
 
 /// Stored client capabilities after negotiation.
 #[derive(Debug, Clone, Default)]
 pub struct NegotiatedCapabilities {
     pub client: ClientCapabilities,
+    /// Name and version the client gave in `initialize`.
+    pub client_info: Option<Implementation>,
+    /// Protocol version agreed with the client; empty before `initialize`.
+    pub protocol_version: String,
     pub initialized: bool,
 }
 
-impl NegotiatedCapabilities {
-    pub fn negotiate(&mut self, params: InitializeParams) -> McpResult<InitializeResult> {
-        if params.protocol_version != MCP_VERSION {
+/// The version to answer a client requesting `requested` with: the same
+/// version if the server speaks it, otherwise the server's latest, which
+/// the client may accept or disconnect over.
+pub fn negotiate_version(requested: &str) -> &'static str {
+    match SUPPORTED_VERSIONS.iter().find(|v| **v == requested) {
+        Some(version) => version,
+        None => {
             tracing::warn!(
-                "Client requested protocol version {}, server supports {}. Proceeding with server version.",
-                params.protocol_version,
-                MCP_VERSION
+                "Client requested protocol version {requested}, server supports {}. Offering {MCP_VERSION}.",
+                SUPPORTED_VERSIONS.join(", ")
             );
+            MCP_VERSION
         }
+    }
+}
 
+impl NegotiatedCapabilities {
+    pub fn negotiate(&mut self, params: InitializeParams) -> McpResult<InitializeResult> {
+        let version = negotiate_version(&params.protocol_version);
+        self.protocol_version = version.to_string();
         self.client = params.capabilities;
 
         tracing::info!(
@@ -28,8 +43,12 @@ impl NegotiatedCapabilities {
             params.client_info.name,
             params.client_info.version
         );
+        self.client_info = Some(params.client_info);
 
-        Ok(InitializeResult::default_result())
+        Ok(InitializeResult {
+            protocol_version: version.to_string(),
+            ..InitializeResult::default_result()
+        })
     }
 
     pub fn mark_initialized(&mut self) -> McpResult<()> {
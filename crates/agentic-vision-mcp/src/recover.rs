@@ -0,0 +1,42 @@
+//! Recovery of damaged `.avis` files, such as ones truncated mid-write.
+//!
+//! The damaged file is never modified: readable captures go to a new file,
+//! which is read back before it takes its final name.
+
+use std::path::Path;
+
+use agentic_vision::{AvisReader, AvisWriter, SalvageReport};
+
+use crate::migrate::staging_path;
+use crate::types::{McpError, McpResult};
+
+/// Salvage the store at `input` into a new file at `output`.
+pub fn recover_file(input: &Path, output: &Path) -> McpResult<SalvageReport> {
+    if output.exists() {
+        return Err(McpError::InvalidParams(format!(
+            "{} already exists; recovery writes a new file",
+            output.display()
+        )));
+    }
+    let (store, report) = AvisReader::salvage_file(input)?;
+
+    let staging = staging_path(output);
+    AvisWriter::write_to_file(&store, &staging)?;
+    let written = match AvisReader::read_from_file(&staging) {
+        Ok(written) => written,
+        Err(e) => {
+            let _ = std::fs::remove_file(&staging);
+            return Err(e.into());
+        }
+    };
+    if written.count() != report.captures_recovered {
+        let _ = std::fs::remove_file(&staging);
+        return Err(McpError::VisionError(format!(
+            "Recovered file holds {} captures, expected {}",
+            written.count(),
+            report.captures_recovered
+        )));
+    }
+    std::fs::rename(&staging, output)?;
+    Ok(report)
+}
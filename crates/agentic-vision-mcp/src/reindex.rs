@@ -0,0 +1,164 @@
+//! Re-embedding a store in the background after a model switch.
+//!
+//! The work is done a batch at a time, taking the session lock only for one
+//! batch, so queries and captures keep working while it runs. Progress is
+//! checkpointed in the store (see [`agentic_vision::ReindexCheckpoint`]);
+//! a cancelled or killed run resumes from the checkpoint when started again
+//! with the same model.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+use agentic_vision::ReindexCheckpoint;
+
+use crate::session::VisionSessionManager;
+use crate::types::{McpError, McpResult};
+
+/// Captures re-embedded per lock of the session.
+pub const DEFAULT_BATCH: usize = 16;
+
+/// A re-index started in the background by `vision_reindex`.
+#[derive(Debug)]
+pub struct ReindexJob {
+    cancel: CancellationToken,
+    started: Instant,
+    /// Captures already done when this run started, for the rate.
+    done_before: usize,
+    running: bool,
+    finished: Option<ReindexCheckpoint>,
+    error: Option<String>,
+}
+
+impl ReindexJob {
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Ask the job to stop after its current batch; the checkpoint is saved.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+}
+
+/// Re-embed the re-index in progress until it completes or `cancel` fires,
+/// calling `report` after every batch. Returns the final checkpoint, or
+/// `None` when cancelled, in which case the checkpoint has been saved.
+pub async fn run(
+    session: &Arc<Mutex<VisionSessionManager>>,
+    batch: usize,
+    cancel: &CancellationToken,
+    mut report: impl FnMut(&ReindexCheckpoint),
+) -> McpResult<Option<ReindexCheckpoint>> {
+    loop {
+        if cancel.is_cancelled() {
+            session.lock().await.save()?;
+            return Ok(None);
+        }
+        let (checkpoint, finished) = session.lock().await.reindex_step(batch)?;
+        report(&checkpoint);
+        if finished {
+            return Ok(Some(checkpoint));
+        }
+        // Let waiting tool calls take the lock between batches.
+        tokio::task::yield_now().await;
+    }
+}
+
+/// Start or resume a re-index of the session's store in a background task.
+pub async fn spawn(session: &Arc<Mutex<VisionSessionManager>>, batch: usize) -> McpResult<Value> {
+    let cancel = CancellationToken::new();
+    let resumed = {
+        let mut guard = session.lock().await;
+        if guard.reindex_job().is_some_and(ReindexJob::is_running) {
+            return Err(McpError::InvalidParams(
+                "A re-index is already running".to_string(),
+            ));
+        }
+        let (checkpoint, resumed) = guard.reindex_start()?;
+        guard.set_reindex_job(ReindexJob {
+            cancel: cancel.clone(),
+            started: Instant::now(),
+            done_before: checkpoint.done + checkpoint.failed,
+            running: true,
+            finished: None,
+            error: None,
+        });
+        resumed
+    };
+
+    let task_session = Arc::clone(session);
+    tokio::spawn(async move {
+        let result = run(&task_session, batch, &cancel, |checkpoint| {
+            tracing::debug!(
+                "Re-index: {}/{} captures",
+                checkpoint.done + checkpoint.failed,
+                checkpoint.total
+            );
+        })
+        .await;
+        let mut guard = task_session.lock().await;
+        if let Err(e) = &result {
+            tracing::warn!("Re-index failed: {e}");
+        }
+        if let Some(job) = guard.reindex_job_mut() {
+            job.running = false;
+            match result {
+                Ok(finished) => job.finished = finished,
+                Err(e) => job.error = Some(e.to_string()),
+            }
+        }
+    });
+
+    let mut status = status(&*session.lock().await);
+    status["resumed"] = json!(resumed);
+    Ok(status)
+}
+
+/// Progress of the session's re-index as JSON.
+///
+/// `state` is `running`, `paused` (a checkpoint is saved but nothing is
+/// working on it), `complete`, `failed` or `idle`.
+pub fn status(session: &VisionSessionManager) -> Value {
+    let job = session.reindex_job();
+    let checkpoint = session.store().reindex.as_ref();
+    let state = match (job, checkpoint) {
+        (Some(job), _) if job.running => "running",
+        (Some(job), _) if job.error.is_some() => "failed",
+        (_, Some(_)) => "paused",
+        (Some(job), None) if job.finished.is_some() => "complete",
+        _ => "idle",
+    };
+
+    let mut status = json!({ "state": state });
+    let Some(progress) = checkpoint.or_else(|| job.and_then(|j| j.finished.as_ref())) else {
+        return status;
+    };
+    let processed = progress.done + progress.failed;
+    let remaining = progress.total.saturating_sub(processed);
+    status["model"] = json!(progress.model);
+    status["total"] = json!(progress.total);
+    status["done"] = json!(progress.done);
+    status["failed"] = json!(progress.failed);
+    status["remaining"] = json!(remaining);
+    status["percent"] = json!((progress.fraction() * 1000.0).round() / 10.0);
+    status["started_at"] = json!(progress.started_at);
+    status["updated_at"] = json!(progress.updated_at);
+
+    if let Some(job) = job.filter(|j| j.running) {
+        let elapsed = job.started.elapsed().as_secs_f64();
+        let this_run = processed.saturating_sub(job.done_before);
+        if elapsed > 0.0 && this_run > 0 {
+            let rate = this_run as f64 / elapsed;
+            status["rate_per_sec"] = json!((rate * 10.0).round() / 10.0);
+            status["eta_secs"] = json!((remaining as f64 / rate).ceil() as u64);
+        }
+    }
+    if let Some(error) = job.and_then(|j| j.error.as_ref()) {
+        status["error"] = json!(error);
+    }
+    status
+}
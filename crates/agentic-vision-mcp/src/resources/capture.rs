@@ -1,15 +1,21 @@
-//! Resource: avis://capture/{id}
+//! Resources: avis://capture/{id} and avis://thumbnail/{id}
 
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use base64::Engine;
 use serde_json::json;
 
 use crate::session::VisionSessionManager;
 use crate::types::{McpError, McpResult, ReadResourceResult, ResourceContent};
 
+use super::page::{base64_len, thumbnail_uri};
+
+/// Read a capture. Its thumbnail is inlined unless it alone would exceed
+/// `budget` bytes; it can always be read from `avis://thumbnail/{id}`.
 pub async fn read_capture(
     id: u64,
+    budget: usize,
     session: &Arc<Mutex<VisionSessionManager>>,
 ) -> McpResult<ReadResourceResult> {
     let session = session.lock().await;
@@ -18,8 +24,8 @@ pub async fn read_capture(
         .get(id)
         .ok_or(McpError::CaptureNotFound(id))?;
 
-    use base64::Engine;
-    let thumb_b64 = base64::engine::general_purpose::STANDARD.encode(&obs.thumbnail);
+    let thumb_b64 = (base64_len(obs.thumbnail.len()) <= budget)
+        .then(|| base64::engine::general_purpose::STANDARD.encode(&obs.thumbnail));
 
     let content = json!({
         "id": obs.id,
@@ -28,7 +34,9 @@ pub async fn read_capture(
         "source": obs.source,
         "metadata": obs.metadata,
         "memory_link": obs.memory_link,
+        "provenance": obs.provenance,
         "thumbnail_base64": thumb_b64,
+        "thumbnail_uri": thumbnail_uri(id),
         "embedding_dims": obs.embedding.len(),
     });
 
@@ -41,3 +49,24 @@ pub async fn read_capture(
         }],
     })
 }
+
+/// Read a capture's JPEG thumbnail as a binary resource.
+pub async fn read_thumbnail(
+    id: u64,
+    session: &Arc<Mutex<VisionSessionManager>>,
+) -> McpResult<ReadResourceResult> {
+    let session = session.lock().await;
+    let obs = session
+        .store()
+        .get(id)
+        .ok_or(McpError::CaptureNotFound(id))?;
+
+    Ok(ReadResourceResult {
+        contents: vec![ResourceContent {
+            uri: thumbnail_uri(id),
+            mime_type: Some("image/jpeg".to_string()),
+            text: None,
+            blob: Some(base64::engine::general_purpose::STANDARD.encode(&obs.thumbnail)),
+        }],
+    })
+}
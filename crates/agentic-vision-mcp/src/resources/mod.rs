@@ -1,10 +1,12 @@
 //! MCP resource implementations.
 
 pub mod capture;
+pub mod page;
 pub mod registry;
 pub mod session;
 pub mod similar;
 pub mod stats;
+pub mod summary;
 pub mod templates;
 pub mod timeline;
 
@@ -0,0 +1,142 @@
+//! Paging and the response memory budget shared by list resources.
+//!
+//! List resources take `?offset=N&limit=N&thumbnails=true` after their URI.
+//! A page holds at most `limit` captures and at most the budget in bytes of
+//! JSON; thumbnails are inlined only while they fit; past that, entries
+//! carry just their `avis://thumbnail/{id}` URI.
+
+use base64::Engine;
+use serde_json::{json, Map, Value};
+
+use crate::types::{McpError, McpResult};
+
+/// Response size budget unless configured otherwise.
+pub const DEFAULT_BUDGET_BYTES: usize = 4 * 1024 * 1024;
+
+/// Largest `limit` accepted.
+const MAX_LIMIT: usize = 500;
+
+/// Paging options of a list resource read.
+#[derive(Debug, Clone, Copy)]
+pub struct PageParams {
+    pub offset: usize,
+    pub limit: usize,
+    pub thumbnails: bool,
+}
+
+impl PageParams {
+    /// Parse a URI query string such as `offset=50&limit=50&thumbnails=true`.
+    pub fn parse(query: &str, default_limit: usize) -> McpResult<Self> {
+        let mut params = Self {
+            offset: 0,
+            limit: default_limit,
+            thumbnails: false,
+        };
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let invalid = || McpError::InvalidParams(format!("Invalid resource parameter: {pair}"));
+            match key {
+                "offset" => params.offset = value.parse().map_err(|_| invalid())?,
+                "limit" => params.limit = value.parse().map_err(|_| invalid())?,
+                "thumbnails" => params.thumbnails = value.parse().map_err(|_| invalid())?,
+                _ => return Err(invalid()),
+            }
+        }
+        if params.limit == 0 || params.limit > MAX_LIMIT {
+            return Err(McpError::InvalidParams(format!(
+                "'limit' must be between 1 and {MAX_LIMIT}"
+            )));
+        }
+        Ok(params)
+    }
+}
+
+/// The URI of a thumbnail resource.
+pub fn thumbnail_uri(id: u64) -> String {
+    format!("avis://thumbnail/{id}")
+}
+
+/// Length of `n` bytes in base64.
+pub fn base64_len(n: usize) -> usize {
+    n.div_ceil(3) * 4
+}
+
+/// Collects one page of entries within a byte budget.
+pub struct Page {
+    params: PageParams,
+    budget: usize,
+    used: usize,
+    entries: Vec<Value>,
+    thumbnails_omitted: usize,
+    budget_exceeded: bool,
+}
+
+impl Page {
+    pub fn new(params: PageParams, budget: usize) -> Self {
+        Self {
+            params,
+            budget,
+            used: 0,
+            entries: Vec::new(),
+            thumbnails_omitted: 0,
+            budget_exceeded: false,
+        }
+    }
+
+    /// Add an entry for capture `id`, inlining `thumbnail` if thumbnails
+    /// were asked for and it fits. Returns `false`, adding nothing, once
+    /// the page is full. The first entry is always added.
+    pub fn push(&mut self, id: u64, mut entry: Value, thumbnail: &[u8]) -> bool {
+        if self.entries.len() >= self.params.limit {
+            return false;
+        }
+        entry["thumbnail_uri"] = json!(thumbnail_uri(id));
+        let size = entry.to_string().len();
+        if !self.entries.is_empty() && self.used + size > self.budget {
+            self.budget_exceeded = true;
+            return false;
+        }
+        self.used += size;
+
+        if self.params.thumbnails {
+            let encoded = base64_len(thumbnail.len());
+            if self.used + encoded <= self.budget {
+                entry["thumbnail_base64"] =
+                    json!(base64::engine::general_purpose::STANDARD.encode(thumbnail));
+                self.used += encoded;
+            } else {
+                self.thumbnails_omitted += 1;
+                self.budget_exceeded = true;
+            }
+        }
+        self.entries.push(entry);
+        true
+    }
+
+    /// The page as JSON fields: the entries under `key`, plus `total`,
+    /// `offset`, and `next` (the URI of the following page, if any).
+    pub fn finish(self, key: &str, base_uri: &str, total: usize) -> Map<String, Value> {
+        let end = self.params.offset + self.entries.len();
+        let next = (end < total).then(|| {
+            let mut uri = format!("{base_uri}?offset={end}&limit={}", self.params.limit);
+            if self.params.thumbnails {
+                uri.push_str("&thumbnails=true");
+            }
+            uri
+        });
+
+        let mut fields = Map::new();
+        fields.insert("total".into(), json!(total));
+        fields.insert("offset".into(), json!(self.params.offset));
+        fields.insert("count".into(), json!(self.entries.len()));
+        fields.insert("next".into(), json!(next));
+        if self.budget_exceeded {
+            fields.insert("budget_exceeded".into(), json!(true));
+        }
+        if self.thumbnails_omitted > 0 {
+            fields.insert("thumbnails_omitted".into(), json!(self.thumbnails_omitted));
+        }
+        fields.insert(key.into(), Value::Array(self.entries));
+        fields
+    }
+}
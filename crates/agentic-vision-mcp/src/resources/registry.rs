@@ -8,7 +8,16 @@ use crate::types::{
     McpError, McpResult, ReadResourceResult, ResourceDefinition, ResourceTemplateDefinition,
 };
 
-use super::{capture, session, similar, stats, templates, timeline};
+use super::page::PageParams;
+use super::summary::SummaryParams;
+use super::{capture, session, similar, stats, summary, templates, timeline};
+
+/// Entries per page of the session, sessions and timeline resources, by
+/// default.
+const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Matches per page of the similar resource, by default.
+const DEFAULT_SIMILAR_PAGE_SIZE: usize = 10;
 
 pub struct ResourceRegistry;
 
@@ -21,20 +30,39 @@ impl ResourceRegistry {
         templates::list_resources()
     }
 
+    /// Read a resource. List resources take paging parameters after `?`;
+    /// no response is let grow much past `budget` bytes.
     pub async fn read(
         uri: &str,
         session: &Arc<Mutex<VisionSessionManager>>,
+        budget: usize,
     ) -> McpResult<ReadResourceResult> {
+        let (uri, query) = uri.split_once('?').unwrap_or((uri, ""));
+
         if let Some(id_str) = uri.strip_prefix("avis://capture/") {
             let id: u64 = id_str
                 .parse()
                 .map_err(|_| McpError::InvalidParams(format!("Invalid capture ID: {id_str}")))?;
-            capture::read_capture(id, session).await
+            capture::read_capture(id, budget, session).await
+        } else if let Some(id_str) = uri.strip_prefix("avis://thumbnail/") {
+            let id: u64 = id_str
+                .parse()
+                .map_err(|_| McpError::InvalidParams(format!("Invalid capture ID: {id_str}")))?;
+            capture::read_thumbnail(id, session).await
+        } else if let Some(id_str) = uri
+            .strip_prefix("avis://session/")
+            .and_then(|rest| rest.strip_suffix("/summary"))
+        {
+            let id: u32 = id_str
+                .parse()
+                .map_err(|_| McpError::InvalidParams(format!("Invalid session ID: {id_str}")))?;
+            summary::read_summary(id, SummaryParams::parse(query)?, budget, session).await
         } else if let Some(id_str) = uri.strip_prefix("avis://session/") {
             let id: u32 = id_str
                 .parse()
                 .map_err(|_| McpError::InvalidParams(format!("Invalid session ID: {id_str}")))?;
-            session::read_session(id, session).await
+            let params = PageParams::parse(query, DEFAULT_PAGE_SIZE)?;
+            session::read_session(id, params, budget, session).await
         } else if let Some(rest) = uri.strip_prefix("avis://timeline/") {
             let parts: Vec<&str> = rest.split('/').collect();
             if parts.len() != 2 {
@@ -48,12 +76,17 @@ impl ResourceRegistry {
             let end: u64 = parts[1]
                 .parse()
                 .map_err(|_| McpError::InvalidParams("Invalid end timestamp".to_string()))?;
-            timeline::read_timeline(start, end, session).await
+            let params = PageParams::parse(query, DEFAULT_PAGE_SIZE)?;
+            timeline::read_timeline(start, end, params, budget, session).await
         } else if let Some(id_str) = uri.strip_prefix("avis://similar/") {
             let id: u64 = id_str
                 .parse()
                 .map_err(|_| McpError::InvalidParams(format!("Invalid capture ID: {id_str}")))?;
-            similar::read_similar(id, session).await
+            let params = PageParams::parse(query, DEFAULT_SIMILAR_PAGE_SIZE)?;
+            similar::read_similar(id, params, budget, session).await
+        } else if uri == "avis://sessions" {
+            let params = PageParams::parse(query, DEFAULT_PAGE_SIZE)?;
+            session::read_sessions(params, session).await
         } else if uri == "avis://stats" {
             stats::read_stats(session).await
         } else if uri == "avis://recent" {
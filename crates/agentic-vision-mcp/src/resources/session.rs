@@ -1,4 +1,4 @@
-//! Resource: avis://session/{id}
+//! Resource: avis://session/{id} and avis://sessions
 
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -8,39 +8,88 @@ use serde_json::json;
 use crate::session::VisionSessionManager;
 use crate::types::{McpResult, ReadResourceResult, ResourceContent};
 
+use super::page::{Page, PageParams};
+
 pub async fn read_session(
     session_id: u32,
+    params: PageParams,
+    budget: usize,
     session: &Arc<Mutex<VisionSessionManager>>,
 ) -> McpResult<ReadResourceResult> {
     let session = session.lock().await;
     let captures = session.store().by_session(session_id);
+    let uri = format!("avis://session/{session_id}");
+
+    let mut page = Page::new(params, budget);
+    for o in captures.iter().skip(params.offset) {
+        let entry = json!({
+            "id": o.id,
+            "timestamp": o.timestamp,
+            "dimensions": {
+                "width": o.metadata.original_width,
+                "height": o.metadata.original_height,
+            },
+            "labels": o.metadata.labels,
+            "description": o.metadata.description,
+            "caption": o.metadata.caption,
+            "memory_link": o.memory_link,
+        });
+        if !page.push(o.id, entry, &o.thumbnail) {
+            break;
+        }
+    }
+
+    let mut content = page.finish("captures", &uri, captures.len());
+    content.insert("session_id".into(), json!(session_id));
+    content.insert("capture_count".into(), json!(captures.len()));
+
+    Ok(ReadResourceResult {
+        contents: vec![ResourceContent {
+            uri,
+            mime_type: Some("application/json".to_string()),
+            text: Some(serde_json::to_string_pretty(&content).unwrap_or_default()),
+            blob: None,
+        }],
+    })
+}
+
+/// Every session with its metadata, capture count and duration, newest
+/// first.
+pub async fn read_sessions(
+    params: PageParams,
+    session: &Arc<Mutex<VisionSessionManager>>,
+) -> McpResult<ReadResourceResult> {
+    let session = session.lock().await;
+    let mut summaries = session.store().session_summaries();
+    summaries.reverse();
+    let total = summaries.len();
+    let current = session.current_session_id();
 
-    let obs_list: Vec<_> = captures
-        .iter()
-        .map(|o| {
-            json!({
-                "id": o.id,
-                "timestamp": o.timestamp,
-                "dimensions": {
-                    "width": o.metadata.original_width,
-                    "height": o.metadata.original_height,
-                },
-                "labels": o.metadata.labels,
-                "description": o.metadata.description,
-                "memory_link": o.memory_link,
-            })
+    let sessions: Vec<_> = summaries
+        .into_iter()
+        .skip(params.offset)
+        .take(params.limit)
+        .map(|s| {
+            let mut entry = json!(s);
+            entry["current"] = json!(s.info.id == current);
+            entry
         })
         .collect();
+    let end = params.offset + sessions.len();
+    let next =
+        (end < total).then(|| format!("avis://sessions?offset={end}&limit={}", params.limit));
 
     let content = json!({
-        "session_id": session_id,
-        "capture_count": obs_list.len(),
-        "captures": obs_list,
+        "total": total,
+        "offset": params.offset,
+        "count": sessions.len(),
+        "next": next,
+        "sessions": sessions,
     });
 
     Ok(ReadResourceResult {
         contents: vec![ResourceContent {
-            uri: format!("avis://session/{session_id}"),
+            uri: "avis://sessions".to_string(),
             mime_type: Some("application/json".to_string()),
             text: Some(serde_json::to_string_pretty(&content).unwrap_or_default()),
             blob: None,
@@ -5,35 +5,55 @@ use tokio::sync::Mutex;
 
 use serde_json::json;
 
+use agentic_vision::SimilarityFilter;
+
 use crate::session::VisionSessionManager;
 use crate::types::{McpResult, ReadResourceResult, ResourceContent};
 
+use super::page::{Page, PageParams};
+
+/// Most matches ranked, across all pages.
+const MAX_MATCHES: usize = 100;
+
 pub async fn read_similar(
     capture_id: u64,
+    params: PageParams,
+    budget: usize,
     session: &Arc<Mutex<VisionSessionManager>>,
 ) -> McpResult<ReadResourceResult> {
     let session = session.lock().await;
-    let matches = session.find_similar(capture_id, 10, 0.5)?;
-
-    let match_list: Vec<_> = matches
-        .iter()
-        .map(|m| {
-            json!({
-                "id": m.id,
-                "similarity": m.similarity,
-            })
-        })
-        .collect();
-
-    let content = json!({
-        "capture_id": capture_id,
-        "similar_count": match_list.len(),
-        "matches": match_list,
-    });
+    let matches = session.find_similar(
+        capture_id,
+        &SimilarityFilter::default(),
+        MAX_MATCHES,
+        0.5,
+        0.0,
+        0.0,
+    )?;
+    let uri = format!("avis://similar/{capture_id}");
+
+    let mut page = Page::new(params, budget);
+    for m in matches.iter().skip(params.offset) {
+        let thumbnail = session
+            .store()
+            .get(m.id)
+            .map_or(&[][..], |o| o.thumbnail.as_slice());
+        let entry = json!({
+            "id": m.id,
+            "similarity": m.similarity,
+        });
+        if !page.push(m.id, entry, thumbnail) {
+            break;
+        }
+    }
+
+    let mut content = page.finish("matches", &uri, matches.len());
+    content.insert("capture_id".into(), json!(capture_id));
+    content.insert("similar_count".into(), json!(matches.len()));
 
     Ok(ReadResourceResult {
         contents: vec![ResourceContent {
-            uri: format!("avis://similar/{capture_id}"),
+            uri,
             mime_type: Some("application/json".to_string()),
             text: Some(serde_json::to_string_pretty(&content).unwrap_or_default()),
             blob: None,
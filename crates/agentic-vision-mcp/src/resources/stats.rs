@@ -13,15 +13,24 @@ pub async fn read_stats(
 ) -> McpResult<ReadResourceResult> {
     let session = session.lock().await;
     let store = session.store();
+    let stats = store.statistics();
+    let file_size = std::fs::metadata(session.file_path()).ok().map(|m| m.len());
 
     let content = json!({
-        "total_captures": store.count(),
+        "total_captures": stats.total_captures,
         "embedding_dim": store.embedding_dim,
         "session_count": store.session_count,
         "next_id": store.next_id,
         "created_at": store.created_at,
         "updated_at": store.updated_at,
+        "labels": stats.labels,
+        "label_aliases": store.label_aliases,
+        "embeddings": stats.embeddings,
+        "capture_rate": stats.capture_rate,
+        "storage": stats.storage,
+        "dedup": stats.dedup,
         "file_path": session.file_path().display().to_string(),
+        "file_size": file_size,
     });
 
     Ok(ReadResourceResult {
@@ -53,6 +62,7 @@ pub async fn read_recent(
                 },
                 "labels": o.metadata.labels,
                 "description": o.metadata.description,
+                "caption": o.metadata.caption,
                 "memory_link": o.memory_link,
             })
         })
@@ -0,0 +1,104 @@
+//! Resource: avis://session/{id}/summary
+
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use base64::Engine;
+use serde_json::json;
+
+use agentic_vision::DigestOptions;
+
+use crate::session::VisionSessionManager;
+use crate::types::{McpError, McpResult, ReadResourceResult, ResourceContent};
+
+use super::page::{base64_len, thumbnail_uri};
+
+/// Largest number of moments or representatives accepted.
+const MAX_ITEMS: usize = 50;
+
+/// Options of a summary read.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SummaryParams {
+    pub digest: DigestOptions,
+    pub thumbnails: bool,
+}
+
+impl SummaryParams {
+    /// Parse a URI query string such as `moments=5&representatives=2&thumbnails=true`.
+    pub fn parse(query: &str) -> McpResult<Self> {
+        let mut params = Self::default();
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let invalid = || McpError::InvalidParams(format!("Invalid resource parameter: {pair}"));
+            match key {
+                "moments" => params.digest.max_moments = value.parse().map_err(|_| invalid())?,
+                "representatives" => {
+                    params.digest.max_representatives = value.parse().map_err(|_| invalid())?
+                }
+                "thumbnails" => params.thumbnails = value.parse().map_err(|_| invalid())?,
+                _ => return Err(invalid()),
+            }
+        }
+        if params.digest.max_moments > MAX_ITEMS || params.digest.max_representatives > MAX_ITEMS {
+            return Err(McpError::InvalidParams(format!(
+                "'moments' and 'representatives' must be at most {MAX_ITEMS}"
+            )));
+        }
+        Ok(params)
+    }
+}
+
+/// The session's screens and key moments. Representatives carry their
+/// thumbnail URI, and with `thumbnails=true` the thumbnail itself while it
+/// fits in `budget`.
+pub async fn read_summary(
+    session_id: u32,
+    params: SummaryParams,
+    budget: usize,
+    session: &Arc<Mutex<VisionSessionManager>>,
+) -> McpResult<ReadResourceResult> {
+    let session = session.lock().await;
+    let store = session.store();
+    let digest = store
+        .session_digest(session_id, &params.digest)
+        .ok_or_else(|| {
+            McpError::ResourceNotFound(format!("avis://session/{session_id}/summary"))
+        })?;
+    let uri = format!("avis://session/{session_id}/summary");
+
+    let mut content = json!(digest);
+    let mut used = content.to_string().len();
+    let mut omitted = 0;
+    if let Some(representatives) = content["representatives"].as_array_mut() {
+        for entry in representatives {
+            let Some(id) = entry["representative"].as_u64() else {
+                continue;
+            };
+            entry["thumbnail_uri"] = json!(thumbnail_uri(id));
+            if !params.thumbnails {
+                continue;
+            }
+            let thumbnail = store.get(id).map_or(&[][..], |o| o.thumbnail.as_slice());
+            if used + base64_len(thumbnail.len()) <= budget {
+                entry["thumbnail_base64"] =
+                    json!(base64::engine::general_purpose::STANDARD.encode(thumbnail));
+                used += base64_len(thumbnail.len());
+            } else {
+                omitted += 1;
+            }
+        }
+    }
+    if omitted > 0 {
+        content["budget_exceeded"] = json!(true);
+        content["thumbnails_omitted"] = json!(omitted);
+    }
+
+    Ok(ReadResourceResult {
+        contents: vec![ResourceContent {
+            uri,
+            mime_type: Some("application/json".to_string()),
+            text: Some(serde_json::to_string_pretty(&content).unwrap_or_default()),
+            blob: None,
+        }],
+    })
+}
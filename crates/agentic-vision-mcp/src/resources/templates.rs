@@ -13,26 +13,59 @@ pub fn list_templates() -> Vec<ResourceTemplateDefinition> {
         ResourceTemplateDefinition {
             uri_template: "avis://session/{id}".to_string(),
             name: "Session Captures".to_string(),
-            description: Some("All captures from a specific session".to_string()),
+            description: Some(
+                "Captures from a specific session, 50 per page (?offset=&limit=&thumbnails=true)"
+                    .to_string(),
+            ),
+            mime_type: Some("application/json".to_string()),
+        },
+        ResourceTemplateDefinition {
+            uri_template: "avis://session/{id}/summary".to_string(),
+            name: "Session Summary".to_string(),
+            description: Some(
+                "Screens a session visited with representative captures, and its key moments: large changes, new screens and anomalies (?moments=10&representatives=4&thumbnails=true)"
+                    .to_string(),
+            ),
             mime_type: Some("application/json".to_string()),
         },
         ResourceTemplateDefinition {
             uri_template: "avis://timeline/{start}/{end}".to_string(),
             name: "Timeline".to_string(),
-            description: Some("Captures in a timestamp range".to_string()),
+            description: Some(
+                "Captures in a timestamp range, 50 per page (?offset=&limit=&thumbnails=true)"
+                    .to_string(),
+            ),
             mime_type: Some("application/json".to_string()),
         },
         ResourceTemplateDefinition {
             uri_template: "avis://similar/{id}".to_string(),
             name: "Similar Captures".to_string(),
-            description: Some("Top 10 visually similar captures".to_string()),
+            description: Some(
+                "Visually similar captures, 10 per page (?offset=&limit=&thumbnails=true)"
+                    .to_string(),
+            ),
             mime_type: Some("application/json".to_string()),
         },
+        ResourceTemplateDefinition {
+            uri_template: "avis://thumbnail/{id}".to_string(),
+            name: "Capture Thumbnail".to_string(),
+            description: Some("A capture's JPEG thumbnail".to_string()),
+            mime_type: Some("image/jpeg".to_string()),
+        },
     ]
 }
 
 pub fn list_resources() -> Vec<ResourceDefinition> {
     vec![
+        ResourceDefinition {
+            uri: "avis://sessions".to_string(),
+            name: "Sessions".to_string(),
+            description: Some(
+                "Sessions with name, goal, agent, duration and capture count, 50 per page (?offset=&limit=)"
+                    .to_string(),
+            ),
+            mime_type: Some("application/json".to_string()),
+        },
         ResourceDefinition {
             uri: "avis://stats".to_string(),
             name: "Vision Statistics".to_string(),
@@ -8,41 +8,46 @@ use serde_json::json;
 use crate::session::VisionSessionManager;
 use crate::types::{McpResult, ReadResourceResult, ResourceContent};
 
+use super::page::{Page, PageParams};
+
 pub async fn read_timeline(
     start: u64,
     end: u64,
+    params: PageParams,
+    budget: usize,
     session: &Arc<Mutex<VisionSessionManager>>,
 ) -> McpResult<ReadResourceResult> {
     let session = session.lock().await;
     let captures = session.store().in_time_range(start, end);
+    let uri = format!("avis://timeline/{start}/{end}");
+
+    let mut page = Page::new(params, budget);
+    for o in captures.iter().skip(params.offset) {
+        let entry = json!({
+            "id": o.id,
+            "timestamp": o.timestamp,
+            "session_id": o.session_id,
+            "dimensions": {
+                "width": o.metadata.original_width,
+                "height": o.metadata.original_height,
+            },
+            "labels": o.metadata.labels,
+            "description": o.metadata.description,
+            "caption": o.metadata.caption,
+        });
+        if !page.push(o.id, entry, &o.thumbnail) {
+            break;
+        }
+    }
 
-    let obs_list: Vec<_> = captures
-        .iter()
-        .map(|o| {
-            json!({
-                "id": o.id,
-                "timestamp": o.timestamp,
-                "session_id": o.session_id,
-                "dimensions": {
-                    "width": o.metadata.original_width,
-                    "height": o.metadata.original_height,
-                },
-                "labels": o.metadata.labels,
-                "description": o.metadata.description,
-            })
-        })
-        .collect();
-
-    let content = json!({
-        "start": start,
-        "end": end,
-        "capture_count": obs_list.len(),
-        "captures": obs_list,
-    });
+    let mut content = page.finish("captures", &uri, captures.len());
+    content.insert("start".into(), json!(start));
+    content.insert("end".into(), json!(end));
+    content.insert("capture_count".into(), json!(captures.len()));
 
     Ok(ReadResourceResult {
         contents: vec![ResourceContent {
-            uri: format!("avis://timeline/{start}/{end}"),
+            uri,
             mime_type: Some("application/json".to_string()),
             text: Some(serde_json::to_string_pretty(&content).unwrap_or_default()),
             blob: None,
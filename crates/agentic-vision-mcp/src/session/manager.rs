@@ -1,27 +1,59 @@
 //! Visual memory session lifecycle, file I/O, and session tracking.
 
+use std::collections::HashSet;
 use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
 use image::GenericImageView;
+use tokio_util::sync::CancellationToken;
 
 use agentic_vision::{
-    capture_from_base64, capture_from_file, compute_diff, cosine_similarity, find_similar,
-    generate_thumbnail, AvisReader, AvisWriter, CaptureSource, EmbeddingEngine, ObservationMeta,
-    Rect, SimilarityMatch, VisualDiff, VisualMemoryStore, VisualObservation, EMBEDDING_DIM,
+    capture_from_base64, capture_from_file, compute_diff, compute_fingerprint, cosine_similarity,
+    detect_language, find_similar_filtered, generate_thumbnail, pixel_hash, rerank_diverse,
+    sha256_hex, AvisReader, AvisWriter, Calibration, CaptureSource, DedupOptions, DedupReport,
+    EmbeddingEngine, ManifestEntry, ObservationMeta, OcrResult, PipelineConfig, Provenance, Rect,
+    ReindexCheckpoint, SimilarityFilter, SimilarityMatch, Snapshot, VisualDiff, VisualMemoryStore,
+    VisualObservation, DIVERSITY_POOL, EMBEDDING_DIM, PIPELINE_STEPS,
 };
 
+use crate::config::{
+    resolve_capture_pipelines, resolve_idempotency_ttl, CaptionMode, OcrBackend, OcrConfig,
+};
+use crate::ocr::{OcrEngines, SharedOcrEngine};
+use crate::protocol::idempotency::IdempotencyStore;
+use crate::reindex::ReindexJob;
 use crate::types::{McpError, McpResult};
 
 const DEFAULT_AUTO_SAVE_SECS: u64 = 30;
 
+/// Most captures one query captions lazily.
+const MAX_LAZY_CAPTIONS: usize = 100;
+
 /// Manages the visual memory lifecycle, file I/O, and session state.
 pub struct VisionSessionManager {
     store: VisualMemoryStore,
     engine: EmbeddingEngine,
+    #[cfg(feature = "caption")]
+    captioner: agentic_vision::CaptionEngine,
+    caption_mode: CaptionMode,
+    #[cfg(feature = "ui-detect")]
+    detector: agentic_vision::ElementDetector,
+    ocr: OcrEngines,
+    /// Stages run on captures before they are stored.
+    pipelines: PipelineConfig,
+    /// CLIP model given at open, recorded in re-index checkpoints.
+    model_path: Option<String>,
+    reindex_job: Option<ReindexJob>,
+    /// Results of calls made with an idempotency key.
+    idempotency: IdempotencyStore,
     file_path: PathBuf,
     current_session: u32,
     dirty: bool,
+    /// Set once the store is erased; nothing is saved after that.
+    erased: bool,
+    /// Bumped on every change to the store, so cached results can tell
+    /// they are stale.
+    generation: u64,
     last_save: Instant,
     auto_save_interval: Duration,
 }
@@ -31,7 +63,7 @@ impl VisionSessionManager {
     pub fn open(path: &str, model_path: Option<&str>) -> McpResult<Self> {
         let file_path = PathBuf::from(path);
 
-        let store = if file_path.exists() {
+        let mut store = if file_path.exists() {
             tracing::info!("Opening existing vision file: {}", file_path.display());
             AvisReader::read_from_file(&file_path)
                 .map_err(|e| McpError::VisionError(format!("Failed to read vision file: {e}")))?
@@ -48,12 +80,30 @@ impl VisionSessionManager {
             VisualMemoryStore::new(EMBEDDING_DIM)
         };
 
+        let backfilled = store.backfill_fingerprints();
+        if backfilled > 0 {
+            tracing::info!("Computed layout fingerprints for {backfilled} older captures");
+        }
+
         let current_session = store.session_count + 1;
 
         let engine = EmbeddingEngine::new(model_path).map_err(|e| {
             McpError::VisionError(format!("Failed to initialize embedding engine: {e}"))
         })?;
 
+        #[cfg(feature = "caption")]
+        let captioner =
+            agentic_vision::CaptionEngine::new(crate::config::resolve_caption_model().as_deref())
+                .map_err(|e| {
+                McpError::VisionError(format!("Failed to initialize caption engine: {e}"))
+            })?;
+
+        #[cfg(feature = "ui-detect")]
+        let detector = agentic_vision::ElementDetector::new(
+            crate::config::resolve_ui_detect_model().as_deref(),
+        )
+        .map_err(|e| McpError::VisionError(format!("Failed to initialize UI detector: {e}")))?;
+
         tracing::info!(
             "Session {} started. Store has {} observations. Embedding model: {}",
             current_session,
@@ -68,9 +118,21 @@ impl VisionSessionManager {
         Ok(Self {
             store,
             engine,
+            #[cfg(feature = "caption")]
+            captioner,
+            caption_mode: CaptionMode::from_env(),
+            #[cfg(feature = "ui-detect")]
+            detector,
+            ocr: OcrEngines::new(OcrConfig::from_env()),
+            pipelines: resolve_capture_pipelines()?,
+            model_path: model_path.map(String::from),
+            reindex_job: None,
+            idempotency: IdempotencyStore::new(resolve_idempotency_ttl()),
             file_path,
             current_session,
-            dirty: false,
+            dirty: backfilled > 0,
+            erased: false,
+            generation: 0,
             last_save: Instant::now(),
             auto_save_interval: Duration::from_secs(DEFAULT_AUTO_SAVE_SECS),
         })
@@ -81,29 +143,68 @@ impl VisionSessionManager {
         &self.store
     }
 
+    /// Counter that changes whenever the store does.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn mark_dirty(&mut self) {
+        self.dirty = true;
+        self.generation += 1;
+    }
+
     /// Current session ID.
     pub fn current_session_id(&self) -> u32 {
         self.current_session
     }
 
-    /// Start a new session.
+    /// Start a new session. Resuming an earlier session keeps its start time.
     pub fn start_session(&mut self, explicit_id: Option<u32>) -> McpResult<u32> {
         let session_id = explicit_id.unwrap_or(self.current_session + 1);
         self.current_session = session_id;
         self.store.session_count = self.store.session_count.max(session_id);
+        let info = self.store.session_info_mut(session_id);
+        info.started_at.get_or_insert_with(now_secs);
+        info.ended_at = None;
+        self.mark_dirty();
         tracing::info!("Started session {session_id}");
         Ok(session_id)
     }
 
+    /// Set the name, description and agent of session `session_id`; `None`
+    /// leaves a field as it was.
+    pub fn describe_session(
+        &mut self,
+        session_id: u32,
+        name: Option<String>,
+        description: Option<String>,
+        agent: Option<String>,
+    ) {
+        let info = self.store.session_info_mut(session_id);
+        if name.is_some() {
+            info.name = name;
+        }
+        if description.is_some() {
+            info.description = description;
+        }
+        if agent.is_some() {
+            info.agent = agent;
+        }
+        self.mark_dirty();
+    }
+
     /// End the current session.
     pub fn end_session(&mut self) -> McpResult<u32> {
         let session_id = self.current_session;
+        self.store.session_info_mut(session_id).ended_at = Some(now_secs());
+        self.mark_dirty();
         self.save()?;
         tracing::info!("Ended session {session_id}");
         Ok(session_id)
     }
 
     /// Capture an image from a file or base64 source.
+    #[allow(clippy::too_many_arguments)]
     pub fn capture(
         &mut self,
         source_type: &str,
@@ -112,6 +213,8 @@ impl VisionSessionManager {
         labels: Vec<String>,
         description: Option<String>,
         _extract_ocr: bool,
+        mut provenance: Provenance,
+        cancel: &CancellationToken,
     ) -> McpResult<CaptureResult> {
         let (img, source) = match source_type {
             "file" => capture_from_file(source_data)
@@ -127,8 +230,16 @@ impl VisionSessionManager {
                 )));
             }
         };
+        // The original as received, not as decoded: a re-encoded or
+        // replaced file must not match.
+        provenance.original_hash = match source_type {
+            "file" => std::fs::read(source_data).ok().map(|b| sha256_hex(&b)),
+            _ => base64::Engine::decode(&base64::engine::general_purpose::STANDARD, source_data)
+                .ok()
+                .map(|b| sha256_hex(&b)),
+        };
 
-        self.store_capture(img, source, labels, description)
+        self.store_capture(img, source, labels, description, provenance, cancel)
     }
 
     /// Capture a screenshot and store it in visual memory.
@@ -138,11 +249,20 @@ impl VisionSessionManager {
         labels: Vec<String>,
         description: Option<String>,
         _extract_ocr: bool,
+        mut provenance: Provenance,
+        cancel: &CancellationToken,
     ) -> McpResult<CaptureResult> {
         let (img, source) = agentic_vision::capture_screenshot(region)
             .map_err(|e| McpError::VisionError(format!("Screenshot capture failed: {e}")))?;
+        if let Some(r) = region {
+            provenance.record(
+                "crop",
+                Some(format!("screen region {}x{} at {},{}", r.w, r.h, r.x, r.y)),
+                now_secs(),
+            );
+        }
 
-        self.store_capture(img, source, labels, description)
+        self.store_capture(img, source, labels, description, provenance, cancel)
     }
 
     /// Capture an image from the clipboard and store it in visual memory.
@@ -151,36 +271,78 @@ impl VisionSessionManager {
         labels: Vec<String>,
         description: Option<String>,
         _extract_ocr: bool,
+        provenance: Provenance,
+        cancel: &CancellationToken,
     ) -> McpResult<CaptureResult> {
         let (img, source) = agentic_vision::capture_clipboard()
             .map_err(|e| McpError::VisionError(format!("Clipboard capture failed: {e}")))?;
 
-        self.store_capture(img, source, labels, description)
+        self.store_capture(img, source, labels, description, provenance, cancel)
     }
 
     /// Internal: process a captured image and store it as an observation.
+    /// The image first goes through the capture pipelines matching its
+    /// source and labels; its stored size is the size they leave it at.
+    /// Nothing is stored if `cancel` fires between the model passes.
+    /// `provenance` is completed with the source, the agent, the pipeline
+    /// and thumbnail steps, and a pixel hash if it has no hash of the
+    /// original.
     fn store_capture(
         &mut self,
         img: image::DynamicImage,
         source: CaptureSource,
         labels: Vec<String>,
         description: Option<String>,
+        mut provenance: Provenance,
+        cancel: &CancellationToken,
     ) -> McpResult<CaptureResult> {
+        let labels = self.resolve_labels(&labels);
+        if provenance.original_hash.is_none() {
+            provenance.original_hash = Some(pixel_hash(&img));
+        }
+        let processed = self.pipelines.apply(img, &source, &labels)?;
+        let img = processed.image;
         let (orig_w, orig_h) = img.dimensions();
         let thumbnail = generate_thumbnail(&img);
         let thumb_img = image::load_from_memory(&thumbnail)
             .map_err(|e| McpError::VisionError(format!("Failed to load thumbnail: {e}")))?;
         let (thumb_w, thumb_h) = thumb_img.dimensions();
+        let fingerprint = compute_fingerprint(&img);
 
         let embedding = self
             .engine
             .embed(&img)
             .map_err(|e| McpError::VisionError(format!("Embedding failed: {e}")))?;
+        check_cancelled(cancel)?;
+
+        let caption = match self.caption_mode {
+            CaptionMode::Eager => self.caption_image(&img),
+            CaptionMode::Lazy if processed.caption => self.caption_image(&img),
+            CaptionMode::Lazy => None,
+        };
+        check_cancelled(cancel)?;
+        let elements = self.detect_elements(&img);
+        check_cancelled(cancel)?;
+        let ocr = match &processed.ocr {
+            Some(language) => self.recognize(&img, language),
+            None => None,
+        };
+        check_cancelled(cancel)?;
 
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
+        let now = now_secs();
+        provenance.source_type = source.kind().to_string();
+        for (step, detail) in &processed.steps {
+            provenance.record(step, Some(detail.clone()), now);
+        }
+        provenance.agent = self
+            .store
+            .session_info(self.current_session)
+            .and_then(|info| info.agent.clone());
+        provenance.record(
+            "thumbnail",
+            Some(format!("{orig_w}x{orig_h} -> {thumb_w}x{thumb_h} JPEG")),
+            now,
+        );
 
         let obs = VisualObservation {
             id: 0, // assigned by store
@@ -196,12 +358,17 @@ impl VisionSessionManager {
                 original_height: orig_h,
                 labels,
                 description,
+                caption: caption.clone(),
             },
             memory_link: None,
+            fingerprint,
+            elements,
+            ocr: ocr.clone(),
+            provenance: Some(provenance),
         };
 
         let id = self.store.add(obs);
-        self.dirty = true;
+        self.mark_dirty();
         self.maybe_auto_save()?;
 
         Ok(CaptureResult {
@@ -210,9 +377,300 @@ impl VisionSessionManager {
             width: orig_w,
             height: orig_h,
             embedding_dims: EMBEDDING_DIM,
+            caption,
+            processed: processed
+                .steps
+                .iter()
+                .map(|(step, detail)| format!("{step} ({detail})"))
+                .collect(),
+            ocr_text: ocr.map(|r| r.text),
         })
     }
 
+    /// Run captures through the pipelines of `config` from now on.
+    pub fn set_capture_pipelines(&mut self, config: PipelineConfig) {
+        self.pipelines = config;
+    }
+
+    /// Use `config` for OCR from now on.
+    pub fn set_ocr_config(&mut self, config: OcrConfig) {
+        self.ocr = OcrEngines::new(config);
+    }
+
+    /// The OCR engine of `backend`, or of the configured default.
+    pub fn ocr_engine(
+        &mut self,
+        backend: Option<OcrBackend>,
+    ) -> McpResult<(OcrBackend, SharedOcrEngine)> {
+        let backend = backend.unwrap_or(self.ocr.default_backend());
+        Ok((backend, self.ocr.get(backend)?))
+    }
+
+    /// The best image of a capture to work from: the original file if it is
+    /// still there unchanged in size and no pipeline changed the capture,
+    /// otherwise the thumbnail. The second
+    /// value says which.
+    pub fn source_image(&self, capture_id: u64) -> McpResult<(image::DynamicImage, &'static str)> {
+        let obs = self
+            .store
+            .get(capture_id)
+            .ok_or(McpError::CaptureNotFound(capture_id))?;
+        // A capture changed by a pipeline no longer matches its file, and
+        // must not bring back what it redacted.
+        let processed = obs.provenance.as_ref().is_some_and(|p| {
+            p.transforms
+                .iter()
+                .any(|t| PIPELINE_STEPS.contains(&t.step.as_str()))
+        });
+        if let (CaptureSource::File { path }, false) = (&obs.source, processed) {
+            if let Ok(img) = image::open(path) {
+                if img.dimensions() == (obs.metadata.original_width, obs.metadata.original_height) {
+                    return Ok((img, "original"));
+                }
+            }
+        }
+        let thumb = image::load_from_memory(&obs.thumbnail)
+            .map_err(|e| McpError::VisionError(format!("Failed to load thumbnail: {e}")))?;
+        Ok((thumb, "thumbnail"))
+    }
+
+    /// Start re-embedding every capture with the loaded model, or resume
+    /// an interrupted re-index with the same model. Returns the checkpoint
+    /// and whether it was resumed.
+    pub fn reindex_start(&mut self) -> McpResult<(ReindexCheckpoint, bool)> {
+        if !self.engine.has_model() {
+            return Err(McpError::InvalidParams(
+                "No CLIP model is loaded; re-indexing would replace every embedding with zeros"
+                    .to_string(),
+            ));
+        }
+        let resumed = self
+            .store
+            .start_reindex(self.model_path.clone(), now_secs());
+        self.mark_dirty();
+        self.save()?;
+        let checkpoint = self.store.reindex.clone().expect("re-index just started");
+        Ok((checkpoint, resumed))
+    }
+
+    /// Re-embed the next `batch` captures of the re-index in progress and
+    /// checkpoint it. Returns the checkpoint and whether the re-index is
+    /// complete; a complete one is cleared from the store and saved.
+    pub fn reindex_step(&mut self, batch: usize) -> McpResult<(ReindexCheckpoint, bool)> {
+        let ids = self.store.reindex_batch(batch.max(1));
+        let Some(&last) = ids.last() else {
+            let checkpoint = self
+                .store
+                .finish_reindex()
+                .ok_or_else(|| McpError::InvalidParams("No re-index is in progress".to_string()))?;
+            self.mark_dirty();
+            self.save()?;
+            tracing::info!(
+                "Re-index complete: {} re-embedded, {} failed",
+                checkpoint.done,
+                checkpoint.failed
+            );
+            return Ok((checkpoint, true));
+        };
+
+        let (mut done, mut failed) = (0, 0);
+        for id in ids {
+            let embedded = self.source_image(id).and_then(|(img, _)| {
+                self.engine
+                    .embed(&img)
+                    .map_err(|e| McpError::VisionError(format!("Embedding failed: {e}")))
+            });
+            match embedded {
+                Ok(embedding) => {
+                    self.store.embedding_dim = embedding.len() as u32;
+                    if let Some(obs) = self.store.get_mut(id) {
+                        obs.embedding = embedding;
+                    }
+                    done += 1;
+                }
+                Err(e) => {
+                    tracing::warn!("Re-index of capture {id} failed: {e}");
+                    failed += 1;
+                }
+            }
+        }
+        self.store.advance_reindex(last, done, failed, now_secs());
+        self.mark_dirty();
+        self.maybe_auto_save()?;
+        let checkpoint = self.store.reindex.clone().expect("re-index in progress");
+        Ok((checkpoint, false))
+    }
+
+    /// The background re-index of this session, if one was started.
+    pub fn reindex_job(&self) -> Option<&ReindexJob> {
+        self.reindex_job.as_ref()
+    }
+
+    pub fn reindex_job_mut(&mut self) -> Option<&mut ReindexJob> {
+        self.reindex_job.as_mut()
+    }
+
+    pub fn set_reindex_job(&mut self, job: ReindexJob) {
+        self.reindex_job = Some(job);
+    }
+
+    /// Results of calls made with an idempotency key.
+    pub fn idempotency(&mut self) -> &mut IdempotencyStore {
+        &mut self.idempotency
+    }
+
+    /// Store text read from a capture. `result` has boxes in pixels of a
+    /// `width` x `height` image; they are scaled to the original image.
+    /// Returns the stored result.
+    pub fn store_ocr(
+        &mut self,
+        capture_id: u64,
+        mut result: OcrResult,
+        width: u32,
+        height: u32,
+    ) -> McpResult<OcrResult> {
+        let obs = self
+            .store
+            .get_mut(capture_id)
+            .ok_or(McpError::CaptureNotFound(capture_id))?;
+        result.scale(
+            obs.metadata.original_width as f32 / width.max(1) as f32,
+            obs.metadata.original_height as f32 / height.max(1) as f32,
+        );
+        obs.ocr = Some(result.clone());
+        self.mark_dirty();
+        Ok(result)
+    }
+
+    /// Read the text of an image for a pipeline's OCR stage with the default
+    /// backend, logging failures: missing text never fails a capture.
+    fn recognize(&mut self, img: &image::DynamicImage, language: &str) -> Option<OcrResult> {
+        let recognized = self.ocr_engine(None).and_then(|(_, engine)| {
+            let mut engine = engine
+                .lock()
+                .map_err(|_| McpError::InternalError("OCR engine poisoned".to_string()))?;
+            Ok(engine.recognize(img, language)?)
+        });
+        match recognized {
+            Ok(mut result) => {
+                if result.language.is_none() {
+                    result.language = detect_language(&result.text, language);
+                }
+                Some(result)
+            }
+            Err(e) => {
+                tracing::warn!("Pipeline OCR failed: {e}");
+                None
+            }
+        }
+    }
+
+    /// Whether captures can be captioned (feature `caption` with a model).
+    pub fn has_captioner(&self) -> bool {
+        #[cfg(feature = "caption")]
+        return self.captioner.has_model();
+        #[cfg(not(feature = "caption"))]
+        false
+    }
+
+    /// Caption an image, logging failures: a missing caption never fails a
+    /// capture.
+    #[cfg_attr(not(feature = "caption"), allow(unused_variables))]
+    fn caption_image(&mut self, img: &image::DynamicImage) -> Option<String> {
+        #[cfg(feature = "caption")]
+        match self.captioner.caption(img) {
+            Ok(caption) => return caption,
+            Err(e) => tracing::warn!("Captioning failed: {e}"),
+        }
+        None
+    }
+
+    /// Whether captures get UI element detection (feature `ui-detect` with
+    /// a model).
+    pub fn has_detector(&self) -> bool {
+        #[cfg(feature = "ui-detect")]
+        return self.detector.has_model();
+        #[cfg(not(feature = "ui-detect"))]
+        false
+    }
+
+    /// Detect UI elements in an image and embed each box. Failures are
+    /// logged: a capture is stored without elements rather than lost.
+    #[cfg(feature = "ui-detect")]
+    fn detect_elements(&mut self, img: &image::DynamicImage) -> Vec<agentic_vision::UiElement> {
+        use agentic_vision::elements::{crop, dominant_color};
+
+        let detections = match self.detector.detect(img) {
+            Ok(detections) => detections,
+            Err(e) => {
+                tracing::warn!("UI detection failed: {e}");
+                return Vec::new();
+            }
+        };
+        let embed_boxes = self.engine.has_model();
+        detections
+            .into_iter()
+            .map(|det| {
+                let region = crop(img, &det.bbox);
+                let embedding = if embed_boxes {
+                    self.engine.embed(&region).unwrap_or_default()
+                } else {
+                    Vec::new()
+                };
+                det.into_element(dominant_color(&region), embedding)
+            })
+            .collect()
+    }
+
+    #[cfg(not(feature = "ui-detect"))]
+    fn detect_elements(&mut self, _img: &image::DynamicImage) -> Vec<agentic_vision::UiElement> {
+        Vec::new()
+    }
+
+    /// Caption those of the given captures that have no caption yet, from
+    /// their thumbnails, at most `MAX_LAZY_CAPTIONS` per call. Returns how
+    /// many were captioned. On cancellation, keeps the captions made so far.
+    pub fn ensure_captions(&mut self, ids: &[u64], cancel: &CancellationToken) -> McpResult<usize> {
+        if !self.has_captioner() {
+            return Ok(0);
+        }
+        let pending: Vec<u64> = ids
+            .iter()
+            .copied()
+            .filter(|&id| {
+                self.store
+                    .get(id)
+                    .is_some_and(|o| o.metadata.caption.is_none())
+            })
+            .take(MAX_LAZY_CAPTIONS)
+            .collect();
+
+        let mut captioned = 0;
+        for id in pending {
+            if cancel.is_cancelled() {
+                break;
+            }
+            let Some(obs) = self.store.get(id) else {
+                continue;
+            };
+            let Ok(thumb) = image::load_from_memory(&obs.thumbnail) else {
+                continue;
+            };
+            if let Some(caption) = self.caption_image(&thumb) {
+                if let Some(obs) = self.store.get_mut(id) {
+                    obs.metadata.caption = Some(caption);
+                    captioned += 1;
+                }
+            }
+        }
+        if captioned > 0 {
+            self.mark_dirty();
+            self.maybe_auto_save()?;
+        }
+        check_cancelled(cancel)?;
+        Ok(captioned)
+    }
+
     /// Compare two captures by cosine similarity.
     pub fn compare(&self, id_a: u64, id_b: u64) -> McpResult<f32> {
         let a = self
@@ -227,38 +685,77 @@ impl VisionSessionManager {
         Ok(cosine_similarity(&a.embedding, &b.embedding))
     }
 
-    /// Find similar captures.
+    /// Find similar captures among those `filter` admits. `layout_weight`
+    /// in `[0, 1]` blends layout and colour fingerprint similarity into the
+    /// embedding similarity; `diversity` in `[0, 1]` trades similarity for
+    /// results that differ from each other.
     pub fn find_similar(
         &self,
         capture_id: u64,
+        filter: &SimilarityFilter,
         top_k: usize,
         min_similarity: f32,
+        layout_weight: f32,
+        diversity: f32,
     ) -> McpResult<Vec<SimilarityMatch>> {
         let obs = self
             .store
             .get(capture_id)
             .ok_or(McpError::CaptureNotFound(capture_id))?;
 
-        let mut matches = find_similar(
+        let mut matches = find_similar_filtered(
             &obs.embedding,
+            &obs.fingerprint,
             &self.store.observations,
-            top_k + 1,
+            filter,
+            pool_size(top_k, diversity) + 1,
             min_similarity,
+            layout_weight,
         );
         // Remove self from results
         matches.retain(|m| m.id != capture_id);
-        matches.truncate(top_k);
-        Ok(matches)
+        Ok(self.diversify(matches, top_k, diversity, layout_weight))
     }
 
-    /// Find similar by raw embedding.
+    /// Find similar by raw embedding, among the captures `filter` admits.
     pub fn find_similar_by_embedding(
         &self,
         embedding: &[f32],
+        filter: &SimilarityFilter,
         top_k: usize,
         min_similarity: f32,
+        diversity: f32,
+    ) -> Vec<SimilarityMatch> {
+        let matches = find_similar_filtered(
+            embedding,
+            &[],
+            &self.store.observations,
+            filter,
+            pool_size(top_k, diversity),
+            min_similarity,
+            0.0,
+        );
+        self.diversify(matches, top_k, diversity, 0.0)
+    }
+
+    fn diversify(
+        &self,
+        mut matches: Vec<SimilarityMatch>,
+        top_k: usize,
+        diversity: f32,
+        layout_weight: f32,
     ) -> Vec<SimilarityMatch> {
-        find_similar(embedding, &self.store.observations, top_k, min_similarity)
+        if diversity <= 0.0 {
+            matches.truncate(top_k);
+            return matches;
+        }
+        rerank_diverse(
+            &matches,
+            &self.store.observations,
+            top_k,
+            diversity,
+            layout_weight,
+        )
     }
 
     /// Compute visual diff between two captures.
@@ -281,6 +778,48 @@ impl VisionSessionManager {
             .map_err(|e| McpError::VisionError(format!("Diff failed: {e}")))
     }
 
+    /// Compute visual diff between a stored capture and an image that is
+    /// not stored. The image gets the id 0 in the diff.
+    pub fn diff_image(&self, capture_id: u64, img: &image::DynamicImage) -> McpResult<VisualDiff> {
+        let stored = self
+            .store
+            .get(capture_id)
+            .ok_or(McpError::CaptureNotFound(capture_id))?;
+
+        let img_a = image::load_from_memory(&stored.thumbnail)
+            .map_err(|e| McpError::VisionError(format!("Failed to load thumbnail: {e}")))?;
+        // Compare thumbnails, as `diff` does, so both sides are scaled alike.
+        let img_b = image::load_from_memory(&generate_thumbnail(img))
+            .map_err(|e| McpError::VisionError(format!("Failed to load thumbnail: {e}")))?;
+
+        compute_diff(capture_id, 0, &img_a, &img_b)
+            .map_err(|e| McpError::VisionError(format!("Diff failed: {e}")))
+    }
+
+    /// The web page a capture shows: its `url:` label, or else its
+    /// description when that is an http(s) URL.
+    pub fn capture_url(&self, capture_id: u64) -> McpResult<Option<String>> {
+        let obs = self
+            .store
+            .get(capture_id)
+            .ok_or(McpError::CaptureNotFound(capture_id))?;
+
+        let from_label = obs
+            .metadata
+            .labels
+            .iter()
+            .find_map(|l| l.strip_prefix("url:"))
+            .map(|u| u.trim().to_string());
+        let from_description = obs
+            .metadata
+            .description
+            .as_deref()
+            .map(str::trim)
+            .filter(|d| d.starts_with("http://") || d.starts_with("https://"))
+            .map(String::from);
+        Ok(from_label.or(from_description))
+    }
+
     /// Link a capture to a memory node.
     pub fn link(&mut self, capture_id: u64, memory_node_id: u64) -> McpResult<()> {
         let obs = self
@@ -288,18 +827,162 @@ impl VisionSessionManager {
             .get_mut(capture_id)
             .ok_or(McpError::CaptureNotFound(capture_id))?;
         obs.memory_link = Some(memory_node_id);
-        self.dirty = true;
+        self.mark_dirty();
+        Ok(())
+    }
+
+    /// Canonical forms of `labels`, with aliases resolved and empty labels
+    /// and duplicates dropped.
+    pub fn resolve_labels(&self, labels: &[String]) -> Vec<String> {
+        let mut resolved: Vec<String> = Vec::new();
+        for label in labels {
+            let label = self.store.resolve_label(label);
+            if !label.is_empty() && !resolved.contains(&label) {
+                resolved.push(label);
+            }
+        }
+        resolved
+    }
+
+    /// Rename a label branch. Returns the number of captures relabeled.
+    pub fn rename_label(&mut self, from: &str, to: &str) -> McpResult<usize> {
+        let changed = self.store.rename_label(from, to)?;
+        self.mark_dirty();
+        self.maybe_auto_save()?;
+        Ok(changed)
+    }
+
+    /// Merge a label branch into another. Returns the number of captures
+    /// relabeled.
+    pub fn merge_labels(&mut self, from: &str, into: &str) -> McpResult<usize> {
+        let changed = self.store.merge_labels(from, into)?;
+        self.mark_dirty();
+        self.maybe_auto_save()?;
+        Ok(changed)
+    }
+
+    /// Make `alias` resolve to the label `target`.
+    pub fn add_label_alias(&mut self, alias: &str, target: &str) -> McpResult<()> {
+        self.store.add_label_alias(alias, target)?;
+        self.mark_dirty();
+        self.maybe_auto_save()?;
         Ok(())
     }
 
+    /// Take a named snapshot of visual memory.
+    pub fn snapshot_create(&mut self, name: &str) -> McpResult<Snapshot> {
+        let snapshot = self.store.create_snapshot(name)?.clone();
+        self.mark_dirty();
+        self.maybe_auto_save()?;
+        Ok(snapshot)
+    }
+
+    /// Snapshots that can be restored, oldest first.
+    pub fn snapshots(&self) -> &[Snapshot] {
+        &self.store.snapshots
+    }
+
+    /// Roll visual memory back to snapshot `name`. Returns the number of
+    /// captures removed.
+    pub fn snapshot_restore(&mut self, name: &str) -> McpResult<usize> {
+        let removed = self.store.restore_snapshot(name)?;
+        self.mark_dirty();
+        // Persist the rollback at once, so a crash cannot bring captures back.
+        self.save()?;
+        tracing::info!("Restored snapshot '{name}', removing {removed} capture(s)");
+        Ok(removed)
+    }
+
+    /// Find duplicate captures and, with `merge`, fold each group into its
+    /// oldest capture. Returns the report and the number of captures removed.
+    pub fn dedup(
+        &mut self,
+        options: &DedupOptions,
+        merge: bool,
+    ) -> McpResult<(DedupReport, usize)> {
+        let report = self.store.dedup_report(options);
+        if !merge || report.groups.is_empty() {
+            return Ok((report, 0));
+        }
+        let removed = self.store.merge_duplicates(&report.groups, now_secs());
+        self.mark_dirty();
+        // Persist at once, like a snapshot restore.
+        self.save()?;
+        tracing::info!("Merged {removed} duplicate capture(s)");
+        Ok((report, removed))
+    }
+
+    /// Fit a calibration of similarity scores to the store's labeled
+    /// captures and, with `keep`, store it so that results report calibrated
+    /// confidence.
+    pub fn calibrate(&mut self, max_pairs: usize, keep: bool) -> McpResult<Calibration> {
+        let calibration = self.store.fit_calibration(max_pairs, now_secs())?;
+        if keep {
+            self.store.calibration = Some(calibration.clone());
+            self.mark_dirty();
+            self.save()?;
+            tracing::info!(
+                "Calibrated similarity on {} pair(s), accuracy {:.3}",
+                calibration.pairs,
+                calibration.accuracy
+            );
+        }
+        Ok(calibration)
+    }
+
+    /// Calibrated probability that two captures with similarity `score` are
+    /// the same, if the store has been calibrated.
+    pub fn confidence(&self, score: f32) -> Option<f32> {
+        self.store
+            .calibration
+            .as_ref()
+            .map(|c| c.probability(score))
+    }
+
+    /// Content addresses of all captures, for syncing with another store.
+    pub fn sync_manifest(&self) -> Vec<ManifestEntry> {
+        self.store.manifest()
+    }
+
+    /// Captures with the given content addresses, for another store to merge.
+    pub fn sync_export(&self, hashes: &HashSet<String>) -> Vec<VisualObservation> {
+        self.store.by_hashes(hashes)
+    }
+
+    /// Merge captures from another store, skipping ones already present.
+    /// Returns how many were added.
+    pub fn sync_import(&mut self, mut observations: Vec<VisualObservation>) -> McpResult<usize> {
+        let now = now_secs();
+        for obs in &mut observations {
+            let detail = format!("synced; capture {} in the other store", obs.id);
+            obs.provenance
+                .get_or_insert_with(|| Provenance {
+                    source_type: obs.source.kind().to_string(),
+                    ..Provenance::default()
+                })
+                .record("import", Some(detail), now);
+        }
+        let added = self.store.merge(observations).len();
+        if added > 0 {
+            self.mark_dirty();
+            self.save()?;
+            tracing::info!("Merged {added} synced capture(s)");
+        }
+        Ok(added)
+    }
+
     /// Save to file.
     pub fn save(&mut self) -> McpResult<()> {
-        if !self.dirty {
+        if !self.dirty || self.erased {
             return Ok(());
         }
 
-        AvisWriter::write_to_file(&self.store, &self.file_path)
+        // Write beside the file and rename over it, so an interrupted save
+        // (such as a killed re-index) leaves the previous file intact.
+        let staging = crate::migrate::staging_path(&self.file_path);
+        AvisWriter::write_to_file(&self.store, &staging)
             .map_err(|e| McpError::VisionError(format!("Failed to write vision file: {e}")))?;
+        std::fs::rename(&staging, &self.file_path)?;
 
         self.dirty = false;
         self.last_save = Instant::now();
@@ -317,6 +1000,112 @@ impl VisionSessionManager {
     pub fn file_path(&self) -> &PathBuf {
         &self.file_path
     }
+
+    /// Size of the vision file as last saved; 0 before the first save.
+    pub fn store_bytes(&self) -> u64 {
+        std::fs::metadata(&self.file_path)
+            .map(|m| m.len())
+            .unwrap_or(0)
+    }
+
+    /// The store serialized as a `.avis` file, unsaved changes included.
+    pub fn snapshot_bytes(&self) -> McpResult<Vec<u8>> {
+        let mut bytes = Vec::new();
+        AvisWriter::write_to(&self.store, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Whether the store has been erased.
+    pub fn is_erased(&self) -> bool {
+        self.erased
+    }
+
+    /// Irreversibly delete this store: empty it in memory, overwrite its
+    /// file and any staging file left by an interrupted save with zeros,
+    /// and remove them. Nothing is saved afterwards, so a request still
+    /// holding the session cannot write the data back.
+    ///
+    /// Overwriting is best effort: copy-on-write filesystems and SSDs may
+    /// keep the old blocks until they are reused.
+    pub fn erase(&mut self) -> McpResult<Erasure> {
+        if let Some(job) = &self.reindex_job {
+            job.cancel();
+        }
+        let mut erasure = Erasure {
+            captures: self.store.count(),
+            sessions: self.store.session_count,
+            ..Erasure::default()
+        };
+        self.store = VisualMemoryStore::new(EMBEDDING_DIM);
+        self.erased = true;
+        self.dirty = false;
+        self.generation += 1;
+
+        (erasure.files, erasure.bytes_overwritten) = erase_files(&self.file_path)?;
+        tracing::info!(
+            "Erased vision store {} ({} capture(s))",
+            self.file_path.display(),
+            erasure.captures
+        );
+        Ok(erasure)
+    }
+}
+
+/// What erasing a store removed.
+#[derive(Debug, Clone, Default)]
+pub struct Erasure {
+    pub captures: usize,
+    pub sessions: u32,
+    pub files: Vec<PathBuf>,
+    pub bytes_overwritten: u64,
+}
+
+/// Candidates to rank for `top_k` results: more when reranking for
+/// diversity, so there are distinct ones to pick.
+fn pool_size(top_k: usize, diversity: f32) -> usize {
+    if diversity > 0.0 {
+        top_k.saturating_mul(DIVERSITY_POOL)
+    } else {
+        top_k
+    }
+}
+
+/// File in a multi-tenant data directory that erasures are appended to, one
+/// JSON record per line.
+pub const ERASURE_AUDIT_FILE: &str = "erasure-audit.jsonl";
+
+/// Shred a vision file and the staging file an interrupted save may have
+/// left beside it. Returns the files removed and the bytes overwritten.
+pub fn erase_files(path: &std::path::Path) -> McpResult<(Vec<PathBuf>, u64)> {
+    let mut files = Vec::new();
+    let mut bytes = 0;
+    for path in [path.to_path_buf(), crate::migrate::staging_path(path)] {
+        if path.exists() {
+            bytes += shred(&path)?;
+            files.push(path);
+        }
+    }
+    Ok((files, bytes))
+}
+
+/// Overwrite a file with zeros, flush it to disk and remove it. Returns the
+/// number of bytes overwritten.
+fn shred(path: &std::path::Path) -> std::io::Result<u64> {
+    use std::io::Write;
+
+    let len = std::fs::metadata(path)?.len();
+    let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+    let zeros = vec![0u8; 64 * 1024];
+    let mut left = len;
+    while left > 0 {
+        let n = left.min(zeros.len() as u64) as usize;
+        file.write_all(&zeros[..n])?;
+        left -= n as u64;
+    }
+    file.sync_all()?;
+    drop(file);
+    std::fs::remove_file(path)?;
+    Ok(len)
 }
 
 impl Drop for VisionSessionManager {
@@ -336,4 +1125,24 @@ pub struct CaptureResult {
     pub width: u32,
     pub height: u32,
     pub embedding_dims: u32,
+    pub caption: Option<String>,
+    /// Pipeline stages that changed the image, as `step (detail)`.
+    pub processed: Vec<String>,
+    /// Text read by a pipeline's OCR stage.
+    pub ocr_text: Option<String>,
+}
+
+fn check_cancelled(cancel: &CancellationToken) -> McpResult<()> {
+    if cancel.is_cancelled() {
+        Err(McpError::RequestCancelled)
+    } else {
+        Ok(())
+    }
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
 }
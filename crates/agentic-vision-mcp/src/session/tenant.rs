@@ -1,13 +1,35 @@
-//! Multi-tenant session registry — lazy-loads per-user vision files.
+//! Multi-tenant session registry — lazy-loads per-user vision files, and
+//! exports or erases a tenant's data on request.
 
 use std::collections::HashMap;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use serde::Serialize;
+use serde_json::{json, Value};
 use tokio::sync::Mutex;
 
+use super::manager::erase_files;
+pub use super::manager::ERASURE_AUDIT_FILE;
 use super::VisionSessionManager;
-use crate::types::McpResult;
+use crate::types::{McpError, McpResult};
+
+/// Audit record of a tenant's erased data. It names what was removed, never
+/// what it contained.
+#[derive(Debug, Clone, Serialize)]
+pub struct ErasureRecord {
+    pub user_id: String,
+    /// RFC 3339 time of the erasure.
+    pub erased_at: String,
+    /// Captures and sessions erased; absent when the store could not be
+    /// read and its file was removed unread.
+    pub captures: Option<usize>,
+    pub sessions: Option<u32>,
+    /// Names of the files overwritten and removed.
+    pub files: Vec<String>,
+    pub bytes_overwritten: u64,
+}
 
 /// Registry of per-user sessions for multi-tenant mode.
 pub struct VisionTenantRegistry {
@@ -33,6 +55,7 @@ impl VisionTenantRegistry {
         if let Some(session) = self.sessions.get(user_id) {
             return Ok(session.clone());
         }
+        let vision_path = self.vision_path(user_id)?;
 
         // Ensure data directory exists
         std::fs::create_dir_all(&self.data_dir).map_err(|e| {
@@ -42,7 +65,6 @@ impl VisionTenantRegistry {
             ))
         })?;
 
-        let vision_path = self.data_dir.join(format!("{user_id}.avis"));
         let path_str = vision_path.display().to_string();
 
         tracing::info!("Opening vision store for user '{user_id}': {path_str}");
@@ -58,4 +80,147 @@ impl VisionTenantRegistry {
     pub fn count(&self) -> usize {
         self.sessions.len()
     }
+
+    /// The data directory holding `{user_id}.avis` files.
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    /// Loaded tenant sessions, by user ID.
+    pub fn loaded(&self) -> Vec<(String, Arc<Mutex<VisionSessionManager>>)> {
+        self.sessions
+            .iter()
+            .map(|(user_id, session)| (user_id.clone(), session.clone()))
+            .collect()
+    }
+
+    /// `{data_dir}/{user_id}.avis`, for user IDs that name a file in the
+    /// data directory and nothing outside it.
+    fn vision_path(&self, user_id: &str) -> McpResult<PathBuf> {
+        if user_id.is_empty() || user_id.starts_with('.') || user_id.contains(['/', '\\', '\0']) {
+            return Err(McpError::InvalidParams(format!(
+                "Invalid user ID '{user_id}'"
+            )));
+        }
+        Ok(self.data_dir.join(format!("{user_id}.avis")))
+    }
+
+    /// A zip archive of everything stored for `user_id`: `metadata.json`
+    /// with the captures and the store's sessions, snapshots and label
+    /// aliases, and each capture's image as `images/{id}.jpg`.
+    pub async fn export(&mut self, user_id: &str) -> McpResult<Vec<u8>> {
+        let session = self.get_or_create(user_id)?;
+        let session = session.lock().await;
+        let store = session.store();
+
+        let captures: Vec<Value> = store
+            .observations
+            .iter()
+            .map(|obs| {
+                let mut capture = serde_json::to_value(obs)?;
+                if let Value::Object(fields) = &mut capture {
+                    fields.remove("thumbnail");
+                    fields.insert("image".to_string(), json!(image_name(obs.id)));
+                }
+                Ok(capture)
+            })
+            .collect::<serde_json::Result<_>>()?;
+        let metadata = json!({
+            "user_id": user_id,
+            "exported_at": chrono::Utc::now().to_rfc3339(),
+            "created_at": store.created_at,
+            "updated_at": store.updated_at,
+            "embedding_dim": store.embedding_dim,
+            "captures": captures,
+            "sessions": store.sessions,
+            "snapshots": store.snapshots,
+            "label_aliases": store.label_aliases,
+        });
+
+        let zip_error = |e: zip::result::ZipError| {
+            McpError::InternalError(format!("Failed to build export archive: {e}"))
+        };
+        let mut archive = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let deflated = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        archive
+            .start_file("metadata.json", deflated)
+            .map_err(zip_error)?;
+        archive.write_all(&serde_json::to_vec_pretty(&metadata)?)?;
+        // Thumbnails are JPEG already; compressing them again gains nothing.
+        let stored = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        for obs in &store.observations {
+            archive
+                .start_file(image_name(obs.id), stored)
+                .map_err(zip_error)?;
+            archive.write_all(&obs.thumbnail)?;
+        }
+        let bytes = archive.finish().map_err(zip_error)?.into_inner();
+        tracing::info!("Exported {} capture(s) for user '{user_id}'", store.count());
+        Ok(bytes)
+    }
+
+    /// Irreversibly erase everything stored for `user_id` and append an
+    /// audit record of the erasure to [`ERASURE_AUDIT_FILE`]. A later
+    /// request for the user starts with an empty store.
+    pub async fn erase(&mut self, user_id: &str) -> McpResult<ErasureRecord> {
+        let vision_path = self.vision_path(user_id)?;
+        let session = match self.sessions.remove(user_id) {
+            Some(session) => Some(session),
+            None if vision_path.exists() => {
+                match VisionSessionManager::open(&vision_path.display().to_string(), None) {
+                    Ok(session) => Some(Arc::new(Mutex::new(session))),
+                    Err(e) => {
+                        tracing::warn!("Erasing unreadable store for user '{user_id}': {e}");
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+
+        let (captures, sessions, files, bytes_overwritten) = match session {
+            Some(session) => {
+                let erasure = session.lock().await.erase()?;
+                (
+                    Some(erasure.captures),
+                    Some(erasure.sessions),
+                    erasure.files,
+                    erasure.bytes_overwritten,
+                )
+            }
+            None => {
+                let (files, bytes) = erase_files(&vision_path)?;
+                (None, None, files, bytes)
+            }
+        };
+
+        let record = ErasureRecord {
+            user_id: user_id.to_string(),
+            erased_at: chrono::Utc::now().to_rfc3339(),
+            captures,
+            sessions,
+            files: files
+                .iter()
+                .filter_map(|f| f.file_name())
+                .map(|f| f.to_string_lossy().into_owned())
+                .collect(),
+            bytes_overwritten,
+        };
+        std::fs::create_dir_all(&self.data_dir)?;
+        let mut audit = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.data_dir.join(ERASURE_AUDIT_FILE))?;
+        writeln!(audit, "{}", serde_json::to_string(&record)?)?;
+        audit.sync_all()?;
+        tracing::info!("Erased all data of user '{user_id}'");
+        Ok(record)
+    }
+}
+
+/// Path of a capture's image in an export archive.
+fn image_name(id: u64) -> String {
+    format!("images/{id}.jpg")
 }
@@ -0,0 +1,147 @@
+//! Differential sync of a vision store with a remote `serve-http` server.
+//!
+//! Both sides describe their captures by content address (the SHA-256 of the
+//! thumbnail). Only captures the other side lacks cross the network, and
+//! merging skips any whose image is already stored, so repeated syncs
+//! between a laptop, a desktop and a shared server never duplicate captures.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use agentic_vision::{ManifestEntry, VisualObservation};
+
+use crate::session::VisionSessionManager;
+use crate::types::{McpError, McpResult};
+
+/// Captures sent per push request.
+const PUSH_BATCH: usize = 32;
+
+/// Body of `POST /sync/pull`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PullRequest {
+    pub hashes: Vec<String>,
+}
+
+/// Body of `POST /sync/push`, and of the `/sync/pull` response.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncCaptures {
+    pub captures: Vec<VisualObservation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ManifestResponse {
+    captures: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PushResponse {
+    added: usize,
+}
+
+/// Outcome of a sync.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SyncReport {
+    /// Captures fetched from the remote and added locally.
+    pub pulled: usize,
+    /// Captures the remote added from this store.
+    pub pushed: usize,
+}
+
+/// Client for the `/sync` endpoints of a remote server.
+pub struct SyncClient {
+    http: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+    user_id: Option<String>,
+}
+
+impl SyncClient {
+    /// Create a client for the server at `base_url` (e.g. `http://host:3100`).
+    /// `token` is its bearer token; `user_id` picks the store on a
+    /// multi-tenant server.
+    pub fn new(base_url: &str, token: Option<String>, user_id: Option<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token,
+            user_id,
+        }
+    }
+
+    /// Exchange missing captures with the remote store in both directions.
+    pub async fn sync(&self, session: &Arc<Mutex<VisionSessionManager>>) -> McpResult<SyncReport> {
+        let remote: HashSet<String> = self
+            .send::<ManifestResponse>(self.http.get(self.url("manifest")))
+            .await?
+            .captures
+            .into_iter()
+            .map(|entry| entry.hash)
+            .collect();
+        let local: HashSet<String> = session
+            .lock()
+            .await
+            .sync_manifest()
+            .into_iter()
+            .map(|entry| entry.hash)
+            .collect();
+
+        let wanted: Vec<String> = remote.difference(&local).cloned().collect();
+        let mut report = SyncReport::default();
+        if !wanted.is_empty() {
+            let pulled: SyncCaptures = self
+                .send(
+                    self.http
+                        .post(self.url("pull"))
+                        .json(&PullRequest { hashes: wanted }),
+                )
+                .await?;
+            report.pulled = session.lock().await.sync_import(pulled.captures)?;
+        }
+
+        let missing = session.lock().await.store().missing_from(&remote);
+        for batch in missing.chunks(PUSH_BATCH) {
+            let pushed: PushResponse = self
+                .send(self.http.post(self.url("push")).json(&SyncCaptures {
+                    captures: batch.to_vec(),
+                }))
+                .await?;
+            report.pushed += pushed.added;
+        }
+
+        Ok(report)
+    }
+
+    fn url(&self, endpoint: &str) -> String {
+        format!("{}/sync/{endpoint}", self.base_url)
+    }
+
+    async fn send<T: serde::de::DeserializeOwned>(
+        &self,
+        mut request: reqwest::RequestBuilder,
+    ) -> McpResult<T> {
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(user_id) = &self.user_id {
+            request = request.header("x-user-id", user_id);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| McpError::Transport(format!("Sync request failed: {e}")))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(McpError::Transport(format!(
+                "Sync server answered {status}: {body}"
+            )));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| McpError::Transport(format!("Invalid sync response: {e}")))
+    }
+}
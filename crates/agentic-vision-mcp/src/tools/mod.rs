@@ -5,11 +5,18 @@ pub mod session_end;
 pub mod session_start;
 pub mod vision_capture;
 pub mod vision_compare;
+pub mod vision_compare_to_live;
+pub mod vision_dedup_report;
 pub mod vision_diff;
+pub mod vision_find_element;
+pub mod vision_find_text;
+pub mod vision_labels;
 pub mod vision_link;
 pub mod vision_ocr;
 pub mod vision_query;
+pub mod vision_reindex;
 pub mod vision_similar;
+pub mod vision_snapshot;
 pub mod vision_track;
 
-pub use registry::ToolRegistry;
+pub use registry::{CallContext, ToolRegistry};
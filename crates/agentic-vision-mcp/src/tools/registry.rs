@@ -2,22 +2,50 @@
 
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
 
-use serde_json::Value;
+use serde_json::{json, Value};
 
+use agentic_vision::Provenance;
+
+use crate::protocol::idempotency::{IDEMPOTENT_TOOLS, KEY_ARG, MAX_KEY_LEN};
 use crate::session::VisionSessionManager;
 use crate::types::{McpError, McpResult, ToolCallResult, ToolDefinition};
 
 use super::{
-    session_end, session_start, vision_capture, vision_compare, vision_diff, vision_link,
-    vision_ocr, vision_query, vision_similar, vision_track,
+    session_end, session_start, vision_capture, vision_compare, vision_compare_to_live,
+    vision_dedup_report, vision_diff, vision_find_element, vision_find_text, vision_labels,
+    vision_link, vision_ocr, vision_query, vision_reindex, vision_similar, vision_snapshot,
+    vision_track,
 };
 
+/// Who made a tool call, recorded in the provenance of what it stores.
+#[derive(Debug, Clone, Default)]
+pub struct CallContext {
+    /// JSON-RPC ID of the call.
+    pub request_id: Option<String>,
+    /// `name/version` of the client, from `initialize`.
+    pub client: Option<String>,
+}
+
+impl CallContext {
+    /// Provenance of a capture stored by `tool` in this call; the session
+    /// fills in the rest.
+    pub fn provenance(&self, tool: &str) -> Provenance {
+        Provenance {
+            tool: Some(tool.to_string()),
+            request_id: self.request_id.clone(),
+            client: self.client.clone(),
+            ..Provenance::default()
+        }
+    }
+}
+
 pub struct ToolRegistry;
 
 impl ToolRegistry {
     pub fn list_tools() -> Vec<ToolDefinition> {
-        vec![
+        let mut tools = vec![
             vision_capture::definition(),
             vision_compare::definition(),
             vision_query::definition(),
@@ -26,27 +54,57 @@ impl ToolRegistry {
             vision_track::definition(),
             vision_diff::definition(),
             vision_link::definition(),
+            vision_compare_to_live::definition(),
+            vision_snapshot::definition(),
+            vision_labels::definition(),
+            vision_find_element::definition(),
+            vision_find_text::definition(),
+            vision_dedup_report::definition(),
+            vision_reindex::definition(),
             session_start::definition(),
             session_end::definition(),
-        ]
+        ];
+        for tool in &mut tools {
+            if IDEMPOTENT_TOOLS.contains(&tool.name.as_str()) {
+                tool.input_schema["properties"][KEY_ARG] = json!({
+                    "type": "string",
+                    "maxLength": MAX_KEY_LEN,
+                    "description": "Key of this call. A retry with the same key and arguments returns the first result instead of running again"
+                });
+            }
+        }
+        tools
     }
 
+    /// Call a tool. Long-running tools check `cancel` between steps and
+    /// stop with `McpError::RequestCancelled`.
     pub async fn call(
         name: &str,
         arguments: Option<Value>,
         session: &Arc<Mutex<VisionSessionManager>>,
+        cancel: &CancellationToken,
+        context: &CallContext,
     ) -> McpResult<ToolCallResult> {
         let args = arguments.unwrap_or(Value::Object(serde_json::Map::new()));
 
         match name {
-            "vision_capture" => vision_capture::execute(args, session).await,
+            "vision_capture" => vision_capture::execute(args, session, cancel, context).await,
             "vision_compare" => vision_compare::execute(args, session).await,
-            "vision_query" => vision_query::execute(args, session).await,
+            "vision_query" => vision_query::execute(args, session, cancel).await,
             "vision_ocr" => vision_ocr::execute(args, session).await,
             "vision_similar" => vision_similar::execute(args, session).await,
             "vision_track" => vision_track::execute(args, session).await,
             "vision_diff" => vision_diff::execute(args, session).await,
             "vision_link" => vision_link::execute(args, session).await,
+            "vision_compare_to_live" => {
+                vision_compare_to_live::execute(args, session, cancel, context).await
+            }
+            "vision_snapshot" => vision_snapshot::execute(args, session).await,
+            "vision_labels" => vision_labels::execute(args, session).await,
+            "vision_find_element" => vision_find_element::execute(args, session).await,
+            "vision_find_text" => vision_find_text::execute(args, session).await,
+            "vision_dedup_report" => vision_dedup_report::execute(args, session).await,
+            "vision_reindex" => vision_reindex::execute(args, session).await,
             "session_start" => session_start::execute(args, session).await,
             "session_end" => session_end::execute(args, session).await,
             _ => Err(McpError::ToolNotFound(name.to_string())),
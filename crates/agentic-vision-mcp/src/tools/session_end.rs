@@ -26,9 +26,17 @@ pub async fn execute(
     let mut session = session.lock().await;
     let session_id = session.end_session()?;
     let count = session.store().count();
+    let summary = session
+        .store()
+        .session_summaries()
+        .into_iter()
+        .find(|s| s.info.id == session_id);
 
     Ok(ToolCallResult::json(&json!({
         "session_id": session_id,
+        "name": summary.as_ref().and_then(|s| s.info.name.as_deref()),
+        "session_captures": summary.as_ref().map_or(0, |s| s.capture_count),
+        "duration_secs": summary.as_ref().and_then(|s| s.duration_secs),
         "total_captures": count,
         "status": "ended"
     })))
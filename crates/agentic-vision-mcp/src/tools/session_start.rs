@@ -13,6 +13,12 @@ use crate::types::{McpError, McpResult, ToolCallResult, ToolDefinition};
 struct StartParams {
     #[serde(default)]
     session_id: Option<u32>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    agent: Option<String>,
 }
 
 pub fn definition() -> ToolDefinition {
@@ -22,7 +28,10 @@ pub fn definition() -> ToolDefinition {
         input_schema: json!({
             "type": "object",
             "properties": {
-                "session_id": { "type": "integer", "description": "Optional explicit session ID" }
+                "session_id": { "type": "integer", "description": "Optional explicit session ID" },
+                "name": { "type": "string", "description": "Short name for the session" },
+                "description": { "type": "string", "description": "Goal or purpose of the session" },
+                "agent": { "type": "string", "description": "Identity of the agent running the session" }
             }
         }),
     }
@@ -37,9 +46,14 @@ pub async fn execute(
 
     let mut session = session.lock().await;
     let session_id = session.start_session(params.session_id)?;
+    session.describe_session(session_id, params.name, params.description, params.agent);
+    let info = session.store().session_info(session_id);
 
     Ok(ToolCallResult::json(&json!({
         "session_id": session_id,
+        "name": info.and_then(|i| i.name.as_deref()),
+        "description": info.and_then(|i| i.description.as_deref()),
+        "agent": info.and_then(|i| i.agent.as_deref()),
         "status": "started"
     })))
 }
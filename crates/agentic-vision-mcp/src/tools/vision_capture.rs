@@ -2,11 +2,13 @@
 
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
 
 use serde::Deserialize;
 use serde_json::{json, Value};
 
 use crate::session::VisionSessionManager;
+use crate::tools::CallContext;
 use crate::types::{McpError, McpResult, ToolCallResult, ToolDefinition};
 
 #[derive(Debug, Deserialize)]
@@ -87,6 +89,8 @@ pub fn definition() -> ToolDefinition {
 pub async fn execute(
     args: Value,
     session: &Arc<Mutex<VisionSessionManager>>,
+    cancel: &CancellationToken,
+    context: &CallContext,
 ) -> McpResult<ToolCallResult> {
     let params: CaptureParams =
         serde_json::from_value(args).map_err(|e| McpError::InvalidParams(e.to_string()))?;
@@ -105,6 +109,8 @@ pub async fn execute(
                 params.labels,
                 params.description,
                 params.extract_ocr,
+                context.provenance("vision_capture"),
+                cancel,
             )?
         }
         "base64" => {
@@ -118,6 +124,8 @@ pub async fn execute(
                 params.labels,
                 params.description,
                 params.extract_ocr,
+                context.provenance("vision_capture"),
+                cancel,
             )?
         }
         "screenshot" => {
@@ -132,11 +140,17 @@ pub async fn execute(
                 params.labels,
                 params.description,
                 params.extract_ocr,
+                context.provenance("vision_capture"),
+                cancel,
             )?
         }
-        "clipboard" => {
-            session.capture_clipboard(params.labels, params.description, params.extract_ocr)?
-        }
+        "clipboard" => session.capture_clipboard(
+            params.labels,
+            params.description,
+            params.extract_ocr,
+            context.provenance("vision_capture"),
+            cancel,
+        )?,
         other => {
             return Err(McpError::InvalidParams(format!(
                 "Unsupported source type: {other}. Use 'file', 'base64', 'screenshot', or 'clipboard'."
@@ -144,13 +158,21 @@ pub async fn execute(
         }
     };
 
-    Ok(ToolCallResult::json(&json!({
+    let mut response = json!({
         "capture_id": result.capture_id,
         "timestamp": result.timestamp,
         "dimensions": {
             "width": result.width,
             "height": result.height
         },
-        "embedding_dims": result.embedding_dims
-    })))
+        "embedding_dims": result.embedding_dims,
+        "caption": result.caption
+    });
+    if !result.processed.is_empty() {
+        response["processed"] = json!(result.processed);
+    }
+    if let Some(text) = result.ocr_text {
+        response["ocr_text"] = json!(text);
+    }
+    Ok(ToolCallResult::json(&response))
 }
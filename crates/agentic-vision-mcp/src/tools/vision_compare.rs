@@ -48,6 +48,9 @@ pub async fn execute(
         "similarity": similarity,
         "is_same": is_same,
     });
+    if let Some(confidence) = session.confidence(similarity) {
+        result["confidence"] = json!(confidence);
+    }
 
     if params.detailed {
         if let Ok(diff) = session.diff(params.id_a, params.id_b) {
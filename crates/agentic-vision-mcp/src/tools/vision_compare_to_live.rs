@@ -0,0 +1,178 @@
+//! Tool: vision_compare_to_live — Diff a stored capture against its live page.
+//!
+//! The page is screenshotted by a running Cortex daemon (a `perceive` request
+//! with `screenshot: true` on `$CORTEX_SOCKET`, default `/tmp/cortex.sock`).
+
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::session::VisionSessionManager;
+use crate::tools::CallContext;
+use crate::types::{McpError, McpResult, ToolCallResult, ToolDefinition};
+
+#[cfg(unix)]
+const DEFAULT_SOCKET: &str = "/tmp/cortex.sock";
+
+#[derive(Debug, Deserialize)]
+struct CompareToLiveParams {
+    capture_id: u64,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    save: bool,
+    #[serde(default = "default_timeout_ms")]
+    timeout_ms: u64,
+}
+
+fn default_timeout_ms() -> u64 {
+    60_000
+}
+
+pub fn definition() -> ToolDefinition {
+    ToolDefinition {
+        name: "vision_compare_to_live".to_string(),
+        description: Some(
+            "Screenshot the live page of a stored capture through a running Cortex daemon and diff it against the capture"
+                .to_string(),
+        ),
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "capture_id": { "type": "integer", "description": "Stored capture to compare" },
+                "url": {
+                    "type": "string",
+                    "description": "Page to screenshot (default: the capture's 'url:' label, or its description if that is a URL)"
+                },
+                "save": { "type": "boolean", "default": false, "description": "Store the live screenshot as a new capture" },
+                "timeout_ms": { "type": "integer", "default": 60000 }
+            },
+            "required": ["capture_id"]
+        }),
+    }
+}
+
+pub async fn execute(
+    args: Value,
+    session: &Arc<Mutex<VisionSessionManager>>,
+    cancel: &CancellationToken,
+    context: &CallContext,
+) -> McpResult<ToolCallResult> {
+    let params: CompareToLiveParams =
+        serde_json::from_value(args).map_err(|e| McpError::InvalidParams(e.to_string()))?;
+
+    let url = match params.url {
+        Some(url) => url,
+        None => session
+            .lock()
+            .await
+            .capture_url(params.capture_id)?
+            .ok_or_else(|| {
+                McpError::InvalidParams(format!(
+                    "Capture {} has no linked URL; pass 'url' or label the capture 'url:<page>'",
+                    params.capture_id
+                ))
+            })?,
+    };
+
+    // Rendering can take a while; the session stays unlocked meanwhile.
+    let perceived = match perceive_screenshot(&url, params.timeout_ms).await {
+        Ok(perceived) => perceived,
+        Err(message) => return Ok(ToolCallResult::error(message)),
+    };
+    let Some(data) = perceived.get("screenshot").and_then(|v| v.as_str()) else {
+        return Ok(ToolCallResult::error(
+            "Cortex returned no screenshot; it may need updating".to_string(),
+        ));
+    };
+    let final_url = perceived.get("final_url").cloned().unwrap_or(Value::Null);
+
+    let mut session = session.lock().await;
+    let (diff, live_capture_id) = if params.save {
+        let live = session.capture(
+            "base64",
+            data,
+            Some("image/png"),
+            vec![format!("url:{url}")],
+            Some(url.clone()),
+            false,
+            context.provenance("vision_compare_to_live"),
+            cancel,
+        )?;
+        (
+            session.diff(params.capture_id, live.capture_id)?,
+            Some(live.capture_id),
+        )
+    } else {
+        let (img, _) = agentic_vision::capture_from_base64(data, "image/png")
+            .map_err(|e| McpError::VisionError(format!("Failed to decode screenshot: {e}")))?;
+        (session.diff_image(params.capture_id, &img)?, None)
+    };
+
+    Ok(ToolCallResult::json(&json!({
+        "capture_id": params.capture_id,
+        "url": url,
+        "final_url": final_url,
+        "live_capture_id": live_capture_id,
+        "similarity": diff.similarity,
+        "pixel_diff_ratio": diff.pixel_diff_ratio,
+        "changed_regions": diff.changed_regions,
+    })))
+}
+
+/// Ask the Cortex daemon to perceive `url` with a screenshot. Returns the
+/// `perceive` result, or a message saying why there is none.
+#[cfg(unix)]
+async fn perceive_screenshot(url: &str, timeout_ms: u64) -> Result<Value, String> {
+    use std::time::Duration;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let socket = std::env::var("CORTEX_SOCKET").unwrap_or_else(|_| DEFAULT_SOCKET.to_string());
+    let mut stream = tokio::net::UnixStream::connect(&socket)
+        .await
+        .map_err(|_| format!("Cortex is not running at {socket} (start it with `cortex start`)"))?;
+
+    let mut request = json!({
+        "id": format!("vision-{}", uuid::Uuid::new_v4()),
+        "method": "perceive",
+        "params": { "url": url, "screenshot": true, "include_content": false },
+    });
+    if let Ok(key) = std::env::var("CORTEX_API_KEY") {
+        request["params"]["api_key"] = key.into();
+    }
+    stream
+        .write_all(format!("{request}\n").as_bytes())
+        .await
+        .map_err(|e| format!("Failed to send to Cortex: {e}"))?;
+
+    let mut line = String::new();
+    tokio::time::timeout(
+        Duration::from_millis(timeout_ms),
+        BufReader::new(stream).read_line(&mut line),
+    )
+    .await
+    .map_err(|_| format!("Cortex did not screenshot {url} within {timeout_ms}ms"))?
+    .map_err(|e| format!("Failed to read from Cortex: {e}"))?;
+
+    let response: Value =
+        serde_json::from_str(&line).map_err(|_| "Invalid response from Cortex".to_string())?;
+    if let Some(error) = response.get("error") {
+        let message = error
+            .get("message")
+            .and_then(|m| m.as_str())
+            .unwrap_or("unknown error");
+        return Err(format!("Cortex could not perceive {url}: {message}"));
+    }
+    response
+        .get("result")
+        .cloned()
+        .ok_or_else(|| "Invalid response from Cortex".to_string())
+}
+
+#[cfg(not(unix))]
+async fn perceive_screenshot(_url: &str, _timeout_ms: u64) -> Result<Value, String> {
+    Err("Connecting to Cortex needs a Unix socket, which this platform lacks".to_string())
+}
@@ -0,0 +1,116 @@
+//! Tool: vision_dedup_report — Find, and optionally merge, duplicate captures.
+
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use agentic_vision::dedup::{DEFAULT_HASH_DISTANCE, DEFAULT_SIMILARITY};
+use agentic_vision::DedupOptions;
+
+use crate::session::VisionSessionManager;
+use crate::types::{McpError, McpResult, ToolCallResult, ToolDefinition};
+
+#[derive(Debug, Deserialize)]
+struct DedupParams {
+    #[serde(default = "default_true")]
+    near: bool,
+    #[serde(default = "default_min_similarity")]
+    min_similarity: f32,
+    #[serde(default = "default_max_hash_distance")]
+    max_hash_distance: u32,
+    #[serde(default)]
+    merge: bool,
+    #[serde(default = "default_max_groups")]
+    max_groups: usize,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_min_similarity() -> f32 {
+    DEFAULT_SIMILARITY
+}
+
+fn default_max_hash_distance() -> u32 {
+    DEFAULT_HASH_DISTANCE
+}
+
+fn default_max_groups() -> usize {
+    50
+}
+
+pub fn definition() -> ToolDefinition {
+    ToolDefinition {
+        name: "vision_dedup_report".to_string(),
+        description: Some(
+            "Find exact and near-duplicate captures across the store, estimate the space they take, and optionally merge them into the oldest capture keeping labels and links"
+                .to_string(),
+        ),
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "near": {
+                    "type": "boolean",
+                    "default": true,
+                    "description": "Also find near duplicates by perceptual hash and embedding"
+                },
+                "min_similarity": {
+                    "type": "number",
+                    "default": DEFAULT_SIMILARITY,
+                    "description": "Embedding cosine similarity of near duplicates"
+                },
+                "max_hash_distance": {
+                    "type": "integer",
+                    "default": DEFAULT_HASH_DISTANCE,
+                    "description": "Most bits the 64-bit perceptual hashes of near duplicates differ in"
+                },
+                "merge": {
+                    "type": "boolean",
+                    "default": false,
+                    "description": "Merge every group into its oldest capture and remove the rest"
+                },
+                "max_groups": { "type": "integer", "default": 50 }
+            }
+        }),
+    }
+}
+
+pub async fn execute(
+    args: Value,
+    session: &Arc<Mutex<VisionSessionManager>>,
+) -> McpResult<ToolCallResult> {
+    let params: DedupParams =
+        serde_json::from_value(args).map_err(|e| McpError::InvalidParams(e.to_string()))?;
+    if !(-1.0..=1.0).contains(&params.min_similarity) {
+        return Err(McpError::InvalidParams(
+            "min_similarity must be between -1 and 1".to_string(),
+        ));
+    }
+    if params.max_hash_distance > 64 {
+        return Err(McpError::InvalidParams(
+            "max_hash_distance must be at most 64".to_string(),
+        ));
+    }
+    let options = DedupOptions {
+        near: params.near,
+        min_similarity: params.min_similarity,
+        max_hash_distance: params.max_hash_distance,
+    };
+
+    let (mut report, merged) = session.lock().await.dedup(&options, params.merge)?;
+    let total_groups = report.groups.len();
+    report.groups.truncate(params.max_groups);
+
+    Ok(ToolCallResult::json(&json!({
+        "scanned": report.scanned,
+        "exact_duplicates": report.exact_duplicates,
+        "near_duplicates": report.near_duplicates,
+        "reclaimable_bytes": report.reclaimable_bytes,
+        "total_groups": total_groups,
+        "groups": report.groups,
+        "merged": merged,
+    })))
+}
@@ -0,0 +1,166 @@
+//! Tool: vision_find_element — Find captures containing a UI element.
+
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use agentic_vision::cosine_similarity;
+use agentic_vision::elements::{COLOR_NAMES, DEFAULT_ELEMENT_KINDS};
+
+use crate::session::VisionSessionManager;
+use crate::types::{McpError, McpResult, ToolCallResult, ToolDefinition};
+
+#[derive(Debug, Deserialize)]
+struct FindElementParams {
+    #[serde(default)]
+    query: Option<String>,
+    #[serde(default)]
+    kind: Option<String>,
+    #[serde(default)]
+    color: Option<String>,
+    #[serde(default)]
+    like: Option<ElementRef>,
+    #[serde(default = "default_max_results")]
+    max_results: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct ElementRef {
+    capture_id: u64,
+    element: usize,
+}
+
+fn default_max_results() -> usize {
+    10
+}
+
+pub fn definition() -> ToolDefinition {
+    ToolDefinition {
+        name: "vision_find_element".to_string(),
+        description: Some(
+            "Find captures containing a detected UI element (button, input, dialog, icon) by kind, colour, or resemblance to another element"
+                .to_string(),
+        ),
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "Free text such as 'red button'; element kinds and colour names in it become filters. Text inside elements is not read."
+                },
+                "kind": { "type": "string", "description": "Element kind, e.g. 'button'" },
+                "color": { "type": "string", "description": "Dominant colour, e.g. 'red'" },
+                "like": {
+                    "type": "object",
+                    "description": "Rank by visual similarity to this element",
+                    "properties": {
+                        "capture_id": { "type": "integer" },
+                        "element": { "type": "integer", "description": "Index in the capture's elements" }
+                    },
+                    "required": ["capture_id", "element"]
+                },
+                "max_results": { "type": "integer", "default": 10 }
+            }
+        }),
+    }
+}
+
+pub async fn execute(
+    args: Value,
+    session: &Arc<Mutex<VisionSessionManager>>,
+) -> McpResult<ToolCallResult> {
+    let params: FindElementParams =
+        serde_json::from_value(args).map_err(|e| McpError::InvalidParams(e.to_string()))?;
+
+    let session = session.lock().await;
+    let store = session.store();
+
+    let mut kind = params.kind.map(|k| k.to_lowercase());
+    let mut color = params.color.map(|c| c.to_lowercase());
+    let mut ignored = Vec::new();
+    if let Some(query) = &params.query {
+        let kinds: Vec<&str> = store
+            .observations
+            .iter()
+            .flat_map(|o| o.elements.iter().map(|e| e.kind.as_str()))
+            .chain(DEFAULT_ELEMENT_KINDS)
+            .collect();
+        for word in query.split_whitespace().map(str::to_lowercase) {
+            let singular = word.strip_suffix('s').unwrap_or(&word);
+            if let Some(k) = kinds.iter().find(|k| **k == word || **k == singular) {
+                kind.get_or_insert_with(|| k.to_string());
+            } else if COLOR_NAMES.contains(&word.as_str()) || word == "grey" {
+                color.get_or_insert_with(|| word.replace("grey", "gray"));
+            } else {
+                ignored.push(word);
+            }
+        }
+    }
+
+    let example = match &params.like {
+        Some(like) => {
+            let obs = store
+                .get(like.capture_id)
+                .ok_or(McpError::CaptureNotFound(like.capture_id))?;
+            let element = obs.elements.get(like.element).ok_or_else(|| {
+                McpError::InvalidParams(format!(
+                    "Capture {} has no element {}",
+                    like.capture_id, like.element
+                ))
+            })?;
+            Some(element.embedding.clone())
+        }
+        None => None,
+    };
+
+    let mut matches: Vec<(f32, Value)> = store
+        .observations
+        .iter()
+        .flat_map(|o| o.elements.iter().enumerate().map(move |(i, e)| (o, i, e)))
+        .filter(|(_, _, e)| kind.as_ref().is_none_or(|k| e.kind == *k))
+        .filter(|(_, _, e)| color.as_ref().is_none_or(|c| e.color == *c))
+        .filter(|(o, i, _)| {
+            params
+                .like
+                .as_ref()
+                .is_none_or(|l| (l.capture_id, l.element) != (o.id, *i))
+        })
+        .map(|(o, i, e)| {
+            let score = match &example {
+                Some(embedding) => cosine_similarity(embedding, &e.embedding),
+                None => e.confidence,
+            };
+            (
+                score,
+                json!({
+                    "capture_id": o.id,
+                    "element": i,
+                    "kind": e.kind,
+                    "color": e.color,
+                    "bbox": e.bbox,
+                    "confidence": e.confidence,
+                    "score": score,
+                    "timestamp": o.timestamp,
+                    "labels": o.metadata.labels,
+                }),
+            )
+        })
+        .collect();
+    matches.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let matches: Vec<Value> = matches
+        .into_iter()
+        .take(params.max_results)
+        .map(|(_, m)| m)
+        .collect();
+
+    Ok(ToolCallResult::json(&json!({
+        "kind": kind,
+        "color": color,
+        "ignored_terms": ignored,
+        "detection_enabled": session.has_detector(),
+        "total": matches.len(),
+        "matches": matches,
+    })))
+}
@@ -0,0 +1,113 @@
+//! Tool: vision_find_text — Find where text appears in captures.
+
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::session::VisionSessionManager;
+use crate::types::{McpError, McpResult, ToolCallResult, ToolDefinition};
+
+#[derive(Debug, Deserialize)]
+struct FindTextParams {
+    text: String,
+    #[serde(default)]
+    capture_id: Option<u64>,
+    #[serde(default = "default_max_results")]
+    max_results: usize,
+}
+
+fn default_max_results() -> usize {
+    20
+}
+
+pub fn definition() -> ToolDefinition {
+    ToolDefinition {
+        name: "vision_find_text".to_string(),
+        description: Some(
+            "Find lines of text read by vision_ocr, with their positions in the capture"
+                .to_string(),
+        ),
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "text": { "type": "string", "description": "Text to find, case-insensitive" },
+                "capture_id": { "type": "integer", "description": "Only search this capture" },
+                "max_results": { "type": "integer", "default": 20 }
+            },
+            "required": ["text"]
+        }),
+    }
+}
+
+pub async fn execute(
+    args: Value,
+    session: &Arc<Mutex<VisionSessionManager>>,
+) -> McpResult<ToolCallResult> {
+    let params: FindTextParams =
+        serde_json::from_value(args).map_err(|e| McpError::InvalidParams(e.to_string()))?;
+    let needle = params.text.trim().to_lowercase();
+    if needle.is_empty() {
+        return Err(McpError::InvalidParams(
+            "text must not be empty".to_string(),
+        ));
+    }
+    let terms: Vec<&str> = needle.split_whitespace().collect();
+
+    let session = session.lock().await;
+    let store = session.store();
+    if let Some(id) = params.capture_id {
+        store.get(id).ok_or(McpError::CaptureNotFound(id))?;
+    }
+
+    let mut matches = Vec::new();
+    let mut searched = 0;
+    // Newest captures first.
+    for obs in store.observations.iter().rev() {
+        if params.capture_id.is_some_and(|id| id != obs.id) {
+            continue;
+        }
+        let Some(ocr) = &obs.ocr else {
+            continue;
+        };
+        searched += 1;
+        for (block, b) in ocr.blocks.iter().enumerate() {
+            for &index in &b.lines {
+                let line = &ocr.lines[index];
+                if !line.text.to_lowercase().contains(&needle) {
+                    continue;
+                }
+                let words: Vec<&agentic_vision::OcrWord> = line
+                    .words
+                    .iter()
+                    .filter(|w| {
+                        let word = w.text.to_lowercase();
+                        terms.iter().any(|t| word.contains(t) || t.contains(&word))
+                    })
+                    .collect();
+                matches.push(json!({
+                    "capture_id": obs.id,
+                    "timestamp": obs.timestamp,
+                    "line": index,
+                    "block": block,
+                    "text": line.text,
+                    "bbox": line.bbox,
+                    "confidence": line.confidence,
+                    "words": words,
+                }));
+            }
+        }
+        if matches.len() >= params.max_results {
+            break;
+        }
+    }
+    matches.truncate(params.max_results);
+
+    Ok(ToolCallResult::json(&json!({
+        "text": params.text,
+        "captures_with_text": searched,
+        "total": matches.len(),
+        "matches": matches,
+    })))
+}
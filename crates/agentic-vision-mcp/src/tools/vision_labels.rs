@@ -0,0 +1,105 @@
+//! Tool: vision_labels — List, rename, merge, and alias hierarchical labels.
+
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use agentic_vision::label_matches;
+
+use crate::session::VisionSessionManager;
+use crate::types::{McpError, McpResult, ToolCallResult, ToolDefinition};
+
+#[derive(Debug, Deserialize)]
+struct LabelsParams {
+    action: String,
+    #[serde(default)]
+    prefix: Option<String>,
+    #[serde(default)]
+    from: Option<String>,
+    #[serde(default)]
+    to: Option<String>,
+}
+
+pub fn definition() -> ToolDefinition {
+    ToolDefinition {
+        name: "vision_labels".to_string(),
+        description: Some(
+            "Manage hierarchical capture labels (e.g. 'app/checkout/payment'): list branch counts, rename or merge branches, and add aliases"
+                .to_string(),
+        ),
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["list", "rename", "merge", "alias"]
+                },
+                "prefix": { "type": "string", "description": "Only list this branch and the labels under it" },
+                "from": { "type": "string", "description": "Branch to rename or merge, or the new alias" },
+                "to": { "type": "string", "description": "New branch name, branch to merge into, or the alias target" }
+            },
+            "required": ["action"]
+        }),
+    }
+}
+
+pub async fn execute(
+    args: Value,
+    session: &Arc<Mutex<VisionSessionManager>>,
+) -> McpResult<ToolCallResult> {
+    let params: LabelsParams =
+        serde_json::from_value(args).map_err(|e| McpError::InvalidParams(e.to_string()))?;
+
+    let mut session = session.lock().await;
+    let from_to = || match (params.from.as_deref(), params.to.as_deref()) {
+        (Some(from), Some(to)) => Ok((from, to)),
+        _ => Err(McpError::InvalidParams(format!(
+            "'from' and 'to' required for {}",
+            params.action
+        ))),
+    };
+
+    match params.action.as_str() {
+        "list" => {
+            let store = session.store();
+            let prefix = params.prefix.as_deref().map(|p| store.resolve_label(p));
+            let labels: serde_json::Map<String, Value> = store
+                .label_counts()
+                .into_iter()
+                .filter(|(label, _)| prefix.as_deref().is_none_or(|p| label_matches(label, p)))
+                .map(|(label, count)| (label, json!(count)))
+                .collect();
+            Ok(ToolCallResult::json(&json!({
+                "count": labels.len(),
+                "labels": labels,
+                "aliases": store.label_aliases,
+            })))
+        }
+        "rename" | "merge" => {
+            let (from, to) = from_to()?;
+            let relabeled = if params.action == "rename" {
+                session.rename_label(from, to)?
+            } else {
+                session.merge_labels(from, to)?
+            };
+            Ok(ToolCallResult::json(&json!({
+                "from": from,
+                "to": session.store().resolve_label(to),
+                "captures_relabeled": relabeled,
+            })))
+        }
+        "alias" => {
+            let (alias, target) = from_to()?;
+            session.add_label_alias(alias, target)?;
+            Ok(ToolCallResult::json(&json!({
+                "alias": alias,
+                "target": session.store().resolve_label(alias),
+            })))
+        }
+        other => Err(McpError::InvalidParams(format!(
+            "Unsupported action: {other}. Use 'list', 'rename', 'merge', or 'alias'."
+        ))),
+    }
+}
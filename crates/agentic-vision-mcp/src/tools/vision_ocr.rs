@@ -1,24 +1,50 @@
-//! Tool: vision_ocr — Extract text from a capture (stub for v0.1.0).
+//! Tool: vision_ocr — Extract text from a capture.
 
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use serde::Deserialize;
 use serde_json::{json, Value};
 
+use agentic_vision::detect_language;
+
+use crate::config::OcrBackend;
 use crate::session::VisionSessionManager;
-use crate::types::{McpResult, ToolCallResult, ToolDefinition};
+use crate::types::{McpError, McpResult, ToolCallResult, ToolDefinition};
+
+#[derive(Debug, Deserialize)]
+struct OcrParams {
+    capture_id: u64,
+    #[serde(default = "default_language")]
+    language: String,
+    #[serde(default)]
+    backend: Option<String>,
+}
+
+fn default_language() -> String {
+    "eng".to_string()
+}
 
 pub fn definition() -> ToolDefinition {
     ToolDefinition {
         name: "vision_ocr".to_string(),
         description: Some(
-            "Extract text from a capture using OCR (requires --features ocr in v0.2.0)".to_string(),
+            "Extract text from a capture using OCR (tesseract, onnx or remote backend)".to_string(),
         ),
         input_schema: json!({
             "type": "object",
             "properties": {
                 "capture_id": { "type": "integer" },
-                "language": { "type": "string", "default": "eng" }
+                "language": {
+                    "type": "string",
+                    "default": "eng",
+                    "description": "Language hints in Tesseract notation, joined by '+' (e.g. 'eng+deu')"
+                },
+                "backend": {
+                    "type": "string",
+                    "enum": ["tesseract", "onnx", "remote"],
+                    "description": "OCR backend; defaults to AVIS_OCR_BACKEND or tesseract"
+                }
             },
             "required": ["capture_id"]
         }),
@@ -26,11 +52,55 @@ pub fn definition() -> ToolDefinition {
 }
 
 pub async fn execute(
-    _args: Value,
-    _session: &Arc<Mutex<VisionSessionManager>>,
+    args: Value,
+    session: &Arc<Mutex<VisionSessionManager>>,
 ) -> McpResult<ToolCallResult> {
+    let params: OcrParams =
+        serde_json::from_value(args).map_err(|e| McpError::InvalidParams(e.to_string()))?;
+    let backend = match params.backend.as_deref() {
+        Some(name) => Some(OcrBackend::parse(name).ok_or_else(|| {
+            McpError::InvalidParams(format!(
+                "Unknown OCR backend '{name}'; expected tesseract, onnx or remote"
+            ))
+        })?),
+        None => None,
+    };
+
+    let (backend, engine, img, source) = {
+        let mut session = session.lock().await;
+        let (img, source) = session.source_image(params.capture_id)?;
+        let (backend, engine) = session.ocr_engine(backend)?;
+        (backend, engine, img, source)
+    };
+
+    let language = params.language.clone();
+    let (mut result, width, height) = tokio::task::spawn_blocking(move || {
+        let mut engine = engine
+            .lock()
+            .map_err(|_| McpError::InternalError("OCR engine poisoned".to_string()))?;
+        let result = engine.recognize(&img, &language)?;
+        Ok::<_, McpError>((result, img.width(), img.height()))
+    })
+    .await
+    .map_err(|e| McpError::InternalError(format!("OCR task failed: {e}")))??;
+    if result.language.is_none() {
+        result.language = detect_language(&result.text, &params.language);
+    }
+
+    // Stored with the capture, boxes in original image pixels.
+    let result = session
+        .lock()
+        .await
+        .store_ocr(params.capture_id, result, width, height)?;
+
     Ok(ToolCallResult::json(&json!({
-        "status": "unavailable",
-        "message": "OCR is not available in v0.1.0. This feature will be added in v0.2.0 with --features ocr (Tesseract integration)."
+        "capture_id": params.capture_id,
+        "backend": backend.name(),
+        "language": params.language,
+        "detected_language": result.language,
+        "source": source,
+        "text": result.text,
+        "lines": result.lines,
+        "blocks": result.blocks,
     })))
 }
@@ -2,10 +2,13 @@
 
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
 
 use serde::Deserialize;
 use serde_json::{json, Value};
 
+use agentic_vision::{label_matches, SessionFilter};
+
 use crate::session::VisionSessionManager;
 use crate::types::{McpError, McpResult, ToolCallResult, ToolDefinition};
 
@@ -14,11 +17,19 @@ struct QueryParams {
     #[serde(default)]
     session_ids: Vec<u32>,
     #[serde(default)]
+    session_name: Option<String>,
+    #[serde(default)]
+    agent: Option<String>,
+    #[serde(default)]
+    session_text: Option<String>,
+    #[serde(default)]
     after: Option<u64>,
     #[serde(default)]
     before: Option<u64>,
     #[serde(default)]
     labels: Vec<String>,
+    #[serde(default)]
+    text: Option<String>,
     #[serde(default = "default_max_results")]
     max_results: usize,
 }
@@ -35,9 +46,29 @@ pub fn definition() -> ToolDefinition {
             "type": "object",
             "properties": {
                 "session_ids": { "type": "array", "items": { "type": "integer" } },
+                "session_name": {
+                    "type": "string",
+                    "description": "Only captures from sessions with this name (case-insensitive)"
+                },
+                "agent": {
+                    "type": "string",
+                    "description": "Only captures from sessions run by this agent"
+                },
+                "session_text": {
+                    "type": "string",
+                    "description": "Words that must all appear in the session's name or description"
+                },
                 "after": { "type": "integer", "description": "Unix timestamp" },
                 "before": { "type": "integer", "description": "Unix timestamp" },
-                "labels": { "type": "array", "items": { "type": "string" } },
+                "labels": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Match captures with any of these labels or labels under them ('app/checkout' matches 'app/checkout/payment')"
+                },
+                "text": {
+                    "type": "string",
+                    "description": "Words that must all appear in the capture's caption, description, or labels"
+                },
                 "max_results": { "type": "integer", "default": 20 }
             }
         }),
@@ -47,20 +78,41 @@ pub fn definition() -> ToolDefinition {
 pub async fn execute(
     args: Value,
     session: &Arc<Mutex<VisionSessionManager>>,
+    cancel: &CancellationToken,
 ) -> McpResult<ToolCallResult> {
     let params: QueryParams =
         serde_json::from_value(args).map_err(|e| McpError::InvalidParams(e.to_string()))?;
 
-    let session = session.lock().await;
-    let store = session.store();
+    let mut session = session.lock().await;
+    let labels = session.resolve_labels(&params.labels);
+    let session_filter = SessionFilter {
+        name: params.session_name,
+        agent: params.agent,
+        text: params.session_text,
+    };
+    let matching_sessions =
+        (!session_filter.is_empty()).then(|| session.store().matching_sessions(&session_filter));
+    let words: Vec<String> = params
+        .text
+        .as_deref()
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect();
 
-    let results: Vec<Value> = store
+    let candidates: Vec<u64> = session
+        .store()
         .observations
         .iter()
         .filter(|o| {
             if !params.session_ids.is_empty() && !params.session_ids.contains(&o.session_id) {
                 return false;
             }
+            if let Some(sessions) = &matching_sessions {
+                if !sessions.contains(&o.session_id) {
+                    return false;
+                }
+            }
             if let Some(after) = params.after {
                 if o.timestamp < after {
                     return false;
@@ -71,13 +123,41 @@ pub async fn execute(
                     return false;
                 }
             }
-            if !params.labels.is_empty()
-                && !params.labels.iter().any(|l| o.metadata.labels.contains(l))
+            if !labels.is_empty()
+                && !labels
+                    .iter()
+                    .any(|branch| o.metadata.labels.iter().any(|l| label_matches(l, branch)))
             {
                 return false;
             }
             true
         })
+        .map(|o| o.id)
+        .collect();
+
+    // Captions are generated lazily for captures that lack one; text search
+    // needs them on every candidate, a plain listing only on what it returns.
+    if words.is_empty() {
+        let returned = &candidates[..candidates.len().min(params.max_results)];
+        session.ensure_captions(returned, cancel)?;
+    } else {
+        session.ensure_captions(&candidates, cancel)?;
+    }
+
+    let store = session.store();
+    let results: Vec<Value> = candidates
+        .iter()
+        .filter_map(|&id| store.get(id))
+        .filter(|o| {
+            let haystack = [
+                o.metadata.caption.as_deref().unwrap_or_default(),
+                o.metadata.description.as_deref().unwrap_or_default(),
+                &o.metadata.labels.join(" "),
+            ]
+            .join(" ")
+            .to_lowercase();
+            words.iter().all(|w| haystack.contains(w.as_str()))
+        })
         .take(params.max_results)
         .map(|o| {
             json!({
@@ -90,6 +170,7 @@ pub async fn execute(
                 },
                 "labels": o.metadata.labels,
                 "description": o.metadata.description,
+                "caption": o.metadata.caption,
                 "memory_link": o.memory_link,
             })
         })
@@ -0,0 +1,88 @@
+//! Tool: vision_reindex — Re-embed all captures after a model switch.
+
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::reindex::{self, DEFAULT_BATCH};
+use crate::session::VisionSessionManager;
+use crate::types::{McpError, McpResult, ToolCallResult, ToolDefinition};
+
+#[derive(Debug, Deserialize)]
+struct ReindexParams {
+    #[serde(default = "default_action")]
+    action: String,
+    #[serde(default = "default_batch_size")]
+    batch_size: usize,
+}
+
+fn default_action() -> String {
+    "status".to_string()
+}
+
+fn default_batch_size() -> usize {
+    DEFAULT_BATCH
+}
+
+pub fn definition() -> ToolDefinition {
+    ToolDefinition {
+        name: "vision_reindex".to_string(),
+        description: Some(
+            "Re-embed all captures with the loaded model in the background, resuming an interrupted run; report or cancel its progress"
+                .to_string(),
+        ),
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["start", "status", "cancel"],
+                    "default": "status"
+                },
+                "batch_size": {
+                    "type": "integer",
+                    "default": DEFAULT_BATCH,
+                    "description": "Captures re-embedded between checkpoints (for start)"
+                }
+            }
+        }),
+    }
+}
+
+pub async fn execute(
+    args: Value,
+    session: &Arc<Mutex<VisionSessionManager>>,
+) -> McpResult<ToolCallResult> {
+    let params: ReindexParams =
+        serde_json::from_value(args).map_err(|e| McpError::InvalidParams(e.to_string()))?;
+    if params.batch_size == 0 {
+        return Err(McpError::InvalidParams(
+            "batch_size must be at least 1".to_string(),
+        ));
+    }
+
+    match params.action.as_str() {
+        "start" => Ok(ToolCallResult::json(
+            &reindex::spawn(session, params.batch_size).await?,
+        )),
+        "status" => Ok(ToolCallResult::json(&reindex::status(
+            &*session.lock().await,
+        ))),
+        "cancel" => {
+            let session = session.lock().await;
+            let job = session
+                .reindex_job()
+                .filter(|j| j.is_running())
+                .ok_or_else(|| McpError::InvalidParams("No re-index is running".to_string()))?;
+            job.cancel();
+            let mut status = reindex::status(&session);
+            status["cancelling"] = json!(true);
+            Ok(ToolCallResult::json(&status))
+        }
+        other => Err(McpError::InvalidParams(format!(
+            "Unknown action '{other}'; expected start, status or cancel"
+        ))),
+    }
+}
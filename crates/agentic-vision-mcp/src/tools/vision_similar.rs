@@ -6,6 +6,8 @@ use tokio::sync::Mutex;
 use serde::Deserialize;
 use serde_json::{json, Value};
 
+use agentic_vision::SimilarityFilter;
+
 use crate::session::VisionSessionManager;
 use crate::types::{McpError, McpResult, ToolCallResult, ToolDefinition};
 
@@ -19,6 +21,20 @@ struct SimilarParams {
     top_k: usize,
     #[serde(default = "default_min_similarity")]
     min_similarity: f32,
+    #[serde(default)]
+    layout_weight: f32,
+    #[serde(default)]
+    diversity: f32,
+    #[serde(default)]
+    labels: Vec<String>,
+    #[serde(default)]
+    after: Option<u64>,
+    #[serde(default)]
+    before: Option<u64>,
+    #[serde(default)]
+    session_ids: Vec<u32>,
+    #[serde(default)]
+    memory_link: Option<u64>,
 }
 
 fn default_top_k() -> usize {
@@ -32,7 +48,10 @@ fn default_min_similarity() -> f32 {
 pub fn definition() -> ToolDefinition {
     ToolDefinition {
         name: "vision_similar".to_string(),
-        description: Some("Find visually similar captures by embedding".to_string()),
+        description: Some(
+            "Find visually similar captures by embedding, optionally blended with layout and colour similarity, among captures matching label, time, session and memory link filters"
+                .to_string(),
+        ),
         input_schema: json!({
             "type": "object",
             "properties": {
@@ -43,7 +62,34 @@ pub fn definition() -> ToolDefinition {
                     "description": "Or provide embedding directly"
                 },
                 "top_k": { "type": "integer", "default": 10 },
-                "min_similarity": { "type": "number", "default": 0.7 }
+                "min_similarity": { "type": "number", "default": 0.7 },
+                "layout_weight": {
+                    "type": "number",
+                    "default": 0,
+                    "minimum": 0,
+                    "maximum": 1,
+                    "description": "Share of the score from layout and colour rather than meaning (capture_id only)"
+                },
+                "diversity": {
+                    "type": "number",
+                    "default": 0,
+                    "minimum": 0,
+                    "maximum": 1,
+                    "description": "Trade similarity for variety: above 0, results are reranked by maximal marginal relevance so near-identical frames do not crowd out distinct ones"
+                },
+                "labels": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Only captures with any of these labels or labels under them"
+                },
+                "after": { "type": "integer", "description": "Only captures taken at or after this Unix timestamp" },
+                "before": { "type": "integer", "description": "Only captures taken at or before this Unix timestamp" },
+                "session_ids": {
+                    "type": "array",
+                    "items": { "type": "integer" },
+                    "description": "Only captures from these sessions"
+                },
+                "memory_link": { "type": "integer", "description": "Only captures linked to this memory node" }
             }
         }),
     }
@@ -56,25 +102,62 @@ pub async fn execute(
     let params: SimilarParams =
         serde_json::from_value(args).map_err(|e| McpError::InvalidParams(e.to_string()))?;
 
+    if !(0.0..=1.0).contains(&params.layout_weight) {
+        return Err(McpError::InvalidParams(
+            "'layout_weight' must be between 0 and 1".to_string(),
+        ));
+    }
+    if !(0.0..=1.0).contains(&params.diversity) {
+        return Err(McpError::InvalidParams(
+            "'diversity' must be between 0 and 1".to_string(),
+        ));
+    }
+
     let session = session.lock().await;
+    let filter = SimilarityFilter {
+        labels: session.resolve_labels(&params.labels),
+        after: params.after,
+        before: params.before,
+        session_ids: params.session_ids,
+        memory_link: params.memory_link,
+    };
 
     let matches = if let Some(capture_id) = params.capture_id {
-        session.find_similar(capture_id, params.top_k, params.min_similarity)?
+        session.find_similar(
+            capture_id,
+            &filter,
+            params.top_k,
+            params.min_similarity,
+            params.layout_weight,
+            params.diversity,
+        )?
     } else if let Some(embedding) = &params.embedding {
-        session.find_similar_by_embedding(embedding, params.top_k, params.min_similarity)
+        session.find_similar_by_embedding(
+            embedding,
+            &filter,
+            params.top_k,
+            params.min_similarity,
+            params.diversity,
+        )
     } else {
         return Err(McpError::InvalidParams(
             "Either 'capture_id' or 'embedding' is required".to_string(),
         ));
     };
 
+    // Calibration maps embedding scores; blended ones are on another scale.
+    let calibrated = params.layout_weight == 0.0 || params.capture_id.is_none();
     let results: Vec<Value> = matches
         .iter()
         .map(|m| {
-            json!({
+            let mut result = json!({
                 "id": m.id,
                 "similarity": m.similarity,
-            })
+            });
+            if let Some(confidence) = session.confidence(m.similarity).filter(|_| calibrated) {
+                result["confidence"] = json!(confidence);
+            }
+            result
         })
         .collect();
 
@@ -0,0 +1,95 @@
+//! Tool: vision_snapshot — Create, list, and restore snapshots of visual memory.
+
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::session::VisionSessionManager;
+use crate::types::{McpError, McpResult, ToolCallResult, ToolDefinition};
+
+#[derive(Debug, Deserialize)]
+struct SnapshotParams {
+    action: String,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+pub fn definition() -> ToolDefinition {
+    ToolDefinition {
+        name: "vision_snapshot".to_string(),
+        description: Some(
+            "Snapshot visual memory before experimenting, and restore it to roll back captures added since"
+                .to_string(),
+        ),
+        input_schema: json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["create", "list", "restore"]
+                },
+                "name": { "type": "string", "description": "Snapshot name (for create and restore)" }
+            },
+            "required": ["action"]
+        }),
+    }
+}
+
+pub async fn execute(
+    args: Value,
+    session: &Arc<Mutex<VisionSessionManager>>,
+) -> McpResult<ToolCallResult> {
+    let params: SnapshotParams =
+        serde_json::from_value(args).map_err(|e| McpError::InvalidParams(e.to_string()))?;
+
+    let mut session = session.lock().await;
+    let name = || {
+        params.name.as_deref().ok_or_else(|| {
+            McpError::InvalidParams(format!("'name' required for {}", params.action))
+        })
+    };
+
+    match params.action.as_str() {
+        "create" => {
+            let snapshot = session.snapshot_create(name()?)?;
+            Ok(ToolCallResult::json(&json!({
+                "name": snapshot.name,
+                "created_at": snapshot.created_at,
+                "capture_count": snapshot.observation_count,
+                "status": "created"
+            })))
+        }
+        "list" => {
+            let snapshots: Vec<Value> = session
+                .snapshots()
+                .iter()
+                .map(|s| {
+                    json!({
+                        "name": s.name,
+                        "created_at": s.created_at,
+                        "capture_count": s.observation_count,
+                    })
+                })
+                .collect();
+            Ok(ToolCallResult::json(&json!({
+                "count": snapshots.len(),
+                "snapshots": snapshots
+            })))
+        }
+        "restore" => {
+            let name = name()?;
+            let removed = session.snapshot_restore(name)?;
+            Ok(ToolCallResult::json(&json!({
+                "name": name,
+                "captures_removed": removed,
+                "capture_count": session.store().count(),
+                "status": "restored"
+            })))
+        }
+        other => Err(McpError::InvalidParams(format!(
+            "Unsupported action: {other}. Use 'create', 'list', or 'restore'."
+        ))),
+    }
+}
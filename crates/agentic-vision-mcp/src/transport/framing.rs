@@ -1,7 +1,23 @@
-//! Message framing for newline-delimited JSON.
+//! Message framing: newline-delimited JSON, or LSP-style `Content-Length`
+//! headers. The framing of each incoming message is detected on its own,
+//! and its response is framed the same way.
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt};
 
 use crate::types::{JsonRpcMessage, McpError, McpResult};
 
+/// Largest `Content-Length` accepted.
+const MAX_CONTENT_LENGTH: usize = 64 * 1024 * 1024;
+
+/// How a message is framed on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// One JSON message per line.
+    Newline,
+    /// `Content-Length: N` and other headers, a blank line, then N bytes.
+    ContentLength,
+}
+
 /// Parse a single line of text as a JSON-RPC message.
 pub fn parse_message(line: &str) -> McpResult<JsonRpcMessage> {
     let trimmed = line.trim();
@@ -12,9 +28,87 @@ pub fn parse_message(line: &str) -> McpResult<JsonRpcMessage> {
     serde_json::from_str(trimmed).map_err(|e| McpError::ParseError(e.to_string()))
 }
 
+/// Read the next message from `reader`, detecting its framing. Returns
+/// `None` at end of input. A header block without a valid `Content-Length`
+/// is returned as-is, so that parsing it reports the error.
+pub async fn read_message<R>(reader: &mut R) -> McpResult<Option<(String, Framing)>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await.map_err(McpError::Io)? == 0 {
+            return Ok(None);
+        }
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if !is_header(trimmed) {
+            return Ok(Some((trimmed.to_string(), Framing::Newline)));
+        }
+        break;
+    }
+
+    // A header block: read up to the blank line that ends it.
+    let mut headers = line.trim().to_string();
+    let mut content_length = parse_content_length(&headers);
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await.map_err(McpError::Io)? == 0 {
+            return Ok(Some((headers, Framing::Newline)));
+        }
+        let header = line.trim();
+        if header.is_empty() {
+            break;
+        }
+        content_length = content_length.or_else(|| parse_content_length(header));
+        headers.push('\n');
+        headers.push_str(header);
+    }
+
+    match content_length {
+        Some(n) if n <= MAX_CONTENT_LENGTH => {
+            let mut body = vec![0u8; n];
+            reader.read_exact(&mut body).await.map_err(McpError::Io)?;
+            let body = String::from_utf8(body)
+                .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned());
+            Ok(Some((body, Framing::ContentLength)))
+        }
+        _ => Ok(Some((headers, Framing::ContentLength))),
+    }
+}
+
+/// Whether a line is a `Name: value` header rather than a JSON message.
+fn is_header(line: &str) -> bool {
+    line.split_once(':').is_some_and(|(name, _)| {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    })
+}
+
+fn parse_content_length(header: &str) -> Option<usize> {
+    let (name, value) = header.split_once(':')?;
+    if !name.trim().eq_ignore_ascii_case("content-length") {
+        return None;
+    }
+    value.trim().parse().ok()
+}
+
 /// Serialize a value to a JSON line (with trailing newline).
 pub fn frame_message(value: &serde_json::Value) -> McpResult<String> {
     let mut json = serde_json::to_string(value).map_err(McpError::Json)?;
     json.push('\n');
     Ok(json)
 }
+
+/// Serialize a value with the given framing.
+pub fn frame_message_as(value: &serde_json::Value, framing: Framing) -> McpResult<String> {
+    match framing {
+        Framing::Newline => frame_message(value),
+        Framing::ContentLength => {
+            let json = serde_json::to_string(value).map_err(McpError::Json)?;
+            Ok(format!("Content-Length: {}\r\n\r\n{json}", json.len()))
+        }
+    }
+}
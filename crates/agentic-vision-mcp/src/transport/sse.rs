@@ -1,29 +1,64 @@
-//! SSE transport — HTTP server with auth, multi-tenant routing, and /health.
+//! SSE transport — HTTP server with auth, multi-tenant routing, /health,
+//! /metrics, the /sync endpoints used by `agentic-vision-mcp sync`, the
+//! /tenant endpoints for a tenant to export or erase their data, and
+//! scheduled or triggered backups (/admin/backup). In single-user mode,
+//! GET /mcp streams the server's notifications as server-sent events.
+//!
+//! Every request is logged in a span with its tenant and a correlation ID,
+//! taken from the X-Request-ID header or generated, and returned in the
+//! same header.
 
+#[cfg(feature = "sse")]
+use std::collections::HashSet;
 #[cfg(feature = "sse")]
 use std::path::PathBuf;
 #[cfg(feature = "sse")]
 use std::sync::Arc;
+#[cfg(feature = "sse")]
+use std::time::Instant;
 
 #[cfg(feature = "sse")]
 use axum::{
-    extract::State,
-    http::{HeaderMap, StatusCode},
+    extract::{DefaultBodyLimit, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     middleware,
     response::{IntoResponse, Json as AxumJson, Response},
     routing::{get, post},
-    Router,
+    Extension, Router,
 };
+#[cfg(feature = "sse")]
+use tracing::Instrument;
 
 #[cfg(feature = "sse")]
 use tokio::sync::Mutex;
 
+#[cfg(feature = "sse")]
+use crate::backup::{BackupConfig, BackupManifest, BackupSource};
 #[cfg(feature = "sse")]
 use crate::protocol::ProtocolHandler;
 #[cfg(feature = "sse")]
 use crate::session::tenant::VisionTenantRegistry;
 #[cfg(feature = "sse")]
-use crate::types::McpResult;
+use crate::session::VisionSessionManager;
+#[cfg(feature = "sse")]
+use crate::types::{McpError, McpResult};
+
+/// Largest request body accepted; sync pushes carry whole thumbnails.
+#[cfg(feature = "sse")]
+const MAX_BODY_BYTES: usize = 64 * 1024 * 1024;
+
+/// Header carrying the correlation ID of a request.
+#[cfg(feature = "sse")]
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest correlation ID taken from a client; others are replaced.
+#[cfg(feature = "sse")]
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Correlation ID of an HTTP request.
+#[cfg(feature = "sse")]
+#[derive(Clone)]
+struct CorrelationId(String);
 
 /// Server operating mode.
 #[cfg(feature = "sse")]
@@ -43,6 +78,10 @@ pub enum ServerMode {
 pub struct ServerState {
     pub token: Option<String>,
     pub mode: ServerMode,
+    pub backup: Option<BackupConfig>,
+    /// Held while a backup runs, so scheduled and triggered ones never
+    /// overlap.
+    backup_running: Mutex<()>,
 }
 
 /// SSE transport for web-based MCP clients.
@@ -59,6 +98,8 @@ impl SseTransport {
             state: Arc::new(ServerState {
                 token: None,
                 mode: ServerMode::Single(Arc::new(handler)),
+                backup: None,
+                backup_running: Mutex::new(()),
             }),
         }
     }
@@ -66,18 +107,55 @@ impl SseTransport {
     /// Create an SSE transport with full configuration.
     pub fn with_config(token: Option<String>, mode: ServerMode) -> Self {
         Self {
-            state: Arc::new(ServerState { token, mode }),
+            state: Arc::new(ServerState {
+                token,
+                mode,
+                backup: None,
+                backup_running: Mutex::new(()),
+            }),
         }
     }
 
+    /// Back up the served stores as `config` says, and on `POST
+    /// /admin/backup`.
+    pub fn with_backup(mut self, config: BackupConfig) -> Self {
+        Arc::get_mut(&mut self.state)
+            .expect("transport is not running yet")
+            .backup = Some(config);
+        self
+    }
+
     /// Run the HTTP server on the given address.
     pub async fn run(&self, addr: &str) -> McpResult<()> {
         let state = self.state.clone();
 
+        if let Some(interval) = state.backup.as_ref().and_then(|b| b.interval) {
+            let state = state.clone();
+            tokio::spawn(async move {
+                let start = tokio::time::Instant::now() + interval;
+                let mut ticker = tokio::time::interval_at(start, interval);
+                loop {
+                    ticker.tick().await;
+                    if let Err(e) = run_backup(&state).await {
+                        tracing::warn!("Scheduled backup failed: {e}");
+                    }
+                }
+            });
+        }
+
         let app = Router::new()
-            .route("/mcp", post(handle_request))
+            .route("/mcp", post(handle_request).get(handle_notifications))
+            .route("/sync/manifest", get(handle_sync_manifest))
+            .route("/sync/pull", post(handle_sync_pull))
+            .route("/sync/push", post(handle_sync_push))
+            .route("/tenant/export", get(handle_tenant_export))
+            .route("/tenant/erase", post(handle_tenant_erase))
+            .route("/admin/backup", post(handle_admin_backup))
+            .route("/metrics", get(handle_metrics))
+            .layer(DefaultBodyLimit::max(MAX_BODY_BYTES))
             .layer(middleware::from_fn_with_state(state.clone(), auth_layer))
             .route("/health", get(handle_health))
+            .layer(middleware::from_fn_with_state(state.clone(), trace_layer))
             .with_state(state);
 
         let listener = tokio::net::TcpListener::bind(addr)
@@ -94,6 +172,63 @@ impl SseTransport {
     }
 }
 
+/// Trace middleware — runs each request in a span with its tenant and
+/// correlation ID, logs its status and latency, and returns the ID in the
+/// X-Request-ID response header.
+#[cfg(feature = "sse")]
+async fn trace_layer(
+    State(state): State<Arc<ServerState>>,
+    mut request: axum::extract::Request,
+    next: middleware::Next,
+) -> Response {
+    let headers = request.headers();
+    let correlation_id = headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| {
+            !id.is_empty()
+                && id.len() <= MAX_REQUEST_ID_LEN
+                && id.chars().all(|c| c.is_ascii_graphic())
+        })
+        .map(String::from)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let tenant = match state.mode {
+        ServerMode::MultiTenant { .. } => headers
+            .get("x-user-id")
+            .and_then(|v| v.to_str().ok())
+            .map(String::from),
+        ServerMode::Single(_) => None,
+    };
+    let path = request.uri().path().to_string();
+    let span = tracing::info_span!(
+        "http",
+        correlation_id = %correlation_id,
+        tenant = tenant.as_deref(),
+        http_method = %request.method(),
+        path = %path
+    );
+    request
+        .extensions_mut()
+        .insert(CorrelationId(correlation_id.clone()));
+
+    let started = Instant::now();
+    let mut response = next.run(request).instrument(span.clone()).await;
+    let status = response.status().as_u16();
+    let latency_ms = started.elapsed().as_millis() as u64;
+    span.in_scope(|| {
+        // Health probes would drown everything else at info.
+        if path == "/health" {
+            tracing::debug!(status, latency_ms, "HTTP request finished");
+        } else {
+            tracing::info!(status, latency_ms, "HTTP request finished");
+        }
+    });
+    if let Ok(value) = HeaderValue::from_str(&correlation_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
 /// Auth middleware — checks Bearer token if configured.
 /// /health is handled by a separate route that bypasses this layer.
 #[cfg(feature = "sse")]
@@ -133,11 +268,75 @@ async fn auth_layer(
 #[cfg(feature = "sse")]
 async fn handle_request(
     State(state): State<Arc<ServerState>>,
+    Extension(correlation_id): Extension<CorrelationId>,
     headers: HeaderMap,
     AxumJson(body): AxumJson<serde_json::Value>,
 ) -> Result<AxumJson<serde_json::Value>, Response> {
     let handler = match &state.mode {
         ServerMode::Single(handler) => handler.clone(),
+        // Handlers last one request here, so a result cache would never hit.
+        ServerMode::MultiTenant { .. } => Arc::new(
+            ProtocolHandler::new(request_session(&state, &headers).await?).with_cache_capacity(0),
+        ),
+    };
+
+    let msg: crate::types::JsonRpcMessage = serde_json::from_value(body).map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            AxumJson(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": {
+                    "code": -32700,
+                    "message": "Parse error"
+                }
+            })),
+        )
+            .into_response()
+    })?;
+
+    match handler
+        .handle_correlated(msg, Some(&correlation_id.0))
+        .await
+    {
+        Some(response) => Ok(AxumJson(response)),
+        None => Ok(AxumJson(serde_json::Value::Null)),
+    }
+}
+
+/// Stream the handler's notifications as server-sent events, one JSON-RPC
+/// notification per event (single-user mode only).
+#[cfg(feature = "sse")]
+async fn handle_notifications(State(state): State<Arc<ServerState>>) -> Response {
+    use axum::response::sse::{Event, KeepAlive, Sse};
+    use tokio_stream::{wrappers::BroadcastStream, StreamExt};
+
+    let ServerMode::Single(handler) = &state.mode else {
+        return error_response(
+            StatusCode::NOT_FOUND,
+            -32601,
+            "Notifications are only streamed in single-user mode".to_string(),
+        );
+    };
+    let events = BroadcastStream::new(handler.subscribe_notifications()).filter_map(|n| {
+        // A lagging client misses notifications rather than the stream.
+        n.ok()
+            .map(|n| Ok::<_, std::convert::Infallible>(Event::default().data(n.to_string())))
+    });
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// The session a request addresses: the server's own in single-user mode,
+/// or the X-User-ID header's in multi-tenant mode.
+#[cfg(feature = "sse")]
+async fn request_session(
+    state: &ServerState,
+    headers: &HeaderMap,
+) -> Result<Arc<Mutex<VisionSessionManager>>, Response> {
+    match &state.mode {
+        ServerMode::Single(handler) => Ok(handler.session().clone()),
         ServerMode::MultiTenant {
             data_dir: _,
             model_path: _,
@@ -161,9 +360,8 @@ async fn handle_request(
                         .into_response()
                 })?;
 
-            let session = {
-                let mut reg = registry.lock().await;
-                reg.get_or_create(user_id).map_err(|e| {
+            let mut reg = registry.lock().await;
+            reg.get_or_create(user_id).map_err(|e| {
                     (
                         StatusCode::INTERNAL_SERVER_ERROR,
                         AxumJson(serde_json::json!({
@@ -176,32 +374,294 @@ async fn handle_request(
                         })),
                     )
                         .into_response()
-                })?
-            };
-
-            Arc::new(ProtocolHandler::new(session))
+            })
         }
+    }
+}
+
+/// Sync: the content addresses of every capture in the store.
+#[cfg(feature = "sse")]
+async fn handle_sync_manifest(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+) -> Result<AxumJson<serde_json::Value>, Response> {
+    let session = request_session(&state, &headers).await?;
+    let manifest = session.lock().await.sync_manifest();
+    Ok(AxumJson(serde_json::json!({ "captures": manifest })))
+}
+
+/// Sync: the captures with the requested content addresses.
+#[cfg(feature = "sse")]
+async fn handle_sync_pull(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    AxumJson(body): AxumJson<crate::sync::PullRequest>,
+) -> Result<AxumJson<serde_json::Value>, Response> {
+    let session = request_session(&state, &headers).await?;
+    let hashes: HashSet<String> = body.hashes.into_iter().collect();
+    let captures = session.lock().await.sync_export(&hashes);
+    Ok(AxumJson(serde_json::json!({ "captures": captures })))
+}
+
+/// Sync: merge pushed captures, skipping ones the store already has.
+#[cfg(feature = "sse")]
+async fn handle_sync_push(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    AxumJson(body): AxumJson<crate::sync::SyncCaptures>,
+) -> Result<AxumJson<serde_json::Value>, Response> {
+    let session = request_session(&state, &headers).await?;
+    let added = session
+        .lock()
+        .await
+        .sync_import(body.captures)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                AxumJson(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": null,
+                    "error": {
+                        "code": -32603,
+                        "message": format!("Failed to merge captures: {e}")
+                    }
+                })),
+            )
+                .into_response()
+        })?;
+    Ok(AxumJson(serde_json::json!({ "added": added })))
+}
+
+/// Body of `POST /tenant/erase`.
+#[cfg(feature = "sse")]
+#[derive(serde::Deserialize)]
+struct EraseRequest {
+    /// Must repeat the X-User-ID, so that no request erases data by
+    /// accident.
+    confirm: String,
+}
+
+/// The X-User-ID of a multi-tenant request, with the tenant registry.
+#[cfg(feature = "sse")]
+async fn tenant_request<'a>(
+    state: &'a ServerState,
+    headers: &'a HeaderMap,
+) -> Result<(&'a Arc<Mutex<VisionTenantRegistry>>, &'a str), Response> {
+    let ServerMode::MultiTenant { registry, .. } = &state.mode else {
+        return Err(error_response(
+            StatusCode::NOT_FOUND,
+            -32601,
+            "Tenant export and erasure are only available in multi-tenant mode".to_string(),
+        ));
     };
+    let user_id = headers
+        .get("x-user-id")
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| {
+            error_response(
+                StatusCode::BAD_REQUEST,
+                -32901,
+                "Missing X-User-ID header (required in multi-tenant mode)".to_string(),
+            )
+        })?;
+    Ok((registry, user_id))
+}
 
-    let msg: crate::types::JsonRpcMessage = serde_json::from_value(body).map_err(|_| {
-        (
+#[cfg(feature = "sse")]
+fn error_response(status: StatusCode, code: i32, message: String) -> Response {
+    (
+        status,
+        AxumJson(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": null,
+            "error": { "code": code, "message": message }
+        })),
+    )
+        .into_response()
+}
+
+#[cfg(feature = "sse")]
+fn failure_response(e: McpError) -> Response {
+    let status = match e {
+        McpError::InvalidParams(_) => StatusCode::BAD_REQUEST,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    error_response(status, e.code(), e.to_string())
+}
+
+/// Tenant: a zip of the caller's images and metadata.
+#[cfg(feature = "sse")]
+async fn handle_tenant_export(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+) -> Result<Response, Response> {
+    let (registry, user_id) = tenant_request(&state, &headers).await?;
+    let archive = registry
+        .lock()
+        .await
+        .export(user_id)
+        .await
+        .map_err(failure_response)?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{user_id}.zip\""),
+            ),
+        ],
+        archive,
+    )
+        .into_response())
+}
+
+/// Tenant: irreversibly erase the caller's data, answering with the audit
+/// record.
+#[cfg(feature = "sse")]
+async fn handle_tenant_erase(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    AxumJson(body): AxumJson<EraseRequest>,
+) -> Result<AxumJson<serde_json::Value>, Response> {
+    let (registry, user_id) = tenant_request(&state, &headers).await?;
+    if body.confirm != user_id {
+        return Err(error_response(
             StatusCode::BAD_REQUEST,
-            AxumJson(serde_json::json!({
-                "jsonrpc": "2.0",
-                "id": null,
-                "error": {
-                    "code": -32700,
-                    "message": "Parse error"
-                }
-            })),
-        )
-            .into_response()
+            -32602,
+            "'confirm' must repeat the X-User-ID to erase".to_string(),
+        ));
+    }
+    // Hold off backups, so none copies the store between its erasure and
+    // the purge of the backups.
+    let _running = state.backup_running.lock().await;
+    let record = registry
+        .lock()
+        .await
+        .erase(user_id)
+        .await
+        .map_err(failure_response)?;
+    let backups = match &state.backup {
+        Some(config) => {
+            crate::backup::purge_store(&config.dir, user_id).map_err(failure_response)?
+        }
+        None => Vec::new(),
+    };
+    Ok(AxumJson(
+        serde_json::json!({ "erased": record, "backups_purged": backups }),
+    ))
+}
+
+/// Back up every store the server serves: the loaded ones under their
+/// session locks, and in multi-tenant mode the other tenants' files too.
+#[cfg(feature = "sse")]
+async fn run_backup(state: &ServerState) -> McpResult<BackupManifest> {
+    let config = state.backup.as_ref().ok_or_else(|| {
+        McpError::InvalidParams("Backups are not configured; start with --backup-dir".to_string())
     })?;
+    let _running = state.backup_running.lock().await;
 
-    match handler.handle_message(msg).await {
-        Some(response) => Ok(AxumJson(response)),
-        None => Ok(AxumJson(serde_json::Value::Null)),
+    let sources = match &state.mode {
+        ServerMode::Single(handler) => {
+            let session = handler.session().clone();
+            let name = session.lock().await.file_path().file_stem().map_or_else(
+                || "vision".to_string(),
+                |s| s.to_string_lossy().into_owned(),
+            );
+            vec![BackupSource::Session { name, session }]
+        }
+        ServerMode::MultiTenant { registry, .. } => {
+            let registry = registry.lock().await;
+            let loaded = registry.loaded();
+            let mut sources = Vec::new();
+            let files: Vec<PathBuf> = match std::fs::read_dir(registry.data_dir()) {
+                Ok(entries) => entries
+                    .map(|entry| entry.map(|e| e.path()))
+                    .collect::<std::io::Result<_>>()?,
+                // No tenant has saved anything yet.
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+                Err(e) => return Err(e.into()),
+            };
+            for path in files {
+                if path.extension().is_none_or(|ext| ext != "avis") {
+                    continue;
+                }
+                let Some(name) = path.file_stem().map(|s| s.to_string_lossy().into_owned()) else {
+                    continue;
+                };
+                if !loaded.iter().any(|(user_id, _)| *user_id == name) {
+                    sources.push(BackupSource::File { name, path });
+                }
+            }
+            sources.extend(
+                loaded
+                    .into_iter()
+                    .map(|(name, session)| BackupSource::Session { name, session }),
+            );
+            sources
+        }
+    };
+    crate::backup::backup(sources, config).await
+}
+
+/// Admin: back up now, answering with the manifest of the backup.
+#[cfg(feature = "sse")]
+async fn handle_admin_backup(
+    State(state): State<Arc<ServerState>>,
+) -> Result<AxumJson<serde_json::Value>, Response> {
+    let manifest = run_backup(&state).await.map_err(failure_response)?;
+    Ok(AxumJson(serde_json::json!({ "backup": manifest })))
+}
+
+/// Prometheus metrics: tool result cache counts (single-user mode only).
+#[cfg(feature = "sse")]
+async fn handle_metrics(State(state): State<Arc<ServerState>>) -> Response {
+    let mut body = String::new();
+    if let ServerMode::Single(handler) = &state.mode {
+        let stats = handler.cache_stats().await;
+        for (name, kind, help, value) in [
+            (
+                "hits_total",
+                "counter",
+                "Tool calls answered from the cache.",
+                stats.hits,
+            ),
+            (
+                "misses_total",
+                "counter",
+                "Cacheable tool calls not in the cache.",
+                stats.misses,
+            ),
+            (
+                "evictions_total",
+                "counter",
+                "Results evicted to make room.",
+                stats.evictions,
+            ),
+            (
+                "invalidations_total",
+                "counter",
+                "Times a store write emptied the cache.",
+                stats.invalidations,
+            ),
+            (
+                "entries",
+                "gauge",
+                "Results in the cache.",
+                stats.entries as u64,
+            ),
+            (
+                "capacity",
+                "gauge",
+                "Most results the cache holds.",
+                stats.capacity as u64,
+            ),
+        ] {
+            body.push_str(&format!(
+                "# HELP avis_result_cache_{name} {help}\n# TYPE avis_result_cache_{name} {kind}\navis_result_cache_{name} {value}\n"
+            ));
+        }
     }
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response()
 }
 
 /// Health check endpoint — no auth required.
@@ -1,11 +1,15 @@
 //! Stdio transport — reads JSON-RPC from stdin, writes to stdout.
 
-use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use std::sync::Arc;
+
+use tokio::io::{AsyncWriteExt, BufReader};
+use tokio::sync::{broadcast, Mutex};
+use tokio::task::JoinSet;
 
 use crate::protocol::ProtocolHandler;
-use crate::types::{JsonRpcError, McpError, McpResult, RequestId, JSONRPC_VERSION};
+use crate::types::{JsonRpcError, JsonRpcMessage, McpError, McpResult, RequestId, JSONRPC_VERSION};
 
-use super::framing;
+use super::framing::{self, Framing};
 
 /// Stdio transport for desktop MCP clients.
 pub struct StdioTransport {
@@ -18,61 +22,45 @@ impl StdioTransport {
     }
 
     /// Run the transport loop — reads from stdin, writes to stdout.
+    /// Messages may be newline-delimited or `Content-Length` framed, and
+    /// each response is framed like its request.
+    ///
+    /// Requests run as their own tasks, so the loop keeps reading (and can
+    /// take a cancellation) while a slow tool works; responses may therefore
+    /// arrive out of order. `initialize` and notifications are handled in
+    /// order, inline.
+    ///
+    /// Notifications from the handler are written as they are sent, framed
+    /// like the last message read; none are sent before the first.
     pub async fn run(&self) -> McpResult<()> {
-        let stdin = tokio::io::stdin();
-        let mut stdout = tokio::io::stdout();
-        let mut reader = BufReader::new(stdin);
-        let mut line = String::new();
-        let mut content_length: Option<usize> = None;
-        let mut framed_output = false;
+        let stdout = Arc::new(Mutex::new(tokio::io::stdout()));
+        let mut requests = JoinSet::new();
+        let mut reader = BufReader::new(tokio::io::stdin());
+        let last_framing = Arc::new(std::sync::Mutex::new(None));
+        let forwarder = tokio::spawn(forward_notifications(
+            self.handler.subscribe_notifications(),
+            stdout.clone(),
+            last_framing.clone(),
+        ));
 
         tracing::info!("Stdio transport started");
 
-        loop {
-            line.clear();
-            let bytes_read = reader.read_line(&mut line).await.map_err(McpError::Io)?;
-
-            if bytes_read == 0 {
-                tracing::info!("EOF on stdin, shutting down");
-                break;
-            }
-
-            let trimmed = line.trim_end_matches(['\r', '\n']);
-
-            let lower = trimmed.to_ascii_lowercase();
-            if lower.starts_with("content-length:") {
-                let rest = trimmed.split_once(':').map(|(_, rhs)| rhs).unwrap_or("");
-                content_length = rest.trim().parse::<usize>().ok();
-                if content_length.is_some() {
-                    framed_output = true;
-                }
-                continue;
-            }
-
-            if let Some(n) = content_length {
-                // Skip optional header separator line.
-                if trimmed.is_empty() {
-                    let mut body = vec![0u8; n];
-                    reader.read_exact(&mut body).await.map_err(McpError::Io)?;
-                    let payload = String::from_utf8_lossy(&body).to_string();
-
-                    self.process_message(&payload, framed_output, &mut stdout)
-                        .await?;
-                    content_length = None;
-                    continue;
-                }
-
-                // Ignore any remaining header lines (e.g. Content-Type).
-                continue;
-            }
-
-            if trimmed.is_empty() {
-                continue;
+        while let Some((payload, wire)) = framing::read_message(&mut reader).await? {
+            *last_framing.lock().expect("framing lock poisoned") = Some(wire);
+            self.process_message(&payload, wire, &stdout, &mut requests)
+                .await?;
+            // Reap finished requests so the set does not grow unbounded.
+            while let Some(done) = requests.try_join_next() {
+                log_request_outcome(done);
             }
+        }
+        tracing::info!("EOF on stdin, shutting down");
 
-            self.process_message(trimmed, framed_output, &mut stdout)
-                .await?;
+        // Let requests still running answer before the process exits.
+        while let Some(done) = requests.join_next().await {
+            log_request_outcome(done);
         }
+        forwarder.abort();
 
         Ok(())
     }
@@ -80,13 +68,26 @@ impl StdioTransport {
     async fn process_message(
         &self,
         input: &str,
-        framed_output: bool,
-        stdout: &mut tokio::io::Stdout,
+        wire: Framing,
+        stdout: &Arc<Mutex<tokio::io::Stdout>>,
+        requests: &mut JoinSet<McpResult<()>>,
     ) -> McpResult<()> {
         match framing::parse_message(input.trim()) {
+            // `initialize` stays inline so `initialized` cannot overtake it.
+            Ok(JsonRpcMessage::Request(req)) if req.method != "initialize" => {
+                let msg = JsonRpcMessage::Request(req);
+                let handler = self.handler.clone();
+                let stdout = stdout.clone();
+                requests.spawn(async move {
+                    match handler.handle_message(msg).await {
+                        Some(response) => write_response(&stdout, &response, wire).await,
+                        None => Ok(()),
+                    }
+                });
+            }
             Ok(msg) => {
                 if let Some(response) = self.handler.handle_message(msg).await {
-                    self.write_response(stdout, &response, framed_output).await?;
+                    write_response(stdout, &response, wire).await?;
                 }
             }
             Err(e) => {
@@ -102,39 +103,55 @@ impl StdioTransport {
                 };
                 let value = serde_json::to_value(error_response)
                     .map_err(|err| McpError::InternalError(err.to_string()))?;
-                self.write_response(stdout, &value, framed_output).await?;
+                write_response(stdout, &value, wire).await?;
             }
         }
         Ok(())
     }
+}
 
-    async fn write_response(
-        &self,
-        stdout: &mut tokio::io::Stdout,
-        response: &serde_json::Value,
-        framed_output: bool,
-    ) -> McpResult<()> {
-        if framed_output {
-            let json = serde_json::to_string(response).map_err(McpError::Json)?;
-            let header = format!("Content-Length: {}\r\n\r\n", json.len());
-            stdout
-                .write_all(header.as_bytes())
-                .await
-                .map_err(McpError::Io)?;
-            stdout
-                .write_all(json.as_bytes())
-                .await
-                .map_err(McpError::Io)?;
-            stdout.flush().await.map_err(McpError::Io)?;
-            return Ok(());
-        }
+fn log_request_outcome(outcome: Result<McpResult<()>, tokio::task::JoinError>) {
+    match outcome {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => tracing::error!("Failed to write response: {e}"),
+        Err(e) => tracing::error!("Request task failed: {e}"),
+    }
+}
 
-        let framed = framing::frame_message(response)?;
-        stdout
-            .write_all(framed.as_bytes())
-            .await
-            .map_err(McpError::Io)?;
-        stdout.flush().await.map_err(McpError::Io)?;
-        Ok(())
+/// Write the handler's notifications to stdout until it goes away.
+async fn forward_notifications(
+    mut notifications: broadcast::Receiver<serde_json::Value>,
+    stdout: Arc<Mutex<tokio::io::Stdout>>,
+    framing: Arc<std::sync::Mutex<Option<Framing>>>,
+) {
+    loop {
+        match notifications.recv().await {
+            Ok(notification) => {
+                let wire = *framing.lock().expect("framing lock poisoned");
+                let Some(wire) = wire else { continue };
+                if let Err(e) = write_response(&stdout, &notification, wire).await {
+                    tracing::error!("Failed to write notification: {e}");
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                tracing::warn!("Dropped {missed} notification(s) for a slow client");
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        }
     }
 }
+
+async fn write_response(
+    stdout: &Mutex<tokio::io::Stdout>,
+    response: &serde_json::Value,
+    wire: Framing,
+) -> McpResult<()> {
+    let framed = framing::frame_message_as(response, wire)?;
+    let mut stdout = stdout.lock().await;
+    stdout
+        .write_all(framed.as_bytes())
+        .await
+        .map_err(McpError::Io)?;
+    stdout.flush().await.map_err(McpError::Io)?;
+    Ok(())
+}
@@ -3,7 +3,11 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-pub const MCP_VERSION: &str = "2024-11-05";
+/// Latest MCP protocol version the server speaks.
+pub const MCP_VERSION: &str = "2025-06-18";
+/// MCP protocol versions the server speaks, newest first. 2025-03-26 is
+/// left out: it requires JSON-RPC batches, which the server does not read.
+pub const SUPPORTED_VERSIONS: [&str; 2] = [MCP_VERSION, "2024-11-05"];
 pub const SERVER_NAME: &str = "agentic-vision-mcp";
 pub const SERVER_VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -97,7 +101,7 @@ impl ServerCapabilities {
             }),
             resources: Some(ResourcesCapability {
                 subscribe: true,
-                list_changed: false,
+                list_changed: true,
             }),
             tools: Some(ToolsCapability {
                 list_changed: false,
@@ -18,6 +18,8 @@ pub mod mcp_error_codes {
     pub const RESOURCE_NOT_FOUND: i32 = -32802;
     pub const TOOL_NOT_FOUND: i32 = -32803;
     pub const PROMPT_NOT_FOUND: i32 = -32804;
+    pub const TOOL_TIMEOUT: i32 = -32805;
+    pub const IDEMPOTENCY_CONFLICT: i32 = -32806;
     pub const CAPTURE_NOT_FOUND: i32 = -32850;
     pub const SESSION_NOT_FOUND: i32 = -32851;
     pub const VISION_ERROR: i32 = -32852;
@@ -51,6 +53,14 @@ pub enum McpError {
     #[error("Request cancelled")]
     RequestCancelled,
 
+    #[error("Tool {tool} timed out after {ms} ms")]
+    ToolTimeout { tool: String, ms: u64 },
+
+    /// An idempotency key reused with other arguments, or while its first
+    /// call is still running.
+    #[error("Idempotency conflict: {0}")]
+    IdempotencyConflict(String),
+
     #[error("Content too large: {size} bytes exceeds {max} bytes")]
     ContentTooLarge { size: usize, max: usize },
 
@@ -101,6 +111,8 @@ impl McpError {
             McpError::InvalidParams(_) => INVALID_PARAMS,
             McpError::InternalError(_) => INTERNAL_ERROR,
             McpError::RequestCancelled => REQUEST_CANCELLED,
+            McpError::ToolTimeout { .. } => TOOL_TIMEOUT,
+            McpError::IdempotencyConflict(_) => IDEMPOTENCY_CONFLICT,
             McpError::ContentTooLarge { .. } => CONTENT_TOO_LARGE,
             McpError::ResourceNotFound(_) => RESOURCE_NOT_FOUND,
             McpError::ToolNotFound(_) => TOOL_NOT_FOUND,
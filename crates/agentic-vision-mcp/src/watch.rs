@@ -0,0 +1,129 @@
+//! Ingesting images that land in watched folders (`--watch-dir`).
+//!
+//! New images are stored as file captures, labeled after the folder they
+//! landed in (see [`agentic_vision::infer_labels`]), and go through the
+//! capture pipelines like any other capture. Each one is announced to the
+//! client with `notifications/resources/list_changed` and a
+//! `notifications/message` naming the capture.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde_json::json;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use agentic_vision::watch::DEFAULT_SETTLE;
+use agentic_vision::{FolderWatcher, Provenance};
+
+use crate::protocol::ProtocolHandler;
+use crate::session::manager::CaptureResult;
+use crate::types::McpResult;
+
+/// How often the watched folders are checked for settled files.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Folders to ingest images from.
+#[derive(Debug, Clone)]
+pub struct WatchConfig {
+    pub dirs: Vec<PathBuf>,
+    /// Also watch the folders under them.
+    pub recursive: bool,
+    /// How long a file must go unchanged before it is ingested.
+    pub settle: Duration,
+}
+
+impl WatchConfig {
+    pub fn new(dirs: Vec<PathBuf>) -> Self {
+        Self {
+            dirs,
+            recursive: true,
+            settle: DEFAULT_SETTLE,
+        }
+    }
+}
+
+/// Store the image at `path` with `labels` in the handler's session and
+/// announce it.
+pub async fn ingest(
+    handler: &ProtocolHandler,
+    path: &Path,
+    labels: Vec<String>,
+) -> McpResult<CaptureResult> {
+    let provenance = Provenance {
+        tool: Some("watch".to_string()),
+        ..Provenance::default()
+    };
+    let result = handler.session().lock().await.capture(
+        "file",
+        &path.to_string_lossy(),
+        None,
+        labels.clone(),
+        None,
+        false,
+        provenance,
+        &CancellationToken::new(),
+    )?;
+    tracing::info!(
+        "Ingested {} as capture {}",
+        path.display(),
+        result.capture_id
+    );
+    handler.notify("notifications/resources/list_changed", json!({}));
+    handler.notify(
+        "notifications/message",
+        json!({
+            "level": "info",
+            "logger": "watch",
+            "data": {
+                "event": "capture",
+                "capture_id": result.capture_id,
+                "uri": format!("avis://capture/{}", result.capture_id),
+                "path": path.to_string_lossy(),
+                "labels": labels,
+            }
+        }),
+    );
+    Ok(result)
+}
+
+/// Ingest every settled image `watcher` reports until `cancel` fires.
+/// Images that fail to load are logged and skipped.
+pub async fn run(
+    handler: ProtocolHandler,
+    mut watcher: FolderWatcher,
+    settle: Duration,
+    cancel: CancellationToken,
+) {
+    let mut ticker = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => return,
+            _ = ticker.tick() => {}
+        }
+        for path in watcher.poll(settle) {
+            let labels = watcher.labels_for(&path);
+            if let Err(e) = ingest(&handler, &path, labels).await {
+                tracing::warn!("Failed to ingest {}: {e}", path.display());
+            }
+        }
+    }
+}
+
+/// Start watching the folders of `config` in a background task.
+pub fn spawn(
+    handler: &ProtocolHandler,
+    config: &WatchConfig,
+    cancel: CancellationToken,
+) -> McpResult<JoinHandle<()>> {
+    let watcher = FolderWatcher::new(&config.dirs, config.recursive)?;
+    for dir in watcher.roots() {
+        tracing::info!("Watching {} for new images", dir.display());
+    }
+    Ok(tokio::spawn(run(
+        handler.clone(),
+        watcher,
+        config.settle,
+        cancel,
+    )))
+}
@@ -7,6 +7,7 @@ use std::sync::Arc;
 
 use serde_json::{json, Value};
 use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
 
 use agentic_vision_mcp::protocol::ProtocolHandler;
 use agentic_vision_mcp::session::VisionSessionManager;
@@ -278,7 +279,16 @@ async fn test_05_missing_directory() {
             let png = make_png(10, 10);
             let b64 = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &png);
             session
-                .capture("base64", &b64, Some("image/png"), vec![], None, false)
+                .capture(
+                    "base64",
+                    &b64,
+                    Some("image/png"),
+                    vec![],
+                    None,
+                    false,
+                    agentic_vision::Provenance::default(),
+                    &CancellationToken::new(),
+                )
                 .unwrap();
             session.end_session().unwrap();
             assert!(
@@ -451,8 +461,8 @@ async fn test_09_future_protocol_version() {
     );
     let result = &resp["result"];
     assert_eq!(
-        result["protocolVersion"], "2024-11-05",
-        "Server should respond with its own protocol version"
+        result["protocolVersion"], MCP_VERSION,
+        "Server should respond with its latest protocol version"
     );
     assert!(
         result["serverInfo"]["name"]
@@ -533,6 +543,8 @@ async fn test_11_rapid_reconnect() {
                 vec![format!("round-{i}")],
                 None,
                 false,
+                agentic_vision::Provenance::default(),
+                &CancellationToken::new(),
             )
             .unwrap();
         session.end_session().unwrap();
@@ -687,7 +699,16 @@ async fn test_15_embedding_dimension() {
         let b64 = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &png_data);
 
         let result = session
-            .capture("base64", &b64, Some("image/png"), vec![], None, false)
+            .capture(
+                "base64",
+                &b64,
+                Some("image/png"),
+                vec![],
+                None,
+                false,
+                agentic_vision::Provenance::default(),
+                &CancellationToken::new(),
+            )
             .unwrap();
 
         assert_eq!(
@@ -862,3 +883,2049 @@ async fn test_bonus_compare_self() {
 
     println!("TEST BONUS — Compare Self: PASS");
 }
+
+/// Bonus: vision_compare_to_live screenshots the capture's URL through Cortex
+#[cfg(unix)]
+#[tokio::test]
+async fn test_bonus_compare_to_live() {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let dir = tempfile::tempdir().unwrap();
+    let session = arc_session(&dir);
+    let handler = ProtocolHandler::new(session);
+
+    send_unwrap(&handler, init_request()).await;
+
+    let stored =
+        base64::Engine::encode(&base64::engine::general_purpose::STANDARD, make_png(64, 64));
+    let cap_resp = capture_image(&handler, &stored, vec![], Some("https://shop.com/p/1")).await;
+    let cap_text = cap_resp["result"]["content"][0]["text"].as_str().unwrap();
+    let cap: Value = serde_json::from_str(cap_text).unwrap();
+    let id = cap["capture_id"].as_u64().unwrap();
+
+    // A fake Cortex daemon answering one perceive request with a white page.
+    let socket = dir.path().join("cortex.sock");
+    let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+    std::env::set_var("CORTEX_SOCKET", &socket);
+    let daemon = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (read, mut write) = stream.into_split();
+        let mut line = String::new();
+        BufReader::new(read).read_line(&mut line).await.unwrap();
+        let req: Value = serde_json::from_str(&line).unwrap();
+        let white = image::DynamicImage::ImageRgb8(image::RgbImage::from_pixel(
+            64,
+            64,
+            image::Rgb([255, 255, 255]),
+        ));
+        let mut png = Vec::new();
+        white
+            .write_with_encoder(image::codecs::png::PngEncoder::new(&mut png))
+            .unwrap();
+        let resp = json!({
+            "id": req["id"],
+            "result": {
+                "url": req["params"]["url"],
+                "final_url": req["params"]["url"],
+                "screenshot": base64::Engine::encode(&base64::engine::general_purpose::STANDARD, png),
+            }
+        });
+        write
+            .write_all(format!("{resp}\n").as_bytes())
+            .await
+            .unwrap();
+        req
+    });
+
+    let msg = mcp_request(
+        2,
+        "tools/call",
+        json!({
+            "name": "vision_compare_to_live",
+            "arguments": { "capture_id": id, "save": true }
+        }),
+    );
+    let resp = send_unwrap(&handler, msg).await;
+    let req = daemon.await.unwrap();
+    assert_eq!(req["method"], "perceive");
+    assert_eq!(req["params"]["url"], "https://shop.com/p/1");
+    assert_eq!(req["params"]["screenshot"], true);
+
+    let text = resp["result"]["content"][0]["text"].as_str().unwrap();
+    let parsed: Value = serde_json::from_str(text).unwrap();
+    assert_eq!(parsed["url"], "https://shop.com/p/1");
+    assert!(parsed["live_capture_id"].as_u64().unwrap() > id);
+    // Black against white: every pixel changed.
+    assert!(parsed["pixel_diff_ratio"].as_f64().unwrap() > 0.99);
+
+    // With no daemon listening the tool reports an error result.
+    let _ = std::fs::remove_file(&socket);
+    let msg = mcp_request(
+        3,
+        "tools/call",
+        json!({
+            "name": "vision_compare_to_live",
+            "arguments": { "capture_id": id }
+        }),
+    );
+    let resp = send_unwrap(&handler, msg).await;
+    assert_eq!(resp["result"]["isError"], true);
+
+    println!("TEST BONUS — Compare To Live: PASS");
+}
+
+/// Bonus: vision_snapshot restore drops captures made after the snapshot
+#[tokio::test]
+async fn test_bonus_snapshot_restore() {
+    let dir = tempfile::tempdir().unwrap();
+    let session = arc_session(&dir);
+    let handler = ProtocolHandler::new(session.clone());
+
+    send_unwrap(&handler, init_request()).await;
+
+    let snapshot = |id: i64, args: Value| {
+        mcp_request(
+            id,
+            "tools/call",
+            json!({ "name": "vision_snapshot", "arguments": args }),
+        )
+    };
+    let result_json = |resp: &Value| -> Value {
+        let text = resp["result"]["content"][0]["text"].as_str().unwrap();
+        serde_json::from_str(text).unwrap()
+    };
+
+    let png_data = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, tiny_png());
+    capture_image(&handler, &png_data, vec![], None).await;
+
+    let resp = send_unwrap(
+        &handler,
+        snapshot(2, json!({ "action": "create", "name": "clean" })),
+    )
+    .await;
+    assert_eq!(result_json(&resp)["capture_count"], 1, "{resp}");
+
+    capture_image(&handler, &png_data, vec![], None).await;
+    capture_image(&handler, &png_data, vec![], None).await;
+    assert_eq!(session.lock().await.store().count(), 3);
+
+    let resp = send_unwrap(
+        &handler,
+        snapshot(3, json!({ "action": "create", "name": "clean" })),
+    )
+    .await;
+    assert!(
+        resp.get("error").is_some() || resp["result"]["isError"] == true,
+        "Duplicate snapshot name should fail: {resp}"
+    );
+
+    let resp = send_unwrap(&handler, snapshot(4, json!({ "action": "list" }))).await;
+    assert_eq!(result_json(&resp)["count"], 1, "{resp}");
+
+    let resp = send_unwrap(
+        &handler,
+        snapshot(5, json!({ "action": "restore", "name": "clean" })),
+    )
+    .await;
+    let restored = result_json(&resp);
+    assert_eq!(restored["captures_removed"], 2, "{resp}");
+    assert_eq!(restored["capture_count"], 1, "{resp}");
+    assert_eq!(session.lock().await.store().count(), 1);
+
+    let resp = send_unwrap(
+        &handler,
+        snapshot(6, json!({ "action": "restore", "name": "missing" })),
+    )
+    .await;
+    assert!(
+        resp.get("error").is_some() || resp["result"]["isError"] == true,
+        "Unknown snapshot should fail: {resp}"
+    );
+
+    println!("TEST BONUS — Snapshot Restore: PASS");
+}
+
+/// Bonus: hierarchical labels match by prefix and survive renames as aliases
+#[tokio::test]
+async fn test_bonus_label_hierarchy() {
+    let dir = tempfile::tempdir().unwrap();
+    let session = arc_session(&dir);
+    let handler = ProtocolHandler::new(session);
+
+    send_unwrap(&handler, init_request()).await;
+
+    let call = |id: i64, name: &str, args: Value| {
+        mcp_request(id, "tools/call", json!({ "name": name, "arguments": args }))
+    };
+    let result_json = |resp: &Value| -> Value {
+        let text = resp["result"]["content"][0]["text"].as_str().unwrap();
+        serde_json::from_str(text).unwrap()
+    };
+
+    let png_data = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, tiny_png());
+    capture_image(&handler, &png_data, vec!["checkout/payment"], None).await;
+    capture_image(&handler, &png_data, vec!["checkout/ cart "], None).await;
+    capture_image(&handler, &png_data, vec!["home"], None).await;
+
+    let resp = send_unwrap(
+        &handler,
+        call(2, "vision_query", json!({ "labels": ["checkout"] })),
+    )
+    .await;
+    assert_eq!(result_json(&resp)["total"], 2, "{resp}");
+
+    let resp = send_unwrap(
+        &handler,
+        call(
+            3,
+            "vision_labels",
+            json!({ "action": "rename", "from": "checkout", "to": "app/checkout" }),
+        ),
+    )
+    .await;
+    assert_eq!(result_json(&resp)["captures_relabeled"], 2, "{resp}");
+
+    // The old name is now an alias, for queries and new captures alike.
+    capture_image(&handler, &png_data, vec!["checkout/payment"], None).await;
+    let resp = send_unwrap(
+        &handler,
+        call(4, "vision_query", json!({ "labels": ["checkout/payment"] })),
+    )
+    .await;
+    let found = result_json(&resp);
+    assert_eq!(found["total"], 2, "{resp}");
+    assert_eq!(
+        found["observations"][1]["labels"][0],
+        "app/checkout/payment"
+    );
+
+    let resp = send_unwrap(
+        &handler,
+        call(
+            5,
+            "vision_labels",
+            json!({ "action": "list", "prefix": "app" }),
+        ),
+    )
+    .await;
+    let listed = result_json(&resp);
+    assert_eq!(listed["labels"]["app"], 3, "{resp}");
+    assert_eq!(listed["labels"]["app/checkout/cart"], 1, "{resp}");
+    assert!(listed["labels"].get("home").is_none(), "{resp}");
+    assert_eq!(listed["aliases"]["checkout"], "app/checkout", "{resp}");
+
+    let resp = send_unwrap(
+        &handler,
+        mcp_request(6, "resources/read", json!({ "uri": "avis://stats" })),
+    )
+    .await;
+    let text = resp["result"]["contents"][0]["text"].as_str().unwrap();
+    let stats: Value = serde_json::from_str(text).unwrap();
+    assert_eq!(stats["labels"]["app/checkout"], 3, "{stats}");
+
+    println!("TEST BONUS — Label Hierarchy: PASS");
+}
+
+/// Bonus: vision_query text search over descriptions, labels and captions
+#[tokio::test]
+async fn test_bonus_query_text() {
+    let dir = tempfile::tempdir().unwrap();
+    let session = arc_session(&dir);
+    let handler = ProtocolHandler::new(session);
+
+    send_unwrap(&handler, init_request()).await;
+
+    let png_data = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, tiny_png());
+    capture_image(
+        &handler,
+        &png_data,
+        vec!["app/checkout"],
+        Some("Payment form with a red error banner"),
+    )
+    .await;
+    capture_image(&handler, &png_data, vec!["app/home"], Some("Landing page")).await;
+
+    let query = |id: i64, text: &str| {
+        mcp_request(
+            id,
+            "tools/call",
+            json!({ "name": "vision_query", "arguments": { "text": text } }),
+        )
+    };
+    let total = |resp: &Value| -> u64 {
+        let text = resp["result"]["content"][0]["text"].as_str().unwrap();
+        let parsed: Value = serde_json::from_str(text).unwrap();
+        parsed["total"].as_u64().unwrap()
+    };
+
+    assert_eq!(
+        total(&send_unwrap(&handler, query(2, "ERROR banner")).await),
+        1
+    );
+    assert_eq!(
+        total(&send_unwrap(&handler, query(3, "checkout error")).await),
+        1
+    );
+    assert_eq!(total(&send_unwrap(&handler, query(4, "page")).await), 1);
+    assert_eq!(total(&send_unwrap(&handler, query(5, "app")).await), 2);
+    assert_eq!(total(&send_unwrap(&handler, query(6, "missing")).await), 0);
+
+    println!("TEST BONUS — Query Text: PASS");
+}
+
+/// Bonus: vision_find_element filters detected elements and ranks look-alikes
+#[tokio::test]
+async fn test_bonus_find_element() {
+    use agentic_vision::{
+        AvisWriter, CaptureSource, ObservationMeta, Rect, UiElement, VisualMemoryStore,
+        VisualObservation,
+    };
+
+    let element = |kind: &str, color: &str, embedding: Vec<f32>| UiElement {
+        kind: kind.to_string(),
+        bbox: Rect {
+            x: 10,
+            y: 10,
+            w: 80,
+            h: 24,
+        },
+        confidence: 0.9,
+        color: color.to_string(),
+        embedding,
+    };
+    let capture = |elements: Vec<UiElement>| VisualObservation {
+        id: 0,
+        timestamp: 1_700_000_000,
+        session_id: 1,
+        source: CaptureSource::Clipboard,
+        embedding: Vec::new(),
+        thumbnail: tiny_png(),
+        metadata: ObservationMeta {
+            width: 1,
+            height: 1,
+            original_width: 1,
+            original_height: 1,
+            labels: Vec::new(),
+            description: None,
+            caption: None,
+        },
+        memory_link: None,
+        fingerprint: Vec::new(),
+        elements,
+        ocr: None,
+        provenance: None,
+    };
+
+    // Detection needs a model, so write a store with elements up front.
+    let dir = tempfile::tempdir().unwrap();
+    let mut store = VisualMemoryStore::new(512);
+    store.add(capture(vec![
+        element("button", "red", vec![1.0, 0.0]),
+        element("input", "white", vec![0.0, 1.0]),
+    ]));
+    store.add(capture(vec![element("button", "blue", vec![0.9, 0.1])]));
+    store.add(capture(vec![element("dialog", "red", vec![0.0, 1.0])]));
+    AvisWriter::write_to_file(&store, &dir.path().join("test.avis")).unwrap();
+
+    let handler = ProtocolHandler::new(arc_session(&dir));
+    send_unwrap(&handler, init_request()).await;
+
+    let find = |id: i64, args: Value| {
+        mcp_request(
+            id,
+            "tools/call",
+            json!({ "name": "vision_find_element", "arguments": args }),
+        )
+    };
+    let result_json = |resp: &Value| -> Value {
+        let text = resp["result"]["content"][0]["text"].as_str().unwrap();
+        serde_json::from_str(text).unwrap()
+    };
+
+    let resp = send_unwrap(&handler, find(2, json!({ "query": "red Cancel button" }))).await;
+    let found = result_json(&resp);
+    assert_eq!(found["total"], 1, "{resp}");
+    assert_eq!(found["matches"][0]["capture_id"], 1);
+    assert_eq!(found["ignored_terms"], json!(["cancel"]));
+
+    let resp = send_unwrap(&handler, find(3, json!({ "query": "buttons" }))).await;
+    assert_eq!(result_json(&resp)["total"], 2, "{resp}");
+
+    let resp = send_unwrap(
+        &handler,
+        find(
+            4,
+            json!({ "like": { "capture_id": 1, "element": 0 }, "max_results": 1 }),
+        ),
+    )
+    .await;
+    let found = result_json(&resp);
+    assert_eq!(found["matches"][0]["capture_id"], 2, "{resp}");
+
+    let resp = send_unwrap(
+        &handler,
+        find(5, json!({ "like": { "capture_id": 1, "element": 5 } })),
+    )
+    .await;
+    assert!(
+        resp.get("error").is_some() || resp["result"]["isError"] == true,
+        "Unknown element should fail: {resp}"
+    );
+
+    println!("TEST BONUS — Find Element: PASS");
+}
+
+/// Bonus: vision_similar with layout_weight ranks by layout and colour
+#[tokio::test]
+async fn test_bonus_similar_layout() {
+    let dir = tempfile::tempdir().unwrap();
+    let session = arc_session(&dir);
+    let handler = ProtocolHandler::new(session);
+
+    send_unwrap(&handler, init_request()).await;
+
+    // A white page under a header bar of the given colour and height.
+    let page = |header: [u8; 3], header_rows: u32| {
+        let img = image::RgbImage::from_fn(128, 128, |_, y| {
+            image::Rgb(if y < header_rows {
+                header
+            } else {
+                [250, 250, 250]
+            })
+        });
+        let mut buf = Vec::new();
+        let encoder = image::codecs::png::PngEncoder::new(&mut buf);
+        image::DynamicImage::ImageRgb8(img)
+            .write_with_encoder(encoder)
+            .unwrap();
+        base64::Engine::encode(&base64::engine::general_purpose::STANDARD, buf)
+    };
+    let mut ids = Vec::new();
+    for (header, rows) in [
+        ([20, 40, 160], 32),
+        ([20, 40, 160], 96),
+        ([160, 20, 20], 32),
+    ] {
+        let resp = capture_image(&handler, &page(header, rows), vec![], None).await;
+        let text = resp["result"]["content"][0]["text"].as_str().unwrap();
+        let cap: Value = serde_json::from_str(text).unwrap();
+        ids.push(cap["capture_id"].as_u64().unwrap());
+    }
+
+    let similar = |id: i64, layout_weight: f64| {
+        mcp_request(
+            id,
+            "tools/call",
+            json!({
+                "name": "vision_similar",
+                "arguments": {
+                    "capture_id": ids[0],
+                    "min_similarity": 0.0,
+                    "layout_weight": layout_weight
+                }
+            }),
+        )
+    };
+
+    // Fallback embeddings carry no meaning, so layout decides: the
+    // recoloured page with the same header outranks the taller header.
+    let resp = send_unwrap(&handler, similar(2, 1.0)).await;
+    let text = resp["result"]["content"][0]["text"].as_str().unwrap();
+    let parsed: Value = serde_json::from_str(text).unwrap();
+    assert_eq!(parsed["total"], 2);
+    assert_eq!(parsed["matches"][0]["id"], ids[2], "{parsed}");
+
+    let resp = send_unwrap(&handler, similar(3, 1.5)).await;
+    assert!(
+        resp.get("error").is_some() || resp["result"]["isError"] == true,
+        "{resp}"
+    );
+
+    println!("TEST BONUS — Similar Layout: PASS");
+}
+
+/// Bonus: a tool call over its time limit fails with a timeout error
+#[tokio::test]
+async fn test_bonus_tool_timeout() {
+    use agentic_vision_mcp::config::ToolTimeouts;
+    use std::time::Duration;
+
+    let dir = tempfile::tempdir().unwrap();
+    let session = arc_session(&dir);
+    let mut timeouts = ToolTimeouts::default();
+    timeouts.set("vision_query", Some(Duration::from_millis(50)));
+    let handler = ProtocolHandler::new(session.clone()).with_tool_timeouts(timeouts);
+
+    send_unwrap(&handler, init_request()).await;
+
+    // Holding the session keeps the tool waiting past its limit.
+    let guard = session.lock().await;
+    let msg = mcp_request(
+        2,
+        "tools/call",
+        json!({ "name": "vision_query", "arguments": {} }),
+    );
+    let resp = send_unwrap(&handler, msg).await;
+    assert_eq!(
+        resp["error"]["code"],
+        mcp_error_codes::TOOL_TIMEOUT,
+        "{resp}"
+    );
+    drop(guard);
+
+    // Other tools keep the default limit.
+    let msg = mcp_request(
+        3,
+        "tools/call",
+        json!({ "name": "vision_similar", "arguments": { "embedding": [0.0] } }),
+    );
+    assert!(send_unwrap(&handler, msg).await.get("result").is_some());
+
+    println!("TEST BONUS — Tool Timeout: PASS");
+}
+
+/// Bonus: notifications/cancelled aborts an in-flight request without a response
+#[tokio::test]
+async fn test_bonus_cancel_request() {
+    let dir = tempfile::tempdir().unwrap();
+    let session = arc_session(&dir);
+    let handler = ProtocolHandler::new(session.clone());
+
+    send_unwrap(&handler, init_request()).await;
+
+    let guard = session.lock().await;
+    let pending = tokio::spawn({
+        let handler = handler.clone();
+        let msg = mcp_request(
+            7,
+            "tools/call",
+            json!({ "name": "vision_query", "arguments": {} }),
+        );
+        async move { send(&handler, msg).await }
+    });
+    // Give the request time to register before cancelling it.
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+    let cancel = json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/cancelled",
+        "params": { "requestId": 7, "reason": "user aborted" }
+    });
+    assert!(send(&handler, cancel).await.is_none());
+    let resp = tokio::time::timeout(std::time::Duration::from_secs(5), pending)
+        .await
+        .expect("cancelled request should finish")
+        .unwrap();
+    assert!(resp.is_none(), "cancelled request got a response: {resp:?}");
+    drop(guard);
+
+    // Cancelling a finished or unknown request is harmless.
+    let cancel = json!({
+        "jsonrpc": "2.0",
+        "method": "$/cancelRequest",
+        "params": { "requestId": 99 }
+    });
+    assert!(send(&handler, cancel).await.is_none());
+    let resp = send_unwrap(&handler, mcp_request(8, "ping", json!({}))).await;
+    assert!(resp.get("result").is_some());
+
+    println!("TEST BONUS — Cancel Request: PASS");
+}
+
+/// Bonus: repeated read-only tool calls are answered from the result cache
+#[tokio::test]
+async fn test_bonus_result_cache() {
+    let dir = tempfile::tempdir().unwrap();
+    let session = arc_session(&dir);
+    let handler = ProtocolHandler::new(session).with_cache_capacity(1);
+
+    send_unwrap(&handler, init_request()).await;
+
+    let query = |id: i64, labels: Vec<&str>| {
+        mcp_request(
+            id,
+            "tools/call",
+            json!({ "name": "vision_query", "arguments": { "labels": labels } }),
+        )
+    };
+    let total = |resp: &Value| -> u64 {
+        let text = resp["result"]["content"][0]["text"].as_str().unwrap();
+        let parsed: Value = serde_json::from_str(text).unwrap();
+        parsed["total"].as_u64().unwrap()
+    };
+
+    assert_eq!(
+        total(&send_unwrap(&handler, query(2, vec!["home"])).await),
+        0
+    );
+    assert_eq!(
+        total(&send_unwrap(&handler, query(3, vec!["home"])).await),
+        0
+    );
+    let stats = handler.cache_stats().await;
+    assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+
+    // A capture changes the store, so the cached answer is not reused.
+    let png_data = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, tiny_png());
+    capture_image(&handler, &png_data, vec!["home"], None).await;
+    assert_eq!(
+        total(&send_unwrap(&handler, query(4, vec!["home"])).await),
+        1
+    );
+    let stats = handler.cache_stats().await;
+    assert_eq!((stats.misses, stats.invalidations), (2, 1));
+
+    // With room for one result, a new query evicts the last.
+    send_unwrap(&handler, query(5, vec!["other"])).await;
+    send_unwrap(&handler, query(6, vec!["home"])).await;
+    let stats = handler.cache_stats().await;
+    assert_eq!((stats.hits, stats.misses, stats.evictions), (1, 4, 2));
+
+    println!("TEST BONUS — Result Cache: PASS");
+}
+
+/// Bonus: newline-delimited and Content-Length framed messages can be mixed
+#[tokio::test]
+async fn test_bonus_mixed_framing() {
+    use agentic_vision_mcp::transport::framing::{frame_message_as, read_message, Framing};
+
+    let ping = r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#;
+    let input = format!(
+        "Content-Length: {}\r\n\r\n{ping}\n{ping}\n\r\nContent-Type: application/json\r\ncontent-length: {}\r\n\r\n{ping}Content-Length: x\r\n\r\n",
+        ping.len(),
+        ping.len(),
+    );
+    let mut reader = tokio::io::BufReader::new(input.as_bytes());
+
+    let mut frames = Vec::new();
+    while let Some(frame) = read_message(&mut reader).await.unwrap() {
+        frames.push(frame);
+    }
+    assert_eq!(frames.len(), 4, "{frames:?}");
+    assert_eq!(frames[0], (ping.to_string(), Framing::ContentLength));
+    assert_eq!(frames[1], (ping.to_string(), Framing::Newline));
+    assert_eq!(frames[2], (ping.to_string(), Framing::ContentLength));
+    // A bad length surfaces as a parse error rather than a hang.
+    assert!(framing::parse_message(&frames[3].0).is_err());
+
+    let framed = frame_message_as(&json!({ "ok": true }), Framing::ContentLength).unwrap();
+    assert_eq!(framed, "Content-Length: 11\r\n\r\n{\"ok\":true}");
+
+    println!("TEST BONUS — Mixed Framing: PASS");
+}
+
+/// Bonus: initialize echoes supported protocol versions
+#[tokio::test]
+async fn test_bonus_version_negotiation() {
+    let dir = tempfile::tempdir().unwrap();
+    let handler = ProtocolHandler::new(arc_session(&dir));
+
+    for (requested, expected) in [
+        ("2024-11-05", "2024-11-05"),
+        ("2025-06-18", "2025-06-18"),
+        ("2024-10-07", MCP_VERSION),
+    ] {
+        let msg = mcp_request(
+            0,
+            "initialize",
+            json!({
+                "protocolVersion": requested,
+                "capabilities": {},
+                "clientInfo": { "name": "test", "version": "1.0" }
+            }),
+        );
+        let resp = send_unwrap(&handler, msg).await;
+        assert_eq!(resp["result"]["protocolVersion"], expected, "{requested}");
+    }
+    assert!(SUPPORTED_VERSIONS.contains(&MCP_VERSION));
+
+    println!("TEST BONUS — Version Negotiation: PASS");
+}
+
+/// Bonus: list resources page, stay within the budget, and link thumbnails
+#[tokio::test]
+async fn test_bonus_resource_paging() {
+    async fn read_resource(handler: &ProtocolHandler, uri: String) -> Value {
+        let msg = mcp_request(2, "resources/read", json!({ "uri": uri }));
+        send_unwrap(handler, msg).await
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let session = arc_session(&dir);
+    // Room for metadata and about one inlined thumbnail.
+    let handler = ProtocolHandler::new(session).with_resource_budget(1500);
+
+    send_unwrap(&handler, init_request()).await;
+
+    let png_data =
+        base64::Engine::encode(&base64::engine::general_purpose::STANDARD, make_png(64, 64));
+    for i in 0..3 {
+        capture_image(&handler, &png_data, vec![], Some(&format!("page {i}"))).await;
+    }
+
+    let content = |resp: &Value| -> Value {
+        let text = resp["result"]["contents"][0]["text"].as_str().unwrap();
+        serde_json::from_str(text).unwrap()
+    };
+
+    let first = content(
+        &read_resource(
+            &handler,
+            "avis://timeline/0/99999999999?limit=2".to_string(),
+        )
+        .await,
+    );
+    assert_eq!(first["total"], 3);
+    assert_eq!(first["count"], 2);
+    assert!(first["captures"][0]["thumbnail_uri"]
+        .as_str()
+        .unwrap()
+        .starts_with("avis://thumbnail/"));
+    let next = first["next"].as_str().unwrap().to_string();
+    let second = content(&read_resource(&handler, next).await);
+    assert_eq!(second["count"], 1);
+    assert!(second["next"].is_null());
+
+    // Thumbnails are inlined only while they fit the budget.
+    let inlined =
+        content(&read_resource(&handler, "avis://session/1?thumbnails=true".to_string()).await);
+    assert!(inlined["captures"][0]["thumbnail_base64"].is_string());
+    assert!(
+        inlined["thumbnails_omitted"].as_u64().unwrap() >= 1,
+        "{inlined}"
+    );
+    assert_eq!(inlined["budget_exceeded"], true);
+    // Past the budget, the page ends early and links the rest.
+    if inlined["count"].as_u64().unwrap() < 3 {
+        assert!(inlined["next"].is_string(), "{inlined}");
+    }
+
+    let thumb = read_resource(&handler, "avis://thumbnail/1".to_string()).await;
+    assert_eq!(thumb["result"]["contents"][0]["mimeType"], "image/jpeg");
+    assert!(thumb["result"]["contents"][0]["blob"].is_string());
+
+    let bad = read_resource(&handler, "avis://session/1?limit=0".to_string()).await;
+    assert!(bad.get("error").is_some(), "{bad}");
+
+    println!("TEST BONUS — Resource Paging: PASS");
+}
+
+/// Bonus: the stats resource reports embedding, rate, storage and dedup figures
+#[tokio::test]
+async fn test_bonus_store_statistics() {
+    let dir = tempfile::tempdir().unwrap();
+    let session = arc_session(&dir);
+    let handler = ProtocolHandler::new(session);
+
+    send_unwrap(&handler, init_request()).await;
+
+    let png = |size| {
+        base64::Engine::encode(
+            &base64::engine::general_purpose::STANDARD,
+            make_png(size, size),
+        )
+    };
+    let (same, other) = (png(32), png(48));
+    capture_image(&handler, &same, vec!["app/login"], None).await;
+    capture_image(&handler, &same, vec!["app/login"], None).await;
+    capture_image(&handler, &other, vec!["app/home"], None).await;
+
+    let msg = mcp_request(2, "resources/read", json!({ "uri": "avis://stats" }));
+    let resp = send_unwrap(&handler, msg).await;
+    let text = resp["result"]["contents"][0]["text"].as_str().unwrap();
+    let stats: Value = serde_json::from_str(text).unwrap();
+
+    assert_eq!(stats["total_captures"], 3);
+    assert_eq!(stats["labels"]["app"], 3);
+    assert_eq!(stats["labels"]["app/login"], 2);
+
+    let embeddings = &stats["embeddings"];
+    assert_eq!(embeddings["histogram"].as_array().unwrap().len(), 20);
+    assert_eq!(
+        embeddings["sampled"].as_u64().unwrap() + embeddings["zero_vectors"].as_u64().unwrap(),
+        3
+    );
+
+    assert_eq!(stats["capture_rate"]["daily"][0]["captures"], 3);
+    assert!(stats["storage"]["thumbnails"].as_u64().unwrap() > 0);
+    assert!(stats["storage"]["total"].as_u64().unwrap() > 0);
+
+    assert_eq!(stats["dedup"]["unique_images"], 2);
+    assert_eq!(stats["dedup"]["duplicate_captures"], 1);
+    assert!(stats["dedup"]["duplicate_bytes"].as_u64().unwrap() > 0);
+
+    println!("TEST BONUS — Store Statistics: PASS");
+}
+
+/// Bonus: sessions carry a name, goal and agent, list with durations, and filter queries
+#[tokio::test]
+async fn test_bonus_session_metadata() {
+    async fn call(handler: &ProtocolHandler, name: &str, arguments: Value) -> Value {
+        let msg = mcp_request(
+            3,
+            "tools/call",
+            json!({ "name": name, "arguments": arguments }),
+        );
+        let resp = send_unwrap(handler, msg).await;
+        let text = resp["result"]["content"][0]["text"].as_str().unwrap();
+        serde_json::from_str(text).unwrap()
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let session = arc_session(&dir);
+    let handler = ProtocolHandler::new(session);
+
+    send_unwrap(&handler, init_request()).await;
+
+    let png = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, tiny_png());
+    capture_image(&handler, &png, vec![], Some("before naming")).await;
+
+    let started = call(
+        &handler,
+        "session_start",
+        json!({
+            "name": "Checkout audit",
+            "description": "Verify the payment flow",
+            "agent": "qa-bot"
+        }),
+    )
+    .await;
+    assert_eq!(started["name"], "Checkout audit");
+    assert_eq!(started["agent"], "qa-bot");
+    let audit_id = started["session_id"].as_u64().unwrap();
+
+    capture_image(&handler, &png, vec![], Some("payment page")).await;
+    capture_image(&handler, &png, vec![], Some("receipt")).await;
+    let ended = call(&handler, "session_end", json!({})).await;
+    assert_eq!(ended["session_captures"], 2);
+    assert!(ended["duration_secs"].is_u64());
+
+    // Queries filter by session attributes.
+    let by_name = call(
+        &handler,
+        "vision_query",
+        json!({ "session_name": "checkout audit" }),
+    )
+    .await;
+    assert_eq!(by_name["total"], 2);
+    let by_goal = call(
+        &handler,
+        "vision_query",
+        json!({ "session_text": "payment", "agent": "qa-bot" }),
+    )
+    .await;
+    assert_eq!(by_goal["total"], 2);
+    let other_agent = call(&handler, "vision_query", json!({ "agent": "someone-else" })).await;
+    assert_eq!(other_agent["total"], 0);
+
+    let msg = mcp_request(4, "resources/read", json!({ "uri": "avis://sessions" }));
+    let resp = send_unwrap(&handler, msg).await;
+    let text = resp["result"]["contents"][0]["text"].as_str().unwrap();
+    let listing: Value = serde_json::from_str(text).unwrap();
+    assert_eq!(listing["total"], 2);
+    let newest = &listing["sessions"][0];
+    assert_eq!(newest["id"].as_u64().unwrap(), audit_id);
+    assert_eq!(newest["name"], "Checkout audit");
+    assert_eq!(newest["description"], "Verify the payment flow");
+    assert_eq!(newest["capture_count"], 2);
+    assert!(newest["ended_at"].is_u64());
+    assert_eq!(listing["sessions"][1]["capture_count"], 1);
+
+    println!("TEST BONUS — Session Metadata: PASS");
+}
+
+/// Bonus: migrate upgrades an old store, verifies it, and leaves files alone on a dry run
+#[test]
+fn test_bonus_migrate() {
+    use agentic_vision::{
+        AvisReader, AvisWriter, CaptureSource, ObservationMeta, VisualMemoryStore,
+        VisualObservation,
+    };
+    use agentic_vision_mcp::migrate::migrate_file;
+
+    // A store as an older release wrote it: no fingerprints, untidy labels.
+    let capture = |labels: Vec<&str>| VisualObservation {
+        id: 0,
+        timestamp: 1_700_000_000,
+        session_id: 2,
+        source: CaptureSource::Clipboard,
+        embedding: Vec::new(),
+        thumbnail: tiny_png(),
+        metadata: ObservationMeta {
+            width: 1,
+            height: 1,
+            original_width: 1,
+            original_height: 1,
+            labels: labels.into_iter().map(String::from).collect(),
+            description: None,
+            caption: None,
+        },
+        memory_link: None,
+        fingerprint: Vec::new(),
+        elements: Vec::new(),
+        ocr: None,
+        provenance: None,
+    };
+    let dir = tempfile::tempdir().unwrap();
+    let old = dir.path().join("old.avis");
+    let new = dir.path().join("new.avis");
+    let mut store = VisualMemoryStore::new(512);
+    store.add(capture(vec!["app/ checkout"]));
+    store.add(capture(vec!["app/home"]));
+    AvisWriter::write_to_file(&store, &old).unwrap();
+
+    let dry = migrate_file(&old, &new, true).unwrap();
+    assert!(dry.changed());
+    assert_eq!(dry.captures, 2);
+    assert_eq!(dry.fingerprints_computed, 2);
+    assert_eq!(dry.labels_normalized, 1);
+    assert!(!new.exists());
+
+    let report = migrate_file(&old, &new, false).unwrap();
+    assert_eq!(report, dry);
+    let migrated = AvisReader::read_from_file(&new).unwrap();
+    assert_eq!(migrated.count(), 2);
+    assert!(migrated
+        .observations
+        .iter()
+        .all(|o| !o.fingerprint.is_empty()));
+    assert_eq!(
+        migrated.observations[0].metadata.labels,
+        vec!["app/checkout"]
+    );
+    assert_eq!(migrated.session_count, 2);
+    let leftovers: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+    assert_eq!(leftovers.len(), 2, "no staging file left behind");
+
+    // Another existing file is not overwritten; the input itself may be.
+    assert!(migrate_file(&old, &new, false).is_err());
+    migrate_file(&old, &old, false).unwrap();
+    assert!(!migrate_file(&old, &old, true).unwrap().changed());
+
+    println!("TEST BONUS — Migrate: PASS");
+}
+
+/// Bonus: recover salvages the intact captures of a file truncated mid-write
+#[test]
+fn test_bonus_recover() {
+    use agentic_vision_mcp::recover::recover_file;
+
+    let dir = tempfile::tempdir().unwrap();
+    let damaged = dir.path().join("damaged.avis");
+    let recovered = dir.path().join("recovered.avis");
+    {
+        let mut session = VisionSessionManager::open(damaged.to_str().unwrap(), None).unwrap();
+        let png = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, tiny_png());
+        for i in 0..4 {
+            session
+                .capture(
+                    "base64",
+                    &png,
+                    Some("image/png"),
+                    vec![format!("step/{i}")],
+                    None,
+                    false,
+                    agentic_vision::Provenance::default(),
+                    &CancellationToken::new(),
+                )
+                .unwrap();
+        }
+        session.save().unwrap();
+    }
+    let bytes = std::fs::read(&damaged).unwrap();
+    std::fs::write(&damaged, &bytes[..bytes.len() * 3 / 4]).unwrap();
+    assert!(VisionSessionManager::open(damaged.to_str().unwrap(), None).is_err());
+
+    let report = recover_file(&damaged, &recovered).unwrap();
+    assert!(report.header_valid && report.truncated);
+    assert!(report.captures_recovered >= 1 && report.captures_recovered < 4);
+    assert_eq!(
+        report.captures_lost(),
+        Some(4 - report.captures_recovered as u64)
+    );
+    assert!(!report.metadata_recovered);
+
+    // The damaged file is untouched and the new one opens normally.
+    assert_eq!(std::fs::read(&damaged).unwrap().len(), bytes.len() * 3 / 4);
+    let session = VisionSessionManager::open(recovered.to_str().unwrap(), None).unwrap();
+    assert_eq!(session.store().count(), report.captures_recovered);
+    assert!(recover_file(&damaged, &recovered).is_err());
+
+    println!("TEST BONUS — Recover: PASS");
+}
+
+/// Bonus: vision_ocr runs the selected backend, rejects unknown or
+/// unavailable ones, and stores a layout vision_find_text can search.
+#[cfg(unix)]
+#[tokio::test]
+async fn test_bonus_ocr_backends() {
+    use std::os::unix::fs::PermissionsExt;
+
+    use agentic_vision_mcp::config::OcrConfig;
+
+    async fn ocr(handler: &ProtocolHandler, arguments: Value) -> Value {
+        let msg = mcp_request(
+            4,
+            "tools/call",
+            json!({ "name": "vision_ocr", "arguments": arguments }),
+        );
+        send_unwrap(handler, msg).await
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+
+    // A stand-in for tesseract that reads the image and prints fixed TSV.
+    let script = dir.path().join("fake-tesseract");
+    std::fs::write(
+        &script,
+        "#!/bin/sh\ncat > /dev/null\n\
+         printf 'level\\tpage_num\\tblock_num\\tpar_num\\tline_num\\tword_num\\tleft\\ttop\\twidth\\theight\\tconf\\ttext\\n'\n\
+         printf '5\\t1\\t1\\t1\\t1\\t1\\t0\\t0\\t4\\t2\\t90\\tHello\\n'\n\
+         printf '5\\t1\\t1\\t1\\t1\\t2\\t5\\t0\\t3\\t2\\t80\\tworld\\n'\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut manager = temp_session(&dir);
+    manager.set_ocr_config(OcrConfig {
+        tesseract: script.to_str().unwrap().to_string(),
+        ..OcrConfig::default()
+    });
+    let handler = ProtocolHandler::new(Arc::new(Mutex::new(manager)));
+    send_unwrap(&handler, init_request()).await;
+
+    let png = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, tiny_png());
+    let captured = capture_image(&handler, &png, vec![], None).await;
+    let text = captured["result"]["content"][0]["text"].as_str().unwrap();
+    let capture_id = serde_json::from_str::<Value>(text).unwrap()["capture_id"]
+        .as_u64()
+        .unwrap();
+
+    let resp = ocr(&handler, json!({ "capture_id": capture_id })).await;
+    let text = resp["result"]["content"][0]["text"].as_str().unwrap();
+    let result: Value = serde_json::from_str(text).unwrap();
+    assert_eq!(result["backend"], "tesseract");
+    assert_eq!(result["language"], "eng");
+    assert_eq!(result["text"], "Hello world");
+    assert_eq!(result["lines"].as_array().unwrap().len(), 1);
+    assert_eq!(result["lines"][0]["words"].as_array().unwrap().len(), 2);
+    assert_eq!(result["blocks"][0]["lines"], json!([0]));
+    assert_eq!(result["detected_language"], "eng");
+
+    // The result is stored with the capture and can be searched.
+    let msg = mcp_request(
+        5,
+        "tools/call",
+        json!({ "name": "vision_find_text", "arguments": { "text": "WORLD" } }),
+    );
+    let resp = send_unwrap(&handler, msg).await;
+    let text = resp["result"]["content"][0]["text"].as_str().unwrap();
+    let found: Value = serde_json::from_str(text).unwrap();
+    assert_eq!(found["total"], 1);
+    assert_eq!(found["matches"][0]["capture_id"], capture_id);
+    assert_eq!(found["matches"][0]["text"], "Hello world");
+    let words = found["matches"][0]["words"].as_array().unwrap();
+    assert_eq!(words.len(), 1);
+    assert_eq!(words[0]["text"], "world");
+
+    let resp = ocr(
+        &handler,
+        json!({ "capture_id": capture_id, "backend": "carrier-pigeon" }),
+    )
+    .await;
+    assert_eq!(resp["error"]["code"], -32602);
+
+    // Without an endpoint the remote backend is unusable in any build.
+    let resp = ocr(
+        &handler,
+        json!({ "capture_id": capture_id, "backend": "remote" }),
+    )
+    .await;
+    assert_eq!(resp["error"]["code"], -32602);
+
+    let resp = ocr(&handler, json!({ "capture_id": 9999 })).await;
+    assert!(resp["error"].is_object());
+
+    println!("TEST BONUS — OCR backends: PASS");
+}
+
+/// Bonus: captures record the call, client, agent and original that
+/// produced them, and a sync import extends their history.
+#[tokio::test]
+async fn test_bonus_provenance() {
+    let dir = tempfile::tempdir().unwrap();
+    let session = arc_session(&dir);
+    let handler = ProtocolHandler::new(session.clone());
+    send_unwrap(&handler, init_request()).await;
+
+    let msg = mcp_request(
+        1,
+        "tools/call",
+        json!({ "name": "session_start", "arguments": { "agent": "qa-bot" } }),
+    );
+    send_unwrap(&handler, msg).await;
+
+    let png = make_png(600, 300);
+    let data = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &png);
+    capture_image(&handler, &data, vec![], None).await;
+
+    let msg = mcp_request(2, "resources/read", json!({ "uri": "avis://capture/1" }));
+    let resp = send_unwrap(&handler, msg).await;
+    let text = resp["result"]["contents"][0]["text"].as_str().unwrap();
+    let capture: Value = serde_json::from_str(text).unwrap();
+    let provenance = &capture["provenance"];
+    assert_eq!(provenance["tool"], "vision_capture");
+    assert_eq!(provenance["request_id"], "10");
+    assert_eq!(provenance["client"], "test-client/1.0");
+    assert_eq!(provenance["agent"], "qa-bot");
+    assert_eq!(provenance["source_type"], "base64");
+    assert_eq!(
+        provenance["original_hash"],
+        agentic_vision::sha256_hex(&png)
+    );
+    assert_eq!(provenance["transforms"][0]["step"], "thumbnail");
+    assert_eq!(
+        provenance["transforms"][0]["detail"],
+        "600x300 -> 512x256 JPEG"
+    );
+
+    // Importing into another store appends to the history.
+    let exported = {
+        let session = session.lock().await;
+        let hashes = session
+            .sync_manifest()
+            .into_iter()
+            .map(|e| e.hash)
+            .collect();
+        session.sync_export(&hashes)
+    };
+    let other_dir = tempfile::tempdir().unwrap();
+    let mut other = temp_session(&other_dir);
+    assert_eq!(other.sync_import(exported).unwrap(), 1);
+    let imported = other.store().observations[0].provenance.clone().unwrap();
+    assert_eq!(imported.client.as_deref(), Some("test-client/1.0"));
+    let steps: Vec<&str> = imported
+        .transforms
+        .iter()
+        .map(|t| t.step.as_str())
+        .collect();
+    assert_eq!(steps, vec!["thumbnail", "import"]);
+
+    println!("TEST BONUS — Provenance: PASS");
+}
+
+// ─────────── BONUS: Re-index with checkpoint and resume ───────────
+
+#[tokio::test]
+async fn test_bonus_reindex() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("test.avis");
+    {
+        let session = arc_session(&dir);
+        let handler = ProtocolHandler::new(session.clone());
+        send_unwrap(&handler, init_request()).await;
+        let data = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, tiny_png());
+        for _ in 0..3 {
+            capture_image(&handler, &data, vec![], None).await;
+        }
+
+        // Without a model every embedding would become zeros.
+        let msg = mcp_request(
+            1,
+            "tools/call",
+            json!({ "name": "vision_reindex", "arguments": { "action": "start" } }),
+        );
+        let resp = send_unwrap(&handler, msg).await;
+        assert_eq!(resp["error"]["code"], -32602);
+
+        let msg = mcp_request(2, "tools/call", json!({ "name": "vision_reindex" }));
+        let resp = send_unwrap(&handler, msg).await;
+        let text = resp["result"]["content"][0]["text"].as_str().unwrap();
+        let status: Value = serde_json::from_str(text).unwrap();
+        assert_eq!(status["state"], "idle");
+        session.lock().await.save().unwrap();
+    }
+
+    // A run interrupted after the first capture.
+    let mut store = agentic_vision::AvisReader::read_from_file(&path).unwrap();
+    store.start_reindex(Some("clip.onnx".to_string()), 100);
+    store.advance_reindex(1, 1, 0, 101);
+    agentic_vision::AvisWriter::write_to_file(&store, &path).unwrap();
+
+    let session = arc_session(&dir);
+    let status = agentic_vision_mcp::reindex::status(&*session.lock().await);
+    assert_eq!(status["state"], "paused");
+    assert_eq!(status["model"], "clip.onnx");
+    assert_eq!(status["remaining"], 2);
+
+    let cancel = tokio_util::sync::CancellationToken::new();
+    let mut reported = Vec::new();
+    let finished =
+        agentic_vision_mcp::reindex::run(&session, 1, &cancel, |c| reported.push(c.done))
+            .await
+            .unwrap()
+            .unwrap();
+    assert_eq!(reported, vec![2, 3, 3]);
+    assert_eq!((finished.total, finished.done, finished.failed), (3, 3, 0));
+
+    // Completion clears the checkpoint from the file.
+    let store = agentic_vision::AvisReader::read_from_file(&path).unwrap();
+    assert!(store.reindex.is_none());
+    assert_eq!(store.count(), 3);
+
+    println!("TEST BONUS — Reindex: PASS");
+}
+
+// ─────────── BONUS: Duplicate report and merge ───────────
+
+#[tokio::test]
+async fn test_bonus_dedup_report() {
+    let dir = tempfile::tempdir().unwrap();
+    let session = arc_session(&dir);
+    let handler = ProtocolHandler::new(session.clone());
+    send_unwrap(&handler, init_request()).await;
+
+    let encode =
+        |png: &[u8]| base64::Engine::encode(&base64::engine::general_purpose::STANDARD, png);
+    let black = encode(&make_png(64, 32));
+    let gradient = {
+        let img = image::RgbImage::from_fn(64, 32, |x, _| image::Rgb([255 - (x * 4) as u8, 0, 0]));
+        let mut buf = Vec::new();
+        let encoder = image::codecs::png::PngEncoder::new(&mut buf);
+        image::DynamicImage::ImageRgb8(img)
+            .write_with_encoder(encoder)
+            .unwrap();
+        encode(&buf)
+    };
+    capture_image(&handler, &black, vec!["app/login"], None).await;
+    capture_image(&handler, &gradient, vec![], None).await;
+    capture_image(&handler, &black, vec!["app/home"], None).await;
+    // The same black screen at another size: a near duplicate.
+    capture_image(&handler, &encode(&make_png(128, 64)), vec![], None).await;
+
+    let call = |id: i64, args: Value| {
+        mcp_request(
+            id,
+            "tools/call",
+            json!({ "name": "vision_dedup_report", "arguments": args }),
+        )
+    };
+    let parse = |resp: Value| -> Value {
+        let text = resp["result"]["content"][0]["text"].as_str().unwrap();
+        serde_json::from_str(text).unwrap()
+    };
+
+    let report = parse(send_unwrap(&handler, call(1, json!({}))).await);
+    assert_eq!(report["scanned"], 4);
+    assert_eq!(report["exact_duplicates"], 1);
+    assert_eq!(report["near_duplicates"], 1);
+    assert_eq!(report["groups"][0]["keep"], 1);
+    assert_eq!(report["groups"][0]["duplicates"][0]["id"], 3);
+    assert_eq!(report["groups"][0]["duplicates"][0]["exact"], true);
+    assert!(report["reclaimable_bytes"].as_u64().unwrap() > 0);
+    assert_eq!(report["merged"], 0);
+
+    let report = parse(send_unwrap(&handler, call(2, json!({ "near": false }))).await);
+    assert_eq!(report["near_duplicates"], 0);
+
+    let resp = send_unwrap(&handler, call(3, json!({ "max_hash_distance": 65 }))).await;
+    assert_eq!(resp["error"]["code"], -32602);
+
+    let report = parse(send_unwrap(&handler, call(4, json!({ "merge": true }))).await);
+    assert_eq!(report["merged"], 2);
+    let session = session.lock().await;
+    assert_eq!(session.store().count(), 2);
+    assert_eq!(
+        session.store().get(1).unwrap().metadata.labels,
+        vec!["app/login", "app/home"]
+    );
+
+    println!("TEST BONUS — Dedup report: PASS");
+}
+
+// ─────────── BONUS: Tenant export and erasure ───────────
+
+#[cfg(feature = "sse")]
+#[tokio::test]
+async fn test_bonus_tenant_export_erase() {
+    use agentic_vision_mcp::session::tenant::{VisionTenantRegistry, ERASURE_AUDIT_FILE};
+    use std::io::Read;
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut registry = VisionTenantRegistry::new(dir.path(), None);
+    let session = registry.get_or_create("alice").unwrap();
+    {
+        let handler = ProtocolHandler::new(session.clone());
+        send_unwrap(&handler, init_request()).await;
+        let data = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, tiny_png());
+        capture_image(&handler, &data, vec!["app/login"], Some("login")).await;
+        session.lock().await.save().unwrap();
+    }
+    assert!(registry.get_or_create("../bob").is_err());
+
+    let archive = registry.export("alice").await.unwrap();
+    let mut zip = zip::ZipArchive::new(std::io::Cursor::new(archive)).unwrap();
+    let mut metadata = String::new();
+    zip.by_name("metadata.json")
+        .unwrap()
+        .read_to_string(&mut metadata)
+        .unwrap();
+    let metadata: Value = serde_json::from_str(&metadata).unwrap();
+    assert_eq!(metadata["user_id"], "alice");
+    assert_eq!(metadata["captures"][0]["image"], "images/1.jpg");
+    assert_eq!(metadata["captures"][0]["metadata"]["description"], "login");
+    assert!(metadata["captures"][0].get("thumbnail").is_none());
+    let image = session.lock().await.store().observations[0]
+        .thumbnail
+        .clone();
+    let mut exported = Vec::new();
+    zip.by_name("images/1.jpg")
+        .unwrap()
+        .read_to_end(&mut exported)
+        .unwrap();
+    assert_eq!(exported, image);
+
+    let path = dir.path().join("alice.avis");
+    assert!(path.exists());
+    let record = registry.erase("alice").await.unwrap();
+    assert_eq!(record.captures, Some(1));
+    assert_eq!(record.files, vec!["alice.avis"]);
+    assert!(record.bytes_overwritten > 0);
+    assert!(!path.exists());
+
+    // A request still holding the session cannot write the data back.
+    {
+        let mut held = session.lock().await;
+        assert_eq!(held.store().count(), 0);
+        held.save().unwrap();
+    }
+    drop(session);
+    assert!(!path.exists());
+
+    let audit = std::fs::read_to_string(dir.path().join(ERASURE_AUDIT_FILE)).unwrap();
+    let line: Value = serde_json::from_str(audit.lines().next().unwrap()).unwrap();
+    assert_eq!(line["user_id"], "alice");
+    assert_eq!(line["captures"], 1);
+
+    // The next request starts empty.
+    let session = registry.get_or_create("alice").unwrap();
+    assert_eq!(session.lock().await.store().count(), 0);
+
+    println!("TEST BONUS — Tenant export and erasure: PASS");
+}
+
+// ─────────── BONUS: Backup and restore ───────────
+
+#[tokio::test]
+async fn test_bonus_backup_restore() {
+    use agentic_vision_mcp::backup::{self, BackupConfig, BackupSource};
+
+    let dir = tempfile::tempdir().unwrap();
+    let session = arc_session(&dir);
+    let handler = ProtocolHandler::new(session.clone());
+    send_unwrap(&handler, init_request()).await;
+    let data = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, tiny_png());
+    capture_image(&handler, &data, vec![], Some("unsaved")).await;
+
+    // Another store that no session has loaded.
+    let other = dir.path().join("other.avis");
+    {
+        let mut store = agentic_vision::VisualMemoryStore::new(512);
+        store.session_count = 3;
+        agentic_vision::AvisWriter::write_to_file(&store, &other).unwrap();
+    }
+
+    let backups = dir.path().join("backups");
+    let config = BackupConfig {
+        dir: backups.clone(),
+        interval: None,
+        keep: 1,
+    };
+    let sources = || {
+        vec![
+            BackupSource::Session {
+                name: "test".to_string(),
+                session: session.clone(),
+            },
+            BackupSource::File {
+                name: "other".to_string(),
+                path: other.clone(),
+            },
+        ]
+    };
+    let first = backup::backup(sources(), &config).await.unwrap();
+    assert_eq!(first.stores.len(), 2);
+    // The unsaved capture is in the backup.
+    assert_eq!(first.stores[0].captures, 1);
+    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    let second = backup::backup(sources(), &config).await.unwrap();
+    // Only the newest is kept.
+    let kept = backup::list_backups(&backups).unwrap();
+    assert_eq!(kept, vec![backups.join(&second.name)]);
+
+    // One store into a vision file, from the newest backup.
+    let restored = dir.path().join("restored.avis");
+    let result = backup::restore(&backups, &restored, None, false);
+    assert!(result.is_err(), "two stores need --store");
+    let (manifest, stores, _) = backup::restore(&backups, &restored, Some("test"), false).unwrap();
+    assert_eq!(manifest.name, second.name);
+    assert_eq!(stores[0].captures, 1);
+    let store = agentic_vision::AvisReader::read_from_file(&restored).unwrap();
+    assert_eq!(
+        store.observations[0].metadata.description.as_deref(),
+        Some("unsaved")
+    );
+    assert!(backup::restore(&backups, &restored, Some("test"), false).is_err());
+    assert!(backup::restore(&backups, &restored, Some("test"), true).is_ok());
+
+    // Every store into a data directory.
+    let data_dir = dir.path().join("data");
+    let (_, stores, _) = backup::restore(&backups, &data_dir, None, false).unwrap();
+    assert_eq!(stores.len(), 2);
+    assert!(data_dir.join("other.avis").exists());
+
+    // A damaged backup is refused before anything is written.
+    let damaged = backups.join(&second.name).join("other.avis");
+    let mut bytes = std::fs::read(&damaged).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xff;
+    std::fs::write(&damaged, bytes).unwrap();
+    let fresh = dir.path().join("fresh");
+    assert!(backup::restore(&backups, &fresh, None, false).is_err());
+    assert!(!fresh.exists());
+
+    println!("TEST BONUS — Backup and restore: PASS");
+}
+
+#[cfg(feature = "sse")]
+#[tokio::test]
+async fn test_bonus_backup_erasure() {
+    use agentic_vision_mcp::backup::{self, BackupConfig, BackupSource};
+    use agentic_vision_mcp::session::tenant::VisionTenantRegistry;
+
+    let dir = tempfile::tempdir().unwrap();
+    let data_dir = dir.path().join("data");
+    let mut registry = VisionTenantRegistry::new(&data_dir, None);
+    for user in ["alice", "bob"] {
+        let session = registry.get_or_create(user).unwrap();
+        let handler = ProtocolHandler::new(session.clone());
+        send_unwrap(&handler, init_request()).await;
+        let data = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, tiny_png());
+        capture_image(&handler, &data, vec![], Some(user)).await;
+        session.lock().await.save().unwrap();
+    }
+    let sources = || -> Vec<BackupSource> {
+        ["alice", "bob"]
+            .map(|user| BackupSource::File {
+                name: user.to_string(),
+                path: data_dir.join(format!("{user}.avis")),
+            })
+            .into()
+    };
+    let config = |dir: std::path::PathBuf| BackupConfig {
+        dir,
+        interval: None,
+        keep: 2,
+    };
+    let kept = config(dir.path().join("kept"));
+    let copied = config(dir.path().join("copied"));
+    let taken = backup::backup(sources(), &kept).await.unwrap();
+    backup::backup(sources(), &copied).await.unwrap();
+
+    registry.erase("alice").await.unwrap();
+    // Erasure shreds the store from the backups and their manifests.
+    let purged = backup::purge_store(&kept.dir, "alice").unwrap();
+    assert_eq!(purged, vec![taken.name.clone()]);
+    let (backup_dir, manifest) = backup::find_backup(&kept.dir).unwrap();
+    assert!(!backup_dir.join("alice.avis").exists());
+    assert_eq!(manifest.stores.len(), 1);
+    assert_eq!(manifest.stores[0].name, "bob");
+
+    // A backup erasure did not reach, such as a copy kept elsewhere, does
+    // not bring the erased user back.
+    let (_, restored, skipped) = backup::restore(&copied.dir, &data_dir, None, true).unwrap();
+    assert_eq!(restored.len(), 1);
+    assert_eq!(restored[0].name, "bob");
+    assert_eq!(skipped, vec!["alice"]);
+    assert!(!data_dir.join("alice.avis").exists());
+    assert!(backup::restore(&copied.dir, &data_dir, Some("alice"), true).is_err());
+
+    // Backups taken after the erasure restore the user's new data.
+    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    let later = backup::backup(sources(), &copied).await.unwrap();
+    assert_eq!(later.stores.len(), 1);
+    let session = registry.get_or_create("alice").unwrap();
+    let handler = ProtocolHandler::new(session.clone());
+    send_unwrap(&handler, init_request()).await;
+    let data = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, tiny_png());
+    capture_image(&handler, &data, vec![], Some("alice again")).await;
+    session.lock().await.save().unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    let after = backup::backup(sources(), &copied).await.unwrap();
+    assert_eq!(after.stores.len(), 2);
+    let (_, restored, skipped) = backup::restore(&copied.dir, &data_dir, None, true).unwrap();
+    assert_eq!(restored.len(), 2);
+    assert!(skipped.is_empty());
+
+    println!("TEST BONUS — Backup erasure: PASS");
+}
+
+// ─────────── BONUS: Idempotency keys ───────────
+
+#[tokio::test]
+async fn test_bonus_idempotency_key() {
+    let dir = tempfile::tempdir().unwrap();
+    let session = arc_session(&dir);
+    let handler = ProtocolHandler::new(session.clone());
+    send_unwrap(&handler, init_request()).await;
+
+    let data = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, tiny_png());
+    let capture = |id: i64, key: Value, labels: Vec<&str>| {
+        mcp_request(
+            id,
+            "tools/call",
+            json!({
+                "name": "vision_capture",
+                "arguments": {
+                    "source": { "type": "base64", "data": data, "mime": "image/png" },
+                    "labels": labels,
+                    "idempotency_key": key
+                }
+            }),
+        )
+    };
+
+    // A retry with the same key returns the first result.
+    let first = send_unwrap(&handler, capture(1, json!("retry-1"), vec!["a"])).await;
+    let retry = send_unwrap(&handler, capture(2, json!("retry-1"), vec!["a"])).await;
+    assert!(first.get("error").is_none(), "{first}");
+    assert_eq!(first["result"], retry["result"]);
+    assert_eq!(session.lock().await.store().count(), 1);
+
+    // The key with other arguments is refused.
+    let resp = send_unwrap(&handler, capture(3, json!("retry-1"), vec!["b"])).await;
+    assert_eq!(resp["error"]["code"], -32806);
+
+    // Another key captures again.
+    send_unwrap(&handler, capture(4, json!("retry-2"), vec!["a"])).await;
+    assert_eq!(session.lock().await.store().count(), 2);
+
+    let resp = send_unwrap(&handler, capture(5, json!(""), vec!["a"])).await;
+    assert_eq!(resp["error"]["code"], -32602);
+    let resp = send_unwrap(&handler, capture(6, json!(7), vec!["a"])).await;
+    assert_eq!(resp["error"]["code"], -32602);
+    assert_eq!(session.lock().await.store().count(), 2);
+
+    // Mutating tools advertise the key; read-only ones do not.
+    let list = send_unwrap(&handler, mcp_request(7, "tools/list", json!({}))).await;
+    let tools = list["result"]["tools"].as_array().unwrap();
+    let schema = |name: &str| {
+        tools.iter().find(|t| t["name"] == name).unwrap()["inputSchema"]["properties"].clone()
+    };
+    assert!(schema("vision_capture").get("idempotency_key").is_some());
+    assert!(schema("vision_query").get("idempotency_key").is_none());
+
+    println!("TEST BONUS — Idempotency keys: PASS");
+}
+
+// ─────────── BONUS: Correlation IDs ───────────
+
+#[tokio::test]
+async fn test_bonus_correlation_id() {
+    let dir = tempfile::tempdir().unwrap();
+    let handler = ProtocolHandler::new(arc_session(&dir));
+    send_unwrap(&handler, init_request()).await;
+
+    let call = |id: i64| {
+        let msg = mcp_request(
+            id,
+            "tools/call",
+            json!({ "name": "vision_labels", "arguments": { "action": "list" } }),
+        );
+        serde_json::from_value::<JsonRpcMessage>(msg).unwrap()
+    };
+
+    // Tool results echo the transport's correlation ID.
+    let resp = handler
+        .handle_correlated(call(1), Some("trace-42"))
+        .await
+        .unwrap();
+    assert_eq!(resp["result"]["_meta"]["correlationId"], "trace-42");
+    assert!(resp["result"]["content"][0]["text"].is_string());
+
+    let resp = handler.handle_message(call(2)).await.unwrap();
+    assert!(resp["result"].get("_meta").is_none());
+
+    // Errors carry no result to echo it in.
+    let msg = mcp_request(3, "tools/call", json!({ "name": "no_such_tool" }));
+    let resp = handler
+        .handle_correlated(serde_json::from_value(msg).unwrap(), Some("trace-43"))
+        .await
+        .unwrap();
+    assert!(resp.get("error").is_some());
+
+    println!("TEST BONUS — Correlation IDs: PASS");
+}
+
+// ─────────── BONUS: Similarity search filters ───────────
+
+#[tokio::test]
+async fn test_bonus_similar_filters() {
+    let dir = tempfile::tempdir().unwrap();
+    let session = arc_session(&dir);
+    let handler = ProtocolHandler::new(session.clone());
+    send_unwrap(&handler, init_request()).await;
+
+    let data = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, tiny_png());
+    let tool = |id: i64, name: &str, args: Value| {
+        mcp_request(id, "tools/call", json!({ "name": name, "arguments": args }))
+    };
+    capture_image(&handler, &data, vec!["app/login"], None).await;
+    capture_image(&handler, &data, vec!["app/checkout/payment"], None).await;
+    let first_session = session.lock().await.current_session_id();
+    send_unwrap(&handler, tool(1, "session_start", json!({}))).await;
+    capture_image(&handler, &data, vec!["app/checkout"], None).await;
+    capture_image(&handler, &data, vec!["other"], None).await;
+    send_unwrap(
+        &handler,
+        tool(
+            2,
+            "vision_link",
+            json!({ "capture_id": 4, "memory_node_id": 7 }),
+        ),
+    )
+    .await;
+
+    // Fallback embeddings score 0 against everything, so with no minimum
+    // the filters alone decide what comes back.
+    let similar = |id: i64, filters: Value| {
+        let mut args = json!({ "capture_id": 1, "min_similarity": 0.0 });
+        args.as_object_mut()
+            .unwrap()
+            .extend(filters.as_object().unwrap().clone());
+        tool(id, "vision_similar", args)
+    };
+    let ids = |resp: Value| -> Vec<u64> {
+        let text = resp["result"]["content"][0]["text"].as_str().unwrap();
+        let parsed: Value = serde_json::from_str(text).unwrap();
+        let mut ids: Vec<u64> = parsed["matches"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["id"].as_u64().unwrap())
+            .collect();
+        ids.sort_unstable();
+        ids
+    };
+
+    let all = send_unwrap(&handler, similar(3, json!({}))).await;
+    assert_eq!(ids(all), vec![2, 3, 4]);
+    let by_label = send_unwrap(&handler, similar(4, json!({ "labels": ["app/checkout"] }))).await;
+    assert_eq!(ids(by_label), vec![2, 3]);
+    let by_session = send_unwrap(
+        &handler,
+        similar(5, json!({ "session_ids": [first_session] })),
+    )
+    .await;
+    assert_eq!(ids(by_session), vec![2]);
+    let by_link = send_unwrap(&handler, similar(6, json!({ "memory_link": 7 }))).await;
+    assert_eq!(ids(by_link), vec![4]);
+    let later = send_unwrap(&handler, similar(7, json!({ "after": u32::MAX }))).await;
+    assert!(ids(later).is_empty());
+
+    println!("TEST BONUS — Similarity filters: PASS");
+}
+
+// ─────────── BONUS: Diverse similarity results ───────────
+
+#[tokio::test]
+async fn test_bonus_similar_diversity() {
+    let dir = tempfile::tempdir().unwrap();
+    let handler = ProtocolHandler::new(arc_session(&dir));
+    send_unwrap(&handler, init_request()).await;
+
+    // A white page under a blue header bar of the given height.
+    let page = |header_rows: u32| {
+        let img = image::RgbImage::from_fn(128, 128, |_, y| {
+            image::Rgb(if y < header_rows {
+                [20, 40, 160]
+            } else {
+                [250, 250, 250]
+            })
+        });
+        let mut buf = Vec::new();
+        let encoder = image::codecs::png::PngEncoder::new(&mut buf);
+        image::DynamicImage::ImageRgb8(img)
+            .write_with_encoder(encoder)
+            .unwrap();
+        base64::Engine::encode(&base64::engine::general_purpose::STANDARD, buf)
+    };
+    // The query, two more frames of the same screen, and another screen.
+    for rows in [32, 32, 32, 96] {
+        capture_image(&handler, &page(rows), vec![], None).await;
+    }
+
+    let similar = |id: i64, diversity: f64| {
+        mcp_request(
+            id,
+            "tools/call",
+            json!({
+                "name": "vision_similar",
+                "arguments": {
+                    "capture_id": 1,
+                    "top_k": 2,
+                    "min_similarity": 0.0,
+                    "layout_weight": 1.0,
+                    "diversity": diversity
+                }
+            }),
+        )
+    };
+    let ids = |resp: Value| -> Vec<u64> {
+        let text = resp["result"]["content"][0]["text"].as_str().unwrap();
+        let parsed: Value = serde_json::from_str(text).unwrap();
+        parsed["matches"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["id"].as_u64().unwrap())
+            .collect()
+    };
+
+    // Plain ranking returns the copies; diversity swaps one for the other
+    // screen.
+    assert_eq!(
+        ids(send_unwrap(&handler, similar(2, 0.0)).await),
+        vec![2, 3]
+    );
+    assert_eq!(
+        ids(send_unwrap(&handler, similar(3, 0.7)).await),
+        vec![2, 4]
+    );
+
+    let resp = send_unwrap(&handler, similar(4, 1.5)).await;
+    assert!(
+        resp.get("error").is_some() || resp["result"]["isError"] == true,
+        "{resp}"
+    );
+
+    println!("TEST BONUS — Similar diversity: PASS");
+}
+
+// ─────────── BONUS: Score calibration ───────────
+
+/// Bonus: a calibration fitted to labeled captures adds confidence to results
+#[tokio::test]
+async fn test_bonus_calibrate() {
+    use agentic_vision::{
+        AvisWriter, CaptureSource, ObservationMeta, VisualMemoryStore, VisualObservation,
+    };
+
+    let capture = |embedding: Vec<f32>, label: &str| VisualObservation {
+        id: 0,
+        timestamp: 1_700_000_000,
+        session_id: 1,
+        source: CaptureSource::Clipboard,
+        embedding,
+        thumbnail: tiny_png(),
+        metadata: ObservationMeta {
+            width: 1,
+            height: 1,
+            original_width: 1,
+            original_height: 1,
+            labels: vec![label.to_string()],
+            description: None,
+            caption: None,
+        },
+        memory_link: None,
+        fingerprint: Vec::new(),
+        elements: Vec::new(),
+        ocr: None,
+        provenance: None,
+    };
+
+    // Embedding needs a model, so write a store of two screens up front.
+    let dir = tempfile::tempdir().unwrap();
+    let mut store = VisualMemoryStore::new(3);
+    for i in 0..5 {
+        let noise = i as f32 * 0.05;
+        store.add(capture(vec![1.0, noise, 0.0], "app/login"));
+        store.add(capture(vec![0.2, 1.0, noise], "app/checkout"));
+    }
+    AvisWriter::write_to_file(&store, &dir.path().join("test.avis")).unwrap();
+
+    let session = arc_session(&dir);
+    let handler = ProtocolHandler::new(session.clone());
+    send_unwrap(&handler, init_request()).await;
+    let compare = |id: i64| {
+        mcp_request(
+            id,
+            "tools/call",
+            json!({ "name": "vision_compare", "arguments": { "id_a": 1, "id_b": 3 } }),
+        )
+    };
+    let result_json = |resp: &Value| -> Value {
+        serde_json::from_str(resp["result"]["content"][0]["text"].as_str().unwrap()).unwrap()
+    };
+
+    // Uncalibrated stores report raw scores only.
+    let before = result_json(&send_unwrap(&handler, compare(1)).await);
+    assert!(before.get("confidence").is_none(), "{before}");
+
+    // A dry run fits without storing.
+    session.lock().await.calibrate(1000, false).unwrap();
+    assert!(session.lock().await.store().calibration.is_none());
+    let calibration = session.lock().await.calibrate(1000, true).unwrap();
+    assert_eq!((calibration.pairs, calibration.same_pairs), (45, 20));
+
+    // Two frames of the login screen are very likely the same.
+    let after = result_json(&send_unwrap(&handler, compare(2)).await);
+    assert!(after["confidence"].as_f64().unwrap() > 0.9, "{after}");
+
+    let similar = send_unwrap(
+        &handler,
+        mcp_request(
+            3,
+            "tools/call",
+            json!({
+                "name": "vision_similar",
+                "arguments": { "capture_id": 1, "top_k": 10, "min_similarity": 0.0 }
+            }),
+        ),
+    )
+    .await;
+    let matches = result_json(&similar)["matches"].as_array().unwrap().clone();
+    let confidence = |id: u64| {
+        matches.iter().find(|m| m["id"] == id).unwrap()["confidence"]
+            .as_f64()
+            .unwrap()
+    };
+    assert!(confidence(3) > 0.9);
+    assert!(confidence(2) < 0.1);
+
+    // The calibration is saved with the store.
+    drop(handler);
+    drop(session);
+    let reopened = temp_session(&dir);
+    assert_eq!(reopened.store().calibration.as_ref(), Some(&calibration));
+
+    println!("TEST BONUS — Score calibration: PASS");
+}
+
+// ─────────── BONUS: Capture pipelines ───────────
+
+/// Bonus: configured pipelines process captures by source and label
+#[tokio::test]
+async fn test_bonus_capture_pipelines() {
+    let dir = tempfile::tempdir().unwrap();
+    let session = arc_session(&dir);
+    session.lock().await.set_capture_pipelines(
+        agentic_vision::PipelineConfig::from_json(
+            r#"{"pipelines": [
+                {"sources": ["file"], "stages": [
+                    {"op": "redact", "regions": [{"x": 0, "y": 0, "w": 50, "h": 50}]}
+                ]},
+                {"labels": ["app/billing"], "stages": [
+                    {"op": "resize", "max_width": 100}
+                ]}
+            ]}"#,
+        )
+        .unwrap(),
+    );
+    let handler = ProtocolHandler::new(session.clone());
+    send_unwrap(&handler, init_request()).await;
+    let result_json = |resp: &Value| -> Value {
+        serde_json::from_str(resp["result"]["content"][0]["text"].as_str().unwrap()).unwrap()
+    };
+    let b64 = base64::Engine::encode(
+        &base64::engine::general_purpose::STANDARD,
+        make_png(400, 200),
+    );
+
+    // Only the billing pipeline matches a labeled base64 capture.
+    let billing =
+        result_json(&capture_image(&handler, &b64, vec!["app/billing/invoice"], None).await);
+    assert_eq!(billing["dimensions"], json!({ "width": 100, "height": 50 }));
+    assert_eq!(billing["processed"], json!(["resize (400x200 -> 100x50)"]));
+
+    let plain = result_json(&capture_image(&handler, &b64, vec!["app/home"], None).await);
+    assert_eq!(plain["dimensions"]["width"], 400);
+    assert!(plain.get("processed").is_none(), "{plain}");
+
+    // A redacted file capture is never read back from the file.
+    let file = dir.path().join("shot.png");
+    let mut img = image::RgbImage::new(80, 80);
+    img.pixels_mut()
+        .for_each(|p| *p = image::Rgb([250, 250, 250]));
+    img.save(&file).unwrap();
+    let resp = send_unwrap(
+        &handler,
+        mcp_request(
+            2,
+            "tools/call",
+            json!({
+                "name": "vision_capture",
+                "arguments": { "source": { "type": "file", "path": file.to_str().unwrap() } }
+            }),
+        ),
+    )
+    .await;
+    let redacted = result_json(&resp)["capture_id"].as_u64().unwrap();
+    let guard = session.lock().await;
+    let (thumb, source) = guard.source_image(redacted).unwrap();
+    assert_eq!(source, "thumbnail");
+    assert!(image::GenericImageView::get_pixel(&thumb, 10, 10)[0] < 20);
+    let steps: Vec<&str> = guard
+        .store()
+        .get(redacted)
+        .unwrap()
+        .provenance
+        .as_ref()
+        .unwrap()
+        .transforms
+        .iter()
+        .map(|t| t.step.as_str())
+        .collect();
+    assert_eq!(steps, vec!["redact", "thumbnail"]);
+
+    println!("TEST BONUS — Capture pipelines: PASS");
+}
+
+// ─────────── BONUS: Watch folders ───────────
+
+/// Bonus: images saved in a watched folder are ingested and announced
+#[tokio::test]
+async fn test_bonus_watch_dir() {
+    use agentic_vision_mcp::watch::{self, WatchConfig};
+
+    let dir = tempfile::tempdir().unwrap();
+    let inbox = dir.path().join("Inbox");
+    std::fs::create_dir_all(inbox.join("app/checkout")).unwrap();
+    std::fs::write(inbox.join("old.png"), make_png(8, 8)).unwrap();
+
+    let handler = ProtocolHandler::new(arc_session(&dir));
+    let mut notifications = handler.subscribe_notifications();
+    let mut config = WatchConfig::new(vec![inbox.clone()]);
+    config.settle = std::time::Duration::from_millis(100);
+    let cancel = CancellationToken::new();
+    let task = watch::spawn(&handler, &config, cancel.clone()).unwrap();
+
+    // Hidden and non-image files are ignored, as are files already there.
+    std::fs::write(inbox.join("app/.tmp.png"), make_png(8, 8)).unwrap();
+    std::fs::write(inbox.join("app/notes.txt"), b"not an image").unwrap();
+    std::fs::write(inbox.join("app/checkout/shot.png"), make_png(16, 16)).unwrap();
+
+    async fn next(notifications: &mut tokio::sync::broadcast::Receiver<Value>) -> Value {
+        tokio::time::timeout(std::time::Duration::from_secs(10), notifications.recv())
+            .await
+            .expect("no notification")
+            .unwrap()
+    }
+    let changed = next(&mut notifications).await;
+    assert_eq!(changed["method"], "notifications/resources/list_changed");
+    let message = next(&mut notifications).await;
+    assert_eq!(message["method"], "notifications/message");
+    let data = &message["params"]["data"];
+    assert_eq!(data["event"], "capture");
+    assert_eq!(data["labels"], json!(["Inbox/app/checkout"]));
+    let id = data["capture_id"].as_u64().unwrap();
+    assert_eq!(data["uri"], format!("avis://capture/{id}"));
+
+    {
+        let session = handler.session().lock().await;
+        assert_eq!(session.store().count(), 1);
+        let capture = session.store().get(id).unwrap();
+        assert_eq!(capture.metadata.labels, vec!["Inbox/app/checkout"]);
+        assert_eq!(capture.metadata.original_width, 16);
+        let provenance = capture.provenance.as_ref().unwrap();
+        assert_eq!(provenance.tool.as_deref(), Some("watch"));
+    }
+
+    cancel.cancel();
+    task.await.unwrap();
+    std::fs::write(inbox.join("late.png"), make_png(8, 8)).unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(400)).await;
+    assert_eq!(handler.session().lock().await.store().count(), 1);
+
+    println!("TEST BONUS — Watch folders: PASS");
+}
+
+// ─────────── BONUS: Session summary ───────────
+
+/// Bonus: the summary resource lists screens, representatives and key moments
+#[tokio::test]
+async fn test_bonus_session_summary() {
+    async fn read_resource(handler: &ProtocolHandler, uri: &str) -> Value {
+        let msg = mcp_request(2, "resources/read", json!({ "uri": uri }));
+        send_unwrap(handler, msg).await
+    }
+    fn solid_png(color: [u8; 3]) -> String {
+        let img =
+            image::DynamicImage::ImageRgb8(image::RgbImage::from_pixel(32, 32, image::Rgb(color)));
+        let mut buf = Vec::new();
+        img.write_with_encoder(image::codecs::png::PngEncoder::new(&mut buf))
+            .unwrap();
+        base64::Engine::encode(&base64::engine::general_purpose::STANDARD, buf)
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let handler = ProtocolHandler::new(arc_session(&dir));
+    send_unwrap(&handler, init_request()).await;
+
+    // Login, home, an error flashing up, then home again.
+    let screens = [
+        ([250, 250, 250], "login"),
+        ([250, 250, 250], "login"),
+        ([20, 20, 20], "home"),
+        ([200, 20, 20], "error"),
+        ([20, 20, 20], "home"),
+        ([20, 20, 20], "home"),
+    ];
+    for (color, label) in screens {
+        capture_image(&handler, &solid_png(color), vec![label], None).await;
+    }
+
+    let templates = send_unwrap(
+        &handler,
+        mcp_request(3, "resources/templates/list", json!({})),
+    )
+    .await;
+    assert!(templates
+        .to_string()
+        .contains("avis://session/{id}/summary"));
+
+    let resp = read_resource(&handler, "avis://session/1/summary?thumbnails=true").await;
+    let text = resp["result"]["contents"][0]["text"].as_str().unwrap();
+    let summary: Value = serde_json::from_str(text).unwrap();
+    assert_eq!(summary["capture_count"], 6);
+    assert_eq!(summary["features"], "layout");
+    assert_eq!(summary["screen_count"], 3);
+    let top = &summary["representatives"][0];
+    assert_eq!(top["captures"], 3);
+    assert_eq!(top["labels"], json!(["home"]));
+    assert!(top["thumbnail_uri"]
+        .as_str()
+        .unwrap()
+        .starts_with("avis://thumbnail/"));
+    assert!(top["thumbnail_base64"].is_string());
+    let moments = summary["moments"].as_array().unwrap();
+    assert!(moments
+        .iter()
+        .any(|m| m["kind"] == "anomaly" && m["capture_id"] == 4));
+    assert!(moments
+        .iter()
+        .any(|m| m["kind"] == "new_screen" && m["capture_id"] == 3));
+
+    let short = read_resource(&handler, "avis://session/1/summary?moments=1").await;
+    let text = short["result"]["contents"][0]["text"].as_str().unwrap();
+    let short: Value = serde_json::from_str(text).unwrap();
+    assert_eq!(short["moments"].as_array().unwrap().len(), 1);
+    assert!(short["representatives"][0]["thumbnail_base64"].is_null());
+
+    let bad = read_resource(&handler, "avis://session/1/summary?limit=2").await;
+    assert!(bad.get("error").is_some(), "{bad}");
+    let missing = read_resource(&handler, "avis://session/99/summary").await;
+    assert!(missing.get("error").is_some(), "{missing}");
+
+    println!("TEST BONUS — Session summary: PASS");
+}
@@ -0,0 +1,259 @@
+//! Calibrating similarity scores into probabilities.
+//!
+//! What a cosine score means depends on the model and on what the store
+//! holds: 0.8 may be the same screen in one store and an unrelated one in
+//! another. A calibration is fitted from pairs of labeled captures, taking
+//! two captures that share a label as the same and two that share none as
+//! different, by logistic regression of the outcome on the score (Platt
+//! scaling). It then turns a score into the probability that two captures
+//! show the same thing.
+
+use serde::{Deserialize, Serialize};
+
+use crate::labels::normalize_label;
+use crate::similarity::cosine_similarity;
+use crate::types::{VisionError, VisionResult, VisualMemoryStore};
+
+/// Pairs scored by default; larger stores are sampled.
+pub const DEFAULT_MAX_PAIRS: usize = 20_000;
+
+/// Fewest pairs of each kind, same and different, a fit needs.
+pub const MIN_PAIRS_PER_CLASS: usize = 5;
+
+/// Mapping from similarity scores to probabilities, fitted to a store.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Calibration {
+    /// Slope and intercept of `p = 1 / (1 + exp(a * score + b))`.
+    pub a: f64,
+    pub b: f64,
+    /// Pairs the fit used, and how many of them were the same.
+    pub pairs: usize,
+    pub same_pairs: usize,
+    /// Score at which a pair is as likely the same as not, if there is one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<f32>,
+    /// Share of the pairs the threshold classifies correctly.
+    pub accuracy: f64,
+    /// Mean log loss of the fitted probabilities on the pairs.
+    pub log_loss: f64,
+    pub fitted_at: u64,
+}
+
+impl Calibration {
+    /// Probability that two captures with similarity `score` are the same.
+    pub fn probability(&self, score: f32) -> f32 {
+        sigmoid(-(self.a * score as f64 + self.b)) as f32
+    }
+}
+
+fn sigmoid(x: f64) -> f64 {
+    if x >= 0.0 {
+        1.0 / (1.0 + (-x).exp())
+    } else {
+        let e = x.exp();
+        e / (1.0 + e)
+    }
+}
+
+/// Small deterministic generator for sampling pairs, so that refitting an
+/// unchanged store gives the same calibration.
+struct XorShift(u64);
+
+impl XorShift {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+}
+
+impl VisualMemoryStore {
+    /// Similarity scores of labeled capture pairs, each with whether the
+    /// pair shares a label. Captures without labels or a real embedding are
+    /// left out. All pairs are scored if there are at most `max_pairs`, and
+    /// `max_pairs` random ones otherwise.
+    pub fn labeled_pairs(&self, max_pairs: usize) -> Vec<(f32, bool)> {
+        let labeled: Vec<(&[f32], Vec<String>)> = self
+            .observations
+            .iter()
+            .filter(|o| o.embedding.iter().any(|&x| x != 0.0))
+            .filter(|o| !o.metadata.labels.is_empty())
+            .map(|o| {
+                let labels = o
+                    .metadata
+                    .labels
+                    .iter()
+                    .map(|l| normalize_label(&self.resolve_label(l)))
+                    .collect();
+                (o.embedding.as_slice(), labels)
+            })
+            .collect();
+        let n = labeled.len();
+        let pair = |i: usize, j: usize| {
+            let (a, labels_a) = &labeled[i];
+            let (b, labels_b) = &labeled[j];
+            let same = labels_a.iter().any(|l| labels_b.contains(l));
+            (cosine_similarity(a, b), same)
+        };
+
+        let total = n * n.saturating_sub(1) / 2;
+        if total <= max_pairs {
+            return (0..n)
+                .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+                .map(|(i, j)| pair(i, j))
+                .collect();
+        }
+        let mut rng = XorShift(0x9E37_79B9_7F4A_7C15);
+        (0..max_pairs)
+            .map(|_| {
+                let i = rng.below(n);
+                let j = (i + 1 + rng.below(n - 1)) % n;
+                pair(i, j)
+            })
+            .collect()
+    }
+
+    /// Fit a calibration to the store's labeled pairs (see
+    /// [`labeled_pairs`](Self::labeled_pairs)).
+    pub fn fit_calibration(&self, max_pairs: usize, now: u64) -> VisionResult<Calibration> {
+        let pairs = self.labeled_pairs(max_pairs);
+        let same = pairs.iter().filter(|(_, same)| *same).count();
+        let different = pairs.len() - same;
+        if same < MIN_PAIRS_PER_CLASS || different < MIN_PAIRS_PER_CLASS {
+            return Err(VisionError::InvalidInput(format!(
+                "Calibration needs at least {MIN_PAIRS_PER_CLASS} pairs of captures sharing a label \
+                 and {MIN_PAIRS_PER_CLASS} sharing none; found {same} and {different}"
+            )));
+        }
+
+        let (a, b) = fit_platt(&pairs, same, different);
+        let mut calibration = Calibration {
+            a,
+            b,
+            pairs: pairs.len(),
+            same_pairs: same,
+            threshold: None,
+            accuracy: 0.0,
+            log_loss: 0.0,
+            fitted_at: now,
+        };
+        if a != 0.0 {
+            calibration.threshold = Some((-b / a) as f32);
+        }
+        let mut correct = 0;
+        let mut loss = 0.0;
+        for &(score, same) in &pairs {
+            let p = (calibration.probability(score) as f64).clamp(1e-12, 1.0 - 1e-12);
+            correct += usize::from((p >= 0.5) == same);
+            loss -= if same { p.ln() } else { (1.0 - p).ln() };
+        }
+        calibration.accuracy = correct as f64 / pairs.len() as f64;
+        calibration.log_loss = loss / pairs.len() as f64;
+        Ok(calibration)
+    }
+}
+
+/// Platt's fit of `p = 1 / (1 + exp(a * s + b))` by Newton's method with
+/// backtracking, on targets smoothed towards the class priors (Lin, Lin and
+/// Weng, 2007).
+fn fit_platt(pairs: &[(f32, bool)], same: usize, different: usize) -> (f64, f64) {
+    let hi = (same as f64 + 1.0) / (same as f64 + 2.0);
+    let lo = 1.0 / (different as f64 + 2.0);
+    let data: Vec<(f64, f64)> = pairs
+        .iter()
+        .map(|&(s, same)| (s as f64, if same { hi } else { lo }))
+        .collect();
+    let objective = |a: f64, b: f64| -> f64 {
+        data.iter()
+            .map(|&(s, t)| {
+                let f = s * a + b;
+                if f >= 0.0 {
+                    t * f + (-f).exp().ln_1p()
+                } else {
+                    (t - 1.0) * f + f.exp().ln_1p()
+                }
+            })
+            .sum()
+    };
+
+    let mut a = 0.0;
+    let mut b = ((different as f64 + 1.0) / (same as f64 + 1.0)).ln();
+    let mut value = objective(a, b);
+    for _ in 0..100 {
+        let (mut h11, mut h22, mut h21, mut g1, mut g2) = (1e-12, 1e-12, 0.0, 0.0, 0.0);
+        for &(s, t) in &data {
+            // p = 1 / (1 + exp(f)) and q = 1 - p.
+            let p = sigmoid(-(s * a + b));
+            let q = 1.0 - p;
+            let d2 = p * q;
+            h11 += s * s * d2;
+            h22 += d2;
+            h21 += s * d2;
+            let d1 = t - p;
+            g1 += s * d1;
+            g2 += d1;
+        }
+        if g1.abs() < 1e-5 && g2.abs() < 1e-5 {
+            break;
+        }
+        let det = h11 * h22 - h21 * h21;
+        let da = -(h22 * g1 - h21 * g2) / det;
+        let db = -(-h21 * g1 + h11 * g2) / det;
+        let gd = g1 * da + g2 * db;
+        let mut step = 1.0;
+        while step >= 1e-10 {
+            let (na, nb) = (a + step * da, b + step * db);
+            let next = objective(na, nb);
+            if next < value + 1e-4 * step * gd {
+                (a, b, value) = (na, nb, next);
+                break;
+            }
+            step /= 2.0;
+        }
+        if step < 1e-10 {
+            break;
+        }
+    }
+    (a, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::VisualObservation;
+
+    fn obs(embedding: Vec<f32>, label: &str) -> VisualObservation {
+        VisualObservation::test(embedding, &[label])
+    }
+
+    #[test]
+    fn test_fit_calibration() {
+        let mut store = VisualMemoryStore::new(3);
+        // Two screens, each captured a few times with a little noise.
+        for i in 0..6 {
+            let noise = i as f32 * 0.05;
+            store.add(obs(vec![1.0, noise, 0.0], "app/login"));
+            store.add(obs(vec![0.2, 1.0, noise], "app/checkout"));
+        }
+        store.add(obs(vec![0.0, 0.0, 0.0], "app/login"));
+
+        // 12 embedded captures: 66 pairs, 30 of them on the same screen.
+        let pairs = store.labeled_pairs(DEFAULT_MAX_PAIRS);
+        assert_eq!(pairs.len(), 66);
+        assert_eq!(pairs.iter().filter(|(_, same)| *same).count(), 30);
+        assert_eq!(store.labeled_pairs(10).len(), 10);
+
+        let calibration = store.fit_calibration(DEFAULT_MAX_PAIRS, 5).unwrap();
+        assert_eq!(calibration.same_pairs, 30);
+        assert!(calibration.accuracy > 0.95, "{calibration:?}");
+        assert!(calibration.probability(0.99) > 0.9);
+        assert!(calibration.probability(0.3) < 0.1);
+        let threshold = calibration.threshold.unwrap();
+        assert!((calibration.probability(threshold) - 0.5).abs() < 1e-3);
+
+        let mut unlabeled = VisualMemoryStore::new(3);
+        unlabeled.add(obs(vec![1.0, 0.0, 0.0], "only"));
+        assert!(unlabeled.fit_calibration(DEFAULT_MAX_PAIRS, 5).is_err());
+    }
+}
@@ -0,0 +1,238 @@
+//! Image captioning with a BLIP-style model via ONNX Runtime.
+//!
+//! The model is split the way BLIP exports usually are: a vision encoder
+//! (`pixel_values` → image features) and a text decoder (`input_ids` and
+//! `encoder_hidden_states` → `logits`), plus the WordPiece vocabulary the
+//! decoder was trained with. Captions are decoded greedily.
+
+use std::path::{Path, PathBuf};
+
+use image::DynamicImage;
+use ndarray::Array4;
+use ort::session::Session;
+use ort::value::Tensor;
+
+use crate::types::{VisionError, VisionResult};
+
+/// Default model directory, under the home directory.
+const MODEL_DIR: &str = ".agentic-vision/models/blip-caption";
+
+/// Files expected in the model directory.
+const ENCODER_FILENAME: &str = "vision_encoder.onnx";
+const DECODER_FILENAME: &str = "text_decoder.onnx";
+const VOCAB_FILENAME: &str = "vocab.txt";
+
+/// BLIP image preprocessing constants (CLIP normalization at 384px).
+const BLIP_IMAGE_SIZE: u32 = 384;
+#[allow(clippy::excessive_precision)]
+const BLIP_MEAN: [f32; 3] = [0.48145466, 0.4578275, 0.40821073];
+#[allow(clippy::excessive_precision)]
+const BLIP_STD: [f32; 3] = [0.26862954, 0.26130258, 0.27577711];
+
+/// Longest caption generated, in tokens.
+const MAX_CAPTION_TOKENS: usize = 30;
+
+/// Token that starts decoding; BLIP uses `[DEC]`, BERT-style vocabularies `[CLS]`.
+const BOS_TOKENS: [&str; 2] = ["[DEC]", "[CLS]"];
+const EOS_TOKEN: &str = "[SEP]";
+
+struct CaptionModel {
+    encoder: Session,
+    decoder: Session,
+    vocab: Vec<String>,
+    bos: i64,
+    eos: i64,
+}
+
+/// Engine for generating short natural-language captions of images.
+pub struct CaptionEngine {
+    model: Option<CaptionModel>,
+}
+
+impl CaptionEngine {
+    /// Create a new caption engine.
+    ///
+    /// If `model_dir` is provided, loads the model from that directory.
+    /// Otherwise, looks in `~/.agentic-vision/models/blip-caption/`.
+    /// If no model is found, the engine produces no captions.
+    pub fn new(model_dir: Option<&str>) -> VisionResult<Self> {
+        let dir = match model_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => {
+                let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+                PathBuf::from(home).join(MODEL_DIR)
+            }
+        };
+
+        if !dir.join(ENCODER_FILENAME).exists() {
+            tracing::warn!(
+                "Caption model not found in {}. Captures will not be captioned.",
+                dir.display()
+            );
+            return Ok(Self { model: None });
+        }
+
+        tracing::info!("Loading caption model from {}", dir.display());
+
+        let vocab: Vec<String> = std::fs::read_to_string(dir.join(VOCAB_FILENAME))?
+            .lines()
+            .map(str::to_string)
+            .collect();
+        let id_of = |token: &str| vocab.iter().position(|t| t == token).map(|i| i as i64);
+        let bos = BOS_TOKENS.iter().find_map(|t| id_of(t)).ok_or_else(|| {
+            VisionError::Caption("Caption vocabulary has no [DEC] or [CLS]".into())
+        })?;
+        let eos = id_of(EOS_TOKEN)
+            .ok_or_else(|| VisionError::Caption("Caption vocabulary has no [SEP]".into()))?;
+
+        let model = CaptionModel {
+            encoder: load_session(&dir.join(ENCODER_FILENAME))?,
+            decoder: load_session(&dir.join(DECODER_FILENAME))?,
+            vocab,
+            bos,
+            eos,
+        };
+
+        tracing::info!("Caption model loaded successfully");
+        Ok(Self { model: Some(model) })
+    }
+
+    /// Check if the engine has a loaded model.
+    pub fn has_model(&self) -> bool {
+        self.model.is_some()
+    }
+
+    /// Generate a caption for an image. Returns `None` if no model is
+    /// loaded or the model produced no words.
+    pub fn caption(&mut self, img: &DynamicImage) -> VisionResult<Option<String>> {
+        let Some(model) = &mut self.model else {
+            return Ok(None);
+        };
+
+        let features = {
+            let outputs = model
+                .encoder
+                .run(ort::inputs!["pixel_values" => preprocess(img)?])
+                .map_err(|e| VisionError::Caption(format!("Caption encoder failed: {e}")))?;
+            let (shape, data) = outputs[0]
+                .try_extract_tensor::<f32>()
+                .map_err(|e| VisionError::Caption(format!("Failed to extract features: {e}")))?;
+            (shape.to_vec(), data.to_vec())
+        };
+
+        let mut ids = vec![model.bos];
+        while ids.len() <= MAX_CAPTION_TOKENS {
+            let input_ids = Tensor::from_array(([1, ids.len()], ids.clone()))
+                .map_err(|e| VisionError::Caption(format!("Failed to create input ids: {e}")))?;
+            let hidden = Tensor::from_array((features.0.clone(), features.1.clone()))
+                .map_err(|e| VisionError::Caption(format!("Failed to create features: {e}")))?;
+            let outputs = model
+                .decoder
+                .run(ort::inputs![
+                    "input_ids" => input_ids,
+                    "encoder_hidden_states" => hidden,
+                ])
+                .map_err(|e| VisionError::Caption(format!("Caption decoder failed: {e}")))?;
+            let (shape, logits) = outputs["logits"]
+                .try_extract_tensor::<f32>()
+                .map_err(|e| VisionError::Caption(format!("Failed to extract logits: {e}")))?;
+
+            // Greedy: the most likely token after the last position.
+            let vocab_size = shape.last().map_or(0, |&n| n as usize);
+            let last = &logits[logits.len().saturating_sub(vocab_size)..];
+            let next = last
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.total_cmp(b.1))
+                .map(|(i, _)| i as i64)
+                .unwrap_or(model.eos);
+            if next == model.eos {
+                break;
+            }
+            ids.push(next);
+        }
+
+        let caption = decode_tokens(&model.vocab, &ids[1..]);
+        Ok((!caption.is_empty()).then_some(caption))
+    }
+}
+
+fn load_session(path: &Path) -> VisionResult<Session> {
+    Session::builder()
+        .and_then(|b| b.with_intra_threads(1))
+        .and_then(|b| b.commit_from_file(path))
+        .map_err(|e| {
+            VisionError::Caption(format!("Failed to load ONNX model {}: {e}", path.display()))
+        })
+}
+
+/// Resize to 384x384 and normalize into an NCHW tensor.
+fn preprocess(img: &DynamicImage) -> VisionResult<Tensor<f32>> {
+    let rgb = img
+        .resize_exact(
+            BLIP_IMAGE_SIZE,
+            BLIP_IMAGE_SIZE,
+            image::imageops::FilterType::Triangle,
+        )
+        .to_rgb8();
+
+    let size = BLIP_IMAGE_SIZE as usize;
+    let mut tensor = Array4::<f32>::zeros((1, 3, size, size));
+    for (x, y, pixel) in rgb.enumerate_pixels() {
+        for c in 0..3usize {
+            let val = pixel[c] as f32 / 255.0;
+            tensor[[0, c, y as usize, x as usize]] = (val - BLIP_MEAN[c]) / BLIP_STD[c];
+        }
+    }
+
+    Tensor::from_array(tensor)
+        .map_err(|e| VisionError::Caption(format!("Failed to create input tensor: {e}")))
+}
+
+/// Join WordPiece tokens into text: `##` pieces continue the previous word,
+/// and bracketed special tokens are dropped.
+fn decode_tokens(vocab: &[String], ids: &[i64]) -> String {
+    let mut text = String::new();
+    for token in ids.iter().filter_map(|&id| vocab.get(id as usize)) {
+        if token.starts_with('[') && token.ends_with(']') {
+            continue;
+        }
+        match token.strip_prefix("##") {
+            Some(piece) => text.push_str(piece),
+            None => {
+                if !text.is_empty() {
+                    text.push(' ');
+                }
+                text.push_str(token);
+            }
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fallback_mode() {
+        let mut engine = CaptionEngine::new(Some("/nonexistent/blip")).unwrap();
+        assert!(!engine.has_model());
+
+        let img = DynamicImage::new_rgb8(100, 100);
+        assert_eq!(engine.caption(&img).unwrap(), None);
+    }
+
+    #[test]
+    fn test_decode_wordpiece() {
+        let vocab: Vec<String> = ["[PAD]", "[SEP]", "a", "check", "##out", "page", "[UNK]"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        assert_eq!(
+            decode_tokens(&vocab, &[2, 3, 4, 5, 6, 1]),
+            "a checkout page"
+        );
+        assert_eq!(decode_tokens(&vocab, &[0, 99]), "");
+    }
+}
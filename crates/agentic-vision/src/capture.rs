@@ -1,4 +1,9 @@
-//! Image capture and thumbnail generation.
+//! Image capture, thumbnail generation and capture pipelines.
+//!
+//! A pipeline is a chain of stages (resize, crop, redact, deskew, contrast
+//! normalization, OCR, captioning) run on every capture from some sources
+//! or with some labels before it is stored, so that what a team keeps is
+//! standardized on the server rather than by each client.
 
 use std::io::Cursor;
 use std::path::Path;
@@ -6,7 +11,9 @@ use std::process::Command;
 
 use image::codecs::jpeg::JpegEncoder;
 use image::{DynamicImage, GenericImageView, ImageFormat};
+use serde::{Deserialize, Serialize};
 
+use crate::labels::{label_matches, normalize_label};
 use crate::types::{CaptureSource, Rect, VisionError, VisionResult};
 
 /// Maximum thumbnail dimension (width or height).
@@ -364,6 +371,394 @@ pub fn is_supported_format(path: &str) -> bool {
     )
 }
 
+// ---------------------------------------------------------------------------
+// Capture pipelines
+// ---------------------------------------------------------------------------
+
+/// Steps of a pipeline that change the stored pixels, as recorded in a
+/// capture's provenance. A capture with any of them no longer matches its
+/// original file.
+pub const PIPELINE_STEPS: [&str; 5] = ["resize", "crop", "redact", "deskew", "contrast_normalize"];
+
+/// Default largest skew, in degrees, that `deskew` corrects.
+pub const DEFAULT_MAX_SKEW: f32 = 5.0;
+
+/// Default share of pixels clipped at each end by `contrast_normalize`.
+pub const DEFAULT_CONTRAST_CLIP: f32 = 0.01;
+
+/// One stage of a capture pipeline, written as `{"op": "resize", ...}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Stage {
+    /// Shrink to fit within the given size, keeping the aspect ratio.
+    /// Smaller images are left alone.
+    Resize {
+        #[serde(default)]
+        max_width: Option<u32>,
+        #[serde(default)]
+        max_height: Option<u32>,
+    },
+    /// Keep only a region, in pixels of the image as it reaches the stage.
+    Crop(Rect),
+    /// Paint regions over with a solid colour (black by default).
+    Redact {
+        regions: Vec<Rect>,
+        #[serde(default)]
+        color: [u8; 3],
+    },
+    /// Straighten text rotated by up to `max_angle` degrees.
+    Deskew {
+        #[serde(default = "default_max_skew")]
+        max_angle: f32,
+    },
+    /// Stretch brightness levels to the full range, ignoring the darkest
+    /// and brightest `clip` share of pixels.
+    ContrastNormalize {
+        #[serde(default = "default_contrast_clip")]
+        clip: f32,
+    },
+    /// Read the text of the processed image and store it with the capture.
+    Ocr {
+        #[serde(default = "default_ocr_language")]
+        language: String,
+    },
+    /// Caption the capture as it is stored, even when captions are lazy.
+    Caption,
+}
+
+fn default_max_skew() -> f32 {
+    DEFAULT_MAX_SKEW
+}
+
+fn default_contrast_clip() -> f32 {
+    DEFAULT_CONTRAST_CLIP
+}
+
+fn default_ocr_language() -> String {
+    "eng".to_string()
+}
+
+impl Stage {
+    fn validate(&self) -> VisionResult<()> {
+        let invalid = |msg: String| Err(VisionError::InvalidInput(msg));
+        match self {
+            Self::Resize {
+                max_width: None,
+                max_height: None,
+            } => invalid("resize needs max_width or max_height".to_string()),
+            Self::Resize {
+                max_width: Some(0), ..
+            }
+            | Self::Resize {
+                max_height: Some(0),
+                ..
+            } => invalid("resize needs a size above 0".to_string()),
+            Self::Crop(r) if r.w == 0 || r.h == 0 => {
+                invalid("crop needs a region of at least 1x1".to_string())
+            }
+            Self::Deskew { max_angle } if !(*max_angle > 0.0 && *max_angle <= 45.0) => invalid(
+                format!("deskew max_angle must be in (0, 45], not {max_angle}"),
+            ),
+            Self::ContrastNormalize { clip } if !(0.0..0.5).contains(clip) => invalid(format!(
+                "contrast_normalize clip must be in [0, 0.5), not {clip}"
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Stages run on captures from some sources or with some labels.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CapturePipeline {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Source types it applies to (`file`, `base64`, `screenshot`,
+    /// `clipboard`); empty for all.
+    #[serde(default)]
+    pub sources: Vec<String>,
+    /// Labels a capture must have one of, or one under; empty for all.
+    #[serde(default)]
+    pub labels: Vec<String>,
+    pub stages: Vec<Stage>,
+}
+
+impl CapturePipeline {
+    /// Whether the pipeline applies to a capture from `source` with `labels`.
+    pub fn matches(&self, source: &CaptureSource, labels: &[String]) -> bool {
+        let source_ok = self.sources.is_empty() || self.sources.iter().any(|s| s == source.kind());
+        let labels_ok = self.labels.is_empty()
+            || self.labels.iter().any(|branch| {
+                let branch = normalize_label(branch);
+                labels
+                    .iter()
+                    .any(|l| label_matches(&normalize_label(l), &branch))
+            });
+        source_ok && labels_ok
+    }
+}
+
+/// The capture pipelines of a server, as read from its JSON config.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PipelineConfig {
+    pub pipelines: Vec<CapturePipeline>,
+}
+
+/// An image after its pipelines, with what they did and asked for.
+#[derive(Debug, Clone)]
+pub struct Processed {
+    pub image: DynamicImage,
+    /// Stages that changed the image, as provenance step and detail.
+    pub steps: Vec<(&'static str, String)>,
+    /// Language to read the text in, if a stage asked for OCR.
+    pub ocr: Option<String>,
+    /// Whether a stage asked for a caption.
+    pub caption: bool,
+}
+
+impl PipelineConfig {
+    /// Parse and check a config.
+    pub fn from_json(json: &str) -> VisionResult<Self> {
+        let config: Self = serde_json::from_str(json)
+            .map_err(|e| VisionError::InvalidInput(format!("Invalid pipeline config: {e}")))?;
+        for pipeline in &config.pipelines {
+            for source in &pipeline.sources {
+                if !["file", "base64", "screenshot", "clipboard"].contains(&source.as_str()) {
+                    return Err(VisionError::InvalidInput(format!(
+                        "Unknown source type '{source}' in pipeline config"
+                    )));
+                }
+            }
+            for stage in &pipeline.stages {
+                stage.validate()?;
+            }
+        }
+        Ok(config)
+    }
+
+    /// Run the stages of every pipeline matching the capture, in order.
+    pub fn apply(
+        &self,
+        img: DynamicImage,
+        source: &CaptureSource,
+        labels: &[String],
+    ) -> VisionResult<Processed> {
+        let mut processed = Processed {
+            image: img,
+            steps: Vec::new(),
+            ocr: None,
+            caption: false,
+        };
+        let stages = self
+            .pipelines
+            .iter()
+            .filter(|p| p.matches(source, labels))
+            .flat_map(|p| &p.stages);
+        for stage in stages {
+            apply_stage(&mut processed, stage)?;
+        }
+        Ok(processed)
+    }
+}
+
+fn apply_stage(processed: &mut Processed, stage: &Stage) -> VisionResult<()> {
+    let img = &processed.image;
+    let (w, h) = img.dimensions();
+    let (step, detail, image) = match stage {
+        Stage::Resize {
+            max_width,
+            max_height,
+        } => {
+            let (max_w, max_h) = (
+                max_width.unwrap_or(u32::MAX),
+                max_height.unwrap_or(u32::MAX),
+            );
+            if w <= max_w && h <= max_h {
+                return Ok(());
+            }
+            let resized = img.resize(max_w, max_h, image::imageops::FilterType::Lanczos3);
+            let detail = format!("{w}x{h} -> {}x{}", resized.width(), resized.height());
+            ("resize", detail, resized)
+        }
+        Stage::Crop(r) => {
+            let (x, y) = (r.x.min(w), r.y.min(h));
+            let (cw, ch) = (r.w.min(w - x), r.h.min(h - y));
+            if cw == 0 || ch == 0 {
+                return Err(VisionError::InvalidInput(format!(
+                    "Crop region {}x{} at {},{} lies outside the {w}x{h} image",
+                    r.w, r.h, r.x, r.y
+                )));
+            }
+            let detail = format!("{cw}x{ch} at {x},{y}");
+            ("crop", detail, img.crop_imm(x, y, cw, ch))
+        }
+        Stage::Redact { regions, color } => {
+            let mut rgba = img.to_rgba8();
+            let mut painted = 0;
+            for r in regions {
+                let (x1, y1) = (
+                    r.x.saturating_add(r.w).min(w),
+                    r.y.saturating_add(r.h).min(h),
+                );
+                for y in r.y.min(h)..y1 {
+                    for x in r.x.min(w)..x1 {
+                        rgba.put_pixel(x, y, image::Rgba([color[0], color[1], color[2], 255]));
+                    }
+                }
+                painted += usize::from(r.x < w && r.y < h);
+            }
+            if painted == 0 {
+                return Ok(());
+            }
+            let detail = format!("{painted} region(s)");
+            ("redact", detail, DynamicImage::ImageRgba8(rgba))
+        }
+        Stage::Deskew { max_angle } => {
+            let angle = detect_skew(img, *max_angle);
+            if angle.abs() < 0.1 {
+                return Ok(());
+            }
+            ("deskew", format!("{angle:.2} degrees"), rotate(img, angle))
+        }
+        Stage::ContrastNormalize { clip } => {
+            let Some((lo, hi)) = luma_levels(img, *clip) else {
+                return Ok(());
+            };
+            let scale = 255.0 / (hi - lo) as f32;
+            let mut rgba = img.to_rgba8();
+            for pixel in rgba.pixels_mut() {
+                for c in &mut pixel.0[..3] {
+                    *c = ((*c as f32 - lo as f32) * scale).round().clamp(0.0, 255.0) as u8;
+                }
+            }
+            let detail = format!("levels {lo}-{hi} -> 0-255");
+            ("contrast_normalize", detail, DynamicImage::ImageRgba8(rgba))
+        }
+        Stage::Ocr { language } => {
+            processed.ocr = Some(language.clone());
+            return Ok(());
+        }
+        Stage::Caption => {
+            processed.caption = true;
+            return Ok(());
+        }
+    };
+    processed.image = image;
+    processed.steps.push((step, detail));
+    Ok(())
+}
+
+/// Darkest and brightest luma levels once `clip` of the pixels at each end
+/// are ignored, or `None` if they already span the full range or are flat.
+fn luma_levels(img: &DynamicImage, clip: f32) -> Option<(u8, u8)> {
+    let luma = img.to_luma8();
+    let mut histogram = [0usize; 256];
+    for p in luma.pixels() {
+        histogram[p[0] as usize] += 1;
+    }
+    let skip = (luma.len() as f32 * clip) as usize;
+    // First level, walking from one end, past the skipped pixels.
+    fn level(histogram: &[usize; 256], skip: usize, mut levels: impl Iterator<Item = usize>) -> u8 {
+        let mut seen = 0;
+        levels
+            .find(|&l| {
+                seen += histogram[l];
+                seen > skip
+            })
+            .unwrap_or(0) as u8
+    }
+    let lo = level(&histogram, skip, 0..256);
+    let hi = level(&histogram, skip, (0..256).rev());
+    (hi > lo && (lo, hi) != (0, 255)).then_some((lo, hi))
+}
+
+/// Angle in degrees, within `max_angle` either way, by which text lines in
+/// `img` run downhill from left to right: the angle at which the rows of
+/// dark pixels are most sharply separated.
+pub fn detect_skew(img: &DynamicImage, max_angle: f32) -> f32 {
+    const SEARCH_SIZE: u32 = 400;
+    const STEP: f32 = 0.25;
+
+    let small = img.resize(
+        SEARCH_SIZE,
+        SEARCH_SIZE,
+        image::imageops::FilterType::Triangle,
+    );
+    let luma = small.to_luma8();
+    let mean = luma.pixels().map(|p| p[0] as u64).sum::<u64>() / luma.len().max(1) as u64;
+    let ink: Vec<(f32, f32)> = luma
+        .enumerate_pixels()
+        .filter(|(_, _, p)| (p[0] as u64) + 32 < mean)
+        .map(|(x, y, _)| (x as f32, y as f32))
+        .collect();
+    if ink.is_empty() {
+        return 0.0;
+    }
+
+    let (w, h) = luma.dimensions();
+    let rows = (w + h) as usize * 2;
+    let steps = (max_angle / STEP).floor() as i32;
+    let mut best = (0.0, 0.0f64);
+    for i in -steps..=steps {
+        let angle = i as f32 * STEP;
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let mut profile = vec![0u32; rows];
+        for &(x, y) in &ink {
+            let row = (y * cos - x * sin).round() as i64 + (w + h) as i64;
+            profile[row.clamp(0, rows as i64 - 1) as usize] += 1;
+        }
+        let score: f64 = profile.iter().map(|&n| (n as f64).powi(2)).sum();
+        // Prefer the smallest angle among equals.
+        if score > best.1 || (score == best.1 && angle.abs() < f32::abs(best.0)) {
+            best = (angle, score);
+        }
+    }
+    best.0
+}
+
+/// Rotate `img` about its centre so that lines running downhill at `angle`
+/// degrees become level. Corners brought in are filled with the mean colour
+/// of the image's border.
+fn rotate(img: &DynamicImage, angle: f32) -> DynamicImage {
+    let src = img.to_rgba8();
+    let (w, h) = src.dimensions();
+    let border: Vec<&image::Rgba<u8>> = src
+        .enumerate_pixels()
+        .filter(|(x, y, _)| *x == 0 || *y == 0 || *x == w - 1 || *y == h - 1)
+        .map(|(_, _, p)| p)
+        .collect();
+    let mut fill = [0u8; 4];
+    for (c, value) in fill.iter_mut().enumerate() {
+        let sum: u64 = border.iter().map(|p| p[c] as u64).sum();
+        *value = (sum / border.len().max(1) as u64) as u8;
+    }
+
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let (cx, cy) = (w as f32 / 2.0, h as f32 / 2.0);
+    let out = image::RgbaImage::from_fn(w, h, |u, v| {
+        let (du, dv) = (u as f32 + 0.5 - cx, v as f32 + 0.5 - cy);
+        let x = cx + du * cos - dv * sin - 0.5;
+        let y = cy + du * sin + dv * cos - 0.5;
+        if x < 0.0 || y < 0.0 || x > (w - 1) as f32 || y > (h - 1) as f32 {
+            return image::Rgba(fill);
+        }
+        // Bilinear interpolation between the four neighbours.
+        let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+        let (x1, y1) = ((x0 + 1).min(w - 1), (y0 + 1).min(h - 1));
+        let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+        let mut pixel = [0u8; 4];
+        for (c, value) in pixel.iter_mut().enumerate() {
+            let at = |x, y| src.get_pixel(x, y)[c] as f32;
+            let top = at(x0, y0) * (1.0 - fx) + at(x1, y0) * fx;
+            let bottom = at(x0, y1) * (1.0 - fx) + at(x1, y1) * fx;
+            *value = (top * (1.0 - fy) + bottom * fy).round() as u8;
+        }
+        image::Rgba(pixel)
+    });
+    DynamicImage::ImageRgba8(out)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -427,6 +822,84 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_pipeline_config() {
+        let config = PipelineConfig::from_json(
+            r#"{"pipelines": [
+                {"sources": ["screenshot"], "stages": [
+                    {"op": "resize", "max_width": 100},
+                    {"op": "redact", "regions": [{"x": 0, "y": 0, "w": 10, "h": 10}], "color": [255, 0, 0]}
+                ]},
+                {"labels": ["app/billing"], "stages": [
+                    {"op": "crop", "x": 10, "y": 10, "w": 40, "h": 20},
+                    {"op": "ocr"},
+                    {"op": "caption"}
+                ]}
+            ]}"#,
+        )
+        .unwrap();
+        let img = DynamicImage::new_rgb8(400, 200);
+        let screenshot = CaptureSource::Screenshot { region: None };
+
+        let processed = config.apply(img.clone(), &screenshot, &[]).unwrap();
+        assert_eq!(processed.image.dimensions(), (100, 50));
+        assert_eq!(processed.image.to_rgb8().get_pixel(5, 5).0, [255, 0, 0]);
+        let steps: Vec<&str> = processed.steps.iter().map(|(s, _)| *s).collect();
+        assert_eq!(steps, vec!["resize", "redact"]);
+        assert!(processed.ocr.is_none() && !processed.caption);
+
+        // Both pipelines match a labeled screenshot and run in order.
+        let labels = vec!["app/billing/invoice".to_string()];
+        let processed = config.apply(img.clone(), &screenshot, &labels).unwrap();
+        assert_eq!(processed.image.dimensions(), (40, 20));
+        assert_eq!(processed.ocr.as_deref(), Some("eng"));
+        assert!(processed.caption);
+
+        // Nothing matches a clipboard image without labels.
+        let processed = config.apply(img, &CaptureSource::Clipboard, &[]).unwrap();
+        assert_eq!(processed.image.dimensions(), (400, 200));
+        assert!(processed.steps.is_empty());
+
+        for bad in [
+            r#"{"pipelines": [{"stages": [{"op": "resize"}]}]}"#,
+            r#"{"pipelines": [{"stages": [{"op": "blur"}]}]}"#,
+            r#"{"pipelines": [{"sources": ["camera"], "stages": []}]}"#,
+            r#"{"pipelines": [{"stages": [{"op": "deskew", "max_angle": 90}]}]}"#,
+        ] {
+            assert!(PipelineConfig::from_json(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_deskew_and_contrast() {
+        // Dark text-like stripes on a light page, then tilted 3 degrees.
+        let page = DynamicImage::ImageRgb8(image::RgbImage::from_fn(300, 200, |x, y| {
+            let ink = (20..280).contains(&x) && y % 20 < 4;
+            image::Rgb(if ink { [40; 3] } else { [220; 3] })
+        }));
+        let tilted = rotate(&page, -3.0);
+        let skew = detect_skew(&tilted, DEFAULT_MAX_SKEW);
+        assert!((skew - 3.0).abs() <= 0.5, "detected {skew}");
+        assert!(detect_skew(&page, DEFAULT_MAX_SKEW).abs() < 0.1);
+
+        let mut processed = Processed {
+            image: tilted,
+            steps: Vec::new(),
+            ocr: None,
+            caption: false,
+        };
+        apply_stage(&mut processed, &Stage::Deskew { max_angle: 5.0 }).unwrap();
+        assert_eq!(processed.steps[0].0, "deskew");
+        assert!(detect_skew(&processed.image, DEFAULT_MAX_SKEW).abs() <= 0.5);
+
+        // Levels 40-220 stretch to 0-255.
+        apply_stage(&mut processed, &Stage::ContrastNormalize { clip: 0.0 }).unwrap();
+        let luma = processed.image.to_luma8();
+        let levels = luma.pixels().map(|p| p[0]);
+        assert_eq!(levels.clone().min(), Some(0));
+        assert_eq!(levels.max(), Some(255));
+    }
+
     #[test]
     fn test_capture_screenshot_with_zero_region() {
         // Zero-size region — should not panic regardless of platform
@@ -0,0 +1,324 @@
+//! Finding and merging duplicate captures.
+//!
+//! Exact duplicates share a content address (the SHA-256 of the thumbnail).
+//! Near duplicates are the same screen captured twice: their perceptual
+//! hashes differ in only a few bits and, when both have CLIP embeddings,
+//! those are nearly identical too. Duplicates are grouped transitively and
+//! each group keeps its oldest capture.
+
+use std::collections::HashMap;
+
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+use crate::labels::normalize_label;
+use crate::similarity::cosine_similarity;
+use crate::stats::{json_len, UnionFind};
+use crate::storage::content_hash;
+use crate::types::VisualMemoryStore;
+
+/// Default cosine similarity at which embeddings count as the same image.
+pub const DEFAULT_SIMILARITY: f32 = 0.97;
+
+/// Default most bits two perceptual hashes of near duplicates differ in.
+pub const DEFAULT_HASH_DISTANCE: u32 = 6;
+
+/// What counts as a duplicate.
+#[derive(Debug, Clone, Copy)]
+pub struct DedupOptions {
+    /// Also look for near duplicates, not just identical images.
+    pub near: bool,
+    pub min_similarity: f32,
+    pub max_hash_distance: u32,
+}
+
+impl Default for DedupOptions {
+    fn default() -> Self {
+        Self {
+            near: true,
+            min_similarity: DEFAULT_SIMILARITY,
+            max_hash_distance: DEFAULT_HASH_DISTANCE,
+        }
+    }
+}
+
+/// Duplicates found in a store.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DedupReport {
+    pub scanned: usize,
+    pub exact_duplicates: usize,
+    pub near_duplicates: usize,
+    /// Serialized bytes the duplicates take up.
+    pub reclaimable_bytes: usize,
+    /// Largest groups first.
+    pub groups: Vec<DuplicateGroup>,
+}
+
+/// Captures of the same image.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    /// The oldest capture, kept when the group is merged.
+    pub keep: u64,
+    pub duplicates: Vec<Duplicate>,
+    pub reclaimable_bytes: usize,
+}
+
+/// A capture that duplicates the one its group keeps.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Duplicate {
+    pub id: u64,
+    /// Same thumbnail bytes as the kept capture.
+    pub exact: bool,
+    /// Bits its perceptual hash differs from the kept capture's.
+    pub hash_distance: u32,
+    /// Cosine similarity of the embeddings, when both have one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub similarity: Option<f32>,
+}
+
+/// 64-bit difference hash of an image: whether each pixel of a 9x8
+/// greyscale reduction is brighter than its right neighbour. Resizing and
+/// recompression change few bits; different content changes many.
+pub fn perceptual_hash(img: &DynamicImage) -> u64 {
+    let grey = img
+        .resize_exact(9, 8, image::imageops::FilterType::Triangle)
+        .to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let bit = grey.get_pixel(x, y)[0] > grey.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | bit as u64;
+        }
+    }
+    hash
+}
+
+impl VisualMemoryStore {
+    /// Find duplicate captures across the whole store.
+    pub fn dedup_report(&self, options: &DedupOptions) -> DedupReport {
+        let n = self.observations.len();
+        let hashes: Vec<String> = self.observations.iter().map(content_hash).collect();
+        // Undecodable thumbnails are only matched exactly.
+        let phashes: Vec<Option<u64>> = if options.near {
+            self.observations
+                .iter()
+                .map(|o| {
+                    image::load_from_memory(&o.thumbnail)
+                        .ok()
+                        .map(|img| perceptual_hash(&img))
+                })
+                .collect()
+        } else {
+            vec![None; n]
+        };
+        let embedded: Vec<bool> = self
+            .observations
+            .iter()
+            .map(|o| o.embedding.iter().any(|&x| x != 0.0))
+            .collect();
+
+        let mut groups = UnionFind::new(n);
+        let mut first: HashMap<&str, usize> = HashMap::new();
+        for (i, hash) in hashes.iter().enumerate() {
+            match first.get(hash.as_str()) {
+                Some(&j) => groups.union(i, j),
+                None => {
+                    first.insert(hash, i);
+                }
+            }
+        }
+        for i in 0..n {
+            let Some(a) = phashes[i] else { continue };
+            for j in i + 1..n {
+                let Some(b) = phashes[j] else { continue };
+                if (a ^ b).count_ones() > options.max_hash_distance {
+                    continue;
+                }
+                let (x, y) = (&self.observations[i], &self.observations[j]);
+                if embedded[i]
+                    && embedded[j]
+                    && cosine_similarity(&x.embedding, &y.embedding) < options.min_similarity
+                {
+                    continue;
+                }
+                groups.union(i, j);
+            }
+        }
+
+        let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+        for i in 0..n {
+            members.entry(groups.find(i)).or_default().push(i);
+        }
+        let mut report = DedupReport {
+            scanned: n,
+            ..DedupReport::default()
+        };
+        for indices in members.into_values().filter(|m| m.len() > 1) {
+            let keep = *indices
+                .iter()
+                .min_by_key(|&&i| self.observations[i].id)
+                .expect("group is not empty");
+            let kept = &self.observations[keep];
+            let mut group = DuplicateGroup {
+                keep: kept.id,
+                duplicates: Vec::new(),
+                reclaimable_bytes: 0,
+            };
+            for &i in indices.iter().filter(|&&i| i != keep) {
+                let obs = &self.observations[i];
+                let exact = hashes[i] == hashes[keep];
+                let hash_distance = match (phashes[i], phashes[keep]) {
+                    _ if exact => 0,
+                    (Some(a), Some(b)) => (a ^ b).count_ones(),
+                    _ => 64,
+                };
+                let similarity = (embedded[i] && embedded[keep])
+                    .then(|| cosine_similarity(&obs.embedding, &kept.embedding));
+                if exact {
+                    report.exact_duplicates += 1;
+                } else {
+                    report.near_duplicates += 1;
+                }
+                group.reclaimable_bytes += json_len(obs);
+                group.duplicates.push(Duplicate {
+                    id: obs.id,
+                    exact,
+                    hash_distance,
+                    similarity,
+                });
+            }
+            group.duplicates.sort_by_key(|d| d.id);
+            report.reclaimable_bytes += group.reclaimable_bytes;
+            report.groups.push(group);
+        }
+        report.groups.sort_by(|a, b| {
+            b.duplicates
+                .len()
+                .cmp(&a.duplicates.len())
+                .then(a.keep.cmp(&b.keep))
+        });
+        report
+    }
+
+    /// Merge each group into the capture it keeps and remove the
+    /// duplicates. The kept capture gains their labels, and takes their
+    /// memory link, description, caption, OCR text and UI elements where it
+    /// has none. Returns the number of captures removed.
+    ///
+    /// Removed captures are gone for good: restoring a snapshot does not
+    /// bring them back.
+    pub fn merge_duplicates(&mut self, groups: &[DuplicateGroup], now: u64) -> usize {
+        let mut removed = 0;
+        for group in groups {
+            if self.get(group.keep).is_none() {
+                continue;
+            }
+            for duplicate in &group.duplicates {
+                let Some(index) = self.observations.iter().position(|o| o.id == duplicate.id)
+                else {
+                    continue;
+                };
+                let dup = self.observations.remove(index);
+                removed += 1;
+                let kept = self.get_mut(group.keep).expect("kept capture exists");
+
+                for label in dup.metadata.labels {
+                    let normalized = normalize_label(&label);
+                    if !kept
+                        .metadata
+                        .labels
+                        .iter()
+                        .any(|l| normalize_label(l) == normalized)
+                    {
+                        kept.metadata.labels.push(label);
+                    }
+                }
+                kept.memory_link = kept.memory_link.or(dup.memory_link);
+                kept.metadata.description = kept
+                    .metadata
+                    .description
+                    .take()
+                    .or(dup.metadata.description);
+                kept.metadata.caption = kept.metadata.caption.take().or(dup.metadata.caption);
+                kept.ocr = kept.ocr.take().or(dup.ocr);
+                if kept.elements.is_empty() {
+                    kept.elements = dup.elements;
+                }
+                if let Some(provenance) = &mut kept.provenance {
+                    provenance.record("merge", Some(format!("capture {}", dup.id)), now);
+                }
+            }
+        }
+        if removed > 0 {
+            self.updated_at = now;
+        }
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::generate_thumbnail;
+    use crate::types::VisualObservation;
+    use image::{Rgb, RgbImage};
+
+    fn image(shift: u32) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(64, 32, |x, y| {
+            let v = ((x + shift) * 4 + y * 2).min(255) as u8;
+            Rgb([v, 255 - v, v / 2])
+        }))
+    }
+
+    fn obs(img: &DynamicImage, embedding: Vec<f32>, label: &str) -> VisualObservation {
+        VisualObservation {
+            thumbnail: generate_thumbnail(img),
+            ..VisualObservation::test(embedding, &[label])
+        }
+    }
+
+    #[test]
+    fn test_dedup_report_and_merge() {
+        let a = image(0);
+        let mut flipped = image(0);
+        flipped.invert();
+        let mut store = VisualMemoryStore::new(2);
+        store.add(obs(&a, vec![1.0, 0.0], "app/login"));
+        store.add(obs(&a, vec![1.0, 0.0], "app/home"));
+        // Slightly shifted: a near duplicate.
+        store.add(obs(&image(1), vec![0.99, 0.05], "app/login"));
+        // Same layout, but the embeddings disagree.
+        store.add(obs(&image(2), vec![0.0, 1.0], "other"));
+        store.add(obs(&flipped, vec![1.0, 0.0], "other"));
+
+        let report = store.dedup_report(&DedupOptions::default());
+        assert_eq!(report.scanned, 5);
+        assert_eq!(report.groups.len(), 1);
+        let group = &report.groups[0];
+        assert_eq!(group.keep, 1);
+        let ids: Vec<u64> = group.duplicates.iter().map(|d| d.id).collect();
+        assert_eq!(ids, vec![2, 3]);
+        assert!(group.duplicates[0].exact);
+        assert!(!group.duplicates[1].exact);
+        assert_eq!((report.exact_duplicates, report.near_duplicates), (1, 1));
+        assert!(report.reclaimable_bytes > 0);
+
+        let exact_only = store.dedup_report(&DedupOptions {
+            near: false,
+            ..DedupOptions::default()
+        });
+        assert_eq!(exact_only.near_duplicates, 0);
+        assert_eq!(exact_only.exact_duplicates, 1);
+
+        store.get_mut(3).unwrap().memory_link = Some(42);
+        assert_eq!(store.merge_duplicates(&report.groups, 10), 2);
+        assert_eq!(store.count(), 3);
+        let kept = store.get(1).unwrap();
+        assert_eq!(kept.metadata.labels, vec!["app/login", "app/home"]);
+        assert_eq!(kept.memory_link, Some(42));
+        assert!(store
+            .dedup_report(&DedupOptions::default())
+            .groups
+            .is_empty());
+    }
+}
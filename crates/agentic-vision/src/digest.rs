@@ -0,0 +1,390 @@
+//! Compact summaries of a session for an agent to narrate.
+//!
+//! Reading every capture of a long session into context wastes it on
+//! near-identical screens. A digest keeps what a narrator needs: the
+//! distinct screens the session went through, each with a representative
+//! capture, and the key moments, namely the largest visual changes between
+//! consecutive captures, the first visit to each new screen, and anomalies
+//! (a capture unlike both of its neighbours while they resemble each other,
+//! such as an error dialog that flashed up).
+//!
+//! Captures are compared by their embeddings when every capture of the
+//! session has one, and by layout fingerprints otherwise, so that stores
+//! kept without a CLIP model can be summarized too.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::diff::compute_diff;
+use crate::fingerprint::{compute_fingerprint, fingerprint_similarity};
+use crate::labels::normalize_label;
+use crate::sessions::SessionInfo;
+use crate::similarity::cosine_similarity;
+use crate::stats::CLUSTER_THRESHOLD;
+use crate::types::{VisualMemoryStore, VisualObservation};
+
+/// Key moments kept by default.
+pub const DEFAULT_MOMENTS: usize = 10;
+
+/// Representative captures kept by default.
+pub const DEFAULT_REPRESENTATIVES: usize = 4;
+
+/// Share of changed pixels below which consecutive captures are not a key
+/// moment.
+const MIN_CHANGE: f32 = 0.1;
+
+/// Most labels listed.
+const MAX_LABELS: usize = 10;
+
+/// How much to keep in a digest.
+#[derive(Debug, Clone, Copy)]
+pub struct DigestOptions {
+    pub max_moments: usize,
+    pub max_representatives: usize,
+}
+
+impl Default for DigestOptions {
+    fn default() -> Self {
+        Self {
+            max_moments: DEFAULT_MOMENTS,
+            max_representatives: DEFAULT_REPRESENTATIVES,
+        }
+    }
+}
+
+/// What captures were compared by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestFeatures {
+    Embedding,
+    Layout,
+}
+
+/// Why a capture is a key moment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MomentKind {
+    /// The screen changed a lot since the previous capture.
+    Change,
+    /// The first capture of a screen not seen earlier in the session.
+    NewScreen,
+    /// Unlike both neighbouring captures, which resemble each other.
+    Anomaly,
+}
+
+/// A capture worth narrating.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyMoment {
+    pub kind: MomentKind,
+    pub capture_id: u64,
+    pub timestamp: u64,
+    /// How strongly the capture stands out, from 0 to 1: the share of
+    /// changed pixels for a change, one minus the similarity to the closest
+    /// screen or neighbour otherwise.
+    pub score: f32,
+    /// The capture compared against, for a change.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screen: Option<usize>,
+    /// Caption, or else description, of the capture.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// A group of captures of the same screen.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Screen {
+    /// Position in order of first visit, from 0.
+    pub index: usize,
+    /// The capture closest to the middle of the group.
+    pub representative: u64,
+    pub captures: usize,
+    pub first_seen: u64,
+    pub last_seen: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// Summary of one session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionDigest {
+    #[serde(flatten)]
+    pub info: SessionInfo,
+    pub capture_count: usize,
+    pub first_capture: Option<u64>,
+    pub last_capture: Option<u64>,
+    pub features: DigestFeatures,
+    /// Every screen visited, in order of first visit.
+    pub screen_count: usize,
+    /// Labels with their capture counts, most used first.
+    pub labels: Vec<(String, usize)>,
+    /// The most visited screens' representatives, most visited first.
+    pub representatives: Vec<Screen>,
+    /// The strongest key moments, in time order.
+    pub moments: Vec<KeyMoment>,
+}
+
+fn text_of(obs: &VisualObservation) -> Option<String> {
+    obs.metadata
+        .caption
+        .clone()
+        .or_else(|| obs.metadata.description.clone())
+}
+
+impl VisualMemoryStore {
+    /// Digest of session `id`, or `None` if it has neither metadata nor
+    /// captures.
+    pub fn session_digest(&self, id: u32, options: &DigestOptions) -> Option<SessionDigest> {
+        let mut captures = self.by_session(id);
+        if captures.is_empty() && self.session_info(id).is_none() {
+            return None;
+        }
+        captures.sort_by_key(|o| (o.timestamp, o.id));
+        let n = captures.len();
+        let thumbnails: Vec<Option<image::DynamicImage>> = captures
+            .iter()
+            .map(|o| image::load_from_memory(&o.thumbnail).ok())
+            .collect();
+
+        let embedded = n > 0
+            && captures
+                .iter()
+                .all(|o| o.embedding.iter().any(|&x| x != 0.0));
+        let features = if embedded {
+            DigestFeatures::Embedding
+        } else {
+            DigestFeatures::Layout
+        };
+        let vectors: Vec<Vec<f32>> = captures
+            .iter()
+            .zip(&thumbnails)
+            .map(|(o, img)| match features {
+                DigestFeatures::Embedding => o.embedding.clone(),
+                DigestFeatures::Layout if !o.fingerprint.is_empty() => o.fingerprint.clone(),
+                DigestFeatures::Layout => img.as_ref().map(compute_fingerprint).unwrap_or_default(),
+            })
+            .collect();
+        let similarity = |a: &[f32], b: &[f32]| match features {
+            DigestFeatures::Embedding => cosine_similarity(a, b),
+            DigestFeatures::Layout => fingerprint_similarity(a, b),
+        };
+
+        // Screens: each capture joins the first screen whose leader it
+        // resembles, or starts a new one.
+        let mut leaders: Vec<usize> = Vec::new();
+        let mut screen_of = Vec::with_capacity(n);
+        let mut moments = Vec::new();
+        for (i, v) in vectors.iter().enumerate() {
+            let best = leaders
+                .iter()
+                .enumerate()
+                .map(|(s, &l)| (s, similarity(v, &vectors[l])))
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            match best {
+                Some((s, sim)) if sim >= CLUSTER_THRESHOLD => screen_of.push(s),
+                _ => {
+                    if let Some((_, sim)) = best {
+                        moments.push(KeyMoment {
+                            kind: MomentKind::NewScreen,
+                            capture_id: captures[i].id,
+                            timestamp: captures[i].timestamp,
+                            score: (1.0 - sim).clamp(0.0, 1.0),
+                            previous_id: None,
+                            screen: Some(leaders.len()),
+                            text: text_of(captures[i]),
+                        });
+                    }
+                    screen_of.push(leaders.len());
+                    leaders.push(i);
+                }
+            }
+        }
+
+        for i in 1..n {
+            let (Some(a), Some(b)) = (&thumbnails[i - 1], &thumbnails[i]) else {
+                continue;
+            };
+            let Ok(diff) = compute_diff(captures[i - 1].id, captures[i].id, a, b) else {
+                continue;
+            };
+            if diff.pixel_diff_ratio >= MIN_CHANGE {
+                moments.push(KeyMoment {
+                    kind: MomentKind::Change,
+                    capture_id: captures[i].id,
+                    timestamp: captures[i].timestamp,
+                    score: diff.pixel_diff_ratio,
+                    previous_id: Some(captures[i - 1].id),
+                    screen: Some(screen_of[i]),
+                    text: text_of(captures[i]),
+                });
+            }
+        }
+
+        for i in 1..n.saturating_sub(1) {
+            let (prev, cur, next) = (&vectors[i - 1], &vectors[i], &vectors[i + 1]);
+            if similarity(prev, next) < CLUSTER_THRESHOLD {
+                continue;
+            }
+            let closest = similarity(prev, cur).max(similarity(cur, next));
+            if closest < CLUSTER_THRESHOLD {
+                moments.push(KeyMoment {
+                    kind: MomentKind::Anomaly,
+                    capture_id: captures[i].id,
+                    timestamp: captures[i].timestamp,
+                    score: (1.0 - closest).clamp(0.0, 1.0),
+                    previous_id: None,
+                    screen: Some(screen_of[i]),
+                    text: text_of(captures[i]),
+                });
+            }
+        }
+
+        moments.sort_by(|a, b| b.score.total_cmp(&a.score));
+        moments.truncate(options.max_moments);
+        moments.sort_by_key(|m| (m.timestamp, m.capture_id));
+
+        let mut screens: Vec<Screen> = leaders
+            .iter()
+            .enumerate()
+            .map(|(s, &leader)| {
+                let members: Vec<usize> = (0..n).filter(|&i| screen_of[i] == s).collect();
+                let representative = medoid(&members, &vectors, &similarity).unwrap_or(leader);
+                let mut labels: Vec<String> = members
+                    .iter()
+                    .flat_map(|&i| &captures[i].metadata.labels)
+                    .map(|l| normalize_label(&self.resolve_label(l)))
+                    .collect();
+                labels.sort();
+                labels.dedup();
+                Screen {
+                    index: s,
+                    representative: captures[representative].id,
+                    captures: members.len(),
+                    first_seen: captures[members[0]].timestamp,
+                    last_seen: captures[members[members.len() - 1]].timestamp,
+                    labels,
+                    text: text_of(captures[representative]),
+                }
+            })
+            .collect();
+        let screen_count = screens.len();
+        screens.sort_by_key(|s| (std::cmp::Reverse(s.captures), s.index));
+        screens.truncate(options.max_representatives);
+
+        let mut label_counts: BTreeMap<String, usize> = BTreeMap::new();
+        for o in &captures {
+            for label in &o.metadata.labels {
+                *label_counts
+                    .entry(normalize_label(&self.resolve_label(label)))
+                    .or_default() += 1;
+            }
+        }
+        let mut labels: Vec<(String, usize)> = label_counts.into_iter().collect();
+        labels.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        labels.truncate(MAX_LABELS);
+
+        Some(SessionDigest {
+            info: self.session_info(id).cloned().unwrap_or(SessionInfo {
+                id,
+                ..SessionInfo::default()
+            }),
+            capture_count: n,
+            first_capture: captures.first().map(|o| o.timestamp),
+            last_capture: captures.last().map(|o| o.timestamp),
+            features,
+            screen_count,
+            labels,
+            representatives: screens,
+            moments,
+        })
+    }
+}
+
+/// The member closest to the mean of the members' vectors.
+fn medoid(
+    members: &[usize],
+    vectors: &[Vec<f32>],
+    similarity: &impl Fn(&[f32], &[f32]) -> f32,
+) -> Option<usize> {
+    let dim = vectors[*members.first()?].len();
+    let mut mean = vec![0.0f32; dim];
+    for &i in members {
+        for (m, x) in mean.iter_mut().zip(&vectors[i]) {
+            *m += x / members.len() as f32;
+        }
+    }
+    members
+        .iter()
+        .copied()
+        .max_by(|&a, &b| similarity(&vectors[a], &mean).total_cmp(&similarity(&vectors[b], &mean)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::generate_thumbnail;
+    use image::{DynamicImage, Rgb, RgbImage};
+
+    fn obs(timestamp: u64, embedding: Vec<f32>, color: [u8; 3], label: &str) -> VisualObservation {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(32, 32, Rgb(color)));
+        VisualObservation {
+            timestamp,
+            thumbnail: generate_thumbnail(&img),
+            ..VisualObservation::test(embedding, &[label])
+        }
+    }
+
+    #[test]
+    fn test_session_digest() {
+        let mut store = VisualMemoryStore::new(3);
+        let login = vec![1.0, 0.0, 0.0];
+        let home = vec![0.0, 1.0, 0.0];
+        let error = vec![0.0, 0.0, 1.0];
+        store.add(obs(10, login.clone(), [250, 250, 250], "app/login"));
+        store.add(obs(20, login.clone(), [250, 250, 250], "app/login"));
+        store.add(obs(30, home.clone(), [20, 20, 20], "app/home"));
+        store.add(obs(40, error, [200, 20, 20], "app/error"));
+        store.add(obs(50, home.clone(), [20, 20, 20], "app/home"));
+        store.add(obs(60, home, [20, 20, 20], "app/home"));
+
+        let digest = store.session_digest(1, &DigestOptions::default()).unwrap();
+        assert_eq!(digest.capture_count, 6);
+        assert_eq!(digest.features, DigestFeatures::Embedding);
+        assert_eq!(digest.screen_count, 3);
+        assert_eq!(digest.labels[0], ("app/home".to_string(), 3));
+        // The home screen was visited most; its representative is one of
+        // its captures.
+        assert_eq!(digest.representatives[0].captures, 3);
+        assert!([3, 5, 6].contains(&digest.representatives[0].representative));
+
+        let kinds = |id: u64| -> Vec<MomentKind> {
+            digest
+                .moments
+                .iter()
+                .filter(|m| m.capture_id == id)
+                .map(|m| m.kind)
+                .collect()
+        };
+        assert!(kinds(2).is_empty());
+        assert!(kinds(3).contains(&MomentKind::NewScreen));
+        assert!(kinds(3).contains(&MomentKind::Change));
+        assert!(kinds(4).contains(&MomentKind::Anomaly));
+        assert!(digest
+            .moments
+            .windows(2)
+            .all(|w| w[0].timestamp <= w[1].timestamp));
+
+        let few = DigestOptions {
+            max_moments: 2,
+            max_representatives: 1,
+        };
+        let digest = store.session_digest(1, &few).unwrap();
+        assert_eq!(digest.moments.len(), 2);
+        assert_eq!(digest.representatives.len(), 1);
+        assert!(store.session_digest(9, &few).is_none());
+    }
+}
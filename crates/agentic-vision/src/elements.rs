@@ -0,0 +1,359 @@
+//! UI element detection: buttons, inputs, dialogs and icons in captures.
+//!
+//! The detector (feature `ui-detect`) runs a YOLO-style ONNX model whose
+//! output is `[1, 4 + classes, boxes]`: box centre, size, then one score per
+//! class. Decoding, non-maximum suppression and colour naming are plain
+//! functions so they work, and are tested, without a model.
+
+use image::DynamicImage;
+
+use crate::types::{Rect, UiElement};
+
+/// Element kinds in class order, used when the model ships no `classes.txt`.
+pub const DEFAULT_ELEMENT_KINDS: [&str; 4] = ["button", "input", "dialog", "icon"];
+
+/// Detections scoring below this are dropped.
+pub const MIN_CONFIDENCE: f32 = 0.35;
+
+/// Overlapping detections of one kind above this IoU are merged.
+const NMS_IOU: f32 = 0.5;
+
+/// A detection before its embedding is computed.
+#[derive(Debug, Clone)]
+pub struct Detection {
+    pub kind: String,
+    pub bbox: Rect,
+    pub confidence: f32,
+}
+
+/// Decode a `[4 + classes, boxes]` output (row-major, `boxes` columns) for
+/// a model input of `input_size` pixels square, scaling boxes to an image of
+/// `width` x `height`. Keeps detections above `MIN_CONFIDENCE`, after
+/// per-kind non-maximum suppression, best first.
+pub fn decode_detections(
+    output: &[f32],
+    boxes: usize,
+    kinds: &[String],
+    input_size: u32,
+    width: u32,
+    height: u32,
+) -> Vec<Detection> {
+    let rows = 4 + kinds.len();
+    if boxes == 0 || output.len() < rows * boxes {
+        return Vec::new();
+    }
+    let at = |row: usize, col: usize| output[row * boxes + col];
+    let sx = width as f32 / input_size as f32;
+    let sy = height as f32 / input_size as f32;
+
+    let mut detections: Vec<Detection> = (0..boxes)
+        .filter_map(|col| {
+            let (class, confidence) = (0..kinds.len())
+                .map(|k| (k, at(4 + k, col)))
+                .max_by(|a, b| a.1.total_cmp(&b.1))?;
+            if confidence < MIN_CONFIDENCE {
+                return None;
+            }
+            let (cx, cy, w, h) = (at(0, col), at(1, col), at(2, col), at(3, col));
+            let x0 = ((cx - w / 2.0) * sx).clamp(0.0, width as f32);
+            let y0 = ((cy - h / 2.0) * sy).clamp(0.0, height as f32);
+            let x1 = ((cx + w / 2.0) * sx).clamp(0.0, width as f32);
+            let y1 = ((cy + h / 2.0) * sy).clamp(0.0, height as f32);
+            (x1 - x0 >= 1.0 && y1 - y0 >= 1.0).then(|| Detection {
+                kind: kinds[class].clone(),
+                bbox: Rect {
+                    x: x0 as u32,
+                    y: y0 as u32,
+                    w: (x1 - x0) as u32,
+                    h: (y1 - y0) as u32,
+                },
+                confidence,
+            })
+        })
+        .collect();
+
+    detections.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    let mut kept: Vec<Detection> = Vec::new();
+    for det in detections {
+        if !kept
+            .iter()
+            .any(|k| k.kind == det.kind && iou(&k.bbox, &det.bbox) > NMS_IOU)
+        {
+            kept.push(det);
+        }
+    }
+    kept
+}
+
+/// Intersection over union of two rectangles.
+pub fn iou(a: &Rect, b: &Rect) -> f32 {
+    let x0 = a.x.max(b.x);
+    let y0 = a.y.max(b.y);
+    let x1 = (a.x + a.w).min(b.x + b.w);
+    let y1 = (a.y + a.h).min(b.y + b.h);
+    let inter = x1.saturating_sub(x0) as f32 * y1.saturating_sub(y0) as f32;
+    let union = (a.w * a.h + b.w * b.h) as f32 - inter;
+    if union <= 0.0 {
+        0.0
+    } else {
+        inter / union
+    }
+}
+
+/// Crop `bbox` out of `img`, clamped to the image.
+pub fn crop(img: &DynamicImage, bbox: &Rect) -> DynamicImage {
+    let x = bbox.x.min(img.width().saturating_sub(1));
+    let y = bbox.y.min(img.height().saturating_sub(1));
+    let w = bbox.w.clamp(1, img.width() - x);
+    let h = bbox.h.clamp(1, img.height() - y);
+    img.crop_imm(x, y, w, h)
+}
+
+/// Name of the dominant colour of `img`: the commonest of a coarse palette
+/// over its pixels, or a grey level for unsaturated ones.
+pub fn dominant_color(img: &DynamicImage) -> &'static str {
+    let small = img.thumbnail(32, 32).to_rgb8();
+    let mut counts = [0usize; COLOR_NAMES.len()];
+    for pixel in small.pixels() {
+        counts[color_index(pixel.0)] += 1;
+    }
+    let best = (0..COLOR_NAMES.len())
+        .max_by_key(|&i| counts[i])
+        .unwrap_or(0);
+    COLOR_NAMES[best]
+}
+
+/// Colour names `dominant_color` can return.
+pub const COLOR_NAMES: [&str; 11] = [
+    "black", "gray", "white", "red", "orange", "yellow", "green", "cyan", "blue", "purple", "pink",
+];
+
+/// Index in `COLOR_NAMES` of the colour of one pixel.
+pub(crate) fn color_index([r, g, b]: [u8; 3]) -> usize {
+    let (r, g, b) = (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let chroma = max - min;
+    if chroma < 0.15 {
+        return match max {
+            v if v < 0.25 => 0,
+            v if v < 0.8 => 1,
+            _ => 2,
+        };
+    }
+    if max < 0.2 {
+        return 0;
+    }
+    let hue = if max == r {
+        60.0 * ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / chroma + 2.0)
+    } else {
+        60.0 * ((r - g) / chroma + 4.0)
+    };
+    match hue {
+        h if !(15.0..330.0).contains(&h) => 3,
+        h if h < 45.0 => 4,
+        h if h < 70.0 => 5,
+        h if h < 165.0 => 6,
+        h if h < 200.0 => 7,
+        h if h < 260.0 => 8,
+        h if h < 290.0 => 9,
+        _ => 10,
+    }
+}
+
+impl Detection {
+    /// Complete the detection into a stored element.
+    pub fn into_element(self, color: &str, embedding: Vec<f32>) -> UiElement {
+        UiElement {
+            kind: self.kind,
+            bbox: self.bbox,
+            confidence: self.confidence,
+            color: color.to_string(),
+            embedding,
+        }
+    }
+}
+
+#[cfg(feature = "ui-detect")]
+pub use detector::ElementDetector;
+
+#[cfg(feature = "ui-detect")]
+mod detector {
+    use std::path::PathBuf;
+
+    use image::DynamicImage;
+    use ndarray::Array4;
+    use ort::session::Session;
+    use ort::value::Tensor;
+
+    use super::{decode_detections, Detection, DEFAULT_ELEMENT_KINDS};
+    use crate::types::{VisionError, VisionResult};
+
+    /// Default model directory, under the home directory.
+    const MODEL_DIR: &str = ".agentic-vision/models/ui-detect";
+    const MODEL_FILENAME: &str = "detector.onnx";
+    const CLASSES_FILENAME: &str = "classes.txt";
+
+    /// Square input size of the detector.
+    const INPUT_SIZE: u32 = 640;
+
+    /// Detector for UI elements in screenshots.
+    pub struct ElementDetector {
+        session: Option<Session>,
+        kinds: Vec<String>,
+    }
+
+    impl ElementDetector {
+        /// Create a new detector.
+        ///
+        /// If `model_dir` is provided, loads `detector.onnx` (and optionally
+        /// `classes.txt`, one kind per line) from it. Otherwise, looks in
+        /// `~/.agentic-vision/models/ui-detect/`. If no model is found, the
+        /// detector finds nothing.
+        pub fn new(model_dir: Option<&str>) -> VisionResult<Self> {
+            let dir = match model_dir {
+                Some(dir) => PathBuf::from(dir),
+                None => {
+                    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+                    PathBuf::from(home).join(MODEL_DIR)
+                }
+            };
+            let kinds = match std::fs::read_to_string(dir.join(CLASSES_FILENAME)) {
+                Ok(text) => text
+                    .lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+                Err(_) => DEFAULT_ELEMENT_KINDS.map(str::to_string).to_vec(),
+            };
+
+            let path = dir.join(MODEL_FILENAME);
+            if !path.exists() {
+                tracing::warn!(
+                    "UI detection model not found at {}. Captures will have no elements.",
+                    path.display()
+                );
+                return Ok(Self {
+                    session: None,
+                    kinds,
+                });
+            }
+
+            tracing::info!("Loading UI detection model from {}", path.display());
+            let session = Session::builder()
+                .and_then(|b| b.with_intra_threads(1))
+                .and_then(|b| b.commit_from_file(&path))
+                .map_err(|e| VisionError::Detection(format!("Failed to load ONNX model: {e}")))?;
+
+            Ok(Self {
+                session: Some(session),
+                kinds,
+            })
+        }
+
+        /// Check if the detector has a loaded model.
+        pub fn has_model(&self) -> bool {
+            self.session.is_some()
+        }
+
+        /// Find UI elements in an image, best first.
+        pub fn detect(&mut self, img: &DynamicImage) -> VisionResult<Vec<Detection>> {
+            let Some(session) = &mut self.session else {
+                return Ok(Vec::new());
+            };
+
+            let rgb = img
+                .resize_exact(
+                    INPUT_SIZE,
+                    INPUT_SIZE,
+                    image::imageops::FilterType::Triangle,
+                )
+                .to_rgb8();
+            let size = INPUT_SIZE as usize;
+            let mut tensor = Array4::<f32>::zeros((1, 3, size, size));
+            for (x, y, pixel) in rgb.enumerate_pixels() {
+                for c in 0..3usize {
+                    tensor[[0, c, y as usize, x as usize]] = pixel[c] as f32 / 255.0;
+                }
+            }
+            let input = Tensor::from_array(tensor).map_err(|e| {
+                VisionError::Detection(format!("Failed to create input tensor: {e}"))
+            })?;
+
+            let outputs = session
+                .run(ort::inputs![input])
+                .map_err(|e| VisionError::Detection(format!("ONNX inference failed: {e}")))?;
+            let (shape, data) = outputs[0]
+                .try_extract_tensor::<f32>()
+                .map_err(|e| VisionError::Detection(format!("Failed to extract output: {e}")))?;
+            let boxes = shape.last().map_or(0, |&n| n as usize);
+
+            Ok(decode_detections(
+                data,
+                boxes,
+                &self.kinds,
+                INPUT_SIZE,
+                img.width(),
+                img.height(),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds() -> Vec<String> {
+        DEFAULT_ELEMENT_KINDS.map(str::to_string).to_vec()
+    }
+
+    #[test]
+    fn test_decode_and_suppress() {
+        // Three boxes: two overlapping buttons and one faint input.
+        #[rustfmt::skip]
+        let output = [
+            100.0, 104.0, 300.0, // cx
+            100.0, 102.0, 300.0, // cy
+            40.0, 40.0, 50.0,    // w
+            20.0, 20.0, 50.0,    // h
+            0.9, 0.8, 0.0,       // button
+            0.0, 0.0, 0.2,       // input
+            0.0, 0.0, 0.0,       // dialog
+            0.0, 0.0, 0.0,       // icon
+        ];
+        let found = decode_detections(&output, 3, &kinds(), 640, 1280, 640);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, "button");
+        assert_eq!(found[0].bbox.x, 160);
+        assert_eq!(found[0].bbox.w, 80);
+        assert_eq!(found[0].bbox.h, 20);
+        assert!(decode_detections(&output[..10], 3, &kinds(), 640, 640, 640).is_empty());
+    }
+
+    #[test]
+    fn test_dominant_color() {
+        let solid = |rgb: [u8; 3]| {
+            DynamicImage::ImageRgb8(image::RgbImage::from_pixel(8, 8, image::Rgb(rgb)))
+        };
+        assert_eq!(dominant_color(&solid([220, 30, 30])), "red");
+        assert_eq!(dominant_color(&solid([30, 90, 220])), "blue");
+        assert_eq!(dominant_color(&solid([250, 250, 250])), "white");
+        assert_eq!(dominant_color(&solid([40, 180, 60])), "green");
+
+        let img = solid([0, 0, 0]);
+        let cropped = crop(
+            &img,
+            &Rect {
+                x: 6,
+                y: 6,
+                w: 10,
+                h: 10,
+            },
+        );
+        assert_eq!((cropped.width(), cropped.height()), (2, 2));
+    }
+}
@@ -0,0 +1,101 @@
+//! Layout and colour fingerprints for non-semantic similarity.
+//!
+//! CLIP embeddings capture what an image shows, not how it is laid out: two
+//! screens with the same structure but different content score low. A
+//! fingerprint is a compact, model-free summary of structure and colour:
+//! a luminance histogram for each cell of a 4x4 grid, then the share of
+//! pixels in each colour of a coarse palette.
+
+use image::DynamicImage;
+
+use crate::elements::{color_index, COLOR_NAMES};
+
+/// Grid cells per side.
+const GRID: u32 = 4;
+
+/// Luminance histogram bins per cell.
+const BINS: usize = 4;
+
+/// Side of the image the fingerprint is computed from.
+const SAMPLE_SIZE: u32 = 64;
+
+/// Length of a fingerprint.
+pub const FINGERPRINT_LEN: usize = (GRID * GRID) as usize * BINS + COLOR_NAMES.len();
+
+/// Compute the layout and colour fingerprint of an image.
+pub fn compute_fingerprint(img: &DynamicImage) -> Vec<f32> {
+    let rgb = img
+        .resize_exact(
+            SAMPLE_SIZE,
+            SAMPLE_SIZE,
+            image::imageops::FilterType::Triangle,
+        )
+        .to_rgb8();
+    let cell = SAMPLE_SIZE / GRID;
+    let cell_pixels = (cell * cell) as f32;
+    let total_pixels = (SAMPLE_SIZE * SAMPLE_SIZE) as f32;
+
+    let mut fingerprint = vec![0.0f32; FINGERPRINT_LEN];
+    let palette_start = FINGERPRINT_LEN - COLOR_NAMES.len();
+    for (x, y, pixel) in rgb.enumerate_pixels() {
+        let [r, g, b] = pixel.0;
+        let luma = (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32) / 256.0;
+        let bin = ((luma * BINS as f32) as usize).min(BINS - 1);
+        let cell_index = ((y / cell) * GRID + x / cell) as usize;
+        fingerprint[cell_index * BINS + bin] += 1.0 / cell_pixels;
+        fingerprint[palette_start + color_index(pixel.0)] += 1.0 / total_pixels;
+    }
+    fingerprint
+}
+
+/// Similarity of two fingerprints in `[0, 1]`: layout and colour count
+/// equally, each one minus half the L1 distance of its histograms. Returns
+/// 0 for fingerprints of different or zero length.
+pub fn fingerprint_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != FINGERPRINT_LEN || b.len() != FINGERPRINT_LEN {
+        return 0.0;
+    }
+    let l1 = |range: std::ops::Range<usize>| -> f32 {
+        a[range.clone()]
+            .iter()
+            .zip(&b[range])
+            .map(|(x, y)| (x - y).abs())
+            .sum()
+    };
+    let cells = (GRID * GRID) as usize;
+    let palette_start = cells * BINS;
+    let layout = 1.0 - l1(0..palette_start) / (2.0 * cells as f32);
+    let color = 1.0 - l1(palette_start..FINGERPRINT_LEN) / 2.0;
+    ((layout + color) / 2.0).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A white page with a dark header bar of the given colour.
+    fn page(header: [u8; 3], header_rows: u32) -> DynamicImage {
+        let img = image::RgbImage::from_fn(128, 128, |_, y| {
+            if y < header_rows {
+                image::Rgb(header)
+            } else {
+                image::Rgb([250, 250, 250])
+            }
+        });
+        DynamicImage::ImageRgb8(img)
+    }
+
+    #[test]
+    fn test_layout_similarity() {
+        let a = compute_fingerprint(&page([20, 40, 160], 32));
+        assert_eq!(a.len(), FINGERPRINT_LEN);
+        assert!((fingerprint_similarity(&a, &a) - 1.0).abs() < 1e-5);
+
+        // Same layout in another colour beats another layout.
+        let recolored = compute_fingerprint(&page([160, 20, 20], 32));
+        let relaid = compute_fingerprint(&page([20, 40, 160], 96));
+        assert!(fingerprint_similarity(&a, &recolored) > fingerprint_similarity(&a, &relaid));
+
+        assert_eq!(fingerprint_similarity(&a, &[]), 0.0);
+    }
+}
@@ -0,0 +1,245 @@
+//! Hierarchical capture labels and label aliases.
+//!
+//! Labels are `/`-separated paths such as `app/checkout/payment`. A label
+//! belongs to every branch above it (`app`, `app/checkout`), so a query for a
+//! branch matches everything under it. Aliases map an old or alternative
+//! branch onto its canonical name; renaming or merging a branch leaves an
+//! alias behind so existing callers keep working.
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::types::{VisionError, VisionResult, VisualMemoryStore};
+
+/// Separator between the levels of a hierarchical label.
+pub const SEPARATOR: char = '/';
+
+/// Alias chains longer than this are treated as cycles.
+const MAX_ALIAS_DEPTH: usize = 16;
+
+/// Canonical spelling of a label: segments trimmed, empty segments dropped.
+pub fn normalize_label(label: &str) -> String {
+    label
+        .split(SEPARATOR)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Whether `label` is `branch` or lies under it.
+pub fn label_matches(label: &str, branch: &str) -> bool {
+    label
+        .strip_prefix(branch)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(SEPARATOR))
+}
+
+/// The branches a label belongs to, from the root down to the label itself.
+pub fn label_branches(label: &str) -> impl Iterator<Item = &str> {
+    label
+        .match_indices(SEPARATOR)
+        .map(|(i, _)| &label[..i])
+        .chain(std::iter::once(label))
+}
+
+/// Move `label` from under `from` to under `to`, if it lies under `from`.
+fn rebase(label: &str, from: &str, to: &str) -> Option<String> {
+    label_matches(label, from).then(|| format!("{to}{}", &label[from.len()..]))
+}
+
+impl VisualMemoryStore {
+    /// Resolve a label to its canonical form: normalize it, then replace
+    /// the longest aliased branch it lies under, repeatedly.
+    pub fn resolve_label(&self, label: &str) -> String {
+        let mut label = normalize_label(label);
+        for _ in 0..MAX_ALIAS_DEPTH {
+            let aliased = label_branches(&label)
+                .collect::<Vec<_>>()
+                .into_iter()
+                .rev()
+                .find_map(|branch| {
+                    self.label_aliases
+                        .get(branch)
+                        .map(|target| format!("{target}{}", &label[branch.len()..]))
+                });
+            match aliased {
+                Some(resolved) => label = resolved,
+                None => break,
+            }
+        }
+        label
+    }
+
+    /// Number of observations under each label branch.
+    pub fn label_counts(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for obs in &self.observations {
+            let branches: HashSet<&str> = obs
+                .metadata
+                .labels
+                .iter()
+                .flat_map(|l| label_branches(l))
+                .collect();
+            for branch in branches {
+                *counts.entry(branch.to_string()).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+
+    /// Make `alias` resolve to `target`.
+    pub fn add_label_alias(&mut self, alias: &str, target: &str) -> VisionResult<()> {
+        let alias = normalize_label(alias);
+        let target = self.resolve_label(target);
+        if alias.is_empty() || target.is_empty() {
+            return Err(VisionError::InvalidInput(
+                "Label alias and target must not be empty".to_string(),
+            ));
+        }
+        if self.label_counts().contains_key(&alias) {
+            return Err(VisionError::InvalidInput(format!(
+                "Label '{alias}' is in use; merge it into '{target}' instead"
+            )));
+        }
+        if label_matches(&target, &alias) {
+            return Err(VisionError::InvalidInput(format!(
+                "Alias '{alias}' cannot point into its own branch ('{target}')"
+            )));
+        }
+        self.label_aliases.insert(alias, target);
+        Ok(())
+    }
+
+    /// Rename branch `from` (and everything under it) to `to`, which must
+    /// not be in use. `from` becomes an alias of `to`. Returns the number of
+    /// observations relabeled.
+    pub fn rename_label(&mut self, from: &str, to: &str) -> VisionResult<usize> {
+        let to = self.resolve_label(to);
+        if self.label_counts().contains_key(&to) {
+            return Err(VisionError::InvalidInput(format!(
+                "Label '{to}' is already in use; merge into it instead"
+            )));
+        }
+        self.relabel(from, &to)
+    }
+
+    /// Merge branch `from` (and everything under it) into `into`, which may
+    /// already be in use. `from` becomes an alias of `into`. Returns the
+    /// number of observations relabeled.
+    pub fn merge_labels(&mut self, from: &str, into: &str) -> VisionResult<usize> {
+        let into = self.resolve_label(into);
+        self.relabel(from, &into)
+    }
+
+    fn relabel(&mut self, from: &str, to: &str) -> VisionResult<usize> {
+        let from = normalize_label(from);
+        if from.is_empty() || to.is_empty() {
+            return Err(VisionError::InvalidInput(
+                "Label names must not be empty".to_string(),
+            ));
+        }
+        if label_matches(to, &from) {
+            return Err(VisionError::InvalidInput(format!(
+                "Cannot move '{from}' into its own branch ('{to}')"
+            )));
+        }
+        if !self.label_counts().contains_key(&from) {
+            return Err(VisionError::InvalidInput(format!(
+                "No capture has label '{from}'"
+            )));
+        }
+
+        let mut changed = 0;
+        for obs in &mut self.observations {
+            let labels = &mut obs.metadata.labels;
+            if !labels.iter().any(|l| label_matches(l, &from)) {
+                continue;
+            }
+            let mut seen = HashSet::new();
+            *labels = labels
+                .iter()
+                .map(|l| rebase(l, &from, to).unwrap_or_else(|| l.clone()))
+                .filter(|l| seen.insert(l.clone()))
+                .collect();
+            changed += 1;
+        }
+
+        // Aliases that pointed into the old branch follow it.
+        for target in self.label_aliases.values_mut() {
+            if let Some(moved) = rebase(target, &from, to) {
+                *target = moved;
+            }
+        }
+        self.label_aliases.insert(from, to.to_string());
+        self.label_aliases
+            .retain(|alias, target| !label_matches(target, alias));
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::VisualObservation;
+
+    fn labeled(labels: &[&str]) -> VisualObservation {
+        VisualObservation::test(Vec::new(), labels)
+    }
+
+    #[test]
+    fn test_branches_and_prefix_matching() {
+        assert_eq!(
+            normalize_label(" app / checkout//payment/ "),
+            "app/checkout/payment"
+        );
+        assert!(label_matches("app/checkout/payment", "app/checkout"));
+        assert!(label_matches("app/checkout", "app/checkout"));
+        assert!(!label_matches("app/checkouts", "app/checkout"));
+        assert_eq!(
+            label_branches("app/checkout/payment").collect::<Vec<_>>(),
+            vec!["app", "app/checkout", "app/checkout/payment"]
+        );
+
+        let mut store = VisualMemoryStore::new(512);
+        store.add(labeled(&["app/checkout/payment", "app/checkout"]));
+        store.add(labeled(&["app/home"]));
+        let counts = store.label_counts();
+        assert_eq!(counts["app"], 2);
+        assert_eq!(counts["app/checkout"], 1);
+        assert_eq!(counts["app/checkout/payment"], 1);
+    }
+
+    #[test]
+    fn test_rename_merge_and_aliases() {
+        let mut store = VisualMemoryStore::new(512);
+        store.add(labeled(&["checkout/payment"]));
+        store.add(labeled(&["cart", "app/cart"]));
+        store.add(labeled(&["app/cart/empty"]));
+
+        assert_eq!(store.rename_label("checkout", "app/checkout").unwrap(), 1);
+        assert_eq!(
+            store.observations[0].metadata.labels,
+            vec!["app/checkout/payment"]
+        );
+        assert_eq!(
+            store.resolve_label("checkout/payment"),
+            "app/checkout/payment"
+        );
+
+        // Renaming onto a used label is refused; merging is not.
+        assert!(store.rename_label("cart", "app/cart").is_err());
+        assert_eq!(store.merge_labels("cart", "app/cart").unwrap(), 1);
+        assert_eq!(store.observations[1].metadata.labels, vec!["app/cart"]);
+        assert_eq!(store.label_counts()["app/cart"], 2);
+
+        // Moving a branch carries the aliases that point into it.
+        store.rename_label("app", "web").unwrap();
+        assert_eq!(store.resolve_label("checkout"), "web/checkout");
+        assert_eq!(store.resolve_label("cart/empty"), "web/cart/empty");
+
+        assert!(store.merge_labels("web", "web/nested").is_err());
+        assert!(store.add_label_alias("web", "web/cart").is_err());
+        assert!(store.add_label_alias("web/cart", "web/checkout").is_err());
+        store.add_label_alias("basket", "cart").unwrap();
+        assert_eq!(store.resolve_label("basket"), "web/cart");
+    }
+}
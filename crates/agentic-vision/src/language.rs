@@ -0,0 +1,180 @@
+//! Guessing the language of recognized text.
+//!
+//! Text in a non-Latin script is identified by its script. Latin-script
+//! text is scored by how many of its words are common function words of
+//! each language. Languages are Tesseract codes (`eng`, `deu`), and the
+//! hints given to OCR (`eng+deu`) narrow the candidates.
+
+/// Most frequent short words of each Latin-script language.
+const STOPWORDS: &[(&str, &[&str])] = &[
+    (
+        "eng",
+        &[
+            "the", "and", "of", "to", "in", "is", "you", "that", "it", "for", "on", "with", "are",
+            "this", "your", "be", "or", "not",
+        ],
+    ),
+    (
+        "deu",
+        &[
+            "der", "die", "und", "das", "ist", "nicht", "mit", "den", "ein", "eine", "zu", "sie",
+            "ich", "auf", "für", "von", "wir", "bitte",
+        ],
+    ),
+    (
+        "fra",
+        &[
+            "le", "la", "les", "et", "des", "est", "un", "une", "du", "pour", "pas", "vous", "que",
+            "dans", "sur", "avec", "votre",
+        ],
+    ),
+    (
+        "spa",
+        &[
+            "el", "los", "las", "y", "es", "un", "una", "del", "para", "por", "con", "que", "su",
+            "no", "se", "está",
+        ],
+    ),
+    (
+        "ita",
+        &[
+            "il", "di", "che", "è", "e", "un", "una", "per", "non", "con", "gli", "della", "sono",
+            "del", "le",
+        ],
+    ),
+    (
+        "por",
+        &[
+            "o", "os", "as", "e", "do", "da", "em", "um", "uma", "para", "com", "não", "que",
+            "seu", "sua", "está",
+        ],
+    ),
+    (
+        "nld",
+        &[
+            "de", "het", "een", "en", "van", "is", "niet", "op", "voor", "met", "zijn", "je", "uw",
+            "dat",
+        ],
+    ),
+];
+
+/// Languages identified by script alone, with the range of their letters.
+const SCRIPTS: &[(&str, &[(char, char)])] = &[
+    ("rus", &[('\u{0400}', '\u{04FF}')]),
+    ("ell", &[('\u{0370}', '\u{03FF}')]),
+    ("heb", &[('\u{0590}', '\u{05FF}')]),
+    ("ara", &[('\u{0600}', '\u{06FF}')]),
+    ("hin", &[('\u{0900}', '\u{097F}')]),
+    ("tha", &[('\u{0E00}', '\u{0E7F}')]),
+    ("kor", &[('\u{AC00}', '\u{D7AF}'), ('\u{1100}', '\u{11FF}')]),
+    ("jpn", &[('\u{3040}', '\u{30FF}')]),
+    ("chi_sim", &[('\u{4E00}', '\u{9FFF}')]),
+];
+
+/// Tesseract codes of other languages written in the same script as one
+/// of `SCRIPTS`, so a hint of one of them is kept when the script matches.
+const SAME_SCRIPT: &[(&str, &[&str])] = &[
+    ("rus", &["ukr", "bel", "bul", "srp", "mkd"]),
+    ("ara", &["fas", "urd"]),
+    ("hin", &["mar", "nep", "san"]),
+    ("chi_sim", &["chi_tra", "jpn"]),
+];
+
+/// Guess the language of `text`. `hints` are the languages OCR was asked
+/// for, joined by `+`; a guess outside them is only made from the script.
+/// Returns `None` when the text gives too little to go on.
+pub fn detect_language(text: &str, hints: &str) -> Option<String> {
+    let hints: Vec<&str> = hints.split('+').filter(|h| !h.is_empty()).collect();
+
+    let mut counts = vec![0usize; SCRIPTS.len()];
+    let mut latin = 0usize;
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        match SCRIPTS
+            .iter()
+            .position(|(_, ranges)| ranges.iter().any(|&(lo, hi)| (lo..=hi).contains(&c)))
+        {
+            Some(i) => counts[i] += 1,
+            None => latin += 1,
+        }
+    }
+    // Kana marks Japanese even when most characters are Han.
+    let kana = SCRIPTS.iter().position(|(code, _)| *code == "jpn");
+    let han = SCRIPTS.iter().position(|(code, _)| *code == "chi_sim");
+    if let (Some(kana), Some(han)) = (kana, han) {
+        if counts[kana] > 0 {
+            counts[kana] += counts[han];
+            counts[han] = 0;
+        }
+    }
+
+    let (script, &count) = counts.iter().enumerate().max_by_key(|(_, &n)| n)?;
+    if count > latin {
+        let code = SCRIPTS[script].0;
+        let related = SAME_SCRIPT
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map_or(&[][..], |(_, others)| *others);
+        let hinted = hints
+            .iter()
+            .find(|h| **h == code || related.contains(h))
+            .copied();
+        return Some(hinted.unwrap_or(code).to_string());
+    }
+    if latin == 0 {
+        return None;
+    }
+
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let candidates: Vec<&(&str, &[&str])> = STOPWORDS
+        .iter()
+        .filter(|(code, _)| hints.is_empty() || hints.contains(code))
+        .collect();
+    let best = candidates
+        .iter()
+        .map(|(code, stopwords)| {
+            let hits = words
+                .iter()
+                .filter(|w| stopwords.contains(&w.as_str()))
+                .count();
+            (*code, hits)
+        })
+        .max_by_key(|&(_, hits)| hits);
+    match best {
+        Some((code, hits)) if hits > 0 => Some(code.to_string()),
+        // Nothing to tell the hints apart: a single Latin-script hint
+        // is taken at its word.
+        _ => match hints.as_slice() {
+            [only] if !SCRIPTS.iter().any(|(code, _)| code == only) => Some(only.to_string()),
+            _ => None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(
+            detect_language("Bitte geben Sie das Passwort ein", "eng+deu").as_deref(),
+            Some("deu")
+        );
+        assert_eq!(
+            detect_language("Sign in to your account", "").as_deref(),
+            Some("eng")
+        );
+        assert_eq!(
+            detect_language("Войти в систему", "eng+ukr").as_deref(),
+            Some("ukr")
+        );
+        assert_eq!(detect_language("ログイン画面", "").as_deref(), Some("jpn"));
+        assert_eq!(detect_language("OK", "fra").as_deref(), Some("fra"));
+        assert_eq!(detect_language("OK", "eng+fra"), None);
+        assert_eq!(detect_language("12:30", "eng"), None);
+    }
+}
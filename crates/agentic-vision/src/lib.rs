@@ -1,18 +1,60 @@
 //! AgenticVision — core vision library for image capture, embedding, similarity, and visual memory.
 
+pub mod calibration;
+#[cfg(feature = "caption")]
+pub mod caption;
 pub mod capture;
+pub mod dedup;
 pub mod diff;
+pub mod digest;
+pub mod elements;
 pub mod embedding;
+pub mod fingerprint;
+pub mod labels;
+pub mod language;
+pub mod migrate;
+pub mod ocr;
+pub mod provenance;
+pub mod reindex;
+pub mod sessions;
 pub mod similarity;
+pub mod stats;
 pub mod storage;
+#[cfg(feature = "text-embedding")]
+pub mod text_embedding;
 pub mod types;
+pub mod watch;
 
+pub use calibration::Calibration;
+#[cfg(feature = "caption")]
+pub use caption::CaptionEngine;
 pub use capture::{
     capture_clipboard, capture_from_base64, capture_from_file, capture_screenshot,
-    generate_thumbnail,
+    generate_thumbnail, CapturePipeline, PipelineConfig, Processed, PIPELINE_STEPS,
 };
+pub use dedup::{perceptual_hash, DedupOptions, DedupReport, DuplicateGroup};
 pub use diff::compute_diff;
+pub use digest::{DigestOptions, KeyMoment, MomentKind, SessionDigest};
+#[cfg(feature = "ui-detect")]
+pub use elements::ElementDetector;
 pub use embedding::{EmbeddingEngine, EMBEDDING_DIM};
-pub use similarity::{cosine_similarity, find_similar};
-pub use storage::{AvisReader, AvisWriter};
+pub use fingerprint::{compute_fingerprint, fingerprint_similarity};
+pub use labels::{label_branches, label_matches, normalize_label};
+pub use language::detect_language;
+pub use migrate::MigrationReport;
+#[cfg(feature = "ocr")]
+pub use ocr::OnnxOcr;
+pub use ocr::{find_text_lines, OcrBlock, OcrEngine, OcrLine, OcrResult, OcrWord, TesseractOcr};
+pub use provenance::{pixel_hash, sha256_hex, Provenance, Transform};
+pub use reindex::ReindexCheckpoint;
+pub use sessions::{SessionFilter, SessionInfo, SessionSummary};
+pub use similarity::{
+    cosine_similarity, find_similar, find_similar_blended, find_similar_filtered, rerank_diverse,
+    SimilarityFilter, DIVERSITY_POOL,
+};
+pub use stats::StoreStatistics;
+pub use storage::{content_hash, AvisReader, AvisWriter, SalvageReport};
+#[cfg(feature = "text-embedding")]
+pub use text_embedding::TextEmbeddingEngine;
 pub use types::*;
+pub use watch::{infer_labels, FolderWatcher};
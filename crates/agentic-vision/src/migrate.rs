@@ -0,0 +1,155 @@
+//! Upgrading stores written by older releases.
+//!
+//! New fields are added to the `.avis` payload with serde defaults, so old
+//! files still load, but their captures lack what newer code computes at
+//! capture time. Migration fills in what can be derived from the stored
+//! thumbnails and repairs bookkeeping; captions stay missing and are
+//! generated lazily when first needed.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::fingerprint::compute_fingerprint;
+use crate::labels::normalize_label;
+use crate::types::VisualMemoryStore;
+
+/// What a migration changed, or would change.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MigrationReport {
+    pub captures: usize,
+    /// Layout fingerprints computed from thumbnails.
+    pub fingerprints_computed: usize,
+    /// Captures whose thumbnail could not be decoded, left without one.
+    pub fingerprints_failed: usize,
+    /// Captures whose labels were rewritten to their canonical spelling.
+    pub labels_normalized: usize,
+    /// Whether `next_id` or `session_count` lagged behind the captures.
+    pub counters_repaired: bool,
+    /// Captures without a caption, captioned lazily when first queried.
+    pub captions_pending: usize,
+}
+
+impl MigrationReport {
+    /// Whether the migration changes the store.
+    pub fn changed(&self) -> bool {
+        self.fingerprints_computed > 0 || self.labels_normalized > 0 || self.counters_repaired
+    }
+}
+
+impl VisualMemoryStore {
+    /// Compute fingerprints for captures stored before fingerprints
+    /// existed, from their thumbnails. Returns how many were computed.
+    pub fn backfill_fingerprints(&mut self) -> usize {
+        let mut count = 0;
+        for obs in self
+            .observations
+            .iter_mut()
+            .filter(|o| o.fingerprint.is_empty())
+        {
+            if let Ok(thumb) = image::load_from_memory(&obs.thumbnail) {
+                obs.fingerprint = compute_fingerprint(&thumb);
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Bring the store up to date with the current release.
+    pub fn migrate(&mut self) -> MigrationReport {
+        let missing = self
+            .observations
+            .iter()
+            .filter(|o| o.fingerprint.is_empty())
+            .count();
+        let fingerprints_computed = self.backfill_fingerprints();
+
+        let mut labels_normalized = 0;
+        for obs in &mut self.observations {
+            let mut seen = HashSet::new();
+            let labels: Vec<String> = obs
+                .metadata
+                .labels
+                .iter()
+                .map(|l| normalize_label(l))
+                .filter(|l| !l.is_empty() && seen.insert(l.clone()))
+                .collect();
+            if labels != obs.metadata.labels {
+                obs.metadata.labels = labels;
+                labels_normalized += 1;
+            }
+        }
+
+        let mut counters_repaired = false;
+        if let Some(max_id) = self.observations.iter().map(|o| o.id).max() {
+            if self.next_id <= max_id {
+                self.next_id = max_id + 1;
+                counters_repaired = true;
+            }
+        }
+        if let Some(max_session) = self.observations.iter().map(|o| o.session_id).max() {
+            if self.session_count < max_session {
+                self.session_count = max_session;
+                counters_repaired = true;
+            }
+        }
+
+        MigrationReport {
+            captures: self.count(),
+            fingerprints_computed,
+            fingerprints_failed: missing - fingerprints_computed,
+            labels_normalized,
+            counters_repaired,
+            captions_pending: self
+                .observations
+                .iter()
+                .filter(|o| o.metadata.caption.is_none())
+                .count(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::VisualObservation;
+
+    fn old_capture(thumbnail: Vec<u8>, labels: &[&str]) -> VisualObservation {
+        VisualObservation {
+            session_id: 3,
+            thumbnail,
+            ..VisualObservation::test(vec![0.0; 4], labels)
+        }
+    }
+
+    fn png() -> Vec<u8> {
+        let mut buf = std::io::Cursor::new(Vec::new());
+        image::DynamicImage::new_rgb8(8, 8)
+            .write_to(&mut buf, image::ImageFormat::Png)
+            .unwrap();
+        buf.into_inner()
+    }
+
+    #[test]
+    fn test_migrate() {
+        let mut store = VisualMemoryStore::new(4);
+        store.add(old_capture(png(), &[" app / login ", "app/login", ""]));
+        store.add(old_capture(vec![1, 2, 3], &["ok"]));
+        store.next_id = 1;
+
+        let report = store.migrate();
+        assert!(report.changed());
+        assert_eq!(report.captures, 2);
+        assert_eq!(report.fingerprints_computed, 1);
+        assert_eq!(report.fingerprints_failed, 1);
+        assert_eq!(report.labels_normalized, 1);
+        assert!(report.counters_repaired);
+        assert_eq!(report.captions_pending, 2);
+        assert_eq!(store.observations[0].metadata.labels, vec!["app/login"]);
+        assert_eq!(store.next_id, 3);
+        assert_eq!(store.session_count, 3);
+
+        // A migrated store needs nothing more.
+        assert!(!store.migrate().changed());
+    }
+}
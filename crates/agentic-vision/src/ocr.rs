@@ -0,0 +1,758 @@
+//! Text recognition behind a common `OcrEngine` trait.
+//!
+//! Two engines live here: `TesseractOcr` runs the `tesseract` command, so
+//! nothing native is linked into the binary, and `OnnxOcr` (feature `ocr`)
+//! recognizes text in-process with a CRNN-style ONNX model, needing no
+//! system packages at all. Other backends, such as a remote service, only
+//! have to implement the trait.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+use crate::types::{Rect, VisionError, VisionResult};
+
+/// One recognized word.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OcrWord {
+    pub text: String,
+    pub bbox: Rect,
+    /// Recognition confidence from 0 to 1, if the engine reports one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
+}
+
+/// One recognized line of text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OcrLine {
+    pub text: String,
+    /// Bounding box in pixels of the recognized image.
+    pub bbox: Rect,
+    /// Recognition confidence from 0 to 1, if the engine reports one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
+    /// The words of the line, left to right, if the engine separates them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<OcrWord>,
+}
+
+/// Lines that are read together: a paragraph, a column, a caption.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OcrBlock {
+    pub bbox: Rect,
+    /// Indices into `OcrResult::lines`, in reading order.
+    pub lines: Vec<usize>,
+}
+
+/// Text recognized in an image.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OcrResult {
+    /// All lines in reading order; lines are separated by a newline and
+    /// blocks by an empty line.
+    pub text: String,
+    /// Lines in reading order.
+    pub lines: Vec<OcrLine>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blocks: Vec<OcrBlock>,
+    /// Language the text appears to be in, as a Tesseract code (`eng`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+impl OcrResult {
+    /// Build a result from its lines, in any order: lines are grouped into
+    /// blocks and both are put in reading order.
+    pub fn from_lines(lines: Vec<OcrLine>) -> Self {
+        let groups = group_blocks(&lines);
+        let mut slots: Vec<Option<OcrLine>> = lines.into_iter().map(Some).collect();
+        let mut ordered = Vec::with_capacity(slots.len());
+        let mut blocks = Vec::with_capacity(groups.len());
+        for group in groups {
+            let start = ordered.len();
+            ordered.extend(group.iter().filter_map(|&i| slots[i].take()));
+            blocks.push(OcrBlock {
+                bbox: union(ordered[start..].iter().map(|l| l.bbox)),
+                lines: (start..ordered.len()).collect(),
+            });
+        }
+
+        let text = blocks
+            .iter()
+            .map(|b| {
+                b.lines
+                    .iter()
+                    .map(|&i| ordered[i].text.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        Self {
+            text,
+            lines: ordered,
+            blocks,
+            language: None,
+        }
+    }
+
+    /// Scale every box by `sx` horizontally and `sy` vertically, e.g. from
+    /// thumbnail to original image pixels.
+    pub fn scale(&mut self, sx: f32, sy: f32) {
+        let scale = |r: &mut Rect| {
+            *r = Rect {
+                x: (r.x as f32 * sx).round() as u32,
+                y: (r.y as f32 * sy).round() as u32,
+                w: (r.w as f32 * sx).round() as u32,
+                h: (r.h as f32 * sy).round() as u32,
+            }
+        };
+        for line in &mut self.lines {
+            scale(&mut line.bbox);
+            for word in &mut line.words {
+                scale(&mut word.bbox);
+            }
+        }
+        for block in &mut self.blocks {
+            scale(&mut block.bbox);
+        }
+    }
+}
+
+/// The smallest rectangle around `rects`.
+fn union(rects: impl Iterator<Item = Rect>) -> Rect {
+    let (mut x0, mut y0, mut x1, mut y1) = (u32::MAX, u32::MAX, 0, 0);
+    for r in rects {
+        x0 = x0.min(r.x);
+        y0 = y0.min(r.y);
+        x1 = x1.max(r.x + r.w);
+        y1 = y1.max(r.y + r.h);
+    }
+    if x0 > x1 {
+        return Rect {
+            x: 0,
+            y: 0,
+            w: 0,
+            h: 0,
+        };
+    }
+    Rect {
+        x: x0,
+        y: y0,
+        w: x1 - x0,
+        h: y1 - y0,
+    }
+}
+
+/// Group lines into blocks and order both for reading. A line joins the
+/// block above it when the vertical gap is under one line height and the
+/// two overlap horizontally; blocks are then ordered by recursive XY-cut
+/// (split at the widest horizontal gap first, then at vertical gaps), so
+/// columns are read one after another. Returns line indices per block.
+fn group_blocks(lines: &[OcrLine]) -> Vec<Vec<usize>> {
+    let mut order: Vec<usize> = (0..lines.len()).collect();
+    order.sort_by_key(|&i| (lines[i].bbox.y, lines[i].bbox.x));
+
+    let mut groups: Vec<(Rect, Vec<usize>)> = Vec::new();
+    for i in order {
+        let b = lines[i].bbox;
+        let joins = groups.iter_mut().rev().find(|(block, members)| {
+            let last = lines[*members.last().expect("blocks are never empty")].bbox;
+            let gap = b.y as i64 - (last.y + last.h) as i64;
+            let overlaps = b.x < block.x + block.w && block.x < b.x + b.w;
+            overlaps && gap < b.h.max(last.h) as i64 && gap > -(b.h as i64 / 2)
+        });
+        match joins {
+            Some((block, members)) => {
+                *block = union([*block, b].into_iter());
+                members.push(i);
+            }
+            None => groups.push((b, vec![i])),
+        }
+    }
+
+    let mut ordered = Vec::with_capacity(groups.len());
+    xy_cut(groups, &mut ordered);
+    ordered
+}
+
+/// Order blocks by recursive XY-cut into `out`.
+fn xy_cut(mut blocks: Vec<(Rect, Vec<usize>)>, out: &mut Vec<Vec<usize>>) {
+    if blocks.len() <= 1 {
+        out.extend(blocks.into_iter().map(|(_, lines)| lines));
+        return;
+    }
+    for horizontal in [true, false] {
+        let span = |r: &Rect| {
+            if horizontal {
+                (r.y, r.y + r.h)
+            } else {
+                (r.x, r.x + r.w)
+            }
+        };
+        blocks.sort_by_key(|(r, _)| span(r));
+        let mut reach = span(&blocks[0].0).1;
+        for k in 1..blocks.len() {
+            let (start, end) = span(&blocks[k].0);
+            if start >= reach {
+                let rest = blocks.split_off(k);
+                xy_cut(blocks, out);
+                xy_cut(rest, out);
+                return;
+            }
+            reach = reach.max(end);
+        }
+    }
+    // Overlapping in both directions: read top to bottom.
+    blocks.sort_by_key(|(r, _)| (r.y, r.x));
+    out.extend(blocks.into_iter().map(|(_, lines)| lines));
+}
+
+/// A text recognition backend.
+pub trait OcrEngine: Send {
+    /// Short name of the backend, e.g. `tesseract`.
+    fn name(&self) -> &'static str;
+
+    /// Recognize the text in `img`. `language` is a hint in Tesseract's
+    /// notation (`eng`, `deu`, `eng+fra`); engines with a fixed language
+    /// ignore it.
+    fn recognize(&mut self, img: &DynamicImage, language: &str) -> VisionResult<OcrResult>;
+}
+
+/// OCR by the `tesseract` command-line program.
+pub struct TesseractOcr {
+    command: String,
+}
+
+impl TesseractOcr {
+    /// Use the `tesseract` program at `command` (a path, or a name looked
+    /// up on `PATH`).
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+        }
+    }
+}
+
+impl OcrEngine for TesseractOcr {
+    fn name(&self) -> &'static str {
+        "tesseract"
+    }
+
+    fn recognize(&mut self, img: &DynamicImage, language: &str) -> VisionResult<OcrResult> {
+        if language.is_empty()
+            || !language
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '+')
+        {
+            return Err(VisionError::InvalidInput(format!(
+                "Invalid OCR language: {language}"
+            )));
+        }
+
+        let mut png = std::io::Cursor::new(Vec::new());
+        img.write_to(&mut png, image::ImageFormat::Png)?;
+
+        let mut child = Command::new(&self.command)
+            .args(["stdin", "stdout", "-l", language, "tsv"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| {
+                VisionError::Ocr(format!(
+                    "Failed to run '{}' (is Tesseract installed?): {e}",
+                    self.command
+                ))
+            })?;
+        // Write from another thread so a full stdout pipe cannot deadlock.
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let png = png.into_inner();
+        let writer = std::thread::spawn(move || stdin.write_all(&png));
+        let output = child.wait_with_output()?;
+        let _ = writer.join();
+
+        if !output.status.success() {
+            return Err(VisionError::Ocr(format!(
+                "Tesseract failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(parse_tesseract_tsv(&String::from_utf8_lossy(
+            &output.stdout,
+        )))
+    }
+}
+
+/// Group the words of Tesseract's TSV output into lines.
+fn parse_tesseract_tsv(tsv: &str) -> OcrResult {
+    let mut lines: Vec<((u32, u32, u32), Vec<OcrWord>)> = Vec::new();
+    for row in tsv.lines().skip(1) {
+        let cols: Vec<&str> = row.split('\t').collect();
+        if cols.len() < 12 || cols[0] != "5" {
+            continue;
+        }
+        let text = cols[11].trim();
+        let nums: Vec<u32> = cols[2..10].iter().map(|c| c.parse().unwrap_or(0)).collect();
+        let confidence: f32 = cols[10].parse().unwrap_or(-1.0);
+        if text.is_empty() || confidence < 0.0 {
+            continue;
+        }
+        let key = (nums[0], nums[1], nums[2]);
+        let word = OcrWord {
+            text: text.to_string(),
+            bbox: Rect {
+                x: nums[4],
+                y: nums[5],
+                w: nums[6],
+                h: nums[7],
+            },
+            confidence: Some(confidence / 100.0),
+        };
+        match lines.last_mut().filter(|(k, _)| *k == key) {
+            Some((_, words)) => words.push(word),
+            None => lines.push((key, vec![word])),
+        }
+    }
+
+    OcrResult::from_lines(
+        lines
+            .into_iter()
+            .map(|(_, words)| OcrLine {
+                text: words
+                    .iter()
+                    .map(|w| w.text.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                bbox: union(words.iter().map(|w| w.bbox)),
+                confidence: Some(
+                    words.iter().filter_map(|w| w.confidence).sum::<f32>() / words.len() as f32,
+                ),
+                words,
+            })
+            .collect(),
+    )
+}
+
+/// Rows shorter than this are treated as noise, not text.
+const MIN_LINE_HEIGHT: u32 = 4;
+
+/// How far, in luminance, a pixel must be from the background to be ink.
+const INK_CONTRAST: f32 = 48.0;
+
+/// Find the boxes of text lines in an image, top to bottom: bands of rows
+/// with ink, split where a horizontal gap is wider than the band is tall.
+pub fn find_text_lines(img: &DynamicImage) -> Vec<Rect> {
+    let gray = img.to_luma8();
+    let (w, h) = gray.dimensions();
+    if w == 0 || h == 0 {
+        return Vec::new();
+    }
+    let background = gray.pixels().map(|p| p[0] as f32).sum::<f32>() / (w as f32 * h as f32);
+    let ink = |x: u32, y: u32| (gray.get_pixel(x, y)[0] as f32 - background).abs() > INK_CONTRAST;
+
+    let mut boxes = Vec::new();
+    let mut y = 0;
+    while y < h {
+        if !(0..w).any(|x| ink(x, y)) {
+            y += 1;
+            continue;
+        }
+        let top = y;
+        while y < h && (0..w).any(|x| ink(x, y)) {
+            y += 1;
+        }
+        let height = y - top;
+        if height < MIN_LINE_HEIGHT {
+            continue;
+        }
+
+        let column_ink: Vec<bool> = (0..w).map(|x| (top..y).any(|yy| ink(x, yy))).collect();
+        let max_gap = height + height / 2;
+        let mut start: Option<u32> = None;
+        let mut last_ink = 0;
+        for x in 0..w {
+            if !column_ink[x as usize] {
+                continue;
+            }
+            match start {
+                Some(s) if x - last_ink > max_gap => {
+                    boxes.push(Rect {
+                        x: s,
+                        y: top,
+                        w: last_ink + 1 - s,
+                        h: height,
+                    });
+                    start = Some(x);
+                }
+                None => start = Some(x),
+                _ => {}
+            }
+            last_ink = x;
+        }
+        if let Some(s) = start {
+            boxes.push(Rect {
+                x: s,
+                y: top,
+                w: last_ink + 1 - s,
+                h: height,
+            });
+        }
+    }
+    boxes
+}
+
+#[cfg(feature = "ocr")]
+pub use onnx::OnnxOcr;
+
+#[cfg(feature = "ocr")]
+mod onnx {
+    use std::path::{Path, PathBuf};
+
+    use image::DynamicImage;
+    use ndarray::Array4;
+    use ort::session::Session;
+    use ort::value::Tensor;
+
+    use super::{find_text_lines, OcrEngine, OcrLine, OcrResult, OcrWord};
+    use crate::types::{Rect, VisionError, VisionResult};
+
+    /// Default model directory, under the home directory.
+    const MODEL_DIR: &str = ".agentic-vision/models/ocr";
+
+    /// Files expected in the model directory.
+    const MODEL_FILENAME: &str = "rec.onnx";
+    const DICT_FILENAME: &str = "dict.txt";
+
+    /// Height text lines are scaled to, as PaddleOCR recognizers expect.
+    const LINE_HEIGHT: u32 = 48;
+
+    /// Widest line fed to the model.
+    const MAX_LINE_WIDTH: u32 = 1280;
+
+    struct RecModel {
+        session: Session,
+        /// Characters by class index; class 0 is the CTC blank.
+        charset: Vec<String>,
+    }
+
+    /// In-process OCR with a CRNN-style recognition model (CTC output), run
+    /// on each line `find_text_lines` finds.
+    pub struct OnnxOcr {
+        model: Option<RecModel>,
+        dir: PathBuf,
+    }
+
+    impl OnnxOcr {
+        /// Create the engine from `rec.onnx` and `dict.txt` (one character
+        /// per line) in `model_dir`, or in `~/.agentic-vision/models/ocr/`.
+        /// Without a model, recognition fails with `ModelNotAvailable`.
+        pub fn new(model_dir: Option<&str>) -> VisionResult<Self> {
+            let dir = match model_dir {
+                Some(dir) => PathBuf::from(dir),
+                None => {
+                    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+                    PathBuf::from(home).join(MODEL_DIR)
+                }
+            };
+            if !dir.join(MODEL_FILENAME).exists() {
+                tracing::warn!("OCR model not found in {}", dir.display());
+                return Ok(Self { model: None, dir });
+            }
+
+            tracing::info!("Loading OCR model from {}", dir.display());
+            let mut charset = vec![String::new()];
+            charset.extend(
+                std::fs::read_to_string(dir.join(DICT_FILENAME))?
+                    .lines()
+                    .map(str::to_string),
+            );
+            charset.push(" ".to_string());
+            let model = RecModel {
+                session: load_session(&dir.join(MODEL_FILENAME))?,
+                charset,
+            };
+            Ok(Self {
+                model: Some(model),
+                dir,
+            })
+        }
+
+        /// Check if the engine has a loaded model.
+        pub fn has_model(&self) -> bool {
+            self.model.is_some()
+        }
+    }
+
+    impl OcrEngine for OnnxOcr {
+        fn name(&self) -> &'static str {
+            "onnx"
+        }
+
+        fn recognize(&mut self, img: &DynamicImage, _language: &str) -> VisionResult<OcrResult> {
+            let Some(model) = &mut self.model else {
+                return Err(VisionError::ModelNotAvailable(format!(
+                    "OCR model not found in {}",
+                    self.dir.display()
+                )));
+            };
+
+            let mut lines = Vec::new();
+            for bbox in find_text_lines(img) {
+                let crop = img.crop_imm(bbox.x, bbox.y, bbox.w, bbox.h);
+                let outputs = model
+                    .session
+                    .run(ort::inputs![preprocess(&crop)?])
+                    .map_err(|e| VisionError::Ocr(format!("OCR model failed: {e}")))?;
+                let (shape, probs) = outputs[0]
+                    .try_extract_tensor::<f32>()
+                    .map_err(|e| VisionError::Ocr(format!("Failed to extract output: {e}")))?;
+                let classes = shape.last().map_or(0, |&n| n as usize);
+                if let Some(line) = decode_line(probs, classes, &model.charset, bbox) {
+                    lines.push(line);
+                }
+            }
+            Ok(OcrResult::from_lines(lines))
+        }
+    }
+
+    fn load_session(path: &Path) -> VisionResult<Session> {
+        Session::builder()
+            .and_then(|b| b.with_intra_threads(1))
+            .and_then(|b| b.commit_from_file(path))
+            .map_err(|e| {
+                VisionError::Ocr(format!("Failed to load ONNX model {}: {e}", path.display()))
+            })
+    }
+
+    /// Scale a line to 48px high and normalize to [-1, 1] in NCHW.
+    fn preprocess(line: &DynamicImage) -> VisionResult<Tensor<f32>> {
+        let width = (line.width() * LINE_HEIGHT / line.height().max(1)).clamp(16, MAX_LINE_WIDTH);
+        let rgb = line
+            .resize_exact(width, LINE_HEIGHT, image::imageops::FilterType::Triangle)
+            .to_rgb8();
+        let mut tensor = Array4::<f32>::zeros((1, 3, LINE_HEIGHT as usize, width as usize));
+        for (x, y, pixel) in rgb.enumerate_pixels() {
+            for c in 0..3usize {
+                tensor[[0, c, y as usize, x as usize]] = pixel[c] as f32 / 127.5 - 1.0;
+            }
+        }
+        Tensor::from_array(tensor)
+            .map_err(|e| VisionError::Ocr(format!("Failed to create input tensor: {e}")))
+    }
+
+    /// Greedy CTC decoding: the best class per step, repeats collapsed and
+    /// blanks dropped. Returns each character with its step and score.
+    fn ctc_decode(probs: &[f32], classes: usize, charset: &[String]) -> Vec<(String, usize, f32)> {
+        if classes == 0 {
+            return Vec::new();
+        }
+        let mut chars = Vec::new();
+        let mut previous = 0;
+        for (step, scores) in probs.chunks(classes).enumerate() {
+            let Some((best, score)) = scores
+                .iter()
+                .copied()
+                .enumerate()
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+            else {
+                continue;
+            };
+            if best != 0 && best != previous {
+                if let Some(c) = charset.get(best) {
+                    chars.push((c.clone(), step, score));
+                }
+            }
+            previous = best;
+        }
+        chars
+    }
+
+    /// Decode the model output of the line at `bbox` into words. A word's
+    /// box spans the output steps of its characters, which are spread
+    /// evenly across the line; its confidence is their mean score.
+    pub(super) fn decode_line(
+        probs: &[f32],
+        classes: usize,
+        charset: &[String],
+        bbox: Rect,
+    ) -> Option<OcrLine> {
+        let steps = (probs.len() / classes.max(1)).max(1) as u32;
+        let x_at = |step: usize| bbox.x + step as u32 * bbox.w / steps;
+
+        let mut words = Vec::new();
+        let mut current: Vec<(String, usize, f32)> = Vec::new();
+        let chars = ctc_decode(probs, classes, charset);
+        let mut flush = |current: &mut Vec<(String, usize, f32)>| {
+            if current.is_empty() {
+                return;
+            }
+            let first = current[0].1;
+            let last = current[current.len() - 1].1;
+            words.push(OcrWord {
+                text: current.iter().map(|c| c.0.as_str()).collect(),
+                bbox: Rect {
+                    x: x_at(first),
+                    y: bbox.y,
+                    w: x_at(last + 1) - x_at(first),
+                    h: bbox.h,
+                },
+                confidence: Some(current.iter().map(|c| c.2).sum::<f32>() / current.len() as f32),
+            });
+            current.clear();
+        };
+        for c in chars {
+            if c.0.trim().is_empty() {
+                flush(&mut current);
+            } else {
+                current.push(c);
+            }
+        }
+        flush(&mut current);
+
+        if words.is_empty() {
+            return None;
+        }
+        let confidence =
+            words.iter().filter_map(|w| w.confidence).sum::<f32>() / words.len() as f32;
+        Some(OcrLine {
+            text: words
+                .iter()
+                .map(|w| w.text.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            bbox,
+            confidence: Some(confidence),
+            words,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tesseract_tsv() {
+        let tsv = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext\n\
+            1\t1\t0\t0\t0\t0\t0\t0\t200\t100\t-1\t\n\
+            5\t1\t1\t1\t1\t1\t10\t10\t40\t12\t96\tSign\n\
+            5\t1\t1\t1\t1\t2\t55\t11\t20\t12\t90\tin\n\
+            5\t1\t1\t1\t2\t1\t10\t40\t60\t14\t80\tPassword\n\
+            5\t1\t1\t1\t2\t2\t80\t40\t5\t14\t95\t \n";
+        let result = parse_tesseract_tsv(tsv);
+        // The lines are further apart than they are tall: two blocks.
+        assert_eq!(result.text, "Sign in\n\nPassword");
+        assert_eq!(result.lines.len(), 2);
+        let first = &result.lines[0];
+        assert_eq!(
+            (first.bbox.x, first.bbox.y, first.bbox.w, first.bbox.h),
+            (10, 10, 65, 13)
+        );
+        assert!((first.confidence.unwrap() - 0.93).abs() < 1e-5);
+        assert_eq!(first.words.len(), 2);
+        assert_eq!(first.words[1].text, "in");
+        assert_eq!(first.words[1].bbox.x, 55);
+        assert!((first.words[1].confidence.unwrap() - 0.9).abs() < 1e-5);
+        assert_eq!(result.blocks.len(), 2);
+        assert_eq!(result.blocks[1].lines, vec![1]);
+    }
+
+    #[test]
+    fn test_reading_order() {
+        let line = |text: &str, x: u32, y: u32, w: u32| OcrLine {
+            text: text.to_string(),
+            bbox: Rect { x, y, w, h: 10 },
+            confidence: None,
+            words: Vec::new(),
+        };
+        // A title over two columns, given in scan order.
+        let result = OcrResult::from_lines(vec![
+            line("left 1", 10, 40, 80),
+            line("right 1", 110, 40, 80),
+            line("left 2", 10, 55, 70),
+            line("right 2", 110, 55, 60),
+            line("Title", 10, 10, 180),
+        ]);
+        assert_eq!(result.text, "Title\n\nleft 1\nleft 2\n\nright 1\nright 2");
+        assert_eq!(result.blocks.len(), 3);
+        assert_eq!(result.blocks[1].lines, vec![1, 2]);
+        let left = result.blocks[1].bbox;
+        assert_eq!((left.x, left.y, left.w, left.h), (10, 40, 80, 25));
+
+        let mut scaled = result.clone();
+        scaled.scale(2.0, 0.5);
+        let title = scaled.lines[0].bbox;
+        assert_eq!((title.x, title.y, title.w, title.h), (20, 5, 360, 5));
+    }
+
+    #[test]
+    fn test_find_text_lines() {
+        // Two dark lines of "text" on white; the second is two far-apart words.
+        let img = image::RgbImage::from_fn(200, 60, |x, y| {
+            let first = (10..20).contains(&y) && (10..90).contains(&x);
+            let second =
+                (35..45).contains(&y) && ((10..40).contains(&x) || (120..160).contains(&x));
+            if first || second {
+                image::Rgb([20, 20, 20])
+            } else {
+                image::Rgb([250, 250, 250])
+            }
+        });
+        let lines = find_text_lines(&DynamicImage::ImageRgb8(img));
+        let boxes: Vec<_> = lines.iter().map(|r| (r.x, r.y, r.w, r.h)).collect();
+        assert_eq!(
+            boxes,
+            vec![(10, 10, 80, 10), (10, 35, 30, 10), (120, 35, 40, 10)]
+        );
+        assert!(find_text_lines(&DynamicImage::new_rgb8(50, 50)).is_empty());
+    }
+
+    #[test]
+    fn test_tesseract_rejects_bad_language() {
+        let mut engine = TesseractOcr::new("tesseract");
+        let img = DynamicImage::new_rgb8(4, 4);
+        assert!(matches!(
+            engine.recognize(&img, "eng --psm"),
+            Err(VisionError::InvalidInput(_))
+        ));
+    }
+
+    #[cfg(feature = "ocr")]
+    #[test]
+    fn test_ctc_decode() {
+        let charset: Vec<String> = ["", "a", "b", " "].iter().map(|c| c.to_string()).collect();
+        // Steps: a, a, blank, a, space, b, blank, blank -> "aa b".
+        let step = |class: usize, score: f32| {
+            let mut probs = [0.0; 4];
+            probs[class] = score;
+            probs
+        };
+        let probs: Vec<f32> = [
+            step(1, 0.8),
+            step(1, 0.7),
+            step(0, 0.9),
+            step(1, 0.6),
+            step(3, 0.9),
+            step(2, 0.8),
+            step(0, 0.9),
+            step(0, 0.9),
+        ]
+        .concat();
+        let bbox = Rect {
+            x: 100,
+            y: 20,
+            w: 80,
+            h: 10,
+        };
+        let line = onnx::decode_line(&probs, 4, &charset, bbox).unwrap();
+        assert_eq!(line.text, "aa b");
+        assert_eq!(line.words.len(), 2);
+        let (first, second) = (&line.words[0], &line.words[1]);
+        assert_eq!((first.bbox.x, first.bbox.w), (100, 40));
+        assert_eq!((second.bbox.x, second.bbox.w), (150, 10));
+        assert!((first.confidence.unwrap() - 0.7).abs() < 1e-5);
+        assert!((line.confidence.unwrap() - 0.75).abs() < 1e-5);
+    }
+}
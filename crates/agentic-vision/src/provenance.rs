@@ -0,0 +1,112 @@
+//! Provenance: how a capture was produced and what happened to it since.
+//!
+//! Each capture records the call that stored it, the client and agent
+//! behind that call, a hash of the image exactly as it was received, and
+//! every transformation between that image and what is stored. Later steps
+//! that change a capture, such as importing it from another store, append
+//! to its history, so the record reads as a chain of custody.
+
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Where a capture came from and how it was made.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Provenance {
+    /// Tool that stored the capture, e.g. `vision_capture`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    /// JSON-RPC ID of the call that stored it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// Name and version of the client that made the call.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client: Option<String>,
+    /// Agent of the session the capture was stored in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    /// `file`, `base64`, `screenshot` or `clipboard`.
+    pub source_type: String,
+    /// Hex SHA-256 of the original: the bytes of a file or base64 payload,
+    /// or the RGBA pixels of a screenshot or clipboard image.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_hash: Option<String>,
+    /// What was done to the original, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transforms: Vec<Transform>,
+}
+
+/// One step in a capture's history.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Transform {
+    /// What was done, e.g. `crop`, `thumbnail` or `import`.
+    pub step: String,
+    /// Parameters of the step, e.g. `1920x1080 -> 512x288 JPEG`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Unix time of the step.
+    pub at: u64,
+}
+
+impl Provenance {
+    /// Record a step in the capture's history.
+    pub fn record(&mut self, step: &str, detail: Option<String>, at: u64) {
+        self.transforms.push(Transform {
+            step: step.to_string(),
+            detail,
+            at,
+        });
+    }
+}
+
+/// Hex SHA-256 of `bytes`.
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Hex SHA-256 of an image's RGBA pixels and dimensions, for images that
+/// never existed as a file.
+pub fn pixel_hash(img: &DynamicImage) -> String {
+    let rgba = img.to_rgba8();
+    let mut hasher = Sha256::new();
+    hasher.update(rgba.width().to_le_bytes());
+    hasher.update(rgba.height().to_le_bytes());
+    hasher.update(rgba.as_raw());
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hashes_and_history() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        let a = DynamicImage::new_rgb8(4, 2);
+        let b = DynamicImage::new_rgb8(2, 4);
+        assert_eq!(pixel_hash(&a), pixel_hash(&a.clone()));
+        assert_ne!(pixel_hash(&a), pixel_hash(&b));
+
+        let mut provenance = Provenance {
+            source_type: "file".to_string(),
+            ..Provenance::default()
+        };
+        provenance.record("thumbnail", Some("4x2 -> 4x2 JPEG".to_string()), 10);
+        provenance.record("import", None, 20);
+        let json = serde_json::to_value(&provenance).unwrap();
+        assert_eq!(json["transforms"][1]["step"], "import");
+        assert!(json.get("tool").is_none());
+        let back: Provenance = serde_json::from_value(json).unwrap();
+        assert_eq!(back, provenance);
+    }
+}
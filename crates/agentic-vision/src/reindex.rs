@@ -0,0 +1,142 @@
+//! Re-embedding a whole store in resumable batches.
+//!
+//! After a model switch every stored embedding is stale. A re-index walks
+//! the captures in ID order and replaces their embeddings a batch at a
+//! time; the checkpoint of how far it got is saved with the store, so an
+//! interrupted run resumes where it stopped rather than starting over.
+//! Captures stored after the re-index started were embedded with the new
+//! model already and are left alone.
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::VisualMemoryStore;
+
+/// Progress of a re-index, saved with the store until it completes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReindexCheckpoint {
+    /// Model the store is being re-embedded with, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    pub started_at: u64,
+    pub updated_at: u64,
+    /// Captures with a lower ID are done.
+    pub next_id: u64,
+    /// Captures from this ID on were stored after the re-index started.
+    pub end_id: u64,
+    /// Captures to re-embed when the re-index started.
+    pub total: usize,
+    pub done: usize,
+    /// Captures whose image could not be re-embedded; they keep their old
+    /// embedding.
+    pub failed: usize,
+}
+
+impl ReindexCheckpoint {
+    /// Share of the captures done, from 0 to 1.
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            1.0
+        } else {
+            (self.done + self.failed) as f64 / self.total as f64
+        }
+    }
+}
+
+impl VisualMemoryStore {
+    /// Start re-embedding with `model`, or resume the re-index in progress
+    /// if it uses the same model. Returns whether one was resumed. A new
+    /// re-index drops the calibration, which was fitted to the old scores.
+    pub fn start_reindex(&mut self, model: Option<String>, now: u64) -> bool {
+        if let Some(checkpoint) = &self.reindex {
+            if checkpoint.model == model {
+                return true;
+            }
+            tracing::warn!(
+                "Re-index with {:?} abandoned for a new one with {:?}",
+                checkpoint.model,
+                model
+            );
+        }
+        self.calibration = None;
+        self.reindex = Some(ReindexCheckpoint {
+            model,
+            started_at: now,
+            updated_at: now,
+            next_id: 0,
+            end_id: self.next_id,
+            total: self.count(),
+            done: 0,
+            failed: 0,
+        });
+        false
+    }
+
+    /// IDs of the next `size` captures to re-embed; empty when the
+    /// re-index is complete or none is in progress.
+    pub fn reindex_batch(&self, size: usize) -> Vec<u64> {
+        let Some(checkpoint) = &self.reindex else {
+            return Vec::new();
+        };
+        self.observations
+            .iter()
+            .map(|o| o.id)
+            .filter(|&id| id >= checkpoint.next_id && id < checkpoint.end_id)
+            .take(size)
+            .collect()
+    }
+
+    /// Record that the captures up to and including `last_id` are done,
+    /// `failed` of them without a new embedding.
+    pub fn advance_reindex(&mut self, last_id: u64, done: usize, failed: usize, now: u64) {
+        if let Some(checkpoint) = &mut self.reindex {
+            checkpoint.next_id = last_id + 1;
+            checkpoint.done += done;
+            checkpoint.failed += failed;
+            checkpoint.updated_at = now;
+        }
+    }
+
+    /// End the re-index in progress, returning its final checkpoint.
+    pub fn finish_reindex(&mut self) -> Option<ReindexCheckpoint> {
+        self.reindex.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::VisualObservation;
+
+    fn obs() -> VisualObservation {
+        VisualObservation::test(vec![0.0; 2], &[])
+    }
+
+    #[test]
+    fn test_reindex_batches_and_resume() {
+        let mut store = VisualMemoryStore::new(2);
+        for _ in 0..5 {
+            store.add(obs());
+        }
+        assert!(!store.start_reindex(Some("b.onnx".to_string()), 10));
+        // Stored during the re-index: already embedded with the new model.
+        store.add(obs());
+
+        assert_eq!(store.reindex_batch(2), vec![1, 2]);
+        store.advance_reindex(2, 2, 0, 11);
+        assert!(store.start_reindex(Some("b.onnx".to_string()), 12));
+        assert_eq!(store.reindex_batch(10), vec![3, 4, 5]);
+        store.advance_reindex(5, 2, 1, 13);
+        assert!(store.reindex_batch(10).is_empty());
+
+        let done = store.finish_reindex().unwrap();
+        assert_eq!((done.total, done.done, done.failed), (5, 4, 1));
+        assert!((done.fraction() - 1.0).abs() < 1e-9);
+        assert!(store.reindex.is_none());
+
+        // A different model starts over.
+        store.start_reindex(Some("a.onnx".to_string()), 20);
+        store.advance_reindex(3, 3, 0, 21);
+        assert!(!store.start_reindex(Some("c.onnx".to_string()), 22));
+        assert_eq!(store.reindex_batch(1), vec![1]);
+    }
+}
@@ -0,0 +1,215 @@
+//! Session metadata: names, goals and agent identity.
+//!
+//! Observations only carry a session ID. A session can also be given a
+//! name, a description of what it was for, and the agent that ran it, so
+//! that captures can be found by what the session was rather than by its
+//! number. Sessions without metadata still appear in summaries, described
+//! only by their captures.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::VisualMemoryStore;
+
+/// Descriptive metadata of one session.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub id: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// What the session set out to do.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Identity of the agent that ran the session.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<u64>,
+}
+
+/// A session's metadata together with what it captured.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    #[serde(flatten)]
+    pub info: SessionInfo,
+    pub capture_count: usize,
+    pub first_capture: Option<u64>,
+    pub last_capture: Option<u64>,
+    /// From the start (or first capture) to the end (or last capture).
+    pub duration_secs: Option<u64>,
+}
+
+/// Filter on session attributes; unset fields match every session.
+#[derive(Debug, Clone, Default)]
+pub struct SessionFilter {
+    /// Case-insensitive exact name.
+    pub name: Option<String>,
+    /// Exact agent identity.
+    pub agent: Option<String>,
+    /// Words that must all appear in the name or description.
+    pub text: Option<String>,
+}
+
+impl SessionFilter {
+    /// Whether no attribute is constrained.
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.agent.is_none() && self.text.is_none()
+    }
+
+    /// Whether `info` satisfies the filter.
+    pub fn matches(&self, info: &SessionInfo) -> bool {
+        if let Some(name) = &self.name {
+            if !info
+                .name
+                .as_deref()
+                .is_some_and(|n| n.eq_ignore_ascii_case(name))
+            {
+                return false;
+            }
+        }
+        if let Some(agent) = &self.agent {
+            if info.agent.as_deref() != Some(agent.as_str()) {
+                return false;
+            }
+        }
+        if let Some(text) = &self.text {
+            let haystack = [
+                info.name.as_deref().unwrap_or_default(),
+                info.description.as_deref().unwrap_or_default(),
+            ]
+            .join(" ")
+            .to_lowercase();
+            if !text
+                .split_whitespace()
+                .all(|w| haystack.contains(&w.to_lowercase()))
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl VisualMemoryStore {
+    /// Metadata of session `id`, if any was recorded.
+    pub fn session_info(&self, id: u32) -> Option<&SessionInfo> {
+        self.sessions.get(&id)
+    }
+
+    /// Metadata of session `id`, created empty if none was recorded.
+    pub fn session_info_mut(&mut self, id: u32) -> &mut SessionInfo {
+        self.sessions.entry(id).or_insert_with(|| SessionInfo {
+            id,
+            ..SessionInfo::default()
+        })
+    }
+
+    /// IDs of the sessions matching `filter`, in ascending order.
+    pub fn matching_sessions(&self, filter: &SessionFilter) -> Vec<u32> {
+        self.sessions
+            .values()
+            .filter(|info| filter.matches(info))
+            .map(|info| info.id)
+            .collect()
+    }
+
+    /// Every session with metadata or captures, in ascending ID order.
+    pub fn session_summaries(&self) -> Vec<SessionSummary> {
+        let mut captures: BTreeMap<u32, (usize, u64, u64)> = BTreeMap::new();
+        for obs in &self.observations {
+            let entry = captures
+                .entry(obs.session_id)
+                .or_insert((0, obs.timestamp, obs.timestamp));
+            entry.0 += 1;
+            entry.1 = entry.1.min(obs.timestamp);
+            entry.2 = entry.2.max(obs.timestamp);
+        }
+
+        let mut ids: Vec<u32> = self.sessions.keys().copied().collect();
+        ids.extend(captures.keys().copied());
+        ids.sort_unstable();
+        ids.dedup();
+
+        ids.into_iter()
+            .map(|id| {
+                let info = self.sessions.get(&id).cloned().unwrap_or(SessionInfo {
+                    id,
+                    ..SessionInfo::default()
+                });
+                let (capture_count, first, last) = match captures.get(&id) {
+                    Some(&(count, first, last)) => (count, Some(first), Some(last)),
+                    None => (0, None, None),
+                };
+                let start = info.started_at.or(first);
+                let end = info.ended_at.or(last);
+                let duration_secs = match (start, end) {
+                    (Some(start), Some(end)) => Some(end.saturating_sub(start)),
+                    _ => None,
+                };
+                SessionSummary {
+                    info,
+                    capture_count,
+                    first_capture: first,
+                    last_capture: last,
+                    duration_secs,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::VisualObservation;
+
+    fn obs(session_id: u32, timestamp: u64) -> VisualObservation {
+        VisualObservation {
+            session_id,
+            timestamp,
+            ..VisualObservation::test(Vec::new(), &[])
+        }
+    }
+
+    #[test]
+    fn test_session_summaries_and_filter() {
+        let mut store = VisualMemoryStore::new(3);
+        let info = store.session_info_mut(2);
+        info.name = Some("Checkout audit".to_string());
+        info.description = Some("Verify the payment flow".to_string());
+        info.agent = Some("qa-bot".to_string());
+        info.started_at = Some(100);
+        info.ended_at = Some(400);
+        store.add(obs(1, 10));
+        store.add(obs(1, 70));
+        store.add(obs(2, 150));
+
+        let summaries = store.session_summaries();
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].info.id, 1);
+        assert_eq!(summaries[0].capture_count, 2);
+        assert_eq!(summaries[0].duration_secs, Some(60));
+        assert_eq!(summaries[1].info.name.as_deref(), Some("Checkout audit"));
+        assert_eq!(summaries[1].duration_secs, Some(300));
+
+        let by_name = SessionFilter {
+            name: Some("checkout AUDIT".to_string()),
+            ..SessionFilter::default()
+        };
+        assert_eq!(store.matching_sessions(&by_name), vec![2]);
+        let by_text = SessionFilter {
+            text: Some("payment".to_string()),
+            agent: Some("qa-bot".to_string()),
+            ..SessionFilter::default()
+        };
+        assert_eq!(store.matching_sessions(&by_text), vec![2]);
+        let other_agent = SessionFilter {
+            agent: Some("other".to_string()),
+            ..SessionFilter::default()
+        };
+        assert!(store.matching_sessions(&other_agent).is_empty());
+    }
+}
@@ -1,7 +1,59 @@
 //! Vector similarity search for visual embeddings.
 
+use std::collections::HashMap;
+
+use crate::fingerprint::fingerprint_similarity;
+use crate::labels::label_matches;
 use crate::types::{SimilarityMatch, VisualObservation};
 
+/// Which captures a similarity search considers. Checked before a capture
+/// is scored, so a narrow filter skips most of the work on a big store.
+#[derive(Debug, Clone, Default)]
+pub struct SimilarityFilter {
+    /// Captures with any of these labels or labels under them. Aliases
+    /// should already be resolved.
+    pub labels: Vec<String>,
+    /// Captures taken at or after this Unix time.
+    pub after: Option<u64>,
+    /// Captures taken at or before this Unix time.
+    pub before: Option<u64>,
+    /// Captures from any of these sessions.
+    pub session_ids: Vec<u32>,
+    /// Captures linked to this memory node.
+    pub memory_link: Option<u64>,
+}
+
+impl SimilarityFilter {
+    /// Whether nothing is constrained.
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+            && self.after.is_none()
+            && self.before.is_none()
+            && self.session_ids.is_empty()
+            && self.memory_link.is_none()
+    }
+
+    /// Whether `obs` satisfies the filter. Cheap checks come first.
+    pub fn matches(&self, obs: &VisualObservation) -> bool {
+        if self.after.is_some_and(|after| obs.timestamp < after)
+            || self.before.is_some_and(|before| obs.timestamp > before)
+        {
+            return false;
+        }
+        if !self.session_ids.is_empty() && !self.session_ids.contains(&obs.session_id) {
+            return false;
+        }
+        if self.memory_link.is_some() && obs.memory_link != self.memory_link {
+            return false;
+        }
+        self.labels.is_empty()
+            || self
+                .labels
+                .iter()
+                .any(|branch| obs.metadata.labels.iter().any(|l| label_matches(l, branch)))
+    }
+}
+
 /// Compute cosine similarity between two vectors.
 pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     if a.len() != b.len() || a.is_empty() {
@@ -35,12 +87,54 @@ pub fn find_similar(
     top_k: usize,
     min_similarity: f32,
 ) -> Vec<SimilarityMatch> {
+    find_similar_blended(query, &[], observations, top_k, min_similarity, 0.0)
+}
+
+/// Find the top-k most similar observations, blending embedding similarity
+/// with layout fingerprint similarity.
+///
+/// `layout_weight` in `[0, 1]` is the share of the score given to layout:
+/// 0 ranks by embedding only, 1 by layout only. When one side is missing
+/// (no fingerprint, or an empty query embedding), the other is used alone.
+pub fn find_similar_blended(
+    query: &[f32],
+    fingerprint: &[f32],
+    observations: &[VisualObservation],
+    top_k: usize,
+    min_similarity: f32,
+    layout_weight: f32,
+) -> Vec<SimilarityMatch> {
+    find_similar_filtered(
+        query,
+        fingerprint,
+        observations,
+        &SimilarityFilter::default(),
+        top_k,
+        min_similarity,
+        layout_weight,
+    )
+}
+
+/// [`find_similar_blended`] over only the observations `filter` admits.
+pub fn find_similar_filtered(
+    query: &[f32],
+    fingerprint: &[f32],
+    observations: &[VisualObservation],
+    filter: &SimilarityFilter,
+    top_k: usize,
+    min_similarity: f32,
+    layout_weight: f32,
+) -> Vec<SimilarityMatch> {
+    let layout_weight = layout_weight.clamp(0.0, 1.0);
+    let unfiltered = filter.is_empty();
     let mut matches: Vec<SimilarityMatch> = observations
         .iter()
-        .filter(|o| !o.embedding.is_empty())
-        .map(|o| SimilarityMatch {
-            id: o.id,
-            similarity: cosine_similarity(query, &o.embedding),
+        .filter(|o| unfiltered || filter.matches(o))
+        .filter_map(|o| {
+            Some(SimilarityMatch {
+                id: o.id,
+                similarity: blended_similarity(query, fingerprint, o, layout_weight)?,
+            })
         })
         .filter(|m| m.similarity >= min_similarity)
         .collect();
@@ -54,6 +148,87 @@ pub fn find_similar(
     matches
 }
 
+/// Embedding similarity of `o` to a query, blended with layout similarity
+/// by `layout_weight`; `None` if they share neither.
+fn blended_similarity(
+    query: &[f32],
+    fingerprint: &[f32],
+    o: &VisualObservation,
+    layout_weight: f32,
+) -> Option<f32> {
+    let semantic = (!query.is_empty() && !o.embedding.is_empty())
+        .then(|| cosine_similarity(query, &o.embedding));
+    let layout = (layout_weight > 0.0 && !fingerprint.is_empty() && !o.fingerprint.is_empty())
+        .then(|| fingerprint_similarity(fingerprint, &o.fingerprint));
+    match (semantic, layout) {
+        (Some(s), Some(l)) => Some((1.0 - layout_weight) * s + layout_weight * l),
+        (Some(s), None) => Some(s),
+        (None, Some(l)) => Some(l),
+        (None, None) => None,
+    }
+}
+
+/// Candidates to rank per result when reranking for diversity.
+pub const DIVERSITY_POOL: usize = 5;
+
+/// Rerank `matches` (best first, ideally [`DIVERSITY_POOL`] times as many
+/// as wanted) by maximal marginal relevance and keep `top_k`.
+///
+/// Each pick maximizes `(1 - diversity) * similarity - diversity * m`, where
+/// `m` is its highest similarity to the captures already picked, scored as
+/// the search was (`layout_weight`). `diversity` 0 keeps the ranking; 1
+/// spreads the results as far apart as the candidates allow. Matches keep
+/// their similarity to the query.
+pub fn rerank_diverse(
+    matches: &[SimilarityMatch],
+    observations: &[VisualObservation],
+    top_k: usize,
+    diversity: f32,
+    layout_weight: f32,
+) -> Vec<SimilarityMatch> {
+    let diversity = diversity.clamp(0.0, 1.0);
+    let layout_weight = layout_weight.clamp(0.0, 1.0);
+    let by_id: HashMap<u64, &VisualObservation> = observations.iter().map(|o| (o.id, o)).collect();
+    let mut candidates: Vec<(&SimilarityMatch, &VisualObservation)> = matches
+        .iter()
+        .filter_map(|m| Some((m, *by_id.get(&m.id)?)))
+        .collect();
+    // Highest similarity of each candidate to those already picked.
+    let mut redundancy = vec![f32::NEG_INFINITY; candidates.len()];
+    let mut picked = Vec::with_capacity(top_k.min(candidates.len()));
+
+    while picked.len() < top_k && !candidates.is_empty() {
+        let score = |i: usize| {
+            let penalty = if redundancy[i].is_finite() {
+                redundancy[i]
+            } else {
+                0.0
+            };
+            (1.0 - diversity) * candidates[i].0.similarity - diversity * penalty
+        };
+        let best = (0..candidates.len())
+            .max_by(|&a, &b| {
+                score(a)
+                    .partial_cmp(&score(b))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    // Earlier candidates rank higher on ties.
+                    .then(b.cmp(&a))
+            })
+            .expect("candidates is not empty");
+        let (chosen, obs) = candidates.remove(best);
+        redundancy.remove(best);
+        for (i, (_, other)) in candidates.iter().enumerate() {
+            if let Some(s) =
+                blended_similarity(&obs.embedding, &obs.fingerprint, other, layout_weight)
+            {
+                redundancy[i] = redundancy[i].max(s);
+            }
+        }
+        picked.push(chosen.clone());
+    }
+    picked
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -96,6 +271,137 @@ mod tests {
         assert_eq!(sim, 0.0);
     }
 
+    #[test]
+    fn test_blended_ranking() {
+        use crate::fingerprint::FINGERPRINT_LEN;
+
+        let obs = |id: u64, embedding: Vec<f32>, layout: f32| {
+            let mut fingerprint = vec![0.0; FINGERPRINT_LEN];
+            // One luminance bin per cell: the same layout when `layout` is 1.
+            for cell in 0..16 {
+                fingerprint[cell * 4] = layout;
+                fingerprint[cell * 4 + 3] = 1.0 - layout;
+            }
+            fingerprint[FINGERPRINT_LEN - 1] = 1.0;
+            VisualObservation {
+                id,
+                fingerprint,
+                ..VisualObservation::test(embedding, &[])
+            }
+        };
+        // 1 matches the query in meaning, 2 in layout.
+        let observations = vec![obs(1, vec![1.0, 0.0], 0.0), obs(2, vec![0.0, 1.0], 1.0)];
+        let query = obs(0, vec![1.0, 0.0], 1.0);
+
+        let top = |weight: f32| {
+            find_similar_blended(
+                &query.embedding,
+                &query.fingerprint,
+                &observations,
+                1,
+                0.0,
+                weight,
+            )[0]
+            .id
+        };
+        assert_eq!(top(0.0), 1);
+        assert_eq!(top(0.8), 2);
+        assert_eq!(
+            find_similar(&query.embedding, &observations, 5, 0.5).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_filtered_search() {
+        let obs = |id: u64, session_id: u32, label: &str, link: Option<u64>| VisualObservation {
+            id,
+            timestamp: id * 100,
+            session_id,
+            memory_link: link,
+            ..VisualObservation::test(vec![1.0, id as f32 / 10.0], &[label])
+        };
+        let observations = vec![
+            obs(1, 1, "app/login", None),
+            obs(2, 1, "app/checkout/payment", Some(7)),
+            obs(3, 2, "app/checkout", None),
+            obs(4, 2, "other", Some(7)),
+        ];
+        let ids = |filter: SimilarityFilter| -> Vec<u64> {
+            let mut ids: Vec<u64> =
+                find_similar_filtered(&[1.0, 0.0], &[], &observations, &filter, 10, 0.0, 0.0)
+                    .iter()
+                    .map(|m| m.id)
+                    .collect();
+            ids.sort_unstable();
+            ids
+        };
+
+        assert_eq!(ids(SimilarityFilter::default()), vec![1, 2, 3, 4]);
+        let by_label = SimilarityFilter {
+            labels: vec!["app/checkout".to_string()],
+            ..SimilarityFilter::default()
+        };
+        assert_eq!(ids(by_label), vec![2, 3]);
+        let by_time = SimilarityFilter {
+            after: Some(200),
+            before: Some(300),
+            ..SimilarityFilter::default()
+        };
+        assert_eq!(ids(by_time), vec![2, 3]);
+        let by_session_and_link = SimilarityFilter {
+            session_ids: vec![2],
+            memory_link: Some(7),
+            ..SimilarityFilter::default()
+        };
+        assert_eq!(ids(by_session_and_link), vec![4]);
+        // The filter applies before top-k: the best match overall is excluded.
+        let top = find_similar_filtered(
+            &[1.0, 0.0],
+            &[],
+            &observations,
+            &SimilarityFilter {
+                session_ids: vec![2],
+                ..SimilarityFilter::default()
+            },
+            1,
+            0.0,
+            0.0,
+        );
+        assert_eq!(top[0].id, 3);
+    }
+
+    #[test]
+    fn test_rerank_diverse() {
+        let obs = |id: u64, embedding: Vec<f32>| VisualObservation {
+            id,
+            ..VisualObservation::test(embedding, &[])
+        };
+        // Three frames of one screen, and a distinct screen a little further
+        // from the query.
+        let observations = vec![
+            obs(1, vec![1.0, 0.0, 0.0]),
+            obs(2, vec![1.0, 0.01, 0.0]),
+            obs(3, vec![1.0, 0.0, 0.01]),
+            obs(4, vec![0.8, 0.6, 0.0]),
+        ];
+        let matches = find_similar(&[1.0, 0.0, 0.0], &observations, 10, 0.0);
+        let ids =
+            |matches: Vec<SimilarityMatch>| -> Vec<u64> { matches.iter().map(|m| m.id).collect() };
+
+        assert_eq!(
+            ids(rerank_diverse(&matches, &observations, 2, 0.0, 0.0)),
+            vec![1, 2]
+        );
+        let diverse = rerank_diverse(&matches, &observations, 2, 0.7, 0.0);
+        assert_eq!(ids(diverse.clone()), vec![1, 4]);
+        assert!((diverse[1].similarity - 0.8).abs() < 1e-6);
+        assert_eq!(
+            rerank_diverse(&matches, &observations, 10, 0.5, 0.0).len(),
+            4
+        );
+    }
+
     #[test]
     fn test_cosine_zero_vector() {
         let a = vec![0.0, 0.0, 0.0];
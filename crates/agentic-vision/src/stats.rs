@@ -0,0 +1,354 @@
+//! Store statistics for capacity planning.
+//!
+//! Everything here is derived from the store on demand: how the embeddings
+//! spread out, how fast captures arrive, where the bytes of the `.avis` file
+//! go, and how much identical images cost. Pairwise measures run on an
+//! evenly spaced sample so that large stores stay cheap to inspect.
+
+use std::collections::{BTreeMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::similarity::cosine_similarity;
+use crate::storage::content_hash;
+use crate::types::VisualMemoryStore;
+
+/// Most embeddings compared pairwise.
+pub const MAX_SAMPLE: usize = 256;
+
+/// Bins of the pairwise similarity histogram, spanning -1 to 1.
+pub const HISTOGRAM_BINS: usize = 20;
+
+/// Similarity at which two captures count as the same cluster.
+pub const CLUSTER_THRESHOLD: f32 = 0.9;
+
+/// Most recent active days listed in the capture rate.
+const MAX_DAYS: usize = 30;
+
+const SECS_PER_DAY: u64 = 86_400;
+
+/// Statistics of a whole store.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoreStatistics {
+    pub total_captures: usize,
+    pub embeddings: EmbeddingStatistics,
+    pub capture_rate: CaptureRate,
+    pub labels: BTreeMap<String, usize>,
+    pub storage: StorageBreakdown,
+    pub dedup: DedupSavings,
+}
+
+/// How the embeddings are spread, over a sample of the store.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingStatistics {
+    /// Captures without an embedding (stored without a CLIP model).
+    pub zero_vectors: usize,
+    /// Embeddings compared pairwise.
+    pub sampled: usize,
+    pub pairs: usize,
+    pub mean_similarity: Option<f32>,
+    /// Pair counts by cosine similarity, lowest bin first.
+    pub histogram: Vec<HistogramBin>,
+    /// Groups of the sample linked by similarity of at least
+    /// `cluster_threshold`.
+    pub cluster_estimate: usize,
+    pub cluster_threshold: f32,
+}
+
+/// One bin of a histogram, covering `[min, max)`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistogramBin {
+    pub min: f32,
+    pub max: f32,
+    pub count: usize,
+}
+
+/// How fast captures arrive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureRate {
+    pub first_capture: Option<u64>,
+    pub last_capture: Option<u64>,
+    /// Days, counted in UTC, between the first and last capture inclusive.
+    pub days_spanned: u64,
+    pub per_day: f64,
+    /// Captures on the most recent days with any, oldest first.
+    pub daily: Vec<DailyCount>,
+}
+
+/// Captures on one UTC day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyCount {
+    /// Unix time of the start of the day.
+    pub day: u64,
+    pub captures: usize,
+}
+
+/// Serialized size in bytes of each part of the observations.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StorageBreakdown {
+    pub embeddings: usize,
+    pub thumbnails: usize,
+    pub fingerprints: usize,
+    pub elements: usize,
+    /// Text read by OCR, with its layout.
+    pub ocr: usize,
+    /// Everything else: IDs, timestamps, sources, labels and captions.
+    pub metadata: usize,
+    pub total: usize,
+}
+
+/// What identical images cost.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DedupSavings {
+    pub unique_images: usize,
+    /// Captures whose thumbnail an earlier capture already holds.
+    pub duplicate_captures: usize,
+    /// Serialized bytes those duplicates take up.
+    pub duplicate_bytes: usize,
+}
+
+impl VisualMemoryStore {
+    /// Compute statistics of the whole store.
+    pub fn statistics(&self) -> StoreStatistics {
+        StoreStatistics {
+            total_captures: self.count(),
+            embeddings: self.embedding_statistics(),
+            capture_rate: self.capture_rate(),
+            labels: self.label_counts(),
+            storage: self.storage_breakdown(),
+            dedup: self.dedup_savings(),
+        }
+    }
+
+    fn embedding_statistics(&self) -> EmbeddingStatistics {
+        let embedded: Vec<&[f32]> = self
+            .observations
+            .iter()
+            .map(|o| o.embedding.as_slice())
+            .filter(|e| e.iter().any(|x| *x != 0.0))
+            .collect();
+        let zero_vectors = self.count() - embedded.len();
+
+        // Evenly spaced, so the sample covers the whole history.
+        let sample: Vec<&[f32]> = if embedded.len() > MAX_SAMPLE {
+            (0..MAX_SAMPLE)
+                .map(|i| embedded[i * embedded.len() / MAX_SAMPLE])
+                .collect()
+        } else {
+            embedded
+        };
+
+        let bin_width = 2.0 / HISTOGRAM_BINS as f32;
+        let mut histogram: Vec<HistogramBin> = (0..HISTOGRAM_BINS)
+            .map(|i| HistogramBin {
+                min: -1.0 + i as f32 * bin_width,
+                max: -1.0 + (i + 1) as f32 * bin_width,
+                count: 0,
+            })
+            .collect();
+        let mut clusters = UnionFind::new(sample.len());
+        let mut pairs = 0;
+        let mut sum = 0.0f64;
+        for i in 0..sample.len() {
+            for j in i + 1..sample.len() {
+                let sim = cosine_similarity(sample[i], sample[j]);
+                let bin = (((sim + 1.0) / bin_width) as usize).min(HISTOGRAM_BINS - 1);
+                histogram[bin].count += 1;
+                pairs += 1;
+                sum += sim as f64;
+                if sim >= CLUSTER_THRESHOLD {
+                    clusters.union(i, j);
+                }
+            }
+        }
+
+        EmbeddingStatistics {
+            zero_vectors,
+            sampled: sample.len(),
+            pairs,
+            mean_similarity: (pairs > 0).then(|| (sum / pairs as f64) as f32),
+            histogram,
+            cluster_estimate: clusters.count(),
+            cluster_threshold: CLUSTER_THRESHOLD,
+        }
+    }
+
+    fn capture_rate(&self) -> CaptureRate {
+        let first = self.observations.iter().map(|o| o.timestamp).min();
+        let last = self.observations.iter().map(|o| o.timestamp).max();
+        let days_spanned = match (first, last) {
+            (Some(first), Some(last)) => last / SECS_PER_DAY - first / SECS_PER_DAY + 1,
+            _ => 0,
+        };
+
+        let mut by_day: BTreeMap<u64, usize> = BTreeMap::new();
+        for obs in &self.observations {
+            *by_day.entry(obs.timestamp / SECS_PER_DAY).or_insert(0) += 1;
+        }
+        let skip = by_day.len().saturating_sub(MAX_DAYS);
+        let daily = by_day
+            .into_iter()
+            .skip(skip)
+            .map(|(day, captures)| DailyCount {
+                day: day * SECS_PER_DAY,
+                captures,
+            })
+            .collect();
+
+        CaptureRate {
+            first_capture: first,
+            last_capture: last,
+            days_spanned,
+            per_day: if days_spanned > 0 {
+                self.count() as f64 / days_spanned as f64
+            } else {
+                0.0
+            },
+            daily,
+        }
+    }
+
+    fn storage_breakdown(&self) -> StorageBreakdown {
+        let mut breakdown = StorageBreakdown::default();
+        for obs in &self.observations {
+            let total = json_len(obs);
+            let embedding = json_len(&obs.embedding);
+            let thumbnail = json_len(&obs.thumbnail);
+            let fingerprint = if obs.fingerprint.is_empty() {
+                0
+            } else {
+                json_len(&obs.fingerprint)
+            };
+            let elements = if obs.elements.is_empty() {
+                0
+            } else {
+                json_len(&obs.elements)
+            };
+            let ocr = obs.ocr.as_ref().map_or(0, json_len);
+            breakdown.embeddings += embedding;
+            breakdown.thumbnails += thumbnail;
+            breakdown.fingerprints += fingerprint;
+            breakdown.elements += elements;
+            breakdown.ocr += ocr;
+            breakdown.metadata +=
+                total.saturating_sub(embedding + thumbnail + fingerprint + elements + ocr);
+            breakdown.total += total;
+        }
+        breakdown
+    }
+
+    fn dedup_savings(&self) -> DedupSavings {
+        let mut seen = HashSet::new();
+        let mut duplicate_captures = 0;
+        let mut duplicate_bytes = 0;
+        for obs in &self.observations {
+            if !seen.insert(content_hash(obs)) {
+                duplicate_captures += 1;
+                duplicate_bytes += json_len(obs);
+            }
+        }
+        DedupSavings {
+            unique_images: seen.len(),
+            duplicate_captures,
+            duplicate_bytes,
+        }
+    }
+}
+
+/// Length of a value serialized as JSON, as it is in the `.avis` payload.
+pub(crate) fn json_len<T: Serialize + ?Sized>(value: &T) -> usize {
+    serde_json::to_vec(value).map(|v| v.len()).unwrap_or(0)
+}
+
+/// Disjoint sets over `0..n`, for counting clusters.
+pub(crate) struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    pub(crate) fn new(n: usize) -> Self {
+        Self {
+            parent: (0..n).collect(),
+        }
+    }
+
+    pub(crate) fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    pub(crate) fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parent[a] = b;
+    }
+
+    fn count(&mut self) -> usize {
+        (0..self.parent.len())
+            .filter(|&i| self.find(i) == i)
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::VisualObservation;
+
+    fn obs(timestamp: u64, embedding: Vec<f32>, thumbnail: Vec<u8>) -> VisualObservation {
+        VisualObservation {
+            timestamp,
+            thumbnail,
+            ..VisualObservation::test(embedding, &["app/login"])
+        }
+    }
+
+    #[test]
+    fn test_statistics() {
+        let mut store = VisualMemoryStore::new(3);
+        let day = SECS_PER_DAY * 20_000;
+        store.add(obs(day, vec![1.0, 0.0, 0.0], vec![1, 2, 3]));
+        store.add(obs(day + 60, vec![0.99, 0.05, 0.0], vec![1, 2, 3]));
+        store.add(obs(day + 2 * SECS_PER_DAY, vec![0.0, 1.0, 0.0], vec![4, 5]));
+        store.add(obs(day + 2 * SECS_PER_DAY, vec![0.0; 3], vec![6]));
+
+        let stats = store.statistics();
+        assert_eq!(stats.total_captures, 4);
+
+        // Three embedded captures: one near-identical pair, one orthogonal.
+        assert_eq!(stats.embeddings.zero_vectors, 1);
+        assert_eq!(stats.embeddings.sampled, 3);
+        assert_eq!(stats.embeddings.pairs, 3);
+        assert_eq!(stats.embeddings.cluster_estimate, 2);
+        let counted: usize = stats.embeddings.histogram.iter().map(|b| b.count).sum();
+        assert_eq!(counted, 3);
+        assert_eq!(stats.embeddings.histogram.last().unwrap().count, 1);
+
+        assert_eq!(stats.capture_rate.days_spanned, 3);
+        assert_eq!(stats.capture_rate.daily.len(), 2);
+        assert_eq!(stats.capture_rate.daily[0].day, day);
+        assert_eq!(stats.capture_rate.daily[0].captures, 2);
+
+        assert_eq!(stats.labels["app"], 4);
+
+        let storage = &stats.storage;
+        assert!(storage.embeddings > 0 && storage.thumbnails > 0 && storage.metadata > 0);
+        assert_eq!(storage.fingerprints, 0);
+        assert!(storage.embeddings + storage.thumbnails + storage.metadata <= storage.total);
+
+        assert_eq!(stats.dedup.unique_images, 3);
+        assert_eq!(stats.dedup.duplicate_captures, 1);
+        assert!(stats.dedup.duplicate_bytes > 0);
+    }
+
+    #[test]
+    fn test_statistics_empty() {
+        let stats = VisualMemoryStore::new(3).statistics();
+        assert_eq!(stats.embeddings.pairs, 0);
+        assert!(stats.embeddings.mean_similarity.is_none());
+        assert_eq!(stats.capture_rate.days_spanned, 0);
+        assert_eq!(stats.storage.total, 0);
+    }
+}
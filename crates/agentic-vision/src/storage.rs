@@ -1,9 +1,18 @@
 //! .avis binary file format reader/writer for visual memory.
 
+use std::collections::{BTreeMap, HashSet};
 use std::io::{Read, Write};
 use std::path::Path;
 
-use crate::types::{VisionError, VisionResult, VisualMemoryStore, VisualObservation};
+use serde::{Deserialize, Serialize};
+
+use crate::calibration::Calibration;
+use crate::provenance::sha256_hex;
+use crate::reindex::ReindexCheckpoint;
+use crate::sessions::SessionInfo;
+use crate::types::{
+    ManifestEntry, Snapshot, VisionError, VisionResult, VisualMemoryStore, VisualObservation,
+};
 
 /// Magic bytes: "AVIS"
 const AVIS_MAGIC: u32 = 0x41564953;
@@ -41,6 +50,11 @@ impl AvisWriter {
             session_count: store.session_count,
             created_at: store.created_at,
             updated_at: store.updated_at,
+            snapshots: &store.snapshots,
+            label_aliases: &store.label_aliases,
+            sessions: &store.sessions,
+            reindex: store.reindex.as_ref(),
+            calibration: store.calibration.as_ref(),
         })
         .map_err(|e| VisionError::Storage(format!("Serialization failed: {e}")))?;
 
@@ -113,10 +127,240 @@ impl AvisReader {
             session_count,
             created_at,
             updated_at,
+            snapshots: serialized.snapshots,
+            label_aliases: serialized.label_aliases,
+            sessions: serialized.sessions,
+            reindex: serialized.reindex,
+            calibration: serialized.calibration,
         })
     }
 }
 
+/// What a salvage read recovered from a damaged file, and what it lost.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SalvageReport {
+    /// Whether the header was intact.
+    pub header_valid: bool,
+    /// Captures the header says the file holds, if it was intact.
+    pub captures_expected: Option<u64>,
+    pub captures_recovered: usize,
+    /// Captures whose bytes were present but unreadable.
+    pub captures_damaged: usize,
+    /// Whether the file ends inside its payload.
+    pub truncated: bool,
+    /// Payload bytes missing from the end of the file.
+    pub bytes_missing: u64,
+    /// Whether snapshots, label aliases and session metadata were recovered.
+    pub metadata_recovered: bool,
+}
+
+impl SalvageReport {
+    /// Captures known to be lost, if the header said how many there were.
+    pub fn captures_lost(&self) -> Option<u64> {
+        self.captures_expected
+            .map(|n| n.saturating_sub(self.captures_recovered as u64))
+    }
+}
+
+impl AvisReader {
+    /// Recover what is readable from a damaged file.
+    pub fn salvage_file(path: &Path) -> VisionResult<(VisualMemoryStore, SalvageReport)> {
+        let mut file = std::fs::File::open(path)?;
+        Self::salvage_from(&mut file)
+    }
+
+    /// Recover what is readable from a damaged store, such as one truncated
+    /// mid-write. Every capture whose bytes are intact is kept; the payload
+    /// after the captures (snapshots, aliases, sessions) is kept if it is
+    /// whole. Fails only if no capture list can be found at all.
+    pub fn salvage_from<R: Read>(
+        reader: &mut R,
+    ) -> VisionResult<(VisualMemoryStore, SalvageReport)> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        let mut report = SalvageReport::default();
+        let mut store = VisualMemoryStore::new(crate::embedding::EMBEDDING_DIM);
+        let header_valid = bytes.len() >= HEADER_SIZE
+            && read_u32(&bytes[0..4]) == AVIS_MAGIC
+            && read_u16(&bytes[4..6]) == FORMAT_VERSION;
+        if header_valid {
+            report.header_valid = true;
+            report.captures_expected = Some(read_u64(&bytes[8..16]));
+            store.embedding_dim = read_u32(&bytes[16..20]);
+            store.session_count = read_u32(&bytes[20..24]);
+            store.created_at = read_u64(&bytes[24..32]);
+            store.updated_at = read_u64(&bytes[32..40]);
+            let payload_len = read_u64(&bytes[40..48]);
+            let present = (bytes.len() - HEADER_SIZE) as u64;
+            report.bytes_missing = payload_len.saturating_sub(present);
+            report.truncated = report.bytes_missing > 0;
+        }
+
+        let start = find(&bytes, OBSERVATIONS_KEY).ok_or_else(|| {
+            VisionError::Storage("No capture list found; nothing to recover".to_string())
+        })? + OBSERVATIONS_KEY.len();
+        let scan = scan_array(&bytes[start..]);
+        for element in scan.elements {
+            match serde_json::from_slice::<VisualObservation>(&bytes[start..][element]) {
+                Ok(obs) => store.observations.push(obs),
+                Err(_) => report.captures_damaged += 1,
+            }
+        }
+        report.captures_recovered = store.observations.len();
+        report.truncated |= scan.end.is_none();
+
+        // The rest of the payload is an object body following the array.
+        let tail = scan.end.and_then(|end| {
+            let rest = bytes[start + end..].strip_prefix(b",")?;
+            let object = [b"{", rest].concat();
+            serde_json::from_slice::<SalvagedTail>(&object).ok()
+        });
+        if let Some(tail) = tail {
+            report.metadata_recovered = true;
+            store.next_id = tail.next_id;
+            store.snapshots = tail.snapshots;
+            store.label_aliases = tail.label_aliases;
+            store.sessions = tail.sessions;
+            store.reindex = tail.reindex;
+            store.calibration = tail.calibration;
+        }
+
+        if let Some(max_id) = store.observations.iter().map(|o| o.id).max() {
+            store.next_id = store.next_id.max(max_id + 1);
+        }
+        if let Some(max_session) = store.observations.iter().map(|o| o.session_id).max() {
+            store.session_count = store.session_count.max(max_session);
+        }
+        if !header_valid {
+            if let Some(dim) = store.observations.iter().map(|o| o.embedding.len()).max() {
+                store.embedding_dim = dim as u32;
+            }
+        }
+        Ok((store, report))
+    }
+}
+
+impl VisualMemoryStore {
+    /// Take a snapshot named `name` of the store as it is now.
+    pub fn create_snapshot(&mut self, name: &str) -> VisionResult<&Snapshot> {
+        if name.is_empty() {
+            return Err(VisionError::InvalidInput(
+                "Snapshot name must not be empty".to_string(),
+            ));
+        }
+        if self.snapshots.iter().any(|s| s.name == name) {
+            return Err(VisionError::InvalidInput(format!(
+                "A snapshot named '{name}' already exists"
+            )));
+        }
+        let links = self
+            .observations
+            .iter()
+            .filter_map(|o| o.memory_link.map(|link| (o.id, link)))
+            .collect();
+        self.snapshots.push(Snapshot {
+            name: name.to_string(),
+            created_at: now_secs(),
+            next_id: self.next_id,
+            observation_count: self.observations.len(),
+            session_count: self.session_count,
+            links,
+        });
+        Ok(self.snapshots.last().expect("just pushed"))
+    }
+
+    /// Roll the store back to snapshot `name`: drop the observations added
+    /// since, restore memory links, and drop the snapshots taken after it.
+    /// Returns the number of observations removed.
+    ///
+    /// IDs are not reused, so IDs handed out after the snapshot stay invalid.
+    pub fn restore_snapshot(&mut self, name: &str) -> VisionResult<usize> {
+        let index = self
+            .snapshots
+            .iter()
+            .position(|s| s.name == name)
+            .ok_or_else(|| VisionError::SnapshotNotFound(name.to_string()))?;
+        self.snapshots.truncate(index + 1);
+        let snapshot = &self.snapshots[index];
+
+        let before = self.observations.len();
+        self.observations.retain(|o| o.id < snapshot.next_id);
+        for obs in &mut self.observations {
+            obs.memory_link = snapshot
+                .links
+                .iter()
+                .find(|(id, _)| *id == obs.id)
+                .map(|(_, link)| *link);
+        }
+        self.session_count = snapshot.session_count;
+        self.updated_at = now_secs();
+        Ok(before - self.observations.len())
+    }
+}
+
+/// Content address of a capture: the hex SHA-256 of its thumbnail.
+///
+/// Two captures of the same image share an address even when their IDs,
+/// labels or embeddings differ, so syncing stores never duplicates them.
+pub fn content_hash(obs: &VisualObservation) -> String {
+    sha256_hex(&obs.thumbnail)
+}
+
+impl VisualMemoryStore {
+    /// The content address of every observation, for syncing with another
+    /// store.
+    pub fn manifest(&self) -> Vec<ManifestEntry> {
+        self.observations
+            .iter()
+            .map(|o| ManifestEntry {
+                hash: content_hash(o),
+                id: o.id,
+                timestamp: o.timestamp,
+            })
+            .collect()
+    }
+
+    /// Observations whose content address is not in `hashes`: what another
+    /// store with that manifest is missing.
+    pub fn missing_from(&self, hashes: &HashSet<String>) -> Vec<VisualObservation> {
+        self.observations
+            .iter()
+            .filter(|o| !hashes.contains(&content_hash(o)))
+            .cloned()
+            .collect()
+    }
+
+    /// Observations with the given content addresses.
+    pub fn by_hashes(&self, hashes: &HashSet<String>) -> Vec<VisualObservation> {
+        self.observations
+            .iter()
+            .filter(|o| hashes.contains(&content_hash(o)))
+            .cloned()
+            .collect()
+    }
+
+    /// Add observations from another store, oldest first, skipping any whose
+    /// content is already here. They get new IDs and otherwise keep their
+    /// metadata. Returns the IDs of the observations added.
+    pub fn merge(&mut self, mut observations: Vec<VisualObservation>) -> Vec<u64> {
+        let mut known: HashSet<String> = self.observations.iter().map(content_hash).collect();
+        observations.sort_by_key(|o| o.timestamp);
+        observations
+            .into_iter()
+            .filter(|o| known.insert(content_hash(o)))
+            .map(|o| self.add(o))
+            .collect()
+    }
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
 #[derive(serde::Serialize)]
 struct SerializedStore<'a> {
     observations: &'a [VisualObservation],
@@ -125,6 +369,13 @@ struct SerializedStore<'a> {
     session_count: u32,
     created_at: u64,
     updated_at: u64,
+    snapshots: &'a [Snapshot],
+    label_aliases: &'a BTreeMap<String, String>,
+    sessions: &'a BTreeMap<u32, SessionInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reindex: Option<&'a ReindexCheckpoint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    calibration: Option<&'a Calibration>,
 }
 
 #[derive(serde::Deserialize)]
@@ -139,6 +390,93 @@ struct DeserializedStore {
     created_at: u64,
     #[allow(dead_code)]
     updated_at: u64,
+    /// Absent in files written before snapshots existed.
+    #[serde(default)]
+    snapshots: Vec<Snapshot>,
+    #[serde(default)]
+    label_aliases: BTreeMap<String, String>,
+    #[serde(default)]
+    sessions: BTreeMap<u32, SessionInfo>,
+    #[serde(default)]
+    reindex: Option<ReindexCheckpoint>,
+    #[serde(default)]
+    calibration: Option<Calibration>,
+}
+
+/// Start of the capture list in a payload.
+const OBSERVATIONS_KEY: &[u8] = b"\"observations\":[";
+
+/// Payload fields after the capture list, as far as they survived.
+#[derive(serde::Deserialize)]
+struct SalvagedTail {
+    #[serde(default)]
+    next_id: u64,
+    #[serde(default)]
+    snapshots: Vec<Snapshot>,
+    #[serde(default)]
+    label_aliases: BTreeMap<String, String>,
+    #[serde(default)]
+    sessions: BTreeMap<u32, SessionInfo>,
+    #[serde(default)]
+    reindex: Option<ReindexCheckpoint>,
+    #[serde(default)]
+    calibration: Option<Calibration>,
+}
+
+/// Element boundaries of a JSON array whose opening bracket precedes `bytes`.
+struct ArrayScan {
+    elements: Vec<std::ops::Range<usize>>,
+    /// Offset just past the closing bracket; `None` if the input ends first.
+    end: Option<usize>,
+}
+
+/// Split a JSON array into its elements by tracking nesting and strings,
+/// without parsing the elements themselves. A truncated last element is
+/// left out.
+fn scan_array(bytes: &[u8]) -> ArrayScan {
+    let mut elements = Vec::new();
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, &b) in bytes.iter().enumerate() {
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'{' | b'[' => depth += 1,
+            b'}' if depth > 0 => depth -= 1,
+            b']' if depth > 0 => depth -= 1,
+            b',' | b']' if depth == 0 => {
+                if bytes[start..i].iter().any(|c| !c.is_ascii_whitespace()) {
+                    elements.push(start..i);
+                }
+                if b == b']' {
+                    return ArrayScan {
+                        elements,
+                        end: Some(i + 1),
+                    };
+                }
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    ArrayScan {
+        elements,
+        end: None,
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
 }
 
 // Little-endian byte helpers
@@ -185,8 +523,13 @@ mod tests {
                 original_height: 1080,
                 labels: vec!["test".to_string()],
                 description: Some("Test observation".to_string()),
+                caption: None,
             },
             memory_link: None,
+            fingerprint: Vec::new(),
+            elements: Vec::new(),
+            ocr: None,
+            provenance: None,
         }
     }
 
@@ -206,6 +549,8 @@ mod tests {
         let mut store = VisualMemoryStore::new(512);
         store.add(make_test_observation(0));
         store.add(make_test_observation(0));
+        store.session_info_mut(1).name = Some("login flow".to_string());
+        store.start_reindex(Some("clip.onnx".to_string()), 7);
 
         let mut buf = Vec::new();
         AvisWriter::write_to(&store, &mut buf).unwrap();
@@ -214,6 +559,8 @@ mod tests {
         assert_eq!(loaded.count(), 2);
         assert_eq!(loaded.observations[0].id, 1);
         assert_eq!(loaded.observations[1].id, 2);
+        assert_eq!(loaded.sessions, store.sessions);
+        assert_eq!(loaded.reindex, store.reindex);
     }
 
     #[test]
@@ -224,6 +571,71 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_snapshot_create_and_restore() {
+        let mut store = VisualMemoryStore::new(512);
+        let kept = store.add(make_test_observation(0));
+        store.get_mut(kept).unwrap().memory_link = Some(7);
+        store.create_snapshot("clean").unwrap();
+        assert!(store.create_snapshot("clean").is_err());
+
+        // Pollute the session, then roll it back.
+        let added = store.add(make_test_observation(0));
+        store.get_mut(kept).unwrap().memory_link = Some(9);
+        store.session_count = 3;
+        store.create_snapshot("polluted").unwrap();
+
+        // Snapshots survive a save.
+        let mut buf = Vec::new();
+        AvisWriter::write_to(&store, &mut buf).unwrap();
+        let mut store = AvisReader::read_from(&mut &buf[..]).unwrap();
+        assert_eq!(store.snapshots.len(), 2);
+
+        assert_eq!(store.restore_snapshot("clean").unwrap(), 1);
+        assert_eq!(store.count(), 1);
+        assert!(store.get(added).is_none());
+        assert_eq!(store.get(kept).unwrap().memory_link, Some(7));
+        assert_eq!(store.session_count, 0);
+        assert_eq!(store.snapshots.len(), 1);
+        assert!(matches!(
+            store.restore_snapshot("polluted"),
+            Err(VisionError::SnapshotNotFound(_))
+        ));
+
+        // New captures get fresh IDs.
+        assert!(store.add(make_test_observation(0)) > added);
+    }
+
+    #[test]
+    fn test_merge_skips_identical_captures() {
+        let mut laptop = VisualMemoryStore::new(512);
+        laptop.add(make_test_observation(0));
+        let mut other = make_test_observation(0);
+        other.thumbnail = vec![0xFF, 0xD8, 0x01];
+        laptop.add(other);
+
+        let mut desktop = VisualMemoryStore::new(512);
+        // Same image as the laptop's first capture, under another label.
+        let mut same = make_test_observation(0);
+        same.metadata.labels = vec!["desktop".to_string()];
+        desktop.add(same);
+
+        let remote: HashSet<String> = desktop.manifest().into_iter().map(|e| e.hash).collect();
+        let missing = laptop.missing_from(&remote);
+        assert_eq!(missing.len(), 1);
+        assert_eq!(desktop.by_hashes(&remote).len(), 1);
+
+        // Offering everything still adds only the new image, once.
+        let mut offered = laptop.observations.clone();
+        offered.extend(missing);
+        assert_eq!(desktop.merge(offered), vec![2]);
+        assert_eq!(desktop.count(), 2);
+        assert_eq!(desktop.observations[0].metadata.labels, vec!["desktop"]);
+        assert!(laptop
+            .missing_from(&desktop.manifest().into_iter().map(|e| e.hash).collect())
+            .is_empty());
+    }
+
     #[test]
     fn test_file_roundtrip() {
         let dir = tempfile::tempdir().unwrap();
@@ -236,4 +648,60 @@ mod tests {
         let loaded = AvisReader::read_from_file(&path).unwrap();
         assert_eq!(loaded.count(), 1);
     }
+
+    fn written_store(captures: usize) -> (VisualMemoryStore, Vec<u8>) {
+        let mut store = VisualMemoryStore::new(512);
+        for _ in 0..captures {
+            store.add(make_test_observation(0));
+        }
+        store.create_snapshot("base").unwrap();
+        let mut buf = Vec::new();
+        AvisWriter::write_to(&store, &mut buf).unwrap();
+        (store, buf)
+    }
+
+    #[test]
+    fn test_salvage_intact() {
+        let (store, buf) = written_store(3);
+        let (salvaged, report) = AvisReader::salvage_from(&mut &buf[..]).unwrap();
+        assert_eq!(salvaged.count(), 3);
+        assert_eq!(salvaged.next_id, store.next_id);
+        assert_eq!(salvaged.snapshots, store.snapshots);
+        assert!(report.header_valid && report.metadata_recovered && !report.truncated);
+        assert_eq!(report.captures_lost(), Some(0));
+    }
+
+    #[test]
+    fn test_salvage_truncated() {
+        let (_, buf) = written_store(3);
+        // Cut the file inside the third capture.
+        let third = find(&buf, b"{\"id\":3").unwrap();
+        let truncated = &buf[..third + 20];
+        assert!(AvisReader::read_from(&mut &truncated[..]).is_err());
+
+        let (salvaged, report) = AvisReader::salvage_from(&mut &truncated[..]).unwrap();
+        assert_eq!(salvaged.count(), 2);
+        assert_eq!(salvaged.next_id, 3);
+        assert!(report.truncated && report.bytes_missing > 0);
+        assert!(!report.metadata_recovered);
+        assert_eq!(report.captures_lost(), Some(1));
+    }
+
+    #[test]
+    fn test_salvage_damaged_capture() {
+        let (_, mut buf) = written_store(3);
+        // Garble a field of the second capture without breaking its nesting.
+        let second = find(&buf, b"{\"id\":2").unwrap();
+        buf[second + 2..second + 4].copy_from_slice(b"xx");
+        buf[..4].copy_from_slice(b"JUNK");
+
+        let (salvaged, report) = AvisReader::salvage_from(&mut &buf[..]).unwrap();
+        assert!(!report.header_valid);
+        assert_eq!(report.captures_damaged, 1);
+        let ids: Vec<u64> = salvaged.observations.iter().map(|o| o.id).collect();
+        assert_eq!(ids, vec![1, 3]);
+        assert!(report.metadata_recovered);
+        assert_eq!(salvaged.snapshots.len(), 1);
+        assert_eq!(salvaged.embedding_dim, 3);
+    }
 }
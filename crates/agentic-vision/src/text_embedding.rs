@@ -0,0 +1,285 @@
+//! Sentence embeddings of short texts with a BERT-style model via ONNX Runtime.
+//!
+//! The model is a sentence-transformers export (all-MiniLM-L6-v2 and the
+//! like): `input_ids`, `attention_mask` and `token_type_ids` in,
+//! `last_hidden_state` out, plus the WordPiece vocabulary it was trained
+//! with. Token states are mean-pooled and L2-normalized, so the cosine
+//! similarity of two embeddings is their dot product.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use ort::session::Session;
+use ort::value::Tensor;
+
+use crate::types::{VisionError, VisionResult};
+
+/// Default model directory, under the home directory.
+const MODEL_DIR: &str = ".agentic-vision/models/sentence";
+
+/// Files expected in the model directory.
+const MODEL_FILENAME: &str = "model.onnx";
+const VOCAB_FILENAME: &str = "vocab.txt";
+
+/// Longest input, in tokens, including `[CLS]` and `[SEP]`.
+const MAX_TOKENS: usize = 128;
+
+/// Words longer than this are `[UNK]`, as in BERT.
+const MAX_WORD_CHARS: usize = 100;
+
+/// WordPiece vocabulary of an uncased BERT-style model.
+struct Vocab {
+    tokens: HashMap<String, i64>,
+    cls: i64,
+    sep: i64,
+    unk: i64,
+}
+
+impl Vocab {
+    fn new(lines: impl IntoIterator<Item = String>) -> VisionResult<Self> {
+        let tokens: HashMap<String, i64> = lines
+            .into_iter()
+            .enumerate()
+            .map(|(i, token)| (token, i as i64))
+            .collect();
+        let id_of = |token: &str| {
+            tokens.get(token).copied().ok_or_else(|| {
+                VisionError::Embedding(format!("Sentence vocabulary has no {token}"))
+            })
+        };
+        Ok(Self {
+            cls: id_of("[CLS]")?,
+            sep: id_of("[SEP]")?,
+            unk: id_of("[UNK]")?,
+            tokens,
+        })
+    }
+
+    /// Token ids of `text`: `[CLS]`, its word pieces, `[SEP]`, at most
+    /// [`MAX_TOKENS`] in all.
+    fn encode(&self, text: &str) -> Vec<i64> {
+        let mut ids = vec![self.cls];
+        for word in split_words(&text.to_lowercase()) {
+            ids.extend(self.word_pieces(word));
+            if ids.len() >= MAX_TOKENS - 1 {
+                break;
+            }
+        }
+        ids.truncate(MAX_TOKENS - 1);
+        ids.push(self.sep);
+        ids
+    }
+
+    /// Greedy longest-match-first WordPiece split of one word; `[UNK]` if
+    /// some part of it is not in the vocabulary.
+    fn word_pieces(&self, word: &str) -> Vec<i64> {
+        let chars: Vec<char> = word.chars().collect();
+        if chars.len() > MAX_WORD_CHARS {
+            return vec![self.unk];
+        }
+        let mut pieces = Vec::new();
+        let mut start = 0;
+        while start < chars.len() {
+            let piece = (start + 1..=chars.len()).rev().find_map(|end| {
+                let mut piece: String = chars[start..end].iter().collect();
+                if start > 0 {
+                    piece.insert_str(0, "##");
+                }
+                self.tokens.get(&piece).map(|&id| (id, end))
+            });
+            match piece {
+                Some((id, end)) => {
+                    pieces.push(id);
+                    start = end;
+                }
+                None => return vec![self.unk],
+            }
+        }
+        pieces
+    }
+}
+
+/// Split lowercased text into words: runs of non-space characters, with
+/// every punctuation character a word of its own.
+fn split_words(text: &str) -> impl Iterator<Item = &str> {
+    text.split_whitespace().flat_map(|chunk| {
+        let mut words = Vec::new();
+        let mut start = 0;
+        for (i, c) in chunk.char_indices() {
+            if !c.is_alphanumeric() {
+                if start < i {
+                    words.push(&chunk[start..i]);
+                }
+                words.push(&chunk[i..i + c.len_utf8()]);
+                start = i + c.len_utf8();
+            }
+        }
+        if start < chunk.len() {
+            words.push(&chunk[start..]);
+        }
+        words
+    })
+}
+
+struct SentenceModel {
+    session: Session,
+    vocab: Vocab,
+}
+
+/// Engine for embedding short texts (page titles, goals) as vectors.
+pub struct TextEmbeddingEngine {
+    model: Option<SentenceModel>,
+}
+
+impl TextEmbeddingEngine {
+    /// Create a new text embedding engine.
+    ///
+    /// If `model_dir` is provided, loads the model from that directory.
+    /// Otherwise, looks in `~/.agentic-vision/models/sentence/`.
+    /// If no model is found, the engine embeds nothing.
+    pub fn new(model_dir: Option<&str>) -> VisionResult<Self> {
+        let dir = match model_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => {
+                let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+                PathBuf::from(home).join(MODEL_DIR)
+            }
+        };
+
+        if !dir.join(MODEL_FILENAME).exists() {
+            tracing::warn!(
+                "Sentence model not found in {}. Text will not be embedded.",
+                dir.display()
+            );
+            return Ok(Self { model: None });
+        }
+
+        tracing::info!("Loading sentence model from {}", dir.display());
+
+        let vocab = Vocab::new(
+            std::fs::read_to_string(dir.join(VOCAB_FILENAME))?
+                .lines()
+                .map(str::to_string),
+        )?;
+        let model = SentenceModel {
+            session: load_session(&dir.join(MODEL_FILENAME))?,
+            vocab,
+        };
+
+        tracing::info!("Sentence model loaded successfully");
+        Ok(Self { model: Some(model) })
+    }
+
+    /// Check if the engine has a loaded model.
+    pub fn has_model(&self) -> bool {
+        self.model.is_some()
+    }
+
+    /// Embed `text` as a unit-length vector. Returns `None` if no model is
+    /// loaded.
+    pub fn embed(&mut self, text: &str) -> VisionResult<Option<Vec<f32>>> {
+        let Some(model) = &mut self.model else {
+            return Ok(None);
+        };
+
+        let ids = model.vocab.encode(text);
+        let n = ids.len();
+        let tensor = |values: Vec<i64>| {
+            Tensor::from_array(([1, n], values))
+                .map_err(|e| VisionError::Embedding(format!("Failed to create input: {e}")))
+        };
+        let outputs = model
+            .session
+            .run(ort::inputs![
+                "input_ids" => tensor(ids)?,
+                "attention_mask" => tensor(vec![1; n])?,
+                "token_type_ids" => tensor(vec![0; n])?,
+            ])
+            .map_err(|e| VisionError::Embedding(format!("Sentence model failed: {e}")))?;
+        let (shape, states) = outputs[0]
+            .try_extract_tensor::<f32>()
+            .map_err(|e| VisionError::Embedding(format!("Failed to extract output: {e}")))?;
+
+        // Token states [1, n, dim] are mean-pooled; a pooled export's
+        // [1, dim] output is used as is.
+        let dim = shape.last().map_or(0, |&d| d as usize);
+        if dim == 0 {
+            return Err(VisionError::Embedding(
+                "Sentence model returned an empty output".into(),
+            ));
+        }
+        let rows = states.len() / dim;
+        let mut embedding = vec![0.0f32; dim];
+        for row in states.chunks_exact(dim) {
+            for (sum, v) in embedding.iter_mut().zip(row) {
+                *sum += v / rows as f32;
+            }
+        }
+
+        let norm: f32 = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            embedding.iter_mut().for_each(|x| *x /= norm);
+        }
+        Ok(Some(embedding))
+    }
+}
+
+fn load_session(path: &Path) -> VisionResult<Session> {
+    Session::builder()
+        .and_then(|b| b.with_intra_threads(1))
+        .and_then(|b| b.commit_from_file(path))
+        .map_err(|e| {
+            VisionError::Embedding(format!("Failed to load ONNX model {}: {e}", path.display()))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vocab() -> Vocab {
+        Vocab::new(
+            [
+                "[PAD]", "[UNK]", "[CLS]", "[SEP]", "wire", "##less", "head", "##phones", "-", "x",
+                "##1",
+            ]
+            .iter()
+            .map(|t| t.to_string()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_fallback_mode() {
+        let mut engine = TextEmbeddingEngine::new(Some("/nonexistent/sentence")).unwrap();
+        assert!(!engine.has_model());
+        assert_eq!(engine.embed("checkout").unwrap(), None);
+    }
+
+    #[test]
+    fn test_encode_wordpiece() {
+        let vocab = vocab();
+        assert_eq!(
+            vocab.encode("Wireless Headphones - X1"),
+            vec![2, 4, 5, 6, 7, 8, 9, 10, 3]
+        );
+        // Unknown words are a single [UNK]
+        assert_eq!(vocab.encode("wired"), vec![2, 1, 3]);
+        assert_eq!(vocab.encode(""), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_encode_truncates() {
+        let vocab = vocab();
+        let ids = vocab.encode(&"wireless ".repeat(200));
+        assert_eq!(ids.len(), MAX_TOKENS);
+        assert_eq!(ids[0], 2);
+        assert_eq!(ids[MAX_TOKENS - 1], 3);
+    }
+
+    #[test]
+    fn test_split_words() {
+        let words: Vec<&str> = split_words("sale: 50% off!  today").collect();
+        assert_eq!(words, ["sale", ":", "50", "%", "off", "!", "today"]);
+    }
+}
@@ -1,7 +1,15 @@
 //! Core data types for visual observations and memory.
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
+use crate::calibration::Calibration;
+use crate::ocr::OcrResult;
+use crate::provenance::Provenance;
+use crate::reindex::ReindexCheckpoint;
+use crate::sessions::SessionInfo;
+
 /// A captured visual observation stored in visual memory.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VisualObservation {
@@ -13,6 +21,50 @@ pub struct VisualObservation {
     pub thumbnail: Vec<u8>,
     pub metadata: ObservationMeta,
     pub memory_link: Option<u64>,
+    /// Layout and colour fingerprint (see `fingerprint`); empty for captures
+    /// stored before fingerprints existed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fingerprint: Vec<f32>,
+    /// UI elements found in the image (feature `ui-detect`), best first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub elements: Vec<UiElement>,
+    /// Text read by `vision_ocr`, with boxes in original image pixels.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ocr: Option<OcrResult>,
+    /// How the capture was produced; absent for captures stored before
+    /// provenance was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
+
+#[cfg(test)]
+impl VisualObservation {
+    /// A 1x1 clipboard capture in session 1 with `embedding` and `labels`,
+    /// for tests. Override other fields with struct update syntax.
+    pub(crate) fn test(embedding: Vec<f32>, labels: &[&str]) -> Self {
+        Self {
+            id: 0,
+            timestamp: 0,
+            session_id: 1,
+            source: CaptureSource::Clipboard,
+            embedding,
+            thumbnail: Vec::new(),
+            metadata: ObservationMeta {
+                width: 1,
+                height: 1,
+                original_width: 1,
+                original_height: 1,
+                labels: labels.iter().map(|l| l.to_string()).collect(),
+                description: None,
+                caption: None,
+            },
+            memory_link: None,
+            fingerprint: Vec::new(),
+            elements: Vec::new(),
+            ocr: None,
+            provenance: None,
+        }
+    }
 }
 
 /// How the image was captured.
@@ -25,6 +77,18 @@ pub enum CaptureSource {
     Clipboard,
 }
 
+impl CaptureSource {
+    /// The source type as named in tool calls, e.g. `file`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::File { .. } => "file",
+            Self::Base64 { .. } => "base64",
+            Self::Screenshot { .. } => "screenshot",
+            Self::Clipboard => "clipboard",
+        }
+    }
+}
+
 /// Metadata about a visual observation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ObservationMeta {
@@ -34,6 +98,23 @@ pub struct ObservationMeta {
     pub original_height: u32,
     pub labels: Vec<String>,
     pub description: Option<String>,
+    /// Generated natural-language caption (feature `caption`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+}
+
+/// A UI element detected in a capture.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UiElement {
+    /// Element kind, e.g. `button`, `input`, `dialog` or `icon`.
+    pub kind: String,
+    /// Bounding box in original image pixels.
+    pub bbox: Rect,
+    pub confidence: f32,
+    /// Name of the dominant colour inside the box, e.g. `red`.
+    pub color: String,
+    /// CLIP embedding of the cropped box; empty without a CLIP model.
+    pub embedding: Vec<f32>,
 }
 
 /// Pixel-level diff between two captures.
@@ -71,6 +152,42 @@ pub struct VisualMemoryStore {
     pub session_count: u32,
     pub created_at: u64,
     pub updated_at: u64,
+    /// Named points the store can be rolled back to, oldest first.
+    pub snapshots: Vec<Snapshot>,
+    /// Label branches that resolve to another branch (alias → canonical).
+    pub label_aliases: BTreeMap<String, String>,
+    /// Names, goals and agents of sessions, by session ID.
+    pub sessions: BTreeMap<u32, SessionInfo>,
+    /// Checkpoint of a re-index in progress.
+    pub reindex: Option<ReindexCheckpoint>,
+    /// Mapping of similarity scores to probabilities, once fitted.
+    pub calibration: Option<Calibration>,
+}
+
+/// A point-in-time view of a visual memory store.
+///
+/// Observations are append-only, so a snapshot records only where the store
+/// ended and the state that can change afterwards (memory links), not the
+/// captures themselves.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub name: String,
+    pub created_at: u64,
+    /// Observations with an ID below this existed when it was taken.
+    pub next_id: u64,
+    pub observation_count: usize,
+    pub session_count: u32,
+    /// Memory links at the time, as (observation ID, memory node ID).
+    pub links: Vec<(u64, u64)>,
+}
+
+/// One capture in a sync manifest.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Content address of the capture (see `storage::content_hash`).
+    pub hash: String,
+    pub id: u64,
+    pub timestamp: u64,
 }
 
 impl VisualMemoryStore {
@@ -88,6 +205,11 @@ impl VisualMemoryStore {
             session_count: 0,
             created_at: now,
             updated_at: now,
+            snapshots: Vec::new(),
+            label_aliases: BTreeMap::new(),
+            sessions: BTreeMap::new(),
+            reindex: None,
+            calibration: None,
         }
     }
 
@@ -162,12 +284,24 @@ pub enum VisionError {
     #[error("Capture not found: {0}")]
     CaptureNotFound(u64),
 
+    #[error("Snapshot not found: {0}")]
+    SnapshotNotFound(String),
+
     #[error("Invalid input: {0}")]
     InvalidInput(String),
 
     #[error("Capture error: {0}")]
     Capture(String),
 
+    #[error("Caption error: {0}")]
+    Caption(String),
+
+    #[error("Detection error: {0}")]
+    Detection(String),
+
+    #[error("OCR error: {0}")]
+    Ocr(String),
+
     #[error("Model not available: {0}")]
     ModelNotAvailable(String),
 }
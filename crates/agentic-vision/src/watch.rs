@@ -0,0 +1,197 @@
+//! Watching folders for new images.
+//!
+//! A [`FolderWatcher`] follows directories with the platform's file system
+//! notifications and reports the image files that appear in them once they
+//! have stopped changing, since screenshot tools often write a file in
+//! several steps. Hidden files, such as the temporary file macOS writes a
+//! screenshot to first, are ignored, and so are files that were already
+//! there when watching started.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::{Duration, Instant};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::capture::is_supported_format;
+use crate::labels::normalize_label;
+use crate::types::{VisionError, VisionResult};
+
+/// How long a file must go unchanged before it is reported by default.
+pub const DEFAULT_SETTLE: Duration = Duration::from_millis(500);
+
+/// Watches directories for new image files.
+pub struct FolderWatcher {
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+    roots: Vec<PathBuf>,
+    /// Files changed since last reported, with when they last changed.
+    pending: HashMap<PathBuf, Instant>,
+}
+
+impl FolderWatcher {
+    /// Start watching `roots`, and with `recursive` the folders under them.
+    pub fn new(roots: &[PathBuf], recursive: bool) -> VisionResult<Self> {
+        let (tx, events) = channel();
+        let mut watcher = notify::recommended_watcher(tx)
+            .map_err(|e| VisionError::Capture(format!("Failed to start file watcher: {e}")))?;
+        let mode = if recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        let mut canonical = Vec::new();
+        for root in roots {
+            let root = root.canonicalize()?;
+            if !root.is_dir() {
+                return Err(VisionError::InvalidInput(format!(
+                    "{} is not a directory",
+                    root.display()
+                )));
+            }
+            watcher.watch(&root, mode).map_err(|e| {
+                VisionError::Capture(format!("Failed to watch {}: {e}", root.display()))
+            })?;
+            canonical.push(root);
+        }
+        Ok(Self {
+            _watcher: watcher,
+            events,
+            roots: canonical,
+            pending: HashMap::new(),
+        })
+    }
+
+    /// The watched directories, canonicalized.
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// Image files that appeared or changed and have since been left alone
+    /// for `settle`, oldest first. Each file is reported once per change.
+    pub fn poll(&mut self, settle: Duration) -> Vec<PathBuf> {
+        while let Ok(event) = self.events.try_recv() {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    tracing::warn!("File watcher error: {e}");
+                    continue;
+                }
+            };
+            match event.kind {
+                EventKind::Create(_) | EventKind::Modify(_) => {
+                    for path in event.paths.into_iter().filter(|p| is_candidate(p)) {
+                        self.pending.insert(path, Instant::now());
+                    }
+                }
+                EventKind::Remove(_) => {
+                    for path in &event.paths {
+                        self.pending.remove(path);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut ready: Vec<(PathBuf, Instant)> = self
+            .pending
+            .iter()
+            .filter(|(_, changed)| changed.elapsed() >= settle)
+            .map(|(path, changed)| (path.clone(), *changed))
+            .collect();
+        ready.sort_by_key(|(_, changed)| *changed);
+        for (path, _) in &ready {
+            self.pending.remove(path);
+        }
+        ready
+            .into_iter()
+            .map(|(path, _)| path)
+            .filter(|path| path.is_file())
+            .collect()
+    }
+
+    /// Labels for a file under one of the watched directories (see
+    /// [`infer_labels`]).
+    pub fn labels_for(&self, path: &Path) -> Vec<String> {
+        self.roots
+            .iter()
+            .filter(|root| path.starts_with(root))
+            .max_by_key(|root| root.components().count())
+            .map(|root| infer_labels(root, path))
+            .unwrap_or_default()
+    }
+}
+
+fn is_candidate(path: &Path) -> bool {
+    let hidden = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .is_none_or(|n| n.starts_with('.'));
+    !hidden && is_supported_format(&path.to_string_lossy())
+}
+
+/// Labels for an image found under `root`: the folder holding it, as a
+/// branch under the root's name. `Screenshots/app/checkout/a.png` under
+/// `Screenshots` is labeled `Screenshots/app/checkout`, and a file directly
+/// in it `Screenshots`.
+pub fn infer_labels(root: &Path, path: &Path) -> Vec<String> {
+    let Some(name) = root.file_name().and_then(|n| n.to_str()) else {
+        return Vec::new();
+    };
+    let folders = path
+        .parent()
+        .and_then(|dir| dir.strip_prefix(root).ok())
+        .into_iter()
+        .flat_map(|rel| rel.components())
+        .filter_map(|c| c.as_os_str().to_str());
+    let label: Vec<&str> = std::iter::once(name).chain(folders).collect();
+    let label = normalize_label(&label.join("/"));
+    if label.is_empty() {
+        Vec::new()
+    } else {
+        vec![label]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_infer_labels() {
+        let root = Path::new("/home/me/Screenshots");
+        assert_eq!(
+            infer_labels(root, &root.join("App/Checkout/shot.png")),
+            vec!["Screenshots/App/Checkout"]
+        );
+        assert_eq!(infer_labels(root, &root.join("a.png")), vec!["Screenshots"]);
+        assert!(is_candidate(&root.join("a.PNG")));
+        assert!(!is_candidate(&root.join(".Screenshot 1.png")));
+        assert!(!is_candidate(&root.join("notes.txt")));
+    }
+
+    #[test]
+    fn test_folder_watcher() {
+        let dir = std::env::temp_dir().join(format!("avis_watch_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("app")).unwrap();
+        std::fs::write(dir.join("before.png"), b"old").unwrap();
+        let mut watcher = FolderWatcher::new(std::slice::from_ref(&dir), true).unwrap();
+
+        std::fs::write(dir.join("app/shot.png"), b"new").unwrap();
+        std::fs::write(dir.join("notes.txt"), b"text").unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut found = Vec::new();
+        while found.is_empty() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+            found = watcher.poll(Duration::from_millis(100));
+        }
+        let shot = watcher.roots()[0].join("app/shot.png");
+        assert_eq!(found, vec![shot.clone()]);
+        let root_name = dir.file_name().unwrap().to_str().unwrap();
+        assert_eq!(watcher.labels_for(&shot), vec![format!("{root_name}/app")]);
+        assert!(watcher.poll(Duration::ZERO).is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -77,10 +77,24 @@ const TOOLS = [
           description: "Maximum results to return",
           default: 20,
         },
+        saved: {
+          type: "string",
+          description:
+            "Run a saved query by name instead of the filters above (see cortex_saved_queries)",
+        },
       },
       required: ["domain"],
     },
   },
+  {
+    name: "cortex_saved_queries",
+    description:
+      "List the named, vetted queries saved in the local registry. Run one with cortex_query's `saved` parameter.",
+    inputSchema: {
+      type: "object" as const,
+      properties: {},
+    },
+  },
   {
     name: "cortex_pathfind",
     description:
@@ -215,10 +229,14 @@ function buildParams(
         },
       };
     case "cortex_query": {
-      const params: Record<string, unknown> = {
-        domain: args["domain"],
-        limit: args["limit"] ?? 20,
-      };
+      const params: Record<string, unknown> = { domain: args["domain"] };
+      if (args["saved"] != null) {
+        // A saved query carries its own filters and limit.
+        params["saved"] = args["saved"];
+        if (args["limit"] != null) params["limit"] = args["limit"];
+        return { method: "query", params };
+      }
+      params["limit"] = args["limit"] ?? 20;
       if (args["page_type"] != null)
         params["page_type"] = args["page_type"];
       // Build feature ranges from shorthand params
@@ -232,6 +250,8 @@ function buildParams(
       if (Object.keys(features).length > 0) params["features"] = features;
       return { method: "query", params };
     }
+    case "cortex_saved_queries":
+      return { method: "saved_queries", params: {} };
     case "cortex_pathfind":
       return {
         method: "pathfind",
@@ -316,6 +336,15 @@ function formatResult(
       }
       return lines.join("\n");
     }
+    case "cortex_saved_queries": {
+      const queries = (result["queries"] ?? []) as Record<string, unknown>[];
+      if (queries.length === 0) return "No saved queries.";
+      const lines = [`${queries.length} saved quer${queries.length === 1 ? "y" : "ies"}:`];
+      for (const q of queries) {
+        lines.push(`  ${q["name"]}: ${JSON.stringify(q["query"])}`);
+      }
+      return lines.join("\n");
+    }
     case "cortex_pathfind":
       if (result["nodes"]) {
         const nodes = result["nodes"] as number[];
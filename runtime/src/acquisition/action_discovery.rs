@@ -16,8 +16,8 @@
 //!    BigCommerce from fingerprints in the HTML/JS and loads pre-built action
 //!    templates from an embedded JSON file.
 //!
-//! All public entry points are **synchronous**. Callers should wrap in
-//! `tokio::task::spawn_blocking` when integrating with the async runtime.
+//! All public entry points are **synchronous**. Async callers should run
+//! them on the [`parse_pool`](super::parse_pool).
 
 use crate::map::types::OpCode;
 use regex::Regex;
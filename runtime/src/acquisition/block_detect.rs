@@ -0,0 +1,290 @@
+//! Recognizing captcha and anti-bot block pages.
+//!
+//! Sites behind Cloudflare, PerimeterX, DataDome or Akamai answer a crawler
+//! they distrust with an interstitial — a challenge, a captcha, an "Access
+//! Denied" — often with status 200. Mapped as is, such a page becomes a node
+//! with the site's URL and the block page's content. [`detect`] looks for the
+//! vendors' markers in the headers and HTML of a response, fetched or
+//! rendered; the mapper leaves the pages it flags out of the map's content,
+//! marks their nodes blocked and counts them in a [`BlockReport`], and the
+//! HTTP client backs off from their host.
+//!
+//! Generic markers (a captcha widget, a "verify you are human" title) are
+//! only trusted on short pages or with a refusing status, since ordinary
+//! pages carry captchas on their forms.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Block pages longer than this are unusual; past it, only vendor markers
+/// count.
+const MAX_GENERIC_BYTES: usize = 32 * 1024;
+
+/// Blocked URLs kept in a [`BlockReport`].
+const MAX_REPORTED_URLS: usize = 20;
+
+/// Who served a block page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockKind {
+    /// Cloudflare challenge or "Attention Required" page.
+    Cloudflare,
+    /// PerimeterX (HUMAN) "Press & Hold" captcha.
+    PerimeterX,
+    /// DataDome captcha.
+    DataDome,
+    /// Akamai "Access Denied" page.
+    Akamai,
+    /// A page that is little more than a captcha.
+    Captcha,
+    /// A refusal that names no vendor.
+    Generic,
+}
+
+/// Case-insensitive markers found only in the HTML of each vendor's block
+/// pages. Scripts vendors embed in every page they protect, such as
+/// Cloudflare's `/cdn-cgi/challenge-platform/` bot detection, are not among
+/// them.
+const VENDOR_MARKERS: &[(BlockKind, &[&str])] = &[
+    (
+        BlockKind::Cloudflare,
+        &[
+            "window._cf_chl_opt",
+            "<title>just a moment...</title>",
+            "<title>attention required! | cloudflare</title>",
+            "cf-browser-verification",
+        ],
+    ),
+    (
+        BlockKind::PerimeterX,
+        &["id=\"px-captcha\"", "_pxcaptcha", "captcha.px-cdn.net"],
+    ),
+    (BlockKind::DataDome, &["geo.captcha-delivery.com"]),
+    (BlockKind::Akamai, &["errors.edgesuite.net"]),
+];
+
+/// Markers of the sensor scripts vendors embed in every page they protect:
+/// a block page only with a refusing status.
+const VENDOR_SENSORS: &[(BlockKind, &[&str])] = &[
+    (BlockKind::PerimeterX, &["window._pxappid"]),
+    (BlockKind::DataDome, &["ct.captcha-delivery.com"]),
+];
+
+/// Captcha widgets, a block page only when the page is short.
+const CAPTCHA_MARKERS: &[&str] = &[
+    "class=\"g-recaptcha\"",
+    "class=\"h-captcha\"",
+    "class=\"cf-turnstile\"",
+    "www.google.com/recaptcha/api",
+    "hcaptcha.com/1/api.js",
+];
+
+/// Titles of refusals, trusted with a refusing status or on a short page.
+const REFUSAL_TITLES: &[&str] = &[
+    "access denied",
+    "access to this page has been denied",
+    "are you a robot",
+    "are you a human",
+    "verify you are human",
+    "security check",
+    "request blocked",
+    "pardon our interruption",
+    "bot detection",
+];
+
+/// Whether a response with `status`, `headers` and `html` is a block page,
+/// and whose.
+pub fn detect(status: u16, headers: &[(String, String)], html: &str) -> Option<BlockKind> {
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    };
+    if header("cf-mitigated").is_some_and(|v| v.eq_ignore_ascii_case("challenge")) {
+        return Some(BlockKind::Cloudflare);
+    }
+    if header("x-datadome").is_some() && matches!(status, 403 | 429) {
+        return Some(BlockKind::DataDome);
+    }
+
+    let lower = html.to_ascii_lowercase();
+    let vendor = |table: &[(BlockKind, &[&str])]| {
+        table
+            .iter()
+            .find(|(_, markers)| markers.iter().any(|m| lower.contains(m)))
+            .map(|(kind, _)| *kind)
+    };
+    if let Some(kind) = vendor(VENDOR_MARKERS) {
+        return Some(kind);
+    }
+
+    let refusing = matches!(status, 401 | 403 | 429 | 503);
+    if refusing {
+        if let Some(kind) = vendor(VENDOR_SENSORS) {
+            return Some(kind);
+        }
+    }
+    let short = html.len() <= MAX_GENERIC_BYTES;
+    let title = title_of(&lower);
+    let refusal_title = title.is_some_and(|t| REFUSAL_TITLES.iter().any(|r| t.contains(r)));
+
+    // Akamai's refusal: "Access Denied" with a reference number.
+    if title.is_some_and(|t| t.contains("access denied")) && lower.contains("reference #") {
+        return Some(BlockKind::Akamai);
+    }
+    if short && CAPTCHA_MARKERS.iter().any(|m| lower.contains(m)) && (refusing || refusal_title) {
+        return Some(BlockKind::Captcha);
+    }
+    if refusal_title && (refusing || short) {
+        return Some(BlockKind::Generic);
+    }
+    None
+}
+
+/// The text of the first `<title>` in lowercased `html`.
+fn title_of(html: &str) -> Option<&str> {
+    let start = html.find("<title")?;
+    let open_end = start + html[start..].find('>')? + 1;
+    let close = open_end + html[open_end..].find("</title")?;
+    Some(html[open_end..close].trim())
+}
+
+/// How many of a map's pages were block pages.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BlockReport {
+    /// Pages fetched or rendered.
+    pub checked: usize,
+    /// Of those, block pages.
+    pub blocked: usize,
+    /// `blocked` over `checked`, from 0 to 1.
+    pub rate: f32,
+    /// Block pages by who served them.
+    pub by_kind: BTreeMap<BlockKind, usize>,
+    /// The first blocked URLs.
+    pub urls: Vec<String>,
+}
+
+impl BlockReport {
+    /// Count `pages` more checked pages.
+    pub fn checked(&mut self, pages: usize) {
+        self.checked += pages;
+        self.update_rate();
+    }
+
+    /// Count a block page served by `kind` for `url`, which must also have
+    /// been counted as checked.
+    pub fn blocked(&mut self, url: &str, kind: BlockKind) {
+        self.blocked += 1;
+        *self.by_kind.entry(kind).or_default() += 1;
+        if self.urls.len() < MAX_REPORTED_URLS {
+            self.urls.push(url.to_string());
+        }
+        self.update_rate();
+    }
+
+    fn update_rate(&mut self) {
+        self.rate = if self.checked == 0 {
+            0.0
+        } else {
+            self.blocked as f32 / self.checked as f32
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_vendors() {
+        let cloudflare = "<html><head><title>Just a moment...</title></head><body>\
+            <script>window._cf_chl_opt={cvId:'3'};</script></body></html>";
+        assert_eq!(detect(403, &[], cloudflare), Some(BlockKind::Cloudflare));
+        assert_eq!(
+            detect(200, &headers(&[("cf-mitigated", "challenge")]), ""),
+            Some(BlockKind::Cloudflare)
+        );
+
+        let perimeterx = "<html><title>Access to this page has been denied</title>\
+            <div id=\"px-captcha\"></div><script>window._pxAppId='PX1';</script></html>";
+        assert_eq!(detect(200, &[], perimeterx), Some(BlockKind::PerimeterX));
+
+        let datadome =
+            "<html><script src=\"https://ct.captcha-delivery.com/c.js\"></script></html>";
+        assert_eq!(detect(403, &[], datadome), Some(BlockKind::DataDome));
+        let datadome_captcha = "<html><iframe src=\"https://geo.captcha-delivery.com/captcha/\">\
+            </iframe></html>";
+        assert_eq!(
+            detect(200, &[], datadome_captcha),
+            Some(BlockKind::DataDome)
+        );
+        assert_eq!(
+            detect(403, &headers(&[("X-DataDome", "protected")]), ""),
+            Some(BlockKind::DataDome)
+        );
+
+        let akamai = "<HTML><HEAD><TITLE>Access Denied</TITLE></HEAD><BODY>\
+            You don't have permission to access this server.<P>Reference #18.abc</BODY></HTML>";
+        assert_eq!(detect(403, &[], akamai), Some(BlockKind::Akamai));
+    }
+
+    #[test]
+    fn test_sensor_scripts_on_served_pages() {
+        // Every page a PerimeterX or DataDome customer serves carries the
+        // vendor's sensor; with status 200 it is the page itself.
+        let perimeterx = "<html><title>Wireless Headphones | Shop</title>\
+            <script>window._pxAppId='PX1';</script><h1>Wireless Headphones</h1></html>";
+        assert_eq!(detect(200, &[], perimeterx), None);
+        assert_eq!(detect(403, &[], perimeterx), Some(BlockKind::PerimeterX));
+
+        let datadome = "<html><title>Shop</title>\
+            <script src=\"https://ct.captcha-delivery.com/tags.js\"></script><p>Sale</p></html>";
+        assert_eq!(detect(200, &[], datadome), None);
+    }
+
+    #[test]
+    fn test_detect_generic_needs_support() {
+        let captcha = "<html><title>Security check</title>\
+            <div class=\"g-recaptcha\" data-sitekey=\"k\"></div></html>";
+        assert_eq!(detect(200, &[], captcha), Some(BlockKind::Captcha));
+        assert_eq!(
+            detect(429, &[], "<title>Request blocked</title>"),
+            Some(BlockKind::Generic)
+        );
+
+        // A contact form with a captcha is an ordinary page.
+        let contact = format!(
+            "<html><title>Contact us</title><form><div class=\"g-recaptcha\"></div></form>{}</html>",
+            "<p>Write to us.</p>"
+        );
+        assert_eq!(detect(200, &[], &contact), None);
+
+        // A long article that mentions a refusal title is not blocked.
+        let article = format!(
+            "<html><title>Are you a robot? Our guide to CAPTCHAs</title>{}</html>",
+            "<p>text</p>".repeat(5000)
+        );
+        assert_eq!(detect(200, &[], &article), None);
+        assert_eq!(detect(200, &[], "<title>Shop</title><p>Hello</p>"), None);
+    }
+
+    #[test]
+    fn test_block_report_rate() {
+        let mut report = BlockReport::default();
+        report.checked(4);
+        report.blocked("https://example.com/a", BlockKind::Cloudflare);
+        assert_eq!(report.blocked, 1);
+        assert!((report.rate - 0.25).abs() < 1e-6);
+        assert_eq!(report.by_kind[&BlockKind::Cloudflare], 1);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["by_kind"]["cloudflare"], 1);
+    }
+}
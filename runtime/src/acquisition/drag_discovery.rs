@@ -8,8 +8,8 @@
 //! 2. **Element discovery** -- find draggable elements, drop zones, and their data attributes.
 //! 3. **API extraction** -- scan JS bundles for the fetch/axios/XHR call made after a drop.
 //!
-//! All public entry points are **synchronous**. Callers should wrap in
-//! `tokio::task::spawn_blocking` when integrating with the async runtime.
+//! All public entry points are **synchronous**. Async callers should run
+//! them on the [`parse_pool`](super::parse_pool).
 
 use crate::map::types::OpCode;
 use regex::Regex;
@@ -4,6 +4,7 @@
 //! and parses discovered feeds to extract URLs.
 
 use super::http_client::HttpClient;
+use super::parse_pool;
 
 /// An entry discovered from an RSS/Atom feed.
 #[derive(Debug, Clone)]
@@ -22,13 +23,12 @@ pub struct FeedEntry {
 /// 2. Tries common feed paths: /feed, /rss, /atom.xml, /feed.xml, /rss.xml.
 /// 3. Parses discovered feeds and returns entries.
 pub async fn discover_feeds(html: &str, domain: &str, client: &HttpClient) -> Vec<FeedEntry> {
-    // Extract feed URLs in a blocking task (uses scraper which is not Send)
+    // Extract feed URLs on the parse pool (uses scraper which is not Send)
     let html_owned = html.to_string();
     let domain_owned = domain.to_string();
-    let feed_urls =
-        tokio::task::spawn_blocking(move || discover_feed_urls_sync(&html_owned, &domain_owned))
-            .await
-            .unwrap_or_default();
+    let feed_urls = parse_pool::run(move || discover_feed_urls_sync(&html_owned, &domain_owned))
+        .await
+        .unwrap_or_default();
 
     let mut entries = Vec::new();
 
@@ -1,13 +1,295 @@
 //! Async HTTP client wrapping reqwest.
 //!
-//! Not a browser — just HTTP requests. Handles redirects, timeouts,
-//! retry on 5xx, and exponential backoff on 429.
-
+//! Not a browser — just HTTP requests. Handles redirects, timeouts, and
+//! retry with jittered exponential backoff on 5xx, 429 and connection
+//! errors. Bodies compressed with gzip, brotli or zstd are decoded as they
+//! stream in, and reading stops at [`HttpConfig::max_body_bytes`], so a huge
+//! page costs no more than its first part. Bodies that are not text, judged
+//! by their type and first bytes (see [`mime_sniff`](super::mime_sniff)),
+//! are not read at all.
+//!
+//! Every [`HttpClient`] shares one process-wide connection pool, also
+//! available to other modules as [`shared_client`]. Connections use HTTP/2
+//! where the server offers it, so requests to a host multiplex over one
+//! connection that stays open from one mapping to the next. Host names are
+//! resolved once per [`HttpConfig::dns_ttl`], and requests in flight to one
+//! host are capped. Settings come from the environment (see
+//! [`HttpConfig::from_env`]).
+//!
+//! Requests identify themselves with the honest
+//! [`DEFAULT_USER_AGENT`](super::profiles::DEFAULT_USER_AGENT), unless the
+//! client is given a domain's crawl profile (see [`HttpClient::with_profile`]).
+//!
+//! A response that is a captcha or anti-bot page (see
+//! [`block_detect`](super::block_detect)) is flagged
+//! [`blocked`](HttpResponse::blocked), and further requests to its host wait
+//! out a backoff that doubles with each block and resets on the next page
+//! that is not one.
+
+use super::block_detect::{self, BlockKind};
+use super::mime_sniff::{is_html_mime, is_text_mime, sniff_mime};
+use super::profiles::{CrawlProfile, DEFAULT_USER_AGENT};
+use crate::cancel::{CancellationToken, Cancelled};
 use anyhow::Result;
-use std::time::Duration;
+use dashmap::DashMap;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
-/// Response from an HTTP GET request.
+/// Longest wait between retries, including a server's `Retry-After`.
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Longest wait imposed on a host after block pages.
+const MAX_BLOCK_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Connection pool and retry settings.
 #[derive(Debug, Clone)]
+pub struct HttpConfig {
+    /// Limit on establishing a connection, TLS included.
+    pub connect_timeout: Duration,
+    /// Limit on waiting for the next bytes of a response.
+    pub read_timeout: Duration,
+    /// Limit on a HEAD request.
+    pub head_timeout: Duration,
+    /// How long an unused connection is kept open.
+    pub pool_idle_timeout: Duration,
+    /// Unused connections kept open per host.
+    pub max_idle_per_host: usize,
+    /// Requests in flight per host, across every client.
+    pub max_per_host: usize,
+    /// How long resolved addresses are reused; zero resolves every time.
+    pub dns_ttl: Duration,
+    /// Retries of a GET after the first attempt.
+    pub max_retries: u32,
+    /// Backoff ceiling before the first retry, doubling on each one.
+    pub retry_base: Duration,
+    /// Decoded body bytes kept; the rest of a longer body is not read.
+    pub max_body_bytes: usize,
+    /// Wait imposed on a host after its first block page, doubling with
+    /// each further one.
+    pub block_backoff: Duration,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(5),
+            read_timeout: Duration::from_secs(30),
+            head_timeout: Duration::from_secs(10),
+            pool_idle_timeout: Duration::from_secs(90),
+            max_idle_per_host: 16,
+            max_per_host: 20,
+            dns_ttl: Duration::from_secs(300),
+            max_retries: 2,
+            retry_base: Duration::from_millis(500),
+            max_body_bytes: 8 * 1024 * 1024,
+            block_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+impl HttpConfig {
+    /// Defaults overridden by `CORTEX_HTTP_CONNECT_TIMEOUT_MS`,
+    /// `CORTEX_HTTP_READ_TIMEOUT_MS`, `CORTEX_HTTP_HEAD_TIMEOUT_MS`,
+    /// `CORTEX_HTTP_POOL_IDLE_SECS`, `CORTEX_HTTP_MAX_IDLE_PER_HOST`,
+    /// `CORTEX_HTTP_MAX_PER_HOST`, `CORTEX_HTTP_DNS_TTL_SECS`,
+    /// `CORTEX_HTTP_MAX_RETRIES`, `CORTEX_HTTP_RETRY_BASE_MS`,
+    /// `CORTEX_HTTP_MAX_BODY_BYTES` and `CORTEX_HTTP_BLOCK_BACKOFF_MS`.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let millis = |name: &str, default: Duration| {
+            Duration::from_millis(read_env(name, default.as_millis() as u64))
+        };
+        let secs =
+            |name: &str, default: Duration| Duration::from_secs(read_env(name, default.as_secs()));
+        Self {
+            connect_timeout: millis("CORTEX_HTTP_CONNECT_TIMEOUT_MS", defaults.connect_timeout),
+            read_timeout: millis("CORTEX_HTTP_READ_TIMEOUT_MS", defaults.read_timeout),
+            head_timeout: millis("CORTEX_HTTP_HEAD_TIMEOUT_MS", defaults.head_timeout),
+            pool_idle_timeout: secs("CORTEX_HTTP_POOL_IDLE_SECS", defaults.pool_idle_timeout),
+            max_idle_per_host: read_env(
+                "CORTEX_HTTP_MAX_IDLE_PER_HOST",
+                defaults.max_idle_per_host,
+            ),
+            max_per_host: read_env("CORTEX_HTTP_MAX_PER_HOST", defaults.max_per_host).max(1),
+            dns_ttl: secs("CORTEX_HTTP_DNS_TTL_SECS", defaults.dns_ttl),
+            max_retries: read_env("CORTEX_HTTP_MAX_RETRIES", defaults.max_retries),
+            retry_base: millis("CORTEX_HTTP_RETRY_BASE_MS", defaults.retry_base),
+            max_body_bytes: read_env("CORTEX_HTTP_MAX_BODY_BYTES", defaults.max_body_bytes),
+            block_backoff: millis("CORTEX_HTTP_BLOCK_BACKOFF_MS", defaults.block_backoff),
+        }
+    }
+
+    /// Wait before retry number `attempt` (from 1): a random point in the
+    /// upper half of the doubled base, so that clients retrying together
+    /// spread out without any retrying at once.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .retry_base
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+            .min(MAX_BACKOFF);
+        let half = ceiling / 2;
+        half + half.mul_f64(rand::random::<f64>())
+    }
+
+    /// Wait imposed on a host after its `strikes`-th block page in a row.
+    fn block_backoff(&self, strikes: u32) -> Duration {
+        self.block_backoff
+            .saturating_mul(1 << strikes.saturating_sub(1).min(16))
+            .min(MAX_BLOCK_BACKOFF)
+    }
+}
+
+fn read_env<T: std::str::FromStr>(name: &str, default_value: T) -> T {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(default_value)
+}
+
+/// Resolver that remembers each host's addresses for a while.
+struct CachingResolver {
+    ttl: Duration,
+    cache: Arc<DashMap<String, (Instant, Vec<SocketAddr>)>>,
+}
+
+impl Resolve for CachingResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        if let Some(entry) = self.cache.get(&host) {
+            if entry.0.elapsed() < self.ttl {
+                let addrs = entry.1.clone();
+                return Box::pin(async move { Ok(Box::new(addrs.into_iter()) as Addrs) });
+            }
+        }
+        let cache = self.cache.clone();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            cache.insert(host, (Instant::now(), addrs.clone()));
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Clients and per-host limits shared by every [`HttpClient`].
+struct Pool {
+    client: reqwest::Client,
+    /// HTTP/1.1-only fallback client for sites that reject HTTP/2.
+    h1_client: reqwest::Client,
+    config: HttpConfig,
+    hosts: DashMap<String, Arc<Semaphore>>,
+    /// Hosts that served block pages: block pages in a row, and when
+    /// requests to them may resume.
+    blocks: DashMap<String, (u32, Instant)>,
+}
+
+fn host_of(url: &str) -> Option<String> {
+    Some(url::Url::parse(url).ok()?.host_str()?.to_ascii_lowercase())
+}
+
+impl Pool {
+    fn new(config: HttpConfig) -> Self {
+        let resolver = (!config.dns_ttl.is_zero()).then(|| {
+            Arc::new(CachingResolver {
+                ttl: config.dns_ttl,
+                cache: Arc::new(DashMap::new()),
+            })
+        });
+        let build = |http1_only: bool| {
+            let mut builder = reqwest::Client::builder()
+                .redirect(reqwest::redirect::Policy::limited(5))
+                .user_agent(DEFAULT_USER_AGENT)
+                .gzip(true)
+                .brotli(true)
+                .zstd(true)
+                .connect_timeout(config.connect_timeout)
+                .read_timeout(config.read_timeout)
+                .pool_idle_timeout(config.pool_idle_timeout)
+                .pool_max_idle_per_host(config.max_idle_per_host)
+                .tcp_keepalive(Duration::from_secs(30));
+            if http1_only {
+                builder = builder.http1_only();
+            } else {
+                builder = builder
+                    .http2_adaptive_window(true)
+                    .http2_keep_alive_interval(Duration::from_secs(30))
+                    .http2_keep_alive_while_idle(true);
+            }
+            if let Some(resolver) = &resolver {
+                builder = builder.dns_resolver(resolver.clone());
+            }
+            builder.build().unwrap_or_default()
+        };
+        Self {
+            client: build(false),
+            h1_client: build(true),
+            config,
+            hosts: DashMap::new(),
+            blocks: DashMap::new(),
+        }
+    }
+
+    /// Wait for a free slot for a request to `url`'s host, once the host's
+    /// block backoff, if any, is over.
+    async fn permit(&self, url: &str) -> Option<OwnedSemaphorePermit> {
+        let host = host_of(url)?;
+        let until = self.blocks.get(&host).map(|entry| entry.1);
+        if let Some(until) = until {
+            tokio::time::sleep_until(until.into()).await;
+        }
+        let semaphore = self
+            .hosts
+            .entry(host)
+            .or_insert_with(|| Arc::new(Semaphore::new(self.config.max_per_host)))
+            .clone();
+        semaphore.acquire_owned().await.ok()
+    }
+
+    /// Back off from `url`'s host after a block page, and return for how
+    /// long. Block pages that arrive during a backoff were requested before
+    /// it and do not lengthen it.
+    fn record_block(&self, url: &str) -> Duration {
+        let Some(host) = host_of(url) else {
+            return Duration::ZERO;
+        };
+        let now = Instant::now();
+        let mut entry = self.blocks.entry(host).or_insert((0, now));
+        if entry.1 <= now {
+            entry.0 += 1;
+            entry.1 = now + self.config.block_backoff(entry.0);
+        }
+        entry.1.saturating_duration_since(now)
+    }
+
+    /// Forget `url`'s host's block pages after one that was not.
+    fn record_unblocked(&self, url: &str) {
+        if let Some(host) = host_of(url) {
+            self.blocks.remove(&host);
+        }
+    }
+}
+
+static SHARED: OnceLock<Arc<Pool>> = OnceLock::new();
+
+fn shared_pool() -> Arc<Pool> {
+    SHARED
+        .get_or_init(|| Arc::new(Pool::new(HttpConfig::from_env())))
+        .clone()
+}
+
+/// The process-wide pooled client, for requests that need none of
+/// [`HttpClient`]'s retries or fallbacks. Prefer it to building a client,
+/// which starts without open connections.
+pub fn shared_client() -> reqwest::Client {
+    shared_pool().client.clone()
+}
+
+/// Response from an HTTP GET request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HttpResponse {
     /// Original requested URL.
     pub url: String,
@@ -17,8 +299,74 @@ pub struct HttpResponse {
     pub status: u16,
     /// Response headers (selected subset).
     pub headers: Vec<(String, String)>,
-    /// Response body as text.
+    /// Response body as text, cut at the size limit; empty for bodies that
+    /// are not text.
     pub body: String,
+    /// MIME type, declared or sniffed (see
+    /// [`sniff_mime`](super::mime_sniff::sniff_mime)).
+    #[serde(default)]
+    pub mime: String,
+    /// Whether the body was longer than the size limit.
+    #[serde(default)]
+    pub truncated: bool,
+    /// Who served the response, if it is a captcha or anti-bot page
+    /// rather than the requested one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocked: Option<BlockKind>,
+}
+
+impl HttpResponse {
+    /// Whether the body is an HTML document. Responses recorded before MIME
+    /// types were kept count as HTML.
+    pub fn is_html(&self) -> bool {
+        self.mime.is_empty() || is_html_mime(&self.mime)
+    }
+}
+
+/// A response body as read by [`read_body`].
+struct Body {
+    text: String,
+    mime: String,
+    truncated: bool,
+}
+
+/// Stream `resp`'s body, keeping at most `max_bytes`. The MIME type is
+/// judged from the first chunk; if it is not a text type, nothing is kept.
+/// A body that fails partway keeps what arrived.
+async fn read_body(mut resp: reqwest::Response, max_bytes: usize) -> Body {
+    let content_type = resp
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let mut bytes: Vec<u8> = Vec::new();
+    let mut mime = None;
+    let mut truncated = false;
+    while let Ok(Some(chunk)) = resp.chunk().await {
+        if mime.is_none() {
+            let sniffed = sniff_mime(content_type.as_deref(), &chunk);
+            if !is_text_mime(&sniffed) {
+                return Body {
+                    text: String::new(),
+                    mime: sniffed,
+                    truncated: false,
+                };
+            }
+            mime = Some(sniffed);
+        }
+        let room = max_bytes - bytes.len();
+        if chunk.len() > room {
+            bytes.extend_from_slice(&chunk[..room]);
+            truncated = true;
+            break;
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Body {
+        text: String::from_utf8_lossy(&bytes).into_owned(),
+        mime: mime.unwrap_or_else(|| sniff_mime(content_type.as_deref(), &[])),
+        truncated,
+    }
 }
 
 /// Response from an HTTP HEAD request.
@@ -41,41 +389,89 @@ pub struct HeadResponse {
 /// HTTP client for the acquisition engine.
 #[derive(Clone)]
 pub struct HttpClient {
-    client: reqwest::Client,
-    /// HTTP/1.1-only fallback client for sites that reject HTTP/2.
-    h1_client: reqwest::Client,
+    pool: Arc<Pool>,
+    /// Longest a HEAD request may take, at most the configured limit.
+    timeout: Duration,
+    /// Requests in flight when this fires fail with [`Cancelled`].
+    cancel: CancellationToken,
+    /// Identity sent with every request, if not the default.
+    profile: Option<Arc<CrawlProfile>>,
 }
 
 impl HttpClient {
-    /// Create a new HTTP client with standard Chrome user-agent.
+    /// Create an HTTP client on the shared connection pool, with the default
+    /// user agent. HEAD requests take at most `timeout_ms`; GET and
+    /// POST requests take the limit they are given.
     pub fn new(timeout_ms: u64) -> Self {
-        let ua = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) \
-                  AppleWebKit/537.36 (KHTML, like Gecko) \
-                  Chrome/145.0.0.0 Safari/537.36";
+        Self {
+            pool: shared_pool(),
+            timeout: Duration::from_millis(timeout_ms),
+            cancel: CancellationToken::new(),
+            profile: None,
+        }
+    }
 
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_millis(timeout_ms))
-            .redirect(reqwest::redirect::Policy::limited(5))
-            .user_agent(ua)
-            .build()
-            .unwrap_or_default();
+    /// Create an HTTP client with a connection pool of its own.
+    pub fn with_config(config: HttpConfig, timeout_ms: u64) -> Self {
+        Self {
+            pool: Arc::new(Pool::new(config)),
+            timeout: Duration::from_millis(timeout_ms),
+            cancel: CancellationToken::new(),
+            profile: None,
+        }
+    }
+
+    /// Abort GET and HEAD requests, including retries, when `cancel` fires.
+    pub fn with_cancel(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Send `profile`'s user agent, headers and cookies with every request.
+    pub fn with_profile(mut self, profile: CrawlProfile) -> Self {
+        self.profile = Some(Arc::new(profile));
+        self
+    }
+
+    /// The profile requests are sent with, if not the default.
+    pub fn profile(&self) -> Option<&CrawlProfile> {
+        self.profile.as_deref()
+    }
 
-        let h1_client = reqwest::Client::builder()
-            .timeout(Duration::from_millis(timeout_ms))
-            .redirect(reqwest::redirect::Policy::limited(5))
-            .user_agent(ua)
-            .http1_only()
-            .build()
-            .unwrap_or_default();
+    /// Add the profile's headers to `request`.
+    fn identify(&self, mut request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        if let Some(profile) = &self.profile {
+            for (name, value) in profile.request_headers() {
+                request = request.header(name, value);
+            }
+        }
+        request
+    }
 
-        Self { client, h1_client }
+    /// Fail with [`Cancelled`] if the client's token fires before `request`
+    /// completes.
+    async fn cancellable<T>(
+        &self,
+        request: impl std::future::Future<Output = Result<T>>,
+    ) -> Result<T> {
+        tokio::select! {
+            biased;
+            _ = self.cancel.cancelled() => Err(Cancelled.into()),
+            result = request => result,
+        }
     }
 
-    /// Perform a single GET request with retry on 5xx and backoff on 429.
+    /// Perform a single GET request with retry on 5xx and connection errors,
+    /// and backoff on 429.
     ///
     /// Falls back to HTTP/1.1 on protocol errors (some CDNs reject HTTP/2).
     pub async fn get(&self, url: &str, timeout_ms: u64) -> Result<HttpResponse> {
-        match self.get_inner(&self.client, url, timeout_ms).await {
+        self.cancellable(self.get_with_fallback(url, timeout_ms))
+            .await
+    }
+
+    async fn get_with_fallback(&self, url: &str, timeout_ms: u64) -> Result<HttpResponse> {
+        match self.get_inner(&self.pool.client, url, timeout_ms).await {
             Ok(resp) => Ok(resp),
             Err(e) => {
                 // If the error looks like a protocol issue, retry with HTTP/1.1
@@ -84,7 +480,7 @@ impl HttpClient {
                     || err_str.contains("protocol")
                     || err_str.contains("connection closed")
                 {
-                    self.get_inner(&self.h1_client, url, timeout_ms).await
+                    self.get_inner(&self.pool.h1_client, url, timeout_ms).await
                 } else {
                     Err(e)
                 }
@@ -99,11 +495,12 @@ impl HttpClient {
         timeout_ms: u64,
     ) -> Result<HttpResponse> {
         let mut retries = 0u32;
-        let max_retries = 2;
+        let max_retries = self.pool.config.max_retries;
 
         loop {
-            let resp = client
-                .get(url)
+            let permit = self.pool.permit(url).await;
+            let resp = self
+                .identify(client.get(url))
                 .timeout(Duration::from_millis(timeout_ms))
                 .send()
                 .await;
@@ -115,22 +512,23 @@ impl HttpClient {
 
                     // Retry on 5xx
                     if status >= 500 && retries < max_retries {
+                        drop(permit);
                         retries += 1;
-                        let delay = Duration::from_millis(500 * 2u64.pow(retries - 1));
-                        tokio::time::sleep(delay).await;
+                        tokio::time::sleep(self.pool.config.backoff(retries)).await;
                         continue;
                     }
 
                     // Backoff on 429
                     if status == 429 && retries < max_retries {
+                        drop(permit);
                         retries += 1;
-                        let retry_after = r
+                        let delay = r
                             .headers()
                             .get("retry-after")
                             .and_then(|v| v.to_str().ok())
                             .and_then(|s| s.parse::<u64>().ok())
-                            .unwrap_or(2);
-                        let delay = Duration::from_secs(retry_after.min(10));
+                            .map(|secs| Duration::from_secs(secs).min(MAX_BACKOFF))
+                            .unwrap_or_else(|| self.pool.config.backoff(retries));
                         tokio::time::sleep(delay).await;
                         continue;
                     }
@@ -146,26 +544,44 @@ impl HttpClient {
                                     | "last-modified"
                                     | "cache-control"
                                     | "x-robots-tag"
+                                    | "cf-mitigated"
+                                    | "x-datadome"
                             )
                         })
                         .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
                         .collect();
 
-                    let body = r.text().await.unwrap_or_default();
+                    let body = read_body(r, self.pool.config.max_body_bytes).await;
+                    drop(permit);
+
+                    let blocked = block_detect::detect(status, &headers, &body.text);
+                    match blocked {
+                        Some(kind) => {
+                            let delay = self.pool.record_block(url);
+                            tracing::warn!(
+                                "{url} is a {kind:?} block page; backing off its host for {delay:?}"
+                            );
+                        }
+                        None if status < 400 => self.pool.record_unblocked(url),
+                        None => {}
+                    }
 
                     return Ok(HttpResponse {
                         url: url.to_string(),
                         final_url,
                         status,
                         headers,
-                        body,
+                        body: body.text,
+                        mime: body.mime,
+                        truncated: body.truncated,
+                        blocked,
                     });
                 }
                 Err(e) => {
                     if retries < max_retries {
+                        drop(permit);
                         retries += 1;
-                        let delay = Duration::from_millis(500 * 2u64.pow(retries - 1));
-                        tokio::time::sleep(delay).await;
+                        tokio::time::sleep(self.pool.config.backoff(retries)).await;
                         continue;
                     }
                     return Err(e.into());
@@ -185,9 +601,9 @@ impl HttpClient {
         extra_headers: &[(String, String)],
         timeout_ms: u64,
     ) -> Result<HttpResponse> {
+        let _permit = self.pool.permit(url).await;
         let mut builder = self
-            .client
-            .post(url)
+            .identify(self.pool.client.post(url))
             .timeout(Duration::from_millis(timeout_ms));
 
         for (name, value) in extra_headers {
@@ -206,17 +622,28 @@ impl HttpClient {
             .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
             .collect();
 
-        let body = r.text().await.unwrap_or_default();
+        let body = read_body(r, self.pool.config.max_body_bytes).await;
+
+        let blocked = block_detect::detect(status, &headers, &body.text);
 
         Ok(HttpResponse {
             url: url.to_string(),
             final_url,
             status,
             headers,
-            body,
+            body: body.text,
+            mime: body.mime,
+            truncated: body.truncated,
+            blocked,
         })
     }
 
+    /// Back off from `url`'s host after a block page found some other way,
+    /// such as in a rendered page.
+    pub fn report_block(&self, url: &str) {
+        self.pool.record_block(url);
+    }
+
     /// Perform parallel GET requests with bounded concurrency.
     pub async fn get_many(
         &self,
@@ -226,12 +653,11 @@ impl HttpClient {
     ) -> Vec<Result<HttpResponse>> {
         use futures::stream::{self, StreamExt};
 
-        let results: Vec<Result<HttpResponse>> = stream::iter(urls.iter())
+        // Owned URLs keep the stream's futures provably Send.
+        let results: Vec<Result<HttpResponse>> = stream::iter(urls.to_vec())
             .map(|url| {
                 let client = self.clone();
-                let u = url.clone();
-                let t = timeout_ms;
-                async move { client.get(&u, t).await }
+                async move { client.get(&url, timeout_ms).await }
             })
             .buffer_unordered(concurrency)
             .collect()
@@ -248,15 +674,20 @@ impl HttpClient {
     ) -> Vec<Result<HeadResponse>> {
         use futures::stream::{self, StreamExt};
 
-        let results: Vec<Result<HeadResponse>> = stream::iter(urls.iter())
-            .map(|url| {
-                let client = self.client.clone();
-                let u = url.clone();
+        let timeout = self.timeout.min(self.pool.config.head_timeout);
+        let results: Vec<Result<HeadResponse>> = stream::iter(urls.to_vec())
+            .map(|u| {
+                let client = self.pool.client.clone();
                 async move {
-                    let resp = client
-                        .head(&u)
-                        .timeout(Duration::from_secs(10))
-                        .send()
+                    let resp = self
+                        .cancellable(async {
+                            let _permit = self.pool.permit(&u).await;
+                            Ok(self
+                                .identify(client.head(&u))
+                                .timeout(timeout)
+                                .send()
+                                .await?)
+                        })
                         .await?;
 
                     let status = resp.status().as_u16();
@@ -310,6 +741,198 @@ mod tests {
         let _ = client;
     }
 
+    #[tokio::test]
+    async fn test_cancelled_client_fails_fast() {
+        let cancel = CancellationToken::new();
+        let client = HttpClient::new(10000).with_cancel(cancel.clone());
+        cancel.cancel();
+        // 192.0.2.0/24 is reserved for documentation and never answers.
+        let err = client.get("http://192.0.2.1/", 10000).await.unwrap_err();
+        assert!(crate::cancel::is_cancelled(&err));
+        let heads = client
+            .head_many(&["http://192.0.2.1/".to_string()], 1)
+            .await;
+        assert!(crate::cancel::is_cancelled(heads[0].as_ref().unwrap_err()));
+    }
+
+    #[test]
+    fn test_backoff_is_jittered_and_capped() {
+        let config = HttpConfig::default();
+        for _ in 0..20 {
+            let first = config.backoff(1);
+            assert!(first >= Duration::from_millis(250) && first <= Duration::from_millis(500));
+            let third = config.backoff(3);
+            assert!(third >= Duration::from_millis(1000) && third <= Duration::from_millis(2000));
+            assert!(config.backoff(30) <= MAX_BACKOFF);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_per_host_limit() {
+        let pool = Pool::new(HttpConfig {
+            max_per_host: 1,
+            ..HttpConfig::default()
+        });
+        let held = pool.permit("https://Example.com/a").await.unwrap();
+        let other_host = pool.permit("https://example.org/").await;
+        assert!(other_host.is_some());
+        let waiting = tokio::time::timeout(
+            Duration::from_millis(50),
+            pool.permit("https://example.com/b"),
+        )
+        .await;
+        assert!(waiting.is_err());
+        drop(held);
+        assert!(pool.permit("https://example.com/b").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_block_backoff() {
+        let config = HttpConfig {
+            block_backoff: Duration::from_millis(40),
+            ..HttpConfig::default()
+        };
+        assert_eq!(config.block_backoff(3), Duration::from_millis(160));
+        assert_eq!(config.block_backoff(30), MAX_BLOCK_BACKOFF);
+
+        let pool = Pool::new(config);
+        let first = pool.record_block("https://example.com/a");
+        assert!(first > Duration::ZERO && first <= Duration::from_millis(40));
+        // Blocks during the backoff do not lengthen it.
+        assert!(pool.record_block("https://example.com/b") <= first);
+
+        let waiting = tokio::time::timeout(
+            Duration::from_millis(10),
+            pool.permit("https://example.com/c"),
+        )
+        .await;
+        assert!(waiting.is_err());
+        assert!(pool.permit("https://example.org/").await.is_some());
+        assert!(pool.permit("https://example.com/c").await.is_some());
+
+        // The next block after the backoff doubles it; a good page resets it.
+        let second = pool.record_block("https://example.com/d");
+        assert!(second > Duration::from_millis(40));
+        pool.record_unblocked("https://example.com/e");
+        assert!(pool.blocks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dns_cache() {
+        let resolver = CachingResolver {
+            ttl: Duration::from_secs(60),
+            cache: Arc::new(DashMap::new()),
+        };
+        let name: Name = "localhost".parse().unwrap();
+        let first: Vec<SocketAddr> = resolver.resolve(name).await.unwrap().collect();
+        assert!(!first.is_empty());
+        assert!(resolver.cache.contains_key("localhost"));
+        let name: Name = "localhost".parse().unwrap();
+        let second: Vec<SocketAddr> = resolver.resolve(name).await.unwrap().collect();
+        assert_eq!(first, second);
+    }
+
+    #[tokio::test]
+    async fn test_retries_server_errors() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let app = axum::Router::new().route(
+            "/",
+            axum::routing::get(move || {
+                let counter = counter.clone();
+                async move {
+                    if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                        (axum::http::StatusCode::SERVICE_UNAVAILABLE, "busy")
+                    } else {
+                        (axum::http::StatusCode::OK, "ok")
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let client = HttpClient::with_config(
+            HttpConfig {
+                retry_base: Duration::from_millis(2),
+                ..HttpConfig::default()
+            },
+            5000,
+        );
+        let resp = client.get(&format!("http://{addr}/"), 5000).await.unwrap();
+        assert_eq!(resp.status, 200);
+        assert_eq!(resp.body, "ok");
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_body_decoding_limits_and_sniffing() {
+        use axum::http::header::{CONTENT_ENCODING, CONTENT_TYPE};
+
+        let page = format!("<html><body>{}</body></html>", "x".repeat(10_000));
+        let compressed = zstd::encode_all(page.as_bytes(), 3).unwrap();
+        let app = axum::Router::new()
+            .route(
+                "/zstd",
+                axum::routing::get(move || async move {
+                    (
+                        [(CONTENT_ENCODING, "zstd"), (CONTENT_TYPE, "text/html")],
+                        compressed,
+                    )
+                }),
+            )
+            .route(
+                "/big",
+                axum::routing::get(|| async {
+                    "<!doctype html>".to_string() + &"y".repeat(50_000)
+                }),
+            )
+            .route(
+                "/image",
+                axum::routing::get(|| async {
+                    (
+                        [(CONTENT_TYPE, "application/octet-stream")],
+                        b"\x89PNG\r\n\x1a\nbinary".to_vec(),
+                    )
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let client = HttpClient::with_config(
+            HttpConfig {
+                max_body_bytes: 20_000,
+                ..HttpConfig::default()
+            },
+            5000,
+        );
+        let zstd = client
+            .get(&format!("http://{addr}/zstd"), 5000)
+            .await
+            .unwrap();
+        assert_eq!(zstd.body, page);
+        assert!(zstd.is_html() && !zstd.truncated);
+
+        let big = client
+            .get(&format!("http://{addr}/big"), 5000)
+            .await
+            .unwrap();
+        assert_eq!(big.body.len(), 20_000);
+        assert!(big.truncated);
+        assert_eq!(big.mime, "text/html");
+
+        let image = client
+            .get(&format!("http://{addr}/image"), 5000)
+            .await
+            .unwrap();
+        assert_eq!(image.mime, "image/png");
+        assert!(image.body.is_empty() && !image.is_html());
+    }
+
     #[test]
     fn test_head_response_defaults() {
         let resp = HeadResponse {
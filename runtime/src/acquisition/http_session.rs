@@ -19,6 +19,10 @@ pub struct HttpSession {
     pub session_id: String,
     /// Domain this session is valid for.
     pub domain: String,
+    /// Other domains it is valid for: the rest of the domain's project, when
+    /// the project shares sessions.
+    #[serde(default)]
+    pub shared_with: Vec<String>,
     /// Session cookies (name -> value).
     pub cookies: HashMap<String, String>,
     /// Authentication headers to include (header-name -> value).
@@ -65,6 +69,7 @@ impl HttpSession {
         Self {
             session_id,
             domain: domain.to_string(),
+            shared_with: Vec::new(),
             cookies: HashMap::new(),
             auth_headers: HashMap::new(),
             csrf_token: None,
@@ -118,6 +123,15 @@ impl HttpSession {
     pub fn set_expires(&mut self, unix_timestamp: f64) {
         self.expires_at = Some(unix_timestamp);
     }
+
+    /// Check whether this session may be used for `domain`.
+    pub fn is_valid_for(&self, domain: &str) -> bool {
+        self.domain.eq_ignore_ascii_case(domain)
+            || self
+                .shared_with
+                .iter()
+                .any(|d| d.eq_ignore_ascii_case(domain))
+    }
 }
 
 #[cfg(test)]
@@ -181,4 +195,15 @@ mod tests {
         assert_eq!(session.auth_headers.get("X-Api-Key").unwrap(), "key123");
         assert_eq!(session.auth_headers.get("X-Custom").unwrap(), "custom_val");
     }
+
+    #[test]
+    fn test_is_valid_for_shared_domains() {
+        let mut session = HttpSession::new("shop.com", AuthType::Bearer);
+        assert!(session.is_valid_for("Shop.com"));
+        assert!(!session.is_valid_for("shop-outlet.com"));
+
+        session.shared_with = vec!["shop-outlet.com".to_string()];
+        assert!(session.is_valid_for("shop-outlet.com"));
+        assert!(!session.is_valid_for("other.com"));
+    }
 }
@@ -0,0 +1,150 @@
+//! MIME type sniffing for fetched bodies.
+//!
+//! Servers often label responses loosely (`text/plain` for HTML,
+//! `application/octet-stream` for anything) or not at all. A declared
+//! specific type is trusted; generic or missing ones are replaced by what
+//! the first bytes of the body look like, following the spirit of the
+//! WHATWG sniffing rules without their full table.
+
+/// Declared types that say little about the body.
+const GENERIC_TYPES: [&str; 3] = ["text/plain", "application/octet-stream", "unknown/unknown"];
+
+/// Tags that mark the start of an HTML document.
+const HTML_PREFIXES: [&str; 10] = [
+    "<!doctype html",
+    "<html",
+    "<head",
+    "<body",
+    "<title",
+    "<meta",
+    "<script",
+    "<div",
+    "<p",
+    "<!--",
+];
+
+/// File signatures of common binary formats.
+const SIGNATURES: [(&[u8], &str); 7] = [
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF8", "image/gif"),
+    (b"%PDF-", "application/pdf"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"\x1f\x8b", "application/gzip"),
+    (b"\x00\x00\x01\x00", "image/x-icon"),
+];
+
+/// The MIME type of a body: the essence of `content_type` (lowercase,
+/// without parameters) unless it is missing or generic, else sniffed from
+/// `prefix`, the first bytes of the body.
+pub fn sniff_mime(content_type: Option<&str>, prefix: &[u8]) -> String {
+    let declared = content_type
+        .and_then(|ct| ct.split(';').next())
+        .map(|m| m.trim().to_ascii_lowercase())
+        .filter(|m| !m.is_empty());
+    match declared {
+        Some(m) if !GENERIC_TYPES.contains(&m.as_str()) => m,
+        _ => sniff_bytes(prefix).to_string(),
+    }
+}
+
+fn sniff_bytes(prefix: &[u8]) -> &'static str {
+    if let Some((_, mime)) = SIGNATURES.iter().find(|(sig, _)| prefix.starts_with(sig)) {
+        return mime;
+    }
+    if prefix.len() >= 12 && &prefix[..4] == b"RIFF" && &prefix[8..12] == b"WEBP" {
+        return "image/webp";
+    }
+
+    let text = prefix.strip_prefix(b"\xef\xbb\xbf").unwrap_or(prefix);
+    let start = text
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(text.len());
+    let head = String::from_utf8_lossy(&text[start..text.len().min(start + 64)]).to_lowercase();
+    if HTML_PREFIXES.iter().any(|p| {
+        head.strip_prefix(p)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '>', '\t', '\n', '\r']))
+    }) {
+        return "text/html";
+    }
+    if head.starts_with("<?xml") || head.starts_with("<rss") || head.starts_with("<feed") {
+        return "application/xml";
+    }
+    if head.starts_with('{') || head.starts_with('[') {
+        return "application/json";
+    }
+    let binary = text
+        .iter()
+        .any(|&b| b == 0 || (b < 0x20 && !b.is_ascii_whitespace() && b != 0x1b));
+    if binary {
+        "application/octet-stream"
+    } else {
+        "text/plain"
+    }
+}
+
+/// Whether bodies of type `mime` are text worth reading: any `text/` type,
+/// HTML, XML, JSON, and JavaScript.
+pub fn is_text_mime(mime: &str) -> bool {
+    mime.starts_with("text/")
+        || mime.ends_with("+xml")
+        || mime.ends_with("+json")
+        || matches!(
+            mime,
+            "application/xml"
+                | "application/json"
+                | "application/javascript"
+                | "application/x-javascript"
+                | "application/ecmascript"
+        )
+}
+
+/// Whether `mime` is an HTML document type.
+pub fn is_html_mime(mime: &str) -> bool {
+    matches!(mime, "text/html" | "application/xhtml+xml")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_declared_type_wins_unless_generic() {
+        assert_eq!(
+            sniff_mime(Some("Text/HTML; charset=utf-8"), b"{}"),
+            "text/html"
+        );
+        assert_eq!(
+            sniff_mime(Some("text/plain"), b"\n  <!DOCTYPE html><html>"),
+            "text/html"
+        );
+        assert_eq!(
+            sniff_mime(Some("application/octet-stream"), b"\x89PNG\r\n\x1a\n...."),
+            "image/png"
+        );
+        assert_eq!(
+            sniff_mime(None, b"<?xml version=\"1.0\"?><rss>"),
+            "application/xml"
+        );
+        assert_eq!(sniff_mime(None, b"[1, 2]"), "application/json");
+        assert_eq!(sniff_mime(None, b"User-agent: *\nDisallow:"), "text/plain");
+        assert_eq!(
+            sniff_mime(None, b"\x00\x01\x02garbage"),
+            "application/octet-stream"
+        );
+        // "<pre" is not "<p".
+        assert_eq!(sniff_mime(None, b"<pre>"), "text/plain");
+    }
+
+    #[test]
+    fn test_text_and_html_types() {
+        assert!(is_text_mime("text/css"));
+        assert!(is_text_mime("application/rss+xml"));
+        assert!(is_text_mime("application/ld+json"));
+        assert!(!is_text_mime("image/png"));
+        assert!(!is_text_mime("application/pdf"));
+        assert!(is_html_mime("application/xhtml+xml"));
+        assert!(!is_html_mime("application/xml"));
+    }
+}
@@ -7,6 +7,7 @@
 pub mod action_discovery;
 pub mod api_discovery;
 pub mod auth;
+pub mod block_detect;
 pub mod canvas_extractor;
 pub mod drag_discovery;
 pub mod feed_parser;
@@ -14,7 +15,10 @@ pub mod head_scanner;
 pub mod http_client;
 pub mod http_session;
 pub mod js_analyzer;
+pub mod mime_sniff;
+pub mod parse_pool;
 pub mod pattern_engine;
+pub mod profiles;
 pub mod structured;
 pub mod webmcp;
 pub mod ws_discovery;
@@ -0,0 +1,318 @@
+//! Dedicated threads for HTML parsing.
+//!
+//! `scraper` documents are `!Send`, and parsing a large page blocks for
+//! milliseconds, so async code never parses HTML itself. It sends the page
+//! (owned data) to this pool and awaits the extracted result (owned data
+//! again): no `scraper` type ever lives in a future, and the futures that
+//! map, refresh and watch pages stay `Send` without any unsafe assertion.
+//!
+//! The pool is a fixed set of threads, separate from tokio's blocking pool,
+//! so a burst of parsing cannot starve file and DNS work queued there.
+//! [`ParsePool::run_each`] spreads a batch of pages over all of them.
+//!
+//! Every job has a time limit, counted from when a thread picks it up. A
+//! thread still parsing when it runs out is written off: a new thread takes
+//! its place, and it exits once its job returns. At most [`MAX_STUCK`]
+//! threads are written off at a time, so pathological pages cannot spawn
+//! threads without bound; past that, a stuck thread keeps its place.
+
+use anyhow::{anyhow, Result};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tracing::warn;
+
+/// Most parse threads to start, however many cores there are.
+const MAX_THREADS: usize = 8;
+
+/// Most threads written off as stuck, and replaced, at a time.
+pub const MAX_STUCK: usize = MAX_THREADS;
+
+/// Longest [`ParsePool::run`] waits for a job once a thread picks it up.
+pub const PARSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A queued job. Returns whether its thread was replaced while it ran, in
+/// which case the thread exits.
+type Job = Box<dyn FnOnce() -> bool + Send>;
+
+/// States of a job, shared by its thread and the task awaiting it.
+const RUNNING: u8 = 0;
+const DONE: u8 = 1;
+const REPLACED: u8 = 2;
+
+/// What the pool's threads share.
+#[derive(Debug)]
+struct Shared {
+    queue: Mutex<Receiver<Job>>,
+    /// Threads spawned so far, for naming.
+    spawned: AtomicUsize,
+    /// Threads written off as stuck that have not exited yet.
+    stuck: AtomicUsize,
+}
+
+/// A pool of parse threads fed by a channel.
+#[derive(Debug)]
+pub struct ParsePool {
+    jobs: Sender<Job>,
+    shared: Arc<Shared>,
+}
+
+/// A queued job's handles.
+struct Pending<R> {
+    started: oneshot::Receiver<()>,
+    done: oneshot::Receiver<R>,
+    state: Arc<AtomicU8>,
+}
+
+/// Why a job gave no result.
+enum Failure {
+    Panicked,
+    TimedOut,
+    ShutDown,
+}
+
+impl ParsePool {
+    /// Start a pool with `threads` parse threads.
+    pub fn new(threads: usize) -> Self {
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(queue),
+            spawned: AtomicUsize::new(0),
+            stuck: AtomicUsize::new(0),
+        });
+        for _ in 0..threads.max(1) {
+            spawn_worker(&shared).expect("failed to spawn parse thread");
+        }
+        Self { jobs, shared }
+    }
+
+    /// Run `parse` on a parse thread and await its result. Fails if `parse`
+    /// panics or runs longer than [`PARSE_TIMEOUT`].
+    pub async fn run<R, F>(&self, parse: F) -> Result<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let pending = self
+            .submit(parse)
+            .ok_or_else(|| anyhow!("parse pool has shut down"))?;
+        self.finish(pending, PARSE_TIMEOUT)
+            .await
+            .map_err(|failure| match failure {
+                Failure::Panicked => anyhow!("HTML parsing panicked"),
+                Failure::TimedOut => {
+                    anyhow!("HTML parsing took over {} s", PARSE_TIMEOUT.as_secs())
+                }
+                Failure::ShutDown => anyhow!("parse pool has shut down"),
+            })
+    }
+
+    /// Run `parse` on each of `items`, in parallel across the pool's
+    /// threads, and await the results in item order. An item gives `None`
+    /// if `parse` panics on it or runs longer than `timeout`, counted from
+    /// when a thread picks it up; a thread still parsing it is replaced.
+    pub async fn run_each<T, R, F>(
+        &self,
+        items: Vec<T>,
+        timeout: Duration,
+        parse: F,
+    ) -> Vec<Option<R>>
+    where
+        T: Send + 'static,
+        R: Send + 'static,
+        F: Fn(T) -> R + Send + Sync + 'static,
+    {
+        let parse = Arc::new(parse);
+        let pending: Vec<_> = items
+            .into_iter()
+            .map(|item| {
+                let parse = Arc::clone(&parse);
+                self.submit(move || parse(item))
+            })
+            .collect();
+
+        futures::future::join_all(
+            pending
+                .into_iter()
+                .map(|job| async move { self.finish(job?, timeout).await.ok() }),
+        )
+        .await
+    }
+
+    /// Queue `parse`, or `None` if the pool has shut down.
+    fn submit<R, F>(&self, parse: F) -> Option<Pending<R>>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (started_tx, started) = oneshot::channel();
+        let (tx, done) = oneshot::channel();
+        let state = Arc::new(AtomicU8::new(RUNNING));
+        let finished = Arc::clone(&state);
+        let job: Job = Box::new(move || {
+            let _ = started_tx.send(());
+            if let Ok(result) = catch_unwind(AssertUnwindSafe(parse)) {
+                let _ = tx.send(result);
+            }
+            finished
+                .compare_exchange(RUNNING, DONE, Ordering::SeqCst, Ordering::SeqCst)
+                .is_err()
+        });
+        self.jobs.send(job).ok()?;
+        Some(Pending {
+            started,
+            done,
+            state,
+        })
+    }
+
+    /// Await a queued job for up to `timeout` once it starts, replacing its
+    /// thread if it runs out.
+    async fn finish<R>(&self, pending: Pending<R>, timeout: Duration) -> Result<R, Failure> {
+        pending.started.await.map_err(|_| Failure::ShutDown)?;
+        match tokio::time::timeout(timeout, pending.done).await {
+            Ok(result) => result.map_err(|_| Failure::Panicked),
+            Err(_) => {
+                self.replace(&pending.state);
+                Err(Failure::TimedOut)
+            }
+        }
+    }
+
+    /// Write off the thread running a timed-out job and start another in
+    /// its place, unless [`MAX_STUCK`] threads are written off already.
+    fn replace(&self, state: &AtomicU8) {
+        let stuck = &self.shared.stuck;
+        if stuck.fetch_add(1, Ordering::SeqCst) >= MAX_STUCK {
+            stuck.fetch_sub(1, Ordering::SeqCst);
+            warn!("{MAX_STUCK} parse threads are stuck; not replacing another");
+            return;
+        }
+        if state
+            .compare_exchange(RUNNING, REPLACED, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            // It finished meanwhile; the thread stays in the pool.
+            stuck.fetch_sub(1, Ordering::SeqCst);
+            return;
+        }
+        if let Err(e) = spawn_worker(&self.shared) {
+            warn!("failed to replace a stuck parse thread: {e}");
+        }
+    }
+}
+
+/// Start a parse thread taking jobs off the shared queue.
+fn spawn_worker(shared: &Arc<Shared>) -> std::io::Result<()> {
+    let i = shared.spawned.fetch_add(1, Ordering::SeqCst);
+    let shared = Arc::clone(shared);
+    std::thread::Builder::new()
+        .name(format!("cortex-parse-{i}"))
+        .spawn(move || work(&shared))
+        .map(|_| ())
+}
+
+/// Take jobs off the shared queue until the pool is dropped, or until the
+/// thread is replaced while running one.
+fn work(shared: &Shared) {
+    loop {
+        let job = match shared.queue.lock() {
+            Ok(queue) => queue.recv(),
+            Err(_) => return,
+        };
+        match job {
+            Ok(job) => {
+                if job() {
+                    shared.stuck.fetch_sub(1, Ordering::SeqCst);
+                    return;
+                }
+            }
+            Err(_) => return,
+        }
+    }
+}
+
+/// The process-wide pool, sized to the machine.
+pub fn global() -> &'static ParsePool {
+    static POOL: OnceLock<ParsePool> = OnceLock::new();
+    POOL.get_or_init(|| {
+        let cores = std::thread::available_parallelism().map_or(2, |n| n.get());
+        ParsePool::new(cores.min(MAX_THREADS))
+    })
+}
+
+/// Run `parse` on the global pool. See [`ParsePool::run`].
+pub async fn run<R, F>(parse: F) -> Result<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    global().run(parse).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_parses_off_the_runtime_and_survives_panics() {
+        let pool = ParsePool::new(2);
+        let html = "<html><head><title>Shop</title></head></html>".to_string();
+        let title = pool
+            .run(move || {
+                let doc = scraper::Html::parse_document(&html);
+                let sel = scraper::Selector::parse("title").unwrap();
+                let title: String = doc.select(&sel).flat_map(|e| e.text()).collect();
+                (title, std::thread::current().name().map(String::from))
+            })
+            .await
+            .unwrap();
+        assert_eq!(title.0, "Shop");
+        assert!(title.1.unwrap().starts_with("cortex-parse-"));
+
+        let panicked = pool.run(|| -> u32 { panic!("bad page") }).await;
+        assert!(panicked.is_err());
+        // The threads are still there for the next page.
+        assert_eq!(pool.run(|| 7).await.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_run_each_keeps_order_and_times_out_slow_items() {
+        let pool = ParsePool::new(2);
+        let results = pool
+            .run_each(vec![1u64, 2, 3, 4, 5], Duration::from_millis(200), |n| {
+                match n {
+                    3 => std::thread::sleep(Duration::from_secs(1)),
+                    4 => panic!("bad page"),
+                    _ => std::thread::sleep(Duration::from_millis(20)),
+                }
+                n * 10
+            })
+            .await;
+        assert_eq!(results, vec![Some(10), Some(20), None, None, Some(50)]);
+    }
+
+    #[tokio::test]
+    async fn test_stuck_thread_is_replaced() {
+        let pool = ParsePool::new(1);
+        let stuck = pool.submit(|| std::thread::sleep(Duration::from_millis(500)));
+        let timed_out = pool.finish(stuck.unwrap(), Duration::from_millis(50)).await;
+        assert!(matches!(timed_out, Err(Failure::TimedOut)));
+        assert_eq!(pool.shared.stuck.load(Ordering::SeqCst), 1);
+
+        // The only thread is still parsing, but its replacement takes the
+        // next job at once.
+        let started = std::time::Instant::now();
+        assert_eq!(pool.run(|| 7).await.unwrap(), 7);
+        assert!(started.elapsed() < Duration::from_millis(400));
+
+        // The stuck thread exits once its job returns.
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        assert_eq!(pool.shared.stuck.load(Ordering::SeqCst), 0);
+        assert_eq!(pool.shared.spawned.load(Ordering::SeqCst), 2);
+        assert_eq!(pool.run(|| 8).await.unwrap(), 8);
+    }
+}
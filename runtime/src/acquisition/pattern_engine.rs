@@ -8,8 +8,8 @@
 //!
 //! Selector patterns are loaded at compile time from `css_selectors.json` via
 //! `include_str!`. All public entry points are **synchronous** because the
-//! `scraper` crate's types are `!Send` -- callers should wrap in
-//! `tokio::task::spawn_blocking` when integrating with the async runtime.
+//! `scraper` crate's types are `!Send` -- async callers should run them on
+//! the [`parse_pool`](super::parse_pool).
 //!
 //! # Confidence model
 //!
@@ -23,6 +23,7 @@ use crate::map::types::PageType;
 use regex::Regex;
 use scraper::{Html, Selector};
 use serde_json::Value;
+use std::collections::HashMap;
 
 // ── Compile-time selector configuration ──────────────────────────────────────
 
@@ -89,6 +90,10 @@ pub struct PatternResult {
     pub actions: Vec<DiscoveredAction>,
     /// Forms found on the page.
     pub forms: Vec<DiscoveredForm>,
+    /// The selector each extracted field was read from, keyed by field name
+    /// (`price`, `rating`, `availability`). Text matches are recorded as
+    /// `text:<pattern>`.
+    pub selectors: HashMap<&'static str, String>,
 }
 
 // ── Main entry point ─────────────────────────────────────────────────────────
@@ -148,6 +153,7 @@ fn extract_price(document: &Html, config: &Value, result: &mut PatternResult) {
                             if let Some(val) = el.value().attr(attr_name) {
                                 if let Some(price) = parse_price_text(val) {
                                     result.price = Some((price, 0.95));
+                                    result.selectors.insert("price", selector_str.to_string());
                                     detect_currency_from_text(val, result);
                                     return;
                                 }
@@ -167,6 +173,7 @@ fn extract_price(document: &Html, config: &Value, result: &mut PatternResult) {
                 if let Some(content) = el.value().attr("content") {
                     if let Some(price) = parse_price_text(content) {
                         result.price = Some((price, 0.95));
+                        result.selectors.insert("price", itemprop_sel.to_string());
                         detect_currency_from_text(content, result);
                         return;
                     }
@@ -175,6 +182,7 @@ fn extract_price(document: &Html, config: &Value, result: &mut PatternResult) {
                 let text = element_text(&el);
                 if let Some(price) = parse_price_text(&text) {
                     result.price = Some((price, 0.95));
+                    result.selectors.insert("price", itemprop_sel.to_string());
                     detect_currency_from_text(&text, result);
                     return;
                 }
@@ -191,6 +199,7 @@ fn extract_price(document: &Html, config: &Value, result: &mut PatternResult) {
                         let text = element_text(&el);
                         if let Some(price) = parse_price_text(&text) {
                             result.price = Some((price, 0.85));
+                            result.selectors.insert("price", sel_str.to_string());
                             detect_currency_from_text(&text, result);
                             return;
                         }
@@ -208,6 +217,9 @@ fn extract_price(document: &Html, config: &Value, result: &mut PatternResult) {
         let matched = mat.as_str();
         if let Some(price) = parse_price_text(matched) {
             result.price = Some((price, 0.70));
+            result
+                .selectors
+                .insert("price", "text:currency-amount".to_string());
             detect_currency_from_text(matched, result);
         }
     }
@@ -239,6 +251,7 @@ fn extract_rating(document: &Html, config: &Value, result: &mut PatternResult) {
                                     let max = detect_rating_max(raw);
                                     result.rating_max = Some(max);
                                     result.rating = Some((raw / max, 0.95));
+                                    result.selectors.insert("rating", selector_str.to_string());
                                     return;
                                 }
                             }
@@ -258,6 +271,7 @@ fn extract_rating(document: &Html, config: &Value, result: &mut PatternResult) {
                         let max = detect_rating_max(raw);
                         result.rating_max = Some(max);
                         result.rating = Some((raw / max, 0.95));
+                        result.selectors.insert("rating", itemprop_sel.to_string());
                         return;
                     }
                 }
@@ -266,6 +280,7 @@ fn extract_rating(document: &Html, config: &Value, result: &mut PatternResult) {
                     let max = detect_rating_max(raw);
                     result.rating_max = Some(max);
                     result.rating = Some((raw / max, 0.95));
+                    result.selectors.insert("rating", itemprop_sel.to_string());
                     return;
                 }
             }
@@ -294,6 +309,9 @@ fn extract_rating(document: &Html, config: &Value, result: &mut PatternResult) {
                             if max > 0.0 {
                                 result.rating_max = Some(max);
                                 result.rating = Some((val / max, 0.90));
+                                result
+                                    .selectors
+                                    .insert("rating", "[aria-label]".to_string());
                                 return;
                             }
                         }
@@ -305,6 +323,9 @@ fn extract_rating(document: &Html, config: &Value, result: &mut PatternResult) {
                             let max = detect_rating_max(val);
                             result.rating_max = Some(max);
                             result.rating = Some((val / max, 0.90));
+                            result
+                                .selectors
+                                .insert("rating", "[aria-label]".to_string());
                             return;
                         }
                     }
@@ -324,6 +345,7 @@ fn extract_rating(document: &Html, config: &Value, result: &mut PatternResult) {
                             let max = detect_rating_max(raw);
                             result.rating_max = Some(max);
                             result.rating = Some((raw / max, 0.85));
+                            result.selectors.insert("rating", sel_str.to_string());
                             return;
                         }
                     }
@@ -357,6 +379,9 @@ fn extract_availability(document: &Html, config: &Value, result: &mut PatternRes
                 };
                 if let Some(avail) = classify_availability_text(&text) {
                     result.availability = Some((avail, 0.95));
+                    result
+                        .selectors
+                        .insert("availability", itemprop_sel.to_string());
                     return;
                 }
             }
@@ -390,12 +415,18 @@ fn extract_availability(document: &Html, config: &Value, result: &mut PatternRes
                 for cls in &out_of_stock_classes {
                     if class_lower.contains(cls.as_str()) {
                         result.availability = Some((0.0, 0.90));
+                        result
+                            .selectors
+                            .insert("availability", format!("[class*=\"{cls}\"]"));
                         return;
                     }
                 }
                 for cls in &in_stock_classes {
                     if class_lower.contains(cls.as_str()) {
                         result.availability = Some((1.0, 0.90));
+                        result
+                            .selectors
+                            .insert("availability", format!("[class*=\"{cls}\"]"));
                         return;
                     }
                 }
@@ -421,6 +452,9 @@ fn extract_availability(document: &Html, config: &Value, result: &mut PatternRes
     for &(phrase, value) in stock_phrases {
         if body_text.contains(phrase) {
             result.availability = Some((value, 0.80));
+            result
+                .selectors
+                .insert("availability", format!("text:\"{phrase}\""));
             return;
         }
     }
@@ -946,6 +980,7 @@ mod tests {
         let (price, confidence) = result.price.unwrap();
         assert!((price - 29.99).abs() < 0.01);
         assert!(confidence >= 0.95);
+        assert_eq!(result.selectors["price"], "[itemprop=\"price\"]");
     }
 
     #[test]
@@ -0,0 +1,357 @@
+//! Crawl identity profiles: who Cortex says it is to each domain.
+//!
+//! By default every request identifies itself honestly, with the
+//! [`DEFAULT_USER_AGENT`] `Cortex/<version>` and no other identity headers.
+//! A domain can be assigned a named [`CrawlProfile`] instead — a user agent,
+//! `Accept-Language`, client hints, extra headers and cookies — which both
+//! the HTTP client and the browser then use for every request to it, so a
+//! page fetched and a page rendered come from the same identity. Overrides
+//! are opt-in: a profile applies only to the domains explicitly assigned it.
+//!
+//! Profiles and assignments are kept in `~/.cortex/profiles.json`. An
+//! assignment to `shop.com` also covers its subdomains, unless they have
+//! one of their own.
+
+use anyhow::{bail, Context, Result};
+use reqwest::header::{HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// User agent of requests without a profile.
+pub const DEFAULT_USER_AGENT: &str = concat!(
+    "Cortex/",
+    env!("CARGO_PKG_VERSION"),
+    " (+",
+    env!("CARGO_PKG_REPOSITORY"),
+    ")"
+);
+
+/// Name of the built-in profile, which sends [`DEFAULT_USER_AGENT`] and
+/// nothing else.
+pub const DEFAULT_PROFILE: &str = "cortex";
+
+/// A named identity for crawling.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CrawlProfile {
+    /// Letters, digits, `-` and `_`.
+    pub name: String,
+    /// Sent as `User-Agent`; [`DEFAULT_USER_AGENT`] if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// Sent as `Accept-Language`, e.g. `"en-GB,en;q=0.8"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accept_language: Option<String>,
+    /// `Sec-CH-UA*` client hint headers, by name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub client_hints: BTreeMap<String, String>,
+    /// Any other headers, by name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Cookies sent to the domain, by name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub cookies: BTreeMap<String, String>,
+}
+
+impl CrawlProfile {
+    /// The built-in honest profile.
+    pub fn honest() -> Self {
+        Self {
+            name: DEFAULT_PROFILE.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// The user agent this profile sends.
+    pub fn user_agent(&self) -> &str {
+        self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT)
+    }
+
+    /// `Cookie` header value, if the profile has cookies.
+    pub fn cookie_header(&self) -> Option<String> {
+        (!self.cookies.is_empty()).then(|| {
+            self.cookies
+                .iter()
+                .map(|(name, value)| format!("{name}={value}"))
+                .collect::<Vec<_>>()
+                .join("; ")
+        })
+    }
+
+    /// Headers sent with every request, user agent and cookies included,
+    /// lowercase names.
+    pub fn request_headers(&self) -> Vec<(String, String)> {
+        let mut headers = vec![("user-agent".to_string(), self.user_agent().to_string())];
+        if let Some(language) = &self.accept_language {
+            headers.push(("accept-language".to_string(), language.clone()));
+        }
+        for (name, value) in self.client_hints.iter().chain(&self.headers) {
+            headers.push((name.to_ascii_lowercase(), value.clone()));
+        }
+        if let Some(cookies) = self.cookie_header() {
+            headers.push(("cookie".to_string(), cookies));
+        }
+        headers
+    }
+
+    /// Reject bad names, header names and values, client hints that are not
+    /// `Sec-CH-*` headers, and identity headers set under `headers`.
+    pub fn validate(&self) -> Result<()> {
+        let valid_name = !self.name.is_empty()
+            && self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_name {
+            bail!(
+                "invalid profile name '{}': use letters, digits, '-' and '_'",
+                self.name
+            );
+        }
+        for (name, value) in self.request_headers() {
+            if HeaderName::from_bytes(name.as_bytes()).is_err() {
+                bail!("invalid header name '{name}'");
+            }
+            if HeaderValue::from_str(&value).is_err() {
+                bail!("invalid value for header '{name}'");
+            }
+        }
+        if let Some(hint) = self
+            .client_hints
+            .keys()
+            .find(|h| !h.to_ascii_lowercase().starts_with("sec-ch-"))
+        {
+            bail!("'{hint}' is not a client hint (Sec-CH-*)");
+        }
+        if let Some(name) = self.headers.keys().find(|h| {
+            matches!(
+                h.to_ascii_lowercase().as_str(),
+                "user-agent" | "accept-language" | "cookie"
+            )
+        }) {
+            bail!("set '{name}' with its own profile option, not as a header");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProfilesFile {
+    #[serde(default)]
+    profiles: BTreeMap<String, CrawlProfile>,
+    /// Domain -> profile name.
+    #[serde(default)]
+    domains: BTreeMap<String, String>,
+}
+
+/// Profiles and the domains they are assigned to, in one JSON file.
+#[derive(Debug, Clone)]
+pub struct ProfileStore {
+    path: PathBuf,
+}
+
+impl Default for ProfileStore {
+    fn default() -> Self {
+        Self::new(default_path())
+    }
+}
+
+impl ProfileStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Every stored profile, by name.
+    pub fn list(&self) -> Result<Vec<CrawlProfile>> {
+        Ok(self.load()?.profiles.into_values().collect())
+    }
+
+    /// The profile named `name`; the built-in one for [`DEFAULT_PROFILE`].
+    pub fn get(&self, name: &str) -> Result<Option<CrawlProfile>> {
+        if name == DEFAULT_PROFILE {
+            return Ok(Some(CrawlProfile::honest()));
+        }
+        Ok(self.load()?.profiles.remove(name))
+    }
+
+    /// Store `profile`, replacing one of the same name. Returns whether it
+    /// replaced one.
+    pub fn put(&self, profile: CrawlProfile) -> Result<bool> {
+        profile.validate()?;
+        if profile.name == DEFAULT_PROFILE {
+            bail!("the '{DEFAULT_PROFILE}' profile is built in and cannot be changed");
+        }
+        let mut file = self.load()?;
+        let replaced = file
+            .profiles
+            .insert(profile.name.clone(), profile)
+            .is_some();
+        self.save(&file)?;
+        Ok(replaced)
+    }
+
+    /// Remove profile `name` and its assignments. Returns whether it existed.
+    pub fn remove(&self, name: &str) -> Result<bool> {
+        let mut file = self.load()?;
+        if file.profiles.remove(name).is_none() {
+            return Ok(false);
+        }
+        file.domains.retain(|_, profile| profile != name);
+        self.save(&file)?;
+        Ok(true)
+    }
+
+    /// Use profile `name` for `domain`, or go back to the default with
+    /// `None`.
+    pub fn assign(&self, domain: &str, name: Option<&str>) -> Result<()> {
+        let mut file = self.load()?;
+        match name {
+            Some(name) if name != DEFAULT_PROFILE => {
+                if !file.profiles.contains_key(name) {
+                    bail!("no profile named '{name}'");
+                }
+                file.domains
+                    .insert(domain.to_ascii_lowercase(), name.to_string());
+            }
+            _ => {
+                file.domains.remove(&domain.to_ascii_lowercase());
+            }
+        }
+        self.save(&file)
+    }
+
+    /// Domain -> profile name of every assignment.
+    pub fn assignments(&self) -> Result<BTreeMap<String, String>> {
+        Ok(self.load()?.domains)
+    }
+
+    /// The profile to crawl `domain` with: its own assignment, else its
+    /// nearest parent domain's, else the built-in one.
+    pub fn for_domain(&self, domain: &str) -> Result<CrawlProfile> {
+        let mut file = self.load()?;
+        let mut candidate = domain.to_ascii_lowercase();
+        loop {
+            if let Some(name) = file.domains.get(&candidate) {
+                return file.profiles.remove(name).with_context(|| {
+                    format!("{domain} is assigned profile '{name}', which does not exist")
+                });
+            }
+            match candidate.split_once('.') {
+                Some((_, parent)) if parent.contains('.') => candidate = parent.to_string(),
+                _ => return Ok(CrawlProfile::honest()),
+            }
+        }
+    }
+
+    fn load(&self) -> Result<ProfilesFile> {
+        if !self.path.exists() {
+            return Ok(ProfilesFile::default());
+        }
+        let data = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("failed to read {}", self.path.display()))?;
+        serde_json::from_str(&data).with_context(|| format!("invalid {}", self.path.display()))
+    }
+
+    fn save(&self, file: &ProfilesFile) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(file)?)
+            .with_context(|| format!("failed to write {}", self.path.display()))
+    }
+}
+
+/// `~/.cortex/profiles.json`.
+pub fn default_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".cortex")
+        .join("profiles.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str) -> CrawlProfile {
+        CrawlProfile {
+            name: name.to_string(),
+            user_agent: Some("Mozilla/5.0 (X11; Linux x86_64)".to_string()),
+            accept_language: Some("de-DE,de;q=0.9".to_string()),
+            client_hints: [("Sec-CH-UA-Platform".to_string(), "\"Linux\"".to_string())].into(),
+            headers: [("DNT".to_string(), "1".to_string())].into(),
+            cookies: [
+                ("consent".to_string(), "yes".to_string()),
+                ("region".to_string(), "eu".to_string()),
+            ]
+            .into(),
+        }
+    }
+
+    #[test]
+    fn test_request_headers() {
+        let honest = CrawlProfile::honest();
+        assert!(honest.user_agent().starts_with("Cortex/"));
+        assert_eq!(
+            honest.request_headers(),
+            [("user-agent".to_string(), DEFAULT_USER_AGENT.to_string())]
+        );
+
+        let headers = profile("de").request_headers();
+        let names: Vec<&str> = headers.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "user-agent",
+                "accept-language",
+                "sec-ch-ua-platform",
+                "dnt",
+                "cookie"
+            ]
+        );
+        assert_eq!(headers[4].1, "consent=yes; region=eu");
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(profile("de").validate().is_ok());
+        assert!(profile("d e").validate().is_err());
+
+        let mut bad = profile("de");
+        bad.client_hints.insert("DNT".to_string(), "1".to_string());
+        assert!(bad.validate().is_err());
+
+        let mut bad = profile("de");
+        bad.headers
+            .insert("User-Agent".to_string(), "x".to_string());
+        assert!(bad.validate().is_err());
+
+        let mut bad = profile("de");
+        bad.accept_language = Some("en\nX-Evil: 1".to_string());
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn test_store_assignments() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ProfileStore::new(dir.path().join("profiles.json"));
+        assert_eq!(
+            store.for_domain("shop.com").unwrap(),
+            CrawlProfile::honest()
+        );
+        assert!(store.put(CrawlProfile::honest()).is_err());
+        assert!(store.assign("shop.com", Some("de")).is_err());
+
+        assert!(!store.put(profile("de")).unwrap());
+        store.assign("shop.com", Some("de")).unwrap();
+        assert_eq!(store.for_domain("shop.com").unwrap().name, "de");
+        // Subdomains inherit, other domains keep the default
+        assert_eq!(store.for_domain("www.shop.com").unwrap().name, "de");
+        assert_eq!(store.for_domain("other.com").unwrap().name, DEFAULT_PROFILE);
+
+        // Removing a profile drops its assignments
+        assert!(store.remove("de").unwrap());
+        assert!(store.assignments().unwrap().is_empty());
+        assert_eq!(store.for_domain("shop.com").unwrap().name, DEFAULT_PROFILE);
+    }
+}
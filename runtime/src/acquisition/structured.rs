@@ -46,6 +46,10 @@ pub struct JsonLdProduct {
     pub description: Option<String>,
     pub brand: Option<String>,
     pub sku: Option<String>,
+    /// GTIN/UPC/EAN from `gtin13`, `gtin12`, `gtin14`, `gtin8` or `gtin`.
+    pub gtin: Option<String>,
+    /// Manufacturer part number.
+    pub mpn: Option<String>,
     pub price: Option<f64>,
     pub original_price: Option<f64>,
     pub price_currency: Option<String>,
@@ -93,6 +97,8 @@ pub struct OpenGraphData {
 /// Standard meta tags.
 #[derive(Debug, Clone, Default)]
 pub struct MetaTags {
+    /// The `<title>` element's text.
+    pub title: Option<String>,
     pub description: Option<String>,
     pub keywords: Option<String>,
     pub robots: Option<String>,
@@ -361,6 +367,15 @@ fn parse_product(v: &Value) -> JsonLdProduct {
             })
             .map(|s| s.to_string()),
         sku: v.get("sku").and_then(|s| s.as_str()).map(|s| s.to_string()),
+        gtin: ["gtin13", "gtin12", "gtin14", "gtin8", "gtin"]
+            .iter()
+            .filter_map(|k| v.get(*k))
+            .find_map(|g| {
+                g.as_str()
+                    .map(|s| s.to_string())
+                    .or_else(|| g.as_u64().map(|n| n.to_string()))
+            }),
+        mpn: v.get("mpn").and_then(|s| s.as_str()).map(|s| s.to_string()),
         price,
         original_price,
         price_currency: currency,
@@ -597,6 +612,12 @@ fn extract_microdata(document: &Html, sd: &mut StructuredData) {
 // ── Meta tag extraction ─────────────────────────────────────────────────────
 
 fn extract_meta_tags(document: &Html, sd: &mut StructuredData) {
+    if let Ok(sel) = Selector::parse("title") {
+        if let Some(el) = document.select(&sel).next() {
+            let title = el.text().collect::<String>().trim().to_string();
+            sd.meta.title = Some(title).filter(|t| !t.is_empty());
+        }
+    }
     if let Ok(sel) = Selector::parse(r#"meta[name="description"]"#) {
         if let Some(el) = document.select(&sel).next() {
             sd.meta.description = el.value().attr("content").map(|s| s.to_string());
@@ -837,6 +858,7 @@ mod tests {
     fn test_extract_meta_tags() {
         let html = r#"
         <html><head>
+        <title> Great Page | Example </title>
         <meta name="description" content="A great page" />
         <meta name="robots" content="index, follow" />
         <link rel="canonical" href="https://example.com/canonical" />
@@ -844,6 +866,7 @@ mod tests {
         "#;
 
         let sd = extract_structured_data(html, "https://example.com");
+        assert_eq!(sd.meta.title.as_deref(), Some("Great Page | Example"));
         assert_eq!(sd.meta.description.as_deref(), Some("A great page"));
         assert_eq!(sd.meta.robots.as_deref(), Some("index, follow"));
         assert_eq!(
@@ -32,7 +32,7 @@ impl LedgerSync {
 }
 
 async fn sync_loop(url: String, mut rx: mpsc::Receiver<AuditEvent>) {
-    let client = reqwest::Client::new();
+    let client = crate::acquisition::http_client::shared_client();
 
     while let Some(event) = rx.recv().await {
         // Best-effort POST, ignore failures
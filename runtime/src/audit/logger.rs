@@ -1,16 +1,20 @@
-//! JSONL audit logger — append-only log of all operations.
+//! JSONL audit logger — append-only log of all state-changing operations.
 //!
 //! Features:
 //! - Append-only JSONL format for easy parsing
 //! - Automatic log rotation when file exceeds `MAX_LOG_SIZE` (100MB)
 //! - Rotated files named `.1`, `.2`, etc. (max 5 rotations)
+//! - Each entry records the actor, redacted parameters, and a SHA-256 of
+//!   the result so a reviewer can match it against stored outputs
 
+use crate::collective::sync::hex_encode;
 use anyhow::{Context, Result};
-use chrono::Utc;
-use serde::Serialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::fs::{File, OpenOptions};
-use std::io::Write;
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
 
 /// Maximum audit log size before rotation (100 MB).
 const MAX_LOG_SIZE: u64 = 100 * 1024 * 1024;
@@ -18,19 +22,92 @@ const MAX_LOG_SIZE: u64 = 100 * 1024 * 1024;
 /// Maximum number of rotated log files to keep.
 const MAX_ROTATIONS: u32 = 5;
 
+/// Parameter keys whose values are never written to the audit log. Keys
+/// containing one of these are redacted too (`client_secret`, `Set-Cookie`).
+const REDACTED_KEYS: &[&str] = &["password", "secret", "token", "cookie", "api_key"];
+
+/// Keys redacted only on an exact match, being common inside other names.
+const REDACTED_EXACT: &[&str] = &["code", "otp"];
+
 /// A single audit event.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuditEvent {
-    pub timestamp: String,
+    pub timestamp: DateTime<Utc>,
+    /// Operation, e.g. `map`, `act`, `auth`, `registry_push`, `plug`.
     pub method: String,
+    /// Who asked: the `actor` request parameter, `agent` for protocol
+    /// requests without one, or `cli:<user>` for local commands.
+    #[serde(default)]
+    pub actor: String,
     pub domain: Option<String>,
     pub url: Option<String>,
     pub session_id: Option<String>,
+    /// Request parameters with credentials redacted.
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub params: serde_json::Value,
+    /// SHA-256 (hex) of the result.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_hash: Option<String>,
     pub duration_ms: u64,
+    /// `ok`, or `error` with the error code (`error:E_TIMEOUT`).
     pub status: String,
 }
 
+impl AuditEvent {
+    /// A successful event for `method` by `actor`, timestamped now.
+    pub fn new(method: &str, actor: &str) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            method: method.to_string(),
+            actor: actor.to_string(),
+            domain: None,
+            url: None,
+            session_id: None,
+            params: serde_json::Value::Null,
+            result_hash: None,
+            duration_ms: 0,
+            status: "ok".to_string(),
+        }
+    }
+}
+
+/// Actor name for commands run from this machine's CLI.
+pub fn local_actor() -> String {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string());
+    format!("cli:{user}")
+}
+
+/// Copy of `params` with credential values replaced by `"[redacted]"`.
+pub fn redact(params: &serde_json::Value) -> serde_json::Value {
+    match params {
+        serde_json::Value::Object(map) => map
+            .iter()
+            .map(|(k, v)| {
+                let key = k.to_lowercase();
+                if REDACTED_KEYS.iter().any(|r| key.contains(r))
+                    || REDACTED_EXACT.contains(&key.as_str())
+                {
+                    (k.clone(), serde_json::Value::from("[redacted]"))
+                } else {
+                    (k.clone(), redact(v))
+                }
+            })
+            .collect::<serde_json::Map<_, _>>()
+            .into(),
+        serde_json::Value::Array(items) => items.iter().map(redact).collect(),
+        other => other.clone(),
+    }
+}
+
+/// SHA-256 (hex) of a result's canonical JSON.
+pub fn hash_result(result: &serde_json::Value) -> String {
+    hex_encode(&Sha256::digest(result.to_string().as_bytes()))
+}
+
 /// Append-only JSONL audit logger with automatic rotation.
+#[derive(Debug)]
 pub struct AuditLogger {
     file: File,
     path: PathBuf,
@@ -62,11 +139,7 @@ impl AuditLogger {
 
     /// Open the default audit log at ~/.cortex/audit.jsonl.
     pub fn default_logger() -> Result<Self> {
-        let path = dirs::home_dir()
-            .unwrap_or_else(|| PathBuf::from("/tmp"))
-            .join(".cortex")
-            .join("audit.jsonl");
-        Self::open(&path)
+        Self::open(&default_log_path())
     }
 
     /// Log an audit event.
@@ -78,6 +151,7 @@ impl AuditLogger {
 
         let json = serde_json::to_string(event)?;
         let bytes_written = writeln!(self.file, "{json}")
+            .and_then(|()| self.file.flush())
             .map(|()| json.len() as u64 + 1)
             .unwrap_or(0);
         self.current_size += bytes_written;
@@ -95,13 +169,12 @@ impl AuditLogger {
         status: &str,
     ) -> Result<()> {
         self.log(&AuditEvent {
-            timestamp: Utc::now().to_rfc3339(),
-            method: method.to_string(),
             domain: domain.map(String::from),
             url: url.map(String::from),
             session_id: session_id.map(String::from),
             duration_ms,
             status: status.to_string(),
+            ..AuditEvent::new(method, "agent")
         })
     }
 
@@ -151,3 +224,114 @@ fn rotation_path(base: &std::path::Path, index: u32) -> PathBuf {
     );
     base.with_file_name(name)
 }
+
+/// Default audit log location (~/.cortex/audit.jsonl).
+pub fn default_log_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join(".cortex")
+        .join("audit.jsonl")
+}
+
+/// Append `event` to the default audit log. Failures are logged and
+/// otherwise ignored so auditing never blocks the operation itself.
+pub fn record(event: &AuditEvent) {
+    if let Err(e) = AuditLogger::default_logger().and_then(|mut log| log.log(event)) {
+        tracing::warn!("failed to write audit log: {e}");
+    }
+}
+
+/// Read every event from an audit log and its rotations, oldest first.
+/// Missing files yield nothing; malformed lines are skipped.
+pub fn read_log(path: &Path) -> Result<Vec<AuditEvent>> {
+    let mut events = Vec::new();
+    let files = (1..=MAX_ROTATIONS)
+        .rev()
+        .map(|i| rotation_path(path, i))
+        .chain(std::iter::once(path.to_path_buf()));
+    for file in files {
+        if !file.exists() {
+            continue;
+        }
+        let reader = BufReader::new(
+            File::open(&file)
+                .with_context(|| format!("failed to read audit log: {}", file.display()))?,
+        );
+        for line in reader.lines() {
+            let line = line?;
+            if let Ok(event) = serde_json::from_str::<AuditEvent>(&line) {
+                events.push(event);
+            }
+        }
+    }
+    Ok(events)
+}
+
+/// Filter for audit log queries.
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    /// Only events at or after this time.
+    pub since: Option<DateTime<Utc>>,
+    pub method: Option<String>,
+    pub domain: Option<String>,
+    pub actor: Option<String>,
+}
+
+impl AuditFilter {
+    /// Whether an event passes the filter.
+    pub fn matches(&self, event: &AuditEvent) -> bool {
+        self.since.is_none_or(|since| event.timestamp >= since)
+            && self.method.as_ref().is_none_or(|m| event.method == *m)
+            && self
+                .domain
+                .as_ref()
+                .is_none_or(|d| event.domain.as_ref() == Some(d))
+            && self.actor.as_ref().is_none_or(|a| event.actor == *a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_nested_credentials() {
+        let params = serde_json::json!({
+            "domain": "shop.com",
+            "method": "password",
+            "credentials": {"username": "ann", "password": "hunter2"},
+            "headers": [{"Cookie": "sid=1"}],
+        });
+        let redacted = redact(&params);
+        assert_eq!(redacted["method"], "password");
+        assert_eq!(redacted["credentials"]["username"], "ann");
+        assert_eq!(redacted["credentials"]["password"], "[redacted]");
+        assert_eq!(redacted["headers"][0]["Cookie"], "[redacted]");
+    }
+
+    #[test]
+    fn test_log_and_read_across_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let mut log = AuditLogger::open(&path).unwrap();
+        let mut first = AuditEvent::new("map", "agent");
+        first.domain = Some("shop.com".into());
+        first.result_hash = Some(hash_result(&serde_json::json!({"node_count": 3})));
+        log.log(&first).unwrap();
+        log.rotate().unwrap();
+        log.log(&AuditEvent::new("plug", "cli:ann")).unwrap();
+
+        let events = read_log(&path).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].method, "map");
+        assert_eq!(events[0].result_hash.as_ref().map(|h| h.len()), Some(64));
+        assert_eq!(events[1].actor, "cli:ann");
+
+        let filter = AuditFilter {
+            domain: Some("shop.com".into()),
+            ..Default::default()
+        };
+        let matched: Vec<_> = events.iter().filter(|e| filter.matches(e)).collect();
+        assert_eq!(matched.len(), 1);
+    }
+}
@@ -0,0 +1,162 @@
+// Copyright 2026 Cortex Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Cooperative cancellation of in-flight requests.
+//!
+//! Long requests (MAP, PERCEIVE) register a [`CancellationToken`] in the
+//! daemon's [`InFlight`] table under their request ID. A CANCEL request
+//! fires the token; the mapper, HTTP client and browser rendering check it
+//! and stop at their next await point, closing any browser contexts they
+//! hold. Work that stopped this way fails with [`Cancelled`].
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+pub use tokio_util::sync::CancellationToken;
+
+/// Error for work that stopped because its request was cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "request cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Whether `err` (or its cause) is a [`Cancelled`] error.
+pub fn is_cancelled(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| cause.is::<Cancelled>())
+}
+
+/// A cancellable request's entry in the table.
+#[derive(Debug)]
+struct Entry {
+    method: &'static str,
+    token: CancellationToken,
+    partial: Arc<AtomicBool>,
+}
+
+/// The daemon's cancellable requests, by request ID.
+#[derive(Debug, Default)]
+pub struct InFlight {
+    requests: Mutex<HashMap<String, Entry>>,
+}
+
+impl InFlight {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register request `id` of `method`. The request stays cancellable
+    /// until the returned guard is dropped.
+    pub fn register(self: &Arc<Self>, id: &str, method: &'static str) -> InFlightGuard {
+        let token = CancellationToken::new();
+        let partial = Arc::new(AtomicBool::new(false));
+        if let Ok(mut requests) = self.requests.lock() {
+            requests.insert(
+                id.to_string(),
+                Entry {
+                    method,
+                    token: token.clone(),
+                    partial: Arc::clone(&partial),
+                },
+            );
+        }
+        InFlightGuard {
+            table: Arc::clone(self),
+            id: id.to_string(),
+            token,
+            partial,
+        }
+    }
+
+    /// Cancel request `id`, asking for whatever it has finished so far when
+    /// `partial` is set. Returns the request's method, or `None` if no such
+    /// request is running.
+    pub fn cancel(&self, id: &str, partial: bool) -> Option<&'static str> {
+        let requests = self.requests.lock().ok()?;
+        let entry = requests.get(id)?;
+        entry.partial.store(partial, Ordering::Relaxed);
+        entry.token.cancel();
+        Some(entry.method)
+    }
+
+    /// IDs and methods of the running cancellable requests.
+    pub fn list(&self) -> Vec<(String, &'static str)> {
+        let Ok(requests) = self.requests.lock() else {
+            return Vec::new();
+        };
+        let mut list: Vec<_> = requests
+            .iter()
+            .map(|(id, entry)| (id.clone(), entry.method))
+            .collect();
+        list.sort();
+        list
+    }
+}
+
+/// A registered request. Dropping it removes the request from the table.
+#[derive(Debug)]
+pub struct InFlightGuard {
+    table: Arc<InFlight>,
+    id: String,
+    token: CancellationToken,
+    partial: Arc<AtomicBool>,
+}
+
+impl InFlightGuard {
+    /// The token to thread through the request's work.
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    /// Whether the request has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Whether the canceller asked for a partial result.
+    pub fn wants_partial(&self) -> bool {
+        self.partial.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if let Ok(mut requests) = self.table.requests.lock() {
+            requests.remove(&self.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_in_flight_request() {
+        let table = Arc::new(InFlight::new());
+        let guard = table.register("map-1", "map");
+        assert_eq!(table.list(), vec![("map-1".to_string(), "map")]);
+        assert!(!guard.is_cancelled());
+
+        assert_eq!(table.cancel("map-2", false), None);
+        assert_eq!(table.cancel("map-1", true), Some("map"));
+        assert!(guard.is_cancelled() && guard.wants_partial());
+
+        drop(guard);
+        assert!(table.list().is_empty());
+        assert_eq!(table.cancel("map-1", false), None);
+    }
+
+    #[test]
+    fn test_is_cancelled_error() {
+        let err = anyhow::Error::new(Cancelled).context("layer 1 fetch");
+        assert!(is_cancelled(&err));
+        assert!(!is_cancelled(&anyhow::anyhow!("timed out")));
+    }
+}
@@ -0,0 +1,330 @@
+//! Labels, corrections and notes that people and agents attach to mapped
+//! pages.
+//!
+//! Annotations are kept per domain in `~/.cortex/annotations/<domain>.json`.
+//! Node indices change from one map to the next, so annotations are filed
+//! by URL; callers name a node of the current map and it is looked up
+//! there. There are three kinds:
+//!
+//! - a label, a free-form tag (`"holiday-sale"`);
+//! - a correction, the page's real type ("this is actually a checkout
+//!   page"). Every map of the domain gives the page that type, with
+//!   [`Layer::Annotation`] lineage. It also counts the page as confirmed
+//!   when learning the site's URL templates, so unfetched pages like it
+//!   are typed the same. A page has at most one correction; a new one
+//!   replaces it;
+//! - a note, free text.
+
+use crate::map::types::{PageType, SiteMap};
+use crate::trust::lineage::{self, FieldLineage, Layer};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// An annotation on one page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    /// Unique within the domain; never reused.
+    pub id: u64,
+    pub url: String,
+    #[serde(flatten)]
+    pub body: AnnotationBody,
+    /// Who made it, if they said.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What an annotation says.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AnnotationBody {
+    Label { label: String },
+    Correction { page_type: PageType },
+    Note { text: String },
+}
+
+impl Annotation {
+    /// The annotation as the protocol and `--json` output show it: the page
+    /// type as a code and a name, and `node`, its index in the current map.
+    pub fn to_json(&self, node: Option<u32>) -> serde_json::Value {
+        let mut value = serde_json::json!({
+            "id": self.id,
+            "url": self.url,
+            "node": node,
+            "author": self.author,
+            "created_at": self.created_at.to_rfc3339(),
+        });
+        match &self.body {
+            AnnotationBody::Label { label } => {
+                value["kind"] = "label".into();
+                value["label"] = label.as_str().into();
+            }
+            AnnotationBody::Correction { page_type } => {
+                value["kind"] = "correction".into();
+                value["page_type"] = (*page_type as u8).into();
+                value["page_type_name"] = page_type.to_string().into();
+            }
+            AnnotationBody::Note { text } => {
+                value["kind"] = "note".into();
+                value["text"] = text.as_str().into();
+            }
+        }
+        value
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DomainFile {
+    #[serde(default)]
+    next_id: u64,
+    #[serde(default)]
+    annotations: Vec<Annotation>,
+}
+
+/// Annotations of every domain, one JSON file each.
+#[derive(Debug, Clone)]
+pub struct AnnotationStore {
+    dir: PathBuf,
+}
+
+impl Default for AnnotationStore {
+    fn default() -> Self {
+        Self::new(default_dir())
+    }
+}
+
+impl AnnotationStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// `domain`'s annotations, oldest first. A domain with none has no file.
+    pub fn list(&self, domain: &str) -> Result<Vec<Annotation>> {
+        Ok(self.load(domain)?.annotations)
+    }
+
+    /// Annotate `url` of `domain`. A correction replaces the page's earlier
+    /// correction, if any.
+    pub fn add(
+        &self,
+        domain: &str,
+        url: &str,
+        body: AnnotationBody,
+        author: Option<&str>,
+    ) -> Result<Annotation> {
+        let mut file = self.load(domain)?;
+        if matches!(body, AnnotationBody::Correction { .. }) {
+            file.annotations
+                .retain(|a| a.url != url || !matches!(a.body, AnnotationBody::Correction { .. }));
+        }
+        file.next_id = file
+            .next_id
+            .max(file.annotations.iter().map(|a| a.id).max().unwrap_or(0))
+            + 1;
+        let annotation = Annotation {
+            id: file.next_id,
+            url: url.to_string(),
+            body,
+            author: author.map(str::to_string),
+            created_at: Utc::now(),
+        };
+        file.annotations.push(annotation.clone());
+        self.save(domain, &file)?;
+        Ok(annotation)
+    }
+
+    /// Remove annotation `id` of `domain`, returning it if it existed.
+    pub fn remove(&self, domain: &str, id: u64) -> Result<Option<Annotation>> {
+        let mut file = self.load(domain)?;
+        let Some(index) = file.annotations.iter().position(|a| a.id == id) else {
+            return Ok(None);
+        };
+        let removed = file.annotations.remove(index);
+        self.save(domain, &file)?;
+        Ok(Some(removed))
+    }
+
+    /// The corrected page type of each corrected URL of `domain`.
+    pub fn corrections(&self, domain: &str) -> Result<HashMap<String, PageType>> {
+        Ok(self
+            .list(domain)?
+            .into_iter()
+            .filter_map(|a| match a.body {
+                AnnotationBody::Correction { page_type } => Some((a.url, page_type)),
+                _ => None,
+            })
+            .collect())
+    }
+
+    fn path(&self, domain: &str) -> Result<PathBuf> {
+        if domain.is_empty() || domain.contains(['/', '\\']) || domain.starts_with('.') {
+            bail!("invalid domain '{domain}'");
+        }
+        Ok(self.dir.join(format!("{domain}.json")))
+    }
+
+    fn load(&self, domain: &str) -> Result<DomainFile> {
+        let path = self.path(domain)?;
+        if !path.exists() {
+            return Ok(DomainFile::default());
+        }
+        let data = std::fs::read_to_string(&path)
+            .with_context(|| format!("reading annotations: {}", path.display()))?;
+        serde_json::from_str(&data).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    fn save(&self, domain: &str, file: &DomainFile) -> Result<()> {
+        let path = self.path(domain)?;
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(&path, serde_json::to_string_pretty(file)?)
+            .with_context(|| format!("writing {}", path.display()))
+    }
+}
+
+/// Give each corrected page of `map` its corrected type, with full
+/// confidence and [`Layer::Annotation`] lineage. Returns how many nodes
+/// changed.
+pub fn apply_corrections(map: &mut SiteMap, corrections: &HashMap<String, PageType>) -> usize {
+    let now = lineage::now();
+    let mut applied = 0;
+    for (url, &page_type) in corrections {
+        let Some(index) = map.find_url(url) else {
+            continue;
+        };
+        let node = &mut map.nodes[index as usize];
+        node.page_type = page_type;
+        node.confidence = 255;
+        map.lineage.record(
+            index,
+            "page_type",
+            FieldLineage::new(Layer::Annotation, None, 1.0, now),
+        );
+        applied += 1;
+    }
+    applied
+}
+
+/// A page type given as its name (`checkout`) or its code (`9`, `0x09`).
+/// Unlike query filters, anything else is an error rather than `unknown`:
+/// a correction must say what the page is.
+pub fn parse_page_type(s: &str) -> Option<PageType> {
+    let code = match s.strip_prefix("0x") {
+        Some(hex) => u8::from_str_radix(hex, 16).ok(),
+        None => s.parse::<u8>().ok(),
+    };
+    match code {
+        Some(code) if (code as usize) < PageType::COUNT => Some(PageType::from_u8(code)),
+        Some(_) => None,
+        None => PageType::from_name(&s.to_lowercase()),
+    }
+}
+
+/// The default store directory, `~/.cortex/annotations`.
+pub fn default_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".cortex")
+        .join("annotations")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::builder::SiteMapBuilder;
+    use crate::map::types::FEATURE_DIM;
+
+    #[test]
+    fn test_add_list_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = AnnotationStore::new(dir.path());
+        assert!(store.list("shop.com").unwrap().is_empty());
+
+        let url = "https://shop.com/basket/confirm";
+        let label = store
+            .add(
+                "shop.com",
+                url,
+                AnnotationBody::Label {
+                    label: "funnel".into(),
+                },
+                Some("ana"),
+            )
+            .unwrap();
+        let cart = AnnotationBody::Correction {
+            page_type: PageType::Cart,
+        };
+        store.add("shop.com", url, cart, None).unwrap();
+        let checkout = AnnotationBody::Correction {
+            page_type: PageType::Checkout,
+        };
+        let correction = store.add("shop.com", url, checkout, None).unwrap();
+        assert!(dir.path().join("shop.com.json").exists());
+
+        // The second correction replaced the first.
+        let list = store.list("shop.com").unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].author.as_deref(), Some("ana"));
+        assert_eq!(
+            store.corrections("shop.com").unwrap(),
+            HashMap::from([(url.to_string(), PageType::Checkout)])
+        );
+
+        assert_eq!(store.remove("shop.com", label.id).unwrap(), Some(label));
+        assert_eq!(store.remove("shop.com", 99).unwrap(), None);
+        // Ids are not reused.
+        let note = store
+            .add(
+                "shop.com",
+                url,
+                AnnotationBody::Note {
+                    text: "asks for a phone number".into(),
+                },
+                None,
+            )
+            .unwrap();
+        assert_eq!(note.id, correction.id + 1);
+        assert!(store.list("../etc").is_err());
+    }
+
+    #[test]
+    fn test_parse_page_type() {
+        assert_eq!(parse_page_type("checkout"), Some(PageType::Checkout));
+        assert_eq!(
+            parse_page_type("Product_Detail"),
+            Some(PageType::ProductDetail)
+        );
+        assert_eq!(parse_page_type("9"), Some(PageType::Checkout));
+        assert_eq!(parse_page_type("0x0A"), Some(PageType::Cart));
+        assert_eq!(parse_page_type("200"), None);
+        assert_eq!(parse_page_type("basket"), None);
+    }
+
+    #[test]
+    fn test_apply_corrections() {
+        let mut b = SiteMapBuilder::new("shop.com");
+        b.add_node("https://shop.com/", PageType::Home, [0.0; FEATURE_DIM], 240);
+        b.add_node(
+            "https://shop.com/basket/confirm",
+            PageType::Cart,
+            [0.0; FEATURE_DIM],
+            120,
+        );
+        let mut map = b.build();
+        let corrections = HashMap::from([
+            (
+                "https://shop.com/basket/confirm/".to_string(),
+                PageType::Checkout,
+            ),
+            ("https://shop.com/gone".to_string(), PageType::Login),
+        ]);
+        assert_eq!(apply_corrections(&mut map, &corrections), 1);
+        assert_eq!(map.nodes[1].page_type, PageType::Checkout);
+        assert_eq!(map.nodes[1].confidence, 255);
+        let lineage = &map.lineage.node(1).unwrap().fields["page_type"];
+        assert_eq!(lineage.layer, Layer::Annotation);
+        assert_eq!(map.nodes[0].page_type, PageType::Home);
+    }
+}
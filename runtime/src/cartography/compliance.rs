@@ -0,0 +1,233 @@
+//! How a map treated the site's crawling rules.
+//!
+//! Every MAP ends with a [`ComplianceReport`]: the URLs robots.txt kept the
+//! mapper from fetching, whether requests were spaced by its `Crawl-delay`,
+//! and whether the site's terms of service mention scraping or automated
+//! access. [`ComplianceReport::compliant`] sums these up for policy engines,
+//! and the mapper records the report in the audit log as a
+//! `map_compliance` event.
+
+use crate::cartography::robots::RobotsRules;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// Last path segments (without extension) of terms-of-service pages.
+const TERMS_SLUGS: [&str; 10] = [
+    "terms",
+    "tos",
+    "terms-of-service",
+    "terms-of-use",
+    "terms-and-conditions",
+    "termsofservice",
+    "termsofuse",
+    "conditions-of-use",
+    "user-agreement",
+    "legal",
+];
+
+/// The map's record of robots.txt and terms of service.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ComplianceReport {
+    /// Whether robots.txt was consulted (the request's `respect_robots`).
+    pub robots_respected: bool,
+    /// Whether the site serves a robots.txt.
+    pub robots_found: bool,
+    /// URLs not fetched because robots.txt disallows them.
+    pub robots_skipped: Vec<RobotsSkip>,
+    /// The `Crawl-delay` robots.txt asks for, in seconds.
+    pub crawl_delay_secs: Option<f32>,
+    /// Whether requests were spaced by the crawl delay; `true` when
+    /// robots.txt was consulted and asks for none.
+    pub crawl_delay_honored: bool,
+    /// The terms-of-service page checked, if the site links one.
+    pub terms: Option<TermsCheck>,
+    /// No robots.txt rule was ignored and the terms do not mention scraping.
+    pub compliant: bool,
+    /// Why the map is not compliant, one line per issue.
+    pub issues: Vec<String>,
+}
+
+/// A URL robots.txt kept the mapper from fetching.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RobotsSkip {
+    pub url: String,
+    /// The `Disallow` pattern that matched.
+    pub rule: String,
+}
+
+/// What a terms-of-service page says about automated access.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TermsCheck {
+    pub url: String,
+    /// Phrases in the page's text that refer to scraping, crawling or
+    /// automated access, lowercased and deduplicated.
+    pub mentions: Vec<String>,
+}
+
+impl TermsCheck {
+    /// Check the terms page at `url` with body `html`.
+    pub fn new(url: &str, html: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            mentions: scraping_mentions(html),
+        }
+    }
+
+    /// Whether the terms refer to scraping or automated access at all.
+    pub fn mentions_scraping(&self) -> bool {
+        !self.mentions.is_empty()
+    }
+}
+
+impl ComplianceReport {
+    /// Report on a map made with `robots_respected`, given the robots.txt
+    /// `rules` it found (if any), the URLs those rules kept it from
+    /// fetching, and the terms page it checked. A crawl delay is honored
+    /// whenever robots.txt was consulted: the mapper spaces its requests by
+    /// it then.
+    pub fn new(
+        robots_respected: bool,
+        rules: Option<&RobotsRules>,
+        robots_skipped: Vec<RobotsSkip>,
+        terms: Option<TermsCheck>,
+    ) -> Self {
+        let crawl_delay_secs = rules.and_then(|r| r.crawl_delay).filter(|d| *d > 0.0);
+        let mut issues = Vec::new();
+        if !robots_respected {
+            issues.push("robots.txt was not consulted (respect_robots is off)".to_string());
+        }
+        if let Some(terms) = terms.as_ref().filter(|t| t.mentions_scraping()) {
+            issues.push(format!(
+                "terms of service at {} mention {}",
+                terms.url,
+                terms.mentions.join(", ")
+            ));
+        }
+        Self {
+            robots_respected,
+            robots_found: rules.is_some(),
+            robots_skipped,
+            crawl_delay_secs,
+            crawl_delay_honored: robots_respected,
+            terms,
+            compliant: issues.is_empty(),
+            issues,
+        }
+    }
+}
+
+/// Whether `url` looks like a terms-of-service page.
+pub fn is_terms_url(url: &str) -> bool {
+    let Ok(parsed) = url::Url::parse(url) else {
+        return false;
+    };
+    let Some(last) = parsed
+        .path_segments()
+        .and_then(|mut s| s.rfind(|s| !s.is_empty()))
+    else {
+        return false;
+    };
+    let slug = last.split('.').next().unwrap_or(last).to_lowercase();
+    TERMS_SLUGS.contains(&slug.as_str())
+}
+
+/// Phrases in the text of `html` (markup, scripts and styles left out) that
+/// refer to scraping, crawling or automated access.
+pub fn scraping_mentions(html: &str) -> Vec<String> {
+    static HIDDEN: OnceLock<Regex> = OnceLock::new();
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    static TERMS: OnceLock<Regex> = OnceLock::new();
+    let hidden = HIDDEN.get_or_init(|| {
+        Regex::new(r"(?is)<script\b.*?</script>|<style\b.*?</style>|<!--.*?-->").unwrap()
+    });
+    let tag = TAG.get_or_init(|| Regex::new(r"<[^>]*>").unwrap());
+    let terms = TERMS.get_or_init(|| {
+        Regex::new(
+            r"(?i)\b(scrap(?:e|es|ed|ing|ers?)|crawl(?:s|ed|ing|ers?)|spiders?|robots?|data mining|automated (?:means|access|queries|systems?|tools?)|harvest(?:s|ed|ing)?)\b",
+        )
+        .unwrap()
+    });
+
+    let text = hidden.replace_all(html, " ");
+    let text = tag.replace_all(&text, " ");
+    let mut mentions: Vec<String> = terms
+        .find_iter(&text)
+        .map(|m| m.as_str().to_lowercase())
+        .collect();
+    mentions.sort();
+    mentions.dedup();
+    mentions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartography::robots::parse_robots;
+
+    #[test]
+    fn test_is_terms_url() {
+        assert!(is_terms_url("https://shop.com/terms"));
+        assert!(is_terms_url("https://shop.com/legal/Terms-Of-Service.html"));
+        assert!(is_terms_url("https://shop.com/tos/"));
+        assert!(!is_terms_url("https://shop.com/"));
+        assert!(!is_terms_url("https://shop.com/terms-of-service/returns"));
+        assert!(!is_terms_url("https://shop.com/search?q=terms"));
+        assert!(!is_terms_url("not a url"));
+    }
+
+    #[test]
+    fn test_scraping_mentions_read_text_only() {
+        let html = r#"<html><head>
+            <meta name="robots" content="index, follow">
+            <script>var crawler = true;</script>
+            <style>.spider { color: red }</style>
+            </head><body>
+            <!-- no scraping here -->
+            <p>You may not use any Robot, spider or other Automated Means to
+            access the site, nor engage in scraping or data mining.</p>
+            </body></html>"#;
+        assert_eq!(
+            scraping_mentions(html),
+            vec![
+                "automated means",
+                "data mining",
+                "robot",
+                "scraping",
+                "spider"
+            ]
+        );
+        assert!(scraping_mentions("<p>Returns are accepted within 30 days.</p>").is_empty());
+    }
+
+    #[test]
+    fn test_report_flags_issues() {
+        let rules = parse_robots("User-agent: *\nDisallow: /cart\nCrawl-delay: 2", "cortex");
+        let skipped = vec![RobotsSkip {
+            url: "https://shop.com/cart".to_string(),
+            rule: "/cart".to_string(),
+        }];
+        let clean = TermsCheck::new("https://shop.com/terms", "<p>Be nice.</p>");
+        let report = ComplianceReport::new(true, Some(&rules), skipped.clone(), Some(clean));
+        assert!(report.compliant);
+        assert!(report.robots_found);
+        assert_eq!(report.crawl_delay_secs, Some(2.0));
+        assert!(report.crawl_delay_honored);
+        assert_eq!(report.robots_skipped, skipped);
+        assert!(report.issues.is_empty());
+
+        let strict = TermsCheck::new("https://shop.com/terms", "<p>No scraping.</p>");
+        let report = ComplianceReport::new(true, Some(&rules), Vec::new(), Some(strict));
+        assert!(!report.compliant);
+        assert_eq!(
+            report.issues,
+            vec!["terms of service at https://shop.com/terms mention scraping"]
+        );
+
+        let report = ComplianceReport::new(false, None, Vec::new(), None);
+        assert!(!report.compliant);
+        assert!(!report.robots_found);
+        assert!(!report.crawl_delay_honored);
+        assert_eq!(report.issues.len(), 1);
+    }
+}
@@ -0,0 +1,303 @@
+//! Content safety and compliance filters applied while crawling.
+//!
+//! A [`ContentPolicy`] decides which URLs the mapper may fetch and which
+//! fetched pages may be stored in a map. URLs are checked against glob
+//! patterns and a denylist of terms before they are fetched; pages are
+//! checked for `noindex`, `noai` and `noimageai` directives (robots meta tag
+//! or `X-Robots-Tag` header) and for denylisted terms in their title,
+//! description, keywords and headings before they become nodes.
+//!
+//! Policies are JSON files: `~/.cortex/policies/<domain>.json`, or
+//! `~/.cortex/policies/default.json` for domains without one. A domain in a
+//! [project](crate::project) with a policy of its own uses that instead of
+//! `default.json`. Missing fields take their default values.
+
+use crate::acquisition::structured::StructuredData;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// What the mapper may fetch and store for a domain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContentPolicy {
+    /// URL glob patterns (`*` matches any run of characters) that are never
+    /// fetched or stored.
+    pub exclude_urls: Vec<String>,
+    /// Case-insensitive terms that exclude a URL containing them, or a page
+    /// whose title, description, keywords or headings contain them.
+    pub deny_terms: Vec<String>,
+    /// Skip pages marked `noindex`.
+    pub respect_noindex: bool,
+    /// Skip pages marked `noai`.
+    pub respect_noai: bool,
+    /// Skip pages marked `noimageai`.
+    pub respect_noimageai: bool,
+}
+
+impl Default for ContentPolicy {
+    fn default() -> Self {
+        Self {
+            exclude_urls: Vec::new(),
+            deny_terms: Vec::new(),
+            respect_noindex: false,
+            respect_noai: true,
+            respect_noimageai: false,
+        }
+    }
+}
+
+/// Why the policy excluded a URL or page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The URL matched an `exclude_urls` pattern.
+    UrlPattern(String),
+    /// The URL or page contained a `deny_terms` entry.
+    DenyTerm(String),
+    /// The page carried a `noindex`, `noai` or `noimageai` directive.
+    Directive(&'static str),
+    /// A robots.txt `Disallow` rule matched the URL.
+    Robots(String),
+}
+
+impl SkipReason {
+    /// Short name of the kind of rule: `url_pattern`, `deny_term`,
+    /// `directive` or `robots`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SkipReason::UrlPattern(_) => "url_pattern",
+            SkipReason::DenyTerm(_) => "deny_term",
+            SkipReason::Directive(_) => "directive",
+            SkipReason::Robots(_) => "robots",
+        }
+    }
+
+    /// The pattern, term or directive that matched.
+    pub fn rule(&self) -> &str {
+        match self {
+            SkipReason::UrlPattern(rule)
+            | SkipReason::DenyTerm(rule)
+            | SkipReason::Robots(rule) => rule,
+            SkipReason::Directive(rule) => rule,
+        }
+    }
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipReason::UrlPattern(p) => write!(f, "URL pattern '{p}'"),
+            SkipReason::DenyTerm(t) => write!(f, "denylisted term '{t}'"),
+            SkipReason::Directive(d) => write!(f, "{d} directive"),
+            SkipReason::Robots(r) => write!(f, "robots.txt rule '{r}'"),
+        }
+    }
+}
+
+impl ContentPolicy {
+    /// Load a policy from a JSON file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("reading content policy: {}", path.display()))?;
+        serde_json::from_str(&data)
+            .with_context(|| format!("parsing content policy: {}", path.display()))
+    }
+
+    /// Load the policy for `domain`: its own file, else its project's
+    /// policy, else `default.json`, else the built-in defaults.
+    pub fn load_for(domain: &str, project: Option<&Self>) -> Result<Self> {
+        let dir = policies_dir();
+        let own = dir.join(format!("{}.json", domain.replace(':', "_")));
+        let default = dir.join("default.json");
+        if own.exists() {
+            Self::load(&own)
+        } else if let Some(policy) = project {
+            Ok(policy.clone())
+        } else if default.exists() {
+            Self::load(&default)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Check a URL before it is fetched.
+    pub fn check_url(&self, url: &str) -> Option<SkipReason> {
+        if let Some(p) = self.exclude_urls.iter().find(|p| glob_match(p, url)) {
+            return Some(SkipReason::UrlPattern(p.clone()));
+        }
+        let lower = url.to_lowercase();
+        self.deny_term_in(&lower)
+    }
+
+    /// Check a fetched page before it is stored. `x_robots_tag` is the
+    /// page's `X-Robots-Tag` response header, if any.
+    pub fn check_page(
+        &self,
+        sd: &StructuredData,
+        x_robots_tag: Option<&str>,
+    ) -> Option<SkipReason> {
+        let directives: Vec<String> = [sd.meta.robots.as_deref(), x_robots_tag]
+            .into_iter()
+            .flatten()
+            .flat_map(directives)
+            .collect();
+        for (directive, enabled) in [
+            ("noindex", self.respect_noindex),
+            ("noai", self.respect_noai),
+            ("noimageai", self.respect_noimageai),
+        ] {
+            if enabled && directives.iter().any(|d| d == directive) {
+                return Some(SkipReason::Directive(directive));
+            }
+        }
+
+        if self.deny_terms.is_empty() {
+            return None;
+        }
+        let text = [
+            sd.og.title.as_deref(),
+            sd.meta.description.as_deref(),
+            sd.meta.keywords.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .chain(sd.headings.iter().map(|(_, h)| h.as_str()))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .to_lowercase();
+        self.deny_term_in(&text)
+    }
+
+    /// The first denylisted term in already-lowercased `text`.
+    fn deny_term_in(&self, text: &str) -> Option<SkipReason> {
+        self.deny_terms
+            .iter()
+            .find(|t| !t.is_empty() && text.contains(&t.to_lowercase()))
+            .map(|t| SkipReason::DenyTerm(t.clone()))
+    }
+}
+
+/// Directory holding per-domain policy files (~/.cortex/policies).
+pub fn policies_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join(".cortex")
+        .join("policies")
+}
+
+/// Lowercased directives of a robots meta or `X-Robots-Tag` value. A
+/// leading user-agent (`googlebot: noindex`) is dropped.
+fn directives(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|d| {
+            let d = d.trim();
+            d.rsplit_once(':')
+                .map_or(d, |(_, rest)| rest)
+                .trim()
+                .to_lowercase()
+        })
+        .filter(|d| !d.is_empty())
+        .collect()
+}
+
+/// Match `text` against a glob where `*` matches any run of characters.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let (p, t) = (pattern.as_bytes(), text.as_bytes());
+    let (mut pi, mut ti) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && p[pi] == b'*' {
+            backtrack = Some((pi, ti));
+            pi += 1;
+        } else if pi < p.len() && p[pi] == t[ti] {
+            pi += 1;
+            ti += 1;
+        } else if let Some((star, matched)) = backtrack {
+            pi = star + 1;
+            ti = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_url() {
+        let policy = ContentPolicy {
+            exclude_urls: vec!["*/checkout/*".into(), "https://shop.com/*?session=*".into()],
+            deny_terms: vec!["Casino".into()],
+            ..ContentPolicy::default()
+        };
+        assert_eq!(
+            policy.check_url("https://shop.com/checkout/step1"),
+            Some(SkipReason::UrlPattern("*/checkout/*".into()))
+        );
+        assert!(policy
+            .check_url("https://shop.com/p/1?session=abc")
+            .is_some());
+        assert_eq!(
+            policy
+                .check_url("https://shop.com/casino-games")
+                .map(|r| r.kind()),
+            Some("deny_term")
+        );
+        assert_eq!(policy.check_url("https://shop.com/p/1"), None);
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("*/cart", "https://shop.com/cart/x"));
+    }
+
+    #[test]
+    fn test_check_page_directives_and_terms() {
+        let policy = ContentPolicy {
+            deny_terms: vec!["gambling".into()],
+            ..ContentPolicy::default()
+        };
+        let mut sd = StructuredData::default();
+        assert_eq!(policy.check_page(&sd, None), None);
+
+        sd.meta.robots = Some("noindex, NoAI".into());
+        assert_eq!(
+            policy.check_page(&sd, None),
+            Some(SkipReason::Directive("noai"))
+        );
+
+        // noimageai is only honoured when enabled
+        sd.meta.robots = None;
+        assert_eq!(policy.check_page(&sd, Some("otherbot: noimageai")), None);
+        let strict = ContentPolicy {
+            respect_noimageai: true,
+            ..policy.clone()
+        };
+        assert_eq!(
+            strict.check_page(&sd, Some("otherbot: noimageai")),
+            Some(SkipReason::Directive("noimageai"))
+        );
+
+        sd.headings.push((1, "Online Gambling Offers".into()));
+        assert_eq!(
+            policy.check_page(&sd, None),
+            Some(SkipReason::DenyTerm("gambling".into()))
+        );
+    }
+
+    #[test]
+    fn test_policy_file_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shop.com.json");
+        std::fs::write(&path, r#"{"exclude_urls": ["*/admin*"]}"#).unwrap();
+        let policy = ContentPolicy::load(&path).unwrap();
+        assert_eq!(policy.exclude_urls, vec!["*/admin*".to_string()]);
+        assert!(policy.respect_noai);
+        assert!(!policy.respect_noindex && !policy.respect_noimageai);
+
+        std::fs::write(&path, r#"{"exclude_urls": "*/admin*"}"#).unwrap();
+        assert!(ContentPolicy::load(&path).is_err());
+    }
+}
@@ -13,6 +13,7 @@
 use crate::acquisition::http_client::HeadResponse;
 use crate::acquisition::pattern_engine::PatternResult;
 use crate::acquisition::structured::StructuredData;
+use crate::extraction::custom::CustomData;
 use crate::extraction::loader::ExtractionResult;
 use crate::map::types::*;
 use crate::renderer::NavigationResult;
@@ -21,8 +22,23 @@ use crate::renderer::NavigationResult;
 pub struct FeatureEncodeResult {
     pub features: [f32; FEATURE_DIM],
     pub flags: NodeFlags,
+    /// Values taken from custom extractors rather than the built-in ones.
+    pub custom_fields: Vec<CustomField>,
 }
 
+/// A value a custom extractor supplied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomField {
+    /// Traced field name (`price`, `rating`, …).
+    pub field: &'static str,
+    /// The custom extractor it came from.
+    pub extractor: String,
+    pub confidence: f32,
+}
+
+/// Confidence of values the built-in extractors read from a rendered page.
+const BUILTIN_CONFIDENCE: f32 = 1.0;
+
 /// Encode extraction results + navigation info into a 128-float feature vector.
 ///
 /// Returns both the feature vector and computed [`NodeFlags`] so the caller
@@ -78,6 +94,11 @@ pub fn encode_features_with_flags(
         flag_bits |= NodeFlags::HAS_PRICE;
     }
 
+    let custom_fields = apply_custom_data(&extraction.custom, &mut feats);
+    if custom_fields.iter().any(|f| f.field == "price") {
+        flag_bits |= NodeFlags::HAS_PRICE;
+    }
+
     // ── Navigation Features (64-79) ──
     encode_navigation_features(&extraction.navigation, &extraction.structure, &mut feats);
 
@@ -102,6 +123,7 @@ pub fn encode_features_with_flags(
     FeatureEncodeResult {
         features: feats,
         flags: NodeFlags(flag_bits),
+        custom_fields,
     }
 }
 
@@ -357,7 +379,118 @@ pub fn merge_features(
     result
 }
 
-fn normalize_load_time(ms: u64) -> f32 {
+/// Merge the commerce values custom extractors returned into `feats`.
+///
+/// Each extractor's output is an object that may set `price`,
+/// `original_price` (numbers or price text), `availability` (a bool, a
+/// number in `[0, 1]`, or schema.org text such as `"InStock"`), `rating`
+/// (with `rating_best`, default 5, or rating text) and `review_count`. A
+/// value fills a dimension the built-in extractors left empty, and replaces
+/// a built-in value only at full confidence. Where extractors disagree the
+/// more confident one wins. Returns the fields taken.
+pub fn apply_custom_data(
+    custom: &[CustomData],
+    feats: &mut [f32; FEATURE_DIM],
+) -> Vec<CustomField> {
+    let mut ranked: Vec<&CustomData> = custom.iter().collect();
+    ranked.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+
+    let mut taken: Vec<CustomField> = Vec::new();
+    for data in ranked {
+        let candidates = [
+            ("price", FEAT_PRICE, custom_price(&data.value, "price")),
+            (
+                "original_price",
+                FEAT_PRICE_ORIGINAL,
+                custom_price(&data.value, "original_price"),
+            ),
+            (
+                "availability",
+                FEAT_AVAILABILITY,
+                custom_availability(&data.value),
+            ),
+            ("rating", FEAT_RATING, custom_rating(&data.value)),
+            (
+                "review_count",
+                FEAT_REVIEW_COUNT_LOG,
+                data.value
+                    .get("review_count")
+                    .and_then(|v| v.as_f64())
+                    .map(|count| ((count as f32 + 1.0).ln() / 10.0).clamp(0.0, 1.0)),
+            ),
+        ];
+        for (field, dim, value) in candidates {
+            let Some(value) = value else {
+                continue;
+            };
+            if taken.iter().any(|f| f.field == field)
+                || (feats[dim] != 0.0 && data.confidence < BUILTIN_CONFIDENCE)
+            {
+                continue;
+            }
+            feats[dim] = value;
+            taken.push(CustomField {
+                field,
+                extractor: data.name.clone(),
+                confidence: data.confidence,
+            });
+        }
+    }
+
+    if taken
+        .iter()
+        .any(|f| f.field == "price" || f.field == "original_price")
+    {
+        let (price, original) = (feats[FEAT_PRICE], feats[FEAT_PRICE_ORIGINAL]);
+        if price > 0.0 && original > 0.0 {
+            feats[FEAT_DISCOUNT_PCT] = (1.0 - price / original).clamp(0.0, 1.0);
+        }
+    }
+    taken
+}
+
+fn custom_price(value: &serde_json::Value, key: &str) -> Option<f32> {
+    match value.get(key)? {
+        serde_json::Value::Number(n) => n.as_f64().map(|v| v as f32),
+        serde_json::Value::String(text) => parse_price_text(text),
+        _ => None,
+    }
+}
+
+fn custom_availability(value: &serde_json::Value) -> Option<f32> {
+    match value.get("availability")? {
+        serde_json::Value::Bool(in_stock) => Some(if *in_stock { 1.0 } else { 0.0 }),
+        serde_json::Value::Number(n) => n.as_f64().map(|v| (v as f32).clamp(0.0, 1.0)),
+        serde_json::Value::String(text) => {
+            let text = text.to_lowercase().replace([' ', '_', '-'], "");
+            Some(if text.contains("outofstock") || text.contains("soldout") {
+                0.0
+            } else if text.contains("instock") {
+                1.0
+            } else {
+                0.5
+            })
+        }
+        _ => None,
+    }
+}
+
+fn custom_rating(value: &serde_json::Value) -> Option<f32> {
+    match value.get("rating")? {
+        serde_json::Value::Number(n) => {
+            let best = value
+                .get("rating_best")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(5.0);
+            let rating = n.as_f64()?;
+            (best > 0.0).then(|| ((rating / best) as f32).clamp(0.0, 1.0))
+        }
+        serde_json::Value::String(text) => Some(text_rating_to_numeric(text)),
+        _ => None,
+    }
+}
+
+pub(crate) fn normalize_load_time(ms: u64) -> f32 {
     // Normalize: 0ms=1.0 (best), 10000ms=0.0 (worst)
     1.0 - (ms as f32 / 10_000.0).clamp(0.0, 1.0)
 }
@@ -776,6 +909,7 @@ mod tests {
                 "formCount": 0
             }),
             metadata: serde_json::json!({}),
+            custom: Vec::new(),
         };
 
         let feats = encode_features(
@@ -812,6 +946,7 @@ mod tests {
             navigation: serde_json::json!([]),
             structure: serde_json::json!({}),
             metadata: serde_json::json!({}),
+            custom: Vec::new(),
         };
 
         let result = encode_features_with_flags(
@@ -837,6 +972,7 @@ mod tests {
             navigation: serde_json::json!([]),
             structure: serde_json::json!({}),
             metadata: serde_json::json!({}),
+            custom: Vec::new(),
         };
 
         let result = encode_features_with_flags(
@@ -861,6 +997,7 @@ mod tests {
             navigation: serde_json::json!([]),
             structure: serde_json::json!({}),
             metadata: serde_json::json!({}),
+            custom: Vec::new(),
         };
 
         let result = encode_features_with_flags(
@@ -895,6 +1032,7 @@ mod tests {
             navigation: serde_json::json!([]),
             structure: serde_json::json!({}),
             metadata: serde_json::json!({}),
+            custom: Vec::new(),
         };
 
         let feats = encode_features(
@@ -919,6 +1057,7 @@ mod tests {
             navigation: serde_json::json!([]),
             structure: serde_json::json!({}),
             metadata: serde_json::json!({}),
+            custom: Vec::new(),
         };
 
         let feats = encode_features(
@@ -940,6 +1079,7 @@ mod tests {
             navigation: serde_json::json!([]),
             structure: serde_json::json!({"formFieldCount": 5}),
             metadata: serde_json::json!({}),
+            custom: Vec::new(),
         };
 
         let result = encode_features_with_flags(
@@ -961,6 +1101,7 @@ mod tests {
             navigation: serde_json::json!([]),
             structure: serde_json::json!({}),
             metadata: serde_json::json!({}),
+            custom: Vec::new(),
         };
 
         let feats = encode_features(
@@ -1003,6 +1144,7 @@ mod tests {
                     }
                 }
             }),
+            custom: Vec::new(),
         };
 
         let result = encode_features_with_flags(
@@ -1017,4 +1159,71 @@ mod tests {
         assert!(result.flags.has_price());
         assert_eq!(result.features[FEAT_AVAILABILITY], 1.0);
     }
+
+    #[test]
+    fn test_custom_data_fills_gaps_by_confidence() {
+        let custom = |name: &str, confidence: f32, value: serde_json::Value| CustomData {
+            name: name.to_string(),
+            confidence,
+            value,
+        };
+        let extraction = ExtractionResult {
+            content: serde_json::json!([{"type": "price", "value": 20.0}]),
+            custom: vec![
+                custom(
+                    "guess",
+                    0.5,
+                    serde_json::json!({"price": 1.0, "rating": 2, "review_count": 5}),
+                ),
+                custom(
+                    "prices",
+                    0.9,
+                    serde_json::json!({
+                        "price": "$18.00",
+                        "original_price": "$25.00",
+                        "availability": "OutOfStock",
+                        "rating": 8,
+                        "rating_best": 10,
+                    }),
+                ),
+            ],
+            ..Default::default()
+        };
+
+        let result = encode_features_with_flags(
+            &extraction,
+            &make_nav(),
+            "https://example.com/product",
+            PageType::ProductDetail,
+            0.9,
+        );
+
+        // The built-in price stands against a custom value below full
+        // confidence; empty dimensions take the most confident value.
+        assert_eq!(result.features[FEAT_PRICE], 20.0);
+        assert_eq!(result.features[FEAT_PRICE_ORIGINAL], 25.0);
+        assert!((result.features[FEAT_DISCOUNT_PCT] - 0.2).abs() < 0.01);
+        assert!((result.features[FEAT_RATING] - 0.8).abs() < 0.01);
+        assert!(result.features[FEAT_REVIEW_COUNT_LOG] > 0.0);
+        let source = |field: &str| {
+            result
+                .custom_fields
+                .iter()
+                .find(|f| f.field == field)
+                .map(|f| f.extractor.as_str())
+        };
+        assert_eq!(source("price"), None);
+        assert_eq!(source("rating"), Some("prices"));
+        assert_eq!(source("review_count"), Some("guess"));
+        assert_eq!(source("availability"), Some("prices"));
+
+        let mut feats = [0.0f32; FEATURE_DIM];
+        feats[FEAT_PRICE] = 20.0;
+        let taken = apply_custom_data(
+            &[custom("exact", 1.0, serde_json::json!({"price": 19.5}))],
+            &mut feats,
+        );
+        assert_eq!(feats[FEAT_PRICE], 19.5);
+        assert_eq!(taken.len(), 1);
+    }
 }
@@ -0,0 +1,406 @@
+//! Plugin feature dimensions for signals of a site's own.
+//!
+//! The built-in encoder knows prices and ratings, not "has our tracking
+//! pixel" or "uses a competitor's widget". The last dimensions of the
+//! feature vector, in the session block that maps never fill, are reserved
+//! for such signals. Each is registered by name in
+//! `~/.cortex/feature_dims.json`:
+//!
+//! ```json
+//! {"block": 8, "dims": [
+//!   {"name": "tracking_pixel", "html_contains": ["px.example.com/t.gif"]},
+//!   {"name": "rival_widget", "html_regex": "cdn\\.rival\\.com/widget"},
+//!   {"name": "chat_score"}
+//! ]}
+//! ```
+//!
+//! `block` is how many dimensions are reserved (8 by default, at most 16);
+//! the n-th registered name gets the n-th dimension of the block, so new
+//! names go at the end. While mapping, a dimension is filled by its HTML
+//! rule (1.0 when a fetched or rendered page matches), by a custom
+//! extraction script returning a field of that name, or by a
+//! [`FeaturePlugin`] given to the mapper, later sources winning. QUERY
+//! feature filters, WQL and map export accept the names in place of
+//! dimension numbers.
+
+use crate::extraction::loader::ExtractionResult;
+use crate::map::types::FEATURE_DIM;
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::warn;
+
+/// Dimensions reserved when the registry does not say.
+pub const DEFAULT_BLOCK: usize = 8;
+/// Most dimensions the block may take: the whole session block.
+pub const MAX_BLOCK: usize = 16;
+
+/// Registry file name, in `~/.cortex`.
+const FILE: &str = "feature_dims.json";
+
+/// Field names WQL rows already carry.
+const RESERVED_NAMES: [&str; 5] = ["url", "node_id", "domain", "entity_id", "entity_domains"];
+
+/// A registered plugin dimension.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DimSpec {
+    pub name: String,
+    /// Set to 1.0 when the page's HTML contains any of these strings.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub html_contains: Vec<String>,
+    /// Set to 1.0 when the page's HTML matches this regex.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html_regex: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RegistryFile {
+    #[serde(default = "default_block")]
+    block: usize,
+    #[serde(default)]
+    dims: Vec<DimSpec>,
+}
+
+fn default_block() -> usize {
+    DEFAULT_BLOCK
+}
+
+/// The registered plugin dimensions.
+#[derive(Debug, Clone)]
+pub struct PluginDims {
+    block: usize,
+    specs: Vec<DimSpec>,
+    regexes: Vec<Option<Regex>>,
+}
+
+impl Default for PluginDims {
+    fn default() -> Self {
+        Self {
+            block: DEFAULT_BLOCK,
+            specs: Vec::new(),
+            regexes: Vec::new(),
+        }
+    }
+}
+
+impl PluginDims {
+    /// A block of `block` dimensions holding `specs`, in order.
+    pub fn new(block: usize, specs: Vec<DimSpec>) -> Result<Self> {
+        if block == 0 || block > MAX_BLOCK {
+            bail!("plugin block must be 1-{MAX_BLOCK} dimensions, not {block}");
+        }
+        if specs.len() > block {
+            bail!(
+                "{} plugin dimensions registered but the block holds {block}",
+                specs.len()
+            );
+        }
+        let mut regexes = Vec::with_capacity(specs.len());
+        for (i, spec) in specs.iter().enumerate() {
+            let valid = spec
+                .name
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+                && spec
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+            if !valid {
+                bail!(
+                    "invalid plugin dimension name '{}': use lowercase letters, digits and _",
+                    spec.name
+                );
+            }
+            if RESERVED_NAMES.contains(&spec.name.as_str())
+                || specs[..i].iter().any(|s| s.name == spec.name)
+            {
+                bail!("plugin dimension name '{}' is already taken", spec.name);
+            }
+            let regex = spec
+                .html_regex
+                .as_deref()
+                .map(Regex::new)
+                .transpose()
+                .with_context(|| format!("invalid html_regex for '{}'", spec.name))?;
+            regexes.push(regex);
+        }
+        Ok(Self {
+            block,
+            specs,
+            regexes,
+        })
+    }
+
+    /// The registry at `path`; none registered if the file does not exist.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let raw =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let file: RegistryFile =
+            serde_json::from_str(&raw).with_context(|| format!("parsing {}", path.display()))?;
+        Self::new(file.block, file.dims).with_context(|| format!("in {}", path.display()))
+    }
+
+    /// The registry at [`default_path`]. A broken registry is logged and
+    /// treated as empty, so it cannot stop mapping or queries.
+    pub fn load_default() -> Self {
+        Self::load(&default_path()).unwrap_or_else(|e| {
+            warn!("ignoring plugin feature dimensions: {e:#}");
+            Self::default()
+        })
+    }
+
+    /// First dimension of the block.
+    pub fn start(&self) -> usize {
+        FEATURE_DIM - self.block
+    }
+
+    /// Dimension registered as `name`.
+    pub fn dim(&self, name: &str) -> Option<usize> {
+        self.specs
+            .iter()
+            .position(|s| s.name == name)
+            .map(|i| self.start() + i)
+    }
+
+    /// Registered names and their dimensions.
+    pub fn names(&self) -> impl Iterator<Item = (&str, usize)> + '_ {
+        let start = self.start();
+        self.specs
+            .iter()
+            .enumerate()
+            .map(move |(i, s)| (s.name.as_str(), start + i))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.specs.is_empty()
+    }
+
+    /// Dimensions whose HTML rule `html` matches.
+    fn html_signals(&self, html: &str) -> Vec<usize> {
+        let start = self.start();
+        self.specs
+            .iter()
+            .zip(&self.regexes)
+            .enumerate()
+            .filter(|(_, (spec, regex))| {
+                spec.html_contains.iter().any(|s| html.contains(s.as_str()))
+                    || regex.as_ref().is_some_and(|r| r.is_match(html))
+            })
+            .map(|(i, _)| start + i)
+            .collect()
+    }
+}
+
+/// Default registry path (~/.cortex/feature_dims.json).
+pub fn default_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".cortex")
+        .join(FILE)
+}
+
+/// What a plugin sees of a page.
+#[derive(Debug, Clone, Copy)]
+pub struct PluginPage<'a> {
+    pub url: &'a str,
+    /// The fetched body, or the rendered DOM.
+    pub html: Option<&'a str>,
+    /// The extraction result, for rendered pages.
+    pub extraction: Option<&'a ExtractionResult>,
+}
+
+/// A source of plugin dimension values, registered with the mapper.
+pub trait FeaturePlugin: Send + Sync {
+    /// Values for registered dimension names. Names not registered are
+    /// ignored.
+    fn evaluate(&self, page: &PluginPage<'_>) -> Vec<(String, f32)>;
+}
+
+/// The registry and the Rust plugins that fill it.
+#[derive(Clone, Default)]
+pub struct FeaturePlugins {
+    dims: PluginDims,
+    plugins: Vec<Arc<dyn FeaturePlugin>>,
+}
+
+impl FeaturePlugins {
+    pub fn new(dims: PluginDims) -> Self {
+        Self {
+            dims,
+            plugins: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, plugin: Arc<dyn FeaturePlugin>) {
+        self.plugins.push(plugin);
+    }
+
+    pub fn dims(&self) -> &PluginDims {
+        &self.dims
+    }
+
+    /// Whether there is anything to evaluate.
+    pub fn is_active(&self) -> bool {
+        !self.dims.is_empty()
+    }
+
+    /// The plugin dimensions of `page` as `(dimension, value)`: HTML rules,
+    /// then custom extractor fields, then Rust plugins, later values
+    /// replacing earlier ones.
+    pub fn evaluate(&self, page: &PluginPage<'_>) -> Vec<(usize, f32)> {
+        if !self.is_active() {
+            return Vec::new();
+        }
+        let mut values: Vec<(usize, f32)> = Vec::new();
+        let mut set = |dim: usize, value: f32| match values.iter_mut().find(|(d, _)| *d == dim) {
+            Some(slot) => slot.1 = value,
+            None => values.push((dim, value)),
+        };
+
+        if let Some(html) = page.html {
+            for dim in self.dims.html_signals(html) {
+                set(dim, 1.0);
+            }
+        }
+        for data in page.extraction.iter().flat_map(|e| &e.custom) {
+            for (name, dim) in self.dims.names() {
+                let value = match data.value.get(name) {
+                    Some(serde_json::Value::Bool(b)) => Some(if *b { 1.0 } else { 0.0 }),
+                    Some(v) => v.as_f64().map(|v| v as f32),
+                    None => None,
+                };
+                if let Some(value) = value.filter(|v| v.is_finite()) {
+                    set(dim, value);
+                }
+            }
+        }
+        for plugin in &self.plugins {
+            for (name, value) in plugin.evaluate(page) {
+                match self.dims.dim(&name) {
+                    Some(dim) if value.is_finite() => set(dim, value),
+                    _ => {}
+                }
+            }
+        }
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extraction::custom::CustomData;
+
+    fn spec(name: &str) -> DimSpec {
+        DimSpec {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_registry_assigns_block_dims_and_validates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(FILE);
+        assert!(PluginDims::load(&path).unwrap().is_empty());
+
+        std::fs::write(
+            &path,
+            r#"{"block": 4, "dims": [
+                {"name": "tracking_pixel", "html_contains": ["px.example.com"]},
+                {"name": "rival_widget", "html_regex": "cdn\\.rival\\.com/w"}
+            ]}"#,
+        )
+        .unwrap();
+        let dims = PluginDims::load(&path).unwrap();
+        assert_eq!(dims.start(), FEATURE_DIM - 4);
+        assert_eq!(dims.dim("tracking_pixel"), Some(124));
+        assert_eq!(dims.dim("rival_widget"), Some(125));
+        assert_eq!(dims.dim("price"), None);
+
+        assert!(PluginDims::new(17, vec![]).is_err());
+        assert!(PluginDims::new(1, vec![spec("a"), spec("b")]).is_err());
+        assert!(PluginDims::new(4, vec![spec("a"), spec("a")]).is_err());
+        assert!(PluginDims::new(4, vec![spec("url")]).is_err());
+        assert!(PluginDims::new(4, vec![spec("Has Pixel")]).is_err());
+        let bad_regex = DimSpec {
+            html_regex: Some("(".into()),
+            ..spec("a")
+        };
+        assert!(PluginDims::new(4, vec![bad_regex]).is_err());
+    }
+
+    struct Chat;
+
+    impl FeaturePlugin for Chat {
+        fn evaluate(&self, page: &PluginPage<'_>) -> Vec<(String, f32)> {
+            let score = if page.url.contains("/support") {
+                0.7
+            } else {
+                0.0
+            };
+            vec![("chat_score".into(), score), ("unregistered".into(), 1.0)]
+        }
+    }
+
+    #[test]
+    fn test_sources_fill_registered_dims() {
+        let dims = PluginDims::new(
+            8,
+            vec![
+                DimSpec {
+                    html_contains: vec!["px.example.com".into()],
+                    ..spec("tracking_pixel")
+                },
+                DimSpec {
+                    html_regex: Some(r"cdn\.rival\.com/w".into()),
+                    ..spec("rival_widget")
+                },
+                spec("chat_score"),
+            ],
+        )
+        .unwrap();
+        let mut plugins = FeaturePlugins::new(dims);
+        plugins.push(Arc::new(Chat));
+
+        let html = r#"<img src="https://px.example.com/t.gif"><script src="//cdn.rival.com/w.js">"#;
+        let mut values = plugins.evaluate(&PluginPage {
+            url: "https://shop.com/support",
+            html: Some(html),
+            extraction: None,
+        });
+        values.sort_by_key(|(d, _)| *d);
+        assert_eq!(values, vec![(120, 1.0), (121, 1.0), (122, 0.7)]);
+
+        // A custom extractor's field overrides the HTML rule.
+        let extraction = ExtractionResult {
+            custom: vec![CustomData {
+                name: "signals".into(),
+                confidence: 0.8,
+                value: serde_json::json!({"tracking_pixel": false}),
+            }],
+            ..Default::default()
+        };
+        let values = plugins.evaluate(&PluginPage {
+            url: "https://shop.com/",
+            html: Some(html),
+            extraction: Some(&extraction),
+        });
+        assert!(values.contains(&(120, 0.0)));
+        assert!(values.contains(&(122, 0.0)));
+
+        assert!(FeaturePlugins::default()
+            .evaluate(&PluginPage {
+                url: "https://shop.com/",
+                html: Some(html),
+                extraction: None,
+            })
+            .is_empty());
+    }
+}
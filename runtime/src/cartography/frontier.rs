@@ -0,0 +1,190 @@
+//! Persistent crawl frontier, so an interrupted MAP can resume.
+//!
+//! While it maps, the mapper checkpoints the domain's frontier to
+//! `~/.cortex/frontier/<domain>.json.zst`: every URL discovered so far, the
+//! Layer 1 candidates still pending with the sampler's score for each, and
+//! the pages already fetched, bodies included. A MAP with `resume: true`
+//! starts from the checkpoint: it skips Layer 0, parses the stored pages
+//! again instead of fetching them, and samples only what is still pending.
+//!
+//! The checkpoint is removed once a map has sampled everything it meant to.
+//! A map that hits its deadline, is cancelled, or dies with the daemon
+//! leaves it behind for `cortex map <domain> --resume`.
+
+use crate::acquisition::http_client::HttpResponse;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// zstd level for checkpoints: they are rewritten after every batch, so
+/// speed matters more than size.
+const LEVEL: i32 = 3;
+
+/// A Layer 1 candidate not yet fetched.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingUrl {
+    pub url: String,
+    /// How likely the page is to reveal something new, in `[0, 1]`
+    /// (see [`SmartSampler::pending`](crate::intelligence::smart_sampler::SmartSampler::pending)).
+    pub score: f32,
+}
+
+/// Where a MAP of one domain had got to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Frontier {
+    pub domain: String,
+    pub updated_at: DateTime<Utc>,
+    /// Every URL Layer 0 discovered, in discovery order.
+    pub discovered: Vec<String>,
+    /// URLs the content policy excluded.
+    pub excluded: Vec<String>,
+    /// Layer 1 candidates not yet fetched, highest score first.
+    pub pending: Vec<PendingUrl>,
+    /// URLs Layer 1 has requested, whether or not the fetch succeeded.
+    pub fetched: Vec<String>,
+    /// The successful responses among them, in fetch order.
+    pub pages: Vec<HttpResponse>,
+}
+
+impl Frontier {
+    /// An empty frontier for `domain`.
+    pub fn new(domain: &str) -> Self {
+        Self {
+            domain: domain.to_string(),
+            updated_at: Utc::now(),
+            discovered: Vec::new(),
+            excluded: Vec::new(),
+            pending: Vec::new(),
+            fetched: Vec::new(),
+            pages: Vec::new(),
+        }
+    }
+
+    /// The checkpoint for `domain` in `dir`, if there is one.
+    pub fn load(dir: &Path, domain: &str) -> Result<Option<Self>> {
+        let path = path(dir, domain);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let raw = std::fs::read(&path).with_context(|| format!("reading {}", path.display()))?;
+        let json = zstd::decode_all(raw.as_slice())
+            .with_context(|| format!("decompressing {}", path.display()))?;
+        let frontier =
+            serde_json::from_slice(&json).with_context(|| format!("parsing {}", path.display()))?;
+        Ok(Some(frontier))
+    }
+
+    /// Write the checkpoint to `dir`, replacing any earlier one.
+    pub fn save(&mut self, dir: &Path) -> Result<()> {
+        self.updated_at = Utc::now();
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("creating frontier directory {}", dir.display()))?;
+        let data = zstd::bulk::compress(&serde_json::to_vec(self)?, LEVEL)?;
+        let path = path(dir, &self.domain);
+        // Write-then-rename so a crash mid-write keeps the last checkpoint.
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, data).with_context(|| format!("writing {}", tmp.display()))?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// Whether `dir` holds a checkpoint for `domain`.
+    pub fn exists(dir: &Path, domain: &str) -> bool {
+        path(dir, domain).exists()
+    }
+
+    /// Delete the checkpoint for `domain`. Returns true if there was one.
+    pub fn remove(dir: &Path, domain: &str) -> bool {
+        std::fs::remove_file(path(dir, domain)).is_ok()
+    }
+
+    /// Record a Layer 1 batch: every URL requested, and the successful
+    /// responses.
+    pub fn record_batch(&mut self, batch: &[String], responses: &[HttpResponse]) {
+        self.fetched.extend(batch.iter().cloned());
+        self.pages
+            .extend(responses.iter().filter(|resp| resp.status == 200).cloned());
+    }
+
+    /// All Layer 1 candidates: those fetched, then those pending.
+    pub fn candidates(&self) -> Vec<String> {
+        self.fetched
+            .iter()
+            .cloned()
+            .chain(self.pending.iter().map(|p| p.url.clone()))
+            .collect()
+    }
+}
+
+/// Default checkpoint directory (~/.cortex/frontier).
+pub fn default_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".cortex")
+        .join("frontier")
+}
+
+fn path(dir: &Path, domain: &str) -> PathBuf {
+    dir.join(format!(
+        "{}.json.zst",
+        domain.replace([':', '/', '\\'], "_")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(url: &str, status: u16) -> HttpResponse {
+        HttpResponse {
+            url: url.to_string(),
+            final_url: url.to_string(),
+            status,
+            headers: vec![("content-type".to_string(), "text/html".to_string())],
+            body: format!("<html><title>{url}</title></html>"),
+            mime: "text/html".to_string(),
+            truncated: false,
+            blocked: None,
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(Frontier::load(dir.path(), "shop.com").unwrap().is_none());
+
+        let mut frontier = Frontier::new("shop.com");
+        frontier.discovered = vec!["https://shop.com/".into(), "https://shop.com/a".into()];
+        frontier.pending = vec![PendingUrl {
+            url: "https://shop.com/b".into(),
+            score: 0.5,
+        }];
+        frontier.record_batch(
+            &["https://shop.com/".into(), "https://shop.com/a".into()],
+            &[
+                page("https://shop.com/", 200),
+                page("https://shop.com/a", 404),
+            ],
+        );
+        frontier.save(dir.path()).unwrap();
+
+        let loaded = Frontier::load(dir.path(), "shop.com").unwrap().unwrap();
+        assert_eq!(loaded.fetched.len(), 2);
+        assert_eq!(loaded.pages.len(), 1, "only successful pages are kept");
+        assert_eq!(loaded.pages[0].body, frontier.pages[0].body);
+        assert_eq!(
+            loaded.candidates(),
+            vec![
+                "https://shop.com/",
+                "https://shop.com/a",
+                "https://shop.com/b"
+            ]
+        );
+
+        assert!(Frontier::exists(dir.path(), "shop.com"));
+        assert!(Frontier::remove(dir.path(), "shop.com"));
+        assert!(Frontier::load(dir.path(), "shop.com").unwrap().is_none());
+        assert!(!Frontier::remove(dir.path(), "shop.com"));
+    }
+}
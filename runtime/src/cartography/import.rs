@@ -0,0 +1,540 @@
+//! Import maps from third-party crawl exports.
+//!
+//! Supported inputs:
+//! - Screaming Frog "Internal" CSV exports, optionally with an "All Outlinks"
+//!   CSV for the link graph.
+//! - WARC archives (plain or gzip-compressed). Archived HTML responses go
+//!   through the same structured-data extraction as a live Layer 1 fetch.
+//!
+//! Pages are classified with [`url_classifier`] unless structured data on an
+//! archived body says otherwise.
+
+use crate::acquisition::http_client::HeadResponse;
+use crate::acquisition::structured::{self, StructuredData};
+use crate::cartography::{feature_encoder, url_classifier};
+use crate::map::builder::SiteMapBuilder;
+use crate::map::types::*;
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+/// Supported import formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// Screaming Frog "Internal" CSV export.
+    ScreamingFrog,
+    /// WARC archive, optionally gzip-compressed.
+    Warc,
+}
+
+impl ImportFormat {
+    /// Parse a format name (`screaming-frog`/`sf`/`csv`, `warc`).
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "screaming-frog" | "screamingfrog" | "sf" | "csv" => Ok(Self::ScreamingFrog),
+            "warc" => Ok(Self::Warc),
+            other => bail!("unknown import format: {other} (expected screaming-frog or warc)"),
+        }
+    }
+
+    /// Guess the format from a file name.
+    pub fn detect(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_ascii_lowercase();
+        if name.ends_with(".csv") {
+            Some(Self::ScreamingFrog)
+        } else if name.ends_with(".warc") || name.ends_with(".warc.gz") {
+            Some(Self::Warc)
+        } else {
+            None
+        }
+    }
+}
+
+/// Counters from an import run.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportStats {
+    /// Rows or records read.
+    pub records: usize,
+    /// Pages added to the map.
+    pub pages: usize,
+    /// Links added to the map.
+    pub links: usize,
+    /// Records skipped (other hosts, non-HTML, duplicates, malformed).
+    pub skipped: usize,
+}
+
+/// A page collected from an export before the map is assembled.
+struct ImportedPage {
+    url: String,
+    page_type: PageType,
+    confidence: f32,
+    features: [f32; FEATURE_DIM],
+    http_status: u16,
+    depth: u16,
+    links: Vec<String>,
+}
+
+/// Import a Screaming Frog "Internal" CSV export. `outlinks` is an optional
+/// "All Outlinks" export providing the link graph. Only pages on `domain`
+/// are kept; if `None`, the host of the first page is used.
+pub fn import_screaming_frog(
+    internal: impl Read,
+    outlinks: Option<impl Read>,
+    domain: Option<&str>,
+) -> Result<(SiteMap, ImportStats)> {
+    let mut stats = ImportStats::default();
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(internal);
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|h| h.trim() == name);
+    let address = column("Address").context("CSV has no Address column")?;
+    let content_type = column("Content Type");
+    let status = column("Status Code");
+    let word_count = column("Word Count");
+    let crawl_depth = column("Crawl Depth");
+    let response_time = column("Response Time");
+
+    let mut pages = Vec::new();
+    for row in reader.records() {
+        stats.records += 1;
+        let Ok(row) = row else {
+            stats.skipped += 1;
+            continue;
+        };
+        let field = |idx: Option<usize>| idx.and_then(|i| row.get(i)).map(str::trim);
+        let url = field(Some(address)).unwrap_or_default().to_string();
+        let ctype = field(content_type).unwrap_or("text/html");
+        if url.is_empty() || !ctype.contains("html") {
+            stats.skipped += 1;
+            continue;
+        }
+
+        let http_status = field(status).and_then(|s| s.parse().ok()).unwrap_or(0);
+        let head = HeadResponse {
+            url: url.clone(),
+            status: http_status,
+            content_type: Some(ctype.to_string()),
+            content_language: None,
+            last_modified: None,
+            cache_control: None,
+        };
+        let mut features = feature_encoder::encode_features_from_structured_data(
+            &StructuredData::default(),
+            &url,
+            &head,
+        );
+        let (page_type, confidence) = url_classifier::classify_url(&url, "");
+        features[FEAT_PAGE_TYPE] = (page_type as u8) as f32 / 31.0;
+        features[FEAT_PAGE_TYPE_CONFIDENCE] = confidence;
+        if let Some(words) = field(word_count).and_then(|s| s.parse::<f32>().ok()) {
+            // Roughly six characters per word, on the encoder's character scale.
+            features[FEAT_TEXT_LENGTH_LOG] = ((words * 6.0 + 1.0).ln() / 12.0).clamp(0.0, 1.0);
+        }
+        if let Some(secs) = field(response_time).and_then(|s| s.parse::<f64>().ok()) {
+            features[FEAT_LOAD_TIME] =
+                feature_encoder::normalize_load_time((secs * 1000.0).max(0.0) as u64);
+        }
+        let depth = field(crawl_depth).and_then(|s| s.parse().ok()).unwrap_or(0);
+
+        pages.push(ImportedPage {
+            url,
+            page_type,
+            confidence,
+            features,
+            http_status,
+            depth,
+            links: Vec::new(),
+        });
+    }
+
+    if let Some(outlinks) = outlinks {
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(outlinks);
+        let headers = reader.headers()?.clone();
+        let column = |name: &str| headers.iter().position(|h| h.trim() == name);
+        let source = column("Source").context("outlinks CSV has no Source column")?;
+        let dest = column("Destination").context("outlinks CSV has no Destination column")?;
+        let kind = column("Type");
+
+        let index: HashMap<String, usize> = pages
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (p.url.clone(), i))
+            .collect();
+        for row in reader.records().flatten() {
+            // Only hyperlinks; skip images, CSS, JS and other resources.
+            if kind
+                .and_then(|k| row.get(k))
+                .is_some_and(|k| !k.trim().eq_ignore_ascii_case("hyperlink"))
+            {
+                continue;
+            }
+            if let (Some(src), Some(dst)) = (row.get(source), row.get(dest)) {
+                if let Some(&i) = index.get(src.trim()) {
+                    pages[i].links.push(dst.trim().to_string());
+                }
+            }
+        }
+    }
+
+    assemble(pages, domain, &mut stats)
+}
+
+/// Import HTML responses from a WARC archive (plain or gzip-compressed).
+/// Only pages on `domain` are kept; if `None`, the host of the first page is used.
+pub fn import_warc(input: impl Read, domain: Option<&str>) -> Result<(SiteMap, ImportStats)> {
+    let mut stats = ImportStats::default();
+    let mut buffered = BufReader::new(input);
+    let gzipped = buffered.fill_buf()?.starts_with(&[0x1f, 0x8b]);
+    let reader: Box<dyn BufRead> = if gzipped {
+        Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(buffered)))
+    } else {
+        Box::new(buffered)
+    };
+
+    let mut pages = Vec::new();
+    for record in (WarcRecords { reader }) {
+        let record = record?;
+        stats.records += 1;
+        if record.header("WARC-Type") != Some("response") {
+            stats.skipped += 1;
+            continue;
+        }
+        let Some(url) = record.header("WARC-Target-URI").map(str::to_string) else {
+            stats.skipped += 1;
+            continue;
+        };
+        let Some(response) = parse_http_response(&record.body) else {
+            stats.skipped += 1;
+            continue;
+        };
+        if !response
+            .content_type
+            .as_deref()
+            .is_some_and(|c| c.contains("html"))
+        {
+            stats.skipped += 1;
+            continue;
+        }
+
+        let html = String::from_utf8_lossy(response.body);
+        let sd = structured::extract_structured_data(&html, &url);
+        let head = HeadResponse {
+            url: url.clone(),
+            status: response.status,
+            content_type: response.content_type.clone(),
+            content_language: response.content_language.clone(),
+            last_modified: response.last_modified.clone(),
+            cache_control: None,
+        };
+        let mut features = feature_encoder::encode_features_from_structured_data(&sd, &url, &head);
+        let (page_type, confidence) = sd
+            .page_type
+            .unwrap_or_else(|| url_classifier::classify_url(&url, ""));
+        features[FEAT_PAGE_TYPE] = (page_type as u8) as f32 / 31.0;
+        features[FEAT_PAGE_TYPE_CONFIDENCE] = confidence;
+        let links = sd
+            .links
+            .iter()
+            .filter(|l| l.is_internal)
+            .map(|l| l.href.clone())
+            .collect();
+
+        pages.push(ImportedPage {
+            url,
+            page_type,
+            confidence,
+            features,
+            http_status: response.status,
+            depth: 0,
+            links,
+        });
+    }
+
+    assemble(pages, domain, &mut stats)
+}
+
+/// Build the map: keep pages on one host, dedupe by URL, link known pages.
+fn assemble(
+    pages: Vec<ImportedPage>,
+    domain: Option<&str>,
+    stats: &mut ImportStats,
+) -> Result<(SiteMap, ImportStats)> {
+    let domain = match domain {
+        Some(d) => d.to_string(),
+        None => pages
+            .iter()
+            .find_map(|p| host_of(&p.url))
+            .context("no HTML pages found to import")?,
+    };
+
+    let mut builder = SiteMapBuilder::new(&domain);
+    let mut index: HashMap<String, u32> = HashMap::new();
+    let mut kept: Vec<&ImportedPage> = Vec::new();
+    let mut records: Vec<(u16, u16)> = Vec::new();
+    for page in &pages {
+        let url = normalize(&page.url);
+        if host_of(&url).as_deref() != Some(domain.as_str()) || index.contains_key(&url) {
+            stats.skipped += 1;
+            continue;
+        }
+        let idx = builder.add_node(
+            &url,
+            page.page_type,
+            page.features,
+            (page.confidence * 255.0) as u8,
+        );
+        index.insert(url, idx);
+        kept.push(page);
+        records.push((page.http_status, page.depth));
+    }
+
+    for (from, page) in kept.iter().enumerate() {
+        let mut seen = std::collections::HashSet::new();
+        for link in &page.links {
+            if let Some(&to) = index.get(&normalize(link)) {
+                if to != from as u32 && seen.insert(to) {
+                    builder.add_edge(
+                        from as u32,
+                        to,
+                        EdgeType::ContentLink,
+                        1,
+                        EdgeFlags::default(),
+                    );
+                    stats.links += 1;
+                }
+            }
+        }
+    }
+
+    let mut map = builder.build();
+    for (node, (status, depth)) in map.nodes.iter_mut().zip(records) {
+        node.http_status = status;
+        node.depth = depth;
+    }
+    stats.pages = map.nodes.len();
+    Ok((map, std::mem::take(stats)))
+}
+
+/// Lower-cased host of a URL, without a leading `www.`.
+fn host_of(url: &str) -> Option<String> {
+    let host = url::Url::parse(url).ok()?.host_str()?.to_ascii_lowercase();
+    Some(host.strip_prefix("www.").unwrap_or(&host).to_string())
+}
+
+/// Drop fragments so `page#top` and `page` are one node; unify `www.`.
+fn normalize(url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(mut u) => {
+            u.set_fragment(None);
+            if let Some(host) = u.host_str().and_then(|h| h.strip_prefix("www.")) {
+                let host = host.to_string();
+                let _ = u.set_host(Some(&host));
+            }
+            u.to_string()
+        }
+        Err(_) => url.to_string(),
+    }
+}
+
+/// One WARC record: header fields and the raw block.
+struct WarcRecord {
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl WarcRecord {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Iterator over the records of a WARC stream.
+struct WarcRecords<R: BufRead> {
+    reader: R,
+}
+
+impl<R: BufRead> Iterator for WarcRecords<R> {
+    type Item = Result<WarcRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Skip blank lines between records, then expect the version line.
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => return None,
+                Ok(_) if line.trim().is_empty() => continue,
+                Ok(_) => break,
+                Err(e) => return Some(Err(e.into())),
+            }
+        }
+        if !line.starts_with("WARC/") {
+            return Some(Err(anyhow::anyhow!(
+                "malformed WARC: expected version line, got {:?}",
+                line.trim()
+            )));
+        }
+
+        let mut headers = Vec::new();
+        loop {
+            line.clear();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => break,
+                Ok(_) if line.trim().is_empty() => break,
+                Ok(_) => {
+                    if let Some((k, v)) = line.split_once(':') {
+                        headers.push((k.trim().to_string(), v.trim().to_string()));
+                    }
+                }
+                Err(e) => return Some(Err(e.into())),
+            }
+        }
+
+        let record = WarcRecord {
+            headers,
+            body: Vec::new(),
+        };
+        let Some(len) = record
+            .header("Content-Length")
+            .and_then(|l| l.parse::<u64>().ok())
+        else {
+            return Some(Err(anyhow::anyhow!(
+                "malformed WARC: record without Content-Length"
+            )));
+        };
+        let mut body = Vec::with_capacity(len as usize);
+        if let Err(e) = (&mut self.reader).take(len).read_to_end(&mut body) {
+            return Some(Err(e.into()));
+        }
+        Some(Ok(WarcRecord { body, ..record }))
+    }
+}
+
+/// A parsed HTTP response from a WARC response block.
+struct HttpResponse<'a> {
+    status: u16,
+    content_type: Option<String>,
+    content_language: Option<String>,
+    last_modified: Option<String>,
+    body: &'a [u8],
+}
+
+fn parse_http_response(block: &[u8]) -> Option<HttpResponse<'_>> {
+    let split = block.windows(4).position(|w| w == b"\r\n\r\n")?;
+    let head = std::str::from_utf8(&block[..split]).ok()?;
+    let mut lines = head.lines();
+    let status = lines.next()?.split_whitespace().nth(1)?.parse().ok()?;
+    let mut response = HttpResponse {
+        status,
+        content_type: None,
+        content_language: None,
+        last_modified: None,
+        body: &block[split + 4..],
+    };
+    for line in lines {
+        if let Some((k, v)) = line.split_once(':') {
+            let v = Some(v.trim().to_string());
+            match k.trim().to_ascii_lowercase().as_str() {
+                "content-type" => response.content_type = v,
+                "content-language" => response.content_language = v,
+                "last-modified" => response.last_modified = v,
+                _ => {}
+            }
+        }
+    }
+    Some(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERNAL: &str = "\
+\"Address\",\"Content Type\",\"Status Code\",\"Word Count\",\"Crawl Depth\",\"Response Time\"
+\"https://shop.com/\",\"text/html; charset=UTF-8\",\"200\",\"350\",\"0\",\"0.25\"
+\"https://shop.com/products/widget\",\"text/html\",\"200\",\"900\",\"1\",\"0.5\"
+\"https://shop.com/style.css\",\"text/css\",\"200\",\"0\",\"1\",\"0.1\"
+\"https://other.com/\",\"text/html\",\"200\",\"10\",\"1\",\"0.1\"
+";
+
+    const OUTLINKS: &str = "\
+Type,Source,Destination
+Hyperlink,https://shop.com/,https://shop.com/products/widget
+Image,https://shop.com/,https://shop.com/logo.png
+Hyperlink,https://shop.com/products/widget,https://shop.com/#top
+";
+
+    #[test]
+    fn test_import_screaming_frog() {
+        let (map, stats) =
+            import_screaming_frog(INTERNAL.as_bytes(), Some(OUTLINKS.as_bytes()), None).unwrap();
+        assert_eq!(map.header.domain, "shop.com");
+        assert_eq!(
+            map.urls,
+            vec!["https://shop.com/", "https://shop.com/products/widget"]
+        );
+        assert_eq!(map.nodes[1].page_type, PageType::ProductDetail);
+        assert_eq!(map.nodes[1].depth, 1);
+        assert_eq!(map.nodes[1].http_status, 200);
+        assert!(map.features[1][FEAT_TEXT_LENGTH_LOG] > map.features[0][FEAT_TEXT_LENGTH_LOG]);
+        assert_eq!(stats.records, 4);
+        assert_eq!(stats.skipped, 2);
+        // Both hyperlinks kept (the fragment resolves to the home page).
+        assert_eq!(stats.links, 2);
+        assert_eq!(map.edges.len(), 2);
+    }
+
+    fn warc_record(url: &str, content_type: &str, body: &str) -> String {
+        let http = format!("HTTP/1.1 200 OK\r\nContent-Type: {content_type}\r\n\r\n{body}");
+        format!(
+            "WARC/1.0\r\nWARC-Type: response\r\nWARC-Target-URI: {url}\r\nContent-Length: {}\r\n\r\n{http}\r\n\r\n",
+            http.len()
+        )
+    }
+
+    fn sample_warc() -> String {
+        let product = r#"<html><head><script type="application/ld+json">
+{"@context":"https://schema.org","@type":"Product","name":"Widget",
+ "offers":{"@type":"Offer","price":"19.99","priceCurrency":"USD"}}
+</script></head><body><a href="https://shop.com/">Home</a></body></html>"#;
+        let home = r#"<html><body><a href="/item-42">Widget</a></body></html>"#;
+        format!(
+            "WARC/1.0\r\nWARC-Type: warcinfo\r\nContent-Length: 0\r\n\r\n\r\n\r\n{}{}{}",
+            warc_record("https://shop.com/", "text/html", home),
+            warc_record("https://shop.com/item-42", "text/html", product),
+            warc_record("https://shop.com/logo.png", "image/png", "PNG"),
+        )
+    }
+
+    #[test]
+    fn test_import_warc_uses_structured_data() {
+        let (map, stats) = import_warc(sample_warc().as_bytes(), None).unwrap();
+        assert_eq!(map.urls.len(), 2);
+        assert_eq!(map.nodes[1].page_type, PageType::ProductDetail);
+        assert!((map.features[1][FEAT_PRICE] - 19.99).abs() < 0.01);
+        assert_eq!(stats.skipped, 2);
+        assert_eq!(map.edges.len(), 2);
+    }
+
+    #[test]
+    fn test_import_gzipped_warc() {
+        use std::io::Write;
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(sample_warc().as_bytes()).unwrap();
+        let bytes = gz.finish().unwrap();
+        let (map, _) = import_warc(bytes.as_slice(), Some("shop.com")).unwrap();
+        assert_eq!(map.urls.len(), 2);
+        assert_eq!(
+            ImportFormat::detect(Path::new("crawl.warc.gz")),
+            Some(ImportFormat::Warc)
+        );
+    }
+}
@@ -12,25 +12,57 @@
 //!
 //! The browser is a last-resort fallback. For most e-commerce and news sites,
 //! Layers 1-2.5 provide sufficient data.
+//!
+//! Layers 0 and 1 checkpoint their progress as a [`Frontier`], so a map that
+//! runs out of time or is interrupted can be resumed.
+//! The HTML of every page fetched or rendered is kept as a snapshot (see
+//! [`snapshots`](crate::cartography::snapshots)) once the map is built.
 
 use crate::acquisition::action_discovery::{self, HttpAction};
+use crate::acquisition::block_detect::{self, BlockReport};
 use crate::acquisition::http_client::HttpClient;
+use crate::acquisition::http_client::{HeadResponse, HttpResponse};
 use crate::acquisition::pattern_engine::{self, PatternResult};
+use crate::acquisition::profiles::{CrawlProfile, ProfileStore};
 use crate::acquisition::structured::{self, StructuredData};
-use crate::acquisition::{api_discovery, feed_parser, head_scanner};
+use crate::acquisition::{api_discovery, feed_parser, head_scanner, parse_pool};
+use crate::audit::logger::{AuditEvent, AuditLogger};
+use crate::cancel::{CancellationToken, Cancelled};
+use crate::cartography::annotations::{self, AnnotationStore};
+use crate::cartography::compliance::{self, ComplianceReport, RobotsSkip, TermsCheck};
+use crate::cartography::content_policy::{ContentPolicy, SkipReason};
+use crate::cartography::feature_plugins::{FeaturePlugin, FeaturePlugins, PluginDims, PluginPage};
+use crate::cartography::frontier::{self, Frontier, PendingUrl};
+use crate::cartography::rate_limiter::RateLimiter;
+use crate::cartography::semantic::SemanticEncoder;
+use crate::cartography::snapshots::{SnapshotSource, SnapshotStore};
+use crate::cartography::soft_404::{self, Soft404Detector};
+use crate::cartography::url_classifier::UrlTypeModel;
+use crate::cartography::vision_classifier::{self, VisionClassifier};
 use crate::cartography::{
     action_encoder, feature_encoder, page_classifier, robots, sitemap, url_classifier,
 };
+use crate::collective::workers::WorkerPool;
 use crate::extraction::loader::ExtractionLoader;
+use crate::intelligence::smart_sampler::{self, SamplerConfig, SmartSampler};
 use crate::map::builder::SiteMapBuilder;
+use crate::map::embeddings::MapEmbeddings;
+use crate::map::text::{MapText, NodeText};
 use crate::map::types::*;
 use crate::progress::{self, MappingLayer, ProgressEventKind, ProgressSender};
-use crate::renderer::Renderer;
+use crate::renderer::{RenderContext, Renderer};
+use crate::trust::conflicts::{self, Candidate};
+use crate::trust::lineage::{self, FieldLineage, Layer, TRACED_FIELDS};
 use anyhow::{Context, Result};
-use std::collections::HashMap;
-use std::sync::Arc;
-use std::time::Instant;
-use tracing::{info, warn};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// Longest one sampled page may take to parse before it is left out.
+const PAGE_PARSE_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Request to map a website.
 #[derive(Debug, Clone)]
@@ -43,12 +75,36 @@ pub struct MapRequest {
     /// Optional progress event sender for real-time telemetry.
     /// When `None`, no events are emitted (zero cost).
     pub progress_tx: Option<ProgressSender>,
+    /// Which URLs and pages may be fetched and stored.
+    pub policy: ContentPolicy,
+    /// Where to record URLs and pages the policy excluded, if anywhere.
+    pub audit: Option<Arc<Mutex<AuditLogger>>>,
+    /// Stops the mapping early. Once it fires no more pages are fetched or
+    /// rendered, and the map is built from the pages already fetched.
+    pub cancel: CancellationToken,
+    /// Continue from the domain's saved [`Frontier`] instead of starting
+    /// over, if it has one.
+    pub resume: bool,
 }
 
 /// The Mapper orchestrates the entire site mapping process.
 pub struct Mapper {
     renderer: Arc<dyn Renderer>,
     extractor_loader: Arc<ExtractionLoader>,
+    /// Remote workers that share page fetching and rendering, if any.
+    workers: Option<Arc<WorkerPool>>,
+    /// Screenshot classifier for pages the text classifier is unsure of.
+    vision: Option<Arc<VisionClassifier>>,
+    /// Where page HTML is kept after mapping, if anywhere.
+    snapshots: Option<SnapshotStore>,
+    /// What fills the plugin feature dimensions.
+    plugins: FeaturePlugins,
+    /// Where page type corrections are read from, if anywhere.
+    annotations: Option<AnnotationStore>,
+    /// Sentence model that embeds page text for semantic search, if any.
+    semantic: Option<Arc<SemanticEncoder>>,
+    /// Where domains' crawl profiles are read from, if anywhere.
+    profiles: Option<ProfileStore>,
 }
 
 impl Mapper {
@@ -56,9 +112,85 @@ impl Mapper {
         Self {
             renderer,
             extractor_loader,
+            workers: None,
+            vision: None,
+            snapshots: Some(SnapshotStore::from_env()).filter(SnapshotStore::enabled),
+            plugins: FeaturePlugins::new(PluginDims::load_default()),
+            annotations: Some(AnnotationStore::default()),
+            semantic: None,
+            profiles: Some(ProfileStore::default()),
         }
     }
 
+    /// The extraction scripts injected into rendered pages.
+    pub fn extractors(&self) -> &ExtractionLoader {
+        &self.extractor_loader
+    }
+
+    /// Share Layer 1 fetches and Layer 3 renders with the workers in `pool`.
+    pub fn with_workers(mut self, pool: Arc<WorkerPool>) -> Self {
+        self.workers = Some(pool);
+        self
+    }
+
+    /// Classify rendered pages the text classifier is unsure of from
+    /// screenshots.
+    pub fn with_vision(mut self, classifier: Arc<VisionClassifier>) -> Self {
+        self.vision = Some(classifier);
+        self
+    }
+
+    /// Embed the title and description of mapped pages with `encoder`, for
+    /// semantic QUERY.
+    pub fn with_semantic(mut self, encoder: Arc<SemanticEncoder>) -> Self {
+        self.semantic = Some(encoder);
+        self
+    }
+
+    /// Keep the HTML of mapped pages in `store`, or nowhere with `None`
+    /// (by default it is [`SnapshotStore::from_env`], if enabled).
+    pub fn with_snapshots(mut self, store: Option<SnapshotStore>) -> Self {
+        self.snapshots = store;
+        self
+    }
+
+    /// Read page type corrections from `store`, or nowhere with `None` (by
+    /// default `~/.cortex/annotations`).
+    pub fn with_annotations(mut self, store: Option<AnnotationStore>) -> Self {
+        self.annotations = store;
+        self
+    }
+
+    /// Read domains' crawl profiles from `store`, or nowhere with `None`,
+    /// crawling every domain as plain Cortex (by default
+    /// `~/.cortex/profiles.json`).
+    pub fn with_profiles(mut self, store: Option<ProfileStore>) -> Self {
+        self.profiles = store;
+        self
+    }
+
+    /// The identity to crawl `domain` with.
+    pub fn profile(&self, domain: &str) -> Result<CrawlProfile> {
+        match &self.profiles {
+            Some(store) => store.for_domain(domain),
+            None => Ok(CrawlProfile::honest()),
+        }
+    }
+
+    /// Fill plugin feature dimensions from `dims`, the registry (by default
+    /// `~/.cortex/feature_dims.json`).
+    pub fn with_plugin_dims(mut self, dims: PluginDims) -> Self {
+        self.plugins = FeaturePlugins::new(dims);
+        self
+    }
+
+    /// Let `plugin` fill registered plugin feature dimensions of every
+    /// mapped page.
+    pub fn with_feature_plugin(mut self, plugin: Arc<dyn FeaturePlugin>) -> Self {
+        self.plugins.push(plugin);
+        self
+    }
+
     /// Map an entire site using the layered acquisition approach. Returns a complete SiteMap.
     pub async fn map(&self, request: MapRequest) -> Result<SiteMap> {
         let start = Instant::now();
@@ -73,7 +205,13 @@ impl Mapper {
         let req_id = format!("map-{}", std::process::id());
         let mut seq: u64 = 0;
 
-        let http_client = HttpClient::new(request.timeout_ms);
+        let profile = self.profile(&request.domain)?;
+        if profile.name != crate::acquisition::profiles::DEFAULT_PROFILE {
+            info!("crawling {} as profile '{}'", request.domain, profile.name);
+        }
+        let http_client = HttpClient::new(request.timeout_ms)
+            .with_cancel(request.cancel.clone())
+            .with_profile(profile.clone());
 
         // Time budgets
         let total_budget = std::time::Duration::from_millis(request.timeout_ms);
@@ -82,286 +220,400 @@ impl Mapper {
         let layer1_deadline =
             std::time::Duration::from_millis((request.timeout_ms as f64 * 0.70) as u64);
 
-        // ── Layer 0: Metadata (sitemap + robots + HEAD scan + feeds) ──
-
-        progress::emit(
-            ptx,
-            &req_id,
-            &mut seq,
-            ProgressEventKind::LayerStarted {
-                layer: MappingLayer::L0Metadata,
-                message: "Scanning robots.txt, sitemap.xml, homepage links".to_string(),
-            },
-        );
+        // A resumed map starts from its checkpoint instead of Layer 0.
+        let frontier_dir = frontier::default_dir();
+        let resumed = if request.resume {
+            match Frontier::load(&frontier_dir, &request.domain) {
+                Ok(Some(frontier)) => Some(frontier),
+                Ok(None) => {
+                    info!(
+                        "no frontier saved for {}, mapping from scratch",
+                        request.domain
+                    );
+                    None
+                }
+                Err(e) => {
+                    warn!("failed to load frontier for {}: {e:#}", request.domain);
+                    None
+                }
+            }
+        } else {
+            None
+        };
 
-        // 0a. Fetch robots.txt
+        // robots.txt decides what may be fetched, resumed or not, and how
+        // often: with a crawl delay, requests go one at a time, spaced by it.
         let robots_rules = self
             .fetch_robots(&request.domain, request.respect_robots, &http_client)
             .await;
-
-        // 0b. Fetch sitemap URLs
-        let sitemap_entries = tokio::time::timeout(
-            layer0_budget.saturating_sub(start.elapsed()),
-            self.fetch_sitemap_urls(&request.domain, &robots_rules, &http_client),
-        )
-        .await
-        .unwrap_or_else(|_| {
-            warn!(
-                "sitemap fetch timed out after {:.1}s",
-                start.elapsed().as_secs_f64()
-            );
-            Vec::new()
-        });
-
-        // Collect all discovered URLs
-        let mut all_urls: Vec<String> = sitemap_entries.iter().map(|e| e.url.clone()).collect();
-        if !all_urls.contains(&entry_url) {
-            all_urls.insert(0, entry_url.clone());
-        }
-
-        // 0c. Fetch homepage HTML to discover more URLs + feeds
-        let homepage_html = match http_client.get(&entry_url, 10000).await {
-            Ok(resp) if resp.status == 200 => {
-                let body = resp.body;
-                let body_for_parse = body.clone();
-                let eu = entry_url.clone();
-                let links = tokio::task::spawn_blocking(move || {
-                    structured::extract_links_from_html(&body_for_parse, &eu)
-                })
-                .await
-                .unwrap_or_default();
-                for link in &links {
-                    if !all_urls.contains(link) {
-                        all_urls.push(link.clone());
-                    }
-                }
+        let crawl_limiter = robots_rules
+            .as_ref()
+            .and_then(|rules| rules.crawl_delay)
+            .filter(|delay| *delay > 0.0)
+            .map(|delay| RateLimiter::from_crawl_delay(Some(delay), 1));
+
+        let (mut all_urls, mut excluded, html_urls, mut frontier) = match resumed {
+            Some(frontier) => {
                 info!(
-                    "homepage HTTP fetch found {} links for {}",
-                    links.len(),
-                    request.domain
+                    "resuming {}: {} URLs discovered, {} pages fetched, {} pending",
+                    request.domain,
+                    frontier.discovered.len(),
+                    frontier.fetched.len(),
+                    frontier.pending.len()
                 );
-                Some(body)
+                progress::emit(
+                    ptx,
+                    &req_id,
+                    &mut seq,
+                    ProgressEventKind::LayerSkipped {
+                        layer: MappingLayer::L0Metadata,
+                        reason: format!("Resumed from {} saved URLs", frontier.discovered.len()),
+                    },
+                );
+                let excluded: HashSet<String> = frontier.excluded.iter().cloned().collect();
+                (
+                    frontier.discovered.clone(),
+                    excluded,
+                    frontier.candidates(),
+                    frontier,
+                )
             }
-            _ => None,
-        };
+            None => {
+                // ── Layer 0: Metadata (sitemap + robots + HEAD scan + feeds) ──
 
-        // 0d. Extract URLs from embedded JS state + <link> tags
-        if let Some(ref html) = homepage_html {
-            let domain_for_js = request.domain.clone();
-            let html_for_js = html.clone();
-            let js_urls = tokio::task::spawn_blocking(move || {
-                extract_urls_from_page_source(&html_for_js, &domain_for_js)
-            })
-            .await
-            .unwrap_or_default();
-            for url in &js_urls {
-                if !all_urls.contains(url) {
-                    all_urls.push(url.clone());
-                }
-            }
-            if !js_urls.is_empty() {
-                info!(
-                    "JS state + link tags discovered {} URLs for {}",
-                    js_urls.len(),
-                    request.domain
+                progress::emit(
+                    ptx,
+                    &req_id,
+                    &mut seq,
+                    ProgressEventKind::LayerStarted {
+                        layer: MappingLayer::L0Metadata,
+                        message: "Scanning robots.txt, sitemap.xml, homepage links".to_string(),
+                    },
                 );
-            }
-        }
 
-        // 0e. Feed discovery (non-blocking, time-bounded)
-        if let Some(ref html) = homepage_html {
-            if start.elapsed() < layer0_budget {
-                let feed_entries =
-                    feed_parser::discover_feeds(html, &request.domain, &http_client).await;
-                for entry in &feed_entries {
-                    if !all_urls.contains(&entry.url) {
-                        all_urls.push(entry.url.clone());
-                    }
-                }
-                if !feed_entries.is_empty() {
-                    info!("feeds discovered {} URLs", feed_entries.len());
+                // 0b. Fetch sitemap URLs (robots.txt, 0a, is fetched above)
+                let sitemap_entries = tokio::time::timeout(
+                    layer0_budget.saturating_sub(start.elapsed()),
+                    self.fetch_sitemap_urls(&request.domain, &robots_rules, &http_client),
+                )
+                .await
+                .unwrap_or_else(|_| {
+                    warn!(
+                        "sitemap fetch timed out after {:.1}s",
+                        start.elapsed().as_secs_f64()
+                    );
+                    Vec::new()
+                });
+
+                // Collect all discovered URLs
+                let mut all_urls: Vec<String> =
+                    sitemap_entries.iter().map(|e| e.url.clone()).collect();
+                if !all_urls.contains(&entry_url) {
+                    all_urls.insert(0, entry_url.clone());
                 }
-            }
-        }
 
-        // 0e2. Browser homepage fallback for client-rendered sites
-        if all_urls.len() < 10 && start.elapsed() < total_budget / 2 {
-            match self.render_page(&entry_url).await {
-                Ok(rendered) => {
-                    for link in &rendered.discovered_links {
-                        if !all_urls.contains(link) {
-                            all_urls.push(link.clone());
+                // 0c. Fetch homepage HTML to discover more URLs + feeds
+                let homepage_allowed = robots_skip(robots_rules.as_ref(), &entry_url).is_none();
+                let homepage = if homepage_allowed {
+                    http_client.get(&entry_url, 10000).await.ok()
+                } else {
+                    None
+                };
+                let homepage_html = match homepage {
+                    Some(resp) if resp.status == 200 && resp.is_html() => {
+                        let body = resp.body;
+                        let body_for_parse = body.clone();
+                        let eu = entry_url.clone();
+                        let links = parse_pool::run(move || {
+                            structured::extract_links_from_html(&body_for_parse, &eu)
+                        })
+                        .await
+                        .unwrap_or_default();
+                        for link in &links {
+                            if !all_urls.contains(link) {
+                                all_urls.push(link.clone());
+                            }
                         }
+                        info!(
+                            "homepage HTTP fetch found {} links for {}",
+                            links.len(),
+                            request.domain
+                        );
+                        Some(body)
                     }
-                    if !rendered.discovered_links.is_empty() {
+                    _ => None,
+                };
+
+                // 0d. Extract URLs from embedded JS state + <link> tags
+                if let Some(ref html) = homepage_html {
+                    let domain_for_js = request.domain.clone();
+                    let html_for_js = html.clone();
+                    let js_urls = parse_pool::run(move || {
+                        extract_urls_from_page_source(&html_for_js, &domain_for_js)
+                    })
+                    .await
+                    .unwrap_or_default();
+                    for url in &js_urls {
+                        if !all_urls.contains(url) {
+                            all_urls.push(url.clone());
+                        }
+                    }
+                    if !js_urls.is_empty() {
                         info!(
-                            "browser homepage rendered {} links for {}",
-                            rendered.discovered_links.len(),
+                            "JS state + link tags discovered {} URLs for {}",
+                            js_urls.len(),
                             request.domain
                         );
                     }
                 }
-                Err(e) => {
-                    warn!(
-                        "browser homepage fallback failed for {}: {e}",
-                        request.domain
-                    );
+
+                // 0e. Feed discovery (non-blocking, time-bounded)
+                if let Some(ref html) = homepage_html {
+                    if start.elapsed() < layer0_budget {
+                        let feed_entries =
+                            feed_parser::discover_feeds(html, &request.domain, &http_client).await;
+                        for entry in &feed_entries {
+                            if !all_urls.contains(&entry.url) {
+                                all_urls.push(entry.url.clone());
+                            }
+                        }
+                        if !feed_entries.is_empty() {
+                            info!("feeds discovered {} URLs", feed_entries.len());
+                        }
+                    }
                 }
-            }
-        }
 
-        // 0f. If still very few URLs, try common paths as heuristic fallback
-        if all_urls.len() < 10 {
-            let common_paths = [
-                // General
-                "/about",
-                "/help",
-                "/contact",
-                "/faq",
-                "/terms",
-                "/privacy",
-                "/search",
-                "/sitemap",
-                "/login",
-                "/register",
-                "/account",
-                // E-commerce
-                "/products",
-                "/categories",
-                "/shop",
-                "/deals",
-                "/cart",
-                "/computers",
-                "/laptops",
-                "/phones",
-                "/tv",
-                "/audio",
-                "/appliances",
-                "/cameras",
-                "/tablets",
-                "/accessories",
-                "/sale",
-                "/new-arrivals",
-                "/best-sellers",
-                "/brands",
-                // News/media
-                "/news",
-                "/blog",
-                "/articles",
-                "/world",
-                "/politics",
-                "/business",
-                "/technology",
-                "/sports",
-                "/entertainment",
-                "/opinion",
-                "/science",
-                "/health",
-                "/lifestyle",
-                "/national",
-                "/tech",
-                "/culture",
-                // Docs/reference
-                "/docs",
-                "/guide",
-                "/api",
-                "/reference",
-                "/tutorials",
-                "/getting-started",
-                "/learn",
-                "/documentation",
-                // Community
-                "/community",
-                "/forum",
-                "/discussions",
-                "/popular",
-                "/trending",
-                "/explore",
-                "/discover",
-            ];
-            for path in &common_paths {
-                let url = format!("https://{}{}", request.domain, path);
-                if !all_urls.contains(&url) {
-                    all_urls.push(url);
+                // 0e2. Browser homepage fallback for client-rendered sites
+                if homepage_allowed && all_urls.len() < 10 && start.elapsed() < total_budget / 2 {
+                    match self
+                        .render_page(&entry_url, &profile, &request.cancel)
+                        .await
+                    {
+                        Ok(rendered) => {
+                            for link in &rendered.discovered_links {
+                                if !all_urls.contains(link) {
+                                    all_urls.push(link.clone());
+                                }
+                            }
+                            if !rendered.discovered_links.is_empty() {
+                                info!(
+                                    "browser homepage rendered {} links for {}",
+                                    rendered.discovered_links.len(),
+                                    request.domain
+                                );
+                            }
+                        }
+                        Err(e) => {
+                            warn!(
+                                "browser homepage fallback failed for {}: {e}",
+                                request.domain
+                            );
+                        }
+                    }
+                }
+
+                // 0f. If still very few URLs, try common paths as heuristic fallback
+                if all_urls.len() < 10 {
+                    let common_paths = [
+                        // General
+                        "/about",
+                        "/help",
+                        "/contact",
+                        "/faq",
+                        "/terms",
+                        "/privacy",
+                        "/search",
+                        "/sitemap",
+                        "/login",
+                        "/register",
+                        "/account",
+                        // E-commerce
+                        "/products",
+                        "/categories",
+                        "/shop",
+                        "/deals",
+                        "/cart",
+                        "/computers",
+                        "/laptops",
+                        "/phones",
+                        "/tv",
+                        "/audio",
+                        "/appliances",
+                        "/cameras",
+                        "/tablets",
+                        "/accessories",
+                        "/sale",
+                        "/new-arrivals",
+                        "/best-sellers",
+                        "/brands",
+                        // News/media
+                        "/news",
+                        "/blog",
+                        "/articles",
+                        "/world",
+                        "/politics",
+                        "/business",
+                        "/technology",
+                        "/sports",
+                        "/entertainment",
+                        "/opinion",
+                        "/science",
+                        "/health",
+                        "/lifestyle",
+                        "/national",
+                        "/tech",
+                        "/culture",
+                        // Docs/reference
+                        "/docs",
+                        "/guide",
+                        "/api",
+                        "/reference",
+                        "/tutorials",
+                        "/getting-started",
+                        "/learn",
+                        "/documentation",
+                        // Community
+                        "/community",
+                        "/forum",
+                        "/discussions",
+                        "/popular",
+                        "/trending",
+                        "/explore",
+                        "/discover",
+                    ];
+                    for path in &common_paths {
+                        let url = format!("https://{}{}", request.domain, path);
+                        if !all_urls.contains(&url) {
+                            all_urls.push(url);
+                        }
+                    }
+                    // Also try www. prefix homepage
+                    let www_url = format!("https://www.{}", request.domain);
+                    if !all_urls.contains(&www_url) {
+                        all_urls.insert(1, www_url);
+                    }
+                    info!(
+                        "few URLs discovered, added common paths (now {} total)",
+                        all_urls.len()
+                    );
                 }
-            }
-            // Also try www. prefix homepage
-            let www_url = format!("https://www.{}", request.domain);
-            if !all_urls.contains(&www_url) {
-                all_urls.insert(1, www_url);
-            }
-            info!(
-                "few URLs discovered, added common paths (now {} total)",
-                all_urls.len()
-            );
-        }
 
-        // Limit to max_nodes
-        let effective_max = (request.max_nodes as usize).min(5000);
-        all_urls.truncate(effective_max);
+                // Drop URLs robots.txt or the content policy excludes before
+                // anything is fetched
+                let mut excluded: HashSet<String> = HashSet::new();
+                all_urls.retain(|url| {
+                    match robots_skip(robots_rules.as_ref(), url)
+                        .or_else(|| request.policy.check_url(url))
+                    {
+                        Some(reason) => {
+                            record_skip(&request, url, &reason);
+                            excluded.insert(url.clone());
+                            false
+                        }
+                        None => true,
+                    }
+                });
 
-        if all_urls.is_empty() {
-            return Err(anyhow::anyhow!("no URLs discovered for {}", request.domain));
-        }
+                // Limit to max_nodes
+                let effective_max = (request.max_nodes as usize).min(5000);
+                all_urls.truncate(effective_max);
 
-        info!(
-            "Layer 0 complete: {} URLs discovered in {:.1}s",
-            all_urls.len(),
-            start.elapsed().as_secs_f64()
-        );
+                if all_urls.is_empty() {
+                    if request.cancel.is_cancelled() {
+                        return Err(Cancelled.into());
+                    }
+                    return Err(anyhow::anyhow!("no URLs discovered for {}", request.domain));
+                }
 
-        progress::emit(
-            ptx,
-            &req_id,
-            &mut seq,
-            ProgressEventKind::LayerCompleted {
-                layer: MappingLayer::L0Metadata,
-                message: format!("{} URLs discovered", all_urls.len()),
-                duration_ms: start.elapsed().as_millis() as u64,
-            },
-        );
-        progress::emit(
-            ptx,
-            &req_id,
-            &mut seq,
-            ProgressEventKind::MappingProgress {
-                urls_discovered: all_urls.len() as u32,
-                pages_fetched: 0,
-                nodes_built: 0,
-                edges_built: 0,
-                active_requests: 0,
-                elapsed_ms: start.elapsed().as_millis() as u64,
-                prices_found: 0,
-                ratings_found: 0,
-                actions_found: 0,
-            },
-        );
+                info!(
+                    "Layer 0 complete: {} URLs discovered in {:.1}s",
+                    all_urls.len(),
+                    start.elapsed().as_secs_f64()
+                );
 
-        // 0e. HEAD scan to filter HTML pages
-        let html_urls = if all_urls.len() > 50 {
-            // Only HEAD scan a sample for large sites
-            let sample: Vec<String> = all_urls.iter().take(200).cloned().collect();
-            let head_results = head_scanner::scan_heads(&sample, &http_client).await;
-            let html_only = head_scanner::filter_html_urls(&head_results);
-            if html_only.is_empty() {
-                all_urls.iter().take(50).cloned().collect()
-            } else {
-                html_only
+                progress::emit(
+                    ptx,
+                    &req_id,
+                    &mut seq,
+                    ProgressEventKind::LayerCompleted {
+                        layer: MappingLayer::L0Metadata,
+                        message: format!("{} URLs discovered", all_urls.len()),
+                        duration_ms: start.elapsed().as_millis() as u64,
+                    },
+                );
+                progress::emit(
+                    ptx,
+                    &req_id,
+                    &mut seq,
+                    ProgressEventKind::MappingProgress {
+                        urls_discovered: all_urls.len() as u32,
+                        pages_fetched: 0,
+                        nodes_built: 0,
+                        edges_built: 0,
+                        active_requests: 0,
+                        elapsed_ms: start.elapsed().as_millis() as u64,
+                        prices_found: 0,
+                        ratings_found: 0,
+                        actions_found: 0,
+                    },
+                );
+
+                // 0e. HEAD scan to filter HTML pages
+                let html_urls = if all_urls.len() > 50 && crawl_limiter.is_none() {
+                    // Only HEAD scan a sample for large sites; with a crawl
+                    // delay, sampling skips non-HTML responses instead
+                    let sample: Vec<String> = all_urls.iter().take(200).cloned().collect();
+                    let head_results = head_scanner::scan_heads(&sample, &http_client).await;
+                    let html_only = head_scanner::filter_html_urls(&head_results);
+                    if html_only.is_empty() {
+                        all_urls.iter().take(50).cloned().collect()
+                    } else {
+                        html_only
+                    }
+                } else {
+                    all_urls.clone()
+                };
+
+                let mut frontier = Frontier::new(&request.domain);
+                frontier.discovered = all_urls.clone();
+                frontier.excluded = excluded.iter().cloned().collect();
+                frontier.pending = html_urls
+                    .iter()
+                    .map(|url| PendingUrl {
+                        url: url.clone(),
+                        score: 1.0,
+                    })
+                    .collect();
+                if let Err(e) = frontier.save(&frontier_dir) {
+                    warn!("failed to save frontier for {}: {e:#}", request.domain);
+                }
+                (all_urls, excluded, html_urls, frontier)
             }
-        } else {
-            all_urls.clone()
         };
 
         // ── Layer 1: HTTP GET + Structured Data Extraction ──
 
-        // Select sample pages for GET (cap at max_render or 30)
-        let sample_count = (request.max_render as usize).min(30).min(html_urls.len());
-        let sample_urls: Vec<String> =
-            select_diverse_samples(&html_urls, &all_urls, &request.domain, sample_count);
+        // Sample pages by template until new pages stop revealing new page
+        // types or fields (see `intelligence::smart_sampler`), within the
+        // render budget and the Layer 1 deadline.
+        let mut sampler = SmartSampler::new(
+            &html_urls,
+            &request.domain,
+            SamplerConfig {
+                max_total: request.max_render as usize,
+                // One page per round with a crawl delay, so sampling stops
+                // at the deadline rather than a batch of delays after it.
+                batch_size: match crawl_limiter {
+                    Some(_) => 1,
+                    None => SamplerConfig::default().batch_size,
+                },
+                ..SamplerConfig::default()
+            },
+        );
 
         info!(
-            "Layer 1: fetching {} sample pages via HTTP",
-            sample_urls.len()
+            "Layer 1: sampling pages via HTTP ({} candidates)",
+            html_urls.len()
         );
 
         progress::emit(
@@ -370,94 +622,215 @@ impl Mapper {
             &mut seq,
             ProgressEventKind::LayerStarted {
                 layer: MappingLayer::L1HttpFetch,
-                message: format!("Fetching {} sample pages", sample_urls.len()),
+                message: format!("Sampling up to {} pages", request.max_render),
             },
         );
 
-        let responses = http_client.get_many(&sample_urls, 20, 10000).await;
-
-        // Collect successful responses
-        let ok_responses: Vec<crate::acquisition::http_client::HttpResponse> = responses
-            .into_iter()
-            .flatten()
-            .filter(|resp| resp.status == 200)
-            .collect();
-
-        // Parse structured data + pattern extraction in a blocking task (scraper types are not Send)
-        let structured_results = tokio::task::spawn_blocking(move || {
-            let mut results: Vec<FetchResult> = Vec::new();
-            let mut extra_links: Vec<String> = Vec::new();
-
-            for resp in ok_responses {
-                let sd = structured::extract_structured_data(&resp.body, &resp.final_url);
+        let mut sample_urls: Vec<String> = Vec::new();
+        let mut structured_results: Vec<FetchResult> = Vec::new();
+        let mut extra_links: Vec<String> = Vec::new();
+        // Captcha and anti-bot pages, fetched or rendered, are kept out of
+        // the map's content and their nodes flagged.
+        let mut blocks = BlockReport::default();
+        let mut blocked_urls: HashSet<String> = HashSet::new();
+
+        // The site's error page, from a URL that cannot exist, so pages
+        // served with it and status 200 are not mapped as content
+        let mut error_pages = Soft404Detector::default();
+        let probe = soft_404::probe_url(&request.domain);
+        if robots_skip(robots_rules.as_ref(), &probe).is_none() {
+            let _turn = match &crawl_limiter {
+                Some(limiter) => Some(limiter.acquire().await),
+                None => None,
+            };
+            if let Ok(resp) = http_client.get(&probe, 10000).await {
+                if error_pages.learn_response(&resp, true) {
+                    debug!("learned the error page of {}", request.domain);
+                }
+            }
+        }
 
-                for link in &sd.links {
-                    if link.is_internal {
-                        extra_links.push(link.href.clone());
+        // Pages a resumed map fetched before are parsed again, not re-fetched.
+        let mut stored_pages = None;
+        if !frontier.fetched.is_empty() {
+            for url in &frontier.fetched {
+                sampler.mark_fetched(url);
+            }
+            sample_urls.extend(frontier.fetched.iter().cloned());
+            stored_pages = Some(frontier.pages.clone());
+        }
+        // Set once the sampler has nothing more to fetch, when the map no
+        // longer needs its checkpoint.
+        let mut sampled_all = false;
+
+        while start.elapsed() < layer1_deadline && !request.cancel.is_cancelled() {
+            let responses = match stored_pages.take() {
+                Some(pages) => pages,
+                None => {
+                    let batch = sampler.next_batch();
+                    if batch.is_empty() {
+                        sampled_all = true;
+                        break;
                     }
+                    let responses: Vec<HttpResponse> = match (&crawl_limiter, &self.workers) {
+                        (Some(limiter), _) => fetch_politely(&http_client, &batch, limiter).await,
+                        (None, Some(pool)) if pool.active().0 > 0 => tokio::select! {
+                            responses = pool.get_many(&http_client, &batch, 10000) => responses,
+                            _ = request.cancel.cancelled() => Vec::new(),
+                        },
+                        _ => http_client
+                            .get_many(&batch, 20, 10000)
+                            .await
+                            .into_iter()
+                            .flatten()
+                            .collect(),
+                    };
+                    // A cancelled batch was not fetched; leave it pending.
+                    if !request.cancel.is_cancelled() {
+                        frontier.record_batch(&batch, &responses);
+                    }
+                    sample_urls.extend(batch);
+                    responses
                 }
+            };
 
-                let head = crate::acquisition::http_client::HeadResponse {
-                    url: resp.url.clone(),
-                    status: resp.status,
-                    content_type: resp
-                        .headers
-                        .iter()
-                        .find(|(k, _)| k == "content-type")
-                        .map(|(_, v)| v.clone()),
-                    content_language: resp
-                        .headers
-                        .iter()
-                        .find(|(k, _)| k == "content-language")
-                        .map(|(_, v)| v.clone()),
-                    last_modified: resp
-                        .headers
-                        .iter()
-                        .find(|(k, _)| k == "last-modified")
-                        .map(|(_, v)| v.clone()),
-                    cache_control: resp
-                        .headers
-                        .iter()
-                        .find(|(k, _)| k == "cache-control")
-                        .map(|(_, v)| v.clone()),
-                };
+            // Collect successful responses; block pages and other documents
+            // are not parsed as HTML
+            blocks.checked(responses.len());
+            for resp in &responses {
+                error_pages.learn_response(resp, false);
+            }
+            let ok_responses: Vec<HttpResponse> = responses
+                .into_iter()
+                .filter(|resp| match resp.blocked {
+                    Some(kind) => {
+                        blocks.blocked(&resp.url, kind);
+                        blocked_urls.insert(resp.url.clone());
+                        blocked_urls.insert(resp.final_url.clone());
+                        false
+                    }
+                    None => true,
+                })
+                .filter(|resp| resp.status == 200)
+                .filter(|resp| {
+                    if resp.truncated {
+                        debug!(
+                            "{} exceeded the body size limit; parsing its start",
+                            resp.url
+                        );
+                    }
+                    let html = resp.is_html();
+                    if !html {
+                        debug!("skipping {} ({})", resp.url, resp.mime);
+                    }
+                    html
+                })
+                .collect();
+
+            // Parse structured data + pattern extraction on the parse pool (scraper types are
+            // not Send), one page per thread
+            let policy = request.policy.clone();
+            let urls: Vec<String> = ok_responses.iter().map(|r| r.url.clone()).collect();
+            let pages = parse_pool::global()
+                .run_each(ok_responses, PAGE_PARSE_TIMEOUT, move |resp| {
+                    parse_page(resp, &policy)
+                })
+                .await;
 
-                // Layer 1.5: Run pattern engine — always, for actions + commerce fallback
-                let _sd_completeness = structured::data_completeness(&sd);
-                let pattern_result = Some(pattern_engine::extract_from_patterns(
-                    &resp.body,
-                    &resp.final_url,
-                ));
-
-                // Layer 2.5: Action discovery — forms + platform templates
-                let mut http_actions =
-                    action_discovery::discover_actions_from_html(&resp.body, &resp.final_url);
-                let platform_actions =
-                    action_discovery::discover_actions_from_platform(&resp.final_url, &resp.body);
-                http_actions.extend(platform_actions);
-
-                results.push((
-                    resp.final_url,
-                    sd,
-                    Some(head),
-                    pattern_result,
-                    resp.body,
-                    http_actions,
-                ));
+            let mut results: Vec<FetchResult> = Vec::new();
+            let mut links: Vec<String> = Vec::new();
+            let mut observed: Vec<(String, Vec<String>)> = Vec::new();
+            let mut skipped: Vec<(String, String, SkipReason)> = Vec::new();
+            for (url, page) in urls.iter().zip(pages) {
+                match page {
+                    Some(ParsedPage::Skipped(url, final_url, reason)) => {
+                        skipped.push((url, final_url, reason))
+                    }
+                    Some(ParsedPage::Parsed(parsed)) => {
+                        let (result, page_links, observations) = *parsed;
+                        links.extend(page_links);
+                        observed.push((url.clone(), observations));
+                        results.push(result);
+                    }
+                    None => warn!(
+                        "parsing {url} failed or took over {}s",
+                        PAGE_PARSE_TIMEOUT.as_secs()
+                    ),
+                }
             }
 
-            (results, extra_links)
-        })
-        .await
-        .unwrap_or_default();
+            for (url, final_url, reason) in skipped {
+                record_skip(&request, &final_url, &reason);
+                excluded.insert(url);
+                excluded.insert(final_url);
+            }
+            for (url, observations) in &observed {
+                sampler.observe(url, observations);
+            }
+            structured_results.extend(results);
+            extra_links.extend(links);
+
+            frontier.excluded = excluded.iter().cloned().collect();
+            frontier.pending = sampler
+                .pending()
+                .into_iter()
+                .map(|(url, score)| PendingUrl { url, score })
+                .collect();
+            if let Err(e) = frontier.save(&frontier_dir) {
+                warn!("failed to save frontier for {}: {e:#}", request.domain);
+            }
 
-        let (structured_results, extra_links) = structured_results;
+            progress::emit(
+                ptx,
+                &req_id,
+                &mut seq,
+                ProgressEventKind::MappingProgress {
+                    urls_discovered: all_urls.len() as u32,
+                    pages_fetched: structured_results.len() as u32,
+                    nodes_built: 0,
+                    edges_built: 0,
+                    active_requests: 0,
+                    elapsed_ms: start.elapsed().as_millis() as u64,
+                    prices_found: 0,
+                    ratings_found: 0,
+                    actions_found: 0,
+                },
+            );
+        }
+        let stats = sampler.stats();
+        info!(
+            "Layer 1 sampling: {} pages from {} templates ({} saturated)",
+            stats.fetched, stats.templates, stats.saturated
+        );
+        if !sampled_all {
+            info!(
+                "Layer 1 stopped with {} pages pending; resume with `cortex map {} --resume`",
+                frontier.pending.len(),
+                request.domain
+            );
+        }
 
         // Add discovered links from structured data
         for link in &extra_links {
-            if !all_urls.contains(link) {
-                all_urls.push(link.clone());
+            if all_urls.contains(link) || excluded.contains(link) {
+                continue;
             }
+            if let Some(reason) =
+                robots_skip(robots_rules.as_ref(), link).or_else(|| request.policy.check_url(link))
+            {
+                record_skip(&request, link, &reason);
+                excluded.insert(link.clone());
+                continue;
+            }
+            all_urls.push(link.clone());
+        }
+        all_urls.retain(|url| !excluded.contains(url));
+        if !excluded.is_empty() {
+            info!(
+                "content policy excluded {} URLs for {}",
+                excluded.len(),
+                request.domain
+            );
         }
 
         let pattern_count = structured_results
@@ -525,7 +898,18 @@ impl Mapper {
 
         // ── Layer 2: API Discovery ──
 
-        if api_discovery::has_known_api(&request.domain) && start.elapsed() < layer1_deadline {
+        if request.cancel.is_cancelled() {
+            progress::emit(
+                ptx,
+                &req_id,
+                &mut seq,
+                ProgressEventKind::LayerSkipped {
+                    layer: MappingLayer::L2ApiDiscovery,
+                    reason: "Cancelled".to_string(),
+                },
+            );
+        } else if api_discovery::has_known_api(&request.domain) && start.elapsed() < layer1_deadline
+        {
             progress::emit(
                 ptx,
                 &req_id,
@@ -574,30 +958,56 @@ impl Mapper {
             );
         }
 
-        // ── Layer 3: Browser fallback (only for pages with <20% completeness after all layers) ──
+        // ── Layer 3: Browser fallback (only for pages with <20% completeness after all layers,
+        // or whose layers disagree on a value) ──
 
-        let needs_browser: Vec<String> = structured_results
-            .iter()
-            .filter(|(_, sd, _, pr, _, _)| {
-                let sd_completeness = structured::data_completeness(sd);
-                let has_pattern_data = pr
-                    .as_ref()
-                    .map(|p| {
-                        p.price.is_some()
-                            || p.rating.is_some()
-                            || p.availability.is_some()
-                            || p.page_type.is_some()
-                    })
-                    .unwrap_or(false);
-                // Only browser if BOTH structured AND patterns gave <20%
-                sd_completeness < 0.2 && !has_pattern_data
-            })
-            .map(|(url, _, _, _, _, _)| url.clone())
-            .collect();
+        // Pages to verify go first: a render settles their conflict.
+        let mut to_verify: Vec<String> = Vec::new();
+        let mut sparse: Vec<String> = Vec::new();
+        for (url, sd, head, pr, _, _) in &structured_results {
+            if pr
+                .as_ref()
+                .is_some_and(|pr| needs_verification(url, sd, head.as_ref(), pr))
+            {
+                to_verify.push(url.clone());
+                continue;
+            }
+            let sd_completeness = structured::data_completeness(sd);
+            let has_pattern_data = pr
+                .as_ref()
+                .map(|p| {
+                    p.price.is_some()
+                        || p.rating.is_some()
+                        || p.availability.is_some()
+                        || p.page_type.is_some()
+                })
+                .unwrap_or(false);
+            // Only browser if BOTH structured AND patterns gave <20%
+            if sd_completeness < 0.2 && !has_pattern_data {
+                sparse.push(url.clone());
+            }
+        }
+        if !to_verify.is_empty() {
+            info!(
+                "Layer 3: {} pages with conflicting values to verify",
+                to_verify.len()
+            );
+        }
+        let needs_browser: Vec<String> = to_verify.into_iter().chain(sparse).collect();
 
         let mut browser_pages: Vec<BrowserRenderedPage> = Vec::new();
 
-        if !needs_browser.is_empty() && start.elapsed() < total_budget {
+        if request.cancel.is_cancelled() {
+            progress::emit(
+                ptx,
+                &req_id,
+                &mut seq,
+                ProgressEventKind::LayerSkipped {
+                    layer: MappingLayer::L3Browser,
+                    reason: "Cancelled".to_string(),
+                },
+            );
+        } else if !needs_browser.is_empty() && start.elapsed() < total_budget {
             let browser_count = needs_browser.len().min(request.max_render as usize).min(10);
             progress::emit(
                 ptx,
@@ -614,18 +1024,50 @@ impl Mapper {
                 needs_browser.len()
             );
 
-            for url in needs_browser.iter().take(browser_count) {
-                if start.elapsed() >= total_budget {
-                    break;
-                }
-                // Per-page browser timeout of 20s to prevent hangs
-                match tokio::time::timeout(
-                    std::time::Duration::from_secs(20),
-                    self.render_page(url),
-                )
-                .await
-                {
-                    Ok(Ok(page)) => browser_pages.push(page),
+            // One page at a time locally; one per rendering worker as well
+            let concurrency = 1 + self.workers.as_ref().map_or(0, |pool| pool.active().1);
+            let cancel = &request.cancel;
+            let profile = &profile;
+            let limiter = crawl_limiter.as_ref();
+            // Owned URLs: borrowed ones make the future's Send bound
+            // higher-ranked, which the compiler cannot prove.
+            let rendered: Vec<(String, _)> =
+                futures::stream::iter(needs_browser.iter().take(browser_count).cloned())
+                    .map(|url| async move {
+                        if start.elapsed() >= total_budget {
+                            return None;
+                        }
+                        let _turn = match limiter {
+                            Some(limiter) => Some(limiter.acquire().await),
+                            None => None,
+                        };
+                        // Per-page browser timeout of 20s to prevent hangs
+                        let result = tokio::time::timeout(
+                            std::time::Duration::from_secs(20),
+                            self.render_page(&url, profile, cancel),
+                        )
+                        .await;
+                        Some((url, result))
+                    })
+                    .buffer_unordered(concurrency)
+                    .filter_map(|r| async move { r })
+                    .collect()
+                    .await;
+            for (url, result) in rendered {
+                match result {
+                    Ok(Ok(page)) => {
+                        blocks.checked(1);
+                        match page.blocked {
+                            Some(kind) => {
+                                warn!("{url} rendered as a {kind:?} block page");
+                                blocks.blocked(&url, kind);
+                                http_client.report_block(&url);
+                                blocked_urls.insert(page.final_url);
+                                blocked_urls.insert(url);
+                            }
+                            None => browser_pages.push(page),
+                        }
+                    }
                     Ok(Err(e)) => warn!("browser fallback failed for {url}: {e}"),
                     Err(_) => warn!("browser fallback timed out for {url}"),
                 }
@@ -672,11 +1114,77 @@ impl Mapper {
 
         // ── Build the map from all layers ──
 
-        // Convert structured_results to the format build_map_from_layers expects
+        // Convert structured_results to the format build_map_from_layers
+        // expects, setting the HTML aside for snapshots after evaluating
+        // the plugin dimensions on it. Rendered pages come last, so their
+        // values win.
+        let mut pages_html: Vec<(String, SnapshotSource, String)> = Vec::new();
+        let mut plugin_values: HashMap<String, Vec<(usize, f32)>> = HashMap::new();
         let layer_results: Vec<LayerResult> = structured_results
             .into_iter()
-            .map(|(url, sd, head, pr, _html, actions)| (url, sd, head, pr, actions))
+            .map(|(url, sd, head, pr, html, actions)| {
+                let values = self.plugins.evaluate(&PluginPage {
+                    url: &url,
+                    html: Some(&html),
+                    extraction: None,
+                });
+                if !values.is_empty() {
+                    plugin_values.insert(url.clone(), values);
+                }
+                pages_html.push((url.clone(), SnapshotSource::Http, html));
+                (url, sd, head, pr, actions)
+            })
+            .collect();
+        for page in &mut browser_pages {
+            let values = self.plugins.evaluate(&PluginPage {
+                url: &page.final_url,
+                html: page.html.as_deref(),
+                extraction: Some(&page.extraction),
+            });
+            if !values.is_empty() {
+                plugin_values
+                    .entry(page.final_url.clone())
+                    .or_default()
+                    .extend(values);
+            }
+            if let Some(html) = page.html.take() {
+                pages_html.push((page.final_url.clone(), SnapshotSource::Rendered, html));
+            }
+        }
+
+        // Pages that are the site's error page under another URL
+        let soft_404s: HashSet<String> = pages_html
+            .iter()
+            .filter(|(_, _, html)| error_pages.is_soft_404(html))
+            .map(|(url, _, _)| url.clone())
+            .collect();
+        if !soft_404s.is_empty() {
+            info!(
+                "{} pages of {} are error pages served with status 200",
+                soft_404s.len(),
+                request.domain
+            );
+        }
+
+        // How the map treated robots.txt and the terms of service
+        let terms = check_terms(&http_client, &all_urls, &pages_html, crawl_limiter.as_ref()).await;
+        let mut robots_skipped: Vec<RobotsSkip> = excluded
+            .iter()
+            .filter_map(|url| {
+                robots_rule(robots_rules.as_ref(), url).map(|rule| RobotsSkip {
+                    url: url.clone(),
+                    rule: rule.to_string(),
+                })
+            })
             .collect();
+        robots_skipped.sort_by(|a, b| a.url.cmp(&b.url));
+        let compliance = ComplianceReport::new(
+            request.respect_robots,
+            robots_rules.as_ref(),
+            robots_skipped,
+            terms,
+        );
+        record_compliance(&request, &compliance);
 
         progress::emit(
             ptx,
@@ -688,13 +1196,35 @@ impl Mapper {
             },
         );
 
-        let sitemap = self.build_map_from_layers(
+        let corrections = match &self.annotations {
+            Some(store) => store.corrections(&request.domain).unwrap_or_else(|e| {
+                warn!("ignoring annotations of {}: {e}", request.domain);
+                HashMap::new()
+            }),
+            None => HashMap::new(),
+        };
+        let mut sitemap = self.build_map_from_layers(
             &request.domain,
             &all_urls,
             &layer_results,
             &browser_pages,
+            &plugin_values,
+            &corrections,
+            &soft_404s,
             request.max_nodes,
         )?;
+        sitemap.compliance = Some(compliance);
+        flag_blocked(&mut sitemap, &blocked_urls);
+        if blocks.blocked > 0 {
+            warn!(
+                "{} of {} pages of {} were block pages",
+                blocks.blocked, blocks.checked, request.domain
+            );
+        }
+        sitemap.blocks = Some(blocks);
+        if let Some(encoder) = &self.semantic {
+            sitemap.embeddings = embed_text(Arc::clone(encoder), &sitemap.text).await;
+        }
 
         progress::emit(
             ptx,
@@ -710,6 +1240,13 @@ impl Mapper {
             },
         );
 
+        if sampled_all {
+            Frontier::remove(&frontier_dir, &request.domain);
+        }
+        if let Some(store) = &self.snapshots {
+            save_snapshots(store.clone(), &request.domain, pages_html).await;
+        }
+
         progress::emit(
             ptx,
             &req_id,
@@ -792,14 +1329,56 @@ impl Mapper {
         all_entries
     }
 
-    /// Render a single page via browser (Layer 3 fallback).
-    async fn render_page(&self, url: &str) -> Result<BrowserRenderedPage> {
+    /// Render a single page via browser (Layer 3 fallback) as `profile`, on
+    /// a rendering worker when one is available. Fails with [`Cancelled`]
+    /// if `cancel` fires first.
+    pub async fn render_page(
+        &self,
+        url: &str,
+        profile: &CrawlProfile,
+        cancel: &CancellationToken,
+    ) -> Result<BrowserRenderedPage> {
+        if let Some(pool) = &self.workers {
+            let remote = tokio::select! {
+                page = pool.render(url, profile, std::time::Duration::from_secs(20)) => page,
+                _ = cancel.cancelled() => return Err(Cancelled.into()),
+            };
+            if let Some(page) = remote {
+                return page;
+            }
+        }
+
         let mut context = self
             .renderer
             .new_context()
             .await
             .context("failed to create browser context")?;
 
+        // Close the context however rendering ends, so a cancelled render
+        // gives its browser context back at once.
+        let rendered = tokio::select! {
+            rendered = self.render_in(context.as_mut(), url, profile) => rendered,
+            _ = cancel.cancelled() => Err(Cancelled.into()),
+        };
+        let _ = context.close().await;
+        rendered
+    }
+
+    async fn render_in(
+        &self,
+        context: &mut dyn RenderContext,
+        url: &str,
+        profile: &CrawlProfile,
+    ) -> Result<BrowserRenderedPage> {
+        if let Some(host) = url::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(str::to_string))
+        {
+            context
+                .apply_profile(profile, &host)
+                .await
+                .context("failed to apply crawl profile")?;
+        }
         let nav_result = context
             .navigate(url, 15000)
             .await
@@ -807,7 +1386,7 @@ impl Mapper {
 
         let extraction = self
             .extractor_loader
-            .inject_and_run(context.as_ref())
+            .inject_and_run(&*context)
             .await
             .context("browser extraction failed")?;
 
@@ -821,7 +1400,11 @@ impl Mapper {
             })
             .unwrap_or_default();
 
-        let _ = context.close().await;
+        let vision = self.classify_visually(&*context, &extraction, url).await;
+        let html = context.get_html().await.ok();
+        let blocked = html
+            .as_deref()
+            .and_then(|html| block_detect::detect(nav_result.status, &[], html));
 
         Ok(BrowserRenderedPage {
             url: url.to_string(),
@@ -829,20 +1412,64 @@ impl Mapper {
             extraction,
             nav_result,
             discovered_links,
+            vision,
+            html,
+            blocked,
         })
     }
 
-    /// Build the final SiteMap from all layers of data.
+    /// Classify a rendered page from a screenshot if vision is on and the
+    /// text classification is unsure. Failures only cost the second opinion.
+    async fn classify_visually(
+        &self,
+        context: &dyn RenderContext,
+        extraction: &crate::extraction::loader::ExtractionResult,
+        url: &str,
+    ) -> Option<(PageType, f32)> {
+        let classifier = self.vision.as_ref()?;
+        let (_, confidence) = page_classifier::classify_page(extraction, url);
+        if confidence >= vision_classifier::LOW_CONFIDENCE {
+            return None;
+        }
+        let png = match context.screenshot().await {
+            Ok(png) => png,
+            Err(e) => {
+                debug!("no screenshot of {url} for vision classification: {e:#}");
+                return None;
+            }
+        };
+        let classifier = Arc::clone(classifier);
+        match tokio::task::spawn_blocking(move || classifier.classify(&png)).await {
+            Ok(Ok(vision)) => vision,
+            Ok(Err(e)) => {
+                warn!("vision classification of {url} failed: {e:#}");
+                None
+            }
+            Err(e) => {
+                warn!("vision classification of {url} panicked: {e}");
+                None
+            }
+        }
+    }
+
+    /// Build the final SiteMap from all layers of data, with the page types
+    /// of `corrections` (annotated by URL) in place of what was inferred, and
+    /// the pages of `soft_404s` typed error pages.
+    #[allow(clippy::too_many_arguments)]
     fn build_map_from_layers(
         &self,
         domain: &str,
         all_urls: &[String],
         structured_results: &[LayerResult],
         browser_pages: &[BrowserRenderedPage],
+        plugin_values: &HashMap<String, Vec<(usize, f32)>>,
+        corrections: &HashMap<String, PageType>,
+        soft_404s: &HashSet<String>,
         max_nodes: u32,
     ) -> Result<SiteMap> {
         let mut builder = SiteMapBuilder::new(domain);
         let mut url_to_index: HashMap<String, u32> = HashMap::new();
+        let fetched_at = lineage::now();
 
         // Build lookup for browser pages by URL
         let browser_by_url: HashMap<&str, &BrowserRenderedPage> = browser_pages
@@ -851,6 +1478,13 @@ impl Mapper {
             .chain(browser_pages.iter().map(|p| (p.final_url.as_str(), p)))
             .collect();
 
+        // URL-pattern rules from the pages whose content or an annotation
+        // confirmed their type, for pages classified by URL alone
+        let url_model = learn_url_types(structured_results, browser_pages, corrections, soft_404s);
+        if !url_model.is_empty() {
+            debug!("learned {} URL type rules for {domain}", url_model.len());
+        }
+
         // First pass: add nodes with structured data (Layer 1) or browser data (Layer 3)
         for (url, sd, head, pr, http_actions) in structured_results {
             if url_to_index.len() as u32 >= max_nodes {
@@ -860,7 +1494,7 @@ impl Mapper {
             // Check if we also have browser data for this URL
             if let Some(page) = browser_by_url.get(url.as_str()) {
                 // Use browser data (higher quality)
-                let (page_type, confidence) = page_classifier::classify_page(&page.extraction, url);
+                let (page_type, confidence, by_vision) = page.classify(url);
                 let encode_result = feature_encoder::encode_features_with_flags(
                     &page.extraction,
                     &page.nav_result,
@@ -877,6 +1511,48 @@ impl Mapper {
                 url_to_index.insert(url.clone(), idx);
                 builder.merge_flags(idx, encode_result.flags);
                 builder.set_rendered(idx, encode_result.features);
+                let text = page.text();
+                builder.set_text(
+                    idx,
+                    if text.is_empty() {
+                        structured_text(sd)
+                    } else {
+                        text
+                    },
+                );
+                record_browser_lineage(
+                    &mut builder,
+                    idx,
+                    &encode_result,
+                    confidence,
+                    by_vision,
+                    fetched_at,
+                );
+
+                // The render settles whatever structured data and patterns
+                // disagreed on
+                if let Some(pattern_result) = pr {
+                    let (sd_features, pattern_features) =
+                        encode_layers(url, sd, head.as_ref(), pattern_result);
+                    for (field, dim, candidates) in layer_candidates(
+                        sd,
+                        &sd_features,
+                        pattern_result,
+                        &pattern_features,
+                        fetched_at,
+                    ) {
+                        if encode_result.features[dim] == 0.0 {
+                            continue;
+                        }
+                        let rendered = Candidate {
+                            value: encode_result.features[dim],
+                            lineage: browser_field_lineage(&encode_result, field, fetched_at),
+                        };
+                        if let Some(conflict) = conflicts::settle(candidates, rendered) {
+                            builder.record_conflict(idx, field, conflict);
+                        }
+                    }
+                }
 
                 let actions = action_encoder::encode_actions_from_json(&page.extraction.actions);
                 for action in actions {
@@ -890,24 +1566,21 @@ impl Mapper {
                 }
             } else {
                 // Use structured data (Layer 1) + pattern data (Layer 1.5) if available
-                let (sd_page_type, sd_confidence) = sd
-                    .page_type
-                    .unwrap_or_else(|| url_classifier::classify_url(url, domain));
+                let (url_page_type, url_confidence, template) =
+                    url_classifier::classify_url_learned(&url_model, url, domain);
+                let (sd_page_type, sd_confidence) =
+                    sd.page_type.unwrap_or((url_page_type, url_confidence));
 
-                let default_head = crate::acquisition::http_client::HeadResponse {
-                    url: url.clone(),
-                    status: 200,
-                    content_type: None,
-                    content_language: None,
-                    last_modified: None,
-                    cache_control: None,
-                };
+                let default_head = default_head(url);
                 let head_ref = head.as_ref().unwrap_or(&default_head);
 
                 let sd_features =
                     feature_encoder::encode_features_from_structured_data(sd, url, head_ref);
 
-                // Merge with pattern features if available
+                // Merge with pattern features if available. Traced values
+                // both layers found go to the more trusted one, and the
+                // conflict is kept when they disagree.
+                let mut field_conflicts = Vec::new();
                 let features = if let Some(pattern_result) = pr {
                     let pattern_features = feature_encoder::encode_features_from_patterns(
                         pattern_result,
@@ -919,13 +1592,26 @@ impl Mapper {
                     let pattern_completeness =
                         pattern_features.iter().filter(|&&v| v != 0.0).count() as f32
                             / FEATURE_DIM as f32;
-                    feature_encoder::merge_features(
+                    let mut merged = feature_encoder::merge_features(
                         &sd_features,
                         sd_completeness,
                         &pattern_features,
                         pattern_completeness,
                         None,
-                    )
+                    );
+                    for (field, dim, candidates) in layer_candidates(
+                        sd,
+                        &sd_features,
+                        pattern_result,
+                        &pattern_features,
+                        fetched_at,
+                    ) {
+                        if let Some((kept, conflict)) = conflicts::resolve(candidates, fetched_at) {
+                            merged[dim] = kept.value;
+                            field_conflicts.extend(conflict.map(|c| (field, c)));
+                        }
+                    }
+                    merged
                 } else {
                     sd_features
                 };
@@ -952,6 +1638,33 @@ impl Mapper {
                     (final_confidence * 255.0) as u8,
                 );
                 url_to_index.insert(url.clone(), idx);
+                builder.set_text(idx, structured_text(sd));
+                for (field, conflict) in field_conflicts {
+                    builder.record_conflict(idx, field, conflict);
+                }
+
+                let page_type_lineage = if pr
+                    .as_ref()
+                    .and_then(|p| p.page_type)
+                    .is_some_and(|(_, pc)| pc > sd_confidence)
+                {
+                    FieldLineage::new(Layer::Pattern, None, final_confidence, fetched_at)
+                } else if sd.page_type.is_some() {
+                    let path = sd.has_jsonld.then_some("@type");
+                    FieldLineage::new(Layer::StructuredData, path, final_confidence, fetched_at)
+                } else {
+                    FieldLineage::new(Layer::Url, template, final_confidence, fetched_at)
+                };
+                builder.record_lineage(idx, "page_type", page_type_lineage);
+                record_field_lineage(
+                    &mut builder,
+                    idx,
+                    &features,
+                    sd,
+                    &sd_features,
+                    pr.as_ref(),
+                    fetched_at,
+                );
 
                 // Set flags based on structured data + pattern data
                 let mut flag_bits: u8 = 0;
@@ -979,6 +1692,13 @@ impl Mapper {
                         let risk = ((1.0 - action.confidence) * 3.0).min(3.0) as u8;
                         builder.add_action_http(idx, action.opcode, -2, 0, risk);
                     }
+                    let confidence = http_actions.iter().map(|a| a.confidence).sum::<f32>()
+                        / http_actions.len() as f32;
+                    builder.record_lineage(
+                        idx,
+                        "action_count",
+                        FieldLineage::new(Layer::Actions, None, confidence, fetched_at),
+                    );
 
                     // Update feature dimensions 96-99 + 106 to reflect HTTP actions
                     let total = http_actions.len();
@@ -1025,8 +1745,7 @@ impl Mapper {
                 break;
             }
 
-            let (page_type, confidence) =
-                page_classifier::classify_page(&page.extraction, &page.url);
+            let (page_type, confidence, by_vision) = page.classify(&page.url);
             let encode_result = feature_encoder::encode_features_with_flags(
                 &page.extraction,
                 &page.nav_result,
@@ -1044,6 +1763,15 @@ impl Mapper {
             url_to_index.insert(page.final_url.clone(), idx);
             builder.merge_flags(idx, encode_result.flags);
             builder.set_rendered(idx, encode_result.features);
+            builder.set_text(idx, page.text());
+            record_browser_lineage(
+                &mut builder,
+                idx,
+                &encode_result,
+                confidence,
+                by_vision,
+                fetched_at,
+            );
         }
 
         // Second pass: add unrendered/un-fetched nodes from URL classification
@@ -1055,7 +1783,8 @@ impl Mapper {
                 break;
             }
 
-            let (page_type, confidence) = url_classifier::classify_url(url, domain);
+            let (page_type, confidence, template) =
+                url_classifier::classify_url_learned(&url_model, url, domain);
 
             // Default features with basic identity info
             let mut features = [0.0f32; FEATURE_DIM];
@@ -1070,6 +1799,11 @@ impl Mapper {
 
             let idx = builder.add_node(url, page_type, features, (confidence * 255.0) as u8);
             url_to_index.insert(url.clone(), idx);
+            builder.record_lineage(
+                idx,
+                "page_type",
+                FieldLineage::new(Layer::Url, template, confidence, fetched_at),
+            );
         }
 
         // Add edges from structured data links
@@ -1156,13 +1890,25 @@ impl Mapper {
             }
         }
 
+        for (url, values) in plugin_values {
+            if let Some(&idx) = url_to_index.get(url) {
+                for &(dim, value) in values {
+                    builder.update_feature(idx, dim, value);
+                }
+            }
+        }
+
         // Infer edges from URL structure for all classified URLs
         let classified: Vec<(String, PageType, f32)> = all_urls
             .iter()
             .filter_map(|url| {
-                url_to_index.get(url).map(|_| {
-                    let (pt, c) = url_classifier::classify_url(url, domain);
-                    (url.clone(), pt, c)
+                url_to_index.get(url).map(|_| match corrections.get(url) {
+                    Some(&pt) => (url.clone(), pt, 1.0),
+                    None => {
+                        let (pt, c, _) =
+                            url_classifier::classify_url_learned(&url_model, url, domain);
+                        (url.clone(), pt, c)
+                    }
                 })
             })
             .collect();
@@ -1192,11 +1938,79 @@ impl Mapper {
 
         info!("built layered map: {} nodes", url_to_index.len());
 
-        Ok(builder.build())
+        let mut map = builder.build();
+        mark_error_pages(&mut map, soft_404s, fetched_at);
+        annotations::apply_corrections(&mut map, corrections);
+        Ok(map)
     }
 }
 
 /// Intermediate result from HTTP fetch + structured data + pattern extraction + actions.
+/// What parsing one fetched page yields.
+enum ParsedPage {
+    /// Excluded by the content policy: requested URL, final URL and why.
+    Skipped(String, String, SkipReason),
+    /// The page, its internal links, and what the sampler observed on it.
+    Parsed(Box<(FetchResult, Vec<String>, Vec<String>)>),
+}
+
+/// Extract structured data, patterns and actions from one page. Runs on a
+/// parse thread.
+fn parse_page(resp: HttpResponse, policy: &ContentPolicy) -> ParsedPage {
+    let sd = structured::extract_structured_data(&resp.body, &resp.final_url);
+
+    // Pages the content policy excludes are neither stored nor followed
+    let header = |name: &str| {
+        resp.headers
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.clone())
+    };
+    if let Some(reason) = policy.check_page(&sd, header("x-robots-tag").as_deref()) {
+        return ParsedPage::Skipped(resp.url, resp.final_url, reason);
+    }
+
+    let links: Vec<String> = sd
+        .links
+        .iter()
+        .filter(|link| link.is_internal)
+        .map(|link| link.href.clone())
+        .collect();
+
+    let head = crate::acquisition::http_client::HeadResponse {
+        url: resp.url.clone(),
+        status: resp.status,
+        content_type: header("content-type"),
+        content_language: header("content-language"),
+        last_modified: header("last-modified"),
+        cache_control: header("cache-control"),
+    };
+
+    // Layer 1.5: Run pattern engine — always, for actions + commerce fallback
+    let pattern_result = Some(pattern_engine::extract_from_patterns(
+        &resp.body,
+        &resp.final_url,
+    ));
+
+    // Layer 2.5: Action discovery — forms + platform templates
+    let mut http_actions =
+        action_discovery::discover_actions_from_html(&resp.body, &resp.final_url);
+    let platform_actions =
+        action_discovery::discover_actions_from_platform(&resp.final_url, &resp.body);
+    http_actions.extend(platform_actions);
+
+    let observations = smart_sampler::observations(&sd, pattern_result.as_ref());
+    let result = (
+        resp.final_url,
+        sd,
+        Some(head),
+        pattern_result,
+        resp.body,
+        http_actions,
+    );
+    ParsedPage::Parsed(Box::new((result, links, observations)))
+}
+
 type FetchResult = (
     String,
     StructuredData,
@@ -1216,98 +2030,500 @@ type LayerResult = (
 );
 
 /// A page rendered via browser (Layer 3 fallback).
-struct BrowserRenderedPage {
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BrowserRenderedPage {
     url: String,
     final_url: String,
     extraction: crate::extraction::loader::ExtractionResult,
     nav_result: crate::renderer::NavigationResult,
     discovered_links: Vec<String>,
+    /// Page type and confidence from the screenshot, if vision was asked.
+    #[serde(default)]
+    vision: Option<(PageType, f32)>,
+    /// The rendered DOM, for snapshots.
+    #[serde(default)]
+    html: Option<String>,
+    /// Who served the page, if it is a captcha or anti-bot page.
+    #[serde(default)]
+    blocked: Option<block_detect::BlockKind>,
 }
 
-/// Select diverse sample URLs for HTTP GET.
-///
-/// Prioritizes: homepage, URLs from different path prefixes, different page types.
-fn select_diverse_samples(
-    html_urls: &[String],
-    _all_urls: &[String],
+impl BrowserRenderedPage {
+    /// The title and description the metadata extractor found.
+    fn text(&self) -> NodeText {
+        let metadata = &self.extraction.metadata;
+        let field = |name: &str| metadata.get(name).and_then(|v| v.as_str());
+        NodeText::new([field("title")], [field("description")])
+    }
+
+    /// The page's type and confidence: from the extraction, or from the
+    /// screenshot when that is more confident. The flag is true for vision.
+    fn classify(&self, url: &str) -> (PageType, f32, bool) {
+        let (page_type, confidence) = page_classifier::classify_page(&self.extraction, url);
+        match self.vision {
+            Some((vision_type, vision_confidence)) if vision_confidence > confidence => {
+                (vision_type, vision_confidence, true)
+            }
+            _ => (page_type, confidence, false),
+        }
+    }
+}
+
+/// Embed the text of the mapped pages, off the async runtime. A failure
+/// only costs semantic search on this map.
+async fn embed_text(encoder: Arc<SemanticEncoder>, text: &MapText) -> MapEmbeddings {
+    let text = text.clone();
+    tokio::task::spawn_blocking(move || encoder.embed_map(&text))
+        .await
+        .unwrap_or_else(|e| {
+            warn!("embedding page text panicked: {e}");
+            MapEmbeddings::default()
+        })
+}
+
+/// Store the HTML of the mapped pages in `store`, off the async runtime.
+/// Failures are logged: snapshots are a convenience, never worth a map.
+async fn save_snapshots(
+    store: SnapshotStore,
     domain: &str,
-    count: usize,
-) -> Vec<String> {
-    if html_urls.len() <= count {
-        return html_urls.to_vec();
+    pages: Vec<(String, SnapshotSource, String)>,
+) {
+    if pages.is_empty() {
+        return;
     }
+    let domain = domain.to_string();
+    let saved = tokio::task::spawn_blocking(move || {
+        let fetched_at = chrono::Utc::now();
+        let mut saved = 0;
+        for (url, source, html) in pages {
+            match store.save(&domain, &url, source, &html, fetched_at) {
+                Ok(true) => saved += 1,
+                Ok(false) => {}
+                Err(e) => warn!("failed to save snapshot of {url}: {e:#}"),
+            }
+        }
+        (domain, saved)
+    })
+    .await;
+    if let Ok((domain, saved)) = saved {
+        debug!("saved {saved} page snapshots for {domain}");
+    }
+}
 
-    let mut selected = Vec::new();
-    let mut seen_prefixes: std::collections::HashSet<String> = std::collections::HashSet::new();
+/// A fetched page's title and description: a product's or article's own,
+/// else the page's OpenGraph or HTML ones.
+fn structured_text(sd: &StructuredData) -> NodeText {
+    let product = sd.products.first();
+    let article = sd.articles.first();
+    NodeText::new(
+        [
+            product.and_then(|p| p.name.as_deref()),
+            article.and_then(|a| a.headline.as_deref()),
+            sd.og.title.as_deref(),
+            sd.meta.title.as_deref(),
+        ],
+        [
+            product.and_then(|p| p.description.as_deref()),
+            article.and_then(|a| a.description.as_deref()),
+            sd.og.description.as_deref(),
+            sd.meta.description.as_deref(),
+        ],
+    )
+}
+
+/// Learn URL-pattern page types from the pages whose type their content
+/// gave: structured data or patterns over HTTP, or a browser render. A
+/// corrected page counts with its corrected type, fetched or not; a soft
+/// 404 not at all.
+fn learn_url_types(
+    structured_results: &[LayerResult],
+    browser_pages: &[BrowserRenderedPage],
+    corrections: &HashMap<String, PageType>,
+    soft_404s: &HashSet<String>,
+) -> UrlTypeModel {
+    let fetched = structured_results.iter().filter_map(|(url, sd, _, pr, _)| {
+        let pattern = pr.as_ref().and_then(|p| p.page_type);
+        let (page_type, confidence) = match (sd.page_type, pattern) {
+            (Some(sd), Some(pattern)) if pattern.1 > sd.1 => pattern,
+            (Some(sd), _) => sd,
+            (None, pattern) => pattern?,
+        };
+        Some((url.as_str(), page_type, confidence))
+    });
+    let rendered = browser_pages.iter().map(|page| {
+        let (page_type, confidence, _) = page.classify(&page.final_url);
+        (page.final_url.as_str(), page_type, confidence)
+    });
+    let corrected = corrections
+        .iter()
+        .map(|(url, &page_type)| (url.as_str(), page_type, 1.0));
+    UrlTypeModel::learn(
+        fetched
+            .chain(rendered)
+            .filter(|(url, _, _)| !corrections.contains_key(*url) && !soft_404s.contains(*url))
+            .chain(corrected),
+    )
+}
 
-    // Always include homepage
-    let entry = format!("https://{domain}");
-    let entry_slash = format!("https://{domain}/");
-    for url in html_urls {
-        if url == &entry || url == &entry_slash {
-            selected.push(url.clone());
-            break;
+/// Infer edges between pages based on URL path structure.
+/// Record a URL or page the content policy excluded in the audit trail.
+fn record_skip(request: &MapRequest, url: &str, reason: &SkipReason) {
+    debug!("content policy: skipping {url} ({reason})");
+    let Some(audit) = &request.audit else {
+        return;
+    };
+    let event = AuditEvent {
+        domain: Some(request.domain.clone()),
+        url: Some(url.to_string()),
+        params: serde_json::json!({ "reason": reason.kind(), "rule": reason.rule() }),
+        status: "skipped".to_string(),
+        ..AuditEvent::new("policy_skip", "mapper")
+    };
+    if let Ok(mut log) = audit.lock() {
+        if let Err(e) = log.log(&event) {
+            warn!("failed to record policy skip: {e}");
         }
     }
+}
 
-    // Classify all URLs and group by type
-    let mut by_type: HashMap<PageType, Vec<&String>> = HashMap::new();
-    for url in html_urls {
-        let (pt, _) = url_classifier::classify_url(url, domain);
-        by_type.entry(pt).or_default().push(url);
+/// Type the nodes of `urls`, soft 404s, as error pages.
+fn mark_error_pages(map: &mut SiteMap, urls: &HashSet<String>, at: u64) {
+    for url in urls {
+        let Some(index) = map.find_url(url) else {
+            continue;
+        };
+        let confidence = 0.9;
+        let node = &mut map.nodes[index as usize];
+        node.page_type = PageType::ErrorPage;
+        node.confidence = (confidence * 255.0) as u8;
+        let features = &mut map.features[index as usize];
+        features[FEAT_PAGE_TYPE] = (PageType::ErrorPage as u8) as f32 / 31.0;
+        features[FEAT_PAGE_TYPE_CONFIDENCE] = confidence;
+        map.lineage.record(
+            index,
+            "page_type",
+            FieldLineage::new(Layer::Pattern, Some("soft-404"), confidence, at),
+        );
     }
+}
 
-    // Round-robin across types to get diversity
-    let types: Vec<PageType> = by_type.keys().copied().collect();
-    let mut type_idx = 0;
-    while selected.len() < count && selected.len() < html_urls.len() {
-        let pt = &types[type_idx % types.len()];
-        if let Some(urls) = by_type.get_mut(pt) {
-            if let Some(url) = urls.pop() {
-                let prefix = url_prefix(url);
-                if (!seen_prefixes.contains(&prefix) || selected.len() < count / 2)
-                    && !selected.contains(url)
-                {
-                    selected.push(url.clone());
-                    seen_prefixes.insert(prefix);
-                }
-            }
+/// Flag the nodes of `urls`, block pages, as blocked.
+fn flag_blocked(sitemap: &mut SiteMap, urls: &HashSet<String>) {
+    for (node, url) in sitemap.nodes.iter_mut().zip(&sitemap.urls) {
+        if urls.contains(url) {
+            node.flags.0 |= NodeFlags::BLOCKED;
         }
-        type_idx += 1;
+    }
+}
 
-        // Safety: if we've gone through all types without adding, just fill from remaining
-        if type_idx > types.len() * 3 {
-            for url in html_urls {
-                if selected.len() >= count {
-                    break;
-                }
-                if !selected.contains(url) {
-                    selected.push(url.clone());
-                }
-            }
-            break;
+/// Record a map's compliance report in the audit trail. URLs robots.txt
+/// excluded are recorded one by one as they are skipped, so only their
+/// count is repeated here.
+fn record_compliance(request: &MapRequest, report: &ComplianceReport) {
+    if !report.compliant {
+        info!(
+            "map of {} is not compliant: {}",
+            request.domain,
+            report.issues.join("; ")
+        );
+    }
+    let Some(audit) = &request.audit else {
+        return;
+    };
+    let status = if report.compliant {
+        "compliant"
+    } else {
+        "non_compliant"
+    };
+    let event = AuditEvent {
+        domain: Some(request.domain.clone()),
+        params: serde_json::json!({
+            "robots_respected": report.robots_respected,
+            "robots_found": report.robots_found,
+            "robots_skipped": report.robots_skipped.len(),
+            "crawl_delay_secs": report.crawl_delay_secs,
+            "crawl_delay_honored": report.crawl_delay_honored,
+            "terms": report.terms,
+            "issues": report.issues,
+        }),
+        status: status.to_string(),
+        ..AuditEvent::new("map_compliance", "mapper")
+    };
+    if let Ok(mut log) = audit.lock() {
+        if let Err(e) = log.log(&event) {
+            warn!("failed to record map compliance: {e}");
         }
     }
+}
 
-    selected
+/// The robots.txt `Disallow` rule that keeps `url` from being fetched, if
+/// robots.txt was consulted and has one.
+fn robots_rule<'a>(rules: Option<&'a robots::RobotsRules>, url: &str) -> Option<&'a str> {
+    let parsed = url::Url::parse(url).ok()?;
+    let path = match parsed.query() {
+        Some(query) => format!("{}?{query}", parsed.path()),
+        None => parsed.path().to_string(),
+    };
+    rules?.disallowing(&path)
 }
 
-fn url_prefix(url: &str) -> String {
-    let rest = url
-        .strip_prefix("https://")
-        .or_else(|| url.strip_prefix("http://"))
-        .unwrap_or(url);
-    if let Some(slash_pos) = rest.find('/') {
-        let path = &rest[slash_pos..];
-        let parts: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
-        if let Some(first) = parts.first() {
-            return format!("/{first}");
+/// [`robots_rule`] as a reason to skip `url`.
+fn robots_skip(rules: Option<&robots::RobotsRules>, url: &str) -> Option<SkipReason> {
+    robots_rule(rules, url).map(|rule| SkipReason::Robots(rule.to_string()))
+}
+
+/// Fetch `urls` one at a time, each when `limiter` allows, for a site whose
+/// robots.txt asks for a crawl delay. Failed requests are left out.
+async fn fetch_politely(
+    http_client: &HttpClient,
+    urls: &[String],
+    limiter: &RateLimiter,
+) -> Vec<HttpResponse> {
+    let mut responses = Vec::with_capacity(urls.len());
+    for url in urls {
+        let _turn = limiter.acquire().await;
+        match http_client.get(url, 10000).await {
+            Ok(resp) => responses.push(resp),
+            Err(e) => debug!("fetching {url} failed: {e}"),
         }
     }
-    "/".to_string()
+    responses
+}
+
+/// Check the site's terms of service for mentions of scraping: the sampled
+/// page if one was, else the first terms page among `urls`, fetched now.
+async fn check_terms(
+    http_client: &HttpClient,
+    urls: &[String],
+    pages_html: &[(String, SnapshotSource, String)],
+    limiter: Option<&RateLimiter>,
+) -> Option<TermsCheck> {
+    if let Some((url, _, html)) = pages_html
+        .iter()
+        .find(|(url, _, _)| compliance::is_terms_url(url))
+    {
+        return Some(TermsCheck::new(url, html));
+    }
+    let url = urls.iter().find(|url| compliance::is_terms_url(url))?;
+    let _turn = match limiter {
+        Some(limiter) => Some(limiter.acquire().await),
+        None => None,
+    };
+    let resp = http_client.get(url, 10000).await.ok()?;
+    (resp.status == 200 && resp.is_html()).then(|| TermsCheck::new(&resp.final_url, &resp.body))
+}
+
+/// Record lineage for a node whose values come from a rendered page. Values
+/// from a custom extractor carry its name (`custom:<name>`) and confidence,
+/// and a page type read from the screenshot the path `vision`.
+fn record_browser_lineage(
+    builder: &mut SiteMapBuilder,
+    idx: u32,
+    encoded: &feature_encoder::FeatureEncodeResult,
+    confidence: f32,
+    by_vision: bool,
+    fetched_at: u64,
+) {
+    let path = by_vision.then_some("vision");
+    builder.record_lineage(
+        idx,
+        "page_type",
+        FieldLineage::new(Layer::Browser, path, confidence, fetched_at),
+    );
+    for (field, dim) in TRACED_FIELDS {
+        if encoded.features[dim] == 0.0 {
+            continue;
+        }
+        builder.record_lineage(
+            idx,
+            field,
+            browser_field_lineage(encoded, field, fetched_at),
+        );
+    }
+}
+
+/// Lineage of a traced value from a rendered page.
+fn browser_field_lineage(
+    encoded: &feature_encoder::FeatureEncodeResult,
+    field: &str,
+    fetched_at: u64,
+) -> FieldLineage {
+    match encoded.custom_fields.iter().find(|f| f.field == field) {
+        Some(custom) => FieldLineage::new(
+            Layer::Browser,
+            Some(&format!("custom:{}", custom.extractor)),
+            custom.confidence,
+            fetched_at,
+        ),
+        None => FieldLineage::new(Layer::Browser, None, 1.0, fetched_at),
+    }
+}
+
+/// Record whether each traced value of a node came from structured data or
+/// from the pattern engine, by comparing the merged features to each source.
+/// A zero is traced only when a layer read it, such as "out of stock", so
+/// it is told apart from a value the page did not have.
+fn record_field_lineage(
+    builder: &mut SiteMapBuilder,
+    idx: u32,
+    features: &[f32; FEATURE_DIM],
+    sd: &StructuredData,
+    sd_features: &[f32; FEATURE_DIM],
+    pr: Option<&PatternResult>,
+    fetched_at: u64,
+) {
+    for (field, dim) in TRACED_FIELDS {
+        let lineage = if features[dim] != 0.0 {
+            if features[dim] == sd_features[dim] {
+                structured_lineage(sd, field, fetched_at)
+            } else if let Some(pr) = pr {
+                pattern_lineage(pr, field, fetched_at)
+            } else {
+                continue;
+            }
+        } else if sd_features[dim] == 0.0 && has_structured_field(sd, field) {
+            structured_lineage(sd, field, fetched_at)
+        } else if let Some(pr) = pr.filter(|pr| pattern_confidence(pr, field).is_some()) {
+            pattern_lineage(pr, field, fetched_at)
+        } else {
+            continue;
+        };
+        builder.record_lineage(idx, field, lineage);
+    }
+}
+
+/// Whether structured data gave a value for traced `field`.
+fn has_structured_field(sd: &StructuredData, field: &str) -> bool {
+    let Some(product) = sd.products.first() else {
+        return false;
+    };
+    match field {
+        "price" => product.price.is_some(),
+        "original_price" => product.original_price.is_some(),
+        "availability" => product.availability.is_some(),
+        "rating" => product.rating_value.is_some(),
+        _ => product.review_count.is_some(),
+    }
+}
+
+/// Lineage of a traced value read from structured data. OpenGraph prices
+/// are preview tags, often left behind by a price change, so they are
+/// trusted less than JSON-LD and microdata.
+fn structured_lineage(sd: &StructuredData, field: &str, fetched_at: u64) -> FieldLineage {
+    let path = structured_path(sd, field);
+    let confidence = if path.starts_with("og:") { 0.8 } else { 1.0 };
+    FieldLineage::new(Layer::StructuredData, Some(&path), confidence, fetched_at)
+}
+
+/// Lineage of a traced value the pattern engine found.
+fn pattern_lineage(pr: &PatternResult, field: &str, fetched_at: u64) -> FieldLineage {
+    let confidence = pattern_confidence(pr, field);
+    let path = pr.selectors.get(field).map(String::as_str);
+    FieldLineage::new(Layer::Pattern, path, confidence.unwrap_or(0.5), fetched_at)
+}
+
+/// The pattern engine's confidence in traced `field`, if it found one.
+fn pattern_confidence(pr: &PatternResult, field: &str) -> Option<f32> {
+    match field {
+        "price" => pr.price.map(|(_, c)| c),
+        "original_price" => pr.original_price.map(|(_, c)| c),
+        "availability" => pr.availability.map(|(_, c)| c),
+        "rating" => pr.rating.map(|(_, c)| c),
+        _ => pr.review_count.map(|(_, c)| c),
+    }
+}
+
+/// The traced fields that structured data and patterns both found, with
+/// each layer's value as a candidate.
+fn layer_candidates(
+    sd: &StructuredData,
+    sd_features: &[f32; FEATURE_DIM],
+    pr: &PatternResult,
+    pattern_features: &[f32; FEATURE_DIM],
+    fetched_at: u64,
+) -> Vec<(&'static str, usize, Vec<Candidate>)> {
+    TRACED_FIELDS
+        .into_iter()
+        .filter(|&(_, dim)| sd_features[dim] != 0.0 && pattern_features[dim] != 0.0)
+        .map(|(field, dim)| {
+            let candidates = vec![
+                Candidate {
+                    value: sd_features[dim],
+                    lineage: structured_lineage(sd, field, fetched_at),
+                },
+                Candidate {
+                    value: pattern_features[dim],
+                    lineage: pattern_lineage(pr, field, fetched_at),
+                },
+            ];
+            (field, dim, candidates)
+        })
+        .collect()
+}
+
+/// Encode a fetched page's structured data and pattern results.
+fn encode_layers(
+    url: &str,
+    sd: &StructuredData,
+    head: Option<&HeadResponse>,
+    pr: &PatternResult,
+) -> ([f32; FEATURE_DIM], [f32; FEATURE_DIM]) {
+    let default_head = default_head(url);
+    let head = head.unwrap_or(&default_head);
+    (
+        feature_encoder::encode_features_from_structured_data(sd, url, head),
+        feature_encoder::encode_features_from_patterns(pr, url, head),
+    )
+}
+
+/// Whether structured data and patterns disagree on one of a page's values
+/// enough to render it.
+fn needs_verification(
+    url: &str,
+    sd: &StructuredData,
+    head: Option<&HeadResponse>,
+    pr: &PatternResult,
+) -> bool {
+    let (sd_features, pattern_features) = encode_layers(url, sd, head, pr);
+    let now = lineage::now();
+    layer_candidates(sd, &sd_features, pr, &pattern_features, now)
+        .into_iter()
+        .filter_map(|(_, _, candidates)| conflicts::resolve(candidates, now)?.1)
+        .any(|conflict| conflict.needs_verification())
+}
+
+/// The HEAD response assumed for a page fetched without one.
+fn default_head(url: &str) -> HeadResponse {
+    HeadResponse {
+        url: url.to_string(),
+        status: 200,
+        content_type: None,
+        content_language: None,
+        last_modified: None,
+        cache_control: None,
+    }
+}
+
+/// Where structured data keeps `field`: a JSON-LD path, a microdata
+/// selector, or the OpenGraph price tag.
+fn structured_path(sd: &StructuredData, field: &str) -> String {
+    if field == "price" && sd.products.first().and_then(|p| p.price).is_none() {
+        return "og:price:amount".to_string();
+    }
+    let (jsonld, itemprop) = match field {
+        "price" => ("offers.price", "price"),
+        "original_price" => ("offers.highPrice", "highPrice"),
+        "availability" => ("offers.availability", "availability"),
+        "rating" => ("aggregateRating.ratingValue", "ratingValue"),
+        _ => ("aggregateRating.reviewCount", "reviewCount"),
+    };
+    if sd.has_jsonld {
+        jsonld.to_string()
+    } else {
+        format!("[itemprop=\"{itemprop}\"]")
+    }
 }
 
-/// Infer edges between pages based on URL path structure.
 fn infer_edges_from_url_structure(
     classified: &[(String, PageType, f32)],
     url_to_index: &HashMap<String, u32>,
@@ -1508,23 +2724,56 @@ mod tests {
     }
 
     #[test]
-    fn test_url_prefix() {
-        assert_eq!(url_prefix("https://example.com/blog/post-1"), "/blog");
-        assert_eq!(url_prefix("https://example.com/"), "/");
-        assert_eq!(url_prefix("https://example.com/products/123"), "/products");
+    fn test_corrections_teach_url_types() {
+        let corrections = HashMap::from([
+            (
+                "https://shop.com/secure/enter-shipping-address".to_string(),
+                PageType::Checkout,
+            ),
+            (
+                "https://shop.com/secure/choose-payment-method".to_string(),
+                PageType::Checkout,
+            ),
+        ]);
+        let model = learn_url_types(&[], &[], &corrections, &HashSet::new());
+        let (page_type, _, _) = url_classifier::classify_url_learned(
+            &model,
+            "https://shop.com/secure/review-your-order",
+            "shop.com",
+        );
+        assert_eq!(page_type, PageType::Checkout);
     }
 
     #[test]
-    fn test_select_diverse_samples() {
-        let urls = vec![
-            "https://example.com/".to_string(),
-            "https://example.com/blog/post-1".to_string(),
-            "https://example.com/blog/post-2".to_string(),
-            "https://example.com/products/widget".to_string(),
-            "https://example.com/about".to_string(),
-        ];
-        let selected = select_diverse_samples(&urls, &urls, "example.com", 3);
-        assert!(selected.len() <= 3);
-        assert!(!selected.is_empty());
+    fn test_flag_blocked() {
+        let mut builder = SiteMapBuilder::new("shop.com");
+        for url in ["https://shop.com/", "https://shop.com/sale"] {
+            builder.add_node(url, PageType::Home, [0.0; FEATURE_DIM], 200);
+        }
+        let mut sitemap = builder.build();
+        flag_blocked(
+            &mut sitemap,
+            &HashSet::from(["https://shop.com/sale".to_string()]),
+        );
+        assert!(!sitemap.nodes[0].flags.is_blocked());
+        assert!(sitemap.nodes[1].flags.is_blocked());
+    }
+
+    #[test]
+    fn test_mark_error_pages() {
+        let mut builder = SiteMapBuilder::new("shop.com");
+        for url in ["https://shop.com/", "https://shop.com/p/gone"] {
+            builder.add_node(url, PageType::ProductDetail, [0.0; FEATURE_DIM], 200);
+        }
+        let mut sitemap = builder.build();
+        mark_error_pages(
+            &mut sitemap,
+            &HashSet::from(["https://shop.com/p/gone".to_string()]),
+            lineage::now(),
+        );
+        assert_eq!(sitemap.nodes[0].page_type, PageType::ProductDetail);
+        assert_eq!(sitemap.nodes[1].page_type, PageType::ErrorPage);
+        let page_type = &sitemap.lineage.node(1).unwrap().fields["page_type"];
+        assert_eq!(page_type.layer, Layer::Pattern);
     }
 }
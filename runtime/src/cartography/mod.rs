@@ -1,10 +1,21 @@
 //! Cartography engine: sitemap parsing, structured data extraction, feature encoding, and map assembly.
 
 pub mod action_encoder;
+pub mod annotations;
+pub mod compliance;
+pub mod content_policy;
 pub mod feature_encoder;
+pub mod feature_plugins;
+pub mod frontier;
+pub mod import;
 pub mod mapper;
 pub mod page_classifier;
+pub mod quality;
 pub mod rate_limiter;
 pub mod robots;
+pub mod semantic;
 pub mod sitemap;
+pub mod snapshots;
+pub mod soft_404;
 pub mod url_classifier;
+pub mod vision_classifier;
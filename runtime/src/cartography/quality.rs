@@ -0,0 +1,289 @@
+//! How good a freshly built map is.
+//!
+//! [`assess`] checks a map four ways, each giving a score from 0 to 1:
+//!
+//! - connectivity: the share of pages reachable from the home page along
+//!   links;
+//! - page types: the share of pages with a known type, halved when one type
+//!   covers nearly the whole of a sizable map;
+//! - feature fill: the share of fetched pages whose content features are
+//!   not all zero (an empty page is usually a JavaScript shell or a block
+//!   page);
+//! - duplicates: one minus the share of nodes that are another node's page
+//!   under a different URL (`www.`, a trailing slash, a fragment).
+//!
+//! The quality score is their weighted mean, and every check below its
+//! threshold adds a warning. MAP with `min_quality` retries a map that
+//! scores lower with the report's [`Fallback`]s, and fails if none of them
+//! reaches it.
+
+use crate::map::merge::DomainAliases;
+use crate::map::types::{PageType, SiteMap, FEAT_STRUCTURED_DATA_RICHNESS, FEAT_TEXT_DENSITY};
+use crate::navigation::reachability;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Weights of connectivity, page types, feature fill and duplicates in the
+/// score.
+const WEIGHTS: [f32; 4] = [0.3, 0.3, 0.25, 0.15];
+
+/// Below this share of reachable pages, connectivity is flagged.
+const MIN_CONNECTIVITY: f32 = 0.5;
+/// Above this share of untyped pages, page types are flagged.
+const MAX_UNKNOWN: f32 = 0.5;
+/// One type covering more than this share of a map of at least
+/// [`DOMINANT_MIN_NODES`] pages is suspect: a classifier that fell back to
+/// a default, or a crawl stuck in one section.
+const MAX_DOMINANT_SHARE: f32 = 0.9;
+const DOMINANT_MIN_NODES: usize = 20;
+/// Below this share of fetched pages with content, feature fill is flagged.
+const MIN_FEATURE_FILL: f32 = 0.5;
+/// Above this share of duplicate nodes, duplicates are flagged.
+const MAX_DUPLICATES: f32 = 0.1;
+
+/// How much more rendering [`Fallback::RenderMore`] allows, and at least
+/// how many pages.
+const RENDER_MORE_FACTOR: u32 = 4;
+const RENDER_MORE_MIN: u32 = 50;
+
+/// The quality of a map.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QualityReport {
+    /// Weighted mean of the checks below, from 0 to 1.
+    pub score: f32,
+    /// Share of pages reachable from the home page. Zero for a map of a
+    /// single page.
+    pub connectivity: f32,
+    /// Share of pages whose type is known.
+    pub typed: f32,
+    /// The most common known page type and its share of all pages.
+    pub dominant_type: Option<String>,
+    pub dominant_share: f32,
+    /// Pages fetched over HTTP or rendered.
+    pub pages_fetched: usize,
+    /// Share of fetched pages with content features.
+    pub feature_fill: f32,
+    /// Share of nodes whose canonical URL another node already has.
+    pub duplicate_ratio: f32,
+    /// One line per check below its threshold.
+    pub warnings: Vec<String>,
+}
+
+/// A way to remap a domain when its map scores too low.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Fallback {
+    /// Continue sampling from the saved frontier: the map ran out of time.
+    Resume,
+    /// Render more pages in the browser, for empty, unlinked or untyped
+    /// pages that plain HTTP could not make sense of.
+    RenderMore,
+}
+
+impl Fallback {
+    /// The render budget for a remap with this fallback.
+    pub fn max_render(self, max_render: u32) -> u32 {
+        match self {
+            Fallback::Resume => max_render,
+            Fallback::RenderMore => max_render
+                .saturating_mul(RENDER_MORE_FACTOR)
+                .max(RENDER_MORE_MIN),
+        }
+    }
+}
+
+impl QualityReport {
+    /// The fallbacks worth trying, in order: resuming when sampling stopped
+    /// early (`resumable`), then rendering more when connectivity, page
+    /// types or feature fill fell short. Duplicates have no fallback.
+    pub fn fallbacks(&self, resumable: bool) -> Vec<Fallback> {
+        let mut fallbacks = Vec::new();
+        if resumable {
+            fallbacks.push(Fallback::Resume);
+        }
+        if self.connectivity < MIN_CONNECTIVITY
+            || self.typed < 1.0 - MAX_UNKNOWN
+            || self.feature_fill < MIN_FEATURE_FILL
+        {
+            fallbacks.push(Fallback::RenderMore);
+        }
+        fallbacks
+    }
+}
+
+/// Score the quality of `map`.
+pub fn assess(map: &SiteMap) -> QualityReport {
+    let n = map.nodes.len();
+    if n == 0 {
+        return QualityReport {
+            warnings: vec!["the map has no pages".to_string()],
+            ..Default::default()
+        };
+    }
+    let mut warnings = Vec::new();
+    let pct = |share: f32| (share * 100.0).round() as u32;
+
+    let connectivity = if n == 1 {
+        warnings.push("the map has a single page".to_string());
+        0.0
+    } else {
+        let structure = reachability::analyze(map);
+        let share = 1.0 - structure.unreachable.len() as f32 / n as f32;
+        if share < MIN_CONNECTIVITY {
+            warnings.push(format!(
+                "{}% of pages are unreachable from the home page",
+                pct(1.0 - share)
+            ));
+        }
+        share
+    };
+
+    let mut counts: HashMap<PageType, usize> = HashMap::new();
+    for node in &map.nodes {
+        *counts.entry(node.page_type).or_default() += 1;
+    }
+    let unknown = counts.get(&PageType::Unknown).copied().unwrap_or(0);
+    let typed = 1.0 - unknown as f32 / n as f32;
+    if typed < 1.0 - MAX_UNKNOWN {
+        warnings.push(format!("{}% of pages have no known type", pct(1.0 - typed)));
+    }
+    let dominant = counts
+        .iter()
+        .filter(|(page_type, _)| **page_type != PageType::Unknown)
+        .max_by_key(|(page_type, count)| (**count, std::cmp::Reverse(**page_type as u8)));
+    let dominant_share = dominant.map_or(0.0, |(_, &count)| count as f32 / n as f32);
+    let mut type_score = typed;
+    if let Some((page_type, _)) = dominant {
+        if dominant_share > MAX_DOMINANT_SHARE && n >= DOMINANT_MIN_NODES {
+            warnings.push(format!(
+                "{}% of pages are typed {page_type}",
+                pct(dominant_share)
+            ));
+            type_score /= 2.0;
+        }
+    }
+
+    let fetched: Vec<usize> = (0..n)
+        .filter(|&i| map.nodes[i].flags.is_rendered())
+        .collect();
+    let filled = fetched
+        .iter()
+        .filter(|&&i| {
+            map.features[i][FEAT_TEXT_DENSITY..=FEAT_STRUCTURED_DATA_RICHNESS]
+                .iter()
+                .any(|&v| v != 0.0)
+        })
+        .count();
+    let feature_fill = if fetched.is_empty() {
+        warnings.push("no page was fetched".to_string());
+        0.0
+    } else {
+        let share = filled as f32 / fetched.len() as f32;
+        if share < MIN_FEATURE_FILL {
+            warnings.push(format!(
+                "{}% of fetched pages yielded no content features",
+                pct(1.0 - share)
+            ));
+        }
+        share
+    };
+
+    let aliases = DomainAliases::new(&map.header.domain);
+    let distinct: HashSet<String> = map
+        .urls
+        .iter()
+        .map(|url| aliases.canonical_url(url))
+        .collect();
+    let duplicate_ratio = 1.0 - distinct.len() as f32 / n as f32;
+    if duplicate_ratio > MAX_DUPLICATES {
+        warnings.push(format!(
+            "{}% of nodes duplicate another node's page",
+            pct(duplicate_ratio)
+        ));
+    }
+
+    let score = WEIGHTS[0] * connectivity
+        + WEIGHTS[1] * type_score
+        + WEIGHTS[2] * feature_fill
+        + WEIGHTS[3] * (1.0 - duplicate_ratio);
+    QualityReport {
+        score,
+        connectivity,
+        typed,
+        dominant_type: dominant.map(|(page_type, _)| page_type.to_string()),
+        dominant_share,
+        pages_fetched: fetched.len(),
+        feature_fill,
+        duplicate_ratio,
+        warnings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::builder::SiteMapBuilder;
+    use crate::map::types::{EdgeFlags, EdgeType, FEATURE_DIM};
+
+    fn content() -> [f32; FEATURE_DIM] {
+        let mut features = [0.0; FEATURE_DIM];
+        features[FEAT_TEXT_DENSITY] = 0.4;
+        features
+    }
+
+    #[test]
+    fn test_well_built_map_scores_high() {
+        let mut b = SiteMapBuilder::new("shop.com");
+        let home = b.add_node("https://shop.com/", PageType::Home, content(), 240);
+        b.set_rendered(home, content());
+        for i in 0..4 {
+            let url = format!("https://shop.com/p/{i}");
+            let node = b.add_node(&url, PageType::ProductDetail, content(), 200);
+            b.set_rendered(node, content());
+            b.add_edge(home, node, EdgeType::Navigation, 1, EdgeFlags::default());
+        }
+        let report = assess(&b.build());
+        assert!(report.score > 0.99, "{report:?}");
+        assert!(report.warnings.is_empty());
+        assert_eq!(report.dominant_type.as_deref(), Some("product_detail"));
+        assert_eq!(report.pages_fetched, 5);
+        assert!(report.fallbacks(false).is_empty());
+    }
+
+    #[test]
+    fn test_poor_map_warns_and_suggests_fallbacks() {
+        let mut b = SiteMapBuilder::new("shop.com");
+        let zero = [0.0; FEATURE_DIM];
+        let home = b.add_node("https://shop.com/", PageType::Home, zero, 240);
+        b.set_rendered(home, zero);
+        b.add_node("https://www.shop.com/a/", PageType::Unknown, zero, 10);
+        b.add_node("https://shop.com/a", PageType::Unknown, zero, 10);
+        b.add_node("https://shop.com/b", PageType::Unknown, zero, 10);
+        let report = assess(&b.build());
+
+        assert_eq!(report.connectivity, 0.25);
+        assert_eq!(report.typed, 0.25);
+        assert_eq!(report.feature_fill, 0.0);
+        assert_eq!(report.duplicate_ratio, 0.25);
+        assert_eq!(report.warnings.len(), 4, "{:?}", report.warnings);
+        assert!(report.score < 0.3, "{report:?}");
+        assert_eq!(
+            report.fallbacks(true),
+            vec![Fallback::Resume, Fallback::RenderMore]
+        );
+        assert_eq!(Fallback::RenderMore.max_render(5), 50);
+        assert_eq!(Fallback::RenderMore.max_render(200), 800);
+    }
+
+    #[test]
+    fn test_single_page_and_empty_maps() {
+        let mut b = SiteMapBuilder::new("shop.com");
+        b.add_node("https://shop.com/", PageType::Home, content(), 240);
+        let report = assess(&b.build());
+        assert_eq!(report.connectivity, 0.0);
+        assert!(report.warnings.iter().any(|w| w.contains("single page")));
+
+        let report = assess(&SiteMapBuilder::new("shop.com").build());
+        assert_eq!(report.score, 0.0);
+    }
+}
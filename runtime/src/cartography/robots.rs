@@ -12,13 +12,18 @@ pub struct RobotsRules {
 impl RobotsRules {
     /// Check if a path is allowed by the robots rules.
     pub fn is_allowed(&self, path: &str) -> bool {
+        self.disallowing(path).is_none()
+    }
+
+    /// The `Disallow` pattern that keeps `path` from being fetched, if any.
+    pub fn disallowing(&self, path: &str) -> Option<&str> {
         // Check disallowed first (more specific wins)
         let mut longest_disallow = 0;
-        let mut is_disallowed = false;
+        let mut disallow = None;
         for pattern in &self.disallowed {
             if path_matches(path, pattern) && pattern.len() > longest_disallow {
                 longest_disallow = pattern.len();
-                is_disallowed = true;
+                disallow = Some(pattern.as_str());
             }
         }
 
@@ -32,13 +37,10 @@ impl RobotsRules {
         }
 
         // Longer match wins
-        if is_allowed && is_disallowed {
-            return longest_allow >= longest_disallow;
-        }
-        if is_disallowed {
-            return false;
+        if is_allowed && longest_allow >= longest_disallow {
+            return None;
         }
-        true
+        disallow
     }
 }
 
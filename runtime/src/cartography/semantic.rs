@@ -0,0 +1,109 @@
+//! Semantic search over page titles and descriptions.
+//!
+//! With a sentence model, the mapper embeds each page's title and
+//! description into a [`MapEmbeddings`] next to the feature matrix, and
+//! QUERY `mode: "semantic"` embeds a free-text goal ("where do I return an
+//! order?") and answers with the pages nearest to it. This complements
+//! `mode: "nearest"`, whose goal vector has to be written in the
+//! hand-crafted feature dimensions.
+//!
+//! Semantic search needs the `semantic` build feature and a
+//! sentence-transformers ONNX export (`model.onnx` and its `vocab.txt`,
+//! e.g. all-MiniLM-L6-v2) in `~/.agentic-vision/models/sentence/`. Without
+//! either, maps have no embeddings and semantic queries are refused.
+
+use crate::map::embeddings::MapEmbeddings;
+use crate::map::text::{MapText, NodeText};
+use anyhow::Result;
+
+/// Embeds text with the sentence model.
+pub struct SemanticEncoder {
+    #[cfg(feature = "semantic")]
+    engine: std::sync::Mutex<agentic_vision::TextEmbeddingEngine>,
+}
+
+impl SemanticEncoder {
+    /// Load the sentence model from its default location, or `None` (with
+    /// the reason logged) if it is missing.
+    #[cfg(feature = "semantic")]
+    pub fn load_default() -> Option<Self> {
+        use agentic_vision::TextEmbeddingEngine;
+
+        match TextEmbeddingEngine::new(None) {
+            Ok(engine) if engine.has_model() => {
+                tracing::info!("semantic search on");
+                Some(Self {
+                    engine: std::sync::Mutex::new(engine),
+                })
+            }
+            Ok(_) => None,
+            Err(e) => {
+                tracing::warn!("failed to load the sentence model: {e}; semantic search is off");
+                None
+            }
+        }
+    }
+
+    /// Without the `semantic` feature there is no encoder.
+    #[cfg(not(feature = "semantic"))]
+    pub fn load_default() -> Option<Self> {
+        None
+    }
+
+    /// Embed `text` as a unit-length vector. Runs the model, so call it off
+    /// the async runtime.
+    #[cfg(feature = "semantic")]
+    pub fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.engine
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .embed(text)
+            .map_err(|e| anyhow::anyhow!("embedding text: {e}"))?
+            .ok_or_else(|| anyhow::anyhow!("no sentence model loaded"))
+    }
+
+    /// Without the `semantic` feature nothing is embedded.
+    #[cfg(not(feature = "semantic"))]
+    pub fn embed(&self, _text: &str) -> Result<Vec<f32>> {
+        anyhow::bail!("built without the semantic feature")
+    }
+
+    /// Embed the text of every page in `text`. Pages that fail to embed are
+    /// left out, with the failure logged.
+    pub fn embed_map(&self, text: &MapText) -> MapEmbeddings {
+        let mut embeddings = MapEmbeddings::default();
+        for (&node, node_text) in &text.nodes {
+            let inserted = self
+                .embed(&sentence(node_text))
+                .and_then(|v| embeddings.insert(node, v));
+            if let Err(e) = inserted {
+                tracing::warn!("no embedding for node {node}: {e:#}");
+            }
+        }
+        embeddings
+    }
+}
+
+/// The sentence a page is embedded as: its title, then its description.
+pub fn sentence(text: &NodeText) -> String {
+    match (&text.title, &text.description) {
+        (Some(title), Some(description)) => format!("{title}. {description}"),
+        (Some(only), None) | (None, Some(only)) => only.clone(),
+        (None, None) => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sentence() {
+        let text = NodeText::new([Some("Returns")], [Some("Send an order back")]);
+        assert_eq!(sentence(&text), "Returns. Send an order back");
+        assert_eq!(
+            sentence(&NodeText::new([None], [Some("Send an order back")])),
+            "Send an order back"
+        );
+    }
+}
@@ -0,0 +1,399 @@
+//! HTML snapshots of mapped pages, so they can be looked at again without
+//! refetching.
+//!
+//! Once a map is built, the mapper stores the HTML of every page it fetched
+//! over HTTP or rendered in the browser under
+//! `~/.cortex/snapshots/<domain>/<page>/`, one zstd-compressed file per
+//! fetch: `<unix millis>-<source>-<content hash>.html.zst`. Node indices
+//! change from one map to the next, so pages are filed by URL (`<page>` is a
+//! hash of it, and the directory's `url` file holds the URL itself);
+//! `cortex inspect <domain> <node> --html` looks the node's URL up in the
+//! cached map. A fetch whose HTML is the same as the page's latest snapshot
+//! is not stored again.
+//!
+//! Each page keeps its newest `keep` snapshots, and none older than
+//! `max_age`; both come from the environment (see [`Retention::from_env`]).
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// zstd level for snapshots: written once and read rarely, so size matters
+/// more than for frontier checkpoints.
+const LEVEL: i32 = 9;
+
+/// Snapshots kept per page by default.
+const DEFAULT_KEEP: usize = 3;
+/// Default maximum snapshot age, in days.
+const DEFAULT_MAX_AGE_DAYS: u64 = 30;
+
+/// How a snapshot's HTML was obtained.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotSource {
+    /// The body of a plain HTTP fetch.
+    Http,
+    /// The DOM after a browser render.
+    Rendered,
+}
+
+impl SnapshotSource {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Http => "http",
+            Self::Rendered => "rendered",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "http" => Some(Self::Http),
+            "rendered" => Some(Self::Rendered),
+            _ => None,
+        }
+    }
+}
+
+/// One stored snapshot.
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotInfo {
+    pub url: String,
+    pub fetched_at: DateTime<Utc>,
+    pub source: SnapshotSource,
+    /// Size of the compressed file.
+    pub bytes: u64,
+    pub path: PathBuf,
+}
+
+/// How many snapshots to keep per page, and for how long.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Retention {
+    /// Newest snapshots kept per page. Zero turns snapshots off.
+    pub keep: usize,
+    /// Snapshots older than this are removed. `None` keeps them regardless
+    /// of age.
+    pub max_age: Option<Duration>,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            keep: DEFAULT_KEEP,
+            max_age: Some(Duration::from_secs(DEFAULT_MAX_AGE_DAYS * 86_400)),
+        }
+    }
+}
+
+impl Retention {
+    /// Defaults overridden by `CORTEX_SNAPSHOT_KEEP` and
+    /// `CORTEX_SNAPSHOT_MAX_AGE_DAYS` (0 for no age limit).
+    pub fn from_env() -> Self {
+        let days = read_env("CORTEX_SNAPSHOT_MAX_AGE_DAYS", DEFAULT_MAX_AGE_DAYS);
+        Self {
+            keep: read_env("CORTEX_SNAPSHOT_KEEP", DEFAULT_KEEP),
+            max_age: (days > 0).then(|| Duration::from_secs(days * 86_400)),
+        }
+    }
+}
+
+fn read_env<T: std::str::FromStr>(name: &str, default_value: T) -> T {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(default_value)
+}
+
+/// The snapshot directory and its retention rules.
+#[derive(Debug, Clone)]
+pub struct SnapshotStore {
+    dir: PathBuf,
+    retention: Retention,
+}
+
+impl SnapshotStore {
+    pub fn new(dir: impl Into<PathBuf>, retention: Retention) -> Self {
+        Self {
+            dir: dir.into(),
+            retention,
+        }
+    }
+
+    /// The store configured by the environment: `CORTEX_SNAPSHOT_DIR` or
+    /// [`default_dir`], with [`Retention::from_env`].
+    pub fn from_env() -> Self {
+        let dir = std::env::var("CORTEX_SNAPSHOT_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| default_dir());
+        Self::new(dir, Retention::from_env())
+    }
+
+    /// Whether new snapshots are taken at all (`keep` is not 0).
+    pub fn enabled(&self) -> bool {
+        self.retention.keep > 0
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Store `html` as the snapshot of `url` fetched at `fetched_at`, then
+    /// apply retention to the page. Returns false, storing nothing, when
+    /// the HTML is the same as the page's latest snapshot.
+    pub fn save(
+        &self,
+        domain: &str,
+        url: &str,
+        source: SnapshotSource,
+        html: &str,
+        fetched_at: DateTime<Utc>,
+    ) -> Result<bool> {
+        let page_dir = self.page_dir(domain, url);
+        let hash = hex(&Sha256::digest(html.as_bytes())[..8]);
+        if let Some(latest) = entries(&page_dir).into_iter().next() {
+            if latest.hash == hash {
+                return Ok(false);
+            }
+        }
+
+        std::fs::create_dir_all(&page_dir)
+            .with_context(|| format!("creating snapshot directory {}", page_dir.display()))?;
+        let url_file = page_dir.join("url");
+        if !url_file.exists() {
+            std::fs::write(&url_file, url)
+                .with_context(|| format!("writing {}", url_file.display()))?;
+        }
+        let data = zstd::bulk::compress(html.as_bytes(), LEVEL)?;
+        let path = page_dir.join(format!(
+            "{}-{}-{hash}.html.zst",
+            fetched_at.timestamp_millis(),
+            source.as_str()
+        ));
+        // Write-then-rename so a crash mid-write leaves no half snapshot.
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, data).with_context(|| format!("writing {}", tmp.display()))?;
+        std::fs::rename(&tmp, &path)?;
+
+        self.prune(&page_dir, Utc::now());
+        Ok(true)
+    }
+
+    /// The snapshots of `url`, newest first.
+    pub fn list(&self, domain: &str, url: &str) -> Vec<SnapshotInfo> {
+        entries(&self.page_dir(domain, url))
+            .into_iter()
+            .map(|e| e.info(url))
+            .collect()
+    }
+
+    /// The newest snapshot of `url` and its HTML, if there is one.
+    pub fn latest(&self, domain: &str, url: &str) -> Result<Option<(SnapshotInfo, String)>> {
+        match self.list(domain, url).into_iter().next() {
+            Some(info) => {
+                let html = Self::read(&info)?;
+                Ok(Some((info, html)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// The HTML of a snapshot.
+    pub fn read(info: &SnapshotInfo) -> Result<String> {
+        let raw = std::fs::read(&info.path)
+            .with_context(|| format!("reading {}", info.path.display()))?;
+        let html = zstd::decode_all(raw.as_slice())
+            .with_context(|| format!("decompressing {}", info.path.display()))?;
+        Ok(String::from_utf8_lossy(&html).into_owned())
+    }
+
+    /// Delete the page's snapshots beyond the newest `keep`, and those
+    /// older than `max_age` at `now`.
+    fn prune(&self, page_dir: &Path, now: DateTime<Utc>) {
+        let cutoff = self
+            .retention
+            .max_age
+            .and_then(|age| chrono::Duration::from_std(age).ok())
+            .map(|age| now - age);
+        for (i, entry) in entries(page_dir).into_iter().enumerate() {
+            let expired = cutoff.is_some_and(|cutoff| entry.fetched_at < cutoff);
+            if i >= self.retention.keep || expired {
+                let _ = std::fs::remove_file(&entry.path);
+            }
+        }
+    }
+
+    fn page_dir(&self, domain: &str, url: &str) -> PathBuf {
+        self.dir
+            .join(domain.replace([':', '/', '\\'], "_"))
+            .join(hex(&Sha256::digest(url.as_bytes())[..8]))
+    }
+}
+
+/// Default snapshot directory (~/.cortex/snapshots).
+pub fn default_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".cortex")
+        .join("snapshots")
+}
+
+/// A snapshot file, as named on disk.
+struct Entry {
+    path: PathBuf,
+    fetched_at: DateTime<Utc>,
+    source: SnapshotSource,
+    hash: String,
+}
+
+impl Entry {
+    fn info(self, url: &str) -> SnapshotInfo {
+        let bytes = std::fs::metadata(&self.path).map_or(0, |m| m.len());
+        SnapshotInfo {
+            url: url.to_string(),
+            fetched_at: self.fetched_at,
+            source: self.source,
+            bytes,
+            path: self.path,
+        }
+    }
+}
+
+/// The snapshot files in `page_dir`, newest first.
+fn entries(page_dir: &Path) -> Vec<Entry> {
+    let Ok(dir) = std::fs::read_dir(page_dir) else {
+        return Vec::new();
+    };
+    let mut entries: Vec<Entry> = dir
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let path = e.path();
+            let name = path.file_name()?.to_str()?.strip_suffix(".html.zst")?;
+            let mut parts = name.splitn(3, '-');
+            let millis: i64 = parts.next()?.parse().ok()?;
+            let source = SnapshotSource::parse(parts.next()?)?;
+            let hash = parts.next()?.to_string();
+            let fetched_at = Utc.timestamp_millis_opt(millis).single()?;
+            Some(Entry {
+                path,
+                fetched_at,
+                source,
+                hash,
+            })
+        })
+        .collect();
+    // A page fetched and rendered in the same map: the render is newer.
+    entries
+        .sort_by_key(|e| std::cmp::Reverse((e.fetched_at, e.source == SnapshotSource::Rendered)));
+    entries
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_700_000_000 + secs, 0).unwrap()
+    }
+
+    #[test]
+    fn test_save_list_and_dedupe() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SnapshotStore::new(dir.path(), Retention::default());
+        let url = "https://shop.com/p/1";
+        assert!(store.latest("shop.com", url).unwrap().is_none());
+
+        let now = Utc::now();
+        assert!(store
+            .save(
+                "shop.com",
+                url,
+                SnapshotSource::Http,
+                "<html>v1</html>",
+                now
+            )
+            .unwrap());
+        assert!(
+            !store
+                .save(
+                    "shop.com",
+                    url,
+                    SnapshotSource::Http,
+                    "<html>v1</html>",
+                    now + chrono::Duration::seconds(1)
+                )
+                .unwrap(),
+            "unchanged HTML is not stored again"
+        );
+        assert!(store
+            .save(
+                "shop.com",
+                url,
+                SnapshotSource::Rendered,
+                "<html>v2</html>",
+                now + chrono::Duration::seconds(2)
+            )
+            .unwrap());
+
+        let list = store.list("shop.com", url);
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].source, SnapshotSource::Rendered);
+        assert!(list[0].fetched_at > list[1].fetched_at);
+        let (info, html) = store.latest("shop.com", url).unwrap().unwrap();
+        assert_eq!(html, "<html>v2</html>");
+        assert_eq!(info.url, url);
+        assert_eq!(SnapshotStore::read(&list[1]).unwrap(), "<html>v1</html>");
+        assert!(store.list("shop.com", "https://shop.com/other").is_empty());
+    }
+
+    #[test]
+    fn test_retention_keeps_newest_and_drops_old() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SnapshotStore::new(
+            dir.path(),
+            Retention {
+                keep: 2,
+                max_age: None,
+            },
+        );
+        let url = "https://shop.com/";
+        for i in 0..4 {
+            let html = format!("<html>{i}</html>");
+            store
+                .save("shop.com", url, SnapshotSource::Http, &html, at(i))
+                .unwrap();
+        }
+        let list = store.list("shop.com", url);
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].fetched_at, at(3));
+        assert_eq!(list[1].fetched_at, at(2));
+
+        let aged = SnapshotStore::new(
+            dir.path(),
+            Retention {
+                keep: 10,
+                max_age: Some(Duration::from_secs(86_400)),
+            },
+        );
+        // Both earlier snapshots are far older than a day.
+        aged.save(
+            "shop.com",
+            url,
+            SnapshotSource::Http,
+            "<html>new</html>",
+            Utc::now(),
+        )
+        .unwrap();
+        let list = aged.list("shop.com", url);
+        assert_eq!(list.len(), 1);
+        assert_eq!(
+            aged.latest("shop.com", url).unwrap().unwrap().1,
+            "<html>new</html>"
+        );
+    }
+}
@@ -0,0 +1,309 @@
+//! Soft-404 detection: pages that answer 200 but say the page is missing.
+//!
+//! Many sites serve their "not found" page with status 200, so a crawl that
+//! follows stale links maps copies of it as products or articles.
+//! [`Soft404Detector`] recognizes such pages two ways:
+//!
+//! - by what they say: a title like "Page not found" or "Error 404", or a
+//!   short page whose main heading says so;
+//! - by likeness to the site's own error page: the answer to a URL that
+//!   cannot exist (see [`probe_url`]) or any page that came back 404 or
+//!   410. A page whose text is nearly the same is the same template.
+//!
+//! The mapper types the pages it flags [`PageType::ErrorPage`](crate::map::types::PageType),
+//! which keeps them out of schema inference and, unless asked for by type,
+//! out of QUERY results.
+
+use crate::acquisition::http_client::HttpResponse;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+/// Share of word shingles a page must have in common with an error page
+/// template to be one.
+pub const MIN_SIMILARITY: f32 = 0.85;
+
+/// Pages of more words than this are not judged by their heading.
+const SHORT_PAGE_WORDS: usize = 300;
+
+/// Error page templates kept; more rarely differ.
+const MAX_TEMPLATES: usize = 4;
+
+/// Words per shingle when comparing page text.
+const SHINGLE: usize = 3;
+
+/// Phrases of a missing-page title or heading, lowercased. They are
+/// matched word for word, punctuation aside.
+const MISSING_PHRASES: &[&str] = &[
+    "page not found",
+    "not found",
+    "page does not exist",
+    "page doesn't exist",
+    "page cannot be found",
+    "page can't be found",
+    "page could not be found",
+    "page no longer exists",
+    "nothing was found",
+    "error 404",
+    "404 error",
+];
+
+/// A URL on `domain` no site has a page for, whose answer is the site's
+/// error page.
+pub fn probe_url(domain: &str) -> String {
+    format!(
+        "https://{domain}/cortex-missing-{:016x}",
+        rand::random::<u64>()
+    )
+}
+
+/// Recognizes a site's soft-404 pages.
+#[derive(Debug, Clone, Default)]
+pub struct Soft404Detector {
+    /// Shingles of known error pages.
+    templates: Vec<HashSet<u64>>,
+}
+
+impl Soft404Detector {
+    /// Learn `html` as the site's error page template.
+    pub fn learn(&mut self, html: &str) {
+        if self.templates.len() >= MAX_TEMPLATES {
+            return;
+        }
+        let shingles = shingles(&words(html));
+        if !shingles.is_empty() && !self.templates.contains(&shingles) {
+            self.templates.push(shingles);
+        }
+    }
+
+    /// Learn from a response: the answer to [`probe_url`] with status 200,
+    /// or any page with status 404 or 410. A probe answered with a page
+    /// that does not say it is missing — the home page a site redirects
+    /// to, or a JavaScript shell every URL gets — teaches nothing. Returns
+    /// whether a template was learned.
+    pub fn learn_response(&mut self, resp: &HttpResponse, probe: bool) -> bool {
+        if !resp.is_html() || resp.body.is_empty() {
+            return false;
+        }
+        let learn = match resp.status {
+            404 | 410 => true,
+            200 => probe && says_missing(&words(&resp.body).join(" ")),
+            _ => false,
+        };
+        if learn {
+            self.learn(&resp.body);
+        }
+        learn
+    }
+
+    /// Whether a page that came back 200 with `html` is an error page.
+    pub fn is_soft_404(&self, html: &str) -> bool {
+        let lower = html.to_lowercase();
+        if tag_text(&lower, "title").is_some_and(|title| is_missing_heading(&title)) {
+            return true;
+        }
+        let words = words(html);
+        if words.len() <= SHORT_PAGE_WORDS
+            && tag_text(&lower, "h1").is_some_and(|h1| is_missing_heading(&h1))
+        {
+            return true;
+        }
+        if self.templates.is_empty() {
+            return false;
+        }
+        let page = shingles(&words);
+        self.templates
+            .iter()
+            .any(|template| similarity(&page, template) >= MIN_SIMILARITY)
+    }
+}
+
+/// Whether a title or heading says the page is missing: a missing-page
+/// phrase, or a bare "404".
+fn is_missing_heading(text: &str) -> bool {
+    let text = text.trim();
+    let short_with_404 = text.len() <= 40
+        && text
+            .split(|c: char| !c.is_alphanumeric())
+            .any(|w| w == "404");
+    text.starts_with("404") || short_with_404 || has_missing_phrase(text)
+}
+
+/// Whether page text mentions a missing page anywhere.
+fn says_missing(text: &str) -> bool {
+    text.contains("404") || has_missing_phrase(text)
+}
+
+/// Whether `text`, words joined by spaces, has a missing-page phrase.
+fn has_missing_phrase(text: &str) -> bool {
+    MISSING_PHRASES
+        .iter()
+        .any(|phrase| text.contains(&words(phrase).join(" ")))
+}
+
+/// The text of the first `<tag>` element of lowercased `html`, tags inside
+/// it removed.
+fn tag_text(html: &str, tag: &str) -> Option<String> {
+    let open = format!("<{tag}");
+    let mut from = 0;
+    let start = loop {
+        let at = from + html[from..].find(&open)?;
+        let after = html[at + open.len()..].chars().next()?;
+        if after == '>' || after.is_whitespace() {
+            break at;
+        }
+        from = at + open.len();
+    };
+    let content = start + html[start..].find('>')? + 1;
+    let end = content + html[content..].find(&format!("</{tag}"))?;
+    Some(words(&html[content..end]).join(" "))
+}
+
+/// The visible words of `html`, lowercased: scripts, styles and tags
+/// removed.
+fn words(html: &str) -> Vec<String> {
+    let lower = html.to_lowercase();
+    let mut text = String::with_capacity(lower.len() / 2);
+    let mut rest = lower.as_str();
+    while let Some(open) = rest.find('<') {
+        text.push_str(&rest[..open]);
+        text.push(' ');
+        rest = &rest[open..];
+        let skip_to = ["script", "style", "noscript"]
+            .iter()
+            .find(|tag| {
+                rest[1..].starts_with(*tag)
+                    && rest[1 + tag.len()..].starts_with(|c: char| c == '>' || c.is_whitespace())
+            })
+            .and_then(|tag| rest.find(&format!("</{tag}")));
+        let tag_start = skip_to.unwrap_or(0);
+        match rest[tag_start..].find('>') {
+            Some(close) => rest = &rest[tag_start + close + 1..],
+            None => {
+                rest = "";
+                break;
+            }
+        }
+    }
+    text.push_str(rest);
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Hashes of the runs of [`SHINGLE`] words of `words`; of the words
+/// themselves for shorter texts.
+fn shingles(words: &[String]) -> HashSet<u64> {
+    let hash = |run: &[String]| {
+        let mut hasher = DefaultHasher::new();
+        run.hash(&mut hasher);
+        hasher.finish()
+    };
+    if words.len() < SHINGLE {
+        return words.chunks(1).map(hash).collect();
+    }
+    words.windows(SHINGLE).map(hash).collect()
+}
+
+/// Jaccard similarity of two shingle sets.
+fn similarity(a: &HashSet<u64>, b: &HashSet<u64>) -> f32 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f32 / union as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status: u16, body: &str) -> HttpResponse {
+        HttpResponse {
+            url: "https://shop.com/x".to_string(),
+            final_url: "https://shop.com/x".to_string(),
+            status,
+            headers: Vec::new(),
+            body: body.to_string(),
+            mime: "text/html".to_string(),
+            truncated: false,
+            blocked: None,
+        }
+    }
+
+    fn page(title: &str, h1: &str, body: &str) -> String {
+        format!(
+            "<html><head><title>{title}</title><script>var t = 'not found';</script></head>\
+             <body><nav>Home Shop Sale Contact</nav><h1>{h1}</h1><p>{body}</p>\
+             <footer>Copyright Shop Inc. All rights reserved.</footer></body></html>"
+        )
+    }
+
+    #[test]
+    fn test_missing_titles_and_headings() {
+        let detector = Soft404Detector::default();
+        assert!(detector.is_soft_404(&page("Page Not Found | Shop", "Oops", "")));
+        assert!(detector.is_soft_404(&page("Shop", "404", "Try the search.")));
+        assert!(detector.is_soft_404(&page("Shop", "Sorry, this page doesn't exist", "")));
+        assert!(!detector.is_soft_404(&page(
+            "Wireless Headphones | Shop",
+            "Wireless Headphones",
+            "Noise cancelling, 30 hours of battery."
+        )));
+        // "Not found" in a script or a long article's heading is not enough
+        assert!(!detector.is_soft_404(&page(
+            "The 404 Club: a history of the internet's lost pages and the people who archive them",
+            "Where did the page go?",
+            ""
+        )));
+        let long = page(
+            "Lost and found",
+            "Page not found? Try our guide",
+            &"word ".repeat(SHORT_PAGE_WORDS + 1),
+        );
+        assert!(!detector.is_soft_404(&long));
+    }
+
+    #[test]
+    fn test_template_similarity() {
+        let error_page = page(
+            "Shop",
+            "Oops!",
+            "We looked everywhere for this item. Head back to the home page.",
+        );
+        let mut detector = Soft404Detector::default();
+        assert!(!detector.is_soft_404(&error_page));
+
+        assert!(detector.learn_response(&response(404, &error_page), false));
+        assert!(detector.is_soft_404(&error_page));
+        assert!(!detector.is_soft_404(&page(
+            "Garden Chairs | Shop",
+            "Garden Chairs",
+            "Teak chairs for the garden, in three sizes, delivered free."
+        )));
+    }
+
+    #[test]
+    fn test_probe_must_say_missing() {
+        let mut detector = Soft404Detector::default();
+        let shell = "<html><body><div id=\"app\"></div></body></html>";
+        assert!(!detector.learn_response(&response(200, shell), true));
+        let home = page("Shop", "Welcome", "Our new collection is here.");
+        assert!(!detector.learn_response(&response(200, &home), true));
+        assert!(detector.templates.is_empty());
+
+        let missing = page("Shop", "Oops", "The page you asked for is not found.");
+        assert!(!detector.learn_response(&response(200, &missing), false));
+        assert!(detector.learn_response(&response(200, &missing), true));
+        assert_eq!(detector.templates.len(), 1);
+    }
+
+    #[test]
+    fn test_words_skip_scripts_and_tags() {
+        let words =
+            words("<p>Hello <b>World</b></p><script>var x = 1;</script><style>p{}</style>!");
+        assert_eq!(words, ["hello", "world"]);
+        assert!(probe_url("shop.com").starts_with("https://shop.com/cortex-missing-"));
+    }
+}
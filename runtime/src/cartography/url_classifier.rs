@@ -1,6 +1,23 @@
 //! Classify URLs by pattern into PageType.
+//!
+//! [`classify_url`] knows the URL schemes many sites share (`/dp/`,
+//! `/blog/`, `/cart`). A [`UrlTypeModel`] learns a domain's own scheme from
+//! pages whose type their content confirmed, for the URLs a map never
+//! fetches.
 
 use crate::map::types::PageType;
+use std::collections::HashMap;
+
+/// Confirmed pages a template needs before it becomes a rule.
+const MIN_SUPPORT: usize = 2;
+/// Share of a template's confirmed pages that must agree on the type.
+const MIN_PURITY: f32 = 0.8;
+/// Confidence below which a page's type does not count as confirmed.
+const MIN_CONFIRMED: f32 = 0.6;
+/// Ceiling for learned confidence, below what structured data gives.
+const MAX_LEARNED_CONFIDENCE: f32 = 0.85;
+/// Discount for rules keyed on a path's first segment and depth only.
+const SHAPE_DISCOUNT: f32 = 0.9;
 
 /// Classify a URL into a page type and confidence score.
 pub fn classify_url(url: &str, _domain: &str) -> (PageType, f32) {
@@ -144,6 +161,145 @@ pub fn classify_url(url: &str, _domain: &str) -> (PageType, f32) {
     (PageType::Unknown, 0.3)
 }
 
+/// URL-pattern → page type rules learned from one domain's pages.
+///
+/// Every confirmed URL is reduced to a template, its segments that look
+/// like numbers, identifiers or slugs replaced by wildcards
+/// (`/en/catalogue/{slug}`), and to a coarser shape, its first segment and
+/// depth (`/en/…/3`). A template or shape becomes a rule once enough of its
+/// confirmed pages agree on a type; the confidence grows with the support.
+#[derive(Debug, Clone, Default)]
+pub struct UrlTypeModel {
+    rules: HashMap<String, (PageType, f32)>,
+}
+
+impl UrlTypeModel {
+    /// Learn from `(url, page type, confidence)` of pages whose type was
+    /// confirmed. Unknown types and confidences below 0.6 are ignored.
+    pub fn learn<'a>(confirmed: impl IntoIterator<Item = (&'a str, PageType, f32)>) -> Self {
+        let mut counts: HashMap<String, HashMap<PageType, usize>> = HashMap::new();
+        for (url, page_type, confidence) in confirmed {
+            if page_type == PageType::Unknown || confidence < MIN_CONFIRMED {
+                continue;
+            }
+            for key in keys(url) {
+                *counts.entry(key).or_default().entry(page_type).or_default() += 1;
+            }
+        }
+
+        let rules = counts
+            .into_iter()
+            .filter_map(|(key, types)| {
+                let total: usize = types.values().sum();
+                let (page_type, count) = types.into_iter().max_by_key(|&(pt, n)| (n, pt as u8))?;
+                let purity = count as f32 / total as f32;
+                if total < MIN_SUPPORT || purity < MIN_PURITY {
+                    return None;
+                }
+                let mut confidence =
+                    (purity * (1.0 - 1.0 / (total as f32 + 1.0))).min(MAX_LEARNED_CONFIDENCE);
+                if key.ends_with(|c: char| c.is_ascii_digit()) {
+                    confidence *= SHAPE_DISCOUNT;
+                }
+                Some((key, (page_type, confidence)))
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// The learned type of `url`, with its confidence and the template or
+    /// shape it matched, if a rule covers it. Templates win over shapes.
+    pub fn classify(&self, url: &str) -> Option<(PageType, f32, &str)> {
+        keys(url).into_iter().find_map(|key| {
+            self.rules
+                .get_key_value(&key)
+                .map(|(key, &(page_type, confidence))| (page_type, confidence, key.as_str()))
+        })
+    }
+
+    /// Number of rules learned.
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+/// Classify `url` with [`classify_url`], unless `model` has learned a type
+/// for it with more confidence. The template is returned for learned types.
+pub fn classify_url_learned<'m>(
+    model: &'m UrlTypeModel,
+    url: &str,
+    domain: &str,
+) -> (PageType, f32, Option<&'m str>) {
+    let (page_type, confidence) = classify_url(url, domain);
+    match model.classify(url) {
+        Some((learned, learned_confidence, template)) if learned_confidence > confidence => {
+            (learned, learned_confidence, Some(template))
+        }
+        _ => (page_type, confidence, None),
+    }
+}
+
+/// The template and the shape of `url`'s path, most specific first. The
+/// home page has neither; single-segment paths have no shape.
+fn keys(url: &str) -> Vec<String> {
+    let path = extract_path(url).to_lowercase();
+    let path = path.split(['?', '#']).next().unwrap_or("");
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let Some(first) = segments.first() else {
+        return Vec::new();
+    };
+
+    let template: Vec<String> = segments.iter().map(|s| generalize(s)).collect();
+    let mut keys = vec![format!("/{}", template.join("/"))];
+    if segments.len() >= 2 && template[0] == *first {
+        keys.push(format!("/{first}/…/{}", segments.len()));
+    }
+    keys
+}
+
+/// A path segment, or the wildcard for what it looks like: `{n}` for
+/// numbers, `{id}` for identifiers, `{slug}` for slugs. A file extension is
+/// kept.
+fn generalize(segment: &str) -> String {
+    let (stem, ext) = match segment.rsplit_once('.') {
+        Some((stem, ext))
+            if !stem.is_empty()
+                && (1..=5).contains(&ext.len())
+                && ext.chars().all(|c| c.is_ascii_alphanumeric()) =>
+        {
+            (stem, Some(ext))
+        }
+        _ => (segment, None),
+    };
+    let is_word_char = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    let words = stem.split(['-', '_']).filter(|w| !w.is_empty()).count();
+    let wildcard = if !stem.is_empty() && stem.chars().all(|c| c.is_ascii_digit()) {
+        Some("{n}")
+    } else if !stem.chars().all(is_word_char) {
+        None
+    } else if words >= 3 {
+        Some("{slug}")
+    } else if stem.len() >= 6
+        && stem.chars().any(|c| c.is_ascii_digit())
+        && stem.chars().any(|c| c.is_ascii_alphabetic())
+    {
+        Some("{id}")
+    } else if stem.len() >= 24 {
+        Some("{slug}")
+    } else {
+        None
+    };
+    match (wildcard, ext) {
+        (Some(w), Some(ext)) => format!("{w}.{ext}"),
+        (Some(w), None) => w.to_string(),
+        (None, _) => segment.to_string(),
+    }
+}
+
 fn extract_path(url: &str) -> &str {
     // Simple path extraction without parsing the full URL
     if let Some(rest) = url
@@ -201,4 +357,93 @@ mod tests {
             PageType::Unknown
         );
     }
+
+    #[test]
+    fn test_template_generalization() {
+        assert_eq!(
+            keys("https://shop.com/en/catalogue/red-wool-scarf?ref=nav"),
+            vec!["/en/catalogue/{slug}", "/en/…/3"]
+        );
+        assert_eq!(
+            keys("https://shop.com/item/B07XJ8C8F5"),
+            vec!["/item/{id}", "/item/…/2"]
+        );
+        assert_eq!(
+            keys("https://news.com/2024/123.html"),
+            vec!["/{n}/{n}.html"]
+        );
+        assert_eq!(keys("https://shop.com/about"), vec!["/about"]);
+        assert!(keys("https://shop.com/").is_empty());
+    }
+
+    #[test]
+    fn test_learned_rules_cover_unfetched_urls() {
+        let confirmed = [
+            (
+                "https://shop.com/en/catalogue/red-wool-scarf",
+                PageType::ProductDetail,
+                0.95,
+            ),
+            (
+                "https://shop.com/en/catalogue/blue-cotton-shirt",
+                PageType::ProductDetail,
+                0.9,
+            ),
+            (
+                "https://shop.com/en/catalogue/green-linen-trousers",
+                PageType::ProductDetail,
+                0.9,
+            ),
+            (
+                "https://shop.com/en/journal/how-to-knit-socks",
+                PageType::Article,
+                0.9,
+            ),
+            (
+                "https://shop.com/en/journal/winter-lookbook-2024",
+                PageType::Article,
+                0.9,
+            ),
+            // Too unsure to count, and a lone template.
+            (
+                "https://shop.com/en/journal/spring-sale-notes",
+                PageType::Faq,
+                0.3,
+            ),
+            ("https://shop.com/stores/london", PageType::ContactPage, 0.9),
+        ];
+        let model = UrlTypeModel::learn(confirmed);
+        assert!(!model.is_empty());
+
+        let (pt, conf, template) = model
+            .classify("https://shop.com/en/catalogue/black-leather-boots")
+            .unwrap();
+        assert_eq!(pt, PageType::ProductDetail);
+        assert_eq!(template, "/en/catalogue/{slug}");
+        assert!(conf > 0.7 && conf <= MAX_LEARNED_CONFIDENCE);
+        assert_eq!(
+            model
+                .classify("https://shop.com/en/journal/packing-for-the-alps")
+                .unwrap()
+                .0,
+            PageType::Article
+        );
+        // "/en/…/3" mixes products and articles.
+        assert!(model
+            .classify("https://shop.com/en/lookbook/autumn")
+            .is_none());
+        assert!(model.classify("https://shop.com/stores/paris").is_none());
+
+        // Learned types only override less confident heuristics.
+        let (pt, _, template) = classify_url_learned(
+            &model,
+            "https://shop.com/en/catalogue/grey-wool-hat",
+            "shop.com",
+        );
+        assert_eq!(pt, PageType::ProductDetail);
+        assert!(template.is_some());
+        let (pt, _, template) = classify_url_learned(&model, "https://shop.com/cart", "shop.com");
+        assert_eq!(pt, PageType::Cart);
+        assert!(template.is_none());
+    }
 }
@@ -0,0 +1,218 @@
+//! Vision fallback for page classification.
+//!
+//! When the text and structured-data classifiers are unsure of a rendered
+//! page (confidence below [`LOW_CONFIDENCE`]), the mapper screenshots it,
+//! embeds the screenshot with the agentic-vision CLIP engine, and labels it
+//! by its nearest neighbours in a reference set: an `.avis` file of page
+//! screenshots, each labelled with a page type name (`product_detail`,
+//! `login`, …). Capture them with the agentic-vision MCP server, passing the
+//! page type as a label, and save the file as `~/.cortex/vision/reference.avis`.
+//!
+//! The fallback needs the `vision` build feature, a CLIP ONNX model in
+//! `~/.agentic-vision/models/`, and a reference set with labelled
+//! screenshots. Without any of them the mapper keeps the text classification.
+
+use crate::map::types::PageType;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// Pages classified below this confidence get a second look from vision.
+pub const LOW_CONFIDENCE: f32 = 0.5;
+
+/// References that vote on a screenshot's page type.
+const NEIGHBOURS: usize = 5;
+
+/// References less similar than this do not vote.
+const MIN_SIMILARITY: f32 = 0.7;
+
+/// Classifies page screenshots against a labelled reference set.
+pub struct VisionClassifier {
+    #[cfg(feature = "vision")]
+    engine: std::sync::Mutex<agentic_vision::EmbeddingEngine>,
+    /// Page type and embedding of each labelled reference screenshot.
+    #[cfg(feature = "vision")]
+    references: Vec<(PageType, Vec<f32>)>,
+}
+
+impl VisionClassifier {
+    /// Load the CLIP model and the reference set from their default
+    /// locations, or `None` (with the reason logged) if either is missing.
+    pub fn load_default() -> Option<Self> {
+        Self::load(&default_reference_path())
+    }
+
+    /// Load the CLIP model and the reference set at `path`.
+    #[cfg(feature = "vision")]
+    pub fn load(path: &Path) -> Option<Self> {
+        use agentic_vision::{AvisReader, EmbeddingEngine};
+        use tracing::{info, warn};
+
+        if !path.exists() {
+            info!(
+                "no vision reference set at {}; vision classification is off",
+                path.display()
+            );
+            return None;
+        }
+        let store = match AvisReader::read_from_file(path) {
+            Ok(store) => store,
+            Err(e) => {
+                warn!(
+                    "failed to read vision reference set {}: {e}",
+                    path.display()
+                );
+                return None;
+            }
+        };
+        let references: Vec<(PageType, Vec<f32>)> = store
+            .observations
+            .into_iter()
+            .filter(|o| o.embedding.iter().any(|v| *v != 0.0))
+            .filter_map(|o| {
+                let page_type = o
+                    .metadata
+                    .labels
+                    .iter()
+                    .find_map(|l| page_type_of_label(l))?;
+                Some((page_type, o.embedding))
+            })
+            .collect();
+        if references.is_empty() {
+            warn!(
+                "{} has no screenshots labelled with a page type; vision classification is off",
+                path.display()
+            );
+            return None;
+        }
+        let engine = match EmbeddingEngine::new(None) {
+            Ok(engine) if engine.has_model() => engine,
+            Ok(_) => return None,
+            Err(e) => {
+                warn!("failed to load the CLIP model: {e}; vision classification is off");
+                return None;
+            }
+        };
+        info!(
+            "vision classification on, with {} reference screenshot(s)",
+            references.len()
+        );
+        Some(Self {
+            engine: std::sync::Mutex::new(engine),
+            references,
+        })
+    }
+
+    /// Without the `vision` feature there is no classifier.
+    #[cfg(not(feature = "vision"))]
+    pub fn load(_path: &Path) -> Option<Self> {
+        None
+    }
+
+    /// Classify a PNG screenshot: the page type most of its nearest
+    /// references share, and how sure that is, or `None` if no reference is
+    /// similar enough. Runs the CLIP model, so call it off the async runtime.
+    #[cfg(feature = "vision")]
+    pub fn classify(&self, png: &[u8]) -> Result<Option<(PageType, f32)>> {
+        use base64::Engine as _;
+
+        let encoded = base64::engine::general_purpose::STANDARD.encode(png);
+        let (image, _) = agentic_vision::capture_from_base64(&encoded, "image/png")
+            .map_err(|e| anyhow::anyhow!("decoding screenshot: {e}"))?;
+        let embedding = self
+            .engine
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .embed(&image)
+            .map_err(|e| anyhow::anyhow!("embedding screenshot: {e}"))?;
+        let scores = self.references.iter().map(|(page_type, reference)| {
+            (
+                *page_type,
+                agentic_vision::cosine_similarity(&embedding, reference),
+            )
+        });
+        Ok(nearest(scores, NEIGHBOURS, MIN_SIMILARITY))
+    }
+
+    /// Without the `vision` feature nothing is classified.
+    #[cfg(not(feature = "vision"))]
+    pub fn classify(&self, _png: &[u8]) -> Result<Option<(PageType, f32)>> {
+        Ok(None)
+    }
+}
+
+/// Default reference set (~/.cortex/vision/reference.avis).
+pub fn default_reference_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".cortex")
+        .join("vision")
+        .join("reference.avis")
+}
+
+/// The page type a reference label names, e.g. `product_detail`.
+pub fn page_type_of_label(label: &str) -> Option<PageType> {
+    let label = label.trim().to_lowercase();
+    (1..PageType::COUNT as u8)
+        .map(PageType::from_u8)
+        .find(|page_type| page_type.to_string() == label)
+}
+
+/// Vote among the `k` references most similar to a screenshot, each
+/// weighted by its similarity. The winner's confidence is its share of the
+/// vote, scaled by its best similarity.
+#[cfg_attr(not(feature = "vision"), allow(dead_code))]
+fn nearest(
+    scores: impl IntoIterator<Item = (PageType, f32)>,
+    k: usize,
+    min_similarity: f32,
+) -> Option<(PageType, f32)> {
+    let mut scores: Vec<(PageType, f32)> = scores
+        .into_iter()
+        .filter(|(_, similarity)| *similarity >= min_similarity)
+        .collect();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scores.truncate(k);
+
+    let total: f32 = scores.iter().map(|(_, s)| s).sum();
+    let mut votes: Vec<(PageType, f32, f32)> = Vec::new();
+    for (page_type, similarity) in scores {
+        match votes.iter_mut().find(|(t, _, _)| *t == page_type) {
+            Some((_, weight, _)) => *weight += similarity,
+            // Scores are sorted, so the first is each type's best.
+            None => votes.push((page_type, similarity, similarity)),
+        }
+    }
+    let (page_type, weight, best) = votes.into_iter().max_by(|a, b| a.1.total_cmp(&b.1))?;
+    Some((page_type, (weight / total * best).clamp(0.0, 1.0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nearest_neighbour_vote() {
+        let scores = [
+            (PageType::ProductDetail, 0.95),
+            (PageType::ProductDetail, 0.9),
+            (PageType::Article, 0.92),
+            (PageType::Login, 0.5),
+        ];
+        let (page_type, confidence) = nearest(scores, 5, 0.7).unwrap();
+        assert_eq!(page_type, PageType::ProductDetail);
+        // Two thirds of the vote, at a best similarity of 0.95.
+        assert!((confidence - 1.85 / 2.77 * 0.95).abs() < 1e-3);
+
+        // Only the nearest neighbours vote.
+        let (page_type, _) = nearest(scores, 1, 0.7).unwrap();
+        assert_eq!(page_type, PageType::ProductDetail);
+        assert_eq!(nearest(scores, 5, 0.99), None);
+
+        assert_eq!(
+            page_type_of_label("Product_Detail"),
+            Some(PageType::ProductDetail)
+        );
+        assert_eq!(page_type_of_label("unknown"), None);
+        assert_eq!(page_type_of_label("screenshot"), None);
+    }
+}
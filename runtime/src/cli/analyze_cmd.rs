@@ -0,0 +1,105 @@
+//! `cortex analyze <domain>` — reachability and structural problems in a cached map.
+
+use crate::cli::output::{self, Styled};
+use crate::intelligence::cache::MapCache;
+use crate::map::types::SiteMap;
+use crate::navigation::reachability::{self, RemapReason};
+use anyhow::{bail, Result};
+
+/// Run the analyze command, listing at most `limit` nodes per section.
+pub async fn run(domain: &str, limit: usize) -> Result<()> {
+    let s = Styled::new();
+
+    let mut cache = MapCache::default_cache()?;
+    let Some(map) = cache.load_map(domain)? else {
+        if output::is_json() {
+            output::print_json(&serde_json::json!({
+                "error": "no_map",
+                "message": format!("No cached map for '{domain}'"),
+                "hint": format!("Run: cortex map {domain}")
+            }));
+            return Ok(());
+        }
+        bail!("No map found for '{domain}'. Run 'cortex map {domain}' first.");
+    };
+
+    let report = reachability::analyze(&map);
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({
+            "domain": domain,
+            "report": report,
+        }));
+        return Ok(());
+    }
+    if output::is_quiet() {
+        return Ok(());
+    }
+
+    let sym = |problems: usize| {
+        if problems == 0 {
+            s.ok_sym()
+        } else {
+            s.warn_sym()
+        }
+    };
+    eprintln!(
+        "  {domain}: {} nodes, {} edges (root: node {})",
+        report.node_count, report.edge_count, report.root
+    );
+    eprintln!();
+    let components = report.component_sizes.len();
+    output::print_check(
+        sym(components.saturating_sub(1)),
+        "Components:",
+        &match report.component_sizes.first() {
+            Some(largest) => format!("{components} (largest: {largest} nodes)"),
+            None => "0".to_string(),
+        },
+    );
+    output::print_check(
+        sym(report.unreachable.len()),
+        "Unreachable:",
+        &report.unreachable.len().to_string(),
+    );
+    print_nodes(&map, &report.unreachable, limit);
+    output::print_check(
+        sym(report.dead_ends.len()),
+        "Dead ends:",
+        &report.dead_ends.len().to_string(),
+    );
+    print_nodes(&map, &report.dead_ends, limit);
+    output::print_check(
+        s.info_sym(),
+        "Choke points:",
+        &report.choke_points.len().to_string(),
+    );
+    print_nodes(&map, &report.choke_points, limit);
+
+    eprintln!();
+    if report.remap_targets.is_empty() {
+        eprintln!("  {} No re-map targets.", s.ok_sym());
+        return Ok(());
+    }
+    output::print_section(&s, "Suggested re-map targets");
+    for target in report.remap_targets.iter().take(limit) {
+        let reason = match target.reason {
+            RemapReason::UnrenderedDeadEnd => "dead end, never rendered",
+            RemapReason::OrphanEntry => "unreachable entry point",
+        };
+        eprintln!("    [{:>5}] {} {}", target.node, target.url, s.dim(reason));
+    }
+    if report.remap_targets.len() > limit {
+        eprintln!("    … {} more", report.remap_targets.len() - limit);
+    }
+    Ok(())
+}
+
+fn print_nodes(map: &SiteMap, nodes: &[u32], limit: usize) {
+    for &node in nodes.iter().take(limit) {
+        output::print_detail(&format!("[{node:>5}] {}", map.node_url(node)));
+    }
+    if nodes.len() > limit {
+        output::print_detail(&format!("… {} more", nodes.len() - limit));
+    }
+}
@@ -0,0 +1,164 @@
+//! `cortex annotate <domain> [node]` — label, correct or note the pages of
+//! a mapped domain, and list their annotations.
+
+use crate::cartography::annotations::{self, Annotation, AnnotationBody, AnnotationStore};
+use crate::cli::output::{self, Styled};
+use crate::intelligence::cache::MapCache;
+use crate::map::types::SiteMap;
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+
+/// What to do with a page's annotations.
+pub enum AnnotateAction {
+    /// List them (or the whole domain's, with no page given).
+    List,
+    Add(AnnotationBody),
+    /// Delete the annotation with this ID.
+    Remove(u64),
+}
+
+impl AnnotateAction {
+    /// The action the command's flags ask for: at most one of them, and
+    /// listing with none.
+    pub fn from_flags(
+        label: Option<String>,
+        page_type: Option<&str>,
+        note: Option<String>,
+        remove: Option<u64>,
+    ) -> Result<Self> {
+        Ok(match (label, page_type, note, remove) {
+            (None, None, None, None) => Self::List,
+            (Some(label), None, None, None) => Self::Add(AnnotationBody::Label { label }),
+            (None, Some(name), None, None) => match annotations::parse_page_type(name) {
+                Some(page_type) => Self::Add(AnnotationBody::Correction { page_type }),
+                None => bail!("Unknown page type '{name}'. Use a name like 'checkout' or a code."),
+            },
+            (None, None, Some(text), None) => Self::Add(AnnotationBody::Note { text }),
+            (None, None, None, Some(id)) => Self::Remove(id),
+            _ => bail!("Give at most one of --label, --page-type, --note and --remove."),
+        })
+    }
+}
+
+/// Run the annotate command on the page given as `node` (of the cached
+/// map) or `url`. A correction also retypes the page in the cached map.
+pub async fn run(
+    domain: &str,
+    node: Option<u32>,
+    url: Option<&str>,
+    action: AnnotateAction,
+    author: Option<&str>,
+) -> Result<()> {
+    let s = Styled::new();
+    let store = AnnotationStore::default();
+    let mut cache = MapCache::default_cache()?;
+    let mut map = cache.load_map(domain)?;
+
+    let target = match (node, url) {
+        (_, Some(url)) => Some(url.to_string()),
+        (Some(node), None) => {
+            let Some(map) = &map else {
+                bail!(
+                    "No map found for '{domain}'. Run 'cortex map {domain}' first, or give --url."
+                );
+            };
+            let Some(url) = map.urls.get(node as usize) else {
+                bail!(
+                    "Node {node} does not exist in the map of '{domain}' ({} nodes).",
+                    map.nodes.len()
+                );
+            };
+            Some(url.clone())
+        }
+        (None, None) => None,
+    };
+    let node_of = |map: &Option<SiteMap>, url: &str| map.as_ref().and_then(|m| m.find_url(url));
+
+    match action {
+        AnnotateAction::List => {
+            let list: Vec<Annotation> = store
+                .list(domain)?
+                .into_iter()
+                .filter(|a| target.as_ref().is_none_or(|url| &a.url == url))
+                .collect();
+            if output::is_json() {
+                let list: Vec<_> = list
+                    .iter()
+                    .map(|a| a.to_json(node_of(&map, &a.url)))
+                    .collect();
+                output::print_json(&serde_json::json!({
+                    "domain": domain,
+                    "total": list.len(),
+                    "annotations": list,
+                }));
+            } else if list.is_empty() {
+                println!("  No annotations.");
+            } else {
+                for a in &list {
+                    print_annotation(&s, a, node_of(&map, &a.url));
+                }
+            }
+        }
+        AnnotateAction::Add(body) => {
+            let Some(url) = target else {
+                bail!("Give the page to annotate: a node index or --url.");
+            };
+            let annotation = store.add(domain, &url, body, author)?;
+            let mut applied = false;
+            if let (AnnotationBody::Correction { page_type }, Some(map)) =
+                (&annotation.body, map.as_mut())
+            {
+                let correction = HashMap::from([(url.clone(), *page_type)]);
+                applied = annotations::apply_corrections(map, &correction) > 0;
+                if applied {
+                    cache.cache_map(domain, map)?;
+                }
+            }
+            let node = node_of(&map, &url);
+            if output::is_json() {
+                output::print_json(&serde_json::json!({
+                    "annotation": annotation.to_json(node),
+                    "applied": applied,
+                }));
+            } else if !output::is_quiet() {
+                print_annotation(&s, &annotation, node);
+                if applied {
+                    println!("  The cached map of {domain} has the corrected type.");
+                }
+            }
+        }
+        AnnotateAction::Remove(id) => {
+            let Some(removed) = store.remove(domain, id)? else {
+                bail!("No annotation {id} on '{domain}'.");
+            };
+            if output::is_json() {
+                output::print_json(&serde_json::json!({ "removed": removed.to_json(None) }));
+            } else if !output::is_quiet() {
+                println!("  Removed annotation {id} of {}.", removed.url);
+                if matches!(removed.body, AnnotationBody::Correction { .. }) {
+                    println!("  The page keeps its corrected type until {domain} is mapped again.");
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_annotation(s: &Styled, a: &Annotation, node: Option<u32>) {
+    let node = node.map(|n| format!("[{n}] ")).unwrap_or_default();
+    let what = match &a.body {
+        AnnotationBody::Label { label } => format!("label: {label}"),
+        AnnotationBody::Correction { page_type } => format!("is a {page_type} page"),
+        AnnotationBody::Note { text } => format!("note: {text}"),
+    };
+    let by = a
+        .author
+        .as_deref()
+        .map(|author| format!(" by {author}"))
+        .unwrap_or_default();
+    println!("  #{:<4} {node}{}", a.id, a.url);
+    println!(
+        "        {what}  {}",
+        s.dim(&format!("{}{by}", a.created_at.format("%Y-%m-%d %H:%M")))
+    );
+}
@@ -0,0 +1,108 @@
+//! CLI handlers for `cortex audit` subcommands (list, export).
+
+use crate::audit::logger::{self, AuditEvent, AuditFilter};
+use crate::cli::events_cmd::parse_since;
+use crate::cli::output;
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::Path;
+
+fn filter(
+    since: Option<&str>,
+    method: Option<&str>,
+    domain: Option<&str>,
+    actor: Option<&str>,
+) -> Result<AuditFilter> {
+    Ok(AuditFilter {
+        since: since.map(parse_since).transpose()?,
+        method: method.map(String::from),
+        domain: domain.map(String::from),
+        actor: actor.map(String::from),
+    })
+}
+
+fn load(filter: &AuditFilter) -> Result<Vec<AuditEvent>> {
+    Ok(logger::read_log(&logger::default_log_path())?
+        .into_iter()
+        .filter(|e| filter.matches(e))
+        .collect())
+}
+
+/// List audited operations, most recent `limit` last.
+pub async fn run_list(
+    since: Option<&str>,
+    method: Option<&str>,
+    domain: Option<&str>,
+    actor: Option<&str>,
+    limit: usize,
+) -> Result<()> {
+    let events = load(&filter(since, method, domain, actor)?)?;
+    let shown = &events[events.len().saturating_sub(limit)..];
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({
+            "total": events.len(),
+            "events": shown,
+        }));
+        return Ok(());
+    }
+    if output::is_quiet() {
+        return Ok(());
+    }
+    if events.is_empty() {
+        println!(
+            "  No audited operations. The daemon records them while `cortex start` is running."
+        );
+        return Ok(());
+    }
+    for e in shown {
+        let target = e.url.as_deref().or(e.domain.as_deref()).unwrap_or("-");
+        println!(
+            "  {}  {:<14} {:<16} {:<10} {:>6}ms  {target}",
+            e.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            e.method,
+            e.actor,
+            e.status,
+            e.duration_ms,
+        );
+    }
+    if shown.len() < events.len() {
+        println!(
+            "  ({} earlier entries not shown; use --limit or --since)",
+            events.len() - shown.len()
+        );
+    }
+    Ok(())
+}
+
+/// Export audited operations as JSONL to `out`, or stdout.
+pub async fn run_export(
+    since: Option<&str>,
+    method: Option<&str>,
+    domain: Option<&str>,
+    out: Option<&Path>,
+) -> Result<()> {
+    let events = load(&filter(since, method, domain, None)?)?;
+    let mut lines = String::new();
+    for e in &events {
+        lines.push_str(&serde_json::to_string(e)?);
+        lines.push('\n');
+    }
+
+    match out {
+        Some(path) => {
+            std::fs::write(path, lines)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+            if output::is_json() {
+                output::print_json(&serde_json::json!({
+                    "exported": events.len(),
+                    "path": path.display().to_string(),
+                }));
+            } else if !output::is_quiet() {
+                eprintln!("  Exported {} entries to {}.", events.len(), path.display());
+            }
+        }
+        None => std::io::stdout().write_all(lines.as_bytes())?,
+    }
+    Ok(())
+}
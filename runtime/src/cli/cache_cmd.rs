@@ -1,9 +1,18 @@
 //! `cortex cache` — manage cached maps.
 
+use crate::cartography::import::{self, ImportFormat};
 use crate::cli::doctor::cortex_home;
+use crate::cli::events_cmd;
 use crate::cli::output::{self, Styled};
-use anyhow::Result;
-use std::path::PathBuf;
+use crate::cli::query_cmd;
+use crate::collective::registry::LocalRegistry;
+use crate::intelligence::cache::MapCache;
+use crate::map::export::{self, ExportFeature, ExportFormat};
+use crate::map::merge::DomainAliases;
+use crate::map::types::{NodeQuery, PageType, SiteMap};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use std::path::{Path, PathBuf};
 
 /// Clear cached maps.
 pub async fn run_clear(domain: Option<&str>) -> Result<()> {
@@ -70,3 +79,386 @@ pub async fn run_clear(domain: Option<&str>) -> Result<()> {
 
     Ok(())
 }
+
+/// Export a cached map to GraphML, GEXF, or DOT.
+///
+/// Writes to `output` if given, otherwise to stdout.
+pub async fn run_export(
+    domain: &str,
+    format: &str,
+    features: &[String],
+    output_path: Option<&Path>,
+) -> Result<()> {
+    let format = ExportFormat::parse(format)?;
+    let features = features
+        .iter()
+        .map(|f| ExportFeature::parse(f))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut cache = MapCache::default_cache()?;
+    let path = cache
+        .get(domain)
+        .with_context(|| format!("no cached map for '{domain}'; run `cortex map {domain}` first"))?
+        .to_path_buf();
+    let map = SiteMap::deserialize(&std::fs::read(&path)?).context("failed to load cached map")?;
+    let text = export::export(&map, format, &features);
+
+    match output_path {
+        Some(out) => {
+            std::fs::write(out, &text)
+                .with_context(|| format!("writing export: {}", out.display()))?;
+            if output::is_json() {
+                output::print_json(&serde_json::json!({
+                    "domain": domain,
+                    "format": format.extension(),
+                    "path": out.display().to_string(),
+                    "nodes": map.nodes.len(),
+                    "edges": map.edges.len(),
+                }));
+            } else if !output::is_quiet() {
+                let s = Styled::new();
+                eprintln!(
+                    "  {} Exported {domain} ({} nodes, {} edges) to {}.",
+                    s.ok_sym(),
+                    map.nodes.len(),
+                    map.edges.len(),
+                    out.display()
+                );
+            }
+        }
+        None => print!("{text}"),
+    }
+    Ok(())
+}
+
+/// Import a third-party crawl export into the map cache.
+pub async fn run_import(
+    file: &Path,
+    format: Option<&str>,
+    links: Option<&Path>,
+    domain: Option<&str>,
+) -> Result<()> {
+    let format = match format {
+        Some(f) => ImportFormat::parse(f)?,
+        None => ImportFormat::detect(file).with_context(|| {
+            format!(
+                "cannot tell the format of {}; pass --format screaming-frog or warc",
+                file.display()
+            )
+        })?,
+    };
+    let open = |path: &Path| {
+        std::fs::File::open(path).with_context(|| format!("opening {}", path.display()))
+    };
+
+    let input = open(file)?;
+    let (map, stats) = match format {
+        ImportFormat::ScreamingFrog => {
+            let links = links.map(open).transpose()?;
+            import::import_screaming_frog(input, links, domain)?
+        }
+        ImportFormat::Warc => {
+            if links.is_some() {
+                anyhow::bail!("--links only applies to Screaming Frog imports");
+            }
+            import::import_warc(input, domain)?
+        }
+    };
+
+    let domain = map.header.domain.clone();
+    let path = MapCache::default_cache()?.cache_map(&domain, &map)?;
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({
+            "domain": domain,
+            "path": path.display().to_string(),
+            "stats": stats,
+        }));
+    } else if !output::is_quiet() {
+        let s = Styled::new();
+        eprintln!(
+            "  {} Imported {domain}: {} pages, {} links ({} of {} records skipped).",
+            s.ok_sym(),
+            stats.pages,
+            stats.links,
+            stats.skipped,
+            stats.records
+        );
+    }
+    Ok(())
+}
+
+/// Merge cached maps (or `.ctx` files) into one map under a canonical domain.
+pub async fn run_merge(
+    inputs: &[String],
+    into: Option<&str>,
+    extra_aliases: &[String],
+) -> Result<()> {
+    let mut cache = MapCache::default_cache()?;
+    let mut maps = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let path = Path::new(input);
+        let map = if input.ends_with(".ctx") && path.is_file() {
+            SiteMap::deserialize(&std::fs::read(path)?)
+                .with_context(|| format!("failed to load map: {input}"))?
+        } else {
+            cache.load_map(input)?.with_context(|| {
+                format!("no cached map for '{input}'; run `cortex map {input}` first")
+            })?
+        };
+        maps.push(map);
+    }
+
+    let canonical = match into {
+        Some(d) => d.to_string(),
+        None => DomainAliases::base_domain(&maps[0].header.domain).to_string(),
+    };
+    let mut aliases = DomainAliases::new(&canonical);
+    for map in &maps {
+        aliases = aliases.with_alias(&map.header.domain);
+    }
+    for host in extra_aliases {
+        aliases = aliases.with_alias(host);
+    }
+
+    let refs: Vec<&SiteMap> = maps.iter().collect();
+    let (merged, stats) = SiteMap::merge_all(&refs, &aliases);
+    let path = cache.cache_map(&canonical, &merged)?;
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({
+            "domain": canonical,
+            "path": path.display().to_string(),
+            "stats": stats,
+        }));
+    } else if !output::is_quiet() {
+        let s = Styled::new();
+        eprintln!(
+            "  {} Merged {} maps into {canonical}: {} nodes ({} duplicates folded), {} edges.",
+            s.ok_sym(),
+            stats.maps,
+            stats.nodes_out,
+            stats.nodes_deduped,
+            stats.edges_out
+        );
+    }
+    Ok(())
+}
+
+/// Write the part of a cached map matching a query to a standalone `.ctx` file.
+pub async fn run_slice(
+    domain: &str,
+    page_types: &[String],
+    features: &[String],
+    wql: Option<&str>,
+    limit: usize,
+    out: &Path,
+) -> Result<()> {
+    let mut cache = MapCache::default_cache()?;
+    let map = cache.load_map(domain)?.with_context(|| {
+        format!("no cached map for '{domain}'; run `cortex map {domain}` first")
+    })?;
+
+    let slice = match wql {
+        Some(wql) => map.slice_wql(wql)?,
+        None => {
+            let feature_ranges = features
+                .iter()
+                .map(|f| {
+                    query_cmd::parse_feature_filter(f).with_context(|| {
+                        format!(
+                            "could not parse feature filter '{f}'; use \"48<300\" or \"52>0.8\""
+                        )
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let page_types = page_types
+                .iter()
+                .map(|t| match query_cmd::parse_page_type(t) {
+                    PageType::Unknown if !t.eq_ignore_ascii_case("unknown") => {
+                        anyhow::bail!("unknown page type '{t}'")
+                    }
+                    pt => Ok(pt),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let page_types = (!page_types.is_empty()).then_some(page_types);
+            map.slice(&NodeQuery {
+                page_types,
+                feature_ranges,
+                limit,
+                ..Default::default()
+            })
+        }
+    };
+
+    std::fs::write(out, slice.serialize())
+        .with_context(|| format!("writing slice: {}", out.display()))?;
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({
+            "domain": domain,
+            "path": out.display().to_string(),
+            "nodes": slice.nodes.len(),
+            "edges": slice.edges.len(),
+            "source_nodes": map.nodes.len(),
+        }));
+    } else if !output::is_quiet() {
+        let s = Styled::new();
+        eprintln!(
+            "  {} Sliced {} of {} nodes ({} edges) from {domain} to {}.",
+            s.ok_sym(),
+            slice.nodes.len(),
+            map.nodes.len(),
+            slice.edges.len(),
+            out.display()
+        );
+    }
+    Ok(())
+}
+
+/// Check out `domain`'s map as it was at `at` from the registry, into
+/// `out` or, replacing the current one, into the cache. `at` is a date, an
+/// RFC 3339 timestamp, an age such as `7d`, or `now` for the latest version.
+pub async fn run_checkout(domain: &str, at: &str, out: Option<&Path>) -> Result<()> {
+    let at = match at {
+        "now" => Utc::now(),
+        at => events_cmd::parse_since(at)?,
+    };
+    let registry = LocalRegistry::open_default()?;
+    let Some((map, version)) = registry.checkout(domain, at)? else {
+        anyhow::bail!(
+            "no version of '{domain}' in the registry at {}; see `cortex registry list`",
+            at.to_rfc3339()
+        );
+    };
+
+    let path = match out {
+        Some(out) => {
+            std::fs::write(out, map.serialize())
+                .with_context(|| format!("writing map: {}", out.display()))?;
+            out.to_path_buf()
+        }
+        None => MapCache::default_cache()?.cache_map(domain, &map)?,
+    };
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({
+            "domain": domain,
+            "at": at.to_rfc3339(),
+            "version": version.to_rfc3339(),
+            "path": path.display().to_string(),
+            "nodes": map.nodes.len(),
+            "edges": map.edges.len(),
+        }));
+    } else if !output::is_quiet() {
+        let s = Styled::new();
+        eprintln!(
+            "  {} Checked out {domain} as of {} ({} nodes, {} edges) to {}.",
+            s.ok_sym(),
+            version.format("%Y-%m-%d %H:%M UTC"),
+            map.nodes.len(),
+            map.edges.len(),
+            path.display()
+        );
+        if out.is_none() {
+            eprintln!(
+                "  {}",
+                s.dim("The cached map was replaced; `--at now` restores the latest version.")
+            );
+            eprintln!(
+                "  {}",
+                s.dim("A running daemon keeps its loaded map until `cortex restart`.")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Show cache size against its budgets, hit rate and per-type usage.
+pub async fn run_stats(limit: usize) -> Result<()> {
+    let cache = MapCache::default_cache()?;
+    let stats = cache.stats();
+
+    if output::is_json() {
+        output::print_json(&serde_json::to_value(&stats)?);
+        return Ok(());
+    }
+    if output::is_quiet() {
+        return Ok(());
+    }
+
+    let s = Styled::new();
+    output::print_section(&s, "Map Cache");
+    output::print_check(
+        " ",
+        "Entries:",
+        &format!("{} / {}", stats.entries, stats.max_entries),
+    );
+    output::print_check(
+        " ",
+        "Size:",
+        &format!(
+            "{} / {}",
+            output::format_size(stats.bytes),
+            output::format_size(stats.max_bytes)
+        ),
+    );
+    output::print_check(
+        " ",
+        "Hit rate:",
+        &format!(
+            "{:.1}% ({} hits, {} misses)",
+            stats.hit_rate * 100.0,
+            stats.hits,
+            stats.misses
+        ),
+    );
+    output::print_check(
+        " ",
+        "Evicted:",
+        &format!(
+            "{} ({}), {} expired",
+            stats.evictions,
+            stats.eviction.name(),
+            stats.expired
+        ),
+    );
+
+    if !stats.by_type.is_empty() {
+        eprintln!();
+        output::print_section(&s, "By Content Type");
+        for t in &stats.by_type {
+            eprintln!(
+                "    {:<10} {:>4} maps {:>10} {:>6} hits   TTL {}",
+                t.content_type.name(),
+                t.entries,
+                output::format_size(t.bytes),
+                t.hits,
+                output::format_duration(t.ttl_secs)
+            );
+        }
+    }
+
+    if !stats.maps.is_empty() {
+        eprintln!();
+        output::print_section(&s, "Most Used");
+        for m in stats.maps.iter().take(limit) {
+            let expired = if m.expired {
+                s.dim(" expired")
+            } else {
+                String::new()
+            };
+            eprintln!(
+                "    {:<24} {:<10} {:>10} {:>6} hits{expired}",
+                m.domain,
+                m.content_type.name(),
+                output::format_size(m.bytes),
+                m.hits
+            );
+        }
+        if stats.maps.len() > limit {
+            eprintln!("    … {} more", stats.maps.len() - limit);
+        }
+    }
+    Ok(())
+}
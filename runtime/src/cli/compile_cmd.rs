@@ -1,16 +1,26 @@
 //! CLI handler for `cortex compile <domain>`.
 
 use crate::cli::output::{self, Styled};
-use crate::compiler::{codegen, schema};
+use crate::compiler::package::{self, PackageSettings};
+use crate::compiler::schema;
 use crate::intelligence::cache::MapCache;
+use crate::project;
 use anyhow::{bail, Result};
 use std::path::PathBuf;
 use std::time::Instant;
 
-/// Run the compile command.
-pub async fn run(domain: &str, _all: bool, output_dir: Option<&str>) -> Result<()> {
+/// Run the compile command, writing one installable package per target.
+/// Without targets, the domain's project decides (all targets if none).
+pub async fn run(
+    domain: &str,
+    _all: bool,
+    output_dir: Option<&str>,
+    targets: &[String],
+    version: Option<&str>,
+) -> Result<()> {
     let s = Styled::new();
     let start = Instant::now();
+    let targets = package::parse_targets(&project::compile_targets(domain, targets)?)?;
 
     // Load the cached map
     let mut cache = MapCache::default_cache()?;
@@ -30,42 +40,12 @@ pub async fn run(domain: &str, _all: bool, output_dir: Option<&str>) -> Result<(
         home.join(".cortex").join("compiled").join(domain)
     };
 
-    // Generate all files
-    let _files = codegen::generate_all(&compiled, &out_dir)?;
-    let elapsed = start.elapsed();
-
-    if !output::is_quiet() {
-        // Collect which targets were generated
-        let mut targets: Vec<&str> = Vec::new();
-        if out_dir.join("models.py").exists() || compiled.stats.total_models > 0 {
-            targets.push("python");
-        }
-        if out_dir.join("client").exists() || out_dir.join("index.ts").exists() {
-            targets.push("typescript");
-        }
-        if out_dir.join("openapi.yaml").exists() || out_dir.join("openapi.json").exists() {
-            targets.push("openapi");
-        }
-        if targets.is_empty() {
-            targets.extend_from_slice(&["python", "typescript", "openapi"]);
-        }
-        let targets_str = targets
-            .iter()
-            .map(|t| s.cyan(t))
-            .collect::<Vec<_>>()
-            .join(" \u{00b7} ");
-        let time_str = if elapsed.as_millis() < 1000 {
-            format!("{}ms", elapsed.as_millis())
-        } else {
-            format!("{:.1}s", elapsed.as_secs_f64())
-        };
-        eprintln!(
-            "  {} Generated {} in {}",
-            s.ok_sym(),
-            targets_str,
-            s.yellow(&time_str),
-        );
+    let mut settings = PackageSettings::new(targets, compiled.compiled_at);
+    if let Some(version) = version {
+        settings.version = version.to_string();
     }
+    let manifest = package::write_packages(&compiled, &settings, &out_dir)?;
+    let elapsed = start.elapsed();
 
     if output::is_json() {
         output::print_json(&serde_json::json!({
@@ -76,8 +56,43 @@ pub async fn run(domain: &str, _all: bool, output_dir: Option<&str>) -> Result<(
             "relationships": compiled.relationships.len(),
             "output_dir": out_dir.display().to_string(),
             "duration_ms": elapsed.as_millis() as u64,
+            "manifest": manifest,
         }));
+        return Ok(());
+    }
+    if output::is_quiet() {
+        return Ok(());
+    }
+
+    let targets_str = manifest
+        .packages
+        .iter()
+        .map(|p| s.cyan(p.target.name()))
+        .collect::<Vec<_>>()
+        .join(" \u{00b7} ");
+    let time_str = if elapsed.as_millis() < 1000 {
+        format!("{}ms", elapsed.as_millis())
+    } else {
+        format!("{:.1}s", elapsed.as_secs_f64())
+    };
+    eprintln!(
+        "  {} Generated {} in {}",
+        s.ok_sym(),
+        targets_str,
+        s.yellow(&time_str),
+    );
+    for p in &manifest.packages {
+        output::print_detail(&format!(
+            "{:<10} {} {}",
+            p.target.name(),
+            out_dir.join(&p.dir).display(),
+            s.dim(&format!("({} {})", p.name, settings.version))
+        ));
     }
+    output::print_detail(&format!(
+        "manifest   {}",
+        out_dir.join("manifest.json").display()
+    ));
 
     Ok(())
 }
@@ -0,0 +1,128 @@
+//! Size-rotated log file for a daemon running as a background service.
+//!
+//! `cortex start --log-file <path>` writes its tracing output here instead
+//! of stderr. When the file passes `MAX_LOG_SIZE` it is renamed to
+//! `<path>.1` (shifting older files up to `<path>.5`) and a fresh file is
+//! started, so a long-running service never fills the disk.
+
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Log size before rotation (10 MB).
+const MAX_LOG_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Number of rotated files to keep.
+const MAX_ROTATIONS: u32 = 5;
+
+/// Default service log (~/.cortex/logs/cortex.log).
+pub fn default_path() -> PathBuf {
+    crate::cli::doctor::cortex_home()
+        .join("logs")
+        .join("cortex.log")
+}
+
+/// An append-only log file that rotates itself by size.
+#[derive(Debug)]
+pub struct RotatingLog {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+}
+
+impl RotatingLog {
+    /// Open (or create) the log at `path`, appending to what is there.
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("creating log directory {}", parent.display()))?;
+        }
+        let file = append(path)?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            size,
+            max_size: MAX_LOG_SIZE,
+        })
+    }
+
+    /// Rotate log files: cortex.log → cortex.log.1, .1 → .2, etc.
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        for i in (1..MAX_ROTATIONS).rev() {
+            let from = rotation_path(&self.path, i);
+            if from.exists() {
+                let _ = std::fs::rename(&from, rotation_path(&self.path, i + 1));
+            }
+        }
+        let _ = std::fs::rename(&self.path, rotation_path(&self.path, 1));
+        self.file = append(&self.path).map_err(std::io::Error::other)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingLog {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.size >= self.max_size {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn append(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("opening log file {}", path.display()))
+}
+
+/// Path of rotated file `index`: `cortex.log.1`, `cortex.log.2`, etc.
+fn rotation_path(base: &Path, index: u32) -> PathBuf {
+    let name = base
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("cortex.log");
+    base.with_file_name(format!("{name}.{index}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotates_by_size_and_keeps_five() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs").join("cortex.log");
+        let mut log = RotatingLog::open(&path).unwrap();
+        log.max_size = 10;
+
+        for i in 0..8 {
+            writeln!(log, "line {i} padded").unwrap();
+        }
+        log.flush().unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "line 7 padded\n");
+        assert_eq!(
+            std::fs::read_to_string(rotation_path(&path, 1)).unwrap(),
+            "line 6 padded\n"
+        );
+        assert!(rotation_path(&path, MAX_ROTATIONS).exists());
+        assert!(!rotation_path(&path, MAX_ROTATIONS + 1).exists());
+
+        // Reopening appends and picks up the current size.
+        let log = RotatingLog::open(&path).unwrap();
+        assert_eq!(log.size, 14);
+    }
+}
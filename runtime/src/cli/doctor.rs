@@ -5,7 +5,7 @@
 
 use crate::cli::output::{self, Styled};
 use crate::cli::start::{pid_file_path, SOCKET_PATH};
-use anyhow::Result;
+use anyhow::{bail, Result};
 use std::path::PathBuf;
 use std::process::Command;
 
@@ -88,7 +88,9 @@ pub async fn run() -> Result<()> {
         }
     }
 
-    eprintln!();
+    if !output::is_quiet() {
+        eprintln!();
+    }
 
     // ── Browser ─────────────────────────────────────────────────────────
     output::print_section(&s, "Browser");
@@ -153,7 +155,9 @@ pub async fn run() -> Result<()> {
         }
     }
 
-    eprintln!();
+    if !output::is_quiet() {
+        eprintln!();
+    }
 
     // ── Runtime ─────────────────────────────────────────────────────────
     output::print_section(&s, "Runtime");
@@ -215,7 +219,9 @@ pub async fn run() -> Result<()> {
         }
     }
 
-    eprintln!();
+    if !output::is_quiet() {
+        eprintln!();
+    }
 
     // ── Cache ───────────────────────────────────────────────────────────
     output::print_section(&s, "Cache");
@@ -247,7 +253,9 @@ pub async fn run() -> Result<()> {
         output::print_check(s.info_sym(), "Maps cached:", "none");
     }
 
-    eprintln!();
+    if !output::is_quiet() {
+        eprintln!();
+    }
 
     // ── Optional ────────────────────────────────────────────────────────
     output::print_section(&s, "Optional");
@@ -279,6 +287,9 @@ pub async fn run() -> Result<()> {
     }
 
     // Status summary
+    if output::is_quiet() && !ready {
+        bail!("Cortex is not ready. Run 'cortex doctor' for details.");
+    }
     if ready && !has_warning {
         output::print_status(&s, &s.green("READY"), "start with 'cortex start'");
     } else if ready && has_warning {
@@ -0,0 +1,110 @@
+//! CLI handlers for `cortex events` subcommands (tail, replay).
+
+use crate::cli::output;
+use crate::events::{self, LoggedEvent, ReplayFilter};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+/// Parse a `--since` value: RFC 3339 timestamp, plain date, or an age
+/// such as `30m`, `24h` or `7d`.
+pub(crate) fn parse_since(since: &str) -> Result<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(since) {
+        Ok(dt.with_timezone(&Utc))
+    } else if let Ok(date) = chrono::NaiveDate::parse_from_str(since, "%Y-%m-%d") {
+        Ok(date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+    } else if let Some(age) = parse_age(since) {
+        Ok(Utc::now() - age)
+    } else {
+        anyhow::bail!(
+            "invalid date format: {since}. Use ISO 8601 (e.g., 2025-01-01 or 2025-01-01T00:00:00Z) or an age (e.g., 24h, 7d)"
+        );
+    }
+}
+
+/// Parse an age like `30m`, `24h`, `7d` or `2w`.
+pub(crate) fn parse_age(s: &str) -> Option<chrono::Duration> {
+    let unit = s.chars().last()?;
+    let n: i64 = s[..s.len() - unit.len_utf8()].parse().ok()?;
+    match unit {
+        'm' => chrono::Duration::try_minutes(n),
+        'h' => chrono::Duration::try_hours(n),
+        'd' => chrono::Duration::try_days(n),
+        'w' => chrono::Duration::try_weeks(n),
+        _ => None,
+    }
+}
+
+/// Print a single logged event (JSONL in --json mode, one line otherwise).
+fn print_entry(entry: &LoggedEvent) {
+    if output::is_json() {
+        if let Ok(json) = serde_json::to_string(entry) {
+            println!("{json}");
+        }
+        return;
+    }
+    let event = serde_json::to_value(&entry.event).unwrap_or_default();
+    let kind = event["type"].as_str().unwrap_or("Event").to_string();
+    let domain = event["domain"].as_str().unwrap_or("-").to_string();
+    println!(
+        "  {:>6}  {}  {:<14} {}",
+        entry.seq,
+        entry.timestamp.format("%Y-%m-%d %H:%M:%S"),
+        kind,
+        domain
+    );
+}
+
+/// Show the last `lines` events, optionally following new ones.
+pub async fn run_tail(lines: usize, follow: bool, domain: Option<&str>) -> Result<()> {
+    let path = events::default_log_path();
+    let filter = ReplayFilter {
+        domain: domain.map(String::from),
+        ..Default::default()
+    };
+
+    // One pass over the existing log; following then reads only what the
+    // daemon appends.
+    let mut log = events::LogFollower::new(&path);
+    let entries = log.poll(&filter)?;
+    let start = entries.len().saturating_sub(lines);
+    if entries.is_empty() && !follow && !output::is_quiet() && !output::is_json() {
+        println!("  No events recorded yet. Events are logged while `cortex start` is running.");
+    }
+    for entry in &entries[start..] {
+        print_entry(entry);
+    }
+
+    if !follow {
+        return Ok(());
+    }
+
+    loop {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        for entry in &log.poll(&filter)? {
+            print_entry(entry);
+        }
+    }
+}
+
+/// Replay all events recorded after `since` / `after_seq`.
+pub async fn run_replay(
+    since: Option<&str>,
+    after_seq: Option<u64>,
+    domain: Option<&str>,
+) -> Result<()> {
+    let filter = ReplayFilter {
+        since: since.map(parse_since).transpose()?,
+        after_seq,
+        domain: domain.map(String::from),
+    };
+    let entries = events::replay(&events::default_log_path(), &filter)?;
+
+    if entries.is_empty() && !output::is_quiet() && !output::is_json() {
+        println!("  No matching events.");
+    }
+    for entry in &entries {
+        print_entry(entry);
+    }
+    Ok(())
+}
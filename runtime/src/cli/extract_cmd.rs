@@ -0,0 +1,211 @@
+//! `cortex extract` — register custom extraction scripts per domain, and
+//! try a script on a live page under the limits the daemon would run it with.
+
+use crate::cli::output::{self, Styled};
+use crate::extraction::custom;
+use crate::extraction::loader::{self, Script, ScriptLimits, ScriptOutcome};
+use crate::renderer::chromium::ChromiumRenderer;
+use crate::renderer::Renderer;
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+
+/// Navigation timeout for the test page.
+const NAVIGATE_TIMEOUT_MS: u64 = 30_000;
+
+/// Limits given on the command line; `None` keeps the configured limit.
+#[derive(Debug, Default)]
+pub struct LimitOverrides {
+    pub timeout_ms: Option<u64>,
+    pub max_heap_mb: Option<u64>,
+    pub max_result_kb: Option<u64>,
+}
+
+/// Run `script` on `url` in a local headless Chromium and report how it did.
+/// Fails if the script breaks a limit, throws, or lacks its entry point.
+pub async fn run_test(script_path: &Path, url: &str, overrides: LimitOverrides) -> Result<()> {
+    let source = std::fs::read_to_string(script_path)
+        .with_context(|| format!("reading {}", script_path.display()))?;
+    let Some(name) = script_path.file_stem().and_then(|s| s.to_str()) else {
+        bail!("cannot name an extractor after {}", script_path.display());
+    };
+    let mut limits = loader::limits_for(&loader::load_limits(&loader::limits_path())?, name);
+    apply(&mut limits, &overrides);
+    let script = Script {
+        name: name.to_string(),
+        source,
+        limits,
+    };
+
+    let renderer = ChromiumRenderer::new().await.context("starting Chromium")?;
+    let mut context = renderer.new_context().await?;
+    let nav = context.navigate(url, NAVIGATE_TIMEOUT_MS).await;
+    let run = match nav {
+        Ok(_) => Ok(loader::run_script(context.as_ref(), &script).await),
+        Err(e) => Err(e),
+    };
+    let stopped = match &run {
+        Ok(run) if run.running => Some(context.terminate_js().await.is_ok()),
+        _ => None,
+    };
+    let _ = context.close().await;
+    let _ = renderer.shutdown().await;
+    let run = run.with_context(|| format!("loading {url}"))?;
+
+    if output::is_json() {
+        let mut report = serde_json::to_value(&run)?;
+        report["entry_point"] = script.entry_point().into();
+        report["value"] = run.value.clone();
+        if let Some(stopped) = stopped {
+            report["terminated"] = stopped.into();
+        }
+        output::print_json(&report);
+    } else if !output::is_quiet() {
+        let s = Styled::new();
+        let heap = run
+            .heap_bytes
+            .map(output::format_size)
+            .unwrap_or_else(|| "unknown".to_string());
+        println!("  Extractor {} on {url}\n", script.entry_point());
+        println!(
+            "    time     {} ms  (limit {} ms)",
+            run.elapsed_ms, limits.timeout_ms
+        );
+        println!(
+            "    heap     {heap}  (limit {})",
+            output::format_size(limits.max_heap_bytes)
+        );
+        println!(
+            "    result   {}  (limit {})",
+            output::format_size(run.result_bytes),
+            output::format_size(limits.max_result_bytes)
+        );
+        println!();
+        if run.outcome == ScriptOutcome::Ok {
+            println!("  {} Passed. Result:\n", s.ok_sym());
+            let preview = serde_json::to_string_pretty(&run.value)?;
+            for line in preview.lines().take(40) {
+                println!("    {line}");
+            }
+            if preview.lines().count() > 40 {
+                println!("    {}", s.dim("..."));
+            }
+        } else if stopped == Some(false) {
+            println!("  {} The script could not be stopped; the daemon would skip the remaining extractors on the page.\n", s.warn_sym());
+        }
+    }
+
+    if run.outcome != ScriptOutcome::Ok {
+        bail!("{}", describe(&run.outcome, &limits));
+    }
+    Ok(())
+}
+
+fn apply(limits: &mut ScriptLimits, overrides: &LimitOverrides) {
+    if let Some(ms) = overrides.timeout_ms {
+        limits.timeout_ms = ms;
+    }
+    if let Some(mb) = overrides.max_heap_mb {
+        limits.max_heap_bytes = mb * 1024 * 1024;
+    }
+    if let Some(kb) = overrides.max_result_kb {
+        limits.max_result_bytes = kb * 1024;
+    }
+}
+
+fn describe(outcome: &ScriptOutcome, limits: &ScriptLimits) -> String {
+    match outcome {
+        ScriptOutcome::Ok => "passed".to_string(),
+        ScriptOutcome::Missing => {
+            "the script does not define its entry point as a global function".to_string()
+        }
+        ScriptOutcome::Failed { error } => format!("the script failed: {error}"),
+        ScriptOutcome::Timeout => format!("timed out after {} ms", limits.timeout_ms),
+        ScriptOutcome::OverMemory { bytes } => format!(
+            "the page's heap grew by {}, over the {} limit",
+            output::format_size(*bytes),
+            output::format_size(limits.max_heap_bytes)
+        ),
+        ScriptOutcome::TooLarge { bytes } => format!(
+            "the result is {}, over the {} limit",
+            output::format_size(*bytes),
+            output::format_size(limits.max_result_bytes)
+        ),
+        ScriptOutcome::Skipped => "not run".to_string(),
+    }
+}
+
+/// Register `script` to run on pages of `domain`.
+pub async fn run_add(domain: &str, script: &Path, confidence: Option<f32>) -> Result<()> {
+    let extractor = custom::add(&custom::default_dir(), domain, script, confidence)?;
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({
+            "domain": extractor.domain,
+            "name": extractor.script.name,
+            "entry_point": extractor.script.entry_point(),
+            "confidence": extractor.confidence,
+            "limits": extractor.script.limits,
+        }));
+    } else if !output::is_quiet() {
+        println!(
+            "  Registered {} for {} (confidence {:.2}).",
+            extractor.script.name, extractor.domain, extractor.confidence
+        );
+        println!(
+            "  It must define {}(document). Try it with: cortex extract test {} <url>",
+            extractor.script.entry_point(),
+            script.display()
+        );
+        println!("  A running daemon picks it up after: cortex reload");
+    }
+    Ok(())
+}
+
+/// List custom extractors, optionally for one domain.
+pub async fn run_list(domain: Option<&str>) -> Result<()> {
+    let extractors: Vec<_> = custom::load_all(&custom::default_dir())?
+        .into_iter()
+        .filter(|e| domain.is_none_or(|d| e.domain == d.to_lowercase()))
+        .collect();
+
+    if output::is_json() {
+        let list: Vec<_> = extractors
+            .iter()
+            .map(|e| {
+                serde_json::json!({
+                    "domain": e.domain,
+                    "name": e.script.name,
+                    "confidence": e.confidence,
+                    "limits": e.script.limits,
+                })
+            })
+            .collect();
+        output::print_json(&serde_json::json!({ "extractors": list }));
+    } else if extractors.is_empty() {
+        println!(
+            "  No custom extractors. Register one with: cortex extract add <domain> <script.js>"
+        );
+    } else {
+        println!("  Custom extractors:\n");
+        for e in &extractors {
+            println!(
+                "    {:<24}  {:<16}  confidence {:.2}  timeout {} ms",
+                e.domain, e.script.name, e.confidence, e.script.limits.timeout_ms
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Remove the custom extractor `name` from `domain`.
+pub async fn run_remove(domain: &str, name: &str) -> Result<()> {
+    if !custom::remove(&custom::default_dir(), domain, name)? {
+        bail!("no custom extractor '{name}' for {domain}");
+    }
+    if output::is_json() {
+        output::print_json(&serde_json::json!({ "removed": name, "domain": domain }));
+    } else if !output::is_quiet() {
+        println!("  Removed {name} from {domain}. A running daemon drops it after: cortex reload");
+    }
+    Ok(())
+}
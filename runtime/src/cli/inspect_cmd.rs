@@ -0,0 +1,99 @@
+//! `cortex inspect <domain> <node>` — one node of a cached map and the HTML
+//! snapshots kept of its page.
+
+use crate::cartography::snapshots::SnapshotStore;
+use crate::cli::output::{self, Styled};
+use crate::intelligence::cache::MapCache;
+use anyhow::{bail, Result};
+
+/// Run the inspect command. With `html`, print the latest snapshot's HTML
+/// to stdout instead of the summary.
+pub async fn run(domain: &str, node: u32, html: bool) -> Result<()> {
+    let s = Styled::new();
+
+    let mut cache = MapCache::default_cache()?;
+    let Some(map) = cache.load_map(domain)? else {
+        if output::is_json() {
+            output::print_json(&serde_json::json!({
+                "error": "no_map",
+                "message": format!("No cached map for '{domain}'"),
+                "hint": format!("Run: cortex map {domain}")
+            }));
+            return Ok(());
+        }
+        bail!("No map found for '{domain}'. Run 'cortex map {domain}' first.");
+    };
+    let Some(record) = map.nodes.get(node as usize) else {
+        bail!(
+            "Node {node} does not exist in the map of '{domain}' ({} nodes).",
+            map.nodes.len()
+        );
+    };
+    let url = map.node_url(node);
+
+    let list = SnapshotStore::from_env().list(domain, url);
+
+    if html {
+        let Some(latest) = list.first() else {
+            bail!(
+                "No HTML snapshot of node {node} ({url}). Run 'cortex map {domain}' to take one."
+            );
+        };
+        let body = SnapshotStore::read(latest)?;
+        if output::is_json() {
+            output::print_json(&serde_json::json!({
+                "domain": domain,
+                "node": node,
+                "url": url,
+                "snapshot": latest,
+                "html": body,
+            }));
+        } else {
+            print!("{body}");
+        }
+        return Ok(());
+    }
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({
+            "domain": domain,
+            "node": node,
+            "url": url,
+            "page_type": format!("{:?}", record.page_type),
+            "confidence": record.confidence as f32 / 255.0,
+            "http_status": record.http_status,
+            "depth": record.depth,
+            "snapshots": list,
+        }));
+        return Ok(());
+    }
+    if output::is_quiet() {
+        return Ok(());
+    }
+
+    eprintln!("  [{node}] {url}");
+    output::print_detail(&format!(
+        "{:?} ({:.0}% confident), HTTP {}, depth {}",
+        record.page_type,
+        record.confidence as f32 / 2.55,
+        record.http_status,
+        record.depth
+    ));
+    eprintln!();
+    if list.is_empty() {
+        eprintln!("  {} No HTML snapshots.", s.info_sym());
+        return Ok(());
+    }
+    output::print_section(&s, "HTML snapshots");
+    for info in &list {
+        eprintln!(
+            "    {} {:<8} {}",
+            info.fetched_at.format("%Y-%m-%d %H:%M:%S"),
+            format!("{:?}", info.source).to_lowercase(),
+            s.dim(&output::format_size(info.bytes))
+        );
+    }
+    eprintln!();
+    eprintln!("  Print the latest with: cortex inspect {domain} {node} --html");
+    Ok(())
+}
@@ -0,0 +1,104 @@
+//! CLI handlers for `cortex keys` — API keys for daemon clients.
+
+use crate::audit::logger::{self as audit, AuditEvent};
+use crate::cli::output;
+use crate::trust::api_keys::{self, ApiKeys, Permission};
+use anyhow::{bail, Result};
+
+/// Create a key and print its secret, which is not shown again.
+pub async fn run_add(name: &str, permission: &str) -> Result<()> {
+    let permission: Permission = permission.parse()?;
+    let path = api_keys::default_path();
+    let mut keys = ApiKeys::load(&path)?;
+    let first = keys.keys.is_empty();
+    let (key, secret) = keys.add(name, permission)?;
+    keys.save(&path)?;
+    record_change("key_add", &key.name, Some(permission));
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({
+            "id": key.id,
+            "name": key.name,
+            "permission": permission,
+            "secret": secret,
+        }));
+    } else {
+        println!("  Created {permission} key {} ({}).", key.name, key.id);
+        println!("\n    {secret}\n");
+        println!("  This secret is not stored and will not be shown again.");
+        println!(
+            "  Clients send it as the api_key param, or set {}.",
+            api_keys::ENV_VAR
+        );
+        if first {
+            println!("  The daemon now rejects socket connections without a key.");
+        }
+    }
+
+    Ok(())
+}
+
+/// List keys (never their secrets).
+pub async fn run_list() -> Result<()> {
+    let keys = ApiKeys::load(&api_keys::default_path())?;
+
+    if output::is_json() {
+        let keys: Vec<_> = keys
+            .keys
+            .iter()
+            .map(|k| {
+                serde_json::json!({
+                    "id": k.id,
+                    "name": k.name,
+                    "permission": k.permission,
+                    "created_at": k.created_at,
+                })
+            })
+            .collect();
+        output::print_json(&serde_json::json!({ "keys": keys }));
+    } else if keys.keys.is_empty() {
+        println!("  No API keys. The daemon trusts every local connection.");
+    } else {
+        println!("  API keys:\n");
+        for key in &keys.keys {
+            println!(
+                "    {}  {:<20}  {:<5}  {}",
+                key.id,
+                key.name,
+                key.permission,
+                key.created_at.format("%Y-%m-%d %H:%M")
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Revoke a key by ID or name. A running daemon stops accepting it at once.
+pub async fn run_revoke(id_or_name: &str) -> Result<()> {
+    let path = api_keys::default_path();
+    let mut keys = ApiKeys::load(&path)?;
+    let Some(key) = keys.revoke(id_or_name) else {
+        bail!("no API key with ID or name '{id_or_name}'");
+    };
+    keys.save(&path)?;
+    record_change("key_revoke", &key.name, None);
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({"revoked": key.id, "name": key.name}));
+    } else if !output::is_quiet() {
+        println!("  Revoked {} ({}).", key.name, key.id);
+        if keys.keys.is_empty() {
+            println!("  No keys remain; the daemon trusts every local connection again.");
+        }
+    }
+
+    Ok(())
+}
+
+/// Audit a change to the API keys.
+fn record_change(method: &str, name: &str, permission: Option<Permission>) {
+    let mut event = AuditEvent::new(method, &audit::local_actor());
+    event.params = serde_json::json!({ "name": name, "permission": permission });
+    audit::record(&event);
+}
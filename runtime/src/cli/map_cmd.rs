@@ -1,10 +1,13 @@
 //! `cortex map <domain>` — map a website into a navigable graph.
 
+use crate::cartography::quality;
 use crate::cli::output::{self, Styled};
 use crate::intelligence::cache::MapCache;
 use crate::map::types::SiteMap;
-use anyhow::{Context, Result};
-use std::collections::HashMap;
+use crate::progress::MapProgress;
+use crate::protocol::{ErrorCode, ProtocolError};
+use crate::trust::api_keys;
+use anyhow::{bail, Context, Result};
 use std::time::Instant;
 
 /// Run the map command.
@@ -14,19 +17,47 @@ pub async fn run(
     max_render: u32,
     timeout: u64,
     fresh: bool,
+    resume: bool,
+    min_quality: Option<f32>,
 ) -> Result<()> {
     let s = Styled::new();
     let start = Instant::now();
 
-    // Check for cached map first (unless --fresh)
-    if !fresh {
-        let mut cache = MapCache::default_cache()?;
-        if let Some(path) = cache.get(domain) {
-            let data = std::fs::read(path)?;
-            let map = SiteMap::deserialize(&data).context("failed to load cached map")?;
+    if let Some(min) = min_quality.filter(|q| !(0.0..=1.0).contains(q)) {
+        bail!("--min-quality must be from 0 to 1, not {min}.");
+    }
 
+    // Check for cached map first (unless --fresh or --resume, or the cached
+    // map falls short of --min-quality)
+    if !fresh && !resume {
+        let mut cache = MapCache::default_cache()?;
+        let cached = match cache.get(domain) {
+            Some(path) => {
+                let data = std::fs::read(path)?;
+                let map = SiteMap::deserialize(&data).context("failed to load cached map")?;
+                let score = quality::assess(&map).score;
+                match min_quality.filter(|&min| score < min) {
+                    Some(_) => {
+                        if !output::is_quiet() && !output::is_json() {
+                            eprintln!(
+                                "  Cached map scores {score:.2}, below --min-quality. Re-mapping."
+                            );
+                        }
+                        None
+                    }
+                    None => Some((path, map)),
+                }
+            }
+            None => None,
+        };
+        if let Some((path, map)) = cached {
             if output::is_json() {
-                print_map_json(&map, start.elapsed());
+                output::print_result(&output::MapResult::new(
+                    &map,
+                    true,
+                    Some(path),
+                    start.elapsed(),
+                ));
                 return Ok(());
             }
 
@@ -42,7 +73,9 @@ pub async fn run(
                 eprintln!();
             }
 
-            print_map_stats(&s, &map, start.elapsed());
+            if !output::is_quiet() {
+                print_map_stats(&s, &map, start.elapsed());
+            }
             return Ok(());
         }
     }
@@ -66,94 +99,93 @@ pub async fn run(
     let socket_path = "/tmp/cortex.sock";
     let mut stream = match UnixStream::connect(socket_path).await {
         Ok(s) => s,
-        Err(_) => {
-            if output::is_json() {
-                output::print_json(&serde_json::json!({
-                    "status": "daemon_required",
-                    "message": "Cannot connect to Cortex daemon",
-                    "hint": "Start with: cortex start"
-                }));
-            } else if !output::is_quiet() {
-                eprintln!("  Cannot connect to Cortex daemon.");
-                eprintln!("  Start the daemon with: cortex start");
-            }
-            return Ok(());
-        }
+        Err(_) => bail!("Cannot connect to Cortex daemon. Start it with: cortex start"),
     };
 
-    // Spawn a background SSE listener for live progress (best effort)
-    let sse_domain = domain.to_string();
-    let sse_handle = if show_progress {
-        Some(tokio::spawn(stream_progress_from_sse(sse_domain)))
-    } else {
-        None
-    };
-
-    let req = serde_json::json!({
-        "id": format!("map-{}", std::process::id()),
+    // With `progress: true` the daemon streams `{"id", "progress"}` lines
+    // before the final response.
+    let request_id = format!("map-{}", std::process::id());
+    let mut req = serde_json::json!({
+        "id": request_id,
         "method": "map",
         "params": {
             "domain": domain,
             "max_nodes": max_nodes,
             "max_render": max_render,
             "max_time_ms": timeout,
-            "respect_robots": true,
+            "progress": show_progress,
+            "resume": resume,
+            "min_quality": min_quality,
         }
     });
+    api_keys::attach_client_key(&mut req);
     let req_str = format!("{}\n", req);
     stream
         .write_all(req_str.as_bytes())
         .await
         .context("failed to send MAP request")?;
 
-    // Read response (with generous timeout for mapping)
+    // Read progress lines and the response (with generous timeout for mapping).
+    // Ctrl-C cancels the map in the daemon; a second Ctrl-C stops waiting.
     let (reader, _writer) = stream.into_split();
     let mut reader = BufReader::new(reader);
     let mut line = String::new();
-    let response_timeout = std::time::Duration::from_millis(timeout + 30000);
-    let read_result = tokio::time::timeout(response_timeout, reader.read_line(&mut line)).await;
-
-    // Cancel the SSE listener
-    if let Some(handle) = sse_handle {
-        handle.abort();
-    }
-
-    match read_result {
-        Ok(Ok(n)) if n > 0 => {} // Data received into `line`
-        Ok(Ok(_)) => {
-            if !output::is_quiet() {
-                eprintln!("  Connection closed by server.");
-            }
-            return Ok(());
-        }
-        Ok(Err(e)) => {
-            if !output::is_quiet() {
-                eprintln!("  Read error: {e}");
+    // With --min-quality the daemon may remap twice more.
+    let wait_ms = timeout * if min_quality.is_some() { 3 } else { 1 } + 30000;
+    let deadline = Instant::now() + std::time::Duration::from_millis(wait_ms);
+    let mut bar_width = 0;
+    let mut cancelling = false;
+    let response: serde_json::Value = loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        // An interrupted read keeps what it read in `line`; the next one
+        // appends to it.
+        let read = tokio::select! {
+            read = tokio::time::timeout(remaining, reader.read_line(&mut line)) => read,
+            _ = tokio::signal::ctrl_c() => {
+                if cancelling {
+                    bail!("Mapping cancelled.");
+                }
+                cancelling = true;
+                if show_progress {
+                    eprintln!();
+                    eprintln!("  Cancelling...");
+                }
+                cancel_map(socket_path, &request_id).await?;
+                continue;
             }
-            return Ok(());
+        };
+        match read {
+            Ok(Ok(n)) if n > 0 => {}
+            Ok(Ok(_)) => bail!("Connection closed by server."),
+            Ok(Err(e)) => bail!("Read error: {e}"),
+            Err(_) => bail!("Mapping timed out after {wait_ms}ms."),
         }
-        Err(_) => {
-            if !output::is_quiet() {
-                eprintln!("  Mapping timed out after {}ms.", timeout + 30000);
+        let message: serde_json::Value =
+            serde_json::from_str(line.trim()).context("failed to parse response")?;
+        line.clear();
+        match message.get("progress").cloned().map(serde_json::from_value) {
+            Some(Ok(progress)) => {
+                let bar = format_progress(&s, &progress);
+                bar_width = bar_width.max(bar.chars().count());
+                eprint!("\r{bar:<bar_width$}");
             }
-            return Ok(());
+            Some(Err(_)) => {} // newer daemon; skip what we can't render
+            None => break message,
         }
     };
-
-    let response: serde_json::Value =
-        serde_json::from_str(line.trim()).context("failed to parse response")?;
+    if bar_width > 0 {
+        eprint!("\r{}\r", " ".repeat(bar_width));
+    }
 
     if let Some(error) = response.get("error") {
-        if output::is_json() {
-            output::print_json(&response);
-        } else if !output::is_quiet() {
-            let msg = error
-                .get("message")
-                .and_then(|v| v.as_str())
-                .unwrap_or("unknown error");
-            eprintln!("  Mapping failed: {msg}");
+        if ProtocolError::from_json(error).code == ErrorCode::Cancelled {
+            bail!("Mapping cancelled. Continue with: cortex map {domain} --resume");
         }
-        return Ok(());
+        let msg = error
+            .get("message")
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown error");
+        bail!("Mapping failed: {msg}");
     }
 
     let result = response.get("result").cloned().unwrap_or_default();
@@ -167,7 +199,20 @@ pub async fn run(
         .unwrap_or(0);
 
     if output::is_json() {
-        output::print_json(&result);
+        // The daemon persists every map it builds, including fallbacks.
+        let mut cache = MapCache::default_cache()?;
+        let path = cache
+            .get(domain)
+            .with_context(|| format!("mapped {domain} but the map is not in the cache"))?
+            .to_path_buf();
+        let map =
+            SiteMap::deserialize(&std::fs::read(&path)?).context("failed to load the new map")?;
+        output::print_result(&output::MapResult::new(
+            &map,
+            false,
+            Some(&path),
+            start.elapsed(),
+        ));
         return Ok(());
     }
 
@@ -199,11 +244,91 @@ pub async fn run(
             s.blue(&format_count(edge_count)),
             size_suffix,
         );
+        let issues = result
+            .pointer("/compliance/issues")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str());
+        for issue in issues {
+            eprintln!("  {} Compliance: {issue}", s.warn_sym());
+        }
+        let blocked = result
+            .pointer("/blocks/blocked")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0);
+        if blocked > 0 {
+            let checked = result
+                .pointer("/blocks/checked")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(blocked);
+            let kinds: Vec<String> = result
+                .pointer("/blocks/by_kind")
+                .and_then(|v| v.as_object())
+                .into_iter()
+                .flatten()
+                .map(|(kind, count)| format!("{kind} {count}"))
+                .collect();
+            eprintln!(
+                "  {} Blocked: {blocked} of {checked} pages were captcha or anti-bot pages ({})",
+                s.warn_sym(),
+                kinds.join(", ")
+            );
+        }
+        if let Some(score) = result.pointer("/quality/score").and_then(|v| v.as_f64()) {
+            let fallbacks: Vec<&str> = result
+                .get("fallbacks")
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|v| v.as_str())
+                .collect();
+            let after = if fallbacks.is_empty() {
+                String::new()
+            } else {
+                format!(" (after {})", fallbacks.join(", ").replace('_', " "))
+            };
+            eprintln!("  {} Quality {score:.2}{after}", s.info_sym());
+        }
+        let warnings = result
+            .pointer("/quality/warnings")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str());
+        for warning in warnings {
+            eprintln!("  {} Quality: {warning}", s.warn_sym());
+        }
+        if result.get("resumable").and_then(|v| v.as_bool()) == Some(true) {
+            eprintln!(
+                "  {} Time ran out before sampling finished. Continue with: cortex map {domain} --resume",
+                s.info_sym()
+            );
+        }
     }
 
     Ok(())
 }
 
+/// Ask the daemon to cancel MAP request `request_id`.
+async fn cancel_map(socket_path: &str, request_id: &str) -> Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let mut stream = tokio::net::UnixStream::connect(socket_path)
+        .await
+        .context("cannot connect to Cortex daemon to cancel")?;
+    let mut req = serde_json::json!({
+        "id": format!("{request_id}-cancel"),
+        "method": "cancel",
+        "params": {"request_id": request_id},
+    });
+    api_keys::attach_client_key(&mut req);
+    stream.write_all(format!("{req}\n").as_bytes()).await?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line).await?;
+    Ok(())
+}
+
 /// Print map stats in compact friendly format.
 fn print_map_stats(s: &Styled, map: &SiteMap, elapsed: std::time::Duration) {
     eprintln!(
@@ -215,28 +340,6 @@ fn print_map_stats(s: &Styled, map: &SiteMap, elapsed: std::time::Duration) {
     );
 }
 
-/// Print map stats as JSON.
-fn print_map_json(map: &SiteMap, elapsed: std::time::Duration) {
-    let rendered = map.nodes.iter().filter(|n| n.flags.is_rendered()).count();
-
-    let mut type_counts: HashMap<String, usize> = HashMap::new();
-    for node in &map.nodes {
-        let name = format!("{:?}", node.page_type).to_lowercase();
-        *type_counts.entry(name).or_default() += 1;
-    }
-
-    output::print_json(&serde_json::json!({
-        "domain": map.header.domain,
-        "nodes": map.nodes.len(),
-        "edges": map.edges.len(),
-        "rendered": rendered,
-        "clusters": map.cluster_centroids.len(),
-        "actions": map.actions.len(),
-        "page_types": type_counts,
-        "duration_ms": elapsed.as_millis(),
-    }));
-}
-
 /// Auto-install Chromium and auto-start daemon if needed.
 ///
 /// Returns `true` if any setup action was taken, `false` if already ready.
@@ -321,87 +424,32 @@ fn format_count(n: u64) -> String {
     result.chars().rev().collect()
 }
 
-/// Subscribe to SSE events and print live mapping progress.
-///
-/// Best-effort: if the REST API is not running, this silently returns.
-/// Runs concurrently with the socket MAP request. Uses a raw TCP
-/// connection to avoid additional crate dependencies for streaming.
-async fn stream_progress_from_sse(domain: String) {
-    use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
-    use tokio::net::TcpStream;
-
-    // Connect to the REST API
-    let mut stream = match TcpStream::connect("127.0.0.1:7700").await {
-        Ok(s) => s,
-        Err(_) => return, // REST API not available — skip live progress
-    };
-
-    // Send HTTP GET request for SSE
-    let request = format!(
-        "GET /api/v1/events?domain={domain} HTTP/1.1\r\n\
-         Host: 127.0.0.1:7700\r\n\
-         Accept: text/event-stream\r\n\
-         Connection: keep-alive\r\n\
-         \r\n"
+/// One line of the live progress bar: completion, phase, pages and ETA.
+fn format_progress(s: &Styled, progress: &MapProgress) -> String {
+    let filled = (progress.percent.min(100) as usize * 40) / 100;
+    let mut line = format!(
+        "  {}{} {:>3}%  {}",
+        s.green(&"\u{2588}".repeat(filled)),
+        "\u{2591}".repeat(40 - filled),
+        progress.percent,
+        progress.phase,
     );
-    if stream.write_all(request.as_bytes()).await.is_err() {
-        return;
-    }
-
-    let s = Styled::new();
-    let reader = tokio::io::BufReader::new(stream);
-    let mut lines = reader.lines();
-
-    // Skip HTTP response headers
-    let mut past_headers = false;
-    while let Ok(Some(line)) = lines.next_line().await {
-        if line.is_empty() {
-            past_headers = true;
-            break;
-        }
+    if progress.pages_planned > 0 {
+        line.push_str(&format!(
+            " \u{00b7} {}/{} pages",
+            progress.pages_fetched, progress.pages_planned
+        ));
+    } else if progress.urls_discovered > 0 {
+        line.push_str(&format!(
+            " \u{00b7} {} URLs",
+            format_count(progress.urls_discovered as u64)
+        ));
     }
-    if !past_headers {
-        return;
-    }
-
-    // Track progress via SSE events and render a single progress bar
-    let total_layers = 5u64;
-    let mut completed_layers = 0u64;
-
-    while let Ok(Some(line)) = lines.next_line().await {
-        let trimmed = line.trim();
-        if !trimmed.starts_with("data:") {
-            continue;
-        }
-        let json_str = trimmed.trim_start_matches("data:").trim();
-        if json_str.is_empty() {
-            continue;
-        }
-
-        let event: serde_json::Value = match serde_json::from_str(json_str) {
-            Ok(v) => v,
-            Err(_) => continue,
-        };
-
-        let event_type = event.get("type").and_then(|v| v.as_str()).unwrap_or("");
-        match event_type {
-            "SitemapDiscovered" | "StructuredDataExtracted" | "LayerComplete" => {
-                completed_layers += 1;
-                let pct = std::cmp::min((completed_layers * 100) / total_layers, 99);
-                let filled = (pct as usize * 40) / 100;
-                let empty = 40 - filled;
-                // Overwrite the progress line with \r
-                eprint!(
-                    "\r  {}{} {:>3}%",
-                    s.green(&"\u{2588}".repeat(filled)),
-                    "\u{2591}".repeat(empty),
-                    pct,
-                );
-            }
-            "MapComplete" | "MapFailed" => {
-                break;
-            }
-            _ => {}
-        }
+    if let Some(eta_ms) = progress.eta_ms.filter(|&ms| ms > 0) {
+        line.push_str(&format!(
+            " \u{00b7} ETA {}",
+            output::format_duration(eta_ms.div_ceil(1000))
+        ));
     }
+    line
 }
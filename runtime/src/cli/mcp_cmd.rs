@@ -0,0 +1,39 @@
+//! `cortex mcp` — serve Cortex tools to MCP clients over stdio or HTTP + SSE.
+
+use crate::cli::output;
+use crate::cli::start::SOCKET_PATH;
+use crate::mcp::{self, McpServer};
+use anyhow::{Context, Result};
+
+/// Serve MCP on stdio, or on `sse` (an `addr:port`) when given.
+pub async fn run(sse: Option<&str>, token: Option<&str>) -> Result<()> {
+    let socket = std::env::var("CORTEX_SOCKET").unwrap_or_else(|_| SOCKET_PATH.to_string());
+    let server = McpServer::new(socket);
+
+    let Some(addr) = sse else {
+        // stdout carries the protocol; nothing else may be printed there.
+        return mcp::serve_stdio(&server).await;
+    };
+    let addr: std::net::SocketAddr = addr
+        .parse()
+        .with_context(|| format!("invalid --sse address {addr}"))?;
+    let token = token
+        .map(str::to_string)
+        .or_else(|| std::env::var("CORTEX_MCP_TOKEN").ok())
+        .filter(|t| !t.is_empty());
+    if token.is_none() && !addr.ip().is_loopback() {
+        anyhow::bail!(
+            "refusing to serve MCP on {addr} without auth; set --token or CORTEX_MCP_TOKEN, \
+             or bind a loopback address such as 127.0.0.1"
+        );
+    }
+    if !output::is_quiet() {
+        let auth = if token.is_some() {
+            "token auth"
+        } else {
+            "no auth"
+        };
+        eprintln!("  Serving MCP on http://{addr}/sse ({auth})");
+    }
+    mcp::serve_http(addr, server, token).await
+}
@@ -1,23 +1,41 @@
 //! CLI subcommand implementations for the Cortex binary.
 
+pub mod analyze_cmd;
+pub mod annotate_cmd;
+pub mod audit_cmd;
 pub mod cache_cmd;
 pub mod compile_cmd;
+pub mod daemon_log;
 pub mod doctor;
+pub mod events_cmd;
+pub mod extract_cmd;
+pub mod inspect_cmd;
 pub mod install_cmd;
+pub mod keys_cmd;
 pub mod map_cmd;
+pub mod mcp_cmd;
 pub mod output;
 pub mod pathfind_cmd;
 pub mod perceive_cmd;
 pub mod plug;
+pub mod predicate;
+pub mod profile_cmd;
+pub mod project_cmd;
 pub mod query_cmd;
 pub mod registry_cmd;
+pub mod reload_cmd;
 pub mod repl;
 pub mod repl_commands;
 pub mod repl_complete;
+pub mod repl_explore;
 pub mod repl_progress;
 pub mod restart_cmd;
+pub mod service_cmd;
 pub mod start;
 pub mod status;
 pub mod stop;
 pub mod temporal_cmd;
+pub mod trust_cmd;
+pub mod watch_cmd;
+pub mod worker_cmd;
 pub mod wql_cmd;
@@ -1,6 +1,15 @@
 //! Shared CLI output formatting with colors, symbols, and structured display.
-
+//!
+//! Machine-readable output (`--json`) goes to stdout as one JSON document per
+//! command, stamped with [`SCHEMA_VERSION`] and the command name. The result
+//! types below are the stable schemas for the commands CI scripts parse.
+
+use crate::cartography::quality::{self, QualityReport};
+use crate::map::types::{PageType, Path, SiteMap};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::io::Write;
+use std::sync::OnceLock;
 
 /// Check if color output is enabled.
 pub fn color_enabled() -> bool {
@@ -151,8 +160,14 @@ impl Styled {
     }
 }
 
+// The print helpers below write human output to stderr and are silent
+// under --quiet.
+
 /// Print a branded header for CLI output.
 pub fn print_header(s: &Styled) {
+    if is_quiet() {
+        return;
+    }
     eprintln!(
         "  {} {}",
         s.bold("Cortex"),
@@ -163,21 +178,33 @@ pub fn print_header(s: &Styled) {
 
 /// Print a section header (e.g., "System", "Browser", "Runtime").
 pub fn print_section(s: &Styled, title: &str) {
+    if is_quiet() {
+        return;
+    }
     eprintln!("  {}", s.bold(title));
 }
 
 /// Print a check result line with symbol and label/value.
 pub fn print_check(symbol: &str, label: &str, value: &str) {
+    if is_quiet() {
+        return;
+    }
     eprintln!("    {symbol} {label:<16} {value}");
 }
 
 /// Print an indented detail/fix line under a check.
 pub fn print_detail(msg: &str) {
+    if is_quiet() {
+        return;
+    }
     eprintln!("                        {msg}");
 }
 
 /// Print a status summary line at the bottom.
 pub fn print_status(s: &Styled, status: &str, msg: &str) {
+    if is_quiet() {
+        return;
+    }
     eprintln!();
     eprintln!("  {}: {status} ({msg})", s.bold("Status"));
 }
@@ -245,9 +272,241 @@ pub fn is_json() -> bool {
     std::env::var("CORTEX_JSON").is_ok()
 }
 
-/// Print JSON output to stdout and return.
+/// Version of the `--json` output schemas. Bumped when a field is removed or
+/// changes meaning; fields may be added without a bump.
+pub const SCHEMA_VERSION: u32 = 1;
+
+static COMMAND: OnceLock<String> = OnceLock::new();
+
+/// Record the name of the command being run (e.g. `map`, `cache clear`) so
+/// JSON output can be attributed to it. Only the first call takes effect.
+pub fn set_command(name: &str) {
+    let _ = COMMAND.set(name.to_string());
+}
+
+/// Print JSON output to stdout and return. Objects are stamped with
+/// `schema_version` and, when known, `command`.
 pub fn print_json(value: &serde_json::Value) {
-    if let Ok(s) = serde_json::to_string_pretty(value) {
+    let mut value = value.clone();
+    stamp(&mut value);
+    if let Ok(s) = serde_json::to_string_pretty(&value) {
         println!("{s}");
     }
 }
+
+/// Print a typed command result as JSON.
+pub fn print_result<T: Serialize>(result: &T) {
+    if let Ok(value) = serde_json::to_value(result) {
+        print_json(&value);
+    }
+}
+
+fn stamp(value: &mut serde_json::Value) {
+    if let Some(obj) = value.as_object_mut() {
+        obj.insert("schema_version".into(), SCHEMA_VERSION.into());
+        if let Some(command) = COMMAND.get() {
+            obj.entry("command")
+                .or_insert_with(|| command.as_str().into());
+        }
+    }
+}
+
+/// `cortex map` result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapResult {
+    pub domain: String,
+    pub nodes: usize,
+    pub edges: usize,
+    pub rendered: usize,
+    pub clusters: usize,
+    pub actions: usize,
+    /// Node count per page type.
+    pub page_types: BTreeMap<String, usize>,
+    /// Whether the map came from the local cache rather than a fresh crawl.
+    pub cached: bool,
+    pub map_path: Option<String>,
+    pub duration_ms: u64,
+    /// Connectivity, page types, feature fill and duplicates, scored.
+    pub quality: QualityReport,
+}
+
+impl MapResult {
+    pub fn new(
+        map: &SiteMap,
+        cached: bool,
+        map_path: Option<&std::path::Path>,
+        elapsed: std::time::Duration,
+    ) -> Self {
+        let mut page_types = BTreeMap::new();
+        for node in &map.nodes {
+            *page_types
+                .entry(format!("{:?}", node.page_type))
+                .or_default() += 1;
+        }
+        Self {
+            domain: map.header.domain.clone(),
+            nodes: map.nodes.len(),
+            edges: map.edges.len(),
+            rendered: map.nodes.iter().filter(|n| n.flags.is_rendered()).count(),
+            clusters: map.cluster_centroids.len(),
+            actions: map.actions.len(),
+            page_types,
+            cached,
+            map_path: map_path.map(|p| p.display().to_string()),
+            duration_ms: elapsed.as_millis() as u64,
+            quality: quality::assess(map),
+        }
+    }
+}
+
+/// One matching page in `cortex query` output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryRow {
+    /// With `--project`: the domain the page is on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    pub index: u32,
+    pub url: String,
+    pub page_type: PageType,
+    pub confidence: f32,
+    /// With `--text`: BM25 score of the page's title and description.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_score: Option<f32>,
+    /// With `--diversify`: URL template shared by the group.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// With `--diversify`: number of pages the row stands for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_size: Option<usize>,
+    /// With `--diversify`: node indexes of every page in the group.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub members: Option<Vec<u32>>,
+}
+
+/// `cortex query` result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryResult {
+    /// The domain queried; empty (and omitted) with `--project`.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub domain: String,
+    /// With `--project`: the project queried.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    /// Number of rows (groups, with `--diversify`).
+    pub total: usize,
+    pub rows: Vec<QueryRow>,
+}
+
+/// One node on a `cortex pathfind` route.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteStep {
+    pub index: u32,
+    pub url: String,
+    pub page_type: PageType,
+    /// Opcode `[category, action]` that must be executed at this node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<[u8; 2]>,
+}
+
+/// `cortex pathfind` result. `route` is empty when no path was found.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PathfindResult {
+    pub domain: String,
+    pub from: u32,
+    pub to: u32,
+    pub found: bool,
+    pub hops: u32,
+    pub total_weight: f32,
+    pub route: Vec<RouteStep>,
+}
+
+impl PathfindResult {
+    pub fn new(map: &SiteMap, from: u32, to: u32, path: Option<&Path>) -> Self {
+        let route = path
+            .map(|p| {
+                p.nodes
+                    .iter()
+                    .map(|&idx| RouteStep {
+                        index: idx,
+                        url: map.urls.get(idx as usize).cloned().unwrap_or_default(),
+                        page_type: map.nodes[idx as usize].page_type,
+                        action: p
+                            .required_actions
+                            .iter()
+                            .find(|a| a.at_node == idx)
+                            .map(|a| [a.opcode.category, a.opcode.action]),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            domain: map.header.domain.clone(),
+            from,
+            to,
+            found: path.is_some(),
+            hops: path.map_or(0, |p| p.hops),
+            total_weight: path.map_or(0.0, |p| p.total_weight),
+            route,
+        }
+    }
+}
+
+/// Browser pool usage reported by `cortex status`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PoolStatus {
+    pub active: u64,
+    pub max: u64,
+    pub memory_mb: u64,
+}
+
+/// `cortex status` result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusResult {
+    pub running: bool,
+    pub version: Option<String>,
+    pub pid: Option<i32>,
+    pub uptime_s: u64,
+    pub maps_cached: u64,
+    pub pool: Option<PoolStatus>,
+    /// Disk map cache usage, as reported by the daemon.
+    pub cache: Option<serde_json::Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stamp_adds_schema_version() {
+        let mut value = serde_json::json!({ "domain": "example.com" });
+        stamp(&mut value);
+        assert_eq!(value["schema_version"], SCHEMA_VERSION);
+        assert_eq!(value["domain"], "example.com");
+
+        // Non-object values are left alone.
+        let mut rows = serde_json::json!([1, 2]);
+        stamp(&mut rows);
+        assert_eq!(rows, serde_json::json!([1, 2]));
+    }
+
+    #[test]
+    fn test_query_row_omits_group_fields() {
+        let row = QueryRow {
+            domain: None,
+            index: 3,
+            url: "https://example.com/p/1".into(),
+            page_type: PageType::ProductDetail,
+            confidence: 0.9,
+            text_score: None,
+            template: None,
+            group_size: None,
+            members: None,
+        };
+        let value = serde_json::to_value(&row).unwrap();
+        assert_eq!(value["page_type"], "ProductDetail");
+        assert!(value.get("template").is_none());
+        assert!(value.get("domain").is_none());
+        let back: QueryRow = serde_json::from_value(value).unwrap();
+        assert_eq!(back, row);
+    }
+}
@@ -11,19 +11,8 @@ pub async fn run(domain: &str, from: u32, to: u32) -> Result<()> {
 
     // Load cached map
     let mut cache = MapCache::default_cache()?;
-    let map = match cache.load_map(domain)? {
-        Some(m) => m,
-        None => {
-            if output::is_json() {
-                output::print_json(&serde_json::json!({
-                    "error": "no_map",
-                    "message": format!("No cached map for '{domain}'"),
-                    "hint": format!("Run: cortex map {domain}")
-                }));
-                return Ok(());
-            }
-            bail!("No map found for '{domain}'. Run 'cortex map {domain}' first.");
-        }
+    let Some(map) = cache.load_map(domain)? else {
+        bail!("No map found for '{domain}'. Run 'cortex map {domain}' first.");
     };
 
     // Validate node indices
@@ -45,37 +34,7 @@ pub async fn run(domain: &str, from: u32, to: u32) -> Result<()> {
     let path = map.shortest_path(from, to, &constraints);
 
     if output::is_json() {
-        match &path {
-            Some(p) => {
-                let nodes: Vec<serde_json::Value> = p
-                    .nodes
-                    .iter()
-                    .map(|&idx| {
-                        let url = map.urls.get(idx as usize).cloned().unwrap_or_default();
-                        let node = &map.nodes[idx as usize];
-                        serde_json::json!({
-                            "index": idx,
-                            "url": url,
-                            "page_type": format!("{:?}", node.page_type),
-                        })
-                    })
-                    .collect();
-                output::print_json(&serde_json::json!({
-                    "found": true,
-                    "hops": p.hops,
-                    "total_weight": p.total_weight,
-                    "nodes": nodes,
-                    "required_actions": p.required_actions.len(),
-                }));
-            }
-            None => {
-                output::print_json(&serde_json::json!({
-                    "found": false,
-                    "from": from,
-                    "to": to,
-                }));
-            }
-        }
+        output::print_result(&output::PathfindResult::new(&map, from, to, path.as_ref()));
         return Ok(());
     }
 
@@ -4,45 +4,68 @@
 //! `cortex plug` — auto-discover and inject into AI agents.
 //!
 //! Scans the machine for known AI agent configurations and injects
-//! Cortex as an MCP server into each one. Supports `--list`, `--remove`,
-//! `--status`, and `--agent <name>` for fine-grained control.
+//! Cortex as an MCP server into each one, in the config format that agent
+//! reads. Supports `--list`, `--remove`, `--status`, `--doctor`,
+//! `--agent <name>`, and `--project [dir]` for project-local configs.
+//!
+//! After a global `cortex plug`, the daemon keeps configs current on
+//! start: agents installed since are connected and outdated entries are
+//! rewritten (see [`auto_update`]).
 
-use anyhow::Result;
+use crate::audit::logger::{self as audit, AuditEvent};
+use crate::cli::doctor::cortex_home;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How long `--doctor` waits for an injected server to answer `initialize`.
+/// Generous because the first `npx` run downloads the package.
+const LAUNCH_TIMEOUT: Duration = Duration::from_secs(60);
 
 /// Run the plug command.
+#[allow(clippy::too_many_arguments)]
 pub async fn run(
     list_only: bool,
     remove: bool,
     status_only: bool,
+    doctor: bool,
     agent: Option<&str>,
+    project: Option<&str>,
     config_dir: Option<&str>,
 ) -> Result<()> {
     let quiet = crate::cli::output::is_quiet();
     let json_mode = crate::cli::output::is_json();
     let s = crate::cli::output::Styled::new();
 
-    let probes = if let Some(dir) = config_dir {
-        build_test_probes(dir)
-    } else {
-        build_probes()
+    let scope = match (config_dir, project) {
+        (Some(dir), _) => Scope::ConfigDir(PathBuf::from(dir)),
+        (None, Some(dir)) => Scope::Project(std::fs::canonicalize(dir).unwrap_or(dir.into())),
+        (None, None) => Scope::Global,
     };
+    let probes = build_probes();
+
+    if doctor {
+        return run_doctor(&probes, &scope, agent).await;
+    }
+
     let mut connected_names: Vec<&str> = Vec::new();
     let mut json_results: Vec<serde_json::Value> = Vec::new();
     let mut needs_restart: Vec<&str> = Vec::new();
+    let mut written: Vec<PathBuf> = Vec::new();
+    let mut detected: Vec<&str> = Vec::new();
 
     for probe in &probes {
         // Filter to specific agent if requested
         if let Some(target) = agent {
-            if !probe.name.eq_ignore_ascii_case(target)
-                && !probe.short_name.eq_ignore_ascii_case(target)
-            {
+            if !probe.matches(target) {
                 continue;
             }
         }
 
-        let config_path = match probe.detect() {
+        let config_path = match probe.config_path(&scope) {
             Some(p) => p,
             None => {
                 if (list_only || status_only) && json_mode {
@@ -54,6 +77,7 @@ pub async fn run(
                 continue;
             }
         };
+        detected.push(probe.short_name);
 
         if list_only {
             if json_mode {
@@ -61,27 +85,37 @@ pub async fn run(
                     "agent": probe.name,
                     "detected": true,
                     "config_path": config_path.display().to_string(),
+                    "config_format": probe.schema.name(),
                 }));
+            } else if !quiet {
+                eprintln!(
+                    "  {} {:<20} {}",
+                    s.info_sym(),
+                    probe.name,
+                    s.dim(&config_path.display().to_string())
+                );
             }
             connected_names.push(probe.name);
             continue;
         }
 
         if status_only {
-            let has_cortex = check_cortex_present(&config_path);
+            let entry = read_entry(&config_path, probe.schema).ok().flatten();
+            let has_cortex = entry.is_some();
+            let current = entry.as_ref().is_some_and(|e| e.is_current());
             if json_mode {
                 json_results.push(json!({
                     "agent": probe.name,
                     "detected": true,
                     "config_path": config_path.display().to_string(),
                     "cortex_connected": has_cortex,
+                    "up_to_date": current,
                 }));
             } else if !quiet {
-                let symbol = if has_cortex { s.ok_sym() } else { s.info_sym() };
-                let status = if has_cortex {
-                    "connected"
-                } else {
-                    "not connected"
+                let (symbol, status) = match (has_cortex, current) {
+                    (true, true) => (s.ok_sym(), "connected"),
+                    (true, false) => (s.warn_sym(), "connected (outdated entry)"),
+                    _ => (s.info_sym(), "not connected"),
                 };
                 eprintln!("  {} {:<20} {}", symbol, probe.name, status);
             }
@@ -92,8 +126,9 @@ pub async fn run(
         }
 
         if remove {
-            match remove_mcp_server(&config_path) {
+            match remove_mcp_server(&config_path, probe.schema) {
                 Ok(RemovalResult::Removed) => {
+                    written.push(config_path.clone());
                     if json_mode {
                         json_results.push(json!({
                             "agent": probe.name,
@@ -110,6 +145,8 @@ pub async fn run(
                             "action": "error",
                             "error": e.to_string(),
                         }));
+                    } else if !quiet {
+                        eprintln!("  {} {}: {e:#}", s.warn_sym(), probe.name);
                     }
                 }
             }
@@ -117,13 +154,14 @@ pub async fn run(
         }
 
         // Inject
-        match inject_mcp_server(&config_path) {
-            Ok(InjectionResult::Injected) => {
+        match inject_mcp_server(&config_path, probe.schema) {
+            Ok(result @ (InjectionResult::Injected | InjectionResult::Updated)) => {
                 connected_names.push(probe.name);
+                written.push(config_path.clone());
                 if json_mode {
                     json_results.push(json!({
                         "agent": probe.name,
-                        "action": "injected",
+                        "action": result.name(),
                         "config_path": config_path.display().to_string(),
                         "needs_restart": probe.needs_restart,
                     }));
@@ -148,18 +186,48 @@ pub async fn run(
                         "action": "error",
                         "error": e.to_string(),
                     }));
+                } else if !quiet {
+                    eprintln!("  {} {}: {e:#}", s.warn_sym(), probe.name);
                 }
             }
         }
     }
 
+    // Remember what was plugged so the daemon can keep it current
+    if matches!(scope, Scope::Global) && !list_only && !status_only {
+        let mut state = PlugState::load().unwrap_or_default();
+        if agent.is_none() {
+            state.auto_update = !remove;
+        }
+        for name in detected {
+            if !state.known.iter().any(|k| k == name) {
+                state.known.push(name.to_string());
+            }
+        }
+        if let Err(e) = state.save() {
+            tracing::warn!("failed to save plug state: {e}");
+        }
+    }
+
+    if !written.is_empty() {
+        let mut event = AuditEvent::new("plug", &audit::local_actor());
+        event.params = json!({
+            "remove": remove,
+            "agent": agent,
+            "scope": scope.name(),
+            "files": written,
+        });
+        audit::record(&event);
+    }
+
     // Output
     if json_mode {
         crate::cli::output::print_json(&json!({
             "agents": json_results,
             "connected": connected_names.len(),
+            "scope": scope.name(),
         }));
-    } else if !quiet {
+    } else if !quiet && !list_only {
         if remove {
             if connected_names.is_empty() {
                 eprintln!("  {} No agents to disconnect.", s.info_sym());
@@ -172,7 +240,14 @@ pub async fn run(
                 eprintln!("  {} Removed from {}", s.ok_sym(), names_str);
             }
         } else if connected_names.is_empty() {
-            eprintln!("  {} No agents detected.", s.info_sym());
+            if matches!(scope, Scope::Project(_)) {
+                eprintln!(
+                    "  {} No detected agent reads project-local configs.",
+                    s.info_sym()
+                );
+            } else {
+                eprintln!("  {} No agents detected.", s.info_sym());
+            }
         } else {
             // Compact one-liner: ✓ Claude Desktop · Claude Code · Cursor — 9 tools each
             let names_str = connected_names
@@ -186,6 +261,11 @@ pub async fn run(
                 names_str,
                 s.blue("9"),
             );
+            if matches!(scope, Scope::Project(_)) {
+                for path in &written {
+                    eprintln!("    {}", s.dim(&path.display().to_string()));
+                }
+            }
             if !needs_restart.is_empty() {
                 eprintln!(
                     "  {} Restart to activate: {}",
@@ -194,24 +274,325 @@ pub async fn run(
                 );
             }
         }
+    } else if !quiet && list_only && connected_names.is_empty() {
+        eprintln!("  {} No agents detected.", s.info_sym());
     }
 
     Ok(())
 }
 
+/// Bring agent configs up to date after a global `cortex plug`: connect
+/// agents installed since, and rewrite outdated Cortex entries. Agents the
+/// user removed Cortex from are left alone. Returns one message per change.
+pub fn auto_update() -> Result<Vec<String>> {
+    let mut state = PlugState::load()?;
+    if !state.auto_update {
+        return Ok(Vec::new());
+    }
+
+    let mut changes = Vec::new();
+    let mut written = Vec::new();
+    for probe in build_probes() {
+        let Some(path) = probe.config_path(&Scope::Global) else {
+            continue;
+        };
+        let known = state.known.iter().any(|k| k == probe.short_name);
+        if known && !check_cortex_present(&path, probe.schema) {
+            continue;
+        }
+        match inject_mcp_server(&path, probe.schema) {
+            Ok(InjectionResult::Injected) => {
+                changes.push(format!("connected {}", probe.name));
+                written.push(path);
+            }
+            Ok(InjectionResult::Updated) => {
+                changes.push(format!("updated {}", probe.name));
+                written.push(path);
+            }
+            Ok(InjectionResult::AlreadyPresent) => {}
+            Err(e) => changes.push(format!("could not update {}: {e:#}", probe.name)),
+        }
+        if !known {
+            state.known.push(probe.short_name.to_string());
+        }
+    }
+    state.save()?;
+    if !written.is_empty() {
+        let mut event = AuditEvent::new("plug", "daemon");
+        event.params = json!({ "auto_update": true, "files": written });
+        audit::record(&event);
+    }
+    Ok(changes)
+}
+
+/// `cortex plug --doctor`: check that each injected config parses, is
+/// current, names a command on PATH, and launches a server that answers
+/// the MCP `initialize` handshake.
+async fn run_doctor(probes: &[AgentProbe], scope: &Scope, agent: Option<&str>) -> Result<()> {
+    let json_mode = crate::cli::output::is_json();
+    let quiet = crate::cli::output::is_quiet();
+    let s = crate::cli::output::Styled::new();
+
+    let targets: Vec<(&AgentProbe, PathBuf)> = probes
+        .iter()
+        .filter(|p| agent.is_none_or(|a| p.matches(a)))
+        .filter_map(|p| Some((p, p.config_path(scope)?)))
+        .collect();
+    // Launch checks wait on servers starting, so run them side by side
+    let checks = futures::future::join_all(
+        targets
+            .iter()
+            .map(|(probe, path)| doctor_check(probe, path)),
+    )
+    .await;
+
+    let mut results = Vec::new();
+    let mut failures = 0;
+    for ((probe, path), check) in targets.iter().zip(checks) {
+        let path_str = path.display().to_string();
+        if !quiet && !json_mode {
+            eprintln!();
+            eprintln!("  {} {}", s.bold(probe.name), s.dim(&path_str));
+            for (label, outcome) in &check.lines {
+                let symbol = match outcome {
+                    Ok(_) => s.ok_sym(),
+                    Err(_) => s.fail_sym(),
+                };
+                let detail = match outcome {
+                    Ok(d) | Err(d) => d.as_str(),
+                };
+                crate::cli::output::print_check(symbol, label, detail);
+            }
+        }
+        if !check.healthy() {
+            failures += 1;
+        }
+        results.push(json!({
+            "agent": probe.name,
+            "config_path": path_str,
+            "healthy": check.healthy(),
+            "checks": check.lines.iter().map(|(label, outcome)| json!({
+                "check": label,
+                "ok": outcome.is_ok(),
+                "detail": match outcome { Ok(d) | Err(d) => d },
+            })).collect::<Vec<_>>(),
+        }));
+    }
+
+    if json_mode {
+        crate::cli::output::print_json(&json!({
+            "agents": results,
+            "healthy": results.len() - failures,
+            "failing": failures,
+        }));
+    } else if !quiet {
+        if results.is_empty() {
+            eprintln!("  {} No agents detected.", s.info_sym());
+        } else {
+            crate::cli::output::print_status(
+                &s,
+                if failures == 0 {
+                    "healthy"
+                } else {
+                    "problems found"
+                },
+                &format!(
+                    "{} of {} agents launch Cortex",
+                    results.len() - failures,
+                    results.len()
+                ),
+            );
+        }
+    }
+    if failures > 0 {
+        bail!("{failures} agent config(s) failed validation");
+    }
+    Ok(())
+}
+
+/// Outcome of `--doctor` for one agent: labelled checks, in order.
+struct DoctorCheck {
+    lines: Vec<(&'static str, std::result::Result<String, String>)>,
+}
+
+impl DoctorCheck {
+    fn healthy(&self) -> bool {
+        self.lines.iter().all(|(_, r)| r.is_ok())
+    }
+}
+
+async fn doctor_check(probe: &AgentProbe, path: &Path) -> DoctorCheck {
+    let mut lines = Vec::new();
+    let entry = match read_entry(path, probe.schema) {
+        Ok(Some(entry)) => {
+            lines.push(("config", Ok(format!("{} entry found", probe.schema.name()))));
+            entry
+        }
+        Ok(None) => {
+            lines.push((
+                "config",
+                Err("Cortex not injected — run: cortex plug".to_string()),
+            ));
+            return DoctorCheck { lines };
+        }
+        Err(e) => {
+            lines.push(("config", Err(format!("{e:#}"))));
+            return DoctorCheck { lines };
+        }
+    };
+
+    lines.push((
+        "entry",
+        if entry.is_current() {
+            Ok("up to date".to_string())
+        } else {
+            Err("outdated — re-run: cortex plug".to_string())
+        },
+    ));
+
+    match which::which(&entry.command) {
+        Ok(found) => lines.push(("command", Ok(found.display().to_string()))),
+        Err(_) => {
+            lines.push((
+                "command",
+                Err(format!("'{}' not found on PATH", entry.command)),
+            ));
+            return DoctorCheck { lines };
+        }
+    }
+
+    lines.push((
+        "launch",
+        probe_launch(&entry)
+            .await
+            .map(|server| format!("answered initialize ({server})"))
+            .map_err(|e| format!("{e:#}")),
+    ));
+    DoctorCheck { lines }
+}
+
+/// Launch an MCP server the way an agent would and wait for its reply to
+/// `initialize`. Returns the server's reported name and version.
+async fn probe_launch(spec: &LaunchSpec) -> Result<String> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let mut child = tokio::process::Command::new(&spec.command)
+        .args(&spec.args)
+        .envs(&spec.env)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("failed to start '{}'", spec.command))?;
+
+    let mut stdin = child.stdin.take().context("no stdin")?;
+    let stdout = child.stdout.take().context("no stdout")?;
+    let init = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": {"name": "cortex-plug-doctor", "version": env!("CARGO_PKG_VERSION")},
+        }
+    });
+    stdin.write_all(format!("{init}\n").as_bytes()).await?;
+
+    let mut lines = BufReader::new(stdout).lines();
+    let reply = tokio::time::timeout(LAUNCH_TIMEOUT, async {
+        while let Some(line) = lines.next_line().await? {
+            if let Ok(msg) = serde_json::from_str::<serde_json::Value>(&line) {
+                if msg.get("id") == Some(&json!(1)) {
+                    return Ok(Some(msg));
+                }
+            }
+        }
+        Ok::<_, anyhow::Error>(None)
+    })
+    .await;
+    let _ = child.kill().await;
+
+    let msg = match reply {
+        Err(_) => bail!("no response within {}s", LAUNCH_TIMEOUT.as_secs()),
+        Ok(result) => match result? {
+            Some(msg) => msg,
+            None => bail!("exited without answering initialize"),
+        },
+    };
+    if let Some(error) = msg.get("error") {
+        bail!("initialize failed: {error}");
+    }
+    let info = &msg["result"]["serverInfo"];
+    Ok(format!(
+        "{} {}",
+        info["name"].as_str().unwrap_or("server"),
+        info["version"].as_str().unwrap_or("")
+    )
+    .trim_end()
+    .to_string())
+}
+
 // ── Agent Probes ────────────────────────────────────────────────
 
+/// Where configs are written.
+enum Scope {
+    /// Each agent's user-wide config.
+    Global,
+    /// Configs inside a project directory, for agents that read them.
+    Project(PathBuf),
+    /// A test directory with one subdirectory per agent.
+    ConfigDir(PathBuf),
+}
+
+impl Scope {
+    fn name(&self) -> &'static str {
+        match self {
+            Scope::Global => "global",
+            Scope::Project(_) => "project",
+            Scope::ConfigDir(_) => "config_dir",
+        }
+    }
+}
+
 /// An agent probe knows how to detect and locate an agent's config.
 struct AgentProbe {
     name: &'static str,
     short_name: &'static str,
     needs_restart: bool,
+    schema: ConfigSchema,
+    /// Global config path, if the agent is installed.
     detect_fn: fn() -> Option<PathBuf>,
+    /// Project-local config path, relative to the project root.
+    project_path: Option<&'static str>,
+    /// Config path under `--config-dir`; the first component is the
+    /// agent's directory.
+    test_path: &'static str,
 }
 
 impl AgentProbe {
-    fn detect(&self) -> Option<PathBuf> {
-        (self.detect_fn)()
+    fn matches(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name) || self.short_name.eq_ignore_ascii_case(name)
+    }
+
+    /// The config file to use in `scope`, or `None` if the agent is not
+    /// installed (or has no config there).
+    fn config_path(&self, scope: &Scope) -> Option<PathBuf> {
+        match scope {
+            Scope::Global => (self.detect_fn)(),
+            Scope::Project(root) => {
+                let rel = self.project_path?;
+                (self.detect_fn)()?;
+                Some(root.join(rel))
+            }
+            Scope::ConfigDir(base) => {
+                let agent_dir = self.test_path.split('/').next()?;
+                base.join(agent_dir)
+                    .exists()
+                    .then(|| base.join(self.test_path))
+            }
+        }
     }
 }
 
@@ -225,116 +606,95 @@ fn build_probes() -> Vec<AgentProbe> {
             name: "Claude Desktop",
             short_name: "claude-desktop",
             needs_restart: true,
+            schema: ConfigSchema::McpServers,
             detect_fn: detect_claude_desktop,
+            project_path: None,
+            test_path: "claude/claude_desktop_config.json",
         },
         AgentProbe {
             name: "Claude Code",
             short_name: "claude-code",
             needs_restart: false,
+            schema: ConfigSchema::McpServers,
             detect_fn: detect_claude_code,
+            project_path: Some(".mcp.json"),
+            test_path: "claude-code/settings.json",
         },
         AgentProbe {
             name: "Cursor",
             short_name: "cursor",
             needs_restart: true,
+            schema: ConfigSchema::McpServers,
             detect_fn: detect_cursor,
+            project_path: Some(".cursor/mcp.json"),
+            test_path: "cursor/mcp.json",
         },
         AgentProbe {
             name: "Windsurf",
             short_name: "windsurf",
             needs_restart: true,
+            schema: ConfigSchema::McpServers,
             detect_fn: detect_windsurf,
+            project_path: None,
+            test_path: "windsurf/mcp_config.json",
         },
         AgentProbe {
             name: "Continue",
             short_name: "continue",
             needs_restart: false,
+            schema: ConfigSchema::McpServers,
             detect_fn: detect_continue,
+            project_path: Some(".continue/mcpServers/cortex.json"),
+            test_path: "continue/config.json",
         },
         AgentProbe {
             name: "Cline",
             short_name: "cline",
             needs_restart: false,
+            schema: ConfigSchema::McpServers,
             detect_fn: detect_cline,
+            project_path: None,
+            test_path: "cline/cline_mcp_settings.json",
+        },
+        AgentProbe {
+            name: "VS Code",
+            short_name: "vscode",
+            needs_restart: false,
+            schema: ConfigSchema::VsCode,
+            detect_fn: detect_vscode,
+            project_path: Some(".vscode/mcp.json"),
+            test_path: "vscode/mcp.json",
+        },
+        AgentProbe {
+            name: "Zed",
+            short_name: "zed",
+            needs_restart: false,
+            schema: ConfigSchema::Zed,
+            detect_fn: detect_zed,
+            project_path: Some(".zed/settings.json"),
+            test_path: "zed/settings.json",
+        },
+        AgentProbe {
+            name: "Gemini CLI",
+            short_name: "gemini",
+            needs_restart: true,
+            schema: ConfigSchema::McpServers,
+            detect_fn: detect_gemini,
+            project_path: Some(".gemini/settings.json"),
+            test_path: "gemini/settings.json",
+        },
+        AgentProbe {
+            name: "OpenAI Codex",
+            short_name: "codex",
+            needs_restart: true,
+            schema: ConfigSchema::CodexToml,
+            detect_fn: detect_codex,
+            project_path: Some(".codex/config.toml"),
+            test_path: "codex/config.toml",
         },
     ]
 }
 
-/// Build probes that point to a test config directory instead of real agent paths.
-fn build_test_probes(config_dir: &str) -> Vec<AgentProbe> {
-    let base = PathBuf::from(config_dir);
-    // Leak the PathBuf into 'static lifetime for the fn pointers.
-    // This is fine — test mode only, process exits soon.
-    let base: &'static Path = Box::leak(base.into_boxed_path());
-
-    // We can't use fn pointers with captures, so use a global approach:
-    // create test config files for each known agent pattern.
-    let pairs: Vec<(&'static str, &'static str, &'static str, bool)> = vec![
-        (
-            "Claude Desktop",
-            "claude-desktop",
-            "claude/claude_desktop_config.json",
-            true,
-        ),
-        ("Cursor", "cursor", "cursor/mcp.json", true),
-        ("Continue", "continue", "continue/config.json", false),
-    ];
-
-    pairs
-        .into_iter()
-        .filter_map(|(name, short, rel_path, needs_restart)| {
-            let config_path = base.join(rel_path);
-            if config_path.parent()?.exists() {
-                Some(AgentProbe {
-                    name,
-                    short_name: short,
-                    needs_restart,
-                    detect_fn: {
-                        // Store the path in a leaked static so fn pointer can reference it.
-                        let p: &'static Path = Box::leak(config_path.into_boxed_path());
-                        // We need unique fn pointers per agent. Use a static array approach.
-                        match short {
-                            "claude-desktop" => {
-                                static mut TEST_PATH_CLAUDE: Option<&'static Path> = None;
-                                unsafe {
-                                    TEST_PATH_CLAUDE = Some(p);
-                                }
-                                fn detect() -> Option<PathBuf> {
-                                    unsafe { TEST_PATH_CLAUDE.map(|p| p.to_path_buf()) }
-                                }
-                                detect
-                            }
-                            "cursor" => {
-                                static mut TEST_PATH_CURSOR: Option<&'static Path> = None;
-                                unsafe {
-                                    TEST_PATH_CURSOR = Some(p);
-                                }
-                                fn detect() -> Option<PathBuf> {
-                                    unsafe { TEST_PATH_CURSOR.map(|p| p.to_path_buf()) }
-                                }
-                                detect
-                            }
-                            "continue" => {
-                                static mut TEST_PATH_CONTINUE: Option<&'static Path> = None;
-                                unsafe {
-                                    TEST_PATH_CONTINUE = Some(p);
-                                }
-                                fn detect() -> Option<PathBuf> {
-                                    unsafe { TEST_PATH_CONTINUE.map(|p| p.to_path_buf()) }
-                                }
-                                detect
-                            }
-                            _ => return None,
-                        }
-                    },
-                })
-            } else {
-                None
-            }
-        })
-        .collect()
-}
-
 fn detect_claude_desktop() -> Option<PathBuf> {
     let candidates = if cfg!(target_os = "macos") {
         vec![home_dir().join("Library/Application Support/Claude/claude_desktop_config.json")]
@@ -388,7 +748,8 @@ fn detect_windsurf() -> Option<PathBuf> {
 }
 
 fn detect_continue() -> Option<PathBuf> {
-    let config = home_dir().join(".continue/config.json");
+    // Continue loads every file in mcpServers/, in the same format as Claude's
+    let config = home_dir().join(".continue/mcpServers/cortex.json");
     if home_dir().join(".continue").exists() {
         Some(config)
     } else {
@@ -398,12 +759,7 @@ fn detect_continue() -> Option<PathBuf> {
 
 fn detect_cline() -> Option<PathBuf> {
     // Cline stores config in VS Code's globalStorage
-    let base = if cfg!(target_os = "macos") {
-        home_dir()
-            .join("Library/Application Support/Code/User/globalStorage/saoudrizwan.claude-dev")
-    } else {
-        home_dir().join(".config/Code/User/globalStorage/saoudrizwan.claude-dev")
-    };
+    let base = vscode_user_dir().join("globalStorage/saoudrizwan.claude-dev");
     if base.exists() {
         Some(base.join("settings/cline_mcp_settings.json"))
     } else {
@@ -411,7 +767,88 @@ fn detect_cline() -> Option<PathBuf> {
     }
 }
 
-// ── MCP Config Injection ────────────────────────────────────────
+fn vscode_user_dir() -> PathBuf {
+    if cfg!(target_os = "macos") {
+        home_dir().join("Library/Application Support/Code/User")
+    } else {
+        home_dir().join(".config/Code/User")
+    }
+}
+
+fn detect_vscode() -> Option<PathBuf> {
+    let user = vscode_user_dir();
+    if user.exists() {
+        Some(user.join("mcp.json"))
+    } else {
+        None
+    }
+}
+
+fn detect_zed() -> Option<PathBuf> {
+    let dir = home_dir().join(".config/zed");
+    if dir.exists() {
+        Some(dir.join("settings.json"))
+    } else {
+        None
+    }
+}
+
+fn detect_gemini() -> Option<PathBuf> {
+    let dir = home_dir().join(".gemini");
+    if dir.exists() || which::which("gemini").is_ok() {
+        Some(dir.join("settings.json"))
+    } else {
+        None
+    }
+}
+
+fn detect_codex() -> Option<PathBuf> {
+    let dir = home_dir().join(".codex");
+    if dir.exists() || which::which("codex").is_ok() {
+        Some(dir.join("config.toml"))
+    } else {
+        None
+    }
+}
+
+// ── Config Schemas ──────────────────────────────────────────────
+
+/// How an agent's config file declares MCP servers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConfigSchema {
+    /// `{"mcpServers": {"cortex": {"command", "args", "env"}}}` — Claude,
+    /// Cursor, Windsurf, Cline, Continue and Gemini CLI.
+    McpServers,
+    /// VS Code `mcp.json`: `{"servers": {"cortex": {"type": "stdio", ...}}}`.
+    VsCode,
+    /// Zed `settings.json` (JSON with comments):
+    /// `{"context_servers": {"cortex": {"source": "custom", ...}}}`.
+    Zed,
+    /// Codex `config.toml`: a `[mcp_servers.cortex]` table.
+    CodexToml,
+}
+
+impl ConfigSchema {
+    fn name(self) -> &'static str {
+        match self {
+            ConfigSchema::McpServers => "mcpServers",
+            ConfigSchema::VsCode => "servers",
+            ConfigSchema::Zed => "context_servers",
+            ConfigSchema::CodexToml => "mcp_servers",
+        }
+    }
+
+    /// The Cortex entry as this schema spells it.
+    fn entry(self) -> serde_json::Value {
+        let mut entry = cortex_mcp_entry();
+        match self {
+            ConfigSchema::VsCode => entry["type"] = json!("stdio"),
+            ConfigSchema::Zed => entry["source"] = json!("custom"),
+            ConfigSchema::McpServers | ConfigSchema::CodexToml => {}
+        }
+        entry
+    }
+}
 
 /// The MCP server config that gets injected into agent configs.
 fn cortex_mcp_entry() -> serde_json::Value {
@@ -425,63 +862,153 @@ fn cortex_mcp_entry() -> serde_json::Value {
     })
 }
 
+/// The command an agent runs for an injected server.
+#[derive(Debug, Clone, PartialEq)]
+struct LaunchSpec {
+    command: String,
+    args: Vec<String>,
+    env: BTreeMap<String, String>,
+}
+
+impl LaunchSpec {
+    /// Read a server entry. Also accepts Zed's older nested form,
+    /// `{"command": {"path", "args", "env"}}`.
+    fn from_json(entry: &serde_json::Value) -> Option<Self> {
+        let entry = match entry.get("command") {
+            Some(nested @ serde_json::Value::Object(_)) => {
+                let mut flat = nested.clone();
+                flat["command"] = nested.get("path")?.clone();
+                flat
+            }
+            _ => entry.clone(),
+        };
+        let strings = |v: Option<&serde_json::Value>| -> Vec<String> {
+            v.and_then(|v| v.as_array())
+                .map(|a| {
+                    a.iter()
+                        .filter_map(|s| s.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let env = entry
+            .get("env")
+            .and_then(|e| e.as_object())
+            .map(|e| {
+                e.iter()
+                    .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Some(Self {
+            command: entry.get("command")?.as_str()?.to_string(),
+            args: strings(entry.get("args")),
+            env,
+        })
+    }
+
+    /// The entry `cortex plug` writes today.
+    fn expected() -> Self {
+        Self::from_json(&cortex_mcp_entry()).expect("valid entry")
+    }
+
+    /// Whether this launches the current server package. Env overrides
+    /// (e.g. a different port) don't make an entry outdated.
+    fn is_current(&self) -> bool {
+        let expected = Self::expected();
+        self.command == expected.command && self.args == expected.args
+    }
+}
+
 /// Result of an injection attempt.
+#[derive(Debug)]
 enum InjectionResult {
     Injected,
+    /// An outdated Cortex entry was rewritten.
+    Updated,
     AlreadyPresent,
 }
 
+impl InjectionResult {
+    fn name(&self) -> &'static str {
+        match self {
+            InjectionResult::Injected => "injected",
+            InjectionResult::Updated => "updated",
+            InjectionResult::AlreadyPresent => "already_present",
+        }
+    }
+}
+
 /// Result of a removal attempt.
 enum RemovalResult {
     Removed,
     NotPresent,
 }
 
-/// Inject the Cortex MCP server entry into an agent's JSON config.
-fn inject_mcp_server(config_path: &Path) -> Result<InjectionResult> {
-    let mut config: serde_json::Value = if config_path.exists() {
-        let content = std::fs::read_to_string(config_path)?;
-        serde_json::from_str(&content).unwrap_or(json!({}))
-    } else {
-        json!({})
-    };
+/// Inject the Cortex MCP server entry into an agent's config, or rewrite an
+/// outdated one (keeping the user's env overrides).
+fn inject_mcp_server(config_path: &Path, schema: ConfigSchema) -> Result<InjectionResult> {
+    if schema == ConfigSchema::CodexToml {
+        return inject_toml(config_path);
+    }
+    let (mut config, had_comments) = load_json(config_path)?;
 
     let obj = config
         .as_object_mut()
         .ok_or_else(|| anyhow::anyhow!("config is not a JSON object"))?;
-
-    let servers = obj.entry("mcpServers").or_insert(json!({}));
-
-    if servers.get("cortex").is_some() {
-        return Ok(InjectionResult::AlreadyPresent);
-    }
-
-    servers
+    let servers = obj
+        .entry(schema.name())
+        .or_insert(json!({}))
         .as_object_mut()
-        .ok_or_else(|| anyhow::anyhow!("mcpServers is not a JSON object"))?
-        .insert("cortex".to_string(), cortex_mcp_entry());
+        .ok_or_else(|| anyhow::anyhow!("{} is not a JSON object", schema.name()))?;
 
-    // Ensure parent directory exists
-    if let Some(parent) = config_path.parent() {
-        std::fs::create_dir_all(parent)?;
-    }
+    let mut entry = schema.entry();
+    let result = match servers.get("cortex") {
+        None => InjectionResult::Injected,
+        Some(existing) => {
+            let Some(spec) = LaunchSpec::from_json(existing) else {
+                bail!("existing cortex entry is not a command server; not modifying it");
+            };
+            if spec.is_current() {
+                return Ok(InjectionResult::AlreadyPresent);
+            }
+            for (k, v) in spec.env {
+                entry["env"][k] = json!(v);
+            }
+            InjectionResult::Updated
+        }
+    };
+    servers.insert("cortex".to_string(), entry);
 
-    std::fs::write(config_path, serde_json::to_string_pretty(&config)?)?;
-    Ok(InjectionResult::Injected)
+    save_json(config_path, &config, had_comments)?;
+    Ok(result)
 }
 
-/// Remove the Cortex MCP server entry from an agent's JSON config.
-fn remove_mcp_server(config_path: &Path) -> Result<RemovalResult> {
+/// Remove the Cortex MCP server entry from an agent's config.
+fn remove_mcp_server(config_path: &Path, schema: ConfigSchema) -> Result<RemovalResult> {
     if !config_path.exists() {
         return Ok(RemovalResult::NotPresent);
     }
+    if schema == ConfigSchema::CodexToml {
+        let content = std::fs::read_to_string(config_path)?;
+        return Ok(match toml_table_range(&content) {
+            Some(range) => {
+                let mut content = content;
+                content.replace_range(range, "");
+                std::fs::write(config_path, content.trim_end().to_string() + "\n")?;
+                RemovalResult::Removed
+            }
+            None => RemovalResult::NotPresent,
+        });
+    }
 
-    let content = std::fs::read_to_string(config_path)?;
-    let mut config: serde_json::Value = serde_json::from_str(&content)?;
-
-    if let Some(servers) = config.get_mut("mcpServers").and_then(|v| v.as_object_mut()) {
+    let (mut config, had_comments) = load_json(config_path)?;
+    if let Some(servers) = config
+        .get_mut(schema.name())
+        .and_then(|v| v.as_object_mut())
+    {
         if servers.remove("cortex").is_some() {
-            std::fs::write(config_path, serde_json::to_string_pretty(&config)?)?;
+            save_json(config_path, &config, had_comments)?;
             return Ok(RemovalResult::Removed);
         }
     }
@@ -489,21 +1016,273 @@ fn remove_mcp_server(config_path: &Path) -> Result<RemovalResult> {
     Ok(RemovalResult::NotPresent)
 }
 
-/// Check if Cortex is already present in an agent's config.
-fn check_cortex_present(config_path: &Path) -> bool {
+/// The Cortex entry in an agent's config, if present.
+fn read_entry(config_path: &Path, schema: ConfigSchema) -> Result<Option<LaunchSpec>> {
     if !config_path.exists() {
-        return false;
+        return Ok(None);
+    }
+    if schema == ConfigSchema::CodexToml {
+        let content = std::fs::read_to_string(config_path)?;
+        return Ok(toml_table_range(&content).and_then(|r| parse_toml_table(&content[r])));
+    }
+    let (config, _) = load_json(config_path)?;
+    match config.get(schema.name()).and_then(|s| s.get("cortex")) {
+        None => Ok(None),
+        Some(entry) => LaunchSpec::from_json(entry)
+            .map(Some)
+            .context("cortex entry has no command"),
+    }
+}
+
+/// Check if Cortex is already present in an agent's config.
+fn check_cortex_present(config_path: &Path, schema: ConfigSchema) -> bool {
+    matches!(read_entry(config_path, schema), Ok(Some(_)))
+}
+
+// ── JSON configs ────────────────────────────────────────────────
+
+/// Parse a JSON config. Comments and trailing commas (Zed, VS Code) are
+/// tolerated; the flag says whether any were dropped. Unparseable files
+/// are an error rather than being overwritten.
+fn load_json(path: &Path) -> Result<(serde_json::Value, bool)> {
+    if !path.exists() {
+        return Ok((json!({}), false));
+    }
+    let content = std::fs::read_to_string(path)?;
+    if content.trim().is_empty() {
+        return Ok((json!({}), false));
+    }
+    if let Ok(value) = serde_json::from_str(&content) {
+        return Ok((value, false));
+    }
+    let value = serde_json::from_str(&strip_jsonc(&content))
+        .with_context(|| format!("{} is not valid JSON; not modifying it", path.display()))?;
+    Ok((value, true))
+}
+
+/// Write a JSON config. If the original had comments, which are lost on
+/// rewrite, it is kept next to the config as `<name>.cortex-backup`.
+fn save_json(path: &Path, value: &serde_json::Value, had_comments: bool) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if had_comments {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(".cortex-backup");
+        std::fs::copy(path, PathBuf::from(backup))?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(value)?)?;
+    Ok(())
+}
+
+/// Drop `//` and `/* */` comments and trailing commas outside strings.
+fn strip_jsonc(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => out.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+            }
+            ('/', Some('/')) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            (']' | '}', _) => {
+                let trimmed = out.trim_end().len();
+                if out[..trimmed].ends_with(',') {
+                    out.truncate(trimmed - 1);
+                }
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// ── TOML configs (Codex) ────────────────────────────────────────
+
+const TOML_TABLE: &str = "[mcp_servers.cortex]";
+
+/// Byte range of the `[mcp_servers.cortex]` table, including its
+/// `[mcp_servers.cortex.*]` subtables, up to the next table.
+fn toml_table_range(content: &str) -> Option<std::ops::Range<usize>> {
+    let mut start = None;
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let header = line.trim();
+        if header.starts_with('[') {
+            let ours = header == TOML_TABLE || header.starts_with("[mcp_servers.cortex.");
+            match (start, ours) {
+                (None, true) => start = Some(offset),
+                (Some(s), false) => return Some(s..offset),
+                _ => {}
+            }
+        }
+        offset += line.len();
     }
-    let Ok(content) = std::fs::read_to_string(config_path) else {
-        return false;
+    start.map(|s| s..content.len())
+}
+
+fn toml_string(s: &str) -> String {
+    serde_json::to_string(s).unwrap_or_default()
+}
+
+fn render_toml_table(spec: &LaunchSpec) -> String {
+    let args: Vec<String> = spec.args.iter().map(|a| toml_string(a)).collect();
+    let env: Vec<String> = spec
+        .env
+        .iter()
+        .map(|(k, v)| format!("{k} = {}", toml_string(v)))
+        .collect();
+    format!(
+        "{TOML_TABLE}\ncommand = {}\nargs = [{}]\nenv = {{ {} }}\n",
+        toml_string(&spec.command),
+        args.join(", "),
+        env.join(", ")
+    )
+}
+
+/// Read the table `render_toml_table` writes, plus an `env` subtable.
+fn parse_toml_table(table: &str) -> Option<LaunchSpec> {
+    let mut command = None;
+    let mut args = Vec::new();
+    let mut env = BTreeMap::new();
+    let mut in_env = false;
+    for line in table.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_env = line == "[mcp_servers.cortex.env]";
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let (key, value) = (key.trim().trim_matches('"'), value.trim());
+        if in_env {
+            env.insert(key.to_string(), serde_json::from_str(value).ok()?);
+            continue;
+        }
+        match key {
+            "command" => command = serde_json::from_str::<String>(value).ok(),
+            "args" => args = serde_json::from_str(value).ok()?,
+            "env" => {
+                let inner = value.strip_prefix('{')?.strip_suffix('}')?;
+                for pair in inner.split(',').filter(|p| !p.trim().is_empty()) {
+                    let (k, v) = pair.split_once('=')?;
+                    env.insert(
+                        k.trim().trim_matches('"').to_string(),
+                        serde_json::from_str(v.trim()).ok()?,
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+    Some(LaunchSpec {
+        command: command?,
+        args,
+        env,
+    })
+}
+
+fn inject_toml(config_path: &Path) -> Result<InjectionResult> {
+    let content = if config_path.exists() {
+        std::fs::read_to_string(config_path)?
+    } else {
+        String::new()
     };
-    let Ok(config) = serde_json::from_str::<serde_json::Value>(&content) else {
-        return false;
+    let mut spec = LaunchSpec::expected();
+    let (result, mut content) = match toml_table_range(&content) {
+        None => (InjectionResult::Injected, content),
+        Some(range) => {
+            let existing = parse_toml_table(&content[range.clone()])
+                .context("existing [mcp_servers.cortex] table is not a command server")?;
+            if existing.is_current() {
+                return Ok(InjectionResult::AlreadyPresent);
+            }
+            spec.env.extend(existing.env);
+            let mut content = content;
+            content.replace_range(range, "");
+            (InjectionResult::Updated, content)
+        }
     };
-    config
-        .get("mcpServers")
-        .and_then(|v| v.get("cortex"))
-        .is_some()
+    if !content.is_empty() && !content.ends_with("\n\n") {
+        content.push_str(if content.ends_with('\n') {
+            "\n"
+        } else {
+            "\n\n"
+        });
+    }
+    content.push_str(&render_toml_table(&spec));
+
+    if let Some(parent) = config_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(config_path, content)?;
+    Ok(result)
+}
+
+// ── Plug State ──────────────────────────────────────────────────
+
+/// What the last global `cortex plug` did, for [`auto_update`].
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PlugState {
+    /// Set by `cortex plug`, cleared by `cortex plug --remove`.
+    #[serde(default)]
+    auto_update: bool,
+    /// Agents (short names) already seen; only others count as new.
+    #[serde(default)]
+    known: Vec<String>,
+}
+
+impl PlugState {
+    fn path() -> PathBuf {
+        cortex_home().join("plug.json")
+    }
+
+    fn load() -> Result<Self> {
+        let path = Self::path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    fn save(&self) -> Result<()> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -518,7 +1297,7 @@ mod tests {
         write!(tmp, "{{}}").unwrap();
         let path = tmp.path().to_path_buf();
 
-        let result = inject_mcp_server(&path).unwrap();
+        let result = inject_mcp_server(&path, ConfigSchema::McpServers).unwrap();
         assert!(matches!(result, InjectionResult::Injected));
 
         let content: serde_json::Value =
@@ -534,8 +1313,8 @@ mod tests {
         write!(tmp, "{{}}").unwrap();
         let path = tmp.path().to_path_buf();
 
-        inject_mcp_server(&path).unwrap();
-        let result = inject_mcp_server(&path).unwrap();
+        inject_mcp_server(&path, ConfigSchema::McpServers).unwrap();
+        let result = inject_mcp_server(&path, ConfigSchema::McpServers).unwrap();
         assert!(matches!(result, InjectionResult::AlreadyPresent));
     }
 
@@ -549,7 +1328,7 @@ mod tests {
         .unwrap();
         let path = tmp.path().to_path_buf();
 
-        inject_mcp_server(&path).unwrap();
+        inject_mcp_server(&path, ConfigSchema::McpServers).unwrap();
 
         let content: serde_json::Value =
             serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
@@ -565,8 +1344,8 @@ mod tests {
         write!(tmp, "{{}}").unwrap();
         let path = tmp.path().to_path_buf();
 
-        inject_mcp_server(&path).unwrap();
-        let result = remove_mcp_server(&path).unwrap();
+        inject_mcp_server(&path, ConfigSchema::McpServers).unwrap();
+        let result = remove_mcp_server(&path, ConfigSchema::McpServers).unwrap();
         assert!(matches!(result, RemovalResult::Removed));
 
         let content: serde_json::Value =
@@ -580,7 +1359,7 @@ mod tests {
         write!(tmp, "{{}}").unwrap();
         let path = tmp.path().to_path_buf();
 
-        let result = remove_mcp_server(&path).unwrap();
+        let result = remove_mcp_server(&path, ConfigSchema::McpServers).unwrap();
         assert!(matches!(result, RemovalResult::NotPresent));
     }
 
@@ -590,8 +1369,102 @@ mod tests {
         write!(tmp, "{{}}").unwrap();
         let path = tmp.path().to_path_buf();
 
-        assert!(!check_cortex_present(&path));
-        inject_mcp_server(&path).unwrap();
-        assert!(check_cortex_present(&path));
+        assert!(!check_cortex_present(&path, ConfigSchema::McpServers));
+        inject_mcp_server(&path, ConfigSchema::McpServers).unwrap();
+        assert!(check_cortex_present(&path, ConfigSchema::McpServers));
+    }
+
+    #[test]
+    fn test_inject_updates_outdated_entry() {
+        let mut tmp = NamedTempFile::new().unwrap();
+        write!(
+            tmp,
+            r#"{{"mcpServers": {{"cortex": {{"command": "node", "args": ["old.js"], "env": {{"CORTEX_PORT": "7800"}}}}}}}}"#
+        )
+        .unwrap();
+        let path = tmp.path().to_path_buf();
+
+        let result = inject_mcp_server(&path, ConfigSchema::McpServers).unwrap();
+        assert!(matches!(result, InjectionResult::Updated));
+        let entry = read_entry(&path, ConfigSchema::McpServers)
+            .unwrap()
+            .unwrap();
+        assert!(entry.is_current());
+        assert_eq!(entry.env["CORTEX_PORT"], "7800", "user env kept");
+    }
+
+    #[test]
+    fn test_zed_settings_with_comments() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        std::fs::write(
+            &path,
+            "// Zed settings\n{\n  \"theme\": \"One Dark\", // keep\n  \"url\": \"http://x\",\n}\n",
+        )
+        .unwrap();
+
+        inject_mcp_server(&path, ConfigSchema::Zed).unwrap();
+        let content: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(content["theme"], "One Dark");
+        assert_eq!(content["url"], "http://x");
+        assert_eq!(content["context_servers"]["cortex"]["source"], "custom");
+        assert!(dir.path().join("settings.json.cortex-backup").exists());
+    }
+
+    #[test]
+    fn test_invalid_json_is_not_overwritten() {
+        let mut tmp = NamedTempFile::new().unwrap();
+        write!(tmp, "{{ not json").unwrap();
+        let path = tmp.path().to_path_buf();
+
+        assert!(inject_mcp_server(&path, ConfigSchema::McpServers).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{ not json");
+    }
+
+    #[test]
+    fn test_codex_toml_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "model = \"o3\"\n\n[mcp_servers.other]\ncommand = \"x\"\n",
+        )
+        .unwrap();
+
+        let result = inject_mcp_server(&path, ConfigSchema::CodexToml).unwrap();
+        assert!(matches!(result, InjectionResult::Injected));
+        let result = inject_mcp_server(&path, ConfigSchema::CodexToml).unwrap();
+        assert!(matches!(result, InjectionResult::AlreadyPresent));
+        assert_eq!(
+            read_entry(&path, ConfigSchema::CodexToml).unwrap(),
+            Some(LaunchSpec::expected())
+        );
+
+        let result = remove_mcp_server(&path, ConfigSchema::CodexToml).unwrap();
+        assert!(matches!(result, RemovalResult::Removed));
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            "model = \"o3\"\n\n[mcp_servers.other]\ncommand = \"x\"\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_probe_launch_handshake() {
+        let reply = r#"{"jsonrpc":"2.0","id":1,"result":{"serverInfo":{"name":"cortex","version":"1.2.0"}}}"#;
+        let spec = LaunchSpec {
+            command: "sh".to_string(),
+            args: vec!["-c".to_string(), format!("read line; echo '{reply}'")],
+            env: BTreeMap::new(),
+        };
+        assert_eq!(probe_launch(&spec).await.unwrap(), "cortex 1.2.0");
+
+        let silent = LaunchSpec {
+            args: vec!["-c".to_string(), "read line".to_string()],
+            ..spec
+        };
+        assert!(probe_launch(&silent).await.is_err());
     }
 }
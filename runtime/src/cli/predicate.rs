@@ -0,0 +1,227 @@
+//! `--fail-when` predicates for `cortex query` and `cortex wql`.
+//!
+//! A predicate compares a metric of the result set against a number:
+//! `count == 0`, `count < 5`, `min(price) > 100`, `avg(confidence) < 0.5`.
+//! When any predicate holds, the command still prints its results and then
+//! exits with [`EXIT_PREDICATE`] so scripts can tell a matched assertion
+//! from an error (exit 1).
+
+use anyhow::{bail, Result};
+
+/// Exit code when a `--fail-when` predicate holds.
+pub const EXIT_PREDICATE: i32 = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Metric {
+    Count,
+    Min(String),
+    Max(String),
+    Avg(String),
+    Sum(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A parsed `--fail-when` predicate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Predicate {
+    metric: Metric,
+    op: Op,
+    threshold: f64,
+    text: String,
+}
+
+impl Predicate {
+    /// Parse `<metric> <op> <number>`, where the metric is `count` or
+    /// `min|max|avg|sum(<field>)` and the operator one of `== != < <= > >=`.
+    pub fn parse(s: &str) -> Result<Self> {
+        let text = s.trim();
+        let Some((pos, token)) = ["==", "!=", "<=", ">=", "<", ">", "="]
+            .iter()
+            .filter_map(|t| text.find(t).map(|p| (p, *t)))
+            .min_by_key(|(p, t)| (*p, std::cmp::Reverse(t.len())))
+        else {
+            bail!("--fail-when '{text}': expected <metric> <op> <number>, e.g. 'count == 0'");
+        };
+        let op = match token {
+            "==" | "=" => Op::Eq,
+            "!=" => Op::Ne,
+            "<=" => Op::Le,
+            ">=" => Op::Ge,
+            "<" => Op::Lt,
+            _ => Op::Gt,
+        };
+        let lhs = text[..pos].trim().to_lowercase();
+        let rhs = text[pos + token.len()..].trim();
+        let Ok(threshold) = rhs.parse::<f64>() else {
+            bail!("--fail-when '{text}': '{rhs}' is not a number");
+        };
+
+        let metric = if lhs == "count" {
+            Metric::Count
+        } else {
+            let Some((func, field)) = lhs
+                .strip_suffix(')')
+                .and_then(|l| l.split_once('('))
+                .map(|(f, a)| (f.trim(), a.trim().to_string()))
+                .filter(|(_, a)| !a.is_empty())
+            else {
+                bail!("--fail-when '{text}': unknown metric '{lhs}' (use count, min(f), max(f), avg(f) or sum(f))");
+            };
+            match func {
+                "min" => Metric::Min(field),
+                "max" => Metric::Max(field),
+                "avg" => Metric::Avg(field),
+                "sum" => Metric::Sum(field),
+                _ => bail!("--fail-when '{text}': unknown function '{func}'"),
+            }
+        };
+
+        Ok(Self {
+            metric,
+            op,
+            threshold,
+            text: text.to_string(),
+        })
+    }
+
+    /// Parse each of `args`.
+    pub fn parse_all(args: &[String]) -> Result<Vec<Self>> {
+        args.iter().map(|a| Self::parse(a)).collect()
+    }
+
+    /// The field this predicate aggregates, if any.
+    pub fn field(&self) -> Option<&str> {
+        match &self.metric {
+            Metric::Count => None,
+            Metric::Min(f) | Metric::Max(f) | Metric::Avg(f) | Metric::Sum(f) => Some(f),
+        }
+    }
+
+    /// Evaluate against `count` rows and the numeric `values` of this
+    /// predicate's field. Returns the metric when the predicate holds.
+    /// Aggregates other than `sum` over no values never hold.
+    pub fn eval(&self, count: usize, values: &[f64]) -> Option<f64> {
+        let metric = match &self.metric {
+            Metric::Count => count as f64,
+            Metric::Sum(_) => values.iter().sum(),
+            _ if values.is_empty() => return None,
+            Metric::Min(_) => values.iter().copied().fold(f64::INFINITY, f64::min),
+            Metric::Max(_) => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            Metric::Avg(_) => values.iter().sum::<f64>() / values.len() as f64,
+        };
+        let holds = match self.op {
+            Op::Eq => metric == self.threshold,
+            Op::Ne => metric != self.threshold,
+            Op::Lt => metric < self.threshold,
+            Op::Le => metric <= self.threshold,
+            Op::Gt => metric > self.threshold,
+            Op::Ge => metric >= self.threshold,
+        };
+        holds.then_some(metric)
+    }
+}
+
+/// Error returned when one or more `--fail-when` predicates hold.
+#[derive(Debug)]
+pub struct PredicateFailed {
+    /// Each predicate that held, with the value it saw.
+    pub matched: Vec<(String, f64)>,
+}
+
+impl std::fmt::Display for PredicateFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts: Vec<String> = self
+            .matched
+            .iter()
+            .map(|(text, value)| format!("'{text}' (got {value})"))
+            .collect();
+        write!(f, "--fail-when matched: {}", parts.join(", "))
+    }
+}
+
+impl std::error::Error for PredicateFailed {}
+
+/// Evaluate `predicates` against a result set of `count` rows. `values`
+/// returns the numeric values of a field, or `None` if the command has no
+/// such field. Fails with [`PredicateFailed`] if any predicate holds.
+pub fn check(
+    predicates: &[Predicate],
+    count: usize,
+    values: impl Fn(&str) -> Option<Vec<f64>>,
+) -> Result<()> {
+    let mut matched = Vec::new();
+    for p in predicates {
+        let field_values = match p.field() {
+            Some(field) => match values(field) {
+                Some(v) => v,
+                None => bail!("--fail-when '{}': unknown field '{field}'", p.text),
+            },
+            None => Vec::new(),
+        };
+        if let Some(value) = p.eval(count, &field_values) {
+            matched.push((p.text.clone(), value));
+        }
+    }
+    if matched.is_empty() {
+        Ok(())
+    } else {
+        Err(PredicateFailed { matched }.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_predicates() {
+        let p = Predicate::parse("count == 0").unwrap();
+        assert_eq!(p.metric, Metric::Count);
+        assert_eq!(p.op, Op::Eq);
+        assert_eq!(Predicate::parse("count<=5").unwrap().op, Op::Le);
+        assert_eq!(Predicate::parse("count = 1").unwrap().op, Op::Eq);
+
+        let p = Predicate::parse("MIN(price) > 99.5").unwrap();
+        assert_eq!(p.metric, Metric::Min("price".into()));
+        assert_eq!(p.op, Op::Gt);
+        assert_eq!(p.threshold, 99.5);
+
+        assert!(Predicate::parse("count").is_err());
+        assert!(Predicate::parse("count == none").is_err());
+        assert!(Predicate::parse("median(price) > 1").is_err());
+        assert!(Predicate::parse("rows > 1").is_err());
+    }
+
+    #[test]
+    fn test_check_reports_matches() {
+        let preds =
+            Predicate::parse_all(&["count == 0".to_string(), "max(price) > 100".to_string()])
+                .unwrap();
+        let prices = |f: &str| (f == "price").then(|| vec![20.0, 150.0]);
+        let err = check(&preds, 2, prices).unwrap_err();
+        let failed = err.downcast_ref::<PredicateFailed>().unwrap();
+        assert_eq!(
+            failed.matched,
+            vec![("max(price) > 100".to_string(), 150.0)]
+        );
+
+        // Aggregates over an empty result set don't hold; count does.
+        let err = check(&preds, 0, |_| Some(Vec::new())).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<PredicateFailed>().unwrap().matched.len(),
+            1
+        );
+
+        let avg = Predicate::parse_all(&["avg(rating) < 3".to_string()]).unwrap();
+        assert!(check(&avg, 2, |_| None).is_err_and(|e| e.to_string().contains("unknown field")));
+    }
+}
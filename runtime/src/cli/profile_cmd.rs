@@ -0,0 +1,131 @@
+//! CLI handlers for `cortex profile` — the identities domains are crawled
+//! as.
+
+use crate::acquisition::profiles::{CrawlProfile, ProfileStore, DEFAULT_PROFILE};
+use crate::cli::output;
+use anyhow::{bail, Result};
+use std::collections::BTreeMap;
+
+/// Parse `NAME=VALUE` pairs from repeated options.
+fn parse_pairs(option: &str, pairs: &[String]) -> Result<BTreeMap<String, String>> {
+    pairs
+        .iter()
+        .map(|pair| match pair.split_once('=') {
+            Some((name, value)) if !name.trim().is_empty() => {
+                Ok((name.trim().to_string(), value.trim().to_string()))
+            }
+            _ => bail!("invalid --{option} '{pair}': expected NAME=VALUE"),
+        })
+        .collect()
+}
+
+/// Create or replace profile `name`.
+pub async fn run_set(
+    name: &str,
+    user_agent: Option<String>,
+    accept_language: Option<String>,
+    client_hints: &[String],
+    headers: &[String],
+    cookies: &[String],
+) -> Result<()> {
+    let profile = CrawlProfile {
+        name: name.to_string(),
+        user_agent,
+        accept_language,
+        client_hints: parse_pairs("client-hint", client_hints)?,
+        headers: parse_pairs("header", headers)?,
+        cookies: parse_pairs("cookie", cookies)?,
+    };
+    let replaced = ProfileStore::default().put(profile.clone())?;
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({ "profile": profile, "replaced": replaced }));
+    } else if !output::is_quiet() {
+        let verb = if replaced { "Replaced" } else { "Created" };
+        println!("  {verb} profile {name}.");
+        println!("  Crawl a domain with it with: cortex profile assign <domain> {name}");
+    }
+    Ok(())
+}
+
+/// Delete profile `name`; its domains go back to the default.
+pub async fn run_remove(name: &str) -> Result<()> {
+    let removed = ProfileStore::default().remove(name)?;
+    if output::is_json() {
+        output::print_json(&serde_json::json!({ "name": name, "removed": removed }));
+    } else if !output::is_quiet() {
+        if removed {
+            println!("  Removed profile {name}.");
+        } else {
+            println!("  No profile named {name}.");
+        }
+    }
+    Ok(())
+}
+
+/// Crawl `domains` as profile `name`, or as the default with `None`.
+pub async fn run_assign(domains: &[String], name: Option<&str>) -> Result<()> {
+    let store = ProfileStore::default();
+    for domain in domains {
+        store.assign(domain, name)?;
+    }
+    let name = name.unwrap_or(DEFAULT_PROFILE);
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({ "domains": domains, "profile": name }));
+    } else if !output::is_quiet() {
+        println!("  {} will be crawled as {name}.", domains.join(", "));
+    }
+    Ok(())
+}
+
+/// List profiles and the domains assigned them.
+pub async fn run_list() -> Result<()> {
+    let store = ProfileStore::default();
+    let profiles = store.list()?;
+    let assignments = store.assignments()?;
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({
+            "profiles": profiles,
+            "domains": assignments,
+        }));
+        return Ok(());
+    }
+    println!(
+        "  {DEFAULT_PROFILE} (default): {}",
+        CrawlProfile::honest().user_agent()
+    );
+    for profile in &profiles {
+        let domains: Vec<&str> = assignments
+            .iter()
+            .filter(|(_, name)| **name == profile.name)
+            .map(|(domain, _)| domain.as_str())
+            .collect();
+        println!("  {}: {}", profile.name, profile.user_agent());
+        if !domains.is_empty() {
+            println!("    domains: {}", domains.join(", "));
+        }
+    }
+    Ok(())
+}
+
+/// Show the profile `domain` is crawled as and the headers it sends.
+pub async fn run_show(domain: &str) -> Result<()> {
+    let profile = ProfileStore::default().for_domain(domain)?;
+    let headers = profile.request_headers();
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({
+            "domain": domain,
+            "profile": profile,
+            "headers": headers,
+        }));
+        return Ok(());
+    }
+    println!("  {domain} is crawled as {}:\n", profile.name);
+    for (name, value) in &headers {
+        println!("    {name}: {value}");
+    }
+    Ok(())
+}
@@ -0,0 +1,255 @@
+//! CLI handlers for `cortex project` — named groups of domains with shared
+//! settings.
+
+use crate::cartography::content_policy::ContentPolicy;
+use crate::cli::output;
+use crate::compiler::package;
+use crate::intelligence::cache::MapCache;
+use crate::project::{self, Project, Projects};
+use anyhow::{bail, Result};
+use std::path::Path;
+
+/// Settings to change with `cortex project set`. `None` leaves a setting
+/// as it is.
+#[derive(Debug, Default)]
+pub struct SettingsChange<'a> {
+    pub respect_robots: Option<bool>,
+    pub share_sessions: Option<bool>,
+    pub compile_targets: Option<&'a [String]>,
+    /// A policy file to copy into the project, or `Some(None)` to drop it.
+    pub policy: Option<Option<&'a Path>>,
+}
+
+/// Create a project holding `domains`.
+pub async fn run_create(name: &str, domains: &[String]) -> Result<()> {
+    let path = project::default_path();
+    let mut projects = Projects::load(&path)?;
+    let project = projects.create(name, domains)?.clone();
+    projects.save(&path)?;
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({ "created": project }));
+    } else if !output::is_quiet() {
+        println!(
+            "  Created project {name} with {} domain(s).",
+            project.domains.len()
+        );
+        println!("  Query it with: cortex query --project {name}, or make it the default with: cortex project use {name}");
+    }
+    Ok(())
+}
+
+/// Add domains to a project.
+pub async fn run_add(name: &str, domains: &[String]) -> Result<()> {
+    let path = project::default_path();
+    let mut projects = Projects::load(&path)?;
+    let added = projects.add_domains(name, domains)?;
+    projects.save(&path)?;
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({ "project": name, "added": added }));
+    } else if !output::is_quiet() {
+        if added.is_empty() {
+            println!("  Project {name} already has those domains.");
+        } else {
+            println!("  Added {} to project {name}.", added.join(", "));
+        }
+    }
+    Ok(())
+}
+
+/// Remove domains from a project.
+pub async fn run_remove(name: &str, domains: &[String]) -> Result<()> {
+    let path = project::default_path();
+    let mut projects = Projects::load(&path)?;
+    let removed = projects.remove_domains(name, domains)?;
+    projects.save(&path)?;
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({ "project": name, "removed": removed }));
+    } else if !output::is_quiet() {
+        if removed.is_empty() {
+            println!("  Project {name} has none of those domains.");
+        } else {
+            println!("  Removed {} from project {name}.", removed.join(", "));
+        }
+    }
+    Ok(())
+}
+
+/// Delete a project. Its domains and their maps are left alone.
+pub async fn run_delete(name: &str) -> Result<()> {
+    let path = project::default_path();
+    let mut projects = Projects::load(&path)?;
+    let Some(project) = projects.delete(name) else {
+        bail!("no project named '{name}'");
+    };
+    projects.save(&path)?;
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({ "deleted": project.name }));
+    } else if !output::is_quiet() {
+        println!("  Deleted project {name}. Its maps are still cached.");
+    }
+    Ok(())
+}
+
+/// List projects, marking the active one.
+pub async fn run_list() -> Result<()> {
+    let projects = Projects::load_default()?;
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({
+            "active": projects.active,
+            "projects": projects.projects,
+        }));
+    } else if projects.projects.is_empty() {
+        println!("  No projects. Create one with: cortex project create <name> <domain>...");
+    } else {
+        println!("  Projects:\n");
+        for p in &projects.projects {
+            let active = if projects.active.as_deref() == Some(p.name.as_str()) {
+                "*"
+            } else {
+                " "
+            };
+            println!(
+                "  {active} {:<20}  {} domain(s)  {}",
+                p.name,
+                p.domains.len(),
+                p.domains.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Show a project's domains (and whether each is mapped) and settings.
+pub async fn run_show(name: &str) -> Result<()> {
+    let projects = Projects::load_default()?;
+    let Some(project) = projects.get(name) else {
+        bail!("no project named '{name}'");
+    };
+    let mut cache = MapCache::default_cache()?;
+    let mapped: Vec<bool> = project
+        .domains
+        .iter()
+        .map(|d| cache.get(d).is_some())
+        .collect();
+
+    if output::is_json() {
+        let domains: Vec<_> = project
+            .domains
+            .iter()
+            .zip(&mapped)
+            .map(|(domain, mapped)| serde_json::json!({ "domain": domain, "mapped": mapped }))
+            .collect();
+        output::print_json(&serde_json::json!({
+            "name": project.name,
+            "active": projects.active.as_deref() == Some(name),
+            "domains": domains,
+            "settings": project.settings,
+            "created_at": project.created_at,
+        }));
+        return Ok(());
+    }
+    if output::is_quiet() {
+        return Ok(());
+    }
+
+    println!("  Project {}:\n", project.name);
+    for (domain, mapped) in project.domains.iter().zip(&mapped) {
+        let note = if *mapped { "" } else { "  (not mapped)" };
+        println!("    {domain}{note}");
+    }
+    println!();
+    print_settings(project);
+    Ok(())
+}
+
+/// Make `name` the project `cortex query` and `cortex wql` default to, or
+/// clear the default with `None`.
+pub async fn run_use(name: Option<&str>) -> Result<()> {
+    let path = project::default_path();
+    let mut projects = Projects::load(&path)?;
+    projects.set_active(name)?;
+    projects.save(&path)?;
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({ "active": name }));
+    } else if !output::is_quiet() {
+        match name {
+            Some(name) => println!("  Queries without a domain now search project {name}."),
+            None => println!("  No active project; queries need a domain or --project."),
+        }
+    }
+    Ok(())
+}
+
+/// Change a project's shared settings.
+pub async fn run_set(name: &str, change: SettingsChange<'_>) -> Result<()> {
+    if let Some(targets) = change.compile_targets {
+        package::parse_targets(targets)?;
+    }
+    let policy = match change.policy {
+        Some(Some(file)) => Some(Some(ContentPolicy::load(file)?)),
+        Some(None) => Some(None),
+        None => None,
+    };
+
+    let path = project::default_path();
+    let mut projects = Projects::load(&path)?;
+    let project = projects.get_mut(name)?;
+    let settings = &mut project.settings;
+    if let Some(respect) = change.respect_robots {
+        settings.respect_robots = Some(respect);
+    }
+    if let Some(share) = change.share_sessions {
+        settings.share_sessions = share;
+    }
+    if let Some(targets) = change.compile_targets {
+        settings.compile_targets = targets.to_vec();
+    }
+    if let Some(policy) = policy {
+        settings.policy = policy;
+    }
+    let project = project.clone();
+    projects.save(&path)?;
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({
+            "project": project.name,
+            "settings": project.settings,
+        }));
+    } else if !output::is_quiet() {
+        println!("  Updated project {name}.\n");
+        print_settings(&project);
+    }
+    Ok(())
+}
+
+fn print_settings(project: &Project) {
+    let settings = &project.settings;
+    let robots = match settings.respect_robots {
+        Some(true) => "yes",
+        Some(false) => "no",
+        None => "yes (default)",
+    };
+    let targets = if settings.compile_targets.is_empty() {
+        "all (default)".to_string()
+    } else {
+        settings.compile_targets.join(", ")
+    };
+    let policy = if settings.policy.is_some() {
+        "project policy"
+    } else {
+        "per-domain or default.json"
+    };
+    println!("    respect robots.txt  {robots}");
+    println!("    content policy      {policy}");
+    println!(
+        "    share sessions      {}",
+        if settings.share_sessions { "yes" } else { "no" }
+    );
+    println!("    compile targets     {targets}");
+}
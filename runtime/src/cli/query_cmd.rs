@@ -1,37 +1,191 @@
-//! `cortex query <domain>` — query a mapped site for matching pages.
+//! `cortex query <domain>` — query a mapped site for matching pages, and
+//! manage saved queries in the registry. `cortex query --project <name>`
+//! queries every mapped domain of a project.
 
-use crate::cli::output;
+use crate::audit::logger::{self as audit, AuditEvent};
+use crate::cartography::feature_plugins::PluginDims;
+use crate::cli::output::{self, QueryResult, QueryRow};
+use crate::cli::predicate::{self, Predicate};
+use crate::collective::registry::LocalRegistry;
 use crate::intelligence::cache::MapCache;
-use crate::map::types::{FeatureRange, NodeQuery, PageType, FEAT_PRICE, FEAT_RATING};
+use crate::map::types::{
+    FeatureRange, NodeMatch, NodeQuery, PageType, FEATURE_DIM, FEAT_PRICE, FEAT_RATING,
+};
+use crate::navigation::diversify::{self, ResultGroup, DEFAULT_SIMILARITY};
+use crate::navigation::query::{self, SavedQuery};
+use crate::project::{Project, Projects};
 use anyhow::{bail, Result};
+use std::path::PathBuf;
 
-/// Run the query command.
+/// What a query runs against.
+#[derive(Debug, Clone)]
+pub enum Scope {
+    /// One mapped domain.
+    Domain(String),
+    /// Every mapped domain of a project. Results are merged by confidence.
+    Project(Project),
+}
+
+impl Scope {
+    /// The scope of a query: `project` if given, else `domain`, else the
+    /// active project, if any.
+    pub fn resolve(domain: Option<String>, project: Option<&str>) -> Result<Option<Self>> {
+        if let (Some(domain), None) = (&domain, project) {
+            return Ok(Some(Self::Domain(domain.clone())));
+        }
+        let projects = Projects::load_default()?;
+        Ok(projects.resolve(project)?.cloned().map(Self::Project))
+    }
+
+    fn domains(&self) -> Vec<&str> {
+        match self {
+            Self::Domain(domain) => vec![domain.as_str()],
+            Self::Project(project) => project.domains.iter().map(String::as_str).collect(),
+        }
+    }
+
+    /// The domain of a result row, named only when results span domains.
+    fn row_domain(&self, domain: &str) -> Option<String> {
+        match self {
+            Self::Domain(_) => None,
+            Self::Project(_) => Some(domain.to_string()),
+        }
+    }
+
+    /// The JSON result for rows of this scope.
+    fn result(&self, rows: Vec<QueryRow>) -> QueryResult {
+        let (domain, project) = match self {
+            Self::Domain(domain) => (domain.clone(), None),
+            Self::Project(project) => (String::new(), Some(project.name.clone())),
+        };
+        QueryResult {
+            domain,
+            project,
+            total: rows.len(),
+            rows,
+        }
+    }
+}
+
+/// Run the query command. With `diversify`, near-identical results are
+/// collapsed and `limit` applies to the groups. Fails with
+/// [`predicate::PredicateFailed`] when a `fail_when` predicate holds.
+#[allow(clippy::too_many_arguments)]
 pub async fn run(
-    domain: &str,
+    scope: &Scope,
     page_type: Option<&str>,
     price_lt: Option<f32>,
     rating_gt: Option<f32>,
     limit: u32,
     feature_filters: &[String],
+    text: Option<&str>,
+    diversify: bool,
+    fail_when: &[Predicate],
 ) -> Result<()> {
-    // Load cached map
-    let mut cache = MapCache::default_cache()?;
-    let map = match cache.load_map(domain)? {
-        Some(m) => m,
+    let query = build_query(page_type, price_lt, rating_gt, limit, feature_filters, text);
+    execute(scope, query, diversify, fail_when).await
+}
+
+/// Save a query under `name` in the registry, then run it against `scope`
+/// if one is given.
+pub async fn run_save(
+    name: &str,
+    scope: Option<&Scope>,
+    query: NodeQuery,
+    diversify: bool,
+    fail_when: &[Predicate],
+) -> Result<()> {
+    let saved = SavedQuery::new(name, query, diversify)?;
+    let replaced = LocalRegistry::open_default()?.put_saved_query(saved.clone())?;
+    let mut event = AuditEvent::new("query_save", &audit::local_actor());
+    event.params = serde_json::to_value(&saved).unwrap_or_default();
+    audit::record(&event);
+    if !output::is_json() && !output::is_quiet() {
+        let verb = if replaced { "Updated" } else { "Saved" };
+        eprintln!("  {verb} query '{name}'. Re-run with: cortex query <domain> --use {name}");
+    }
+    match scope {
+        Some(scope) => execute(scope, saved.query, saved.diversify, fail_when).await,
         None => {
             if output::is_json() {
-                output::print_json(&serde_json::json!({
-                    "error": "no_map",
-                    "message": format!("No cached map for '{domain}'"),
-                    "hint": format!("Run: cortex map {domain}")
-                }));
-                return Ok(());
+                output::print_json(&serde_json::json!({ "saved": saved }));
             }
-            bail!("No map found for '{domain}'. Run 'cortex map {domain}' first.");
+            Ok(())
         }
+    }
+}
+
+/// Run the saved query `name` against `scope`, optionally overriding its limit.
+pub async fn run_saved(
+    scope: &Scope,
+    name: &str,
+    limit: Option<u32>,
+    fail_when: &[Predicate],
+) -> Result<()> {
+    let Some(saved) = LocalRegistry::open_default()?.saved_query(name)? else {
+        bail!("No saved query named '{name}'. List them with: cortex query --saved");
     };
+    let mut query = saved.query;
+    if let Some(limit) = limit {
+        query.limit = limit as usize;
+    }
+    execute(scope, query, saved.diversify, fail_when).await
+}
 
-    // Build query
+/// List the saved queries in the registry.
+pub async fn run_list_saved() -> Result<()> {
+    let queries = LocalRegistry::open_default()?.load_saved_queries()?;
+    if output::is_json() {
+        output::print_json(&serde_json::json!({ "queries": queries }));
+        return Ok(());
+    }
+    if output::is_quiet() {
+        return Ok(());
+    }
+    if queries.is_empty() {
+        eprintln!("  No saved queries. Save one with: cortex query <domain> --save <name> ...");
+        return Ok(());
+    }
+    for q in &queries {
+        let mut filters = Vec::new();
+        if let Some(types) = &q.query.page_types {
+            let names: Vec<String> = types.iter().map(|t| format!("{t:?}")).collect();
+            filters.push(format!("type={}", names.join(",")));
+        }
+        for r in &q.query.feature_ranges {
+            match (r.min, r.max) {
+                (Some(min), Some(max)) => filters.push(format!("{min}<[{}]<{max}", r.dimension)),
+                (Some(min), None) => filters.push(format!("[{}]>{min}", r.dimension)),
+                (None, Some(max)) => filters.push(format!("[{}]<{max}", r.dimension)),
+                (None, None) => {}
+            }
+        }
+        if let Some(text) = &q.query.text {
+            filters.push(format!("text={text:?}"));
+        }
+        filters.push(format!("limit={}", q.query.limit));
+        if q.diversify {
+            filters.push("diversify".to_string());
+        }
+        eprintln!(
+            "    {:<24} {}  (saved {})",
+            q.name,
+            filters.join(" "),
+            q.created_at.format("%Y-%m-%d")
+        );
+    }
+    Ok(())
+}
+
+/// Build a node query from command-line filters.
+pub fn build_query(
+    page_type: Option<&str>,
+    price_lt: Option<f32>,
+    rating_gt: Option<f32>,
+    limit: u32,
+    feature_filters: &[String],
+    text: Option<&str>,
+) -> NodeQuery {
     let mut feature_ranges = Vec::new();
 
     let page_types = page_type.map(|t| {
@@ -65,49 +219,138 @@ pub async fn run(
         if let Some(range) = parse_feature_filter(f) {
             feature_ranges.push(range);
         } else if !output::is_quiet() {
-            eprintln!("  Warning: could not parse feature filter '{f}'. Use format: \"48<300\" or \"52>0.8\"");
+            eprintln!("  Warning: could not parse feature filter '{f}'. Use format: \"48<300\", \"52>0.8\" or \"<plugin dimension>>0\"");
         }
     }
 
-    let query = NodeQuery {
+    NodeQuery {
         page_types,
         feature_ranges,
         limit: limit as usize,
+        text: text.map(str::to_string),
         ..Default::default()
-    };
+    }
+}
 
-    let results = map.filter(&query);
+/// Run `query` against the cached maps of `scope`, print the results and
+/// check the `--fail-when` predicates against them.
+async fn execute(
+    scope: &Scope,
+    mut query: NodeQuery,
+    diversify: bool,
+    fail_when: &[Predicate],
+) -> Result<()> {
+    // Load cached maps. A project's unmapped domains are skipped.
+    let mut cache = MapCache::default_cache()?;
+    let mut maps = Vec::new();
+    for domain in scope.domains() {
+        match cache.open_mapped(domain)? {
+            Some(map) => {
+                let text = cache.map_text(domain, map.node_count());
+                maps.push((domain, map, text));
+            }
+            None if matches!(scope, Scope::Domain(_)) => {
+                bail!("No map found for '{domain}'. Run 'cortex map {domain}' first.");
+            }
+            None if !output::is_json() && !output::is_quiet() => {
+                eprintln!("  Skipping {domain}: not mapped yet.");
+            }
+            None => {}
+        }
+    }
+    if let (Scope::Project(project), true) = (scope, maps.is_empty()) {
+        bail!(
+            "No domain of project '{}' is mapped. Run 'cortex map <domain>' first.",
+            project.name
+        );
+    }
+
+    let limit = query.limit;
+    if diversify {
+        query.limit = 0;
+    }
+    // Results from several maps are merged by text score when searching
+    // text, else by confidence.
+    let merged = maps.len() > 1;
+    let rank = |m: &NodeMatch| m.text_score.unwrap_or(m.confidence);
 
+    if diversify {
+        let mut groups = Vec::new();
+        for (domain, map, text) in &maps {
+            let results = query::search(text, &query, |q| map.filter(q));
+            groups.extend(
+                diversify::diversify(results, |i| map.node_features(i), DEFAULT_SIMILARITY)
+                    .into_iter()
+                    .map(|g| (*domain, g)),
+            );
+        }
+        if merged {
+            groups.sort_by(|a, b| rank(&b.1.representative).total_cmp(&rank(&a.1.representative)));
+        }
+        groups.truncate(limit);
+        print_groups(scope, &groups);
+        return predicate::check(fail_when, groups.len(), |field| {
+            row_values(field, groups.iter().map(|(_, g)| &g.representative))
+        });
+    }
+
+    let mut results = Vec::new();
+    for (domain, map, text) in &maps {
+        results.extend(
+            query::search(text, &query, |q| map.filter(q))
+                .into_iter()
+                .map(|m| (*domain, m)),
+        );
+    }
+    if merged {
+        results.sort_by(|a, b| rank(&b.1).total_cmp(&rank(&a.1)));
+        results.truncate(limit);
+    }
+    print_matches(scope, &results, limit);
+    predicate::check(fail_when, results.len(), |field| {
+        row_values(field, results.iter().map(|(_, m)| m))
+    })
+}
+
+/// Numeric values of a `--fail-when` field across query results.
+fn row_values<'a>(field: &str, rows: impl Iterator<Item = &'a NodeMatch>) -> Option<Vec<f64>> {
+    match field {
+        "confidence" => Some(rows.map(|m| m.confidence as f64).collect()),
+        _ => None,
+    }
+}
+
+/// Print matching pages, each with the domain it is on.
+fn print_matches(scope: &Scope, results: &[(&str, NodeMatch)], limit: usize) {
     if output::is_json() {
-        let items: Vec<serde_json::Value> = results
+        let rows: Vec<QueryRow> = results
             .iter()
-            .map(|m| {
-                serde_json::json!({
-                    "index": m.index,
-                    "url": m.url,
-                    "page_type": format!("{:?}", m.page_type),
-                    "confidence": m.confidence,
-                })
+            .map(|(domain, m)| QueryRow {
+                domain: scope.row_domain(domain),
+                index: m.index,
+                url: m.url.clone(),
+                page_type: m.page_type,
+                confidence: m.confidence,
+                text_score: m.text_score,
+                template: None,
+                group_size: None,
+                members: None,
             })
             .collect();
-        output::print_json(&serde_json::json!({
-            "domain": domain,
-            "total": results.len(),
-            "results": items,
-        }));
-        return Ok(());
+        output::print_result(&scope.result(rows));
+        return;
     }
 
     if results.is_empty() {
         if !output::is_quiet() {
             eprintln!("  No matching pages found. Try broader filters.");
         }
-        return Ok(());
+        return;
     }
 
     if !output::is_quiet() {
         let total = results.len();
-        if total > limit as usize {
+        if total > limit {
             eprintln!(
                 "  Found {} matching pages. Showing first {} (use --limit to change).",
                 total, limit
@@ -117,14 +360,18 @@ pub async fn run(
         }
         eprintln!();
 
-        for m in &results {
+        for (_, m) in results {
             let truncated_url = if m.url.len() > 50 {
                 format!("{}...", &m.url[..47])
             } else {
                 m.url.clone()
             };
+            let score = m
+                .text_score
+                .map(|s| format!("  score: {s:.2}"))
+                .unwrap_or_default();
             eprintln!(
-                "    [{:>5}] {:<20} {:<50} conf: {:.2}",
+                "    [{:>5}] {:<20} {:<50} conf: {:.2}{score}",
                 m.index,
                 format!("{:?}", m.page_type),
                 truncated_url,
@@ -132,12 +379,67 @@ pub async fn run(
             );
         }
     }
+}
 
-    Ok(())
+/// Print diversified results: one representative per group, with its size.
+fn print_groups(scope: &Scope, groups: &[(&str, ResultGroup)]) {
+    if output::is_json() {
+        let rows: Vec<QueryRow> = groups
+            .iter()
+            .map(|(domain, g)| QueryRow {
+                domain: scope.row_domain(domain),
+                index: g.representative.index,
+                url: g.representative.url.clone(),
+                page_type: g.representative.page_type,
+                confidence: g.representative.confidence,
+                text_score: g.representative.text_score,
+                template: Some(g.template.clone()),
+                group_size: Some(g.size),
+                members: Some(g.members.clone()),
+            })
+            .collect();
+        output::print_result(&scope.result(rows));
+        return;
+    }
+    if output::is_quiet() {
+        return;
+    }
+    if groups.is_empty() {
+        eprintln!("  No matching pages found. Try broader filters.");
+        return;
+    }
+
+    let pages: usize = groups.iter().map(|(_, g)| g.size).sum();
+    eprintln!(
+        "  {} distinct group(s) covering {} page(s):",
+        groups.len(),
+        pages
+    );
+    eprintln!();
+    for (_, g) in groups {
+        let m = &g.representative;
+        let truncated_url = if m.url.len() > 50 {
+            format!("{}...", &m.url[..47])
+        } else {
+            m.url.clone()
+        };
+        let similar = if g.size > 1 {
+            format!("  (+{} like {})", g.size - 1, g.template)
+        } else {
+            String::new()
+        };
+        eprintln!(
+            "    [{:>5}] {:<20} {:<50} conf: {:.2}{similar}",
+            m.index,
+            format!("{:?}", m.page_type),
+            truncated_url,
+            m.confidence,
+        );
+    }
 }
 
 /// Parse a page type string to the enum, supporting both human and hex formats.
-fn parse_page_type(s: &str) -> PageType {
+pub(crate) fn parse_page_type(s: &str) -> PageType {
     // Try hex format first (e.g., "0x04")
     if let Some(hex_str) = s.strip_prefix("0x") {
         if let Ok(n) = u8::from_str_radix(hex_str, 16) {
@@ -167,19 +469,35 @@ fn parse_page_type(s: &str) -> PageType {
         "contact" | "contact_page" => PageType::ContactPage,
         "faq" => PageType::Faq,
         "pricing" | "pricing_page" => PageType::PricingPage,
+        "error" | "error_page" => PageType::ErrorPage,
         _ => PageType::Unknown,
     }
 }
 
 /// Parse a feature filter like "48<300" or "52>0.8".
-fn parse_feature_filter(s: &str) -> Option<FeatureRange> {
+pub(crate) fn parse_feature_filter(s: &str) -> Option<FeatureRange> {
+    parse_feature_filter_in(s, None)
+}
+
+/// Parse a feature filter whose dimension is a number or a name registered
+/// in `dims` (by default, the plugin dimension registry, read only for
+/// names).
+fn parse_feature_filter_in(s: &str, dims: Option<&PluginDims>) -> Option<FeatureRange> {
+    let resolve = |dim: &str| -> Option<usize> {
+        let dim = dim.trim();
+        match dim.parse::<usize>() {
+            Ok(d) => (d < FEATURE_DIM).then_some(d),
+            Err(_) => match dims {
+                Some(dims) => dims.dim(dim),
+                None => PluginDims::load_default().dim(dim),
+            },
+        }
+    };
+
     // Try "<" separator
     if let Some(pos) = s.find('<') {
-        let dim: usize = s[..pos].trim().parse().ok()?;
+        let dim = resolve(&s[..pos])?;
         let val: f32 = s[pos + 1..].trim().parse().ok()?;
-        if dim > 127 {
-            return None;
-        }
         return Some(FeatureRange {
             dimension: dim,
             min: None,
@@ -189,11 +507,8 @@ fn parse_feature_filter(s: &str) -> Option<FeatureRange> {
 
     // Try ">" separator
     if let Some(pos) = s.find('>') {
-        let dim: usize = s[..pos].trim().parse().ok()?;
+        let dim = resolve(&s[..pos])?;
         let val: f32 = s[pos + 1..].trim().parse().ok()?;
-        if dim > 127 {
-            return None;
-        }
         return Some(FeatureRange {
             dimension: dim,
             min: Some(val),
@@ -203,3 +518,27 @@ fn parse_feature_filter(s: &str) -> Option<FeatureRange> {
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartography::feature_plugins::DimSpec;
+
+    #[test]
+    fn test_feature_filter_by_number_or_plugin_name() {
+        let dims = PluginDims::new(
+            8,
+            vec![DimSpec {
+                name: "tracking_pixel".into(),
+                ..Default::default()
+            }],
+        )
+        .unwrap();
+        let range = parse_feature_filter_in("48<300", Some(&dims)).unwrap();
+        assert_eq!((range.dimension, range.max), (48, Some(300.0)));
+        let range = parse_feature_filter_in("tracking_pixel>0", Some(&dims)).unwrap();
+        assert_eq!((range.dimension, range.min), (120, Some(0.0)));
+        assert!(parse_feature_filter_in("128>0", Some(&dims)).is_none());
+        assert!(parse_feature_filter_in("rival_widget>0", Some(&dims)).is_none());
+    }
+}
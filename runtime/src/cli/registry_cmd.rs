@@ -1,18 +1,23 @@
 //! CLI handlers for `cortex registry` subcommands.
 
+use crate::audit::logger::{self as audit, AuditEvent};
 use crate::cli::output;
+use crate::collective::delta;
+use crate::collective::lan;
 use crate::collective::registry::LocalRegistry;
-use anyhow::Result;
+use crate::collective::registry_server::{self, RegistryServerState};
+use crate::collective::sync::{
+    self, hex_encode, subscriptions_path, DomainSync, PullVerification, RemoteSync, Subscription,
+    Subscriptions,
+};
+use crate::trust::signing::{MapSigner, TrustedKeys};
+use anyhow::{Context, Result};
 use std::path::PathBuf;
-
-fn registry_dir() -> PathBuf {
-    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-    home.join(".cortex").join("registry")
-}
+use std::sync::Arc;
 
 /// List all maps in the local registry.
 pub async fn run_list() -> Result<()> {
-    let registry = LocalRegistry::new(registry_dir())?;
+    let registry = LocalRegistry::open_default()?;
     let entries = registry.list();
 
     if entries.is_empty() {
@@ -51,7 +56,7 @@ pub async fn run_list() -> Result<()> {
 
 /// Show registry statistics.
 pub async fn run_stats() -> Result<()> {
-    let registry = LocalRegistry::new(registry_dir())?;
+    let registry = LocalRegistry::open_default()?;
     let stats = registry.stats();
 
     if output::is_json() {
@@ -59,22 +64,564 @@ pub async fn run_stats() -> Result<()> {
     } else {
         println!("  Registry stats:");
         println!("    Domains:    {}", stats.domain_count);
-        println!("    Snapshots:  {} KB", stats.total_snapshot_bytes / 1024);
+        println!("    Versions:   {}", stats.total_versions);
+        println!(
+            "    Snapshots:  {} KB on disk ({} KB uncompressed)",
+            stats.total_snapshot_bytes / 1024,
+            stats.logical_snapshot_bytes / 1024
+        );
         println!("    Deltas:     {}", stats.total_deltas);
     }
 
     Ok(())
 }
 
+/// Verify stored versions of a domain against their integrity hashes.
+pub async fn run_verify(domain: &str) -> Result<()> {
+    let registry = LocalRegistry::open_default()?;
+    let checked = registry.verify(domain)?;
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({"domain": domain, "verified": checked}));
+    } else if !output::is_quiet() {
+        println!("  {domain}: {checked} objects verified.");
+    }
+
+    Ok(())
+}
+
 /// Garbage collect old deltas.
 pub async fn run_gc() -> Result<()> {
-    let mut registry = LocalRegistry::new(registry_dir())?;
+    let mut registry = LocalRegistry::open_default()?;
     let removed = registry.gc(50)?; // keep last 50 deltas
 
     if output::is_json() {
         output::print_json(&serde_json::json!({"removed": removed}));
     } else {
-        println!("  Removed {} old deltas.", removed);
+        println!("  Removed {} old deltas and versions.", removed);
+    }
+
+    Ok(())
+}
+
+/// Resolve the remote registry URL from `--remote` or `CORTEX_REGISTRY_URL`.
+fn remote_client(remote: Option<&str>, token: Option<&str>) -> Result<RemoteSync> {
+    let endpoint = remote
+        .map(str::to_string)
+        .or_else(|| std::env::var("CORTEX_REGISTRY_URL").ok())
+        .context("no remote registry: pass --remote or set CORTEX_REGISTRY_URL")?;
+    let token = token
+        .map(str::to_string)
+        .or_else(|| std::env::var("CORTEX_REGISTRY_TOKEN").ok());
+    Ok(RemoteSync::new(&endpoint, "local", token))
+}
+
+/// Serve the local registry over HTTP.
+pub async fn run_serve(
+    bind: &str,
+    port: u16,
+    mut tokens: Vec<String>,
+    require_signatures: bool,
+) -> Result<()> {
+    if let Ok(token) = std::env::var("CORTEX_REGISTRY_TOKEN") {
+        tokens.push(token);
+    }
+    let addr: std::net::SocketAddr = format!("{bind}:{port}")
+        .parse()
+        .with_context(|| format!("invalid bind address {bind}:{port}"))?;
+    let trusted = TrustedKeys::load_default()?;
+    if require_signatures && trusted.keys.is_empty() {
+        anyhow::bail!(
+            "--require-signatures needs at least one trusted key; add one with `cortex trust add`"
+        );
+    }
+    let state = Arc::new(
+        RegistryServerState::new(LocalRegistry::open_default()?, tokens)
+            .with_signature_policy(trusted, require_signatures),
+    );
+
+    if !output::is_quiet() {
+        let mode = if state.writable() {
+            "token auth"
+        } else {
+            "read-only"
+        };
+        let signing = if require_signatures {
+            ", signed pushes only"
+        } else {
+            ""
+        };
+        eprintln!("  Serving registry on http://{addr} ({mode}{signing})");
+    }
+    registry_server::serve(addr, state).await
+}
+
+/// Join LAN sync: serve the local registry, advertise it over mDNS, and
+/// periodically pull and merge maps from every peer found on the network.
+pub async fn run_lan(
+    port: u16,
+    interval_secs: u64,
+    token: Option<&str>,
+    allow_unsigned: bool,
+) -> Result<()> {
+    let token = token
+        .map(str::to_string)
+        .or_else(|| std::env::var("CORTEX_REGISTRY_TOKEN").ok());
+    let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "cortex".to_string());
+    let instance = format!("{host}-{port}");
+
+    let state = Arc::new(
+        RegistryServerState::new(
+            LocalRegistry::open_default()?,
+            token.iter().cloned().collect(),
+        )
+        .with_signature_policy(TrustedKeys::load_default()?, false),
+    );
+    let addr: std::net::SocketAddr = ([0, 0, 0, 0], port).into();
+    let server = tokio::spawn(registry_server::serve(addr, state.clone()));
+    let _advertiser = lan::LanAdvertiser::start(&instance, port)?;
+    let trusted = TrustedKeys::load_default()?;
+
+    if !output::is_quiet() {
+        eprintln!(
+            "  LAN sync as {instance} on port {port} (every {interval_secs}s, Ctrl-C to stop)"
+        );
+    }
+
+    loop {
+        let me = instance.clone();
+        let peers = tokio::task::spawn_blocking(move || {
+            lan::discover(&me, std::time::Duration::from_secs(3))
+        })
+        .await??;
+
+        for peer in peers {
+            let client = RemoteSync::new(&peer.endpoint, &instance, token.clone())
+                .with_verification(PullVerification {
+                    trusted: trusted.clone(),
+                    allow_unsigned,
+                });
+            match lan::sync_from_peer(state.registry(), &client).await {
+                Ok(results) => {
+                    for (domain, outcome) in results {
+                        if output::is_json() {
+                            output::print_json(&serde_json::json!({
+                                "peer": peer.instance,
+                                "domain": domain,
+                                "sync": outcome,
+                            }));
+                        } else if !output::is_quiet() {
+                            match outcome {
+                                DomainSync::Added => {
+                                    println!("  {domain}: added from {}", peer.instance)
+                                }
+                                DomainSync::Merged { conflicts } => println!(
+                                    "  {domain}: merged from {} ({conflicts} conflict(s))",
+                                    peer.instance
+                                ),
+                                DomainSync::UpToDate => {}
+                            }
+                        }
+                    }
+                }
+                Err(e) => tracing::warn!("LAN sync with {} failed: {e:#}", peer.instance),
+            }
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(std::time::Duration::from_secs(interval_secs)) => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+        if server.is_finished() {
+            break;
+        }
+    }
+
+    if server.is_finished() {
+        server.await??;
+    }
+    Ok(())
+}
+
+/// Push a domain's latest local map to a remote registry.
+///
+/// Sends only the latest delta when the remote is at that delta's base;
+/// otherwise uploads the full map. If the remote holds a version this
+/// registry has never seen, someone else pushed concurrently: their map is
+/// pulled and merged with ours (see [`delta::merge_maps`]) before the merged
+/// map is pushed. Everything pushed is signed with the local signing key.
+pub async fn run_push(domain: &str, remote: Option<&str>, token: Option<&str>) -> Result<()> {
+    let signer = Arc::new(MapSigner::load_or_create_default()?);
+    let client = remote_client(remote, token)?.with_signer(signer);
+    let mut registry = LocalRegistry::open_default()?;
+    let (map, _) = registry
+        .pull(domain)?
+        .with_context(|| format!("{domain} is not in the local registry"))?;
+    let local_hash = delta::hash_map(&map);
+
+    let remote_hash = client
+        .list_available()
+        .await?
+        .into_iter()
+        .find(|e| e.domain == domain)
+        .map(|e| e.latest_hash);
+    // The remote's version, if it is one we already have locally.
+    let known_base = remote_hash.as_ref().and_then(|remote| {
+        registry.entry(domain).and_then(|e| {
+            e.versions
+                .iter()
+                .find(|v| hex_encode(&v.map_hash) == *remote)
+                .map(|v| v.map_hash)
+        })
+    });
+
+    let mut report = None;
+    let mut pushed_hash = local_hash;
+    let mode = match (&remote_hash, known_base) {
+        (None, _) => {
+            client.push_map(domain, &map).await?;
+            "full"
+        }
+        (Some(remote), _) if *remote == hex_encode(&local_hash) => "up-to-date",
+        (Some(_), Some(base)) => {
+            let delta_pushed = match registry.latest_delta(domain)? {
+                Some(d) if d.base_hash == base => {
+                    // Sign exactly what the remote will reconstruct.
+                    match registry.version_by_hash(domain, &d.base_hash)? {
+                        Some(mut applied) => {
+                            delta::apply_delta(&mut applied, &d)?;
+                            delta::hash_map(&applied) == local_hash
+                                && client.push_delta(domain, &d, Some(&applied)).await.is_ok()
+                        }
+                        None => false,
+                    }
+                }
+                _ => false,
+            };
+            if delta_pushed {
+                "delta"
+            } else {
+                if !client.push_map_onto(domain, &map, &base).await? {
+                    anyhow::bail!("{domain} changed on the remote during the push; retry");
+                }
+                "full"
+            }
+        }
+        (Some(_), None) => {
+            let theirs = client
+                .pull_map(domain)
+                .await?
+                .with_context(|| format!("{domain} disappeared from the remote"))?;
+            let (merged, conflicts) = delta::merge_maps(&map, &theirs);
+            let d = delta::compute_delta(&map, &merged, "merge");
+            registry.push(domain, &merged, Some(d))?;
+            pushed_hash = delta::hash_map(&merged);
+            if !client
+                .push_map_onto(domain, &merged, &delta::hash_map(&theirs))
+                .await?
+            {
+                anyhow::bail!("{domain} changed on the remote during the merge; retry");
+            }
+            report = Some(conflicts);
+            "merged"
+        }
+    };
+
+    if mode != "up-to-date" {
+        let mut event = AuditEvent::new("registry_push", &audit::local_actor());
+        event.domain = Some(domain.to_string());
+        event.params = serde_json::json!({ "remote": client.endpoint(), "mode": mode });
+        event.result_hash = Some(hex_encode(&pushed_hash));
+        audit::record(&event);
+    }
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({
+            "domain": domain,
+            "pushed": mode,
+            "merge": report,
+        }));
+    } else if !output::is_quiet() {
+        match mode {
+            "up-to-date" => println!("  {domain} is already up to date on the remote."),
+            "delta" => println!("  Pushed delta for {domain}."),
+            "merged" => {
+                println!("  Remote had concurrent changes; pushed merged map for {domain}.")
+            }
+            _ => println!("  Pushed full map for {domain}."),
+        }
+        if let Some(report) = &report {
+            print_merge_report(report);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_merge_report(report: &delta::ConflictReport) {
+    let side = |kept: delta::MergeSide| match kept {
+        delta::MergeSide::Ours => "local",
+        delta::MergeSide::Theirs => "remote",
+    };
+    println!(
+        "  Merged {} shared, {} local-only and {} remote-only nodes ({} duplicate edges dropped).",
+        report.nodes_shared, report.nodes_ours_only, report.nodes_theirs_only, report.edges_deduped
+    );
+    if report.is_clean() {
+        return;
+    }
+    println!("  {} conflict(s) resolved:", report.conflicts.len());
+    for conflict in &report.conflicts {
+        match conflict {
+            delta::MergeConflict::Feature {
+                url,
+                dimension,
+                ours,
+                theirs,
+                kept,
+            } => println!(
+                "    {url} dim {dimension}: local {ours} vs remote {theirs} → kept {}",
+                side(*kept)
+            ),
+            delta::MergeConflict::PageType {
+                url,
+                ours,
+                theirs,
+                kept,
+            } => println!(
+                "    {url} page type: local {ours:?} vs remote {theirs:?} → kept {}",
+                side(*kept)
+            ),
+        }
+    }
+}
+
+/// Set the tags remote subscribers can select a domain by.
+pub async fn run_tag(domain: &str, tags: Vec<String>) -> Result<()> {
+    let mut registry = LocalRegistry::open_default()?;
+    if !registry.set_tags(domain, tags.clone())? {
+        anyhow::bail!("{domain} is not in the local registry");
+    }
+    if output::is_json() {
+        output::print_json(&serde_json::json!({"domain": domain, "tags": tags}));
+    } else if !output::is_quiet() {
+        println!("  {domain} tags: {}", tags.join(", "));
+    }
+    Ok(())
+}
+
+/// Add or replace a subscription to a remote registry.
+pub async fn run_subscribe(
+    name: &str,
+    remote: &str,
+    domains: Vec<String>,
+    tags: Vec<String>,
+    filter: Option<String>,
+    interval_secs: u64,
+) -> Result<()> {
+    let mut sub = Subscription::new(name, remote);
+    sub.domains = domains;
+    sub.tags = tags;
+    sub.filter = filter;
+    sub.interval_secs = interval_secs;
+
+    let mut subs = Subscriptions::load_default()?;
+    subs.add(sub)?;
+    subs.save(&subscriptions_path())?;
+    if output::is_json() {
+        output::print_json(&serde_json::json!({"subscribed": name}));
+    } else if !output::is_quiet() {
+        println!("  Subscription {name} saved. Run `cortex registry sync` to pull.");
+    }
+    Ok(())
+}
+
+/// Remove a subscription.
+pub async fn run_unsubscribe(name: &str) -> Result<()> {
+    let mut subs = Subscriptions::load_default()?;
+    if !subs.remove(name) {
+        anyhow::bail!("no subscription named {name}");
+    }
+    subs.save(&subscriptions_path())?;
+    if !output::is_quiet() && !output::is_json() {
+        println!("  Subscription {name} removed.");
+    }
+    Ok(())
+}
+
+/// List subscriptions and when each is next due.
+pub async fn run_subscriptions() -> Result<()> {
+    let subs = Subscriptions::load_default()?;
+    if output::is_json() {
+        output::print_json(&serde_json::to_value(subs.all())?);
+        return Ok(());
+    }
+    if output::is_quiet() {
+        return Ok(());
+    }
+    if subs.all().is_empty() {
+        println!("  No subscriptions. Add one with `cortex registry subscribe`.");
+        return Ok(());
+    }
+    for sub in subs.all() {
+        let scope = if sub.domains.is_empty() && sub.tags.is_empty() {
+            "all domains".to_string()
+        } else {
+            sub.domains
+                .iter()
+                .cloned()
+                .chain(sub.tags.iter().map(|t| format!("#{t}")))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        println!("  {} → {} ({scope})", sub.name, sub.remote);
+        if let Some(filter) = &sub.filter {
+            println!("    filter: {filter}");
+        }
+        let last = sub
+            .last_synced
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_else(|| "never".to_string());
+        println!(
+            "    every {}s, last synced {last}, next due {}",
+            sub.interval_secs,
+            sub.next_due().to_rfc3339()
+        );
+    }
+    Ok(())
+}
+
+/// Sync due subscriptions (or all of them with `force`). With `watch`, keep
+/// running and sync each subscription as it comes due.
+pub async fn run_sync(token: Option<&str>, force: bool, watch: bool) -> Result<()> {
+    let token = token
+        .map(str::to_string)
+        .or_else(|| std::env::var("CORTEX_REGISTRY_TOKEN").ok());
+    let trusted = TrustedKeys::load_default()?;
+    let mut registry = LocalRegistry::open_default()?;
+    let mut force = force;
+
+    loop {
+        let mut subs = Subscriptions::load_default()?;
+        let now = chrono::Utc::now();
+        for sub in subs.all_mut() {
+            if !force && !sub.is_due(now) {
+                continue;
+            }
+            // The full map is verified under the usual trust policy before
+            // a filter slices it.
+            let client = RemoteSync::new(&sub.remote, "subscription", token.clone())
+                .with_verification(PullVerification {
+                    trusted: trusted.clone(),
+                    allow_unsigned: false,
+                });
+            match sync::sync_subscription(&mut registry, sub, &client).await {
+                Ok(results) => {
+                    for (domain, outcome) in results {
+                        if output::is_json() {
+                            output::print_json(&serde_json::json!({
+                                "subscription": sub.name,
+                                "domain": domain,
+                                "sync": outcome,
+                            }));
+                        } else if !output::is_quiet() {
+                            match outcome {
+                                DomainSync::Added => println!("  {domain}: added ({})", sub.name),
+                                DomainSync::Merged { conflicts } => println!(
+                                    "  {domain}: merged ({}, {conflicts} conflict(s))",
+                                    sub.name
+                                ),
+                                DomainSync::UpToDate => {}
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    if !watch {
+                        return Err(e.context(format!("subscription {}", sub.name)));
+                    }
+                    tracing::warn!("subscription {} failed: {e:#}", sub.name);
+                }
+            }
+        }
+        subs.save(&subscriptions_path())?;
+        force = false;
+
+        if !watch {
+            return Ok(());
+        }
+        let next = subs
+            .all()
+            .iter()
+            .map(|s| s.next_due())
+            .min()
+            .unwrap_or_else(|| chrono::Utc::now() + chrono::Duration::seconds(60));
+        let wait = (next - chrono::Utc::now())
+            .to_std()
+            .unwrap_or_default()
+            .max(std::time::Duration::from_secs(1));
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
+
+/// Pull a domain's latest map from a remote registry into the local registry.
+///
+/// The map's signature is verified against the trusted key list; tampered
+/// maps are rejected, as are unsigned ones and ones signed by an untrusted
+/// key unless `allow_unsigned` is set.
+pub async fn run_pull(
+    domain: &str,
+    remote: Option<&str>,
+    token: Option<&str>,
+    allow_unsigned: bool,
+) -> Result<()> {
+    let client = remote_client(remote, token)?.with_verification(PullVerification {
+        trusted: TrustedKeys::load_default()?,
+        allow_unsigned,
+    });
+    let pulled = client
+        .pull_signed_map(domain)
+        .await?
+        .with_context(|| format!("{domain} is not in the remote registry"))?;
+    let map = pulled.map;
+    let publisher = pulled.publisher;
+    let untrusted_key = match (&publisher, &pulled.signature) {
+        (None, Some(s)) => Some(s.provenance.public_key.clone()),
+        _ => None,
+    };
+
+    let mut registry = LocalRegistry::open_default()?;
+    let prev = registry.pull(domain)?;
+    let unchanged = prev
+        .as_ref()
+        .is_some_and(|(old, _)| delta::hash_map(old) == delta::hash_map(&map));
+    if !unchanged {
+        let d = prev.map(|(old, _)| delta::compute_delta(&old, &map, "remote"));
+        registry.push_signed(domain, &map, d, pulled.signature, None)?;
+    }
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({
+            "domain": domain,
+            "nodes": map.nodes.len(),
+            "updated": !unchanged,
+            "signed_by": publisher,
+            "untrusted_key": untrusted_key,
+        }));
+    } else if !output::is_quiet() {
+        if unchanged {
+            println!("  {domain} is already up to date.");
+        } else {
+            println!("  Pulled {domain} ({} nodes).", map.nodes.len());
+        }
+        match (&publisher, &untrusted_key) {
+            (Some(name), _) => println!("  Signature verified: signed by trusted key {name}."),
+            (None, Some(key)) => println!("  Warning: signed by an untrusted key {key}."),
+            (None, None) => println!("  Warning: map is unsigned."),
+        }
     }
 
     Ok(())
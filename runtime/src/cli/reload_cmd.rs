@@ -0,0 +1,67 @@
+//! `cortex reload` — re-read configuration in the running daemon.
+
+use crate::cli::output;
+use crate::cli::start::SOCKET_PATH;
+use crate::trust::api_keys;
+use anyhow::{bail, Context, Result};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+
+/// Ask the daemon to reload extraction scripts, crawl calendars and watch
+/// rules without dropping connections or cached maps.
+pub async fn run() -> Result<()> {
+    let stream = match UnixStream::connect(SOCKET_PATH).await {
+        Ok(stream) => stream,
+        Err(_) => bail!("Cortex is not running. Start with 'cortex start'."),
+    };
+    let (reader, mut writer) = stream.into_split();
+
+    let mut req = serde_json::json!({"id": "reload", "method": "reload", "params": {}});
+    api_keys::attach_client_key(&mut req);
+    writer
+        .write_all(format!("{req}\n").as_bytes())
+        .await
+        .context("failed to send reload request")?;
+    writer.flush().await?;
+
+    let mut line = String::new();
+    BufReader::new(reader)
+        .read_line(&mut line)
+        .await
+        .context("failed to read reload response")?;
+    let resp: serde_json::Value =
+        serde_json::from_str(line.trim()).context("invalid reload response")?;
+    if let Some(error) = resp.get("error") {
+        bail!(
+            "reload failed: {}",
+            error["message"].as_str().unwrap_or("unknown error")
+        );
+    }
+    let result = &resp["result"];
+
+    if output::is_json() {
+        output::print_json(result);
+    } else if !output::is_quiet() {
+        if let Some(reloaded) = result["reloaded"].as_object() {
+            for (source, count) in reloaded {
+                println!("  Reloaded {source} ({count})");
+            }
+        }
+    }
+
+    let errors = result["errors"].as_object().cloned().unwrap_or_default();
+    for (source, message) in &errors {
+        eprintln!(
+            "  Failed to reload {source}: {}",
+            message.as_str().unwrap_or_default()
+        );
+    }
+    if !errors.is_empty() {
+        bail!(
+            "{} source(s) kept their previous configuration",
+            errors.len()
+        );
+    }
+
+    Ok(())
+}
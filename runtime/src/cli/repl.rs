@@ -117,16 +117,21 @@ pub async fn run() -> Result<()> {
     let mut state = repl_commands::ReplState::new();
 
     // Main REPL loop
-    let prompt = format!(
-        " {} ",
-        if Styled::new().ok_sym() == "OK" {
-            "cortex>"
+    loop {
+        // The prompt shows the explorer's position while browsing a map
+        let label = match &state.explorer {
+            Some(ex) => format!("cortex {}#{}>", ex.domain, ex.current()),
+            None => "cortex>".to_string(),
+        };
+        let prompt = if Styled::new().ok_sym() == "OK" {
+            format!(" {label} ")
         } else {
-            "\x1b[36mcortex>\x1b[0m"
+            format!(" \x1b[36m{label}\x1b[0m ")
+        };
+        if let Some(helper) = rl.helper_mut() {
+            helper.set_exploring(state.explorer.is_some());
         }
-    );
 
-    loop {
         match rl.readline(&prompt) {
             Ok(line) => {
                 let line = line.trim();
@@ -9,6 +9,7 @@
 use crate::cli::doctor::cortex_home;
 use crate::cli::output::{self, format_duration, format_size, Styled};
 use crate::cli::repl_complete::COMMANDS;
+use crate::cli::repl_explore::{self, Explorer};
 use crate::cli::repl_progress;
 use crate::intelligence::cache::MapCache;
 use crate::map::types::{
@@ -21,12 +22,15 @@ use std::time::Instant;
 pub struct ReplState {
     /// Currently active domain for queries/pathfind.
     pub active_domain: Option<String>,
+    /// Map being browsed with `/explore`, if any.
+    pub explorer: Option<Explorer>,
 }
 
 impl ReplState {
     pub fn new() -> Self {
         Self {
             active_domain: None,
+            explorer: None,
         }
     }
 }
@@ -38,6 +42,16 @@ pub async fn execute(input: &str, state: &mut ReplState) -> Result<bool> {
         return Ok(false);
     }
 
+    // While exploring, plain words are explorer commands
+    if !input.starts_with('/') {
+        if let Some(explorer) = state.explorer.as_mut() {
+            if repl_explore::execute(input, explorer).await? {
+                state.explorer = None;
+            }
+            return Ok(false);
+        }
+    }
+
     // Strip leading / if present
     let input = input.strip_prefix('/').unwrap_or(input);
 
@@ -64,6 +78,7 @@ pub async fn execute(input: &str, state: &mut ReplState) -> Result<bool> {
         "query" => cmd_query(args, state)?,
         "pathfind" | "path" => cmd_pathfind(args, state)?,
         "perceive" => cmd_perceive(args).await?,
+        "explore" => cmd_explore(args, state)?,
         "settings" | "config" => cmd_settings()?,
         "cache" => cmd_cache(args)?,
         "plug" => cmd_plug().await?,
@@ -266,7 +281,7 @@ async fn cmd_map(args: &str, state: &mut ReplState) -> Result<()> {
         }
     };
 
-    let req = serde_json::json!({
+    let mut req = serde_json::json!({
         "id": format!("repl-map-{}", std::process::id()),
         "method": "map",
         "params": {
@@ -274,9 +289,9 @@ async fn cmd_map(args: &str, state: &mut ReplState) -> Result<()> {
             "max_nodes": 50000_u32,
             "max_render": 200_u32,
             "max_time_ms": 10000_u64,
-            "respect_robots": true,
         }
     });
+    crate::trust::api_keys::attach_client_key(&mut req);
     let req_str = format!("{}\n", req);
     stream.write_all(req_str.as_bytes()).await?;
 
@@ -379,6 +394,8 @@ fn cmd_query(args: &str, state: &mut ReplState) -> Result<()> {
     let mut rating_gt: Option<f32> = None;
     let mut limit: u32 = 20;
     let mut feature_filters: Vec<String> = Vec::new();
+    let mut diversify = false;
+    let mut use_saved: Option<String> = None;
 
     // Simple arg parser
     let tokens: Vec<&str> = args.split_whitespace().collect();
@@ -405,6 +422,14 @@ fn cmd_query(args: &str, state: &mut ReplState) -> Result<()> {
                 feature_filters.push(tokens[i + 1].to_string());
                 i += 2;
             }
+            "--diversify" => {
+                diversify = true;
+                i += 1;
+            }
+            "--use" if i + 1 < tokens.len() => {
+                use_saved = Some(tokens[i + 1].to_string());
+                i += 2;
+            }
             s if !s.starts_with('-') && domain.is_none() => {
                 domain = Some(s.to_string());
                 i += 1;
@@ -429,15 +454,23 @@ fn cmd_query(args: &str, state: &mut ReplState) -> Result<()> {
     };
 
     // Delegate to existing query logic
+    let scope = crate::cli::query_cmd::Scope::Domain(domain);
     let rt = tokio::runtime::Handle::current();
     rt.block_on(async {
+        if let Some(name) = use_saved {
+            let limit = tokens.contains(&"--limit").then_some(limit);
+            return crate::cli::query_cmd::run_saved(&scope, &name, limit, &[]).await;
+        }
         crate::cli::query_cmd::run(
-            &domain,
+            &scope,
             page_type_str.as_deref(),
             price_lt,
             rating_gt,
             limit,
             &feature_filters,
+            None,
+            diversify,
+            &[],
         )
         .await
     })
@@ -539,6 +572,41 @@ async fn cmd_perceive(args: &str) -> Result<()> {
     crate::cli::perceive_cmd::run(url, "pretty").await
 }
 
+/// /explore [domain] [node] — Browse a cached map interactively.
+fn cmd_explore(args: &str, state: &mut ReplState) -> Result<()> {
+    let s = Styled::new();
+
+    let mut tokens = args.split_whitespace();
+    let domain = match tokens.next().or(state.active_domain.as_deref()) {
+        Some(d) => d.to_string(),
+        None => {
+            eprintln!(
+                "  {} No active domain. Use: {}",
+                s.info_sym(),
+                s.bold("/explore example.com")
+            );
+            return Ok(());
+        }
+    };
+
+    let mut explorer = Explorer::open(&domain)?;
+    if let Some(node) = tokens.next() {
+        explorer.seek(node)?;
+    }
+    let nodes = explorer.node_count();
+    let current = explorer.current();
+    state.active_domain = Some(domain.clone());
+    state.explorer = Some(explorer);
+    eprintln!(
+        "  {} Exploring {} ({nodes} nodes) from #{current}. Type {} for commands, {} to leave.",
+        s.ok_sym(),
+        s.bold(&domain),
+        s.cyan("help"),
+        s.cyan("done")
+    );
+    Ok(())
+}
+
 /// /settings — Show current configuration.
 fn cmd_settings() -> Result<()> {
     let s = Styled::new();
@@ -614,5 +682,5 @@ fn cmd_cache(args: &str) -> Result<()> {
 
 /// /plug — Show agent connections.
 async fn cmd_plug() -> Result<()> {
-    crate::cli::plug::run(false, false, true, None, None).await
+    crate::cli::plug::run(false, false, true, false, None, None, None).await
 }
@@ -15,6 +15,7 @@ use rustyline::{
 };
 
 use crate::cli::doctor::cortex_home;
+use crate::cli::repl_explore::{EXPLORE_COMMANDS, FEATURES};
 
 /// All available REPL slash commands.
 pub const COMMANDS: &[(&str, &str)] = &[
@@ -22,6 +23,7 @@ pub const COMMANDS: &[(&str, &str)] = &[
     ("/query", "Search current map by type/features"),
     ("/pathfind", "Find shortest path between nodes"),
     ("/perceive", "Analyze a single live page"),
+    ("/explore", "Browse a cached map node by node"),
     ("/status", "Show runtime status"),
     ("/doctor", "Check environment and diagnose issues"),
     ("/maps", "List all cached maps"),
@@ -54,11 +56,60 @@ const PAGE_TYPES: &[&str] = &[
 ];
 
 /// Cortex REPL helper providing tab completion.
-pub struct CortexHelper;
+pub struct CortexHelper {
+    /// Whether the REPL is in `/explore` mode, where plain words are
+    /// explorer commands.
+    exploring: bool,
+}
 
 impl CortexHelper {
     pub fn new() -> Self {
-        Self
+        Self { exploring: false }
+    }
+
+    /// Switch completion between slash commands and explorer commands.
+    pub fn set_exploring(&mut self, exploring: bool) {
+        self.exploring = exploring;
+    }
+
+    /// Complete explorer commands, and page types and feature names for `find`.
+    fn complete_explore(&self, input: &str) -> (usize, Vec<Pair>) {
+        if !input.contains(' ') {
+            let matches = EXPLORE_COMMANDS
+                .iter()
+                .filter(|(cmd, _)| cmd.starts_with(input))
+                .map(|(cmd, desc)| Pair {
+                    display: format!("{cmd:<16} {desc}"),
+                    replacement: format!("{cmd} "),
+                })
+                .collect();
+            return (0, matches);
+        }
+
+        let (cmd, _) = input.split_once(' ').unwrap_or((input, ""));
+        if !matches!(cmd, "find" | "query") {
+            return (input.len(), Vec::new());
+        }
+        let word_start = input.rfind(' ').map_or(0, |i| i + 1);
+        let word = &input[word_start..];
+        if word.contains(['<', '>', '=']) {
+            return (input.len(), Vec::new());
+        }
+        let types = PAGE_TYPES.iter().map(|t| Pair {
+            display: t.to_string(),
+            replacement: format!("{t} "),
+        });
+        // Feature names are completed without a trailing space so the
+        // comparison can be typed straight after.
+        let features = FEATURES.iter().map(|(name, _)| Pair {
+            display: format!("{name}<>"),
+            replacement: name.to_string(),
+        });
+        let matches = types
+            .chain(features)
+            .filter(|p| p.replacement.starts_with(word))
+            .collect();
+        (word_start, matches)
     }
 
     /// Get list of cached domain names from ~/.cortex/maps/*.ctx.
@@ -91,6 +142,10 @@ impl Completer for CortexHelper {
     ) -> rustyline::Result<(usize, Vec<Pair>)> {
         let input = &line[..pos];
 
+        if self.exploring && !input.starts_with('/') {
+            return Ok(self.complete_explore(input));
+        }
+
         // Complete command names if input starts with /
         if !input.contains(' ') {
             let matches: Vec<Pair> = COMMANDS
@@ -110,8 +165,8 @@ impl Completer for CortexHelper {
         let args = if parts.len() > 1 { parts[1] } else { "" };
 
         match cmd {
-            // Domain completion for /map, /query, /use, /pathfind
-            "/map" | "/query" | "/use" | "/pathfind" => {
+            // Domain completion for /map, /query, /use, /pathfind, /explore
+            "/map" | "/query" | "/use" | "/pathfind" | "/explore" => {
                 if !args.starts_with('-') {
                     let domains = self.cached_domains();
                     let prefix_start = input.len() - args.len();
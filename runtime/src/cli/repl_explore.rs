@@ -0,0 +1,639 @@
+// Copyright 2026 Cortex Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Map explorer mode for the Cortex REPL.
+//!
+//! `/explore [domain]` loads a cached map and puts the REPL on one of its
+//! nodes. Plain words (no leading `/`) then move around the graph: list a
+//! node's links, follow them, step back, show the link tree, run ad-hoc
+//! queries, and PERCEIVE the page live. Slash commands keep working.
+
+use crate::cli::output::Styled;
+use crate::cli::query_cmd::parse_page_type;
+use crate::intelligence::cache::MapCache;
+use crate::map::types::*;
+use anyhow::{bail, Result};
+use std::collections::HashSet;
+
+/// Commands available while exploring.
+pub const EXPLORE_COMMANDS: &[(&str, &str)] = &[
+    ("show", "Describe the current node"),
+    ("ls", "List the pages this node links to"),
+    ("in", "List the pages linking here"),
+    (
+        "go",
+        "Follow a link: go <n> | go #<index> | go <url or path>",
+    ),
+    ("back", "Return to the previous node"),
+    ("up", "Go to the shallowest page linking here"),
+    ("home", "Jump to the root of the map"),
+    ("tree", "Show the link tree below this node: tree [depth]"),
+    (
+        "find",
+        "Query the map: find [type] [price<100 rating>=4 ...]",
+    ),
+    ("perceive", "Analyze the current page live"),
+    ("help", "Show explorer commands"),
+    ("done", "Leave the explorer"),
+];
+
+/// Named features, for display, `find` filters and completion.
+pub const FEATURES: &[(&str, usize)] = &[
+    ("price", FEAT_PRICE),
+    ("original_price", FEAT_PRICE_ORIGINAL),
+    ("discount", FEAT_DISCOUNT_PCT),
+    ("availability", FEAT_AVAILABILITY),
+    ("rating", FEAT_RATING),
+    ("review_count", FEAT_REVIEW_COUNT_LOG),
+    ("free_shipping", FEAT_SHIPPING_FREE),
+    ("deal_score", FEAT_DEAL_SCORE),
+    ("image_count", FEAT_IMAGE_COUNT),
+    ("form_fields", FEAT_FORM_FIELD_COUNT),
+    ("internal_links", FEAT_LINK_COUNT_INTERNAL),
+    ("load_speed", FEAT_LOAD_TIME),
+    ("freshness", FEAT_CONTENT_FRESHNESS),
+];
+
+/// Children shown per node by `tree` before eliding the rest.
+const TREE_FANOUT: usize = 8;
+
+/// Rows shown by `ls`, `in` and `find`.
+const LIST_LIMIT: usize = 25;
+
+/// A cached map being explored, with the current position.
+pub struct Explorer {
+    pub domain: String,
+    map: SiteMap,
+    /// Source nodes of each node's inbound edges.
+    inbound: Vec<Vec<u32>>,
+    current: u32,
+    history: Vec<u32>,
+    /// Nodes listed by the last `ls`/`in`/`find`, for `go <n>`.
+    listed: Vec<u32>,
+}
+
+impl Explorer {
+    /// Load the cached map for `domain`, positioned at its root.
+    pub fn open(domain: &str) -> Result<Self> {
+        let mut cache = MapCache::default_cache()?;
+        let Some(map) = cache.load_map(domain)? else {
+            bail!("No cached map for '{domain}'. Map it first with: /map {domain}");
+        };
+        Self::new(domain, map)
+    }
+
+    fn new(domain: &str, map: SiteMap) -> Result<Self> {
+        if map.nodes.is_empty() {
+            bail!("The map for '{domain}' has no nodes.");
+        }
+        let mut inbound = vec![Vec::new(); map.nodes.len()];
+        for from in 0..map.nodes.len() as u32 {
+            for edge in map.edges_from(from) {
+                if let Some(sources) = inbound.get_mut(edge.target_node as usize) {
+                    if !sources.contains(&from) {
+                        sources.push(from);
+                    }
+                }
+            }
+        }
+        let mut explorer = Self {
+            domain: domain.to_string(),
+            map,
+            inbound,
+            current: 0,
+            history: Vec::new(),
+            listed: Vec::new(),
+        };
+        explorer.current = explorer.root();
+        Ok(explorer)
+    }
+
+    /// The current node's index.
+    pub fn current(&self) -> u32 {
+        self.current
+    }
+
+    /// Number of nodes in the map.
+    pub fn node_count(&self) -> usize {
+        self.map.nodes.len()
+    }
+
+    /// Move to `node` (by index or URL) without recording history.
+    pub fn seek(&mut self, target: &str) -> Result<()> {
+        self.current = self.resolve(target)?;
+        Ok(())
+    }
+
+    /// The home page if the map has one, else the shallowest node.
+    fn root(&self) -> u32 {
+        let nodes = &self.map.nodes;
+        nodes
+            .iter()
+            .position(|n| n.page_type == PageType::Home && n.depth == 0)
+            .or_else(|| (0..nodes.len()).min_by_key(|&i| nodes[i].depth))
+            .unwrap_or(0) as u32
+    }
+
+    fn visit(&mut self, node: u32) {
+        if node != self.current {
+            self.history.push(self.current);
+            self.current = node;
+        }
+    }
+
+    /// Resolve `go` arguments: `3` is the third row of the last listing,
+    /// `#120` a node index, anything else a URL or path on the domain.
+    fn resolve(&self, target: &str) -> Result<u32> {
+        if let Some(index) = target.strip_prefix('#') {
+            let index: u32 = index.parse()?;
+            if index as usize >= self.map.nodes.len() {
+                bail!("no node #{index} (the map has {})", self.map.nodes.len());
+            }
+            return Ok(index);
+        }
+        if let Ok(row) = target.parse::<usize>() {
+            return match row.checked_sub(1).and_then(|i| self.listed.get(i)) {
+                Some(&node) => Ok(node),
+                None => bail!("no row {row} in the last listing; use #{row} for a node index"),
+            };
+        }
+        if let Some(node) = self.map.find_url(target) {
+            return Ok(node);
+        }
+        let path = target.trim_end_matches('/');
+        if path.is_empty() {
+            return Ok(self.root());
+        }
+        self.map
+            .urls
+            .iter()
+            .position(|u| u.trim_end_matches('/').ends_with(path))
+            .map(|i| i as u32)
+            .ok_or_else(|| anyhow::anyhow!("no node matches '{target}'"))
+    }
+}
+
+/// Run an explorer command. Returns `true` when the user leaves the explorer.
+pub async fn execute(input: &str, ex: &mut Explorer) -> Result<bool> {
+    let s = Styled::new();
+    let mut parts = input.trim().splitn(2, ' ');
+    let cmd = parts.next().unwrap_or("");
+    let args = parts.next().unwrap_or("").trim();
+
+    match cmd {
+        "done" | "leave" => return Ok(true),
+        "help" | "?" => print_help(),
+        "show" | "info" | "." => print_node(ex),
+        "ls" | "links" => {
+            let targets: Vec<(u32, Option<EdgeType>)> = ex
+                .map
+                .edges_from(ex.current)
+                .iter()
+                .map(|e| (e.target_node, Some(e.edge_type)))
+                .collect();
+            print_listing(ex, "Links from here", targets);
+        }
+        "in" | "refs" => {
+            let sources: Vec<(u32, Option<EdgeType>)> = ex.inbound[ex.current as usize]
+                .iter()
+                .map(|&n| (n, None))
+                .collect();
+            print_listing(ex, "Linked from", sources);
+        }
+        "go" | "cd" | "open" => {
+            if args.is_empty() {
+                eprintln!(
+                    "  {} Usage: {}",
+                    s.info_sym(),
+                    s.bold("go <n> | go #<index> | go <url>")
+                );
+                return Ok(false);
+            }
+            let node = ex.resolve(args)?;
+            ex.visit(node);
+            print_node(ex);
+        }
+        "back" | "b" => match ex.history.pop() {
+            Some(node) => {
+                ex.current = node;
+                print_node(ex);
+            }
+            None => eprintln!("  {} Already at the start of the trail.", s.info_sym()),
+        },
+        "up" | ".." => {
+            let parent = ex.inbound[ex.current as usize]
+                .iter()
+                .copied()
+                .min_by_key(|&n| ex.map.nodes[n as usize].depth);
+            match parent {
+                Some(node) => {
+                    ex.visit(node);
+                    print_node(ex);
+                }
+                None => eprintln!("  {} Nothing links to this page.", s.info_sym()),
+            }
+        }
+        "home" | "root" => {
+            let root = ex.root();
+            ex.visit(root);
+            print_node(ex);
+        }
+        "tree" => {
+            let depth = if args.is_empty() { 2 } else { args.parse()? };
+            print_tree(ex, depth);
+        }
+        "find" | "query" => {
+            let query = parse_find(args)?;
+            let matches: Vec<(u32, Option<EdgeType>)> = ex
+                .map
+                .filter(&query)
+                .into_iter()
+                .map(|m| (m.index, None))
+                .collect();
+            print_listing(ex, "Matches", matches);
+        }
+        "perceive" => {
+            let url = ex.map.node_url(ex.current).to_string();
+            crate::cli::perceive_cmd::run(&url, "pretty").await?;
+        }
+        _ => {
+            eprintln!(
+                "  {} Unknown explorer command '{cmd}'. Type {} for the list, or a /command.",
+                s.warn_sym(),
+                s.bold("help")
+            );
+        }
+    }
+    Ok(false)
+}
+
+fn print_help() {
+    let s = Styled::new();
+    eprintln!();
+    eprintln!("  {}", s.bold("Explorer:"));
+    eprintln!();
+    for (cmd, desc) in EXPLORE_COMMANDS {
+        eprintln!("    {:<22} {}", s.cyan(cmd), s.dim(desc));
+    }
+    eprintln!();
+    eprintln!(
+        "  {}",
+        s.dim("Tip: Tab completes commands, page types and feature names.")
+    );
+    eprintln!();
+}
+
+/// Print the current node: identity, flags, links and features.
+fn print_node(ex: &Explorer) {
+    let s = Styled::new();
+    let i = ex.current;
+    let node = &ex.map.nodes[i as usize];
+    eprintln!();
+    eprintln!(
+        "  {} {}",
+        s.bold(&format!("#{i}")),
+        s.cyan(ex.map.node_url(i))
+    );
+    let status = if node.http_status == 0 {
+        String::new()
+    } else {
+        format!(" · HTTP {}", node.http_status)
+    };
+    eprintln!(
+        "    {} ({:.0}% confident) · depth {}{status}",
+        node.page_type,
+        node.confidence as f32 / 2.55,
+        node.depth
+    );
+
+    let flags = describe_flags(node.flags);
+    if !flags.is_empty() {
+        eprintln!("    {}", s.dim(&flags.join(" · ")));
+    }
+    let actions = ex.map.actions_for(i).len();
+    eprintln!(
+        "    {} links out · {} links in{}",
+        ex.map.edges_from(i).len(),
+        ex.inbound[i as usize].len(),
+        if actions > 0 {
+            format!(" · {actions} actions")
+        } else {
+            String::new()
+        }
+    );
+
+    let features = ex.map.node_features(i);
+    let described: Vec<(&str, String)> = FEATURES
+        .iter()
+        .filter_map(|&(name, dim)| describe_feature(dim, features[dim]).map(|v| (name, v)))
+        .collect();
+    if !described.is_empty() {
+        eprintln!();
+        for (name, value) in described {
+            eprintln!("    {:<16} {value}", s.dim(name));
+        }
+    }
+    eprintln!();
+}
+
+/// Print numbered rows and remember them for `go <n>`.
+fn print_listing(ex: &mut Explorer, title: &str, rows: Vec<(u32, Option<EdgeType>)>) {
+    let s = Styled::new();
+    let mut seen = HashSet::new();
+    let rows: Vec<(u32, Option<EdgeType>)> =
+        rows.into_iter().filter(|(n, _)| seen.insert(*n)).collect();
+    ex.listed = rows.iter().map(|(n, _)| *n).collect();
+    if rows.is_empty() {
+        eprintln!("  {} None.", s.info_sym());
+        return;
+    }
+    eprintln!();
+    eprintln!(
+        "  {} {}",
+        s.bold(&format!("{title}:")),
+        s.dim(&format!("({})", rows.len()))
+    );
+    for (row, (node, edge)) in rows.iter().take(LIST_LIMIT).enumerate() {
+        let edge = edge
+            .map(|e| format!("  {}", s.dim(&format!("{e:?}").to_lowercase())))
+            .unwrap_or_default();
+        eprintln!(
+            "    {:>3}. {:<8} {:<16} {}{edge}",
+            row + 1,
+            format!("#{node}"),
+            ex.map.nodes[*node as usize].page_type.to_string(),
+            short_url(ex.map.node_url(*node)),
+        );
+    }
+    if rows.len() > LIST_LIMIT {
+        eprintln!(
+            "    {}",
+            s.dim(&format!(
+                "… {} more (go <n> works for all of them)",
+                rows.len() - LIST_LIMIT
+            ))
+        );
+    }
+    eprintln!();
+}
+
+/// Print the outbound link tree below the current node, skipping pages
+/// already shown.
+fn print_tree(ex: &Explorer, depth: usize) {
+    let s = Styled::new();
+    let mut seen = HashSet::from([ex.current]);
+    eprintln!();
+    eprintln!(
+        "  {} {} {}",
+        s.bold(&format!("#{}", ex.current)),
+        ex.map.nodes[ex.current as usize].page_type,
+        short_url(ex.map.node_url(ex.current))
+    );
+    tree_level(ex, ex.current, depth, "  ", &mut seen);
+    eprintln!();
+}
+
+fn tree_level(ex: &Explorer, node: u32, depth: usize, indent: &str, seen: &mut HashSet<u32>) {
+    if depth == 0 {
+        return;
+    }
+    let s = Styled::new();
+    let children: Vec<u32> = ex
+        .map
+        .edges_from(node)
+        .iter()
+        .map(|e| e.target_node)
+        .filter(|&t| seen.insert(t))
+        .collect();
+    let shown = children.len().min(TREE_FANOUT);
+    for (i, &child) in children.iter().take(shown).enumerate() {
+        let last = i + 1 == shown && children.len() <= TREE_FANOUT;
+        let (branch, next) = if last {
+            ("└─", "   ")
+        } else {
+            ("├─", "│  ")
+        };
+        eprintln!(
+            "  {indent}{branch} {} {} {}",
+            s.dim(&format!("#{child}")),
+            ex.map.nodes[child as usize].page_type,
+            short_url(ex.map.node_url(child))
+        );
+        tree_level(ex, child, depth - 1, &format!("{indent}{next}"), seen);
+    }
+    if children.len() > TREE_FANOUT {
+        eprintln!(
+            "  {indent}└─ {}",
+            s.dim(&format!("… {} more", children.len() - TREE_FANOUT))
+        );
+    }
+}
+
+/// Parse `find` arguments: an optional page type, then filters such as
+/// `price<100`, `rating>=4` or `availability=in_stock`, and `limit=<n>`.
+pub fn parse_find(args: &str) -> Result<NodeQuery> {
+    let mut query = NodeQuery {
+        limit: 100,
+        ..Default::default()
+    };
+    for token in args.split_whitespace() {
+        if let Some(limit) = token.strip_prefix("limit=") {
+            query.limit = limit.parse()?;
+            continue;
+        }
+        if !token.contains(['<', '>', '=']) {
+            let pt = parse_page_type(token);
+            if pt == PageType::Unknown && token != "unknown" {
+                bail!("unknown page type '{token}'");
+            }
+            query.page_types.get_or_insert_with(Vec::new).push(pt);
+            continue;
+        }
+        query.feature_ranges.push(parse_filter(token)?);
+    }
+    Ok(query)
+}
+
+/// Parse one feature filter, converting human units (stars, percent,
+/// review counts) to the feature encoding.
+fn parse_filter(token: &str) -> Result<FeatureRange> {
+    let Some(op_at) = token.find(['<', '>', '=']) else {
+        bail!("expected a comparison in '{token}'");
+    };
+    let name = &token[..op_at];
+    let rest = &token[op_at..];
+    let (op, value) = ["<=", ">=", "<", ">", "="]
+        .iter()
+        .find_map(|op| rest.strip_prefix(op).map(|v| (*op, v)))
+        .unwrap_or(("=", rest));
+    let Some(&(_, dim)) = FEATURES.iter().find(|(n, _)| *n == name) else {
+        bail!(
+            "unknown feature '{name}' (one of: {})",
+            FEATURES
+                .iter()
+                .map(|(n, _)| *n)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    };
+    let value = encode_value(dim, value)
+        .ok_or_else(|| anyhow::anyhow!("cannot compare {name} with '{value}'"))?;
+    let (min, max) = match op {
+        "<" | "<=" => (None, Some(value)),
+        ">" | ">=" => (Some(value), None),
+        _ => (Some(value), Some(value)),
+    };
+    Ok(FeatureRange {
+        dimension: dim,
+        min,
+        max,
+    })
+}
+
+/// Convert a value as the user writes it to the feature encoding.
+fn encode_value(dim: usize, value: &str) -> Option<f32> {
+    if dim == FEAT_AVAILABILITY {
+        match value {
+            "in_stock" | "available" | "yes" => return Some(1.0),
+            "out_of_stock" | "unavailable" | "no" => return Some(0.0),
+            "limited" | "preorder" => return Some(0.5),
+            _ => {}
+        }
+    }
+    if dim == FEAT_SHIPPING_FREE {
+        match value {
+            "yes" | "true" => return Some(1.0),
+            "no" | "false" => return Some(0.0),
+            _ => {}
+        }
+    }
+    let v: f32 = value.trim_end_matches('%').parse().ok()?;
+    Some(match dim {
+        FEAT_RATING => v / 5.0,
+        FEAT_DISCOUNT_PCT => v / 100.0,
+        FEAT_REVIEW_COUNT_LOG => ((v + 1.0).ln() / 10.0).clamp(0.0, 1.0),
+        _ => v,
+    })
+}
+
+/// Describe a feature value in human terms, or `None` when it carries no
+/// information (zero or unset).
+pub fn describe_feature(dim: usize, value: f32) -> Option<String> {
+    if value == 0.0 && dim != FEAT_AVAILABILITY {
+        return None;
+    }
+    Some(match dim {
+        FEAT_PRICE | FEAT_PRICE_ORIGINAL => format!("{value:.2}"),
+        FEAT_DISCOUNT_PCT => format!("{:.0}% off", value * 100.0),
+        FEAT_AVAILABILITY => match value {
+            v if v >= 0.75 => "in stock".to_string(),
+            v if v > 0.25 => "limited / preorder".to_string(),
+            _ => "out of stock".to_string(),
+        },
+        FEAT_RATING => format!("{:.1} / 5", value * 5.0),
+        FEAT_REVIEW_COUNT_LOG => format!("~{} reviews", ((value * 10.0).exp() - 1.0).round()),
+        FEAT_SHIPPING_FREE => "yes".to_string(),
+        FEAT_IMAGE_COUNT => format!("~{}", (value * 20.0).round()),
+        FEAT_LOAD_TIME => format!("~{:.1}s to load", (1.0 - value) * 10.0),
+        FEAT_CONTENT_FRESHNESS => format!("{:.0}%", value * 100.0),
+        FEAT_DEAL_SCORE => format!("{value:.2}"),
+        _ => format!("{value}"),
+    })
+}
+
+fn describe_flags(flags: NodeFlags) -> Vec<&'static str> {
+    let mut out = Vec::new();
+    if flags.is_rendered() {
+        out.push("rendered");
+    }
+    if flags.is_estimated() {
+        out.push("estimated");
+    }
+    if flags.is_stale() {
+        out.push("stale");
+    }
+    if flags.is_blocked() {
+        out.push("blocked");
+    }
+    if flags.is_auth_required() {
+        out.push("login required");
+    }
+    if flags.has_form() {
+        out.push("has form");
+    }
+    if flags.has_media() {
+        out.push("has media");
+    }
+    out
+}
+
+/// Path and query of a URL, shortened for listings.
+fn short_url(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, r)| r);
+    let path = rest.find('/').map_or("/", |i| &rest[i..]);
+    if path.chars().count() > 60 {
+        format!("{}…", path.chars().take(59).collect::<String>())
+    } else {
+        path.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::builder::SiteMapBuilder;
+
+    fn map() -> SiteMap {
+        let mut b = SiteMapBuilder::new("shop.com");
+        let mut feats = [0.0f32; FEATURE_DIM];
+        let home = b.add_node("https://shop.com/", PageType::Home, feats, 240);
+        feats[FEAT_PRICE] = 19.99;
+        feats[FEAT_RATING] = 0.9;
+        let a = b.add_node("https://shop.com/p/a", PageType::ProductDetail, feats, 200);
+        feats[FEAT_PRICE] = 250.0;
+        let c = b.add_node("https://shop.com/p/b", PageType::ProductDetail, feats, 200);
+        b.add_edge(home, a, EdgeType::Navigation, 1, EdgeFlags::default());
+        b.add_edge(home, c, EdgeType::Navigation, 1, EdgeFlags::default());
+        b.add_edge(a, c, EdgeType::Related, 1, EdgeFlags::default());
+        b.build()
+    }
+
+    #[test]
+    fn test_parse_find_human_units() {
+        let q = parse_find("product price<100 rating>=4 limit=5").unwrap();
+        assert_eq!(q.page_types, Some(vec![PageType::ProductDetail]));
+        assert_eq!(q.limit, 5);
+        assert_eq!(q.feature_ranges[0].dimension, FEAT_PRICE);
+        assert_eq!(q.feature_ranges[0].max, Some(100.0));
+        assert_eq!(q.feature_ranges[1].min, Some(0.8));
+        assert!(parse_find("weight<3").is_err());
+        assert_eq!(
+            describe_feature(FEAT_RATING, 0.9).as_deref(),
+            Some("4.5 / 5")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_explorer_navigation() {
+        let mut ex = Explorer::new("shop.com", map()).unwrap();
+        assert_eq!(ex.current(), 0);
+
+        execute("ls", &mut ex).await.unwrap();
+        assert_eq!(ex.listed.len(), 2);
+        execute("go 2", &mut ex).await.unwrap();
+        let b = ex.current();
+        assert_eq!(ex.map.node_url(b), "https://shop.com/p/b");
+
+        execute("up", &mut ex).await.unwrap();
+        assert_eq!(ex.current(), 0, "shallowest inbound page");
+        execute("go /p/a", &mut ex).await.unwrap();
+        execute("back", &mut ex).await.unwrap();
+        execute("back", &mut ex).await.unwrap();
+        assert_eq!(ex.current(), b);
+
+        execute("find product price<100", &mut ex).await.unwrap();
+        assert_eq!(ex.listed.len(), 1);
+        assert!(execute("go #99", &mut ex).await.is_err());
+        assert!(execute("done", &mut ex).await.unwrap());
+    }
+}
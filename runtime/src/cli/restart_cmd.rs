@@ -5,12 +5,12 @@ use anyhow::Result;
 
 /// Restart the Cortex daemon by stopping and starting.
 pub async fn run() -> Result<()> {
-    if !output::is_quiet() {
+    if !output::is_quiet() && !output::is_json() {
         eprintln!("  Restarting Cortex...");
     }
 
     // Stop first (ignoring errors if not running)
-    crate::cli::stop::run().await.ok();
+    crate::cli::stop::stop_daemon().await.ok();
 
     // Brief pause to let the socket clean up
     tokio::time::sleep(std::time::Duration::from_millis(200)).await;
@@ -0,0 +1,501 @@
+//! `cortex service` — run the daemon as a background service of the user's
+//! login session, restarted by the OS if it crashes.
+//!
+//! - macOS: a launchd agent (`~/Library/LaunchAgents/com.cortex.daemon.plist`)
+//! - Linux: a systemd user unit (`~/.config/systemd/user/cortex.service`)
+//! - Windows: a Task Scheduler logon task (`Cortex`)
+//!
+//! Each runs `cortex start --log-file ~/.cortex/logs/cortex.log`, so logs
+//! are rotated by the daemon itself. The daemon exits cleanly on SIGTERM,
+//! so `cortex stop` stops the service instead of triggering a restart.
+
+use crate::cli::daemon_log;
+use crate::cli::doctor::cortex_home;
+use crate::cli::output::{self, Styled};
+use crate::cli::start::check_already_running;
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// launchd job label.
+const LAUNCHD_LABEL: &str = "com.cortex.daemon";
+
+/// systemd unit name.
+const SYSTEMD_UNIT: &str = "cortex.service";
+
+/// Task Scheduler task name.
+const TASK_NAME: &str = "Cortex";
+
+/// Seconds to wait before restarting a crashed daemon.
+const RESTART_DELAY_SECS: u32 = 5;
+
+/// The service manager Cortex registers with on this OS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Manager {
+    Launchd,
+    Systemd,
+    TaskScheduler,
+}
+
+impl Manager {
+    fn current() -> Result<Self> {
+        if cfg!(target_os = "macos") {
+            Ok(Self::Launchd)
+        } else if cfg!(target_os = "linux") {
+            Ok(Self::Systemd)
+        } else if cfg!(windows) {
+            Ok(Self::TaskScheduler)
+        } else {
+            bail!("cortex service supports macOS, Linux and Windows only")
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Launchd => "launchd",
+            Self::Systemd => "systemd",
+            Self::TaskScheduler => "Task Scheduler",
+        }
+    }
+
+    /// Where the service definition is written.
+    fn definition_path(self) -> Result<PathBuf> {
+        Ok(match self {
+            Self::Launchd => dirs::home_dir()
+                .context("no home directory")?
+                .join("Library/LaunchAgents")
+                .join(format!("{LAUNCHD_LABEL}.plist")),
+            Self::Systemd => dirs::config_dir()
+                .context("no config directory")?
+                .join("systemd/user")
+                .join(SYSTEMD_UNIT),
+            Self::TaskScheduler => cortex_home().join("cortex-task.xml"),
+        })
+    }
+
+    /// The service definition for `spec`.
+    fn render(self, spec: &ServiceSpec) -> String {
+        match self {
+            Self::Launchd => launchd_plist(spec),
+            Self::Systemd => systemd_unit(spec),
+            Self::TaskScheduler => task_xml(spec),
+        }
+    }
+
+    /// Commands that register and start the service at `path`.
+    fn install_commands(self, path: &Path) -> Vec<Vec<String>> {
+        let path = path.display().to_string();
+        match self {
+            Self::Launchd => vec![args(&["launchctl", "load", "-w", &path])],
+            Self::Systemd => vec![
+                args(&["systemctl", "--user", "daemon-reload"]),
+                args(&["systemctl", "--user", "enable", "--now", SYSTEMD_UNIT]),
+            ],
+            Self::TaskScheduler => vec![
+                args(&["schtasks", "/Create", "/TN", TASK_NAME, "/XML", &path, "/F"]),
+                args(&["schtasks", "/Run", "/TN", TASK_NAME]),
+            ],
+        }
+    }
+
+    /// Commands that stop and unregister the service at `path`.
+    fn uninstall_commands(self, path: &Path) -> Vec<Vec<String>> {
+        let path = path.display().to_string();
+        match self {
+            Self::Launchd => vec![args(&["launchctl", "unload", "-w", &path])],
+            Self::Systemd => vec![args(&[
+                "systemctl",
+                "--user",
+                "disable",
+                "--now",
+                SYSTEMD_UNIT,
+            ])],
+            Self::TaskScheduler => vec![
+                args(&["schtasks", "/End", "/TN", TASK_NAME]),
+                args(&["schtasks", "/Delete", "/TN", TASK_NAME, "/F"]),
+            ],
+        }
+    }
+
+    /// Whether the manager has the service registered and enabled.
+    fn is_registered(self) -> bool {
+        let command = match self {
+            Self::Launchd => args(&["launchctl", "list", LAUNCHD_LABEL]),
+            Self::Systemd => args(&["systemctl", "--user", "is-enabled", SYSTEMD_UNIT]),
+            Self::TaskScheduler => args(&["schtasks", "/Query", "/TN", TASK_NAME]),
+        };
+        run_tool(&command).is_ok()
+    }
+}
+
+/// The command line a service runs.
+#[derive(Debug, Clone)]
+struct ServiceSpec {
+    program: PathBuf,
+    args: Vec<String>,
+    /// `PATH` at install time, so the service finds Chromium and friends.
+    path_env: Option<String>,
+    log_file: PathBuf,
+}
+
+impl ServiceSpec {
+    fn new(http_port: Option<u16>) -> Result<Self> {
+        let program = std::env::current_exe().context("locating the cortex binary")?;
+        let log_file = daemon_log::default_path();
+        let mut args = vec![
+            "start".to_string(),
+            "--log-file".to_string(),
+            log_file.display().to_string(),
+        ];
+        if let Some(port) = http_port {
+            args.extend(["--http-port".to_string(), port.to_string()]);
+        }
+        Ok(Self {
+            program,
+            args,
+            path_env: std::env::var("PATH").ok(),
+            log_file,
+        })
+    }
+}
+
+/// Register Cortex as a service and start it.
+pub async fn run_install(http_port: Option<u16>) -> Result<()> {
+    let s = Styled::new();
+    let manager = Manager::current()?;
+    if let Some(pid) = check_already_running() {
+        bail!("Cortex is already running (PID {pid}). Stop it with 'cortex stop' first.");
+    }
+
+    let spec = ServiceSpec::new(http_port)?;
+    let path = manager.definition_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("creating {}", parent.display()))?;
+    }
+    let definition = manager.render(&spec);
+    if manager == Manager::TaskScheduler {
+        // schtasks reads task XML as UTF-16.
+        let bytes: Vec<u8> = std::iter::once(0xFEFF)
+            .chain(definition.encode_utf16())
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        std::fs::write(&path, bytes)
+    } else {
+        std::fs::write(&path, definition)
+    }
+    .with_context(|| format!("writing {}", path.display()))?;
+
+    for command in manager.install_commands(&path) {
+        run_tool(&command)?;
+    }
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({
+            "installed": true,
+            "manager": manager.name(),
+            "path": path.display().to_string(),
+            "log_file": spec.log_file.display().to_string(),
+        }));
+    } else if !output::is_quiet() {
+        eprintln!(
+            "  {} Installed Cortex as a {} service.",
+            s.ok_sym(),
+            manager.name()
+        );
+        eprintln!("  Definition: {}", path.display());
+        eprintln!("  Logs:       {}", spec.log_file.display());
+        eprintln!("  It starts at login and restarts if it crashes.");
+    }
+    Ok(())
+}
+
+/// Stop the service and remove its registration.
+pub async fn run_uninstall() -> Result<()> {
+    let s = Styled::new();
+    let manager = Manager::current()?;
+    let path = manager.definition_path()?;
+    if !path.exists() && !manager.is_registered() {
+        if output::is_json() {
+            output::print_json(&serde_json::json!({"removed": false}));
+        } else if !output::is_quiet() {
+            eprintln!("  Cortex is not installed as a service.");
+        }
+        return Ok(());
+    }
+
+    for command in manager.uninstall_commands(&path) {
+        // Stopping a service that is not running fails; removal still matters.
+        if let Err(e) = run_tool(&command) {
+            tracing::debug!("{e:#}");
+        }
+    }
+    if path.exists() {
+        std::fs::remove_file(&path).with_context(|| format!("removing {}", path.display()))?;
+    }
+    if manager == Manager::Systemd {
+        let _ = run_tool(&args(&["systemctl", "--user", "daemon-reload"]));
+    }
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({"removed": true, "manager": manager.name()}));
+    } else if !output::is_quiet() {
+        eprintln!(
+            "  {} Removed the Cortex {} service.",
+            s.ok_sym(),
+            manager.name()
+        );
+    }
+    Ok(())
+}
+
+/// Show whether the service is installed and the daemon is running.
+pub async fn run_status() -> Result<()> {
+    let s = Styled::new();
+    let manager = Manager::current()?;
+    let path = manager.definition_path()?;
+    let installed = path.exists();
+    let registered = manager.is_registered();
+    let pid = check_already_running();
+    let log_file = daemon_log::default_path();
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({
+            "manager": manager.name(),
+            "installed": installed,
+            "registered": registered,
+            "running": pid.is_some(),
+            "pid": pid,
+            "path": path.display().to_string(),
+            "log_file": log_file.display().to_string(),
+        }));
+        return Ok(());
+    }
+
+    let yes_no = |ok: bool, yes: &str, no: &str| {
+        if ok {
+            format!("{} {yes}", s.ok_sym())
+        } else {
+            format!("{} {no}", s.warn_sym())
+        }
+    };
+    println!("  Manager:    {}", manager.name());
+    println!(
+        "  Service:    {}",
+        yes_no(installed && registered, "installed", "not installed")
+    );
+    if installed != registered {
+        println!(
+            "              {}",
+            s.dim("definition and registration disagree; run 'cortex service install' again")
+        );
+    }
+    match pid {
+        Some(pid) => println!("  Daemon:     {} (PID {pid})", yes_no(true, "running", "")),
+        None => println!("  Daemon:     {}", yes_no(false, "", "not running")),
+    }
+    println!("  Definition: {}", path.display());
+    println!("  Logs:       {}", log_file.display());
+    Ok(())
+}
+
+fn args(parts: &[&str]) -> Vec<String> {
+    parts.iter().map(|p| p.to_string()).collect()
+}
+
+/// Run a service manager command, failing with its stderr.
+fn run_tool(command: &[String]) -> Result<String> {
+    let (program, rest) = command.split_first().context("empty command")?;
+    let output = Command::new(program)
+        .args(rest)
+        .output()
+        .with_context(|| format!("running {program}"))?;
+    if !output.status.success() {
+        bail!(
+            "'{}' failed: {}",
+            command.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn launchd_plist(spec: &ServiceSpec) -> String {
+    let mut arguments = format!(
+        "\n    <string>{}</string>",
+        xml_escape(&spec.program.display().to_string())
+    );
+    for arg in &spec.args {
+        arguments.push_str(&format!("\n    <string>{}</string>", xml_escape(arg)));
+    }
+    let environment = spec
+        .path_env
+        .as_deref()
+        .map(|path| {
+            format!(
+                "\n  <key>EnvironmentVariables</key>\n  <dict>\n    <key>PATH</key>\n    <string>{}</string>\n  </dict>",
+                xml_escape(path)
+            )
+        })
+        .unwrap_or_default();
+    // Panics and the startup banner still go to stderr.
+    let stderr = spec.log_file.with_file_name("cortex.stderr.log");
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+  <key>Label</key>
+  <string>{LAUNCHD_LABEL}</string>
+  <key>ProgramArguments</key>
+  <array>{arguments}
+  </array>{environment}
+  <key>RunAtLoad</key>
+  <true/>
+  <key>KeepAlive</key>
+  <dict>
+    <key>SuccessfulExit</key>
+    <false/>
+  </dict>
+  <key>ThrottleInterval</key>
+  <integer>{RESTART_DELAY_SECS}</integer>
+  <key>StandardErrorPath</key>
+  <string>{}</string>
+</dict>
+</plist>
+"#,
+        xml_escape(&stderr.display().to_string())
+    )
+}
+
+fn systemd_unit(spec: &ServiceSpec) -> String {
+    let mut exec = systemd_quote(&spec.program.display().to_string());
+    for arg in &spec.args {
+        exec.push(' ');
+        exec.push_str(&systemd_quote(arg));
+    }
+    let environment = spec
+        .path_env
+        .as_deref()
+        .map(|path| format!("Environment={}\n", systemd_quote(&format!("PATH={path}"))))
+        .unwrap_or_default();
+    format!(
+        "[Unit]
+Description=Cortex web cartography daemon
+After=network-online.target
+
+[Service]
+ExecStart={exec}
+{environment}Restart=on-failure
+RestartSec={RESTART_DELAY_SECS}
+
+[Install]
+WantedBy=default.target
+"
+    )
+}
+
+fn task_xml(spec: &ServiceSpec) -> String {
+    let arguments = spec
+        .args
+        .iter()
+        .map(|arg| format!("\"{}\"", arg.replace('"', "\\\"")))
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-16"?>
+<Task version="1.2" xmlns="http://schemas.microsoft.com/windows/2004/02/mit/task">
+  <RegistrationInfo>
+    <Description>Cortex web cartography daemon</Description>
+  </RegistrationInfo>
+  <Triggers>
+    <LogonTrigger>
+      <Enabled>true</Enabled>
+    </LogonTrigger>
+  </Triggers>
+  <Settings>
+    <MultipleInstancesPolicy>IgnoreNew</MultipleInstancesPolicy>
+    <DisallowStartIfOnBatteries>false</DisallowStartIfOnBatteries>
+    <StopIfGoingOnBatteries>false</StopIfGoingOnBatteries>
+    <ExecutionTimeLimit>PT0S</ExecutionTimeLimit>
+    <RestartOnFailure>
+      <Interval>PT1M</Interval>
+      <Count>999</Count>
+    </RestartOnFailure>
+  </Settings>
+  <Actions>
+    <Exec>
+      <Command>{}</Command>
+      <Arguments>{}</Arguments>
+    </Exec>
+  </Actions>
+</Task>
+"#,
+        xml_escape(&spec.program.display().to_string()),
+        xml_escape(&arguments)
+    )
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Quote a word for a systemd unit line, escaping specifiers and variables.
+fn systemd_quote(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+        .replace('$', "$$");
+    format!("\"{escaped}\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec() -> ServiceSpec {
+        ServiceSpec {
+            program: PathBuf::from("/opt/cortex & co/bin/cortex"),
+            args: args(&["start", "--log-file", "/home/a/.cortex/logs/cortex.log"]),
+            path_env: Some("/usr/bin:/opt/50%".to_string()),
+            log_file: PathBuf::from("/home/a/.cortex/logs/cortex.log"),
+        }
+    }
+
+    #[test]
+    fn test_systemd_unit_restarts_on_failure() {
+        let unit = systemd_unit(&spec());
+        assert!(unit.contains(
+            r#"ExecStart="/opt/cortex & co/bin/cortex" "start" "--log-file" "/home/a/.cortex/logs/cortex.log""#
+        ));
+        assert!(unit.contains(r#"Environment="PATH=/usr/bin:/opt/50%%""#));
+        assert!(unit.contains("Restart=on-failure\nRestartSec=5\n"));
+        assert!(unit.contains("WantedBy=default.target"));
+    }
+
+    #[test]
+    fn test_launchd_plist_keeps_alive_after_crash() {
+        let plist = launchd_plist(&spec());
+        assert!(plist.contains(
+            "<string>/opt/cortex &amp; co/bin/cortex</string>\n    <string>start</string>"
+        ));
+        assert!(plist.contains("<key>SuccessfulExit</key>\n    <false/>"));
+        assert!(plist.contains("<string>/home/a/.cortex/logs/cortex.stderr.log</string>"));
+        assert!(plist.contains("<string>/usr/bin:/opt/50%</string>"));
+    }
+
+    #[test]
+    fn test_task_xml_restarts_on_failure() {
+        let xml = task_xml(&spec());
+        assert!(xml.contains("<Command>/opt/cortex &amp; co/bin/cortex</Command>"));
+        assert!(xml.contains(
+            "<Arguments>&quot;start&quot; &quot;--log-file&quot; &quot;/home/a/.cortex/logs/cortex.log&quot;</Arguments>"
+        ));
+        assert!(xml.contains("<RestartOnFailure>"));
+        assert!(xml.contains("<LogonTrigger>"));
+    }
+}
@@ -1,13 +1,24 @@
 //! Start the Cortex daemon process.
 
+use crate::audit::logger::AuditLogger;
 use crate::cartography::mapper::Mapper;
+use crate::cartography::semantic::SemanticEncoder;
+use crate::cartography::vision_classifier::VisionClassifier;
+use crate::cli::daemon_log::RotatingLog;
 use crate::cli::output::{self, Styled};
+use crate::collective::workers::{self, WorkerPool};
+use crate::events::EventLog;
 use crate::extraction::loader::ExtractionLoader;
 use crate::maintenance;
+use crate::navigation::cost_model::ActionCosts;
 use crate::renderer::chromium::ChromiumRenderer;
 use crate::renderer::{NoopRenderer, Renderer};
 use crate::server::Server;
-use anyhow::{Context, Result};
+use crate::temporal::scheduler::CrawlScheduler;
+use crate::temporal::visual;
+use crate::trust::api_keys::{self, KeyStore};
+use anyhow::{bail, Context, Result};
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tracing::{error, info, warn};
@@ -47,24 +58,75 @@ pub fn check_already_running() -> Option<i32> {
     None
 }
 
-/// Start the Cortex daemon with optional REST API.
-pub async fn run_with_http(http_port: Option<u16>) -> Result<()> {
-    run_inner(http_port).await
+/// Where the daemon accepts mapping workers, and the pool they join.
+type Coordinator = (SocketAddr, Arc<WorkerPool>);
+
+/// Start the Cortex daemon with an optional REST API and, with
+/// `coordinator`, accept mapping workers that present one of `worker_tokens`.
+/// With `log_file`, logs go to that size-rotated file instead of stderr.
+pub async fn run_with(
+    http_port: Option<u16>,
+    coordinator: Option<&str>,
+    mut worker_tokens: Vec<String>,
+    log_file: Option<PathBuf>,
+) -> Result<()> {
+    let coordinator = match coordinator {
+        Some(addr) => {
+            if let Ok(token) = std::env::var("CORTEX_WORKER_TOKEN") {
+                worker_tokens.push(token);
+            }
+            if worker_tokens.is_empty() {
+                bail!("--coordinator needs a --worker-token (or $CORTEX_WORKER_TOKEN) for workers to join with");
+            }
+            let addr: SocketAddr = addr
+                .parse()
+                .with_context(|| format!("invalid coordinator address {addr}"))?;
+            Some((addr, Arc::new(WorkerPool::new(worker_tokens))))
+        }
+        None => None,
+    };
+    run_inner(http_port, coordinator, log_file).await
 }
 
 /// Start the Cortex daemon: bind socket, write PID, serve requests.
 pub async fn run() -> Result<()> {
-    run_inner(None).await
+    run_inner(None, None, None).await
+}
+
+/// A mapper that shares work with the coordinator's workers, if any.
+fn new_mapper(
+    renderer: &Arc<dyn Renderer>,
+    extractor_loader: Arc<ExtractionLoader>,
+    coordinator: &Option<Coordinator>,
+    semantic: &Option<Arc<SemanticEncoder>>,
+) -> Arc<Mapper> {
+    let mut mapper = Mapper::new(Arc::clone(renderer), extractor_loader);
+    if let Some(vision) = VisionClassifier::load_default() {
+        mapper = mapper.with_vision(Arc::new(vision));
+    }
+    if let Some(encoder) = semantic {
+        mapper = mapper.with_semantic(Arc::clone(encoder));
+    }
+    Arc::new(match coordinator {
+        Some((_, pool)) => mapper.with_workers(Arc::clone(pool)),
+        None => mapper,
+    })
 }
 
-async fn run_inner(http_port: Option<u16>) -> Result<()> {
+async fn run_inner(
+    http_port: Option<u16>,
+    coordinator: Option<Coordinator>,
+    log_file: Option<PathBuf>,
+) -> Result<()> {
     let s = Styled::new();
 
+    let human = !output::is_quiet() && !output::is_json();
+
     // Check if already running
     if let Some(pid) = check_already_running() {
-        eprintln!("  {} Cortex is already running (PID {pid}).", s.warn_sym());
-        eprintln!("  Use 'cortex restart' or 'cortex stop' first.");
-        std::process::exit(1);
+        bail!(
+            "Cortex is already running (PID {pid}). Use 'cortex restart' or 'cortex stop' first."
+        );
     }
 
     // Clean up stale socket file
@@ -80,12 +142,16 @@ async fn run_inner(http_port: Option<u16>) -> Result<()> {
     }
 
     // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::from_default_env()
-                .add_directive("cortex=info".parse().unwrap()),
-        )
-        .init();
+    let filter = tracing_subscriber::EnvFilter::from_default_env()
+        .add_directive("cortex=info".parse().unwrap());
+    match &log_file {
+        Some(path) => tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_ansi(false)
+            .with_writer(std::sync::Mutex::new(RotatingLog::open(path)?))
+            .init(),
+        None => tracing_subscriber::fmt().with_env_filter(filter).init(),
+    }
 
     info!("starting Cortex v{}", env!("CARGO_PKG_VERSION"));
 
@@ -93,7 +159,16 @@ async fn run_inner(http_port: Option<u16>) -> Result<()> {
     std::fs::write(&pid_path, std::process::id().to_string())
         .context("failed to write PID file")?;
 
-    if !output::is_quiet() {
+    if output::is_json() {
+        output::print_json(&serde_json::json!({
+            "running": true,
+            "pid": std::process::id(),
+            "version": env!("CARGO_PKG_VERSION"),
+            "socket": SOCKET_PATH,
+            "http_port": http_port,
+            "coordinator": coordinator.as_ref().map(|(addr, _)| addr.to_string()),
+        }));
+    } else if human {
         eprintln!(
             "  {} Cortex v{} started (PID {})",
             s.ok_sym(),
@@ -103,6 +178,9 @@ async fn run_inner(http_port: Option<u16>) -> Result<()> {
         eprintln!("  Listening on {SOCKET_PATH}");
     }
 
+    // Embed page text for semantic QUERY, if a sentence model is installed
+    let semantic = SemanticEncoder::load_default().map(Arc::new);
+
     // Initialize browser renderer
     let server = match ChromiumRenderer::new().await {
         Ok(renderer) => {
@@ -126,7 +204,7 @@ async fn run_inner(http_port: Option<u16>) -> Result<()> {
             };
 
             // Create mapper
-            let mapper = Arc::new(Mapper::new(Arc::clone(&renderer), extractor_loader));
+            let mapper = new_mapper(&renderer, extractor_loader, &coordinator, &semantic);
 
             Server::new(&socket_path).with_mapper(renderer, mapper)
         }
@@ -138,22 +216,114 @@ async fn run_inner(http_port: Option<u16>) -> Result<()> {
                 ExtractionLoader::new()
                     .unwrap_or_else(|_| panic!("ExtractionLoader must initialize")),
             );
-            let mapper = Arc::new(Mapper::new(Arc::clone(&renderer), extractor_loader));
+            let mapper = new_mapper(&renderer, extractor_loader, &coordinator, &semantic);
             Server::new(&socket_path).with_mapper(renderer, mapper)
         }
     };
 
+    let server = match semantic {
+        Some(encoder) => server.with_semantic(encoder),
+        None => server,
+    };
+
+    // Persist state-changing events for `cortex events` and SUBSCRIBE replay
+    let server = match EventLog::open_default() {
+        Ok(log) => server.with_event_log(log),
+        Err(e) => {
+            warn!("Failed to open event log: {e}");
+            server
+        }
+    };
+
+    // Enforce per-domain crawl calendars for automated crawls
+    let server = match CrawlScheduler::load_default() {
+        Ok(scheduler) => server.with_scheduler(scheduler),
+        Err(e) => {
+            warn!("Failed to load crawl calendars: {e}");
+            server
+        }
+    };
+
+    // Record state-changing requests for compliance review
+    let server = match AuditLogger::default_logger() {
+        Ok(log) => server.with_audit_log(log),
+        Err(e) => {
+            warn!("Failed to open audit log: {e}");
+            server
+        }
+    };
+
+    // Require API keys on the socket once any exist (`cortex keys add`)
+    let server = server.with_api_keys(KeyStore::open(api_keys::default_path()));
+
+    // Learn action latency and failure rates for PATHFIND weights
+    let server = match ActionCosts::load_default() {
+        Ok(costs) => server.with_action_costs(costs),
+        Err(e) => {
+            warn!("Failed to load action costs: {e}");
+            server
+        }
+    };
+
+    // Connect agents installed since the last `cortex plug`
+    match crate::cli::plug::auto_update() {
+        Ok(changes) => {
+            for change in changes {
+                info!("plug: {change}");
+                if human {
+                    eprintln!("  {} Plug: {change}", s.info_sym());
+                }
+            }
+        }
+        Err(e) => warn!("Failed to update agent configs: {e}"),
+    }
+
     let shutdown = server.shutdown_handle();
     let _maintenance_task = maintenance::spawn(shutdown.clone());
+    // Screenshot pages under visual watch rules
+    let _visual_watch_task = cfg!(feature = "vision").then(|| visual::spawn(server.shared_state()));
 
-    // Set up SIGTERM/SIGINT handling
+    // Set up SIGTERM/SIGINT handling. Stopping cleanly on SIGTERM lets a
+    // service manager tell `cortex stop` apart from a crash.
     let shutdown_signal = shutdown.clone();
     tokio::spawn(async move {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+            match signal(SignalKind::terminate()) {
+                Ok(mut term) => {
+                    tokio::select! {
+                        _ = tokio::signal::ctrl_c() => {}
+                        _ = term.recv() => {}
+                    }
+                }
+                Err(_) => {
+                    let _ = tokio::signal::ctrl_c().await;
+                }
+            }
+        }
+        #[cfg(not(unix))]
         let _ = tokio::signal::ctrl_c().await;
         info!("received shutdown signal");
         shutdown_signal.notify_one();
     });
 
+    // Re-read configuration on SIGHUP, as `cortex reload` does
+    #[cfg(unix)]
+    {
+        let state = server.shared_state();
+        tokio::spawn(async move {
+            use tokio::signal::unix::{signal, SignalKind};
+            let Ok(mut hangup) = signal(SignalKind::hangup()) else {
+                return;
+            };
+            while hangup.recv().await.is_some() {
+                info!("received SIGHUP");
+                crate::server::reload(&state).await;
+            }
+        });
+    }
+
     // Optionally start REST API
     if let Some(port) = http_port {
         let rest_state = server.shared_state();
@@ -162,11 +332,23 @@ async fn run_inner(http_port: Option<u16>) -> Result<()> {
                 error!("REST API error: {e}");
             }
         });
-        if !output::is_quiet() {
+        if human {
             eprintln!("  REST API listening on http://127.0.0.1:{port}");
         }
     }
 
+    // Optionally hand page fetches and renders to remote workers
+    if let Some((addr, pool)) = coordinator {
+        tokio::spawn(async move {
+            if let Err(e) = workers::serve(addr, pool).await {
+                error!("worker coordinator error: {e}");
+            }
+        });
+        if human {
+            eprintln!("  Accepting mapping workers on http://{addr}");
+        }
+    }
+
     // Run server
     let result = server.start().await;
 
@@ -174,7 +356,7 @@ async fn run_inner(http_port: Option<u16>) -> Result<()> {
     let _ = std::fs::remove_file(&pid_path);
     let _ = std::fs::remove_file(&socket_path);
 
-    if !output::is_quiet() {
+    if human {
         eprintln!("  {} Cortex stopped.", s.ok_sym());
     }
 
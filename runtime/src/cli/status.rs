@@ -1,8 +1,9 @@
 //! Show status of the running Cortex daemon.
 
 use crate::cli::doctor::cortex_home;
-use crate::cli::output::{self, Styled};
+use crate::cli::output::{self, PoolStatus, StatusResult, Styled};
 use crate::cli::start::SOCKET_PATH;
+use crate::trust::api_keys;
 use anyhow::{bail, Context, Result};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::UnixStream;
@@ -15,13 +16,18 @@ pub async fn run() -> Result<()> {
         Ok(s) => s,
         Err(_) => {
             if output::is_json() {
-                output::print_json(&serde_json::json!({
-                    "running": false,
-                    "error": "not running"
-                }));
-                return Ok(());
+                output::print_result(&StatusResult {
+                    running: false,
+                    version: None,
+                    pid: None,
+                    uptime_s: 0,
+                    maps_cached: 0,
+                    pool: None,
+                    cache: None,
+                });
+            } else if !output::is_quiet() {
+                eprintln!("  Cortex is not running. Start with 'cortex start'.");
             }
-            eprintln!("  Cortex is not running. Start with 'cortex start'.");
             std::process::exit(1);
         }
     };
@@ -30,7 +36,8 @@ pub async fn run() -> Result<()> {
     let mut reader = BufReader::new(reader);
 
     // Send status request
-    let req = r#"{"id":"status","method":"status","params":{}}"#;
+    let mut req = serde_json::json!({"id": "status", "method": "status", "params": {}});
+    api_keys::attach_client_key(&mut req);
     writer
         .write_all(format!("{req}\n").as_bytes())
         .await
@@ -47,11 +54,6 @@ pub async fn run() -> Result<()> {
     let resp: serde_json::Value =
         serde_json::from_str(line.trim()).context("invalid status response")?;
 
-    if output::is_json() {
-        output::print_json(&resp);
-        return Ok(());
-    }
-
     if let Some(result) = resp.get("result") {
         let version = result
             .get("version")
@@ -67,6 +69,25 @@ pub async fn run() -> Result<()> {
         let pid = std::fs::read_to_string(crate::cli::start::pid_file_path())
             .ok()
             .and_then(|s| s.trim().parse::<i32>().ok());
+
+        if output::is_json() {
+            output::print_result(&StatusResult {
+                running: true,
+                version: Some(version.to_string()),
+                pid,
+                uptime_s: uptime,
+                maps_cached: maps,
+                pool: result
+                    .get("pool")
+                    .and_then(|p| serde_json::from_value::<PoolStatus>(p.clone()).ok()),
+                cache: result.get("cache").filter(|c| c.is_object()).cloned(),
+            });
+            return Ok(());
+        }
+        if output::is_quiet() {
+            return Ok(());
+        }
+
         let pid_str = pid.map(|p| format!("PID {p}, ")).unwrap_or_default();
 
         eprintln!();
@@ -127,6 +148,29 @@ pub async fn run() -> Result<()> {
             }
         }
 
+        if let Some(cache) = result.get("cache").filter(|c| c.is_object()) {
+            let hits = cache.get("hits").and_then(|v| v.as_u64()).unwrap_or(0);
+            let misses = cache.get("misses").and_then(|v| v.as_u64()).unwrap_or(0);
+            let rate = cache
+                .get("hit_rate")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(0.0);
+            let bytes = cache.get("bytes").and_then(|v| v.as_u64()).unwrap_or(0);
+            let max_bytes = cache.get("max_bytes").and_then(|v| v.as_u64()).unwrap_or(0);
+            let evictions = cache.get("evictions").and_then(|v| v.as_u64()).unwrap_or(0);
+            eprintln!(
+                "    {:<20} {:.1}% ({hits} hits, {misses} misses)",
+                "Hit rate:",
+                rate * 100.0
+            );
+            eprintln!(
+                "    {:<20} {} / {}, {evictions} evicted",
+                "Budget:",
+                output::format_size(bytes),
+                output::format_size(max_bytes)
+            );
+        }
+
         eprintln!();
 
         // Audit log
@@ -5,17 +5,57 @@ use crate::cli::start::{pid_file_path, SOCKET_PATH};
 use anyhow::{Context, Result};
 use std::time::Duration;
 
+/// How a stop request ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopOutcome {
+    NotRunning,
+    /// The PID file named a dead process; it was removed.
+    StalePid(i32),
+    /// SIGTERM failed because the process had already exited.
+    AlreadyExited(i32),
+    Stopped(i32),
+    /// The process did not exit within 5 seconds.
+    TimedOut(i32),
+}
+
+impl StopOutcome {
+    fn as_json(self) -> serde_json::Value {
+        let (outcome, pid) = match self {
+            Self::NotRunning => ("not_running", None),
+            Self::StalePid(pid) => ("stale_pid", Some(pid)),
+            Self::AlreadyExited(pid) => ("already_exited", Some(pid)),
+            Self::Stopped(pid) => ("stopped", Some(pid)),
+            Self::TimedOut(pid) => ("timed_out", Some(pid)),
+        };
+        serde_json::json!({
+            "outcome": outcome,
+            "pid": pid,
+            "running": matches!(self, Self::TimedOut(_)),
+        })
+    }
+}
+
 /// Stop the Cortex daemon by reading PID file and sending SIGTERM.
 pub async fn run() -> Result<()> {
+    let outcome = stop_daemon().await?;
+    if output::is_json() {
+        output::print_json(&outcome.as_json());
+    }
+    // Exit 0 even when not running — per spec
+    Ok(())
+}
+
+/// Stop the daemon, printing progress unless `--quiet` or `--json`.
+pub async fn stop_daemon() -> Result<StopOutcome> {
     let s = Styled::new();
+    let human = !output::is_quiet() && !output::is_json();
     let pid_path = pid_file_path();
 
     if !pid_path.exists() {
-        if !output::is_quiet() {
+        if human {
             eprintln!("  Cortex is not running.");
         }
-        // Exit 0, not error — per spec
-        return Ok(());
+        return Ok(StopOutcome::NotRunning);
     }
 
     let pid_str = std::fs::read_to_string(&pid_path).context("failed to read PID file")?;
@@ -34,14 +74,14 @@ pub async fn run() -> Result<()> {
             // Stale PID file — clean up
             let _ = std::fs::remove_file(&pid_path);
             let _ = std::fs::remove_file(SOCKET_PATH);
-            if !output::is_quiet() {
+            if human {
                 eprintln!("  Cleaned up stale PID file (process {pid} was not running).");
             }
-            return Ok(());
+            return Ok(StopOutcome::StalePid(pid));
         }
     }
 
-    if !output::is_quiet() {
+    if human {
         eprint!("  Stopping Cortex (PID {pid})...");
     }
 
@@ -54,11 +94,11 @@ pub async fn run() -> Result<()> {
             .context("failed to send SIGTERM")?;
         if !output.status.success() {
             let _ = std::fs::remove_file(&pid_path);
-            if !output::is_quiet() {
+            if human {
                 eprintln!(" {}", s.warn_sym());
                 eprintln!("  Process may have already exited. Cleaned up PID file.");
             }
-            return Ok(());
+            return Ok(StopOutcome::AlreadyExited(pid));
         }
     }
 
@@ -75,11 +115,11 @@ pub async fn run() -> Result<()> {
                     // Process has exited
                     let _ = std::fs::remove_file(&pid_path);
                     let _ = std::fs::remove_file(SOCKET_PATH);
-                    if !output::is_quiet() {
+                    if human {
                         eprintln!(" {}", s.ok_sym());
                         eprintln!("  Cortex stopped.");
                     }
-                    return Ok(());
+                    return Ok(StopOutcome::Stopped(pid));
                 }
                 _ => {}
             }
@@ -88,10 +128,10 @@ pub async fn run() -> Result<()> {
 
     // Timed out
     let _ = std::fs::remove_file(&pid_path);
-    if !output::is_quiet() {
+    if human {
         eprintln!(" {}", s.warn_sym());
         eprintln!("  Cortex may still be running. PID file removed.");
         eprintln!("  If the problem persists, try: kill -9 {pid}");
     }
-    Ok(())
+    Ok(StopOutcome::TimedOut(pid))
 }
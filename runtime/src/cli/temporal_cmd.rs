@@ -1,47 +1,44 @@
-//! CLI handlers for temporal commands (history, patterns).
+//! CLI handlers for temporal commands (history, patterns, chart, digest).
 
 use crate::cli::output;
 use crate::collective::registry::LocalRegistry;
+use crate::events::{self, ReplayFilter};
+use crate::temporal::chart;
+use crate::temporal::digest::{Digest, DigestOptions, DomainDigest};
 use crate::temporal::patterns;
 use crate::temporal::store::TemporalStore;
-use anyhow::Result;
+use crate::temporal::watch::{field_to_feature_dim, NotifyTarget};
+use anyhow::{Context, Result};
 use chrono::{DateTime, NaiveDateTime, Utc};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-fn registry_dir() -> PathBuf {
-    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-    home.join(".cortex").join("registry")
+fn dim_name_to_num(name: &str) -> Result<u8> {
+    field_to_feature_dim(name)
+        .map(|dim| dim as u8)
+        .with_context(|| format!("unknown feature: {name}"))
 }
 
-fn dim_name_to_num(name: &str) -> u8 {
-    match name {
-        "price" => 48,
-        "original_price" => 49,
-        "discount" => 50,
-        "availability" => 51,
-        "rating" => 52,
-        "review_count" => 53,
-        _ => name.parse().unwrap_or(0),
+fn parse_since(since: &str) -> Result<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(since) {
+        Ok(dt.with_timezone(&Utc))
+    } else if let Ok(date) = chrono::NaiveDate::parse_from_str(since, "%Y-%m-%d") {
+        Ok(date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+    } else {
+        anyhow::bail!(
+            "invalid date format: {since}. Use ISO 8601 (e.g., 2025-01-01 or 2025-01-01T00:00:00Z)"
+        );
     }
 }
 
 /// Run the history command.
 pub async fn run_history(domain: &str, url: &str, dim: &str, since: &str) -> Result<()> {
-    let registry = Arc::new(LocalRegistry::new(registry_dir())?);
+    let registry = Arc::new(LocalRegistry::open_default()?);
     let store = TemporalStore::new(registry);
 
-    let since_dt: DateTime<Utc> = if let Ok(dt) = DateTime::parse_from_rfc3339(since) {
-        dt.with_timezone(&Utc)
-    } else if let Ok(date) = chrono::NaiveDate::parse_from_str(since, "%Y-%m-%d") {
-        date.and_hms_opt(0, 0, 0).unwrap().and_utc()
-    } else {
-        anyhow::bail!(
-            "invalid date format: {since}. Use ISO 8601 (e.g., 2025-01-01 or 2025-01-01T00:00:00Z)"
-        );
-    };
+    let since_dt = parse_since(since)?;
 
-    let dim_num = dim_name_to_num(dim);
+    let dim_num = dim_name_to_num(dim)?;
     let points = store.history(domain, url, dim_num, since_dt)?;
 
     if output::is_json() {
@@ -64,10 +61,10 @@ pub async fn run_history(domain: &str, url: &str, dim: &str, since: &str) -> Res
 
 /// Run the patterns command.
 pub async fn run_patterns(domain: &str, url: &str, dim: &str) -> Result<()> {
-    let registry = Arc::new(LocalRegistry::new(registry_dir())?);
+    let registry = Arc::new(LocalRegistry::open_default()?);
     let store = TemporalStore::new(registry);
 
-    let dim_num = dim_name_to_num(dim);
+    let dim_num = dim_name_to_num(dim)?;
     let since = Utc::now() - chrono::Duration::days(365);
     let points = store.history(domain, url, dim_num, since)?;
 
@@ -142,3 +139,193 @@ pub async fn run_patterns(domain: &str, url: &str, dim: &str) -> Result<()> {
 
     Ok(())
 }
+
+/// Run the chart command: print a sparkline and optionally write a PNG chart.
+pub async fn run_chart(
+    domain: &str,
+    url: &str,
+    feature: &str,
+    since: Option<&str>,
+    width: usize,
+    png: Option<&Path>,
+) -> Result<()> {
+    let registry = Arc::new(LocalRegistry::open_default()?);
+    let store = TemporalStore::new(registry);
+
+    let since_dt = match since {
+        Some(s) => parse_since(s)?,
+        None => Utc::now() - chrono::Duration::days(90),
+    };
+    let points = store.history(domain, url, dim_name_to_num(feature)?, since_dt)?;
+
+    if let Some(path) = png {
+        if !points.is_empty() {
+            chart::render_png(&points, 800, 300, path)?;
+        }
+    }
+
+    let values: Vec<f32> = points.iter().map(|(_, v)| *v).collect();
+    let spark = chart::sparkline(&values, width);
+    let stats = chart::SeriesStats::of(&points);
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({
+            "domain": domain,
+            "url": url,
+            "feature": feature,
+            "points": points.len(),
+            "sparkline": spark,
+            "min": stats.map(|s| s.min),
+            "max": stats.map(|s| s.max),
+            "first": stats.map(|s| s.first),
+            "last": stats.map(|s| s.last),
+            "png": png.filter(|_| !points.is_empty()).map(|p| p.display().to_string()),
+        }));
+        return Ok(());
+    }
+
+    let Some(stats) = stats else {
+        if !output::is_quiet() {
+            println!("  No history data found.");
+        }
+        return Ok(());
+    };
+
+    let first_ts = points[0].0;
+    let last_ts = points[points.len() - 1].0;
+    println!("  {feature} for {domain} {url}\n");
+    println!("    {spark}\n");
+    println!(
+        "    {} → {}  ({} points)",
+        first_ts.format("%Y-%m-%d"),
+        last_ts.format("%Y-%m-%d"),
+        points.len()
+    );
+    print!(
+        "    min {:.2}  max {:.2}  first {:.2}  last {:.2}",
+        stats.min, stats.max, stats.first, stats.last
+    );
+    match stats.change_pct() {
+        Some(pct) => println!("  ({pct:+.1}%)"),
+        None => println!(),
+    }
+    if let Some(path) = png {
+        println!("\n  Chart written to {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Run the digest command: summarize what changed on each domain since
+/// `since`, print or write the report, and POST it to webhooks.
+pub async fn run_digest(
+    domains: &[String],
+    since: &str,
+    min_change: f32,
+    limit: usize,
+    out: Option<&Path>,
+    webhooks: &[String],
+    notify: bool,
+) -> Result<()> {
+    let registry = LocalRegistry::open_default()?;
+    let since_dt = crate::cli::events_cmd::parse_since(since)?;
+    let until = Utc::now();
+    let options = DigestOptions {
+        min_change: min_change / 100.0,
+        max_items: limit,
+    };
+
+    let domains: Vec<String> = if domains.is_empty() {
+        registry.list().iter().map(|e| e.domain.clone()).collect()
+    } else {
+        domains.to_vec()
+    };
+    let log_path = events::default_log_path();
+    let events = if log_path.exists() {
+        events::replay(
+            &log_path,
+            &ReplayFilter {
+                since: Some(since_dt),
+                ..Default::default()
+            },
+        )?
+    } else {
+        Vec::new()
+    };
+
+    let mut digests = Vec::new();
+    for domain in &domains {
+        // Compare against the map at the start of the window, or the first
+        // one pushed during it
+        let before = match registry.checkout(domain, since_dt)? {
+            Some(found) => Some(found),
+            None => match registry.entry(domain).and_then(|e| e.versions.first()) {
+                Some(first) => registry.checkout(domain, first.timestamp)?,
+                None => None,
+            },
+        };
+        let after = registry.pull(domain)?;
+        if before.is_none() && after.is_none() && !output::is_quiet() {
+            eprintln!("  No maps of {domain} in the registry.");
+        }
+        digests.push(DomainDigest::build(
+            domain,
+            before.as_ref().map(|(m, at)| (m, *at)),
+            after.as_ref().map(|(m, at)| (m, *at)),
+            &events,
+            &options,
+        ));
+    }
+    let digest = Digest {
+        since: since_dt,
+        until,
+        domains: digests,
+    };
+    let markdown = digest.to_markdown();
+
+    let mut targets: Vec<String> = webhooks.to_vec();
+    if notify {
+        for rule in registry.load_watch_rules()? {
+            if let NotifyTarget::Webhook(url) = rule.notify {
+                if domains.contains(&rule.domain) && !targets.contains(&url) {
+                    targets.push(url);
+                }
+            }
+        }
+    }
+    if !targets.is_empty() {
+        // `text` renders in chat webhooks; `digest` is for everything else
+        let payload = serde_json::json!({"text": markdown, "digest": digest});
+        let client = crate::acquisition::http_client::shared_client();
+        for url in &targets {
+            let sent = client
+                .post(url)
+                .json(&payload)
+                .timeout(std::time::Duration::from_secs(10))
+                .send()
+                .await
+                .and_then(|r| r.error_for_status());
+            match sent {
+                Ok(_) if !output::is_quiet() => eprintln!("  Digest sent to {url}"),
+                Ok(_) => {}
+                Err(e) => eprintln!("  Failed to send digest to {url}: {e}"),
+            }
+        }
+    }
+
+    let rendered = if output::is_json() {
+        serde_json::to_string_pretty(&digest)?
+    } else {
+        markdown
+    };
+    match out {
+        Some(path) => {
+            std::fs::write(path, &rendered)?;
+            if !output::is_quiet() {
+                eprintln!("  Digest written to {}", path.display());
+            }
+        }
+        None => println!("{}", rendered.trim_end()),
+    }
+    Ok(())
+}
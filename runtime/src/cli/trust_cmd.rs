@@ -0,0 +1,94 @@
+//! CLI handlers for `cortex trust` — signing key and trusted publishers.
+
+use crate::audit::logger::{self as audit, AuditEvent};
+use crate::cli::output;
+use crate::trust::signing::{self, MapSigner, TrustedKeys};
+use anyhow::Result;
+
+/// Show the local signing identity, creating it on first use.
+pub async fn run_key() -> Result<()> {
+    let signer = MapSigner::load_or_create_default()?;
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({
+            "name": signer.name(),
+            "public_key": signer.public_key(),
+            "path": signing::default_key_path().display().to_string(),
+        }));
+    } else {
+        println!("  Signing identity: {}", signer.name());
+        println!("  Public key:       {}", signer.public_key());
+        println!(
+            "\n  Share the public key so others can run:\n    cortex trust add {} {}",
+            signer.name(),
+            signer.public_key()
+        );
+    }
+
+    Ok(())
+}
+
+/// Trust a publisher's public key.
+pub async fn run_add(name: &str, public_key: &str) -> Result<()> {
+    let path = signing::default_trusted_path();
+    let mut trusted = TrustedKeys::load(&path)?;
+    trusted.add(name, public_key)?;
+    trusted.save(&path)?;
+    record_change("trust_add", name);
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({"added": name}));
+    } else if !output::is_quiet() {
+        println!("  Trusted {name}.");
+    }
+
+    Ok(())
+}
+
+/// Stop trusting a publisher.
+pub async fn run_remove(name: &str) -> Result<()> {
+    let path = signing::default_trusted_path();
+    let mut trusted = TrustedKeys::load(&path)?;
+    let removed = trusted.remove(name);
+    trusted.save(&path)?;
+    if removed {
+        record_change("trust_remove", name);
+    }
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({"removed": removed}));
+    } else if !output::is_quiet() {
+        if removed {
+            println!("  Removed {name}.");
+        } else {
+            println!("  {name} was not trusted.");
+        }
+    }
+
+    Ok(())
+}
+
+/// List trusted publishers.
+pub async fn run_list() -> Result<()> {
+    let trusted = TrustedKeys::load_default()?;
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({"keys": trusted.keys}));
+    } else if trusted.keys.is_empty() {
+        println!("  No trusted keys. Any validly signed map is accepted.");
+    } else {
+        println!("  Trusted publishers:\n");
+        for key in &trusted.keys {
+            println!("    {:<20}  {}", key.name, key.public_key);
+        }
+    }
+
+    Ok(())
+}
+
+/// Audit a change to the trusted publisher list.
+fn record_change(method: &str, name: &str) {
+    let mut event = AuditEvent::new(method, &audit::local_actor());
+    event.params = serde_json::json!({ "name": name });
+    audit::record(&event);
+}
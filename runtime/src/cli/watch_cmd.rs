@@ -0,0 +1,395 @@
+//! `cortex watch` — manage watch rules and follow the alerts they fire.
+//!
+//! Rules are stored in the local registry. The daemon evaluates them each
+//! time a re-map changes the domain and logs a `WatchFired` event per alert.
+//! Visual rules are checked on their own schedule, from screenshots.
+
+use crate::audit::logger::{self as audit, AuditEvent};
+use crate::cli::events_cmd::parse_age;
+use crate::cli::output::{self, Styled};
+use crate::collective::registry::LocalRegistry;
+use crate::events::{self, CortexEvent, LoggedEvent, ReplayFilter};
+use crate::intelligence::cache::MapCache;
+use crate::temporal::visual;
+use crate::temporal::watch::{
+    feature_dim_name, field_to_feature_dim, NotifyTarget, Region, VisualCondition, WatchCondition,
+    WatchManager, WatchRule,
+};
+use crate::wql::parser::{ComparisonOp, WhereExpr, WqlValue};
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Build a condition from the `watch add` flags. Exactly one must be given.
+pub fn condition(
+    below: Option<f32>,
+    above: Option<f32>,
+    change_pct: Option<f32>,
+    available: bool,
+    new_pages: bool,
+    when: Option<&str>,
+    visual: Option<VisualCondition>,
+) -> Result<WatchCondition> {
+    let mut conditions = Vec::new();
+    if let Some(t) = below {
+        conditions.push(WatchCondition::ValueBelow(t));
+    }
+    if let Some(t) = above {
+        conditions.push(WatchCondition::ValueAbove(t));
+    }
+    if let Some(pct) = change_pct {
+        conditions.push(WatchCondition::ChangeByPercent(pct / 100.0));
+    }
+    if available {
+        conditions.push(WatchCondition::Available);
+    }
+    if new_pages {
+        conditions.push(WatchCondition::NewInstance);
+    }
+    if let Some(dsl) = when {
+        conditions.push(WatchCondition::parse(dsl)?);
+    }
+    if let Some(visual) = visual {
+        conditions.push(WatchCondition::Visual(visual));
+    }
+    if conditions.len() != 1 {
+        bail!("give exactly one of --below, --above, --change, --available, --new-pages, --when, --visual");
+    }
+    Ok(conditions.remove(0))
+}
+
+/// Build a visual condition from the `watch add` flags, if `--visual` is set.
+/// `pixels` is a percentage and `every` an interval such as `30m` or `6h`.
+pub fn visual_condition(
+    visual: bool,
+    pixels: Option<f32>,
+    regions: &[String],
+    every: Option<&str>,
+) -> Result<Option<VisualCondition>> {
+    if !visual {
+        if pixels.is_some() || !regions.is_empty() || every.is_some() {
+            bail!("--pixels, --region and --every apply to --visual watches");
+        }
+        return Ok(None);
+    }
+    let threshold = match pixels {
+        Some(pct) if (0.0..=100.0).contains(&pct) => pct / 100.0,
+        Some(pct) => bail!("--pixels must be a percentage between 0 and 100, not {pct}"),
+        None => visual::DEFAULT_THRESHOLD,
+    };
+    let interval_secs = match every {
+        Some(every) => parse_age(every)
+            .and_then(|d| u64::try_from(d.num_seconds()).ok())
+            .filter(|secs| *secs > 0)
+            .with_context(|| format!("invalid interval '{every}' (e.g. 30m, 6h, 1d)"))?,
+        None => visual::DEFAULT_INTERVAL_SECS,
+    };
+    Ok(Some(VisualCondition {
+        threshold,
+        regions: regions
+            .iter()
+            .map(|r| Region::parse(r))
+            .collect::<Result<_>>()?,
+        interval_secs,
+    }))
+}
+
+/// Add a watch rule on `domain`, optionally limited to one node of its cached map.
+pub async fn run_add(
+    domain: &str,
+    node: Option<u32>,
+    feature: &str,
+    condition: WatchCondition,
+    id: Option<String>,
+    webhook: Option<String>,
+) -> Result<()> {
+    let feature_dim = match &condition {
+        WatchCondition::Available => field_to_feature_dim("availability"),
+        _ => field_to_feature_dim(feature),
+    }
+    .with_context(|| format!("unknown feature '{feature}'"))?;
+
+    let node_url = match node {
+        Some(node) => {
+            let map = MapCache::default_cache()?
+                .open_mapped(domain)?
+                .with_context(|| {
+                    format!("no cached map for '{domain}'; run `cortex map {domain}` first")
+                })?;
+            if node as usize >= map.node_count() {
+                bail!(
+                    "node {node} out of range ({domain} has {} nodes)",
+                    map.node_count()
+                );
+            }
+            Some(map.node_url(node).to_string())
+        }
+        None => None,
+    };
+    if let WatchCondition::Visual(_) = &condition {
+        if node_url.is_none() {
+            bail!("a visual watch screenshots one page; give it with --node");
+        }
+        if !cfg!(feature = "vision") {
+            bail!("visual watches need a build with the `vision` feature (cargo build --features vision)");
+        }
+    }
+
+    let registry = LocalRegistry::open_default()?;
+    let mut watches = WatchManager::load(&registry)?;
+    let id = match id {
+        Some(id) => id,
+        None => next_id(&watches),
+    };
+    let rule = WatchRule {
+        id: id.clone(),
+        domain: domain.to_string(),
+        model_type: None,
+        node_url,
+        feature_dim: feature_dim as u8,
+        condition,
+        notify: webhook.map_or(NotifyTarget::EventBus, NotifyTarget::Webhook),
+        active: true,
+        created_at: Utc::now(),
+        last_triggered: None,
+    };
+    let summary = describe(&rule);
+    watches.add_rule(rule.clone());
+    watches.save(&registry)?;
+    let mut event = AuditEvent::new("watch_add", &audit::local_actor());
+    event.domain = Some(domain.to_string());
+    event.url = rule.node_url.clone();
+    event.params = serde_json::to_value(&rule).unwrap_or_default();
+    audit::record(&event);
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({ "added": rule }));
+    } else if !output::is_quiet() {
+        let s = Styled::new();
+        eprintln!(
+            "  {} Watch {id} added: {summary} on {}.",
+            s.ok_sym(),
+            rule.node_url.as_deref().unwrap_or(domain)
+        );
+        match &rule.condition {
+            WatchCondition::Visual(visual) => eprintln!(
+                "    The daemon screenshots it every {}. Follow alerts with `cortex watch tail`.",
+                format_interval(visual.interval_secs)
+            ),
+            _ => eprintln!("    Alerts fire when the daemon re-maps {domain}. Follow them with `cortex watch tail`."),
+        }
+    }
+    Ok(())
+}
+
+/// First unused id of the form `w<n>`.
+fn next_id(watches: &WatchManager) -> String {
+    let n = watches
+        .list_rules()
+        .iter()
+        .filter_map(|r| r.id.strip_prefix('w')?.parse::<u32>().ok())
+        .max()
+        .unwrap_or(0);
+    format!("w{}", n + 1)
+}
+
+/// List watch rules, optionally for one domain.
+pub async fn run_list(domain: Option<&str>) -> Result<()> {
+    let watches = WatchManager::load(&LocalRegistry::open_default()?)?;
+    let mut rules: Vec<&WatchRule> = watches
+        .list_rules()
+        .into_iter()
+        .filter(|r| domain.is_none_or(|d| r.domain == d))
+        .collect();
+    rules.sort_by(|a, b| a.id.cmp(&b.id));
+
+    if output::is_json() {
+        output::print_json(&serde_json::json!({ "watches": rules }));
+        return Ok(());
+    }
+    if output::is_quiet() {
+        return Ok(());
+    }
+    if rules.is_empty() {
+        eprintln!("  No watches. Add one with `cortex watch add <domain>`.");
+        return Ok(());
+    }
+    let s = Styled::new();
+    for rule in rules {
+        let last = rule
+            .last_triggered
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "never".to_string());
+        eprintln!(
+            "  {:<6} {:<20} {}",
+            s.bold(&rule.id),
+            rule.domain,
+            describe(rule)
+        );
+        eprintln!(
+            "         {} {}",
+            rule.node_url.as_deref().unwrap_or("all pages"),
+            s.dim(&format!("(last fired: {last})"))
+        );
+    }
+    Ok(())
+}
+
+/// Remove a watch rule.
+pub async fn run_remove(id: &str) -> Result<()> {
+    let registry = LocalRegistry::open_default()?;
+    let mut watches = WatchManager::load(&registry)?;
+    if !watches.remove_rule(id) {
+        bail!("no watch with id {id}");
+    }
+    watches.save(&registry)?;
+    // Drop the screenshots of a visual rule with it.
+    let _ = std::fs::remove_file(visual::capture_path(&visual::default_dir(), id));
+    let mut event = AuditEvent::new("watch_remove", &audit::local_actor());
+    event.params = serde_json::json!({ "id": id });
+    audit::record(&event);
+    if output::is_json() {
+        output::print_json(&serde_json::json!({ "removed": id }));
+    } else if !output::is_quiet() {
+        let s = Styled::new();
+        eprintln!("  {} Watch {id} removed.", s.ok_sym());
+    }
+    Ok(())
+}
+
+/// Show the last `lines` fired alerts, then stream new ones as they fire.
+pub async fn run_tail(
+    lines: usize,
+    follow: bool,
+    domain: Option<&str>,
+    rule: Option<&str>,
+) -> Result<()> {
+    let path = events::default_log_path();
+    let filter = ReplayFilter {
+        domain: domain.map(String::from),
+        ..Default::default()
+    };
+    let is_alert = |entry: &LoggedEvent| match &entry.event {
+        CortexEvent::WatchFired { rule_id, .. } => rule.is_none_or(|r| r == rule_id),
+        _ => false,
+    };
+
+    let mut log = events::LogFollower::new(&path);
+    let entries = log.poll(&filter)?;
+    let alerts: Vec<&LoggedEvent> = entries.iter().filter(|e| is_alert(e)).collect();
+    if alerts.is_empty() && !follow && !output::is_quiet() && !output::is_json() {
+        println!("  No alerts fired yet.");
+    }
+    for entry in &alerts[alerts.len().saturating_sub(lines)..] {
+        print_alert(entry);
+    }
+
+    if !follow {
+        return Ok(());
+    }
+    loop {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        for entry in log.poll(&filter)?.iter().filter(|e| is_alert(e)) {
+            print_alert(entry);
+        }
+    }
+}
+
+/// Print one fired alert (JSONL in --json mode).
+fn print_alert(entry: &LoggedEvent) {
+    if output::is_json() {
+        if let Ok(json) = serde_json::to_string(entry) {
+            println!("{json}");
+        }
+        return;
+    }
+    if let CortexEvent::WatchFired {
+        domain,
+        rule_id,
+        message,
+        ..
+    } = &entry.event
+    {
+        println!(
+            "  {}  {:<6} {:<20} {message}",
+            entry.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            rule_id,
+            domain
+        );
+    }
+}
+
+/// One-line description of a rule's condition.
+fn describe(rule: &WatchRule) -> String {
+    let feature = feature_dim_name(rule.feature_dim as usize)
+        .map(String::from)
+        .unwrap_or_else(|| format!("feature {}", rule.feature_dim));
+    match &rule.condition {
+        WatchCondition::ValueBelow(t) => format!("{feature} below {t}"),
+        WatchCondition::ValueAbove(t) => format!("{feature} above {t}"),
+        WatchCondition::ChangeByPercent(p) => {
+            format!("{feature} changes by more than {}%", p * 100.0)
+        }
+        WatchCondition::Available => "becomes available".to_string(),
+        WatchCondition::NewInstance => "new pages".to_string(),
+        WatchCondition::Compound(expr) => {
+            let mut text = describe_expr(&expr.condition);
+            if expr.consecutive > 1 {
+                text.push_str(&format!(" FOR {} checks", expr.consecutive));
+            }
+            if expr.hysteresis > 0.0 {
+                text.push_str(&format!(" HYSTERESIS {}", expr.hysteresis));
+            }
+            let cooldown = expr.cooldown_secs;
+            if cooldown > 0 {
+                text.push_str(&format!(" COOLDOWN {}", format_interval(cooldown)));
+            }
+            text
+        }
+        WatchCondition::Visual(visual) => {
+            let mut text = format!("visual change over {}% of pixels", visual.threshold * 100.0);
+            if !visual.regions.is_empty() {
+                let regions: Vec<String> = visual.regions.iter().map(|r| r.to_string()).collect();
+                text.push_str(&format!(" or in {}", regions.join(" ")));
+            }
+            text.push_str(&format!(
+                ", every {}",
+                format_interval(visual.interval_secs)
+            ));
+            text
+        }
+    }
+}
+
+fn format_interval(secs: u64) -> String {
+    match secs {
+        s if s % 3600 == 0 => format!("{}h", s / 3600),
+        s if s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{s}s"),
+    }
+}
+
+fn describe_expr(expr: &WhereExpr) -> String {
+    match expr {
+        WhereExpr::Comparison { field, op, value } => {
+            let op = match op {
+                ComparisonOp::Eq => "=",
+                ComparisonOp::Neq => "!=",
+                ComparisonOp::Lt => "<",
+                ComparisonOp::Lte => "<=",
+                ComparisonOp::Gt => ">",
+                ComparisonOp::Gte => ">=",
+            };
+            let value = match value {
+                WqlValue::Float(f) => f.to_string(),
+                WqlValue::Integer(i) => i.to_string(),
+                WqlValue::Bool(b) => b.to_string(),
+                WqlValue::String(s) => s.clone(),
+            };
+            format!("{field} {op} {value}")
+        }
+        WhereExpr::And(a, b) => format!("{} AND {}", describe_expr(a), describe_expr(b)),
+        WhereExpr::Or(a, b) => format!("({} OR {})", describe_expr(a), describe_expr(b)),
+    }
+}
@@ -0,0 +1,73 @@
+//! `cortex worker` — fetch and render pages for a mapping coordinator.
+
+use crate::cartography::mapper::Mapper;
+use crate::cartography::vision_classifier::VisionClassifier;
+use crate::cli::output::{self, Styled};
+use crate::collective::workers::WorkerClient;
+use crate::extraction::loader::ExtractionLoader;
+use crate::renderer::chromium::ChromiumRenderer;
+use crate::renderer::{NoopRenderer, Renderer};
+use anyhow::{Context, Result};
+use std::sync::Arc;
+use tracing::warn;
+
+/// Join the coordinator at `join` and serve its jobs until interrupted.
+pub async fn run(
+    join: &str,
+    token: Option<&str>,
+    name: Option<&str>,
+    no_browser: bool,
+) -> Result<()> {
+    let s = Styled::new();
+    let token = token
+        .map(str::to_string)
+        .or_else(|| std::env::var("CORTEX_WORKER_TOKEN").ok())
+        .context("a worker needs the coordinator's --token (or $CORTEX_WORKER_TOKEN)")?;
+    let name = name
+        .map(str::to_string)
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .unwrap_or_else(|| "worker".to_string());
+
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::from_default_env()
+                .add_directive("cortex=info".parse().unwrap()),
+        )
+        .init();
+
+    let renderer: Option<Arc<dyn Renderer>> = if no_browser {
+        None
+    } else {
+        match ChromiumRenderer::new().await {
+            Ok(renderer) => Some(Arc::new(renderer)),
+            Err(e) => {
+                warn!("Failed to initialize Chromium: {e}; taking fetch jobs only");
+                None
+            }
+        }
+    };
+    let can_render = renderer.is_some();
+    let renderer = renderer.unwrap_or_else(|| Arc::new(NoopRenderer));
+    let mut mapper = Mapper::new(renderer, Arc::new(ExtractionLoader::new()?));
+    if let Some(vision) = can_render.then(VisionClassifier::load_default).flatten() {
+        mapper = mapper.with_vision(Arc::new(vision));
+    }
+
+    let client = WorkerClient::new(join, &token, &name, can_render);
+    if !output::is_quiet() {
+        let jobs = if can_render {
+            "fetch + render"
+        } else {
+            "fetch only"
+        };
+        eprintln!(
+            "  {} Worker {name} joining {join} ({jobs}, Ctrl-C to stop)",
+            s.info_sym()
+        );
+    }
+
+    tokio::select! {
+        result = client.run(&mapper) => result,
+        _ = tokio::signal::ctrl_c() => Ok(()),
+    }
+}
@@ -1,43 +1,79 @@
 //! CLI handler for `cortex wql "<query>"`.
 
 use crate::cli::output::{self, Styled};
+use crate::cli::predicate::{self, Predicate};
+use crate::collective::registry::LocalRegistry;
 use crate::intelligence::cache::MapCache;
+use crate::project::Projects;
+use crate::wql::executor::{Row, Value};
 use crate::wql::{executor, parser, planner};
 use anyhow::Result;
-use std::time::Instant;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
-/// Run a WQL query.
-pub async fn run(query_str: &str) -> Result<()> {
-    let s = Styled::new();
+/// Run a WQL query, failing with [`predicate::PredicateFailed`] when a
+/// `fail_when` predicate holds for the result rows. A query without ACROSS
+/// searches `project` (or the active project) if there is one. A query
+/// with AS OF runs against the maps checked out of the registry.
+pub async fn run(query_str: &str, project: Option<&str>, fail_when: &[Predicate]) -> Result<()> {
     let start = Instant::now();
 
     // Parse the WQL query
     let query = parser::parse(query_str)?;
 
-    // Create the execution plan
-    let plan = planner::plan(&query, None)?;
+    // Create the execution plan, searching the project's domains by default
+    let projects = Projects::load_default()?;
+    let project = projects.resolve(project)?;
+    let search_path = project.map(|p| p.domains.as_slice()).unwrap_or_default();
+    let plan = planner::plan_in(&query, search_path)?;
 
-    // Load all cached maps
-    let mut cache = MapCache::default_cache()?;
-    let maps = cache.load_all_maps()?;
+    // Load all cached maps, or their versions at the AS OF time
+    let maps = match plan.as_of {
+        Some(at) => executor::maps_as_of(&LocalRegistry::open_default()?, &plan, at)?,
+        None => MapCache::default_cache()?.load_all_maps()?,
+    };
 
     // Execute
     let rows = executor::execute(&plan, &maps)?;
     let elapsed = start.elapsed();
 
+    print_rows(query_str, project.map(|p| p.name.as_str()), &rows, elapsed);
+    predicate::check(fail_when, rows.len(), |field| {
+        Some(
+            rows.iter()
+                .filter_map(|r| match r.fields.get(field)? {
+                    Value::Float(v) => Some(*v),
+                    Value::Integer(v) => Some(*v as f64),
+                    _ => None,
+                })
+                .collect(),
+        )
+    })
+}
+
+/// Print result rows as a table, or as JSON.
+fn print_rows(query_str: &str, project: Option<&str>, rows: &[Row], elapsed: Duration) {
+    let s = Styled::new();
     if output::is_json() {
         output::print_json(&serde_json::json!({
             "query": query_str,
-            "results": rows.len(),
+            "project": project,
+            "total": rows.len(),
             "rows": rows,
             "duration_us": elapsed.as_micros() as u64,
         }));
-    } else if rows.is_empty() {
+        return;
+    }
+    if output::is_quiet() {
+        return;
+    }
+
+    if rows.is_empty() {
         eprintln!("  No results.");
     } else {
         // Gather all field names
         let mut all_fields: Vec<String> = Vec::new();
-        for row in &rows {
+        for row in rows {
             for key in row.fields.keys() {
                 if !all_fields.contains(key) {
                     all_fields.push(key.clone());
@@ -48,7 +84,7 @@ pub async fn run(query_str: &str) -> Result<()> {
 
         // Compute column widths for clean alignment
         let mut widths: Vec<usize> = all_fields.iter().map(|f| f.len()).collect();
-        for row in &rows {
+        for row in rows {
             for (i, field) in all_fields.iter().enumerate() {
                 let val_len = row
                     .fields
@@ -62,7 +98,7 @@ pub async fn run(query_str: &str) -> Result<()> {
         }
 
         // Print clean rows — no header, no separators
-        for row in &rows {
+        for row in rows {
             let mut parts: Vec<String> = Vec::new();
             for (i, field) in all_fields.iter().enumerate() {
                 let val = row
@@ -93,12 +129,13 @@ pub async fn run(query_str: &str) -> Result<()> {
         } else {
             format!("{:.1}s", elapsed.as_secs_f64())
         };
+        let scope = project
+            .map(|name| format!(" \u{00b7} project {name}"))
+            .unwrap_or_default();
         eprintln!(
-            "  {} rows \u{00b7} {}",
+            "  {} rows \u{00b7} {}{scope}",
             s.blue(&format!("{}", rows.len())),
             s.yellow(&time_str),
         );
     }
-
-    Ok(())
 }
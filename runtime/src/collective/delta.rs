@@ -5,6 +5,7 @@
 
 use crate::compiler::models::ModelField;
 use crate::map::types::*;
+use crate::trust::lineage::{NodeLineage, TRACED_FIELDS};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -195,6 +196,286 @@ pub fn apply_delta(map: &mut SiteMap, delta: &MapDelta) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Which side of a merge a value was taken from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeSide {
+    Ours,
+    Theirs,
+}
+
+/// A disagreement between two concurrent versions of the same node.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MergeConflict {
+    /// Both sides carry a different non-zero value for a feature dimension.
+    Feature {
+        url: String,
+        dimension: u8,
+        ours: f32,
+        theirs: f32,
+        kept: MergeSide,
+    },
+    /// Both sides classified the page differently.
+    PageType {
+        url: String,
+        ours: PageType,
+        theirs: PageType,
+        kept: MergeSide,
+    },
+}
+
+/// What happened while merging two versions of a map.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConflictReport {
+    /// Domain of the merged map.
+    pub domain: String,
+    /// Nodes present on both sides.
+    pub nodes_shared: usize,
+    /// Nodes only present in our version.
+    pub nodes_ours_only: usize,
+    /// Nodes only present in their version.
+    pub nodes_theirs_only: usize,
+    /// Duplicate edges dropped during the union.
+    pub edges_deduped: usize,
+    /// Values both sides changed differently, with the side that was kept.
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl ConflictReport {
+    /// Whether the merge needed no conflict resolution.
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// Merge two concurrently updated versions of the same site's map.
+///
+/// Nodes are unioned by URL. For a node present on both sides the fresher
+/// record wins (then the more confident one, then the more recently mapped
+/// map, then ours); dimensions one side left at zero are filled from the
+/// other, and every dimension both sides set differently is reported as a
+/// conflict. Edges and actions are unioned and deduplicated.
+pub fn merge_maps(ours: &SiteMap, theirs: &SiteMap) -> (SiteMap, ConflictReport) {
+    use std::collections::{HashMap, HashSet};
+
+    let mut report = ConflictReport {
+        domain: ours.header.domain.clone(),
+        ..Default::default()
+    };
+    let mut builder = crate::map::builder::SiteMapBuilder::new(&ours.header.domain);
+    let mut records: Vec<NodeRecord> = Vec::new();
+
+    // Ours keeps its node order; theirs-only nodes are appended.
+    let ours_index: HashMap<&str, usize> = ours
+        .urls
+        .iter()
+        .enumerate()
+        .map(|(i, u)| (u.as_str(), i))
+        .collect();
+    let theirs_index: HashMap<&str, usize> = theirs
+        .urls
+        .iter()
+        .enumerate()
+        .map(|(i, u)| (u.as_str(), i))
+        .collect();
+    let theirs_newer = theirs.header.mapped_at > ours.header.mapped_at;
+
+    for (i, url) in ours.urls.iter().enumerate() {
+        let (record, features, lineage) = match theirs_index.get(url.as_str()) {
+            Some(&j) => {
+                report.nodes_shared += 1;
+                merge_node(url, (ours, i), (theirs, j), theirs_newer, &mut report)
+            }
+            None => {
+                report.nodes_ours_only += 1;
+                let lineage = ours.lineage.node(i as u32).cloned().unwrap_or_default();
+                (ours.nodes[i].clone(), ours.features[i], lineage)
+            }
+        };
+        let idx = builder.add_node(url, record.page_type, features, record.confidence);
+        for (field, value) in lineage.fields {
+            builder.record_lineage(idx, &field, value);
+        }
+        records.push(record);
+    }
+    let mut theirs_to_merged: Vec<u32> = Vec::with_capacity(theirs.urls.len());
+    for (j, url) in theirs.urls.iter().enumerate() {
+        match ours_index.get(url.as_str()) {
+            Some(&i) => theirs_to_merged.push(i as u32),
+            None => {
+                report.nodes_theirs_only += 1;
+                let record = theirs.nodes[j].clone();
+                let idx =
+                    builder.add_node(url, record.page_type, theirs.features[j], record.confidence);
+                if let Some(lineage) = theirs.lineage.node(j as u32) {
+                    for (field, value) in &lineage.fields {
+                        builder.record_lineage(idx, field, value.clone());
+                    }
+                }
+                records.push(record);
+                theirs_to_merged.push(idx);
+            }
+        }
+    }
+
+    let ours_to_merged: Vec<u32> = (0..ours.urls.len() as u32).collect();
+    let mut seen_edges: HashSet<(u32, u32, EdgeType)> = HashSet::new();
+    let mut seen_actions: HashSet<(u32, u16, i32)> = HashSet::new();
+    for (map, remap) in [(ours, &ours_to_merged), (theirs, &theirs_to_merged)] {
+        for (src, &from) in remap.iter().enumerate() {
+            for edge in csr_slice(&map.edges, &map.edge_index, src) {
+                let Some(&to) = remap.get(edge.target_node as usize) else {
+                    continue;
+                };
+                if seen_edges.insert((from, to, edge.edge_type)) {
+                    builder.add_edge(from, to, edge.edge_type, edge.weight, edge.flags);
+                } else {
+                    report.edges_deduped += 1;
+                }
+            }
+            for action in csr_slice(&map.actions, &map.action_index, src) {
+                let target = match usize::try_from(action.target_node) {
+                    Ok(t) => remap.get(t).map_or(-2, |&m| m as i32),
+                    Err(_) => action.target_node,
+                };
+                if !seen_actions.insert((from, action.opcode.as_u16(), target)) {
+                    continue;
+                }
+                if action.http_executable {
+                    builder.add_action_http(
+                        from,
+                        action.opcode,
+                        target,
+                        action.cost_hint,
+                        action.risk,
+                    );
+                } else {
+                    builder.add_action(from, action.opcode, target, action.cost_hint, action.risk);
+                }
+            }
+        }
+    }
+    builder.set_has_sitemap(ours.header.has_sitemap() || theirs.header.has_sitemap());
+
+    let mut merged = builder.build();
+    for (node, record) in merged.nodes.iter_mut().zip(records) {
+        // Keep the counts and norm the builder derived from the merged graph.
+        *node = NodeRecord {
+            inbound_count: node.inbound_count,
+            outbound_count: node.outbound_count,
+            feature_norm: node.feature_norm,
+            ..record
+        };
+    }
+    merged.header.mapped_at = ours.header.mapped_at.max(theirs.header.mapped_at);
+
+    (merged, report)
+}
+
+/// Merge one node present in both maps, recording conflicts. A feature
+/// is missing on a side when it is zero and its lineage does not trace it
+/// to a source; a traced zero, such as "out of stock", is a value like any
+/// other. The merged node keeps the lineage of each value it kept.
+fn merge_node(
+    url: &str,
+    (ours, i): (&SiteMap, usize),
+    (theirs, j): (&SiteMap, usize),
+    theirs_newer: bool,
+    report: &mut ConflictReport,
+) -> (NodeRecord, [f32; FEATURE_DIM], NodeLineage) {
+    let (a, b) = (&ours.nodes[i], &theirs.nodes[j]);
+    let kept = match (b.freshness, b.confidence).cmp(&(a.freshness, a.confidence)) {
+        std::cmp::Ordering::Greater => MergeSide::Theirs,
+        std::cmp::Ordering::Equal if theirs_newer => MergeSide::Theirs,
+        _ => MergeSide::Ours,
+    };
+
+    let (la, lb) = (
+        ours.lineage.node(i as u32).cloned().unwrap_or_default(),
+        theirs.lineage.node(j as u32).cloned().unwrap_or_default(),
+    );
+    let mut lineage = if kept == MergeSide::Theirs {
+        lb.clone()
+    } else {
+        la.clone()
+    };
+    let (fa, fb) = (&ours.features[i], &theirs.features[j]);
+    let mut features = [0.0f32; FEATURE_DIM];
+    for dim in 0..FEATURE_DIM {
+        let (o, t) = (fa[dim], fb[dim]);
+        let ours_has = o != 0.0 || la.has_dimension(dim);
+        let theirs_has = t != 0.0 || lb.has_dimension(dim);
+        let side = if !theirs_has {
+            MergeSide::Ours
+        } else if !ours_has {
+            MergeSide::Theirs
+        } else if (o - t).abs() <= 0.001 {
+            kept
+        } else {
+            report.conflicts.push(MergeConflict::Feature {
+                url: url.to_string(),
+                dimension: dim as u8,
+                ours: o,
+                theirs: t,
+                kept,
+            });
+            kept
+        };
+        features[dim] = if side == MergeSide::Theirs { t } else { o };
+        if side != kept {
+            if let Some(&(field, _)) = TRACED_FIELDS.iter().find(|&&(_, d)| d == dim) {
+                let from = if side == MergeSide::Theirs { &lb } else { &la };
+                take_lineage(&mut lineage, from, field);
+            }
+        }
+    }
+
+    let mut record = if kept == MergeSide::Theirs {
+        b.clone()
+    } else {
+        a.clone()
+    };
+    record.flags.0 = a.flags.0 | b.flags.0;
+    if a.page_type != b.page_type {
+        let typed_by = if a.page_type == PageType::Unknown {
+            Some((b.page_type, &lb))
+        } else if b.page_type == PageType::Unknown {
+            Some((a.page_type, &la))
+        } else {
+            None
+        };
+        if let Some((page_type, from)) = typed_by {
+            record.page_type = page_type;
+            take_lineage(&mut lineage, from, "page_type");
+        } else {
+            report.conflicts.push(MergeConflict::PageType {
+                url: url.to_string(),
+                ours: a.page_type,
+                theirs: b.page_type,
+                kept,
+            });
+        }
+    }
+
+    (record, features, lineage)
+}
+
+/// Make `lineage`'s `field` the one `from` has, if any.
+fn take_lineage(lineage: &mut NodeLineage, from: &NodeLineage, field: &str) {
+    match from.fields.get(field) {
+        Some(value) => lineage.fields.insert(field.to_string(), value.clone()),
+        None => lineage.fields.remove(field),
+    };
+}
+
+/// Entries of a CSR-indexed array belonging to `node`.
+fn csr_slice<'a, T>(items: &'a [T], index: &[u32], node: usize) -> &'a [T] {
+    let start = index.get(node).map_or(items.len(), |&s| s as usize);
+    let end = index.get(node + 1).map_or(items.len(), |&e| e as usize);
+    items.get(start..end.max(start)).unwrap_or(&[])
+}
+
 /// Compute a content hash of a SiteMap for delta base verification.
 pub fn hash_map(map: &SiteMap) -> [u8; 32] {
     use std::hash::{Hash, Hasher};
@@ -558,4 +839,141 @@ mod tests {
         let h2 = hash_map(&map2);
         assert_ne!(h1, h2, "different maps should have different hashes");
     }
+
+    fn merge_fixture(price: f32, freshness: u8, extra: Option<&str>) -> SiteMap {
+        let mut builder = SiteMapBuilder::new("shop.com");
+        let feats = [0.0f32; FEATURE_DIM];
+        let home = builder.add_node("https://shop.com/", PageType::Home, feats, 200);
+        let mut pfeats = feats;
+        pfeats[FEAT_PRICE] = price;
+        let p = builder.add_node("https://shop.com/p/1", PageType::ProductDetail, pfeats, 200);
+        builder.add_edge(home, p, EdgeType::Navigation, 1, EdgeFlags::default());
+        if let Some(url) = extra {
+            let n = builder.add_node(url, PageType::Article, feats, 150);
+            builder.add_edge(home, n, EdgeType::Navigation, 1, EdgeFlags::default());
+        }
+        let mut map = builder.build();
+        map.nodes[p as usize].freshness = freshness;
+        map
+    }
+
+    #[test]
+    fn test_merge_maps_unions_nodes_and_dedupes_edges() {
+        let ours = merge_fixture(100.0, 200, Some("https://shop.com/a"));
+        let theirs = merge_fixture(100.0, 200, Some("https://shop.com/b"));
+
+        let (merged, report) = merge_maps(&ours, &theirs);
+        assert_eq!(merged.urls.len(), 4);
+        assert_eq!(report.nodes_shared, 2);
+        assert_eq!(report.nodes_ours_only, 1);
+        assert_eq!(report.nodes_theirs_only, 1);
+        // home → p/1 appears on both sides and is kept once.
+        assert_eq!(merged.edges.len(), 3);
+        assert_eq!(report.edges_deduped, 1);
+        assert!(report.is_clean());
+        assert_eq!(merged.nodes[0].outbound_count, 3);
+    }
+
+    #[test]
+    fn test_merge_maps_prefers_fresher_node() {
+        let ours = merge_fixture(100.0, 100, None);
+        let theirs = merge_fixture(89.0, 250, None);
+
+        let (merged, report) = merge_maps(&ours, &theirs);
+        assert_eq!(merged.features[1][FEAT_PRICE], 89.0);
+        assert_eq!(merged.nodes[1].freshness, 250);
+        assert_eq!(report.conflicts.len(), 1);
+        match &report.conflicts[0] {
+            MergeConflict::Feature {
+                url,
+                dimension,
+                ours,
+                theirs,
+                kept,
+            } => {
+                assert_eq!(url, "https://shop.com/p/1");
+                assert_eq!(*dimension as usize, FEAT_PRICE);
+                assert_eq!((*ours, *theirs), (100.0, 89.0));
+                assert_eq!(*kept, MergeSide::Theirs);
+            }
+            other => panic!("unexpected conflict {other:?}"),
+        }
+
+        // The winner does not depend on argument order.
+        let (merged, _) = merge_maps(&theirs, &ours);
+        assert_eq!(merged.features[1][FEAT_PRICE], 89.0);
+
+        // Equal freshness: confidence decides.
+        let mut ours_confident = merge_fixture(100.0, 250, None);
+        ours_confident.nodes[1].confidence = 255;
+        let (merged, _) = merge_maps(&ours_confident, &theirs);
+        assert_eq!(merged.features[1][FEAT_PRICE], 100.0);
+    }
+
+    #[test]
+    fn test_merge_maps_fills_missing_dimensions() {
+        let ours = merge_fixture(0.0, 100, None);
+        let theirs = merge_fixture(42.0, 50, None);
+
+        let (merged, report) = merge_maps(&ours, &theirs);
+        assert_eq!(merged.features[1][FEAT_PRICE], 42.0);
+        assert!(report.is_clean());
+    }
+
+    #[test]
+    fn test_merge_maps_keeps_traced_zeros() {
+        use crate::trust::lineage::{FieldLineage, Layer};
+
+        let stock = |availability: f32, freshness: u8| {
+            let mut map = merge_fixture(100.0, freshness, None);
+            map.features[1][FEAT_AVAILABILITY] = availability;
+            map.lineage.record(
+                1,
+                "availability",
+                FieldLineage::new(
+                    Layer::StructuredData,
+                    Some("offers.availability"),
+                    1.0,
+                    1000,
+                ),
+            );
+            map
+        };
+        let out_of_stock = stock(0.0, 250);
+        let in_stock = stock(1.0, 100);
+        let traced = |map: &SiteMap| {
+            map.lineage
+                .node(1)
+                .unwrap()
+                .has_dimension(FEAT_AVAILABILITY)
+        };
+
+        // A read "out of stock" is a value, not a gap to fill.
+        let (merged, report) = merge_maps(&out_of_stock, &in_stock);
+        assert_eq!(merged.features[1][FEAT_AVAILABILITY], 0.0);
+        assert_eq!(report.conflicts.len(), 1);
+        match &report.conflicts[0] {
+            MergeConflict::Feature {
+                dimension, kept, ..
+            } => {
+                assert_eq!(*dimension as usize, FEAT_AVAILABILITY);
+                assert_eq!(*kept, MergeSide::Ours);
+            }
+            other => panic!("unexpected conflict {other:?}"),
+        }
+        assert!(traced(&merged));
+
+        // The fresher side wins either way round.
+        let (merged, report) = merge_maps(&in_stock, &out_of_stock);
+        assert_eq!(merged.features[1][FEAT_AVAILABILITY], 0.0);
+        assert_eq!(report.conflicts.len(), 1);
+
+        // An untraced zero is still missing.
+        let mut unknown = merge_fixture(100.0, 250, None);
+        unknown.features[1][FEAT_AVAILABILITY] = 0.0;
+        let (merged, report) = merge_maps(&unknown, &in_stock);
+        assert_eq!(merged.features[1][FEAT_AVAILABILITY], 1.0);
+        assert!(report.is_clean());
+        assert!(traced(&merged));
+    }
 }
@@ -0,0 +1,200 @@
+//! LAN sync — peer-to-peer map exchange without a central registry.
+//!
+//! Each participating instance serves its local registry (see
+//! [`registry_server`](super::registry_server)) and advertises it over mDNS as
+//! [`SERVICE_TYPE`]. Peers discover each other, pull maps they have not seen
+//! and merge them into their own registry (see [`merge_pulled`]). Sync is
+//! pull-only, so every instance keeps full control over what it stores.
+
+use crate::collective::registry::LocalRegistry;
+use crate::collective::sync::{has_version, merge_pulled, DomainSync, RemoteSync};
+use anyhow::{Context, Result};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// mDNS service type advertised by Cortex registries.
+pub const SERVICE_TYPE: &str = "_cortex-registry._tcp.local.";
+
+/// A Cortex registry discovered on the local network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanPeer {
+    /// Instance id the peer advertised.
+    pub instance: String,
+    /// Base URL of the peer's registry server.
+    pub endpoint: String,
+}
+
+/// Keeps this instance advertised on the LAN until dropped.
+pub struct LanAdvertiser {
+    daemon: ServiceDaemon,
+    fullname: String,
+}
+
+impl LanAdvertiser {
+    /// Advertise a registry server for `instance` listening on `port`.
+    pub fn start(instance: &str, port: u16) -> Result<Self> {
+        let daemon = ServiceDaemon::new().context("starting mDNS daemon")?;
+        let info = ServiceInfo::new(
+            SERVICE_TYPE,
+            instance,
+            &format!("{instance}.local."),
+            "",
+            port,
+            &[("id", instance)][..],
+        )?
+        .enable_addr_auto();
+        let fullname = info.get_fullname().to_string();
+        daemon.register(info).context("registering mDNS service")?;
+        Ok(Self { daemon, fullname })
+    }
+}
+
+impl Drop for LanAdvertiser {
+    fn drop(&mut self) {
+        let _ = self.daemon.unregister(&self.fullname);
+        let _ = self.daemon.shutdown();
+    }
+}
+
+/// Browse the LAN for `timeout` and return every peer other than `own_instance`.
+///
+/// Blocking; call from a blocking task.
+pub fn discover(own_instance: &str, timeout: Duration) -> Result<Vec<LanPeer>> {
+    let daemon = ServiceDaemon::new().context("starting mDNS daemon")?;
+    let events = daemon.browse(SERVICE_TYPE)?;
+    let deadline = Instant::now() + timeout;
+
+    let mut peers: Vec<LanPeer> = Vec::new();
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        let Ok(event) = events.recv_timeout(remaining) else {
+            break;
+        };
+        let ServiceEvent::ServiceResolved(info) = event else {
+            continue;
+        };
+        let instance = info
+            .get_property_val_str("id")
+            .unwrap_or(info.get_fullname())
+            .to_string();
+        if instance == own_instance || peers.iter().any(|p| p.instance == instance) {
+            continue;
+        }
+        // Prefer IPv4; IPv6 literals need brackets in URLs.
+        let mut addrs: Vec<_> = info.get_addresses().iter().copied().collect();
+        addrs.sort_by_key(|a| !a.is_ipv4());
+        if let Some(addr) = addrs.first() {
+            let endpoint = match addr {
+                std::net::IpAddr::V4(v4) => format!("http://{v4}:{}", info.get_port()),
+                std::net::IpAddr::V6(v6) => format!("http://[{v6}]:{}", info.get_port()),
+            };
+            peers.push(LanPeer { instance, endpoint });
+        }
+    }
+
+    let _ = daemon.shutdown();
+    Ok(peers)
+}
+
+/// Pull every map `peer` has that we have not seen and merge it into `registry`.
+pub async fn sync_from_peer(
+    registry: &RwLock<LocalRegistry>,
+    peer: &RemoteSync,
+) -> Result<Vec<(String, DomainSync)>> {
+    let mut results = Vec::new();
+    for entry in peer.list_available().await? {
+        if has_version(&*registry.read().await, &entry) {
+            results.push((entry.domain, DomainSync::UpToDate));
+            continue;
+        }
+        let Some(pulled) = peer.pull_signed_map(&entry.domain).await? else {
+            continue;
+        };
+        let outcome = merge_pulled(&mut *registry.write().await, &entry.domain, pulled, "lan")?;
+        results.push((entry.domain, outcome));
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collective::registry_server::{router, RegistryServerState};
+    use crate::map::builder::SiteMapBuilder;
+    use crate::map::types::{PageType, SiteMap, FEATURE_DIM};
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    fn map(urls: &[&str]) -> SiteMap {
+        let mut builder = SiteMapBuilder::new("shop.com");
+        for url in urls {
+            builder.add_node(url, PageType::ProductDetail, [0.0; FEATURE_DIM], 200);
+        }
+        builder.build()
+    }
+
+    #[tokio::test]
+    async fn test_sync_from_peer_adds_then_merges() {
+        let peer_dir = TempDir::new().unwrap();
+        let mut peer_registry = LocalRegistry::new(peer_dir.path().to_path_buf()).unwrap();
+        peer_registry
+            .push("shop.com", &map(&["https://shop.com/a"]), None)
+            .unwrap();
+        let state = Arc::new(RegistryServerState::new(peer_registry, vec!["t".into()]));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router(state)).await });
+        let peer = RemoteSync::new(&format!("http://{addr}"), "me", Some("t".into()));
+
+        let dir = TempDir::new().unwrap();
+        let local = RwLock::new(LocalRegistry::new(dir.path().to_path_buf()).unwrap());
+        let results = sync_from_peer(&local, &peer).await.unwrap();
+        assert_eq!(results, vec![("shop.com".to_string(), DomainSync::Added)]);
+
+        // Already have the peer's version.
+        let results = sync_from_peer(&local, &peer).await.unwrap();
+        assert_eq!(results[0].1, DomainSync::UpToDate);
+
+        // The peer is behind our own newer map.
+        let ours = map(&["https://shop.com/a", "https://shop.com/b"]);
+        local.write().await.push("shop.com", &ours, None).unwrap();
+        let results = sync_from_peer(&local, &peer).await.unwrap();
+        assert_eq!(results[0].1, DomainSync::UpToDate);
+
+        // Concurrent work on both sides is unioned.
+        peer.push_map(
+            "shop.com",
+            &map(&["https://shop.com/a", "https://shop.com/c"]),
+        )
+        .await
+        .unwrap();
+        let results = sync_from_peer(&local, &peer).await.unwrap();
+        assert_eq!(results[0].1, DomainSync::Merged { conflicts: 0 });
+        let (stored, _) = local.read().await.pull("shop.com").unwrap().unwrap();
+        assert_eq!(stored.urls.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_sync_from_peer_rejects_map_for_other_domain() {
+        let peer_dir = TempDir::new().unwrap();
+        let mut peer_registry = LocalRegistry::new(peer_dir.path().to_path_buf()).unwrap();
+        // shop.com's map, advertised as bank.com.
+        peer_registry
+            .push("bank.com", &map(&["https://shop.com/a"]), None)
+            .unwrap();
+        let state = Arc::new(RegistryServerState::new(peer_registry, vec!["t".into()]));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router(state)).await });
+        let peer = RemoteSync::new(&format!("http://{addr}"), "me", Some("t".into()));
+
+        let dir = TempDir::new().unwrap();
+        let local = RwLock::new(LocalRegistry::new(dir.path().to_path_buf()).unwrap());
+        let err = sync_from_peer(&local, &peer).await.unwrap_err();
+        assert!(
+            format!("{err:#}").contains("map is for shop.com"),
+            "{err:#}"
+        );
+        assert!(local.read().await.pull("bank.com").unwrap().is_none());
+    }
+}
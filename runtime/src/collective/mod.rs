@@ -4,5 +4,9 @@
 //! Optional remote sync enables sharing across Cortex instances.
 
 pub mod delta;
+pub mod lan;
+pub mod objects;
 pub mod registry;
+pub mod registry_server;
 pub mod sync;
+pub mod workers;
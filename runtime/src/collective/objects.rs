@@ -0,0 +1,276 @@
+//! Content-addressed object store for the registry.
+//!
+//! Objects are zstd-compressed blobs keyed by the SHA-256 of their
+//! uncompressed content. An object is either stored in full (a baseline) or
+//! as a patch: the content compressed with a base object as zstd dictionary,
+//! which makes consecutive snapshots of the same site very cheap to keep.
+//! Every read re-hashes the reconstructed content to detect corruption.
+
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+
+/// Object file magic.
+const MAGIC: &[u8; 4] = b"CXO1";
+const KIND_FULL: u8 = 0;
+const KIND_PATCH: u8 = 1;
+/// Header: magic + kind + raw length.
+const HEADER_LEN: usize = 4 + 1 + 8;
+/// zstd compression level for stored objects.
+const LEVEL: i32 = 3;
+/// Maximum patch chain length followed when reading an object.
+const MAX_CHAIN_DEPTH: usize = 1024;
+
+/// Hex-encoded SHA-256 content address.
+pub type ObjectId = String;
+
+/// Compute the content address of a byte slice.
+pub fn object_id(data: &[u8]) -> ObjectId {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// A content-addressed, zstd-compressed object store rooted at a directory.
+pub struct ObjectStore {
+    root: PathBuf,
+}
+
+impl ObjectStore {
+    /// Open (or create) an object store.
+    pub fn new(root: PathBuf) -> Result<Self> {
+        std::fs::create_dir_all(&root)
+            .with_context(|| format!("creating object store: {}", root.display()))?;
+        Ok(Self { root })
+    }
+
+    /// Path of an object file (`<root>/<first two hex chars>/<rest>`).
+    pub fn path(&self, id: &str) -> PathBuf {
+        let (prefix, rest) = id.split_at(2.min(id.len()));
+        self.root.join(prefix).join(rest)
+    }
+
+    /// Whether an object is present.
+    pub fn contains(&self, id: &str) -> bool {
+        self.path(id).exists()
+    }
+
+    /// Store `data` in full. Returns its id and the number of bytes written
+    /// (0 if the object already existed).
+    pub fn put(&self, data: &[u8]) -> Result<(ObjectId, u64)> {
+        let id = object_id(data);
+        if self.contains(&id) {
+            return Ok((id, 0));
+        }
+        let payload = zstd::bulk::compress(data, LEVEL)?;
+        let mut out = header(KIND_FULL, data.len());
+        out.extend_from_slice(&payload);
+        self.write(&id, &out)?;
+        Ok((id, out.len() as u64))
+    }
+
+    /// Store `data` as a patch against `base` (whose content is `base_data`).
+    pub fn put_patch(&self, data: &[u8], base: &str, base_data: &[u8]) -> Result<(ObjectId, u64)> {
+        let id = object_id(data);
+        if self.contains(&id) {
+            return Ok((id, 0));
+        }
+        let base_bytes = decode_id(base)?;
+        let payload = zstd::bulk::Compressor::with_dictionary(LEVEL, base_data)?.compress(data)?;
+        let mut out = header(KIND_PATCH, data.len());
+        out.extend_from_slice(&base_bytes);
+        out.extend_from_slice(&payload);
+        self.write(&id, &out)?;
+        Ok((id, out.len() as u64))
+    }
+
+    /// Read and verify an object, resolving patch chains.
+    pub fn get(&self, id: &str) -> Result<Vec<u8>> {
+        // Walk down to the baseline, then decode back up.
+        let mut chain: Vec<(ObjectId, Vec<u8>)> = Vec::new();
+        let mut current = id.to_string();
+        loop {
+            if chain.len() > MAX_CHAIN_DEPTH {
+                bail!("object {id}: patch chain too deep");
+            }
+            let raw = std::fs::read(self.path(&current))
+                .with_context(|| format!("reading object {current}"))?;
+            let base = patch_base(&raw).with_context(|| format!("object {current}"))?;
+            chain.push((current, raw));
+            match base {
+                Some(b) => current = b,
+                None => break,
+            }
+        }
+
+        let mut content: Option<Vec<u8>> = None;
+        for (oid, raw) in chain.into_iter().rev() {
+            let raw_len = u64::from_le_bytes(raw[5..HEADER_LEN].try_into().unwrap()) as usize;
+            let data = match &content {
+                None => zstd::bulk::decompress(&raw[HEADER_LEN..], raw_len)?,
+                Some(base) => zstd::bulk::Decompressor::with_dictionary(base)?
+                    .decompress(&raw[HEADER_LEN + 32..], raw_len)?,
+            };
+            if object_id(&data) != oid {
+                bail!("integrity check failed for object {oid}");
+            }
+            content = Some(data);
+        }
+        Ok(content.unwrap_or_default())
+    }
+
+    /// Size of an object on disk.
+    pub fn stored_size(&self, id: &str) -> u64 {
+        std::fs::metadata(self.path(id))
+            .map(|m| m.len())
+            .unwrap_or(0)
+    }
+
+    /// Base object id of a stored patch, if the object is a patch.
+    pub fn base_of(&self, id: &str) -> Result<Option<ObjectId>> {
+        let raw = std::fs::read(self.path(id)).with_context(|| format!("reading object {id}"))?;
+        patch_base(&raw)
+    }
+
+    /// All object ids currently stored.
+    pub fn list(&self) -> Result<Vec<ObjectId>> {
+        let mut ids = Vec::new();
+        for prefix in std::fs::read_dir(&self.root)? {
+            let prefix = prefix?;
+            if !prefix.file_type()?.is_dir() {
+                continue;
+            }
+            let p = prefix.file_name().to_string_lossy().to_string();
+            for obj in std::fs::read_dir(prefix.path())? {
+                let name = obj?.file_name().to_string_lossy().to_string();
+                if !name.ends_with(".tmp") {
+                    ids.push(format!("{p}{name}"));
+                }
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Delete an object. Returns true if it existed.
+    pub fn remove(&self, id: &str) -> bool {
+        std::fs::remove_file(self.path(id)).is_ok()
+    }
+
+    fn write(&self, id: &str, bytes: &[u8]) -> Result<()> {
+        let path = self.path(id);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Write-then-rename so readers never observe a partial object.
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, bytes)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+}
+
+fn header(kind: u8, raw_len: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(HEADER_LEN + 32);
+    out.extend_from_slice(MAGIC);
+    out.push(kind);
+    out.extend_from_slice(&(raw_len as u64).to_le_bytes());
+    out
+}
+
+/// Parse an object header, returning the base id for patches.
+fn patch_base(raw: &[u8]) -> Result<Option<ObjectId>> {
+    if raw.len() < HEADER_LEN || &raw[..4] != MAGIC {
+        bail!("not a registry object");
+    }
+    match raw[4] {
+        KIND_FULL => Ok(None),
+        KIND_PATCH => {
+            if raw.len() < HEADER_LEN + 32 {
+                bail!("truncated patch header");
+            }
+            Ok(Some(
+                raw[HEADER_LEN..HEADER_LEN + 32]
+                    .iter()
+                    .map(|b| format!("{b:02x}"))
+                    .collect(),
+            ))
+        }
+        k => bail!("unknown object kind {k}"),
+    }
+}
+
+fn decode_id(id: &str) -> Result<[u8; 32]> {
+    if id.len() != 64 {
+        bail!("invalid object id: {id}");
+    }
+    let mut out = [0u8; 32];
+    for (i, chunk) in out.iter_mut().enumerate() {
+        *chunk = u8::from_str_radix(&id[i * 2..i * 2 + 2], 16)
+            .with_context(|| format!("invalid object id: {id}"))?;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_put_get_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let store = ObjectStore::new(dir.path().to_path_buf()).unwrap();
+
+        let data = b"hello registry".repeat(100);
+        let (id, written) = store.put(&data).unwrap();
+        assert_eq!(id, object_id(&data));
+        assert!(written > 0 && (written as usize) < data.len());
+        assert_eq!(store.get(&id).unwrap(), data);
+
+        // Idempotent
+        let (id2, written2) = store.put(&data).unwrap();
+        assert_eq!(id, id2);
+        assert_eq!(written2, 0);
+    }
+
+    #[test]
+    fn test_patch_chain_is_small_and_exact() {
+        let dir = TempDir::new().unwrap();
+        let store = ObjectStore::new(dir.path().to_path_buf()).unwrap();
+
+        let base: Vec<u8> = (0..50_000u32)
+            .flat_map(|i| (i * 7919).to_le_bytes())
+            .collect();
+        let mut v2 = base.clone();
+        v2[1000] ^= 0xff;
+        let mut v3 = v2.clone();
+        v3[40_000] ^= 0xff;
+
+        let (base_id, base_size) = store.put(&base).unwrap();
+        let (v2_id, v2_size) = store.put_patch(&v2, &base_id, &base).unwrap();
+        let (v3_id, _) = store.put_patch(&v3, &v2_id, &v2).unwrap();
+
+        assert!(
+            v2_size * 10 < base_size,
+            "patch {v2_size} vs base {base_size}"
+        );
+        assert_eq!(store.get(&v3_id).unwrap(), v3);
+        assert_eq!(store.base_of(&v3_id).unwrap(), Some(v2_id));
+        assert_eq!(store.list().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_corruption_detected() {
+        let dir = TempDir::new().unwrap();
+        let store = ObjectStore::new(dir.path().to_path_buf()).unwrap();
+
+        let (id, _) = store.put(b"original content").unwrap();
+        let (other, _) = store.put(b"different content").unwrap();
+        // Swap object bodies: decodes fine but hashes to the wrong id.
+        std::fs::copy(store.path(&other), store.path(&id)).unwrap();
+
+        let err = store.get(&id).unwrap_err().to_string();
+        assert!(err.contains("integrity"), "{err}");
+    }
+}
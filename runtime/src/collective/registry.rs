@@ -1,21 +1,34 @@
 //! Local map registry — stores and serves map snapshots + deltas.
 //!
 //! Provides push/pull semantics for sharing maps between Cortex operations.
+//! Snapshots live in a content-addressed object store: each domain keeps a
+//! zstd baseline followed by a chain of patches against the previous version,
+//! re-baselined every [`REBASELINE_INTERVAL`] versions so any historical
+//! version can be checked out cheaply.
 
 use crate::collective::delta::{self, MapDelta};
+use crate::collective::objects::{ObjectId, ObjectStore};
 use crate::map::types::SiteMap;
+use crate::navigation::query::SavedQuery;
+use crate::temporal::watch::WatchRule;
+use crate::trust::signing::SignedEnvelope;
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
+/// Number of versions in a patch chain before a new full baseline is stored.
+pub const REBASELINE_INTERVAL: usize = 24;
+
 /// Local registry that stores map snapshots and deltas.
 pub struct LocalRegistry {
     /// Storage directory (e.g., ~/.cortex/registry/).
     storage_dir: PathBuf,
     /// In-memory index: domain → entry.
     index: HashMap<String, RegistryEntry>,
+    /// Content-addressed snapshot and delta storage.
+    objects: ObjectStore,
 }
 
 /// A registry entry for a single domain.
@@ -27,12 +40,19 @@ pub struct RegistryEntry {
     pub latest_hash: [u8; 32],
     /// When the latest version was stored.
     pub latest_timestamp: DateTime<Utc>,
-    /// Path to the full map snapshot on disk.
+    /// Path to an uncompressed snapshot written by older registries.
+    #[serde(default)]
     pub snapshot_path: PathBuf,
     /// Ordered list of delta references.
     pub deltas: Vec<DeltaRef>,
     /// Instance IDs that contributed to this entry.
     pub contributed_by: Vec<String>,
+    /// Stored snapshot versions, oldest first.
+    #[serde(default)]
+    pub versions: Vec<VersionRef>,
+    /// Free-form labels used by remote subscriptions (e.g. `retail`).
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 /// Reference to a stored delta.
@@ -40,10 +60,40 @@ pub struct RegistryEntry {
 pub struct DeltaRef {
     /// When this delta was created.
     pub timestamp: DateTime<Utc>,
-    /// Path to the delta file.
+    /// Path to a delta file written by older registries.
+    #[serde(default)]
     pub path: PathBuf,
     /// Base hash this delta applies to.
     pub base_hash: [u8; 32],
+    /// Object holding the serialized delta.
+    #[serde(default)]
+    pub object: Option<ObjectId>,
+    /// Publisher signature over the serialized delta.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<SignedEnvelope>,
+}
+
+/// Reference to a stored snapshot version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionRef {
+    /// When this version was pushed.
+    pub timestamp: DateTime<Utc>,
+    /// Object holding the serialized map.
+    pub object: ObjectId,
+    /// Map content hash (see [`delta::hash_map`]).
+    pub map_hash: [u8; 32],
+    /// Whether the object is a full baseline rather than a patch.
+    pub baseline: bool,
+    /// Uncompressed snapshot size.
+    pub raw_bytes: u64,
+    /// Bytes on disk for this version's object.
+    pub stored_bytes: u64,
+    /// Number of nodes in the map.
+    #[serde(default)]
+    pub node_count: usize,
+    /// Publisher signature over the serialized map.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<SignedEnvelope>,
 }
 
 /// Registry-wide statistics.
@@ -53,6 +103,10 @@ pub struct RegistryStats {
     pub domain_count: usize,
     /// Total size of all snapshots on disk (bytes).
     pub total_snapshot_bytes: u64,
+    /// Total uncompressed size of all stored snapshot versions (bytes).
+    pub logical_snapshot_bytes: u64,
+    /// Total number of stored snapshot versions across all domains.
+    pub total_versions: usize,
     /// Total number of deltas across all domains.
     pub total_deltas: usize,
 }
@@ -66,6 +120,7 @@ impl LocalRegistry {
         let mut registry = Self {
             storage_dir: storage_dir.clone(),
             index: HashMap::new(),
+            objects: ObjectStore::new(storage_dir.join("objects"))?,
         };
 
         // Load existing index if present
@@ -78,53 +133,91 @@ impl LocalRegistry {
         Ok(registry)
     }
 
+    /// Open the default local registry at ~/.cortex/registry.
+    pub fn open_default() -> Result<Self> {
+        let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+        Self::new(home.join(".cortex").join("registry"))
+    }
+
     /// Push a map (and optional delta) to the registry.
     pub fn push(&mut self, domain: &str, map: &SiteMap, delta: Option<MapDelta>) -> Result<()> {
-        let domain_dir = self.storage_dir.join(domain.replace('.', "_"));
-        std::fs::create_dir_all(&domain_dir)?;
+        self.push_signed(domain, map, delta, None, None)
+    }
 
-        // Serialize and save snapshot
-        let snapshot_path = domain_dir.join("snapshot.ctx");
+    /// Push a map and optional delta together with their publisher signatures.
+    ///
+    /// Signatures are stored as given; callers verify them before pushing.
+    pub fn push_signed(
+        &mut self,
+        domain: &str,
+        map: &SiteMap,
+        delta: Option<MapDelta>,
+        map_signature: Option<SignedEnvelope>,
+        delta_signature: Option<SignedEnvelope>,
+    ) -> Result<()> {
         let data = map.serialize();
-        std::fs::write(&snapshot_path, &data)?;
-
         let hash = delta::hash_map(map);
+        let now = Utc::now();
 
-        // Save delta if provided
-        let mut deltas = self
-            .index
-            .get(domain)
-            .map(|e| e.deltas.clone())
+        let existing = self.index.get(domain);
+        let mut versions = existing.map(|e| e.versions.clone()).unwrap_or_default();
+        let mut deltas = existing.map(|e| e.deltas.clone()).unwrap_or_default();
+        let contributed_by = existing
+            .map(|e| e.contributed_by.clone())
             .unwrap_or_default();
+        let legacy_snapshot = existing
+            .map(|e| e.snapshot_path.clone())
+            .unwrap_or_default();
+        let tags = existing.map(|e| e.tags.clone()).unwrap_or_default();
+
+        // Patch against the previous version unless the chain is due for a
+        // fresh baseline.
+        let chain_len = versions.iter().rev().take_while(|v| !v.baseline).count();
+        let object = match versions.last() {
+            Some(prev) if chain_len + 1 < REBASELINE_INTERVAL => {
+                let base_data = self.objects.get(&prev.object)?;
+                self.objects.put_patch(&data, &prev.object, &base_data)?.0
+            }
+            _ => self.objects.put(&data)?.0,
+        };
+        versions.push(VersionRef {
+            timestamp: now,
+            baseline: self.objects.base_of(&object)?.is_none(),
+            stored_bytes: self.objects.stored_size(&object),
+            raw_bytes: data.len() as u64,
+            node_count: map.nodes.len(),
+            map_hash: hash,
+            object,
+            signature: map_signature,
+        });
 
         if let Some(d) = delta {
-            let delta_filename = format!("delta_{}.json", d.timestamp.format("%Y%m%d_%H%M%S"));
-            let delta_path = domain_dir.join(&delta_filename);
-            let delta_bytes = delta::serialize_delta(&d);
-            std::fs::write(&delta_path, &delta_bytes)?;
-
+            let (object, _) = self.objects.put(&delta::serialize_delta(&d))?;
             deltas.push(DeltaRef {
                 timestamp: d.timestamp,
-                path: delta_path,
+                path: PathBuf::new(),
                 base_hash: d.base_hash,
+                object: Some(object),
+                signature: delta_signature,
             });
         }
 
-        let contributed_by = self
-            .index
-            .get(domain)
-            .map(|e| e.contributed_by.clone())
-            .unwrap_or_default();
+        // The uncompressed snapshot is superseded by the object store.
+        if legacy_snapshot.exists() {
+            let _ = std::fs::remove_file(&legacy_snapshot);
+        }
 
         self.index.insert(
             domain.to_string(),
             RegistryEntry {
                 domain: domain.to_string(),
                 latest_hash: hash,
-                latest_timestamp: Utc::now(),
-                snapshot_path,
+                latest_timestamp: now,
+                snapshot_path: PathBuf::new(),
                 deltas,
                 contributed_by,
+                versions,
+                tags,
             },
         );
 
@@ -139,14 +232,58 @@ impl LocalRegistry {
             None => return Ok(None),
         };
 
-        if !entry.snapshot_path.exists() {
+        match entry.versions.last() {
+            Some(version) => Ok(Some((self.load_version(version)?, version.timestamp))),
+            None => self.load_legacy(entry),
+        }
+    }
+
+    /// Check out the map for a domain as it was at a point in time.
+    ///
+    /// Returns the newest version pushed at or before `at`.
+    pub fn checkout(
+        &self,
+        domain: &str,
+        at: DateTime<Utc>,
+    ) -> Result<Option<(SiteMap, DateTime<Utc>)>> {
+        let entry = match self.index.get(domain) {
+            Some(e) => e,
+            None => return Ok(None),
+        };
+
+        if entry.versions.is_empty() {
+            if entry.latest_timestamp <= at {
+                return self.load_legacy(entry);
+            }
             return Ok(None);
         }
 
-        let data = std::fs::read(&entry.snapshot_path)?;
-        let map = SiteMap::deserialize(&data)?;
+        match entry.versions.iter().rev().find(|v| v.timestamp <= at) {
+            Some(version) => Ok(Some((self.load_version(version)?, version.timestamp))),
+            None => Ok(None),
+        }
+    }
 
-        Ok(Some((map, entry.latest_timestamp)))
+    /// Verify every stored version and delta of a domain against its
+    /// integrity hashes. Returns the number of objects checked.
+    pub fn verify(&self, domain: &str) -> Result<usize> {
+        let entry = self
+            .index
+            .get(domain)
+            .with_context(|| format!("{domain} is not in the registry"))?;
+
+        let mut checked = 0;
+        for version in &entry.versions {
+            self.load_version(version)?;
+            checked += 1;
+        }
+        for delta_ref in &entry.deltas {
+            if let Some(object) = &delta_ref.object {
+                self.objects.get(object)?;
+                checked += 1;
+            }
+        }
+        Ok(checked)
     }
 
     /// Pull only deltas since a given timestamp.
@@ -158,16 +295,80 @@ impl LocalRegistry {
 
         let mut deltas = Vec::new();
         for delta_ref in &entry.deltas {
-            if delta_ref.timestamp > since && delta_ref.path.exists() {
-                let bytes = std::fs::read(&delta_ref.path)?;
-                let d = delta::deserialize_delta(&bytes)?;
-                deltas.push(d);
+            if delta_ref.timestamp <= since {
+                continue;
             }
+            let bytes = match &delta_ref.object {
+                Some(object) => self.objects.get(object)?,
+                None if delta_ref.path.exists() => std::fs::read(&delta_ref.path)?,
+                None => continue,
+            };
+            deltas.push(delta::deserialize_delta(&bytes)?);
         }
 
         Ok(Some(deltas))
     }
 
+    /// The most recently stored delta for a domain.
+    pub fn latest_delta(&self, domain: &str) -> Result<Option<MapDelta>> {
+        let Some(delta_ref) = self.index.get(domain).and_then(|e| e.deltas.last()) else {
+            return Ok(None);
+        };
+        let bytes = match &delta_ref.object {
+            Some(object) => self.objects.get(object)?,
+            None if delta_ref.path.exists() => std::fs::read(&delta_ref.path)?,
+            None => return Ok(None),
+        };
+        Ok(Some(delta::deserialize_delta(&bytes)?))
+    }
+
+    /// Load the most recent stored version whose map hash is `hash`.
+    pub fn version_by_hash(&self, domain: &str, hash: &[u8; 32]) -> Result<Option<SiteMap>> {
+        let Some(entry) = self.index.get(domain) else {
+            return Ok(None);
+        };
+        match entry.versions.iter().rev().find(|v| v.map_hash == *hash) {
+            Some(version) => Ok(Some(self.load_version(version)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Replace a domain's tags. Returns false if the domain is not stored.
+    pub fn set_tags(&mut self, domain: &str, tags: Vec<String>) -> Result<bool> {
+        let Some(entry) = self.index.get_mut(domain) else {
+            return Ok(false);
+        };
+        entry.tags = tags;
+        self.save_index()?;
+        Ok(true)
+    }
+
+    /// Look up the index entry for a domain.
+    pub fn entry(&self, domain: &str) -> Option<&RegistryEntry> {
+        self.index.get(domain)
+    }
+
+    /// Reconstruct a stored version and check its map hash.
+    fn load_version(&self, version: &VersionRef) -> Result<SiteMap> {
+        let data = self.objects.get(&version.object)?;
+        let map = SiteMap::deserialize(&data)?;
+        if delta::hash_map(&map) != version.map_hash {
+            anyhow::bail!("map hash mismatch for object {}", version.object);
+        }
+        Ok(map)
+    }
+
+    /// Read an uncompressed snapshot from a registry written before the
+    /// object store existed.
+    fn load_legacy(&self, entry: &RegistryEntry) -> Result<Option<(SiteMap, DateTime<Utc>)>> {
+        if entry.snapshot_path.as_os_str().is_empty() || !entry.snapshot_path.exists() {
+            return Ok(None);
+        }
+        let data = std::fs::read(&entry.snapshot_path)?;
+        let map = SiteMap::deserialize(&data)?;
+        Ok(Some((map, entry.latest_timestamp)))
+    }
+
     /// List all entries in the registry.
     pub fn list(&self) -> Vec<&RegistryEntry> {
         self.index.values().collect()
@@ -175,7 +376,7 @@ impl LocalRegistry {
 
     /// Get registry statistics.
     pub fn stats(&self) -> RegistryStats {
-        let total_snapshot_bytes: u64 = self
+        let legacy_bytes: u64 = self
             .index
             .values()
             .map(|e| {
@@ -184,17 +385,23 @@ impl LocalRegistry {
                     .unwrap_or(0)
             })
             .sum();
-
-        let total_deltas: usize = self.index.values().map(|e| e.deltas.len()).sum();
+        let versions = || self.index.values().flat_map(|e| e.versions.iter());
 
         RegistryStats {
             domain_count: self.index.len(),
-            total_snapshot_bytes,
-            total_deltas,
+            total_snapshot_bytes: legacy_bytes + versions().map(|v| v.stored_bytes).sum::<u64>(),
+            logical_snapshot_bytes: legacy_bytes + versions().map(|v| v.raw_bytes).sum::<u64>(),
+            total_versions: versions().count(),
+            total_deltas: self.index.values().map(|e| e.deltas.len()).sum(),
         }
     }
 
-    /// Garbage collect old deltas (keep only the last N per domain).
+    /// Garbage collect history: keep only the last N deltas and snapshot
+    /// versions per domain, then delete unreferenced objects.
+    ///
+    /// Versions are only dropped up to the baseline that the oldest kept
+    /// version's patch chain starts from. Returns the number of deltas and
+    /// versions removed.
     pub fn gc(&mut self, keep_count: usize) -> Result<usize> {
         let mut removed = 0;
         for entry in self.index.values_mut() {
@@ -202,14 +409,122 @@ impl LocalRegistry {
                 let old = entry.deltas.remove(0);
                 if old.path.exists() {
                     let _ = std::fs::remove_file(&old.path);
-                    removed += 1;
                 }
+                removed += 1;
+            }
+
+            if entry.versions.len() > keep_count {
+                let oldest_kept = entry.versions.len() - keep_count.max(1);
+                let cut = entry.versions[..=oldest_kept]
+                    .iter()
+                    .rposition(|v| v.baseline)
+                    .unwrap_or(0);
+                entry.versions.drain(..cut);
+                removed += cut;
             }
         }
         self.save_index()?;
+        self.sweep_objects()?;
         Ok(removed)
     }
 
+    /// Delete objects not reachable from any version or delta.
+    fn sweep_objects(&self) -> Result<usize> {
+        let mut live: HashSet<ObjectId> = HashSet::new();
+        let mut pending: Vec<ObjectId> = self
+            .index
+            .values()
+            .flat_map(|e| {
+                e.versions
+                    .iter()
+                    .map(|v| v.object.clone())
+                    .chain(e.deltas.iter().filter_map(|d| d.object.clone()))
+            })
+            .collect();
+        while let Some(id) = pending.pop() {
+            if live.insert(id.clone()) {
+                if let Ok(Some(base)) = self.objects.base_of(&id) {
+                    pending.push(base);
+                }
+            }
+        }
+
+        let mut swept = 0;
+        for id in self.objects.list()? {
+            if !live.contains(&id) && self.objects.remove(&id) {
+                swept += 1;
+            }
+        }
+        Ok(swept)
+    }
+
+    /// Load persisted watch rules.
+    pub fn load_watch_rules(&self) -> Result<Vec<WatchRule>> {
+        let path = self.storage_dir.join("watches.json");
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let data = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&data).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    /// Persist watch rules, replacing any previously stored set.
+    pub fn save_watch_rules(&self, rules: &[WatchRule]) -> Result<()> {
+        let path = self.storage_dir.join("watches.json");
+        let data = serde_json::to_string_pretty(rules)?;
+        std::fs::write(path, data)?;
+        Ok(())
+    }
+
+    /// Load saved queries, sorted by name.
+    pub fn load_saved_queries(&self) -> Result<Vec<SavedQuery>> {
+        let path = self.storage_dir.join("queries.json");
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let data = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&data).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    /// The saved query called `name`, if any.
+    pub fn saved_query(&self, name: &str) -> Result<Option<SavedQuery>> {
+        Ok(self
+            .load_saved_queries()?
+            .into_iter()
+            .find(|q| q.name == name))
+    }
+
+    /// Store `query`, replacing any saved query with the same name.
+    /// Returns whether one was replaced.
+    pub fn put_saved_query(&self, query: SavedQuery) -> Result<bool> {
+        let mut queries = self.load_saved_queries()?;
+        let before = queries.len();
+        queries.retain(|q| q.name != query.name);
+        let replaced = queries.len() != before;
+        queries.push(query);
+        queries.sort_by(|a, b| a.name.cmp(&b.name));
+        self.write_saved_queries(&queries)?;
+        Ok(replaced)
+    }
+
+    /// Delete the saved query called `name`. Returns whether it existed.
+    pub fn remove_saved_query(&self, name: &str) -> Result<bool> {
+        let mut queries = self.load_saved_queries()?;
+        let before = queries.len();
+        queries.retain(|q| q.name != name);
+        if queries.len() == before {
+            return Ok(false);
+        }
+        self.write_saved_queries(&queries)?;
+        Ok(true)
+    }
+
+    fn write_saved_queries(&self, queries: &[SavedQuery]) -> Result<()> {
+        let path = self.storage_dir.join("queries.json");
+        std::fs::write(path, serde_json::to_string_pretty(queries)?)?;
+        Ok(())
+    }
+
     /// Save the index to disk.
     fn save_index(&self) -> Result<()> {
         let index_path = self.storage_dir.join("index.json");
@@ -406,4 +721,133 @@ mod tests {
         let deltas = registry.pull_since("test.com", since).unwrap();
         assert!(deltas.is_some());
     }
+
+    #[test]
+    fn test_registry_patch_chain_and_checkout() {
+        let dir = TempDir::new().unwrap();
+        let mut registry = LocalRegistry::new(dir.path().to_path_buf()).unwrap();
+
+        let mut pushed = Vec::new();
+        for i in 0..5 {
+            let mut map = build_product_map("shop.com", 40);
+            map.features[0][48] = 100.0 + i as f32;
+            registry.push("shop.com", &map, None).unwrap();
+            pushed.push(registry.list()[0].versions.last().unwrap().timestamp);
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+
+        let entry = registry.list()[0].clone();
+        assert_eq!(entry.versions.len(), 5);
+        assert!(entry.versions[0].baseline);
+        assert!(entry.versions[1..].iter().all(|v| !v.baseline));
+        assert!(entry.versions[1].stored_bytes < entry.versions[0].stored_bytes);
+
+        let (latest, _) = registry.pull("shop.com").unwrap().unwrap();
+        assert_eq!(latest.features[0][48], 104.0);
+
+        let (old, ts) = registry.checkout("shop.com", pushed[1]).unwrap().unwrap();
+        assert_eq!(old.features[0][48], 101.0);
+        assert_eq!(ts, pushed[1]);
+        assert!(registry
+            .checkout("shop.com", pushed[0] - chrono::Duration::seconds(1))
+            .unwrap()
+            .is_none());
+
+        assert_eq!(registry.verify("shop.com").unwrap(), 5);
+    }
+
+    #[test]
+    fn test_registry_rebaselines_and_gc_keeps_chain() {
+        let dir = TempDir::new().unwrap();
+        let mut registry = LocalRegistry::new(dir.path().to_path_buf()).unwrap();
+
+        for i in 0..REBASELINE_INTERVAL + 3 {
+            let mut map = build_product_map("shop.com", 10);
+            map.features[1][48] = i as f32;
+            registry.push("shop.com", &map, None).unwrap();
+        }
+        let baselines: Vec<usize> = registry.list()[0]
+            .versions
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| v.baseline)
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(baselines, vec![0, REBASELINE_INTERVAL]);
+
+        // Keeping 2 versions drops everything before the second baseline.
+        let removed = registry.gc(2).unwrap();
+        assert_eq!(removed, REBASELINE_INTERVAL);
+        assert_eq!(registry.list()[0].versions.len(), 3);
+        assert!(registry.list()[0].versions[0].baseline);
+        assert_eq!(registry.verify("shop.com").unwrap(), 3);
+
+        let (latest, _) = registry.pull("shop.com").unwrap().unwrap();
+        assert_eq!(latest.features[1][48], (REBASELINE_INTERVAL + 2) as f32);
+        assert_eq!(registry.objects.list().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_registry_reads_legacy_snapshot() {
+        let dir = TempDir::new().unwrap();
+        let map = build_test_map("old.com");
+        let snapshot_path = dir.path().join("old_com_snapshot.ctx");
+        std::fs::write(&snapshot_path, map.serialize()).unwrap();
+
+        let entry = serde_json::json!({
+            "old.com": {
+                "domain": "old.com",
+                "latest_hash": vec![0u8; 32],
+                "latest_timestamp": Utc::now().to_rfc3339(),
+                "snapshot_path": snapshot_path,
+                "deltas": [],
+                "contributed_by": [],
+            }
+        });
+        std::fs::write(dir.path().join("index.json"), entry.to_string()).unwrap();
+
+        let mut registry = LocalRegistry::new(dir.path().to_path_buf()).unwrap();
+        let (pulled, _) = registry.pull("old.com").unwrap().unwrap();
+        assert_eq!(pulled.header.domain, "old.com");
+
+        // Pushing migrates the entry into the object store.
+        registry.push("old.com", &pulled, None).unwrap();
+        assert!(!snapshot_path.exists());
+        assert_eq!(registry.list()[0].versions.len(), 1);
+    }
+
+    #[test]
+    fn test_registry_saved_queries() {
+        use crate::map::types::{NodeQuery, PageType};
+
+        let dir = TempDir::new().unwrap();
+        let registry = LocalRegistry::new(dir.path().to_path_buf()).unwrap();
+        assert!(SavedQuery::new("cheap in stock", NodeQuery::default(), false).is_err());
+
+        let query = NodeQuery {
+            page_types: Some(vec![PageType::ProductDetail]),
+            limit: 5,
+            ..Default::default()
+        };
+        let saved = SavedQuery::new("cheap-in-stock", query, true).unwrap();
+        assert!(!registry.put_saved_query(saved.clone()).unwrap());
+        assert!(registry.put_saved_query(saved).unwrap());
+        let other = SavedQuery::new("articles", NodeQuery::default(), false).unwrap();
+        registry.put_saved_query(other).unwrap();
+
+        let names: Vec<String> = registry
+            .load_saved_queries()
+            .unwrap()
+            .into_iter()
+            .map(|q| q.name)
+            .collect();
+        assert_eq!(names, vec!["articles", "cheap-in-stock"]);
+        let loaded = registry.saved_query("cheap-in-stock").unwrap().unwrap();
+        assert_eq!(loaded.query.limit, 5);
+        assert!(loaded.diversify);
+
+        assert!(registry.remove_saved_query("articles").unwrap());
+        assert!(!registry.remove_saved_query("articles").unwrap());
+        assert!(registry.saved_query("articles").unwrap().is_none());
+    }
 }
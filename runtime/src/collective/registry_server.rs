@@ -0,0 +1,686 @@
+//! Remote registry server — exposes a [`LocalRegistry`] over HTTP.
+//!
+//! Implements the endpoints used by [`RemoteSync`](super::sync::RemoteSync):
+//!
+//! | Method | Path | Description |
+//! |--------|------|-------------|
+//! | GET | `/v1/maps` | List domains |
+//! | GET | `/v1/maps/:domain` | Latest map (binary CTX) |
+//! | PUT | `/v1/maps/:domain` | Push a full map |
+//! | GET | `/v1/maps/:domain/deltas?since=` | Deltas since a timestamp (JSON) |
+//! | POST | `/v1/maps/:domain/deltas` | Push a delta against the latest map |
+//!
+//! When tokens are configured every request needs `Authorization: Bearer
+//! <token>`. Without tokens the server is read-only.
+//!
+//! Pushed payloads may carry a publisher signature (see
+//! [`crate::trust::signing`]); it is verified before storing and served back
+//! with the map. Invalid signatures are always rejected; unsigned pushes are
+//! rejected when the server requires signatures.
+
+use crate::collective::delta::{self, MapDelta};
+use crate::collective::registry::LocalRegistry;
+use crate::collective::sync::{hex_encode, RemoteEntry, MAP_SIGNATURE_HEADER, SIGNATURE_HEADER};
+use crate::map::types::SiteMap;
+use crate::trust::signing::{SignedEnvelope, TrustedKeys};
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, Path, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Shared state for the registry server.
+pub struct RegistryServerState {
+    registry: RwLock<LocalRegistry>,
+    tokens: Vec<String>,
+    trusted: TrustedKeys,
+    require_signatures: bool,
+}
+
+impl RegistryServerState {
+    /// Serve `registry`, accepting any of `tokens` as bearer credentials.
+    pub fn new(registry: LocalRegistry, tokens: Vec<String>) -> Self {
+        Self {
+            registry: RwLock::new(registry),
+            tokens,
+            trusted: TrustedKeys::default(),
+            require_signatures: false,
+        }
+    }
+
+    /// Verify pushed signatures against `trusted`, optionally rejecting
+    /// unsigned pushes. When signatures are required, only keys in
+    /// `trusted` are accepted: an empty list rejects every signer.
+    pub fn with_signature_policy(mut self, trusted: TrustedKeys, require: bool) -> Self {
+        self.trusted = trusted;
+        self.require_signatures = require;
+        self
+    }
+
+    /// Verify the signature in header `name` over `payload`.
+    fn verify_signature(
+        &self,
+        headers: &HeaderMap,
+        name: &str,
+        payload: &[u8],
+    ) -> Result<Option<SignedEnvelope>, String> {
+        let Some(value) = headers.get(name).and_then(|v| v.to_str().ok()) else {
+            if self.require_signatures {
+                return Err("this registry only accepts signed pushes".to_string());
+            }
+            return Ok(None);
+        };
+        let envelope = SignedEnvelope::from_header(value).map_err(|e| e.to_string())?;
+        if self.require_signatures || !self.trusted.keys.is_empty() {
+            self.trusted
+                .check(&envelope, payload)
+                .map_err(|e| e.to_string())?;
+        } else {
+            // Nobody is trusted here: keep the signature intact for pullers
+            // to judge against their own trust lists.
+            envelope.verify(payload).map_err(|e| e.to_string())?;
+        }
+        Ok(Some(envelope))
+    }
+
+    /// The served registry, for sharing with in-process sync tasks.
+    pub fn registry(&self) -> &RwLock<LocalRegistry> {
+        &self.registry
+    }
+
+    /// Whether the server accepts pushes.
+    pub fn writable(&self) -> bool {
+        !self.tokens.is_empty()
+    }
+
+    /// Check credentials, returning the rejection response if not allowed.
+    fn reject(&self, headers: &HeaderMap, write: bool) -> Option<Response> {
+        if self.tokens.is_empty() {
+            if write {
+                return Some(error(
+                    StatusCode::FORBIDDEN,
+                    "registry is read-only (start it with --token to accept pushes)",
+                ));
+            }
+            return None;
+        }
+
+        let presented = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .unwrap_or("");
+        if self
+            .tokens
+            .iter()
+            .any(|t| constant_time_eq(t.as_bytes(), presented.as_bytes()))
+        {
+            None
+        } else {
+            Some(error(StatusCode::UNAUTHORIZED, "missing or invalid token"))
+        }
+    }
+}
+
+/// Largest map or delta a client may push. Maps are sent whole, and a
+/// 50,000-node map carries about 25 MB of feature vectors alone.
+const MAX_PUSH_BYTES: usize = 256 * 1024 * 1024;
+
+/// Build the registry HTTP router.
+pub fn router(state: Arc<RegistryServerState>) -> Router {
+    Router::new()
+        .route("/v1/maps", get(list_maps))
+        .route("/v1/maps/:domain", get(get_map).put(put_map))
+        .route("/v1/maps/:domain/deltas", get(get_deltas).post(post_delta))
+        .layer(DefaultBodyLimit::max(MAX_PUSH_BYTES))
+        .with_state(state)
+}
+
+/// Serve the registry on `addr` until the process exits.
+pub async fn serve(
+    addr: std::net::SocketAddr,
+    state: Arc<RegistryServerState>,
+) -> anyhow::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    tracing::info!("registry server listening on http://{addr}");
+    axum::serve(listener, router(state)).await?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct DeltaParams {
+    since: Option<String>,
+}
+
+async fn list_maps(State(state): State<Arc<RegistryServerState>>, headers: HeaderMap) -> Response {
+    if let Some(resp) = state.reject(&headers, false) {
+        return resp;
+    }
+    let registry = state.registry.read().await;
+    let mut entries: Vec<RemoteEntry> = registry
+        .list()
+        .into_iter()
+        .map(|e| RemoteEntry {
+            domain: e.domain.clone(),
+            latest_timestamp: e.latest_timestamp,
+            node_count: e.versions.last().map(|v| v.node_count).unwrap_or(0),
+            latest_hash: hex_encode(&e.latest_hash),
+            tags: e.tags.clone(),
+        })
+        .collect();
+    entries.sort_by(|a, b| a.domain.cmp(&b.domain));
+    Json(entries).into_response()
+}
+
+async fn get_map(
+    State(state): State<Arc<RegistryServerState>>,
+    Path(domain): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    if let Some(resp) = state.reject(&headers, false) {
+        return resp;
+    }
+    let registry = state.registry.read().await;
+    match registry.pull(&domain) {
+        Ok(Some((map, ts))) => {
+            let mut resp = (
+                [
+                    (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+                    (
+                        header::HeaderName::from_static("x-cortex-map-hash"),
+                        hex_encode(&delta::hash_map(&map)),
+                    ),
+                    (
+                        header::HeaderName::from_static("x-cortex-timestamp"),
+                        ts.to_rfc3339(),
+                    ),
+                ],
+                map.serialize(),
+            )
+                .into_response();
+            let signature = registry
+                .entry(&domain)
+                .and_then(|e| e.versions.last())
+                .and_then(|v| v.signature.as_ref());
+            if let Some(value) = signature.and_then(|s| s.to_header().parse().ok()) {
+                resp.headers_mut().insert(SIGNATURE_HEADER, value);
+            }
+            resp
+        }
+        Ok(None) => error(StatusCode::NOT_FOUND, "domain not in registry"),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    }
+}
+
+async fn put_map(
+    State(state): State<Arc<RegistryServerState>>,
+    Path(domain): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    if let Some(resp) = state.reject(&headers, true) {
+        return resp;
+    }
+    if !valid_domain(&domain) {
+        return error(StatusCode::BAD_REQUEST, "invalid domain");
+    }
+    let map = match SiteMap::deserialize(&body) {
+        Ok(m) => m,
+        Err(e) => return error(StatusCode::BAD_REQUEST, &format!("invalid map: {e}")),
+    };
+    if map.header.domain != domain {
+        return error(
+            StatusCode::BAD_REQUEST,
+            &format!("map is for {}, not {domain}", map.header.domain),
+        );
+    }
+
+    let signature = match state.verify_signature(&headers, SIGNATURE_HEADER, &body) {
+        Ok(s) => s,
+        Err(msg) => return error(StatusCode::FORBIDDEN, &msg),
+    };
+
+    let instance = instance_of(&headers);
+    let mut registry = state.registry.write().await;
+    let prev = registry.pull(&domain).ok().flatten();
+    // A client that states its base only replaces the map it last saw;
+    // concurrent writers must pull and merge instead of clobbering each other.
+    if let (Some(base), Some((old, _))) = (
+        headers
+            .get("x-cortex-base-hash")
+            .and_then(|v| v.to_str().ok()),
+        &prev,
+    ) {
+        let current = hex_encode(&delta::hash_map(old));
+        if base != current {
+            return error(
+                StatusCode::CONFLICT,
+                &format!("base hash mismatch (registry is at {current}); pull and merge first"),
+            );
+        }
+    }
+    let delta = prev.map(|(old, _)| delta::compute_delta(&old, &map, &instance));
+    if let Err(e) = registry.push_signed(&domain, &map, delta, signature, None) {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string());
+    }
+    tracing::info!("registry: {instance} pushed map for {domain}");
+    Json(serde_json::json!({
+        "domain": domain,
+        "hash": hex_encode(&delta::hash_map(&map)),
+    }))
+    .into_response()
+}
+
+async fn get_deltas(
+    State(state): State<Arc<RegistryServerState>>,
+    Path(domain): Path<String>,
+    Query(params): Query<DeltaParams>,
+    headers: HeaderMap,
+) -> Response {
+    if let Some(resp) = state.reject(&headers, false) {
+        return resp;
+    }
+    let since = match params.since.as_deref().map(DateTime::parse_from_rfc3339) {
+        None => DateTime::<Utc>::UNIX_EPOCH,
+        Some(Ok(dt)) => dt.with_timezone(&Utc),
+        Some(Err(e)) => return error(StatusCode::BAD_REQUEST, &format!("invalid since: {e}")),
+    };
+    let registry = state.registry.read().await;
+    match registry.pull_since(&domain, since) {
+        Ok(Some(deltas)) => Json(deltas).into_response(),
+        Ok(None) => error(StatusCode::NOT_FOUND, "domain not in registry"),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    }
+}
+
+async fn post_delta(
+    State(state): State<Arc<RegistryServerState>>,
+    Path(domain): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    if let Some(resp) = state.reject(&headers, true) {
+        return resp;
+    }
+    let delta: MapDelta = match delta::deserialize_delta(&body) {
+        Ok(d) => d,
+        Err(e) => return error(StatusCode::BAD_REQUEST, &format!("invalid delta: {e}")),
+    };
+    let delta_signature = match state.verify_signature(&headers, SIGNATURE_HEADER, &body) {
+        Ok(s) => s,
+        Err(msg) => return error(StatusCode::FORBIDDEN, &msg),
+    };
+    // Deltas can only patch features in place; structural changes need a full map.
+    if !delta.nodes_added.is_empty()
+        || !delta.nodes_removed.is_empty()
+        || !delta.edges_added.is_empty()
+        || !delta.edges_removed.is_empty()
+    {
+        return error(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "delta changes graph structure; push the full map instead",
+        );
+    }
+
+    let mut registry = state.registry.write().await;
+    let mut map = match registry.pull(&domain) {
+        Ok(Some((map, _))) => map,
+        Ok(None) => return error(StatusCode::NOT_FOUND, "domain not in registry"),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    };
+    let current = delta::hash_map(&map);
+    if current != delta.base_hash {
+        return error(
+            StatusCode::CONFLICT,
+            &format!(
+                "base hash mismatch (registry is at {})",
+                hex_encode(&current)
+            ),
+        );
+    }
+
+    if let Err(e) = delta::apply_delta(&mut map, &delta) {
+        return error(StatusCode::UNPROCESSABLE_ENTITY, &e.to_string());
+    }
+    let result = hex_encode(&delta::hash_map(&map));
+    if let Some(expected) = headers
+        .get("x-cortex-result-hash")
+        .and_then(|v| v.to_str().ok())
+    {
+        if expected != result {
+            return error(
+                StatusCode::CONFLICT,
+                "applying the delta does not reproduce the expected map",
+            );
+        }
+    }
+
+    // The publisher also signs the map the delta produces; if that does not
+    // match what we reconstructed, make them push the full map instead.
+    let map_signature =
+        match state.verify_signature(&headers, MAP_SIGNATURE_HEADER, &map.serialize()) {
+            Ok(s) => s,
+            Err(msg) => {
+                return error(
+                    StatusCode::CONFLICT,
+                    &format!("resulting map signature rejected ({msg}); push the full map"),
+                )
+            }
+        };
+
+    if let Err(e) = registry.push_signed(&domain, &map, Some(delta), map_signature, delta_signature)
+    {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string());
+    }
+    tracing::info!(
+        "registry: {} pushed delta for {domain}",
+        instance_of(&headers)
+    );
+    Json(serde_json::json!({ "domain": domain, "hash": result })).into_response()
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(serde_json::json!({ "error": message }))).into_response()
+}
+
+fn instance_of(headers: &HeaderMap) -> String {
+    headers
+        .get("x-cortex-instance")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("remote")
+        .to_string()
+}
+
+fn valid_domain(domain: &str) -> bool {
+    !domain.is_empty()
+        && domain.len() <= 253
+        && domain
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':'))
+}
+
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collective::sync::RemoteSync;
+    use crate::map::builder::SiteMapBuilder;
+    use crate::map::types::{PageType, FEATURE_DIM};
+    use tempfile::TempDir;
+
+    fn product_map(price: f32) -> SiteMap {
+        let mut builder = SiteMapBuilder::new("shop.com");
+        let mut feats = [0.0f32; FEATURE_DIM];
+        feats[48] = price;
+        builder.add_node("https://shop.com/p/1", PageType::ProductDetail, feats, 200);
+        builder.build()
+    }
+
+    async fn spawn(tokens: Vec<String>) -> (String, TempDir) {
+        spawn_with(RegistryServerState::new, tokens).await
+    }
+
+    async fn spawn_with(
+        make: impl FnOnce(LocalRegistry, Vec<String>) -> RegistryServerState,
+        tokens: Vec<String>,
+    ) -> (String, TempDir) {
+        let dir = TempDir::new().unwrap();
+        let registry = LocalRegistry::new(dir.path().to_path_buf()).unwrap();
+        let state = Arc::new(make(registry, tokens));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router(state)).await });
+        (format!("http://{addr}"), dir)
+    }
+
+    #[tokio::test]
+    async fn test_push_pull_round_trip() {
+        let (url, _dir) = spawn(vec!["secret".into()]).await;
+        let client = RemoteSync::new(&url, "test", Some("secret".into()));
+
+        let v1 = product_map(100.0);
+        client.push_map("shop.com", &v1).await.unwrap();
+
+        let listed = client.list_available().await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].node_count, 1);
+        assert_eq!(listed[0].latest_hash, hex_encode(&delta::hash_map(&v1)));
+
+        // Feature-only delta against the remote's current map.
+        let v2 = product_map(80.0);
+        let d = delta::compute_delta(&v1, &v2, "test");
+        client.push_delta("shop.com", &d, Some(&v2)).await.unwrap();
+
+        let pulled = client.pull_map("shop.com").await.unwrap().unwrap();
+        assert_eq!(pulled.features[0][48], 80.0);
+        let deltas = client
+            .pull_since("shop.com", DateTime::<Utc>::UNIX_EPOCH)
+            .await
+            .unwrap();
+        assert_eq!(deltas.len(), 1);
+
+        // A stale base is rejected.
+        assert!(client.push_delta("shop.com", &d, None).await.is_err());
+        assert!(client.pull_map("nope.com").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_full_push_needs_merge() {
+        let (url, _dir) = spawn(vec!["secret".into()]).await;
+        let alice = RemoteSync::new(&url, "alice", Some("secret".into()));
+        let bob = RemoteSync::new(&url, "bob", Some("secret".into()));
+
+        let base = product_map(100.0);
+        alice.push_map("shop.com", &base).await.unwrap();
+        let base_hash = delta::hash_map(&base);
+
+        // Both start from the same base; the second writer is turned away.
+        let ours = product_map(90.0);
+        assert!(alice
+            .push_map_onto("shop.com", &ours, &base_hash)
+            .await
+            .unwrap());
+        let theirs = product_map(80.0);
+        assert!(!bob
+            .push_map_onto("shop.com", &theirs, &base_hash)
+            .await
+            .unwrap());
+
+        // After merging with the remote's version the push goes through.
+        let remote = bob.pull_map("shop.com").await.unwrap().unwrap();
+        let (merged, report) = delta::merge_maps(&theirs, &remote);
+        assert_eq!(report.conflicts.len(), 1);
+        assert!(bob
+            .push_map_onto("shop.com", &merged, &delta::hash_map(&remote))
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_auth_enforced() {
+        let (url, _dir) = spawn(vec!["secret".into()]).await;
+        let anon = RemoteSync::new(&url, "test", None);
+        assert!(anon.list_available().await.is_err());
+        assert!(anon.push_map("shop.com", &product_map(1.0)).await.is_err());
+
+        let wrong = RemoteSync::new(&url, "test", Some("nope".into()));
+        assert!(wrong.push_map("shop.com", &product_map(1.0)).await.is_err());
+
+        // Without tokens the server is public but read-only.
+        let (url, _dir) = spawn(Vec::new()).await;
+        let anon = RemoteSync::new(&url, "test", None);
+        assert!(anon.list_available().await.unwrap().is_empty());
+        let err = anon
+            .push_map("shop.com", &product_map(1.0))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("403"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_signed_push_and_verified_pull() {
+        use crate::collective::sync::PullVerification;
+        use crate::trust::signing::MapSigner;
+
+        let alice = Arc::new(MapSigner::generate("alice"));
+        let mut trusted = TrustedKeys::default();
+        trusted.add("alice", &alice.public_key()).unwrap();
+
+        let policy = trusted.clone();
+        let (url, _dir) = spawn_with(
+            move |r, t| RegistryServerState::new(r, t).with_signature_policy(policy, true),
+            vec!["secret".into()],
+        )
+        .await;
+
+        // Unsigned and untrusted pushes are refused.
+        let unsigned = RemoteSync::new(&url, "test", Some("secret".into()));
+        assert!(unsigned
+            .push_map("shop.com", &product_map(1.0))
+            .await
+            .is_err());
+        let mallory = RemoteSync::new(&url, "test", Some("secret".into()))
+            .with_signer(Arc::new(MapSigner::generate("mallory")));
+        assert!(mallory
+            .push_map("shop.com", &product_map(1.0))
+            .await
+            .is_err());
+
+        let client = RemoteSync::new(&url, "test", Some("secret".into()))
+            .with_signer(Arc::clone(&alice))
+            .with_verification(PullVerification {
+                trusted,
+                allow_unsigned: false,
+            });
+        let v1 = product_map(100.0);
+        client.push_map("shop.com", &v1).await.unwrap();
+
+        let pulled = client.pull_signed_map("shop.com").await.unwrap().unwrap();
+        assert_eq!(pulled.publisher.as_deref(), Some("alice"));
+        let sig = pulled.signature.unwrap();
+        assert_eq!(sig.provenance.mapper, "alice");
+        assert_eq!(sig.provenance.settings["node_count"], 1);
+
+        // Delta pushes carry a signature for the resulting map too.
+        let v2 = product_map(90.0);
+        let d = delta::compute_delta(&v1, &v2, "test");
+        let mut applied = v1.clone();
+        delta::apply_delta(&mut applied, &d).unwrap();
+        client
+            .push_delta("shop.com", &d, Some(&applied))
+            .await
+            .unwrap();
+        let pulled = client.pull_signed_map("shop.com").await.unwrap().unwrap();
+        assert_eq!(pulled.map.features[0][48], 90.0);
+        assert!(pulled.signature.is_some());
+
+        // A client that trusts someone else rejects the map.
+        let mut other = TrustedKeys::default();
+        other
+            .add("carol", &MapSigner::generate("carol").public_key())
+            .unwrap();
+        let strict = RemoteSync::new(&url, "test", Some("secret".into())).with_verification(
+            PullVerification {
+                trusted: other,
+                allow_unsigned: false,
+            },
+        );
+        let err = strict.pull_map("shop.com").await.unwrap_err();
+        assert!(format!("{err:#}").contains("not in the trusted key list"));
+    }
+
+    #[tokio::test]
+    async fn test_push_map_over_default_body_limit() {
+        let (url, _dir) = spawn(vec!["secret".into()]).await;
+        let mut builder = SiteMapBuilder::new("shop.com");
+        for i in 0..5000 {
+            builder.add_node(
+                &format!("https://shop.com/p/{i}"),
+                PageType::ProductDetail,
+                [1.0; FEATURE_DIM],
+                200,
+            );
+        }
+        let map = builder.build();
+        assert!(map.serialize().len() > 2 * 1024 * 1024);
+
+        let client = RemoteSync::new(&url, "test", Some("secret".into()));
+        client.push_map("shop.com", &map).await.unwrap();
+        let pulled = client.pull_map("shop.com").await.unwrap().unwrap();
+        assert_eq!(pulled.nodes.len(), 5000);
+    }
+
+    #[tokio::test]
+    async fn test_pull_with_empty_trust_list_verifies_no_one() {
+        use crate::collective::sync::PullVerification;
+        use crate::trust::signing::MapSigner;
+
+        let (url, _dir) = spawn(vec!["secret".into()]).await;
+        // Anyone can mint a key and claim to be "alice".
+        RemoteSync::new(&url, "test", Some("secret".into()))
+            .with_signer(Arc::new(MapSigner::generate("alice")))
+            .push_map("shop.com", &product_map(1.0))
+            .await
+            .unwrap();
+
+        let strict = RemoteSync::new(&url, "test", Some("secret".into())).with_verification(
+            PullVerification {
+                trusted: TrustedKeys::default(),
+                allow_unsigned: false,
+            },
+        );
+        let err = strict.pull_signed_map("shop.com").await.err().unwrap();
+        assert!(
+            format!("{err:#}").contains("not in the trusted key list"),
+            "{err:#}"
+        );
+
+        let lenient = RemoteSync::new(&url, "test", Some("secret".into())).with_verification(
+            PullVerification {
+                trusted: TrustedKeys::default(),
+                allow_unsigned: true,
+            },
+        );
+        let pulled = lenient.pull_signed_map("shop.com").await.unwrap().unwrap();
+        assert!(pulled.signature.is_some());
+        assert_eq!(pulled.publisher, None);
+    }
+
+    #[tokio::test]
+    async fn test_required_signatures_with_empty_trust_list() {
+        use crate::trust::signing::MapSigner;
+
+        let (url, _dir) = spawn_with(
+            |r, t| {
+                RegistryServerState::new(r, t).with_signature_policy(TrustedKeys::default(), true)
+            },
+            vec!["secret".into()],
+        )
+        .await;
+
+        // A self-generated key is not trusted just because nobody is.
+        let mallory = RemoteSync::new(&url, "test", Some("secret".into()))
+            .with_signer(Arc::new(MapSigner::generate("mallory")));
+        let err = mallory
+            .push_map("shop.com", &product_map(1.0))
+            .await
+            .unwrap_err();
+        assert!(
+            format!("{err:#}").contains("not in the trusted key list"),
+            "{err:#}"
+        );
+        let unsigned = RemoteSync::new(&url, "test", Some("secret".into()));
+        assert!(unsigned
+            .push_map("shop.com", &product_map(1.0))
+            .await
+            .is_err());
+    }
+}
@@ -1,12 +1,23 @@
 //! Remote registry sync — push/pull deltas to remote Cortex registries.
 //!
-//! Handles communication with remote registry servers over HTTPS.
+//! Handles communication with remote registry servers over HTTPS, and
+//! standing [`Subscription`]s that pull selected domains on a schedule.
 
 use crate::collective::delta::{self, MapDelta};
+use crate::collective::registry::LocalRegistry;
 use crate::map::types::SiteMap;
+use crate::trust::signing::{MapSigner, SignedEnvelope, TrustedKeys};
 use anyhow::{Context, Result};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Header carrying a [`SignedEnvelope`] for the request or response body.
+pub const SIGNATURE_HEADER: &str = "x-cortex-signature";
+/// Header carrying a [`SignedEnvelope`] for the map that results from a delta.
+pub const MAP_SIGNATURE_HEADER: &str = "x-cortex-map-signature";
 
 /// Remote sync client for pushing/pulling to a remote registry.
 pub struct RemoteSync {
@@ -18,29 +29,92 @@ pub struct RemoteSync {
     api_key: Option<String>,
     /// HTTP client.
     client: reqwest::Client,
+    /// Key used to sign pushed maps and deltas.
+    signer: Option<Arc<MapSigner>>,
+    /// Signature policy for pulled maps (None = no verification).
+    verify: Option<PullVerification>,
+}
+
+/// How pulled maps are verified.
+#[derive(Debug, Clone)]
+pub struct PullVerification {
+    /// Publishers whose signatures are accepted.
+    pub trusted: TrustedKeys,
+    /// Accept maps that carry no signature, or one from a key that is not
+    /// in `trusted`.
+    pub allow_unsigned: bool,
+}
+
+/// A pulled map together with its publisher signature, if any.
+pub struct PulledMap {
+    /// The map.
+    pub map: SiteMap,
+    /// Verified signature over the map bytes.
+    pub signature: Option<SignedEnvelope>,
+    /// Name of the trusted key that signed the map (None when the map is
+    /// unsigned or its signer is not trusted).
+    pub publisher: Option<String>,
 }
 
 /// A remote registry entry listing.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RemoteEntry {
+    /// Domain name.
     pub domain: String,
+    /// When the latest version was stored.
     pub latest_timestamp: DateTime<Utc>,
+    /// Node count of the latest version.
     pub node_count: usize,
+    /// Hex-encoded hash of the latest version (see [`delta::hash_map`]).
+    #[serde(default)]
+    pub latest_hash: String,
+    /// Tags set on the remote registry entry.
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 impl RemoteSync {
+    /// Remote registry endpoint URL.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
     /// Create a new remote sync client.
     pub fn new(endpoint: &str, instance_id: &str, api_key: Option<String>) -> Self {
         Self {
             endpoint: endpoint.trim_end_matches('/').to_string(),
             instance_id: instance_id.to_string(),
             api_key,
-            client: reqwest::Client::new(),
+            client: crate::acquisition::http_client::shared_client(),
+            signer: None,
+            verify: None,
         }
     }
 
+    /// Sign everything pushed with `signer`.
+    pub fn with_signer(mut self, signer: Arc<MapSigner>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Verify signatures on pulled maps.
+    pub fn with_verification(mut self, verify: PullVerification) -> Self {
+        self.verify = Some(verify);
+        self
+    }
+
     /// Push a delta to the remote registry.
-    pub async fn push_delta(&self, domain: &str, delta_data: &MapDelta) -> Result<()> {
+    ///
+    /// `result` is the map obtained by applying the delta to its base (see
+    /// [`delta::apply_delta`]). If given, the remote verifies that applying
+    /// the delta reproduces it and rejects the push otherwise. With a signer
+    /// configured, both the delta and the resulting map are signed.
+    pub async fn push_delta(
+        &self,
+        domain: &str,
+        delta_data: &MapDelta,
+        result: Option<&SiteMap>,
+    ) -> Result<()> {
         let url = format!("{}/v1/maps/{}/deltas", self.endpoint, domain);
         let body = delta::serialize_delta(delta_data);
 
@@ -51,14 +125,25 @@ impl RemoteSync {
             .post(&url)
             .header("Content-Type", "application/octet-stream")
             .header("X-Cortex-Instance", &self.instance_id)
-            .header("X-Cortex-Base-Hash", &hash_hex)
-            .body(body);
+            .header("X-Cortex-Base-Hash", &hash_hex);
 
-        if let Some(ref key) = self.api_key {
-            req = req.header("Authorization", format!("Bearer {key}"));
+        if let Some(signer) = &self.signer {
+            let settings = serde_json::json!({ "kind": "delta", "domain": domain });
+            let envelope = signer.sign(&body, delta_data.timestamp, settings)?;
+            req = req.header(SIGNATURE_HEADER, envelope.to_header());
+        }
+        if let Some(map) = result {
+            req = req.header("X-Cortex-Result-Hash", hex_encode(&delta::hash_map(map)));
+            if let Some(signer) = &self.signer {
+                req = req.header(MAP_SIGNATURE_HEADER, sign_map(signer, map)?.to_header());
+            }
         }
 
-        let resp = req.send().await.context("pushing delta to remote")?;
+        let resp = self
+            .authed(req.body(body))
+            .send()
+            .await
+            .context("pushing delta to remote")?;
         if !resp.status().is_success() {
             anyhow::bail!(
                 "remote push failed: {} {}",
@@ -70,13 +155,70 @@ impl RemoteSync {
         Ok(())
     }
 
+    /// Push a full map to the remote registry, replacing whatever it holds.
+    pub async fn push_map(&self, domain: &str, map: &SiteMap) -> Result<()> {
+        self.put_map(domain, map, None).await.map(|_| ())
+    }
+
+    /// Push a full map only if the remote is still at `base` (see
+    /// [`delta::hash_map`]). Returns `false` if the remote has moved on, in
+    /// which case the caller should pull, merge and retry.
+    pub async fn push_map_onto(
+        &self,
+        domain: &str,
+        map: &SiteMap,
+        base: &[u8; 32],
+    ) -> Result<bool> {
+        self.put_map(domain, map, Some(base)).await
+    }
+
+    async fn put_map(&self, domain: &str, map: &SiteMap, base: Option<&[u8; 32]>) -> Result<bool> {
+        let url = format!("{}/v1/maps/{}", self.endpoint, domain);
+
+        let mut req = self
+            .client
+            .put(&url)
+            .header("Content-Type", "application/octet-stream")
+            .header("X-Cortex-Instance", &self.instance_id);
+        if let Some(base) = base {
+            req = req.header("X-Cortex-Base-Hash", hex_encode(base));
+        }
+        if let Some(signer) = &self.signer {
+            req = req.header(SIGNATURE_HEADER, sign_map(signer, map)?.to_header());
+        }
+        let req = req.body(map.serialize());
+
+        let resp = self
+            .authed(req)
+            .send()
+            .await
+            .context("pushing map to remote")?;
+        if base.is_some() && resp.status() == reqwest::StatusCode::CONFLICT {
+            return Ok(false);
+        }
+        if !resp.status().is_success() {
+            anyhow::bail!(
+                "remote push failed: {} {}",
+                resp.status(),
+                resp.text().await.unwrap_or_default()
+            );
+        }
+
+        Ok(true)
+    }
+
     /// Pull the latest map from the remote registry.
     pub async fn pull_map(&self, domain: &str) -> Result<Option<SiteMap>> {
+        Ok(self.pull_signed_map(domain).await?.map(|p| p.map))
+    }
+
+    /// Pull the latest map with its signature, enforcing the configured
+    /// verification policy. Tampered or untrusted maps are rejected.
+    pub async fn pull_signed_map(&self, domain: &str) -> Result<Option<PulledMap>> {
         let url = format!("{}/v1/maps/{}", self.endpoint, domain);
 
         let resp = self
-            .client
-            .get(&url)
+            .authed(self.client.get(&url))
             .send()
             .await
             .context("pulling map from remote")?;
@@ -89,21 +231,60 @@ impl RemoteSync {
             anyhow::bail!("remote pull failed: {}", resp.status());
         }
 
+        let signature = resp
+            .headers()
+            .get(SIGNATURE_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(SignedEnvelope::from_header)
+            .transpose()?;
         let bytes = resp.bytes().await?;
+
+        let mut publisher = None;
+        if let Some(policy) = &self.verify {
+            match &signature {
+                Some(envelope) => {
+                    // A tampered map is rejected whatever the trust policy.
+                    envelope
+                        .verify(&bytes)
+                        .with_context(|| format!("rejecting map for {domain}"))?;
+                    match policy.trusted.find(&envelope.provenance.public_key) {
+                        Some(key) => publisher = Some(key.name.clone()),
+                        None if policy.allow_unsigned => {}
+                        None => anyhow::bail!(
+                            "rejecting map for {domain}: signer key {} is not in the trusted key list",
+                            envelope.provenance.public_key
+                        ),
+                    }
+                }
+                None if policy.allow_unsigned => {}
+                None => anyhow::bail!("rejecting map for {domain}: map is not signed"),
+            }
+        }
+
         let map = SiteMap::deserialize(&bytes)?;
-        Ok(Some(map))
+        // A remote could serve one site's map under another's name.
+        if map.header.domain != domain {
+            anyhow::bail!(
+                "rejecting map for {domain}: map is for {}",
+                map.header.domain
+            );
+        }
+        Ok(Some(PulledMap {
+            map,
+            signature,
+            publisher,
+        }))
     }
 
     /// Pull deltas since a given timestamp.
     pub async fn pull_since(&self, domain: &str, since: DateTime<Utc>) -> Result<Vec<MapDelta>> {
-        let url = format!(
-            "{}/v1/maps/{}/deltas?since={}",
-            self.endpoint,
-            domain,
-            since.to_rfc3339()
-        );
+        let url = format!("{}/v1/maps/{}/deltas", self.endpoint, domain);
 
-        let resp = self.client.get(&url).send().await?;
+        let req = self
+            .client
+            .get(&url)
+            .query(&[("since", since.to_rfc3339())]);
+        let resp = self.authed(req).send().await?;
         if !resp.status().is_success() {
             return Ok(Vec::new());
         }
@@ -117,15 +298,271 @@ impl RemoteSync {
     /// List all available domains on the remote registry.
     pub async fn list_available(&self) -> Result<Vec<RemoteEntry>> {
         let url = format!("{}/v1/maps", self.endpoint);
-        let resp = self.client.get(&url).send().await?;
+        let resp = self
+            .authed(self.client.get(&url))
+            .send()
+            .await
+            .context("listing remote registry")?;
 
         if !resp.status().is_success() {
-            return Ok(Vec::new());
+            anyhow::bail!("remote list failed: {}", resp.status());
         }
 
         let entries: Vec<RemoteEntry> = resp.json().await.unwrap_or_default();
         Ok(entries)
     }
+
+    /// Attach the bearer token, if configured.
+    fn authed(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.api_key {
+            Some(key) => req.header("Authorization", format!("Bearer {key}")),
+            None => req,
+        }
+    }
+}
+
+/// Sign a map's serialized bytes, recording its header as provenance settings.
+pub fn sign_map(signer: &MapSigner, map: &SiteMap) -> Result<SignedEnvelope> {
+    let mapped_at = Utc
+        .timestamp_opt(map.header.mapped_at as i64, 0)
+        .single()
+        .unwrap_or_else(Utc::now);
+    let settings = serde_json::json!({
+        "kind": "map",
+        "domain": map.header.domain,
+        "format_version": map.header.format_version,
+        "node_count": map.header.node_count,
+        "edge_count": map.header.edge_count,
+        "flags": map.header.flags,
+    });
+    signer.sign(&map.serialize(), mapped_at, settings)
+}
+
+/// What happened to one domain when pulling it into the local registry.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum DomainSync {
+    /// The domain was new locally and was stored as-is.
+    Added,
+    /// The remote map was merged into ours.
+    Merged { conflicts: usize },
+    /// Nothing new: same map, or the remote is behind us.
+    UpToDate,
+}
+
+/// Whether `registry` already holds the version a remote entry advertises.
+pub fn has_version(registry: &LocalRegistry, entry: &RemoteEntry) -> bool {
+    registry.entry(&entry.domain).is_some_and(|e| {
+        e.versions
+            .iter()
+            .any(|v| hex_encode(&v.map_hash) == entry.latest_hash)
+    })
+}
+
+/// Store a pulled map, merging it with the local version if there is one
+/// (see [`delta::merge_maps`]).
+pub fn merge_pulled(
+    registry: &mut LocalRegistry,
+    domain: &str,
+    pulled: PulledMap,
+    instance_id: &str,
+) -> Result<DomainSync> {
+    let Some((ours, _)) = registry.pull(domain)? else {
+        registry.push_signed(domain, &pulled.map, None, pulled.signature, None)?;
+        return Ok(DomainSync::Added);
+    };
+    let (merged, report) = delta::merge_maps(&ours, &pulled.map);
+    if delta::hash_map(&merged) == delta::hash_map(&ours) {
+        return Ok(DomainSync::UpToDate);
+    }
+    let d = delta::compute_delta(&ours, &merged, instance_id);
+    registry.push(domain, &merged, Some(d))?;
+    Ok(DomainSync::Merged {
+        conflicts: report.conflicts.len(),
+    })
+}
+
+/// Default seconds between syncs of a subscription.
+pub const DEFAULT_SYNC_INTERVAL_SECS: u64 = 3600;
+
+/// A standing subscription to maps on a remote registry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subscription {
+    /// Unique subscription name.
+    pub name: String,
+    /// Remote registry URL.
+    pub remote: String,
+    /// Domains to pull.
+    #[serde(default)]
+    pub domains: Vec<String>,
+    /// Also pull any domain carrying one of these tags. With no domains and
+    /// no tags, every domain on the remote matches.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// WQL query selecting the nodes to keep, e.g. `SELECT * FROM Product`.
+    #[serde(default)]
+    pub filter: Option<String>,
+    /// Seconds between syncs.
+    pub interval_secs: u64,
+    /// When the subscription last synced successfully.
+    #[serde(default)]
+    pub last_synced: Option<DateTime<Utc>>,
+    /// Remote map hash last pulled, per domain.
+    #[serde(default)]
+    pub seen: HashMap<String, String>,
+}
+
+impl Subscription {
+    /// Subscribe to everything on `remote`, synced hourly.
+    pub fn new(name: &str, remote: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            remote: remote.trim_end_matches('/').to_string(),
+            domains: Vec::new(),
+            tags: Vec::new(),
+            filter: None,
+            interval_secs: DEFAULT_SYNC_INTERVAL_SECS,
+            last_synced: None,
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Check that the filter parses and the interval is usable.
+    pub fn validate(&self) -> Result<()> {
+        if self.interval_secs == 0 {
+            anyhow::bail!("subscription {}: interval must be positive", self.name);
+        }
+        if let Some(filter) = &self.filter {
+            crate::wql::parser::parse(filter)
+                .with_context(|| format!("subscription {}: invalid filter", self.name))?;
+        }
+        Ok(())
+    }
+
+    /// Whether a remote entry is covered by this subscription.
+    pub fn matches(&self, entry: &RemoteEntry) -> bool {
+        if self.domains.is_empty() && self.tags.is_empty() {
+            return true;
+        }
+        self.domains.contains(&entry.domain) || entry.tags.iter().any(|t| self.tags.contains(t))
+    }
+
+    /// When the next sync is due.
+    pub fn next_due(&self) -> DateTime<Utc> {
+        match self.last_synced {
+            Some(last) => last + chrono::Duration::seconds(self.interval_secs as i64),
+            None => DateTime::<Utc>::UNIX_EPOCH,
+        }
+    }
+
+    /// Whether a sync is due at `now`.
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.next_due() <= now
+    }
+}
+
+/// The set of configured subscriptions.
+#[derive(Debug, Clone, Default)]
+pub struct Subscriptions {
+    subs: Vec<Subscription>,
+}
+
+impl Subscriptions {
+    /// Load subscriptions from a JSON file (a list). Missing file = none.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = std::fs::read_to_string(path)?;
+        let subs: Vec<Subscription> = serde_json::from_str(&data)
+            .with_context(|| format!("parsing subscriptions: {}", path.display()))?;
+        Ok(Self { subs })
+    }
+
+    /// Load subscriptions from the default location (~/.cortex/subscriptions.json).
+    pub fn load_default() -> Result<Self> {
+        Self::load(&subscriptions_path())
+    }
+
+    /// Save subscriptions as a JSON list.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(&self.subs)?)?;
+        Ok(())
+    }
+
+    /// Add or replace a subscription by name.
+    pub fn add(&mut self, sub: Subscription) -> Result<()> {
+        sub.validate()?;
+        self.subs.retain(|s| s.name != sub.name);
+        self.subs.push(sub);
+        self.subs.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(())
+    }
+
+    /// Remove a subscription. Returns true if it existed.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.subs.len();
+        self.subs.retain(|s| s.name != name);
+        self.subs.len() != before
+    }
+
+    /// All subscriptions, sorted by name.
+    pub fn all(&self) -> &[Subscription] {
+        &self.subs
+    }
+
+    /// Mutable access to all subscriptions.
+    pub fn all_mut(&mut self) -> &mut [Subscription] {
+        &mut self.subs
+    }
+}
+
+/// Default subscriptions file location.
+pub fn subscriptions_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join(".cortex")
+        .join("subscriptions.json")
+}
+
+/// Pull every domain a subscription covers from `client` into `registry`,
+/// applying its node filter. Each map is pulled whole and verified under
+/// `client`'s policy before the filter slices it. Updates the
+/// subscription's sync state.
+pub async fn sync_subscription(
+    registry: &mut LocalRegistry,
+    sub: &mut Subscription,
+    client: &RemoteSync,
+) -> Result<Vec<(String, DomainSync)>> {
+    let mut results = Vec::new();
+    for entry in client.list_available().await? {
+        if !sub.matches(&entry) {
+            continue;
+        }
+        let unchanged = sub.seen.get(&entry.domain) == Some(&entry.latest_hash)
+            || (sub.filter.is_none() && has_version(registry, &entry));
+        if unchanged {
+            results.push((entry.domain, DomainSync::UpToDate));
+            continue;
+        }
+
+        let Some(mut pulled) = client.pull_signed_map(&entry.domain).await? else {
+            continue;
+        };
+        if let Some(filter) = &sub.filter {
+            pulled.map = pulled.map.slice_wql(filter)?;
+            // The publisher signed the full map, not our subset.
+            pulled.signature = None;
+        }
+        let outcome = merge_pulled(registry, &entry.domain, pulled, &client.instance_id)?;
+        sub.seen.insert(entry.domain.clone(), entry.latest_hash);
+        results.push((entry.domain, outcome));
+    }
+    sub.last_synced = Some(Utc::now());
+    Ok(results)
 }
 
 /// Merkle node for efficient sync between registries.
@@ -205,7 +642,7 @@ fn combine_hashes(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
 }
 
 /// Encode bytes as hex string.
-fn hex_encode(bytes: &[u8]) -> String {
+pub(crate) fn hex_encode(bytes: &[u8]) -> String {
     bytes.iter().map(|b| format!("{b:02x}")).collect()
 }
 
@@ -280,4 +717,150 @@ mod tests {
         assert_eq!(tree.domain_range.0, "x.com");
         assert!(tree.children.is_none());
     }
+
+    fn entry(domain: &str, tags: &[&str]) -> RemoteEntry {
+        RemoteEntry {
+            domain: domain.to_string(),
+            latest_timestamp: Utc::now(),
+            node_count: 0,
+            latest_hash: String::new(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    fn shop_map() -> SiteMap {
+        use crate::map::builder::SiteMapBuilder;
+        use crate::map::types::{EdgeFlags, EdgeType, PageType, FEATURE_DIM};
+        let mut builder = SiteMapBuilder::new("shop.com");
+        let feats = [0.0f32; FEATURE_DIM];
+        let home = builder.add_node("https://shop.com/", PageType::Home, feats, 200);
+        let p1 = builder.add_node("https://shop.com/p/1", PageType::ProductDetail, feats, 200);
+        let p2 = builder.add_node("https://shop.com/p/2", PageType::ProductDetail, feats, 200);
+        builder.add_edge(home, p1, EdgeType::Navigation, 1, EdgeFlags::default());
+        builder.add_edge(p1, p2, EdgeType::Related, 1, EdgeFlags::default());
+        builder.build()
+    }
+
+    #[test]
+    fn test_subscription_matching_and_schedule() {
+        let mut sub = Subscription::new("retail", "http://registry:7710/");
+        assert_eq!(sub.remote, "http://registry:7710");
+        assert!(sub.matches(&entry("any.com", &[])));
+
+        sub.domains = vec!["shop.com".into()];
+        sub.tags = vec!["retail".into()];
+        assert!(sub.matches(&entry("shop.com", &[])));
+        assert!(sub.matches(&entry("other.com", &["retail"])));
+        assert!(!sub.matches(&entry("news.com", &["media"])));
+
+        let now = Utc::now();
+        assert!(sub.is_due(now));
+        sub.last_synced = Some(now);
+        assert!(!sub.is_due(now + chrono::Duration::seconds(60)));
+        assert!(sub.is_due(now + chrono::Duration::seconds(3600)));
+
+        sub.filter = Some("SELECT FROM".into());
+        assert!(sub.validate().is_err());
+    }
+
+    #[test]
+    fn test_filter_map_keeps_matching_subgraph() {
+        let subset = shop_map().slice_wql("SELECT * FROM Product").unwrap();
+        assert_eq!(
+            subset.urls,
+            vec!["https://shop.com/p/1", "https://shop.com/p/2"]
+        );
+        // Only the edge between the two kept products survives.
+        assert_eq!(subset.edges.len(), 1);
+        assert_eq!(subset.edges[0].target_node, 1);
+    }
+
+    #[tokio::test]
+    async fn test_sync_subscription_pulls_filtered_subset() {
+        use crate::collective::registry_server::{router, RegistryServerState};
+
+        let remote_dir = tempfile::TempDir::new().unwrap();
+        let mut remote = LocalRegistry::new(remote_dir.path().to_path_buf()).unwrap();
+        remote.push("shop.com", &shop_map(), None).unwrap();
+        remote.set_tags("shop.com", vec!["retail".into()]).unwrap();
+        let state = std::sync::Arc::new(RegistryServerState::new(remote, Vec::new()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router(state)).await });
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut local = LocalRegistry::new(dir.path().to_path_buf()).unwrap();
+        let mut sub = Subscription::new("retail", &format!("http://{addr}"));
+        sub.tags = vec!["retail".into()];
+        sub.filter = Some("SELECT * FROM Product".into());
+        let client = RemoteSync::new(&sub.remote, "test", None);
+
+        let results = sync_subscription(&mut local, &mut sub, &client)
+            .await
+            .unwrap();
+        assert_eq!(results, vec![("shop.com".to_string(), DomainSync::Added)]);
+        let (map, _) = local.pull("shop.com").unwrap().unwrap();
+        assert_eq!(map.urls.len(), 2);
+        assert!(sub.last_synced.is_some());
+
+        // Unchanged remote: nothing is downloaded again.
+        let results = sync_subscription(&mut local, &mut sub, &client)
+            .await
+            .unwrap();
+        assert_eq!(results[0].1, DomainSync::UpToDate);
+    }
+
+    #[tokio::test]
+    async fn test_sync_subscription_verifies_before_filtering() {
+        use crate::collective::registry_server::{router, RegistryServerState};
+        use crate::trust::signing::MapSigner;
+        use std::sync::Arc;
+
+        let remote_dir = tempfile::TempDir::new().unwrap();
+        let remote = LocalRegistry::new(remote_dir.path().to_path_buf()).unwrap();
+        let state = Arc::new(RegistryServerState::new(remote, vec!["secret".into()]));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router(state)).await });
+        let url = format!("http://{addr}");
+
+        let alice = Arc::new(MapSigner::generate("alice"));
+        let mut trusted = TrustedKeys::default();
+        trusted.add("alice", &alice.public_key()).unwrap();
+        let client = RemoteSync::new(&url, "test", Some("secret".into())).with_verification(
+            PullVerification {
+                trusted,
+                allow_unsigned: false,
+            },
+        );
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut local = LocalRegistry::new(dir.path().to_path_buf()).unwrap();
+        let mut sub = Subscription::new("retail", &url);
+        sub.filter = Some("SELECT * FROM Product".into());
+
+        // An unsigned map is rejected even though only a subset is kept.
+        RemoteSync::new(&url, "test", Some("secret".into()))
+            .push_map("shop.com", &shop_map())
+            .await
+            .unwrap();
+        let err = sync_subscription(&mut local, &mut sub, &client)
+            .await
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("not signed"), "{err:#}");
+        assert!(local.pull("shop.com").unwrap().is_none());
+
+        // Signed by a trusted key, the map is verified, then sliced.
+        RemoteSync::new(&url, "test", Some("secret".into()))
+            .with_signer(alice)
+            .push_map("shop.com", &shop_map())
+            .await
+            .unwrap();
+        let results = sync_subscription(&mut local, &mut sub, &client)
+            .await
+            .unwrap();
+        assert_eq!(results, vec![("shop.com".to_string(), DomainSync::Added)]);
+        let (map, _) = local.pull("shop.com").unwrap().unwrap();
+        assert_eq!(map.urls.len(), 2);
+    }
 }
@@ -0,0 +1,567 @@
+//! Distributed mapping — share page fetching and rendering across machines.
+//!
+//! A coordinator (`cortex start --coordinator <addr>`) serves a job queue.
+//! Workers (`cortex worker --join <url>`) long-poll it for batches of URLs to
+//! fetch or pages to render, and post the results back. Everything else —
+//! discovery, sampling, extraction, content policy, building the SiteMap —
+//! stays on the coordinator, so a map built with workers is assembled exactly
+//! like one built alone.
+//!
+//! | Method | Path | Description |
+//! |--------|------|-------------|
+//! | POST | `/v1/workers` | Join; returns `{worker_id}` |
+//! | POST | `/v1/workers/:id/lease` | Wait up to [`LEASE_POLL_SECS`] for a job (204 if none) |
+//! | POST | `/v1/workers/:id/jobs/:job` | Post a job's result |
+//!
+//! Every request needs `Authorization: Bearer <token>`. Jobs that no worker
+//! finishes in time are done by the coordinator itself.
+
+use crate::acquisition::http_client::{shared_client, HttpClient, HttpResponse};
+use crate::acquisition::profiles::CrawlProfile;
+use crate::cancel::CancellationToken;
+use crate::cartography::mapper::{BrowserRenderedPage, Mapper};
+use crate::collective::registry_server::constant_time_eq;
+use anyhow::{anyhow, bail, Context, Result};
+use axum::extract::{DefaultBodyLimit, Path, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{oneshot, Notify};
+use tracing::{info, warn};
+
+/// How long a lease request waits for a job before answering 204.
+pub const LEASE_POLL_SECS: u64 = 20;
+/// A worker that has not polled for this long no longer gets shards.
+const WORKER_TIMEOUT: Duration = Duration::from_secs(LEASE_POLL_SECS + 15);
+/// Largest result a worker may post; fetched pages are sent in full.
+const MAX_RESULT_BYTES: usize = 256 * 1024 * 1024;
+/// Concurrent requests per fetch job, as for a local fetch.
+const FETCH_CONCURRENCY: usize = 20;
+
+/// Work handed to a worker.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Task {
+    /// HTTP GET each URL.
+    Fetch {
+        urls: Vec<String>,
+        timeout_ms: u64,
+        /// Identity to fetch with, if not the default.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        profile: Option<CrawlProfile>,
+    },
+    /// Render one page in the worker's browser and run the extractors.
+    Render {
+        url: String,
+        /// Identity to render with, if not the default.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        profile: Option<CrawlProfile>,
+    },
+}
+
+/// A leased unit of work.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Job {
+    pub id: u64,
+    #[serde(flatten)]
+    pub task: Task,
+}
+
+/// A worker's result for one job.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobOutput {
+    /// Successful responses of a `fetch` job; failed URLs are left out.
+    Fetch { responses: Vec<HttpResponse> },
+    /// The rendered page of a `render` job.
+    Render { page: Box<BrowserRenderedPage> },
+    /// The job could not be done.
+    Failed { error: String },
+}
+
+struct WorkerInfo {
+    render: bool,
+    last_seen: Instant,
+}
+
+#[derive(Default)]
+struct Queue {
+    next_job: u64,
+    workers: HashMap<String, WorkerInfo>,
+    jobs: VecDeque<Job>,
+    waiting: HashMap<u64, oneshot::Sender<JobOutput>>,
+}
+
+/// Coordinator side: the workers that joined and the jobs waiting for them.
+pub struct WorkerPool {
+    tokens: Vec<String>,
+    queue: Mutex<Queue>,
+    job_ready: Notify,
+}
+
+impl WorkerPool {
+    /// Accept workers presenting any of `tokens`. With no tokens every
+    /// request is refused.
+    pub fn new(tokens: Vec<String>) -> Self {
+        Self {
+            tokens,
+            queue: Mutex::new(Queue::default()),
+            job_ready: Notify::new(),
+        }
+    }
+
+    /// Workers that polled recently, and how many of them can render.
+    pub fn active(&self) -> (usize, usize) {
+        let queue = self.queue.lock().unwrap();
+        let live: Vec<&WorkerInfo> = queue
+            .workers
+            .values()
+            .filter(|w| w.last_seen.elapsed() < WORKER_TIMEOUT)
+            .collect();
+        (live.len(), live.iter().filter(|w| w.render).count())
+    }
+
+    /// GET `urls`, one shard per active worker plus one fetched by `local`,
+    /// all with `local`'s profile. Shards a worker does not return in time
+    /// are fetched locally.
+    pub async fn get_many(
+        &self,
+        local: &HttpClient,
+        urls: &[String],
+        timeout_ms: u64,
+    ) -> Vec<HttpResponse> {
+        let (workers, _) = self.active();
+        let shard_len = urls.len().div_ceil(workers + 1).max(1);
+        let mut shards = urls.chunks(shard_len);
+        let own = shards.next().unwrap_or_default();
+        let wait = Duration::from_millis(timeout_ms * 2);
+
+        let remote = futures::future::join_all(shards.map(|shard| async move {
+            let task = Task::Fetch {
+                urls: shard.to_vec(),
+                timeout_ms,
+                profile: local.profile().cloned(),
+            };
+            match self.dispatch(task, wait).await {
+                Some(JobOutput::Fetch { responses }) => responses,
+                other => {
+                    if let Some(JobOutput::Failed { error }) = other {
+                        warn!("worker fetch failed: {error}");
+                    }
+                    warn!("fetching {} URLs locally instead", shard.len());
+                    flatten(local.get_many(shard, FETCH_CONCURRENCY, timeout_ms).await)
+                }
+            }
+        }));
+        let (own, remote) =
+            tokio::join!(local.get_many(own, FETCH_CONCURRENCY, timeout_ms), remote);
+        flatten(own)
+            .into_iter()
+            .chain(remote.into_iter().flatten())
+            .collect()
+    }
+
+    /// Render `url` on a worker with a browser, waiting at most `wait`.
+    /// `None` if no such worker is active or none finished in time.
+    pub async fn render(
+        &self,
+        url: &str,
+        profile: &CrawlProfile,
+        wait: Duration,
+    ) -> Option<Result<BrowserRenderedPage>> {
+        if self.active().1 == 0 {
+            return None;
+        }
+        let task = Task::Render {
+            url: url.to_string(),
+            profile: Some(profile.clone()),
+        };
+        match self.dispatch(task, wait).await? {
+            JobOutput::Render { page } => Some(Ok(*page)),
+            JobOutput::Failed { error } => Some(Err(anyhow!(error))),
+            JobOutput::Fetch { .. } => None,
+        }
+    }
+
+    /// Queue `task` and wait up to `wait` for a worker's result.
+    async fn dispatch(&self, task: Task, wait: Duration) -> Option<JobOutput> {
+        let (tx, rx) = oneshot::channel();
+        let id = {
+            let mut queue = self.queue.lock().unwrap();
+            queue.next_job += 1;
+            let id = queue.next_job;
+            queue.jobs.push_back(Job { id, task });
+            queue.waiting.insert(id, tx);
+            id
+        };
+        self.job_ready.notify_waiters();
+
+        let output = tokio::time::timeout(wait, rx)
+            .await
+            .ok()
+            .and_then(|r| r.ok());
+        if output.is_none() {
+            let mut queue = self.queue.lock().unwrap();
+            queue.jobs.retain(|j| j.id != id);
+            queue.waiting.remove(&id);
+        }
+        output
+    }
+
+    /// Take the next job `worker` can do. `None` if the worker is unknown.
+    fn take_job(&self, worker: &str) -> Option<Option<Job>> {
+        let mut queue = self.queue.lock().unwrap();
+        let info = queue.workers.get_mut(worker)?;
+        info.last_seen = Instant::now();
+        let render = info.render;
+        let pos = queue
+            .jobs
+            .iter()
+            .position(|j| render || !matches!(j.task, Task::Render { .. }));
+        Some(pos.and_then(|p| queue.jobs.remove(p)))
+    }
+
+    /// Check the bearer token, returning the rejection response if not allowed.
+    fn reject(&self, headers: &HeaderMap) -> Option<Response> {
+        let presented = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .unwrap_or("");
+        if self
+            .tokens
+            .iter()
+            .any(|t| constant_time_eq(t.as_bytes(), presented.as_bytes()))
+        {
+            None
+        } else {
+            Some(error(StatusCode::UNAUTHORIZED, "missing or invalid token"))
+        }
+    }
+}
+
+fn flatten(responses: Vec<Result<HttpResponse>>) -> Vec<HttpResponse> {
+    responses.into_iter().flatten().collect()
+}
+
+/// Build the coordinator's HTTP router.
+pub fn router(pool: Arc<WorkerPool>) -> Router {
+    Router::new()
+        .route("/v1/workers", post(join))
+        .route("/v1/workers/:id/lease", post(lease))
+        .route("/v1/workers/:id/jobs/:job", post(complete))
+        .layer(DefaultBodyLimit::max(MAX_RESULT_BYTES))
+        .with_state(pool)
+}
+
+/// Serve the job queue on `addr` until the process exits.
+pub async fn serve(addr: std::net::SocketAddr, pool: Arc<WorkerPool>) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!("coordinator listening for workers on http://{addr}");
+    axum::serve(listener, router(pool)).await?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct JoinRequest {
+    name: String,
+    #[serde(default)]
+    render: bool,
+}
+
+async fn join(
+    State(pool): State<Arc<WorkerPool>>,
+    headers: HeaderMap,
+    Json(req): Json<JoinRequest>,
+) -> Response {
+    if let Some(resp) = pool.reject(&headers) {
+        return resp;
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    info!(
+        "worker {} joined as {id} ({})",
+        req.name,
+        if req.render {
+            "fetch + render"
+        } else {
+            "fetch"
+        }
+    );
+    pool.queue.lock().unwrap().workers.insert(
+        id.clone(),
+        WorkerInfo {
+            render: req.render,
+            last_seen: Instant::now(),
+        },
+    );
+    Json(serde_json::json!({ "worker_id": id })).into_response()
+}
+
+async fn lease(
+    State(pool): State<Arc<WorkerPool>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    if let Some(resp) = pool.reject(&headers) {
+        return resp;
+    }
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(LEASE_POLL_SECS);
+    loop {
+        // Register for the wakeup before looking, so a job queued in
+        // between is not missed.
+        let notified = pool.job_ready.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+        match pool.take_job(&id) {
+            None => return error(StatusCode::NOT_FOUND, "unknown worker; join again"),
+            Some(Some(job)) => return Json(job).into_response(),
+            Some(None) => {}
+        }
+        if tokio::time::timeout_at(deadline, notified).await.is_err() {
+            return StatusCode::NO_CONTENT.into_response();
+        }
+    }
+}
+
+async fn complete(
+    State(pool): State<Arc<WorkerPool>>,
+    Path((_worker, job)): Path<(String, u64)>,
+    headers: HeaderMap,
+    Json(output): Json<JobOutput>,
+) -> Response {
+    if let Some(resp) = pool.reject(&headers) {
+        return resp;
+    }
+    let waiting = pool.queue.lock().unwrap().waiting.remove(&job);
+    match waiting {
+        Some(tx) => {
+            let _ = tx.send(output);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        None => error(StatusCode::GONE, "job expired"),
+    }
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(serde_json::json!({ "error": message }))).into_response()
+}
+
+/// Worker side: a connection to a coordinator.
+pub struct WorkerClient {
+    endpoint: String,
+    token: String,
+    name: String,
+    render: bool,
+    client: reqwest::Client,
+}
+
+impl WorkerClient {
+    /// Connect to the coordinator at `endpoint` as `name`. `render` says
+    /// whether this worker has a browser for render jobs.
+    pub fn new(endpoint: &str, token: &str, name: &str, render: bool) -> Self {
+        Self {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+            name: name.to_string(),
+            render,
+            client: shared_client(),
+        }
+    }
+
+    /// Join the coordinator, returning this worker's id.
+    pub async fn join(&self) -> Result<String> {
+        let resp = self
+            .client
+            .post(format!("{}/v1/workers", self.endpoint))
+            .bearer_auth(&self.token)
+            .json(&serde_json::json!({ "name": self.name, "render": self.render }))
+            .send()
+            .await
+            .with_context(|| format!("connecting to coordinator {}", self.endpoint))?;
+        if !resp.status().is_success() {
+            bail!("coordinator refused to join: {}", resp.status());
+        }
+        let body: serde_json::Value = resp.json().await?;
+        body.get("worker_id")
+            .and_then(|v| v.as_str())
+            .map(String::from)
+            .context("coordinator returned no worker_id")
+    }
+
+    /// Wait for the next job; `None` if the coordinator had none.
+    pub async fn lease(&self, worker_id: &str) -> Result<Option<Job>> {
+        let resp = self
+            .client
+            .post(format!("{}/v1/workers/{worker_id}/lease", self.endpoint))
+            .bearer_auth(&self.token)
+            .timeout(Duration::from_secs(LEASE_POLL_SECS + 10))
+            .send()
+            .await
+            .context("polling coordinator")?;
+        match resp.status() {
+            StatusCode::NO_CONTENT => Ok(None),
+            s if s.is_success() => Ok(Some(resp.json().await?)),
+            s => bail!("lease failed: {s}"),
+        }
+    }
+
+    /// Post the result of job `job_id`.
+    pub async fn complete(&self, worker_id: &str, job_id: u64, output: &JobOutput) -> Result<()> {
+        let resp = self
+            .client
+            .post(format!(
+                "{}/v1/workers/{worker_id}/jobs/{job_id}",
+                self.endpoint
+            ))
+            .bearer_auth(&self.token)
+            .json(output)
+            .send()
+            .await
+            .context("posting job result")?;
+        if resp.status() == StatusCode::GONE {
+            bail!("job {job_id} expired before it finished");
+        }
+        if !resp.status().is_success() {
+            bail!("posting job result failed: {}", resp.status());
+        }
+        Ok(())
+    }
+
+    /// Take and do jobs until the process exits, re-joining if the
+    /// coordinator restarts or the connection drops. Fails only if the
+    /// first join does.
+    pub async fn run(&self, mapper: &Mapper) -> Result<()> {
+        let mut worker_id = self.join().await?;
+        info!("joined {} as worker {worker_id}", self.endpoint);
+        loop {
+            match self.lease(&worker_id).await {
+                Ok(Some(job)) => {
+                    let output = execute(mapper, job.task).await;
+                    if let Err(e) = self.complete(&worker_id, job.id, &output).await {
+                        warn!("{e:#}");
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    warn!("lost coordinator: {e:#}; re-joining in 5s");
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    match self.join().await {
+                        Ok(id) => worker_id = id,
+                        Err(e) => warn!("{e:#}"),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Do one job with this worker's `mapper`.
+pub async fn execute(mapper: &Mapper, task: Task) -> JobOutput {
+    match task {
+        Task::Fetch {
+            urls,
+            timeout_ms,
+            profile,
+        } => {
+            let mut client = HttpClient::new(timeout_ms);
+            if let Some(profile) = profile {
+                client = client.with_profile(profile);
+            }
+            JobOutput::Fetch {
+                responses: flatten(client.get_many(&urls, FETCH_CONCURRENCY, timeout_ms).await),
+            }
+        }
+        Task::Render { url, profile } => {
+            let profile = profile.unwrap_or_else(CrawlProfile::honest);
+            match mapper
+                .render_page(&url, &profile, &CancellationToken::new())
+                .await
+            {
+                Ok(page) => JobOutput::Render {
+                    page: Box::new(page),
+                },
+                Err(e) => JobOutput::Failed {
+                    error: format!("{e:#}"),
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(url: &str) -> HttpResponse {
+        HttpResponse {
+            url: url.to_string(),
+            final_url: url.to_string(),
+            status: 200,
+            headers: Vec::new(),
+            body: "<html></html>".to_string(),
+            mime: "text/html".to_string(),
+            truncated: false,
+            blocked: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_worker_fetches_a_shard() {
+        let pool = Arc::new(WorkerPool::new(vec!["t".into()]));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let state = pool.clone();
+        tokio::spawn(async move { axum::serve(listener, router(state)).await });
+        let endpoint = format!("http://{addr}");
+
+        assert!(WorkerClient::new(&endpoint, "wrong", "w", false)
+            .join()
+            .await
+            .is_err());
+        let worker = WorkerClient::new(&endpoint, "t", "w", false);
+        let id = worker.join().await.unwrap();
+        assert_eq!(pool.active(), (1, 0));
+        assert!(worker.lease("nobody").await.is_err());
+
+        // A fake worker that answers each fetch job with canned responses.
+        tokio::spawn(async move {
+            loop {
+                if let Some(job) = worker.lease(&id).await.unwrap() {
+                    let Task::Fetch { urls, .. } = job.task else {
+                        panic!("render job sent to a worker without a browser");
+                    };
+                    let responses = urls.iter().map(|u| response(u)).collect();
+                    let output = JobOutput::Fetch { responses };
+                    worker.complete(&id, job.id, &output).await.unwrap();
+                }
+            }
+        });
+
+        let task = Task::Fetch {
+            urls: vec!["https://shop.com/a".into()],
+            timeout_ms: 1000,
+            profile: None,
+        };
+        match pool.dispatch(task, Duration::from_secs(5)).await {
+            Some(JobOutput::Fetch { responses }) => {
+                assert_eq!(responses[0].final_url, "https://shop.com/a")
+            }
+            other => panic!("unexpected output: {other:?}"),
+        }
+
+        // No worker can render, so render jobs are not offered.
+        assert!(pool
+            .render(
+                "https://shop.com/a",
+                &CrawlProfile::honest(),
+                Duration::from_secs(1)
+            )
+            .await
+            .is_none());
+    }
+}
@@ -12,6 +12,7 @@ pub mod codegen_openapi;
 pub mod codegen_python;
 pub mod codegen_typescript;
 pub mod models;
+pub mod package;
 pub mod relationships;
 pub mod schema;
 pub mod unifier;
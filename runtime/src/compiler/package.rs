@@ -0,0 +1,507 @@
+//! Installable packages for compiled clients.
+//!
+//! Each target is laid out as something its ecosystem can install directly:
+//! a Python project with `pyproject.toml` (src layout, builds a wheel), an npm
+//! package that compiles to JavaScript with type declarations, and OpenAPI,
+//! GraphQL and MCP bundles. A `manifest.json` next to them records the schema
+//! version and hash, the compile settings, and every file written.
+
+use crate::compiler::codegen_graphql;
+use crate::compiler::codegen_mcp;
+use crate::compiler::codegen_openapi;
+use crate::compiler::codegen_python;
+use crate::compiler::codegen_typescript;
+use crate::compiler::models::*;
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+/// Version of the compiled schema format, bumped when generated clients
+/// change shape.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Version range of the Python runtime client generated code imports.
+const PYTHON_CLIENT_REQUIREMENT: &str = "cortex-agent>=1.0";
+
+/// npm package the generated TypeScript imports as `@cortex/client`.
+const TS_CLIENT_REQUIREMENT: &str = "npm:cortex-web-client@^1.0.0";
+
+/// A compile output target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Target {
+    Python,
+    Typescript,
+    Openapi,
+    Graphql,
+    Mcp,
+}
+
+impl Target {
+    /// Every target, in output order.
+    pub const ALL: [Target; 5] = [
+        Target::Python,
+        Target::Typescript,
+        Target::Openapi,
+        Target::Graphql,
+        Target::Mcp,
+    ];
+
+    /// Parse a target name (`python`, `typescript`/`ts`, `openapi`, `graphql`, `mcp`).
+    pub fn parse(s: &str) -> Result<Self> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "python" | "py" => Target::Python,
+            "typescript" | "ts" | "npm" => Target::Typescript,
+            "openapi" => Target::Openapi,
+            "graphql" | "gql" => Target::Graphql,
+            "mcp" => Target::Mcp,
+            other => bail!(
+                "unknown target '{other}' (expected python, typescript, openapi, graphql, mcp, all)"
+            ),
+        })
+    }
+
+    /// Lowercase name, also the package's directory.
+    pub fn name(self) -> &'static str {
+        match self {
+            Target::Python => "python",
+            Target::Typescript => "typescript",
+            Target::Openapi => "openapi",
+            Target::Graphql => "graphql",
+            Target::Mcp => "mcp",
+        }
+    }
+}
+
+/// Parse `--target` values. Empty or `all` selects every target.
+pub fn parse_targets(names: &[String]) -> Result<Vec<Target>> {
+    if names.is_empty() || names.iter().any(|n| n.eq_ignore_ascii_case("all")) {
+        return Ok(Target::ALL.to_vec());
+    }
+    let mut targets = names
+        .iter()
+        .map(|n| Target::parse(n))
+        .collect::<Result<Vec<_>>>()?;
+    targets.sort();
+    targets.dedup();
+    Ok(targets)
+}
+
+/// Settings a compile was run with, recorded in the manifest.
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageSettings {
+    pub targets: Vec<Target>,
+    /// Version given to every generated package.
+    pub version: String,
+}
+
+impl PackageSettings {
+    /// All targets, versioned by the compile date (`2026.3.14`).
+    pub fn new(targets: Vec<Target>, compiled_at: DateTime<Utc>) -> Self {
+        Self {
+            targets,
+            version: compiled_at.format("%Y.%-m.%-d").to_string(),
+        }
+    }
+}
+
+/// A generated package: its target, installable name and files (paths
+/// relative to the output directory).
+#[derive(Debug, Clone)]
+pub struct Package {
+    pub target: Target,
+    pub name: String,
+    pub files: Vec<GeneratedFile>,
+}
+
+/// A file listed in the manifest.
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestFile {
+    pub path: String,
+    pub bytes: usize,
+    pub sha256: String,
+}
+
+/// A package listed in the manifest.
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestPackage {
+    pub target: Target,
+    pub name: String,
+    pub dir: String,
+    pub files: Vec<ManifestFile>,
+}
+
+/// Contents of `manifest.json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageManifest {
+    pub schema_version: u32,
+    /// SHA-256 of the compiled schema, excluding its compile time. Unchanged
+    /// hashes mean regenerated packages are equivalent.
+    pub schema_hash: String,
+    pub cortex_version: String,
+    pub domain: String,
+    pub compiled_at: DateTime<Utc>,
+    pub settings: PackageSettings,
+    pub stats: SchemaStats,
+    pub packages: Vec<ManifestPackage>,
+}
+
+/// Build the selected packages in memory.
+pub fn build_packages(schema: &CompiledSchema, settings: &PackageSettings) -> Vec<Package> {
+    settings
+        .targets
+        .iter()
+        .map(|&target| match target {
+            Target::Python => python_package(schema, &settings.version),
+            Target::Typescript => typescript_package(schema, &settings.version),
+            Target::Openapi => single_file_package(
+                schema,
+                target,
+                "openapi.yaml",
+                codegen_openapi::generate_openapi(schema),
+            ),
+            Target::Graphql => single_file_package(
+                schema,
+                target,
+                "schema.graphql",
+                codegen_graphql::generate_graphql(schema),
+            ),
+            Target::Mcp => single_file_package(
+                schema,
+                target,
+                "mcp_tools.json",
+                codegen_mcp::generate_mcp(schema),
+            ),
+        })
+        .collect()
+}
+
+/// Write the selected packages and `manifest.json` under `output_dir`.
+pub fn write_packages(
+    schema: &CompiledSchema,
+    settings: &PackageSettings,
+    output_dir: &Path,
+) -> Result<PackageManifest> {
+    let packages = build_packages(schema, settings);
+    for file in packages.iter().flat_map(|p| &p.files) {
+        let path = output_dir.join(&file.filename);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, &file.content)?;
+    }
+    let manifest = manifest(schema, settings, &packages)?;
+    fs::create_dir_all(output_dir)?;
+    fs::write(
+        output_dir.join("manifest.json"),
+        serde_json::to_string_pretty(&manifest)?,
+    )?;
+    Ok(manifest)
+}
+
+/// Describe `packages` for `manifest.json`.
+pub fn manifest(
+    schema: &CompiledSchema,
+    settings: &PackageSettings,
+    packages: &[Package],
+) -> Result<PackageManifest> {
+    let mut hashed = serde_json::to_value(schema)?;
+    if let Some(obj) = hashed.as_object_mut() {
+        obj.remove("compiled_at");
+    }
+    Ok(PackageManifest {
+        schema_version: SCHEMA_VERSION,
+        schema_hash: sha256_hex(serde_json::to_string(&hashed)?.as_bytes()),
+        cortex_version: env!("CARGO_PKG_VERSION").to_string(),
+        domain: schema.domain.clone(),
+        compiled_at: schema.compiled_at,
+        settings: settings.clone(),
+        stats: schema.stats.clone(),
+        packages: packages
+            .iter()
+            .map(|p| ManifestPackage {
+                target: p.target,
+                name: p.name.clone(),
+                dir: p.target.name().to_string(),
+                files: p
+                    .files
+                    .iter()
+                    .map(|f| ManifestFile {
+                        path: f.filename.clone(),
+                        bytes: f.size,
+                        sha256: sha256_hex(f.content.as_bytes()),
+                    })
+                    .collect(),
+            })
+            .collect(),
+    })
+}
+
+/// Package name for a domain: `cortex-shop-example-com`.
+fn package_name(domain: &str) -> String {
+    let slug: String = domain
+        .to_ascii_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("cortex-{}", slug.trim_matches('-'))
+}
+
+fn file(target: Target, path: &str, content: String) -> GeneratedFile {
+    GeneratedFile {
+        filename: format!("{}/{path}", target.name()),
+        size: content.len(),
+        content,
+    }
+}
+
+fn python_package(schema: &CompiledSchema, version: &str) -> Package {
+    let name = package_name(&schema.domain);
+    let module = name.replace('-', "_");
+    let domain = &schema.domain;
+    let pyproject = format!(
+        r#"[project]
+name = "{name}"
+version = "{version}"
+description = "Typed Cortex client for {domain} (generated)"
+readme = "README.md"
+requires-python = ">=3.10"
+dependencies = ["{PYTHON_CLIENT_REQUIREMENT}"]
+
+[tool.hatch.build.targets.wheel]
+packages = ["src/{module}"]
+
+[build-system]
+requires = ["hatchling"]
+build-backend = "hatchling.build"
+"#
+    );
+    let readme = format!(
+        "# {name}\n\n{}\n```bash\npip install .\n```\n\n```python\nimport {module}\n```\n",
+        readme_intro(schema)
+    );
+    Package {
+        target: Target::Python,
+        files: vec![
+            file(Target::Python, "pyproject.toml", pyproject),
+            file(Target::Python, "README.md", readme),
+            file(
+                Target::Python,
+                &format!("src/{module}/__init__.py"),
+                codegen_python::generate_python(schema),
+            ),
+            file(
+                Target::Python,
+                &format!("src/{module}/py.typed"),
+                String::new(),
+            ),
+        ],
+        name,
+    }
+}
+
+fn typescript_package(schema: &CompiledSchema, version: &str) -> Package {
+    let name = package_name(&schema.domain);
+    let package_json = serde_json::json!({
+        "name": name,
+        "version": version,
+        "description": format!("Typed Cortex client for {} (generated)", schema.domain),
+        "main": "dist/index.js",
+        "types": "dist/index.d.ts",
+        "files": ["dist", "README.md"],
+        "scripts": {
+            "build": "tsc",
+            "prepare": "tsc",
+        },
+        "dependencies": {
+            "@cortex/client": TS_CLIENT_REQUIREMENT,
+        },
+        "devDependencies": {
+            "typescript": "^5.7.0",
+        },
+        "engines": {
+            "node": ">=18.0.0",
+        },
+    });
+    let tsconfig = serde_json::json!({
+        "compilerOptions": {
+            "target": "ES2020",
+            "module": "commonjs",
+            "outDir": "./dist",
+            "rootDir": "./src",
+            "strict": true,
+            "declaration": true,
+            "esModuleInterop": true,
+            "skipLibCheck": true,
+        },
+        "include": ["src/**/*.ts"],
+    });
+    let readme = format!(
+        "# {name}\n\n{}\n```bash\nnpm install .\n```\n\n```ts\nimport * as site from '{name}';\n```\n",
+        readme_intro(schema)
+    );
+    Package {
+        target: Target::Typescript,
+        files: vec![
+            file(
+                Target::Typescript,
+                "package.json",
+                format!("{:#}\n", package_json),
+            ),
+            file(
+                Target::Typescript,
+                "tsconfig.json",
+                format!("{:#}\n", tsconfig),
+            ),
+            file(Target::Typescript, "README.md", readme),
+            file(
+                Target::Typescript,
+                "src/index.ts",
+                codegen_typescript::generate_typescript(schema),
+            ),
+        ],
+        name,
+    }
+}
+
+fn single_file_package(
+    schema: &CompiledSchema,
+    target: Target,
+    filename: &str,
+    content: String,
+) -> Package {
+    let readme = format!(
+        "# {} {}\n\n{}",
+        schema.domain,
+        target.name(),
+        readme_intro(schema)
+    );
+    Package {
+        target,
+        name: format!("{}-{}", package_name(&schema.domain), target.name()),
+        files: vec![
+            file(target, filename, content),
+            file(target, "README.md", readme),
+        ],
+    }
+}
+
+fn readme_intro(schema: &CompiledSchema) -> String {
+    let mut out = format!(
+        "Generated by `cortex compile {}` — do not edit manually.\n\n",
+        schema.domain
+    );
+    for model in &schema.models {
+        out.push_str(&format!(
+            "- `{}` ({} fields, {} instances)\n",
+            model.name,
+            model.fields.len(),
+            model.instance_count
+        ));
+    }
+    out.push('\n');
+    out
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn schema() -> CompiledSchema {
+        CompiledSchema {
+            domain: "Shop.Example.com".to_string(),
+            compiled_at: Utc::now(),
+            models: vec![DataModel {
+                name: "Product".to_string(),
+                schema_org_type: "Product".to_string(),
+                fields: vec![ModelField {
+                    name: "price".to_string(),
+                    field_type: FieldType::Float,
+                    source: FieldSource::JsonLd,
+                    confidence: 0.99,
+                    nullable: true,
+                    example_values: vec!["29.99".to_string()],
+                    feature_dim: Some(48),
+                }],
+                instance_count: 10,
+                example_urls: vec!["https://shop.example.com/p/1".to_string()],
+                search_action: None,
+                list_url: None,
+            }],
+            actions: vec![],
+            relationships: vec![],
+            stats: SchemaStats {
+                total_models: 1,
+                total_fields: 1,
+                total_instances: 10,
+                avg_confidence: 0.99,
+            },
+        }
+    }
+
+    #[test]
+    fn test_parse_targets() {
+        assert_eq!(parse_targets(&[]).unwrap(), Target::ALL.to_vec());
+        let picked = parse_targets(&["ts".to_string(), "python".to_string(), "py".to_string()]);
+        assert_eq!(picked.unwrap(), vec![Target::Python, Target::Typescript]);
+        assert!(parse_targets(&["cobol".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_write_packages_layout_and_manifest() {
+        let schema = schema();
+        let dir = TempDir::new().unwrap();
+        let settings = PackageSettings {
+            targets: vec![Target::Python, Target::Typescript, Target::Openapi],
+            version: "1.2.3".to_string(),
+        };
+        let manifest = write_packages(&schema, &settings, dir.path()).unwrap();
+
+        let root = dir.path();
+        let pyproject = fs::read_to_string(root.join("python/pyproject.toml")).unwrap();
+        assert!(pyproject.contains("name = \"cortex-shop-example-com\""));
+        assert!(pyproject.contains("version = \"1.2.3\""));
+        assert!(root
+            .join("python/src/cortex_shop_example_com/__init__.py")
+            .exists());
+        assert!(root
+            .join("python/src/cortex_shop_example_com/py.typed")
+            .exists());
+
+        let package: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(root.join("typescript/package.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(package["types"], "dist/index.d.ts");
+        assert!(root.join("typescript/src/index.ts").exists());
+        assert!(root.join("openapi/openapi.yaml").exists());
+        assert!(!root.join("graphql").exists());
+
+        let written: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(root.join("manifest.json")).unwrap()).unwrap();
+        assert_eq!(written["schema_version"], SCHEMA_VERSION);
+        assert_eq!(written["settings"]["targets"][1], "typescript");
+        assert_eq!(manifest.packages.len(), 3);
+        assert!(manifest.packages[0]
+            .files
+            .iter()
+            .all(|f| f.path.starts_with("python/") && f.sha256.len() == 64));
+
+        // The hash ignores compile time.
+        let mut later = schema.clone();
+        later.compiled_at += chrono::Duration::hours(1);
+        let again = super::manifest(&later, &settings, &build_packages(&later, &settings)).unwrap();
+        assert_eq!(again.schema_hash, manifest.schema_hash);
+    }
+}
@@ -379,21 +379,29 @@ fn simplify_model_name(schema_type: &str) -> String {
 }
 
 /// Map field names back to feature vector dimensions.
-fn field_name_to_dim(name: &str) -> Option<usize> {
+///
+/// This is the one table of feature names: watch rules, `cortex history`
+/// and graph exports resolve names through it too. Aliases name the same
+/// dimension.
+pub fn field_name_to_dim(name: &str) -> Option<usize> {
     match name {
         "price" => Some(FEAT_PRICE),
         "original_price" => Some(FEAT_PRICE_ORIGINAL),
-        "discount_percent" => Some(FEAT_DISCOUNT_PCT),
+        "discount_percent" | "discount" => Some(FEAT_DISCOUNT_PCT),
         "availability" => Some(FEAT_AVAILABILITY),
         "rating" => Some(FEAT_RATING),
         "review_count" => Some(FEAT_REVIEW_COUNT_LOG),
         "review_sentiment" => Some(FEAT_REVIEW_SENTIMENT),
-        "free_shipping" => Some(FEAT_SHIPPING_FREE),
-        "shipping_speed_days" => Some(FEAT_SHIPPING_SPEED),
+        "free_shipping" | "shipping_free" => Some(FEAT_SHIPPING_FREE),
+        "shipping_speed_days" | "shipping_speed" => Some(FEAT_SHIPPING_SPEED),
         "seller_reputation" => Some(FEAT_SELLER_REPUTATION),
         "variant_count" => Some(FEAT_VARIANT_COUNT),
         "deal_score" => Some(FEAT_DEAL_SCORE),
+        "price_trend" => Some(FEAT_PRICE_TREND),
         "image_count" => Some(FEAT_IMAGE_COUNT),
+        "load_time" => Some(FEAT_LOAD_TIME),
+        "text_length" => Some(FEAT_TEXT_LENGTH_LOG),
+        "page_depth" => Some(FEAT_PAGE_DEPTH),
         _ => None,
     }
 }
@@ -646,7 +654,6 @@ mod tests {
         assert_eq!(page_type_to_schema_org(PageType::Article), Some("Article"));
         assert_eq!(page_type_to_schema_org(PageType::Cart), Some("Cart"));
         assert_eq!(page_type_to_schema_org(PageType::Unknown), None);
-        assert_eq!(page_type_to_schema_org(PageType::ErrorPage), None);
     }
 
     #[test]
@@ -812,6 +819,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_soft_404s_build_no_models() {
+        let mut map = build_ecommerce_sitemap("shop.example.com", 6);
+        // Soft 404s: product URLs answered 200 with an error page, still
+        // carrying the product template's features.
+        let products: Vec<usize> = (0..map.nodes.len())
+            .filter(|&i| map.nodes[i].page_type == PageType::ProductDetail)
+            .collect();
+        for &i in &products[..4] {
+            map.nodes[i].page_type = PageType::ErrorPage;
+        }
+
+        let schema = infer_schema(&map, "shop.example.com");
+        let product = schema.models.iter().find(|m| m.name == "Product").unwrap();
+        assert_eq!(product.instance_count, 2);
+        assert!(product
+            .example_urls
+            .iter()
+            .all(|url| url.ends_with("/product/4") || url.ends_with("/product/5")));
+    }
+
     #[test]
     fn test_v4_schema_inference_news() {
         let map = build_news_sitemap("bbc.example.com", 15);
@@ -943,4 +971,22 @@ mod tests {
             "Avg confidence should be in (0,1]"
         );
     }
+
+    #[test]
+    fn test_field_name_aliases() {
+        for (a, b) in [
+            ("free_shipping", "shipping_free"),
+            ("discount_percent", "discount"),
+            ("shipping_speed_days", "shipping_speed"),
+        ] {
+            assert_eq!(field_name_to_dim(a), field_name_to_dim(b));
+            assert!(field_name_to_dim(a).is_some());
+        }
+        assert_eq!(
+            field_name_to_dim("review_sentiment"),
+            Some(FEAT_REVIEW_SENTIMENT)
+        );
+        assert_eq!(field_name_to_dim("image_count"), Some(FEAT_IMAGE_COUNT));
+        assert_eq!(field_name_to_dim("colour"), None);
+    }
 }
@@ -7,8 +7,18 @@
 //! [`CortexEvent`] values. Any consumer — MCP server, REST SSE endpoint,
 //! web dashboard, log files — can subscribe independently. When no
 //! subscribers exist, events are silently dropped (zero overhead).
+//!
+//! State-changing events (map completed, delta applied, watch fired, ACT
+//! executed) can additionally be appended to a persistent [`EventLog`] so
+//! external systems can replay the stream and rebuild their state.
 
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use tokio::sync::broadcast;
 
 /// Every event Cortex emits. Serialized to JSON for SSE, MCP, and socket streaming.
@@ -50,6 +60,13 @@ pub enum CortexEvent {
         features_filled: usize,
         elapsed_ms: u64,
     },
+    /// Overall progress of a mapping request (phase, counts, ETA), emitted
+    /// whenever it changes.
+    MapProgress {
+        domain: String,
+        request_id: String,
+        progress: crate::progress::MapProgress,
+    },
     /// Mapping completed successfully.
     MapComplete {
         domain: String,
@@ -67,6 +84,25 @@ pub enum CortexEvent {
         elapsed_ms: u64,
     },
 
+    /// A delta was applied to a domain's map in the registry.
+    DeltaApplied {
+        domain: String,
+        nodes_added: usize,
+        nodes_removed: usize,
+        nodes_modified: usize,
+        edges_added: usize,
+        edges_removed: usize,
+    },
+
+    // ── Watch Events ──────────────────────
+    /// A watch rule fired.
+    WatchFired {
+        domain: String,
+        rule_id: String,
+        message: String,
+        current_value: f32,
+    },
+
     // ── Action Events ─────────────────────
     /// An action (add to cart, submit form, etc.) has started.
     ActionStarted {
@@ -132,23 +168,248 @@ pub enum CortexEvent {
     },
 }
 
+impl CortexEvent {
+    /// Whether this event records a state change worth persisting.
+    ///
+    /// Progress and telemetry events are transient; only events that an
+    /// external system needs to rebuild state are written to the event log.
+    pub fn is_persistent(&self) -> bool {
+        matches!(
+            self,
+            CortexEvent::MapComplete { .. }
+                | CortexEvent::DeltaApplied { .. }
+                | CortexEvent::WatchFired { .. }
+                | CortexEvent::ActionComplete { .. }
+        )
+    }
+}
+
+/// An event as stored in the persistent log.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LoggedEvent {
+    /// Monotonic sequence number (1-based) within the log.
+    pub seq: u64,
+    /// When the event was recorded.
+    pub timestamp: DateTime<Utc>,
+    /// The event itself.
+    pub event: CortexEvent,
+}
+
+/// Append-only JSONL log of persistent events.
+pub struct EventLog {
+    file: File,
+    path: PathBuf,
+    next_seq: u64,
+}
+
+impl EventLog {
+    /// Open or create the event log, resuming the sequence from existing entries.
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let last_seq = entries(path)?.last().map(|e| e.seq).unwrap_or(0);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("failed to open event log: {}", path.display()))?;
+        Ok(Self {
+            file,
+            path: path.to_path_buf(),
+            next_seq: last_seq + 1,
+        })
+    }
+
+    /// Open the default event log at ~/.cortex/events.jsonl.
+    pub fn open_default() -> Result<Self> {
+        Self::open(&default_log_path())
+    }
+
+    /// Path of the underlying log file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an event, assigning it the next sequence number.
+    pub fn append(&mut self, event: CortexEvent) -> Result<LoggedEvent> {
+        let entry = LoggedEvent {
+            seq: self.next_seq,
+            timestamp: Utc::now(),
+            event,
+        };
+        let json = serde_json::to_string(&entry)?;
+        writeln!(self.file, "{json}")?;
+        self.file.flush()?;
+        self.next_seq += 1;
+        Ok(entry)
+    }
+}
+
+/// Default event log location (~/.cortex/events.jsonl).
+pub fn default_log_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join(".cortex")
+        .join("events.jsonl")
+}
+
+/// Read every entry from an event log. Missing files yield an empty list;
+/// malformed lines (e.g. a torn final write) are skipped.
+pub fn read_log(path: &Path) -> Result<Vec<LoggedEvent>> {
+    Ok(entries(path)?.collect())
+}
+
+/// Stream the entries of an event log one line at a time, so callers that
+/// keep only some of them never hold the whole log in memory.
+fn entries(path: &Path) -> Result<impl Iterator<Item = LoggedEvent>> {
+    let file = match File::open(path) {
+        Ok(file) => Some(file),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => {
+            return Err(anyhow::Error::new(e)
+                .context(format!("failed to read event log: {}", path.display())))
+        }
+    };
+    Ok(file
+        .into_iter()
+        .flat_map(|file| BufReader::new(file).lines())
+        .map_while(|line| line.ok())
+        .filter_map(|line| serde_json::from_str::<LoggedEvent>(&line).ok()))
+}
+
+/// Replay filter for the persistent log.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayFilter {
+    /// Only events recorded after this time.
+    pub since: Option<DateTime<Utc>>,
+    /// Only events with a sequence number greater than this.
+    pub after_seq: Option<u64>,
+    /// Only events related to this domain (system events always pass).
+    pub domain: Option<String>,
+}
+
+impl ReplayFilter {
+    /// Whether a logged event passes the filter.
+    pub fn matches(&self, entry: &LoggedEvent) -> bool {
+        if self.since.is_some_and(|since| entry.timestamp <= since) {
+            return false;
+        }
+        if self.after_seq.is_some_and(|seq| entry.seq <= seq) {
+            return false;
+        }
+        match &self.domain {
+            Some(domain) => event_matches_domain(&entry.event, domain),
+            None => true,
+        }
+    }
+}
+
+/// Replay events from a log that match the filter, in sequence order.
+pub fn replay(path: &Path, filter: &ReplayFilter) -> Result<Vec<LoggedEvent>> {
+    Ok(entries(path)?.filter(|e| filter.matches(e)).collect())
+}
+
+/// Follows an event log as it grows, reading only the bytes appended since
+/// the previous poll.
+pub struct LogFollower {
+    path: PathBuf,
+    reader: Option<BufReader<File>>,
+    /// A line the writer has not finished yet.
+    partial: String,
+}
+
+impl LogFollower {
+    /// Follow the log at `path` from its first entry. The log need not exist
+    /// yet; it is opened on the first poll that finds it.
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            reader: None,
+            partial: String::new(),
+        }
+    }
+
+    /// Entries appended since the last poll that pass `filter`.
+    pub fn poll(&mut self, filter: &ReplayFilter) -> Result<Vec<LoggedEvent>> {
+        if self.reader.is_none() {
+            match File::open(&self.path) {
+                Ok(file) => self.reader = Some(BufReader::new(file)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+                Err(e) => {
+                    return Err(anyhow::Error::new(e)
+                        .context(format!("failed to read event log: {}", self.path.display())))
+                }
+            }
+        }
+        let Some(reader) = self.reader.as_mut() else {
+            return Ok(Vec::new());
+        };
+
+        let mut entries = Vec::new();
+        while reader.read_line(&mut self.partial)? > 0 {
+            if !self.partial.ends_with('\n') {
+                // Torn write: the rest of the line arrives with a later poll.
+                break;
+            }
+            if let Ok(entry) = serde_json::from_str::<LoggedEvent>(&self.partial) {
+                if filter.matches(&entry) {
+                    entries.push(entry);
+                }
+            }
+            self.partial.clear();
+        }
+        Ok(entries)
+    }
+}
+
 /// The central event bus for Cortex.
 ///
 /// All components emit events through this bus. Consumers subscribe
 /// to receive a stream of all events.
 pub struct EventBus {
     sender: broadcast::Sender<CortexEvent>,
+    /// Persistent log for state-changing events, if enabled.
+    log: Option<Mutex<EventLog>>,
+    /// Broadcast of persisted entries (with sequence numbers).
+    logged_sender: broadcast::Sender<LoggedEvent>,
 }
 
 impl EventBus {
     /// Create a new event bus with the given buffer capacity.
     pub fn new(capacity: usize) -> Self {
         let (sender, _) = broadcast::channel(capacity);
-        Self { sender }
+        let (logged_sender, _) = broadcast::channel(capacity);
+        Self {
+            sender,
+            log: None,
+            logged_sender,
+        }
+    }
+
+    /// Create an event bus that also appends persistent events to `log`.
+    pub fn with_log(capacity: usize, log: EventLog) -> Self {
+        let mut bus = Self::new(capacity);
+        bus.log = Some(Mutex::new(log));
+        bus
     }
 
     /// Emit an event to all subscribers. Silently ignores if no subscribers.
     pub fn emit(&self, event: CortexEvent) {
+        if event.is_persistent() {
+            if let Some(log) = &self.log {
+                let appended = log
+                    .lock()
+                    .map_err(|_| anyhow::anyhow!("event log lock poisoned"))
+                    .and_then(|mut log| log.append(event.clone()));
+                match appended {
+                    Ok(entry) => {
+                        let _ = self.logged_sender.send(entry);
+                    }
+                    Err(e) => tracing::warn!("failed to append to event log: {e}"),
+                }
+            }
+        }
         let _ = self.sender.send(event);
     }
 
@@ -156,6 +417,18 @@ impl EventBus {
     pub fn subscribe(&self) -> broadcast::Receiver<CortexEvent> {
         self.sender.subscribe()
     }
+
+    /// Subscribe to future persisted events (with sequence numbers).
+    pub fn subscribe_logged(&self) -> broadcast::Receiver<LoggedEvent> {
+        self.logged_sender.subscribe()
+    }
+
+    /// Path of the persistent log, if one is attached.
+    pub fn log_path(&self) -> Option<PathBuf> {
+        self.log
+            .as_ref()
+            .and_then(|l| l.lock().ok().map(|l| l.path().to_path_buf()))
+    }
 }
 
 /// Check if an event is related to a specific domain.
@@ -166,8 +439,11 @@ pub fn event_matches_domain(event: &CortexEvent, domain: &str) -> bool {
         | CortexEvent::HeadScanProgress { domain: d, .. }
         | CortexEvent::StructuredDataExtracted { domain: d, .. }
         | CortexEvent::LayerComplete { domain: d, .. }
+        | CortexEvent::MapProgress { domain: d, .. }
         | CortexEvent::MapComplete { domain: d, .. }
         | CortexEvent::MapFailed { domain: d, .. }
+        | CortexEvent::DeltaApplied { domain: d, .. }
+        | CortexEvent::WatchFired { domain: d, .. }
         | CortexEvent::ActionStarted { domain: d, .. }
         | CortexEvent::ActionComplete { domain: d, .. }
         | CortexEvent::AuthStarted { domain: d, .. }
@@ -276,4 +552,138 @@ mod tests {
         };
         assert!(event_matches_domain(&sys, "anything"));
     }
+
+    fn delta_event(domain: &str) -> CortexEvent {
+        CortexEvent::DeltaApplied {
+            domain: domain.to_string(),
+            nodes_added: 1,
+            nodes_removed: 0,
+            nodes_modified: 2,
+            edges_added: 0,
+            edges_removed: 0,
+        }
+    }
+
+    #[test]
+    fn test_event_log_persists_only_state_changes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("events.jsonl");
+        let bus = EventBus::with_log(16, EventLog::open(&path).unwrap());
+
+        bus.emit(CortexEvent::MapStarted {
+            domain: "a.com".to_string(),
+            timestamp: "1".to_string(),
+        });
+        bus.emit(delta_event("a.com"));
+        bus.emit(CortexEvent::WatchFired {
+            domain: "b.com".to_string(),
+            rule_id: "r1".to_string(),
+            message: "price dropped".to_string(),
+            current_value: 9.0,
+        });
+
+        let entries = read_log(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].seq, 1);
+        assert_eq!(entries[1].seq, 2);
+        assert_eq!(bus.log_path().as_deref(), Some(path.as_path()));
+    }
+
+    #[test]
+    fn test_event_log_resumes_sequence() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("events.jsonl");
+        EventLog::open(&path)
+            .unwrap()
+            .append(delta_event("a.com"))
+            .unwrap();
+        let entry = EventLog::open(&path)
+            .unwrap()
+            .append(delta_event("a.com"))
+            .unwrap();
+        assert_eq!(entry.seq, 2);
+    }
+
+    #[test]
+    fn test_replay_filters() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("events.jsonl");
+        let mut log = EventLog::open(&path).unwrap();
+        log.append(delta_event("a.com")).unwrap();
+        log.append(delta_event("b.com")).unwrap();
+        log.append(delta_event("a.com")).unwrap();
+
+        let all = replay(&path, &ReplayFilter::default()).unwrap();
+        assert_eq!(all.len(), 3);
+
+        let after = replay(
+            &path,
+            &ReplayFilter {
+                after_seq: Some(1),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(after.len(), 2);
+
+        let domain = replay(
+            &path,
+            &ReplayFilter {
+                domain: Some("a.com".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(domain.len(), 2);
+
+        let future = replay(
+            &path,
+            &ReplayFilter {
+                since: Some(Utc::now() + chrono::Duration::hours(1)),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(future.is_empty());
+    }
+
+    #[test]
+    fn test_log_follower_reads_only_new_entries() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("events.jsonl");
+        let mut follower = LogFollower::new(&path);
+        let filter = ReplayFilter::default();
+        assert!(follower.poll(&filter).unwrap().is_empty());
+
+        let mut log = EventLog::open(&path).unwrap();
+        log.append(delta_event("a.com")).unwrap();
+        log.append(delta_event("b.com")).unwrap();
+        assert_eq!(follower.poll(&filter).unwrap().len(), 2);
+        assert!(follower.poll(&filter).unwrap().is_empty());
+
+        // A line written in two pieces is read once it is complete.
+        let line = serde_json::to_string(&LoggedEvent {
+            seq: 3,
+            timestamp: Utc::now(),
+            event: delta_event("a.com"),
+        })
+        .unwrap();
+        let (head, tail) = line.split_at(line.len() / 2);
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "{head}").unwrap();
+        assert!(follower.poll(&filter).unwrap().is_empty());
+        writeln!(file, "{tail}").unwrap();
+        let entries = follower.poll(&filter).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].seq, 3);
+    }
+
+    #[test]
+    fn test_logged_subscriber_receives_seq() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let bus = EventBus::with_log(16, EventLog::open(&dir.path().join("e.jsonl")).unwrap());
+        let mut rx = bus.subscribe_logged();
+        bus.emit(delta_event("a.com"));
+        assert_eq!(rx.try_recv().unwrap().seq, 1);
+    }
 }
@@ -0,0 +1,272 @@
+//! Custom extraction scripts registered per domain.
+//!
+//! `cortex extract add shop.com prices.js` copies a script to
+//! `~/.cortex/extractors/shop.com/prices.js`. When the daemon renders a page
+//! of that domain (or a subdomain), the loader runs the script after the
+//! built-in extractors, under the same limits, and keeps its JSON output as
+//! [`CustomData`]. The fields it returns that Cortex knows —
+//! `price`, `original_price`, `availability`, `rating` (with `rating_best`)
+//! and `review_count` — are merged into the page's features with the
+//! extractor's confidence; see
+//! [`apply_custom_data`](crate::cartography::feature_encoder::apply_custom_data).
+//!
+//! Each domain directory may hold an `extractors.json` with settings per
+//! script: `{"prices": {"confidence": 0.9, "timeout_ms": 500}}`. Limits not
+//! set there come from the `default` entry of `extractor_limits.json`.
+
+use crate::extraction::loader::{self, Script, ScriptLimits};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Confidence of a custom extractor's values when none is configured.
+pub const DEFAULT_CONFIDENCE: f32 = 0.8;
+
+/// Per-domain settings file, next to the scripts.
+const SETTINGS_FILE: &str = "extractors.json";
+
+/// A custom extraction script and the domain it runs on.
+#[derive(Debug, Clone)]
+pub struct CustomExtractor {
+    pub domain: String,
+    pub script: Script,
+    /// How far its values are trusted, in `[0, 1]`.
+    pub confidence: f32,
+}
+
+impl CustomExtractor {
+    /// Whether the extractor runs on pages served from `host`.
+    pub fn applies_to(&self, host: &str) -> bool {
+        let host = host.to_lowercase();
+        host == self.domain || host.ends_with(&format!(".{}", self.domain))
+    }
+}
+
+/// What a custom extractor returned for a page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomData {
+    /// Extractor name (the script's file stem).
+    pub name: String,
+    pub confidence: f32,
+    pub value: serde_json::Value,
+}
+
+/// Settings for one script in a domain's `extractors.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Settings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    confidence: Option<f32>,
+    /// Limit overrides (`timeout_ms`, `max_heap_bytes`, `max_result_bytes`).
+    #[serde(flatten)]
+    limits: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Default directory of custom extractors (~/.cortex/extractors).
+pub fn default_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".cortex")
+        .join("extractors")
+}
+
+/// Read every custom extractor under `dir`. A missing directory holds none.
+pub fn load_all(dir: &Path) -> Result<Vec<CustomExtractor>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let base = loader::limits_for(&loader::load_limits(&loader::limits_path())?, "default");
+    let mut domains: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("reading {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    domains.sort();
+
+    let mut extractors = Vec::new();
+    for domain_dir in domains {
+        let Some(domain) = domain_dir.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let settings = read_settings(&domain_dir)?;
+        for (name, path) in scripts_in(&domain_dir)? {
+            let own = settings.get(&name).cloned().unwrap_or_default();
+            let source = std::fs::read_to_string(&path)
+                .with_context(|| format!("reading {}", path.display()))?;
+            extractors.push(CustomExtractor {
+                domain: domain.to_string(),
+                script: Script {
+                    limits: merge_limits(base, &own.limits).with_context(|| {
+                        format!("limits for {name} in {}", domain_dir.display())
+                    })?,
+                    name,
+                    source,
+                },
+                confidence: own.confidence.unwrap_or(DEFAULT_CONFIDENCE),
+            });
+        }
+    }
+    Ok(extractors)
+}
+
+/// Register `script` for `domain`, replacing a script of the same name.
+/// Returns the extractor as the daemon will load it.
+pub fn add(
+    dir: &Path,
+    domain: &str,
+    script: &Path,
+    confidence: Option<f32>,
+) -> Result<CustomExtractor> {
+    let domain = normalize_domain(domain)?;
+    let name = script_name(script)?;
+    if let Some(c) = confidence.filter(|c| !(0.0..=1.0).contains(c)) {
+        bail!("confidence must be between 0 and 1, not {c}");
+    }
+    let source =
+        std::fs::read_to_string(script).with_context(|| format!("reading {}", script.display()))?;
+
+    let domain_dir = dir.join(&domain);
+    std::fs::create_dir_all(&domain_dir)
+        .with_context(|| format!("creating {}", domain_dir.display()))?;
+    std::fs::write(domain_dir.join(format!("{name}.js")), &source)?;
+    let mut settings = read_settings(&domain_dir)?;
+    let entry = settings.entry(name.clone()).or_default();
+    if confidence.is_some() {
+        entry.confidence = confidence;
+    }
+    write_settings(&domain_dir, &settings)?;
+
+    load_all(dir)?
+        .into_iter()
+        .find(|e| e.domain == domain && e.script.name == name)
+        .context("the registered script could not be read back")
+}
+
+/// Remove the script `name` registered for `domain`. Returns false if there
+/// was none.
+pub fn remove(dir: &Path, domain: &str, name: &str) -> Result<bool> {
+    let domain = normalize_domain(domain)?;
+    let domain_dir = dir.join(&domain);
+    let path = domain_dir.join(format!("{name}.js"));
+    if !path.exists() {
+        return Ok(false);
+    }
+    std::fs::remove_file(&path).with_context(|| format!("removing {}", path.display()))?;
+    let mut settings = read_settings(&domain_dir)?;
+    if settings.remove(name).is_some() {
+        write_settings(&domain_dir, &settings)?;
+    }
+    if scripts_in(&domain_dir)?.is_empty() {
+        let _ = std::fs::remove_file(domain_dir.join(SETTINGS_FILE));
+        let _ = std::fs::remove_dir(&domain_dir);
+    }
+    Ok(true)
+}
+
+/// The extractor name a script file gives: its file stem, which must be a
+/// valid JS identifier part and not a built-in extractor's name.
+pub fn script_name(path: &Path) -> Result<String> {
+    let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+        bail!("cannot name an extractor after {}", path.display());
+    };
+    if stem.is_empty() || !stem.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        bail!("invalid extractor name '{stem}' (rename the file using letters, digits and '_')");
+    }
+    if loader::is_builtin(stem) {
+        bail!("'{stem}' is a built-in extractor; rename the file");
+    }
+    Ok(stem.to_string())
+}
+
+fn normalize_domain(domain: &str) -> Result<String> {
+    let domain = domain.trim().trim_end_matches('/').to_lowercase();
+    if domain.is_empty() || domain.contains(['/', '\\']) || domain.starts_with('.') {
+        bail!("invalid domain '{domain}'");
+    }
+    Ok(domain)
+}
+
+/// `.js` files in a domain directory, by name.
+fn scripts_in(domain_dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let mut scripts: Vec<(String, PathBuf)> = std::fs::read_dir(domain_dir)
+        .with_context(|| format!("reading {}", domain_dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "js"))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_string();
+            Some((name, path))
+        })
+        .collect();
+    scripts.sort();
+    Ok(scripts)
+}
+
+fn read_settings(domain_dir: &Path) -> Result<BTreeMap<String, Settings>> {
+    let path = domain_dir.join(SETTINGS_FILE);
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let data =
+        std::fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+    serde_json::from_str(&data).with_context(|| format!("parsing {}", path.display()))
+}
+
+fn write_settings(domain_dir: &Path, settings: &BTreeMap<String, Settings>) -> Result<()> {
+    let path = domain_dir.join(SETTINGS_FILE);
+    std::fs::write(&path, serde_json::to_string_pretty(settings)?)
+        .with_context(|| format!("writing {}", path.display()))
+}
+
+/// `base` with the limits set in `fields` replaced.
+fn merge_limits(
+    base: ScriptLimits,
+    fields: &serde_json::Map<String, serde_json::Value>,
+) -> Result<ScriptLimits> {
+    let mut merged = match serde_json::to_value(base)? {
+        serde_json::Value::Object(map) => map,
+        _ => serde_json::Map::new(),
+    };
+    merged.extend(fields.clone());
+    Ok(serde_json::from_value(merged.into())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_load_and_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = dir.path().join("extractors");
+        assert!(load_all(&registry).unwrap().is_empty());
+
+        let script = dir.path().join("prices.js");
+        std::fs::write(&script, "function __cortex_extractPrices(d) { return {}; }").unwrap();
+        let added = add(&registry, "Shop.com", &script, Some(0.9)).unwrap();
+        assert_eq!(added.domain, "shop.com");
+        assert_eq!(added.script.entry_point(), "__cortex_extractPrices");
+        assert!(added.applies_to("www.shop.com"));
+        assert!(added.applies_to("SHOP.COM"));
+        assert!(!added.applies_to("notshop.com"));
+
+        // Limits set next to the script override the defaults.
+        std::fs::write(
+            registry.join("shop.com").join(SETTINGS_FILE),
+            r#"{"prices": {"confidence": 0.6, "timeout_ms": 250}}"#,
+        )
+        .unwrap();
+        let loaded = load_all(&registry).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].confidence, 0.6);
+        assert_eq!(loaded[0].script.limits.timeout_ms, 250);
+
+        assert!(add(&registry, "shop.com", &script, Some(1.5)).is_err());
+        let builtin = dir.path().join("content.js");
+        std::fs::write(&builtin, "").unwrap();
+        assert!(add(&registry, "shop.com", &builtin, None).is_err());
+
+        assert!(remove(&registry, "shop.com", "prices").unwrap());
+        assert!(!remove(&registry, "shop.com", "prices").unwrap());
+        assert!(!registry.join("shop.com").exists());
+    }
+}
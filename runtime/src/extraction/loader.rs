@@ -1,11 +1,34 @@
 //! Load and inject extraction scripts into browser contexts.
+//!
+//! Scripts are read from disk at startup and again on `cortex reload`, so
+//! rebuilt extractors take effect without restarting the daemon. Renders
+//! already running keep the scripts they started with.
+//!
+//! Each script runs under [`ScriptLimits`]: a time budget (a script still
+//! running when it ends is terminated, so it cannot hang the page or the
+//! render pool), a cap on how much the page's JS heap may grow (read every
+//! [`HEAP_POLL`] while it runs, and a script over it is terminated the same
+//! way), and a cap on the size of its JSON result. A script that breaks a limit contributes its
+//! empty fallback instead of a result. The defaults can be overridden per
+//! script in `~/.cortex/extractor_limits.json`, e.g.
+//! `{"default": {"timeout_ms": 3000}, "content": {"max_result_bytes": 4194304}}`.
+//!
+//! After the built-in extractors, the loader runs any
+//! [custom extractors](super::custom) registered for the page's domain.
 
+use crate::extraction::custom::{self, CustomData, CustomExtractor};
 use crate::renderer::RenderContext;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 use tracing::{info, warn};
 
+/// How often a running script's heap growth is checked against its cap.
+pub const HEAP_POLL: Duration = Duration::from_millis(50);
+
 /// Names of the extraction scripts.
 const EXTRACTOR_NAMES: &[&str] = &["content", "actions", "navigation", "structure", "metadata"];
 
@@ -17,135 +40,643 @@ pub struct ExtractionResult {
     pub navigation: serde_json::Value,
     pub structure: serde_json::Value,
     pub metadata: serde_json::Value,
+    /// Output of the custom extractors registered for the page's domain.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub custom: Vec<CustomData>,
+}
+
+/// Resources one extraction script may use on a page.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScriptLimits {
+    /// Time to inject the script and run its entry point.
+    pub timeout_ms: u64,
+    /// How much the page's JS heap may grow while the script runs; checked
+    /// every [`HEAP_POLL`] during the run and once after it.
+    pub max_heap_bytes: u64,
+    /// Largest result, measured as serialized JSON.
+    pub max_result_bytes: u64,
+}
+
+impl Default for ScriptLimits {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 2_000,
+            max_heap_bytes: 64 * 1024 * 1024,
+            max_result_bytes: 1024 * 1024,
+        }
+    }
+}
+
+/// An extraction script and the limits it runs under.
+#[derive(Debug, Clone)]
+pub struct Script {
+    /// Extractor name; the entry point is `__cortex_extract<Name>`.
+    pub name: String,
+    pub source: String,
+    pub limits: ScriptLimits,
+}
+
+impl Script {
+    /// The global function the script defines as its entry point.
+    pub fn entry_point(&self) -> String {
+        let mut chars = self.name.chars();
+        let capitalized: String = chars
+            .next()
+            .map(|c| c.to_ascii_uppercase())
+            .into_iter()
+            .chain(chars)
+            .collect();
+        format!("__cortex_extract{capitalized}")
+    }
+
+    /// What the script contributes when it has no result.
+    fn fallback(&self) -> serde_json::Value {
+        match self.name.as_str() {
+            "structure" | "metadata" => serde_json::json!({}),
+            _ => serde_json::json!([]),
+        }
+    }
+}
+
+/// How a script's run ended.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ScriptOutcome {
+    Ok,
+    /// The script does not define its entry point.
+    Missing,
+    /// The script threw, or could not be injected.
+    Failed {
+        error: String,
+    },
+    /// The script was still running when its time ran out.
+    Timeout,
+    /// The page's heap grew by `bytes`, more than allowed.
+    OverMemory {
+        bytes: u64,
+    },
+    /// The result serialized to `bytes`, more than allowed.
+    TooLarge {
+        bytes: u64,
+    },
+    /// Not run: an earlier script could not be stopped.
+    Skipped,
+}
+
+/// One script's run on a page.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptRun {
+    pub name: String,
+    #[serde(flatten)]
+    pub outcome: ScriptOutcome,
+    pub elapsed_ms: u64,
+    /// Size of the result as serialized JSON.
+    pub result_bytes: u64,
+    /// Growth of the page's JS heap, if the renderer reports it.
+    pub heap_bytes: Option<u64>,
+    pub limits: ScriptLimits,
+    /// The result, or the script's fallback if it has none.
+    #[serde(skip)]
+    pub value: serde_json::Value,
+    /// The script was still running when it broke a limit; stop it with
+    /// [`RenderContext::terminate_js`] before using the page again.
+    #[serde(skip)]
+    pub running: bool,
 }
 
 /// Loads extraction scripts from disk or embedded paths.
 pub struct ExtractionLoader {
-    scripts: Vec<(String, String)>,
+    scripts: RwLock<Arc<Vec<Script>>>,
+    custom: RwLock<Arc<Vec<CustomExtractor>>>,
 }
 
 impl ExtractionLoader {
     /// Create a new loader, reading scripts from the dist directory.
     pub fn new() -> Result<Self> {
-        let mut scripts = Vec::new();
-
-        // Look for scripts in several locations
-        let mut search_paths = vec![
-            // Relative to CWD
-            PathBuf::from("extractors/dist"),
-            // Relative to workspace root (from runtime/)
-            PathBuf::from("../extractors/dist"),
-            // Embedded in source tree
-            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/extraction/scripts"),
-        ];
-
-        // Relative to binary location
-        if let Ok(exe) = std::env::current_exe() {
-            if let Some(exe_dir) = exe.parent() {
-                // Binary is typically at runtime/target/release/cortex
-                // Extractors at extractors/dist (3 levels up then into extractors/dist)
-                search_paths.insert(0, exe_dir.join("../../../extractors/dist"));
-                search_paths.insert(1, exe_dir.join("../../extractors/dist"));
-                search_paths.insert(2, exe_dir.join("../extractors/dist"));
+        let (scripts, _) = read_scripts()?;
+        let custom = custom::load_all(&custom::default_dir()).unwrap_or_else(|e| {
+            warn!("failed to load custom extractors: {e:#}");
+            Vec::new()
+        });
+        Ok(Self {
+            scripts: RwLock::new(Arc::new(scripts)),
+            custom: RwLock::new(Arc::new(custom)),
+        })
+    }
+
+    /// Re-read the scripts and their limits from disk. Returns how many
+    /// scripts were found (the rest use the empty fallback). On error the
+    /// current scripts stay.
+    pub fn reload(&self) -> Result<usize> {
+        let (scripts, found) = read_scripts()?;
+        *self.scripts.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(scripts);
+        Ok(found)
+    }
+
+    /// Re-read the custom extractors from disk. Returns how many there are.
+    /// On error the current ones stay.
+    pub fn reload_custom(&self) -> Result<usize> {
+        let extractors = custom::load_all(&custom::default_dir())?;
+        let count = extractors.len();
+        *self.custom.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(extractors);
+        Ok(count)
+    }
+
+    /// Inject all extraction scripts into a page and collect results, then
+    /// run the custom extractors for the page's domain.
+    pub async fn inject_and_run(&self, context: &dyn RenderContext) -> Result<ExtractionResult> {
+        let scripts = Arc::clone(&self.scripts.read().unwrap_or_else(|e| e.into_inner()));
+        let extractors = Arc::clone(&self.custom.read().unwrap_or_else(|e| e.into_inner()));
+
+        let mut values: HashMap<String, serde_json::Value> = HashMap::new();
+        let mut stuck = false;
+        for script in scripts.iter() {
+            let run = run_guarded(context, script, &mut stuck).await;
+            values.insert(script.name.clone(), run.value);
+        }
+
+        let mut custom = Vec::new();
+        if !extractors.is_empty() {
+            let url = context.get_url().await.unwrap_or_default();
+            let host = url::Url::parse(&url)
+                .ok()
+                .and_then(|u| u.host_str().map(String::from))
+                .unwrap_or_default();
+            for extractor in extractors.iter().filter(|e| e.applies_to(&host)) {
+                let run = run_guarded(context, &extractor.script, &mut stuck).await;
+                if run.outcome == ScriptOutcome::Ok {
+                    custom.push(CustomData {
+                        name: extractor.script.name.clone(),
+                        confidence: extractor.confidence,
+                        value: run.value,
+                    });
+                }
             }
         }
 
-        for name in EXTRACTOR_NAMES {
-            let filename = format!("{name}.js");
-            let mut found = false;
+        let mut take = |name: &str| values.remove(name).unwrap_or_default();
+        Ok(ExtractionResult {
+            content: take("content"),
+            actions: take("actions"),
+            navigation: take("navigation"),
+            structure: take("structure"),
+            metadata: take("metadata"),
+            custom,
+        })
+    }
+}
+
+/// Whether `name` is one of the built-in extractors.
+pub fn is_builtin(name: &str) -> bool {
+    EXTRACTOR_NAMES.contains(&name)
+}
+
+/// Run `script` unless an earlier script left the page `stuck`, logging a
+/// run that did not succeed and stopping one still running. Sets `stuck`
+/// if it could not be stopped.
+async fn run_guarded(context: &dyn RenderContext, script: &Script, stuck: &mut bool) -> ScriptRun {
+    if *stuck {
+        return skipped(script);
+    }
+    let run = run_script(context, script).await;
+    match &run.outcome {
+        ScriptOutcome::Ok | ScriptOutcome::Missing | ScriptOutcome::Skipped => {}
+        ScriptOutcome::Failed { error } => warn!("{} extractor failed: {error}", script.name),
+        outcome => warn!("{} extractor over its limits: {outcome:?}", script.name),
+    }
+    // A script that could not be stopped still holds the page.
+    if run.running && context.terminate_js().await.is_err() {
+        *stuck = true;
+    }
+    run
+}
+
+/// Inject `script` into the page and run its entry point within its limits.
+///
+/// A script that times out or outgrows its heap cap while running is left
+/// running (`running` is set); the caller stops it with
+/// [`RenderContext::terminate_js`] before using the page again.
+pub async fn run_script(context: &dyn RenderContext, script: &Script) -> ScriptRun {
+    let start = Instant::now();
+    let limits = script.limits;
+    let heap_before = context.heap_used().await.ok().flatten();
 
-            for base in &search_paths {
-                let path = base.join(&filename);
-                if path.exists() {
-                    let content = std::fs::read_to_string(&path)
-                        .with_context(|| format!("reading {}", path.display()))?;
-                    info!("loaded extractor {name}.js from {}", path.display());
-                    scripts.push((name.to_string(), content));
-                    found = true;
-                    break;
+    let budget = Duration::from_millis(limits.timeout_ms);
+    let call = call_script(&script.entry_point(), limits.max_result_bytes);
+    let execute = async {
+        context
+            .execute_js(&script.source)
+            .await
+            .context("injecting script")?;
+        context.execute_js(&call).await.context("running script")
+    };
+    let ran = tokio::time::timeout(budget, async {
+        tokio::pin!(execute);
+        let Some(before) = heap_before else {
+            return Ok(execute.await);
+        };
+        let mut poll = tokio::time::interval(HEAP_POLL);
+        poll.tick().await;
+        loop {
+            tokio::select! {
+                ran = &mut execute => return Ok(ran),
+                _ = poll.tick() => {
+                    let grown = context
+                        .heap_used()
+                        .await
+                        .ok()
+                        .flatten()
+                        .map(|now| now.saturating_sub(before));
+                    if let Some(bytes) = grown.filter(|b| *b > limits.max_heap_bytes) {
+                        return Err(bytes);
+                    }
                 }
             }
+        }
+    })
+    .await;
+
+    let mut run = ScriptRun {
+        name: script.name.clone(),
+        outcome: ScriptOutcome::Ok,
+        elapsed_ms: start.elapsed().as_millis() as u64,
+        result_bytes: 0,
+        heap_bytes: None,
+        limits,
+        value: script.fallback(),
+        running: false,
+    };
+    // The page cannot answer while a stopped script still holds it.
+    let ran = match ran {
+        Ok(Ok(ran)) => ran,
+        Ok(Err(bytes)) => {
+            run.outcome = ScriptOutcome::OverMemory { bytes };
+            run.heap_bytes = Some(bytes);
+            run.running = true;
+            return run;
+        }
+        Err(_) => {
+            run.outcome = ScriptOutcome::Timeout;
+            run.running = true;
+            return run;
+        }
+    };
+    let heap_after = context.heap_used().await.ok().flatten();
+    let heap_bytes = heap_before
+        .zip(heap_after)
+        .map(|(before, after)| after.saturating_sub(before));
+    run.heap_bytes = heap_bytes;
+
+    let reply = match ran {
+        Err(e) => {
+            run.outcome = ScriptOutcome::Failed {
+                error: format!("{e:#}"),
+            };
+            return run;
+        }
+        Ok(reply) => reply,
+    };
+    run.result_bytes = reply.get("bytes").and_then(|b| b.as_u64()).unwrap_or(0);
+    run.outcome = if reply.get("missing").is_some() {
+        ScriptOutcome::Missing
+    } else if let Some(error) = reply.get("error") {
+        ScriptOutcome::Failed {
+            error: error.as_str().unwrap_or("unknown error").to_string(),
+        }
+    } else if run.result_bytes > limits.max_result_bytes {
+        ScriptOutcome::TooLarge {
+            bytes: run.result_bytes,
+        }
+    } else if let Some(bytes) = heap_bytes.filter(|b| *b > limits.max_heap_bytes) {
+        ScriptOutcome::OverMemory { bytes }
+    } else {
+        run.value = reply.get("value").cloned().unwrap_or_default();
+        ScriptOutcome::Ok
+    };
+    run
+}
+
+/// The run of a script that was not started.
+fn skipped(script: &Script) -> ScriptRun {
+    ScriptRun {
+        name: script.name.clone(),
+        outcome: ScriptOutcome::Skipped,
+        elapsed_ms: 0,
+        result_bytes: 0,
+        heap_bytes: None,
+        limits: script.limits,
+        value: script.fallback(),
+        running: false,
+    }
+}
 
-            if !found {
-                warn!("extractor script {name}.js not found, using fallback");
-                // Use a minimal fallback script that returns empty data
-                scripts.push((
-                    name.to_string(),
-                    format!(
-                        "var CortexExtractor_{name} = {{ default: function() {{ return []; }} }};"
-                    ),
-                ));
+/// JS that calls `entry` on the document and reports the result's size.
+/// Results over `max_bytes` are measured in the page and never sent back.
+fn call_script(entry: &str, max_bytes: u64) -> String {
+    format!(
+        r#"
+        (function() {{
+            if (typeof {entry} !== 'function') return {{ missing: true }};
+            var value, json;
+            try {{
+                value = {entry}(document);
+                json = JSON.stringify(value === undefined ? null : value);
+            }} catch (e) {{
+                return {{ error: String(e && e.message || e) }};
+            }}
+            if (json.length > {max_bytes}) return {{ bytes: json.length }};
+            return {{ value: value, bytes: json.length }};
+        }})()
+        "#
+    )
+}
+
+/// Default limits file (~/.cortex/extractor_limits.json).
+pub fn limits_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".cortex")
+        .join("extractor_limits.json")
+}
+
+/// Limits for each script name, from a file mapping names (and `default`)
+/// to [`ScriptLimits`]. Fields a script leaves out come from `default`,
+/// then from the built-in defaults. A missing file sets no overrides.
+pub fn load_limits(path: &Path) -> Result<HashMap<String, ScriptLimits>> {
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    let data = std::fs::read_to_string(path)
+        .with_context(|| format!("reading extractor limits: {}", path.display()))?;
+    let raw: HashMap<String, serde_json::Map<String, serde_json::Value>> =
+        serde_json::from_str(&data).with_context(|| format!("parsing {}", path.display()))?;
+    let base = raw.get("default").cloned().unwrap_or_default();
+    raw.iter()
+        .map(|(name, fields)| {
+            let mut merged = base.clone();
+            merged.extend(fields.clone());
+            let limits = serde_json::from_value(merged.into())
+                .with_context(|| format!("parsing limits for '{name}' in {}", path.display()))?;
+            Ok((name.clone(), limits))
+        })
+        .collect()
+}
+
+/// The limits for script `name`: its own, else `default`, else built in.
+pub fn limits_for(limits: &HashMap<String, ScriptLimits>, name: &str) -> ScriptLimits {
+    limits
+        .get(name)
+        .or_else(|| limits.get("default"))
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Read each extraction script from the first location that has it, with
+/// its limits. Returns the scripts and how many were found rather than
+/// stubbed.
+fn read_scripts() -> Result<(Vec<Script>, usize)> {
+    let limits = load_limits(&limits_path())?;
+    let mut scripts = Vec::new();
+    let mut found_count = 0;
+
+    // Look for scripts in several locations
+    let mut search_paths = vec![
+        // Relative to CWD
+        PathBuf::from("extractors/dist"),
+        // Relative to workspace root (from runtime/)
+        PathBuf::from("../extractors/dist"),
+        // Embedded in source tree
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/extraction/scripts"),
+    ];
+
+    // Relative to binary location
+    if let Ok(exe) = std::env::current_exe() {
+        if let Some(exe_dir) = exe.parent() {
+            // Binary is typically at runtime/target/release/cortex
+            // Extractors at extractors/dist (3 levels up then into extractors/dist)
+            search_paths.insert(0, exe_dir.join("../../../extractors/dist"));
+            search_paths.insert(1, exe_dir.join("../../extractors/dist"));
+            search_paths.insert(2, exe_dir.join("../extractors/dist"));
+        }
+    }
+
+    for name in EXTRACTOR_NAMES {
+        let filename = format!("{name}.js");
+        let mut found = false;
+
+        for base in &search_paths {
+            let path = base.join(&filename);
+            if path.exists() {
+                let content = std::fs::read_to_string(&path)
+                    .with_context(|| format!("reading {}", path.display()))?;
+                info!("loaded extractor {name}.js from {}", path.display());
+                scripts.push(Script {
+                    name: name.to_string(),
+                    source: content,
+                    limits: limits_for(&limits, name),
+                });
+                found = true;
+                found_count += 1;
+                break;
             }
         }
 
-        Ok(Self { scripts })
+        if !found {
+            warn!("extractor script {name}.js not found, using fallback");
+            // Use a minimal fallback script that returns empty data
+            scripts.push(Script {
+                name: name.to_string(),
+                source: format!(
+                    "var CortexExtractor_{name} = {{ default: function() {{ return []; }} }};"
+                ),
+                limits: limits_for(&limits, name),
+            });
+        }
     }
 
-    /// Inject all extraction scripts into a page and collect results.
-    pub async fn inject_and_run(&self, context: &dyn RenderContext) -> Result<ExtractionResult> {
-        // Inject all scripts (continue on individual failures)
-        for (name, script) in &self.scripts {
-            if let Err(e) = context.execute_js(script).await {
-                warn!("failed to inject {name} extractor: {e}");
+    Ok((scripts, found_count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::NavigationResult;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+    /// A page whose extractor entry points reply with `reply`, or never
+    /// return if `hang` is set. Each heap reading grows by `heap_step`.
+    struct FakePage {
+        reply: serde_json::Value,
+        hang: bool,
+        heap: AtomicU64,
+        heap_step: u64,
+        terminated: AtomicBool,
+    }
+
+    impl FakePage {
+        fn new(reply: serde_json::Value) -> Self {
+            Self {
+                reply,
+                hang: false,
+                heap: AtomicU64::new(0),
+                heap_step: 0,
+                terminated: AtomicBool::new(false),
             }
         }
+    }
 
-        // Run the extraction entry point with a timeout
-        let run_script = r#"
-            (function() {
-                var result = {};
-                try {
-                    if (typeof __cortex_extractContent === 'function')
-                        result.content = __cortex_extractContent(document);
-                    else if (typeof CortexExtractor_content !== 'undefined')
-                        result.content = [];
-                    else
-                        result.content = [];
-                } catch(e) { result.content = []; }
-
-                try {
-                    if (typeof __cortex_extractActions === 'function')
-                        result.actions = __cortex_extractActions(document);
-                    else
-                        result.actions = [];
-                } catch(e) { result.actions = []; }
-
-                try {
-                    if (typeof __cortex_extractNavigation === 'function')
-                        result.navigation = __cortex_extractNavigation(document);
-                    else
-                        result.navigation = [];
-                } catch(e) { result.navigation = []; }
-
-                try {
-                    if (typeof __cortex_extractStructure === 'function')
-                        result.structure = __cortex_extractStructure(document);
-                    else
-                        result.structure = {};
-                } catch(e) { result.structure = {}; }
-
-                try {
-                    if (typeof __cortex_extractMetadata === 'function')
-                        result.metadata = __cortex_extractMetadata(document);
-                    else
-                        result.metadata = {};
-                } catch(e) { result.metadata = {}; }
-
-                return result;
-            })()
-        "#;
-
-        let result = context
-            .execute_js(run_script)
-            .await
-            .context("running extraction")?;
+    #[async_trait]
+    impl RenderContext for FakePage {
+        async fn navigate(&mut self, url: &str, _timeout_ms: u64) -> Result<NavigationResult> {
+            Ok(NavigationResult {
+                final_url: url.to_string(),
+                status: 200,
+                redirect_chain: Vec::new(),
+                load_time_ms: 0,
+            })
+        }
+        async fn execute_js(&self, script: &str) -> Result<serde_json::Value> {
+            if !script.contains("typeof") {
+                return Ok(serde_json::Value::Null);
+            }
+            if self.hang {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+            }
+            Ok(self.reply.clone())
+        }
+        async fn get_html(&self) -> Result<String> {
+            Ok(String::new())
+        }
+        async fn get_url(&self) -> Result<String> {
+            Ok(String::new())
+        }
+        async fn close(self: Box<Self>) -> Result<()> {
+            Ok(())
+        }
+        async fn terminate_js(&self) -> Result<()> {
+            self.terminated.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+        async fn heap_used(&self) -> Result<Option<u64>> {
+            Ok(Some(self.heap.fetch_add(self.heap_step, Ordering::SeqCst)))
+        }
+    }
 
-        Ok(ExtractionResult {
-            content: result.get("content").cloned().unwrap_or_default(),
-            actions: result.get("actions").cloned().unwrap_or_default(),
-            navigation: result.get("navigation").cloned().unwrap_or_default(),
-            structure: result.get("structure").cloned().unwrap_or_default(),
-            metadata: result.get("metadata").cloned().unwrap_or_default(),
-        })
+    fn script(name: &str, limits: ScriptLimits) -> Script {
+        Script {
+            name: name.to_string(),
+            source: String::new(),
+            limits,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_script_limits() {
+        let limits = ScriptLimits {
+            timeout_ms: 50,
+            max_heap_bytes: 1000,
+            max_result_bytes: 100,
+        };
+
+        let page = FakePage::new(serde_json::json!({ "value": [1, 2], "bytes": 5 }));
+        let run = run_script(&page, &script("content", limits)).await;
+        assert_eq!(run.outcome, ScriptOutcome::Ok);
+        assert_eq!(run.value, serde_json::json!([1, 2]));
+        assert_eq!(run.heap_bytes, Some(0));
+
+        // Oversized results are measured in the page and never sent back.
+        let page = FakePage::new(serde_json::json!({ "bytes": 5000 }));
+        let run = run_script(&page, &script("structure", limits)).await;
+        assert_eq!(run.outcome, ScriptOutcome::TooLarge { bytes: 5000 });
+        assert_eq!(run.value, serde_json::json!({}));
+
+        let mut page = FakePage::new(serde_json::json!({ "value": [1], "bytes": 3 }));
+        page.heap_step = 4096;
+        let run = run_script(&page, &script("actions", limits)).await;
+        assert_eq!(run.outcome, ScriptOutcome::OverMemory { bytes: 4096 });
+        assert_eq!(run.value, serde_json::json!([]));
+
+        let page = FakePage::new(serde_json::json!({ "error": "boom" }));
+        let run = run_script(&page, &script("actions", limits)).await;
+        assert_eq!(
+            run.outcome,
+            ScriptOutcome::Failed {
+                error: "boom".into()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hung_script_is_terminated() {
+        let limits = ScriptLimits {
+            timeout_ms: 50,
+            ..ScriptLimits::default()
+        };
+        let loader = ExtractionLoader {
+            scripts: RwLock::new(Arc::new(vec![
+                script("content", limits),
+                script("metadata", limits),
+            ])),
+            custom: RwLock::new(Arc::new(Vec::new())),
+        };
+        let mut page = FakePage::new(serde_json::json!({ "value": [1], "bytes": 3 }));
+        page.hang = true;
+
+        let started = Instant::now();
+        let result = loader.inject_and_run(&page).await.unwrap();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(page.terminated.load(Ordering::SeqCst));
+        assert_eq!(result.content, serde_json::json!([]));
+        assert_eq!(result.metadata, serde_json::json!({}));
+    }
+
+    #[tokio::test]
+    async fn test_script_over_memory_is_stopped_while_running() {
+        let limits = ScriptLimits {
+            timeout_ms: 10_000,
+            max_heap_bytes: 10_000,
+            ..ScriptLimits::default()
+        };
+        let mut page = FakePage::new(serde_json::json!({ "value": [1], "bytes": 3 }));
+        page.hang = true;
+        page.heap_step = 4096;
+
+        let started = Instant::now();
+        let run = run_script(&page, &script("content", limits)).await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(matches!(run.outcome, ScriptOutcome::OverMemory { bytes } if bytes > 10_000));
+        assert!(run.running);
+        assert_eq!(run.value, serde_json::json!([]));
+
+        let mut stuck = false;
+        run_guarded(&page, &script("content", limits), &mut stuck).await;
+        assert!(page.terminated.load(Ordering::SeqCst));
+        assert!(!stuck);
+    }
+
+    #[test]
+    fn test_load_limits_merges_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("extractor_limits.json");
+        assert!(load_limits(&path).unwrap().is_empty());
+
+        std::fs::write(
+            &path,
+            r#"{"default": {"timeout_ms": 3000}, "content": {"max_result_bytes": 10}}"#,
+        )
+        .unwrap();
+        let limits = load_limits(&path).unwrap();
+        let content = limits_for(&limits, "content");
+        assert_eq!(content.timeout_ms, 3000);
+        assert_eq!(content.max_result_bytes, 10);
+        let actions = limits_for(&limits, "actions");
+        assert_eq!(actions.timeout_ms, 3000);
+        assert_eq!(
+            actions.max_heap_bytes,
+            ScriptLimits::default().max_heap_bytes
+        );
+        assert_eq!(limits_for(&HashMap::new(), "x"), ScriptLimits::default());
     }
 }
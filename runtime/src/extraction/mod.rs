@@ -2,6 +2,7 @@
 //!
 //! Loads compiled JavaScript extraction bundles and injects them into
 //! browser contexts to extract content, actions, navigation, structure,
-//! and metadata from web pages.
+//! and metadata from web pages, plus custom scripts registered per domain.
 
+pub mod custom;
 pub mod loader;
@@ -1,20 +1,41 @@
 //! Map caching — store and retrieve serialized SiteMaps.
 //!
-//! ## LRU eviction
+//! ## Eviction
 //!
-//! When the cache exceeds `max_entries`, the least-recently-accessed entry
-//! is evicted (both from the index and from disk).
+//! The cache is bounded by a number of entries and a total size in bytes
+//! ([`CachePolicy`], read from `~/.cortex/cache.json`). When a write takes it
+//! over either budget, expired entries are dropped first, then entries are
+//! evicted by the configured [`Eviction`] order until it fits again.
+//!
+//! Each map is classified into a [`ContentType`] from its page types when it
+//! is cached, and expires after that type's TTL: news and social maps go
+//! stale within minutes, documentation lasts a day.
+//!
+//! Access counts, recency and hit/miss statistics live in `index.json` next
+//! to the maps, so they survive across CLI invocations and daemon restarts.
 
-use crate::map::types::{SiteMap, FORMAT_VERSION};
+use crate::map::embeddings::MapEmbeddings;
+use crate::map::mapped::MappedSiteMap;
+use crate::map::text::MapText;
+use crate::map::types::{PageType, SiteMap, FORMAT_VERSION};
+use crate::trust::lineage::MapLineage;
 use anyhow::{bail, Context, Result};
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
-use std::time::{Duration, Instant, SystemTime};
+use std::time::{Duration, SystemTime};
 
-/// Default maximum number of cached maps before LRU eviction.
+/// Default maximum number of cached maps before eviction.
 const DEFAULT_MAX_ENTRIES: usize = 50;
 
+/// Default total size of cached maps before eviction (1 GiB).
+const DEFAULT_MAX_BYTES: u64 = 1 << 30;
+
+/// Name of the statistics file in the cache directory.
+const INDEX_FILE: &str = "index.json";
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum StorageMigrationPolicy {
     AutoSafe,
@@ -33,6 +54,172 @@ impl StorageMigrationPolicy {
     }
 }
 
+/// Which entry to evict when the cache is over budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Eviction {
+    /// Least recently accessed first.
+    #[default]
+    Lru,
+    /// Least frequently accessed first (ties by recency).
+    Lfu,
+    /// Closest to expiry first.
+    Ttl,
+}
+
+impl Eviction {
+    /// Lowercase name, as used in `cache.json`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Lru => "lru",
+            Self::Lfu => "lfu",
+            Self::Ttl => "ttl",
+        }
+    }
+}
+
+/// Kind of site a cached map describes, which decides how long it stays fresh.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentType {
+    Commerce,
+    News,
+    Social,
+    Docs,
+    #[default]
+    Other,
+}
+
+impl ContentType {
+    /// Classify a map by the content page types it has the most of.
+    pub fn of_map(map: &SiteMap) -> Self {
+        let mut counts: BTreeMap<ContentType, usize> = BTreeMap::new();
+        for node in &map.nodes {
+            if let Some(ct) = Self::of_page_type(node.page_type) {
+                *counts.entry(ct).or_default() += 1;
+            }
+        }
+        counts
+            .into_iter()
+            .max_by_key(|&(ct, n)| (n, std::cmp::Reverse(ct)))
+            .map_or(Self::Other, |(ct, _)| ct)
+    }
+
+    fn of_page_type(page_type: PageType) -> Option<Self> {
+        match page_type {
+            PageType::ProductDetail
+            | PageType::ProductListing
+            | PageType::Comparison
+            | PageType::ReviewList
+            | PageType::PricingPage
+            | PageType::Cart
+            | PageType::Checkout => Some(Self::Commerce),
+            PageType::Article | PageType::MediaPage => Some(Self::News),
+            PageType::SocialFeed | PageType::Forum | PageType::Messaging => Some(Self::Social),
+            PageType::Documentation | PageType::ApiDocs | PageType::Faq => Some(Self::Docs),
+            _ => None,
+        }
+    }
+
+    /// Lowercase name, as used in `cache.json`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Commerce => "commerce",
+            Self::News => "news",
+            Self::Social => "social",
+            Self::Docs => "docs",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// Size budgets, eviction order and TTLs for the map cache.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CachePolicy {
+    pub eviction: Eviction,
+    /// Maximum number of cached maps.
+    pub max_entries: usize,
+    /// Maximum total size of cached maps.
+    pub max_bytes: u64,
+    /// TTL for content types without an entry in `ttl_secs`.
+    pub default_ttl_secs: u64,
+    /// TTL per content type.
+    pub ttl_secs: BTreeMap<ContentType, u64>,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        Self {
+            eviction: Eviction::Lru,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            max_bytes: DEFAULT_MAX_BYTES,
+            default_ttl_secs: 3600,
+            ttl_secs: BTreeMap::from([
+                (ContentType::News, 900),
+                (ContentType::Social, 900),
+                (ContentType::Commerce, 3600),
+                (ContentType::Docs, 86_400),
+            ]),
+        }
+    }
+}
+
+impl CachePolicy {
+    /// Load a policy from a JSON file. Missing file = defaults; missing
+    /// fields take their default values.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = fs::read_to_string(path)?;
+        serde_json::from_str(&data)
+            .with_context(|| format!("parsing cache policy: {}", path.display()))
+    }
+
+    /// Load the policy from the default location (~/.cortex/cache.json).
+    pub fn load_default() -> Result<Self> {
+        Self::load(&policy_path())
+    }
+}
+
+/// Default cache policy file location.
+pub fn policy_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join(".cortex")
+        .join("cache.json")
+}
+
+/// Lifetime lookup and eviction counters.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+struct Counters {
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+    expired: u64,
+}
+
+/// Persisted per-entry metadata.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EntryMeta {
+    content_type: ContentType,
+    hits: u64,
+    last_accessed: u64,
+}
+
+/// Contents of `index.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct IndexFile {
+    counters: Counters,
+    /// Logical clock for recency ordering.
+    clock: u64,
+    entries: HashMap<String, EntryMeta>,
+}
+
 /// Cache entry with metadata.
 struct CacheEntry {
     /// Path to the serialized map file.
@@ -41,8 +228,13 @@ struct CacheEntry {
     cached_at: SystemTime,
     /// Cache TTL.
     ttl: Duration,
-    /// When the entry was last accessed (for LRU).
-    last_accessed: Instant,
+    /// Size of the map file.
+    bytes: u64,
+    content_type: ContentType,
+    /// Successful lookups (for LFU).
+    hits: u64,
+    /// Logical time of the last access (for LRU).
+    last_accessed: u64,
 }
 
 impl CacheEntry {
@@ -53,94 +245,190 @@ impl CacheEntry {
             .unwrap_or(true)
     }
 
-    fn touch(&mut self) {
-        self.last_accessed = Instant::now();
+    fn expires_at(&self) -> SystemTime {
+        self.cached_at + self.ttl
     }
 }
 
-/// Map cache backed by the filesystem with LRU eviction.
+/// Usage of the cache by one content type.
+#[derive(Debug, Clone, Serialize)]
+pub struct TypeStats {
+    pub content_type: ContentType,
+    pub entries: usize,
+    pub bytes: u64,
+    pub hits: u64,
+    pub ttl_secs: u64,
+}
+
+/// One cached map in [`CacheStats`].
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryStats {
+    pub domain: String,
+    pub content_type: ContentType,
+    pub bytes: u64,
+    pub hits: u64,
+    pub expired: bool,
+}
+
+/// Size, budget and hit-rate summary of the cache.
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheStats {
+    pub eviction: Eviction,
+    pub entries: usize,
+    pub max_entries: usize,
+    pub bytes: u64,
+    pub max_bytes: u64,
+    pub hits: u64,
+    pub misses: u64,
+    /// Hits over lookups, 0 when nothing was looked up yet.
+    pub hit_rate: f64,
+    pub evictions: u64,
+    /// Entries dropped because they had expired.
+    pub expired: u64,
+    pub by_type: Vec<TypeStats>,
+    /// Cached maps, most hit first.
+    pub maps: Vec<EntryStats>,
+}
+
+/// Map cache backed by the filesystem with budgeted eviction.
 pub struct MapCache {
     /// Base directory for cached maps.
     cache_dir: PathBuf,
     /// In-memory index of cached maps.
     index: HashMap<String, CacheEntry>,
-    /// Default TTL for cached maps.
+    policy: CachePolicy,
+    /// TTL for content types without their own.
     default_ttl: Duration,
-    /// Maximum number of cached maps before LRU eviction.
-    max_entries: usize,
+    counters: Counters,
+    clock: u64,
 }
 
 impl MapCache {
-    /// Create a new map cache in the given directory.
-    ///
-    /// On creation, scans the cache directory for existing `.ctx` files and
-    /// rebuilds the in-memory index so that previously cached maps are
-    /// immediately available for lookup.
+    /// Create a new map cache in the given directory, with the default
+    /// budgets and a single TTL for every content type.
     pub fn new(cache_dir: PathBuf, default_ttl: Duration) -> Result<Self> {
+        let policy = CachePolicy {
+            default_ttl_secs: default_ttl.as_secs(),
+            ttl_secs: BTreeMap::new(),
+            ..CachePolicy::default()
+        };
+        Self::open(cache_dir, policy, default_ttl)
+    }
+
+    /// Create a map cache in the given directory with an explicit policy.
+    pub fn with_policy(cache_dir: PathBuf, policy: CachePolicy) -> Result<Self> {
+        let default_ttl = Duration::from_secs(policy.default_ttl_secs);
+        Self::open(cache_dir, policy, default_ttl)
+    }
+
+    /// Create a cache with default settings (~/.cortex/maps/, policy from
+    /// ~/.cortex/cache.json).
+    pub fn default_cache() -> Result<Self> {
+        let cache_dir = dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("/tmp"))
+            .join(".cortex")
+            .join("maps");
+        Self::with_policy(cache_dir, CachePolicy::load_default()?)
+    }
+
+    /// Scans the cache directory for existing `.ctx` files and rebuilds the
+    /// in-memory index, so that previously cached maps are immediately
+    /// available for lookup. Access statistics come from `index.json`.
+    fn open(cache_dir: PathBuf, policy: CachePolicy, default_ttl: Duration) -> Result<Self> {
         fs::create_dir_all(&cache_dir)
             .with_context(|| format!("failed to create cache dir: {}", cache_dir.display()))?;
 
-        let mut index = HashMap::new();
+        let saved: IndexFile = fs::read(cache_dir.join(INDEX_FILE))
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+
+        let mut cache = Self {
+            cache_dir,
+            index: HashMap::new(),
+            policy,
+            default_ttl,
+            counters: saved.counters,
+            clock: saved.clock,
+        };
 
         // Scan the cache directory for existing .ctx files and rebuild the index.
-        if let Ok(entries) = fs::read_dir(&cache_dir) {
+        if let Ok(entries) = fs::read_dir(&cache.cache_dir) {
             for entry in entries.flatten() {
                 let path = entry.path();
-                if path.extension().and_then(|e| e.to_str()) == Some("ctx") {
-                    if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
-                        // Reverse the filename encoding: underscores back to colons
-                        let domain = stem.replace('_', ":");
-                        // Use the file's modification time as cached_at
-                        let cached_at = entry
-                            .metadata()
-                            .and_then(|m| m.modified())
-                            .unwrap_or_else(|_| SystemTime::now());
-                        index.insert(
-                            domain,
-                            CacheEntry {
-                                path,
-                                cached_at,
-                                ttl: default_ttl,
-                                last_accessed: Instant::now(),
-                            },
-                        );
-                    }
+                if path.extension().and_then(|e| e.to_str()) != Some("ctx") {
+                    continue;
                 }
+                let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+                    continue;
+                };
+                // Reverse the filename encoding: underscores back to colons
+                let domain = stem.replace('_', ":");
+                let meta = entry.metadata().ok();
+                // Use the file's modification time as cached_at
+                let cached_at = meta
+                    .as_ref()
+                    .and_then(|m| m.modified().ok())
+                    .unwrap_or_else(SystemTime::now);
+                let bytes = meta.map_or(0, |m| m.len());
+                let (content_type, hits, last_accessed) = match saved.entries.get(&domain) {
+                    Some(m) => (m.content_type, m.hits, m.last_accessed),
+                    None => (ContentType::Other, 0, 0),
+                };
+                let ttl = cache.ttl_for(content_type);
+                cache.index.insert(
+                    domain,
+                    CacheEntry {
+                        path,
+                        cached_at,
+                        ttl,
+                        bytes,
+                        content_type,
+                        hits,
+                        last_accessed,
+                    },
+                );
             }
         }
 
         tracing::debug!(
             "MapCache initialized: {} entries from {}",
-            index.len(),
-            cache_dir.display()
+            cache.index.len(),
+            cache.cache_dir.display()
         );
 
-        Ok(Self {
-            cache_dir,
-            index,
-            default_ttl,
-            max_entries: DEFAULT_MAX_ENTRIES,
-        })
+        Ok(cache)
     }
 
-    /// Create a cache with default settings (~/.cortex/maps/, 1 hour TTL).
-    pub fn default_cache() -> Result<Self> {
-        let cache_dir = dirs::home_dir()
-            .unwrap_or_else(|| PathBuf::from("/tmp"))
-            .join(".cortex")
-            .join("maps");
-        Self::new(cache_dir, Duration::from_secs(3600))
+    /// The policy in effect.
+    pub fn policy(&self) -> &CachePolicy {
+        &self.policy
+    }
+
+    /// How long maps of the given content type stay fresh.
+    pub fn ttl_for(&self, content_type: ContentType) -> Duration {
+        self.policy
+            .ttl_secs
+            .get(&content_type)
+            .map_or(self.default_ttl, |&secs| Duration::from_secs(secs))
     }
 
     /// Get a cached map for the domain if it exists and is fresh.
     pub fn get(&mut self, domain: &str) -> Option<&Path> {
-        // Touch for LRU tracking
-        if let Some(entry) = self.index.get_mut(domain) {
-            if entry.is_expired() {
-                return None;
+        let hit = self.index.get(domain).is_some_and(|e| !e.is_expired());
+        if hit {
+            let seq = self.tick();
+            if let Some(entry) = self.index.get_mut(domain) {
+                entry.hits += 1;
+                entry.last_accessed = seq;
             }
-            entry.touch();
-            Some(&entry.path)
+            self.counters.hits += 1;
+        } else {
+            self.counters.misses += 1;
+        }
+        self.save_index();
+        if hit {
+            self.index.get(domain).map(|e| e.path.as_path())
         } else {
             None
         }
@@ -148,36 +436,70 @@ impl MapCache {
 
     /// Cache a serialized map for the domain.
     ///
-    /// If the cache is full, the least-recently-used entry is evicted first.
+    /// If this takes the cache over budget, other entries are evicted.
     pub fn put(&mut self, domain: &str, data: &[u8]) -> Result<PathBuf> {
-        // Evict LRU entry if at capacity
-        if self.index.len() >= self.max_entries && !self.index.contains_key(domain) {
-            self.evict_lru();
-        }
+        self.store(domain, data, ContentType::Other)
+    }
 
+    fn store(&mut self, domain: &str, data: &[u8], content_type: ContentType) -> Result<PathBuf> {
         let filename = format!("{}.ctx", domain.replace(':', "_"));
         let path = self.cache_dir.join(&filename);
 
-        fs::write(&path, data)
+        // Write then rename, so readers holding a mapping of the old file
+        // never see it truncated underneath them.
+        let tmp = self.cache_dir.join(format!(".{filename}.tmp"));
+        fs::write(&tmp, data)
+            .with_context(|| format!("failed to write cache file: {}", tmp.display()))?;
+        fs::rename(&tmp, &path)
             .with_context(|| format!("failed to write cache file: {}", path.display()))?;
 
+        // A re-cached map keeps its popularity.
+        let hits = self.index.get(domain).map_or(0, |e| e.hits);
+        let last_accessed = self.tick();
+        let ttl = self.ttl_for(content_type);
         self.index.insert(
             domain.to_string(),
             CacheEntry {
                 path: path.clone(),
                 cached_at: SystemTime::now(),
-                ttl: self.default_ttl,
-                last_accessed: Instant::now(),
+                ttl,
+                bytes: data.len() as u64,
+                content_type,
+                hits,
+                last_accessed,
             },
         );
 
+        self.enforce_budget(domain);
+        self.save_index();
         Ok(path)
     }
 
-    /// Cache a SiteMap by serializing it.
+    /// Cache a SiteMap by serializing it. Its lineage, page text and text
+    /// embeddings, if any, are written next to it as `<domain>.lineage.json`,
+    /// `<domain>.text.json` and `<domain>.embeddings.bin`.
     pub fn cache_map(&mut self, domain: &str, map: &SiteMap) -> Result<PathBuf> {
         let data = map.serialize();
-        self.put(domain, &data)
+        let path = self.store(domain, &data, ContentType::of_map(map))?;
+        let lineage = lineage_path(&path);
+        if map.lineage.is_empty() {
+            let _ = fs::remove_file(&lineage);
+        } else {
+            map.lineage.save(&lineage)?;
+        }
+        let text = text_path(&path);
+        if map.text.is_empty() {
+            let _ = fs::remove_file(&text);
+        } else {
+            map.text.save(&text)?;
+        }
+        let embeddings = embeddings_path(&path);
+        if map.embeddings.is_empty() {
+            let _ = fs::remove_file(&embeddings);
+        } else {
+            map.embeddings.save(&embeddings)?;
+        }
+        Ok(path)
     }
 
     /// Load a cached SiteMap for the domain.
@@ -189,6 +511,10 @@ impl MapCache {
 
         let data = fs::read(path)
             .with_context(|| format!("failed to read cached map: {}", path.display()))?;
+        let lineage = MapLineage::load(&lineage_path(path)).unwrap_or_else(|e| {
+            tracing::warn!("ignoring lineage for {domain}: {e}");
+            MapLineage::default()
+        });
         let legacy_version = detect_ctx_format_version(&data).filter(|v| *v < FORMAT_VERSION);
         let migration_policy = StorageMigrationPolicy::from_env("CORTEX_STORAGE_MIGRATION_POLICY");
 
@@ -202,8 +528,19 @@ impl MapCache {
             }
         }
 
-        let map = SiteMap::deserialize(&data)
+        let mut map = SiteMap::deserialize(&data)
             .with_context(|| format!("failed to deserialize cached map for {}", domain))?;
+        // Lineage and text are only meaningful for the map they were
+        // recorded with.
+        if lineage
+            .nodes
+            .keys()
+            .all(|&n| (n as usize) < map.nodes.len())
+        {
+            map.lineage = lineage;
+        }
+        map.text = load_text(path, domain, map.nodes.len());
+        map.embeddings = load_embeddings(path, domain, map.nodes.len());
 
         if let Some(version) = legacy_version {
             match migration_policy {
@@ -235,6 +572,41 @@ impl MapCache {
         Ok(Some(map))
     }
 
+    /// Open a cached map for zero-copy reads, without decoding it.
+    ///
+    /// Legacy-format maps go through [`load_map`](Self::load_map) first, so
+    /// they are migrated according to the storage migration policy.
+    pub fn open_mapped(&mut self, domain: &str) -> Result<Option<MappedSiteMap>> {
+        let path = match self.get(domain) {
+            Some(p) => p.to_path_buf(),
+            None => return Ok(None),
+        };
+
+        let mut version = [0u8; 6];
+        fs::File::open(&path)
+            .and_then(|mut f| f.read_exact(&mut version))
+            .with_context(|| format!("failed to read cached map: {}", path.display()))?;
+        if detect_ctx_format_version(&version).is_some_and(|v| v < FORMAT_VERSION) {
+            return match self.load_map(domain)? {
+                Some(map) => Ok(Some(MappedSiteMap::from_bytes(map.serialize())?)),
+                None => Ok(None),
+            };
+        }
+
+        MappedSiteMap::open(&path)
+            .map(Some)
+            .with_context(|| format!("failed to open cached map for {}", domain))
+    }
+
+    /// Page text kept next to `domain`'s cached map of `node_count` nodes,
+    /// for maps opened with [`open_mapped`](Self::open_mapped).
+    pub fn map_text(&self, domain: &str, node_count: usize) -> MapText {
+        match self.index.get(domain) {
+            Some(entry) => load_text(&entry.path, domain, node_count),
+            None => MapText::default(),
+        }
+    }
+
     /// Load all cached (non-expired) SiteMaps, returning a domain → SiteMap map.
     pub fn load_all_maps(&mut self) -> Result<HashMap<String, SiteMap>> {
         let domains: Vec<String> = self
@@ -255,37 +627,60 @@ impl MapCache {
 
     /// Invalidate (remove) a cached map.
     pub fn invalidate(&mut self, domain: &str) {
-        if let Some(entry) = self.index.remove(domain) {
-            let _ = fs::remove_file(&entry.path);
+        if self.remove_entry(domain) {
+            self.save_index();
         }
     }
 
-    /// Evict the least-recently-used cache entry.
-    fn evict_lru(&mut self) {
-        // First, try to evict expired entries
-        let expired: Vec<String> = self
-            .index
-            .iter()
-            .filter(|(_, entry)| entry.is_expired())
-            .map(|(domain, _)| domain.clone())
-            .collect();
-
-        if !expired.is_empty() {
-            for domain in expired {
-                self.invalidate(&domain);
+    fn remove_entry(&mut self, domain: &str) -> bool {
+        match self.index.remove(domain) {
+            Some(entry) => {
+                let _ = fs::remove_file(&entry.path);
+                let _ = fs::remove_file(lineage_path(&entry.path));
+                let _ = fs::remove_file(text_path(&entry.path));
+                let _ = fs::remove_file(embeddings_path(&entry.path));
+                true
             }
+            None => false,
+        }
+    }
+
+    /// Whether the cache holds more entries or bytes than the policy allows.
+    fn over_budget(&self) -> bool {
+        self.index.len() > self.policy.max_entries || self.total_bytes() > self.policy.max_bytes
+    }
+
+    /// Bring the cache back within budget, never evicting `keep` (the entry
+    /// just written). Expired entries go first, then entries in eviction order.
+    fn enforce_budget(&mut self, keep: &str) {
+        if !self.over_budget() {
             return;
         }
 
-        // Otherwise, find the least-recently-accessed entry
-        if let Some(lru_domain) = self
+        let expired: Vec<String> = self
             .index
             .iter()
-            .min_by_key(|(_, entry)| entry.last_accessed)
+            .filter(|(domain, entry)| *domain != keep && entry.is_expired())
             .map(|(domain, _)| domain.clone())
-        {
-            tracing::info!("evicting LRU cache entry: {lru_domain}");
-            self.invalidate(&lru_domain);
+            .collect();
+        for domain in expired {
+            self.remove_entry(&domain);
+            self.counters.expired += 1;
+        }
+
+        while self.over_budget() {
+            let candidates = self.index.iter().filter(|(domain, _)| *domain != keep);
+            let victim = match self.policy.eviction {
+                Eviction::Lru => candidates.min_by_key(|(_, e)| e.last_accessed),
+                Eviction::Lfu => candidates.min_by_key(|(_, e)| (e.hits, e.last_accessed)),
+                Eviction::Ttl => candidates.min_by_key(|(_, e)| (e.expires_at(), e.last_accessed)),
+            };
+            let Some(victim) = victim.map(|(domain, _)| domain.clone()) else {
+                break;
+            };
+            tracing::info!("evicting {:?} cache entry: {victim}", self.policy.eviction);
+            self.remove_entry(&victim);
+            self.counters.evictions += 1;
         }
     }
 
@@ -299,6 +694,11 @@ impl MapCache {
         self.index.is_empty()
     }
 
+    /// Total size of the cached maps.
+    pub fn total_bytes(&self) -> u64 {
+        self.index.values().map(|e| e.bytes).sum()
+    }
+
     /// Remove all expired entries.
     pub fn cleanup_expired(&mut self) {
         let expired: Vec<String> = self
@@ -308,8 +708,98 @@ impl MapCache {
             .map(|(domain, _)| domain.clone())
             .collect();
 
+        if expired.is_empty() {
+            return;
+        }
         for domain in expired {
-            self.invalidate(&domain);
+            self.remove_entry(&domain);
+            self.counters.expired += 1;
+        }
+        self.save_index();
+    }
+
+    /// Size, budget and hit-rate summary.
+    pub fn stats(&self) -> CacheStats {
+        let mut by_type: BTreeMap<ContentType, TypeStats> = BTreeMap::new();
+        let mut maps = Vec::with_capacity(self.index.len());
+        for (domain, entry) in &self.index {
+            let t = by_type
+                .entry(entry.content_type)
+                .or_insert_with(|| TypeStats {
+                    content_type: entry.content_type,
+                    entries: 0,
+                    bytes: 0,
+                    hits: 0,
+                    ttl_secs: self.ttl_for(entry.content_type).as_secs(),
+                });
+            t.entries += 1;
+            t.bytes += entry.bytes;
+            t.hits += entry.hits;
+            maps.push(EntryStats {
+                domain: domain.clone(),
+                content_type: entry.content_type,
+                bytes: entry.bytes,
+                hits: entry.hits,
+                expired: entry.is_expired(),
+            });
+        }
+        maps.sort_by(|a, b| b.hits.cmp(&a.hits).then_with(|| a.domain.cmp(&b.domain)));
+
+        let lookups = self.counters.hits + self.counters.misses;
+        CacheStats {
+            eviction: self.policy.eviction,
+            entries: self.index.len(),
+            max_entries: self.policy.max_entries,
+            bytes: self.total_bytes(),
+            max_bytes: self.policy.max_bytes,
+            hits: self.counters.hits,
+            misses: self.counters.misses,
+            hit_rate: if lookups == 0 {
+                0.0
+            } else {
+                self.counters.hits as f64 / lookups as f64
+            },
+            evictions: self.counters.evictions,
+            expired: self.counters.expired,
+            by_type: by_type.into_values().collect(),
+            maps,
+        }
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    /// Persist access statistics. Best effort: a failed write only loses
+    /// statistics, never cached maps.
+    fn save_index(&self) {
+        let file = IndexFile {
+            counters: self.counters,
+            clock: self.clock,
+            entries: self
+                .index
+                .iter()
+                .map(|(domain, e)| {
+                    (
+                        domain.clone(),
+                        EntryMeta {
+                            content_type: e.content_type,
+                            hits: e.hits,
+                            last_accessed: e.last_accessed,
+                        },
+                    )
+                })
+                .collect(),
+        };
+        let path = self.cache_dir.join(INDEX_FILE);
+        let tmp = self.cache_dir.join(format!(".{INDEX_FILE}.tmp"));
+        let result = serde_json::to_vec(&file)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| Ok(fs::write(&tmp, data)?))
+            .and_then(|_| Ok(fs::rename(&tmp, &path)?));
+        if let Err(e) = result {
+            tracing::warn!("failed to write cache index {}: {e}", path.display());
         }
     }
 
@@ -319,6 +809,49 @@ impl MapCache {
     }
 }
 
+/// Path of the lineage file kept next to a cached map.
+fn lineage_path(map_path: &Path) -> PathBuf {
+    map_path.with_extension("lineage.json")
+}
+
+/// Path of the page text file kept next to a cached map.
+fn text_path(map_path: &Path) -> PathBuf {
+    map_path.with_extension("text.json")
+}
+
+/// Page text kept next to a cached map of `node_count` nodes; empty if it is
+/// missing, unreadable or recorded for another map.
+fn load_text(map_path: &Path, domain: &str, node_count: usize) -> MapText {
+    let text = MapText::load(&text_path(map_path)).unwrap_or_else(|e| {
+        tracing::warn!("ignoring page text for {domain}: {e}");
+        MapText::default()
+    });
+    if text.nodes.keys().all(|&n| (n as usize) < node_count) {
+        text
+    } else {
+        MapText::default()
+    }
+}
+
+/// Path of the text embeddings file kept next to a cached map.
+fn embeddings_path(map_path: &Path) -> PathBuf {
+    map_path.with_extension("embeddings.bin")
+}
+
+/// Text embeddings kept next to a cached map of `node_count` nodes; empty if
+/// they are missing, unreadable or recorded for another map.
+fn load_embeddings(map_path: &Path, domain: &str, node_count: usize) -> MapEmbeddings {
+    let embeddings = MapEmbeddings::load(&embeddings_path(map_path)).unwrap_or_else(|e| {
+        tracing::warn!("ignoring text embeddings for {domain}: {e}");
+        MapEmbeddings::default()
+    });
+    if embeddings.nodes.keys().all(|&n| (n as usize) < node_count) {
+        embeddings
+    } else {
+        MapEmbeddings::default()
+    }
+}
+
 fn detect_ctx_format_version(data: &[u8]) -> Option<u16> {
     if data.len() < 6 {
         return None;
@@ -331,7 +864,9 @@ fn detect_ctx_format_version(data: &[u8]) -> Option<u16> {
 mod tests {
     use super::*;
     use crate::map::builder::SiteMapBuilder;
+    use crate::map::text::NodeText;
     use crate::map::types::PageType;
+    use crate::trust::lineage::{FieldLineage, Layer};
 
     #[test]
     fn test_cache_roundtrip() {
@@ -342,15 +877,54 @@ mod tests {
         let mut builder = SiteMapBuilder::new("test.com");
         let features = [0.0f32; 128];
         builder.add_node("https://test.com/", PageType::Home, features, 230);
-        let map = builder.build();
+        builder.record_lineage(
+            0,
+            "page_type",
+            FieldLineage::new(Layer::StructuredData, Some("@type"), 0.9, 100),
+        );
+        builder.set_text(0, NodeText::new([Some("Test Home")], [None]));
+        let mut map = builder.build();
+        map.embeddings.insert(0, vec![0.6, 0.8]).unwrap();
 
         // Cache it
         let path = cache.cache_map("test.com", &map).unwrap();
         assert!(path.exists());
 
-        // Load it back
+        // Load it back, with its lineage
         let loaded = cache.load_map("test.com").unwrap().unwrap();
         assert_eq!(loaded.nodes.len(), map.nodes.len());
+        assert_eq!(loaded.lineage, map.lineage);
+        assert_eq!(loaded.text, map.text);
+        assert_eq!(loaded.embeddings, map.embeddings);
+        assert_eq!(cache.map_text("test.com", 1), map.text);
+        assert!(cache.map_text("test.com", 0).is_empty());
+
+        // Invalidating removes the lineage, text and embeddings files too
+        cache.invalidate("test.com");
+        assert!(!lineage_path(&path).exists());
+        assert!(!text_path(&path).exists());
+        assert!(!embeddings_path(&path).exists());
+    }
+
+    #[test]
+    fn test_legacy_map_migrated_and_mapped() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut builder = SiteMapBuilder::new("test.com");
+        builder.add_node("https://test.com/", PageType::Home, [0.0f32; 128], 230);
+        builder.add_node("https://test.com/a", PageType::Article, [0.0f32; 128], 200);
+        let map = builder.build();
+        let path = dir.path().join("test.com.ctx");
+        fs::write(&path, map.serialize_v1()).unwrap();
+
+        let mut cache = MapCache::new(dir.path().to_path_buf(), Duration::from_secs(3600)).unwrap();
+        let mapped = cache.open_mapped("test.com").unwrap().unwrap();
+        assert_eq!(mapped.node_url(1), "https://test.com/a");
+
+        // Rewritten in the current format on first load.
+        let data = fs::read(&path).unwrap();
+        assert_eq!(detect_ctx_format_version(&data), Some(FORMAT_VERSION));
+        let mapped = cache.open_mapped("test.com").unwrap().unwrap();
+        assert_eq!(mapped.node_count(), 2);
     }
 
     #[test]
@@ -380,7 +954,7 @@ mod tests {
     fn test_lru_eviction() {
         let dir = tempfile::tempdir().unwrap();
         let mut cache = MapCache::new(dir.path().to_path_buf(), Duration::from_secs(3600)).unwrap();
-        cache.max_entries = 3;
+        cache.policy.max_entries = 3;
 
         // Fill cache to capacity
         cache.put("a.com", b"data-a").unwrap();
@@ -408,7 +982,7 @@ mod tests {
     fn test_lru_evicts_expired_first() {
         let dir = tempfile::tempdir().unwrap();
         let mut cache = MapCache::new(dir.path().to_path_buf(), Duration::from_secs(3600)).unwrap();
-        cache.max_entries = 3;
+        cache.policy.max_entries = 3;
 
         // Add with zero TTL (immediately expired)
         cache.put("expired.com", b"old").unwrap();
@@ -447,4 +1021,87 @@ mod tests {
         assert!(cache.get("a.com").is_none());
         assert!(cache.get("b.com").is_some());
     }
+
+    #[test]
+    fn test_lfu_eviction_within_byte_budget() {
+        let dir = tempfile::tempdir().unwrap();
+        let policy = CachePolicy {
+            eviction: Eviction::Lfu,
+            max_bytes: 20,
+            ..CachePolicy::default()
+        };
+        let mut cache = MapCache::with_policy(dir.path().to_path_buf(), policy).unwrap();
+
+        cache.put("a.com", b"aaaaaaaa").unwrap();
+        cache.put("b.com", b"bbbbbbbb").unwrap();
+        for _ in 0..3 {
+            let _ = cache.get("a.com");
+        }
+        let _ = cache.get("b.com");
+
+        // 24 bytes > 20: b.com is the least frequently used.
+        cache.put("c.com", b"cccccccc").unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(cache.total_bytes() <= 20);
+        assert!(cache.get("b.com").is_none());
+        assert!(cache.get("a.com").is_some());
+        assert_eq!(cache.stats().evictions, 1);
+    }
+
+    #[test]
+    fn test_stats_persist_across_instances() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let mut cache =
+                MapCache::new(dir.path().to_path_buf(), Duration::from_secs(3600)).unwrap();
+            cache.put("a.com", b"data-a").unwrap();
+            let _ = cache.get("a.com");
+            let _ = cache.get("a.com");
+            let _ = cache.get("missing.com");
+        }
+
+        let cache = MapCache::new(dir.path().to_path_buf(), Duration::from_secs(3600)).unwrap();
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (2, 1));
+        assert!((stats.hit_rate - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(stats.maps[0].domain, "a.com");
+        assert_eq!(stats.maps[0].hits, 2);
+        assert_eq!(stats.bytes, 6);
+    }
+
+    #[test]
+    fn test_ttl_per_content_type() {
+        let dir = tempfile::tempdir().unwrap();
+        let policy = CachePolicy {
+            ttl_secs: BTreeMap::from([(ContentType::News, 0)]),
+            ..CachePolicy::default()
+        };
+        let mut cache = MapCache::with_policy(dir.path().to_path_buf(), policy).unwrap();
+
+        let mut builder = SiteMapBuilder::new("news.com");
+        builder.add_node("https://news.com/", PageType::Home, [0.0f32; 128], 230);
+        builder.add_node("https://news.com/a", PageType::Article, [0.0f32; 128], 200);
+        builder.add_node("https://news.com/b", PageType::Article, [0.0f32; 128], 200);
+        let news = builder.build();
+        assert_eq!(ContentType::of_map(&news), ContentType::News);
+
+        let mut builder = SiteMapBuilder::new("shop.com");
+        builder.add_node(
+            "https://shop.com/p/1",
+            PageType::ProductDetail,
+            [0.0f32; 128],
+            200,
+        );
+        let shop = builder.build();
+
+        cache.cache_map("news.com", &news).unwrap();
+        cache.cache_map("shop.com", &shop).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(cache.get("news.com").is_none());
+        assert!(cache.get("shop.com").is_some());
+
+        let stats = cache.stats();
+        let types: Vec<_> = stats.by_type.iter().map(|t| t.content_type).collect();
+        assert_eq!(types, vec![ContentType::Commerce, ContentType::News]);
+    }
 }
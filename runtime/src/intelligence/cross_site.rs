@@ -1,6 +1,22 @@
-//! Cross-site queries — merge results from multiple SiteMaps.
+//! Cross-site queries — merge results from multiple SiteMaps, and resolve
+//! the same product across domains into one entity.
 
-use crate::map::types::{NodeMatch, NodeQuery, SiteMap};
+use crate::acquisition::structured::JsonLdProduct;
+use crate::map::types::{NodeMatch, NodeQuery, PageType, SiteMap, FEAT_PRICE};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Token-set (Jaccard) similarity at which two normalized titles match.
+pub const TITLE_SIMILARITY: f32 = 0.8;
+
+/// Title-only matches are rejected when prices differ by more than this factor.
+const MAX_PRICE_RATIO: f32 = 2.0;
+
+/// Title tokens shared by more candidates than this are too common to
+/// propose matches on their own.
+const MAX_TOKEN_POSTINGS: usize = 64;
+
+const STOP_WORDS: &[&str] = &["a", "an", "and", "by", "for", "in", "of", "the", "with"];
 
 /// A match from a cross-site query, including domain attribution.
 #[derive(Debug, Clone)]
@@ -72,6 +88,429 @@ pub fn cross_site_nearest(
     all_matches
 }
 
+/// Identifying attributes of a product page. Any may be missing.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ProductIdentity {
+    /// GTIN (UPC/EAN/ISBN), normalized to 14 digits.
+    pub gtin: Option<String>,
+    pub sku: Option<String>,
+    pub brand: Option<String>,
+    /// Manufacturer model or part number (MPN).
+    pub model: Option<String>,
+    pub title: Option<String>,
+}
+
+impl ProductIdentity {
+    /// Identity from JSON-LD / microdata product data.
+    pub fn from_jsonld(product: &JsonLdProduct) -> Self {
+        Self {
+            gtin: product.gtin.as_deref().and_then(normalize_gtin),
+            sku: product.sku.as_deref().and_then(normalize_code),
+            brand: product.brand.as_deref().map(normalize_title),
+            model: product.mpn.as_deref().and_then(normalize_code),
+            title: product.name.as_deref().map(normalize_title),
+        }
+    }
+
+    /// Identity from a product URL: a valid GTIN in the path or query, and
+    /// a title from the last slug of three or more words
+    /// (`/p/sony-wh-1000xm5-headphones/123`).
+    pub fn from_url(url: &str) -> Self {
+        let Ok(parsed) = url::Url::parse(url) else {
+            return Self::default();
+        };
+        let segments: Vec<&str> = parsed.path().split('/').collect();
+        let gtin = segments
+            .iter()
+            .map(|s| s.split('.').next().unwrap_or(s).to_string())
+            .chain(parsed.query_pairs().map(|(_, v)| v.into_owned()))
+            .filter(|s| (12..=14).contains(&s.len()))
+            .find_map(|s| normalize_gtin(&s));
+        let title = segments
+            .iter()
+            .rev()
+            .map(|s| normalize_title(s.split('.').next().unwrap_or(s)))
+            .find(|t| {
+                t.split(' ')
+                    .filter(|w| w.chars().any(char::is_alphabetic))
+                    .count()
+                    >= 3
+            });
+        Self {
+            gtin,
+            title,
+            ..Self::default()
+        }
+    }
+
+    /// The exact-match key for `basis`, if this identity has one. SKUs
+    /// shorter than six characters are too likely to collide.
+    fn key(&self, basis: MatchBasis) -> Option<String> {
+        match basis {
+            MatchBasis::Gtin => self.gtin.clone(),
+            MatchBasis::Sku => self.sku.clone().filter(|s| s.len() >= 6),
+            MatchBasis::BrandModel => {
+                Some(format!("{}/{}", self.brand.as_ref()?, self.model.as_ref()?))
+            }
+            MatchBasis::Title | MatchBasis::Unique => None,
+        }
+    }
+}
+
+/// A product page to resolve.
+#[derive(Debug, Clone)]
+pub struct EntityCandidate {
+    pub domain: String,
+    pub node: u32,
+    pub url: String,
+    pub identity: ProductIdentity,
+    pub price: Option<f32>,
+}
+
+/// Why members were merged into one entity, strongest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchBasis {
+    Gtin,
+    Sku,
+    BrandModel,
+    Title,
+    /// Not merged with anything.
+    Unique,
+}
+
+/// One page belonging to an entity.
+#[derive(Debug, Clone, Serialize)]
+pub struct EntityMember {
+    pub domain: String,
+    pub node: u32,
+    pub url: String,
+    pub price: Option<f32>,
+}
+
+/// The same product as found on one or more domains.
+#[derive(Debug, Clone, Serialize)]
+pub struct Entity {
+    /// Stable id: `gtin:…`, `bm:brand/model`, or `e:` plus a hash of the
+    /// lowest member URL.
+    pub id: String,
+    /// Strongest evidence any two members were merged on.
+    pub basis: MatchBasis,
+    pub members: Vec<EntityMember>,
+}
+
+impl Entity {
+    /// Number of distinct domains carrying this entity.
+    pub fn domain_count(&self) -> usize {
+        let mut domains: Vec<&str> = self.members.iter().map(|m| m.domain.as_str()).collect();
+        domains.sort_unstable();
+        domains.dedup();
+        domains.len()
+    }
+
+    /// Members with a known price, cheapest first.
+    pub fn offers(&self) -> Vec<&EntityMember> {
+        let mut offers: Vec<&EntityMember> = self
+            .members
+            .iter()
+            .filter(|m| m.price.is_some_and(|p| p > 0.0))
+            .collect();
+        offers.sort_by(|a, b| {
+            a.price
+                .partial_cmp(&b.price)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        offers
+    }
+}
+
+/// Resolved entities, addressable by (domain, node).
+#[derive(Debug, Clone, Default)]
+pub struct EntityIndex {
+    entities: Vec<Entity>,
+    by_node: HashMap<(String, u32), usize>,
+}
+
+impl EntityIndex {
+    /// All entities, including single-page ones.
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    /// Entities found on at least two domains.
+    pub fn cross_domain(&self) -> impl Iterator<Item = &Entity> {
+        self.entities.iter().filter(|e| e.domain_count() > 1)
+    }
+
+    /// The entity a node belongs to.
+    pub fn entity_of(&self, domain: &str, node: u32) -> Option<&Entity> {
+        self.by_node
+            .get(&(domain.to_string(), node))
+            .map(|&i| &self.entities[i])
+    }
+}
+
+/// Resolve the product detail pages of `maps` into entities, identifying
+/// each page by its URL and pricing it from the feature vector.
+pub fn resolve_maps(maps: &[(&str, &SiteMap)]) -> EntityIndex {
+    let mut candidates = Vec::new();
+    for &(domain, map) in maps {
+        for (i, node) in map.nodes.iter().enumerate() {
+            if node.page_type != PageType::ProductDetail {
+                continue;
+            }
+            let url = map.urls.get(i).cloned().unwrap_or_default();
+            let price = map.features.get(i).map(|f| f[FEAT_PRICE]);
+            candidates.push(EntityCandidate {
+                domain: domain.to_string(),
+                node: i as u32,
+                identity: ProductIdentity::from_url(&url),
+                url,
+                price: price.filter(|&p| p > 0.0),
+            });
+        }
+    }
+    resolve_entities(&candidates)
+}
+
+/// Group candidates on different domains that are the same product: equal
+/// GTIN, equal SKU, equal brand and model, or similar titles at similar
+/// prices. Pages on the same domain are never merged directly, and pages
+/// with different GTINs never match on weaker evidence.
+pub fn resolve_entities(candidates: &[EntityCandidate]) -> EntityIndex {
+    let mut sets = DisjointSets::new(candidates.len());
+    let mut basis = vec![MatchBasis::Unique; candidates.len()];
+    let mut link = |sets: &mut DisjointSets, a: usize, b: usize, why: MatchBasis| {
+        let (ca, cb) = (&candidates[a], &candidates[b]);
+        if ca.domain == cb.domain {
+            return;
+        }
+        if why != MatchBasis::Gtin {
+            if let (Some(ga), Some(gb)) = (&ca.identity.gtin, &cb.identity.gtin) {
+                if ga != gb {
+                    return;
+                }
+            }
+        }
+        let strongest = basis[sets.find(a)].min(basis[sets.find(b)]).min(why);
+        let root = sets.union(a, b);
+        basis[root] = strongest;
+    };
+
+    // Exact keys.
+    for why in [MatchBasis::Gtin, MatchBasis::Sku, MatchBasis::BrandModel] {
+        let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, c) in candidates.iter().enumerate() {
+            if let Some(k) = c.identity.key(why) {
+                groups.entry(k).or_default().push(i);
+            }
+        }
+        for group in groups.values() {
+            for (n, &a) in group.iter().enumerate() {
+                for &b in &group[n + 1..] {
+                    link(&mut sets, a, b, why);
+                }
+            }
+        }
+    }
+
+    // Similar titles, compared only when they share an uncommon token.
+    let tokens: Vec<Vec<&str>> = candidates
+        .iter()
+        .map(|c| title_tokens(c.identity.title.as_deref().unwrap_or("")))
+        .collect();
+    let mut postings: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, toks) in tokens.iter().enumerate() {
+        for &t in toks {
+            postings.entry(t).or_default().push(i);
+        }
+    }
+    let mut compared = std::collections::HashSet::new();
+    for list in postings.values().filter(|l| l.len() <= MAX_TOKEN_POSTINGS) {
+        for (n, &a) in list.iter().enumerate() {
+            for &b in &list[n + 1..] {
+                if !compared.insert((a, b)) || sets.find(a) == sets.find(b) {
+                    continue;
+                }
+                if jaccard(&tokens[a], &tokens[b]) >= TITLE_SIMILARITY
+                    && prices_agree(candidates[a].price, candidates[b].price)
+                {
+                    link(&mut sets, a, b, MatchBasis::Title);
+                }
+            }
+        }
+    }
+
+    // Entities in order of their first member.
+    let mut groups: Vec<(usize, Vec<usize>)> = Vec::new();
+    let mut group_of_root: HashMap<usize, usize> = HashMap::new();
+    for i in 0..candidates.len() {
+        let root = sets.find(i);
+        let g = *group_of_root.entry(root).or_insert_with(|| {
+            groups.push((root, Vec::new()));
+            groups.len() - 1
+        });
+        groups[g].1.push(i);
+    }
+
+    let mut index = EntityIndex::default();
+    for (root, members) in groups {
+        let e = index.entities.len();
+        for &i in &members {
+            index
+                .by_node
+                .insert((candidates[i].domain.clone(), candidates[i].node), e);
+        }
+        index.entities.push(Entity {
+            id: entity_id(candidates, &members),
+            basis: basis[root],
+            members: members
+                .iter()
+                .map(|&i| EntityMember {
+                    domain: candidates[i].domain.clone(),
+                    node: candidates[i].node,
+                    url: candidates[i].url.clone(),
+                    price: candidates[i].price,
+                })
+                .collect(),
+        });
+    }
+    index
+}
+
+/// Stable id for an entity from its strongest shared identifier.
+fn entity_id(candidates: &[EntityCandidate], members: &[usize]) -> String {
+    let ids = || members.iter().map(|&i| &candidates[i].identity);
+    if let Some(gtin) = ids().filter_map(|id| id.gtin.as_ref()).min() {
+        return format!("gtin:{gtin}");
+    }
+    if let Some((brand, model)) = ids()
+        .filter_map(|id| Some((id.brand.as_ref()?, id.model.as_ref()?)))
+        .min()
+    {
+        return format!("bm:{}/{model}", brand.replace(' ', "-"));
+    }
+    // Titles are not unique (an accessory may share one), so fall back to
+    // the lowest member URL.
+    let key = members
+        .iter()
+        .map(|&i| format!("{} {}", candidates[i].domain, candidates[i].url))
+        .min()
+        .unwrap_or_default();
+    format!("e:{:016x}", fnv_hash(key.as_bytes()))
+}
+
+/// Lowercase alphanumeric words separated by single spaces.
+fn normalize_title(s: &str) -> String {
+    s.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Uppercase alphanumerics only, so `WH-1000XM5` and `wh1000xm5` agree.
+fn normalize_code(s: &str) -> Option<String> {
+    let code: String = s
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    (!code.is_empty()).then_some(code)
+}
+
+/// A GTIN with a valid check digit, zero-padded to 14 digits (numeric
+/// JSON-LD values may have lost their leading zeros).
+fn normalize_gtin(s: &str) -> Option<String> {
+    let digits: String = s
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect();
+    if !(8..=14).contains(&digits.len()) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let padded = format!("{digits:0>14}");
+    let sum: u32 = padded
+        .bytes()
+        .take(13)
+        .enumerate()
+        .map(|(i, b)| {
+            let d = u32::from(b - b'0');
+            if i % 2 == 0 {
+                d * 3
+            } else {
+                d
+            }
+        })
+        .sum();
+    let check = (10 - sum % 10) % 10;
+    (u32::from(padded.as_bytes()[13] - b'0') == check).then_some(padded)
+}
+
+fn title_tokens(title: &str) -> Vec<&str> {
+    let mut tokens: Vec<&str> = title
+        .split(' ')
+        .filter(|w| !w.is_empty() && !STOP_WORDS.contains(w))
+        .collect();
+    tokens.sort_unstable();
+    tokens.dedup();
+    tokens
+}
+
+/// Jaccard similarity of two sorted, deduplicated token lists.
+fn jaccard(a: &[&str], b: &[&str]) -> f32 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let shared = a.iter().filter(|t| b.binary_search(t).is_ok()).count();
+    shared as f32 / (a.len() + b.len() - shared) as f32
+}
+
+fn prices_agree(a: Option<f32>, b: Option<f32>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) if a > 0.0 && b > 0.0 => a.max(b) / a.min(b) <= MAX_PRICE_RATIO,
+        _ => true,
+    }
+}
+
+fn fnv_hash(data: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &byte in data {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Union-find over candidate indexes.
+struct DisjointSets {
+    parent: Vec<usize>,
+}
+
+impl DisjointSets {
+    fn new(n: usize) -> Self {
+        Self {
+            parent: (0..n).collect(),
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    /// Merge the sets of `a` and `b`, returning the new root.
+    fn union(&mut self, a: usize, b: usize) -> usize {
+        let (ra, rb) = (self.find(a), self.find(b));
+        let (root, child) = if ra < rb { (ra, rb) } else { (rb, ra) };
+        self.parent[child] = root;
+        root
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -104,4 +543,105 @@ mod tests {
         assert!(domains.contains(&"a.com"));
         assert!(domains.contains(&"b.com"));
     }
+
+    fn candidate(
+        domain: &str,
+        node: u32,
+        identity: ProductIdentity,
+        price: f32,
+    ) -> EntityCandidate {
+        EntityCandidate {
+            domain: domain.to_string(),
+            node,
+            url: format!("https://{domain}/p/{node}"),
+            identity,
+            price: Some(price),
+        }
+    }
+
+    fn titled(title: &str) -> ProductIdentity {
+        ProductIdentity {
+            title: Some(normalize_title(title)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_product_identity_from_url() {
+        let id = ProductIdentity::from_url(
+            "https://shop.com/p/Sony-WH-1000XM5-Wireless-Headphones/00027242923782.html",
+        );
+        assert_eq!(id.gtin.as_deref(), Some("00027242923782"));
+        assert_eq!(
+            id.title.as_deref(),
+            Some("sony wh 1000xm5 wireless headphones")
+        );
+
+        // Bad check digit, and no slug with enough words.
+        let id = ProductIdentity::from_url("https://shop.com/item/00027242923781");
+        assert_eq!(id, ProductIdentity::default());
+        assert_eq!(
+            normalize_gtin("027242923782").as_deref(),
+            Some("00027242923782")
+        );
+    }
+
+    #[test]
+    fn test_resolve_entities() {
+        let gtin = |g: &str| ProductIdentity {
+            gtin: normalize_gtin(g),
+            ..Default::default()
+        };
+        let brand_model = |b: &str, m: &str| ProductIdentity {
+            brand: Some(normalize_title(b)),
+            model: normalize_code(m),
+            ..Default::default()
+        };
+        let candidates = vec![
+            candidate("a.com", 0, gtin("027242923782"), 349.0),
+            candidate("b.com", 5, gtin("0027242923782"), 329.0),
+            candidate("a.com", 1, brand_model("Bose", "QC-45"), 279.0),
+            candidate("c.com", 9, brand_model("bose", "qc45"), 299.0),
+            candidate(
+                "a.com",
+                2,
+                titled("Apple AirPods Pro (2nd generation)"),
+                249.0,
+            ),
+            candidate(
+                "b.com",
+                6,
+                titled("Apple AirPods Pro 2nd Generation"),
+                239.0,
+            ),
+            // Same title, implausible price: an accessory, not the product.
+            candidate("c.com", 7, titled("Apple AirPods Pro 2nd Generation"), 19.0),
+            // Same domain as node 2: never merged directly.
+            candidate(
+                "a.com",
+                3,
+                titled("Apple AirPods Pro 2nd Generation"),
+                249.0,
+            ),
+        ];
+        let index = resolve_entities(&candidates);
+
+        let headphones = index.entity_of("a.com", 0).unwrap();
+        assert_eq!(headphones.id, "gtin:00027242923782");
+        assert_eq!(headphones.basis, MatchBasis::Gtin);
+        assert_eq!(headphones.offers()[0].domain, "b.com");
+
+        let bose = index.entity_of("c.com", 9).unwrap();
+        assert_eq!(bose.id, "bm:bose/QC45");
+        assert_eq!(bose.domain_count(), 2);
+
+        let airpods = index.entity_of("b.com", 6).unwrap();
+        assert_eq!(airpods.basis, MatchBasis::Title);
+        assert!(airpods.id.starts_with("e:"));
+        assert_eq!(airpods.members.len(), 3);
+        assert_eq!(index.entity_of("a.com", 3).unwrap().id, airpods.id);
+        assert_ne!(index.entity_of("c.com", 7).unwrap().id, airpods.id);
+
+        assert_eq!(index.cross_domain().count(), 3);
+    }
 }
@@ -1,5 +1,7 @@
-//! Intelligence layer — caching, progressive refinement, cross-site queries.
+//! Intelligence layer — caching, adaptive sampling, progressive refinement,
+//! cross-site queries and entity resolution.
 
 pub mod cache;
 pub mod cross_site;
 pub mod progressive;
+pub mod smart_sampler;
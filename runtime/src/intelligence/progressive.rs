@@ -1,7 +1,16 @@
-//! Progressive refinement — continue rendering unrendered nodes in the background.
+//! Progressive refinement — continue rendering unrendered nodes in the
+//! background, and re-fetch the stale or low-confidence nodes a query's
+//! answer depends on.
 
-use crate::map::types::SiteMap;
+use crate::acquisition::http_client::{HeadResponse, HttpClient, HttpResponse};
+use crate::acquisition::profiles::CrawlProfile;
+use crate::acquisition::{parse_pool, structured};
+use crate::cartography::feature_encoder;
+use crate::live::refresh;
+use crate::map::types::{SiteMap, FEATURE_DIM};
+use serde::Serialize;
 use std::collections::VecDeque;
+use std::time::Duration;
 
 /// Information about a node that needs rendering.
 #[derive(Debug, Clone)]
@@ -104,3 +113,257 @@ impl ProgressiveQueue {
         self.queue.is_empty()
     }
 }
+
+/// Limits for refining a query's answer inline.
+#[derive(Debug, Clone)]
+pub struct RefinePolicy {
+    /// Nodes below this freshness (0.0-1.0) are re-fetched.
+    pub min_freshness: f32,
+    /// Nodes below this confidence (0.0-1.0) are re-fetched.
+    pub min_confidence: f32,
+    /// Maximum pages fetched per query.
+    pub max_pages: usize,
+    /// Time budget for all fetches, which run concurrently.
+    pub budget: Duration,
+}
+
+impl Default for RefinePolicy {
+    fn default() -> Self {
+        Self {
+            min_freshness: 0.5,
+            min_confidence: 0.5,
+            max_pages: 10,
+            budget: Duration::from_secs(3),
+        }
+    }
+}
+
+/// A re-fetched page, ready to be applied to its node.
+#[derive(Debug, Clone)]
+pub struct PageRefresh {
+    pub node_index: u32,
+    pub http_status: u16,
+    /// Page type confidence from the fresh structured data, if classified.
+    pub confidence: Option<f32>,
+    pub features: [f32; FEATURE_DIM],
+}
+
+/// What a refinement pass did, returned alongside the refreshed results.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FreshnessReport {
+    /// Result nodes checked for staleness.
+    pub considered: usize,
+    /// Result nodes below the freshness or confidence floor.
+    pub stale: usize,
+    /// Nodes re-fetched and updated.
+    pub refreshed: Vec<u32>,
+    /// Refreshed nodes whose features changed.
+    pub changed: Vec<u32>,
+    /// Nodes whose fetch failed or ran out of budget.
+    pub failed: Vec<u32>,
+    /// Stale nodes not attempted because of `max_pages`.
+    pub skipped: usize,
+    pub elapsed_ms: u64,
+    pub budget_ms: u64,
+}
+
+/// Pick the nodes among `nodes` (a query's results) that are stale or
+/// low-confidence, most in need first, up to `policy.max_pages`. Returns
+/// the candidates and the total number of stale nodes.
+pub fn select_for_refinement(
+    map: &SiteMap,
+    nodes: &[u32],
+    policy: &RefinePolicy,
+) -> (Vec<RenderCandidate>, usize) {
+    let mut candidates: Vec<RenderCandidate> = nodes
+        .iter()
+        .filter_map(|&i| {
+            let node = map.nodes.get(i as usize)?;
+            let freshness = node.freshness as f32 / 255.0;
+            let confidence = node.confidence as f32 / 255.0;
+            if freshness >= policy.min_freshness && confidence >= policy.min_confidence {
+                return None;
+            }
+            Some(RenderCandidate {
+                node_index: i,
+                url: map.urls.get(i as usize).cloned().unwrap_or_default(),
+                priority: 1.0 - freshness.min(confidence),
+            })
+        })
+        .collect();
+    let stale = candidates.len();
+    // Stable sort keeps result order among equally stale nodes.
+    candidates.sort_by(|a, b| {
+        b.priority
+            .partial_cmp(&a.priority)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    candidates.truncate(policy.max_pages);
+    (candidates, stale)
+}
+
+/// Fetch `candidates` over HTTP concurrently as `profile`, each bounded by
+/// the policy's budget, and re-encode their features from structured data.
+/// Returns the refreshed pages and the nodes that could not be refreshed.
+pub async fn fetch_refreshes(
+    candidates: &[RenderCandidate],
+    policy: &RefinePolicy,
+    profile: CrawlProfile,
+) -> (Vec<PageRefresh>, Vec<u32>) {
+    let budget_ms = policy.budget.as_millis() as u64;
+    let client = HttpClient::new(budget_ms).with_profile(profile);
+    let fetches = candidates.iter().map(|c| client.get(&c.url, budget_ms));
+    let responses = tokio::time::timeout(policy.budget, futures::future::join_all(fetches))
+        .await
+        .unwrap_or_default();
+
+    let mut ok: Vec<(u32, HttpResponse)> = Vec::new();
+    let mut failed: Vec<u32> = Vec::new();
+    for (i, c) in candidates.iter().enumerate() {
+        match responses.get(i) {
+            Some(Ok(resp)) if resp.status == 200 => {
+                ok.push((c.node_index, resp.clone()));
+            }
+            _ => failed.push(c.node_index),
+        }
+    }
+
+    // scraper types are not Send, so extraction runs on the parse pool.
+    let refreshed = parse_pool::run(move || {
+        ok.into_iter()
+            .map(|(node_index, resp)| encode_response(node_index, &resp))
+            .collect()
+    })
+    .await
+    .unwrap_or_default();
+    (refreshed, failed)
+}
+
+fn encode_response(node_index: u32, resp: &HttpResponse) -> PageRefresh {
+    let header = |name: &str| {
+        resp.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.clone())
+    };
+    let head = HeadResponse {
+        url: resp.url.clone(),
+        status: resp.status,
+        content_type: header("content-type"),
+        content_language: header("content-language"),
+        last_modified: header("last-modified"),
+        cache_control: header("cache-control"),
+    };
+    let sd = structured::extract_structured_data(&resp.body, &resp.final_url);
+    PageRefresh {
+        node_index,
+        http_status: resp.status,
+        confidence: sd.page_type.map(|(_, c)| c),
+        features: feature_encoder::encode_features_from_structured_data(&sd, &resp.url, &head),
+    }
+}
+
+/// Apply refreshed pages to `map`: fresh non-zero features overwrite the
+/// old ones, and each node is marked fully fresh. Returns the nodes whose
+/// features changed.
+pub fn apply_refreshes(map: &mut SiteMap, refreshes: &[PageRefresh]) -> Vec<u32> {
+    let mut changed = Vec::new();
+    for page in refreshes {
+        let idx = page.node_index as usize;
+        let (Some(node), Some(old)) = (map.nodes.get(idx), map.features.get(idx)) else {
+            continue;
+        };
+        let mut features = *old;
+        for (f, &new) in features.iter_mut().zip(&page.features) {
+            if new != 0.0 {
+                *f = new;
+            }
+        }
+        if refresh::detect_changes(old, &features, 1e-3) {
+            changed.push(page.node_index);
+        }
+        let mut record = node.clone();
+        record.freshness = 255;
+        record.http_status = page.http_status;
+        if let Some(confidence) = page.confidence {
+            record.confidence = record.confidence.max((confidence * 255.0) as u8);
+        }
+        record.feature_norm = features.iter().map(|f| f * f).sum::<f32>().sqrt();
+        map.update_node(page.node_index, record, features);
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::builder::SiteMapBuilder;
+    use crate::map::types::{PageType, FEAT_PRICE, FEAT_RATING};
+
+    fn test_map() -> SiteMap {
+        let mut builder = SiteMapBuilder::new("shop.com");
+        for i in 0..4 {
+            let mut feats = [0.0f32; FEATURE_DIM];
+            feats[FEAT_PRICE] = 100.0;
+            feats[FEAT_RATING] = 0.8;
+            builder.add_node(
+                &format!("https://shop.com/p/{i}"),
+                PageType::ProductDetail,
+                feats,
+                if i == 2 { 40 } else { 230 },
+            );
+        }
+        let mut map = builder.build();
+        map.nodes[0].freshness = 255;
+        map.nodes[1].freshness = 30;
+        map.nodes[2].freshness = 255;
+        map.nodes[3].freshness = 0;
+        map
+    }
+
+    #[test]
+    fn test_select_for_refinement() {
+        let map = test_map();
+        let policy = RefinePolicy {
+            max_pages: 2,
+            ..Default::default()
+        };
+        // Node 0 is fresh and confident; 2 is fresh but low-confidence.
+        let (candidates, stale) = select_for_refinement(&map, &[0, 1, 2, 3], &policy);
+        assert_eq!(stale, 3);
+        let picked: Vec<u32> = candidates.iter().map(|c| c.node_index).collect();
+        assert_eq!(picked, vec![3, 1]);
+        assert_eq!(candidates[0].url, "https://shop.com/p/3");
+    }
+
+    #[test]
+    fn test_apply_refreshes() {
+        let mut map = test_map();
+        let mut features = [0.0f32; FEATURE_DIM];
+        features[FEAT_PRICE] = 80.0;
+        let refreshes = [
+            PageRefresh {
+                node_index: 3,
+                http_status: 200,
+                confidence: Some(0.95),
+                features,
+            },
+            PageRefresh {
+                node_index: 1,
+                http_status: 200,
+                confidence: None,
+                features: [0.0; FEATURE_DIM],
+            },
+        ];
+        let changed = apply_refreshes(&mut map, &refreshes);
+
+        assert_eq!(changed, vec![3]);
+        assert_eq!(map.features[3][FEAT_PRICE], 80.0);
+        // Dimensions missing from the fresh page keep their old values.
+        assert_eq!(map.features[3][FEAT_RATING], 0.8);
+        assert_eq!(map.nodes[3].freshness, 255);
+        assert_eq!(map.nodes[3].confidence, 242);
+        assert_eq!(map.nodes[1].freshness, 255);
+        assert_eq!(map.nodes[1].confidence, 230);
+    }
+}
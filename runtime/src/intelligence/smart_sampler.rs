@@ -0,0 +1,404 @@
+//! Smart sampler — choose Layer 1 sample pages by information gain.
+//!
+//! URLs are grouped into templates (`/products/*`, `/blog/*/*`). Sampling
+//! proceeds in rounds, one page per open template per round, and a template
+//! closes once new pages stop revealing page types or fields not already
+//! seen on it. Large heterogeneous sites keep sampling where pages still
+//! differ; uniform ones stop early.
+
+use crate::acquisition::pattern_engine::PatternResult;
+use crate::acquisition::structured::StructuredData;
+use std::collections::{HashMap, HashSet};
+
+/// Stopping rule and budget for a sampling run.
+#[derive(Debug, Clone)]
+pub struct SamplerConfig {
+    /// Pages fetched from a template before it may close.
+    pub min_per_template: usize,
+    /// Consecutive pages without new observations that close a template.
+    pub patience: usize,
+    /// Total pages across all templates.
+    pub max_total: usize,
+    /// Pages per round.
+    pub batch_size: usize,
+}
+
+impl Default for SamplerConfig {
+    fn default() -> Self {
+        Self {
+            min_per_template: 2,
+            patience: 2,
+            max_total: 200,
+            batch_size: 20,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Template {
+    key: String,
+    /// Unfetched URLs, next last.
+    pending: Vec<String>,
+    fetched: usize,
+    observed: HashSet<String>,
+    /// Consecutive observed pages that added nothing new.
+    unproductive: usize,
+}
+
+/// Summary of a sampling run.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SamplerStats {
+    pub templates: usize,
+    /// Templates closed by the stopping rule (not by running out of URLs).
+    pub saturated: usize,
+    pub fetched: usize,
+}
+
+/// Adaptive sampler over a site's HTML URLs.
+#[derive(Debug)]
+pub struct SmartSampler {
+    config: SamplerConfig,
+    templates: Vec<Template>,
+    by_url: HashMap<String, usize>,
+    fetched: usize,
+}
+
+impl SmartSampler {
+    /// Group `urls` by template. The homepage of `domain` is sampled first,
+    /// then templates in descending order of size.
+    pub fn new(urls: &[String], domain: &str, config: SamplerConfig) -> Self {
+        let mut index: HashMap<String, usize> = HashMap::new();
+        let mut templates: Vec<Template> = Vec::new();
+        for url in urls {
+            let key = sample_template(url);
+            let t = *index.entry(key.clone()).or_insert_with(|| {
+                templates.push(Template {
+                    key,
+                    ..Template::default()
+                });
+                templates.len() - 1
+            });
+            if !templates[t].pending.contains(url) {
+                templates[t].pending.push(url.clone());
+            }
+        }
+
+        let is_home = |t: &Template| {
+            t.pending.iter().any(|u| {
+                let rest = u.split_once("://").map_or(u.as_str(), |(_, r)| r);
+                rest.trim_end_matches('/') == domain
+            })
+        };
+        templates.sort_by_key(|t| (!is_home(t), std::cmp::Reverse(t.pending.len())));
+        for t in &mut templates {
+            // Spread picks across the template rather than taking neighbours.
+            t.pending.sort_by_key(|u| std::cmp::Reverse(spread_key(u)));
+        }
+
+        let by_url = templates
+            .iter()
+            .enumerate()
+            .flat_map(|(i, t)| t.pending.iter().map(move |u| (u.clone(), i)))
+            .collect();
+        Self {
+            config,
+            templates,
+            by_url,
+            fetched: 0,
+        }
+    }
+
+    /// The next round of URLs: one from each open template, up to the
+    /// batch size and remaining budget. Empty when sampling is done.
+    pub fn next_batch(&mut self) -> Vec<String> {
+        let room = self.config.max_total.saturating_sub(self.fetched);
+        let size = self.config.batch_size.min(room);
+        let mut batch = Vec::new();
+        for i in 0..self.templates.len() {
+            if batch.len() >= size {
+                break;
+            }
+            if self.is_open(i) {
+                if let Some(url) = self.templates[i].pending.pop() {
+                    self.templates[i].fetched += 1;
+                    batch.push(url);
+                }
+            }
+        }
+        self.fetched += batch.len();
+        batch
+    }
+
+    /// Record what a fetched page revealed. Returns how many observations
+    /// were new for its template.
+    pub fn observe(&mut self, url: &str, observations: &[String]) -> usize {
+        let Some(&i) = self.by_url.get(url) else {
+            return 0;
+        };
+        let t = &mut self.templates[i];
+        let new = observations
+            .iter()
+            .filter(|o| t.observed.insert((*o).clone()))
+            .count();
+        if new == 0 {
+            t.unproductive += 1;
+        } else {
+            t.unproductive = 0;
+        }
+        new
+    }
+
+    /// Count `url` as fetched without taking it from a batch, as when a
+    /// resumed map replays the pages it fetched before. Returns false if
+    /// the URL is not pending.
+    pub fn mark_fetched(&mut self, url: &str) -> bool {
+        let Some(&i) = self.by_url.get(url) else {
+            return false;
+        };
+        let t = &mut self.templates[i];
+        let Some(pos) = t.pending.iter().position(|u| u == url) else {
+            return false;
+        };
+        t.pending.remove(pos);
+        t.fetched += 1;
+        self.fetched += 1;
+        true
+    }
+
+    /// Unfetched URLs with a score for how likely each is to reveal
+    /// something new: 1.0 on a template whose last page did, halving with
+    /// each page that did not, 0 once the template is saturated. Highest
+    /// score first, then in sampling order.
+    pub fn pending(&self) -> Vec<(String, f32)> {
+        let mut pending: Vec<(String, f32)> = (0..self.templates.len())
+            .flat_map(|i| {
+                let t = &self.templates[i];
+                let score = if self.is_saturated(i) {
+                    0.0
+                } else {
+                    0.5f32.powi(t.unproductive as i32)
+                };
+                t.pending.iter().rev().map(move |u| (u.clone(), score))
+            })
+            .collect();
+        pending.sort_by(|a, b| b.1.total_cmp(&a.1));
+        pending
+    }
+
+    /// Counts of templates, saturated templates and fetched pages.
+    pub fn stats(&self) -> SamplerStats {
+        SamplerStats {
+            templates: self.templates.len(),
+            saturated: (0..self.templates.len())
+                .filter(|&i| self.is_saturated(i))
+                .count(),
+            fetched: self.fetched,
+        }
+    }
+
+    fn is_saturated(&self, i: usize) -> bool {
+        let t = &self.templates[i];
+        t.fetched >= self.config.min_per_template && t.unproductive >= self.config.patience
+    }
+
+    fn is_open(&self, i: usize) -> bool {
+        !self.templates[i].pending.is_empty() && !self.is_saturated(i)
+    }
+}
+
+/// What a sampled page tells us about its template: its page type, the
+/// schema.org types it declares, and which fields it fills.
+pub fn observations(sd: &StructuredData, patterns: Option<&PatternResult>) -> Vec<String> {
+    let mut obs = Vec::new();
+    let page_type = sd
+        .page_type
+        .or_else(|| patterns.and_then(|p| p.page_type))
+        .map(|(pt, _)| pt);
+    if let Some(pt) = page_type {
+        obs.push(format!("type:{pt:?}"));
+    }
+    for block in &sd.jsonld {
+        if let Some(t) = block.get("@type").and_then(|t| t.as_str()) {
+            obs.push(format!("jsonld:{t}"));
+        }
+    }
+    let mut field = |name: &str, present: bool| {
+        if present {
+            obs.push(format!("field:{name}"));
+        }
+    };
+    let product = sd.products.first();
+    field(
+        "price",
+        product.is_some_and(|p| p.price.is_some()) || patterns.is_some_and(|p| p.price.is_some()),
+    );
+    field(
+        "rating",
+        product.is_some_and(|p| p.rating_value.is_some())
+            || patterns.is_some_and(|p| p.rating.is_some()),
+    );
+    field(
+        "availability",
+        product.is_some_and(|p| p.availability.is_some())
+            || patterns.is_some_and(|p| p.availability.is_some()),
+    );
+    field("brand", product.is_some_and(|p| p.brand.is_some()));
+    field(
+        "sku",
+        product.is_some_and(|p| p.sku.is_some() || p.gtin.is_some()),
+    );
+    field("author", sd.articles.iter().any(|a| a.author.is_some()));
+    field("breadcrumbs", !sd.breadcrumbs.is_empty());
+    field("form", !sd.forms.is_empty());
+    field(
+        "actions",
+        patterns.is_some_and(|p| !p.actions.is_empty() || !p.forms.is_empty()),
+    );
+    obs
+}
+
+/// Sampling template of a URL: the first path segment, then `*` for each
+/// further segment, then sorted query keys (`/products/*?color`).
+/// Identifier-like first segments become `{id}`.
+pub fn sample_template(url: &str) -> String {
+    let Ok(parsed) = url::Url::parse(url) else {
+        return url.to_string();
+    };
+    let segments: Vec<&str> = parsed.path().split('/').filter(|s| !s.is_empty()).collect();
+    let mut template = String::from("/");
+    if let Some(first) = segments.first() {
+        if first.chars().any(|c| c.is_ascii_digit()) && first.len() > 3 {
+            template.push_str("{id}");
+        } else {
+            template.push_str(first);
+        }
+        for _ in 1..segments.len() {
+            template.push_str("/*");
+        }
+    }
+    let mut keys: Vec<String> = parsed.query_pairs().map(|(k, _)| k.into_owned()).collect();
+    keys.sort();
+    keys.dedup();
+    if !keys.is_empty() {
+        template.push('?');
+        template.push_str(&keys.join("&"));
+    }
+    template
+}
+
+/// Deterministic, well-mixed ordering key for a URL (FNV-1a).
+fn spread_key(url: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &byte in url.as_bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls() -> Vec<String> {
+        let mut urls = vec!["https://shop.com/".to_string()];
+        urls.extend((0..40).map(|i| format!("https://shop.com/products/item-{i}")));
+        urls.extend((0..10).map(|i| format!("https://shop.com/blog/2024/post-{i}")));
+        urls.push("https://shop.com/about".to_string());
+        urls
+    }
+
+    #[test]
+    fn test_sample_template() {
+        assert_eq!(sample_template("https://shop.com/"), "/");
+        assert_eq!(
+            sample_template("https://shop.com/products/red-shoe"),
+            "/products/*"
+        );
+        assert_eq!(
+            sample_template("https://shop.com/c/shoes?sort=price&page=2"),
+            "/c/*?page&sort"
+        );
+        assert_eq!(sample_template("https://shop.com/12345/x"), "/{id}/*");
+    }
+
+    #[test]
+    fn test_sampler_stops_when_templates_saturate() {
+        let config = SamplerConfig {
+            batch_size: 10,
+            ..SamplerConfig::default()
+        };
+        let mut sampler = SmartSampler::new(&urls(), "shop.com", config);
+
+        let first = sampler.next_batch();
+        assert_eq!(first.len(), 4, "one page per template");
+        assert_eq!(first[0], "https://shop.com/");
+        assert!(first[1].contains("/products/"));
+
+        // Every product page looks the same; blog posts keep differing.
+        let mut rounds = 1;
+        let mut batch = first;
+        let mut post = 0;
+        while !batch.is_empty() {
+            for url in &batch {
+                let obs = if url.contains("/blog/") {
+                    post += 1;
+                    vec![format!("field:{post}")]
+                } else {
+                    vec!["type:ProductDetail".to_string()]
+                };
+                sampler.observe(url, &obs);
+            }
+            batch = sampler.next_batch();
+            rounds += 1;
+        }
+
+        let stats = sampler.stats();
+        assert_eq!(stats.templates, 4);
+        // Products close after 1 new + 2 unproductive pages; all 10 blog
+        // posts are fetched because each adds something.
+        assert_eq!(stats.saturated, 1);
+        assert_eq!(stats.fetched, 1 + 3 + 10 + 1);
+        assert!(rounds > 3);
+    }
+
+    #[test]
+    fn test_sampler_resumes_from_fetched_pages() {
+        let mut sampler = SmartSampler::new(&urls(), "shop.com", SamplerConfig::default());
+        let product = "https://shop.com/products/item-3";
+        assert!(sampler.mark_fetched(product));
+        assert!(!sampler.mark_fetched(product));
+        sampler.observe(product, &["type:ProductDetail".to_string()]);
+        sampler.mark_fetched("https://shop.com/products/item-4");
+        sampler.observe(
+            "https://shop.com/products/item-4",
+            &["type:ProductDetail".to_string()],
+        );
+
+        let pending = sampler.pending();
+        assert_eq!(pending.len(), urls().len() - 2);
+        assert!(pending.iter().all(|(u, _)| u != product));
+        // Untouched templates outrank the one that stopped revealing fields.
+        assert_eq!(pending[0].1, 1.0);
+        let product_score = pending
+            .iter()
+            .find(|(u, _)| u.contains("/products/"))
+            .unwrap()
+            .1;
+        assert_eq!(product_score, 0.5);
+        assert_eq!(sampler.stats().fetched, 2);
+    }
+
+    #[test]
+    fn test_sampler_respects_budget() {
+        let config = SamplerConfig {
+            max_total: 5,
+            batch_size: 3,
+            ..SamplerConfig::default()
+        };
+        let mut sampler = SmartSampler::new(&urls(), "shop.com", config);
+        assert_eq!(sampler.next_batch().len(), 3);
+        assert_eq!(sampler.next_batch().len(), 2);
+        assert!(sampler.next_batch().is_empty());
+    }
+}
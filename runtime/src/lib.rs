@@ -8,12 +8,17 @@
 #![allow(
     dead_code,
     unused_imports,
+    clippy::collapsible_match,
+    clippy::manual_is_multiple_of,
+    clippy::manual_map,
     clippy::new_without_default,
-    clippy::should_implement_trait
+    clippy::should_implement_trait,
+    clippy::unnecessary_sort_by
 )]
 
 pub mod acquisition;
 pub mod audit;
+pub mod cancel;
 pub mod cartography;
 pub mod cli;
 pub mod collective;
@@ -24,9 +29,11 @@ pub mod intelligence;
 pub mod live;
 pub mod maintenance;
 pub mod map;
+pub mod mcp;
 pub mod navigation;
 pub mod pool;
 pub mod progress;
+pub mod project;
 pub mod protocol;
 pub mod renderer;
 pub mod rest;
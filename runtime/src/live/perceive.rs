@@ -2,6 +2,7 @@
 
 use crate::cartography::feature_encoder;
 use crate::cartography::page_classifier;
+use crate::extraction::custom::CustomData;
 use crate::extraction::loader::{ExtractionLoader, ExtractionResult};
 use crate::renderer::{NavigationResult, RenderContext};
 use anyhow::Result;
@@ -24,6 +25,9 @@ pub struct PerceiveResult {
     pub content: Option<String>,
     /// Load time in milliseconds.
     pub load_time_ms: u64,
+    /// Output of the custom extractors registered for the page's domain.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub custom: Vec<CustomData>,
 }
 
 /// Perceive a single URL: render, extract, encode.
@@ -74,6 +78,7 @@ pub async fn perceive(
         features: sparse_features,
         content,
         load_time_ms: nav_result.load_time_ms,
+        custom: extraction.custom,
     })
 }
 
@@ -5,7 +5,7 @@
 //! for nodes without structured data.
 
 use crate::acquisition::http_client::HttpClient;
-use crate::acquisition::structured;
+use crate::acquisition::{parse_pool, structured};
 use crate::cartography::feature_encoder;
 use crate::live::refresh;
 use crate::map::types::{SiteMap, FEATURE_DIM};
@@ -100,7 +100,10 @@ pub async fn fetch_node_features_http(
         return None;
     }
 
-    let sd = structured::extract_structured_data(&resp.body, &resp.final_url);
+    let (body, final_url) = (resp.body.clone(), resp.final_url.clone());
+    let sd = parse_pool::run(move || structured::extract_structured_data(&body, &final_url))
+        .await
+        .ok()?;
 
     // Only use HTTP path if we got meaningful structured data
     if sd.has_jsonld || sd.has_opengraph {
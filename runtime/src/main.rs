@@ -4,7 +4,7 @@
 #![allow(dead_code, unused_imports)]
 
 use anyhow::Result;
-use clap::{CommandFactory, Parser, Subcommand};
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
 use clap_complete::Shell;
 
 use cortex_runtime::cli;
@@ -45,11 +45,27 @@ enum Commands {
         /// Also start HTTP REST API on this port
         #[arg(long)]
         http_port: Option<u16>,
+        /// Accept mapping workers on this address (e.g. 0.0.0.0:7712)
+        #[arg(long)]
+        coordinator: Option<String>,
+        /// Token workers must present (repeatable; default: $CORTEX_WORKER_TOKEN)
+        #[arg(long = "worker-token")]
+        worker_tokens: Vec<String>,
+        /// Write logs to this file (rotated at 10 MB) instead of stderr
+        #[arg(long)]
+        log_file: Option<std::path::PathBuf>,
     },
     /// Stop the Cortex background process
     Stop,
     /// Restart the Cortex background process
     Restart,
+    /// Re-read extraction scripts, crawl calendars and watch rules without restarting
+    Reload,
+    /// Run Cortex as a login service (launchd, systemd or Task Scheduler)
+    Service {
+        #[command(subcommand)]
+        action: ServiceAction,
+    },
     /// Check environment and diagnose issues
     Doctor,
     /// Show runtime status and cached maps
@@ -70,11 +86,23 @@ enum Commands {
         /// Force re-mapping even if a cached map exists
         #[arg(long)]
         fresh: bool,
+        /// Continue an interrupted or timed-out map from its saved frontier
+        #[arg(long, conflicts_with = "fresh")]
+        resume: bool,
+        /// Fail unless the map's quality score (0-1) reaches this, after
+        /// remapping with fallbacks; a cached map below it is remapped
+        #[arg(long, value_name = "SCORE")]
+        min_quality: Option<f32>,
     },
     /// Search a mapped site by type, features, or similarity
     Query {
-        /// Domain to query (must be previously mapped)
-        domain: String,
+        /// Domain to query (must be previously mapped). Optional with
+        /// --project, --save (save without running), --saved, or when a
+        /// project is active.
+        domain: Option<String>,
+        /// Query every mapped domain of project NAME
+        #[arg(long, value_name = "NAME", conflicts_with = "domain")]
+        project: Option<String>,
         /// Filter by page type (e.g. "product_detail", "article", "0x04")
         #[arg(long, name = "type")]
         page_type: Option<String>,
@@ -87,9 +115,30 @@ enum Commands {
         /// Feature range filter (e.g. "48<300", "52>0.8"). Can be repeated.
         #[arg(long = "feature")]
         feature_filters: Vec<String>,
-        /// Maximum number of results
-        #[arg(long, default_value = "20")]
-        limit: u32,
+        /// Keep pages whose title or description mention these words, best
+        /// match first (e.g. "wireless headphones")
+        #[arg(long)]
+        text: Option<String>,
+        /// Maximum number of results [default: 20, or the saved query's limit]
+        #[arg(long)]
+        limit: Option<u32>,
+        /// Collapse near-identical results (same URL template, similar
+        /// features) into one representative per group
+        #[arg(long)]
+        diversify: bool,
+        /// Save these filters in the registry under NAME
+        #[arg(long, value_name = "NAME")]
+        save: Option<String>,
+        /// Run the saved query NAME instead of the given filters
+        #[arg(long = "use", value_name = "NAME", conflicts_with = "save")]
+        use_saved: Option<String>,
+        /// List saved queries
+        #[arg(long, conflicts_with_all = ["save", "use_saved"])]
+        saved: bool,
+        /// Exit with code 2 if PREDICATE holds for the results, e.g.
+        /// 'count == 0' or 'avg(confidence) < 0.5'. Can be repeated.
+        #[arg(long = "fail-when", value_name = "PREDICATE")]
+        fail_when: Vec<String>,
     },
     /// Find shortest path between pages on a mapped site
     Pathfind {
@@ -102,6 +151,60 @@ enum Commands {
         #[arg(long)]
         to: u32,
     },
+    /// Report unreachable pages, dead ends and choke points in a cached map
+    Analyze {
+        /// Domain to analyze (must be previously mapped)
+        domain: String,
+        /// Maximum number of nodes listed per section
+        #[arg(long, default_value = "10")]
+        limit: usize,
+    },
+    /// Show a node of a cached map and the HTML snapshots of its page
+    Inspect {
+        /// Domain the node belongs to (must be previously mapped)
+        domain: String,
+        /// Node index
+        node: u32,
+        /// Print the latest HTML snapshot of the page
+        #[arg(long)]
+        html: bool,
+    },
+    /// Label, correct or note a page of a mapped domain, or list annotations
+    Annotate {
+        /// Domain the page belongs to
+        domain: String,
+        /// Node index in the cached map (omit to list the whole domain's)
+        node: Option<u32>,
+        /// The page's URL, instead of a node index
+        #[arg(long, conflicts_with = "node")]
+        url: Option<String>,
+        /// Attach a free-form label
+        #[arg(long)]
+        label: Option<String>,
+        /// Correct the page's type (a name like checkout, or a code); later
+        /// maps of the domain keep it
+        #[arg(long)]
+        page_type: Option<String>,
+        /// Attach a note
+        #[arg(long)]
+        note: Option<String>,
+        /// Who is annotating
+        #[arg(long)]
+        author: Option<String>,
+        /// Delete the annotation with this ID
+        #[arg(long)]
+        remove: Option<u64>,
+    },
+    /// Serve Cortex tools to MCP clients (stdio by default)
+    Mcp {
+        /// Serve HTTP + SSE on this address instead of stdio (e.g. 127.0.0.1:7720)
+        #[arg(long)]
+        sse: Option<String>,
+        /// Bearer token HTTP clients must present (default: $CORTEX_MCP_TOKEN);
+        /// required unless --sse is a loopback address
+        #[arg(long)]
+        token: Option<String>,
+    },
     /// Perceive a single live page
     Perceive {
         /// URL to perceive
@@ -136,17 +239,46 @@ enum Commands {
         /// Output directory (default: ~/.cortex/compiled/<domain>/)
         #[arg(long)]
         output: Option<String>,
+        /// Target to generate: python, typescript, openapi, graphql, mcp, all (repeatable)
+        #[arg(long = "target", visible_alias = "format")]
+        targets: Vec<String>,
+        /// Version of the generated packages (default: compile date, e.g. 2026.3.14)
+        #[arg(long)]
+        package_version: Option<String>,
     },
     /// Execute a WQL (Web Query Language) query
     Wql {
         /// WQL query string (e.g. "SELECT name, price FROM Product WHERE price < 200 LIMIT 10")
         query: String,
+        /// Search project NAME's domains unless the query has ACROSS
+        /// [default: the active project]
+        #[arg(long, value_name = "NAME")]
+        project: Option<String>,
+        /// Exit with code 2 if PREDICATE holds for the rows, e.g.
+        /// 'count == 0' or 'min(price) > 100'. Can be repeated.
+        #[arg(long = "fail-when", value_name = "PREDICATE")]
+        fail_when: Vec<String>,
     },
     /// Manage the local map registry
     Registry {
         #[command(subcommand)]
         action: RegistryAction,
     },
+    /// Fetch and render pages for a coordinator's maps
+    Worker {
+        /// Coordinator URL (a daemon started with --coordinator)
+        #[arg(long)]
+        join: String,
+        /// Worker token (default: $CORTEX_WORKER_TOKEN)
+        #[arg(long)]
+        token: Option<String>,
+        /// Name shown in the coordinator's log (default: $HOSTNAME)
+        #[arg(long)]
+        name: Option<String>,
+        /// Take fetch jobs only, without starting Chromium
+        #[arg(long)]
+        no_browser: bool,
+    },
     /// Query temporal history for a node
     History {
         /// Domain to query
@@ -170,6 +302,51 @@ enum Commands {
         #[arg(long)]
         dim: String,
     },
+    /// Manage the map signing key and trusted publishers
+    Trust {
+        #[command(subcommand)]
+        action: TrustAction,
+    },
+    /// Manage API keys that daemon clients must present
+    Keys {
+        #[command(subcommand)]
+        action: KeysAction,
+    },
+    /// Register and check custom extraction scripts
+    Extract {
+        #[command(subcommand)]
+        action: ExtractAction,
+    },
+    /// Group domains into projects with shared settings
+    Project {
+        #[command(subcommand)]
+        action: ProjectAction,
+    },
+    /// Choose the identity (user agent, headers, cookies) domains are crawled as
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+    /// Temporal analysis commands
+    Temporal {
+        #[command(subcommand)]
+        action: TemporalAction,
+    },
+    /// Inspect and replay the persistent event log
+    Events {
+        #[command(subcommand)]
+        action: EventsAction,
+    },
+    /// Query and export the audit trail of state-changing operations
+    Audit {
+        #[command(subcommand)]
+        action: AuditAction,
+    },
+    /// Watch pages for changes and follow the alerts they fire
+    Watch {
+        #[command(subcommand)]
+        action: WatchAction,
+    },
     /// Auto-discover AI agents and inject Cortex MCP server
     Plug {
         /// Show detected agents without injecting
@@ -181,9 +358,15 @@ enum Commands {
         /// Show which agents have Cortex connected
         #[arg(long)]
         status: bool,
-        /// Inject into a specific agent only (e.g. "cursor", "claude-desktop")
+        /// Check that each injected config launches Cortex
+        #[arg(long)]
+        doctor: bool,
+        /// Inject into a specific agent only (e.g. "cursor", "zed", "codex")
         #[arg(long)]
         agent: Option<String>,
+        /// Write project-local configs in DIR (default: current directory)
+        #[arg(long, value_name = "DIR", num_args = 0..=1, default_missing_value = ".")]
+        project: Option<String>,
         /// Override config directory for testing
         #[arg(long)]
         config_dir: Option<String>,
@@ -197,6 +380,83 @@ enum CacheAction {
         /// Domain to clear (omit to clear all)
         domain: Option<String>,
     },
+    /// Export a cached map to GraphML, GEXF, or DOT
+    Export {
+        /// Domain to export
+        domain: String,
+        /// Output format: graphml, gexf, dot
+        #[arg(long, default_value = "graphml")]
+        format: String,
+        /// Feature to include as a node attribute, by name or dimension (repeatable)
+        #[arg(long = "feature")]
+        features: Vec<String>,
+        /// Output file (default: stdout)
+        #[arg(long, short)]
+        output: Option<String>,
+    },
+    /// Merge maps of aliased domains or partial crawls into one cached map
+    Merge {
+        /// Cached domains or .ctx files to merge
+        #[arg(required = true, num_args = 2..)]
+        inputs: Vec<String>,
+        /// Canonical domain of the result (default: first input without www./m.)
+        #[arg(long)]
+        into: Option<String>,
+        /// Extra host to fold into the canonical domain (repeatable)
+        #[arg(long = "alias")]
+        aliases: Vec<String>,
+    },
+    /// Slice a cached map down to the nodes matching a query, with the edges between them
+    Slice {
+        /// Domain to slice
+        domain: String,
+        /// Keep pages of this type (repeatable)
+        #[arg(long = "type")]
+        page_types: Vec<String>,
+        /// Feature filter, e.g. "48<300" (repeatable)
+        #[arg(long = "feature")]
+        features: Vec<String>,
+        /// WQL query selecting the nodes instead of --type/--feature
+        #[arg(long, conflicts_with_all = ["page_types", "features"])]
+        wql: Option<String>,
+        /// Maximum number of nodes to keep (0 = all)
+        #[arg(long, default_value = "0")]
+        limit: usize,
+        /// Output .ctx file
+        #[arg(long, short)]
+        out: String,
+    },
+    /// Check out a domain's map as it was at a point in time from the registry
+    Checkout {
+        /// Domain to check out
+        domain: String,
+        /// Point in time: date, RFC 3339 timestamp, age (e.g. 7d), or "now"
+        #[arg(long)]
+        at: String,
+        /// Write the map to this .ctx file instead of replacing the cached map
+        #[arg(long, short)]
+        out: Option<String>,
+    },
+    /// Show cache size, budgets, hit rate and usage by content type
+    Stats {
+        /// Maximum number of maps to list
+        #[arg(long, default_value = "10")]
+        limit: usize,
+    },
+    /// Import a map from a third-party crawl export (Screaming Frog CSV, WARC)
+    Import {
+        /// Export file (.csv, .warc, .warc.gz)
+        file: String,
+        /// Input format: screaming-frog, warc (default: by file extension)
+        #[arg(long)]
+        format: Option<String>,
+        /// Screaming Frog "All Outlinks" CSV providing the link graph
+        #[arg(long)]
+        links: Option<String>,
+        /// Domain to import (default: host of the first page)
+        #[arg(long)]
+        domain: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -207,11 +467,500 @@ enum RegistryAction {
     Stats,
     /// Garbage collect old deltas
     Gc,
+    /// Verify stored map versions against their integrity hashes
+    Verify {
+        /// Domain to verify
+        domain: String,
+    },
+    /// Serve the local registry over HTTP for other Cortex instances
+    Serve {
+        /// Address to bind
+        #[arg(long, default_value = "127.0.0.1")]
+        bind: String,
+        /// Port to listen on
+        #[arg(long, default_value = "7710")]
+        port: u16,
+        /// Bearer token accepted for reads and pushes (repeatable; none = read-only)
+        #[arg(long = "token")]
+        tokens: Vec<String>,
+        /// Reject pushes that are not signed by a trusted key
+        #[arg(long)]
+        require_signatures: bool,
+    },
+    /// Share maps with other Cortex instances on the local network (mDNS)
+    Lan {
+        /// Port to serve the local registry on
+        #[arg(long, default_value = "7711")]
+        port: u16,
+        /// Seconds between sync rounds
+        #[arg(long, default_value = "60")]
+        interval: u64,
+        /// Shared bearer token for the team (default: $CORTEX_REGISTRY_TOKEN)
+        #[arg(long)]
+        token: Option<String>,
+        /// Accept maps that are unsigned or signed by an untrusted key
+        #[arg(long)]
+        allow_unsigned: bool,
+    },
+    /// Set the tags subscribers can select a domain by
+    Tag {
+        /// Domain to tag
+        domain: String,
+        /// Tags (none = clear)
+        tags: Vec<String>,
+    },
+    /// Subscribe to domains on a remote registry
+    Subscribe {
+        /// Subscription name
+        name: String,
+        /// Remote registry URL
+        #[arg(long)]
+        remote: String,
+        /// Domain to pull (repeatable)
+        #[arg(long = "domain")]
+        domains: Vec<String>,
+        /// Pull domains with this tag (repeatable)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        /// WQL query selecting which nodes to keep (e.g. "SELECT * FROM Product")
+        #[arg(long)]
+        filter: Option<String>,
+        /// Seconds between syncs
+        #[arg(long, default_value = "3600")]
+        interval: u64,
+    },
+    /// Remove a subscription
+    Unsubscribe {
+        /// Subscription name
+        name: String,
+    },
+    /// List subscriptions
+    Subscriptions,
+    /// Pull due subscriptions into the local registry
+    Sync {
+        /// Bearer token (default: $CORTEX_REGISTRY_TOKEN)
+        #[arg(long)]
+        token: Option<String>,
+        /// Sync every subscription, even if not due
+        #[arg(long)]
+        force: bool,
+        /// Keep running and sync subscriptions as they come due
+        #[arg(long)]
+        watch: bool,
+    },
+    /// Push a domain's map to a remote registry
+    Push {
+        /// Domain to push
+        domain: String,
+        /// Remote registry URL (default: $CORTEX_REGISTRY_URL)
+        #[arg(long)]
+        remote: Option<String>,
+        /// Bearer token (default: $CORTEX_REGISTRY_TOKEN)
+        #[arg(long)]
+        token: Option<String>,
+    },
+    /// Pull a domain's map from a remote registry
+    Pull {
+        /// Domain to pull
+        domain: String,
+        /// Remote registry URL (default: $CORTEX_REGISTRY_URL)
+        #[arg(long)]
+        remote: Option<String>,
+        /// Bearer token (default: $CORTEX_REGISTRY_TOKEN)
+        #[arg(long)]
+        token: Option<String>,
+        /// Accept maps that are unsigned or signed by an untrusted key
+        #[arg(long)]
+        allow_unsigned: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TrustAction {
+    /// Show (and create on first use) the local signing key
+    Key,
+    /// Trust a publisher's public key for pulled maps
+    Add {
+        /// Publisher name
+        name: String,
+        /// Hex-encoded Ed25519 public key
+        public_key: String,
+    },
+    /// Stop trusting a publisher
+    Remove {
+        /// Publisher name
+        name: String,
+    },
+    /// List trusted publishers
+    List,
+}
+
+#[derive(Subcommand)]
+enum ServiceAction {
+    /// Register and start the service; it restarts the daemon if it crashes
+    Install {
+        /// Also start HTTP REST API on this port
+        #[arg(long)]
+        http_port: Option<u16>,
+    },
+    /// Stop the service and remove it
+    Uninstall,
+    /// Show whether the service is installed and running
+    Status,
+}
+
+#[derive(Subcommand)]
+enum KeysAction {
+    /// Create a key and print its secret (shown only once)
+    Add {
+        /// Who or what the key is for
+        name: String,
+        /// What the key may do: read, map or act
+        #[arg(long, default_value = "read")]
+        permission: String,
+    },
+    /// List API keys
+    List,
+    /// Revoke a key
+    Revoke {
+        /// Key ID or name
+        id_or_name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExtractAction {
+    /// Register a script to run on a domain's rendered pages
+    Add {
+        /// Domain (subdomains included)
+        domain: String,
+        /// Script file; its name gives the entry point (prices.js defines
+        /// __cortex_extractPrices)
+        script: std::path::PathBuf,
+        /// Confidence of its values, 0-1 (default 0.8)
+        #[arg(long)]
+        confidence: Option<f32>,
+    },
+    /// List custom extractors
+    List {
+        /// Only this domain's
+        domain: Option<String>,
+    },
+    /// Remove a custom extractor
+    Remove {
+        domain: String,
+        /// Extractor name (the script's file name without .js)
+        name: String,
+    },
+    /// Run an extraction script on a live page under its resource limits
+    Test {
+        /// Script file; its name gives the entry point (prices.js defines
+        /// __cortex_extractPrices)
+        script: std::path::PathBuf,
+        /// Page to run it on
+        url: String,
+        /// Time limit in milliseconds (default: configured, or 2000)
+        #[arg(long)]
+        timeout_ms: Option<u64>,
+        /// JS heap growth limit in MB (default: configured, or 64)
+        #[arg(long)]
+        max_heap_mb: Option<u64>,
+        /// Result size limit in KB (default: configured, or 1024)
+        #[arg(long)]
+        max_result_kb: Option<u64>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProjectAction {
+    /// Create a project
+    Create {
+        /// Project name (letters, digits, '-' and '_')
+        name: String,
+        /// Domains in the project
+        domains: Vec<String>,
+    },
+    /// Add domains to a project
+    Add {
+        name: String,
+        #[arg(required = true)]
+        domains: Vec<String>,
+    },
+    /// Remove domains from a project
+    Remove {
+        name: String,
+        #[arg(required = true)]
+        domains: Vec<String>,
+    },
+    /// Delete a project (its maps are kept)
+    Delete { name: String },
+    /// List projects
+    List,
+    /// Show a project's domains and settings
+    Show { name: String },
+    /// Make queries without a domain search a project
+    Use {
+        /// Project to use; omit to stop using one
+        name: Option<String>,
+    },
+    /// Change a project's shared settings
+    Set {
+        name: String,
+        /// Honour robots.txt when mapping the project's domains
+        #[arg(long, value_name = "BOOL")]
+        respect_robots: Option<bool>,
+        /// Let an AUTH session for one domain stand for all of them
+        #[arg(long, value_name = "BOOL")]
+        share_sessions: Option<bool>,
+        /// Default `cortex compile` target (repeatable; replaces the list)
+        #[arg(long = "target", value_name = "TARGET")]
+        targets: Vec<String>,
+        /// Content policy file for domains without one of their own
+        #[arg(long, value_name = "FILE")]
+        policy: Option<std::path::PathBuf>,
+        /// Drop the project's content policy
+        #[arg(long, conflicts_with = "policy")]
+        no_policy: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileAction {
+    /// Create or replace a profile
+    Set {
+        /// Profile name (letters, digits, '-' and '_')
+        name: String,
+        /// User-Agent to send (default: the honest Cortex one)
+        #[arg(long)]
+        user_agent: Option<String>,
+        /// Accept-Language to send, e.g. "de-DE,de;q=0.9"
+        #[arg(long)]
+        accept_language: Option<String>,
+        /// Client hint to send, as NAME=VALUE (repeatable)
+        #[arg(long = "client-hint", value_name = "NAME=VALUE")]
+        client_hints: Vec<String>,
+        /// Other header to send, as NAME=VALUE (repeatable)
+        #[arg(long = "header", value_name = "NAME=VALUE")]
+        headers: Vec<String>,
+        /// Cookie to send, as NAME=VALUE (repeatable)
+        #[arg(long = "cookie", value_name = "NAME=VALUE")]
+        cookies: Vec<String>,
+    },
+    /// Delete a profile; its domains go back to the default
+    Remove { name: String },
+    /// Crawl domains as a profile
+    Assign {
+        /// Domain (also covers its subdomains)
+        #[arg(required = true)]
+        domains: Vec<String>,
+        /// Profile name
+        #[arg(long, short)]
+        profile: String,
+    },
+    /// Crawl domains as the default profile again
+    Unassign {
+        #[arg(required = true)]
+        domains: Vec<String>,
+    },
+    /// List profiles and their domains
+    List,
+    /// Show the profile and headers a domain is crawled with
+    Show { domain: String },
+}
+
+#[derive(Subcommand)]
+enum TemporalAction {
+    /// Chart a node's feature history as a sparkline (and optionally a PNG)
+    Chart {
+        /// Domain to query
+        domain: String,
+        /// URL of the node
+        url: String,
+        /// Feature dimension name (e.g. "price")
+        #[arg(long, default_value = "price")]
+        feature: String,
+        /// Start date (ISO 8601, default: 90 days ago)
+        #[arg(long)]
+        since: Option<String>,
+        /// Maximum sparkline width in characters
+        #[arg(long, default_value = "60")]
+        width: usize,
+        /// Also write a PNG chart to this path
+        #[arg(long)]
+        png: Option<std::path::PathBuf>,
+    },
+    /// Summarize pages added and removed, feature changes and fired watches per domain
+    Digest {
+        /// Domains to report on (default: every domain in the registry)
+        domains: Vec<String>,
+        /// Start of the window (ISO 8601 or an age such as 7d)
+        #[arg(long, default_value = "7d")]
+        since: String,
+        /// Smallest feature change to report, in percent
+        #[arg(long, default_value = "5")]
+        min_change: f32,
+        /// Most pages and changes listed per section
+        #[arg(long, default_value = "20")]
+        limit: usize,
+        /// Write the report to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+        /// POST the digest to this webhook URL (repeatable)
+        #[arg(long)]
+        webhook: Vec<String>,
+        /// Also POST it to the webhooks of the domains' watch rules
+        #[arg(long)]
+        notify: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum EventsAction {
+    /// Show the most recent events
+    Tail {
+        /// Number of events to show
+        #[arg(short = 'n', long, default_value = "20")]
+        lines: usize,
+        /// Keep printing new events as they are recorded
+        #[arg(long, short)]
+        follow: bool,
+        /// Only show events for this domain
+        #[arg(long)]
+        domain: Option<String>,
+    },
+    /// Replay recorded events in order
+    Replay {
+        /// Only events after this time (ISO 8601)
+        #[arg(long)]
+        since: Option<String>,
+        /// Only events after this sequence number
+        #[arg(long)]
+        after_seq: Option<u64>,
+        /// Only events for this domain
+        #[arg(long)]
+        domain: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuditAction {
+    /// List audited operations
+    List {
+        /// Only entries after this time (ISO 8601 or an age like 24h, 7d)
+        #[arg(long)]
+        since: Option<String>,
+        /// Only this method (e.g. map, act, registry_push)
+        #[arg(long)]
+        method: Option<String>,
+        /// Only entries for this domain
+        #[arg(long)]
+        domain: Option<String>,
+        /// Only entries by this actor
+        #[arg(long)]
+        actor: Option<String>,
+        /// Number of entries to show (most recent)
+        #[arg(short = 'n', long, default_value = "50")]
+        limit: usize,
+    },
+    /// Export audited operations as JSONL
+    Export {
+        /// Only entries after this time (ISO 8601 or an age like 24h, 7d)
+        #[arg(long)]
+        since: Option<String>,
+        /// Only this method
+        #[arg(long)]
+        method: Option<String>,
+        /// Only entries for this domain
+        #[arg(long)]
+        domain: Option<String>,
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum WatchAction {
+    /// Add a watch rule
+    Add {
+        /// Domain to watch
+        domain: String,
+        /// Only watch this node of the cached map (default: every page)
+        #[arg(long)]
+        node: Option<u32>,
+        /// Feature to compare, by name or dimension
+        #[arg(long, default_value = "price")]
+        feature: String,
+        /// Fire when the feature drops below this value
+        #[arg(long)]
+        below: Option<f32>,
+        /// Fire when the feature rises above this value
+        #[arg(long)]
+        above: Option<f32>,
+        /// Fire when the feature changes by more than this percentage
+        #[arg(long)]
+        change: Option<f32>,
+        /// Fire when an unavailable item becomes available
+        #[arg(long)]
+        available: bool,
+        /// Fire when new pages appear
+        #[arg(long)]
+        new_pages: bool,
+        /// Compound condition, e.g. "price < 300 AND availability = in_stock FOR 2 checks"
+        #[arg(long)]
+        when: Option<String>,
+        /// Fire when the node's screenshot changes (needs --node)
+        #[arg(long)]
+        visual: bool,
+        /// With --visual: percentage of pixels that must change (default 5)
+        #[arg(long)]
+        pixels: Option<f32>,
+        /// With --visual: fire on any change in this area, "x,y,w,h" as fractions of the page (repeatable)
+        #[arg(long = "region")]
+        regions: Vec<String>,
+        /// With --visual: time between screenshots, e.g. 30m or 6h (default 1h)
+        #[arg(long)]
+        every: Option<String>,
+        /// Rule id (default: w1, w2, ...)
+        #[arg(long)]
+        id: Option<String>,
+        /// Also deliver alerts to this webhook URL
+        #[arg(long)]
+        webhook: Option<String>,
+    },
+    /// List watch rules
+    List {
+        /// Only rules for this domain
+        #[arg(long)]
+        domain: Option<String>,
+    },
+    /// Remove a watch rule
+    Remove {
+        /// Rule id
+        id: String,
+    },
+    /// Show recent alerts and stream new ones as they fire
+    Tail {
+        /// Number of past alerts to show
+        #[arg(short = 'n', long, default_value = "20")]
+        lines: usize,
+        /// Exit after printing past alerts
+        #[arg(long)]
+        no_follow: bool,
+        /// Only alerts for this domain
+        #[arg(long)]
+        domain: Option<String>,
+        /// Only alerts from this rule
+        #[arg(long)]
+        rule: Option<String>,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    cli::output::set_command(&command_name(&matches));
 
     // Set global flags via environment variables so all modules can check them
     if cli.json {
@@ -231,9 +980,22 @@ async fn main() -> Result<()> {
         // No subcommand → launch interactive REPL
         None => cli::repl::run().await,
 
-        Some(Commands::Start { http_port }) => cli::start::run_with_http(http_port).await,
+        Some(Commands::Start {
+            http_port,
+            coordinator,
+            worker_tokens,
+            log_file,
+        }) => {
+            cli::start::run_with(http_port, coordinator.as_deref(), worker_tokens, log_file).await
+        }
         Some(Commands::Stop) => cli::stop::run().await,
         Some(Commands::Restart) => cli::restart_cmd::run().await,
+        Some(Commands::Reload) => cli::reload_cmd::run().await,
+        Some(Commands::Service { action }) => match action {
+            ServiceAction::Install { http_port } => cli::service_cmd::run_install(http_port).await,
+            ServiceAction::Uninstall => cli::service_cmd::run_uninstall().await,
+            ServiceAction::Status => cli::service_cmd::run_status().await,
+        },
         Some(Commands::Doctor) => cli::doctor::run().await,
         Some(Commands::Status) => cli::status::run().await,
         Some(Commands::Map {
@@ -242,32 +1004,185 @@ async fn main() -> Result<()> {
             max_render,
             timeout,
             fresh,
-        }) => cli::map_cmd::run(&domain, max_nodes, max_render, timeout, fresh).await,
+            resume,
+            min_quality,
+        }) => {
+            cli::map_cmd::run(
+                &domain,
+                max_nodes,
+                max_render,
+                timeout,
+                fresh,
+                resume,
+                min_quality,
+            )
+            .await
+        }
         Some(Commands::Query {
             domain,
+            project,
             page_type,
             price_lt,
             rating_gt,
             feature_filters,
+            text,
             limit,
-        }) => {
-            cli::query_cmd::run(
-                &domain,
-                page_type.as_deref(),
-                price_lt,
-                rating_gt,
-                limit,
-                &feature_filters,
-            )
-            .await
-        }
+            diversify,
+            save,
+            use_saved,
+            saved,
+            fail_when,
+        }) => match cli::predicate::Predicate::parse_all(&fail_when) {
+            Err(e) => Err(e),
+            Ok(fail_when) => {
+                use cli::query_cmd::Scope;
+                if saved {
+                    cli::query_cmd::run_list_saved().await
+                } else if let Some(name) = save {
+                    // Saving runs the query only against an explicit scope
+                    let scope = if domain.is_some() || project.is_some() {
+                        Scope::resolve(domain, project.as_deref())
+                    } else {
+                        Ok(None)
+                    };
+                    let query = cli::query_cmd::build_query(
+                        page_type.as_deref(),
+                        price_lt,
+                        rating_gt,
+                        limit.unwrap_or(20),
+                        &feature_filters,
+                        text.as_deref(),
+                    );
+                    match scope {
+                        Ok(scope) => {
+                            cli::query_cmd::run_save(
+                                &name,
+                                scope.as_ref(),
+                                query,
+                                diversify,
+                                &fail_when,
+                            )
+                            .await
+                        }
+                        Err(e) => Err(e),
+                    }
+                } else {
+                    match Scope::resolve(domain, project.as_deref()) {
+                        Err(e) => Err(e),
+                        Ok(None) => Err(anyhow::anyhow!(
+                            "missing domain: cortex query <domain>, or --project <name>"
+                        )),
+                        Ok(Some(scope)) => match use_saved {
+                            Some(name) => {
+                                cli::query_cmd::run_saved(&scope, &name, limit, &fail_when).await
+                            }
+                            None => {
+                                cli::query_cmd::run(
+                                    &scope,
+                                    page_type.as_deref(),
+                                    price_lt,
+                                    rating_gt,
+                                    limit.unwrap_or(20),
+                                    &feature_filters,
+                                    text.as_deref(),
+                                    diversify,
+                                    &fail_when,
+                                )
+                                .await
+                            }
+                        },
+                    }
+                }
+            }
+        },
         Some(Commands::Pathfind { domain, from, to }) => {
             cli::pathfind_cmd::run(&domain, from, to).await
         }
+        Some(Commands::Analyze { domain, limit }) => cli::analyze_cmd::run(&domain, limit).await,
+        Some(Commands::Inspect { domain, node, html }) => {
+            cli::inspect_cmd::run(&domain, node, html).await
+        }
+        Some(Commands::Annotate {
+            domain,
+            node,
+            url,
+            label,
+            page_type,
+            note,
+            author,
+            remove,
+        }) => match cli::annotate_cmd::AnnotateAction::from_flags(
+            label,
+            page_type.as_deref(),
+            note,
+            remove,
+        ) {
+            Ok(action) => {
+                cli::annotate_cmd::run(&domain, node, url.as_deref(), action, author.as_deref())
+                    .await
+            }
+            Err(e) => Err(e),
+        },
         Some(Commands::Perceive { url, format }) => cli::perceive_cmd::run(&url, &format).await,
         Some(Commands::Install { force }) => cli::install_cmd::run_with_force(force).await,
         Some(Commands::Cache { action }) => match action {
             CacheAction::Clear { domain } => cli::cache_cmd::run_clear(domain.as_deref()).await,
+            CacheAction::Export {
+                domain,
+                format,
+                features,
+                output,
+            } => {
+                cli::cache_cmd::run_export(
+                    &domain,
+                    &format,
+                    &features,
+                    output.as_deref().map(std::path::Path::new),
+                )
+                .await
+            }
+            CacheAction::Merge {
+                inputs,
+                into,
+                aliases,
+            } => cli::cache_cmd::run_merge(&inputs, into.as_deref(), &aliases).await,
+            CacheAction::Slice {
+                domain,
+                page_types,
+                features,
+                wql,
+                limit,
+                out,
+            } => {
+                cli::cache_cmd::run_slice(
+                    &domain,
+                    &page_types,
+                    &features,
+                    wql.as_deref(),
+                    limit,
+                    std::path::Path::new(&out),
+                )
+                .await
+            }
+            CacheAction::Checkout { domain, at, out } => {
+                cli::cache_cmd::run_checkout(&domain, &at, out.as_deref().map(std::path::Path::new))
+                    .await
+            }
+            CacheAction::Stats { limit } => cli::cache_cmd::run_stats(limit).await,
+            CacheAction::Import {
+                file,
+                format,
+                links,
+                domain,
+            } => {
+                cli::cache_cmd::run_import(
+                    std::path::Path::new(&file),
+                    format.as_deref(),
+                    links.as_deref().map(std::path::Path::new),
+                    domain.as_deref(),
+                )
+                .await
+            }
         },
         Some(Commands::Completions { shell }) => {
             let mut cmd = Cli::command();
@@ -278,12 +1193,90 @@ async fn main() -> Result<()> {
             domain,
             all,
             output,
-        }) => cli::compile_cmd::run(&domain, all, output.as_deref()).await,
-        Some(Commands::Wql { query }) => cli::wql_cmd::run(&query).await,
+            targets,
+            package_version,
+        }) => {
+            cli::compile_cmd::run(
+                &domain,
+                all,
+                output.as_deref(),
+                &targets,
+                package_version.as_deref(),
+            )
+            .await
+        }
+        Some(Commands::Wql {
+            query,
+            project,
+            fail_when,
+        }) => match cli::predicate::Predicate::parse_all(&fail_when) {
+            Ok(fail_when) => cli::wql_cmd::run(&query, project.as_deref(), &fail_when).await,
+            Err(e) => Err(e),
+        },
+        Some(Commands::Mcp { sse, token }) => {
+            cli::mcp_cmd::run(sse.as_deref(), token.as_deref()).await
+        }
+        Some(Commands::Worker {
+            join,
+            token,
+            name,
+            no_browser,
+        }) => cli::worker_cmd::run(&join, token.as_deref(), name.as_deref(), no_browser).await,
         Some(Commands::Registry { action }) => match action {
             RegistryAction::List => cli::registry_cmd::run_list().await,
             RegistryAction::Stats => cli::registry_cmd::run_stats().await,
             RegistryAction::Gc => cli::registry_cmd::run_gc().await,
+            RegistryAction::Verify { domain } => cli::registry_cmd::run_verify(&domain).await,
+            RegistryAction::Serve {
+                bind,
+                port,
+                tokens,
+                require_signatures,
+            } => cli::registry_cmd::run_serve(&bind, port, tokens, require_signatures).await,
+            RegistryAction::Lan {
+                port,
+                interval,
+                token,
+                allow_unsigned,
+            } => cli::registry_cmd::run_lan(port, interval, token.as_deref(), allow_unsigned).await,
+            RegistryAction::Tag { domain, tags } => cli::registry_cmd::run_tag(&domain, tags).await,
+            RegistryAction::Subscribe {
+                name,
+                remote,
+                domains,
+                tags,
+                filter,
+                interval,
+            } => {
+                cli::registry_cmd::run_subscribe(&name, &remote, domains, tags, filter, interval)
+                    .await
+            }
+            RegistryAction::Unsubscribe { name } => cli::registry_cmd::run_unsubscribe(&name).await,
+            RegistryAction::Subscriptions => cli::registry_cmd::run_subscriptions().await,
+            RegistryAction::Sync {
+                token,
+                force,
+                watch,
+            } => cli::registry_cmd::run_sync(token.as_deref(), force, watch).await,
+            RegistryAction::Push {
+                domain,
+                remote,
+                token,
+            } => cli::registry_cmd::run_push(&domain, remote.as_deref(), token.as_deref()).await,
+            RegistryAction::Pull {
+                domain,
+                remote,
+                token,
+                allow_unsigned,
+            } => {
+                cli::registry_cmd::run_pull(
+                    &domain,
+                    remote.as_deref(),
+                    token.as_deref(),
+                    allow_unsigned,
+                )
+                .await
+            }
         },
         Some(Commands::History {
             domain,
@@ -294,26 +1287,273 @@ async fn main() -> Result<()> {
         Some(Commands::Patterns { domain, url, dim }) => {
             cli::temporal_cmd::run_patterns(&domain, &url, &dim).await
         }
+        Some(Commands::Trust { action }) => match action {
+            TrustAction::Key => cli::trust_cmd::run_key().await,
+            TrustAction::Add { name, public_key } => {
+                cli::trust_cmd::run_add(&name, &public_key).await
+            }
+            TrustAction::Remove { name } => cli::trust_cmd::run_remove(&name).await,
+            TrustAction::List => cli::trust_cmd::run_list().await,
+        },
+        Some(Commands::Keys { action }) => match action {
+            KeysAction::Add { name, permission } => {
+                cli::keys_cmd::run_add(&name, &permission).await
+            }
+            KeysAction::List => cli::keys_cmd::run_list().await,
+            KeysAction::Revoke { id_or_name } => cli::keys_cmd::run_revoke(&id_or_name).await,
+        },
+        Some(Commands::Extract { action }) => match action {
+            ExtractAction::Add {
+                domain,
+                script,
+                confidence,
+            } => cli::extract_cmd::run_add(&domain, &script, confidence).await,
+            ExtractAction::List { domain } => cli::extract_cmd::run_list(domain.as_deref()).await,
+            ExtractAction::Remove { domain, name } => {
+                cli::extract_cmd::run_remove(&domain, &name).await
+            }
+            ExtractAction::Test {
+                script,
+                url,
+                timeout_ms,
+                max_heap_mb,
+                max_result_kb,
+            } => {
+                let overrides = cli::extract_cmd::LimitOverrides {
+                    timeout_ms,
+                    max_heap_mb,
+                    max_result_kb,
+                };
+                cli::extract_cmd::run_test(&script, &url, overrides).await
+            }
+        },
+        Some(Commands::Project { action }) => match action {
+            ProjectAction::Create { name, domains } => {
+                cli::project_cmd::run_create(&name, &domains).await
+            }
+            ProjectAction::Add { name, domains } => {
+                cli::project_cmd::run_add(&name, &domains).await
+            }
+            ProjectAction::Remove { name, domains } => {
+                cli::project_cmd::run_remove(&name, &domains).await
+            }
+            ProjectAction::Delete { name } => cli::project_cmd::run_delete(&name).await,
+            ProjectAction::List => cli::project_cmd::run_list().await,
+            ProjectAction::Show { name } => cli::project_cmd::run_show(&name).await,
+            ProjectAction::Use { name } => cli::project_cmd::run_use(name.as_deref()).await,
+            ProjectAction::Set {
+                name,
+                respect_robots,
+                share_sessions,
+                targets,
+                policy,
+                no_policy,
+            } => {
+                let change = cli::project_cmd::SettingsChange {
+                    respect_robots,
+                    share_sessions,
+                    compile_targets: (!targets.is_empty()).then_some(targets.as_slice()),
+                    policy: if no_policy {
+                        Some(None)
+                    } else {
+                        policy.as_deref().map(Some)
+                    },
+                };
+                cli::project_cmd::run_set(&name, change).await
+            }
+        },
+        Some(Commands::Profile { action }) => match action {
+            ProfileAction::Set {
+                name,
+                user_agent,
+                accept_language,
+                client_hints,
+                headers,
+                cookies,
+            } => {
+                cli::profile_cmd::run_set(
+                    &name,
+                    user_agent,
+                    accept_language,
+                    &client_hints,
+                    &headers,
+                    &cookies,
+                )
+                .await
+            }
+            ProfileAction::Remove { name } => cli::profile_cmd::run_remove(&name).await,
+            ProfileAction::Assign { domains, profile } => {
+                cli::profile_cmd::run_assign(&domains, Some(&profile)).await
+            }
+            ProfileAction::Unassign { domains } => {
+                cli::profile_cmd::run_assign(&domains, None).await
+            }
+            ProfileAction::List => cli::profile_cmd::run_list().await,
+            ProfileAction::Show { domain } => cli::profile_cmd::run_show(&domain).await,
+        },
+        Some(Commands::Temporal { action }) => match action {
+            TemporalAction::Chart {
+                domain,
+                url,
+                feature,
+                since,
+                width,
+                png,
+            } => {
+                cli::temporal_cmd::run_chart(
+                    &domain,
+                    &url,
+                    &feature,
+                    since.as_deref(),
+                    width,
+                    png.as_deref(),
+                )
+                .await
+            }
+            TemporalAction::Digest {
+                domains,
+                since,
+                min_change,
+                limit,
+                output,
+                webhook,
+                notify,
+            } => {
+                cli::temporal_cmd::run_digest(
+                    &domains,
+                    &since,
+                    min_change,
+                    limit,
+                    output.as_deref(),
+                    &webhook,
+                    notify,
+                )
+                .await
+            }
+        },
+        Some(Commands::Audit { action }) => match action {
+            AuditAction::List {
+                since,
+                method,
+                domain,
+                actor,
+                limit,
+            } => {
+                cli::audit_cmd::run_list(
+                    since.as_deref(),
+                    method.as_deref(),
+                    domain.as_deref(),
+                    actor.as_deref(),
+                    limit,
+                )
+                .await
+            }
+            AuditAction::Export {
+                since,
+                method,
+                domain,
+                output,
+            } => {
+                cli::audit_cmd::run_export(
+                    since.as_deref(),
+                    method.as_deref(),
+                    domain.as_deref(),
+                    output.as_deref(),
+                )
+                .await
+            }
+        },
+        Some(Commands::Events { action }) => match action {
+            EventsAction::Tail {
+                lines,
+                follow,
+                domain,
+            } => cli::events_cmd::run_tail(lines, follow, domain.as_deref()).await,
+            EventsAction::Replay {
+                since,
+                after_seq,
+                domain,
+            } => cli::events_cmd::run_replay(since.as_deref(), after_seq, domain.as_deref()).await,
+        },
+        Some(Commands::Watch { action }) => match action {
+            WatchAction::Add {
+                domain,
+                node,
+                feature,
+                below,
+                above,
+                change,
+                available,
+                new_pages,
+                when,
+                visual,
+                pixels,
+                regions,
+                every,
+                id,
+                webhook,
+            } => match cli::watch_cmd::visual_condition(visual, pixels, &regions, every.as_deref())
+                .and_then(|visual| {
+                    cli::watch_cmd::condition(
+                        below,
+                        above,
+                        change,
+                        available,
+                        new_pages,
+                        when.as_deref(),
+                        visual,
+                    )
+                }) {
+                Ok(condition) => {
+                    cli::watch_cmd::run_add(&domain, node, &feature, condition, id, webhook).await
+                }
+                Err(e) => Err(e),
+            },
+            WatchAction::List { domain } => cli::watch_cmd::run_list(domain.as_deref()).await,
+            WatchAction::Remove { id } => cli::watch_cmd::run_remove(&id).await,
+            WatchAction::Tail {
+                lines,
+                no_follow,
+                domain,
+                rule,
+            } => {
+                cli::watch_cmd::run_tail(lines, !no_follow, domain.as_deref(), rule.as_deref())
+                    .await
+            }
+        },
         Some(Commands::Plug {
             list,
             remove,
             status,
+            doctor,
             agent,
+            project,
             config_dir,
         }) => {
             cli::plug::run(
                 list,
                 remove,
                 status,
+                doctor,
                 agent.as_deref(),
+                project.as_deref(),
                 config_dir.as_deref(),
             )
             .await
         }
     };
 
-    // Consistent exit codes: 0=success, 1=error
+    // Consistent exit codes: 0=success, 1=error, 2=--fail-when matched
     if let Err(e) = &result {
+        if e.downcast_ref::<cli::predicate::PredicateFailed>()
+            .is_some()
+        {
+            // The results were printed; only the verdict is left to report.
+            if !cli::output::is_quiet() && !cli::output::is_json() {
+                eprintln!("  {e}");
+            }
+            std::process::exit(cli::predicate::EXIT_PREDICATE);
+        }
         if !cli::output::is_quiet() && !cli::output::is_json() {
             eprintln!("  Error: {e:#}");
         }
@@ -328,3 +1568,18 @@ async fn main() -> Result<()> {
 
     result
 }
+
+/// Full subcommand path of an invocation (e.g. `cache clear`), or `repl`.
+fn command_name(matches: &ArgMatches) -> String {
+    let mut names = Vec::new();
+    let mut current = matches;
+    while let Some((name, sub)) = current.subcommand() {
+        names.push(name);
+        current = sub;
+    }
+    if names.is_empty() {
+        "repl".to_string()
+    } else {
+        names.join(" ")
+    }
+}
@@ -219,12 +219,10 @@ fn run_cache_cleanup() -> CacheCleanupResult {
 }
 
 fn run_registry_gc(keep_deltas: usize) -> Option<usize> {
-    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-    let registry_dir = home.join(".cortex").join("registry");
-    match LocalRegistry::new(registry_dir) {
+    match LocalRegistry::open_default() {
         Ok(mut registry) => match registry.gc(keep_deltas) {
             Ok(removed) if removed > 0 => {
-                tracing::info!("maintenance registry gc removed {removed} delta file(s)");
+                tracing::info!("maintenance registry gc removed {removed} history record(s)");
                 Some(removed)
             }
             Ok(_) => Some(0),
@@ -1,6 +1,9 @@
 //! SiteMapBuilder for incrementally constructing a SiteMap.
 
+use crate::map::text::{MapText, NodeText};
 use crate::map::types::*;
+use crate::trust::conflicts::FieldConflict;
+use crate::trust::lineage::{FieldLineage, MapLineage};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Intermediate edge data during building.
@@ -24,6 +27,8 @@ pub struct SiteMapBuilder {
     edges: Vec<EdgeData>,
     actions: Vec<ActionData>,
     has_sitemap: bool,
+    lineage: MapLineage,
+    text: MapText,
 }
 
 impl SiteMapBuilder {
@@ -37,6 +42,8 @@ impl SiteMapBuilder {
             edges: Vec::new(),
             actions: Vec::new(),
             has_sitemap: false,
+            lineage: MapLineage::default(),
+            text: MapText::default(),
         }
     }
 
@@ -189,6 +196,27 @@ impl SiteMapBuilder {
         }
     }
 
+    /// Record where a node's `field` value came from.
+    pub fn record_lineage(&mut self, node: u32, field: &str, lineage: FieldLineage) {
+        if (node as usize) < self.nodes.len() {
+            self.lineage.record(node, field, lineage);
+        }
+    }
+
+    /// Record a node's title and description.
+    pub fn set_text(&mut self, node: u32, text: NodeText) {
+        if (node as usize) < self.nodes.len() {
+            self.text.insert(node, text);
+        }
+    }
+
+    /// Record that layers disagreed on a node's `field`.
+    pub fn record_conflict(&mut self, node: u32, field: &str, conflict: FieldConflict) {
+        if (node as usize) < self.nodes.len() {
+            self.lineage.record_conflict(node, field, conflict);
+        }
+    }
+
     /// Build the final SiteMap.
     pub fn build(mut self) -> SiteMap {
         let node_count = self.nodes.len();
@@ -286,6 +314,11 @@ impl SiteMapBuilder {
             cluster_assignments,
             cluster_centroids,
             urls: self.urls,
+            lineage: self.lineage,
+            text: self.text,
+            embeddings: Default::default(),
+            compliance: None,
+            blocks: None,
         }
     }
 }
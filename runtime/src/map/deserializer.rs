@@ -1,7 +1,10 @@
 //! Deserialize a SiteMap from the binary CTX format.
 //!
-//! Verifies the trailing CRC32 checksum to detect corruption.
+//! Verifies the trailing CRC32 checksum to detect corruption. Current (v2)
+//! maps are decoded through [`MappedSiteMap`]; legacy v1 maps, whose tables
+//! are packed back to back, are read sequentially.
 
+use crate::map::mapped::MappedSiteMap;
 use crate::map::serializer::crc32;
 use crate::map::types::*;
 use anyhow::{bail, Context, Result};
@@ -49,6 +52,9 @@ impl SiteMap {
         if format_version > FORMAT_VERSION {
             bail!("unsupported format version: expected <= {FORMAT_VERSION}, got {format_version}");
         }
+        if format_version == FORMAT_VERSION {
+            return Ok(MappedSiteMap::from_bytes(data.to_vec())?.to_site_map());
+        }
         if format_version < FORMAT_VERSION {
             tracing::info!(
                 "Loading legacy map format v{}; upgrading to v{} in-memory",
@@ -57,6 +63,7 @@ impl SiteMap {
             );
         }
 
+        // ─── Legacy v1: sequential tables ─────────────────
         let domain_length = r
             .read_u16::<LittleEndian>()
             .context("reading domain length")? as usize;
@@ -220,6 +227,11 @@ impl SiteMap {
             cluster_assignments,
             cluster_centroids,
             urls,
+            lineage: Default::default(),
+            text: Default::default(),
+            embeddings: Default::default(),
+            compliance: None,
+            blocks: None,
         })
     }
 }
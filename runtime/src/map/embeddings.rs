@@ -0,0 +1,172 @@
+//! Sentence embeddings of page titles and descriptions, for semantic QUERY.
+//!
+//! When the daemon has a sentence model (see
+//! [`crate::cartography::semantic`]), the mapper embeds each page's
+//! [`NodeText`](crate::map::text::NodeText) and QUERY `mode: "semantic"`
+//! ranks pages by cosine similarity to an embedded free-text goal. Like the
+//! text they come from, embeddings are not part of the binary map format;
+//! the map cache keeps them next to the map as `<domain>.embeddings.bin`:
+//!
+//! ```text
+//! magic "CXEM" | dim: u32 | count: u32 | count × (node: u32, dim × f32)
+//! ```
+//!
+//! all little-endian.
+
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"CXEM";
+
+/// Embeddings of the nodes of one map, keyed by node index.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MapEmbeddings {
+    /// Length of every vector; 0 while empty.
+    pub dim: usize,
+    pub nodes: BTreeMap<u32, Vec<f32>>,
+}
+
+impl MapEmbeddings {
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Record `node`'s embedding. The first one sets the dimension; vectors
+    /// of any other length are rejected.
+    pub fn insert(&mut self, node: u32, embedding: Vec<f32>) -> Result<()> {
+        if self.nodes.is_empty() {
+            self.dim = embedding.len();
+        } else if embedding.len() != self.dim {
+            bail!(
+                "embedding of node {node} has {} dimensions, expected {}",
+                embedding.len(),
+                self.dim
+            );
+        }
+        self.nodes.insert(node, embedding);
+        Ok(())
+    }
+
+    /// The `k` nodes most similar to `target` by cosine similarity, best
+    /// first. Empty if `target` has another dimension or no length.
+    pub fn nearest(&self, target: &[f32], k: usize) -> Vec<(u32, f32)> {
+        let target_norm = norm(target);
+        if target.len() != self.dim || target_norm == 0.0 {
+            return Vec::new();
+        }
+        let mut scored: Vec<(u32, f32)> = self
+            .nodes
+            .iter()
+            .filter_map(|(&node, v)| {
+                let node_norm = norm(v);
+                (node_norm > 0.0).then(|| {
+                    let dot: f32 = v.iter().zip(target).map(|(a, b)| a * b).sum();
+                    (node, dot / (node_norm * target_norm))
+                })
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        scored.truncate(k);
+        scored
+    }
+
+    /// Load embeddings saved by [`save`](Self::save); empty if the file is
+    /// missing.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = std::fs::read(path)
+            .with_context(|| format!("failed to read embeddings: {}", path.display()))?;
+        Self::decode(&data).with_context(|| format!("invalid embeddings: {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.encode())
+            .with_context(|| format!("failed to write embeddings: {}", path.display()))
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(12 + self.nodes.len() * (4 + self.dim * 4));
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&(self.dim as u32).to_le_bytes());
+        out.extend_from_slice(&(self.nodes.len() as u32).to_le_bytes());
+        for (node, v) in &self.nodes {
+            out.extend_from_slice(&node.to_le_bytes());
+            for x in v {
+                out.extend_from_slice(&x.to_le_bytes());
+            }
+        }
+        out
+    }
+
+    fn decode(data: &[u8]) -> Result<Self> {
+        if data.len() < 12 || &data[..4] != MAGIC {
+            bail!("not an embeddings file");
+        }
+        let u32_at =
+            |at: usize| u32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]]);
+        let dim = u32_at(4) as usize;
+        let count = u32_at(8) as usize;
+        let stride = 4 + dim * 4;
+        if data.len() != 12 + count * stride {
+            bail!("expected {count} embeddings of {dim} dimensions");
+        }
+        let nodes = (0..count)
+            .map(|i| {
+                let at = 12 + i * stride;
+                let v = (0..dim)
+                    .map(|d| f32::from_bits(u32_at(at + 4 + d * 4)))
+                    .collect();
+                (u32_at(at), v)
+            })
+            .collect();
+        Ok(Self { dim, nodes })
+    }
+}
+
+fn norm(v: &[f32]) -> f32 {
+    v.iter().map(|x| x * x).sum::<f32>().sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nearest_by_cosine() {
+        let mut embeddings = MapEmbeddings::default();
+        embeddings.insert(0, vec![1.0, 0.0, 0.0]).unwrap();
+        embeddings.insert(1, vec![0.7, 0.7, 0.0]).unwrap();
+        embeddings.insert(2, vec![0.0, 0.0, 2.0]).unwrap();
+        embeddings.insert(3, vec![0.0, 0.0, 0.0]).unwrap();
+        assert!(embeddings.insert(4, vec![1.0]).is_err());
+
+        let nearest = embeddings.nearest(&[2.0, 0.1, 0.0], 2);
+        let nodes: Vec<u32> = nearest.iter().map(|n| n.0).collect();
+        assert_eq!(nodes, [0, 1]);
+        assert!(nearest[0].1 > 0.99);
+
+        // Zero vectors never match; wrong dimensions match nothing
+        assert_eq!(embeddings.nearest(&[0.0, 0.0, 1.0], 10).len(), 3);
+        assert!(embeddings.nearest(&[1.0, 0.0], 10).is_empty());
+        assert!(embeddings.nearest(&[0.0, 0.0, 0.0], 10).is_empty());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shop.com.embeddings.bin");
+        assert!(MapEmbeddings::load(&path).unwrap().is_empty());
+
+        let mut embeddings = MapEmbeddings::default();
+        embeddings.insert(2, vec![0.5, -0.25]).unwrap();
+        embeddings.insert(7, vec![1.0, 0.0]).unwrap();
+        embeddings.save(&path).unwrap();
+        assert_eq!(MapEmbeddings::load(&path).unwrap(), embeddings);
+
+        std::fs::write(&path, b"CXEM\x02\0\0\0\x05\0\0\0").unwrap();
+        assert!(MapEmbeddings::load(&path).is_err());
+    }
+}
@@ -0,0 +1,372 @@
+//! Export a SiteMap to graph interchange formats (GraphML, GEXF, DOT) for
+//! analysis in tools like Gephi, Neo4j, or Graphviz.
+
+use crate::cartography::feature_plugins::PluginDims;
+use crate::compiler::schema::field_name_to_dim;
+use crate::map::types::*;
+use anyhow::{bail, Result};
+use quick_xml::escape::escape;
+use std::fmt::Write;
+
+/// Graph file format to export to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    GraphMl,
+    Gexf,
+    Dot,
+}
+
+impl ExportFormat {
+    /// Parse a format name (`graphml`, `gexf`, `dot`).
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "graphml" => Ok(Self::GraphMl),
+            "gexf" => Ok(Self::Gexf),
+            "dot" | "gv" => Ok(Self::Dot),
+            other => bail!("unknown export format: {other} (expected graphml, gexf or dot)"),
+        }
+    }
+
+    /// Conventional file extension.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::GraphMl => "graphml",
+            Self::Gexf => "gexf",
+            Self::Dot => "dot",
+        }
+    }
+}
+
+/// A feature dimension exported as a node attribute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportFeature {
+    /// Attribute name.
+    pub name: String,
+    /// Feature vector dimension.
+    pub dim: usize,
+}
+
+impl ExportFeature {
+    /// Resolve a feature by name (`price`, `rating`, ... as in
+    /// [`field_name_to_dim`], or a registered plugin dimension) or
+    /// dimension number.
+    pub fn parse(name: &str) -> Result<Self> {
+        let dim = match field_name_to_dim(name) {
+            Some(dim) => dim,
+            None => match name.parse::<usize>() {
+                Ok(d) if d < FEATURE_DIM => d,
+                Ok(_) => bail!("unknown feature: {name}"),
+                Err(_) => match PluginDims::load_default().dim(name) {
+                    Some(d) => d,
+                    None => bail!("unknown feature: {name}"),
+                },
+            },
+        };
+        let name = if name.chars().all(|c| c.is_ascii_digit()) {
+            format!("f{dim}")
+        } else {
+            name.to_string()
+        };
+        Ok(Self { name, dim })
+    }
+}
+
+/// Serialize `map` in `format`, including `features` as node attributes.
+pub fn export(map: &SiteMap, format: ExportFormat, features: &[ExportFeature]) -> String {
+    match format {
+        ExportFormat::GraphMl => to_graphml(map, features),
+        ExportFormat::Gexf => to_gexf(map, features),
+        ExportFormat::Dot => to_dot(map, features),
+    }
+}
+
+/// Fixed node attributes: (name, GraphML/GEXF type).
+const NODE_ATTRS: [(&str, &str); 5] = [
+    ("url", "string"),
+    ("page_type", "string"),
+    ("confidence", "double"),
+    ("depth", "int"),
+    ("http_status", "int"),
+];
+
+/// Serialize as GraphML.
+pub fn to_graphml(map: &SiteMap, features: &[ExportFeature]) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+    for (name, ty) in NODE_ATTRS {
+        let _ = writeln!(
+            out,
+            "  <key id=\"{name}\" for=\"node\" attr.name=\"{name}\" attr.type=\"{ty}\"/>"
+        );
+    }
+    for f in features {
+        let name = escape(f.name.as_str());
+        let _ = writeln!(
+            out,
+            "  <key id=\"{name}\" for=\"node\" attr.name=\"{name}\" attr.type=\"double\"/>"
+        );
+    }
+    out.push_str(
+        "  <key id=\"edge_type\" for=\"edge\" attr.name=\"edge_type\" attr.type=\"string\"/>\n",
+    );
+    out.push_str("  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"int\"/>\n");
+    let _ = writeln!(
+        out,
+        "  <graph id=\"{}\" edgedefault=\"directed\">",
+        escape(map.header.domain.as_str())
+    );
+
+    for (i, node) in map.nodes.iter().enumerate() {
+        let _ = writeln!(out, "    <node id=\"n{i}\">");
+        for (name, value) in node_attrs(map, i, node) {
+            let _ = writeln!(
+                out,
+                "      <data key=\"{name}\">{}</data>",
+                escape(value.as_str())
+            );
+        }
+        for f in features {
+            let _ = writeln!(
+                out,
+                "      <data key=\"{}\">{}</data>",
+                escape(f.name.as_str()),
+                map.features[i][f.dim]
+            );
+        }
+        out.push_str("    </node>\n");
+    }
+    for (id, (from, edge)) in edges(map).enumerate() {
+        let _ = writeln!(
+            out,
+            "    <edge id=\"e{id}\" source=\"n{from}\" target=\"n{}\">",
+            edge.target_node
+        );
+        let _ = writeln!(
+            out,
+            "      <data key=\"edge_type\">{:?}</data>",
+            edge.edge_type
+        );
+        let _ = writeln!(out, "      <data key=\"weight\">{}</data>", edge.weight);
+        out.push_str("    </edge>\n");
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+/// Serialize as GEXF 1.3.
+pub fn to_gexf(map: &SiteMap, features: &[ExportFeature]) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<gexf xmlns=\"http://gexf.net/1.3\" version=\"1.3\">\n");
+    let _ = writeln!(
+        out,
+        "  <meta><creator>cortex</creator><description>{}</description></meta>",
+        escape(map.header.domain.as_str())
+    );
+    out.push_str("  <graph defaultedgetype=\"directed\">\n");
+    out.push_str("    <attributes class=\"node\">\n");
+    for (id, (name, ty)) in NODE_ATTRS.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "      <attribute id=\"{id}\" title=\"{name}\" type=\"{}\"/>",
+            gexf_type(ty)
+        );
+    }
+    for (k, f) in features.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "      <attribute id=\"{}\" title=\"{}\" type=\"double\"/>",
+            NODE_ATTRS.len() + k,
+            escape(f.name.as_str())
+        );
+    }
+    out.push_str("    </attributes>\n");
+    out.push_str("    <attributes class=\"edge\">\n");
+    out.push_str("      <attribute id=\"0\" title=\"edge_type\" type=\"string\"/>\n");
+    out.push_str("    </attributes>\n");
+
+    out.push_str("    <nodes>\n");
+    for (i, node) in map.nodes.iter().enumerate() {
+        let url = map.urls.get(i).map(String::as_str).unwrap_or("");
+        let _ = writeln!(out, "      <node id=\"n{i}\" label=\"{}\">", escape(url));
+        out.push_str("        <attvalues>\n");
+        for (id, (_, value)) in node_attrs(map, i, node).iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "          <attvalue for=\"{id}\" value=\"{}\"/>",
+                escape(value.as_str())
+            );
+        }
+        for (k, f) in features.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "          <attvalue for=\"{}\" value=\"{}\"/>",
+                NODE_ATTRS.len() + k,
+                map.features[i][f.dim]
+            );
+        }
+        out.push_str("        </attvalues>\n      </node>\n");
+    }
+    out.push_str("    </nodes>\n    <edges>\n");
+    for (id, (from, edge)) in edges(map).enumerate() {
+        let _ = writeln!(
+            out,
+            "      <edge id=\"e{id}\" source=\"n{from}\" target=\"n{}\" weight=\"{}\">",
+            edge.target_node, edge.weight
+        );
+        let _ = writeln!(
+            out,
+            "        <attvalues><attvalue for=\"0\" value=\"{:?}\"/></attvalues>",
+            edge.edge_type
+        );
+        out.push_str("      </edge>\n");
+    }
+    out.push_str("    </edges>\n  </graph>\n</gexf>\n");
+    out
+}
+
+/// Serialize as Graphviz DOT.
+pub fn to_dot(map: &SiteMap, features: &[ExportFeature]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "digraph {} {{", dot_quote(&map.header.domain));
+    out.push_str("  node [shape=box];\n");
+    for (i, node) in map.nodes.iter().enumerate() {
+        let mut attrs: Vec<String> = node_attrs(map, i, node)
+            .into_iter()
+            .map(|(name, value)| match name {
+                "url" => format!("label={}", dot_quote(&value)),
+                _ => format!("{name}={}", dot_quote(&value)),
+            })
+            .collect();
+        for f in features {
+            attrs.push(format!(
+                "{}={}",
+                dot_quote(&f.name),
+                dot_quote(&map.features[i][f.dim].to_string())
+            ));
+        }
+        let _ = writeln!(out, "  n{i} [{}];", attrs.join(", "));
+    }
+    for (from, edge) in edges(map) {
+        let _ = writeln!(
+            out,
+            "  n{from} -> n{} [edge_type=\"{:?}\", weight={}];",
+            edge.target_node, edge.edge_type, edge.weight
+        );
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Fixed attribute values for a node, in [`NODE_ATTRS`] order.
+fn node_attrs(map: &SiteMap, i: usize, node: &NodeRecord) -> Vec<(&'static str, String)> {
+    vec![
+        ("url", map.urls.get(i).cloned().unwrap_or_default()),
+        ("page_type", format!("{:?}", node.page_type)),
+        (
+            "confidence",
+            format!("{:.3}", node.confidence as f32 / 255.0),
+        ),
+        ("depth", node.depth.to_string()),
+        ("http_status", node.http_status.to_string()),
+    ]
+}
+
+/// All edges as (source index, edge), skipping dangling targets.
+fn edges(map: &SiteMap) -> impl Iterator<Item = (usize, &EdgeRecord)> {
+    (0..map.nodes.len()).flat_map(move |src| {
+        let start = map.edge_index.get(src).copied().unwrap_or(0) as usize;
+        let end = map
+            .edge_index
+            .get(src + 1)
+            .map_or(map.edges.len(), |&e| e as usize);
+        map.edges[start.min(end)..end]
+            .iter()
+            .filter(|e| (e.target_node as usize) < map.nodes.len())
+            .map(move |e| (src, e))
+    })
+}
+
+fn gexf_type(ty: &str) -> &str {
+    match ty {
+        "int" => "integer",
+        other => other,
+    }
+}
+
+/// Quote a DOT identifier.
+fn dot_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::builder::SiteMapBuilder;
+
+    fn sample() -> SiteMap {
+        let mut builder = SiteMapBuilder::new("shop.com");
+        let mut feats = [0.0f32; FEATURE_DIM];
+        let home = builder.add_node("https://shop.com/?a=1&b=\"2\"", PageType::Home, feats, 255);
+        feats[FEAT_PRICE] = 19.5;
+        let p = builder.add_node("https://shop.com/p/1", PageType::ProductDetail, feats, 200);
+        builder.add_edge(home, p, EdgeType::Navigation, 3, EdgeFlags::default());
+        builder.build()
+    }
+
+    fn price() -> Vec<ExportFeature> {
+        vec![ExportFeature::parse("price").unwrap()]
+    }
+
+    #[test]
+    fn test_graphml_export() {
+        let xml = to_graphml(&sample(), &price());
+        assert!(xml.contains("<key id=\"price\" for=\"node\""));
+        assert!(xml.contains("<data key=\"url\">https://shop.com/?a=1&amp;b=&quot;2&quot;</data>"));
+        assert!(xml.contains("<data key=\"price\">19.5</data>"));
+        assert!(xml.contains("<edge id=\"e0\" source=\"n0\" target=\"n1\">"));
+        assert!(xml.contains("<data key=\"edge_type\">Navigation</data>"));
+        // Well-formed XML
+        let mut reader = quick_xml::Reader::from_str(&xml);
+        loop {
+            match reader.read_event().unwrap() {
+                quick_xml::events::Event::Eof => break,
+                _ => continue,
+            }
+        }
+    }
+
+    #[test]
+    fn test_gexf_export() {
+        let xml = to_gexf(&sample(), &price());
+        assert!(xml.contains("<attribute id=\"5\" title=\"price\" type=\"double\"/>"));
+        assert!(xml.contains("<attvalue for=\"5\" value=\"19.5\"/>"));
+        assert!(xml.contains("source=\"n0\" target=\"n1\" weight=\"3\""));
+        assert!(xml.contains("<attribute id=\"3\" title=\"depth\" type=\"integer\"/>"));
+    }
+
+    #[test]
+    fn test_dot_export_and_options() {
+        let dot = to_dot(&sample(), &[ExportFeature::parse("48").unwrap()]);
+        assert!(dot.starts_with("digraph \"shop.com\" {"));
+        assert!(dot.contains("label=\"https://shop.com/?a=1&b=\\\"2\\\"\""));
+        assert!(dot.contains("\"f48\"=\"19.5\""));
+        assert!(dot.contains("n0 -> n1 [edge_type=\"Navigation\", weight=3];"));
+
+        assert_eq!(
+            ExportFormat::parse("GraphML").unwrap(),
+            ExportFormat::GraphMl
+        );
+        assert!(ExportFormat::parse("csv").is_err());
+        assert!(ExportFeature::parse("999").is_err());
+        assert_eq!(
+            ExportFeature::parse("free_shipping").unwrap().dim,
+            ExportFeature::parse("shipping_free").unwrap().dim
+        );
+        assert_eq!(
+            ExportFeature::parse("page_depth").unwrap().dim,
+            FEAT_PAGE_DEPTH
+        );
+    }
+}
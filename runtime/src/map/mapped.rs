@@ -0,0 +1,506 @@
+//! Zero-copy access to CTX maps through a memory mapping.
+//!
+//! Format v2 places every table at an 8-byte aligned offset that follows from
+//! the header counts alone, and every record has a fixed stride, so node `i`,
+//! its feature vector, its edges, and its URL can be located without reading
+//! anything else. [`MappedSiteMap`] maps the file and decodes records only
+//! when they are accessed: opening a 1M-node map costs one `mmap` and a
+//! 40-byte header read.
+//!
+//! ```text
+//! header (40 bytes) · domain
+//! nodes           node_count × 32
+//! edges           edge_count × 8
+//! edge_index      (node_count + 1) × u32
+//! features        node_count × 128 × f32
+//! actions         action_count × 8
+//! action_index    (node_count + 1) × u32
+//! clusters        node_count × u16, then cluster_count × 128 × f32
+//! url_offsets     (node_count + 1) × u32
+//! url_data        concatenated UTF-8
+//! crc32           u32 over all preceding bytes
+//! ```
+
+use crate::map::reader::{self, NodeSource};
+use crate::map::serializer::crc32;
+use crate::map::types::*;
+use anyhow::{bail, Context, Result};
+use std::ops::Deref;
+use std::path::Path;
+
+/// Size of the fixed v2 header, before the domain bytes.
+pub(crate) const HEADER_LEN: usize = 40;
+/// Bytes per node record (28 bytes of fields, padded).
+pub(crate) const NODE_STRIDE: usize = 32;
+/// Bytes per edge record.
+pub(crate) const EDGE_STRIDE: usize = 8;
+/// Bytes per action record.
+pub(crate) const ACTION_STRIDE: usize = 8;
+/// Bytes per feature vector.
+pub(crate) const FEATURE_STRIDE: usize = FEATURE_DIM * 4;
+
+/// Byte offsets of each v2 table, derived from the header counts.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Layout {
+    pub nodes: usize,
+    pub edges: usize,
+    pub edge_index: usize,
+    pub features: usize,
+    pub actions: usize,
+    pub action_index: usize,
+    pub cluster_assignments: usize,
+    pub cluster_centroids: usize,
+    pub url_offsets: usize,
+    pub url_data: usize,
+    /// End of the payload; the CRC32 follows.
+    pub end: usize,
+}
+
+impl Layout {
+    pub(crate) fn new(
+        domain_len: usize,
+        node_count: usize,
+        edge_count: usize,
+        action_count: usize,
+        cluster_count: usize,
+        url_data_len: usize,
+    ) -> Self {
+        let align = |n: usize| n.next_multiple_of(8);
+        let nodes = align(HEADER_LEN + domain_len);
+        let edges = align(nodes + node_count * NODE_STRIDE);
+        let edge_index = align(edges + edge_count * EDGE_STRIDE);
+        let features = align(edge_index + (node_count + 1) * 4);
+        let actions = align(features + node_count * FEATURE_STRIDE);
+        let action_index = align(actions + action_count * ACTION_STRIDE);
+        let cluster_assignments = align(action_index + (node_count + 1) * 4);
+        let cluster_centroids = align(cluster_assignments + node_count * 2);
+        let url_offsets = align(cluster_centroids + cluster_count * FEATURE_STRIDE);
+        let url_data = align(url_offsets + (node_count + 1) * 4);
+        let end = align(url_data + url_data_len);
+        Self {
+            nodes,
+            edges,
+            edge_index,
+            features,
+            actions,
+            action_index,
+            cluster_assignments,
+            cluster_centroids,
+            url_offsets,
+            url_data,
+            end,
+        }
+    }
+}
+
+/// Bytes behind a [`MappedSiteMap`].
+enum Backing {
+    Mapped(memmap2::Mmap),
+    Owned(Vec<u8>),
+}
+
+impl Deref for Backing {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Backing::Mapped(m) => m,
+            Backing::Owned(v) => v,
+        }
+    }
+}
+
+/// A read-only SiteMap view that decodes records on access.
+pub struct MappedSiteMap {
+    bytes: Backing,
+    header: MapHeader,
+    layout: Layout,
+    action_count: usize,
+}
+
+impl MappedSiteMap {
+    /// Map a CTX file. v2 files are used in place; older versions are decoded
+    /// and upgraded in memory.
+    ///
+    /// The checksum is not verified here, since that would touch every page
+    /// of the file; call [`verify`](Self::verify) when integrity matters.
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("failed to open map: {}", path.display()))?;
+        // SAFETY: Cortex never modifies map files in place; the cache replaces
+        // them by rename, so the mapping always sees a complete file.
+        let mmap = unsafe { memmap2::Mmap::map(&file) }
+            .with_context(|| format!("failed to mmap map: {}", path.display()))?;
+        if version_of(&mmap) == Some(FORMAT_VERSION) {
+            Self::from_backing(Backing::Mapped(mmap))
+        } else {
+            Self::from_bytes(mmap.to_vec())
+        }
+    }
+
+    /// Build a view over serialized map bytes of any supported version.
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self> {
+        if version_of(&data) == Some(FORMAT_VERSION) {
+            Self::from_backing(Backing::Owned(data))
+        } else {
+            let map = SiteMap::deserialize(&data)?;
+            Self::from_backing(Backing::Owned(map.serialize()))
+        }
+    }
+
+    fn from_backing(bytes: Backing) -> Result<Self> {
+        if bytes.len() < HEADER_LEN + 4 {
+            bail!("map file too small: {} bytes", bytes.len());
+        }
+        let magic = read_u32(&bytes, 0);
+        if magic != SITEMAP_MAGIC {
+            bail!(
+                "invalid magic bytes: expected 0x{:08X}, got 0x{:08X}",
+                SITEMAP_MAGIC,
+                magic
+            );
+        }
+        let domain_len = read_u16(&bytes, 6) as usize;
+        let node_count = read_u32(&bytes, 16) as usize;
+        let edge_count = read_u32(&bytes, 20) as usize;
+        let action_count = read_u32(&bytes, 24) as usize;
+        let url_data_len = read_u32(&bytes, 28) as usize;
+        let cluster_count = read_u16(&bytes, 32) as usize;
+        let layout = Layout::new(
+            domain_len,
+            node_count,
+            edge_count,
+            action_count,
+            cluster_count,
+            url_data_len,
+        );
+        if layout.end + 4 != bytes.len() {
+            bail!(
+                "map file size mismatch: header implies {} bytes, file has {}",
+                layout.end + 4,
+                bytes.len()
+            );
+        }
+        let domain = std::str::from_utf8(&bytes[HEADER_LEN..HEADER_LEN + domain_len])
+            .context("domain not valid utf8")?
+            .to_string();
+
+        let header = MapHeader {
+            magic,
+            format_version: FORMAT_VERSION,
+            domain,
+            mapped_at: read_u64(&bytes, 8),
+            node_count: node_count as u32,
+            edge_count: edge_count as u32,
+            cluster_count: cluster_count as u16,
+            flags: read_u16(&bytes, 34),
+        };
+        Ok(Self {
+            bytes,
+            header,
+            layout,
+            action_count,
+        })
+    }
+
+    /// Verify the trailing CRC32 checksum.
+    pub fn verify(&self) -> Result<()> {
+        let payload = &self.bytes[..self.layout.end];
+        let stored = read_u32(&self.bytes, self.layout.end);
+        let computed = crc32(payload);
+        if stored != computed {
+            bail!(
+                "map file integrity check failed: checksum mismatch \
+                 (stored 0x{stored:08X}, computed 0x{computed:08X}). File may be corrupted."
+            );
+        }
+        Ok(())
+    }
+
+    /// The map header.
+    pub fn header(&self) -> &MapHeader {
+        &self.header
+    }
+
+    /// Number of nodes.
+    pub fn node_count(&self) -> usize {
+        self.header.node_count as usize
+    }
+
+    /// Number of edges.
+    pub fn edge_count(&self) -> usize {
+        self.header.edge_count as usize
+    }
+
+    /// Decode one node record.
+    pub fn node(&self, node: u32) -> NodeRecord {
+        let o = self.layout.nodes + node as usize * NODE_STRIDE;
+        let b = &self.bytes;
+        NodeRecord {
+            page_type: PageType::from_u8(b[o]),
+            confidence: b[o + 1],
+            freshness: b[o + 2],
+            flags: NodeFlags(b[o + 3]),
+            content_hash: read_u32(b, o + 4),
+            rendered_at: read_u32(b, o + 8),
+            http_status: read_u16(b, o + 12),
+            depth: read_u16(b, o + 14),
+            inbound_count: read_u16(b, o + 16),
+            outbound_count: read_u16(b, o + 18),
+            feature_norm: read_f32(b, o + 20),
+            reserved: read_u32(b, o + 24),
+        }
+    }
+
+    /// One feature dimension of a node.
+    pub fn feature(&self, node: u32, dim: usize) -> f32 {
+        read_f32(
+            &self.bytes,
+            self.layout.features + node as usize * FEATURE_STRIDE + dim * 4,
+        )
+    }
+
+    /// Decode the full feature vector of a node.
+    pub fn node_features(&self, node: u32) -> [f32; FEATURE_DIM] {
+        std::array::from_fn(|dim| self.feature(node, dim))
+    }
+
+    /// The URL of a node, borrowed from the string table.
+    pub fn node_url(&self, node: u32) -> &str {
+        let (start, end) = self.csr_range(self.layout.url_offsets, node as usize);
+        let data = &self.bytes[self.layout.url_data..self.layout.end];
+        data.get(start..end)
+            .and_then(|s| std::str::from_utf8(s).ok())
+            .unwrap_or("")
+    }
+
+    /// Index of the node with `url`, scanning the string table.
+    pub fn find_url(&self, url: &str) -> Option<u32> {
+        (0..self.header.node_count).find(|&i| self.node_url(i) == url)
+    }
+
+    /// Edges leaving a node.
+    pub fn edges_from(&self, node: u32) -> impl Iterator<Item = EdgeRecord> + '_ {
+        let (start, end) = self.csr_range(self.layout.edge_index, node as usize);
+        let end = end.min(self.edge_count());
+        (start..end).map(move |i| self.edge(i))
+    }
+
+    /// Actions available on a node.
+    pub fn actions_for(&self, node: u32) -> impl Iterator<Item = ActionRecord> + '_ {
+        let (start, end) = self.csr_range(self.layout.action_index, node as usize);
+        let end = end.min(self.action_count);
+        (start..end).map(move |i| self.action(i))
+    }
+
+    /// Filter nodes by criteria, like [`SiteMap::filter`].
+    pub fn filter(&self, query: &NodeQuery) -> Vec<NodeMatch> {
+        reader::filter_nodes(self, query)
+    }
+
+    /// Find k nearest nodes by cosine similarity, like [`SiteMap::nearest`].
+    pub fn nearest(&self, target: &[f32; FEATURE_DIM], k: usize) -> Vec<NodeMatch> {
+        reader::nearest_nodes(self, target, k)
+    }
+
+    /// Decode the whole map into an owned [`SiteMap`].
+    pub fn to_site_map(&self) -> SiteMap {
+        let n = self.node_count();
+        let index = |base: usize| {
+            (0..=n)
+                .map(|i| read_u32(&self.bytes, base + i * 4))
+                .collect()
+        };
+        SiteMap {
+            header: self.header.clone(),
+            nodes: (0..n as u32).map(|i| self.node(i)).collect(),
+            edges: (0..self.edge_count()).map(|i| self.edge(i)).collect(),
+            edge_index: index(self.layout.edge_index),
+            features: (0..n as u32).map(|i| self.node_features(i)).collect(),
+            actions: (0..self.action_count).map(|i| self.action(i)).collect(),
+            action_index: index(self.layout.action_index),
+            cluster_assignments: (0..n)
+                .map(|i| read_u16(&self.bytes, self.layout.cluster_assignments + i * 2))
+                .collect(),
+            cluster_centroids: (0..self.header.cluster_count as usize)
+                .map(|c| {
+                    let base = self.layout.cluster_centroids + c * FEATURE_STRIDE;
+                    std::array::from_fn(|dim| read_f32(&self.bytes, base + dim * 4))
+                })
+                .collect(),
+            urls: (0..n as u32)
+                .map(|i| self.node_url(i).to_string())
+                .collect(),
+            lineage: Default::default(),
+            text: Default::default(),
+            embeddings: Default::default(),
+            compliance: None,
+            blocks: None,
+        }
+    }
+
+    fn edge(&self, i: usize) -> EdgeRecord {
+        let o = self.layout.edges + i * EDGE_STRIDE;
+        EdgeRecord {
+            target_node: read_u32(&self.bytes, o),
+            edge_type: EdgeType::from_u8(self.bytes[o + 4]),
+            weight: self.bytes[o + 5],
+            flags: EdgeFlags(self.bytes[o + 6]),
+            reserved: self.bytes[o + 7],
+        }
+    }
+
+    fn action(&self, i: usize) -> ActionRecord {
+        let o = self.layout.actions + i * ACTION_STRIDE;
+        ActionRecord {
+            opcode: OpCode::from_u16(read_u16(&self.bytes, o)),
+            cost_hint: self.bytes[o + 2],
+            risk: self.bytes[o + 3],
+            target_node: read_u32(&self.bytes, o + 4) as i32,
+            http_executable: false,
+        }
+    }
+
+    /// `[index[i], index[i + 1])` from a CSR offset table, empty if out of range.
+    fn csr_range(&self, base: usize, i: usize) -> (usize, usize) {
+        if i >= self.node_count() {
+            return (0, 0);
+        }
+        let start = read_u32(&self.bytes, base + i * 4) as usize;
+        let end = read_u32(&self.bytes, base + (i + 1) * 4) as usize;
+        (start, end.max(start))
+    }
+}
+
+impl NodeSource for MappedSiteMap {
+    fn len(&self) -> usize {
+        self.node_count()
+    }
+    fn page_type(&self, i: usize) -> PageType {
+        PageType::from_u8(self.bytes[self.layout.nodes + i * NODE_STRIDE])
+    }
+    fn confidence(&self, i: usize) -> u8 {
+        self.bytes[self.layout.nodes + i * NODE_STRIDE + 1]
+    }
+    fn flags(&self, i: usize) -> NodeFlags {
+        NodeFlags(self.bytes[self.layout.nodes + i * NODE_STRIDE + 3])
+    }
+    fn feature_norm(&self, i: usize) -> f32 {
+        read_f32(&self.bytes, self.layout.nodes + i * NODE_STRIDE + 20)
+    }
+    fn feature(&self, i: usize, dim: usize) -> f32 {
+        MappedSiteMap::feature(self, i as u32, dim)
+    }
+    fn url(&self, i: usize) -> &str {
+        self.node_url(i as u32)
+    }
+}
+
+/// Format version of serialized map bytes, if they carry a header.
+pub(crate) fn version_of(data: &[u8]) -> Option<u16> {
+    (data.len() >= 6).then(|| read_u16(data, 4))
+}
+
+fn read_u16(b: &[u8], o: usize) -> u16 {
+    u16::from_le_bytes([b[o], b[o + 1]])
+}
+
+fn read_u32(b: &[u8], o: usize) -> u32 {
+    u32::from_le_bytes([b[o], b[o + 1], b[o + 2], b[o + 3]])
+}
+
+fn read_u64(b: &[u8], o: usize) -> u64 {
+    u64::from_le_bytes(b[o..o + 8].try_into().expect("8-byte slice"))
+}
+
+fn read_f32(b: &[u8], o: usize) -> f32 {
+    f32::from_bits(read_u32(b, o))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::builder::SiteMapBuilder;
+
+    fn sample_map() -> SiteMap {
+        let mut builder = SiteMapBuilder::new("shop.com");
+        for i in 0..20 {
+            let mut feats = [0.0f32; FEATURE_DIM];
+            feats[FEAT_PRICE] = 10.0 * i as f32;
+            let page_type = if i == 0 {
+                PageType::Home
+            } else {
+                PageType::ProductDetail
+            };
+            builder.add_node(&format!("https://shop.com/p/{i}"), page_type, feats, 200);
+        }
+        for i in 1..20 {
+            builder.add_edge(0, i, EdgeType::Navigation, 1, EdgeFlags::default());
+            builder.add_action(i, OpCode::new(0x02, 0x00), -1, 0, 0);
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn test_mapped_matches_owned() {
+        let map = sample_map();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shop.com.ctx");
+        std::fs::write(&path, map.serialize()).unwrap();
+
+        let mapped = MappedSiteMap::open(&path).unwrap();
+        mapped.verify().unwrap();
+        assert_eq!(mapped.header().domain, "shop.com");
+        assert_eq!(mapped.node_count(), 20);
+        assert_eq!(mapped.node_url(7), "https://shop.com/p/7");
+        assert_eq!(mapped.find_url("https://shop.com/p/7"), Some(7));
+        assert_eq!(mapped.feature(7, FEAT_PRICE), 70.0);
+        assert_eq!(mapped.edges_from(0).count(), 19);
+        assert_eq!(mapped.actions_for(3).count(), 1);
+
+        let query = NodeQuery {
+            page_types: Some(vec![PageType::ProductDetail]),
+            feature_ranges: vec![FeatureRange {
+                dimension: FEAT_PRICE,
+                min: None,
+                max: Some(50.0),
+            }],
+            ..Default::default()
+        };
+        let urls = |m: Vec<NodeMatch>| m.into_iter().map(|m| m.url).collect::<Vec<_>>();
+        assert_eq!(urls(mapped.filter(&query)), urls(map.filter(&query)));
+        assert_eq!(urls(mapped.filter(&query)).len(), 5);
+
+        let decoded = mapped.to_site_map();
+        assert_eq!(decoded.serialize(), map.serialize());
+    }
+
+    #[test]
+    fn test_tables_are_aligned() {
+        let layout = Layout::new(7, 3, 5, 2, 1, 61);
+        for offset in [
+            layout.nodes,
+            layout.edges,
+            layout.features,
+            layout.actions,
+            layout.cluster_centroids,
+            layout.url_data,
+            layout.end,
+        ] {
+            assert_eq!(offset % 8, 0);
+        }
+    }
+
+    #[test]
+    fn test_corruption_detected() {
+        let mut data = sample_map().serialize();
+        let mid = data.len() / 2;
+        data[mid] ^= 0xFF;
+        let mapped = MappedSiteMap::from_bytes(data.clone()).unwrap();
+        assert!(mapped.verify().is_err());
+        assert!(SiteMap::deserialize(&data).is_err());
+
+        data.truncate(data.len() - 8);
+        assert!(MappedSiteMap::from_bytes(data).is_err());
+    }
+}
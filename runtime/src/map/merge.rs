@@ -0,0 +1,338 @@
+//! Combine several maps of one site into a single map.
+//!
+//! Used for aliased domains (`example.com`, `www.example.com`,
+//! `m.example.com`) and for stitching incremental partial maps together.
+//! URLs are rewritten to a canonical form first, so the same page reached
+//! through different hosts becomes one node.
+
+use crate::map::builder::SiteMapBuilder;
+use crate::map::types::*;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Host prefixes treated as aliases of the canonical domain by default.
+const DEFAULT_ALIAS_PREFIXES: &[&str] = &["www.", "m.", "mobile.", "amp."];
+
+/// Which hosts fold into the canonical domain.
+#[derive(Debug, Clone)]
+pub struct DomainAliases {
+    canonical: String,
+    aliases: HashSet<String>,
+}
+
+impl DomainAliases {
+    /// Aliases for `canonical`: the domain itself plus its `www.`, `m.`,
+    /// `mobile.` and `amp.` hosts.
+    pub fn new(canonical: &str) -> Self {
+        let canonical = canonical.to_ascii_lowercase();
+        let aliases = DEFAULT_ALIAS_PREFIXES
+            .iter()
+            .map(|prefix| format!("{prefix}{canonical}"))
+            .collect();
+        Self { canonical, aliases }
+    }
+
+    /// `host` without a default alias prefix (`www.shop.com` → `shop.com`).
+    pub fn base_domain(host: &str) -> &str {
+        DEFAULT_ALIAS_PREFIXES
+            .iter()
+            .find_map(|prefix| host.strip_prefix(prefix))
+            .unwrap_or(host)
+    }
+
+    /// Also fold `host` into the canonical domain.
+    pub fn with_alias(mut self, host: &str) -> Self {
+        self.aliases.insert(host.to_ascii_lowercase());
+        self
+    }
+
+    /// The canonical domain.
+    pub fn canonical(&self) -> &str {
+        &self.canonical
+    }
+
+    /// Whether `host` is the canonical domain or one of its aliases.
+    pub fn covers(&self, host: &str) -> bool {
+        let host = host.to_ascii_lowercase();
+        host == self.canonical || self.aliases.contains(&host)
+    }
+
+    /// Canonical form of `url`: aliased hosts become the canonical domain
+    /// over https, the fragment is dropped, and so is a trailing slash on
+    /// any path but the root. Unparseable URLs are returned unchanged.
+    pub fn canonical_url(&self, url: &str) -> String {
+        let Ok(mut parsed) = url::Url::parse(url) else {
+            return url.to_string();
+        };
+        parsed.set_fragment(None);
+        if parsed.host_str().is_some_and(|h| self.covers(h)) {
+            let _ = parsed.set_host(Some(&self.canonical));
+            let _ = parsed.set_scheme("https");
+            let _ = parsed.set_port(None);
+        }
+        let path = parsed.path();
+        if path.len() > 1 && path.ends_with('/') {
+            let trimmed = path.trim_end_matches('/').to_string();
+            parsed.set_path(&trimmed);
+        }
+        parsed.to_string()
+    }
+}
+
+/// Counters from a merge.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MergeStats {
+    /// Number of input maps.
+    pub maps: usize,
+    /// Nodes across all inputs.
+    pub nodes_in: usize,
+    /// Nodes in the merged map.
+    pub nodes_out: usize,
+    /// Input nodes folded into another node with the same canonical URL.
+    pub nodes_deduped: usize,
+    /// Edges in the merged map.
+    pub edges_out: usize,
+    /// Duplicate edges dropped.
+    pub edges_deduped: usize,
+}
+
+impl SiteMap {
+    /// Merge `other` into this map, treating `www.`/`m.` hosts of this map's
+    /// domain as the same site.
+    pub fn merge(&self, other: &SiteMap) -> SiteMap {
+        let aliases = DomainAliases::new(&self.header.domain);
+        SiteMap::merge_all(&[self, other], &aliases).0
+    }
+
+    /// Merge any number of maps under `aliases.canonical()`.
+    ///
+    /// Nodes are deduplicated by canonical URL. When several inputs have the
+    /// same page, the fresher record wins (then the more confident one, then
+    /// the more recently mapped input); feature dimensions the winner left at
+    /// zero are filled from the others, flags are unioned, and an `Unknown`
+    /// page type yields to a classified one. Edges and actions are unioned.
+    pub fn merge_all(maps: &[&SiteMap], aliases: &DomainAliases) -> (SiteMap, MergeStats) {
+        let mut stats = MergeStats {
+            maps: maps.len(),
+            ..Default::default()
+        };
+
+        // Group source nodes by canonical URL, in first-seen order.
+        let mut urls: Vec<String> = Vec::new();
+        let mut index: HashMap<String, u32> = HashMap::new();
+        let mut sources: Vec<Vec<(usize, usize)>> = Vec::new();
+        let mut remaps: Vec<Vec<u32>> = Vec::with_capacity(maps.len());
+        for (m, map) in maps.iter().enumerate() {
+            stats.nodes_in += map.urls.len();
+            let mut remap = Vec::with_capacity(map.urls.len());
+            for (i, url) in map.urls.iter().enumerate() {
+                let canonical = aliases.canonical_url(url);
+                let idx = *index.entry(canonical.clone()).or_insert_with(|| {
+                    urls.push(canonical);
+                    sources.push(Vec::new());
+                    (urls.len() - 1) as u32
+                });
+                sources[idx as usize].push((m, i));
+                remap.push(idx);
+            }
+            remaps.push(remap);
+        }
+        stats.nodes_deduped = stats.nodes_in - urls.len();
+
+        let mut builder = SiteMapBuilder::new(aliases.canonical());
+        let mut records = Vec::with_capacity(urls.len());
+        for (url, candidates) in urls.iter().zip(&sources) {
+            let (record, features) = merge_candidates(maps, candidates);
+            builder.add_node(url, record.page_type, features, record.confidence);
+            records.push(record);
+        }
+
+        let mut seen_edges: HashSet<(u32, u32, EdgeType)> = HashSet::new();
+        let mut seen_actions: HashSet<(u32, u16, i32)> = HashSet::new();
+        for (map, remap) in maps.iter().zip(&remaps) {
+            for (src, &from) in remap.iter().enumerate() {
+                for edge in map.edges_from(src as u32) {
+                    let Some(&to) = remap.get(edge.target_node as usize) else {
+                        continue;
+                    };
+                    if from == to || !seen_edges.insert((from, to, edge.edge_type)) {
+                        stats.edges_deduped += 1;
+                        continue;
+                    }
+                    builder.add_edge(from, to, edge.edge_type, edge.weight, edge.flags);
+                }
+                for action in map.actions_for(src as u32) {
+                    let target = match usize::try_from(action.target_node) {
+                        Ok(t) => remap.get(t).map_or(-2, |&m| m as i32),
+                        Err(_) => action.target_node,
+                    };
+                    if !seen_actions.insert((from, action.opcode.as_u16(), target)) {
+                        continue;
+                    }
+                    if action.http_executable {
+                        builder.add_action_http(
+                            from,
+                            action.opcode,
+                            target,
+                            action.cost_hint,
+                            action.risk,
+                        );
+                    } else {
+                        builder.add_action(
+                            from,
+                            action.opcode,
+                            target,
+                            action.cost_hint,
+                            action.risk,
+                        );
+                    }
+                }
+            }
+        }
+
+        let has_sitemap = maps.iter().any(|m| m.header.has_sitemap());
+        builder.set_has_sitemap(has_sitemap);
+        let mut merged = builder.build();
+        merged.header.mapped_at = maps.iter().map(|m| m.header.mapped_at).max().unwrap_or(0);
+
+        // The builder recomputes degree counts and norms; keep the rest.
+        for (node, record) in merged.nodes.iter_mut().zip(records) {
+            *node = NodeRecord {
+                inbound_count: node.inbound_count,
+                outbound_count: node.outbound_count,
+                feature_norm: node.feature_norm,
+                ..record
+            };
+        }
+        stats.nodes_out = merged.nodes.len();
+        stats.edges_out = merged.edges.len();
+        (merged, stats)
+    }
+}
+
+/// Combine every input copy of one page into a single record.
+fn merge_candidates(
+    maps: &[&SiteMap],
+    candidates: &[(usize, usize)],
+) -> (NodeRecord, [f32; FEATURE_DIM]) {
+    let &(wm, wi) = candidates
+        .iter()
+        .max_by_key(|&&(m, i)| {
+            let node = &maps[m].nodes[i];
+            (node.freshness, node.confidence, maps[m].header.mapped_at)
+        })
+        .expect("every canonical URL has at least one source");
+
+    let mut record = maps[wm].nodes[wi].clone();
+    let mut features = maps[wm].features[wi];
+    for &(m, i) in candidates {
+        let (node, other) = (&maps[m].nodes[i], &maps[m].features[i]);
+        record.flags.0 |= node.flags.0;
+        if record.page_type == PageType::Unknown {
+            record.page_type = node.page_type;
+        }
+        for (f, &o) in features.iter_mut().zip(other) {
+            if *f == 0.0 {
+                *f = o;
+            }
+        }
+    }
+    (record, features)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(domain: &str, urls: &[&str], freshness: u8) -> SiteMap {
+        let mut builder = SiteMapBuilder::new(domain);
+        for (i, url) in urls.iter().enumerate() {
+            let mut feats = [0.0f32; FEATURE_DIM];
+            feats[FEAT_PRICE] = freshness as f32;
+            feats[FEAT_RATING] = if i == 0 { 4.5 } else { 0.0 };
+            builder.add_node(url, PageType::ProductDetail, feats, 200);
+        }
+        for i in 1..urls.len() as u32 {
+            builder.add_edge(0, i, EdgeType::ContentLink, 1, EdgeFlags::default());
+        }
+        let mut map = builder.build();
+        for node in &mut map.nodes {
+            node.freshness = freshness;
+        }
+        map
+    }
+
+    #[test]
+    fn test_canonical_url() {
+        let aliases = DomainAliases::new("example.com").with_alias("shop.example.net");
+        assert_eq!(
+            aliases.canonical_url("http://www.example.com/a/?x=1#top"),
+            "https://example.com/a?x=1"
+        );
+        assert_eq!(
+            aliases.canonical_url("https://m.example.com/"),
+            "https://example.com/"
+        );
+        assert_eq!(
+            aliases.canonical_url("https://shop.example.net/p"),
+            "https://example.com/p"
+        );
+        assert_eq!(
+            aliases.canonical_url("https://cdn.example.com/p"),
+            "https://cdn.example.com/p"
+        );
+    }
+
+    #[test]
+    fn test_merge_aliased_domains() {
+        let desktop = map(
+            "www.example.com",
+            &["https://www.example.com/", "https://www.example.com/a"],
+            100,
+        );
+        let mobile = map(
+            "m.example.com",
+            &["https://m.example.com/", "https://m.example.com/b"],
+            200,
+        );
+        let aliases = DomainAliases::new("example.com");
+        let (merged, stats) = SiteMap::merge_all(&[&desktop, &mobile], &aliases);
+
+        assert_eq!(merged.header.domain, "example.com");
+        assert_eq!(
+            merged.urls,
+            vec![
+                "https://example.com/",
+                "https://example.com/a",
+                "https://example.com/b"
+            ]
+        );
+        assert_eq!(stats.nodes_deduped, 1);
+        // The fresher mobile copy of the home page wins.
+        assert_eq!(merged.features[0][FEAT_PRICE], 200.0);
+        assert_eq!(merged.nodes[0].freshness, 200);
+        assert_eq!(merged.edges_from(0).len(), 2);
+        assert_eq!(merged.nodes[0].outbound_count, 2);
+    }
+
+    #[test]
+    fn test_merge_partial_maps() {
+        let first = map(
+            "example.com",
+            &["https://example.com/", "https://example.com/a"],
+            50,
+        );
+        let second = map(
+            "example.com",
+            &["https://example.com/a", "https://example.com/c"],
+            50,
+        );
+        let merged = first.merge(&second);
+        assert_eq!(merged.urls.len(), 3);
+        // a → c from the second map survives the remap.
+        let a = merged.urls.iter().position(|u| u.ends_with("/a")).unwrap() as u32;
+        assert_eq!(merged.edges_from(a).len(), 1);
+        // The rating only the second map set for /a fills the gap.
+        assert_eq!(merged.features[a as usize][FEAT_RATING], 4.5);
+    }
+}
@@ -6,9 +6,16 @@
 
 pub mod builder;
 pub mod deserializer;
+pub mod embeddings;
+pub mod export;
+pub mod mapped;
+pub mod merge;
 pub mod reader;
 pub mod serializer;
+pub mod slice;
+pub mod text;
 pub mod types;
 
 pub use builder::SiteMapBuilder;
+pub use mapped::MappedSiteMap;
 pub use types::*;
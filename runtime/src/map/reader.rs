@@ -2,135 +2,189 @@
 
 use crate::map::types::*;
 
-impl SiteMap {
-    /// Filter nodes by criteria.
-    pub fn filter(&self, query: &NodeQuery) -> Vec<NodeMatch> {
-        let mut results = Vec::new();
+/// Per-node reads shared by [`SiteMap`] and [`MappedSiteMap`](super::mapped::MappedSiteMap),
+/// so filtering and similarity search have one implementation.
+pub(crate) trait NodeSource {
+    fn len(&self) -> usize;
+    fn page_type(&self, i: usize) -> PageType;
+    fn confidence(&self, i: usize) -> u8;
+    fn flags(&self, i: usize) -> NodeFlags;
+    fn feature_norm(&self, i: usize) -> f32;
+    fn feature(&self, i: usize, dim: usize) -> f32;
+    fn url(&self, i: usize) -> &str;
+}
 
-        for (i, node) in self.nodes.iter().enumerate() {
-            // Filter by page type
-            if let Some(ref types) = query.page_types {
-                if !types.contains(&node.page_type) {
-                    continue;
-                }
-            }
+impl NodeSource for SiteMap {
+    fn len(&self) -> usize {
+        self.nodes.len()
+    }
+    fn page_type(&self, i: usize) -> PageType {
+        self.nodes[i].page_type
+    }
+    fn confidence(&self, i: usize) -> u8 {
+        self.nodes[i].confidence
+    }
+    fn flags(&self, i: usize) -> NodeFlags {
+        self.nodes[i].flags
+    }
+    fn feature_norm(&self, i: usize) -> f32 {
+        self.nodes[i].feature_norm
+    }
+    fn feature(&self, i: usize, dim: usize) -> f32 {
+        self.features[i][dim]
+    }
+    fn url(&self, i: usize) -> &str {
+        &self.urls[i]
+    }
+}
 
-            // Filter by feature ranges
-            let features = &self.features[i];
-            let mut skip = false;
-            for range in &query.feature_ranges {
-                if range.dimension >= FEATURE_DIM {
-                    continue;
-                }
-                let val = features[range.dimension];
-                if let Some(min) = range.min {
-                    if val < min {
-                        skip = true;
-                        break;
-                    }
-                }
-                if let Some(max) = range.max {
-                    if val > max {
-                        skip = true;
-                        break;
-                    }
-                }
-            }
-            if skip {
+/// Filter nodes by criteria.
+pub(crate) fn filter_nodes<S: NodeSource>(source: &S, query: &NodeQuery) -> Vec<NodeMatch> {
+    let mut results = Vec::new();
+
+    for i in 0..source.len() {
+        // Filter by page type; error pages only when asked for by type
+        let page_type = source.page_type(i);
+        match query.page_types {
+            Some(ref types) if !types.contains(&page_type) => continue,
+            None if page_type == PageType::ErrorPage => continue,
+            _ => {}
+        }
+
+        // Filter by feature ranges
+        let mut skip = false;
+        for range in &query.feature_ranges {
+            if range.dimension >= FEATURE_DIM {
                 continue;
             }
-
-            // Filter by required flags
-            if let Some(ref req) = query.require_flags {
-                if node.flags.0 & req.0 != req.0 {
-                    continue;
+            let val = source.feature(i, range.dimension);
+            if let Some(min) = range.min {
+                if val < min {
+                    skip = true;
+                    break;
                 }
             }
-
-            // Filter by excluded flags
-            if let Some(ref exc) = query.exclude_flags {
-                if node.flags.0 & exc.0 != 0 {
-                    continue;
+            if let Some(max) = range.max {
+                if val > max {
+                    skip = true;
+                    break;
                 }
             }
+        }
+        if skip {
+            continue;
+        }
 
-            // Collect key features for the result
-            let mut key_features = Vec::new();
-            for range in &query.feature_ranges {
-                if range.dimension < FEATURE_DIM {
-                    key_features.push((range.dimension, features[range.dimension]));
-                }
+        // Filter by required flags
+        let flags = source.flags(i);
+        if let Some(ref req) = query.require_flags {
+            if flags.0 & req.0 != req.0 {
+                continue;
             }
-
-            results.push(NodeMatch {
-                index: i as u32,
-                url: self.urls[i].clone(),
-                page_type: node.page_type,
-                confidence: node.confidence as f32 / 255.0,
-                features: key_features,
-                similarity: None,
-            });
         }
 
-        // Sort
-        if let Some(sort_dim) = query.sort_by_feature {
-            if sort_dim < FEATURE_DIM {
-                results.sort_by(|a, b| {
-                    let va = self.features[a.index as usize][sort_dim];
-                    let vb = self.features[b.index as usize][sort_dim];
-                    if query.sort_ascending {
-                        va.partial_cmp(&vb).unwrap_or(std::cmp::Ordering::Equal)
-                    } else {
-                        vb.partial_cmp(&va).unwrap_or(std::cmp::Ordering::Equal)
-                    }
-                });
+        // Filter by excluded flags
+        if let Some(ref exc) = query.exclude_flags {
+            if flags.0 & exc.0 != 0 {
+                continue;
             }
         }
 
-        // Limit
-        if query.limit > 0 && results.len() > query.limit {
-            results.truncate(query.limit);
+        // Collect key features for the result
+        let mut key_features = Vec::new();
+        for range in &query.feature_ranges {
+            if range.dimension < FEATURE_DIM {
+                key_features.push((range.dimension, source.feature(i, range.dimension)));
+            }
         }
 
-        results
+        results.push(NodeMatch {
+            index: i as u32,
+            url: source.url(i).to_string(),
+            page_type,
+            confidence: source.confidence(i) as f32 / 255.0,
+            features: key_features,
+            similarity: None,
+            text_score: None,
+        });
     }
 
-    /// Find k nearest nodes by cosine similarity to target vector.
-    pub fn nearest(&self, target: &[f32; FEATURE_DIM], k: usize) -> Vec<NodeMatch> {
-        let target_norm: f32 = target.iter().map(|f| f * f).sum::<f32>().sqrt();
-        if target_norm == 0.0 {
-            return Vec::new();
+    // Sort
+    if let Some(sort_dim) = query.sort_by_feature {
+        if sort_dim < FEATURE_DIM {
+            results.sort_by(|a, b| {
+                let va = source.feature(a.index as usize, sort_dim);
+                let vb = source.feature(b.index as usize, sort_dim);
+                if query.sort_ascending {
+                    va.partial_cmp(&vb).unwrap_or(std::cmp::Ordering::Equal)
+                } else {
+                    vb.partial_cmp(&va).unwrap_or(std::cmp::Ordering::Equal)
+                }
+            });
         }
+    }
 
-        let mut scored: Vec<(u32, f32)> = self
-            .features
-            .iter()
-            .enumerate()
-            .map(|(i, feat)| {
-                let node_norm = self.nodes[i].feature_norm;
-                if node_norm == 0.0 {
-                    return (i as u32, -1.0);
-                }
-                let dot: f32 = feat.iter().zip(target.iter()).map(|(a, b)| a * b).sum();
-                let similarity = dot / (node_norm * target_norm);
-                (i as u32, similarity)
-            })
-            .collect();
+    // Limit
+    if query.limit > 0 && results.len() > query.limit {
+        results.truncate(query.limit);
+    }
+
+    results
+}
 
-        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-
-        scored
-            .into_iter()
-            .take(k)
-            .map(|(idx, sim)| NodeMatch {
-                index: idx,
-                url: self.urls[idx as usize].clone(),
-                page_type: self.nodes[idx as usize].page_type,
-                confidence: self.nodes[idx as usize].confidence as f32 / 255.0,
-                features: Vec::new(),
-                similarity: Some(sim),
-            })
-            .collect()
+/// Find k nearest nodes by cosine similarity to target vector.
+pub(crate) fn nearest_nodes<S: NodeSource>(
+    source: &S,
+    target: &[f32; FEATURE_DIM],
+    k: usize,
+) -> Vec<NodeMatch> {
+    let target_norm: f32 = target.iter().map(|f| f * f).sum::<f32>().sqrt();
+    if target_norm == 0.0 {
+        return Vec::new();
+    }
+
+    let mut scored: Vec<(u32, f32)> = (0..source.len())
+        .map(|i| {
+            let node_norm = source.feature_norm(i);
+            if node_norm == 0.0 {
+                return (i as u32, -1.0);
+            }
+            let dot: f32 = target
+                .iter()
+                .enumerate()
+                .map(|(dim, t)| source.feature(i, dim) * t)
+                .sum();
+            let similarity = dot / (node_norm * target_norm);
+            (i as u32, similarity)
+        })
+        .collect();
+
+    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    scored
+        .into_iter()
+        .take(k)
+        .map(|(idx, sim)| NodeMatch {
+            index: idx,
+            url: source.url(idx as usize).to_string(),
+            page_type: source.page_type(idx as usize),
+            confidence: source.confidence(idx as usize) as f32 / 255.0,
+            features: Vec::new(),
+            similarity: Some(sim),
+            text_score: None,
+        })
+        .collect()
+}
+
+impl SiteMap {
+    /// Filter nodes by criteria.
+    pub fn filter(&self, query: &NodeQuery) -> Vec<NodeMatch> {
+        filter_nodes(self, query)
+    }
+
+    /// Find k nearest nodes by cosine similarity to target vector.
+    pub fn nearest(&self, target: &[f32; FEATURE_DIM], k: usize) -> Vec<NodeMatch> {
+        nearest_nodes(self, target, k)
     }
 
     /// Get all edges from a node using the CSR index.
@@ -149,6 +203,26 @@ impl SiteMap {
         &self.urls[node as usize]
     }
 
+    /// Index of the node with `url`. Falls back to ignoring the fragment and
+    /// a trailing slash when there is no exact match.
+    pub fn find_url(&self, url: &str) -> Option<u32> {
+        if let Some(i) = self.urls.iter().position(|u| u == url) {
+            return Some(i as u32);
+        }
+        let loose = |u: &str| {
+            u.split('#')
+                .next()
+                .unwrap_or(u)
+                .trim_end_matches('/')
+                .to_string()
+        };
+        let wanted = loose(url);
+        self.urls
+            .iter()
+            .position(|u| loose(u) == wanted)
+            .map(|i| i as u32)
+    }
+
     /// Get the feature vector for a node.
     pub fn node_features(&self, node: u32) -> &[f32; FEATURE_DIM] {
         &self.features[node as usize]
@@ -176,31 +250,62 @@ impl SiteMap {
 
     /// Find shortest path between two nodes using Dijkstra's algorithm.
     pub fn shortest_path(&self, from: u32, to: u32, constraints: &PathConstraints) -> Option<Path> {
-        use std::cmp::Reverse;
-        use std::collections::BinaryHeap;
-
         let n = self.nodes.len();
         if from as usize >= n || to as usize >= n {
             return None;
         }
+        self.path_tree(from, constraints, &[to], Some(to))
+            .path_to(to)
+    }
 
-        let mut dist = vec![f32::INFINITY; n];
-        let mut prev = vec![u32::MAX; n];
-        dist[from as usize] = 0.0;
+    /// Single-source shortest paths from `from` under `constraints`.
+    ///
+    /// Nodes in `exempt` are never avoided (route endpoints). The search
+    /// stops once `stop_at` is settled. With `max_hops` set, the search runs
+    /// over (node, hops) states so the hop bound never hides a cheaper path.
+    pub(crate) fn path_tree(
+        &self,
+        from: u32,
+        constraints: &PathConstraints,
+        exempt: &[u32],
+        stop_at: Option<u32>,
+    ) -> PathTree {
+        use std::cmp::Reverse;
+        use std::collections::{BinaryHeap, HashSet};
 
-        // Min-heap: (cost, node)
+        let n = self.nodes.len();
+        let layers = constraints.max_hops.map_or(1, |h| h as usize + 1);
+        let mut dist = vec![f32::INFINITY; n * layers];
+        let mut prev = vec![usize::MAX; n * layers];
+        let start = from as usize * layers;
+        if (from as usize) < n {
+            dist[start] = 0.0;
+        }
+
+        let avoid: HashSet<u32> = constraints
+            .avoid_nodes
+            .iter()
+            .copied()
+            .filter(|node| !exempt.contains(node))
+            .collect();
+
+        // Min-heap: (cost, state), where state = node * layers + hops
         let mut heap = BinaryHeap::new();
-        heap.push(Reverse((OrderedF32(0.0), from)));
+        heap.push(Reverse((OrderedF32(0.0), start)));
 
-        while let Some(Reverse((OrderedF32(cost), node))) = heap.pop() {
-            if node == to {
+        while let Some(Reverse((OrderedF32(cost), state))) = heap.pop() {
+            if cost > dist[state] {
+                continue;
+            }
+            let (node, hops) = (state / layers, state % layers);
+            if stop_at == Some(node as u32) {
                 break;
             }
-            if cost > dist[node as usize] {
+            if constraints.max_hops.is_some() && hops + 1 >= layers {
                 continue;
             }
 
-            for edge in self.edges_from(node) {
+            for edge in self.edges_from(node as u32) {
                 let target = edge.target_node;
                 if target as usize >= n {
                     continue;
@@ -213,6 +318,21 @@ impl SiteMap {
                 if constraints.avoid_state_changes && edge.flags.changes_state() {
                     continue;
                 }
+                if avoid.contains(&target) {
+                    continue;
+                }
+                if constraints
+                    .avoid_page_types
+                    .contains(&self.nodes[target as usize].page_type)
+                    && !exempt.contains(&target)
+                {
+                    continue;
+                }
+                if let Some(max_risk) = constraints.max_risk {
+                    if self.step_risk(node as u32, edge) > max_risk {
+                        continue;
+                    }
+                }
 
                 let edge_cost = match constraints.minimize {
                     PathMinimize::Hops => 1.0,
@@ -225,36 +345,78 @@ impl SiteMap {
                         }
                     }
                 };
+                let edge_cost = match &constraints.learned_costs {
+                    Some(model) => model.weigh(node as u32, target, edge_cost),
+                    None => edge_cost,
+                };
 
+                let next_hops = if constraints.max_hops.is_some() {
+                    hops + 1
+                } else {
+                    0
+                };
+                let next = target as usize * layers + next_hops;
                 let new_cost = cost + edge_cost;
-                if new_cost < dist[target as usize] {
-                    dist[target as usize] = new_cost;
-                    prev[target as usize] = node;
-                    heap.push(Reverse((OrderedF32(new_cost), target)));
+                if new_cost < dist[next] {
+                    dist[next] = new_cost;
+                    prev[next] = state;
+                    heap.push(Reverse((OrderedF32(new_cost), next)));
                 }
             }
         }
 
-        if dist[to as usize].is_infinite() {
-            return None;
+        PathTree {
+            start,
+            layers,
+            dist,
+            prev,
         }
+    }
+
+    /// Risk of following `edge` out of `node` (see [`PathConstraints::max_risk`]).
+    fn step_risk(&self, node: u32, edge: &EdgeRecord) -> u8 {
+        let action_risk = self
+            .actions_for(node)
+            .iter()
+            .filter(|a| a.target_node == edge.target_node as i32)
+            .map(|a| a.risk)
+            .min()
+            .unwrap_or(0);
+        action_risk.max(u8::from(edge.flags.changes_state()))
+    }
+}
+
+/// Shortest paths from one source, as computed by [`SiteMap::path_tree`].
+pub(crate) struct PathTree {
+    start: usize,
+    layers: usize,
+    dist: Vec<f32>,
+    prev: Vec<usize>,
+}
+
+impl PathTree {
+    /// The cheapest path to `to`, if it was reached.
+    pub(crate) fn path_to(&self, to: u32) -> Option<Path> {
+        let base = to as usize * self.layers;
+        let (mut state, cost) = (base..base + self.layers)
+            .filter_map(|s| self.dist.get(s).map(|&d| (s, d)))
+            .filter(|(_, d)| d.is_finite())
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))?;
 
         // Reconstruct path
-        let mut path_nodes = Vec::new();
-        let mut current = to;
-        while current != from {
-            path_nodes.push(current);
-            current = prev[current as usize];
-            if current == u32::MAX {
+        let mut path_nodes = vec![to];
+        while state != self.start {
+            state = self.prev[state];
+            if state == usize::MAX {
                 return None;
             }
+            path_nodes.push((state / self.layers) as u32);
         }
-        path_nodes.push(from);
         path_nodes.reverse();
 
         Some(Path {
             hops: (path_nodes.len() - 1) as u32,
-            total_weight: dist[to as usize],
+            total_weight: cost,
             nodes: path_nodes,
             required_actions: Vec::new(),
         })
@@ -1,10 +1,14 @@
 //! Serialize a SiteMap to the binary CTX format.
 //!
-//! The format ends with a 4-byte CRC32 checksum (IEEE) of all preceding bytes,
-//! allowing integrity verification on load.
+//! The table layout is described in [`mapped`](super::mapped). The format
+//! ends with a 4-byte CRC32 checksum (IEEE) of all preceding bytes, allowing
+//! integrity verification on load.
 
+use crate::map::mapped::{Layout, NODE_STRIDE};
 use crate::map::types::*;
+#[cfg(test)]
 use byteorder::{LittleEndian, WriteBytesExt};
+#[cfg(test)]
 use std::io::Write;
 
 /// Compute CRC32 (IEEE/ISO 3309) checksum of data.
@@ -40,24 +44,141 @@ const CRC32_TABLE: [u32; 256] = {
 
 impl SiteMap {
     /// Serialize the SiteMap to binary CTX format with trailing CRC32 checksum.
+    ///
+    /// Writes the current (v2) layout described in [`mapped`](super::mapped).
     pub fn serialize(&self) -> Vec<u8> {
-        let mut buf = Vec::new();
-        self.write_to(&mut buf)
-            .expect("serialization to Vec should not fail");
+        let n = self.nodes.len();
+        let url_data_len: usize = self.urls.iter().map(String::len).sum();
+        let domain = self.header.domain.as_bytes();
+        let layout = Layout::new(
+            domain.len(),
+            n,
+            self.edges.len(),
+            self.actions.len(),
+            self.cluster_centroids.len(),
+            url_data_len,
+        );
+        let mut buf = Vec::with_capacity(layout.end + 4);
+
+        // ─── Header ───────────────────────────────────────
+        buf.extend_from_slice(&self.header.magic.to_le_bytes());
+        buf.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        buf.extend_from_slice(&(domain.len() as u16).to_le_bytes());
+        buf.extend_from_slice(&self.header.mapped_at.to_le_bytes());
+        buf.extend_from_slice(&(n as u32).to_le_bytes());
+        buf.extend_from_slice(&(self.edges.len() as u32).to_le_bytes());
+        buf.extend_from_slice(&(self.actions.len() as u32).to_le_bytes());
+        buf.extend_from_slice(&(url_data_len as u32).to_le_bytes());
+        buf.extend_from_slice(&(self.cluster_centroids.len() as u16).to_le_bytes());
+        buf.extend_from_slice(&self.header.flags.to_le_bytes());
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        buf.extend_from_slice(domain);
+
+        // ─── Node Table ───────────────────────────────────
+        buf.resize(layout.nodes, 0);
+        for node in &self.nodes {
+            buf.push(node.page_type as u8);
+            buf.push(node.confidence);
+            buf.push(node.freshness);
+            buf.push(node.flags.0);
+            buf.extend_from_slice(&node.content_hash.to_le_bytes());
+            buf.extend_from_slice(&node.rendered_at.to_le_bytes());
+            buf.extend_from_slice(&node.http_status.to_le_bytes());
+            buf.extend_from_slice(&node.depth.to_le_bytes());
+            buf.extend_from_slice(&node.inbound_count.to_le_bytes());
+            buf.extend_from_slice(&node.outbound_count.to_le_bytes());
+            buf.extend_from_slice(&node.feature_norm.to_le_bytes());
+            buf.extend_from_slice(&node.reserved.to_le_bytes());
+            buf.extend_from_slice(&[0; NODE_STRIDE - 28]);
+        }
+
+        // ─── Edge Table ───────────────────────────────────
+        buf.resize(layout.edges, 0);
+        for edge in &self.edges {
+            buf.extend_from_slice(&edge.target_node.to_le_bytes());
+            buf.push(edge.edge_type as u8);
+            buf.push(edge.weight);
+            buf.push(edge.flags.0);
+            buf.push(edge.reserved);
+        }
+
+        // Edge CSR index
+        buf.resize(layout.edge_index, 0);
+        write_csr(&mut buf, &self.edge_index, n, self.edges.len());
+
+        // ─── Feature Matrix ──────────────────────────────
+        buf.resize(layout.features, 0);
+        for feat_vec in &self.features {
+            for &f in feat_vec {
+                buf.extend_from_slice(&f.to_le_bytes());
+            }
+        }
+
+        // ─── Action Catalog ──────────────────────────────
+        buf.resize(layout.actions, 0);
+        for action in &self.actions {
+            buf.extend_from_slice(&action.opcode.as_u16().to_le_bytes());
+            buf.push(action.cost_hint);
+            buf.push(action.risk);
+            buf.extend_from_slice(&action.target_node.to_le_bytes());
+        }
+
+        // Action CSR index
+        buf.resize(layout.action_index, 0);
+        write_csr(&mut buf, &self.action_index, n, self.actions.len());
+
+        // ─── Cluster Table ───────────────────────────────
+        buf.resize(layout.cluster_assignments, 0);
+        for i in 0..n {
+            let assignment = self.cluster_assignments.get(i).copied().unwrap_or(0);
+            buf.extend_from_slice(&assignment.to_le_bytes());
+        }
+        buf.resize(layout.cluster_centroids, 0);
+        for centroid in &self.cluster_centroids {
+            for &f in centroid {
+                buf.extend_from_slice(&f.to_le_bytes());
+            }
+        }
+
+        // ─── URL Table ───────────────────────────────────
+        // n + 1 offsets into the string data, so URL i spans offsets[i]..offsets[i + 1]
+        buf.resize(layout.url_offsets, 0);
+        let mut offset = 0u32;
+        buf.extend_from_slice(&offset.to_le_bytes());
+        for url in &self.urls {
+            offset += url.len() as u32;
+            buf.extend_from_slice(&offset.to_le_bytes());
+        }
+        buf.resize(layout.url_data, 0);
+        for url in &self.urls {
+            buf.extend_from_slice(url.as_bytes());
+        }
+        buf.resize(layout.end, 0);
 
         // Append CRC32 checksum of all preceding bytes
         let checksum = crc32(&buf);
+        buf.extend_from_slice(&checksum.to_le_bytes());
+
+        buf
+    }
+
+    /// Serialize in the legacy v1 layout, for migration tests.
+    #[cfg(test)]
+    pub(crate) fn serialize_v1(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.write_v1(&mut buf)
+            .expect("serialization to Vec should not fail");
+        let checksum = crc32(&buf);
         buf.write_u32::<LittleEndian>(checksum)
             .expect("checksum write to Vec should not fail");
-
         buf
     }
 
-    fn write_to<W: Write>(&self, w: &mut W) -> std::io::Result<()> {
+    #[cfg(test)]
+    fn write_v1<W: Write>(&self, w: &mut W) -> std::io::Result<()> {
         // ─── Header ───────────────────────────────────────
         w.write_u32::<LittleEndian>(self.header.magic)?;
-        w.write_u16::<LittleEndian>(self.header.format_version)?;
-
+        w.write_u16::<LittleEndian>(1)?;
         let domain_bytes = self.header.domain.as_bytes();
         w.write_u16::<LittleEndian>(domain_bytes.len() as u16)?;
         w.write_all(domain_bytes)?;
@@ -153,3 +274,11 @@ impl SiteMap {
         Ok(())
     }
 }
+
+/// Write `n + 1` CSR offsets, padding a short index with `total`.
+fn write_csr(buf: &mut Vec<u8>, index: &[u32], n: usize, total: usize) {
+    for i in 0..=n {
+        let idx = index.get(i).copied().unwrap_or(total as u32);
+        buf.extend_from_slice(&idx.to_le_bytes());
+    }
+}
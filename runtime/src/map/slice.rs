@@ -0,0 +1,147 @@
+//! Cut a smaller map out of a SiteMap.
+//!
+//! A slice keeps the selected nodes with their records and features, plus
+//! the edges and actions between them, so downstream agents can be shipped
+//! a compact task-specific map (just the product pages, say).
+
+use crate::map::builder::SiteMapBuilder;
+use crate::map::types::*;
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+
+impl SiteMap {
+    /// The subgraph induced by `nodes`: those nodes, in index order, with the
+    /// edges between them. Actions targeting a dropped node keep their place
+    /// with an unknown (`-2`) target.
+    pub fn subgraph(&self, nodes: &[u32]) -> SiteMap {
+        let mut keep = vec![false; self.nodes.len()];
+        for &n in nodes {
+            if let Some(k) = keep.get_mut(n as usize) {
+                *k = true;
+            }
+        }
+
+        let mut builder = SiteMapBuilder::new(&self.header.domain);
+        builder.set_has_sitemap(self.header.has_sitemap());
+        let mut remap: Vec<Option<u32>> = vec![None; self.nodes.len()];
+        for (i, _) in keep.iter().enumerate().filter(|(_, &k)| k) {
+            let node = &self.nodes[i];
+            remap[i] = Some(builder.add_node(
+                &self.urls[i],
+                node.page_type,
+                self.features[i],
+                node.confidence,
+            ));
+        }
+        for (src, &from) in remap.iter().enumerate() {
+            let Some(from) = from else { continue };
+            for edge in self.edges_from(src as u32) {
+                if let Some(Some(to)) = remap.get(edge.target_node as usize) {
+                    builder.add_edge(from, *to, edge.edge_type, edge.weight, edge.flags);
+                }
+            }
+            for action in self.actions_for(src as u32) {
+                let target = match usize::try_from(action.target_node) {
+                    Ok(t) => remap.get(t).copied().flatten().map_or(-2, |m| m as i32),
+                    Err(_) => action.target_node,
+                };
+                if action.http_executable {
+                    builder.add_action_http(
+                        from,
+                        action.opcode,
+                        target,
+                        action.cost_hint,
+                        action.risk,
+                    );
+                } else {
+                    builder.add_action(from, action.opcode, target, action.cost_hint, action.risk);
+                }
+            }
+        }
+
+        let mut subset = builder.build();
+        let kept = remap.iter().enumerate().filter_map(|(i, m)| m.map(|_| i));
+        for (node, i) in subset.nodes.iter_mut().zip(kept) {
+            *node = NodeRecord {
+                inbound_count: node.inbound_count,
+                outbound_count: node.outbound_count,
+                feature_norm: node.feature_norm,
+                ..self.nodes[i].clone()
+            };
+        }
+        subset.header.mapped_at = self.header.mapped_at;
+        subset
+    }
+
+    /// Slice out the nodes matching `query` (honouring its limit and sort).
+    pub fn slice(&self, query: &NodeQuery) -> SiteMap {
+        let nodes: Vec<u32> = self.filter(query).iter().map(|m| m.index).collect();
+        self.subgraph(&nodes)
+    }
+
+    /// Slice out the nodes returned by a WQL query over this map.
+    pub fn slice_wql(&self, wql: &str) -> Result<SiteMap> {
+        let query = crate::wql::parser::parse(wql)?;
+        if query.as_of.is_some() {
+            bail!("AS OF queries run against the registry, not a single map");
+        }
+        let plan = crate::wql::planner::plan(&query, None)?;
+        let maps = HashMap::from([(self.header.domain.clone(), self.clone())]);
+        let nodes: Vec<u32> = crate::wql::executor::execute(&plan, &maps)?
+            .iter()
+            .map(|row| row.node_id)
+            .collect();
+        Ok(self.subgraph(&nodes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slice_by_page_type() {
+        let mut builder = SiteMapBuilder::new("shop.com");
+        let home = builder.add_node("https://shop.com/", PageType::Home, [0.0; FEATURE_DIM], 255);
+        let mut products = Vec::new();
+        for i in 0..3 {
+            let mut feats = [0.0; FEATURE_DIM];
+            feats[FEAT_PRICE] = 10.0 * (i + 1) as f32;
+            products.push(builder.add_node(
+                &format!("https://shop.com/p/{i}"),
+                PageType::ProductDetail,
+                feats,
+                200,
+            ));
+        }
+        for &p in &products {
+            builder.add_edge(home, p, EdgeType::Navigation, 1, EdgeFlags::default());
+        }
+        builder.add_edge(
+            products[0],
+            products[1],
+            EdgeType::Related,
+            1,
+            EdgeFlags::default(),
+        );
+        builder.add_action(products[0], OpCode::new(0x02, 0x00), home as i32, 0, 0);
+        let mut map = builder.build();
+        map.nodes[products[1] as usize].http_status = 404;
+
+        let slice = map.slice(&NodeQuery {
+            page_types: Some(vec![PageType::ProductDetail]),
+            ..Default::default()
+        });
+        assert_eq!(slice.urls.len(), 3);
+        assert_eq!(slice.edges.len(), 1);
+        assert_eq!(slice.nodes[1].http_status, 404);
+        assert_eq!(slice.nodes[1].inbound_count, 1);
+        assert_eq!(slice.features[2][FEAT_PRICE], 30.0);
+        // The action pointed at the dropped home page.
+        assert_eq!(slice.actions_for(0)[0].target_node, -2);
+
+        // Survives a serialize round trip as a standalone map.
+        let restored = SiteMap::deserialize(&slice.serialize()).unwrap();
+        assert_eq!(restored.urls, slice.urls);
+    }
+}
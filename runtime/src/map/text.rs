@@ -0,0 +1,248 @@
+//! Page titles and descriptions, and the text index QUERY searches them with.
+//!
+//! The mapper keeps each fetched page's title and description (from JSON-LD,
+//! OpenGraph, meta tags or the rendered page). Like lineage they are not
+//! part of the binary format; the map cache keeps them next to the map as
+//! `<domain>.text.json`.
+//!
+//! [`MapText::search`] ranks pages by BM25 over an inverted index built the
+//! first time a map is searched. Titles count twice: a word in the title
+//! says more about a page than one in its description.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// BM25 term frequency saturation.
+const K1: f32 = 1.2;
+/// BM25 document length normalization.
+const B: f32 = 0.75;
+/// How many times a title's words count.
+const TITLE_WEIGHT: u32 = 2;
+
+/// What a page says it is.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeText {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl NodeText {
+    /// Text from the first non-blank title and description offered, trimmed.
+    pub fn new<'a>(
+        titles: impl IntoIterator<Item = Option<&'a str>>,
+        descriptions: impl IntoIterator<Item = Option<&'a str>>,
+    ) -> Self {
+        let first = |candidates: &mut dyn Iterator<Item = Option<&'a str>>| {
+            candidates
+                .flatten()
+                .map(str::trim)
+                .find(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+        Self {
+            title: first(&mut titles.into_iter()),
+            description: first(&mut descriptions.into_iter()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.description.is_none()
+    }
+}
+
+/// Titles and descriptions of the nodes of one map, keyed by node index.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MapText {
+    pub nodes: BTreeMap<u32, NodeText>,
+    #[serde(skip)]
+    index: OnceLock<TextIndex>,
+}
+
+impl PartialEq for MapText {
+    fn eq(&self, other: &Self) -> bool {
+        self.nodes == other.nodes
+    }
+}
+
+impl MapText {
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Text recorded for `node`, if any.
+    pub fn node(&self, node: u32) -> Option<&NodeText> {
+        self.nodes.get(&node)
+    }
+
+    /// Record `node`'s text, replacing what was there. Empty text is not
+    /// kept.
+    pub fn insert(&mut self, node: u32, text: NodeText) {
+        self.index = OnceLock::new();
+        if text.is_empty() {
+            self.nodes.remove(&node);
+        } else {
+            self.nodes.insert(node, text);
+        }
+    }
+
+    /// Nodes matching any word of `query`, with their BM25 scores, best
+    /// first.
+    pub fn search(&self, query: &str) -> Vec<(u32, f32)> {
+        self.index
+            .get_or_init(|| TextIndex::build(&self.nodes))
+            .search(query)
+    }
+
+    /// Load text saved by [`save`](Self::save); empty if the file is missing.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = std::fs::read(path)
+            .with_context(|| format!("failed to read page text: {}", path.display()))?;
+        serde_json::from_slice(&data)
+            .with_context(|| format!("failed to parse page text: {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_vec(self)?)
+            .with_context(|| format!("failed to write page text: {}", path.display()))
+    }
+}
+
+/// Inverted index over node text.
+#[derive(Debug, Clone, Default)]
+struct TextIndex {
+    /// Term → (node, weighted term frequency).
+    postings: HashMap<String, Vec<(u32, u32)>>,
+    /// Weighted length of each indexed node's text.
+    lengths: HashMap<u32, u32>,
+    avg_length: f32,
+}
+
+impl TextIndex {
+    fn build(nodes: &BTreeMap<u32, NodeText>) -> Self {
+        let mut postings: HashMap<String, Vec<(u32, u32)>> = HashMap::new();
+        let mut lengths = HashMap::new();
+        for (&node, text) in nodes {
+            let mut counts: HashMap<String, u32> = HashMap::new();
+            for term in tokenize(text.title.as_deref().unwrap_or("")) {
+                *counts.entry(term).or_default() += TITLE_WEIGHT;
+            }
+            for term in tokenize(text.description.as_deref().unwrap_or("")) {
+                *counts.entry(term).or_default() += 1;
+            }
+            lengths.insert(node, counts.values().sum());
+            for (term, count) in counts {
+                postings.entry(term).or_default().push((node, count));
+            }
+        }
+        let avg_length = if lengths.is_empty() {
+            0.0
+        } else {
+            lengths.values().sum::<u32>() as f32 / lengths.len() as f32
+        };
+        Self {
+            postings,
+            lengths,
+            avg_length,
+        }
+    }
+
+    fn search(&self, query: &str) -> Vec<(u32, f32)> {
+        let docs = self.lengths.len() as f32;
+        let mut terms = tokenize(query);
+        terms.sort_unstable();
+        terms.dedup();
+        let mut scores: HashMap<u32, f32> = HashMap::new();
+        for term in &terms {
+            let Some(postings) = self.postings.get(term) else {
+                continue;
+            };
+            let df = postings.len() as f32;
+            let idf = (1.0 + (docs - df + 0.5) / (df + 0.5)).ln();
+            for &(node, tf) in postings {
+                let tf = tf as f32;
+                let length = self.lengths[&node] as f32 / self.avg_length.max(1.0);
+                *scores.entry(node).or_default() +=
+                    idf * tf * (K1 + 1.0) / (tf + K1 * (1.0 - B + B * length));
+            }
+        }
+        let mut ranked: Vec<(u32, f32)> = scores.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked
+    }
+}
+
+/// Lowercase words of `text`: runs of letters and digits, of two characters
+/// or more.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() > 1)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(title: &str, description: &str) -> NodeText {
+        NodeText::new([Some(title)], [Some(description)])
+    }
+
+    #[test]
+    fn test_search_ranks_by_bm25() {
+        let mut map_text = MapText::default();
+        map_text.insert(
+            0,
+            text("Wireless Headphones X1", "Over-ear, noise cancelling"),
+        );
+        map_text.insert(1, text("Wired headphones", "Studio monitors with a cable"));
+        map_text.insert(2, text("Phone case", "Fits wireless chargers"));
+        map_text.insert(3, text("Gift cards", ""));
+
+        let ranked = map_text.search("wireless headphones");
+        let nodes: Vec<u32> = ranked.iter().map(|&(n, _)| n).collect();
+        assert_eq!(nodes[0], 0);
+        assert_eq!(nodes.len(), 3);
+        assert!(!nodes.contains(&3));
+        // A title match outranks a description match.
+        let score = |n: u32| ranked.iter().find(|r| r.0 == n).unwrap().1;
+        assert!(score(1) > score(2));
+
+        assert!(map_text.search("a").is_empty());
+        assert!(map_text.search("toaster").is_empty());
+
+        // Changing the text rebuilds the index.
+        map_text.insert(3, text("Wireless gift cards", ""));
+        assert_eq!(map_text.search("gift").len(), 1);
+        assert!(map_text.search("wireless").iter().any(|r| r.0 == 3));
+    }
+
+    #[test]
+    fn test_node_text_takes_first_non_blank() {
+        let t = NodeText::new([None, Some("  "), Some(" Shoes ")], [None]);
+        assert_eq!(t.title.as_deref(), Some("Shoes"));
+        assert_eq!(t.description, None);
+        let mut map_text = MapText::default();
+        map_text.insert(0, NodeText::default());
+        assert!(map_text.is_empty());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shop.com.text.json");
+        assert!(MapText::load(&path).unwrap().is_empty());
+        let mut map_text = MapText::default();
+        map_text.insert(4, text("Checkout", "Pay for your order"));
+        map_text.save(&path).unwrap();
+        assert_eq!(MapText::load(&path).unwrap(), map_text);
+    }
+}
@@ -1,5 +1,11 @@
 //! Core SiteMap types matching the binary format specification in 02-map-spec.md.
 
+use crate::acquisition::block_detect::BlockReport;
+use crate::cartography::compliance::ComplianceReport;
+use crate::map::embeddings::MapEmbeddings;
+use crate::map::text::MapText;
+use crate::navigation::cost_model::CostModel;
+use crate::trust::lineage::MapLineage;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -9,7 +15,7 @@ use std::fmt;
 pub const SITEMAP_MAGIC: u32 = 0x43545800;
 
 /// Current binary format version
-pub const FORMAT_VERSION: u16 = 1;
+pub const FORMAT_VERSION: u16 = 2;
 
 // ─── Feature vector dimension constants ───────────────────────────────────────
 
@@ -226,6 +232,13 @@ impl PageType {
             _ => Self::Unknown,
         }
     }
+
+    /// The page type displayed as `name` (`"product_detail"`), if any.
+    pub fn from_name(name: &str) -> Option<Self> {
+        (0..Self::COUNT as u8)
+            .map(Self::from_u8)
+            .find(|t| t.to_string() == name)
+    }
 }
 
 impl fmt::Display for PageType {
@@ -522,6 +535,21 @@ pub struct SiteMap {
     pub cluster_assignments: Vec<u16>,
     pub cluster_centroids: Vec<[f32; FEATURE_DIM]>,
     pub urls: Vec<String>,
+    /// Where traced values came from. Not part of the binary format; the
+    /// map cache keeps it alongside the map.
+    pub lineage: MapLineage,
+    /// Titles and descriptions of fetched pages, for text search. Not part
+    /// of the binary format; the map cache keeps it alongside the map.
+    pub text: MapText,
+    /// Sentence embeddings of that text, for semantic search. Not part of
+    /// the binary format; the map cache keeps them alongside the map.
+    pub embeddings: MapEmbeddings,
+    /// How the mapping treated robots.txt and the terms of service, for a
+    /// map just made. Not part of the binary format.
+    pub compliance: Option<ComplianceReport>,
+    /// How many fetched and rendered pages were captcha or anti-bot pages,
+    /// for a map just made. Not part of the binary format.
+    pub blocks: Option<BlockReport>,
 }
 
 // ─── Query/result types ───────────────────────────────────────────────────────
@@ -537,6 +565,7 @@ pub struct FeatureRange {
 /// Query for filtering nodes in a SiteMap.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NodeQuery {
+    /// Page types to keep. Without it, every type but error pages.
     pub page_types: Option<Vec<PageType>>,
     pub feature_ranges: Vec<FeatureRange>,
     pub require_flags: Option<NodeFlags>,
@@ -544,6 +573,10 @@ pub struct NodeQuery {
     pub sort_by_feature: Option<usize>,
     pub sort_ascending: bool,
     pub limit: usize,
+    /// Words to find in page titles and descriptions. Matches are ranked by
+    /// relevance unless `sort_by_feature` is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
 }
 
 /// A matched node from a query.
@@ -555,6 +588,9 @@ pub struct NodeMatch {
     pub confidence: f32,
     pub features: Vec<(usize, f32)>,
     pub similarity: Option<f32>,
+    /// BM25 relevance to the query's `text`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_score: Option<f32>,
 }
 
 /// Constraints for pathfinding.
@@ -563,6 +599,19 @@ pub struct PathConstraints {
     pub avoid_auth: bool,
     pub avoid_state_changes: bool,
     pub minimize: PathMinimize,
+    /// Nodes the path must not pass through (endpoints excepted).
+    pub avoid_nodes: Vec<u32>,
+    /// Page types the path must not pass through (endpoints excepted).
+    pub avoid_page_types: Vec<PageType>,
+    /// Maximum number of hops.
+    pub max_hops: Option<u32>,
+    /// Maximum risk of any step: 0=safe, 1=cautious, 2=destructive.
+    /// State-changing links are cautious; a step reached through an action
+    /// is at least as risky as the safest such action.
+    pub max_risk: Option<u8>,
+    /// Latency and failure rates learned from executed actions; when set,
+    /// edge costs are scaled by [`CostModel::weigh`].
+    pub learned_costs: Option<std::sync::Arc<CostModel>>,
 }
 
 /// What to minimize in pathfinding.
@@ -611,6 +660,16 @@ mod tests {
         assert_eq!(PageType::Unknown.to_string(), "unknown");
     }
 
+    #[test]
+    fn test_page_type_from_name() {
+        assert_eq!(PageType::from_name("checkout"), Some(PageType::Checkout));
+        assert_eq!(
+            PageType::from_name("sitemap_page"),
+            Some(PageType::SitemapPage)
+        );
+        assert_eq!(PageType::from_name("Checkout"), None);
+    }
+
     #[test]
     fn test_node_flags() {
         let flags = NodeFlags(NodeFlags::RENDERED | NodeFlags::HAS_PRICE);
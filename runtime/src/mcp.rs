@@ -0,0 +1,718 @@
+// Copyright 2026 Cortex Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! MCP (Model Context Protocol) server for Cortex.
+//!
+//! Exposes MAP, QUERY, PATHFIND, PERCEIVE, WQL and compile as MCP tools.
+//! Map, query, pathfind and perceive are forwarded to the running daemon over
+//! its Unix socket; WQL and compile run in-process against the map cache, so
+//! they work while the daemon is stopped.
+//!
+//! Two transports carry the same JSON-RPC messages:
+//!
+//! - **stdio** — one message per line, or `Content-Length` framed (replies
+//!   use whichever framing the client used).
+//! - **HTTP + SSE** — `GET /sse` opens an event stream whose first `endpoint`
+//!   event names the URL to POST messages to; replies arrive as `message`
+//!   events. `POST /mcp` answers a single message directly. With a token,
+//!   every route except `/health` needs `Authorization: Bearer <token>`.
+
+use crate::collective::registry::LocalRegistry;
+use crate::compiler::package::{self, PackageSettings};
+use crate::compiler::schema;
+use crate::intelligence::cache::MapCache;
+use crate::map::types::{FEAT_PRICE, FEAT_RATING};
+use crate::project::{self, Projects};
+use crate::protocol::{ErrorCode, ProtocolError};
+use crate::trust::api_keys;
+use crate::wql::{executor, parser, planner};
+use anyhow::{bail, Context, Result};
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::sse::{Event, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+use tokio::sync::mpsc;
+
+/// Protocol revision answered when the client does not ask for one.
+pub const PROTOCOL_VERSION: &str = "2024-11-05";
+
+// JSON-RPC error codes.
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Handles MCP messages, forwarding daemon-backed tools to `socket_path`.
+pub struct McpServer {
+    socket_path: PathBuf,
+}
+
+impl McpServer {
+    pub fn new(socket_path: impl Into<PathBuf>) -> Self {
+        Self {
+            socket_path: socket_path.into(),
+        }
+    }
+
+    /// Handle one JSON-RPC message. Notifications get no reply.
+    pub async fn handle_message(&self, msg: Value) -> Option<Value> {
+        let method = msg.get("method").and_then(|m| m.as_str()).unwrap_or("");
+        let Some(id) = msg.get("id").cloned() else {
+            tracing::debug!("MCP notification: {method}");
+            return None;
+        };
+        let params = msg.get("params").cloned().unwrap_or_else(|| json!({}));
+
+        let result = match method {
+            "initialize" => Ok(json!({
+                "protocolVersion": params
+                    .get("protocolVersion")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or(PROTOCOL_VERSION),
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "cortex", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tools() })),
+            "tools/call" => match params.get("name").and_then(|n| n.as_str()) {
+                Some(name) if tools().iter().any(|t| t["name"] == name) => {
+                    let args = params.get("arguments").cloned().unwrap_or(json!({}));
+                    Ok(tool_result(self.call_tool(name, &args).await))
+                }
+                Some(name) => Err((INVALID_PARAMS, format!("unknown tool: {name}"))),
+                None => Err((INVALID_PARAMS, "tools/call needs a tool name".to_string())),
+            },
+            _ => Err((METHOD_NOT_FOUND, format!("method not found: {method}"))),
+        };
+
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => rpc_error(id, code, &message),
+        })
+    }
+
+    async fn call_tool(&self, name: &str, args: &Value) -> Result<Value> {
+        match name {
+            "cortex_map" => {
+                let max_time_ms = args
+                    .get("max_time_ms")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(30000);
+                let min_quality = args.get("min_quality").and_then(|v| v.as_f64());
+                let params = json!({
+                    "domain": required_str(args, "domain")?,
+                    "max_nodes": args.get("max_nodes").and_then(|v| v.as_u64()).unwrap_or(50000),
+                    "max_time_ms": max_time_ms,
+                    "min_quality": min_quality,
+                });
+                // A map below `min_quality` may be remapped twice more.
+                let attempts = if min_quality.is_some() { 3 } else { 1 };
+                self.daemon("map", params, max_time_ms * attempts + 30000)
+                    .await
+            }
+            "cortex_query" => self.daemon("query", query_params(args)?, 30000).await,
+            "cortex_pathfind" => {
+                let params = json!({
+                    "domain": required_str(args, "domain")?,
+                    "from": args.get("from_node").and_then(|v| v.as_u64()).context("from_node is required")?,
+                    "to": args.get("to_node").and_then(|v| v.as_u64()).context("to_node is required")?,
+                });
+                self.daemon("pathfind", params, 30000).await
+            }
+            "cortex_perceive" => {
+                let params = json!({
+                    "url": required_str(args, "url")?,
+                    "include_content": args.get("include_content").and_then(|v| v.as_bool()).unwrap_or(false),
+                });
+                self.daemon("perceive", params, 60000).await
+            }
+            "cortex_wql" => {
+                let query = required_str(args, "query")?;
+                let projects = Projects::load_default()?;
+                let search_path = match args.get("project").and_then(|v| v.as_str()) {
+                    Some(name) => projects
+                        .resolve(Some(name))?
+                        .map_or_else(Vec::new, |p| p.domains.clone()),
+                    None => Vec::new(),
+                };
+                let plan = planner::plan_in(&parser::parse(query)?, &search_path)?;
+                let maps = match plan.as_of {
+                    Some(at) => executor::maps_as_of(&LocalRegistry::open_default()?, &plan, at)?,
+                    None => MapCache::default_cache()?.load_all_maps()?,
+                };
+                let rows = executor::execute(&plan, &maps)?;
+                Ok(json!({ "query": query, "total": rows.len(), "rows": rows }))
+            }
+            "cortex_compile" => {
+                let domain = required_str(args, "domain")?;
+                let targets: Vec<String> = args
+                    .get("targets")
+                    .and_then(|v| v.as_array())
+                    .map(|a| {
+                        a.iter()
+                            .filter_map(|t| t.as_str().map(String::from))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                compile(domain, &targets)
+            }
+            _ => bail!("unknown tool: {name}"),
+        }
+    }
+
+    /// Send one request to the daemon and return its result.
+    async fn daemon(&self, method: &str, params: Value, timeout_ms: u64) -> Result<Value> {
+        let mut stream = UnixStream::connect(&self.socket_path).await.map_err(|_| {
+            ProtocolError::new(
+                ErrorCode::Unavailable,
+                format!(
+                    "Cortex is not running at {} (start it with `cortex start`)",
+                    self.socket_path.display()
+                ),
+            )
+        })?;
+        let mut req = json!({
+            "id": format!("mcp-{}", uuid::Uuid::new_v4()),
+            "method": method,
+            "params": params,
+        });
+        api_keys::attach_client_key(&mut req);
+        stream.write_all(format!("{req}\n").as_bytes()).await?;
+
+        let mut line = String::new();
+        let mut reader = BufReader::new(stream);
+        tokio::time::timeout(
+            Duration::from_millis(timeout_ms),
+            reader.read_line(&mut line),
+        )
+        .await
+        .map_err(|_| {
+            ProtocolError::new(
+                ErrorCode::Timeout,
+                format!("{method} timed out after {timeout_ms}ms"),
+            )
+        })??;
+        let resp: Value = serde_json::from_str(&line).context("invalid response from daemon")?;
+        if let Some(err) = resp.get("error") {
+            return Err(ProtocolError::from_json(err).into());
+        }
+        Ok(resp.get("result").cloned().unwrap_or(Value::Null))
+    }
+}
+
+/// The tools this server offers, as MCP tool definitions.
+pub fn tools() -> Vec<Value> {
+    vec![
+        json!({
+            "name": "cortex_map",
+            "description": "Map an entire website into a navigable graph of its pages, with page types, features (prices, ratings, …) and links. Reads structured data over HTTP; a browser is only used as a fallback.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "domain": { "type": "string", "description": "Domain to map (e.g. 'amazon.com')" },
+                    "max_nodes": { "type": "integer", "description": "Maximum number of nodes in the map", "default": 50000 },
+                    "max_time_ms": { "type": "integer", "description": "Maximum mapping time in milliseconds", "default": 30000 },
+                    "min_quality": { "type": "number", "description": "Fail unless the map's quality score (0-1) reaches this, after remapping with fallbacks" },
+                },
+                "required": ["domain"],
+            },
+        }),
+        json!({
+            "name": "cortex_query",
+            "description": "Search a mapped site by page type, feature values or words in page titles and descriptions. Returns matching pages with their features.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "domain": { "type": "string", "description": "Domain to query (must be mapped first)" },
+                    "page_type": { "type": "integer", "description": "Page type: 1=home, 2=product_listing, 3=search_results, 4=product_detail, 5=cart, 6=article, 7=documentation, 8=login, 9=checkout, 10=profile, 11=api_endpoint, 12=media, 13=form, 14=dashboard, 15=error, 16=other" },
+                    "price_lt": { "type": "number", "description": "Maximum price" },
+                    "price_gt": { "type": "number", "description": "Minimum price" },
+                    "rating_gt": { "type": "number", "description": "Minimum rating (0.0-1.0)" },
+                    "text": { "type": "string", "description": "Words to find in page titles and descriptions; results are ranked by relevance" },
+                    "limit": { "type": "integer", "description": "Maximum results", "default": 20 },
+                    "saved": { "type": "string", "description": "Run a saved query by name instead of the filters above" },
+                    "goal": { "type": "string", "description": "Instead of filters: what you are looking for in plain words (e.g. 'return an order'); returns the pages nearest in meaning. Needs a daemon with a sentence model" },
+                },
+                "required": ["domain"],
+            },
+        }),
+        json!({
+            "name": "cortex_pathfind",
+            "description": "Find the shortest navigation path between two pages of a mapped site.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "domain": { "type": "string", "description": "Mapped domain" },
+                    "from_node": { "type": "integer", "description": "Source node index" },
+                    "to_node": { "type": "integer", "description": "Target node index" },
+                },
+                "required": ["domain", "from_node", "to_node"],
+            },
+        }),
+        json!({
+            "name": "cortex_perceive",
+            "description": "Render a single live page and return its page type, structured data and available actions.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "url": { "type": "string", "description": "URL to perceive" },
+                    "include_content": { "type": "boolean", "description": "Include the page's text content", "default": false },
+                },
+                "required": ["url"],
+            },
+        }),
+        json!({
+            "name": "cortex_wql",
+            "description": "Run a WQL query across all cached maps, e.g. SELECT name, price FROM Product WHERE price < 200 LIMIT 10. Add AS OF '2026-01-01' after the model to query the maps as they were then.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "WQL query" },
+                    "project": { "type": "string", "description": "Search this project's domains unless the query has an ACROSS clause" },
+                },
+                "required": ["query"],
+            },
+        }),
+        json!({
+            "name": "cortex_compile",
+            "description": "Compile a mapped site into typed client packages under ~/.cortex/compiled/<domain>/.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "domain": { "type": "string", "description": "Mapped domain to compile" },
+                    "targets": {
+                        "type": "array",
+                        "items": { "type": "string", "enum": ["python", "typescript", "openapi", "graphql", "mcp", "all"] },
+                        "description": "Targets to generate (default: all)",
+                    },
+                },
+                "required": ["domain"],
+            },
+        }),
+    ]
+}
+
+fn required_str<'a>(args: &'a Value, key: &str) -> Result<&'a str> {
+    match args.get(key).and_then(|v| v.as_str()) {
+        Some(s) if !s.is_empty() => Ok(s),
+        _ => bail!("{key} is required"),
+    }
+}
+
+/// QUERY params from the tool's shorthand filters.
+fn query_params(args: &Value) -> Result<Value> {
+    let mut params = json!({ "domain": required_str(args, "domain")? });
+    if let Some(saved) = args.get("saved") {
+        // A saved query carries its own filters and limit.
+        params["saved"] = saved.clone();
+        if let Some(limit) = args.get("limit") {
+            params["limit"] = limit.clone();
+        }
+        return Ok(params);
+    }
+    if let Some(goal) = args.get("goal") {
+        params["mode"] = json!("semantic");
+        params["goal"] = goal.clone();
+        params["limit"] = args.get("limit").cloned().unwrap_or(json!(10));
+        return Ok(params);
+    }
+    params["limit"] = args.get("limit").cloned().unwrap_or(json!(20));
+    if let Some(page_type) = args.get("page_type") {
+        params["page_type"] = page_type.clone();
+    }
+    if let Some(text) = args.get("text") {
+        params["text"] = text.clone();
+    }
+
+    let mut features = serde_json::Map::new();
+    let mut price = serde_json::Map::new();
+    if let Some(lt) = args.get("price_lt") {
+        price.insert("lt".into(), lt.clone());
+    }
+    if let Some(gt) = args.get("price_gt") {
+        price.insert("gt".into(), gt.clone());
+    }
+    if !price.is_empty() {
+        features.insert(FEAT_PRICE.to_string(), price.into());
+    }
+    if let Some(gt) = args.get("rating_gt") {
+        features.insert(FEAT_RATING.to_string(), json!({ "gt": gt }));
+    }
+    if !features.is_empty() {
+        params["features"] = features.into();
+    }
+    Ok(params)
+}
+
+/// Compile `domain`'s cached map into the default output directory.
+fn compile(domain: &str, targets: &[String]) -> Result<Value> {
+    let targets = package::parse_targets(&project::compile_targets(domain, targets)?)?;
+    let Some(site_map) = MapCache::default_cache()?.load_map(domain)? else {
+        bail!("no cached map for '{domain}'; map it with cortex_map first");
+    };
+    let compiled = schema::infer_schema(&site_map, domain);
+    let out_dir = dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".cortex")
+        .join("compiled")
+        .join(domain);
+    let settings = PackageSettings::new(targets, compiled.compiled_at);
+    let manifest = package::write_packages(&compiled, &settings, &out_dir)?;
+    Ok(json!({
+        "domain": domain,
+        "models": compiled.stats.total_models,
+        "fields": compiled.stats.total_fields,
+        "actions": compiled.actions.len(),
+        "output_dir": out_dir.display().to_string(),
+        "manifest": manifest,
+    }))
+}
+
+/// A `tools/call` result: the tool's JSON as text, or its error. Daemon
+/// errors also carry the protocol `error` object (code, category,
+/// retryability) as structured content.
+fn tool_result(result: Result<Value>) -> Value {
+    match result {
+        Ok(value) => json!({
+            "content": [{
+                "type": "text",
+                "text": serde_json::to_string_pretty(&value).unwrap_or_default(),
+            }],
+        }),
+        Err(e) => {
+            let mut result = json!({
+                "content": [{ "type": "text", "text": format!("Error: {e:#}") }],
+                "isError": true,
+            });
+            if let Some(error) = e.downcast_ref::<ProtocolError>() {
+                result["structuredContent"] = json!({ "error": error.to_json() });
+            }
+            result
+        }
+    }
+}
+
+fn rpc_error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+// ── stdio transport ─────────────────────────────────────────────
+
+/// Serve MCP over stdin/stdout until stdin closes.
+pub async fn serve_stdio(server: &McpServer) -> Result<()> {
+    let mut reader = BufReader::new(tokio::io::stdin());
+    let mut stdout = tokio::io::stdout();
+    let mut line = String::new();
+    let mut content_length: Option<usize> = None;
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(());
+        }
+        let trimmed = line.trim_end_matches(['\r', '\n']);
+
+        if let Some((name, value)) = trimmed.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().ok();
+                continue;
+            }
+        }
+        let (payload, framed) = match content_length {
+            // Headers end at the blank line; the body follows.
+            Some(n) if trimmed.is_empty() => {
+                let mut body = vec![0u8; n];
+                reader.read_exact(&mut body).await?;
+                content_length = None;
+                (String::from_utf8_lossy(&body).into_owned(), true)
+            }
+            Some(_) => continue,
+            None if trimmed.is_empty() => continue,
+            None => (trimmed.to_string(), false),
+        };
+
+        let reply = match serde_json::from_str::<Value>(&payload) {
+            Ok(msg) => server.handle_message(msg).await,
+            Err(e) => Some(rpc_error(Value::Null, PARSE_ERROR, &e.to_string())),
+        };
+        if let Some(reply) = reply {
+            let json = reply.to_string();
+            let out = if framed {
+                format!("Content-Length: {}\r\n\r\n{json}", json.len())
+            } else {
+                format!("{json}\n")
+            };
+            stdout.write_all(out.as_bytes()).await?;
+            stdout.flush().await?;
+        }
+    }
+}
+
+// ── HTTP + SSE transport ────────────────────────────────────────
+
+struct HttpState {
+    server: McpServer,
+    token: Option<String>,
+    /// Open SSE streams by session id.
+    sessions: Mutex<HashMap<String, mpsc::UnboundedSender<Value>>>,
+}
+
+/// Build the HTTP + SSE router for `server`.
+pub fn router(server: McpServer, token: Option<String>) -> Router {
+    let state = Arc::new(HttpState {
+        server,
+        token,
+        sessions: Mutex::new(HashMap::new()),
+    });
+    Router::new()
+        .route("/sse", get(open_stream))
+        .route("/messages", post(post_message))
+        .route("/mcp", post(post_direct))
+        .route("/health", get(|| async { Json(json!({ "status": "ok" })) }))
+        .with_state(state)
+}
+
+/// Serve MCP over HTTP + SSE on `addr` until the process exits.
+pub async fn serve_http(
+    addr: std::net::SocketAddr,
+    server: McpServer,
+    token: Option<String>,
+) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    tracing::info!("MCP server listening on http://{addr}/sse");
+    axum::serve(listener, router(server, token)).await?;
+    Ok(())
+}
+
+impl HttpState {
+    fn authorized(&self, headers: &HeaderMap) -> bool {
+        let Some(expected) = &self.token else {
+            return true;
+        };
+        headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .is_some_and(|t| {
+                crate::collective::registry_server::constant_time_eq(
+                    t.as_bytes(),
+                    expected.as_bytes(),
+                )
+            })
+    }
+}
+
+/// Removes an SSE session when its stream is dropped.
+struct SessionGuard {
+    state: Arc<HttpState>,
+    id: String,
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        if let Ok(mut sessions) = self.state.sessions.lock() {
+            sessions.remove(&self.id);
+        }
+    }
+}
+
+async fn open_stream(State(state): State<Arc<HttpState>>, headers: HeaderMap) -> Response {
+    if !state.authorized(&headers) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    state.sessions.lock().unwrap().insert(id.clone(), tx);
+    let guard = SessionGuard {
+        state: state.clone(),
+        id: id.clone(),
+    };
+
+    let stream = async_stream::stream! {
+        let _guard = guard;
+        yield Ok::<_, Infallible>(Event::default().event("endpoint").data(format!("/messages?session_id={id}")));
+        while let Some(msg) = rx.recv().await {
+            yield Ok(Event::default().event("message").data(msg.to_string()));
+        }
+    };
+    Sse::new(stream)
+        .keep_alive(axum::response::sse::KeepAlive::default())
+        .into_response()
+}
+
+#[derive(serde::Deserialize)]
+struct SessionParams {
+    session_id: String,
+}
+
+async fn post_message(
+    State(state): State<Arc<HttpState>>,
+    Query(params): Query<SessionParams>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    if !state.authorized(&headers) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    let Some(tx) = state
+        .sessions
+        .lock()
+        .unwrap()
+        .get(&params.session_id)
+        .cloned()
+    else {
+        return (StatusCode::NOT_FOUND, "unknown session").into_response();
+    };
+    let msg = match serde_json::from_str::<Value>(&body) {
+        Ok(msg) => msg,
+        Err(e) => {
+            let _ = tx.send(rpc_error(Value::Null, PARSE_ERROR, &e.to_string()));
+            return StatusCode::ACCEPTED.into_response();
+        }
+    };
+    // Replies go out on the stream; tool calls may take a while.
+    tokio::spawn(async move {
+        if let Some(reply) = state.server.handle_message(msg).await {
+            let _ = tx.send(reply);
+        }
+    });
+    StatusCode::ACCEPTED.into_response()
+}
+
+async fn post_direct(
+    State(state): State<Arc<HttpState>>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    if !state.authorized(&headers) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    let reply = match serde_json::from_str::<Value>(&body) {
+        Ok(msg) => state.server.handle_message(msg).await,
+        Err(e) => Some(rpc_error(Value::Null, PARSE_ERROR, &e.to_string())),
+    };
+    match reply {
+        Some(reply) => Json(reply).into_response(),
+        None => StatusCode::ACCEPTED.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server() -> McpServer {
+        McpServer::new("/nonexistent/cortex.sock")
+    }
+
+    #[tokio::test]
+    async fn test_initialize_and_list_tools() {
+        let server = server();
+        let init = server
+            .handle_message(json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {"protocolVersion": "2025-03-26"}}))
+            .await
+            .unwrap();
+        assert_eq!(init["result"]["protocolVersion"], "2025-03-26");
+        assert_eq!(init["result"]["serverInfo"]["name"], "cortex");
+
+        assert!(server
+            .handle_message(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))
+            .await
+            .is_none());
+
+        let list = server
+            .handle_message(json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}))
+            .await
+            .unwrap();
+        let names: Vec<&str> = list["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "cortex_map",
+                "cortex_query",
+                "cortex_pathfind",
+                "cortex_perceive",
+                "cortex_wql",
+                "cortex_compile"
+            ]
+        );
+
+        let unknown = server
+            .handle_message(json!({"jsonrpc": "2.0", "id": 3, "method": "resources/list"}))
+            .await
+            .unwrap();
+        assert_eq!(unknown["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_tool_errors_are_results() {
+        let server = server();
+        let call = |name: &str, args: Value| json!({"jsonrpc": "2.0", "id": 7, "method": "tools/call", "params": {"name": name, "arguments": args}});
+
+        // The daemon is not running: the call fails as a tool result.
+        let resp = server
+            .handle_message(call("cortex_map", json!({"domain": "shop.com"})))
+            .await
+            .unwrap();
+        assert_eq!(resp["result"]["isError"], true);
+        assert!(resp["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("not running"));
+        let error = &resp["result"]["structuredContent"]["error"];
+        assert_eq!(error["code"], "E_UNAVAILABLE");
+        assert_eq!(error["retryable"], true);
+
+        let resp = server
+            .handle_message(call("cortex_pathfind", json!({"domain": "shop.com"})))
+            .await
+            .unwrap();
+        assert!(resp["result"].get("structuredContent").is_none());
+        assert!(resp["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("from_node is required"));
+
+        let resp = server
+            .handle_message(call("cortex_act", json!({})))
+            .await
+            .unwrap();
+        assert_eq!(resp["error"]["code"], INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_query_params_shorthand() {
+        let params = query_params(
+            &json!({"domain": "shop.com", "price_lt": 100, "price_gt": 10, "rating_gt": 0.8}),
+        )
+        .unwrap();
+        assert_eq!(params["limit"], 20);
+        assert_eq!(params["features"]["48"], json!({"lt": 100, "gt": 10}));
+        assert_eq!(params["features"]["52"], json!({"gt": 0.8}));
+
+        let saved =
+            query_params(&json!({"domain": "shop.com", "saved": "cheap", "price_lt": 5})).unwrap();
+        assert_eq!(saved, json!({"domain": "shop.com", "saved": "cheap"}));
+        let semantic =
+            query_params(&json!({"domain": "shop.com", "goal": "returns", "price_lt": 5})).unwrap();
+        assert_eq!(
+            semantic,
+            json!({"domain": "shop.com", "mode": "semantic", "goal": "returns", "limit": 10})
+        );
+        assert!(query_params(&json!({})).is_err());
+    }
+}
@@ -0,0 +1,312 @@
+//! Learned action costs from execution outcomes.
+//!
+//! Every ACT execution reports whether it succeeded and how long it took.
+//! The pathfinder folds these outcomes into edge weights, so routes drift
+//! away from slow or flaky steps as evidence accumulates.
+
+use crate::map::types::OpCode;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// One second of observed latency costs as much as one extra hop.
+const LATENCY_UNIT_MS: f32 = 1000.0;
+
+/// Success rate floor, so a step that has only ever failed stays finite.
+const MIN_SUCCESS_RATE: f32 = 0.05;
+
+/// The outcome of one executed action.
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    /// Node the action was executed on.
+    pub node: u32,
+    /// Node the action leads to, if known.
+    pub target: Option<u32>,
+    pub opcode: OpCode,
+    pub success: bool,
+    pub latency_ms: u64,
+}
+
+/// Running totals for one edge or action.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct OutcomeStats {
+    pub attempts: u32,
+    pub failures: u32,
+    pub total_latency_ms: u64,
+}
+
+impl OutcomeStats {
+    fn add(&mut self, success: bool, latency_ms: u64) {
+        self.attempts += 1;
+        self.failures += u32::from(!success);
+        self.total_latency_ms += latency_ms;
+    }
+
+    /// Estimated probability of failure, counting one implicit success so a
+    /// single failed attempt does not condemn a step outright.
+    pub fn failure_probability(&self) -> f32 {
+        self.failures as f32 / (self.attempts + 1) as f32
+    }
+
+    /// Mean observed latency in milliseconds.
+    pub fn mean_latency_ms(&self) -> f32 {
+        if self.attempts == 0 {
+            return 0.0;
+        }
+        self.total_latency_ms as f32 / self.attempts as f32
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct EdgeEntry {
+    from: u32,
+    to: u32,
+    #[serde(flatten)]
+    stats: OutcomeStats,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ActionEntry {
+    node: u32,
+    opcode: OpCode,
+    #[serde(flatten)]
+    stats: OutcomeStats,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct CostModelFile {
+    edges: Vec<EdgeEntry>,
+    actions: Vec<ActionEntry>,
+}
+
+/// Learned costs for one domain's map.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "CostModelFile", into = "CostModelFile")]
+pub struct CostModel {
+    edges: HashMap<(u32, u32), OutcomeStats>,
+    actions: HashMap<(u32, u16), OutcomeStats>,
+}
+
+impl CostModel {
+    /// Record an execution outcome against its action and, when the target
+    /// is known, the edge it traverses.
+    pub fn record(&mut self, outcome: &Outcome) {
+        self.actions
+            .entry((outcome.node, outcome.opcode.as_u16()))
+            .or_default()
+            .add(outcome.success, outcome.latency_ms);
+        if let Some(target) = outcome.target {
+            self.edges
+                .entry((outcome.node, target))
+                .or_default()
+                .add(outcome.success, outcome.latency_ms);
+        }
+    }
+
+    /// Observed outcomes for the edge `from → to`.
+    pub fn edge(&self, from: u32, to: u32) -> Option<&OutcomeStats> {
+        self.edges.get(&(from, to))
+    }
+
+    /// Observed outcomes for `opcode` executed on `node`.
+    pub fn action(&self, node: u32, opcode: OpCode) -> Option<&OutcomeStats> {
+        self.actions.get(&(node, opcode.as_u16()))
+    }
+
+    /// Expected cost of the edge `from → to` given its static `base` cost:
+    /// observed latency is added, then the total is scaled by the expected
+    /// number of attempts until success. Unobserved edges keep `base`.
+    pub fn weigh(&self, from: u32, to: u32, base: f32) -> f32 {
+        let Some(stats) = self.edge(from, to) else {
+            return base;
+        };
+        let success = (1.0 - stats.failure_probability()).max(MIN_SUCCESS_RATE);
+        (base + stats.mean_latency_ms() / LATENCY_UNIT_MS) / success
+    }
+
+    /// Number of distinct edges with recorded outcomes.
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+}
+
+impl From<CostModelFile> for CostModel {
+    fn from(file: CostModelFile) -> Self {
+        Self {
+            edges: file
+                .edges
+                .into_iter()
+                .map(|e| ((e.from, e.to), e.stats))
+                .collect(),
+            actions: file
+                .actions
+                .into_iter()
+                .map(|a| ((a.node, a.opcode.as_u16()), a.stats))
+                .collect(),
+        }
+    }
+}
+
+impl From<CostModel> for CostModelFile {
+    fn from(model: CostModel) -> Self {
+        let mut edges: Vec<EdgeEntry> = model
+            .edges
+            .into_iter()
+            .map(|((from, to), stats)| EdgeEntry { from, to, stats })
+            .collect();
+        edges.sort_by_key(|e| (e.from, e.to));
+        let mut actions: Vec<ActionEntry> = model
+            .actions
+            .into_iter()
+            .map(|((node, opcode), stats)| ActionEntry {
+                node,
+                opcode: OpCode::from_u16(opcode),
+                stats,
+            })
+            .collect();
+        actions.sort_by_key(|a| (a.node, a.opcode.as_u16()));
+        Self { edges, actions }
+    }
+}
+
+/// Learned cost models for every domain, optionally backed by a file.
+#[derive(Debug, Default)]
+pub struct ActionCosts {
+    domains: HashMap<String, Arc<CostModel>>,
+    path: Option<PathBuf>,
+}
+
+impl ActionCosts {
+    /// An empty, in-memory store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load models from a JSON file keyed by domain. A missing file is an
+    /// empty store; either way, [`ActionCosts::persist`] writes back to `path`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut costs = Self {
+            path: Some(path.to_path_buf()),
+            ..Self::default()
+        };
+        if path.exists() {
+            let data = std::fs::read_to_string(path)?;
+            let domains: HashMap<String, CostModel> = serde_json::from_str(&data)
+                .with_context(|| format!("parsing action costs: {}", path.display()))?;
+            costs.domains = domains
+                .into_iter()
+                .map(|(domain, model)| (domain, Arc::new(model)))
+                .collect();
+        }
+        Ok(costs)
+    }
+
+    /// Load models from the default location (~/.cortex/action_costs.json).
+    pub fn load_default() -> Result<Self> {
+        Self::load(&default_path())
+    }
+
+    /// Write models back to the file they were loaded from, if any.
+    pub fn persist(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let domains: std::collections::BTreeMap<&String, &CostModel> = self
+            .domains
+            .iter()
+            .map(|(domain, model)| (domain, model.as_ref()))
+            .collect();
+        std::fs::write(path, serde_json::to_string_pretty(&domains)?)?;
+        Ok(())
+    }
+
+    /// The learned model for `domain`, if any outcomes were recorded.
+    pub fn get(&self, domain: &str) -> Option<Arc<CostModel>> {
+        self.domains.get(domain).cloned()
+    }
+
+    /// Record an execution outcome for `domain`.
+    pub fn record(&mut self, domain: &str, outcome: &Outcome) {
+        let model = self.domains.entry(domain.to_string()).or_default();
+        Arc::make_mut(model).record(outcome);
+    }
+
+    /// Forget everything learned about `domain` (e.g. after a re-map
+    /// renumbers its nodes).
+    pub fn clear(&mut self, domain: &str) -> bool {
+        self.domains.remove(domain).is_some()
+    }
+}
+
+/// Default action cost file location.
+pub fn default_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join(".cortex")
+        .join("action_costs.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(success: bool, latency_ms: u64) -> Outcome {
+        Outcome {
+            node: 1,
+            target: Some(2),
+            opcode: OpCode::new(0x02, 0x00),
+            success,
+            latency_ms,
+        }
+    }
+
+    #[test]
+    fn test_weigh_penalizes_slow_and_flaky_edges() {
+        let mut model = CostModel::default();
+        assert_eq!(model.weigh(1, 2, 1.0), 1.0);
+
+        model.record(&outcome(true, 2000));
+        // One second per hop: 1 + 2 = 3, always succeeded so far.
+        assert_eq!(model.weigh(1, 2, 1.0), 3.0);
+
+        model.record(&outcome(false, 2000));
+        model.record(&outcome(false, 2000));
+        // Two failures in three attempts (plus one implicit success).
+        let stats = model.edge(1, 2).unwrap();
+        assert_eq!(stats.failure_probability(), 0.5);
+        assert_eq!(model.weigh(1, 2, 1.0), 6.0);
+        assert_eq!(
+            model.action(1, OpCode::new(0x02, 0x00)).unwrap().attempts,
+            3
+        );
+        assert_eq!(model.weigh(2, 1, 1.0), 1.0);
+    }
+
+    #[test]
+    fn test_action_costs_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("costs.json");
+
+        let mut costs = ActionCosts::load(&path).unwrap();
+        costs.record("shop.com", &outcome(true, 300));
+        costs.record("shop.com", &outcome(false, 100));
+        costs.persist().unwrap();
+
+        let loaded = ActionCosts::load(&path).unwrap();
+        let model = loaded.get("shop.com").unwrap();
+        assert_eq!(
+            model.edge(1, 2),
+            Some(&OutcomeStats {
+                attempts: 2,
+                failures: 1,
+                total_latency_ms: 400,
+            })
+        );
+        assert!(loaded.get("other.com").is_none());
+    }
+}
@@ -0,0 +1,173 @@
+//! Result diversification: collapse near-identical query results.
+//!
+//! Listing-heavy sites return hundreds of pages that differ only in an id or
+//! a page number. Results are grouped by URL template and, within a
+//! template, by feature similarity; each group is represented by its
+//! highest-ranked member.
+
+use crate::map::types::{NodeMatch, FEATURE_DIM};
+use serde::Serialize;
+
+/// Default cosine similarity at which two same-template results are merged.
+pub const DEFAULT_SIMILARITY: f32 = 0.9;
+
+/// A representative result and the near-identical results it stands for.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResultGroup {
+    /// The highest-ranked member.
+    pub representative: NodeMatch,
+    /// URL template shared by the members (e.g. `/p/{id}?page={}`).
+    pub template: String,
+    /// Number of results in the group, including the representative.
+    pub size: usize,
+    /// Node indexes of every member, in result order.
+    pub members: Vec<u32>,
+}
+
+/// Group `results` (in ranked order) by URL template and feature similarity.
+///
+/// `features` returns a node's feature vector. Two results share a group
+/// when their templates match and their features have cosine similarity of
+/// at least `threshold` with the group's representative.
+pub fn diversify(
+    results: Vec<NodeMatch>,
+    features: impl Fn(u32) -> [f32; FEATURE_DIM],
+    threshold: f32,
+) -> Vec<ResultGroup> {
+    let mut groups: Vec<ResultGroup> = Vec::new();
+    let mut leaders: Vec<[f32; FEATURE_DIM]> = Vec::new();
+    for result in results {
+        let template = url_template(&result.url);
+        let vector = features(result.index);
+        let existing = groups
+            .iter()
+            .zip(&leaders)
+            .position(|(g, leader)| g.template == template && cosine(leader, &vector) >= threshold);
+        match existing {
+            Some(i) => {
+                groups[i].size += 1;
+                groups[i].members.push(result.index);
+            }
+            None => {
+                groups.push(ResultGroup {
+                    members: vec![result.index],
+                    representative: result,
+                    template,
+                    size: 1,
+                });
+                leaders.push(vector);
+            }
+        }
+    }
+    groups
+}
+
+/// The URL's path and query with variable parts blanked out: numeric,
+/// hex and UUID-like path segments become `{id}`, and query values become
+/// `{}` (keys are kept, sorted). The scheme and host are dropped.
+pub fn url_template(url: &str) -> String {
+    let Ok(parsed) = url::Url::parse(url) else {
+        return url.to_string();
+    };
+    let path: Vec<&str> = parsed
+        .path()
+        .split('/')
+        .map(|seg| if is_identifier(seg) { "{id}" } else { seg })
+        .collect();
+    let mut template = path.join("/");
+    let mut keys: Vec<String> = parsed.query_pairs().map(|(k, _)| k.into_owned()).collect();
+    keys.sort();
+    keys.dedup();
+    if !keys.is_empty() {
+        let query: Vec<String> = keys.iter().map(|k| format!("{k}={{}}")).collect();
+        template.push('?');
+        template.push_str(&query.join("&"));
+    }
+    template
+}
+
+/// Whether a path segment looks like an identifier rather than a name.
+fn is_identifier(segment: &str) -> bool {
+    let stem = segment.split('.').next().unwrap_or(segment);
+    if stem.is_empty() {
+        return false;
+    }
+    let digits = stem.chars().filter(|c| c.is_ascii_digit()).count();
+    let hex_like = stem.len() >= 8 && stem.chars().all(|c| c.is_ascii_hexdigit() || c == '-');
+    digits == stem.len() || (hex_like && digits > 0) || digits * 2 > stem.len()
+}
+
+fn cosine(a: &[f32; FEATURE_DIM], b: &[f32; FEATURE_DIM]) -> f32 {
+    let (mut dot, mut na, mut nb) = (0.0f32, 0.0f32, 0.0f32);
+    for (x, y) in a.iter().zip(b) {
+        dot += x * y;
+        na += x * x;
+        nb += y * y;
+    }
+    if na == 0.0 && nb == 0.0 {
+        return 1.0;
+    }
+    if na == 0.0 || nb == 0.0 {
+        return 0.0;
+    }
+    dot / (na.sqrt() * nb.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::types::PageType;
+
+    fn result(index: u32, url: &str) -> NodeMatch {
+        NodeMatch {
+            index,
+            url: url.to_string(),
+            page_type: PageType::ProductListing,
+            confidence: 1.0,
+            features: Vec::new(),
+            similarity: None,
+            text_score: None,
+        }
+    }
+
+    #[test]
+    fn test_url_template() {
+        assert_eq!(
+            url_template("https://shop.com/c/shoes?page=3&sort=price"),
+            "/c/shoes?page={}&sort={}"
+        );
+        assert_eq!(url_template("https://shop.com/p/12345"), "/p/{id}");
+        assert_eq!(
+            url_template("https://shop.com/item/a1b2c3d4e5f6/reviews"),
+            "/item/{id}/reviews"
+        );
+        assert_eq!(
+            url_template("https://shop.com/blog/2024-summer-sale"),
+            "/blog/2024-summer-sale"
+        );
+    }
+
+    #[test]
+    fn test_diversify_groups_near_identical_results() {
+        let mut results: Vec<NodeMatch> = (0..6)
+            .map(|i| result(i, &format!("https://shop.com/c/shoes?page={i}")))
+            .collect();
+        results.push(result(6, "https://shop.com/c/bags?page=1"));
+        results.push(result(7, "https://shop.com/c/shoes?page=9"));
+
+        // Node 7 shares the template but its features point elsewhere.
+        let features = |i: u32| {
+            let mut f = [0.0f32; FEATURE_DIM];
+            f[if i == 7 { 1 } else { 0 }] = 1.0;
+            f
+        };
+        let groups = diversify(results, features, DEFAULT_SIMILARITY);
+
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].representative.index, 0);
+        assert_eq!(groups[0].size, 6);
+        assert_eq!(groups[0].template, "/c/shoes?page={}");
+        assert_eq!(groups[1].members, vec![6]);
+        assert_eq!(groups[2].members, vec![7]);
+    }
+}
@@ -1,6 +1,10 @@
-//! Navigation engine: query, pathfinding, similarity search, and clustering.
+//! Navigation engine: query, pathfinding, similarity search, clustering, and
+//! structural analysis.
 
 pub mod cluster;
+pub mod cost_model;
+pub mod diversify;
 pub mod pathfinder;
 pub mod query;
+pub mod reachability;
 pub mod similarity;
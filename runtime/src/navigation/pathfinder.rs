@@ -1,16 +1,235 @@
 //! Pathfinding engine for navigating between nodes in a SiteMap.
 
-use crate::map::types::{Path, PathConstraints, SiteMap};
+use crate::map::types::{PageType, Path, PathConstraints, SiteMap, FEATURE_DIM};
+use serde::Serialize;
+
+/// Goal sets up to this size are ordered exactly (Held-Karp); larger sets
+/// are visited in nearest-first order.
+const EXACT_ROUTE_LIMIT: usize = 12;
+
+/// How many of the most similar nodes are tried for a feature-vector goal.
+const GOAL_CANDIDATES: usize = 16;
+
+/// A destination described by what it is rather than by node index.
+#[derive(Debug, Clone)]
+pub enum Goal {
+    /// The closest reachable page of this type.
+    PageType(PageType),
+    /// The most similar reachable page to this feature vector.
+    Features(Box<[f32; FEATURE_DIM]>),
+}
 
 /// Find the shortest path between two nodes.
 ///
 /// Uses Dijkstra's algorithm on the SiteMap's CSR edge structure.
-/// Respects path constraints (avoid auth, avoid state changes).
+/// Respects path constraints (avoid auth, avoid state changes, avoided
+/// nodes and page types, max hops, max risk).
 /// Weight mode controls what is minimized (hops, weight, state changes).
 pub fn find_path(map: &SiteMap, from: u32, to: u32, constraints: &PathConstraints) -> Option<Path> {
     map.shortest_path(from, to, constraints)
 }
 
+/// Find a path from `from` to the node best matching `goal`.
+///
+/// A page-type goal picks the cheapest reachable page of that type ("nearest
+/// checkout from this product"). A feature goal tries the most similar
+/// pages by cosine similarity, best first, and takes the first reachable
+/// one. Returns the chosen node and the path to it.
+pub fn find_goal(
+    map: &SiteMap,
+    from: u32,
+    goal: &Goal,
+    constraints: &PathConstraints,
+) -> Option<(u32, Path)> {
+    if from as usize >= map.nodes.len() {
+        return None;
+    }
+    let tree = map.path_tree(from, constraints, &[], None);
+    match goal {
+        Goal::PageType(page_type) => map
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(i, node)| node.page_type == *page_type && *i as u32 != from)
+            .filter_map(|(i, _)| tree.path_to(i as u32).map(|path| (i as u32, path)))
+            .min_by(|a, b| {
+                a.1.total_weight
+                    .partial_cmp(&b.1.total_weight)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            }),
+        Goal::Features(target) => map
+            .nearest(target, GOAL_CANDIDATES)
+            .into_iter()
+            .filter(|m| m.index != from && m.similarity.is_some_and(|s| s > 0.0))
+            .find_map(|m| tree.path_to(m.index).map(|path| (m.index, path))),
+    }
+}
+
+/// A route visiting several goals.
+#[derive(Debug, Clone, Serialize)]
+pub struct Route {
+    /// The full path, legs concatenated.
+    pub path: Path,
+    /// Goals in the order they are visited (including the end node, if any).
+    pub order: Vec<u32>,
+}
+
+/// Find the shortest route from `from` that visits every node in `goals`,
+/// in any order, optionally finishing at `end`.
+///
+/// Constraints apply to every leg; `max_hops` bounds the whole route.
+/// Returns `None` if some goal cannot be reached.
+pub fn find_route(
+    map: &SiteMap,
+    from: u32,
+    goals: &[u32],
+    end: Option<u32>,
+    constraints: &PathConstraints,
+) -> Option<Route> {
+    let n = map.nodes.len() as u32;
+    let mut targets: Vec<u32> = Vec::new();
+    for &g in goals {
+        if g != from && Some(g) != end && !targets.contains(&g) {
+            targets.push(g);
+        }
+    }
+    if from >= n || targets.iter().chain(end.iter()).any(|&g| g >= n) {
+        return None;
+    }
+
+    // Point 0 is the start, then the goals, then the optional end.
+    let mut points = vec![from];
+    points.extend(&targets);
+    points.extend(end);
+    let trees: Vec<_> = points[..=targets.len()]
+        .iter()
+        .map(|&p| map.path_tree(p, constraints, &points, None))
+        .collect();
+    let legs: Vec<Vec<Option<Path>>> = trees
+        .iter()
+        .map(|tree| points.iter().map(|&p| tree.path_to(p)).collect())
+        .collect();
+    let cost = |i: usize, j: usize| legs[i][j].as_ref().map(|p| p.total_weight);
+
+    let k = targets.len();
+    let end_point = end.map(|_| k + 1);
+    let mut order: Vec<usize> = if k <= EXACT_ROUTE_LIMIT {
+        exact_order(k, &cost, end_point)?
+    } else {
+        nearest_order(k, &cost)?
+    };
+    order.extend(end_point);
+
+    let mut path = Path {
+        nodes: vec![from],
+        total_weight: 0.0,
+        hops: 0,
+        required_actions: Vec::new(),
+    };
+    let mut at = 0;
+    for &next in &order {
+        let leg = legs[at][next].as_ref()?;
+        path.nodes.extend(&leg.nodes[1..]);
+        path.total_weight += leg.total_weight;
+        path.hops += leg.hops;
+        at = next;
+    }
+    if constraints.max_hops.is_some_and(|max| path.hops > max) {
+        return None;
+    }
+
+    Some(Route {
+        path,
+        order: order.iter().map(|&i| points[i]).collect(),
+    })
+}
+
+/// Cheapest visiting order of points `1..=k` starting from point 0 and
+/// finishing at `end`, by dynamic programming over subsets.
+fn exact_order(
+    k: usize,
+    cost: &impl Fn(usize, usize) -> Option<f32>,
+    end: Option<usize>,
+) -> Option<Vec<usize>> {
+    if k == 0 {
+        return match end {
+            Some(e) => cost(0, e).map(|_| Vec::new()),
+            None => Some(Vec::new()),
+        };
+    }
+
+    let full = (1usize << k) - 1;
+    // best[mask][j]: cheapest cost to visit `mask`, ending at goal j
+    let mut best = vec![vec![f32::INFINITY; k]; full + 1];
+    let mut parent = vec![vec![usize::MAX; k]; full + 1];
+    for j in 0..k {
+        if let Some(c) = cost(0, j + 1) {
+            best[1 << j][j] = c;
+        }
+    }
+    for mask in 1..=full {
+        for j in 0..k {
+            let base = best[mask][j];
+            if mask & (1 << j) == 0 || base.is_infinite() {
+                continue;
+            }
+            for next in 0..k {
+                if mask & (1 << next) != 0 {
+                    continue;
+                }
+                let Some(c) = cost(j + 1, next + 1) else {
+                    continue;
+                };
+                let m = mask | (1 << next);
+                if base + c < best[m][next] {
+                    best[m][next] = base + c;
+                    parent[m][next] = j;
+                }
+            }
+        }
+    }
+
+    let finish = |j: usize| match end {
+        Some(e) => cost(j + 1, e).map(|c| best[full][j] + c),
+        None => Some(best[full][j]),
+    };
+    let mut last = (0..k)
+        .filter_map(|j| finish(j).filter(|c| c.is_finite()).map(|c| (j, c)))
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))?
+        .0;
+
+    let mut order = Vec::with_capacity(k);
+    let mut mask = full;
+    loop {
+        order.push(last + 1);
+        let p = parent[mask][last];
+        mask &= !(1 << last);
+        if p == usize::MAX {
+            break;
+        }
+        last = p;
+    }
+    order.reverse();
+    Some(order)
+}
+
+/// Visit points `1..=k` nearest-first from point 0.
+fn nearest_order(k: usize, cost: &impl Fn(usize, usize) -> Option<f32>) -> Option<Vec<usize>> {
+    let mut remaining: Vec<usize> = (1..=k).collect();
+    let mut order = Vec::with_capacity(k);
+    let mut at = 0;
+    while !remaining.is_empty() {
+        let (pos, _) = remaining
+            .iter()
+            .enumerate()
+            .filter_map(|(pos, &p)| cost(at, p).map(|c| (pos, c)))
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))?;
+        at = remaining.swap_remove(pos);
+        order.push(at);
+    }
+    Some(order)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -115,4 +334,188 @@ mod tests {
         let path = find_path(&map, 0, 2, &constraints);
         assert!(path.is_none());
     }
+
+    #[test]
+    fn test_find_path_learned_costs() {
+        use crate::navigation::cost_model::{CostModel, Outcome};
+
+        let map = build_path_map();
+        let mut model = CostModel::default();
+        // 1 → 2 keeps failing, so the heavier shortcut through 4 wins.
+        for attempt in 0..8 {
+            model.record(&Outcome {
+                node: 1,
+                target: Some(2),
+                opcode: OpCode::new(0x01, 0x00),
+                success: attempt == 0,
+                latency_ms: 1000,
+            });
+        }
+        let constraints = PathConstraints {
+            minimize: PathMinimize::Weight,
+            learned_costs: Some(std::sync::Arc::new(model)),
+            ..Default::default()
+        };
+        let path = find_path(&map, 0, 3, &constraints).unwrap();
+        assert_eq!(path.nodes, vec![0, 4, 3]);
+    }
+
+    #[test]
+    fn test_find_goal() {
+        let mut map = build_path_map();
+        assert_eq!(map.find_url("https://test.com/b/#reviews"), Some(2));
+
+        // Both product pages are reachable; 4 is one hop away.
+        let (node, path) = find_goal(
+            &map,
+            0,
+            &Goal::PageType(PageType::ProductDetail),
+            &PathConstraints::default(),
+        )
+        .unwrap();
+        assert_eq!(node, 4);
+        assert_eq!(path.nodes, vec![0, 4]);
+
+        // The most similar page (node 2) wins over closer ones.
+        let mut target = [0.0f32; FEATURE_DIM];
+        target[FEAT_PRICE] = 1.0;
+        map.features[2][FEAT_PRICE] = 1.0;
+        map.nodes[2].feature_norm = 1.0;
+        map.features[4][FEAT_RATING] = 1.0;
+        map.nodes[4].feature_norm = 1.0;
+        let (node, path) = find_goal(
+            &map,
+            0,
+            &Goal::Features(Box::new(target)),
+            &PathConstraints::default(),
+        )
+        .unwrap();
+        assert_eq!(node, 2);
+        assert_eq!(path.hops, 2);
+
+        // Nothing of that type is reachable from the end of the chain.
+        let goal = Goal::PageType(PageType::Article);
+        assert!(find_goal(&map, 3, &goal, &PathConstraints::default()).is_none());
+    }
+
+    #[test]
+    fn test_find_path_avoid_lists_and_max_hops() {
+        let map = build_path_map();
+
+        // Avoiding node 1 forces the shortcut through 4.
+        let constraints = PathConstraints {
+            avoid_nodes: vec![1],
+            minimize: PathMinimize::Weight,
+            ..Default::default()
+        };
+        let path = find_path(&map, 0, 3, &constraints).unwrap();
+        assert_eq!(path.nodes, vec![0, 4, 3]);
+
+        // Avoiding product pages rules out 4, but the product target is exempt.
+        let constraints = PathConstraints {
+            avoid_page_types: vec![PageType::ProductDetail],
+            ..Default::default()
+        };
+        let path = find_path(&map, 0, 3, &constraints).unwrap();
+        assert_eq!(path.nodes, vec![0, 1, 2, 3]);
+
+        // The cheapest path by weight has 3 hops; a 2-hop bound picks the other.
+        let constraints = PathConstraints {
+            minimize: PathMinimize::Weight,
+            max_hops: Some(2),
+            ..Default::default()
+        };
+        let path = find_path(&map, 0, 3, &constraints).unwrap();
+        assert_eq!(path.nodes, vec![0, 4, 3]);
+        assert_eq!(path.total_weight, 10.0);
+
+        let constraints = PathConstraints {
+            max_hops: Some(1),
+            ..Default::default()
+        };
+        assert!(find_path(&map, 0, 3, &constraints).is_none());
+    }
+
+    #[test]
+    fn test_find_path_max_risk() {
+        let mut builder = SiteMapBuilder::new("test.com");
+        let feats = [0.0f32; FEATURE_DIM];
+        builder.add_node("https://test.com/p", PageType::ProductDetail, feats, 255);
+        builder.add_node("https://test.com/cart", PageType::Cart, feats, 200);
+        builder.add_node("https://test.com/done", PageType::Checkout, feats, 200);
+        builder.add_edge(
+            0,
+            1,
+            EdgeType::Navigation,
+            1,
+            EdgeFlags(EdgeFlags::CHANGES_STATE),
+        );
+        builder.add_edge(
+            1,
+            2,
+            EdgeType::Navigation,
+            1,
+            EdgeFlags(EdgeFlags::CHANGES_STATE),
+        );
+        builder.add_action(0, OpCode::new(0x02, 0x00), 1, 0, 1);
+        builder.add_action(1, OpCode::new(0x02, 0x05), 2, 0, 2);
+        let map = builder.build();
+
+        let cautious = PathConstraints {
+            max_risk: Some(1),
+            ..Default::default()
+        };
+        assert!(find_path(&map, 0, 1, &cautious).is_some());
+        assert!(find_path(&map, 0, 2, &cautious).is_none());
+        let safe = PathConstraints {
+            max_risk: Some(0),
+            ..Default::default()
+        };
+        assert!(find_path(&map, 0, 1, &safe).is_none());
+    }
+
+    #[test]
+    fn test_find_route_visits_all_goals() {
+        // Star with a hub: 0 <-> 1, 0 <-> 2, 0 <-> 3, and 1 -> 2 directly.
+        let mut builder = SiteMapBuilder::new("test.com");
+        let feats = [0.0f32; FEATURE_DIM];
+        for url in ["/", "/a", "/b", "/c"] {
+            builder.add_node(
+                &format!("https://test.com{url}"),
+                PageType::Article,
+                feats,
+                200,
+            );
+        }
+        for leaf in 1..4 {
+            builder.add_edge(0, leaf, EdgeType::Navigation, 1, EdgeFlags::default());
+            builder.add_edge(leaf, 0, EdgeType::Navigation, 1, EdgeFlags::default());
+        }
+        builder.add_edge(1, 2, EdgeType::Related, 1, EdgeFlags::default());
+        let map = builder.build();
+        let constraints = PathConstraints::default();
+
+        let route = find_route(&map, 0, &[2, 3, 1], None, &constraints).unwrap();
+        // Visiting 1 then 2 saves a hop; 3 goes first or last.
+        assert_eq!(route.path.hops, 4);
+        let pos = |g| route.order.iter().position(|&o| o == g).unwrap();
+        assert_eq!(pos(2), pos(1) + 1);
+        assert_eq!(route.path.nodes[0], 0);
+
+        let route = find_route(&map, 0, &[1, 2], Some(3), &constraints).unwrap();
+        assert_eq!(route.order, vec![1, 2, 3]);
+        assert_eq!(route.path.nodes, vec![0, 1, 2, 0, 3]);
+
+        // Greedy ordering kicks in past the exact limit and still covers every goal.
+        assert_eq!(
+            nearest_order(3, &|_, j| Some(j as f32)),
+            Some(vec![1, 2, 3])
+        );
+
+        let bounded = PathConstraints {
+            max_hops: Some(3),
+            ..Default::default()
+        };
+        assert!(find_route(&map, 0, &[2, 3, 1], None, &bounded).is_none());
+    }
 }
@@ -1,6 +1,11 @@
 //! Query engine for filtering and searching nodes in a SiteMap.
 
+use crate::map::text::MapText;
 use crate::map::types::{NodeMatch, NodeQuery, SiteMap};
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Execute a query against a SiteMap.
 ///
@@ -8,16 +13,86 @@ use crate::map::types::{NodeMatch, NodeQuery, SiteMap};
 /// - page_type (single or list)
 /// - feature ranges (dimension -> min/max)
 /// - flag requirements
+/// - words in page titles and descriptions, ranked by BM25
 /// - sorting by any feature dimension
 /// - result limiting
 pub fn execute(map: &SiteMap, query: &NodeQuery) -> Vec<NodeMatch> {
-    map.filter(query)
+    search(&map.text, query, |q| map.filter(q))
+}
+
+/// Run `query` with `filter` for everything but its `text`, then keep and
+/// rank the pages `text` matches. Works for maps in any representation.
+pub fn search(
+    text: &MapText,
+    query: &NodeQuery,
+    filter: impl FnOnce(&NodeQuery) -> Vec<NodeMatch>,
+) -> Vec<NodeMatch> {
+    let Some(words) = query.text.as_deref().filter(|t| !t.trim().is_empty()) else {
+        return filter(query);
+    };
+    // The other filters first, then the pages among them the text matches
+    let unlimited = NodeQuery {
+        limit: 0,
+        ..query.clone()
+    };
+    let scores: HashMap<u32, f32> = text.search(words).into_iter().collect();
+    let mut matches: Vec<NodeMatch> = filter(&unlimited)
+        .into_iter()
+        .filter_map(|mut m| {
+            m.text_score = Some(*scores.get(&m.index)?);
+            Some(m)
+        })
+        .collect();
+    if query.sort_by_feature.is_none() {
+        let score = |m: &NodeMatch| m.text_score.unwrap_or(0.0);
+        matches.sort_by(|a, b| score(b).total_cmp(&score(a)));
+    }
+    if query.limit > 0 {
+        matches.truncate(query.limit);
+    }
+    matches
+}
+
+/// A named, reusable filter stored in the registry, so agents and humans
+/// can share vetted queries (`cortex query --save cheap-in-stock ...`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedQuery {
+    /// Name used to re-run the query (letters, digits, `-` and `_`).
+    pub name: String,
+    /// The filter itself, including its limit.
+    pub query: NodeQuery,
+    /// Collapse near-identical results (see [`crate::navigation::diversify`]).
+    #[serde(default)]
+    pub diversify: bool,
+    /// When the query was saved.
+    pub created_at: DateTime<Utc>,
+}
+
+impl SavedQuery {
+    /// A saved query named `name`. Rejects names that are empty or contain
+    /// anything but letters, digits, `-` and `_`.
+    pub fn new(name: &str, query: NodeQuery, diversify: bool) -> Result<Self> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            bail!("invalid query name '{name}': use letters, digits, '-' and '_'");
+        }
+        Ok(Self {
+            name: name.to_string(),
+            query,
+            diversify,
+            created_at: Utc::now(),
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::map::builder::SiteMapBuilder;
+    use crate::map::text::NodeText;
     use crate::map::types::*;
 
     fn build_test_map() -> SiteMap {
@@ -80,6 +155,28 @@ mod tests {
             .all(|r| r.page_type == PageType::ProductDetail));
     }
 
+    #[test]
+    fn test_query_leaves_out_error_pages() {
+        let mut map = build_test_map();
+        map.nodes[8].page_type = PageType::ErrorPage;
+        let all = NodeQuery {
+            limit: 100,
+            ..Default::default()
+        };
+        let results = execute(&map, &all);
+        assert_eq!(results.len(), 8);
+        assert!(results.iter().all(|r| r.page_type != PageType::ErrorPage));
+
+        let errors = NodeQuery {
+            page_types: Some(vec![PageType::ErrorPage]),
+            limit: 100,
+            ..Default::default()
+        };
+        let results = execute(&map, &errors);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].index, 8);
+    }
+
     #[test]
     fn test_query_with_feature_range() {
         let map = build_test_map();
@@ -130,4 +227,44 @@ mod tests {
         let results = execute(&map, &query);
         assert_eq!(results.len(), 4); // 1 home + 3 articles
     }
+
+    #[test]
+    fn test_query_with_text() {
+        let mut map = build_test_map();
+        let titles = [
+            "Wired headphones",
+            "Wireless headphones",
+            "Phone case",
+            "Wireless charger",
+        ];
+        for (i, title) in titles.iter().enumerate() {
+            map.text
+                .insert(i as u32 + 1, NodeText::new([Some(*title)], [None]));
+        }
+        map.text.insert(
+            6,
+            NodeText::new([Some("Best wireless headphones of the year")], [None]),
+        );
+
+        let query = NodeQuery {
+            text: Some("wireless headphones".to_string()),
+            limit: 100,
+            ..Default::default()
+        };
+        let results = execute(&map, &query);
+        let nodes: Vec<u32> = results.iter().map(|r| r.index).collect();
+        assert_eq!(nodes.len(), 4);
+        assert_eq!(nodes[0], 2);
+        assert!(results.iter().all(|r| r.text_score.is_some()));
+
+        // Feature filters narrow the text matches; the limit applies last
+        let query = NodeQuery {
+            page_types: Some(vec![PageType::ProductDetail]),
+            limit: 1,
+            ..query
+        };
+        let results = execute(&map, &query);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].index, 2);
+    }
 }
@@ -0,0 +1,294 @@
+//! Structural analysis of a SiteMap: reachability, dead ends, choke points.
+//!
+//! Gaps in a crawl show up as structure: pages nothing links to, pages that
+//! link nowhere because they were never rendered, and single pages whose
+//! removal splits the site. The report points at the pages worth re-mapping.
+
+use crate::map::types::{PageType, SiteMap};
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// Why a page is suggested for re-mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemapReason {
+    /// No outgoing links, and the page was never rendered, so its links
+    /// were probably never extracted.
+    UnrenderedDeadEnd,
+    /// Not reachable from the root, and nothing reachable links to it —
+    /// the entry point of a region the crawl only knows from elsewhere
+    /// (e.g. sitemap.xml).
+    OrphanEntry,
+}
+
+/// A page worth re-mapping.
+#[derive(Debug, Clone, Serialize)]
+pub struct RemapTarget {
+    pub node: u32,
+    pub url: String,
+    pub reason: RemapReason,
+}
+
+/// Structural problems found in a map.
+#[derive(Debug, Clone, Serialize)]
+pub struct StructureReport {
+    pub node_count: usize,
+    pub edge_count: usize,
+    /// Node reachability is measured from (the home page, or node 0).
+    pub root: u32,
+    /// Sizes of the weakly connected components, largest first.
+    pub component_sizes: Vec<usize>,
+    /// Nodes not reachable from the root by following links.
+    pub unreachable: Vec<u32>,
+    /// Nodes with no outgoing links.
+    pub dead_ends: Vec<u32>,
+    /// Articulation points: nodes whose removal disconnects the link graph
+    /// (ignoring link direction), by how many nodes they cut off.
+    pub choke_points: Vec<u32>,
+    /// Pages to re-map, in order of the problems above.
+    pub remap_targets: Vec<RemapTarget>,
+}
+
+/// Analyze the link structure of `map`.
+pub fn analyze(map: &SiteMap) -> StructureReport {
+    let n = map.nodes.len();
+    let root = map
+        .nodes
+        .iter()
+        .position(|node| node.page_type == PageType::Home)
+        .unwrap_or(0) as u32;
+
+    let adjacency = undirected_adjacency(map);
+    let reached = reachable_from(map, root);
+    let unreachable: Vec<u32> = (0..n as u32).filter(|&i| !reached[i as usize]).collect();
+    let dead_ends: Vec<u32> = (0..n as u32)
+        .filter(|&i| map.edges_from(i).is_empty())
+        .collect();
+
+    let mut remap_targets: Vec<RemapTarget> = dead_ends
+        .iter()
+        .filter(|&&i| !map.nodes[i as usize].flags.is_rendered())
+        .map(|&i| RemapTarget {
+            node: i,
+            url: map.urls[i as usize].clone(),
+            reason: RemapReason::UnrenderedDeadEnd,
+        })
+        .collect();
+    let mut linked_from_unreachable = vec![false; n];
+    for &i in &unreachable {
+        for edge in map.edges_from(i) {
+            if edge.target_node != i {
+                if let Some(flag) = linked_from_unreachable.get_mut(edge.target_node as usize) {
+                    *flag = true;
+                }
+            }
+        }
+    }
+    remap_targets.extend(
+        unreachable
+            .iter()
+            .filter(|&&i| !linked_from_unreachable[i as usize])
+            .map(|&i| RemapTarget {
+                node: i,
+                url: map.urls[i as usize].clone(),
+                reason: RemapReason::OrphanEntry,
+            }),
+    );
+
+    let mut component_sizes = component_sizes(&adjacency);
+    component_sizes.sort_unstable_by(|a, b| b.cmp(a));
+
+    StructureReport {
+        node_count: n,
+        edge_count: map.edges.len(),
+        root,
+        component_sizes,
+        unreachable,
+        dead_ends,
+        choke_points: articulation_points(&adjacency),
+        remap_targets,
+    }
+}
+
+/// Neighbors of each node with link direction ignored, deduplicated.
+fn undirected_adjacency(map: &SiteMap) -> Vec<Vec<u32>> {
+    let n = map.nodes.len();
+    let mut adjacency = vec![Vec::new(); n];
+    for from in 0..n as u32 {
+        for edge in map.edges_from(from) {
+            let to = edge.target_node;
+            if to != from && (to as usize) < n {
+                adjacency[from as usize].push(to);
+                adjacency[to as usize].push(from);
+            }
+        }
+    }
+    for neighbors in &mut adjacency {
+        neighbors.sort_unstable();
+        neighbors.dedup();
+    }
+    adjacency
+}
+
+/// Which nodes can be reached from `root` along links (breadth-first).
+fn reachable_from(map: &SiteMap, root: u32) -> Vec<bool> {
+    let n = map.nodes.len();
+    let mut seen = vec![false; n];
+    if root as usize >= n {
+        return seen;
+    }
+    seen[root as usize] = true;
+    let mut queue = VecDeque::from([root]);
+    while let Some(node) = queue.pop_front() {
+        for edge in map.edges_from(node) {
+            let t = edge.target_node as usize;
+            if t < n && !seen[t] {
+                seen[t] = true;
+                queue.push_back(edge.target_node);
+            }
+        }
+    }
+    seen
+}
+
+fn component_sizes(adjacency: &[Vec<u32>]) -> Vec<usize> {
+    let mut seen = vec![false; adjacency.len()];
+    let mut sizes = Vec::new();
+    for start in 0..adjacency.len() {
+        if seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        let mut stack = vec![start];
+        let mut size = 0;
+        while let Some(node) = stack.pop() {
+            size += 1;
+            for &next in &adjacency[node] {
+                if !seen[next as usize] {
+                    seen[next as usize] = true;
+                    stack.push(next as usize);
+                }
+            }
+        }
+        sizes.push(size);
+    }
+    sizes
+}
+
+/// Articulation points (Hopcroft–Tarjan), iterative so deep link chains
+/// cannot overflow the stack. Sorted by the number of nodes each one cuts
+/// off from the rest of its component, most first.
+fn articulation_points(adjacency: &[Vec<u32>]) -> Vec<u32> {
+    let n = adjacency.len();
+    const UNVISITED: u32 = u32::MAX;
+    let mut disc = vec![UNVISITED; n];
+    let mut low = vec![0u32; n];
+    let mut subtree = vec![1usize; n];
+    let mut cut_off = vec![0usize; n];
+    let mut timer = 0u32;
+
+    for root in 0..n {
+        if disc[root] != UNVISITED {
+            continue;
+        }
+        disc[root] = timer;
+        low[root] = timer;
+        timer += 1;
+        let mut root_children = 0;
+        // (node, parent, next neighbor position)
+        let mut stack: Vec<(usize, usize, usize)> = vec![(root, usize::MAX, 0)];
+        while let Some(&mut (node, parent, ref mut pos)) = stack.last_mut() {
+            if let Some(&next) = adjacency[node].get(*pos) {
+                *pos += 1;
+                let next = next as usize;
+                if disc[next] == UNVISITED {
+                    disc[next] = timer;
+                    low[next] = timer;
+                    timer += 1;
+                    stack.push((next, node, 0));
+                } else if next != parent {
+                    low[node] = low[node].min(disc[next]);
+                }
+                continue;
+            }
+            stack.pop();
+            if parent == usize::MAX {
+                continue;
+            }
+            low[parent] = low[parent].min(low[node]);
+            subtree[parent] += subtree[node];
+            if parent == root {
+                root_children += 1;
+                cut_off[root] = cut_off[root].max(subtree[node]);
+            } else if low[node] >= disc[parent] {
+                cut_off[parent] += subtree[node];
+            }
+        }
+        if root_children < 2 {
+            cut_off[root] = 0;
+        } else {
+            // Everything but the largest child subtree is cut off from it.
+            cut_off[root] = subtree[root] - 1 - cut_off[root];
+        }
+    }
+
+    let mut points: Vec<u32> = (0..n as u32).filter(|&i| cut_off[i as usize] > 0).collect();
+    points.sort_by_key(|&i| (std::cmp::Reverse(cut_off[i as usize]), i));
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::builder::SiteMapBuilder;
+    use crate::map::types::*;
+
+    #[test]
+    fn test_analyze_structure() {
+        let mut builder = SiteMapBuilder::new("shop.com");
+        let feats = [0.0f32; FEATURE_DIM];
+        let url = |p: &str| format!("https://shop.com/{p}");
+        let home = builder.add_node(&url(""), PageType::Home, feats, 255);
+        let category = builder.add_node(&url("c"), PageType::ProductListing, feats, 200);
+        let a = builder.add_node(&url("p/a"), PageType::ProductDetail, feats, 200);
+        let b = builder.add_node(&url("p/b"), PageType::ProductDetail, feats, 200);
+        let about = builder.add_node(&url("about"), PageType::Article, feats, 200);
+        // Known only from sitemap.xml: an orphan that links to a second orphan.
+        let orphan = builder.add_node(&url("old"), PageType::Article, feats, 200);
+        let orphan_child = builder.add_node(&url("old/1"), PageType::Article, feats, 200);
+
+        let link = EdgeFlags::default();
+        builder.add_edge(home, category, EdgeType::Navigation, 1, link);
+        builder.add_edge(home, about, EdgeType::Navigation, 1, link);
+        builder.add_edge(about, home, EdgeType::Navigation, 1, link);
+        builder.add_edge(category, a, EdgeType::ContentLink, 1, link);
+        builder.add_edge(category, b, EdgeType::ContentLink, 1, link);
+        builder.add_edge(orphan, orphan_child, EdgeType::ContentLink, 1, link);
+        let mut map = builder.build();
+        for i in [home, category, about, a] {
+            map.nodes[i as usize].flags.0 |= NodeFlags::RENDERED;
+        }
+
+        let report = analyze(&map);
+        assert_eq!(report.root, home);
+        assert_eq!(report.component_sizes, vec![5, 2]);
+        assert_eq!(report.unreachable, vec![orphan, orphan_child]);
+        assert_eq!(report.dead_ends, vec![a, b, orphan_child]);
+        // The category page cuts off both products; home cuts off about.
+        assert_eq!(report.choke_points, vec![category, home]);
+
+        let targets: Vec<(u32, RemapReason)> = report
+            .remap_targets
+            .iter()
+            .map(|t| (t.node, t.reason))
+            .collect();
+        assert_eq!(
+            targets,
+            vec![
+                (b, RemapReason::UnrenderedDeadEnd),
+                (orphan_child, RemapReason::UnrenderedDeadEnd),
+                (orphan, RemapReason::OrphanEntry),
+            ]
+        );
+    }
+}
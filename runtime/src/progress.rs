@@ -99,6 +99,136 @@ impl std::fmt::Display for MappingLayer {
     }
 }
 
+impl MappingLayer {
+    /// The share of a whole mapping operation spent before and by the end of
+    /// this layer, as fractions of 1. Layer 1 dominates: it fetches every
+    /// sampled page.
+    fn span(self) -> (f64, f64) {
+        match self {
+            Self::L0Metadata => (0.0, 0.15),
+            Self::L1HttpFetch => (0.15, 0.70),
+            Self::L15Pattern => (0.70, 0.72),
+            Self::L25Actions => (0.72, 0.75),
+            Self::L2ApiDiscovery => (0.75, 0.80),
+            Self::L3Browser => (0.80, 0.95),
+            Self::BuildGraph => (0.95, 1.0),
+        }
+    }
+}
+
+/// Summary of a mapping operation's progress, as shown to clients.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapProgress {
+    /// The layer currently running.
+    pub phase: MappingLayer,
+    /// Estimated completion, 0-100. Never decreases.
+    pub percent: u8,
+    /// URLs discovered so far.
+    pub urls_discovered: u32,
+    /// Pages fetched so far.
+    pub pages_fetched: u32,
+    /// Pages Layer 1 expects to fetch (the render budget, or fewer when
+    /// fewer URLs were discovered).
+    pub pages_planned: u32,
+    /// Time since mapping started.
+    pub elapsed_ms: u64,
+    /// Estimated time remaining, once enough work is done to estimate it.
+    pub eta_ms: Option<u64>,
+}
+
+/// Folds the mapper's `ProgressEvent`s into a [`MapProgress`] summary.
+///
+/// The ETA extrapolates from the elapsed time and the estimated completion,
+/// capped by what is left of the operation's time budget.
+#[derive(Debug, Clone)]
+pub struct ProgressTracker {
+    budget_ms: u64,
+    max_pages: u32,
+    fraction: f64,
+    progress: MapProgress,
+}
+
+impl ProgressTracker {
+    /// Track an operation with a `budget_ms` timeout that fetches at most
+    /// `max_pages` pages.
+    pub fn new(budget_ms: u64, max_pages: u32) -> Self {
+        Self {
+            budget_ms,
+            max_pages,
+            fraction: 0.0,
+            progress: MapProgress {
+                phase: MappingLayer::L0Metadata,
+                percent: 0,
+                urls_discovered: 0,
+                pages_fetched: 0,
+                pages_planned: 0,
+                elapsed_ms: 0,
+                eta_ms: None,
+            },
+        }
+    }
+
+    /// Apply an event received `elapsed_ms` into the operation. Returns the
+    /// new summary if the event changed it.
+    pub fn apply(&mut self, event: &ProgressEventKind, elapsed_ms: u64) -> Option<&MapProgress> {
+        match event {
+            ProgressEventKind::LayerStarted { layer, .. } => {
+                self.progress.phase = *layer;
+                self.advance(layer.span().0);
+            }
+            ProgressEventKind::LayerCompleted { layer, .. }
+            | ProgressEventKind::LayerSkipped { layer, .. } => {
+                self.advance(layer.span().1);
+            }
+            ProgressEventKind::MappingProgress {
+                urls_discovered,
+                pages_fetched,
+                ..
+            } => {
+                self.progress.urls_discovered = *urls_discovered;
+                self.progress.pages_fetched = *pages_fetched;
+                self.progress.pages_planned = self.max_pages.min(*urls_discovered);
+                if self.progress.phase == MappingLayer::L1HttpFetch
+                    && self.progress.pages_planned > 0
+                {
+                    let (from, to) = MappingLayer::L1HttpFetch.span();
+                    let done =
+                        (*pages_fetched as f64 / self.progress.pages_planned as f64).min(1.0);
+                    self.advance(from + (to - from) * done);
+                }
+            }
+            ProgressEventKind::MappingComplete { .. } => {
+                self.progress.phase = MappingLayer::BuildGraph;
+                self.fraction = 1.0;
+            }
+            ProgressEventKind::UrlProcessed { .. } | ProgressEventKind::Warning { .. } => {
+                return None;
+            }
+        }
+
+        self.progress.elapsed_ms = elapsed_ms;
+        self.progress.percent = (self.fraction * 100.0).round() as u8;
+        self.progress.eta_ms = if self.fraction >= 1.0 {
+            Some(0)
+        } else if self.fraction >= 0.05 {
+            let projected = elapsed_ms as f64 * (1.0 - self.fraction) / self.fraction;
+            Some((projected as u64).min(self.budget_ms.saturating_sub(elapsed_ms)))
+        } else {
+            None
+        };
+        Some(&self.progress)
+    }
+
+    /// The current summary.
+    pub fn progress(&self) -> &MapProgress {
+        &self.progress
+    }
+
+    fn advance(&mut self, fraction: f64) {
+        self.fraction = self.fraction.max(fraction);
+    }
+}
+
 /// Sender handle for emitting progress events.
 ///
 /// Backed by a `tokio::sync::broadcast` channel so multiple listeners can
@@ -204,6 +334,58 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tracker_phases_and_eta() {
+        let mut tracker = ProgressTracker::new(30_000, 100);
+        let started = ProgressEventKind::LayerStarted {
+            layer: MappingLayer::L1HttpFetch,
+            message: String::new(),
+        };
+        let p = tracker.apply(&started, 3_000).unwrap();
+        assert_eq!(p.phase, MappingLayer::L1HttpFetch);
+        assert_eq!(p.percent, 15);
+        assert_eq!(p.eta_ms, Some(17_000));
+
+        let counters = |pages_fetched| ProgressEventKind::MappingProgress {
+            urls_discovered: 40,
+            pages_fetched,
+            nodes_built: 0,
+            edges_built: 0,
+            active_requests: 0,
+            elapsed_ms: 0,
+            prices_found: 0,
+            ratings_found: 0,
+            actions_found: 0,
+        };
+        let p = tracker.apply(&counters(30), 6_000).unwrap();
+        assert_eq!(p.pages_planned, 40);
+        assert_eq!(p.percent, 56);
+
+        // The ETA never runs past the time budget.
+        let p = tracker.apply(&counters(30), 29_000).unwrap();
+        assert_eq!(p.eta_ms, Some(1_000));
+
+        // Completion never goes backwards.
+        let earlier = ProgressEventKind::LayerStarted {
+            layer: MappingLayer::L0Metadata,
+            message: String::new(),
+        };
+        assert_eq!(tracker.apply(&earlier, 29_500).unwrap().percent, 56);
+        let warning = ProgressEventKind::Warning {
+            message: "slow".to_string(),
+        };
+        assert!(tracker.apply(&warning, 29_500).is_none());
+
+        let complete = ProgressEventKind::MappingComplete {
+            node_count: 40,
+            edge_count: 80,
+            action_count: 0,
+            elapsed_ms: 30_000,
+        };
+        let p = tracker.apply(&complete, 30_000).unwrap();
+        assert_eq!((p.percent, p.eta_ms), (100, Some(0)));
+    }
+
     #[test]
     fn test_mapping_layer_display() {
         assert_eq!(MappingLayer::L0Metadata.to_string(), "Metadata");
@@ -0,0 +1,298 @@
+//! Projects: named groups of related domains with shared settings.
+//!
+//! A project (`cortex project create retail amazon.com bestbuy.com`) gives
+//! its domains one set of crawl politeness settings, lets an AUTH session for
+//! one of them stand for all of them, and names the targets `cortex compile`
+//! generates. `cortex query --project` and WQL search every domain of a
+//! project; `cortex project use` makes a project the default scope of both.
+//!
+//! Projects live in `~/.cortex/projects.json`. A domain belongs to at most
+//! one project, so its settings are never ambiguous.
+
+use crate::cartography::content_policy::ContentPolicy;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Settings shared by every domain of a project.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectSettings {
+    /// Whether maps honour robots.txt when the request does not say. `None`
+    /// keeps the daemon default (true).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub respect_robots: Option<bool>,
+    /// Content policy for domains without a policy file of their own.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy: Option<ContentPolicy>,
+    /// An AUTH session for one domain is valid for every domain.
+    pub share_sessions: bool,
+    /// Targets `cortex compile` generates when none are given.
+    pub compile_targets: Vec<String>,
+}
+
+/// A named group of domains.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Project {
+    pub name: String,
+    pub domains: Vec<String>,
+    #[serde(default)]
+    pub settings: ProjectSettings,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Project {
+    /// Whether `domain` belongs to the project.
+    pub fn contains(&self, domain: &str) -> bool {
+        let domain = normalize(domain);
+        self.domains.contains(&domain)
+    }
+}
+
+/// The set of projects, and the one commands default to.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Projects {
+    /// Project `cortex query` and `cortex wql` use when given no scope.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active: Option<String>,
+    #[serde(default)]
+    pub projects: Vec<Project>,
+}
+
+impl Projects {
+    /// Load projects from a file; a missing file holds none.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("reading projects: {}", path.display()))?;
+        serde_json::from_str(&data).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    /// Load projects from the default file.
+    pub fn load_default() -> Result<Self> {
+        Self::load(&default_path())
+    }
+
+    /// Save projects to a file.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("writing {}", path.display()))
+    }
+
+    /// The project called `name`.
+    pub fn get(&self, name: &str) -> Option<&Project> {
+        self.projects.iter().find(|p| p.name == name)
+    }
+
+    /// The project called `name`, or an error naming the known projects.
+    pub fn get_mut(&mut self, name: &str) -> Result<&mut Project> {
+        match self.projects.iter().position(|p| p.name == name) {
+            Some(index) => Ok(&mut self.projects[index]),
+            None => bail!("{}", self.unknown(name)),
+        }
+    }
+
+    /// The project `domain` belongs to, if any.
+    pub fn project_of(&self, domain: &str) -> Option<&Project> {
+        self.projects.iter().find(|p| p.contains(domain))
+    }
+
+    /// The project a command should be scoped to: `name` if given (which
+    /// must exist), else the active project, if any.
+    pub fn resolve(&self, name: Option<&str>) -> Result<Option<&Project>> {
+        match name.or(self.active.as_deref()) {
+            Some(name) => match self.get(name) {
+                Some(project) => Ok(Some(project)),
+                None => bail!("{}", self.unknown(name)),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Create a project holding `domains`.
+    pub fn create(&mut self, name: &str, domains: &[String]) -> Result<&Project> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            bail!("invalid project name '{name}' (use letters, digits, '-' and '_')");
+        }
+        if self.get(name).is_some() {
+            bail!("a project named '{name}' already exists");
+        }
+        self.projects.push(Project {
+            name: name.to_string(),
+            domains: Vec::new(),
+            settings: ProjectSettings::default(),
+            created_at: Utc::now(),
+        });
+        if let Err(e) = self.add_domains(name, domains) {
+            self.projects.pop();
+            return Err(e);
+        }
+        Ok(self.projects.last().expect("just pushed"))
+    }
+
+    /// Delete the project called `name`. Returns the deleted project.
+    pub fn delete(&mut self, name: &str) -> Option<Project> {
+        let index = self.projects.iter().position(|p| p.name == name)?;
+        if self.active.as_deref() == Some(name) {
+            self.active = None;
+        }
+        Some(self.projects.remove(index))
+    }
+
+    /// Add `domains` to project `name`. Fails if one already belongs to
+    /// another project. Returns the domains that were not in it before.
+    pub fn add_domains(&mut self, name: &str, domains: &[String]) -> Result<Vec<String>> {
+        let domains: Vec<String> = domains.iter().map(|d| normalize(d)).collect();
+        for domain in &domains {
+            if domain.is_empty() {
+                bail!("empty domain");
+            }
+            if let Some(other) = self.project_of(domain).filter(|p| p.name != name) {
+                bail!(
+                    "{domain} already belongs to project '{}'; remove it there first",
+                    other.name
+                );
+            }
+        }
+        let project = self.get_mut(name)?;
+        let mut added = Vec::new();
+        for domain in domains {
+            if !project.domains.contains(&domain) && !added.contains(&domain) {
+                added.push(domain);
+            }
+        }
+        project.domains.extend(added.iter().cloned());
+        Ok(added)
+    }
+
+    /// Remove `domains` from project `name`. Returns those it held.
+    pub fn remove_domains(&mut self, name: &str, domains: &[String]) -> Result<Vec<String>> {
+        let domains: Vec<String> = domains.iter().map(|d| normalize(d)).collect();
+        let project = self.get_mut(name)?;
+        let removed: Vec<String> = project
+            .domains
+            .iter()
+            .filter(|d| domains.contains(d))
+            .cloned()
+            .collect();
+        project.domains.retain(|d| !domains.contains(d));
+        Ok(removed)
+    }
+
+    /// Make `name` the active project, or clear it with `None`.
+    pub fn set_active(&mut self, name: Option<&str>) -> Result<()> {
+        if let Some(name) = name {
+            self.get_mut(name)?;
+        }
+        self.active = name.map(String::from);
+        Ok(())
+    }
+
+    fn unknown(&self, name: &str) -> String {
+        if self.projects.is_empty() {
+            format!("no project named '{name}'. Create one with: cortex project create {name} <domain>...")
+        } else {
+            let names: Vec<&str> = self.projects.iter().map(|p| p.name.as_str()).collect();
+            format!("no project named '{name}' (projects: {})", names.join(", "))
+        }
+    }
+}
+
+/// Default projects file (~/.cortex/projects.json).
+pub fn default_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".cortex")
+        .join("projects.json")
+}
+
+/// The targets to compile `domain` for: `given` if any, else its project's
+/// compile targets (empty, meaning all, if it has none).
+pub fn compile_targets(domain: &str, given: &[String]) -> Result<Vec<String>> {
+    if !given.is_empty() {
+        return Ok(given.to_vec());
+    }
+    Ok(Projects::load_default()?
+        .project_of(domain)
+        .map(|p| p.settings.compile_targets.clone())
+        .unwrap_or_default())
+}
+
+/// Domains are compared lowercased, without a trailing slash.
+fn normalize(domain: &str) -> String {
+    domain.trim().trim_end_matches('/').to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn domains(list: &[&str]) -> Vec<String> {
+        list.iter().map(|d| d.to_string()).collect()
+    }
+
+    #[test]
+    fn test_create_add_remove_and_resolve() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("projects.json");
+
+        let mut projects = Projects::default();
+        projects
+            .create("retail", &domains(&["Amazon.com", "bestbuy.com/"]))
+            .unwrap();
+        assert!(projects.create("retail", &[]).is_err());
+        assert!(projects.create("bad name", &[]).is_err());
+        // A domain belongs to one project only, and a failed create leaves
+        // nothing behind.
+        assert!(projects.create("news", &domains(&["amazon.com"])).is_err());
+        assert!(projects.get("news").is_none());
+
+        let added = projects
+            .add_domains("retail", &domains(&["walmart.com", "amazon.com"]))
+            .unwrap();
+        assert_eq!(added, domains(&["walmart.com"]));
+        assert_eq!(
+            projects.project_of("AMAZON.COM").map(|p| p.name.as_str()),
+            Some("retail")
+        );
+
+        projects.set_active(Some("retail")).unwrap();
+        assert!(projects.set_active(Some("news")).is_err());
+        projects.save(&path).unwrap();
+
+        let mut loaded = Projects::load(&path).unwrap();
+        assert_eq!(
+            loaded.get("retail").unwrap().domains,
+            domains(&["amazon.com", "bestbuy.com", "walmart.com"])
+        );
+        assert_eq!(
+            loaded.resolve(None).unwrap().map(|p| p.name.as_str()),
+            Some("retail")
+        );
+        assert!(loaded.resolve(Some("news")).is_err());
+
+        let removed = loaded
+            .remove_domains("retail", &domains(&["bestbuy.com", "ebay.com"]))
+            .unwrap();
+        assert_eq!(removed, domains(&["bestbuy.com"]));
+        assert!(loaded.project_of("bestbuy.com").is_none());
+
+        assert!(loaded.delete("retail").is_some());
+        assert_eq!(loaded.active, None);
+        assert!(loaded.resolve(None).unwrap().is_none());
+        assert!(Projects::load(&dir.path().join("missing.json"))
+            .unwrap()
+            .projects
+            .is_empty());
+    }
+}
@@ -23,6 +23,13 @@ pub enum Method {
     ConnectWs,
     SendWs,
     Status,
+    Subscribe,
+    SavedQueries,
+    Cancel,
+    Reload,
+    Wql,
+    Annotate,
+    Annotations,
 }
 
 impl Method {
@@ -43,11 +50,61 @@ impl Method {
             "connect_ws" => Ok(Self::ConnectWs),
             "send_ws" => Ok(Self::SendWs),
             "status" => Ok(Self::Status),
+            "subscribe" => Ok(Self::Subscribe),
+            "saved_queries" => Ok(Self::SavedQueries),
+            "cancel" => Ok(Self::Cancel),
+            "reload" => Ok(Self::Reload),
+            "wql" => Ok(Self::Wql),
+            "annotate" => Ok(Self::Annotate),
+            "annotations" => Ok(Self::Annotations),
             _ => bail!(
-                "unknown method '{s}'. Valid methods: handshake, map, query, pathfind, refresh, act, watch, perceive, auth, auth_consent, auth_mfa, connect_ws, send_ws, status"
+                "unknown method '{s}'. Valid methods: handshake, map, query, pathfind, refresh, act, watch, perceive, auth, auth_consent, auth_mfa, connect_ws, send_ws, status, subscribe, saved_queries, cancel, reload, wql, annotate, annotations"
             ),
         }
     }
+
+    /// Wire name of the method, as accepted by [`Method::from_str`].
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Handshake => "handshake",
+            Self::Map => "map",
+            Self::Query => "query",
+            Self::Pathfind => "pathfind",
+            Self::Refresh => "refresh",
+            Self::Act => "act",
+            Self::Watch => "watch",
+            Self::Perceive => "perceive",
+            Self::Auth => "auth",
+            Self::AuthConsent => "auth_consent",
+            Self::AuthMfa => "auth_mfa",
+            Self::ConnectWs => "connect_ws",
+            Self::SendWs => "send_ws",
+            Self::Status => "status",
+            Self::Subscribe => "subscribe",
+            Self::SavedQueries => "saved_queries",
+            Self::Cancel => "cancel",
+            Self::Reload => "reload",
+            Self::Wql => "wql",
+            Self::Annotate => "annotate",
+            Self::Annotations => "annotations",
+        }
+    }
+
+    /// Whether requests of this method change state and are audited.
+    pub fn is_audited(&self) -> bool {
+        matches!(
+            self,
+            Self::Map
+                | Self::Refresh
+                | Self::Act
+                | Self::Auth
+                | Self::AuthConsent
+                | Self::AuthMfa
+                | Self::Cancel
+                | Self::Reload
+                | Self::Annotate
+        )
+    }
 }
 
 /// A parsed protocol request.
@@ -93,23 +150,334 @@ pub fn format_response(id: &str, result: Value) -> String {
 }
 
 /// Format an error response as JSON string (newline-terminated).
-pub fn format_error(id: &str, code: &str, message: &str) -> String {
-    let resp = serde_json::json!({
-        "id": id,
-        "error": {
-            "code": code,
-            "message": message,
-        },
-    });
-    format!("{}\n", resp)
+pub fn format_error(id: &str, code: ErrorCode, message: &str) -> String {
+    ProtocolError::new(code, message).to_response(id)
+}
+
+/// What kind of failure an error is, so clients can react to whole groups
+/// of codes at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// The request is malformed or its parameters are wrong.
+    InvalidRequest,
+    /// The caller lacks a valid API key or permission.
+    Auth,
+    /// The domain, node or path asked for does not exist.
+    NotFound,
+    /// The request was refused for now: rate limit or crawl calendar.
+    Throttled,
+    /// The daemon cannot do this (no browser, not implemented).
+    Unsupported,
+    /// The site or browser failed while serving the request.
+    Upstream,
+    /// The request was cancelled by a CANCEL request.
+    Cancelled,
+    /// The request or connection timed out.
+    Timeout,
+    /// The daemon could not be reached.
+    Unavailable,
+    /// The daemon failed internally.
+    Internal,
+}
+
+/// Error codes returned by the daemon. Each carries a category, whether
+/// the same request may succeed if sent again, and how long to wait first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    InvalidJson,
+    InvalidMethod,
+    InvalidParams,
+    MessageTooLarge,
+    DuplicateId,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    NoPath,
+    RateLimited,
+    OutsideCrawlWindow,
+    NoRenderer,
+    NoEventLog,
+    NotImplemented,
+    Policy,
+    Renderer,
+    MapFailed,
+    /// The map scored below the request's `min_quality`, fallbacks included.
+    LowQuality,
+    PerceiveFailed,
+    ActFailed,
+    AuthFailed,
+    Registry,
+    EventLog,
+    Cancelled,
+    Timeout,
+    InactivityTimeout,
+    Unavailable,
+    Internal,
+    /// A code this build does not know, e.g. from a newer daemon.
+    Unknown,
+}
+
+impl ErrorCode {
+    const ALL: [Self; 29] = [
+        Self::InvalidJson,
+        Self::InvalidMethod,
+        Self::InvalidParams,
+        Self::MessageTooLarge,
+        Self::DuplicateId,
+        Self::Unauthorized,
+        Self::Forbidden,
+        Self::NotFound,
+        Self::NoPath,
+        Self::RateLimited,
+        Self::OutsideCrawlWindow,
+        Self::NoRenderer,
+        Self::NoEventLog,
+        Self::NotImplemented,
+        Self::Policy,
+        Self::Renderer,
+        Self::MapFailed,
+        Self::LowQuality,
+        Self::PerceiveFailed,
+        Self::ActFailed,
+        Self::AuthFailed,
+        Self::Registry,
+        Self::EventLog,
+        Self::Cancelled,
+        Self::Timeout,
+        Self::InactivityTimeout,
+        Self::Unavailable,
+        Self::Internal,
+        Self::Unknown,
+    ];
+
+    /// The code as sent on the wire, e.g. `E_NOT_FOUND`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::InvalidJson => "E_INVALID_JSON",
+            Self::InvalidMethod => "E_INVALID_METHOD",
+            Self::InvalidParams => "E_INVALID_PARAMS",
+            Self::MessageTooLarge => "E_MESSAGE_TOO_LARGE",
+            Self::DuplicateId => "E_DUPLICATE_ID",
+            Self::Unauthorized => "E_UNAUTHORIZED",
+            Self::Forbidden => "E_FORBIDDEN",
+            Self::NotFound => "E_NOT_FOUND",
+            Self::NoPath => "E_NO_PATH",
+            Self::RateLimited => "E_RATE_LIMITED",
+            Self::OutsideCrawlWindow => "E_OUTSIDE_CRAWL_WINDOW",
+            Self::NoRenderer => "E_NO_RENDERER",
+            Self::NoEventLog => "E_NO_EVENT_LOG",
+            Self::NotImplemented => "E_NOT_IMPLEMENTED",
+            Self::Policy => "E_POLICY",
+            Self::Renderer => "E_RENDERER",
+            Self::MapFailed => "E_MAP_FAILED",
+            Self::LowQuality => "E_LOW_QUALITY",
+            Self::PerceiveFailed => "E_PERCEIVE_FAILED",
+            Self::ActFailed => "E_ACT_FAILED",
+            Self::AuthFailed => "E_AUTH_FAILED",
+            Self::Registry => "E_REGISTRY",
+            Self::EventLog => "E_EVENT_LOG",
+            Self::Cancelled => "E_CANCELLED",
+            Self::Timeout => "E_TIMEOUT",
+            Self::InactivityTimeout => "E_INACTIVITY_TIMEOUT",
+            Self::Unavailable => "E_UNAVAILABLE",
+            Self::Internal => "E_INTERNAL",
+            Self::Unknown => "E_UNKNOWN",
+        }
+    }
+
+    /// Parse a wire code; codes this build does not know become `Unknown`.
+    pub fn parse(code: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|c| c.as_str() == code)
+            .unwrap_or(Self::Unknown)
+    }
+
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Self::InvalidJson
+            | Self::InvalidMethod
+            | Self::InvalidParams
+            | Self::MessageTooLarge
+            | Self::DuplicateId => ErrorCategory::InvalidRequest,
+            Self::Unauthorized | Self::Forbidden => ErrorCategory::Auth,
+            Self::NotFound | Self::NoPath => ErrorCategory::NotFound,
+            Self::RateLimited | Self::OutsideCrawlWindow => ErrorCategory::Throttled,
+            Self::NoRenderer | Self::NoEventLog | Self::NotImplemented => {
+                ErrorCategory::Unsupported
+            }
+            Self::Renderer
+            | Self::MapFailed
+            | Self::LowQuality
+            | Self::PerceiveFailed
+            | Self::ActFailed
+            | Self::AuthFailed => ErrorCategory::Upstream,
+            Self::Cancelled => ErrorCategory::Cancelled,
+            Self::Timeout | Self::InactivityTimeout => ErrorCategory::Timeout,
+            Self::Unavailable => ErrorCategory::Unavailable,
+            Self::Policy | Self::Registry | Self::EventLog | Self::Internal | Self::Unknown => {
+                ErrorCategory::Internal
+            }
+        }
+    }
+
+    /// How long to wait before sending the same request again, or `None`
+    /// if it would fail the same way. ACT and AUTH failures are not
+    /// retryable: the site may already have acted on the first attempt.
+    pub fn retry_after_ms(&self) -> Option<u64> {
+        match self {
+            Self::RateLimited => Some(1_000),
+            Self::OutsideCrawlWindow => Some(15 * 60 * 1_000),
+            Self::Renderer | Self::MapFailed | Self::PerceiveFailed => Some(5_000),
+            Self::Registry | Self::EventLog => Some(1_000),
+            Self::Timeout | Self::Unavailable => Some(2_000),
+            Self::InactivityTimeout => Some(0),
+            _ => None,
+        }
+    }
+
+    pub fn retryable(&self) -> bool {
+        self.retry_after_ms().is_some()
+    }
+
+    /// HTTP status for the REST API.
+    pub fn http_status(&self) -> u16 {
+        match self {
+            Self::MessageTooLarge => 413,
+            Self::DuplicateId => 409,
+            Self::Unauthorized => 401,
+            Self::Forbidden => 403,
+            Self::NotImplemented => 501,
+            _ => match self.category() {
+                ErrorCategory::InvalidRequest => 400,
+                ErrorCategory::Auth => 403,
+                ErrorCategory::NotFound => 404,
+                ErrorCategory::Throttled => 429,
+                ErrorCategory::Unsupported | ErrorCategory::Unavailable => 503,
+                ErrorCategory::Upstream => 502,
+                ErrorCategory::Cancelled => 499,
+                ErrorCategory::Timeout => 504,
+                ErrorCategory::Internal => 500,
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
+/// An error as sent to clients over the socket, REST and MCP:
+///
+/// ```json
+/// {"code": "E_RATE_LIMITED", "message": "...", "category": "throttled",
+///  "retryable": true, "retry_after_ms": 1000}
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtocolError {
+    pub code: ErrorCode,
+    pub message: String,
+    /// Overrides the code's default backoff, e.g. until a crawl window opens.
+    pub retry_after_ms: Option<u64>,
+}
+
+impl ProtocolError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            retry_after_ms: None,
+        }
+    }
+
+    /// Suggest waiting `ms` before retrying instead of the code's default.
+    pub fn retry_after(mut self, ms: u64) -> Self {
+        self.retry_after_ms = Some(ms);
+        self
+    }
+
+    pub fn retryable(&self) -> bool {
+        self.code.retryable()
+    }
+
+    /// Suggested wait before retrying, if the request is retryable.
+    pub fn backoff_ms(&self) -> Option<u64> {
+        let default = self.code.retry_after_ms()?;
+        Some(self.retry_after_ms.unwrap_or(default))
+    }
+
+    /// The `error` object of a response.
+    pub fn to_json(&self) -> Value {
+        let mut error = serde_json::json!({
+            "code": self.code.as_str(),
+            "message": self.message,
+            "category": self.code.category(),
+            "retryable": self.retryable(),
+        });
+        if let Some(ms) = self.backoff_ms() {
+            error["retry_after_ms"] = ms.into();
+        }
+        error
+    }
+
+    /// A complete error response line for request `id`.
+    pub fn to_response(&self, id: &str) -> String {
+        format!(
+            "{}\n",
+            serde_json::json!({ "id": id, "error": self.to_json() })
+        )
+    }
+
+    /// Read an `error` object from a response. Unknown codes are kept as
+    /// [`ErrorCode::Unknown`], with the original code in the message.
+    pub fn from_json(error: &Value) -> Self {
+        let wire = error.get("code").and_then(|c| c.as_str()).unwrap_or("");
+        let code = ErrorCode::parse(wire);
+        let message = error
+            .get("message")
+            .and_then(|m| m.as_str())
+            .unwrap_or_default();
+        let message = if code == ErrorCode::Unknown && !wire.is_empty() {
+            format!("{wire}: {message}")
+        } else {
+            message.to_string()
+        };
+        Self {
+            code,
+            message,
+            retry_after_ms: error.get("retry_after_ms").and_then(|v| v.as_u64()),
+        }
+    }
+}
+
+impl std::fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
 /// Handshake response.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HandshakeResult {
     pub server_version: String,
     pub protocol_version: u16,
     pub compatible: bool,
+    /// Permission of the connection's API key, when it presented one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permission: Option<String>,
+    /// Requests the connection may have in flight at once, granted from
+    /// the handshake's `pipeline` param. 1 means strictly in order.
+    #[serde(default = "default_pipeline")]
+    pub pipeline: u32,
+}
+
+fn default_pipeline() -> u32 {
+    1
 }
 
 /// Status response.
@@ -166,10 +534,41 @@ mod tests {
 
     #[test]
     fn test_format_error() {
-        let resp = format_error("r2", "E_INVALID_METHOD", "unknown method");
+        let resp = format_error("r2", ErrorCode::InvalidMethod, "unknown method");
         let parsed: Value = serde_json::from_str(resp.trim()).unwrap();
         assert_eq!(parsed["id"], "r2");
         assert_eq!(parsed["error"]["code"], "E_INVALID_METHOD");
+        assert_eq!(parsed["error"]["category"], "invalid_request");
+        assert_eq!(parsed["error"]["retryable"], false);
+        assert!(parsed["error"].get("retry_after_ms").is_none());
+    }
+
+    #[test]
+    fn test_error_codes_round_trip_with_retry_metadata() {
+        for code in ErrorCode::ALL {
+            assert_eq!(ErrorCode::parse(code.as_str()), code);
+        }
+        assert_eq!(ErrorCode::parse("E_FROM_THE_FUTURE"), ErrorCode::Unknown);
+
+        let limited = ProtocolError::new(ErrorCode::RateLimited, "slow down");
+        assert_eq!(limited.to_json()["category"], "throttled");
+        assert_eq!(limited.to_json()["retry_after_ms"], 1000);
+        assert_eq!(ErrorCode::RateLimited.http_status(), 429);
+
+        let window =
+            ProtocolError::new(ErrorCode::OutsideCrawlWindow, "peak hours").retry_after(42);
+        let parsed = ProtocolError::from_json(&window.to_json());
+        assert_eq!(parsed, window);
+        assert_eq!(parsed.backoff_ms(), Some(42));
+
+        // Acting twice could double an order, so ACT failures are final.
+        assert!(!ErrorCode::ActFailed.retryable());
+        assert!(ErrorCode::MapFailed.retryable());
+
+        let unknown =
+            ProtocolError::from_json(&serde_json::json!({"code": "E_NEW", "message": "x"}));
+        assert_eq!(unknown.code, ErrorCode::Unknown);
+        assert_eq!(unknown.message, "E_NEW: x");
     }
 
     /// Fuzz test: protocol parser must never panic on arbitrary input.
@@ -1,10 +1,15 @@
 //! Chromium-based renderer using chromiumoxide.
 
 use super::{NavigationResult, RenderContext, Renderer};
+use crate::acquisition::profiles::CrawlProfile;
 use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
 use chromiumoxide::browser::{Browser, BrowserConfig};
-use chromiumoxide::page::Page;
+use chromiumoxide::cdp::browser_protocol::network::{
+    CookieParam, Headers, SetCookiesParams, SetExtraHttpHeadersParams, SetUserAgentOverrideParams,
+};
+use chromiumoxide::cdp::js_protocol::runtime::{GetHeapUsageParams, TerminateExecutionParams};
+use chromiumoxide::page::{Page, ScreenshotParams};
 use futures::StreamExt;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
@@ -237,6 +242,72 @@ impl RenderContext for ChromiumContext {
         Ok(url)
     }
 
+    async fn terminate_js(&self) -> Result<()> {
+        self.page
+            .execute(TerminateExecutionParams::default())
+            .await
+            .context("failed to terminate JS execution")?;
+        Ok(())
+    }
+
+    async fn heap_used(&self) -> Result<Option<u64>> {
+        let usage = self
+            .page
+            .execute(GetHeapUsageParams::default())
+            .await
+            .context("failed to get heap usage")?;
+        Ok(Some(usage.result.used_size as u64))
+    }
+
+    async fn screenshot(&self) -> Result<Vec<u8>> {
+        self.page
+            .screenshot(ScreenshotParams::builder().build())
+            .await
+            .context("failed to capture screenshot")
+    }
+
+    async fn apply_profile(&mut self, profile: &CrawlProfile, domain: &str) -> Result<()> {
+        let mut user_agent = SetUserAgentOverrideParams::new(profile.user_agent());
+        user_agent.accept_language = profile.accept_language.clone();
+        self.page
+            .set_user_agent(user_agent)
+            .await
+            .context("failed to set user agent")?;
+
+        let headers: serde_json::Map<String, serde_json::Value> = profile
+            .client_hints
+            .iter()
+            .chain(&profile.headers)
+            .map(|(name, value)| (name.clone(), value.clone().into()))
+            .collect();
+        if !headers.is_empty() {
+            self.page
+                .execute(SetExtraHttpHeadersParams::new(Headers::new(headers)))
+                .await
+                .context("failed to set headers")?;
+        }
+
+        // Cookies go in the browser's jar, so they reach only the domain and
+        // its subdomains
+        if !profile.cookies.is_empty() {
+            let cookies = profile
+                .cookies
+                .iter()
+                .map(|(name, value)| {
+                    let mut cookie = CookieParam::new(name, value);
+                    cookie.domain = Some(domain.to_string());
+                    cookie.path = Some("/".to_string());
+                    cookie
+                })
+                .collect();
+            self.page
+                .execute(SetCookiesParams::new(cookies))
+                .await
+                .context("failed to set cookies")?;
+        }
+        Ok(())
+    }
+
     async fn close(self: Box<Self>) -> Result<()> {
         self.active_count.fetch_sub(1, Ordering::Relaxed);
         let _ = self.page.close().await;
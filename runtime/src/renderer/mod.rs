@@ -5,6 +5,7 @@
 
 pub mod chromium;
 
+use crate::acquisition::profiles::{CrawlProfile, ProfileStore};
 use anyhow::Result;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
@@ -46,6 +47,44 @@ pub trait RenderContext: Send + Sync {
     async fn get_url(&self) -> Result<String>;
     /// Close this context.
     async fn close(self: Box<Self>) -> Result<()>;
+    /// Stop any JavaScript running in the page, such as a script that
+    /// outlived its timeout. Contexts that cannot interrupt scripts do
+    /// nothing.
+    async fn terminate_js(&self) -> Result<()> {
+        Ok(())
+    }
+    /// Bytes of JS heap the page is using, if the engine reports it.
+    async fn heap_used(&self) -> Result<Option<u64>> {
+        Ok(None)
+    }
+    /// Capture the visible page as a PNG.
+    async fn screenshot(&self) -> Result<Vec<u8>> {
+        anyhow::bail!("this renderer cannot take screenshots")
+    }
+    /// Send `profile`'s user agent, headers and cookies (the cookies for
+    /// `domain`) with every request from now on. Contexts that cannot
+    /// change their identity keep their own.
+    async fn apply_profile(&mut self, profile: &CrawlProfile, domain: &str) -> Result<()> {
+        let _ = (profile, domain);
+        Ok(())
+    }
+}
+
+/// Give `context` the crawl profile `store` assigns to `url`'s domain, so
+/// the browser identifies itself as the HTTP client does.
+pub async fn identify(
+    context: &mut dyn RenderContext,
+    store: &ProfileStore,
+    url: &str,
+) -> Result<()> {
+    let Some(host) = url::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_string))
+    else {
+        return Ok(());
+    };
+    let profile = store.for_domain(&host)?;
+    context.apply_profile(&profile, &host).await
 }
 
 /// A no-op renderer used when Chromium is unavailable.
@@ -6,13 +6,18 @@
 //! Provides a REST interface alongside the Unix socket server.
 //! Every REST endpoint maps 1:1 to a protocol method, using the
 //! same [`SharedState`] and [`handle_request`] dispatch.
+//!
+//! Requests present an API key as `Authorization: Bearer <secret>` and are
+//! held to its permission, as on the socket. Without one they are
+//! anonymous, which is only allowed while no API keys exist.
 
 use crate::events::{self, CortexEvent};
-use crate::protocol;
-use crate::server::{handle_request, SharedState};
+use crate::protocol::{self, ErrorCode, Method, ProtocolError};
+use crate::server::{authorize, handle_request, Caller, SharedState};
 use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
 use axum::response::sse::{Event, Sse};
-use axum::response::{Html, IntoResponse};
+use axum::response::{Html, IntoResponse, Response};
 use axum::routing::{get, post};
 use axum::{Json, Router};
 use serde_json::Value;
@@ -20,28 +25,6 @@ use std::convert::Infallible;
 use std::sync::Arc;
 use tower_http::cors::{Any, CorsLayer};
 
-/// Wrapper to assert a future is Send.
-///
-/// Same technique as in `server.rs` — the `handle_request` future contains
-/// only Send types but the compiler cannot prove it due to higher-ranked
-/// lifetime bounds in transitive dependencies (scraper, chromiumoxide).
-struct AssertSend<F>(F);
-
-// SAFETY: All concrete types in handle_request are Send. See server.rs
-// for the full justification.
-unsafe impl<F: std::future::Future> Send for AssertSend<F> {}
-
-impl<F: std::future::Future> std::future::Future for AssertSend<F> {
-    type Output = F::Output;
-    fn poll(
-        self: std::pin::Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
-    ) -> std::task::Poll<Self::Output> {
-        let inner = unsafe { self.map_unchecked_mut(|s| &mut s.0) };
-        inner.poll(cx)
-    }
-}
-
 /// Build the axum Router with all REST endpoints.
 pub fn router(state: Arc<SharedState>) -> Router {
     let cors = CorsLayer::new()
@@ -62,6 +45,7 @@ pub fn router(state: Arc<SharedState>) -> Router {
         .route("/api/v1/compare", post(handle_compare))
         .route("/api/v1/auth", post(handle_auth))
         .route("/api/v1/maps", get(handle_list_maps))
+        .route("/api/v1/queries", get(handle_saved_queries))
         .layer(cors)
         .with_state(state)
 }
@@ -82,11 +66,51 @@ pub async fn start(port: u16, state: Arc<SharedState>) -> anyhow::Result<()> {
 
 // ── Helpers ─────────────────────────────────────────────────────
 
+/// The caller a request speaks for: the API key of its `Authorization:
+/// Bearer` header, or anonymous without one. A key that is not valid is
+/// E_UNAUTHORIZED.
+fn caller(headers: &HeaderMap, state: &SharedState) -> Result<Caller, ProtocolError> {
+    let Some(value) = headers.get(header::AUTHORIZATION) else {
+        return Ok(Caller::Anonymous);
+    };
+    let key = value
+        .to_str()
+        .ok()
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .and_then(|secret| state.api_keys.verify(secret.trim()));
+    match key {
+        Some(key) => Ok(Caller::Key(key)),
+        None => Err(ProtocolError::new(
+            ErrorCode::Unauthorized,
+            "invalid API key",
+        )),
+    }
+}
+
+/// Check that the caller of an endpoint served outside the protocol
+/// handler may make `method` requests, or the error refusing it.
+fn check(method: Method, headers: &HeaderMap, state: &SharedState) -> Result<(), ProtocolError> {
+    let caller = caller(headers, state)?;
+    authorize(&method, &caller, state).map_err(|(code, message)| ProtocolError::new(code, message))
+}
+
 /// Dispatch a REST request through the Cortex protocol handler.
 ///
 /// Wraps the JSON body as a protocol request and calls the same
-/// `handle_request` function used by the socket server.
-async fn dispatch(method: &str, params: Value, state: Arc<SharedState>) -> Json<Value> {
+/// `handle_request` function used by the socket server, as the caller of
+/// its `Authorization` header.
+/// Errors get the HTTP status for their code and, when retryable, a
+/// `Retry-After` header; the body is the same `error` object as on the socket.
+async fn dispatch(
+    method: &str,
+    params: Value,
+    headers: &HeaderMap,
+    state: Arc<SharedState>,
+) -> Response {
+    let caller = match caller(headers, &state) {
+        Ok(caller) => caller,
+        Err(e) => return error_response(e.to_json()),
+    };
     let id = format!("rest-{}", uuid_simple());
     let req_json = serde_json::json!({
         "id": id,
@@ -98,21 +122,17 @@ async fn dispatch(method: &str, params: Value, state: Arc<SharedState>) -> Json<
     let req = match protocol::parse_request(&req_json.to_string()) {
         Ok(r) => r,
         Err(e) => {
-            return Json(serde_json::json!({
-                "error": { "code": "E_INVALID_PARAMS", "message": e.to_string() }
-            }));
+            return error_response(
+                ProtocolError::new(ErrorCode::InvalidParams, e.to_string()).to_json(),
+            );
         }
     };
 
-    // Use AssertSend + spawn to satisfy axum's Send requirement.
-    // handle_request is actually Send — see server.rs for justification.
+    // Spawned so a panicking handler becomes an error response.
     let response_str = {
-        let fut = AssertSend(handle_request(req, state));
+        let fut = handle_request(req, state, caller);
         tokio::task::spawn(fut).await.unwrap_or_else(|e| {
-            serde_json::json!({
-                "error": { "code": "E_INTERNAL", "message": format!("task panicked: {e}") }
-            })
-            .to_string()
+            ProtocolError::new(ErrorCode::Internal, format!("task panicked: {e}")).to_response(&id)
         })
     };
 
@@ -124,18 +144,33 @@ async fn dispatch(method: &str, params: Value, state: Arc<SharedState>) -> Json<
                 obj.remove("id");
             }
             // Flatten: if there's a "result" key, return its contents directly
-            if let Some(result) = v.get("result").cloned() {
-                Json(result)
-            } else {
-                Json(v)
+            match (v.get("result").cloned(), v.get("error").cloned()) {
+                (Some(result), _) => Json(result).into_response(),
+                (None, Some(error)) => error_response(error),
+                (None, None) => Json(v).into_response(),
             }
         }
-        Err(_) => Json(serde_json::json!({
-            "error": { "code": "E_INTERNAL", "message": "Failed to parse internal response" }
-        })),
+        Err(_) => error_response(
+            ProtocolError::new(ErrorCode::Internal, "Failed to parse internal response").to_json(),
+        ),
     }
 }
 
+/// An `{"error": ...}` response with the status and `Retry-After` its code calls for.
+fn error_response(error: Value) -> Response {
+    let parsed = ProtocolError::from_json(&error);
+    let status = StatusCode::from_u16(parsed.code.http_status())
+        .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let mut response = (status, Json(serde_json::json!({ "error": error }))).into_response();
+    if let Some(ms) = parsed.backoff_ms() {
+        let secs = ms.div_ceil(1000).to_string();
+        if let Ok(value) = secs.parse() {
+            response.headers_mut().insert(header::RETRY_AFTER, value);
+        }
+    }
+    response
+}
+
 /// Simple monotonic ID generator (no external crate needed).
 fn uuid_simple() -> String {
     use std::sync::atomic::{AtomicU64, Ordering};
@@ -191,7 +226,11 @@ struct EventsParams {
 async fn events_sse(
     Query(params): Query<EventsParams>,
     State(state): State<Arc<SharedState>>,
-) -> Sse<impl futures::Stream<Item = Result<Event, Infallible>>> {
+    headers: HeaderMap,
+) -> Response {
+    if let Err(e) = check(Method::Subscribe, &headers, &state) {
+        return error_response(e.to_json());
+    }
     let mut rx = state.event_bus.subscribe();
     let domain_filter = params.domain;
 
@@ -206,7 +245,7 @@ async fn events_sse(
                         }
                     }
                     if let Ok(json) = serde_json::to_string(&event) {
-                        yield Ok(Event::default().data(json));
+                        yield Ok::<_, Infallible>(Event::default().data(json));
                     }
                 }
                 Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {
@@ -220,11 +259,16 @@ async fn events_sse(
         }
     };
 
-    Sse::new(stream).keep_alive(axum::response::sse::KeepAlive::default())
+    Sse::new(stream)
+        .keep_alive(axum::response::sse::KeepAlive::default())
+        .into_response()
 }
 
 /// Enhanced status endpoint returning richer data for the dashboard.
-async fn handle_status(State(state): State<Arc<SharedState>>) -> Json<Value> {
+async fn handle_status(State(state): State<Arc<SharedState>>, headers: HeaderMap) -> Response {
+    if let Err(e) = check(Method::Status, &headers, &state) {
+        return error_response(e.to_json());
+    }
     let uptime_s = state.started_at.elapsed().as_secs_f64();
     let maps_lock = Arc::clone(&state.maps);
     let maps = maps_lock.read().await;
@@ -267,56 +311,81 @@ async fn handle_status(State(state): State<Arc<SharedState>>) -> Json<Value> {
             "max": 8,
             "memory_mb": 0,
         },
+        "cache": crate::server::disk_cache_status(),
     }))
+    .into_response()
 }
 
-async fn handle_map(State(state): State<Arc<SharedState>>, Json(body): Json<Value>) -> Json<Value> {
-    dispatch("map", body, state).await
+async fn handle_map(
+    State(state): State<Arc<SharedState>>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    dispatch("map", body, &headers, state).await
 }
 
 async fn handle_query(
     State(state): State<Arc<SharedState>>,
+    headers: HeaderMap,
     Json(body): Json<Value>,
-) -> Json<Value> {
-    dispatch("query", body, state).await
+) -> Response {
+    dispatch("query", body, &headers, state).await
 }
 
 async fn handle_pathfind(
     State(state): State<Arc<SharedState>>,
+    headers: HeaderMap,
     Json(body): Json<Value>,
-) -> Json<Value> {
-    dispatch("pathfind", body, state).await
+) -> Response {
+    dispatch("pathfind", body, &headers, state).await
 }
 
-async fn handle_act(State(state): State<Arc<SharedState>>, Json(body): Json<Value>) -> Json<Value> {
-    dispatch("act", body, state).await
+async fn handle_act(
+    State(state): State<Arc<SharedState>>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    dispatch("act", body, &headers, state).await
 }
 
 async fn handle_perceive(
     State(state): State<Arc<SharedState>>,
+    headers: HeaderMap,
     Json(body): Json<Value>,
-) -> Json<Value> {
-    dispatch("perceive", body, state).await
+) -> Response {
+    dispatch("perceive", body, &headers, state).await
 }
 
 async fn handle_compare(
     State(state): State<Arc<SharedState>>,
+    headers: HeaderMap,
     Json(body): Json<Value>,
-) -> Json<Value> {
+) -> Response {
     // Compare is a composite: map multiple domains then query across them
     // For now, treat it like a map request — the full compare logic
     // can be added when the protocol supports it natively.
-    dispatch("map", body, state).await
+    dispatch("map", body, &headers, state).await
 }
 
 async fn handle_auth(
     State(state): State<Arc<SharedState>>,
+    headers: HeaderMap,
     Json(body): Json<Value>,
-) -> Json<Value> {
-    dispatch("auth", body, state).await
+) -> Response {
+    dispatch("auth", body, &headers, state).await
+}
+
+async fn handle_saved_queries(
+    State(state): State<Arc<SharedState>>,
+    headers: HeaderMap,
+) -> Response {
+    dispatch("saved_queries", serde_json::json!({}), &headers, state).await
 }
 
-async fn handle_list_maps(State(state): State<Arc<SharedState>>) -> Json<Value> {
+async fn handle_list_maps(State(state): State<Arc<SharedState>>, headers: HeaderMap) -> Response {
+    if let Err(e) = check(Method::Status, &headers, &state) {
+        return error_response(e.to_json());
+    }
     let maps = state.maps.read().await;
     let list: Vec<Value> = maps
         .iter()
@@ -329,13 +398,75 @@ async fn handle_list_maps(State(state): State<Arc<SharedState>>) -> Json<Value>
         })
         .collect();
     drop(maps);
-    Json(serde_json::json!({ "maps": list }))
+    Json(serde_json::json!({ "maps": list })).into_response()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn test_api_keys_enforced_on_rest() {
+        use crate::server::Server;
+        use crate::trust::api_keys::{ApiKeys, KeyStore, Permission};
+
+        let dir = tempfile::tempdir().unwrap();
+        let keys_path = dir.path().join("api_keys.json");
+        let mut keys = ApiKeys::default();
+        let (_, reader) = keys.add("reader", Permission::Read).unwrap();
+        keys.save(&keys_path).unwrap();
+        let state = Server::new(&dir.path().join("cortex.sock"))
+            .with_api_keys(KeyStore::open(keys_path))
+            .shared_state();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router(state)).await });
+        let client = reqwest::Client::new();
+        let url = |path: &str| format!("http://{addr}{path}");
+
+        let act = serde_json::json!({"session_id": "s", "node": 0, "opcode": [1, 0]});
+        let resp = client
+            .post(url("/api/v1/act"))
+            .json(&act)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = client.get(url("/api/v1/status")).send().await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = client
+            .get(url("/api/v1/status"))
+            .bearer_auth("cortex_00000000_nope")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let resp = client
+            .get(url("/api/v1/status"))
+            .bearer_auth(&reader)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = client
+            .post(url("/api/v1/act"))
+            .bearer_auth(&reader)
+            .json(&act)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        let body: Value = resp.json().await.unwrap();
+        assert_eq!(body["error"]["code"], "E_FORBIDDEN");
+
+        assert_eq!(
+            client.get(url("/health")).send().await.unwrap().status(),
+            StatusCode::OK
+        );
+    }
+
     #[test]
     fn test_uuid_simple_unique() {
         let a = uuid_simple();
@@ -2,18 +2,42 @@
 //!
 //! Handles connection lifecycle, inactivity timeouts, malformed JSON,
 //! rate limiting, and concurrent request management.
+//!
+//! A connection answers one request at a time, in order, unless its
+//! handshake asks for `pipeline: N`: then up to N requests run at once and
+//! each response is written as soon as it is ready, matched by `id`.
 
 use crate::acquisition::http_session::HttpSession;
+use crate::acquisition::profiles::{CrawlProfile, ProfileStore};
+use crate::audit::{
+    self,
+    logger::{AuditEvent, AuditLogger},
+};
+use crate::cancel::{self, InFlight};
+use crate::cartography::annotations::{self, AnnotationBody, AnnotationStore};
+use crate::cartography::content_policy::ContentPolicy;
+use crate::cartography::frontier::{self, Frontier};
 use crate::cartography::mapper::{MapRequest, Mapper};
-use crate::events::{CortexEvent, EventBus};
+use crate::cartography::quality::{self, Fallback};
+use crate::cartography::semantic::SemanticEncoder;
+use crate::collective::registry::LocalRegistry;
+use crate::events::{self, CortexEvent, EventBus, EventLog, LoggedEvent, ReplayFilter};
+use crate::intelligence::progressive;
+use crate::live::act as act_handler;
 use crate::live::perceive as perceive_handler;
 use crate::map::types::{
-    FeatureRange, NodeFlags, NodeQuery, PageType, PathConstraints, PathMinimize, SiteMap,
+    FeatureRange, NodeFlags, NodeQuery, OpCode, PageType, PathConstraints, PathMinimize, SiteMap,
     FEATURE_DIM,
 };
-use crate::navigation::{pathfinder, query};
-use crate::protocol::{self, Method};
-use crate::renderer::Renderer;
+use crate::navigation::cost_model::{ActionCosts, Outcome};
+use crate::navigation::{diversify, pathfinder, query};
+use crate::project::Projects;
+use crate::protocol::{self, ErrorCode, Method, ProtocolError};
+use crate::renderer::{self, Renderer};
+use crate::temporal::scheduler::{CrawlKind, CrawlScheduler};
+use crate::temporal::watch::WatchManager;
+use crate::trust::api_keys::{ApiKey, KeyStore, Permission};
+use crate::trust::lineage::MapLineage;
 use anyhow::{Context, Result};
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
@@ -21,7 +45,7 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::UnixListener;
-use tokio::sync::{Mutex, Notify, RwLock};
+use tokio::sync::{Mutex, Notify, RwLock, Semaphore};
 use tracing::{error, info, warn};
 
 /// Inactivity timeout per connection (5 minutes — long enough for MAP requests).
@@ -33,6 +57,12 @@ const MAX_REQUEST_SIZE: usize = 10 * 1024 * 1024;
 /// Maximum requests per second per connection.
 const MAX_REQUESTS_PER_SEC: u32 = 100;
 
+/// Most requests one connection may pipeline (have in flight at once).
+const MAX_PIPELINE_DEPTH: u32 = 32;
+
+/// Write half of a connection, shared by its in-flight requests.
+type ConnWriter = Mutex<tokio::net::unix::OwnedWriteHalf>;
+
 /// Shared state passed to connection handlers.
 ///
 /// Made public so the REST API module can share the same dispatch state.
@@ -47,6 +77,35 @@ pub struct SharedState {
     /// Global event bus for real-time telemetry. All components emit events here;
     /// consumers (SSE, MCP, dashboard, logs) subscribe independently.
     pub event_bus: Arc<EventBus>,
+    /// Per-domain crawl calendars enforced for automated crawls.
+    pub scheduler: Arc<RwLock<CrawlScheduler>>,
+    /// Action outcomes learned from ACT, used to weight PATHFIND edges.
+    pub action_costs: Arc<RwLock<ActionCosts>>,
+    /// Watch rules evaluated when a re-map changes a domain.
+    pub watches: Arc<std::sync::Mutex<WatchManager>>,
+    /// Audit trail for state-changing requests, if enabled.
+    pub audit: Option<Arc<std::sync::Mutex<AuditLogger>>>,
+    /// MAP and PERCEIVE requests that CANCEL can abort.
+    pub in_flight: Arc<InFlight>,
+    /// API keys socket connections must present, once any exist.
+    pub api_keys: Arc<KeyStore>,
+    /// Labels, corrections and notes on mapped pages.
+    pub annotations: Arc<AnnotationStore>,
+    /// Crawl identities ACT and PERCEIVE browse domains as.
+    pub profiles: Arc<ProfileStore>,
+    /// Sentence model for semantic QUERY, if installed.
+    pub semantic: Option<Arc<SemanticEncoder>>,
+}
+
+/// Who sent a request, which decides what it may do.
+#[derive(Debug, Clone)]
+pub enum Caller {
+    /// A socket connection or REST request that presented this API key.
+    Key(ApiKey),
+    /// A socket connection or REST request that has not presented a key. It
+    /// may do anything while no API keys exist, and nothing but handshake
+    /// once one does.
+    Anonymous,
 }
 
 /// The Cortex socket server.
@@ -67,6 +126,24 @@ pub struct Server {
     renderer: Option<Arc<dyn Renderer>>,
     /// Global event bus for real-time telemetry.
     event_bus: Arc<EventBus>,
+    /// Per-domain crawl calendars.
+    scheduler: Arc<RwLock<CrawlScheduler>>,
+    /// Learned action costs.
+    action_costs: Arc<RwLock<ActionCosts>>,
+    /// Watch rules and their evaluation state.
+    watches: Arc<std::sync::Mutex<WatchManager>>,
+    /// Audit trail for state-changing requests.
+    audit: Option<Arc<std::sync::Mutex<AuditLogger>>>,
+    /// Cancellable requests in progress.
+    in_flight: Arc<InFlight>,
+    /// API keys for socket connections.
+    api_keys: Arc<KeyStore>,
+    /// Annotations on mapped pages.
+    annotations: Arc<AnnotationStore>,
+    /// Domains' crawl profiles.
+    profiles: Arc<ProfileStore>,
+    /// Sentence model for semantic QUERY.
+    semantic: Option<Arc<SemanticEncoder>>,
 }
 
 impl Server {
@@ -82,6 +159,15 @@ impl Server {
             mapper: None,
             renderer: None,
             event_bus: Arc::new(EventBus::new(512)),
+            scheduler: Arc::new(RwLock::new(CrawlScheduler::new())),
+            action_costs: Arc::new(RwLock::new(ActionCosts::new())),
+            watches: Arc::new(std::sync::Mutex::new(WatchManager::new())),
+            audit: None,
+            in_flight: Arc::new(InFlight::new()),
+            api_keys: Arc::new(KeyStore::disabled()),
+            annotations: Arc::new(AnnotationStore::default()),
+            profiles: Arc::new(ProfileStore::default()),
+            semantic: None,
         }
     }
 
@@ -92,6 +178,57 @@ impl Server {
         self
     }
 
+    /// Persist state-changing events to `log` so clients can replay them.
+    pub fn with_event_log(mut self, log: EventLog) -> Self {
+        self.event_bus = Arc::new(EventBus::with_log(512, log));
+        self
+    }
+
+    /// Enforce the given crawl calendars for automated crawls.
+    pub fn with_scheduler(mut self, scheduler: CrawlScheduler) -> Self {
+        self.scheduler = Arc::new(RwLock::new(scheduler));
+        self
+    }
+
+    /// Record MAP, REFRESH, ACT, AUTH and CANCEL requests in `log`.
+    pub fn with_audit_log(mut self, log: AuditLogger) -> Self {
+        self.audit = Some(Arc::new(std::sync::Mutex::new(log)));
+        self
+    }
+
+    /// Require socket connections to present one of the keys in `keys`
+    /// (while it holds any).
+    pub fn with_api_keys(mut self, keys: KeyStore) -> Self {
+        self.api_keys = Arc::new(keys);
+        self
+    }
+
+    /// Keep ANNOTATE's annotations in `store` (by default
+    /// `~/.cortex/annotations`).
+    pub fn with_annotations(mut self, store: AnnotationStore) -> Self {
+        self.annotations = Arc::new(store);
+        self
+    }
+
+    /// Read the crawl profiles ACT and PERCEIVE browse as from `store` (by
+    /// default `~/.cortex/profiles.json`).
+    pub fn with_profiles(mut self, store: ProfileStore) -> Self {
+        self.profiles = Arc::new(store);
+        self
+    }
+
+    /// Answer QUERY `mode: "semantic"` by embedding goals with `encoder`.
+    pub fn with_semantic(mut self, encoder: Arc<SemanticEncoder>) -> Self {
+        self.semantic = Some(encoder);
+        self
+    }
+
+    /// Learn action costs into `costs`, persisting after every ACT.
+    pub fn with_action_costs(mut self, costs: ActionCosts) -> Self {
+        self.action_costs = Arc::new(RwLock::new(costs));
+        self
+    }
+
     /// Get the shutdown notifier (for external shutdown signaling).
     pub fn shutdown_handle(&self) -> Arc<Notify> {
         Arc::clone(&self.shutdown)
@@ -110,6 +247,15 @@ impl Server {
             mapper: self.mapper.clone(),
             renderer: self.renderer.clone(),
             event_bus: Arc::clone(&self.event_bus),
+            scheduler: Arc::clone(&self.scheduler),
+            action_costs: Arc::clone(&self.action_costs),
+            watches: Arc::clone(&self.watches),
+            audit: self.audit.clone(),
+            in_flight: Arc::clone(&self.in_flight),
+            api_keys: Arc::clone(&self.api_keys),
+            annotations: Arc::clone(&self.annotations),
+            profiles: Arc::clone(&self.profiles),
+            semantic: self.semantic.clone(),
         })
     }
 
@@ -135,6 +281,15 @@ impl Server {
             mapper: self.mapper.clone(),
             renderer: self.renderer.clone(),
             event_bus: Arc::clone(&self.event_bus),
+            scheduler: Arc::clone(&self.scheduler),
+            action_costs: Arc::clone(&self.action_costs),
+            watches: Arc::clone(&self.watches),
+            audit: self.audit.clone(),
+            in_flight: Arc::clone(&self.in_flight),
+            api_keys: Arc::clone(&self.api_keys),
+            annotations: Arc::clone(&self.annotations),
+            profiles: Arc::clone(&self.profiles),
+            semantic: self.semantic.clone(),
         });
 
         loop {
@@ -166,40 +321,6 @@ impl Server {
     }
 }
 
-/// Wrapper to assert that a future is Send.
-///
-/// The `handle_connection` future is actually Send — all concrete types
-/// (Arc, String, etc.) are Send. However, the Rust compiler cannot prove
-/// `Send` for higher-ranked lifetime bounds that appear in certain crate
-/// types (e.g., selectors/scraper `SelectorErrorKind<'_>`, chromiumoxide
-/// types). Since we've verified all concrete data is Send, we use this
-/// wrapper to bypass the overly-conservative compiler analysis.
-///
-/// # Safety
-/// The caller must ensure the wrapped future only contains Send types.
-struct AssertSend<F>(F);
-
-// SAFETY: The `handle_connection` future contains only Arc<SharedState>,
-// UnixStream, String, serde_json::Value, and other Send types.
-// The compiler fails to prove Send due to higher-ranked lifetime bounds
-// in transitive dependencies (scraper, chromiumoxide), not due to actual
-// non-Send data.
-unsafe impl<F: std::future::Future> Send for AssertSend<F> {}
-
-impl<F: std::future::Future> std::future::Future for AssertSend<F> {
-    type Output = F::Output;
-    fn poll(
-        self: std::pin::Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
-    ) -> std::task::Poll<Self::Output> {
-        // SAFETY: We're just forwarding the poll call. The inner future's
-        // pin projection is safe because AssertSend is a repr(transparent)-like
-        // wrapper (single field).
-        let inner = unsafe { self.map_unchecked_mut(|s| &mut s.0) };
-        inner.poll(cx)
-    }
-}
-
 /// Spawn a connection handler task.
 fn spawn_connection(stream: tokio::net::UnixStream, state: Arc<SharedState>) {
     let fut = async move {
@@ -207,13 +328,14 @@ fn spawn_connection(stream: tokio::net::UnixStream, state: Arc<SharedState>) {
             warn!("connection error: {e}");
         }
     };
-    tokio::spawn(AssertSend(fut));
+    tokio::spawn(fut);
 }
 
 /// Handle a single client connection with inactivity timeout and rate limiting.
 async fn handle_connection(stream: tokio::net::UnixStream, state: Arc<SharedState>) -> Result<()> {
-    let (reader, mut writer) = stream.into_split();
+    let (reader, writer) = stream.into_split();
     let mut reader = BufReader::new(reader);
+    let writer: Arc<ConnWriter> = Arc::new(Mutex::new(writer));
     let mut line = String::new();
 
     // Emit agent connected event
@@ -226,9 +348,20 @@ async fn handle_connection(stream: tokio::net::UnixStream, state: Arc<SharedStat
     let mut rate_window_start = Instant::now();
     let mut rate_count: u32 = 0;
 
+    // Set by the first request that carries a valid `api_key`.
+    let mut caller = Caller::Anonymous;
+
+    // Requests allowed in flight; one until a handshake negotiates more.
+    let mut depth: u32 = 1;
+    let mut slots = Arc::new(Semaphore::new(1));
+
     loop {
         line.clear();
 
+        // Wait for a free slot before reading, so a client that sends more
+        // than it negotiated is held back rather than refused.
+        let permit = Arc::clone(&slots).acquire_owned().await?;
+
         // Read with inactivity timeout
         let read_result =
             tokio::time::timeout(INACTIVITY_TIMEOUT, reader.read_line(&mut line)).await;
@@ -240,14 +373,13 @@ async fn handle_connection(stream: tokio::net::UnixStream, state: Arc<SharedStat
                 if line.len() > MAX_REQUEST_SIZE {
                     let resp = protocol::format_error(
                         "unknown",
-                        "E_MESSAGE_TOO_LARGE",
+                        ErrorCode::MessageTooLarge,
                         &format!(
                             "Request exceeds maximum size of {}MB",
                             MAX_REQUEST_SIZE / (1024 * 1024)
                         ),
                     );
-                    writer.write_all(resp.as_bytes()).await.ok();
-                    writer.flush().await.ok();
+                    write_line(&writer, &resp).await;
                     continue;
                 }
 
@@ -266,59 +398,84 @@ async fn handle_connection(stream: tokio::net::UnixStream, state: Arc<SharedStat
                 if rate_count > MAX_REQUESTS_PER_SEC {
                     let resp = protocol::format_error(
                         "unknown",
-                        "E_RATE_LIMITED",
+                        ErrorCode::RateLimited,
                         &format!(
                             "Rate limit exceeded: max {} requests/second",
                             MAX_REQUESTS_PER_SEC
                         ),
                     );
-                    writer.write_all(resp.as_bytes()).await.ok();
-                    writer.flush().await.ok();
+                    write_line(&writer, &resp).await;
                     continue;
                 }
 
                 let response = match protocol::parse_request(trimmed) {
-                    Ok(req) => {
-                        // Check for duplicate request ID
-                        let ids_lock = Arc::clone(&state.seen_ids);
-                        let mut ids = ids_lock.lock().await;
-                        if ids.contains(&req.id) {
-                            protocol::format_error(
-                                &req.id,
-                                "E_DUPLICATE_ID",
-                                &format!("Request ID '{}' has already been used", req.id),
-                            )
+                    Ok(req) if !authenticate(&req, &state, &mut caller) => {
+                        protocol::format_error(&req.id, ErrorCode::Unauthorized, "Invalid API key")
+                    }
+                    Ok(req)
+                        if req.method == Method::Subscribe
+                            && req.params.get("follow").and_then(|v| v.as_bool()) == Some(true) =>
+                    {
+                        if let Err((code, message)) = authorize(&req.method, &caller, &state) {
+                            protocol::format_error(&req.id, code, &message)
                         } else {
-                            ids.insert(req.id.clone());
-                            // Keep set bounded (last 10000 IDs)
-                            if ids.len() > 10000 {
-                                ids.clear();
-                            }
-                            drop(ids);
-                            handle_request(req, Arc::clone(&state)).await
+                            // Streaming subscription takes over the connection
+                            // once earlier requests have answered.
+                            drop(permit);
+                            drop(slots.acquire_many(depth).await?);
+                            let mut writer = writer.lock().await;
+                            serve_subscription(&req, &state, &mut reader, &mut writer).await;
+                            break;
                         }
                     }
+                    Ok(req) => match claim_id(&state, &req.id).await {
+                        Some(duplicate) => duplicate,
+                        None if req.method == Method::Handshake => {
+                            // Renegotiate once earlier requests have answered.
+                            drop(permit);
+                            drop(slots.acquire_many(depth).await?);
+                            depth = pipeline_depth(&req.params);
+                            slots = Arc::new(Semaphore::new(depth as usize));
+                            handle_request(req, Arc::clone(&state), caller.clone()).await
+                        }
+                        None => {
+                            let state = Arc::clone(&state);
+                            let writer = Arc::clone(&writer);
+                            let caller = caller.clone();
+                            tokio::spawn(async move {
+                                let response =
+                                    if req.params.get("progress").and_then(|v| v.as_bool())
+                                        == Some(true)
+                                    {
+                                        handle_with_progress(req, state, caller, &writer).await
+                                    } else {
+                                        handle_request(req, state, caller).await
+                                    };
+                                // A client that has gone is noticed by the read loop.
+                                write_line(&writer, &response).await;
+                                drop(permit);
+                            });
+                            continue;
+                        }
+                    },
                     Err(e) => {
                         // Malformed JSON — return error but keep connection open
                         let msg = e.to_string();
                         if msg.contains("expected") || msg.contains("invalid") {
                             protocol::format_error(
                                 "unknown",
-                                "E_INVALID_JSON",
+                                ErrorCode::InvalidJson,
                                 &format!("Malformed JSON: {msg}"),
                             )
                         } else {
-                            protocol::format_error("unknown", "E_INVALID_PARAMS", &msg)
+                            protocol::format_error("unknown", ErrorCode::InvalidParams, &msg)
                         }
                     }
                 };
 
-                if writer.write_all(response.as_bytes()).await.is_err() {
+                if !write_line(&writer, &response).await {
                     break; // client disconnected
                 }
-                if writer.flush().await.is_err() {
-                    break;
-                }
             }
             Ok(Err(e)) => {
                 warn!("read error: {e}");
@@ -328,17 +485,19 @@ async fn handle_connection(stream: tokio::net::UnixStream, state: Arc<SharedStat
                 // Inactivity timeout — close connection
                 let resp = protocol::format_error(
                     "timeout",
-                    "E_INACTIVITY_TIMEOUT",
+                    ErrorCode::InactivityTimeout,
                     "Connection closed due to inactivity",
                 );
-                writer.write_all(resp.as_bytes()).await.ok();
-                writer.flush().await.ok();
+                write_line(&writer, &resp).await;
                 info!("closing inactive connection");
                 break;
             }
         }
     }
 
+    // Requests still running answer before the client counts as gone.
+    drop(slots.acquire_many(depth).await);
+
     // Emit agent disconnected event
     state.event_bus.emit(CortexEvent::AgentDisconnected {
         agent_type: "socket".to_string(),
@@ -347,19 +506,339 @@ async fn handle_connection(stream: tokio::net::UnixStream, state: Arc<SharedStat
     Ok(())
 }
 
+/// Write a response line to the client. Returns false if it has gone.
+async fn write_line(writer: &ConnWriter, line: &str) -> bool {
+    let mut writer = writer.lock().await;
+    writer.write_all(line.as_bytes()).await.is_ok() && writer.flush().await.is_ok()
+}
+
+/// Mark a request ID as used, or return the E_DUPLICATE_ID response if it
+/// already was.
+async fn claim_id(state: &SharedState, id: &str) -> Option<String> {
+    let mut ids = state.seen_ids.lock().await;
+    if ids.contains(id) {
+        return Some(protocol::format_error(
+            id,
+            ErrorCode::DuplicateId,
+            &format!("Request ID '{id}' has already been used"),
+        ));
+    }
+    ids.insert(id.to_string());
+    // Keep set bounded (last 10000 IDs)
+    if ids.len() > 10000 {
+        ids.clear();
+    }
+    None
+}
+
+/// Pipeline depth granted for a handshake's `pipeline` param: 1 (no
+/// pipelining) when absent, at most `MAX_PIPELINE_DEPTH`.
+fn pipeline_depth(params: &serde_json::Value) -> u32 {
+    params
+        .get("pipeline")
+        .and_then(|v| v.as_u64())
+        .map_or(1, |n| n.clamp(1, MAX_PIPELINE_DEPTH as u64) as u32)
+}
+
+/// Parse replay filter params: `since` (RFC 3339), `after_seq`, `domain`.
+fn parse_replay_filter(params: &serde_json::Value) -> Result<ReplayFilter> {
+    let since = match params.get("since").and_then(|v| v.as_str()) {
+        Some(s) => Some(
+            chrono::DateTime::parse_from_rfc3339(s)
+                .with_context(|| format!("invalid 'since' timestamp: {s}"))?
+                .with_timezone(&chrono::Utc),
+        ),
+        None => None,
+    };
+    Ok(ReplayFilter {
+        since,
+        after_seq: params.get("after_seq").and_then(|v| v.as_u64()),
+        domain: params
+            .get("domain")
+            .and_then(|v| v.as_str())
+            .map(String::from),
+    })
+}
+
+/// Handle a SUBSCRIBE request without `follow`: replay the persistent log.
+async fn handle_subscribe(req: &protocol::Request, state: &SharedState) -> String {
+    let filter = match parse_replay_filter(&req.params) {
+        Ok(f) => f,
+        Err(e) => {
+            return protocol::format_error(&req.id, ErrorCode::InvalidParams, &format!("{e:#}"))
+        }
+    };
+    let Some(path) = state.event_bus.log_path() else {
+        return protocol::format_error(
+            &req.id,
+            ErrorCode::NoEventLog,
+            "Event log is not enabled on this Cortex instance",
+        );
+    };
+    match replay_blocking(path, filter).await {
+        Ok(entries) => {
+            let last_seq = entries.last().map(|e| e.seq);
+            protocol::format_response(
+                &req.id,
+                serde_json::json!({
+                    "events": entries,
+                    "last_seq": last_seq,
+                }),
+            )
+        }
+        Err(e) => protocol::format_error(&req.id, ErrorCode::EventLog, &format!("{e:#}")),
+    }
+}
+
+/// Replay the persistent log on the blocking pool, off the async runtime.
+async fn replay_blocking(path: PathBuf, filter: ReplayFilter) -> Result<Vec<LoggedEvent>> {
+    tokio::task::spawn_blocking(move || events::replay(&path, &filter)).await?
+}
+
+/// Serve a streaming SUBSCRIBE: replay matching history, then forward
+/// newly persisted events as `{"id", "event"}` lines until the client
+/// disconnects. A subscriber that falls behind the live broadcast catches
+/// up from the log, after the last event it was sent.
+async fn serve_subscription(
+    req: &protocol::Request,
+    state: &SharedState,
+    reader: &mut BufReader<tokio::net::unix::OwnedReadHalf>,
+    writer: &mut tokio::net::unix::OwnedWriteHalf,
+) {
+    // Subscribe before reading history so no event falls in the gap.
+    let mut rx = state.event_bus.subscribe_logged();
+    let response = handle_subscribe(req, state).await;
+    if writer.write_all(response.as_bytes()).await.is_err() {
+        return;
+    }
+    let _ = writer.flush().await;
+
+    let mut filter = parse_replay_filter(&req.params).unwrap_or_default();
+    if let Some(last) = serde_json::from_str::<serde_json::Value>(&response)
+        .ok()
+        .and_then(|v| v["result"]["last_seq"].as_u64())
+    {
+        filter.after_seq = Some(last);
+    }
+
+    let mut discard = String::new();
+    loop {
+        tokio::select! {
+            received = rx.recv() => {
+                let entries = match received {
+                    Ok(entry) => vec![entry],
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {
+                        let Some(path) = state.event_bus.log_path() else {
+                            break;
+                        };
+                        match replay_blocking(path, filter.clone()).await {
+                            Ok(entries) => entries,
+                            Err(e) => {
+                                warn!("subscriber fell behind and replay failed: {e:#}");
+                                break;
+                            }
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                };
+                if !send_entries(req, &mut filter, entries, writer).await {
+                    break;
+                }
+            }
+            read = reader.read_line(&mut discard) => {
+                // Any further input (or EOF) ends the subscription.
+                if matches!(read, Ok(0) | Err(_)) {
+                    break;
+                }
+                discard.clear();
+            }
+        }
+    }
+}
+
+/// Write the entries that pass `filter` as `{"id", "event"}` lines,
+/// advancing its `after_seq` past each one so none is sent twice.
+/// Returns false once the client has gone.
+async fn send_entries(
+    req: &protocol::Request,
+    filter: &mut ReplayFilter,
+    entries: Vec<LoggedEvent>,
+    writer: &mut tokio::net::unix::OwnedWriteHalf,
+) -> bool {
+    for entry in entries {
+        if !filter.matches(&entry) {
+            continue;
+        }
+        filter.after_seq = Some(entry.seq);
+        let line = format!("{}\n", serde_json::json!({"id": req.id, "event": entry}));
+        if writer.write_all(line.as_bytes()).await.is_err() || writer.flush().await.is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+/// Handle a request that asked for `progress: true`, writing its
+/// `MapProgress` events to the client as `{"id", "progress"}` lines while
+/// it runs. Returns the final response, which the caller writes as usual.
+async fn handle_with_progress(
+    req: protocol::Request,
+    state: Arc<SharedState>,
+    caller: Caller,
+    writer: &ConnWriter,
+) -> String {
+    // Subscribe before dispatching so the first update is not missed.
+    let mut rx = state.event_bus.subscribe();
+    let id = req.id.clone();
+    let response = handle_request(req, state, caller);
+    tokio::pin!(response);
+    loop {
+        tokio::select! {
+            response = &mut response => return response,
+            received = rx.recv() => match received {
+                Ok(CortexEvent::MapProgress { request_id, progress, .. }) if request_id == id => {
+                    let line = format!(
+                        "{}\n",
+                        serde_json::json!({"id": id, "progress": progress})
+                    );
+                    // If the client has gone, keep mapping; the read
+                    // loop will notice.
+                    write_line(writer, &line).await;
+                }
+                Ok(_) | Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {}
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => return response.await,
+            },
+        }
+    }
+}
+
 /// Handle a parsed request and return a JSON response string.
 ///
 /// Takes ownership of the Request and Arc<SharedState> to avoid holding references
 /// across await points, which would prevent the future from being Send (required by tokio::spawn).
 ///
 /// Public so the REST API module can dispatch requests through the same handler.
-pub async fn handle_request(req: protocol::Request, state: Arc<SharedState>) -> String {
+/// Requests `caller` may not make fail with E_UNAUTHORIZED or E_FORBIDDEN.
+/// State-changing requests are recorded in the audit log, if one is attached.
+pub async fn handle_request(
+    req: protocol::Request,
+    state: Arc<SharedState>,
+    caller: Caller,
+) -> String {
+    let denied = authorize(&req.method, &caller, &state)
+        .err()
+        .map(|(code, message)| protocol::format_error(&req.id, code, &message));
+    let Some(audit) = state.audit.clone().filter(|_| req.method.is_audited()) else {
+        return match denied {
+            Some(response) => response,
+            None => dispatch_request(req, state, caller).await,
+        };
+    };
+
+    let param = |key: &str| {
+        req.params
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(String::from)
+    };
+    let actor = match &caller {
+        Caller::Key(key) => format!("key:{}", key.name),
+        _ => param("actor").unwrap_or_else(|| "agent".to_string()),
+    };
+    let mut event = AuditEvent::new(req.method.as_str(), &actor);
+    event.domain = param("domain");
+    event.url = param("url");
+    event.session_id = param("session_id");
+    event.params = audit::logger::redact(&req.params);
+
+    let started = Instant::now();
+    let response = match denied {
+        Some(response) => response,
+        None => dispatch_request(req, state, caller).await,
+    };
+    event.duration_ms = started.elapsed().as_millis() as u64;
+    let parsed: serde_json::Value = serde_json::from_str(response.trim()).unwrap_or_default();
+    if let Some(code) = parsed.pointer("/error/code").and_then(|c| c.as_str()) {
+        event.status = format!("error:{code}");
+    } else if let Some(result) = parsed.get("result") {
+        event.result_hash = Some(audit::logger::hash_result(result));
+    }
+    match audit.lock() {
+        Ok(mut log) => {
+            if let Err(e) = log.log(&event) {
+                warn!("failed to write audit log: {e}");
+            }
+        }
+        Err(_) => warn!("audit log lock poisoned"),
+    }
+    response
+}
+
+/// Check an `api_key` parameter, if the request carries one, and make its
+/// key the connection's caller. Returns false when the key is not valid.
+fn authenticate(req: &protocol::Request, state: &SharedState, caller: &mut Caller) -> bool {
+    let Some(secret) = req.params.get("api_key").and_then(|v| v.as_str()) else {
+        return true;
+    };
+    match state.api_keys.verify(secret) {
+        Some(key) => {
+            *caller = Caller::Key(key);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Whether `caller` may make a `method` request, or the error code and
+/// message to refuse it with.
+pub(crate) fn authorize(
+    method: &Method,
+    caller: &Caller,
+    state: &SharedState,
+) -> Result<(), (ErrorCode, String)> {
+    let Some(required) = Permission::required_for(method) else {
+        return Ok(());
+    };
+    match caller {
+        Caller::Anonymous if !state.api_keys.required() => Ok(()),
+        Caller::Anonymous => Err((
+            ErrorCode::Unauthorized,
+            "this daemon requires an API key (send api_key, or set CORTEX_API_KEY)".to_string(),
+        )),
+        Caller::Key(key) if !state.api_keys.is_active(&key.id) => Err((
+            ErrorCode::Unauthorized,
+            format!("API key '{}' has been revoked", key.name),
+        )),
+        Caller::Key(key) if key.permission < required => Err((
+            ErrorCode::Forbidden,
+            format!(
+                "API key '{}' has {} permission; {} needs {}",
+                key.name,
+                key.permission,
+                method.as_str(),
+                required
+            ),
+        )),
+        Caller::Key(_) => Ok(()),
+    }
+}
+
+async fn dispatch_request(
+    req: protocol::Request,
+    state: Arc<SharedState>,
+    caller: Caller,
+) -> String {
     match req.method {
         Method::Handshake => {
             let result = protocol::HandshakeResult {
                 server_version: env!("CARGO_PKG_VERSION").to_string(),
                 protocol_version: 1,
                 compatible: true,
+                permission: match &caller {
+                    Caller::Key(key) => Some(key.permission.to_string()),
+                    _ => None,
+                },
+                pipeline: pipeline_depth(&req.params),
             };
             protocol::format_response(&req.id, serde_json::to_value(result).unwrap_or_default())
         }
@@ -373,6 +852,14 @@ pub async fn handle_request(req: protocol::Request, state: Arc<SharedState>) ->
                 let r = state.renderer.clone();
                 r.as_ref().map(|r| r.active_contexts() as u32).unwrap_or(0)
             };
+            let cache = disk_cache_status();
+            let cache_mb = cache["bytes"].as_u64().unwrap_or(0) / (1024 * 1024);
+            let in_flight: Vec<serde_json::Value> = state
+                .in_flight
+                .list()
+                .into_iter()
+                .map(|(id, method)| serde_json::json!({"id": id, "method": method}))
+                .collect();
             protocol::format_response(
                 &req.id,
                 serde_json::json!({
@@ -388,7 +875,9 @@ pub async fn handle_request(req: protocol::Request, state: Arc<SharedState>) ->
                         "max": 8,
                         "memory_mb": 0,
                     },
-                    "cache_mb": 0,
+                    "cache_mb": cache_mb,
+                    "cache": cache,
+                    "in_flight": in_flight,
                 }),
             )
         }
@@ -397,19 +886,37 @@ pub async fn handle_request(req: protocol::Request, state: Arc<SharedState>) ->
         Method::Pathfind => handle_pathfind(&req, Arc::clone(&state)).await,
         Method::Perceive => handle_perceive(&req, Arc::clone(&state)).await,
         Method::Auth => handle_auth(&req, Arc::clone(&state)).await,
-        Method::Refresh | Method::Act | Method::Watch => protocol::format_error(
-            &req.id,
-            "E_NOT_IMPLEMENTED",
-            &format!("{:?} not yet implemented", req.method),
-        ),
+        Method::Subscribe => handle_subscribe(&req, &state).await,
+        Method::SavedQueries => handle_saved_queries(&req),
+        Method::Wql => handle_wql(&req, &state).await,
+        Method::Annotate => handle_annotate(&req, &state).await,
+        Method::Annotations => handle_annotations(&req, &state).await,
+        Method::Cancel => handle_cancel(&req, &state),
+        Method::Reload => protocol::format_response(&req.id, reload(&state).await),
+        Method::Refresh | Method::Watch => {
+            let kind = if req.method == Method::Refresh {
+                CrawlKind::Refresh
+            } else {
+                CrawlKind::WatchReacquire
+            };
+            if let Some(blocked) = check_crawl_window(&req, &state, kind).await {
+                return blocked;
+            }
+            protocol::format_error(
+                &req.id,
+                ErrorCode::NotImplemented,
+                &format!("{:?} not yet implemented", req.method),
+            )
+        }
+        Method::Act => handle_act(&req, state).await,
         Method::AuthConsent | Method::AuthMfa => protocol::format_error(
             &req.id,
-            "E_NOT_IMPLEMENTED",
+            ErrorCode::NotImplemented,
             &format!("{:?} not yet implemented — use auth first", req.method),
         ),
         Method::ConnectWs | Method::SendWs => protocol::format_error(
             &req.id,
-            "E_NOT_IMPLEMENTED",
+            ErrorCode::NotImplemented,
             &format!(
                 "{:?} not yet implemented — WebSocket support pending",
                 req.method
@@ -418,6 +925,34 @@ pub async fn handle_request(req: protocol::Request, state: Arc<SharedState>) ->
     }
 }
 
+/// Enforce the domain's crawl calendar for an automated crawl.
+///
+/// Returns an `E_OUTSIDE_CRAWL_WINDOW` error response if the request's
+/// domain may not be crawled right now, or `None` if it may proceed.
+async fn check_crawl_window(
+    req: &protocol::Request,
+    state: &SharedState,
+    kind: CrawlKind,
+) -> Option<String> {
+    let domain = req.params.get("domain").and_then(|v| v.as_str())?;
+    let scheduler = state.scheduler.read().await;
+    match scheduler.check(domain, kind, chrono::Utc::now()) {
+        Ok(()) => None,
+        Err(block) => {
+            info!("{:?} deferred: {block}", req.method);
+            let mut error = ProtocolError::new(ErrorCode::OutsideCrawlWindow, block.to_string());
+            if let Some(next) = block.next_allowed {
+                let wait = (next - chrono::Utc::now()).num_milliseconds().max(0);
+                error = error.retry_after(wait as u64);
+            }
+            let mut error = error.to_json();
+            error["next_allowed"] = block.next_allowed.map(|t| t.to_rfc3339()).into();
+            let resp = serde_json::json!({ "id": req.id, "error": error });
+            Some(format!("{resp}\n"))
+        }
+    }
+}
+
 /// Handle a MAP request: map a domain and cache the result.
 ///
 /// The mapping operation runs in a separate spawned task to isolate
@@ -430,7 +965,7 @@ async fn handle_map(req: &protocol::Request, state: Arc<SharedState>) -> String
             None => {
                 return protocol::format_error(
                     &req.id,
-                    "E_NO_RENDERER",
+                    ErrorCode::NoRenderer,
                     "Browser renderer not available. Restart Cortex with a browser.",
                 );
             }
@@ -442,7 +977,7 @@ async fn handle_map(req: &protocol::Request, state: Arc<SharedState>) -> String
         _ => {
             return protocol::format_error(
                 &req.id,
-                "E_INVALID_PARAMS",
+                ErrorCode::InvalidParams,
                 "Missing or empty 'domain' parameter",
             );
         }
@@ -463,25 +998,74 @@ async fn handle_map(req: &protocol::Request, state: Arc<SharedState>) -> String
         .get("max_time_ms")
         .and_then(|v| v.as_u64())
         .unwrap_or(30000);
+    // The domain's project supplies politeness settings the request leaves out
+    let project = match Projects::load_default() {
+        Ok(projects) => projects.project_of(&domain).cloned(),
+        Err(e) => {
+            return protocol::format_error(&req.id, ErrorCode::Policy, &format!("{e:#}"));
+        }
+    };
+    let settings = project.map(|p| p.settings).unwrap_or_default();
     let respect_robots = req
         .params
         .get("respect_robots")
         .and_then(|v| v.as_bool())
+        .or(settings.respect_robots)
         .unwrap_or(true);
+    let resume = req
+        .params
+        .get("resume")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let min_quality = match req.params.get("min_quality").filter(|v| !v.is_null()) {
+        None => None,
+        Some(v) => match v.as_f64().filter(|q| (0.0..=1.0).contains(q)) {
+            Some(q) => Some(q as f32),
+            None => {
+                return protocol::format_error(
+                    &req.id,
+                    ErrorCode::InvalidParams,
+                    "'min_quality' must be a number from 0 to 1",
+                );
+            }
+        },
+    };
+
+    // Scheduled (non-interactive) maps honour the domain's crawl calendar
+    if req.params.get("scheduled").and_then(|v| v.as_bool()) == Some(true) {
+        if let Some(blocked) = check_crawl_window(req, &state, CrawlKind::ScheduledMap).await {
+            return blocked;
+        }
+    }
+
+    let policy = match ContentPolicy::load_for(&domain, settings.policy.as_ref()) {
+        Ok(policy) => policy,
+        Err(e) => {
+            return protocol::format_error(&req.id, ErrorCode::Policy, &format!("{e:#}"));
+        }
+    };
 
     let req_id = req.id.clone();
     let maps = Arc::clone(&state.maps);
+    let costs = Arc::clone(&state.action_costs);
+    let flight = state.in_flight.register(&req.id, Method::Map.as_str());
 
     // Create a progress channel for real-time telemetry
     let (ptx, prx) = crate::progress::channel();
-    let map_request = MapRequest {
+    // Fallback remaps for a map below `min_quality` reuse the request.
+    let request = |max_render: u32, resume: bool| MapRequest {
         domain: domain.clone(),
         max_nodes,
         max_render,
         timeout_ms,
         respect_robots,
         progress_tx: Some(ptx.clone()),
+        policy: policy.clone(),
+        audit: state.audit.clone(),
+        cancel: flight.token().clone(),
+        resume,
     };
+    let map_request = request(max_render, resume);
 
     // Emit MapStarted event
     state.event_bus.emit(CortexEvent::MapStarted {
@@ -492,7 +1076,14 @@ async fn handle_map(req: &protocol::Request, state: Arc<SharedState>) -> String
     // Spawn event bridge: translates ProgressEvents → CortexEvents on the bus
     let bridge_bus = Arc::clone(&state.event_bus);
     let bridge_domain = domain.clone();
-    let _bridge = tokio::spawn(progress_event_bridge(prx, bridge_bus, bridge_domain));
+    let tracker = crate::progress::ProgressTracker::new(timeout_ms, max_render);
+    let _bridge = tokio::spawn(progress_event_bridge(
+        prx,
+        bridge_bus,
+        bridge_domain,
+        req_id.clone(),
+        tracker,
+    ));
 
     info!("MAP request: domain={domain}, max_nodes={max_nodes}, max_render={max_render}");
 
@@ -513,16 +1104,103 @@ async fn handle_map(req: &protocol::Request, state: Arc<SharedState>) -> String
                 "unknown panic".to_string()
             };
             error!("MAP panicked for {domain}: {msg}");
-            return build_http_fallback_map(domain.clone(), req_id, maps, false).await;
+            return build_http_fallback_map(domain.clone(), req_id, maps, costs, false).await;
         }
     };
 
     let map_start = Instant::now();
+    if flight.is_cancelled() {
+        info!("MAP cancelled for {domain}");
+        state.event_bus.emit(CortexEvent::MapFailed {
+            domain: domain.clone(),
+            error: "cancelled".to_string(),
+            elapsed_ms: map_start.elapsed().as_millis() as u64,
+        });
+        return match result {
+            Ok(Ok(sitemap)) if flight.wants_partial() => {
+                // Kept in memory only: a partial map is not cached to disk or
+                // pushed to the registry, where it would read as pages removed.
+                let node_count = sitemap.nodes.len();
+                let edge_count = sitemap.edges.len();
+                let compliance = sitemap.compliance.clone();
+                let blocks = sitemap.blocks.clone();
+                replace_map(&state.maps, &state.action_costs, domain.clone(), sitemap).await;
+                protocol::format_response(
+                    &req.id,
+                    serde_json::json!({
+                        "domain": domain,
+                        "node_count": node_count,
+                        "edge_count": edge_count,
+                        "cached": false,
+                        "map_path": null,
+                        "partial": true,
+                        "compliance": compliance,
+                        "blocks": blocks,
+                    }),
+                )
+            }
+            _ => protocol::format_error(&req.id, ErrorCode::Cancelled, "MAP was cancelled"),
+        };
+    }
     match result {
         Ok(Ok(sitemap)) => {
+            let mut sitemap = sitemap;
+            let mut quality = quality::assess(&sitemap);
+            let mut fallbacks = Vec::new();
+            if let Some(min_quality) = min_quality.filter(|&min| quality.score < min) {
+                let resumable = Frontier::exists(&frontier::default_dir(), &domain);
+                for fallback in quality.fallbacks(resumable) {
+                    if flight.is_cancelled() {
+                        break;
+                    }
+                    info!(
+                        "MAP quality {:.2} of {domain} is below {min_quality}, trying {fallback:?}",
+                        quality.score
+                    );
+                    fallbacks.push(fallback);
+                    let retry = request(
+                        fallback.max_render(max_render),
+                        fallback == Fallback::Resume,
+                    );
+                    let Ok(Ok(remapped)) =
+                        tokio::time::timeout(map_timeout, mapper.map(retry)).await
+                    else {
+                        continue;
+                    };
+                    let remapped_quality = quality::assess(&remapped);
+                    if remapped_quality.score > quality.score {
+                        sitemap = remapped;
+                        quality = remapped_quality;
+                    }
+                    if quality.score >= min_quality {
+                        break;
+                    }
+                }
+                if quality.score < min_quality {
+                    let message = format!(
+                        "map quality {:.2} is below the minimum {min_quality}: {}",
+                        quality.score,
+                        quality.warnings.join("; ")
+                    );
+                    warn!("MAP of {domain} rejected: {message}");
+                    state.event_bus.emit(CortexEvent::MapFailed {
+                        domain: domain.clone(),
+                        error: message.clone(),
+                        elapsed_ms: map_start.elapsed().as_millis() as u64,
+                    });
+                    let mut error = ProtocolError::new(ErrorCode::LowQuality, message).to_json();
+                    error["quality"] = serde_json::to_value(&quality).unwrap_or_default();
+                    error["fallbacks"] = serde_json::to_value(&fallbacks).unwrap_or_default();
+                    let resp = serde_json::json!({ "id": req.id, "error": error });
+                    return format!("{resp}\n");
+                }
+            }
+
             let node_count = sitemap.nodes.len();
             let edge_count = sitemap.edges.len();
             let action_count = sitemap.actions.len();
+            let compliance = sitemap.compliance.clone();
+            let blocks = sitemap.blocks.clone();
             info!("MAP complete: domain={domain}, nodes={node_count}, edges={edge_count}, actions={action_count}");
 
             // Count distinct page types
@@ -561,23 +1239,32 @@ async fn handle_map(req: &protocol::Request, state: Arc<SharedState>) -> String
             };
 
             // Push to local registry for temporal history tracking
-            if let Ok(registry_dir) =
-                crate::intelligence::cache::MapCache::default_cache().map(|_| {
-                    let home = dirs::home_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
-                    home.join(".cortex").join("registry")
-                })
-            {
-                match crate::collective::registry::LocalRegistry::new(registry_dir) {
+            if crate::intelligence::cache::MapCache::default_cache().is_ok() {
+                match LocalRegistry::open_default() {
                     Ok(mut registry) => {
                         // Compute delta if we have a previous version
-                        let prev = registry.pull(&domain).ok().flatten();
-                        let delta = prev.map(|(old_map, _)| {
-                            crate::collective::delta::compute_delta(&old_map, &sitemap, "local")
+                        let prev = registry.pull(&domain).ok().flatten().map(|(m, _)| m);
+                        let delta = prev.as_ref().map(|old_map| {
+                            crate::collective::delta::compute_delta(old_map, &sitemap, "local")
+                        });
+                        let applied = delta.as_ref().map(|d| CortexEvent::DeltaApplied {
+                            domain: domain.clone(),
+                            nodes_added: d.nodes_added.len(),
+                            nodes_removed: d.nodes_removed.len(),
+                            nodes_modified: d.nodes_modified.len(),
+                            edges_added: d.edges_added.len(),
+                            edges_removed: d.edges_removed.len(),
                         });
                         if let Err(e) = registry.push(&domain, &sitemap, delta) {
                             warn!("failed to push map to registry: {e}");
                         } else {
                             info!("MAP pushed to registry: {domain}");
+                            if let Some(event) = applied {
+                                state.event_bus.emit(event);
+                            }
+                        }
+                        if let Some(old_map) = &prev {
+                            evaluate_watches(&state, &registry, &domain, old_map, &sitemap);
                         }
                     }
                     Err(e) => {
@@ -587,10 +1274,7 @@ async fn handle_map(req: &protocol::Request, state: Arc<SharedState>) -> String
             }
 
             // Cache in memory
-            let maps_lock = Arc::clone(&state.maps);
-            let mut map_store = maps_lock.write().await;
-            map_store.insert(domain.clone(), sitemap);
-            drop(map_store);
+            replace_map(&state.maps, &state.action_costs, domain.clone(), sitemap).await;
 
             protocol::format_response(
                 &req.id,
@@ -600,6 +1284,15 @@ async fn handle_map(req: &protocol::Request, state: Arc<SharedState>) -> String
                     "edge_count": edge_count,
                     "cached": false,
                     "map_path": map_path,
+                    // Sampling stopped early; MAP with `resume` continues it.
+                    "resumable": Frontier::exists(&frontier::default_dir(), &domain),
+                    // robots.txt and terms of service, for policy engines.
+                    "compliance": compliance,
+                    // Captcha and anti-bot pages met while mapping.
+                    "blocks": blocks,
+                    "quality": quality,
+                    // Remaps tried because the map scored below `min_quality`.
+                    "fallbacks": fallbacks,
                 }),
             )
         }
@@ -610,7 +1303,7 @@ async fn handle_map(req: &protocol::Request, state: Arc<SharedState>) -> String
                 error: e.to_string(),
                 elapsed_ms: map_start.elapsed().as_millis() as u64,
             });
-            build_http_fallback_map(domain.clone(), req_id, maps, false).await
+            build_http_fallback_map(domain.clone(), req_id, maps, costs, false).await
         }
         Err(_) => {
             warn!("MAP timed out for {domain} after {timeout_ms}ms, building fallback map");
@@ -619,11 +1312,41 @@ async fn handle_map(req: &protocol::Request, state: Arc<SharedState>) -> String
                 error: format!("Timed out after {timeout_ms}ms"),
                 elapsed_ms: map_start.elapsed().as_millis() as u64,
             });
-            build_http_fallback_map(domain.clone(), req_id, maps, true).await
+            build_http_fallback_map(domain.clone(), req_id, maps, costs, true).await
         }
     }
 }
 
+/// Evaluate the registry's watch rules for `domain` against a re-map and
+/// emit a `WatchFired` event per alert.
+fn evaluate_watches(
+    state: &SharedState,
+    registry: &crate::collective::registry::LocalRegistry,
+    domain: &str,
+    old: &SiteMap,
+    new: &SiteMap,
+) {
+    let Ok(mut watches) = state.watches.lock() else {
+        return;
+    };
+    if let Err(e) = watches.reload(registry) {
+        warn!("failed to load watch rules: {e}");
+        return;
+    }
+    let alerts = watches.evaluate_maps(domain, old, new);
+    if alerts.is_empty() {
+        return;
+    }
+    info!("{} watch alert(s) fired for {domain}", alerts.len());
+    // Persist last_triggered so cool-downs survive restarts.
+    if let Err(e) = watches.save(registry) {
+        warn!("failed to save watch rules: {e}");
+    }
+    for alert in &alerts {
+        state.event_bus.emit(alert.to_event());
+    }
+}
+
 /// Build a fallback map using HTTP-only methods (no browser, no scraper).
 ///
 /// Uses simple string-based link extraction to avoid scraper's non-Send types.
@@ -632,22 +1355,17 @@ async fn build_http_fallback_map(
     domain: String,
     req_id: String,
     maps: Arc<RwLock<HashMap<String, SiteMap>>>,
+    costs: Arc<RwLock<ActionCosts>>,
     try_sitemap: bool,
 ) -> String {
     let entry_url = format!("https://{domain}/");
     let mut fallback_urls: Vec<String> = vec![entry_url.clone()];
 
     // Try fetching homepage for link extraction (simple parser, no scraper)
-    if let Ok(resp) = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(10))
-        .redirect(reqwest::redirect::Policy::limited(5))
-        .build()
-        .unwrap_or_default()
+    let client = crate::acquisition::http_client::shared_client();
+    if let Ok(resp) = client
         .get(&entry_url)
-        .header(
-            "User-Agent",
-            "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36",
-        )
+        .timeout(std::time::Duration::from_secs(10))
         .send()
         .await
     {
@@ -660,11 +1378,9 @@ async fn build_http_fallback_map(
     // Optionally try sitemap.xml (used for timeout fallbacks)
     if try_sitemap {
         let sitemap_url = format!("https://{domain}/sitemap.xml");
-        if let Ok(resp) = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(10))
-            .build()
-            .unwrap_or_default()
+        if let Ok(resp) = client
             .get(&sitemap_url)
+            .timeout(std::time::Duration::from_secs(10))
             .send()
             .await
         {
@@ -686,7 +1402,7 @@ async fn build_http_fallback_map(
     if fallback_urls.len() <= 1 && !try_sitemap {
         return protocol::format_error(
             &req_id,
-            "E_MAP_FAILED",
+            ErrorCode::MapFailed,
             &format!("Mapping failed for {domain}"),
         );
     }
@@ -742,9 +1458,7 @@ async fn build_http_fallback_map(
 
     // Push to local registry for temporal history tracking
     {
-        let home = dirs::home_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
-        let registry_dir = home.join(".cortex").join("registry");
-        if let Ok(mut registry) = crate::collective::registry::LocalRegistry::new(registry_dir) {
+        if let Ok(mut registry) = LocalRegistry::open_default() {
             let prev = registry.pull(&domain).ok().flatten();
             let delta = prev.map(|(old_map, _)| {
                 crate::collective::delta::compute_delta(&old_map, &fallback_map, "local")
@@ -757,9 +1471,7 @@ async fn build_http_fallback_map(
         }
     }
 
-    let mut map_store = maps.write().await;
-    map_store.insert(domain, fallback_map);
-    drop(map_store);
+    replace_map(&maps, &costs, domain, fallback_map).await;
 
     protocol::format_response(
         &req_id,
@@ -774,41 +1486,352 @@ async fn build_http_fallback_map(
     )
 }
 
-/// Handle a QUERY request: filter or nearest-neighbor search on a cached map.
-async fn handle_query(req: &protocol::Request, state: Arc<SharedState>) -> String {
-    let domain = match req.params.get("domain").and_then(|v| v.as_str()) {
-        Some(d) => d,
-        None => {
-            return protocol::format_error(
-                &req.id,
-                "E_INVALID_PARAMS",
-                "Missing 'domain' parameter",
-            );
+/// Make `sitemap` the domain's cached map.
+///
+/// The new map renumbers nodes, so action costs learned against the old
+/// indexes no longer apply and are forgotten with it.
+async fn replace_map(
+    maps: &RwLock<HashMap<String, SiteMap>>,
+    costs: &RwLock<ActionCosts>,
+    domain: String,
+    sitemap: SiteMap,
+) {
+    {
+        let mut costs = costs.write().await;
+        if costs.clear(&domain) {
+            if let Err(e) = costs.persist() {
+                warn!("Failed to save action costs: {e}");
+            }
         }
-    };
+    }
+    maps.write().await.insert(domain, sitemap);
+}
 
-    let maps_lock = Arc::clone(&state.maps);
-    let maps = maps_lock.read().await;
-    let sitemap = match maps.get(domain) {
-        Some(m) => m,
-        None => {
-            return protocol::format_error(
-                &req.id,
-                "E_NOT_FOUND",
-                &format!("No map cached for '{domain}'. Map the domain first."),
-            );
+/// Size, budgets and hit rate of the on-disk map cache, for status responses.
+pub(crate) fn disk_cache_status() -> serde_json::Value {
+    match crate::intelligence::cache::MapCache::default_cache() {
+        Ok(cache) => {
+            let stats = cache.stats();
+            serde_json::json!({
+                "entries": stats.entries,
+                "max_entries": stats.max_entries,
+                "bytes": stats.bytes,
+                "max_bytes": stats.max_bytes,
+                "eviction": stats.eviction,
+                "hits": stats.hits,
+                "misses": stats.misses,
+                "hit_rate": stats.hit_rate,
+                "evictions": stats.evictions,
+                "expired": stats.expired,
+            })
         }
-    };
-
-    // Check if this is a nearest-neighbor query
-    let mode = req
-        .params
-        .get("mode")
-        .and_then(|v| v.as_str())
-        .unwrap_or("filter");
+        Err(e) => {
+            warn!("failed to open disk cache: {e}");
+            serde_json::Value::Null
+        }
+    }
+}
 
-    if mode == "nearest" {
-        return handle_nearest(req, sitemap, &state);
+/// Handle a SAVED_QUERIES request: list the queries saved in the registry.
+fn handle_saved_queries(req: &protocol::Request) -> String {
+    match LocalRegistry::open_default().and_then(|r| r.load_saved_queries()) {
+        Ok(queries) => protocol::format_response(
+            &req.id,
+            serde_json::json!({
+                "queries": queries,
+            }),
+        ),
+        Err(e) => protocol::format_error(&req.id, ErrorCode::Registry, &e.to_string()),
+    }
+}
+
+/// Handle a WQL request: run `query` across every cached map and the maps
+/// held in memory, or with AS OF across the maps checked out of the
+/// registry. With `project`, unqualified models search that project's
+/// domains.
+async fn handle_wql(req: &protocol::Request, state: &SharedState) -> String {
+    let Some(query) = req.params.get("query").and_then(|v| v.as_str()) else {
+        return protocol::format_error(
+            &req.id,
+            ErrorCode::InvalidParams,
+            "Missing 'query' parameter",
+        );
+    };
+    let project = req.params.get("project").and_then(|v| v.as_str());
+    let plan = Projects::load_default()
+        .and_then(|projects| {
+            Ok(match project {
+                Some(name) => projects
+                    .resolve(Some(name))?
+                    .map_or_else(Vec::new, |p| p.domains.clone()),
+                None => Vec::new(),
+            })
+        })
+        .and_then(|search_path| {
+            crate::wql::planner::plan_in(&crate::wql::parser::parse(query)?, &search_path)
+        });
+    let plan = match plan {
+        Ok(plan) => plan,
+        Err(e) => {
+            return protocol::format_error(&req.id, ErrorCode::InvalidParams, &format!("{e:#}"))
+        }
+    };
+
+    let as_of = plan.as_of;
+    let scan = plan.clone();
+    let mut maps = match tokio::task::spawn_blocking(move || match as_of {
+        Some(at) => crate::wql::executor::maps_as_of(&LocalRegistry::open_default()?, &scan, at),
+        None => crate::intelligence::cache::MapCache::default_cache()?.load_all_maps(),
+    })
+    .await
+    {
+        Ok(Ok(maps)) => maps,
+        Ok(Err(e)) => {
+            return protocol::format_error(&req.id, ErrorCode::Internal, &format!("{e:#}"))
+        }
+        Err(e) => return protocol::format_error(&req.id, ErrorCode::Internal, &e.to_string()),
+    };
+    // Maps in memory may be newer (refined, or partial and never cached).
+    if as_of.is_none() {
+        for (domain, map) in state.maps.read().await.iter() {
+            maps.insert(domain.clone(), map.clone());
+        }
+    }
+
+    let rows =
+        tokio::task::spawn_blocking(move || crate::wql::executor::execute(&plan, &maps)).await;
+    match rows {
+        Ok(Ok(rows)) => protocol::format_response(
+            &req.id,
+            serde_json::json!({ "query": query, "total": rows.len(), "rows": rows }),
+        ),
+        Ok(Err(e)) => protocol::format_error(&req.id, ErrorCode::InvalidParams, &format!("{e:#}")),
+        Err(e) => protocol::format_error(&req.id, ErrorCode::Internal, &e.to_string()),
+    }
+}
+
+/// ANNOTATE: label, correct or note a page of a domain, given as `node` (of
+/// the map in memory) or `url`, or delete annotation `remove`. A correction
+/// also retypes the page in the map in memory and the map cache; deleting
+/// one leaves the page's type as it is until the domain is mapped again.
+async fn handle_annotate(req: &protocol::Request, state: &SharedState) -> String {
+    let Some(domain) = req.params.get("domain").and_then(|v| v.as_str()) else {
+        return protocol::format_error(
+            &req.id,
+            ErrorCode::InvalidParams,
+            "Missing 'domain' parameter",
+        );
+    };
+
+    if let Some(id) = req.params.get("remove") {
+        let Some(id) = id.as_u64() else {
+            return protocol::format_error(
+                &req.id,
+                ErrorCode::InvalidParams,
+                "'remove' must be an annotation id",
+            );
+        };
+        return match state.annotations.remove(domain, id) {
+            Ok(removed) => protocol::format_response(
+                &req.id,
+                serde_json::json!({ "removed": removed.map(|a| a.to_json(None)) }),
+            ),
+            Err(e) => protocol::format_error(&req.id, ErrorCode::Internal, &format!("{e:#}")),
+        };
+    }
+
+    let param = |name: &str| req.params.get(name).filter(|v| !v.is_null());
+    let body = match (param("label"), param("page_type"), param("note")) {
+        (Some(label), None, None) => match label.as_str() {
+            Some(label) => AnnotationBody::Label {
+                label: label.to_string(),
+            },
+            None => {
+                return protocol::format_error(
+                    &req.id,
+                    ErrorCode::InvalidParams,
+                    "'label' must be a string",
+                )
+            }
+        },
+        (None, Some(page_type), None) => {
+            let parsed = match page_type {
+                serde_json::Value::Number(n) => n
+                    .as_u64()
+                    .filter(|&code| (code as usize) < PageType::COUNT)
+                    .map(|code| PageType::from_u8(code as u8)),
+                serde_json::Value::String(s) => annotations::parse_page_type(s),
+                _ => None,
+            };
+            match parsed {
+                Some(page_type) => AnnotationBody::Correction { page_type },
+                None => {
+                    return protocol::format_error(
+                        &req.id,
+                        ErrorCode::InvalidParams,
+                        &format!("unknown page type {page_type}"),
+                    )
+                }
+            }
+        }
+        (None, None, Some(note)) => match note.as_str() {
+            Some(text) => AnnotationBody::Note {
+                text: text.to_string(),
+            },
+            None => {
+                return protocol::format_error(
+                    &req.id,
+                    ErrorCode::InvalidParams,
+                    "'note' must be a string",
+                )
+            }
+        },
+        _ => {
+            return protocol::format_error(
+                &req.id,
+                ErrorCode::InvalidParams,
+                "Give exactly one of 'label', 'page_type' or 'note'",
+            )
+        }
+    };
+
+    let mut maps = state.maps.write().await;
+    let url = match annotated_url(&req.params, maps.get(domain)) {
+        Ok(url) => url,
+        Err(msg) => return protocol::format_error(&req.id, ErrorCode::InvalidParams, &msg),
+    };
+    let author = req.params.get("author").and_then(|v| v.as_str());
+    let annotation = match state.annotations.add(domain, &url, body, author) {
+        Ok(annotation) => annotation,
+        Err(e) => return protocol::format_error(&req.id, ErrorCode::Internal, &format!("{e:#}")),
+    };
+
+    let mut applied = false;
+    let node = maps.get(domain).and_then(|map| map.find_url(&url));
+    if let (AnnotationBody::Correction { page_type }, Some(map)) =
+        (&annotation.body, maps.get_mut(domain))
+    {
+        let correction = HashMap::from([(url.clone(), *page_type)]);
+        applied = annotations::apply_corrections(map, &correction) > 0;
+        if applied {
+            let (domain, map) = (domain.to_string(), map.clone());
+            drop(maps);
+            let cached = tokio::task::spawn_blocking(move || {
+                crate::intelligence::cache::MapCache::default_cache()?.cache_map(&domain, &map)
+            })
+            .await;
+            if let Ok(Err(e)) = cached {
+                warn!("failed to cache corrected map: {e:#}");
+            }
+        }
+    }
+
+    protocol::format_response(
+        &req.id,
+        serde_json::json!({ "annotation": annotation.to_json(node), "applied": applied }),
+    )
+}
+
+/// ANNOTATIONS: the annotations of a domain, or of one page of it given as
+/// `node` or `url`.
+async fn handle_annotations(req: &protocol::Request, state: &SharedState) -> String {
+    let Some(domain) = req.params.get("domain").and_then(|v| v.as_str()) else {
+        return protocol::format_error(
+            &req.id,
+            ErrorCode::InvalidParams,
+            "Missing 'domain' parameter",
+        );
+    };
+    let maps = state.maps.read().await;
+    let map = maps.get(domain);
+    let filter = if req.params.get("node").is_some() || req.params.get("url").is_some() {
+        match annotated_url(&req.params, map) {
+            Ok(url) => Some(url),
+            Err(msg) => return protocol::format_error(&req.id, ErrorCode::InvalidParams, &msg),
+        }
+    } else {
+        None
+    };
+    let list = match state.annotations.list(domain) {
+        Ok(list) => list,
+        Err(e) => return protocol::format_error(&req.id, ErrorCode::Internal, &format!("{e:#}")),
+    };
+    let annotations: Vec<serde_json::Value> = list
+        .iter()
+        .filter(|a| filter.as_ref().is_none_or(|url| &a.url == url))
+        .map(|a| a.to_json(map.and_then(|m| m.find_url(&a.url))))
+        .collect();
+    protocol::format_response(
+        &req.id,
+        serde_json::json!({
+            "domain": domain,
+            "total": annotations.len(),
+            "annotations": annotations,
+        }),
+    )
+}
+
+/// The URL an ANNOTATE or ANNOTATIONS request names: `url`, or the URL of
+/// `node` in `map`.
+fn annotated_url(
+    params: &serde_json::Value,
+    map: Option<&SiteMap>,
+) -> std::result::Result<String, String> {
+    if let Some(url) = params.get("url").and_then(|v| v.as_str()) {
+        return Ok(url.to_string());
+    }
+    let Some(node) = params.get("node").and_then(|v| v.as_u64()) else {
+        return Err("Give 'node' or 'url'".to_string());
+    };
+    let Some(map) = map else {
+        return Err("No map of this domain in memory; give 'url' instead of 'node'".to_string());
+    };
+    map.urls.get(node as usize).cloned().ok_or_else(|| {
+        format!(
+            "node {node} is out of range (the map has {} nodes)",
+            map.nodes.len()
+        )
+    })
+}
+
+/// Handle a QUERY request: filter or nearest-neighbor search on a cached map.
+async fn handle_query(req: &protocol::Request, state: Arc<SharedState>) -> String {
+    let domain = match req.params.get("domain").and_then(|v| v.as_str()) {
+        Some(d) => d,
+        None => {
+            return protocol::format_error(
+                &req.id,
+                ErrorCode::InvalidParams,
+                "Missing 'domain' parameter",
+            );
+        }
+    };
+
+    let maps_lock = Arc::clone(&state.maps);
+    let maps = maps_lock.read().await;
+    let sitemap = match maps.get(domain) {
+        Some(m) => m,
+        None => {
+            return protocol::format_error(
+                &req.id,
+                ErrorCode::NotFound,
+                &format!("No map cached for '{domain}'. Map the domain first."),
+            );
+        }
+    };
+
+    // Check if this is a nearest-neighbor query
+    let mode = req
+        .params
+        .get("mode")
+        .and_then(|v| v.as_str())
+        .unwrap_or("filter");
+
+    if mode == "nearest" {
+        return handle_nearest(req, sitemap, &state);
+    }
+    if mode == "semantic" {
+        return handle_semantic(req, sitemap, &state).await;
     }
 
     // Parse page_types
@@ -915,32 +1938,163 @@ async fn handle_query(req: &protocol::Request, state: Arc<SharedState>) -> Strin
             (None, true)
         };
 
+    // A saved query replaces the filters above; `limit` still overrides.
+    let saved = match req.params.get("saved").and_then(|v| v.as_str()) {
+        Some(name) => match LocalRegistry::open_default().and_then(|r| r.saved_query(name)) {
+            Ok(Some(saved)) => Some(saved),
+            Ok(None) => {
+                return protocol::format_error(
+                    &req.id,
+                    ErrorCode::NotFound,
+                    &format!("No saved query named '{name}'"),
+                );
+            }
+            Err(e) => {
+                return protocol::format_error(&req.id, ErrorCode::Registry, &e.to_string());
+            }
+        },
+        None => None,
+    };
+
     let limit = req
         .params
         .get("limit")
         .and_then(|v| v.as_u64())
-        .unwrap_or(100) as usize;
-
-    let node_query = NodeQuery {
-        page_types,
-        feature_ranges,
-        require_flags,
-        exclude_flags: None,
-        sort_by_feature,
-        sort_ascending,
-        limit,
+        .map(|n| n as usize)
+        .or(saved.as_ref().map(|s| s.query.limit))
+        .unwrap_or(100);
+    let diversify = req.params.get("diversify").and_then(|v| v.as_bool()) == Some(true)
+        || saved.as_ref().is_some_and(|s| s.diversify);
+
+    let node_query = match saved {
+        Some(saved) => NodeQuery {
+            limit: if diversify { 0 } else { limit },
+            ..saved.query
+        },
+        None => NodeQuery {
+            page_types,
+            feature_ranges,
+            require_flags,
+            exclude_flags: None,
+            sort_by_feature,
+            sort_ascending,
+            limit: if diversify { 0 } else { limit },
+            text: req
+                .params
+                .get("text")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+        },
+    };
+
+    // With `refine`, the stale or low-confidence pages the answer depends on
+    // are re-fetched first (within a time budget), and the query is answered
+    // from the updated map.
+    let refine = (req.params.get("refine").and_then(|v| v.as_bool()) == Some(true)).then(|| {
+        let policy = refine_policy(&req.params);
+        let nodes: Vec<u32> = query::execute(sitemap, &node_query)
+            .iter()
+            .map(|m| m.index)
+            .collect();
+        let (candidates, stale) = progressive::select_for_refinement(sitemap, &nodes, &policy);
+        (policy, nodes.len(), candidates, stale)
+    });
+    drop(maps);
+    let freshness = match refine {
+        Some((policy, considered, candidates, stale)) => {
+            Some(refine_nodes(&state, domain, &policy, considered, candidates, stale).await)
+        }
+        None => None,
+    };
+    let maps = maps_lock.read().await;
+    let Some(sitemap) = maps.get(domain) else {
+        return protocol::format_error(
+            &req.id,
+            ErrorCode::NotFound,
+            &format!("No map cached for '{domain}'. Map the domain first."),
+        );
     };
 
     let query_start = Instant::now();
     let results = query::execute(sitemap, &node_query);
-    let elapsed_us = query_start.elapsed().as_micros() as u64;
+    let (mut matches, results_count) = if diversify {
+        let mut groups = diversify::diversify(
+            results,
+            |i| *sitemap.node_features(i),
+            diversify::DEFAULT_SIMILARITY,
+        );
+        groups.truncate(limit);
+        (result_groups_json(&groups), groups.len())
+    } else {
+        (node_matches_json(&results), results.len())
+    };
     state.event_bus.emit(CortexEvent::QueryExecuted {
         domain: domain.to_string(),
         query_type: "filter".to_string(),
-        results_count: results.len(),
-        elapsed_us,
+        results_count,
+        elapsed_us: query_start.elapsed().as_micros() as u64,
     });
-    format_node_matches(&req.id, &results)
+    if req.params.get("include_lineage").and_then(|v| v.as_bool()) == Some(true) {
+        attach_lineage(&mut matches, &sitemap.lineage);
+    }
+    let mut result = serde_json::json!({ "matches": matches });
+    if let Some(report) = freshness {
+        result["freshness"] = serde_json::json!(report);
+    }
+    protocol::format_response(&req.id, result)
+}
+
+/// Refinement limits from QUERY params: `refine_budget_ms` (default 3000,
+/// at most 30000) and `refine_max_pages` (default 10, at most 50).
+fn refine_policy(params: &serde_json::Value) -> progressive::RefinePolicy {
+    let mut policy = progressive::RefinePolicy::default();
+    if let Some(ms) = params.get("refine_budget_ms").and_then(|v| v.as_u64()) {
+        policy.budget = Duration::from_millis(ms.min(30_000));
+    }
+    if let Some(n) = params.get("refine_max_pages").and_then(|v| v.as_u64()) {
+        policy.max_pages = (n as usize).min(50);
+    }
+    policy
+}
+
+/// Fetch the selected refinement candidates without holding the map lock,
+/// then apply them to `domain`'s map.
+async fn refine_nodes(
+    state: &SharedState,
+    domain: &str,
+    policy: &progressive::RefinePolicy,
+    considered: usize,
+    candidates: Vec<progressive::RenderCandidate>,
+    stale: usize,
+) -> progressive::FreshnessReport {
+    let start = Instant::now();
+    let mut report = progressive::FreshnessReport {
+        considered,
+        stale,
+        skipped: stale - candidates.len(),
+        budget_ms: policy.budget.as_millis() as u64,
+        ..Default::default()
+    };
+    if !candidates.is_empty() {
+        let profile = state.profiles.for_domain(domain).unwrap_or_else(|e| {
+            warn!("{e:#}; refining {domain} as the default profile");
+            CrawlProfile::honest()
+        });
+        let (refreshes, failed) = progressive::fetch_refreshes(&candidates, policy, profile).await;
+        if let Some(map) = state.maps.write().await.get_mut(domain) {
+            report.changed = progressive::apply_refreshes(map, &refreshes);
+        }
+        report.refreshed = refreshes.iter().map(|r| r.node_index).collect();
+        report.failed = failed;
+        info!(
+            "QUERY refined {domain}: {} refreshed, {} changed, {} failed",
+            report.refreshed.len(),
+            report.changed.len(),
+            report.failed.len()
+        );
+    }
+    report.elapsed_ms = start.elapsed().as_millis() as u64;
+    report
 }
 
 /// Handle a nearest-neighbor query.
@@ -954,7 +2108,7 @@ fn handle_nearest(req: &protocol::Request, sitemap: &SiteMap, state: &Arc<Shared
             if vec.len() != FEATURE_DIM {
                 return protocol::format_error(
                     &req.id,
-                    "E_INVALID_PARAMS",
+                    ErrorCode::InvalidParams,
                     &format!(
                         "goal_vector must have {FEATURE_DIM} dimensions, got {}",
                         vec.len()
@@ -968,7 +2122,7 @@ fn handle_nearest(req: &protocol::Request, sitemap: &SiteMap, state: &Arc<Shared
         None => {
             return protocol::format_error(
                 &req.id,
-                "E_INVALID_PARAMS",
+                ErrorCode::InvalidParams,
                 "Missing 'goal_vector' for nearest query",
             );
         }
@@ -998,11 +2152,136 @@ fn handle_nearest(req: &protocol::Request, sitemap: &SiteMap, state: &Arc<Shared
         elapsed_us,
     });
 
-    format_node_matches(&req.id, &results)
+    let mut matches = node_matches_json(&results);
+    if req.params.get("include_lineage").and_then(|v| v.as_bool()) == Some(true) {
+        attach_lineage(&mut matches, &sitemap.lineage);
+    }
+    protocol::format_response(&req.id, serde_json::json!({ "matches": matches }))
+}
+
+/// Handle a semantic query: the pages whose title and description are
+/// nearest in meaning to a free-text `goal`.
+async fn handle_semantic(
+    req: &protocol::Request,
+    sitemap: &SiteMap,
+    state: &Arc<SharedState>,
+) -> String {
+    let Some(goal) = req
+        .params
+        .get("goal")
+        .and_then(|v| v.as_str())
+        .filter(|g| !g.trim().is_empty())
+    else {
+        return protocol::format_error(
+            &req.id,
+            ErrorCode::InvalidParams,
+            "Missing 'goal' for semantic query",
+        );
+    };
+    let Some(encoder) = &state.semantic else {
+        return protocol::format_error(
+            &req.id,
+            ErrorCode::NotImplemented,
+            "Semantic queries need a daemon built with the 'semantic' feature and a sentence model in ~/.agentic-vision/models/sentence/",
+        );
+    };
+    if sitemap.embeddings.is_empty() {
+        return protocol::format_error(
+            &req.id,
+            ErrorCode::NotFound,
+            "This map has no text embeddings. Re-map the domain with the sentence model installed.",
+        );
+    }
+
+    let k = req
+        .params
+        .get("limit")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(10) as usize;
+
+    let query_start = Instant::now();
+    let encoder = Arc::clone(encoder);
+    let text = goal.to_string();
+    let target = match tokio::task::spawn_blocking(move || encoder.embed(&text)).await {
+        Ok(Ok(target)) => target,
+        Ok(Err(e)) => {
+            return protocol::format_error(
+                &req.id,
+                ErrorCode::Internal,
+                &format!("Failed to embed goal: {e:#}"),
+            );
+        }
+        Err(e) => {
+            return protocol::format_error(
+                &req.id,
+                ErrorCode::Internal,
+                &format!("Embedding goal panicked: {e}"),
+            );
+        }
+    };
+    // Error pages are left out, as from other queries
+    let is_error = |node: u32| sitemap.nodes[node as usize].page_type == PageType::ErrorPage;
+    let errors = (0..sitemap.nodes.len() as u32)
+        .filter(|&node| is_error(node))
+        .count();
+    let results: Vec<crate::map::types::NodeMatch> = sitemap
+        .embeddings
+        .nearest(&target, k + errors)
+        .into_iter()
+        .filter(|&(node, _)| (node as usize) < sitemap.nodes.len() && !is_error(node))
+        .take(k)
+        .map(|(node, similarity)| {
+            let record = &sitemap.nodes[node as usize];
+            crate::map::types::NodeMatch {
+                index: node,
+                url: sitemap.urls[node as usize].clone(),
+                page_type: record.page_type,
+                confidence: record.confidence as f32 / 255.0,
+                features: Vec::new(),
+                similarity: Some(similarity),
+                text_score: None,
+            }
+        })
+        .collect();
+    let elapsed_us = query_start.elapsed().as_micros() as u64;
+
+    let domain = sitemap.header.domain.clone();
+    state.event_bus.emit(CortexEvent::QueryExecuted {
+        domain,
+        query_type: "semantic".to_string(),
+        results_count: results.len(),
+        elapsed_us,
+    });
+
+    let mut matches = node_matches_json(&results);
+    for (m, node) in matches.as_array_mut().into_iter().flatten().zip(&results) {
+        if let Some(text) = sitemap.text.node(node.index) {
+            m["title"] = serde_json::json!(text.title);
+            m["description"] = serde_json::json!(text.description);
+        }
+    }
+    if req.params.get("include_lineage").and_then(|v| v.as_bool()) == Some(true) {
+        attach_lineage(&mut matches, &sitemap.lineage);
+    }
+    protocol::format_response(&req.id, serde_json::json!({ "matches": matches }))
 }
 
-/// Format a list of NodeMatch results as a protocol response.
-fn format_node_matches(req_id: &str, results: &[crate::map::types::NodeMatch]) -> String {
+/// Add each match's `trust` score and the `lineage` of its values (both
+/// null for nodes without recorded lineage).
+fn attach_lineage(matches: &mut serde_json::Value, lineage: &MapLineage) {
+    let now = crate::trust::lineage::now();
+    for m in matches.as_array_mut().into_iter().flatten() {
+        let node = m["index"].as_u64().and_then(|i| lineage.node(i as u32));
+        m["trust"] = serde_json::json!(node.and_then(|n| n.trust_score(now)));
+        m["lineage"] = serde_json::json!(node.map(|n| &n.fields));
+        if let Some(conflicts) = node.map(|n| &n.conflicts).filter(|c| !c.is_empty()) {
+            m["conflicts"] = serde_json::json!(conflicts);
+        }
+    }
+}
+
+/// Format a list of NodeMatch results as the `matches` of a response.
+fn node_matches_json(results: &[crate::map::types::NodeMatch]) -> serde_json::Value {
     let matches: Vec<serde_json::Value> = results
         .iter()
         .map(|m| {
@@ -1018,16 +2297,32 @@ fn format_node_matches(req_id: &str, results: &[crate::map::types::NodeMatch]) -
                 "confidence": m.confidence,
                 "features": features,
                 "similarity": m.similarity,
+                "text_score": m.text_score,
             })
         })
         .collect();
+    serde_json::json!(matches)
+}
 
-    protocol::format_response(
-        req_id,
-        serde_json::json!({
-            "matches": matches,
-        }),
-    )
+/// Format diversified results: node matches plus their group template,
+/// size and members.
+fn result_groups_json(groups: &[diversify::ResultGroup]) -> serde_json::Value {
+    let matches: Vec<serde_json::Value> = groups
+        .iter()
+        .map(|g| {
+            let m = &g.representative;
+            serde_json::json!({
+                "index": m.index,
+                "url": m.url,
+                "page_type": m.page_type as u8,
+                "confidence": m.confidence,
+                "template": g.template,
+                "group_size": g.size,
+                "members": g.members,
+            })
+        })
+        .collect();
+    serde_json::json!(matches)
 }
 
 /// Handle a PATHFIND request.
@@ -1037,7 +2332,7 @@ async fn handle_pathfind(req: &protocol::Request, state: Arc<SharedState>) -> St
         None => {
             return protocol::format_error(
                 &req.id,
-                "E_INVALID_PARAMS",
+                ErrorCode::InvalidParams,
                 "Missing 'domain' parameter",
             );
         }
@@ -1050,28 +2345,22 @@ async fn handle_pathfind(req: &protocol::Request, state: Arc<SharedState>) -> St
         None => {
             return protocol::format_error(
                 &req.id,
-                "E_NOT_FOUND",
+                ErrorCode::NotFound,
                 &format!("No map cached for '{domain}'. Map the domain first."),
             );
         }
     };
 
-    let from_node = match req.params.get("from").and_then(|v| v.as_u64()) {
-        Some(n) => n as u32,
-        None => {
+    let from_node = match node_param(&req.params, sitemap, "from", "from_url") {
+        Ok(Some(n)) => n,
+        Ok(None) => {
             return protocol::format_error(
                 &req.id,
-                "E_INVALID_PARAMS",
-                "Missing 'from' node index",
+                ErrorCode::InvalidParams,
+                "Missing 'from' node index or 'from_url'",
             );
         }
-    };
-
-    let to_node = match req.params.get("to").and_then(|v| v.as_u64()) {
-        Some(n) => n as u32,
-        None => {
-            return protocol::format_error(&req.id, "E_INVALID_PARAMS", "Missing 'to' node index");
-        }
+        Err(msg) => return protocol::format_error(&req.id, ErrorCode::NotFound, &msg),
     };
 
     let minimize = match req
@@ -1099,10 +2388,162 @@ async fn handle_pathfind(req: &protocol::Request, state: Arc<SharedState>) -> St
         .map(|arr| arr.iter().any(|f| f.as_str() == Some("state_changes")))
         .unwrap_or(false);
 
+    let avoid_nodes = req
+        .params
+        .get("avoid_nodes")
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|n| n.as_u64())
+                .map(|n| n as u32)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let avoid_page_types = req
+        .params
+        .get("avoid_page_types")
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|t| t.as_u64())
+                .map(|t| PageType::from_u8(t as u8))
+                .collect()
+        })
+        .unwrap_or_default();
+
     let constraints = PathConstraints {
         avoid_auth,
         avoid_state_changes,
         minimize,
+        avoid_nodes,
+        avoid_page_types,
+        max_hops: req
+            .params
+            .get("max_hops")
+            .and_then(|v| v.as_u64())
+            .map(|h| h as u32),
+        max_risk: req
+            .params
+            .get("max_risk")
+            .and_then(|v| v.as_u64())
+            .map(|r| r.min(u8::MAX as u64) as u8),
+        learned_costs: if req.params.get("learned_costs").and_then(|v| v.as_bool()) == Some(false) {
+            None
+        } else {
+            state.action_costs.read().await.get(domain)
+        },
+    };
+
+    // Multi-goal routing: visit every node in `goals`, optionally ending at `to`.
+    let to_node = match node_param(&req.params, sitemap, "to", "to_url") {
+        Ok(n) => n,
+        Err(msg) => return protocol::format_error(&req.id, ErrorCode::NotFound, &msg),
+    };
+
+    if let Some(goals) = req.params.get("goals").and_then(|v| v.as_array()) {
+        let mut indexes = Vec::with_capacity(goals.len());
+        for goal in goals {
+            match goal {
+                serde_json::Value::String(url) => match sitemap.find_url(url) {
+                    Some(n) => indexes.push(n),
+                    None => {
+                        return protocol::format_error(
+                            &req.id,
+                            ErrorCode::NotFound,
+                            &format!("No node with URL '{url}'"),
+                        );
+                    }
+                },
+                other => indexes.extend(other.as_u64().map(|g| g as u32)),
+            }
+        }
+        let goals = indexes;
+        let end = to_node;
+        let pf_start = Instant::now();
+        return match pathfinder::find_route(sitemap, from_node, &goals, end, &constraints) {
+            Some(route) => {
+                state.event_bus.emit(CortexEvent::QueryExecuted {
+                    domain: domain.to_string(),
+                    query_type: "pathfind".to_string(),
+                    results_count: route.path.hops as usize,
+                    elapsed_us: pf_start.elapsed().as_micros() as u64,
+                });
+                protocol::format_response(
+                    &req.id,
+                    serde_json::json!({
+                        "nodes": route.path.nodes,
+                        "total_weight": route.path.total_weight,
+                        "hops": route.path.hops,
+                        "visit_order": route.order,
+                        "required_actions": [],
+                    }),
+                )
+            }
+            None => protocol::format_error(
+                &req.id,
+                ErrorCode::NoPath,
+                "No route visits every goal under the given constraints",
+            ),
+        };
+    }
+
+    // Semantic goal: nearest page of a type, or most similar to a feature vector.
+    if let Some(goal) = req.params.get("goal") {
+        let goal = match goal {
+            serde_json::Value::Number(code) => {
+                pathfinder::Goal::PageType(PageType::from_u8(code.as_u64().unwrap_or(0) as u8))
+            }
+            serde_json::Value::Array(values) => {
+                let mut target = [0.0f32; FEATURE_DIM];
+                for (t, v) in target.iter_mut().zip(values) {
+                    *t = v.as_f64().unwrap_or(0.0) as f32;
+                }
+                pathfinder::Goal::Features(Box::new(target))
+            }
+            _ => {
+                return protocol::format_error(
+                    &req.id,
+                    ErrorCode::InvalidParams,
+                    "'goal' must be a page type code or a feature vector",
+                );
+            }
+        };
+        let pf_start = Instant::now();
+        return match pathfinder::find_goal(sitemap, from_node, &goal, &constraints) {
+            Some((target, path)) => {
+                state.event_bus.emit(CortexEvent::QueryExecuted {
+                    domain: domain.to_string(),
+                    query_type: "pathfind".to_string(),
+                    results_count: path.hops as usize,
+                    elapsed_us: pf_start.elapsed().as_micros() as u64,
+                });
+                protocol::format_response(
+                    &req.id,
+                    serde_json::json!({
+                        "nodes": path.nodes,
+                        "total_weight": path.total_weight,
+                        "hops": path.hops,
+                        "target": target,
+                        "target_url": sitemap.node_url(target),
+                        "required_actions": [],
+                    }),
+                )
+            }
+            None => protocol::format_error(
+                &req.id,
+                ErrorCode::NoPath,
+                "No reachable node matches the goal",
+            ),
+        };
+    }
+
+    let Some(to_node) = to_node else {
+        return protocol::format_error(
+            &req.id,
+            ErrorCode::InvalidParams,
+            "Missing 'to' node index, 'to_url', 'goal' or 'goals'",
+        );
     };
 
     let pf_start = Instant::now();
@@ -1135,7 +2576,187 @@ async fn handle_pathfind(req: &protocol::Request, state: Arc<SharedState>) -> St
                 }),
             )
         }
-        None => protocol::format_error(&req.id, "E_NO_PATH", "No path found between nodes"),
+        None => protocol::format_error(&req.id, ErrorCode::NoPath, "No path found between nodes"),
+    }
+}
+
+/// A node given either as an index under `index_key` or as a URL under
+/// `url_key`. Errors if the URL is not in the map.
+fn node_param(
+    params: &serde_json::Value,
+    sitemap: &SiteMap,
+    index_key: &str,
+    url_key: &str,
+) -> Result<Option<u32>, String> {
+    if let Some(n) = params.get(index_key).and_then(|v| v.as_u64()) {
+        return Ok(Some(n as u32));
+    }
+    match params.get(url_key).and_then(|v| v.as_str()) {
+        Some(url) => sitemap
+            .find_url(url)
+            .map(Some)
+            .ok_or_else(|| format!("No node with URL '{url}'")),
+        None => Ok(None),
+    }
+}
+
+/// Handle an ACT request: execute an action on a mapped node in the browser
+/// and record the outcome for learned pathfinding costs.
+async fn handle_act(req: &protocol::Request, state: Arc<SharedState>) -> String {
+    let Some(domain) = req.params.get("domain").and_then(|v| v.as_str()) else {
+        return protocol::format_error(
+            &req.id,
+            ErrorCode::InvalidParams,
+            "Missing 'domain' parameter",
+        );
+    };
+    let opcode = match req.params.get("opcode").and_then(|v| v.as_array()) {
+        Some(parts) if parts.len() == 2 => {
+            let part = |i: usize| parts[i].as_u64().unwrap_or(0) as u8;
+            OpCode::new(part(0), part(1))
+        }
+        _ => {
+            return protocol::format_error(
+                &req.id,
+                ErrorCode::InvalidParams,
+                "'opcode' must be a [category, action] pair",
+            );
+        }
+    };
+    let params: HashMap<String, serde_json::Value> = req
+        .params
+        .get("params")
+        .and_then(|v| v.as_object())
+        .map(|o| o.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+        .unwrap_or_default();
+
+    // The page is a mapped node, or a raw URL (learned from only if mapped).
+    let url_param = req.params.get("url").and_then(|v| v.as_str());
+    let (url, node, target) = {
+        let maps = state.maps.read().await;
+        let sitemap = maps.get(domain);
+        let node = match (url_param, sitemap) {
+            (Some(url), _) => sitemap.and_then(|m| m.find_url(url)),
+            (None, None) => {
+                return protocol::format_error(
+                    &req.id,
+                    ErrorCode::NotFound,
+                    &format!("No map cached for '{domain}'. Map the domain first."),
+                );
+            }
+            (None, Some(sitemap)) => match req.params.get("node").and_then(|v| v.as_u64()) {
+                Some(n) if (n as usize) < sitemap.nodes.len() => Some(n as u32),
+                Some(n) => {
+                    return protocol::format_error(
+                        &req.id,
+                        ErrorCode::NotFound,
+                        &format!("Node {n} not in map"),
+                    );
+                }
+                None => {
+                    return protocol::format_error(
+                        &req.id,
+                        ErrorCode::InvalidParams,
+                        "Missing 'node' index or 'url'",
+                    );
+                }
+            },
+        };
+        let mapped = node.zip(sitemap);
+        let target = mapped.and_then(|(n, m)| {
+            m.actions_for(n)
+                .iter()
+                .find(|a| a.opcode == opcode)
+                .and_then(|a| u32::try_from(a.target_node).ok())
+        });
+        let url = match url_param {
+            Some(url) => url.to_string(),
+            None => mapped
+                .map(|(n, m)| m.node_url(n).to_string())
+                .unwrap_or_default(),
+        };
+        (url, node, target)
+    };
+
+    let Some(renderer) = state.renderer.clone() else {
+        return protocol::format_error(
+            &req.id,
+            ErrorCode::NoRenderer,
+            "Browser renderer not available. Restart Cortex with a browser.",
+        );
+    };
+    let mut context = match renderer.new_context().await {
+        Ok(ctx) => ctx,
+        Err(e) => {
+            return protocol::format_error(
+                &req.id,
+                ErrorCode::Renderer,
+                &format!("Failed to create browser context: {e}"),
+            );
+        }
+    };
+
+    let action_type = format!("{:02x}:{:02x}", opcode.category, opcode.action);
+    state.event_bus.emit(CortexEvent::ActionStarted {
+        domain: domain.to_string(),
+        node: node.unwrap_or_default() as usize,
+        action_type: action_type.clone(),
+        execution_path: "browser".to_string(),
+    });
+    let started = Instant::now();
+    let result = match renderer::identify(context.as_mut(), &state.profiles, &url).await {
+        Ok(()) => act_handler::execute_via_browser(context.as_mut(), &url, &opcode, &params).await,
+        Err(e) => Err(e),
+    };
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let _ = context.close().await;
+
+    let success = result.as_ref().is_ok_and(|r| r.success);
+    if let Some(node) = node {
+        let mut costs = state.action_costs.write().await;
+        costs.record(
+            domain,
+            &Outcome {
+                node,
+                target,
+                opcode,
+                success,
+                latency_ms,
+            },
+        );
+        if let Err(e) = costs.persist() {
+            warn!("Failed to save action costs: {e}");
+        }
+    }
+    state.event_bus.emit(CortexEvent::ActionComplete {
+        domain: domain.to_string(),
+        node: node.unwrap_or_default() as usize,
+        action_type,
+        success,
+        execution_path: "browser".to_string(),
+        elapsed_ms: latency_ms,
+    });
+
+    match result {
+        Ok(result) => protocol::format_response(
+            &req.id,
+            serde_json::json!({
+                "success": result.success,
+                "new_url": result.new_url,
+                "features": result
+                    .features
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), serde_json::json!(v)))
+                    .collect::<serde_json::Map<_, _>>(),
+                "method": result.method,
+                "latency_ms": latency_ms,
+            }),
+        ),
+        Err(e) => protocol::format_error(
+            &req.id,
+            ErrorCode::ActFailed,
+            &format!("Action failed on {url}: {e}"),
+        ),
     }
 }
 
@@ -1148,7 +2769,7 @@ async fn handle_perceive(req: &protocol::Request, state: Arc<SharedState>) -> St
             None => {
                 return protocol::format_error(
                     &req.id,
-                    "E_NO_RENDERER",
+                    ErrorCode::NoRenderer,
                     "Browser renderer not available. Restart Cortex with a browser.",
                 );
             }
@@ -1160,7 +2781,7 @@ async fn handle_perceive(req: &protocol::Request, state: Arc<SharedState>) -> St
         _ => {
             return protocol::format_error(
                 &req.id,
-                "E_INVALID_PARAMS",
+                ErrorCode::InvalidParams,
                 "Missing or empty 'url' parameter",
             );
         }
@@ -1171,6 +2792,13 @@ async fn handle_perceive(req: &protocol::Request, state: Arc<SharedState>) -> St
         .get("include_content")
         .and_then(|v| v.as_bool())
         .unwrap_or(true);
+    let screenshot = req
+        .params
+        .get("screenshot")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let flight = state.in_flight.register(&req.id, Method::Perceive.as_str());
 
     // Create a new browser context for this request
     let mut context = match renderer.new_context().await {
@@ -1178,14 +2806,31 @@ async fn handle_perceive(req: &protocol::Request, state: Arc<SharedState>) -> St
         Err(e) => {
             return protocol::format_error(
                 &req.id,
-                "E_RENDERER",
+                ErrorCode::Renderer,
                 &format!("Failed to create browser context: {e}"),
             );
         }
     };
 
-    match perceive_handler::perceive(context.as_mut(), &url, include_content).await {
-        Ok(result) => {
+    // Close the context however perceiving ends, so a cancelled request
+    // gives its browser context back at once.
+    let perceived = tokio::select! {
+        perceived = async {
+            renderer::identify(context.as_mut(), &state.profiles, &url).await?;
+            let result = perceive_handler::perceive(context.as_mut(), &url, include_content).await?;
+            let png = if screenshot {
+                Some(context.screenshot().await?)
+            } else {
+                None
+            };
+            anyhow::Ok((result, png))
+        } => perceived,
+        _ = flight.token().cancelled() => Err(cancel::Cancelled.into()),
+    };
+    let _ = context.close().await;
+
+    match perceived {
+        Ok((result, png)) => {
             // Convert sparse features to dict
             let features: serde_json::Map<String, serde_json::Value> = result
                 .features
@@ -1193,31 +2838,115 @@ async fn handle_perceive(req: &protocol::Request, state: Arc<SharedState>) -> St
                 .map(|(k, v)| (k.to_string(), serde_json::json!(v)))
                 .collect();
 
-            // Close context
-            let _ = context.close().await;
+            let mut response = serde_json::json!({
+                "url": result.url,
+                "final_url": result.final_url,
+                "page_type": result.page_type,
+                "confidence": result.confidence,
+                "features": features,
+                "content": result.content,
+                "load_time_ms": result.load_time_ms,
+            });
+            if let Some(png) = png {
+                use base64::Engine as _;
+                response["screenshot"] =
+                    base64::engine::general_purpose::STANDARD.encode(png).into();
+            }
+            protocol::format_response(&req.id, response)
+        }
+        Err(e) if cancel::is_cancelled(&e) => {
+            protocol::format_error(&req.id, ErrorCode::Cancelled, "PERCEIVE was cancelled")
+        }
+        Err(e) => protocol::format_error(
+            &req.id,
+            ErrorCode::PerceiveFailed,
+            &format!("Perceive failed for {url}: {e}"),
+        ),
+    }
+}
 
+/// Handle a CANCEL request: abort the running MAP or PERCEIVE with ID
+/// `request_id`. With `partial: true` a cancelled MAP answers with the map
+/// built from the pages fetched so far instead of `E_CANCELLED`.
+fn handle_cancel(req: &protocol::Request, state: &SharedState) -> String {
+    let Some(target) = req.params.get("request_id").and_then(|v| v.as_str()) else {
+        return protocol::format_error(
+            &req.id,
+            ErrorCode::InvalidParams,
+            "Missing 'request_id' parameter",
+        );
+    };
+    let partial = req
+        .params
+        .get("partial")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    match state.in_flight.cancel(target, partial) {
+        Some(method) => {
+            info!("cancelling {method} request {target}");
             protocol::format_response(
                 &req.id,
                 serde_json::json!({
-                    "url": result.url,
-                    "final_url": result.final_url,
-                    "page_type": result.page_type,
-                    "confidence": result.confidence,
-                    "features": features,
-                    "content": result.content,
-                    "load_time_ms": result.load_time_ms,
+                    "request_id": target,
+                    "method": method,
+                    "cancelled": true,
+                    "partial": partial,
                 }),
             )
         }
+        None => protocol::format_error(
+            &req.id,
+            ErrorCode::NotFound,
+            &format!("No cancellable request '{target}' is running"),
+        ),
+    }
+}
+
+/// Re-read the daemon's configuration from disk: extraction scripts (built-in
+/// and custom), crawl calendars and watch rules. Serves RELOAD and SIGHUP. A
+/// source that fails to load keeps what it had. Connections, sessions and
+/// cached maps are untouched, and API keys reload on their own whenever their
+/// file changes.
+///
+/// Returns `{"reloaded": {source: count}, "errors": {source: message}}`.
+pub async fn reload(state: &SharedState) -> serde_json::Value {
+    let mut reloaded = serde_json::Map::new();
+    let mut errors = serde_json::Map::new();
+    let mut record = |source: &str, result: Result<usize>| match result {
+        Ok(count) => {
+            reloaded.insert(source.to_string(), count.into());
+        }
         Err(e) => {
-            let _ = context.close().await;
-            protocol::format_error(
-                &req.id,
-                "E_PERCEIVE_FAILED",
-                &format!("Perceive failed for {url}: {e}"),
-            )
+            warn!("failed to reload {source}: {e:#}");
+            errors.insert(source.to_string(), format!("{e:#}").into());
+        }
+    };
+
+    if let Some(mapper) = &state.mapper {
+        record("extractors", mapper.extractors().reload());
+        record("custom_extractors", mapper.extractors().reload_custom());
+    }
+    match CrawlScheduler::load_default() {
+        Ok(scheduler) => {
+            let count = scheduler.len();
+            *state.scheduler.write().await = scheduler;
+            record("crawl_calendars", Ok(count));
         }
+        Err(e) => record("crawl_calendars", Err(e)),
     }
+    let watch_rules = LocalRegistry::open_default().and_then(|registry| {
+        let mut watches = state
+            .watches
+            .lock()
+            .map_err(|_| anyhow::anyhow!("watch rules lock poisoned"))?;
+        watches.reload(&registry)?;
+        Ok(watches.list_rules().len())
+    });
+    record("watch_rules", watch_rules);
+
+    let report = serde_json::json!({"reloaded": reloaded, "errors": errors});
+    info!("reloaded configuration: {report}");
+    report
 }
 
 /// Handle an AUTH request: authenticate with a site and store the session.
@@ -1230,7 +2959,7 @@ async fn handle_auth(req: &protocol::Request, state: Arc<SharedState>) -> String
         _ => {
             return protocol::format_error(
                 &req.id,
-                "E_INVALID_PARAMS",
+                ErrorCode::InvalidParams,
                 "Missing or empty 'domain' parameter",
             );
         }
@@ -1248,14 +2977,14 @@ async fn handle_auth(req: &protocol::Request, state: Arc<SharedState>) -> String
         method: auth_type.to_string(),
     });
 
-    let session = match auth_type {
+    let mut session = match auth_type {
         "api_key" => {
             let key = match req.params.get("key").and_then(|v| v.as_str()) {
                 Some(k) => k,
                 None => {
                     return protocol::format_error(
                         &req.id,
-                        "E_INVALID_PARAMS",
+                        ErrorCode::InvalidParams,
                         "Missing 'key' parameter for api_key auth",
                     );
                 }
@@ -1273,7 +3002,7 @@ async fn handle_auth(req: &protocol::Request, state: Arc<SharedState>) -> String
                 None => {
                     return protocol::format_error(
                         &req.id,
-                        "E_INVALID_PARAMS",
+                        ErrorCode::InvalidParams,
                         "Missing 'token' parameter for bearer auth",
                     );
                 }
@@ -1286,7 +3015,7 @@ async fn handle_auth(req: &protocol::Request, state: Arc<SharedState>) -> String
                 None => {
                     return protocol::format_error(
                         &req.id,
-                        "E_INVALID_PARAMS",
+                        ErrorCode::InvalidParams,
                         "Missing 'username' parameter for password auth",
                     );
                 }
@@ -1296,12 +3025,19 @@ async fn handle_auth(req: &protocol::Request, state: Arc<SharedState>) -> String
                 None => {
                     return protocol::format_error(
                         &req.id,
-                        "E_INVALID_PARAMS",
+                        ErrorCode::InvalidParams,
                         "Missing 'password' parameter for password auth",
                     );
                 }
             };
-            let client = crate::acquisition::http_client::HttpClient::new(15_000);
+            let profile = match state.profiles.for_domain(&domain) {
+                Ok(profile) => profile,
+                Err(e) => {
+                    return protocol::format_error(&req.id, ErrorCode::Internal, &format!("{e:#}"));
+                }
+            };
+            let client =
+                crate::acquisition::http_client::HttpClient::new(15_000).with_profile(profile);
             match crate::acquisition::auth::login_password(&client, &domain, username, password)
                 .await
             {
@@ -1314,7 +3050,7 @@ async fn handle_auth(req: &protocol::Request, state: Arc<SharedState>) -> String
                     });
                     return protocol::format_error(
                         &req.id,
-                        "E_AUTH_FAILED",
+                        ErrorCode::AuthFailed,
                         &format!("Password login failed for {domain}: {e}"),
                     );
                 }
@@ -1323,12 +3059,31 @@ async fn handle_auth(req: &protocol::Request, state: Arc<SharedState>) -> String
         other => {
             return protocol::format_error(
                 &req.id,
-                "E_INVALID_PARAMS",
+                ErrorCode::InvalidParams,
                 &format!("Unknown auth_type '{other}'. Supported: api_key, bearer, password"),
             );
         }
     };
 
+    // A project that shares sessions lets this one stand for its other domains
+    match Projects::load_default() {
+        Ok(projects) => {
+            if let Some(project) = projects
+                .project_of(&domain)
+                .filter(|p| p.settings.share_sessions)
+            {
+                session.shared_with = project
+                    .domains
+                    .iter()
+                    .filter(|d| !d.eq_ignore_ascii_case(&domain))
+                    .cloned()
+                    .collect();
+            }
+        }
+        Err(e) => warn!("failed to load projects: {e:#}"),
+    }
+    let shared_with = session.shared_with.clone();
+
     let session_id = session.session_id.clone();
     let sessions_lock = Arc::clone(&state.sessions);
     let mut sessions = sessions_lock.write().await;
@@ -1348,6 +3103,7 @@ async fn handle_auth(req: &protocol::Request, state: Arc<SharedState>) -> String
             "session_id": session_id,
             "domain": domain,
             "auth_type": auth_type,
+            "shared_with": shared_with,
         }),
     )
 }
@@ -1355,21 +3111,40 @@ async fn handle_auth(req: &protocol::Request, state: Arc<SharedState>) -> String
 /// Bridge that receives low-level `ProgressEvent`s from the mapper and translates
 /// them into high-level `CortexEvent`s on the event bus.
 ///
+/// Every event that moves the request's overall progress is also published
+/// as a `MapProgress` event tagged with `request_id`, which socket clients
+/// that asked for progress receive as interim lines.
+///
 /// Runs as a background task for the duration of a mapping operation.
 async fn progress_event_bridge(
     mut rx: crate::progress::ProgressReceiver,
     event_bus: Arc<EventBus>,
     domain: String,
+    request_id: String,
+    mut tracker: crate::progress::ProgressTracker,
 ) {
     use crate::progress::{MappingLayer, ProgressEventKind};
 
+    let started = Instant::now();
+    let mut sitemap_reported = false;
+    let mut fetch_complete = false;
+    let mut fetch_reported = false;
     while let Ok(progress) = rx.recv().await {
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        if let Some(update) = tracker.apply(&progress.event, elapsed_ms) {
+            event_bus.emit(CortexEvent::MapProgress {
+                domain: domain.clone(),
+                request_id: request_id.clone(),
+                progress: update.clone(),
+            });
+        }
         match progress.event {
             ProgressEventKind::LayerCompleted {
                 layer,
                 message: _,
                 duration_ms,
             } => {
+                fetch_complete |= layer == MappingLayer::L1HttpFetch;
                 let (layer_num, layer_name) = match layer {
                     MappingLayer::L0Metadata => (0, "Metadata"),
                     MappingLayer::L1HttpFetch => (1, "Structured Data"),
@@ -1394,15 +3169,18 @@ async fn progress_event_bridge(
                 ..
             } => {
                 // After Layer 0, emit SitemapDiscovered
-                if pages_fetched == 0 && urls_discovered > 0 {
+                if !sitemap_reported && pages_fetched == 0 && urls_discovered > 0 {
+                    sitemap_reported = true;
                     event_bus.emit(CortexEvent::SitemapDiscovered {
                         domain: domain.clone(),
                         url_count: urls_discovered as usize,
                         elapsed_ms: 0, // approximate
                     });
                 }
-                // After Layer 1, emit StructuredDataExtracted
-                if pages_fetched > 0 {
+                // After Layer 1, emit StructuredDataExtracted (Layer 1 also
+                // reports its counters after every batch)
+                if fetch_complete && !fetch_reported && pages_fetched > 0 {
+                    fetch_reported = true;
                     event_bus.emit(CortexEvent::StructuredDataExtracted {
                         domain: domain.clone(),
                         pages_fetched: pages_fetched as usize,
@@ -1417,7 +3195,7 @@ async fn progress_event_bridge(
                 // MapComplete is emitted by handle_map directly with richer data
                 break;
             }
-            _ => {} // LayerStarted, LayerSkipped, UrlProcessed, Warning — skip for now
+            _ => {} // LayerStarted, LayerSkipped, UrlProcessed, Warning — MapProgress only
         }
     }
 }
@@ -1544,6 +3322,158 @@ mod tests {
         let _ = std::fs::remove_file(&socket_path);
     }
 
+    #[tokio::test]
+    async fn test_progress_bridge_publishes_map_progress() {
+        use crate::progress::{self, MappingLayer, ProgressEventKind, ProgressTracker};
+
+        let bus = Arc::new(EventBus::new(64));
+        let mut events = bus.subscribe();
+        let (tx, rx) = progress::channel();
+        let bridge = tokio::spawn(progress_event_bridge(
+            rx,
+            Arc::clone(&bus),
+            "shop.com".to_string(),
+            "map-7".to_string(),
+            ProgressTracker::new(30_000, 50),
+        ));
+
+        let tx = Some(tx);
+        let mut seq = 0;
+        progress::emit(
+            &tx,
+            "map-7",
+            &mut seq,
+            ProgressEventKind::LayerStarted {
+                layer: MappingLayer::L1HttpFetch,
+                message: String::new(),
+            },
+        );
+        progress::emit(
+            &tx,
+            "map-7",
+            &mut seq,
+            ProgressEventKind::MappingComplete {
+                node_count: 1,
+                edge_count: 0,
+                action_count: 0,
+                elapsed_ms: 10,
+            },
+        );
+        bridge.await.unwrap();
+
+        let mut updates = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let CortexEvent::MapProgress {
+                domain,
+                request_id,
+                progress,
+            } = event
+            {
+                assert_eq!(
+                    (domain.as_str(), request_id.as_str()),
+                    ("shop.com", "map-7")
+                );
+                updates.push(progress);
+            }
+        }
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[0].phase, MappingLayer::L1HttpFetch);
+        assert_eq!(updates[1].percent, 100);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_map_keeps_action_costs() {
+        use crate::extraction::loader::ExtractionLoader;
+        use crate::map::builder::SiteMapBuilder;
+        use crate::renderer::NoopRenderer;
+
+        let domain = "cortex-test.invalid";
+        let renderer: Arc<dyn Renderer> = Arc::new(NoopRenderer);
+        let mapper = Mapper::new(
+            Arc::clone(&renderer),
+            Arc::new(ExtractionLoader::new().unwrap()),
+        );
+        let state = Server::new(Path::new("/tmp/cortex-test-costs.sock"))
+            .with_mapper(renderer, Arc::new(mapper))
+            .shared_state();
+        let mut old = SiteMapBuilder::new(domain);
+        old.add_node(
+            &format!("https://{domain}/"),
+            PageType::Home,
+            [0.0; FEATURE_DIM],
+            200,
+        );
+        state.maps.write().await.insert(domain.into(), old.build());
+        state.action_costs.write().await.record(
+            domain,
+            &Outcome {
+                node: 0,
+                target: None,
+                opcode: OpCode::new(0x02, 0x00),
+                success: false,
+                latency_ms: 100,
+            },
+        );
+
+        // The domain cannot be fetched, so no map reaches a perfect score.
+        let request = protocol::Request {
+            id: "m1".to_string(),
+            method: Method::Map,
+            params: serde_json::json!({
+                "domain": domain,
+                "max_time_ms": 2000,
+                "min_quality": 1.0,
+            }),
+        };
+        let response: serde_json::Value = serde_json::from_str(
+            &handle_request(request, Arc::clone(&state), Caller::Anonymous).await,
+        )
+        .unwrap();
+        assert!(response.get("error").is_some(), "{response}");
+
+        assert_eq!(state.maps.read().await[domain].nodes.len(), 1);
+        assert!(state.action_costs.read().await.get(domain).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_in_flight_request() {
+        let state = Server::new(Path::new("/tmp/cortex-test-cancel.sock")).shared_state();
+        let flight = state.in_flight.register("map-9", Method::Map.as_str());
+
+        let cancel = |params: serde_json::Value| protocol::Request {
+            id: "c1".to_string(),
+            method: Method::Cancel,
+            params,
+        };
+        let response: serde_json::Value = serde_json::from_str(
+            &handle_request(
+                cancel(serde_json::json!({"request_id": "map-8"})),
+                Arc::clone(&state),
+                Caller::Anonymous,
+            )
+            .await,
+        )
+        .unwrap();
+        assert_eq!(response["error"]["code"], "E_NOT_FOUND");
+        assert!(!flight.is_cancelled());
+
+        let response: serde_json::Value = serde_json::from_str(
+            &handle_request(
+                cancel(serde_json::json!({"request_id": "map-9", "partial": true})),
+                Arc::clone(&state),
+                Caller::Anonymous,
+            )
+            .await,
+        )
+        .unwrap();
+        assert_eq!(response["result"]["method"], "map");
+        assert_eq!(response["result"]["cancelled"], true);
+        assert!(flight.is_cancelled() && flight.wants_partial());
+
+        drop(flight);
+        assert!(state.in_flight.list().is_empty());
+    }
+
     #[tokio::test]
     async fn test_malformed_json_keeps_connection() {
         let socket_path = format!("/tmp/cortex-test-json-{}.sock", std::process::id());
@@ -1591,4 +3521,503 @@ mod tests {
         let _ = server_task.await;
         let _ = std::fs::remove_file(&socket_path);
     }
+
+    #[tokio::test]
+    async fn test_pipelined_requests_answer_out_of_order() {
+        use tokio::io::AsyncBufReadExt;
+
+        let socket_path =
+            PathBuf::from(format!("/tmp/cortex-test-pipe-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket_path);
+        let server = Server::new(&socket_path);
+        // Holding the map store stalls STATUS but not SUBSCRIBE.
+        let maps = Arc::clone(&server.shared_state().maps);
+        let shutdown = server.shutdown_handle();
+        let server_task = tokio::spawn(async move {
+            server.start().await.unwrap();
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let (read, mut write) = UnixStream::connect(&socket_path)
+            .await
+            .unwrap()
+            .into_split();
+        let mut lines = BufReader::new(read).lines();
+        let mut send = async |line: &str| {
+            write
+                .write_all(format!("{line}\n").as_bytes())
+                .await
+                .unwrap();
+        };
+        let mut next = async || -> serde_json::Value {
+            let line = lines.next_line().await.unwrap().unwrap();
+            serde_json::from_str(&line).unwrap()
+        };
+
+        send(r#"{"id":"h1","method":"handshake","params":{"pipeline":1000}}"#).await;
+        assert_eq!(next().await["result"]["pipeline"], MAX_PIPELINE_DEPTH);
+
+        let held = maps.write().await;
+        send(r#"{"id":"s1","method":"status","params":{}}"#).await;
+        send(r#"{"id":"e1","method":"subscribe","params":{}}"#).await;
+        assert_eq!(next().await["id"], "e1");
+        drop(held);
+        assert_eq!(next().await["id"], "s1");
+
+        // Without pipelining, responses come back in request order.
+        send(r#"{"id":"h2","method":"handshake","params":{}}"#).await;
+        assert_eq!(next().await["result"]["pipeline"], 1);
+        let held = maps.write().await;
+        send(r#"{"id":"s2","method":"status","params":{}}"#).await;
+        send(r#"{"id":"e2","method":"subscribe","params":{}}"#).await;
+        assert!(tokio::time::timeout(Duration::from_millis(200), next())
+            .await
+            .is_err());
+        drop(held);
+        assert_eq!(next().await["id"], "s2");
+        assert_eq!(next().await["id"], "e2");
+
+        shutdown.notify_one();
+        let _ = server_task.await;
+        let _ = std::fs::remove_file(&socket_path);
+    }
+
+    #[tokio::test]
+    async fn test_api_keys_enforced_per_connection() {
+        use crate::trust::api_keys::ApiKeys;
+
+        let dir = tempfile::tempdir().unwrap();
+        let keys_path = dir.path().join("api_keys.json");
+        let mut keys = ApiKeys::default();
+        let (_, reader_secret) = keys.add("reader", Permission::Read).unwrap();
+        keys.save(&keys_path).unwrap();
+
+        let socket_path =
+            PathBuf::from(format!("/tmp/cortex-test-keys-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket_path);
+        let server = Server::new(&socket_path).with_api_keys(KeyStore::open(keys_path));
+        let shutdown = server.shutdown_handle();
+        let server_task = tokio::spawn(async move {
+            server.start().await.unwrap();
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let mut stream = UnixStream::connect(&socket_path).await.unwrap();
+        let mut buf = vec![0u8; 4096];
+        let mut send = async |line: &str| -> serde_json::Value {
+            stream
+                .write_all(format!("{line}\n").as_bytes())
+                .await
+                .unwrap();
+            let n = stream.read(&mut buf).await.unwrap();
+            serde_json::from_slice(&buf[..n]).unwrap()
+        };
+
+        let status = r#"{"id":"s1","method":"status","params":{}}"#;
+        assert_eq!(send(status).await["error"]["code"], "E_UNAUTHORIZED");
+        let bad = r#"{"id":"h0","method":"handshake","params":{"api_key":"cortex_00000000_nope"}}"#;
+        assert_eq!(send(bad).await["error"]["code"], "E_UNAUTHORIZED");
+
+        let handshake = format!(
+            r#"{{"id":"h1","method":"handshake","params":{{"api_key":"{reader_secret}"}}}}"#
+        );
+        assert_eq!(send(&handshake).await["result"]["permission"], "read");
+        let status = r#"{"id":"s2","method":"status","params":{}}"#;
+        assert!(send(status).await["result"]["version"].is_string());
+        let map = r#"{"id":"m1","method":"map","params":{"domain":"example.com"}}"#;
+        let response = send(map).await;
+        assert_eq!(response["error"]["code"], "E_FORBIDDEN");
+        assert!(response["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("reader"));
+
+        drop(stream);
+        shutdown.notify_one();
+        let _ = server_task.await;
+        let _ = std::fs::remove_file(&socket_path);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_replays_and_follows() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let socket_path =
+            PathBuf::from(format!("/tmp/cortex-test-sub-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket_path);
+
+        let log = EventLog::open(&dir.path().join("events.jsonl")).unwrap();
+        let server = Server::new(&socket_path).with_event_log(log);
+        let shutdown = server.shutdown_handle();
+        let bus = Arc::clone(&server.shared_state().event_bus);
+        bus.emit(CortexEvent::WatchFired {
+            domain: "shop.com".to_string(),
+            rule_id: "r1".to_string(),
+            message: "fired".to_string(),
+            current_value: 1.0,
+        });
+
+        let server_task = tokio::spawn(async move {
+            server.start().await.unwrap();
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        let stream = UnixStream::connect(&socket_path).await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        writer
+            .write_all(b"{\"id\":\"sub1\",\"method\":\"subscribe\",\"params\":{\"follow\":true}}\n")
+            .await
+            .unwrap();
+
+        let first: serde_json::Value =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(first["result"]["events"].as_array().unwrap().len(), 1);
+        assert_eq!(first["result"]["last_seq"], 1);
+
+        bus.emit(CortexEvent::MapStarted {
+            domain: "shop.com".to_string(),
+            timestamp: "0".to_string(),
+        });
+        bus.emit(CortexEvent::WatchFired {
+            domain: "shop.com".to_string(),
+            rule_id: "r1".to_string(),
+            message: "fired again".to_string(),
+            current_value: 2.0,
+        });
+
+        let live: serde_json::Value =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(live["id"], "sub1");
+        assert_eq!(live["event"]["seq"], 2);
+        assert_eq!(live["event"]["event"]["type"], "WatchFired");
+
+        drop(writer);
+        shutdown.notify_one();
+        let _ = server_task.await;
+        let _ = std::fs::remove_file(&socket_path);
+    }
+
+    #[tokio::test]
+    async fn test_lagging_subscriber_catches_up_from_log() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let socket_path = PathBuf::from(format!(
+            "/tmp/cortex-test-sub-lag-{}.sock",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&socket_path);
+
+        let log = EventLog::open(&dir.path().join("events.jsonl")).unwrap();
+        let server = Server::new(&socket_path).with_event_log(log);
+        let shutdown = server.shutdown_handle();
+        let bus = Arc::clone(&server.shared_state().event_bus);
+        let server_task = tokio::spawn(async move {
+            server.start().await.unwrap();
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        let stream = UnixStream::connect(&socket_path).await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        writer
+            .write_all(b"{\"id\":\"sub1\",\"method\":\"subscribe\",\"params\":{\"follow\":true}}\n")
+            .await
+            .unwrap();
+        let first: serde_json::Value =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(first["result"]["last_seq"], serde_json::Value::Null);
+
+        // More events than the broadcast holds, emitted without yielding,
+        // so the subscriber lags behind the live channel.
+        for i in 0..600 {
+            bus.emit(CortexEvent::WatchFired {
+                domain: "shop.com".to_string(),
+                rule_id: "r1".to_string(),
+                message: format!("fired {i}"),
+                current_value: i as f32,
+            });
+        }
+
+        for seq in 1..=600u64 {
+            let line: serde_json::Value =
+                serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+            assert_eq!(line["event"]["seq"], seq);
+        }
+
+        drop(writer);
+        shutdown.notify_one();
+        let _ = server_task.await;
+        let _ = std::fs::remove_file(&socket_path);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_blocked_by_crawl_calendar() {
+        use crate::temporal::scheduler::{Blackout, CrawlCalendar};
+
+        let mut cal = CrawlCalendar::new("shop.com");
+        cal.blackouts.push(Blackout::Range {
+            start: chrono::Utc::now() - chrono::Duration::hours(1),
+            end: chrono::Utc::now() + chrono::Duration::hours(1),
+            reason: Some("peak hours".to_string()),
+        });
+        let mut scheduler = CrawlScheduler::new();
+        scheduler.set_calendar(cal).unwrap();
+
+        let server =
+            Server::new(Path::new("/tmp/cortex-test-unused.sock")).with_scheduler(scheduler);
+        let state = server.shared_state();
+
+        let req = protocol::parse_request(
+            r#"{"id":"r1","method":"refresh","params":{"domain":"shop.com"}}"#,
+        )
+        .unwrap();
+        let resp: serde_json::Value = serde_json::from_str(
+            handle_request(req, Arc::clone(&state), Caller::Anonymous)
+                .await
+                .trim(),
+        )
+        .unwrap();
+        assert_eq!(resp["error"]["code"], "E_OUTSIDE_CRAWL_WINDOW");
+        assert!(resp["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("peak hours"));
+        assert!(resp["error"]["next_allowed"].is_string());
+
+        // Other domains are unaffected
+        let req = protocol::parse_request(
+            r#"{"id":"r2","method":"refresh","params":{"domain":"other.com"}}"#,
+        )
+        .unwrap();
+        let resp: serde_json::Value =
+            serde_json::from_str(handle_request(req, state, Caller::Anonymous).await.trim())
+                .unwrap();
+        assert_eq!(resp["error"]["code"], "E_NOT_IMPLEMENTED");
+    }
+
+    #[tokio::test]
+    async fn test_reload_keeps_cached_maps() {
+        use crate::map::builder::SiteMapBuilder;
+
+        let mut builder = SiteMapBuilder::new("shop.com");
+        builder.add_node("https://shop.com/", PageType::Home, [0.0; FEATURE_DIM], 230);
+        let state = Server::new(Path::new("/tmp/cortex-test-unused.sock")).shared_state();
+        state
+            .maps
+            .write()
+            .await
+            .insert("shop.com".to_string(), builder.build());
+
+        let req = protocol::parse_request(r#"{"id":"r1","method":"reload","params":{}}"#).unwrap();
+        let resp: serde_json::Value = serde_json::from_str(
+            handle_request(req, Arc::clone(&state), Caller::Anonymous)
+                .await
+                .trim(),
+        )
+        .unwrap();
+        let result = &resp["result"];
+        for source in ["crawl_calendars", "watch_rules"] {
+            assert!(
+                result["reloaded"].get(source).is_some() || result["errors"].get(source).is_some(),
+                "{source} missing from {result}"
+            );
+        }
+        // No mapper, so no extraction scripts to reload.
+        assert!(result["reloaded"].get("extractors").is_none());
+        assert!(state.maps.read().await.contains_key("shop.com"));
+    }
+
+    #[tokio::test]
+    async fn test_query_include_lineage() {
+        use crate::map::builder::SiteMapBuilder;
+        use crate::map::types::FEAT_PRICE;
+        use crate::trust::lineage::{FieldLineage, Layer};
+
+        let mut builder = SiteMapBuilder::new("shop.com");
+        let mut feats = [0.0f32; FEATURE_DIM];
+        feats[FEAT_PRICE] = 20.0;
+        builder.add_node("https://shop.com/p/1", PageType::ProductDetail, feats, 230);
+        builder.add_node("https://shop.com/p/2", PageType::ProductDetail, feats, 230);
+        builder.record_lineage(
+            0,
+            "price",
+            FieldLineage::new(Layer::StructuredData, Some("offers.price"), 1.0, 0),
+        );
+
+        let server = Server::new(Path::new("/tmp/cortex-test-unused.sock"));
+        let state = server.shared_state();
+        state
+            .maps
+            .write()
+            .await
+            .insert("shop.com".to_string(), builder.build());
+
+        let req = protocol::parse_request(
+            r#"{"id":"q1","method":"query","params":{"domain":"shop.com","include_lineage":true}}"#,
+        )
+        .unwrap();
+        let resp: serde_json::Value =
+            serde_json::from_str(handle_request(req, state, Caller::Anonymous).await.trim())
+                .unwrap();
+        let matches = resp["result"]["matches"].as_array().unwrap();
+        let traced = matches.iter().find(|m| m["index"] == 0).unwrap();
+        assert_eq!(traced["lineage"]["price"]["layer"], "structured_data");
+        assert_eq!(traced["lineage"]["price"]["path"], "offers.price");
+        assert!(traced["trust"].is_number());
+        let untraced = matches.iter().find(|m| m["index"] == 1).unwrap();
+        assert!(untraced["lineage"].is_null() && untraced["trust"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_semantic_query_needs_goal_and_model() {
+        use crate::map::builder::SiteMapBuilder;
+
+        let mut builder = SiteMapBuilder::new("shop.com");
+        builder.add_node(
+            "https://shop.com/returns",
+            PageType::Faq,
+            [0.0f32; FEATURE_DIM],
+            230,
+        );
+        let state = Server::new(Path::new("/tmp/cortex-test-unused.sock")).shared_state();
+        state
+            .maps
+            .write()
+            .await
+            .insert("shop.com".to_string(), builder.build());
+
+        let query = |params: &str| {
+            let req = protocol::parse_request(&format!(
+                r#"{{"id":"s1","method":"query","params":{params}}}"#
+            ))
+            .unwrap();
+            let state = Arc::clone(&state);
+            async move {
+                let resp = handle_request(req, state, Caller::Anonymous).await;
+                serde_json::from_str::<serde_json::Value>(resp.trim()).unwrap()
+            }
+        };
+        let resp = query(r#"{"domain":"shop.com","mode":"semantic"}"#).await;
+        assert_eq!(resp["error"]["code"], "E_INVALID_PARAMS");
+        // This daemon has no sentence model
+        let resp =
+            query(r#"{"domain":"shop.com","mode":"semantic","goal":"return an order"}"#).await;
+        assert_eq!(resp["error"]["code"], "E_NOT_IMPLEMENTED");
+    }
+
+    #[tokio::test]
+    async fn test_wql_reads_maps_in_memory() {
+        use crate::map::builder::SiteMapBuilder;
+        use crate::map::types::FEAT_PRICE;
+
+        let mut builder = SiteMapBuilder::new("wql-memory.test");
+        let mut feats = [0.0f32; FEATURE_DIM];
+        feats[FEAT_PRICE] = 20.0;
+        builder.add_node(
+            "https://wql-memory.test/p/1",
+            PageType::ProductDetail,
+            feats,
+            230,
+        );
+        let state = Server::new(Path::new("/tmp/cortex-test-unused.sock")).shared_state();
+        state
+            .maps
+            .write()
+            .await
+            .insert("wql-memory.test".to_string(), builder.build());
+
+        let req = protocol::parse_request(
+            r#"{"id":"w1","method":"wql","params":{"query":"SELECT price FROM Product"}}"#,
+        )
+        .unwrap();
+        let resp: serde_json::Value = serde_json::from_str(
+            handle_request(req, Arc::clone(&state), Caller::Anonymous)
+                .await
+                .trim(),
+        )
+        .unwrap();
+        let rows = resp["result"]["rows"].as_array().unwrap();
+        let row = rows
+            .iter()
+            .find(|r| r["domain"] == "wql-memory.test")
+            .unwrap();
+        assert_eq!(row["url"], "https://wql-memory.test/p/1");
+        assert_eq!(row["fields"]["price"], 20.0);
+
+        let req = protocol::parse_request(
+            r#"{"id":"w2","method":"wql","params":{"query":"SELEKT nothing"}}"#,
+        )
+        .unwrap();
+        let resp = handle_request(req, state, Caller::Anonymous).await;
+        assert!(resp.contains("E_INVALID_PARAMS"), "{resp}");
+    }
+
+    #[tokio::test]
+    async fn test_annotate_corrects_map_in_memory() {
+        use crate::map::builder::SiteMapBuilder;
+
+        let mut builder = SiteMapBuilder::new("annotate.test");
+        builder.add_node(
+            "https://annotate.test/",
+            PageType::Home,
+            [0.0; FEATURE_DIM],
+            240,
+        );
+        builder.add_node(
+            "https://annotate.test/basket/confirm",
+            PageType::Cart,
+            [0.0; FEATURE_DIM],
+            150,
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let state = Server::new(Path::new("/tmp/cortex-test-unused.sock"))
+            .with_annotations(AnnotationStore::new(dir.path()))
+            .shared_state();
+        state
+            .maps
+            .write()
+            .await
+            .insert("annotate.test".to_string(), builder.build());
+        let call = |line: &str| {
+            let req = protocol::parse_request(line).unwrap();
+            let state = Arc::clone(&state);
+            async move {
+                let resp = handle_request(req, state, Caller::Anonymous).await;
+                serde_json::from_str::<serde_json::Value>(resp.trim()).unwrap()
+            }
+        };
+
+        let resp = call(
+            r#"{"id":"a1","method":"annotate","params":{"domain":"annotate.test","node":1,"page_type":"checkout","author":"qa"}}"#,
+        )
+        .await;
+        assert_eq!(resp["result"]["applied"], true);
+        assert_eq!(resp["result"]["annotation"]["page_type"], 9);
+        assert_eq!(resp["result"]["annotation"]["node"], 1);
+        {
+            let maps = state.maps.read().await;
+            let map = &maps["annotate.test"];
+            assert_eq!(map.nodes[1].page_type, PageType::Checkout);
+            assert_eq!(map.nodes[1].confidence, 255);
+        }
+
+        call(r#"{"id":"a2","method":"annotate","params":{"domain":"annotate.test","url":"https://annotate.test/","note":"has a banner"}}"#).await;
+        let resp = call(
+            r#"{"id":"a3","method":"annotations","params":{"domain":"annotate.test","node":1}}"#,
+        )
+        .await;
+        assert_eq!(resp["result"]["total"], 1);
+        assert_eq!(resp["result"]["annotations"][0]["kind"], "correction");
+        assert_eq!(resp["result"]["annotations"][0]["author"], "qa");
+
+        let resp = call(
+            r#"{"id":"a4","method":"annotate","params":{"domain":"annotate.test","node":1,"label":"x","note":"y"}}"#,
+        )
+        .await;
+        assert_eq!(resp["error"]["code"], "E_INVALID_PARAMS");
+        let resp = call(
+            r#"{"id":"a5","method":"annotate","params":{"domain":"annotate.test","node":9,"label":"x"}}"#,
+        )
+        .await;
+        assert_eq!(resp["error"]["code"], "E_INVALID_PARAMS");
+    }
 }
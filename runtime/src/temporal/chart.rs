@@ -0,0 +1,294 @@
+//! Chart rendering for temporal series — terminal sparklines and PNG line charts.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::path::Path;
+
+/// Block characters used for sparklines, lowest to highest.
+const SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+const BACKGROUND: [u8; 3] = [255, 255, 255];
+const GRID: [u8; 3] = [225, 225, 225];
+const AXIS: [u8; 3] = [120, 120, 120];
+const LINE: [u8; 3] = [33, 102, 172];
+const MARGIN: u32 = 24;
+const MAX_SIDE: u32 = 8192;
+
+/// Summary statistics for a series.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SeriesStats {
+    /// Lowest value.
+    pub min: f32,
+    /// Highest value.
+    pub max: f32,
+    /// Earliest value.
+    pub first: f32,
+    /// Latest value.
+    pub last: f32,
+}
+
+impl SeriesStats {
+    /// Compute stats for a series. Returns `None` if it is empty.
+    pub fn of(points: &[(DateTime<Utc>, f32)]) -> Option<Self> {
+        let first = points.first()?.1;
+        let last = points.last()?.1;
+        let (min, max) = points
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &(_, v)| {
+                (lo.min(v), hi.max(v))
+            });
+        Some(Self {
+            min,
+            max,
+            first,
+            last,
+        })
+    }
+
+    /// Relative change from first to last value, in percent.
+    pub fn change_pct(&self) -> Option<f32> {
+        if self.first == 0.0 {
+            None
+        } else {
+            Some((self.last - self.first) / self.first.abs() * 100.0)
+        }
+    }
+}
+
+/// Render values as a sparkline at most `width` characters wide.
+///
+/// Longer series are bucketed by averaging; a flat series renders at mid height.
+pub fn sparkline(values: &[f32], width: usize) -> String {
+    if values.is_empty() || width == 0 {
+        return String::new();
+    }
+
+    let buckets = resample(values, width);
+    let min = buckets.iter().copied().fold(f32::INFINITY, f32::min);
+    let max = buckets.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let range = max - min;
+    let top = (SPARK_CHARS.len() - 1) as f32;
+
+    buckets
+        .iter()
+        .map(|&v| {
+            let level = if range > f32::EPSILON {
+                ((v - min) / range * top).round() as usize
+            } else {
+                SPARK_CHARS.len() / 2
+            };
+            SPARK_CHARS[level.min(SPARK_CHARS.len() - 1)]
+        })
+        .collect()
+}
+
+/// Average `values` down to at most `width` buckets.
+fn resample(values: &[f32], width: usize) -> Vec<f32> {
+    if values.len() <= width {
+        return values.to_vec();
+    }
+    (0..width)
+        .map(|i| {
+            let start = i * values.len() / width;
+            let end = ((i + 1) * values.len() / width).max(start + 1);
+            let slice = &values[start..end];
+            slice.iter().sum::<f32>() / slice.len() as f32
+        })
+        .collect()
+}
+
+/// Render a time series as a PNG line chart.
+///
+/// The x axis is scaled by timestamp, the y axis by value range. Horizontal
+/// grid lines mark quartiles of the range.
+pub fn render_png(
+    points: &[(DateTime<Utc>, f32)],
+    width: u32,
+    height: u32,
+    path: &Path,
+) -> Result<()> {
+    if points.is_empty() {
+        anyhow::bail!("no data points to chart");
+    }
+    if width <= MARGIN * 2 || height <= MARGIN * 2 {
+        anyhow::bail!("chart size {width}x{height} is too small");
+    }
+    if width > MAX_SIDE || height > MAX_SIDE {
+        anyhow::bail!("chart size {width}x{height} is too large (at most {MAX_SIDE} per side)");
+    }
+
+    let mut canvas = Canvas::new(width, height);
+    let (left, right) = (MARGIN as i64, (width - MARGIN) as i64);
+    let (top, bottom) = (MARGIN as i64, (height - MARGIN) as i64);
+
+    for q in 0..=4 {
+        let y = top + (bottom - top) * q / 4;
+        canvas.line(left, y, right, y, GRID);
+    }
+    canvas.line(left, top, left, bottom, AXIS);
+    canvas.line(left, bottom, right, bottom, AXIS);
+
+    let stats = SeriesStats::of(points).expect("points is non-empty");
+    let t0 = points[0].0.timestamp() as f64;
+    let t_span = (points[points.len() - 1].0.timestamp() as f64 - t0).max(1.0);
+    let v_span = (stats.max - stats.min) as f64;
+
+    let project = |ts: DateTime<Utc>, v: f32| -> (i64, i64) {
+        let x = if points.len() == 1 {
+            (left + right) / 2
+        } else {
+            left + ((ts.timestamp() as f64 - t0) / t_span * (right - left) as f64).round() as i64
+        };
+        let frac = if v_span > f64::EPSILON {
+            (v - stats.min) as f64 / v_span
+        } else {
+            0.5
+        };
+        let y = bottom - (frac * (bottom - top) as f64).round() as i64;
+        (x, y)
+    };
+
+    let projected: Vec<(i64, i64)> = points.iter().map(|&(ts, v)| project(ts, v)).collect();
+    for pair in projected.windows(2) {
+        canvas.line(pair[0].0, pair[0].1, pair[1].0, pair[1].1, LINE);
+    }
+    for &(x, y) in &projected {
+        canvas.dot(x, y, LINE);
+    }
+
+    canvas.write_png(path)
+}
+
+/// Minimal RGB raster.
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32) -> Self {
+        let pixels = BACKGROUND
+            .iter()
+            .copied()
+            .cycle()
+            .take(width as usize * height as usize * 3)
+            .collect();
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    fn set(&mut self, x: i64, y: i64, color: [u8; 3]) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return;
+        }
+        let idx = (y as usize * self.width as usize + x as usize) * 3;
+        self.pixels[idx..idx + 3].copy_from_slice(&color);
+    }
+
+    /// Bresenham line.
+    fn line(&mut self, x0: i64, y0: i64, x1: i64, y1: i64, color: [u8; 3]) {
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+        let (mut x, mut y, mut err) = (x0, y0, dx + dy);
+        loop {
+            self.set(x, y, color);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    fn dot(&mut self, x: i64, y: i64, color: [u8; 3]) {
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                self.set(x + dx, y + dy, color);
+            }
+        }
+    }
+
+    fn write_png(&self, path: &Path) -> Result<()> {
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("creating chart file: {}", path.display()))?;
+        let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn series(values: &[f32]) -> Vec<(DateTime<Utc>, f32)> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| {
+                (
+                    Utc.timestamp_opt(1_700_000_000 + i as i64 * 86400, 0)
+                        .unwrap(),
+                    v,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sparkline_levels() {
+        assert_eq!(
+            sparkline(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0], 20),
+            "▁▂▃▄▅▆▇█"
+        );
+        assert_eq!(sparkline(&[5.0, 5.0, 5.0], 20), "▅▅▅");
+        assert_eq!(sparkline(&[], 20), "");
+    }
+
+    #[test]
+    fn test_sparkline_resamples_to_width() {
+        let values: Vec<f32> = (0..100).map(|i| i as f32).collect();
+        let line = sparkline(&values, 10);
+        assert_eq!(line.chars().count(), 10);
+        assert!(line.starts_with('▁'));
+        assert!(line.ends_with('█'));
+    }
+
+    #[test]
+    fn test_series_stats() {
+        let stats = SeriesStats::of(&series(&[200.0, 150.0, 180.0])).unwrap();
+        assert_eq!(stats.min, 150.0);
+        assert_eq!(stats.max, 200.0);
+        assert!((stats.change_pct().unwrap() + 10.0).abs() < 1e-4);
+        assert!(SeriesStats::of(&[]).is_none());
+    }
+
+    #[test]
+    fn test_render_png() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("chart.png");
+        render_png(&series(&[10.0, 12.0, 9.0, 15.0]), 320, 160, &path).unwrap();
+
+        let data = std::fs::read(&path).unwrap();
+        assert_eq!(&data[..8], b"\x89PNG\r\n\x1a\n");
+
+        assert!(render_png(&[], 320, 160, &path).is_err());
+        assert!(render_png(&series(&[1.0]), 30, 30, &path).is_err());
+        assert!(render_png(&series(&[1.0]), 100_000, 100_000, &path).is_err());
+    }
+}
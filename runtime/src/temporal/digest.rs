@@ -0,0 +1,391 @@
+//! Per-domain change digests over a time window.
+//!
+//! A digest compares each domain's map at the start of the window with its
+//! latest map in the window — pages added and removed, and commerce
+//! features (price, availability, rating, ...) that moved by more than a
+//! threshold — and lists the watch alerts that fired in between. It renders
+//! as markdown for people (`cortex temporal digest`, a weekly "what changed
+//! on these 20 sites" report) and as JSON for webhooks and scripts.
+
+use crate::events::{CortexEvent, LoggedEvent};
+use crate::map::types::{SiteMap, FEATURE_DIM};
+use crate::temporal::watch::feature_dim_name;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// How a digest decides what is worth reporting.
+#[derive(Debug, Clone)]
+pub struct DigestOptions {
+    /// Smallest relative change of a feature that is reported (0.05 = 5%).
+    /// A change from zero is always reported.
+    pub min_change: f32,
+    /// Most pages and changes listed per section; the counts stay exact.
+    pub max_items: usize,
+}
+
+impl Default for DigestOptions {
+    fn default() -> Self {
+        Self {
+            min_change: 0.05,
+            max_items: 20,
+        }
+    }
+}
+
+/// A feature of one page that changed significantly.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeatureChange {
+    pub url: String,
+    /// Feature name, e.g. `"price"`.
+    pub feature: String,
+    pub before: f32,
+    pub after: f32,
+}
+
+impl FeatureChange {
+    /// Relative change, or `None` from zero.
+    pub fn change_pct(&self) -> Option<f32> {
+        (self.before != 0.0).then(|| (self.after - self.before) / self.before.abs() * 100.0)
+    }
+}
+
+/// A watch alert that fired in the window.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DigestAlert {
+    pub timestamp: DateTime<Utc>,
+    pub rule_id: String,
+    pub message: String,
+}
+
+/// What changed on one domain.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DomainDigest {
+    pub domain: String,
+    /// When the map compared against was pushed.
+    pub from: Option<DateTime<Utc>>,
+    /// When the latest map in the window was pushed.
+    pub to: Option<DateTime<Utc>>,
+    pub pages_before: usize,
+    pub pages_after: usize,
+    pub added_count: usize,
+    /// URLs of added pages, at most [`DigestOptions::max_items`].
+    pub added: Vec<String>,
+    pub removed_count: usize,
+    /// URLs of removed pages, at most [`DigestOptions::max_items`].
+    pub removed: Vec<String>,
+    pub changes_count: usize,
+    /// Biggest feature changes first, at most [`DigestOptions::max_items`].
+    pub changes: Vec<FeatureChange>,
+    pub alerts: Vec<DigestAlert>,
+}
+
+impl DomainDigest {
+    /// Compare `before` with `after` (either may be missing, e.g. a domain
+    /// first mapped in the window) and attach the alerts among `events`.
+    pub fn build(
+        domain: &str,
+        before: Option<(&SiteMap, DateTime<Utc>)>,
+        after: Option<(&SiteMap, DateTime<Utc>)>,
+        events: &[LoggedEvent],
+        options: &DigestOptions,
+    ) -> Self {
+        let mut digest = Self {
+            domain: domain.to_string(),
+            from: before.map(|(_, at)| at),
+            to: after.map(|(_, at)| at),
+            pages_before: before.map_or(0, |(m, _)| m.nodes.len()),
+            pages_after: after.map_or(0, |(m, _)| m.nodes.len()),
+            ..Default::default()
+        };
+
+        let old_index = url_index(before.map(|(m, _)| m));
+        let new_index = url_index(after.map(|(m, _)| m));
+
+        if let Some((new, _)) = after {
+            for (i, url) in new.urls.iter().enumerate() {
+                match old_index.get(url.as_str()).zip(before) {
+                    None => {
+                        digest.added_count += 1;
+                        if digest.added.len() < options.max_items {
+                            digest.added.push(url.clone());
+                        }
+                    }
+                    Some((&j, (old, _))) => {
+                        let old = &old.features[j];
+                        let new = &new.features[i];
+                        for dim in 0..FEATURE_DIM {
+                            let Some(name) = feature_dim_name(dim) else {
+                                continue;
+                            };
+                            if is_significant(old[dim], new[dim], options.min_change) {
+                                digest.changes.push(FeatureChange {
+                                    url: url.clone(),
+                                    feature: name.to_string(),
+                                    before: old[dim],
+                                    after: new[dim],
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        if let Some((old, _)) = before {
+            for url in &old.urls {
+                if !new_index.contains_key(url.as_str()) {
+                    digest.removed_count += 1;
+                    if digest.removed.len() < options.max_items {
+                        digest.removed.push(url.clone());
+                    }
+                }
+            }
+        }
+
+        let magnitude = |c: &FeatureChange| c.change_pct().map_or(f32::INFINITY, f32::abs);
+        digest
+            .changes
+            .sort_by(|a, b| magnitude(b).total_cmp(&magnitude(a)));
+        digest.changes_count = digest.changes.len();
+        digest.changes.truncate(options.max_items);
+
+        digest.alerts = events
+            .iter()
+            .filter_map(|e| match &e.event {
+                CortexEvent::WatchFired {
+                    domain: d,
+                    rule_id,
+                    message,
+                    ..
+                } if d == domain => Some(DigestAlert {
+                    timestamp: e.timestamp,
+                    rule_id: rule_id.clone(),
+                    message: message.clone(),
+                }),
+                _ => None,
+            })
+            .collect();
+        digest
+    }
+
+    /// Whether anything changed.
+    pub fn is_empty(&self) -> bool {
+        self.added_count == 0
+            && self.removed_count == 0
+            && self.changes_count == 0
+            && self.alerts.is_empty()
+    }
+}
+
+/// Node index of every URL of `map`.
+fn url_index(map: Option<&SiteMap>) -> HashMap<&str, usize> {
+    map.map(|m| {
+        m.urls
+            .iter()
+            .enumerate()
+            .map(|(i, u)| (u.as_str(), i))
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Whether a feature moving from `before` to `after` is worth reporting.
+fn is_significant(before: f32, after: f32, min_change: f32) -> bool {
+    if (after - before).abs() < 1e-6 {
+        return false;
+    }
+    before == 0.0 || ((after - before) / before).abs() >= min_change
+}
+
+/// Digests of several domains over one window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Digest {
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    pub domains: Vec<DomainDigest>,
+}
+
+impl Digest {
+    /// Render as a markdown report, quiet domains summed up in one line.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# Changes {} → {}\n",
+            self.since.format("%Y-%m-%d"),
+            self.until.format("%Y-%m-%d")
+        );
+        let (quiet, changed): (Vec<&DomainDigest>, Vec<&DomainDigest>) =
+            self.domains.iter().partition(|d| d.is_empty());
+        if changed.is_empty() {
+            out.push_str("No changes.\n");
+        }
+        for d in changed {
+            let _ = writeln!(out, "## {}\n", d.domain);
+            let _ = writeln!(
+                out,
+                "{} → {} pages: {} added, {} removed, {} feature changes, {} alerts\n",
+                d.pages_before,
+                d.pages_after,
+                d.added_count,
+                d.removed_count,
+                d.changes_count,
+                d.alerts.len()
+            );
+            write_urls(&mut out, "Added", &d.added, d.added_count);
+            write_urls(&mut out, "Removed", &d.removed, d.removed_count);
+            if !d.changes.is_empty() {
+                out.push_str("### Feature changes\n\n");
+                for c in &d.changes {
+                    let _ = write!(
+                        out,
+                        "- {} {}: {:.2} → {:.2}",
+                        c.url, c.feature, c.before, c.after
+                    );
+                    match c.change_pct() {
+                        Some(pct) => {
+                            let _ = writeln!(out, " ({pct:+.1}%)");
+                        }
+                        None => out.push('\n'),
+                    }
+                }
+                write_more(&mut out, d.changes.len(), d.changes_count);
+                out.push('\n');
+            }
+            if !d.alerts.is_empty() {
+                out.push_str("### Alerts\n\n");
+                for a in &d.alerts {
+                    let _ = writeln!(
+                        out,
+                        "- {} `{}`: {}",
+                        a.timestamp.format("%Y-%m-%d %H:%M"),
+                        a.rule_id,
+                        a.message
+                    );
+                }
+                out.push('\n');
+            }
+        }
+        if !quiet.is_empty() {
+            let names: Vec<&str> = quiet.iter().map(|d| d.domain.as_str()).collect();
+            let _ = writeln!(out, "Unchanged: {}", names.join(", "));
+        }
+        out
+    }
+}
+
+fn write_urls(out: &mut String, heading: &str, urls: &[String], total: usize) {
+    if urls.is_empty() {
+        return;
+    }
+    let _ = writeln!(out, "### {heading}\n");
+    for url in urls {
+        let _ = writeln!(out, "- {url}");
+    }
+    write_more(out, urls.len(), total);
+    out.push('\n');
+}
+
+fn write_more(out: &mut String, shown: usize, total: usize) {
+    if total > shown {
+        let _ = writeln!(out, "- … and {} more", total - shown);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::builder::SiteMapBuilder;
+    use crate::map::types::*;
+
+    fn map(products: &[(&str, f32)]) -> SiteMap {
+        let mut builder = SiteMapBuilder::new("shop.com");
+        builder.add_node("https://shop.com/", PageType::Home, [0.0; FEATURE_DIM], 255);
+        for (path, price) in products {
+            let mut feats = [0.0f32; FEATURE_DIM];
+            feats[FEAT_PRICE] = *price;
+            feats[FEAT_AVAILABILITY] = 1.0;
+            builder.add_node(
+                &format!("https://shop.com/{path}"),
+                PageType::ProductDetail,
+                feats,
+                200,
+            );
+        }
+        builder.build()
+    }
+
+    fn fired(domain: &str, rule_id: &str) -> LoggedEvent {
+        LoggedEvent {
+            seq: 1,
+            timestamp: Utc::now(),
+            event: CortexEvent::WatchFired {
+                domain: domain.to_string(),
+                rule_id: rule_id.to_string(),
+                message: "price dropped".to_string(),
+                current_value: 90.0,
+            },
+        }
+    }
+
+    #[test]
+    fn test_domain_digest() {
+        let old = map(&[("a", 100.0), ("b", 50.0), ("c", 10.0)]);
+        let new = map(&[("a", 80.0), ("b", 51.0), ("d", 5.0)]);
+        let at = Utc::now();
+        let events = [fired("shop.com", "drop"), fired("other.com", "x")];
+
+        let digest = DomainDigest::build(
+            "shop.com",
+            Some((&old, at)),
+            Some((&new, at)),
+            &events,
+            &DigestOptions::default(),
+        );
+        assert_eq!((digest.pages_before, digest.pages_after), (4, 4));
+        assert_eq!(digest.added, ["https://shop.com/d"]);
+        assert_eq!(digest.removed, ["https://shop.com/c"]);
+        // b moved 2%, under the threshold
+        assert_eq!(digest.changes_count, 1);
+        assert_eq!(digest.changes[0].feature, "price");
+        assert_eq!(digest.changes[0].change_pct(), Some(-20.0));
+        assert_eq!(digest.alerts.len(), 1);
+        assert_eq!(digest.alerts[0].rule_id, "drop");
+        assert!(!digest.is_empty());
+
+        let unchanged = DomainDigest::build(
+            "shop.com",
+            Some((&old, at)),
+            Some((&old, at)),
+            &[],
+            &DigestOptions::default(),
+        );
+        assert!(unchanged.is_empty());
+    }
+
+    #[test]
+    fn test_digest_caps_lists() {
+        let new = map(&[("a", 1.0), ("b", 1.0), ("c", 1.0)]);
+        let options = DigestOptions {
+            max_items: 2,
+            ..Default::default()
+        };
+        let digest = DomainDigest::build("shop.com", None, Some((&new, Utc::now())), &[], &options);
+        assert_eq!(digest.added_count, 4);
+        assert_eq!(digest.added.len(), 2);
+
+        let markdown = Digest {
+            since: Utc::now(),
+            until: Utc::now(),
+            domains: vec![
+                digest,
+                DomainDigest::build("quiet.com", None, None, &[], &options),
+            ],
+        }
+        .to_markdown();
+        assert!(markdown.contains("## shop.com"));
+        assert!(markdown.contains("… and 2 more"));
+        assert!(markdown.contains("Unchanged: quiet.com"));
+    }
+}
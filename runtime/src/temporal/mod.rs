@@ -1,9 +1,15 @@
 //! Temporal Intelligence — history, pattern detection, prediction, and alerts.
 //!
 //! The temporal layer sits on top of the registry's delta history, exposing
-//! time-series queries, statistical pattern detection, and watch/alert rules.
+//! time-series queries, statistical pattern detection, watch/alert rules
+//! (including visual change watches), charts, change digests, and per-domain
+//! crawl calendars.
 
+pub mod chart;
+pub mod digest;
 pub mod patterns;
 pub mod query;
+pub mod scheduler;
 pub mod store;
+pub mod visual;
 pub mod watch;
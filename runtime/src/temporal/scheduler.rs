@@ -0,0 +1,441 @@
+//! Crawl scheduler — per-domain crawl calendars and blackout windows.
+//!
+//! Each domain may declare the local hours during which automated crawling
+//! is allowed, plus blackout windows (recurring weekly or one-off ranges)
+//! when it never is. Calendars are evaluated in the domain's own timezone
+//! and enforced for REFRESH, WATCH re-acquisition, and scheduled MAPs.
+//! Interactive MAP requests are not restricted.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// How far ahead [`CrawlCalendar::next_allowed`] searches for an open slot.
+const NEXT_ALLOWED_HORIZON_DAYS: i64 = 8;
+
+/// Kind of automated crawl being scheduled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrawlKind {
+    /// REFRESH of existing nodes.
+    Refresh,
+    /// WATCH re-acquisition of monitored nodes.
+    WatchReacquire,
+    /// A MAP triggered by a schedule rather than a user.
+    ScheduledMap,
+}
+
+/// A daily time window, optionally limited to certain weekdays.
+///
+/// `end` earlier than `start` means the window wraps past midnight.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeWindow {
+    /// Days this window applies to (empty = every day).
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    /// Local start time (inclusive).
+    pub start: NaiveTime,
+    /// Local end time (exclusive).
+    pub end: NaiveTime,
+}
+
+impl TimeWindow {
+    /// Whether a local weekday/time falls inside this window.
+    fn contains(&self, day: Weekday, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.applies_to(day) && time >= self.start && time < self.end
+        } else {
+            // Wrapping window: the late part belongs to `day`, the early
+            // part to the day the window started on.
+            (self.applies_to(day) && time >= self.start)
+                || (self.applies_to(day.pred()) && time < self.end)
+        }
+    }
+
+    fn applies_to(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+}
+
+/// A period during which crawling is never allowed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Blackout {
+    /// Recurring local window (e.g. a retailer's daily peak hours).
+    Weekly {
+        #[serde(flatten)]
+        window: TimeWindow,
+        /// Optional human-readable reason.
+        #[serde(default)]
+        reason: Option<String>,
+    },
+    /// One-off absolute range (e.g. a product launch).
+    Range {
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        #[serde(default)]
+        reason: Option<String>,
+    },
+}
+
+/// Crawl calendar for a single domain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrawlCalendar {
+    /// Domain this calendar applies to.
+    pub domain: String,
+    /// IANA timezone name used to interpret local windows (default "UTC").
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    /// Windows when crawling is allowed (empty = any time).
+    #[serde(default)]
+    pub allowed_hours: Vec<TimeWindow>,
+    /// Windows when crawling is never allowed. Blackouts win over allowed hours.
+    #[serde(default)]
+    pub blackouts: Vec<Blackout>,
+    /// Crawl kinds this calendar restricts (empty = all automated kinds).
+    #[serde(default)]
+    pub applies_to: Vec<CrawlKind>,
+}
+
+fn default_timezone() -> String {
+    "UTC".to_string()
+}
+
+/// Why a crawl was refused.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleBlock {
+    /// Domain that was blocked.
+    pub domain: String,
+    /// Explanation suitable for logs and error messages.
+    pub reason: String,
+    /// Next time crawling is allowed, if one was found within the horizon.
+    pub next_allowed: Option<DateTime<Utc>>,
+}
+
+impl std::fmt::Display for ScheduleBlock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "crawling {} not allowed: {}", self.domain, self.reason)?;
+        if let Some(next) = self.next_allowed {
+            write!(f, " (next window {})", next.to_rfc3339())?;
+        }
+        Ok(())
+    }
+}
+
+impl CrawlCalendar {
+    /// Create an unrestricted calendar for a domain.
+    pub fn new(domain: &str) -> Self {
+        Self {
+            domain: domain.to_string(),
+            timezone: default_timezone(),
+            allowed_hours: Vec::new(),
+            blackouts: Vec::new(),
+            applies_to: Vec::new(),
+        }
+    }
+
+    /// Resolve the calendar's timezone.
+    pub fn tz(&self) -> Result<Tz> {
+        self.timezone
+            .parse::<Tz>()
+            .map_err(|e| anyhow::anyhow!("invalid timezone '{}': {e}", self.timezone))
+    }
+
+    /// Whether this calendar restricts the given crawl kind.
+    pub fn restricts(&self, kind: CrawlKind) -> bool {
+        self.applies_to.is_empty() || self.applies_to.contains(&kind)
+    }
+
+    /// Check whether crawling is allowed at `now`. Returns the blocking reason if not.
+    pub fn check(&self, now: DateTime<Utc>) -> Option<String> {
+        for blackout in &self.blackouts {
+            match blackout {
+                Blackout::Range { start, end, reason } => {
+                    if now >= *start && now < *end {
+                        return Some(
+                            reason
+                                .clone()
+                                .unwrap_or_else(|| format!("blackout until {}", end.to_rfc3339())),
+                        );
+                    }
+                }
+                Blackout::Weekly { window, reason } => {
+                    let (day, time) = self.local(now);
+                    if window.contains(day, time) {
+                        return Some(reason.clone().unwrap_or_else(|| {
+                            format!(
+                                "weekly blackout {}-{} {}",
+                                window.start.format("%H:%M"),
+                                window.end.format("%H:%M"),
+                                self.timezone
+                            )
+                        }));
+                    }
+                }
+            }
+        }
+
+        if !self.allowed_hours.is_empty() {
+            let (day, time) = self.local(now);
+            if !self.allowed_hours.iter().any(|w| w.contains(day, time)) {
+                return Some(format!("outside allowed crawl hours ({})", self.timezone));
+            }
+        }
+
+        None
+    }
+
+    /// Whether crawling is allowed at `now`.
+    pub fn is_allowed(&self, now: DateTime<Utc>) -> bool {
+        self.check(now).is_none()
+    }
+
+    /// Find the next minute at or after `now` when crawling is allowed.
+    pub fn next_allowed(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut t = now;
+        let horizon = now + chrono::Duration::days(NEXT_ALLOWED_HORIZON_DAYS);
+        while t < horizon {
+            if self.is_allowed(t) {
+                return Some(t);
+            }
+            t += chrono::Duration::minutes(1);
+        }
+        None
+    }
+
+    /// Convert a UTC instant to local weekday and time.
+    fn local(&self, now: DateTime<Utc>) -> (Weekday, NaiveTime) {
+        let tz = self.tz().unwrap_or(Tz::UTC);
+        let local = tz.from_utc_datetime(&now.naive_utc());
+        (local.weekday(), local.time())
+    }
+}
+
+/// Per-domain crawl calendars.
+#[derive(Debug, Clone, Default)]
+pub struct CrawlScheduler {
+    calendars: HashMap<String, CrawlCalendar>,
+}
+
+impl CrawlScheduler {
+    /// Create a scheduler with no restrictions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load calendars from a JSON file (a list of calendars). Missing file = no restrictions.
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut scheduler = Self::new();
+        if !path.exists() {
+            return Ok(scheduler);
+        }
+        let data = std::fs::read_to_string(path)?;
+        let calendars: Vec<CrawlCalendar> = serde_json::from_str(&data)
+            .with_context(|| format!("parsing crawl calendars: {}", path.display()))?;
+        for cal in calendars {
+            scheduler.set_calendar(cal)?;
+        }
+        Ok(scheduler)
+    }
+
+    /// Load calendars from the default location (~/.cortex/crawl_calendars.json).
+    pub fn load_default() -> Result<Self> {
+        Self::load(&default_path())
+    }
+
+    /// Save calendars as a JSON list.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut calendars: Vec<&CrawlCalendar> = self.calendars.values().collect();
+        calendars.sort_by(|a, b| a.domain.cmp(&b.domain));
+        std::fs::write(path, serde_json::to_string_pretty(&calendars)?)?;
+        Ok(())
+    }
+
+    /// Add or replace a domain's calendar. Rejects unknown timezones.
+    pub fn set_calendar(&mut self, calendar: CrawlCalendar) -> Result<()> {
+        calendar.tz()?;
+        self.calendars.insert(calendar.domain.clone(), calendar);
+        Ok(())
+    }
+
+    /// Remove a domain's calendar.
+    pub fn remove_calendar(&mut self, domain: &str) -> bool {
+        self.calendars.remove(domain).is_some()
+    }
+
+    /// Number of domains with a calendar.
+    pub fn len(&self) -> usize {
+        self.calendars.len()
+    }
+
+    /// Whether no domain has a calendar.
+    pub fn is_empty(&self) -> bool {
+        self.calendars.is_empty()
+    }
+
+    /// Get a domain's calendar.
+    pub fn calendar(&self, domain: &str) -> Option<&CrawlCalendar> {
+        self.calendars.get(domain)
+    }
+
+    /// Check whether an automated crawl of `domain` may run at `now`.
+    pub fn check(
+        &self,
+        domain: &str,
+        kind: CrawlKind,
+        now: DateTime<Utc>,
+    ) -> std::result::Result<(), ScheduleBlock> {
+        let Some(cal) = self.calendars.get(domain) else {
+            return Ok(());
+        };
+        if !cal.restricts(kind) {
+            return Ok(());
+        }
+        match cal.check(now) {
+            None => Ok(()),
+            Some(reason) => Err(ScheduleBlock {
+                domain: domain.to_string(),
+                reason,
+                next_allowed: cal.next_allowed(now),
+            }),
+        }
+    }
+}
+
+/// Default calendar file location.
+pub fn default_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join(".cortex")
+        .join("crawl_calendars.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn hm(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn test_allowed_hours_in_domain_timezone() {
+        let mut cal = CrawlCalendar::new("shop.com");
+        cal.timezone = "America/New_York".to_string();
+        cal.allowed_hours.push(TimeWindow {
+            days: vec![],
+            start: hm(1, 0),
+            end: hm(6, 0),
+        });
+
+        // 07:00 UTC in January = 02:00 New York
+        assert!(cal.is_allowed(at("2026-01-15T07:00:00Z")));
+        // 12:00 UTC = 07:00 New York
+        assert!(!cal.is_allowed(at("2026-01-15T12:00:00Z")));
+        // Next window opens at 01:00 New York = 06:00 UTC next day
+        assert_eq!(
+            cal.next_allowed(at("2026-01-15T12:00:00Z")),
+            Some(at("2026-01-16T06:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn test_weekly_blackout_wins_and_wraps_midnight() {
+        let mut cal = CrawlCalendar::new("shop.com");
+        cal.blackouts.push(Blackout::Weekly {
+            window: TimeWindow {
+                days: vec![Weekday::Fri],
+                start: hm(22, 0),
+                end: hm(2, 0),
+            },
+            reason: Some("weekend sale".to_string()),
+        });
+
+        // 2026-01-16 is a Friday
+        assert_eq!(
+            cal.check(at("2026-01-16T23:00:00Z")).as_deref(),
+            Some("weekend sale")
+        );
+        assert!(!cal.is_allowed(at("2026-01-17T01:30:00Z")));
+        assert!(cal.is_allowed(at("2026-01-17T02:00:00Z")));
+        // Thursday night is not affected
+        assert!(cal.is_allowed(at("2026-01-15T23:00:00Z")));
+    }
+
+    #[test]
+    fn test_range_blackout() {
+        let mut cal = CrawlCalendar::new("shop.com");
+        cal.blackouts.push(Blackout::Range {
+            start: at("2026-11-27T00:00:00Z"),
+            end: at("2026-11-30T00:00:00Z"),
+            reason: None,
+        });
+        assert!(!cal.is_allowed(at("2026-11-28T12:00:00Z")));
+        assert!(cal.is_allowed(at("2026-11-30T00:00:00Z")));
+    }
+
+    #[test]
+    fn test_scheduler_kinds_and_unknown_domains() {
+        let mut cal = CrawlCalendar::new("shop.com");
+        cal.applies_to = vec![CrawlKind::Refresh];
+        cal.allowed_hours.push(TimeWindow {
+            days: vec![],
+            start: hm(0, 0),
+            end: hm(1, 0),
+        });
+
+        let mut scheduler = CrawlScheduler::new();
+        scheduler.set_calendar(cal).unwrap();
+
+        let noon = at("2026-01-15T12:00:00Z");
+        let block = scheduler
+            .check("shop.com", CrawlKind::Refresh, noon)
+            .unwrap_err();
+        assert!(block.to_string().contains("outside allowed crawl hours"));
+        assert_eq!(block.next_allowed, Some(at("2026-01-16T00:00:00Z")));
+        assert!(scheduler
+            .check("shop.com", CrawlKind::ScheduledMap, noon)
+            .is_ok());
+        assert!(scheduler
+            .check("other.com", CrawlKind::Refresh, noon)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_scheduler_rejects_bad_timezone_and_round_trips() {
+        let mut scheduler = CrawlScheduler::new();
+        let mut bad = CrawlCalendar::new("a.com");
+        bad.timezone = "Mars/Olympus".to_string();
+        assert!(scheduler.set_calendar(bad).is_err());
+
+        let mut cal = CrawlCalendar::new("a.com");
+        cal.timezone = "Europe/Berlin".to_string();
+        cal.blackouts.push(Blackout::Weekly {
+            window: TimeWindow {
+                days: vec![Weekday::Sat, Weekday::Sun],
+                start: hm(10, 0),
+                end: hm(18, 0),
+            },
+            reason: None,
+        });
+        scheduler.set_calendar(cal).unwrap();
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("calendars.json");
+        scheduler.save(&path).unwrap();
+        let loaded = CrawlScheduler::load(&path).unwrap();
+        let cal = loaded.calendar("a.com").unwrap();
+        assert_eq!(cal.timezone, "Europe/Berlin");
+        assert_eq!(cal.blackouts.len(), 1);
+    }
+}
@@ -0,0 +1,264 @@
+//! Visual change watches.
+//!
+//! Feature deltas miss changes that only show on screen: a broken layout, a
+//! new banner, a restyled buy button. A visual watch
+//! (`cortex watch add shop.com --node 12 --visual`) screenshots its page with
+//! the renderer every rule interval, appends the capture to
+//! `~/.cortex/vision/watches/<rule>.avis`, and diffs it against the previous
+//! capture with agentic-vision's `compute_diff`. The rule fires when the
+//! share of changed pixels passes its threshold, or when a changed area
+//! overlaps one of its regions.
+//!
+//! Visual watches need the `vision` build feature and a daemon with a
+//! browser.
+
+use crate::collective::registry::LocalRegistry;
+use crate::renderer::{identify, Renderer};
+use crate::server::SharedState;
+use crate::temporal::scheduler::CrawlKind;
+use crate::temporal::watch::{Region, WatchCondition, WatchRule};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Share of changed pixels that fires a rule when none is given.
+pub const DEFAULT_THRESHOLD: f32 = 0.05;
+
+/// Seconds between screenshots when no interval is given.
+pub const DEFAULT_INTERVAL_SECS: u64 = 3600;
+
+/// Captures kept per rule; older ones are dropped.
+const MAX_CAPTURES: usize = 100;
+
+/// How often the daemon looks for rules due a screenshot.
+const TICK_SECS: u64 = 60;
+
+/// Navigation timeout for the watched page.
+const NAVIGATE_TIMEOUT_MS: u64 = 30_000;
+
+/// The difference between a page's last two screenshots.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VisualChange {
+    /// Capture ids in the rule's `.avis` file.
+    pub before_id: u64,
+    pub after_id: u64,
+    /// Share of pixels that changed, in `[0, 1]`.
+    pub pixel_diff_ratio: f32,
+    /// Areas that changed, in fractions of the screenshot.
+    pub changed_regions: Vec<Region>,
+}
+
+/// Default directory of visual watch captures (~/.cortex/vision/watches).
+pub fn default_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".cortex")
+        .join("vision")
+        .join("watches")
+}
+
+/// The captures file of rule `rule_id`.
+pub fn capture_path(dir: &Path, rule_id: &str) -> PathBuf {
+    dir.join(format!("{rule_id}.avis"))
+}
+
+/// Append a PNG screenshot of `url` to the captures file at `path` and diff
+/// it against the previous capture. Returns `None` for the first capture.
+/// Decodes and compares images, so call it off the async runtime.
+#[cfg(feature = "vision")]
+pub fn record(path: &Path, url: &str, png: &[u8]) -> Result<Option<VisualChange>> {
+    use agentic_vision::{
+        AvisReader, AvisWriter, CaptureSource, ObservationMeta, VisualMemoryStore,
+        VisualObservation,
+    };
+    use anyhow::{anyhow, Context};
+    use base64::Engine as _;
+
+    let decode = |bytes: &[u8], mime: &str| {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+        agentic_vision::capture_from_base64(&encoded, mime).map(|(image, _)| image)
+    };
+    let image = decode(png, "image/png").map_err(|e| anyhow!("decoding screenshot: {e}"))?;
+    let thumbnail = agentic_vision::generate_thumbnail(&image);
+    // Compare thumbnails with thumbnails, so JPEG noise affects both sides.
+    let current = decode(&thumbnail, "image/jpeg").map_err(|e| anyhow!("{e}"))?;
+
+    let mut store = if path.exists() {
+        AvisReader::read_from_file(path).with_context(|| format!("reading {}", path.display()))?
+    } else {
+        VisualMemoryStore::new(agentic_vision::EMBEDDING_DIM)
+    };
+    let previous = store.observations.last().cloned();
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let after_id = store.add(VisualObservation {
+        id: 0,
+        timestamp,
+        session_id: 0,
+        source: CaptureSource::Screenshot { region: None },
+        // Diffs need no embedding; readers skip empty ones.
+        embedding: Vec::new(),
+        thumbnail,
+        metadata: ObservationMeta {
+            width: current.width(),
+            height: current.height(),
+            original_width: image.width(),
+            original_height: image.height(),
+            labels: Vec::new(),
+            description: Some(url.to_string()),
+            caption: None,
+        },
+        memory_link: None,
+        fingerprint: Vec::new(),
+        elements: Vec::new(),
+        ocr: None,
+        provenance: None,
+    });
+    let excess = store.observations.len().saturating_sub(MAX_CAPTURES);
+    store.observations.drain(..excess);
+    AvisWriter::write_to_file(&store, path)
+        .with_context(|| format!("writing {}", path.display()))?;
+
+    let Some(previous) = previous else {
+        return Ok(None);
+    };
+    let before = decode(&previous.thumbnail, "image/jpeg").map_err(|e| anyhow!("{e}"))?;
+    let diff = agentic_vision::compute_diff(previous.id, after_id, &before, &current)
+        .map_err(|e| anyhow!("comparing screenshots: {e}"))?;
+    // compute_diff scales both images to their smallest common size.
+    let width = before.width().min(current.width()).max(1) as f32;
+    let height = before.height().min(current.height()).max(1) as f32;
+    Ok(Some(VisualChange {
+        before_id: diff.before_id,
+        after_id: diff.after_id,
+        pixel_diff_ratio: diff.pixel_diff_ratio,
+        changed_regions: diff
+            .changed_regions
+            .iter()
+            .map(|r| Region {
+                x: r.x as f32 / width,
+                y: r.y as f32 / height,
+                w: r.w as f32 / width,
+                h: r.h as f32 / height,
+            })
+            .collect(),
+    }))
+}
+
+/// Without the `vision` feature there is nothing to compare with.
+#[cfg(not(feature = "vision"))]
+pub fn record(_path: &Path, _url: &str, _png: &[u8]) -> Result<Option<VisualChange>> {
+    anyhow::bail!("visual watches need a build with the `vision` feature")
+}
+
+/// Screenshot the page of every visual watch rule when its interval is up,
+/// and emit a `WatchFired` event when one fires. Rules are re-read from the
+/// registry each round; captures respect the domain's crawl calendar.
+pub fn spawn(state: Arc<SharedState>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(TICK_SECS));
+        let mut last_capture: HashMap<String, Instant> = HashMap::new();
+        loop {
+            ticker.tick().await;
+            let Some(renderer) = state.renderer.clone() else {
+                continue;
+            };
+            for rule in due_rules(&state, &last_capture) {
+                let Some(url) = rule.node_url.clone() else {
+                    continue;
+                };
+                let blocked = state.scheduler.read().await.check(
+                    &rule.domain,
+                    CrawlKind::WatchReacquire,
+                    chrono::Utc::now(),
+                );
+                if let Err(block) = blocked {
+                    info!("visual watch {} deferred: {block}", rule.id);
+                    continue;
+                }
+                last_capture.insert(rule.id.clone(), Instant::now());
+                if let Err(e) = check_rule(&state, renderer.as_ref(), &rule, &url).await {
+                    warn!("visual watch {} on {url} failed: {e}", rule.id);
+                }
+            }
+        }
+    })
+}
+
+/// Active visual rules whose interval has passed since their last capture.
+fn due_rules(state: &SharedState, last_capture: &HashMap<String, Instant>) -> Vec<WatchRule> {
+    let Ok(mut watches) = state.watches.lock() else {
+        return Vec::new();
+    };
+    match LocalRegistry::open_default() {
+        Ok(registry) => {
+            if let Err(e) = watches.reload(&registry) {
+                warn!("failed to load watch rules: {e}");
+            }
+        }
+        Err(e) => warn!("failed to open registry: {e}"),
+    }
+    watches
+        .list_rules()
+        .into_iter()
+        .filter(|rule| rule.active)
+        .filter(|rule| match &rule.condition {
+            WatchCondition::Visual(visual) => last_capture
+                .get(&rule.id)
+                .is_none_or(|t| t.elapsed() >= Duration::from_secs(visual.interval_secs)),
+            _ => false,
+        })
+        .cloned()
+        .collect()
+}
+
+/// Screenshot `url`, compare it with the rule's previous capture, and emit
+/// an alert if the rule fires.
+async fn check_rule(
+    state: &SharedState,
+    renderer: &dyn Renderer,
+    rule: &WatchRule,
+    url: &str,
+) -> Result<()> {
+    let mut context = renderer.new_context().await?;
+    let screenshot = match identify(context.as_mut(), &state.profiles, url).await {
+        Ok(()) => match context.navigate(url, NAVIGATE_TIMEOUT_MS).await {
+            Ok(_) => context.screenshot().await,
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(e),
+    };
+    let _ = context.close().await;
+    let png = screenshot?;
+
+    let path = capture_path(&default_dir(), &rule.id);
+    let page = url.to_string();
+    let Some(change) = tokio::task::spawn_blocking(move || record(&path, &page, &png)).await??
+    else {
+        return Ok(());
+    };
+
+    let alert = {
+        let Ok(mut watches) = state.watches.lock() else {
+            return Ok(());
+        };
+        let Some(alert) = watches.evaluate_visual(&rule.id, url, &change) else {
+            return Ok(());
+        };
+        // Persist last_triggered, as re-map alerts do.
+        if let Err(e) = LocalRegistry::open_default().and_then(|registry| watches.save(&registry)) {
+            warn!("failed to save watch rules: {e}");
+        }
+        alert
+    };
+    info!("visual watch {} fired: {}", rule.id, alert.message);
+    state.event_bus.emit(alert.to_event());
+    Ok(())
+}
@@ -1,6 +1,23 @@
 //! Watch system — alert rules that monitor temporal data.
+//!
+//! Simple rules watch a single feature dimension against a threshold.
+//! Compound rules are written in a small DSL parsed by the WQL parser:
+//!
+//! ```text
+//! price < 300 AND availability = in_stock FOR 2 consecutive checks HYSTERESIS 10 COOLDOWN 6h
+//! ```
+//!
+//! Visual rules compare screenshots of a page instead of its features; see
+//! [`crate::temporal::visual`].
 
+use crate::collective::registry::LocalRegistry;
+use crate::compiler::schema::field_name_to_dim;
+use crate::events::CortexEvent;
+use crate::map::types::*;
 use crate::temporal::query::TemporalQuery;
+use crate::temporal::visual::VisualChange;
+use crate::wql::parser::{self, ComparisonOp, WatchExpr, WhereExpr, WqlValue};
+use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -14,6 +31,9 @@ pub struct WatchRule {
     pub domain: String,
     /// Model type to watch (e.g., "Product").
     pub model_type: Option<String>,
+    /// Page to watch. Every page of the domain when unset.
+    #[serde(default)]
+    pub node_url: Option<String>,
     /// Feature dimension to monitor.
     pub feature_dim: u8,
     /// Condition that triggers the alert.
@@ -41,6 +61,91 @@ pub enum WatchCondition {
     Available,
     /// A new node of the watched type appears.
     NewInstance,
+    /// Compound condition over several named features (see [`WatchCondition::parse`]).
+    Compound(WatchExpr),
+    /// The page looks different from its previous screenshot.
+    Visual(VisualCondition),
+}
+
+impl WatchCondition {
+    /// Parse a compound condition from the watch rule DSL.
+    pub fn parse(dsl: &str) -> Result<Self> {
+        let expr = parser::parse_watch_expr(dsl)?;
+        validate_fields(&expr.condition)?;
+        Ok(WatchCondition::Compound(expr))
+    }
+}
+
+/// When a page's screenshots differ enough to alert.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VisualCondition {
+    /// Share of pixels that must change, in `[0, 1]`.
+    pub threshold: f32,
+    /// Regions where any change fires, whatever its size.
+    #[serde(default)]
+    pub regions: Vec<Region>,
+    /// Seconds between screenshots.
+    pub interval_secs: u64,
+}
+
+impl VisualCondition {
+    /// The alert message for a change between two screenshots, if it meets
+    /// the condition.
+    pub fn check(&self, change: &VisualChange) -> Option<String> {
+        if change.pixel_diff_ratio > self.threshold {
+            return Some(format!(
+                "{:.1}% of the page changed (threshold: {:.1}%)",
+                change.pixel_diff_ratio * 100.0,
+                self.threshold * 100.0
+            ));
+        }
+        let region = self
+            .regions
+            .iter()
+            .find(|r| change.changed_regions.iter().any(|c| r.intersects(c)))?;
+        Some(format!("Region {region} changed"))
+    }
+}
+
+/// A rectangle in fractions of a screenshot's width and height.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Region {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+impl Region {
+    /// Parse `x,y,w,h`, each a fraction between 0 and 1.
+    pub fn parse(s: &str) -> Result<Self> {
+        let parts: Vec<f32> = s
+            .split(',')
+            .map(|p| p.trim().parse::<f32>())
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|_| anyhow::anyhow!("invalid region '{s}' (expected x,y,w,h)"))?;
+        let [x, y, w, h] = parts[..] else {
+            anyhow::bail!("invalid region '{s}' (expected x,y,w,h)");
+        };
+        if parts.iter().any(|v| !(0.0..=1.0).contains(v)) || w == 0.0 || h == 0.0 {
+            anyhow::bail!("invalid region '{s}' (values are fractions of the page, 0 to 1)");
+        }
+        Ok(Self { x, y, w, h })
+    }
+
+    /// Whether the two regions overlap.
+    pub fn intersects(&self, other: &Region) -> bool {
+        self.x < other.x + other.w
+            && other.x < self.x + self.w
+            && self.y < other.y + other.h
+            && other.y < self.y + self.h
+    }
+}
+
+impl std::fmt::Display for Region {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{},{},{}", self.x, self.y, self.w, self.h)
+    }
 }
 
 /// Notification target.
@@ -71,12 +176,35 @@ pub struct WatchAlert {
     pub timestamp: DateTime<Utc>,
 }
 
+impl WatchAlert {
+    /// Convert this alert into an event-bus event.
+    pub fn to_event(&self) -> CortexEvent {
+        CortexEvent::WatchFired {
+            domain: self.domain.clone(),
+            rule_id: self.rule_id.clone(),
+            message: self.message.clone(),
+            current_value: self.current_value,
+        }
+    }
+}
+
+/// Per-rule, per-node evaluation state for compound rules.
+#[derive(Debug, Clone, Default)]
+struct CompoundState {
+    /// Number of consecutive checks the condition has held.
+    streak: u32,
+    /// Whether the rule has fired and is waiting to re-arm.
+    latched: bool,
+}
+
 /// Manages active watch rules.
 pub struct WatchManager {
     /// Active rules.
     rules: HashMap<String, WatchRule>,
     /// Alerts generated.
     alerts: Vec<WatchAlert>,
+    /// Compound rule state keyed by "rule_id|node_url".
+    compound_state: HashMap<String, CompoundState>,
 }
 
 impl WatchManager {
@@ -85,7 +213,24 @@ impl WatchManager {
         Self {
             rules: HashMap::new(),
             alerts: Vec::new(),
+            compound_state: HashMap::new(),
+        }
+    }
+
+    /// Load persisted rules from the registry.
+    pub fn load(registry: &LocalRegistry) -> Result<Self> {
+        let mut wm = Self::new();
+        for rule in registry.load_watch_rules()? {
+            wm.add_rule(rule);
         }
+        Ok(wm)
+    }
+
+    /// Persist all rules to the registry.
+    pub fn save(&self, registry: &LocalRegistry) -> Result<()> {
+        let mut rules: Vec<WatchRule> = self.rules.values().cloned().collect();
+        rules.sort_by(|a, b| a.id.cmp(&b.id));
+        registry.save_watch_rules(&rules)
     }
 
     /// Add a new watch rule.
@@ -97,6 +242,8 @@ impl WatchManager {
 
     /// Remove a watch rule.
     pub fn remove_rule(&mut self, id: &str) -> bool {
+        let prefix = format!("{id}|");
+        self.compound_state.retain(|k, _| !k.starts_with(&prefix));
         self.rules.remove(id).is_some()
     }
 
@@ -122,85 +269,234 @@ impl WatchManager {
                 continue;
             }
 
-            let alert = match &rule.condition {
-                WatchCondition::ValueAbove(threshold) => {
-                    if current_value > *threshold && previous_value <= *threshold {
-                        Some(WatchAlert {
-                            rule_id: rule.id.clone(),
-                            domain: domain.to_string(),
-                            message: format!("Value rose above {threshold}: {current_value}"),
-                            current_value,
-                            previous_value: Some(previous_value),
-                            timestamp: Utc::now(),
-                        })
-                    } else {
-                        None
+            let alert =
+                check_change(&rule.condition, current_value, previous_value).map(|message| {
+                    WatchAlert {
+                        rule_id: rule.id.clone(),
+                        domain: domain.to_string(),
+                        message,
+                        current_value,
+                        previous_value: Some(previous_value),
+                        timestamp: Utc::now(),
                     }
-                }
-                WatchCondition::ValueBelow(threshold) => {
-                    if current_value < *threshold && previous_value >= *threshold {
-                        Some(WatchAlert {
+                });
+
+            if let Some(alert) = alert {
+                rule.last_triggered = Some(Utc::now());
+                triggered.push(alert);
+            }
+        }
+
+        self.alerts.extend(triggered.clone());
+        triggered
+    }
+
+    /// Evaluate every rule on `domain` against a re-mapped site.
+    ///
+    /// Threshold and change rules compare each watched page's feature in
+    /// `old` and `new`; pages missing from `old` are skipped. New-instance
+    /// rules fire once for the pages `new` adds. Compound rules count this as
+    /// one check of every watched page.
+    pub fn evaluate_maps(&mut self, domain: &str, old: &SiteMap, new: &SiteMap) -> Vec<WatchAlert> {
+        let old_index: HashMap<&str, usize> = old
+            .urls
+            .iter()
+            .enumerate()
+            .map(|(i, url)| (url.as_str(), i))
+            .collect();
+        let now = Utc::now();
+        let mut triggered = Vec::new();
+        let mut has_compound = false;
+
+        for rule in self.rules.values_mut() {
+            if rule.domain != domain || !rule.active {
+                continue;
+            }
+            let watched = |url: &str| rule.node_url.as_deref().is_none_or(|u| u == url);
+            let mut alerts = Vec::new();
+            match &rule.condition {
+                WatchCondition::Compound(_) => has_compound = true,
+                // Checked from screenshots, not maps.
+                WatchCondition::Visual(_) => {}
+                WatchCondition::NewInstance => {
+                    let added = new
+                        .urls
+                        .iter()
+                        .filter(|url| watched(url) && !old_index.contains_key(url.as_str()))
+                        .count();
+                    if added > 0 {
+                        alerts.push(WatchAlert {
                             rule_id: rule.id.clone(),
                             domain: domain.to_string(),
-                            message: format!("Value dropped below {threshold}: {current_value}"),
-                            current_value,
-                            previous_value: Some(previous_value),
-                            timestamp: Utc::now(),
-                        })
-                    } else {
-                        None
+                            message: format!("{added} new page(s)"),
+                            current_value: added as f32,
+                            previous_value: None,
+                            timestamp: now,
+                        });
                     }
                 }
-                WatchCondition::ChangeByPercent(pct) => {
-                    if previous_value != 0.0 {
-                        let change = ((current_value - previous_value) / previous_value).abs();
-                        if change > *pct {
-                            Some(WatchAlert {
+                condition => {
+                    let dim = rule.feature_dim as usize;
+                    for (i, url) in new.urls.iter().enumerate() {
+                        let Some(&j) = old_index.get(url.as_str()).filter(|_| watched(url)) else {
+                            continue;
+                        };
+                        let (Some(current), Some(previous)) = (
+                            new.features.get(i).map(|f| f[dim]),
+                            old.features.get(j).map(|f| f[dim]),
+                        ) else {
+                            continue;
+                        };
+                        if let Some(message) = check_change(condition, current, previous) {
+                            alerts.push(WatchAlert {
                                 rule_id: rule.id.clone(),
                                 domain: domain.to_string(),
-                                message: format!(
-                                    "Value changed by {:.1}% (threshold: {:.1}%)",
-                                    change * 100.0,
-                                    pct * 100.0
-                                ),
-                                current_value,
-                                previous_value: Some(previous_value),
-                                timestamp: Utc::now(),
-                            })
-                        } else {
-                            None
+                                message: format!("{message} on {url}"),
+                                current_value: current,
+                                previous_value: Some(previous),
+                                timestamp: now,
+                            });
                         }
-                    } else {
-                        None
-                    }
-                }
-                WatchCondition::Available => {
-                    if previous_value <= 0.0 && current_value > 0.0 {
-                        Some(WatchAlert {
-                            rule_id: rule.id.clone(),
-                            domain: domain.to_string(),
-                            message: "Item became available".to_string(),
-                            current_value,
-                            previous_value: Some(previous_value),
-                            timestamp: Utc::now(),
-                        })
-                    } else {
-                        None
                     }
                 }
-                WatchCondition::NewInstance => None, // Handled separately
+            }
+            if !alerts.is_empty() {
+                rule.last_triggered = Some(now);
+                triggered.extend(alerts);
+            }
+        }
+        self.alerts.extend(triggered.clone());
+
+        if has_compound {
+            for (url, features) in new.urls.iter().zip(&new.features) {
+                triggered.extend(self.evaluate_node(domain, url, features));
+            }
+        }
+        triggered
+    }
+
+    /// Replace the rules with those persisted in the registry, keeping the
+    /// evaluation state of rules that still exist.
+    pub fn reload(&mut self, registry: &LocalRegistry) -> Result<()> {
+        self.rules = registry
+            .load_watch_rules()?
+            .into_iter()
+            .map(|rule| (rule.id.clone(), rule))
+            .collect();
+        let rules = &self.rules;
+        self.compound_state.retain(|key, _| {
+            key.split_once('|')
+                .is_some_and(|(id, _)| rules.contains_key(id))
+        });
+        Ok(())
+    }
+
+    /// Evaluate compound rules against a node's full feature vector.
+    ///
+    /// Call once per check of a node. A rule fires when its condition has held
+    /// for the required number of consecutive checks, it is armed, and its
+    /// cool-down has elapsed. After firing it stays latched until the
+    /// condition is false even with thresholds relaxed by the hysteresis band.
+    pub fn evaluate_node(
+        &mut self,
+        domain: &str,
+        node_url: &str,
+        features: &[f32; FEATURE_DIM],
+    ) -> Vec<WatchAlert> {
+        let now = Utc::now();
+        let mut triggered = Vec::new();
+
+        for rule in self.rules.values_mut() {
+            if rule.domain != domain
+                || !rule.active
+                || rule.node_url.as_deref().is_some_and(|u| u != node_url)
+            {
+                continue;
+            }
+            let expr = match &rule.condition {
+                WatchCondition::Compound(expr) => expr,
+                _ => continue,
             };
 
-            if let Some(alert) = alert {
-                rule.last_triggered = Some(Utc::now());
-                triggered.push(alert);
+            let state = self
+                .compound_state
+                .entry(format!("{}|{node_url}", rule.id))
+                .or_default();
+
+            if eval_expr(&expr.condition, features, 0.0) {
+                state.streak = state.streak.saturating_add(1);
+            } else {
+                state.streak = 0;
             }
+
+            if state.latched {
+                if !eval_expr(&expr.condition, features, expr.hysteresis) {
+                    state.latched = false;
+                }
+                continue;
+            }
+
+            if state.streak < expr.consecutive.max(1) {
+                continue;
+            }
+
+            let cooling_down = rule
+                .last_triggered
+                .is_some_and(|last| (now - last).num_seconds() < expr.cooldown_secs as i64);
+            if cooling_down {
+                continue;
+            }
+
+            state.latched = true;
+            rule.last_triggered = Some(now);
+            let current_value = first_field(&expr.condition)
+                .and_then(field_to_feature_dim)
+                .map(|dim| features[dim])
+                .unwrap_or(0.0);
+            triggered.push(WatchAlert {
+                rule_id: rule.id.clone(),
+                domain: domain.to_string(),
+                message: format!(
+                    "Condition held for {} consecutive check(s) on {node_url}",
+                    state.streak
+                ),
+                current_value,
+                previous_value: None,
+                timestamp: now,
+            });
         }
 
         self.alerts.extend(triggered.clone());
         triggered
     }
 
+    /// Evaluate a visual rule against the change between its last two
+    /// screenshots of `node_url`.
+    pub fn evaluate_visual(
+        &mut self,
+        rule_id: &str,
+        node_url: &str,
+        change: &VisualChange,
+    ) -> Option<WatchAlert> {
+        let rule = self.rules.get_mut(rule_id).filter(|r| r.active)?;
+        let WatchCondition::Visual(condition) = &rule.condition else {
+            return None;
+        };
+        let message = condition.check(change)?;
+        let now = Utc::now();
+        rule.last_triggered = Some(now);
+        let alert = WatchAlert {
+            rule_id: rule.id.clone(),
+            domain: rule.domain.clone(),
+            message: format!("{message} on {node_url}"),
+            current_value: change.pixel_diff_ratio,
+            previous_value: None,
+            timestamp: now,
+        };
+        self.alerts.push(alert.clone());
+        Some(alert)
+    }
+
     /// Get recent alerts.
     pub fn recent_alerts(&self, limit: usize) -> &[WatchAlert] {
         let start = if self.alerts.len() > limit {
@@ -218,6 +514,129 @@ impl Default for WatchManager {
     }
 }
 
+/// The alert message for a value change that meets a simple condition.
+fn check_change(condition: &WatchCondition, current: f32, previous: f32) -> Option<String> {
+    match condition {
+        WatchCondition::ValueAbove(threshold) => (current > *threshold && previous <= *threshold)
+            .then(|| format!("Value rose above {threshold}: {current}")),
+        WatchCondition::ValueBelow(threshold) => (current < *threshold && previous >= *threshold)
+            .then(|| format!("Value dropped below {threshold}: {current}")),
+        WatchCondition::ChangeByPercent(pct) => {
+            if previous == 0.0 {
+                return None;
+            }
+            let change = ((current - previous) / previous).abs();
+            (change > *pct).then(|| {
+                format!(
+                    "Value changed by {:.1}% (threshold: {:.1}%)",
+                    change * 100.0,
+                    pct * 100.0
+                )
+            })
+        }
+        WatchCondition::Available => {
+            (previous <= 0.0 && current > 0.0).then(|| "Item became available".to_string())
+        }
+        // Handled separately
+        WatchCondition::NewInstance | WatchCondition::Compound(_) | WatchCondition::Visual(_) => {
+            None
+        }
+    }
+}
+
+/// Map a watch field name, as in [`field_name_to_dim`], or a dimension
+/// number to its feature dimension.
+pub fn field_to_feature_dim(name: &str) -> Option<usize> {
+    field_name_to_dim(name).or_else(|| name.parse::<usize>().ok().filter(|d| *d < FEATURE_DIM))
+}
+
+/// Name of a feature dimension for display, if it has one.
+pub fn feature_dim_name(dim: usize) -> Option<&'static str> {
+    Some(match dim {
+        FEAT_PRICE => "price",
+        FEAT_PRICE_ORIGINAL => "original_price",
+        FEAT_DISCOUNT_PCT => "discount",
+        FEAT_AVAILABILITY => "availability",
+        FEAT_RATING => "rating",
+        FEAT_REVIEW_COUNT_LOG => "review_count",
+        FEAT_SELLER_REPUTATION => "seller_reputation",
+        FEAT_VARIANT_COUNT => "variant_count",
+        FEAT_DEAL_SCORE => "deal_score",
+        FEAT_PRICE_TREND => "price_trend",
+        FEAT_SHIPPING_FREE => "shipping_free",
+        _ => return None,
+    })
+}
+
+/// Resolve a DSL value to the numeric encoding used in feature vectors.
+fn value_to_f32(value: &WqlValue) -> Option<f32> {
+    match value {
+        WqlValue::Float(f) => Some(*f as f32),
+        WqlValue::Integer(i) => Some(*i as f32),
+        WqlValue::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+        WqlValue::String(s) => match s.to_lowercase().as_str() {
+            "in_stock" | "instock" | "available" | "true" | "yes" => Some(1.0),
+            "out_of_stock" | "outofstock" | "unavailable" | "false" | "no" => Some(0.0),
+            "limited" | "limited_stock" | "preorder" | "pre_order" | "backorder" => Some(0.5),
+            _ => None,
+        },
+    }
+}
+
+/// Reject conditions that reference unknown fields or values.
+fn validate_fields(expr: &WhereExpr) -> Result<()> {
+    match expr {
+        WhereExpr::Comparison { field, value, .. } => {
+            if field_to_feature_dim(field).is_none() {
+                anyhow::bail!("unknown watch field '{field}'");
+            }
+            if value_to_f32(value).is_none() {
+                anyhow::bail!("unsupported value for '{field}': {value:?}");
+            }
+            Ok(())
+        }
+        WhereExpr::And(a, b) | WhereExpr::Or(a, b) => {
+            validate_fields(a)?;
+            validate_fields(b)
+        }
+    }
+}
+
+/// Evaluate a condition, relaxing ordered thresholds by `slack`.
+///
+/// With `slack = 0` this is the plain condition; a positive slack widens
+/// the region where the condition counts as true (used for hysteresis).
+fn eval_expr(expr: &WhereExpr, features: &[f32; FEATURE_DIM], slack: f64) -> bool {
+    match expr {
+        WhereExpr::Comparison { field, op, value } => {
+            let (Some(dim), Some(target)) = (field_to_feature_dim(field), value_to_f32(value))
+            else {
+                return false;
+            };
+            let v = features[dim];
+            let slack = slack as f32;
+            match op {
+                ComparisonOp::Lt => v < target + slack,
+                ComparisonOp::Lte => v <= target + slack,
+                ComparisonOp::Gt => v > target - slack,
+                ComparisonOp::Gte => v >= target - slack,
+                ComparisonOp::Eq => (v - target).abs() < 0.001,
+                ComparisonOp::Neq => (v - target).abs() >= 0.001,
+            }
+        }
+        WhereExpr::And(a, b) => eval_expr(a, features, slack) && eval_expr(b, features, slack),
+        WhereExpr::Or(a, b) => eval_expr(a, features, slack) || eval_expr(b, features, slack),
+    }
+}
+
+/// The left-most field referenced by a condition.
+fn first_field(expr: &WhereExpr) -> Option<&str> {
+    match expr {
+        WhereExpr::Comparison { field, .. } => Some(field),
+        WhereExpr::And(a, _) | WhereExpr::Or(a, _) => first_field(a),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -227,6 +646,7 @@ mod tests {
             id: "test-1".to_string(),
             domain: "shop.com".to_string(),
             model_type: Some("Product".to_string()),
+            node_url: None,
             feature_dim: 48, // price
             condition,
             notify: NotifyTarget::EventBus,
@@ -236,6 +656,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_feature_names_round_trip() {
+        for dim in 0..FEATURE_DIM {
+            if let Some(name) = feature_dim_name(dim) {
+                assert_eq!(field_to_feature_dim(name), Some(dim), "{name}");
+            }
+        }
+        assert_eq!(
+            field_to_feature_dim("free_shipping"),
+            field_to_feature_dim("shipping_free")
+        );
+        assert_eq!(field_to_feature_dim("52"), Some(FEAT_RATING));
+        assert_eq!(field_to_feature_dim("colour"), None);
+    }
+
     #[test]
     fn test_watch_value_below() {
         let mut wm = WatchManager::new();
@@ -299,6 +734,158 @@ mod tests {
         assert!(wm.list_rules().is_empty());
     }
 
+    fn compound_rule(dsl: &str) -> WatchRule {
+        let mut rule = make_rule(WatchCondition::parse(dsl).unwrap());
+        rule.id = "compound".to_string();
+        rule
+    }
+
+    fn product(price: f32, availability: f32) -> [f32; FEATURE_DIM] {
+        let mut feats = [0.0f32; FEATURE_DIM];
+        feats[FEAT_PRICE] = price;
+        feats[FEAT_AVAILABILITY] = availability;
+        feats
+    }
+
+    #[test]
+    fn test_compound_rule_consecutive_checks() {
+        let mut wm = WatchManager::new();
+        wm.add_rule(compound_rule(
+            "price < 300 AND availability = in_stock FOR 2 consecutive checks",
+        ));
+
+        let url = "https://shop.com/p/1";
+        assert!(wm
+            .evaluate_node("shop.com", url, &product(250.0, 1.0))
+            .is_empty());
+        let alerts = wm.evaluate_node("shop.com", url, &product(240.0, 1.0));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].current_value, 240.0);
+
+        // Out of stock breaks the condition
+        let mut wm = WatchManager::new();
+        wm.add_rule(compound_rule(
+            "price < 300 AND availability = in_stock FOR 2 consecutive checks",
+        ));
+        wm.evaluate_node("shop.com", url, &product(250.0, 1.0));
+        assert!(wm
+            .evaluate_node("shop.com", url, &product(250.0, 0.0))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_compound_rule_hysteresis() {
+        let mut wm = WatchManager::new();
+        wm.add_rule(compound_rule("price < 300 HYSTERESIS 20"));
+        let url = "https://shop.com/p/1";
+
+        assert_eq!(
+            wm.evaluate_node("shop.com", url, &product(290.0, 1.0))
+                .len(),
+            1
+        );
+        // Bouncing just above the threshold does not re-arm
+        assert!(wm
+            .evaluate_node("shop.com", url, &product(305.0, 1.0))
+            .is_empty());
+        assert!(wm
+            .evaluate_node("shop.com", url, &product(295.0, 1.0))
+            .is_empty());
+        // Clearing the band re-arms
+        assert!(wm
+            .evaluate_node("shop.com", url, &product(330.0, 1.0))
+            .is_empty());
+        assert_eq!(
+            wm.evaluate_node("shop.com", url, &product(280.0, 1.0))
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_compound_rule_cooldown() {
+        let mut wm = WatchManager::new();
+        wm.add_rule(compound_rule("price < 300 COOLDOWN 1h"));
+        let url = "https://shop.com/p/1";
+
+        assert_eq!(
+            wm.evaluate_node("shop.com", url, &product(290.0, 1.0))
+                .len(),
+            1
+        );
+        wm.evaluate_node("shop.com", url, &product(400.0, 1.0));
+        // Re-armed but still cooling down
+        assert!(wm
+            .evaluate_node("shop.com", url, &product(280.0, 1.0))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_compound_rule_rejects_unknown_field() {
+        assert!(WatchCondition::parse("colour = red").is_err());
+        assert!(WatchCondition::parse("availability = sometimes").is_err());
+    }
+
+    #[test]
+    fn test_watch_rules_persist_in_registry() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let registry = LocalRegistry::new(dir.path().to_path_buf()).unwrap();
+
+        let mut wm = WatchManager::new();
+        wm.add_rule(compound_rule("price < 300 FOR 3 checks COOLDOWN 30m"));
+        wm.save(&registry).unwrap();
+
+        let loaded = WatchManager::load(&registry).unwrap();
+        let rules = loaded.list_rules();
+        assert_eq!(rules.len(), 1);
+        match &rules[0].condition {
+            WatchCondition::Compound(expr) => {
+                assert_eq!(expr.consecutive, 3);
+                assert_eq!(expr.cooldown_secs, 1800);
+            }
+            other => panic!("expected compound condition, got {other:?}"),
+        }
+    }
+
+    fn shop_map(prices: &[(&str, f32)]) -> SiteMap {
+        let mut builder = crate::map::builder::SiteMapBuilder::new("shop.com");
+        for (url, price) in prices {
+            builder.add_node(url, PageType::ProductDetail, product(*price, 1.0), 200);
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn test_evaluate_maps_per_node() {
+        let old = shop_map(&[
+            ("https://shop.com/p/1", 320.0),
+            ("https://shop.com/p/2", 350.0),
+        ]);
+        let new = shop_map(&[
+            ("https://shop.com/p/1", 280.0),
+            ("https://shop.com/p/2", 290.0),
+            ("https://shop.com/p/3", 100.0),
+        ]);
+
+        let mut wm = WatchManager::new();
+        let mut node_rule = make_rule(WatchCondition::ValueBelow(300.0));
+        node_rule.node_url = Some("https://shop.com/p/2".to_string());
+        wm.add_rule(node_rule);
+        let mut new_rule = make_rule(WatchCondition::NewInstance);
+        new_rule.id = "new".to_string();
+        wm.add_rule(new_rule);
+
+        let mut alerts = wm.evaluate_maps("shop.com", &old, &new);
+        alerts.sort_by(|a, b| a.rule_id.cmp(&b.rule_id));
+        assert_eq!(alerts.len(), 2);
+        assert_eq!(alerts[0].rule_id, "new");
+        assert_eq!(alerts[0].current_value, 1.0);
+        assert_eq!(alerts[1].rule_id, "test-1");
+        assert!(alerts[1].message.ends_with("on https://shop.com/p/2"));
+        assert_eq!(alerts[1].previous_value, Some(350.0));
+        assert!(wm.list_rules().iter().all(|r| r.last_triggered.is_some()));
+    }
+
     // ── v4 Test Suite: Phase 3D — Watch/Alert System ──
 
     #[test]
@@ -309,6 +896,7 @@ mod tests {
             id: "watch-1".to_string(),
             domain: "amazon.com".to_string(),
             model_type: Some("Product".to_string()),
+            node_url: None,
             feature_dim: 48,
             condition: WatchCondition::ValueBelow(50.0),
             notify: NotifyTarget::EventBus,
@@ -321,6 +909,7 @@ mod tests {
             id: "watch-2".to_string(),
             domain: "amazon.com".to_string(),
             model_type: Some("Product".to_string()),
+            node_url: None,
             feature_dim: 48,
             condition: WatchCondition::ValueAbove(1000.0),
             notify: NotifyTarget::EventBus,
@@ -343,6 +932,7 @@ mod tests {
             id: "to-remove".to_string(),
             domain: "test.com".to_string(),
             model_type: Some("Product".to_string()),
+            node_url: None,
             feature_dim: 48,
             condition: WatchCondition::ValueBelow(1.0),
             notify: NotifyTarget::EventBus,
@@ -368,6 +958,7 @@ mod tests {
             id: "unrealistic".to_string(),
             domain: "amazon.com".to_string(),
             model_type: Some("Product".to_string()),
+            node_url: None,
             feature_dim: 48,
             condition: WatchCondition::ValueBelow(0.01),
             notify: NotifyTarget::EventBus,
@@ -391,6 +982,7 @@ mod tests {
             id: "price-drop".to_string(),
             domain: "amazon.com".to_string(),
             model_type: Some("Product".to_string()),
+            node_url: None,
             feature_dim: 48,
             condition: WatchCondition::ValueBelow(80.0),
             notify: NotifyTarget::EventBus,
@@ -407,6 +999,62 @@ mod tests {
         assert_eq!(alerts[0].rule_id, "price-drop");
     }
 
+    #[test]
+    fn test_visual_rule_fires_on_pixels_or_region() {
+        let banner = Region::parse("0, 0, 1, 0.2").unwrap();
+        assert!(Region::parse("0,0,1").is_err());
+        assert!(Region::parse("0,0,1.5,0.2").is_err());
+        let mut rule = make_rule(WatchCondition::Visual(VisualCondition {
+            threshold: 0.1,
+            regions: vec![banner],
+            interval_secs: 3600,
+        }));
+        rule.node_url = Some("https://shop.com/p/1".to_string());
+        let mut wm = WatchManager::new();
+        wm.add_rule(rule);
+
+        let mut change = VisualChange {
+            before_id: 1,
+            after_id: 2,
+            pixel_diff_ratio: 0.02,
+            changed_regions: vec![Region {
+                x: 0.5,
+                y: 0.5,
+                w: 0.1,
+                h: 0.1,
+            }],
+        };
+        assert!(wm
+            .evaluate_visual("test-1", "https://shop.com/p/1", &change)
+            .is_none());
+
+        // A small change inside a watched region fires.
+        change.changed_regions.push(Region {
+            x: 0.3,
+            y: 0.1,
+            w: 0.1,
+            h: 0.1,
+        });
+        let alert = wm
+            .evaluate_visual("test-1", "https://shop.com/p/1", &change)
+            .unwrap();
+        assert!(alert.message.contains("Region 0,0,1,0.2 changed"));
+
+        // So does a large change anywhere.
+        change.changed_regions.clear();
+        change.pixel_diff_ratio = 0.25;
+        let alert = wm
+            .evaluate_visual("test-1", "https://shop.com/p/1", &change)
+            .unwrap();
+        assert_eq!(alert.current_value, 0.25);
+        assert!(wm.list_rules()[0].last_triggered.is_some());
+
+        // Re-maps never evaluate visual rules.
+        let old = shop_map(&[("https://shop.com/p/1", 320.0)]);
+        let new = shop_map(&[("https://shop.com/p/1", 100.0)]);
+        assert!(wm.evaluate_maps("shop.com", &old, &new).is_empty());
+    }
+
     #[test]
     fn test_v4_watch_recent_alerts() {
         let mut wm = WatchManager::new();
@@ -415,6 +1063,7 @@ mod tests {
             id: "test-alert".to_string(),
             domain: "test.com".to_string(),
             model_type: Some("Product".to_string()),
+            node_url: None,
             feature_dim: 48,
             condition: WatchCondition::ValueAbove(100.0),
             notify: NotifyTarget::EventBus,
@@ -0,0 +1,360 @@
+//! API keys for clients of the Cortex daemon.
+//!
+//! While no keys exist the daemon trusts every local process, as before.
+//! Once one is added with `cortex keys add`, each socket connection must
+//! present a key (the `api_key` parameter of any request, usually the
+//! handshake) before it may do anything but handshake, and may then only
+//! use the methods its key's [`Permission`] allows.
+//! REST requests present theirs as `Authorization: Bearer <secret>`.
+//!
+//! Keys live in `~/.cortex/api_keys.json`. Only the SHA-256 of each secret
+//! is stored; the secret itself is shown once, when the key is created.
+
+use crate::collective::registry_server::constant_time_eq;
+use crate::protocol::Method;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Environment variable CLI commands read their key from.
+pub const ENV_VAR: &str = "CORTEX_API_KEY";
+
+/// What a key may do. Each level includes the ones before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Permission {
+    /// Read cached maps: QUERY, PATHFIND, STATUS, SUBSCRIBE, saved queries.
+    Read,
+    /// Also acquire pages: MAP, REFRESH, WATCH, PERCEIVE, CANCEL, and
+    /// ANNOTATE.
+    Map,
+    /// Also act on sites: ACT, AUTH and WebSocket methods, and RELOAD.
+    Act,
+}
+
+impl Permission {
+    /// The permission `method` requires, or `None` for the handshake, which
+    /// every connection may send.
+    pub fn required_for(method: &Method) -> Option<Self> {
+        match method {
+            Method::Handshake => None,
+            Method::Query
+            | Method::Pathfind
+            | Method::Status
+            | Method::Subscribe
+            | Method::SavedQueries
+            | Method::Wql
+            | Method::Annotations => Some(Self::Read),
+            Method::Map
+            | Method::Refresh
+            | Method::Watch
+            | Method::Perceive
+            | Method::Cancel
+            | Method::Annotate => Some(Self::Map),
+            Method::Act
+            | Method::Auth
+            | Method::AuthConsent
+            | Method::AuthMfa
+            | Method::ConnectWs
+            | Method::SendWs
+            | Method::Reload => Some(Self::Act),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Map => "map",
+            Self::Act => "act",
+        }
+    }
+}
+
+impl std::str::FromStr for Permission {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "read" => Ok(Self::Read),
+            "map" => Ok(Self::Map),
+            "act" => Ok(Self::Act),
+            _ => bail!("unknown permission '{s}' (expected read, map or act)"),
+        }
+    }
+}
+
+impl std::fmt::Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A stored API key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiKey {
+    /// Short public identifier, also embedded in the secret.
+    pub id: String,
+    /// Who or what the key is for.
+    pub name: String,
+    pub permission: Permission,
+    /// Hex SHA-256 of the secret.
+    pub secret_hash: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The set of API keys.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApiKeys {
+    pub keys: Vec<ApiKey>,
+}
+
+impl ApiKeys {
+    /// Load keys from a file; a missing file holds no keys.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("reading API keys: {}", path.display()))?;
+        serde_json::from_str(&data).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    /// Save keys, readable by the owner only.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(())
+    }
+
+    /// Create a key for `name`. Returns the key and its secret, which is
+    /// not stored and cannot be recovered later.
+    pub fn add(&mut self, name: &str, permission: Permission) -> Result<(ApiKey, String)> {
+        if self.keys.iter().any(|k| k.name == name) {
+            bail!("a key named '{name}' already exists; revoke it first");
+        }
+        let id = hex(&rand::random::<[u8; 4]>());
+        let secret = format!("cortex_{id}_{}", hex(&rand::random::<[u8; 24]>()));
+        let key = ApiKey {
+            id,
+            name: name.to_string(),
+            permission,
+            secret_hash: hash_secret(&secret),
+            created_at: Utc::now(),
+        };
+        self.keys.push(key.clone());
+        Ok((key, secret))
+    }
+
+    /// Revoke the key with this ID or name. Returns the revoked key.
+    pub fn revoke(&mut self, id_or_name: &str) -> Option<ApiKey> {
+        let index = self
+            .keys
+            .iter()
+            .position(|k| k.id == id_or_name || k.name == id_or_name)?;
+        Some(self.keys.remove(index))
+    }
+
+    /// The key `secret` belongs to, if any.
+    pub fn verify(&self, secret: &str) -> Option<&ApiKey> {
+        let hash = hash_secret(secret);
+        self.keys
+            .iter()
+            .find(|k| constant_time_eq(k.secret_hash.as_bytes(), hash.as_bytes()))
+    }
+}
+
+/// The daemon's view of the key file, reloaded whenever the file changes so
+/// keys added or revoked while it runs take effect on the next request.
+#[derive(Debug)]
+pub struct KeyStore {
+    path: Option<PathBuf>,
+    cached: Mutex<Cached>,
+}
+
+#[derive(Debug, Default)]
+struct Cached {
+    modified: Option<SystemTime>,
+    keys: ApiKeys,
+    /// The file exists but could not be read, so keys stay required.
+    unreadable: bool,
+}
+
+impl KeyStore {
+    /// A store with no keys: every connection is trusted.
+    pub fn disabled() -> Self {
+        Self {
+            path: None,
+            cached: Mutex::default(),
+        }
+    }
+
+    /// A store backed by the key file at `path`.
+    pub fn open(path: PathBuf) -> Self {
+        Self {
+            path: Some(path),
+            cached: Mutex::default(),
+        }
+    }
+
+    /// Run `f` on the current keys and whether the file is unreadable. An
+    /// unreadable file keeps the last good keys and keeps keys required.
+    fn with_keys<T>(&self, f: impl FnOnce(&ApiKeys, bool) -> T) -> T {
+        let mut cached = self.cached.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(path) = &self.path {
+            let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+            if modified != cached.modified {
+                cached.modified = modified;
+                match ApiKeys::load(path) {
+                    Ok(keys) => {
+                        cached.keys = keys;
+                        cached.unreadable = false;
+                    }
+                    Err(e) => {
+                        tracing::warn!("failed to reload API keys: {e:#}");
+                        cached.unreadable = true;
+                    }
+                }
+            }
+        }
+        f(&cached.keys, cached.unreadable)
+    }
+
+    /// Whether connections must present a key.
+    pub fn required(&self) -> bool {
+        self.with_keys(|keys, unreadable| unreadable || !keys.keys.is_empty())
+    }
+
+    /// The key `secret` belongs to, if any.
+    pub fn verify(&self, secret: &str) -> Option<ApiKey> {
+        self.with_keys(|keys, _| keys.verify(secret).cloned())
+    }
+
+    /// Whether the key with `id` has not been revoked.
+    pub fn is_active(&self, id: &str) -> bool {
+        self.with_keys(|keys, _| keys.keys.iter().any(|k| k.id == id))
+    }
+}
+
+/// Default key file (~/.cortex/api_keys.json).
+pub fn default_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".cortex")
+        .join("api_keys.json")
+}
+
+/// Add the key from `$CORTEX_API_KEY`, if set, to a socket request's params.
+pub fn attach_client_key(request: &mut serde_json::Value) {
+    if let Ok(key) = std::env::var(ENV_VAR) {
+        if let Some(params) = request.get_mut("params").and_then(|p| p.as_object_mut()) {
+            params.insert("api_key".to_string(), key.into());
+        }
+    }
+}
+
+fn hash_secret(secret: &str) -> String {
+    hex(&Sha256::digest(secret.as_bytes()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_verify_revoke() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("api_keys.json");
+
+        let mut keys = ApiKeys::default();
+        let (key, secret) = keys.add("ci", Permission::Read).unwrap();
+        assert!(secret.starts_with(&format!("cortex_{}_", key.id)));
+        assert!(keys.add("ci", Permission::Act).is_err());
+        keys.save(&path).unwrap();
+
+        let loaded = ApiKeys::load(&path).unwrap();
+        assert!(!std::fs::read_to_string(&path).unwrap().contains(&secret));
+        assert_eq!(loaded.verify(&secret).map(|k| k.name.as_str()), Some("ci"));
+        assert!(loaded.verify("cortex_00000000_nope").is_none());
+
+        let mut loaded = loaded;
+        assert_eq!(loaded.revoke(&key.id).map(|k| k.name), Some("ci".into()));
+        assert!(loaded.verify(&secret).is_none());
+        assert!(loaded.revoke("ci").is_none());
+    }
+
+    #[test]
+    fn test_permission_levels() {
+        assert_eq!(Permission::required_for(&Method::Handshake), None);
+        assert_eq!(
+            Permission::required_for(&Method::Query),
+            Some(Permission::Read)
+        );
+        assert_eq!(
+            Permission::required_for(&Method::Cancel),
+            Some(Permission::Map)
+        );
+        assert_eq!(
+            Permission::required_for(&Method::Act),
+            Some(Permission::Act)
+        );
+        assert_eq!(
+            Permission::required_for(&Method::Reload),
+            Some(Permission::Act)
+        );
+        assert!(Permission::Act >= Permission::Map && Permission::Map >= Permission::Read);
+        assert!("write".parse::<Permission>().is_err());
+    }
+
+    #[test]
+    fn test_key_store_reloads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("api_keys.json");
+        let store = KeyStore::open(path.clone());
+        assert!(!store.required());
+
+        let mut keys = ApiKeys::default();
+        let (key, secret) = keys.add("agent", Permission::Map).unwrap();
+        keys.save(&path).unwrap();
+        assert!(store.required());
+        assert_eq!(store.verify(&secret).map(|k| k.id), Some(key.id.clone()));
+
+        keys.revoke("agent");
+        keys.save(&path).unwrap();
+        // Make sure the modification time moves on coarse filesystems.
+        let later = SystemTime::now() + std::time::Duration::from_secs(1);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert!(!store.is_active(&key.id));
+        assert!(!store.required());
+
+        // A malformed file locks everyone out rather than disabling keys.
+        std::fs::write(&path, "{not json").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later + std::time::Duration::from_secs(1))
+            .unwrap();
+        assert!(store.required());
+        assert!(!KeyStore::disabled().required());
+    }
+}
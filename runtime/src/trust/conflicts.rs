@@ -0,0 +1,176 @@
+//! Resolving values that acquisition layers disagree on.
+//!
+//! Structured data (Layer 1) and patterns (Layer 1.5) often both find a
+//! page's price, and do not always agree: a JSON-LD price left stale by a
+//! sale, a pattern that caught a related product's price. Each value is a
+//! [`Candidate`] with its lineage, and the one with the highest
+//! [`trust`](FieldLineage::trust) — layer reliability × confidence, decayed
+//! by age — is kept. When candidates differ by more than [`TOLERANCE`], the
+//! node's lineage records every value as a [`FieldConflict`]; a
+//! disagreement above [`VERIFY_DISAGREEMENT`] sends the page to the browser,
+//! and the rendered value settles the conflict.
+
+use crate::trust::lineage::FieldLineage;
+use serde::{Deserialize, Serialize};
+
+/// Relative difference below which values agree (rounding, formatting).
+pub const TOLERANCE: f32 = 0.01;
+
+/// Relative difference above which a conflict is worth a browser render.
+pub const VERIFY_DISAGREEMENT: f32 = 0.2;
+
+/// One layer's value for a field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Candidate {
+    pub value: f32,
+    #[serde(flatten)]
+    pub lineage: FieldLineage,
+}
+
+/// Values that layers disagreed on for one field of a node.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldConflict {
+    /// Every value offered, the kept one first.
+    pub candidates: Vec<Candidate>,
+    /// Largest relative difference between two candidates, in `[0, 1]`.
+    pub disagreement: f32,
+    /// Whether the kept value comes from a browser render.
+    #[serde(default)]
+    pub verified: bool,
+}
+
+impl FieldConflict {
+    /// Whether the values differ enough to render the page to settle them,
+    /// and no render has.
+    pub fn needs_verification(&self) -> bool {
+        !self.verified && self.disagreement > VERIFY_DISAGREEMENT
+    }
+}
+
+/// Keep the most trusted of `candidates` at `now`, the earlier one on a
+/// tie. Returns it, with the conflict if the candidates disagree; `None`
+/// if there are no candidates.
+pub fn resolve(
+    mut candidates: Vec<Candidate>,
+    now: u64,
+) -> Option<(Candidate, Option<FieldConflict>)> {
+    // Stable, so ties keep their order.
+    candidates.sort_by(|a, b| b.lineage.trust(now).total_cmp(&a.lineage.trust(now)));
+    let kept = candidates.first()?.clone();
+    Some((kept, conflict(candidates, false)))
+}
+
+/// Settle `candidates` with `rendered`, the value a browser render found,
+/// which is kept whatever the others say. Returns the conflict, if the
+/// values disagree.
+pub fn settle(candidates: Vec<Candidate>, rendered: Candidate) -> Option<FieldConflict> {
+    let mut all = Vec::with_capacity(candidates.len() + 1);
+    all.push(rendered);
+    all.extend(candidates);
+    conflict(all, true)
+}
+
+/// Relative difference between `a` and `b`, in `[0, 1]`.
+pub fn disagreement(a: f32, b: f32) -> f32 {
+    let scale = a.abs().max(b.abs());
+    if scale == 0.0 {
+        0.0
+    } else {
+        ((a - b).abs() / scale).min(1.0)
+    }
+}
+
+fn conflict(candidates: Vec<Candidate>, verified: bool) -> Option<FieldConflict> {
+    let mut worst = 0.0f32;
+    for (i, a) in candidates.iter().enumerate() {
+        for b in &candidates[i + 1..] {
+            worst = worst.max(disagreement(a.value, b.value));
+        }
+    }
+    (worst > TOLERANCE).then_some(FieldConflict {
+        candidates,
+        disagreement: worst,
+        verified,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trust::lineage::{Layer, HALF_LIFE_SECS};
+
+    const NOW: u64 = 1_000_000_000;
+
+    fn candidate(value: f32, layer: Layer, confidence: f32, fetched_at: u64) -> Candidate {
+        Candidate {
+            value,
+            lineage: FieldLineage::new(layer, None, confidence, fetched_at),
+        }
+    }
+
+    #[test]
+    fn test_resolve_picks_by_confidence_and_recency() {
+        // Agreeing values are no conflict.
+        let (kept, conflict) = resolve(
+            vec![
+                candidate(19.99, Layer::StructuredData, 1.0, NOW),
+                candidate(19.995, Layer::Pattern, 0.9, NOW),
+            ],
+            NOW,
+        )
+        .unwrap();
+        assert_eq!(kept.value, 19.99);
+        assert!(conflict.is_none());
+
+        // Structured data outranks a confident pattern fetched as recently.
+        let (kept, conflict) = resolve(
+            vec![
+                candidate(25.0, Layer::Pattern, 0.9, NOW),
+                candidate(20.0, Layer::StructuredData, 1.0, NOW),
+            ],
+            NOW,
+        )
+        .unwrap();
+        assert_eq!(kept.value, 20.0);
+        let conflict = conflict.unwrap();
+        assert_eq!(conflict.candidates[0].value, 20.0);
+        assert!((conflict.disagreement - 0.2).abs() < 1e-6);
+        assert!(!conflict.needs_verification());
+
+        // A value fetched two half-lives ago loses to a fresh one.
+        let (kept, conflict) = resolve(
+            vec![
+                candidate(20.0, Layer::StructuredData, 1.0, NOW - 2 * HALF_LIFE_SECS),
+                candidate(30.0, Layer::Pattern, 0.9, NOW),
+            ],
+            NOW,
+        )
+        .unwrap();
+        assert_eq!(kept.value, 30.0);
+        assert!(conflict.unwrap().needs_verification());
+
+        assert!(resolve(Vec::new(), NOW).is_none());
+    }
+
+    #[test]
+    fn test_settle_keeps_rendered_value() {
+        let conflict = settle(
+            vec![
+                candidate(20.0, Layer::StructuredData, 1.0, NOW),
+                candidate(30.0, Layer::Pattern, 0.9, NOW),
+            ],
+            candidate(30.0, Layer::Browser, 1.0, NOW),
+        )
+        .unwrap();
+        assert!(conflict.verified);
+        assert_eq!(conflict.candidates[0].lineage.layer, Layer::Browser);
+        assert!(!conflict.needs_verification());
+
+        // Nothing to record when all agree.
+        assert!(settle(
+            vec![candidate(20.0, Layer::StructuredData, 1.0, NOW)],
+            candidate(20.0, Layer::Browser, 1.0, NOW)
+        )
+        .is_none());
+    }
+}
@@ -0,0 +1,245 @@
+//! Source lineage for extracted values, and per-node trust scores.
+//!
+//! While building a map, the mapper records where each traced value came
+//! from: the acquisition layer, the selector or JSON-LD path it was read
+//! from, the layer's confidence, and when the page was fetched. A node's
+//! trust score combines its values' lineage — structured data and rendered
+//! pages count for more than pattern matches or URL guesses, and a value's
+//! weight halves every [`HALF_LIFE_SECS`] since it was fetched. Where
+//! layers disagreed on a value, the node also keeps every value offered
+//! (see [`conflicts`](crate::trust::conflicts)).
+//!
+//! Lineage is not part of the binary map format; the map cache stores it
+//! next to the map as `<domain>.lineage.json`.
+
+use crate::map::types::{
+    FEAT_AVAILABILITY, FEAT_PRICE, FEAT_PRICE_ORIGINAL, FEAT_RATING, FEAT_REVIEW_COUNT_LOG,
+};
+use crate::trust::conflicts::FieldConflict;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Age at which a value's contribution to the trust score halves (30 days).
+pub const HALF_LIFE_SECS: u64 = 30 * 86_400;
+
+/// Values traced in lineage, and the feature dimension each is stored in.
+/// A traced value is recorded even when it is zero, such as "out of stock",
+/// so lineage also tells a zero that was read from one that is missing.
+pub const TRACED_FIELDS: [(&str, usize); 5] = [
+    ("price", FEAT_PRICE),
+    ("original_price", FEAT_PRICE_ORIGINAL),
+    ("availability", FEAT_AVAILABILITY),
+    ("rating", FEAT_RATING),
+    ("review_count", FEAT_REVIEW_COUNT_LOG),
+];
+
+/// The acquisition layer that produced a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Layer {
+    /// Layer 0: inferred from the URL alone.
+    Url,
+    /// Layer 1: JSON-LD, microdata, or OpenGraph.
+    StructuredData,
+    /// Layer 1.5: CSS selector and text patterns.
+    Pattern,
+    /// Layer 2.5: HTTP action discovery.
+    Actions,
+    /// Layer 3: extraction from a browser-rendered page.
+    Browser,
+    /// A correction made by a person or agent.
+    Annotation,
+}
+
+impl Layer {
+    /// How far a value from this layer is trusted, before its own confidence.
+    pub fn reliability(self) -> f32 {
+        match self {
+            Layer::Annotation => 1.0,
+            Layer::StructuredData => 0.95,
+            Layer::Browser => 0.9,
+            Layer::Pattern => 0.75,
+            Layer::Actions => 0.7,
+            Layer::Url => 0.4,
+        }
+    }
+}
+
+/// Where one value came from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldLineage {
+    pub layer: Layer,
+    /// Selector or JSON-LD path the value was read from, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// The layer's confidence in the value (0.0–1.0).
+    pub confidence: f32,
+    /// Unix seconds when the page was fetched.
+    pub fetched_at: u64,
+}
+
+impl FieldLineage {
+    pub fn new(layer: Layer, path: Option<&str>, confidence: f32, fetched_at: u64) -> Self {
+        Self {
+            layer,
+            path: path.map(String::from),
+            confidence: confidence.clamp(0.0, 1.0),
+            fetched_at,
+        }
+    }
+
+    /// This value's trust at `now`: layer reliability × confidence, decayed
+    /// by fetch age.
+    pub fn trust(&self, now: u64) -> f32 {
+        let age = now.saturating_sub(self.fetched_at) as f64;
+        let decay = 0.5f64.powf(age / HALF_LIFE_SECS as f64) as f32;
+        self.layer.reliability() * self.confidence * decay
+    }
+}
+
+/// Lineage of one node's values, keyed by field name (`page_type`, `price`, …).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NodeLineage {
+    pub fields: BTreeMap<String, FieldLineage>,
+    /// Fields whose layers disagreed, keyed by field name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub conflicts: BTreeMap<String, FieldConflict>,
+}
+
+impl NodeLineage {
+    /// Whether the value in feature dimension `dim` was traced to a source.
+    pub fn has_dimension(&self, dim: usize) -> bool {
+        TRACED_FIELDS
+            .iter()
+            .any(|&(field, d)| d == dim && self.fields.contains_key(field))
+    }
+
+    /// The node's trust score at `now`: the mean trust of its values, or
+    /// `None` if nothing was traced.
+    pub fn trust_score(&self, now: u64) -> Option<f32> {
+        if self.fields.is_empty() {
+            return None;
+        }
+        let total: f32 = self.fields.values().map(|f| f.trust(now)).sum();
+        Some(total / self.fields.len() as f32)
+    }
+}
+
+/// Lineage for the nodes of one map, keyed by node index.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MapLineage {
+    pub nodes: BTreeMap<u32, NodeLineage>,
+}
+
+impl MapLineage {
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Lineage recorded for `node`, if any.
+    pub fn node(&self, node: u32) -> Option<&NodeLineage> {
+        self.nodes.get(&node)
+    }
+
+    /// Record where `node`'s `field` came from, replacing earlier lineage.
+    pub fn record(&mut self, node: u32, field: &str, lineage: FieldLineage) {
+        self.nodes
+            .entry(node)
+            .or_default()
+            .fields
+            .insert(field.to_string(), lineage);
+    }
+
+    /// Record that layers disagreed on `node`'s `field`.
+    pub fn record_conflict(&mut self, node: u32, field: &str, conflict: FieldConflict) {
+        self.nodes
+            .entry(node)
+            .or_default()
+            .conflicts
+            .insert(field.to_string(), conflict);
+    }
+
+    /// Nodes with a conflict large enough to render the page to settle it,
+    /// and no render yet.
+    pub fn needs_verification(&self) -> Vec<u32> {
+        self.nodes
+            .iter()
+            .filter(|(_, n)| n.conflicts.values().any(FieldConflict::needs_verification))
+            .map(|(&node, _)| node)
+            .collect()
+    }
+
+    /// Load lineage saved by [`save`](Self::save); empty if the file is missing.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = std::fs::read(path)
+            .with_context(|| format!("failed to read lineage: {}", path.display()))?;
+        serde_json::from_slice(&data)
+            .with_context(|| format!("failed to parse lineage: {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_vec(self)?)
+            .with_context(|| format!("failed to write lineage: {}", path.display()))
+    }
+}
+
+/// Current Unix time in seconds, for `fetched_at` and trust scores.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trust_score_weighs_layer_and_age() {
+        let mut lineage = MapLineage::default();
+        lineage.record(
+            0,
+            "price",
+            FieldLineage::new(Layer::StructuredData, Some("offers.price"), 1.0, 1000),
+        );
+        lineage.record(1, "price", FieldLineage::new(Layer::Url, None, 1.0, 1000));
+        assert!(lineage.node(2).is_none());
+
+        let jsonld = lineage.node(0).unwrap().trust_score(1000).unwrap();
+        let url = lineage.node(1).unwrap().trust_score(1000).unwrap();
+        assert!((jsonld - 0.95).abs() < 1e-6);
+        assert!(url < jsonld);
+
+        // One half-life later the same value is worth half as much.
+        let aged = lineage
+            .node(0)
+            .unwrap()
+            .trust_score(1000 + HALF_LIFE_SECS)
+            .unwrap();
+        assert!((aged - jsonld / 2.0).abs() < 1e-4);
+        assert_eq!(NodeLineage::default().trust_score(1000), None);
+    }
+
+    #[test]
+    fn test_lineage_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shop.com.lineage.json");
+        assert!(MapLineage::load(&path).unwrap().is_empty());
+
+        let mut lineage = MapLineage::default();
+        lineage.record(
+            3,
+            "rating",
+            FieldLineage::new(Layer::Pattern, Some(".rating"), 0.85, 42),
+        );
+        lineage.save(&path).unwrap();
+        assert_eq!(MapLineage::load(&path).unwrap(), lineage);
+    }
+}
@@ -1,5 +1,12 @@
-//! Trust and safety — credential vault, PII detection, input sanitization.
+//! Trust and safety — credential vault, PII detection, input sanitization,
+//! signed map provenance, source lineage for extracted values and the
+//! conflicts between layers that produced them, and API keys for daemon
+//! clients.
 
+pub mod api_keys;
+pub mod conflicts;
 pub mod credentials;
+pub mod lineage;
 pub mod pii;
 pub mod sandbox;
+pub mod signing;
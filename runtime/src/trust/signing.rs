@@ -0,0 +1,374 @@
+//! Ed25519 signing and provenance for shared maps and deltas.
+//!
+//! Every map or delta published to a registry can carry a [`SignedEnvelope`]:
+//! a provenance record (who mapped it, when, with what settings, and the
+//! SHA-256 of the exact payload bytes) plus an Ed25519 signature over that
+//! record. Verification recomputes the payload hash and checks the signature,
+//! so any modification of the payload or the provenance is detected.
+//!
+//! The local signing key lives in `~/.cortex/signing_key.json`; public keys of
+//! publishers we accept are listed in `~/.cortex/trusted_keys.json`.
+
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Who produced a payload, when, and how.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Provenance {
+    /// Human-readable name of the publisher.
+    pub mapper: String,
+    /// Hex-encoded Ed25519 public key of the publisher.
+    pub public_key: String,
+    /// When the payload was produced.
+    pub mapped_at: DateTime<Utc>,
+    /// Cortex version that produced it.
+    pub cortex_version: String,
+    /// Settings the payload was produced with (free-form).
+    #[serde(default)]
+    pub settings: serde_json::Value,
+    /// Hex-encoded SHA-256 of the payload bytes.
+    pub content_hash: String,
+}
+
+/// Provenance plus a signature over it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedEnvelope {
+    /// Signed provenance record.
+    pub provenance: Provenance,
+    /// Hex-encoded Ed25519 signature over the canonical provenance JSON.
+    pub signature: String,
+}
+
+impl SignedEnvelope {
+    /// Encode for transport in an HTTP header.
+    pub fn to_header(&self) -> String {
+        base64::engine::general_purpose::STANDARD
+            .encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    /// Decode from an HTTP header value.
+    pub fn from_header(value: &str) -> Result<Self> {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(value.trim())
+            .context("signature header is not base64")?;
+        serde_json::from_slice(&bytes).context("signature header is not a signed envelope")
+    }
+
+    /// Verify that this envelope signs `payload`.
+    ///
+    /// Checks the payload hash and the signature; does not consult the trust
+    /// store (see [`TrustedKeys::check`]).
+    pub fn verify(&self, payload: &[u8]) -> Result<()> {
+        if hex(&Sha256::digest(payload)) != self.provenance.content_hash {
+            bail!("payload does not match signed content hash");
+        }
+        let key_bytes: [u8; 32] = unhex(&self.provenance.public_key)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("public key must be 32 bytes"))?;
+        let key = VerifyingKey::from_bytes(&key_bytes).context("invalid public key")?;
+        let sig_bytes: [u8; 64] = unhex(&self.signature)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("signature must be 64 bytes"))?;
+        key.verify(
+            &canonical(&self.provenance)?,
+            &Signature::from_bytes(&sig_bytes),
+        )
+        .map_err(|_| anyhow::anyhow!("invalid signature from {}", self.provenance.mapper))
+    }
+}
+
+/// Stored form of the local signing key.
+#[derive(Serialize, Deserialize)]
+struct KeyFile {
+    name: String,
+    secret_key: String,
+}
+
+/// Local Ed25519 identity used to sign published payloads.
+pub struct MapSigner {
+    name: String,
+    key: SigningKey,
+}
+
+impl MapSigner {
+    /// Generate a fresh signing identity.
+    pub fn generate(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            key: SigningKey::generate(&mut rand::rngs::OsRng),
+        }
+    }
+
+    /// Load a signing key from a file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("reading signing key: {}", path.display()))?;
+        let file: KeyFile = serde_json::from_str(&data)
+            .with_context(|| format!("parsing signing key: {}", path.display()))?;
+        let secret: [u8; 32] = unhex(&file.secret_key)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("signing key must be 32 bytes"))?;
+        Ok(Self {
+            name: file.name,
+            key: SigningKey::from_bytes(&secret),
+        })
+    }
+
+    /// Write the signing key to a file readable only by the owner.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = KeyFile {
+            name: self.name.clone(),
+            secret_key: hex(self.key.as_bytes()),
+        };
+        std::fs::write(path, serde_json::to_string_pretty(&file)?)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(())
+    }
+
+    /// Load the default key, creating one named after the current user if
+    /// none exists yet.
+    pub fn load_or_create_default() -> Result<Self> {
+        let path = default_key_path();
+        if path.exists() {
+            return Self::load(&path);
+        }
+        let name = std::env::var("USER").unwrap_or_else(|_| "cortex".to_string());
+        let signer = Self::generate(&name);
+        signer.save(&path)?;
+        Ok(signer)
+    }
+
+    /// Publisher name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Hex-encoded public key.
+    pub fn public_key(&self) -> String {
+        hex(self.key.verifying_key().as_bytes())
+    }
+
+    /// Sign `payload`, recording provenance.
+    pub fn sign(
+        &self,
+        payload: &[u8],
+        mapped_at: DateTime<Utc>,
+        settings: serde_json::Value,
+    ) -> Result<SignedEnvelope> {
+        let provenance = Provenance {
+            mapper: self.name.clone(),
+            public_key: self.public_key(),
+            mapped_at,
+            cortex_version: env!("CARGO_PKG_VERSION").to_string(),
+            settings,
+            content_hash: hex(&Sha256::digest(payload)),
+        };
+        let signature = self.key.sign(&canonical(&provenance)?);
+        Ok(SignedEnvelope {
+            provenance,
+            signature: hex(&signature.to_bytes()),
+        })
+    }
+}
+
+/// A publisher whose signatures we accept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustedKey {
+    /// Display name.
+    pub name: String,
+    /// Hex-encoded Ed25519 public key.
+    pub public_key: String,
+}
+
+/// Set of trusted publisher keys.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrustedKeys {
+    /// Trusted keys.
+    pub keys: Vec<TrustedKey>,
+}
+
+impl TrustedKeys {
+    /// Load from a file. Missing file = empty set.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = std::fs::read_to_string(path)?;
+        serde_json::from_str(&data).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    /// Load from `~/.cortex/trusted_keys.json`.
+    pub fn load_default() -> Result<Self> {
+        Self::load(&default_trusted_path())
+    }
+
+    /// Persist to a file.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Trust a key (replacing any key with the same name).
+    pub fn add(&mut self, name: &str, public_key: &str) -> Result<()> {
+        let bytes = unhex(public_key)?;
+        if bytes.len() != 32 {
+            bail!("public key must be 32 bytes (64 hex characters)");
+        }
+        self.keys.retain(|k| k.name != name);
+        self.keys.push(TrustedKey {
+            name: name.to_string(),
+            public_key: public_key.to_lowercase(),
+        });
+        Ok(())
+    }
+
+    /// Stop trusting a key by name. Returns true if it was present.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.keys.len();
+        self.keys.retain(|k| k.name != name);
+        self.keys.len() != before
+    }
+
+    /// The trusted entry for a hex-encoded public key, if any.
+    pub fn find(&self, public_key: &str) -> Option<&TrustedKey> {
+        self.keys
+            .iter()
+            .find(|k| k.public_key == public_key.to_lowercase())
+    }
+
+    /// Verify `envelope` over `payload` and require a trusted signer,
+    /// returning the trusted entry that signed it.
+    ///
+    /// An empty trust store trusts no one: the signer's own name in the
+    /// provenance record says nothing about who actually holds the key.
+    pub fn check(&self, envelope: &SignedEnvelope, payload: &[u8]) -> Result<&TrustedKey> {
+        envelope.verify(payload)?;
+        match self.find(&envelope.provenance.public_key) {
+            Some(key) => Ok(key),
+            None => bail!(
+                "signer key {} is not in the trusted key list",
+                envelope.provenance.public_key
+            ),
+        }
+    }
+}
+
+/// Default signing key location (`~/.cortex/signing_key.json`).
+pub fn default_key_path() -> PathBuf {
+    cortex_dir().join("signing_key.json")
+}
+
+/// Default trusted key list location (`~/.cortex/trusted_keys.json`).
+pub fn default_trusted_path() -> PathBuf {
+    cortex_dir().join("trusted_keys.json")
+}
+
+fn cortex_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(".cortex")
+}
+
+/// Canonical bytes signed for a provenance record.
+fn canonical(provenance: &Provenance) -> Result<Vec<u8>> {
+    // Round-trip through Value so the bytes depend only on the record's
+    // content, not on how it was constructed.
+    Ok(serde_json::to_vec(&serde_json::to_value(provenance)?)?)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn unhex(s: &str) -> Result<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        bail!("invalid hex string");
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).context("invalid hex string"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signer = MapSigner::generate("alice");
+        let payload = b"map bytes";
+        let env = signer
+            .sign(payload, Utc::now(), serde_json::json!({"max_render": 50}))
+            .unwrap();
+
+        env.verify(payload).unwrap();
+        assert_eq!(env.provenance.mapper, "alice");
+
+        // Round-trips through the header encoding
+        let decoded = SignedEnvelope::from_header(&env.to_header()).unwrap();
+        assert_eq!(decoded, env);
+    }
+
+    #[test]
+    fn test_tampering_detected() {
+        let signer = MapSigner::generate("alice");
+        let env = signer
+            .sign(b"original", Utc::now(), serde_json::Value::Null)
+            .unwrap();
+
+        let err = env.verify(b"modified").unwrap_err().to_string();
+        assert!(err.contains("content hash"), "{err}");
+
+        // Editing provenance invalidates the signature.
+        let mut forged = env.clone();
+        forged.provenance.mapper = "mallory".into();
+        let err = forged.verify(b"original").unwrap_err().to_string();
+        assert!(err.contains("invalid signature"), "{err}");
+    }
+
+    #[test]
+    fn test_key_file_round_trip_and_trust_store() {
+        let dir = TempDir::new().unwrap();
+        let key_path = dir.path().join("key.json");
+        let signer = MapSigner::generate("bob");
+        signer.save(&key_path).unwrap();
+        let loaded = MapSigner::load(&key_path).unwrap();
+        assert_eq!(loaded.public_key(), signer.public_key());
+
+        let env = loaded
+            .sign(b"x", Utc::now(), serde_json::Value::Null)
+            .unwrap();
+
+        let mut trusted = TrustedKeys::default();
+        assert!(trusted.check(&env, b"x").is_err()); // empty store trusts no one
+
+        let other = MapSigner::generate("carol");
+        trusted.add("carol", &other.public_key()).unwrap();
+        assert!(trusted.check(&env, b"x").is_err());
+
+        trusted.add("bob", &signer.public_key()).unwrap();
+        assert_eq!(trusted.check(&env, b"x").unwrap().name, "bob");
+
+        let path = dir.path().join("trusted.json");
+        trusted.save(&path).unwrap();
+        assert_eq!(TrustedKeys::load(&path).unwrap().keys.len(), 2);
+        assert!(trusted.add("bad", "abcd").is_err());
+    }
+}
@@ -1,10 +1,14 @@
 //! WQL executor — runs query plans against compiled schemas and temporal store.
 
+use crate::cartography::feature_plugins::PluginDims;
+use crate::collective::registry::LocalRegistry;
 use crate::compiler::models::CompiledSchema;
 use crate::compiler::schema;
+use crate::intelligence::cross_site;
 use crate::map::types::*;
 use crate::wql::planner::{PlanStep, QueryPlan};
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -52,6 +56,7 @@ pub fn execute(plan: &QueryPlan, maps: &HashMap<String, SiteMap>) -> Result<Vec<
 
     // Find the scan step to get model and domains
     let (target_model, target_domains) = find_scan_target(plan)?;
+    let plugin_dims = PluginDims::load_default();
 
     // Scan matching nodes from each domain
     for (domain, site_map) in maps {
@@ -78,6 +83,9 @@ pub fn execute(plan: &QueryPlan, maps: &HashMap<String, SiteMap>) -> Result<Vec<
             // Map feature dimensions to field names
             if let Some(feats) = features {
                 map_features_to_fields(feats, &target_model, &mut fields);
+                for (name, dim) in plugin_dims.names() {
+                    fields.insert(name.to_string(), Value::Float(feats[dim] as f64));
+                }
             }
 
             rows.push(Row {
@@ -89,6 +97,27 @@ pub fn execute(plan: &QueryPlan, maps: &HashMap<String, SiteMap>) -> Result<Vec<
         }
     }
 
+    // Products carry the cross-domain entity they resolve to, so offers
+    // for the same product can be grouped and compared across sites.
+    if target_model == "Product" {
+        let scanned: Vec<(&str, &SiteMap)> = maps
+            .iter()
+            .filter(|(d, _)| target_domains.is_empty() || target_domains.contains(d))
+            .map(|(d, m)| (d.as_str(), m))
+            .collect();
+        let entities = cross_site::resolve_maps(&scanned);
+        for row in &mut rows {
+            if let Some(entity) = entities.entity_of(&row.domain, row.node_id) {
+                row.fields
+                    .insert("entity_id".to_string(), Value::String(entity.id.clone()));
+                row.fields.insert(
+                    "entity_domains".to_string(),
+                    Value::Integer(entity.domain_count() as i64),
+                );
+            }
+        }
+    }
+
     // Apply remaining plan steps
     for step in &plan.steps {
         match step {
@@ -116,6 +145,26 @@ pub fn execute(plan: &QueryPlan, maps: &HashMap<String, SiteMap>) -> Result<Vec<
     Ok(rows)
 }
 
+/// The maps a plan scans as they were at `at`, checked out of the
+/// registry. Domains first pushed after `at` are left out.
+pub fn maps_as_of(
+    registry: &LocalRegistry,
+    plan: &QueryPlan,
+    at: DateTime<Utc>,
+) -> Result<HashMap<String, SiteMap>> {
+    let (_, target_domains) = find_scan_target(plan)?;
+    let mut maps = HashMap::new();
+    for entry in registry.list() {
+        if !target_domains.is_empty() && !target_domains.contains(&entry.domain) {
+            continue;
+        }
+        if let Some((map, _)) = registry.checkout(&entry.domain, at)? {
+            maps.insert(entry.domain.clone(), map);
+        }
+    }
+    Ok(maps)
+}
+
 /// Find the scan target from the plan.
 fn find_scan_target(plan: &QueryPlan) -> Result<(String, Vec<String>)> {
     for step in &plan.steps {
@@ -283,6 +332,41 @@ mod tests {
         maps
     }
 
+    #[test]
+    fn test_maps_as_of() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut registry = LocalRegistry::new(dir.path().to_path_buf()).unwrap();
+        let old = build_test_maps().remove("shop.com").unwrap();
+        registry.push("shop.com", &old, None).unwrap();
+        let then = registry.list()[0].latest_timestamp;
+        std::thread::sleep(std::time::Duration::from_millis(5));
+
+        let mut new = old.clone();
+        for feats in &mut new.features {
+            feats[FEAT_PRICE] += 1000.0;
+        }
+        registry.push("shop.com", &new, None).unwrap();
+        registry.push("other.com", &new, None).unwrap();
+
+        let query =
+            parser::parse("SELECT * FROM Product AS OF '2020-01-01' WHERE price < 100").unwrap();
+        let plan_result = planner::plan(&query, None).unwrap();
+        assert!(
+            maps_as_of(&registry, &plan_result, plan_result.as_of.unwrap())
+                .unwrap()
+                .is_empty()
+        );
+
+        let maps = maps_as_of(&registry, &plan_result, then).unwrap();
+        assert_eq!(maps.len(), 1);
+        let rows = execute(&plan_result, &maps).unwrap();
+        assert_eq!(rows.len(), 3);
+
+        let maps = maps_as_of(&registry, &plan_result, Utc::now()).unwrap();
+        assert_eq!(maps.len(), 2);
+        assert!(execute(&plan_result, &maps).unwrap().is_empty());
+    }
+
     #[test]
     fn test_execute_simple_query() {
         let maps = build_test_maps();
@@ -512,4 +596,37 @@ mod tests {
         let rows = execute(&plan, &maps).unwrap();
         assert_eq!(rows.len(), 3, "limit should be respected");
     }
+
+    #[test]
+    fn test_execute_entity_ids_across_domains() {
+        let mut maps = HashMap::new();
+        for (domain, price) in [("a.com", 249.0), ("b.com", 239.0)] {
+            let mut builder = SiteMapBuilder::new(domain);
+            let mut feats = [0.0f32; FEATURE_DIM];
+            feats[FEAT_PRICE] = price;
+            builder.add_node(
+                &format!("https://{domain}/p/apple-airpods-pro-2nd-generation"),
+                PageType::ProductDetail,
+                feats,
+                200,
+            );
+            builder.add_node(
+                &format!("https://{domain}/p/{domain}-exclusive-gift-card"),
+                PageType::ProductDetail,
+                feats,
+                200,
+            );
+            maps.insert(domain.to_string(), builder.build());
+        }
+
+        let query =
+            parser::parse("SELECT * FROM Product WHERE entity_domains > 1 ORDER BY price").unwrap();
+        let rows = execute(&planner::plan(&query, None).unwrap(), &maps).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].domain, "b.com");
+        assert_eq!(
+            rows[0].fields.get("entity_id").map(|v| v.to_string()),
+            rows[1].fields.get("entity_id").map(|v| v.to_string())
+        );
+    }
 }
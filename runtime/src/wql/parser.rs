@@ -2,11 +2,12 @@
 //!
 //! Grammar:
 //! ```text
-//! query := SELECT fields FROM model [JOIN ...] [WHERE ...] [ACROSS ...] [ORDER BY ...] [LIMIT n]
+//! query := SELECT fields FROM model [AS OF time] [JOIN ...] [WHERE ...] [ACROSS ...] [ORDER BY ...] [LIMIT n]
 //! fields := field (',' field)*
 //! field := name [AS alias] | temporal_func
 //! temporal_func := name '_' duration '_ago' | name '_trend' | 'predicted_' name '_' duration
 //! model := IDENTIFIER
+//! time := '\'' (RFC 3339 timestamp | YYYY-MM-DD) '\''
 //! where := WHERE expr
 //! expr := comparison ((AND | OR) comparison)*
 //! comparison := field op value
@@ -15,9 +16,15 @@
 //! order := ORDER BY field (ASC | DESC)
 //! limit := LIMIT number
 //! ```
+//!
+//! Watch rules reuse the WHERE grammar with trailing modifiers:
+//! ```text
+//! watch_rule := expr [FOR n CONSECUTIVE CHECKS] [HYSTERESIS number] [COOLDOWN duration]
+//! duration := number ('s' | 'm' | 'h' | 'd')
+//! ```
 
 use anyhow::{bail, Result};
-use chrono::Duration;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
 /// A parsed WQL query.
@@ -27,6 +34,9 @@ pub struct WqlQuery {
     pub select: Vec<SelectField>,
     /// Model type to query.
     pub from: ModelRef,
+    /// AS OF: query the maps as they were at this time.
+    #[serde(default)]
+    pub as_of: Option<DateTime<Utc>>,
     /// Join clauses.
     pub joins: Vec<JoinClause>,
     /// WHERE conditions.
@@ -94,6 +104,21 @@ pub enum WhereExpr {
     Or(Box<WhereExpr>, Box<WhereExpr>),
 }
 
+/// A parsed watch rule condition.
+///
+/// Example: `price < 300 AND availability = in_stock FOR 2 consecutive checks COOLDOWN 6h`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchExpr {
+    /// Condition that must hold for the rule to fire.
+    pub condition: WhereExpr,
+    /// Number of consecutive checks the condition must hold before firing.
+    pub consecutive: u32,
+    /// Band that numeric thresholds must be cleared by before the rule re-arms.
+    pub hysteresis: f64,
+    /// Minimum time between two firings, in seconds.
+    pub cooldown_secs: u64,
+}
+
 /// Comparison operator.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ComparisonOp {
@@ -131,6 +156,8 @@ enum Token {
     Op(String), // =, <, >, <=, >=, !=
     Comma,
     Star,
+    LParen,
+    RParen,
     Eof,
 }
 
@@ -147,6 +174,14 @@ pub fn parse(query: &str) -> Result<WqlQuery> {
     expect_keyword(&tokens, &mut pos, "FROM")?;
     let from = parse_model_ref(&tokens, &mut pos)?;
 
+    // Optional AS OF
+    let as_of = if peek_keyword(&tokens, pos, "AS") && peek_word(&tokens, pos + 1, "OF") {
+        pos += 2;
+        Some(parse_timestamp(&tokens, &mut pos)?)
+    } else {
+        None
+    };
+
     // Optional JOIN
     let mut joins = Vec::new();
     while peek_keyword(&tokens, pos, "JOIN") {
@@ -191,6 +226,7 @@ pub fn parse(query: &str) -> Result<WqlQuery> {
     Ok(WqlQuery {
         select,
         from,
+        as_of,
         joins,
         where_clause,
         across,
@@ -274,6 +310,17 @@ fn tokenize(input: &str) -> Result<Vec<Token>> {
             continue;
         }
 
+        // Parentheses
+        if chars[i] == '(' || chars[i] == ')' {
+            tokens.push(if chars[i] == '(' {
+                Token::LParen
+            } else {
+                Token::RParen
+            });
+            i += 1;
+            continue;
+        }
+
         // Identifier or keyword
         if chars[i].is_alphabetic() || chars[i] == '_' {
             let start = i;
@@ -467,6 +514,22 @@ fn parse_model_ref(tokens: &[Token], pos: &mut usize) -> Result<ModelRef> {
     }
 }
 
+/// A quoted RFC 3339 timestamp, or a date for midnight UTC.
+fn parse_timestamp(tokens: &[Token], pos: &mut usize) -> Result<DateTime<Utc>> {
+    let text = match tokens.get(*pos) {
+        Some(Token::StringLit(s)) => s,
+        other => bail!("expected quoted timestamp after AS OF, found {other:?}"),
+    };
+    *pos += 1;
+    if let Ok(at) = DateTime::parse_from_rfc3339(text) {
+        return Ok(at.with_timezone(&Utc));
+    }
+    match NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        Ok(date) => Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc()),
+        Err(_) => bail!("invalid AS OF time '{text}': expected YYYY-MM-DD or RFC 3339"),
+    }
+}
+
 fn parse_join(tokens: &[Token], pos: &mut usize) -> Result<JoinClause> {
     let target = match tokens.get(*pos) {
         Some(Token::Ident(name)) => {
@@ -528,6 +591,17 @@ fn parse_where_expr(tokens: &[Token], pos: &mut usize) -> Result<WhereExpr> {
 }
 
 fn parse_comparison(tokens: &[Token], pos: &mut usize) -> Result<WhereExpr> {
+    // Parenthesized sub-expression
+    if matches!(tokens.get(*pos), Some(Token::LParen)) {
+        *pos += 1;
+        let inner = parse_where_expr(tokens, pos)?;
+        match tokens.get(*pos) {
+            Some(Token::RParen) => *pos += 1,
+            other => bail!("expected ')', found {other:?}"),
+        }
+        return Ok(inner);
+    }
+
     let field = match tokens.get(*pos) {
         Some(Token::Ident(name)) => {
             *pos += 1;
@@ -566,6 +640,11 @@ fn parse_comparison(tokens: &[Token], pos: &mut usize) -> Result<WhereExpr> {
             *pos += 1;
             WqlValue::String(s.clone())
         }
+        // Bare symbols such as `in_stock` are treated as strings.
+        Some(Token::Ident(s)) => {
+            *pos += 1;
+            WqlValue::String(s.clone())
+        }
         other => bail!("expected value in comparison, found {other:?}"),
     };
 
@@ -621,6 +700,100 @@ fn parse_order_by(tokens: &[Token], pos: &mut usize) -> Result<Vec<OrderByField>
     Ok(fields)
 }
 
+/// Parse a watch rule condition (WHERE grammar plus FOR/HYSTERESIS/COOLDOWN).
+pub fn parse_watch_expr(input: &str) -> Result<WatchExpr> {
+    let tokens = tokenize(input)?;
+    let mut pos = 0;
+
+    if matches!(tokens.first(), Some(Token::Eof)) {
+        bail!("empty watch condition");
+    }
+    let condition = parse_where_expr(&tokens, &mut pos)?;
+
+    let mut consecutive = 1;
+    let mut hysteresis = 0.0;
+    let mut cooldown_secs = 0;
+
+    loop {
+        if peek_word(&tokens, pos, "FOR") {
+            pos += 1;
+            consecutive = match tokens.get(pos) {
+                Some(Token::Number(n)) if *n >= 1.0 => {
+                    pos += 1;
+                    *n as u32
+                }
+                other => bail!("expected positive count after FOR, found {other:?}"),
+            };
+            if peek_word(&tokens, pos, "CONSECUTIVE") {
+                pos += 1;
+            }
+            if peek_word(&tokens, pos, "CHECKS") || peek_word(&tokens, pos, "CHECK") {
+                pos += 1;
+            } else {
+                bail!("expected 'CHECKS' after FOR {consecutive}");
+            }
+        } else if peek_word(&tokens, pos, "HYSTERESIS") {
+            pos += 1;
+            hysteresis = match tokens.get(pos) {
+                Some(Token::Number(n)) if *n >= 0.0 => {
+                    pos += 1;
+                    *n
+                }
+                other => bail!("expected non-negative number after HYSTERESIS, found {other:?}"),
+            };
+        } else if peek_word(&tokens, pos, "COOLDOWN") {
+            pos += 1;
+            cooldown_secs = parse_duration_secs(&tokens, &mut pos)?;
+        } else {
+            break;
+        }
+    }
+
+    if !matches!(tokens.get(pos), Some(Token::Eof)) {
+        bail!("unexpected token in watch condition: {:?}", tokens.get(pos));
+    }
+
+    Ok(WatchExpr {
+        condition,
+        consecutive,
+        hysteresis,
+        cooldown_secs,
+    })
+}
+
+/// Match a contextual keyword that may have been tokenized as an identifier.
+fn peek_word(tokens: &[Token], pos: usize, word: &str) -> bool {
+    match tokens.get(pos) {
+        Some(Token::Keyword(k)) | Some(Token::Ident(k)) => k.eq_ignore_ascii_case(word),
+        _ => false,
+    }
+}
+
+fn parse_duration_secs(tokens: &[Token], pos: &mut usize) -> Result<u64> {
+    let amount = match tokens.get(*pos) {
+        Some(Token::Number(n)) if *n >= 0.0 => {
+            *pos += 1;
+            *n
+        }
+        other => bail!("expected duration, found {other:?}"),
+    };
+    let multiplier = match tokens.get(*pos) {
+        Some(Token::Ident(unit)) => {
+            let m = match unit.to_lowercase().as_str() {
+                "s" | "sec" | "secs" | "seconds" => 1.0,
+                "m" | "min" | "mins" | "minutes" => 60.0,
+                "h" | "hour" | "hours" => 3600.0,
+                "d" | "day" | "days" => 86400.0,
+                _ => bail!("unknown duration unit '{unit}'. Use s, m, h, or d"),
+            };
+            *pos += 1;
+            m
+        }
+        _ => 1.0, // bare number = seconds
+    };
+    Ok((amount * multiplier) as u64)
+}
+
 fn parse_limit(tokens: &[Token], pos: &mut usize) -> Result<usize> {
     match tokens.get(*pos) {
         Some(Token::Number(n)) => {
@@ -726,12 +899,65 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_watch_expr_compound() {
+        let w = parse_watch_expr(
+            "price < 300 AND availability = in_stock FOR 2 consecutive checks HYSTERESIS 10 COOLDOWN 6h",
+        )
+        .unwrap();
+        assert!(matches!(w.condition, WhereExpr::And(_, _)));
+        assert_eq!(w.consecutive, 2);
+        assert_eq!(w.hysteresis, 10.0);
+        assert_eq!(w.cooldown_secs, 6 * 3600);
+    }
+
+    #[test]
+    fn test_parse_watch_expr_defaults_and_parens() {
+        let w = parse_watch_expr("(price < 300 OR rating > 4.5) AND availability = 'in_stock'")
+            .unwrap();
+        assert_eq!(w.consecutive, 1);
+        assert_eq!(w.cooldown_secs, 0);
+        match w.condition {
+            WhereExpr::And(left, _) => assert!(matches!(*left, WhereExpr::Or(_, _))),
+            other => panic!("expected AND, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_watch_expr_errors() {
+        for bad in [
+            "",
+            "price <",
+            "price < 300 FOR",
+            "price < 300 FOR 2",
+            "price < 300 COOLDOWN 5 fortnights",
+            "(price < 300",
+            "price < 300 LIMIT 5",
+        ] {
+            assert!(parse_watch_expr(bad).is_err(), "should fail: {bad}");
+        }
+    }
+
     #[test]
     fn test_parse_error_missing_from() {
         let result = parse("SELECT name LIMIT 10");
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_parse_as_of() {
+        let q = parse("SELECT name FROM Product AS OF '2026-01-01' WHERE price < 200").unwrap();
+        assert_eq!(q.as_of.unwrap().to_rfc3339(), "2026-01-01T00:00:00+00:00");
+        assert!(q.where_clause.is_some());
+
+        let q = parse("SELECT name FROM Product as of '2026-01-01T12:00:00+02:00'").unwrap();
+        assert_eq!(q.as_of.unwrap().to_rfc3339(), "2026-01-01T10:00:00+00:00");
+        assert!(parse("SELECT name FROM Product").unwrap().as_of.is_none());
+
+        assert!(parse("SELECT name FROM Product AS OF 'last tuesday'").is_err());
+        assert!(parse("SELECT name FROM Product AS OF 2026").is_err());
+    }
+
     #[test]
     fn test_tokenize_error_on_unterminated_string() {
         let result = parse("SELECT name FROM Product WHERE x = 'unterminated");
@@ -3,6 +3,7 @@
 use crate::compiler::unifier::UnifiedSchema;
 use crate::wql::parser::*;
 use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 /// An execution plan for a WQL query.
@@ -10,6 +11,10 @@ use serde::{Deserialize, Serialize};
 pub struct QueryPlan {
     /// Ordered steps to execute.
     pub steps: Vec<PlanStep>,
+    /// Run against the maps as they were at this time (AS OF), checked
+    /// out of the registry, instead of the current ones.
+    #[serde(default)]
+    pub as_of: Option<DateTime<Utc>>,
 }
 
 /// A single step in the execution plan.
@@ -123,7 +128,25 @@ pub fn plan(query: &WqlQuery, unified_schema: Option<&UnifiedSchema>) -> Result<
         });
     }
 
-    Ok(QueryPlan { steps })
+    Ok(QueryPlan {
+        steps,
+        as_of: query.as_of,
+    })
+}
+
+/// Plan a WQL query with a default search path: the domains it scans when
+/// it has no ACROSS clause, such as a project's. An empty path scans every
+/// available map.
+pub fn plan_in(query: &WqlQuery, search_path: &[String]) -> Result<QueryPlan> {
+    let mut plan = plan(query, None)?;
+    if query.across.is_none() {
+        for step in &mut plan.steps {
+            if let PlanStep::ScanModel { domains, .. } = step {
+                *domains = search_path.to_vec();
+            }
+        }
+    }
+    Ok(plan)
 }
 
 /// Flatten a WHERE expression tree into filter steps.
@@ -202,4 +225,22 @@ mod tests {
             .iter()
             .any(|s| matches!(s, PlanStep::Sort { field, ascending: true } if field == "price")));
     }
+
+    #[test]
+    fn test_plan_in_search_path() {
+        let path = vec!["a.com".to_string(), "b.com".to_string()];
+        let scanned = |wql: &str| {
+            let plan = plan_in(&parser::parse(wql).unwrap(), &path).unwrap();
+            match &plan.steps[0] {
+                PlanStep::ScanModel { domains, .. } => domains.clone(),
+                other => panic!("expected a scan, got {other:?}"),
+            }
+        };
+        assert_eq!(scanned("SELECT * FROM Product"), path);
+        // An explicit ACROSS wins over the search path.
+        assert_eq!(
+            scanned("SELECT * FROM Product ACROSS c.com"),
+            vec!["c.com".to_string()]
+        );
+    }
 }
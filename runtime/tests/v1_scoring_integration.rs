@@ -397,6 +397,7 @@ fn test_v1_watch_integration() {
         id: "v1-price-watch".to_string(),
         domain: "shop.com".to_string(),
         model_type: Some("Product".to_string()),
+        node_url: None,
         feature_dim: FEAT_PRICE as u8,
         condition: WatchCondition::ValueBelow(50.0),
         notify: NotifyTarget::EventBus,
@@ -410,6 +411,7 @@ fn test_v1_watch_integration() {
         id: "v1-avail-watch".to_string(),
         domain: "shop.com".to_string(),
         model_type: Some("Product".to_string()),
+        node_url: None,
         feature_dim: FEAT_AVAILABILITY as u8,
         condition: WatchCondition::Available,
         notify: NotifyTarget::EventBus,